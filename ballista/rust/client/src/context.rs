@@ -18,21 +18,36 @@
 //! Distributed execution context.
 
 use std::collections::HashMap;
+use std::convert::TryInto;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use ballista_core::config::BallistaConfig;
+use ballista_core::dataset::DatasetTable;
+use ballista_core::error::BallistaError;
+use ballista_core::serde::logical_plan::json::logical_plan_from_json;
+use ballista_core::serde::protobuf::scheduler_grpc_client::SchedulerGrpcClient;
+use ballista_core::serde::protobuf::{self, GetDatasetParams, GetTableStatisticsParams};
+use ballista_core::serde::{udaf, udf};
 use ballista_core::utils::create_df_ctx_with_ballista_query_planner;
 
 use datafusion::catalog::TableReference;
 use datafusion::dataframe::DataFrame;
+use datafusion::datasource::listing::{Bucketing, ListingOptions, ListingTable};
 use datafusion::datasource::TableProvider;
 use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::ExecutionContext;
 use datafusion::execution::dataframe_impl::DataFrameImpl;
-use datafusion::logical_plan::{CreateExternalTable, LogicalPlan, TableScan};
+use datafusion::logical_plan::{
+    CreateExternalTable, CreateMemoryTable, LogicalPlan, LogicalPlanBuilder, TableScan,
+};
+use datafusion::physical_plan::udaf::AggregateUDF;
+use datafusion::physical_plan::udf::ScalarUDF;
+use datafusion::physical_plan::udtf::TableUDF;
+use datafusion::physical_plan::Statistics;
 use datafusion::prelude::{AvroReadOptions, CsvReadOptions};
-use datafusion::sql::parser::FileType;
+use datafusion::sql::parser::{FileType, Statement};
 
 struct BallistaContextState {
     /// Ballista configuration
@@ -43,6 +58,12 @@ struct BallistaContextState {
     scheduler_port: u16,
     /// Tables that have been registered with this context
     tables: HashMap<String, Arc<dyn TableProvider>>,
+    /// Aggregate UDFs that have been registered with this context
+    aggregate_functions: HashMap<String, AggregateUDF>,
+    /// Scalar UDFs that have been registered with this context
+    scalar_functions: HashMap<String, ScalarUDF>,
+    /// Table functions that have been registered with this context
+    table_functions: HashMap<String, TableUDF>,
 }
 
 impl BallistaContextState {
@@ -56,6 +77,9 @@ impl BallistaContextState {
             scheduler_host,
             scheduler_port,
             tables: HashMap::new(),
+            aggregate_functions: HashMap::new(),
+            scalar_functions: HashMap::new(),
+            table_functions: HashMap::new(),
         }
     }
 
@@ -64,8 +88,6 @@ impl BallistaContextState {
         config: &BallistaConfig,
         concurrent_tasks: usize,
     ) -> ballista_core::error::Result<Self> {
-        use ballista_core::serde::protobuf::scheduler_grpc_client::SchedulerGrpcClient;
-
         log::info!("Running in local mode. Scheduler will be run in-proc");
 
         let addr = ballista_scheduler::new_standalone_scheduler().await?;
@@ -91,6 +113,9 @@ impl BallistaContextState {
             scheduler_host: "localhost".to_string(),
             scheduler_port: addr.port(),
             tables: HashMap::new(),
+            aggregate_functions: HashMap::new(),
+            scalar_functions: HashMap::new(),
+            table_functions: HashMap::new(),
         })
     }
 
@@ -205,6 +230,38 @@ impl BallistaContext {
         Ok(())
     }
 
+    /// Register a scalar UDF that can be called from a SQL query.
+    ///
+    /// The UDF is also registered in this process so that the plans calling it
+    /// can be deserialized, which it must be in the scheduler and executors too
+    /// with [`udf::register_udf`] when they run in other processes.
+    pub fn register_udf(&self, f: ScalarUDF) {
+        udf::register_udf(f.clone());
+        let mut state = self.state.lock().unwrap();
+        state.scalar_functions.insert(f.name.clone(), f);
+    }
+
+    /// Register an aggregate UDF that can be called from a SQL query.
+    ///
+    /// The UDAF is also registered in this process so that the plans calling it
+    /// can be deserialized, which it must be in the scheduler and executors too
+    /// with [`udaf::register_udaf`] when they run in other processes.
+    pub fn register_udaf(&self, f: AggregateUDF) {
+        udaf::register_udaf(f.clone());
+        let mut state = self.state.lock().unwrap();
+        state.aggregate_functions.insert(f.name.clone(), f);
+    }
+
+    /// Register a table function that SQL queries can select from.
+    ///
+    /// The function is called while planning the query in this process, so the
+    /// tables it returns must be serializable to run in the cluster, as the
+    /// registered tables are.
+    pub fn register_udtf(&self, f: TableUDF) {
+        let mut state = self.state.lock().unwrap();
+        state.table_functions.insert(f.name.clone(), f);
+    }
+
     pub async fn register_csv(
         &self,
         name: &str,
@@ -242,31 +299,162 @@ impl BallistaContext {
         }
     }
 
-    /// Create a DataFrame from a SQL statement.
-    ///
-    /// This method is `async` because queries of type `CREATE EXTERNAL TABLE`
-    /// might require the schema to be inferred.
-    pub async fn sql(&self, sql: &str) -> Result<Arc<dyn DataFrame>> {
-        let mut ctx = {
+    /// Register a dataset that was persisted by caching a DataFrame, possibly from
+    /// another context, as a table that can be referenced from a SQL query
+    pub async fn register_dataset(&self, name: &str, dataset_id: &str) -> Result<()> {
+        let scheduler_url = {
             let state = self.state.lock().unwrap();
-            create_df_ctx_with_ballista_query_planner(
-                &state.scheduler_host,
-                state.scheduler_port,
-                state.config(),
-            )
+            format!("http://{}:{}", state.scheduler_host, state.scheduler_port)
         };
+        let mut scheduler = SchedulerGrpcClient::connect(scheduler_url)
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+        let dataset = scheduler
+            .get_dataset(GetDatasetParams {
+                dataset_id: dataset_id.to_owned(),
+            })
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?
+            .into_inner()
+            .dataset
+            .ok_or_else(|| {
+                DataFusionError::Internal("Received empty dataset".to_owned())
+            })?;
+        let table: DatasetTable = dataset
+            .try_into()
+            .map_err(|e: BallistaError| DataFusionError::Execution(format!("{:?}", e)))?;
+        self.register_table(name, Arc::new(table))
+    }
 
-        // register tables with DataFusion context
-        {
+    /// Returns the statistics of the Parquet files at `path`, as read and cached by
+    /// the scheduler, which are the statistics the plans scanning the files are
+    /// optimized with when they are planned by the scheduler
+    pub async fn table_statistics(&self, path: &str) -> Result<Statistics> {
+        self.scheduler_table_statistics(GetTableStatisticsParams {
+            path: path.to_owned(),
+            file_type: protobuf::FileType::Parquet as i32,
+            table_name: String::new(),
+        })
+        .await
+    }
+
+    /// Returns the statistics of a table registered in the scheduler, which are the
+    /// statistics the plans scanning the table are optimized with
+    pub async fn registered_table_statistics(
+        &self,
+        table_name: &str,
+    ) -> Result<Statistics> {
+        self.scheduler_table_statistics(GetTableStatisticsParams {
+            table_name: table_name.to_owned(),
+            ..Default::default()
+        })
+        .await
+    }
+
+    async fn scheduler_table_statistics(
+        &self,
+        params: GetTableStatisticsParams,
+    ) -> Result<Statistics> {
+        let scheduler_url = {
             let state = self.state.lock().unwrap();
-            for (name, prov) in &state.tables {
-                ctx.register_table(
-                    TableReference::Bare { table: name },
-                    Arc::clone(prov),
-                )?;
+            format!("http://{}:{}", state.scheduler_host, state.scheduler_port)
+        };
+        let mut scheduler = SchedulerGrpcClient::connect(scheduler_url)
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+        let statistics = scheduler
+            .get_table_statistics(params)
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?
+            .into_inner()
+            .statistics
+            .ok_or_else(|| {
+                DataFusionError::Internal("Received empty statistics".to_owned())
+            })?;
+        (&statistics)
+            .try_into()
+            .map_err(|e: BallistaError| DataFusionError::Execution(format!("{:?}", e)))
+    }
+
+    /// Declare the bucketing of the files of a registered table
+    fn register_bucketing(&self, name: &str, bucketing: &Bucketing) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let table = match state
+            .tables
+            .get(name)
+            .and_then(|table| table.as_any().downcast_ref::<ListingTable>())
+        {
+            Some(table) => ListingTable::new_with_paths(
+                table.object_store().clone(),
+                table.table_paths().to_vec(),
+                table.schema(),
+                ListingOptions {
+                    bucketing: Some(bucketing.clone()),
+                    ..table.options().clone()
+                },
+            ),
+            None => {
+                return Err(DataFusionError::Internal(format!(
+                    "Expected table {} to be a listing table",
+                    name
+                )))
             }
+        };
+        state.tables.insert(name.to_owned(), Arc::new(table));
+        Ok(())
+    }
+
+    /// Create a DataFusion context that plans queries with the scheduler, with
+    /// the tables registered with this context
+    fn create_df_ctx(&self) -> Result<ExecutionContext> {
+        let state = self.state.lock().unwrap();
+        let mut ctx = create_df_ctx_with_ballista_query_planner(
+            &state.scheduler_host,
+            state.scheduler_port,
+            state.config(),
+        );
+        for (name, prov) in &state.tables {
+            ctx.register_table(TableReference::Bare { table: name }, Arc::clone(prov))?;
+        }
+        for f in state.scalar_functions.values() {
+            ctx.register_udf(f.clone());
+        }
+        for f in state.aggregate_functions.values() {
+            ctx.register_udaf(f.clone());
+        }
+        for f in state.table_functions.values() {
+            ctx.register_udtf(f.clone());
         }
+        Ok(ctx)
+    }
+
+    /// Create a DataFrame from a logical plan, such as a plan saved with
+    /// [`logical_plan_to_json`](ballista_core::serde::logical_plan::json::logical_plan_to_json)
+    /// and read back with [`logical_plan_from_json`], to replay it.
+    pub fn execute_logical_plan(&self, plan: &LogicalPlan) -> Result<Arc<dyn DataFrame>> {
+        let ctx = self.create_df_ctx()?;
+        Ok(Arc::new(DataFrameImpl::new(ctx.state, plan)))
+    }
+
+    /// Create a DataFrame from a plan saved as JSON with
+    /// [`logical_plan_to_json`](ballista_core::serde::logical_plan::json::logical_plan_to_json)
+    pub fn read_json_plan(&self, json: &str) -> Result<Arc<dyn DataFrame>> {
+        let plan = logical_plan_from_json(json)
+            .map_err(|e| DataFusionError::Plan(format!("{}", e)))?;
+        self.execute_logical_plan(&plan)
+    }
 
+    /// Create a DataFrame from a SQL statement.
+    ///
+    /// This method is `async` because queries of type `CREATE EXTERNAL TABLE`
+    /// might require the schema to be inferred.
+    pub async fn sql(&self, sql: &str) -> Result<Arc<dyn DataFrame>> {
+        let mut ctx = self.create_df_ctx()?;
+
+        // custom statements, e.g. `SHOW JOBS`, are planned by their extension
+        if let Statement::Custom(_) = ctx.parse_statement(sql)? {
+            return ctx.sql(sql).await;
+        }
         let plan = ctx.create_logical_plan(sql)?;
         match plan {
             LogicalPlan::CreateExternalTable(CreateExternalTable {
@@ -275,32 +463,55 @@ impl BallistaContext {
                 ref location,
                 ref file_type,
                 ref has_header,
-            }) => match file_type {
-                FileType::CSV => {
-                    self.register_csv(
-                        name,
-                        location,
-                        CsvReadOptions::new()
-                            .schema(&schema.as_ref().to_owned().into())
-                            .has_header(*has_header),
-                    )
-                    .await?;
-                    Ok(Arc::new(DataFrameImpl::new(ctx.state, &plan)))
+                ref bucketing,
+            }) => {
+                match file_type {
+                    FileType::CSV => {
+                        self.register_csv(
+                            name,
+                            location,
+                            CsvReadOptions::new()
+                                .schema(&schema.as_ref().to_owned().into())
+                                .has_header(*has_header),
+                        )
+                        .await?
+                    }
+                    FileType::Parquet => self.register_parquet(name, location).await?,
+                    FileType::Avro => {
+                        self.register_avro(name, location, AvroReadOptions::default())
+                            .await?
+                    }
+                    _ => {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "Unsupported file type {:?}.",
+                            file_type
+                        )))
+                    }
                 }
-                FileType::Parquet => {
-                    self.register_parquet(name, location).await?;
-                    Ok(Arc::new(DataFrameImpl::new(ctx.state, &plan)))
+                if let Some(bucketing) = bucketing {
+                    self.register_bucketing(name, bucketing)?;
                 }
-                FileType::Avro => {
-                    self.register_avro(name, location, AvroReadOptions::default())
-                        .await?;
-                    Ok(Arc::new(DataFrameImpl::new(ctx.state, &plan)))
+                Ok(Arc::new(DataFrameImpl::new(ctx.state, &plan)))
+            }
+            LogicalPlan::CreateMemoryTable(CreateMemoryTable { name, input }) => {
+                // the table is persisted as a dataset on the executors, rather than
+                // in the memory of the short lived context planning the query
+                let df = DataFrameImpl::new(ctx.state.clone(), &input)
+                    .cache()
+                    .await?;
+                match df.to_logical_plan() {
+                    LogicalPlan::TableScan(TableScan { source, .. }) => {
+                        self.register_table(&name, source)?
+                    }
+                    _ => {
+                        return Err(DataFusionError::Internal(
+                            "Expected tables scan".to_owned(),
+                        ))
+                    }
                 }
-                _ => Err(DataFusionError::NotImplemented(format!(
-                    "Unsupported file type {:?}.",
-                    file_type
-                ))),
-            },
+                let plan = LogicalPlanBuilder::empty(false).build()?;
+                Ok(Arc::new(DataFrameImpl::new(ctx.state, &plan)))
+            }
 
             _ => ctx.sql(sql).await,
         }
@@ -319,4 +530,47 @@ mod tests {
         let df = context.sql("SELECT 1;").await.unwrap();
         df.collect().await.unwrap();
     }
+
+    #[tokio::test]
+    #[cfg(feature = "standalone")]
+    async fn test_standalone_cache() {
+        use super::*;
+        let context = BallistaContext::standalone(&BallistaConfig::new().unwrap(), 1)
+            .await
+            .unwrap();
+        let df = context.sql("SELECT 1 AS a").await.unwrap();
+        let df = df.cache().await.unwrap();
+        let dataset_id = match df.to_logical_plan() {
+            LogicalPlan::TableScan(TableScan { source, .. }) => source
+                .as_any()
+                .downcast_ref::<DatasetTable>()
+                .unwrap()
+                .dataset_id()
+                .to_owned(),
+            plan => panic!("expected a table scan, got {:?}", plan),
+        };
+
+        // the persisted dataset can be scanned by later queries
+        context.register_dataset("t", &dataset_id).await.unwrap();
+        let df = context.sql("SELECT a FROM t").await.unwrap();
+        let batches = df.collect().await.unwrap();
+        assert_eq!(1, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "standalone")]
+    async fn test_standalone_json_plan() {
+        use super::*;
+        use ballista_core::serde::logical_plan::json::logical_plan_to_json;
+        let context = BallistaContext::standalone(&BallistaConfig::new().unwrap(), 1)
+            .await
+            .unwrap();
+        let df = context.sql("SELECT 1 AS a").await.unwrap();
+        let json = logical_plan_to_json(&df.to_logical_plan()).unwrap();
+
+        // the saved plan can be replayed
+        let df = context.read_json_plan(&json).unwrap();
+        let batches = df.collect().await.unwrap();
+        assert_eq!(1, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+    }
 }
@@ -20,7 +20,18 @@ fn main() -> Result<(), String> {
     println!("cargo:rerun-if-env-changed=FORCE_REBUILD");
 
     println!("cargo:rerun-if-changed=proto/ballista.proto");
+    let mut config = prost_build::Config::new();
+    // the chunks of the large messages are sliced from and decoded as shared
+    // buffers rather than copied
+    config.bytes(&[".ballista.protobuf.MessageChunk.data"]);
     tonic_build::configure()
-        .compile(&["proto/ballista.proto"], &["proto"])
+        // the messages are also serialized as JSON, see `serde::logical_plan::json`
+        .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+        // keeps the JSON plans saved before schemas were interned readable
+        .field_attribute(
+            ".ballista.protobuf.Schema.interned_index",
+            "#[serde(default)]",
+        )
+        .compile_with_config(config, &["proto/ballista.proto"], &["proto"])
         .map_err(|e| format!("protobuf compilation failed: {}", e))
 }
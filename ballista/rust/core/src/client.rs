@@ -17,7 +17,7 @@
 
 //! Client API for sending requests to executors.
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::{collections::HashMap, pin::Pin};
 use std::{
     convert::{TryFrom, TryInto},
@@ -26,29 +26,41 @@ use std::{
 
 use crate::error::{ballista_error, BallistaError, Result};
 use crate::memory_stream::MemoryStream;
+use crate::result_encoding::ResultEncoding;
 use crate::serde::protobuf::{self};
 use crate::serde::scheduler::{
     Action, ExecutePartition, ExecutePartitionResult, PartitionId, PartitionStats,
 };
+use crate::utils::ipc_message_checksum;
 
-use arrow_flight::utils::flight_data_to_arrow_batch;
-use arrow_flight::Ticket;
-use arrow_flight::{flight_service_client::FlightServiceClient, FlightData};
+use arrow_flight::utils::{flight_data_from_arrow_batch, flight_data_to_arrow_batch};
+use arrow_flight::{
+    flight_descriptor::DescriptorType, flight_service_client::FlightServiceClient,
+    FlightData, FlightDescriptor, SchemaAsIpc, Ticket,
+};
 use datafusion::arrow::{
-    array::{StringArray, StructArray},
+    array::{ArrayRef, StringArray, StructArray},
     datatypes::{Schema, SchemaRef},
     error::{ArrowError, Result as ArrowResult},
+    ipc::{self, writer::IpcWriteOptions},
     record_batch::RecordBatch,
 };
 use datafusion::physical_plan::common::collect;
 use datafusion::physical_plan::{ExecutionPlan, SendableRecordBatchStream};
 use datafusion::{logical_plan::LogicalPlan, physical_plan::RecordBatchStream};
 use futures::{Stream, StreamExt};
+use lazy_static::lazy_static;
 use log::debug;
 use prost::Message;
-use tonic::Streaming;
 use uuid::Uuid;
 
+lazy_static! {
+    /// Clients connected to the executors, by host and port, shared by the fetches
+    /// of the shuffle partitions
+    static ref CLIENT_POOL: Mutex<HashMap<(String, u16), BallistaClient>> =
+        Mutex::new(HashMap::new());
+}
+
 /// Client for interacting with Ballista executors.
 #[derive(Clone)]
 pub struct BallistaClient {
@@ -75,6 +87,25 @@ impl BallistaClient {
         Ok(Self { flight_client })
     }
 
+    /// Get a client connected to the executor listening on the specified host and
+    /// port, reusing the connection of a previous client if there is one
+    pub async fn pooled(host: &str, port: u16) -> Result<Self> {
+        let key = (host.to_owned(), port);
+        let client = CLIENT_POOL.lock().unwrap().get(&key).cloned();
+        if let Some(client) = client {
+            return Ok(client);
+        }
+        let client = Self::try_new(host, port).await?;
+        CLIENT_POOL.lock().unwrap().insert(key, client.clone());
+        Ok(client)
+    }
+
+    /// Remove the pooled client of the executor listening on the specified host and
+    /// port, so that the next one opens a new connection
+    pub fn evict(host: &str, port: u16) {
+        CLIENT_POOL.lock().unwrap().remove(&(host.to_owned(), port));
+    }
+
     /// Fetch a partition from an executor
     pub async fn fetch_partition(
         &mut self,
@@ -82,16 +113,106 @@ impl BallistaClient {
         stage_id: usize,
         partition_id: usize,
         path: &str,
+        checksum: Option<u32>,
     ) -> Result<SendableRecordBatchStream> {
         let action = Action::FetchPartition {
             job_id: job_id.to_string(),
             stage_id,
             partition_id,
             path: path.to_owned(),
+            checksum,
+            encoding: ResultEncoding::ArrowIpc,
         };
         self.execute_action(&action).await
     }
 
+    /// Fetch a partition from an executor serialized with a text encoding, e.g. as
+    /// JSON, and return the bytes of its record batches
+    pub async fn fetch_encoded_partition(
+        &mut self,
+        job_id: &str,
+        stage_id: usize,
+        partition_id: usize,
+        path: &str,
+        encoding: ResultEncoding,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>>> {
+        let action = Action::FetchPartition {
+            job_id: job_id.to_string(),
+            stage_id,
+            partition_id,
+            path: path.to_owned(),
+            checksum: None,
+            encoding,
+        };
+        let serialized_action: protobuf::Action = action.clone().try_into()?;
+        let mut buf: Vec<u8> = Vec::with_capacity(serialized_action.encoded_len());
+        serialized_action
+            .encode(&mut buf)
+            .map_err(|e| BallistaError::General(format!("{:?}", e)))?;
+        let request = tonic::Request::new(Ticket { ticket: buf });
+        let mut stream = self
+            .flight_client
+            .do_get(request)
+            .await
+            .map_err(|e| action_error(&action, e))?
+            .into_inner();
+
+        // the first message holds the content type of the batches
+        let content_type = stream
+            .message()
+            .await
+            .map_err(|e| action_error(&action, e))?
+            .map(|flight_data| flight_data.app_metadata);
+        if content_type.as_deref() != Some(encoding.content_type().as_bytes()) {
+            return Err(ballista_error(&format!(
+                "Did not receive {} results from flight server",
+                encoding
+            )));
+        }
+        Ok(Box::pin(stream.map(move |flight_data| {
+            flight_data
+                .map(|flight_data| flight_data.data_body)
+                .map_err(|e| action_error(&action, e))
+        })))
+    }
+
+    /// Push record batches to the executor, which keeps them as a new partition of the
+    /// dataset `dataset_id` so that later queries can scan them, and return the dataset
+    pub async fn put_dataset(
+        &mut self,
+        dataset_id: &str,
+        schema: SchemaRef,
+        batches: &[RecordBatch],
+    ) -> Result<protobuf::Dataset> {
+        let options = IpcWriteOptions::default();
+        // the first message describes the dataset and holds the schema of the batches
+        let mut schema_flight_data: FlightData =
+            SchemaAsIpc::new(schema.as_ref(), &options).into();
+        schema_flight_data.flight_descriptor = Some(FlightDescriptor {
+            r#type: DescriptorType::Path as i32,
+            cmd: vec![],
+            path: vec![dataset_id.to_owned()],
+        });
+        let mut flight_data = vec![schema_flight_data];
+        for batch in batches {
+            let (dictionaries, batch) = flight_data_from_arrow_batch(batch, &options);
+            flight_data.extend(dictionaries);
+            flight_data.push(batch);
+        }
+
+        let mut results = self
+            .flight_client
+            .do_put(futures::stream::iter(flight_data))
+            .await?
+            .into_inner();
+        let result = results.message().await?.ok_or_else(|| {
+            ballista_error("Did not receive the dataset from flight server")
+        })?;
+        protobuf::Dataset::decode(result.app_metadata.as_slice()).map_err(|e| {
+            BallistaError::General(format!("Could not deserialize dataset: {:?}", e))
+        })
+    }
+
     /// Execute an action and retrieve the results
     pub async fn execute_action(
         &mut self,
@@ -111,21 +232,29 @@ impl BallistaClient {
             .flight_client
             .do_get(request)
             .await
-            .map_err(|e| BallistaError::General(format!("{:?}", e)))?
+            .map_err(|e| action_error(action, e))?
             .into_inner();
 
+        // the checksums of the IPC messages of the shuffle partition are verified
+        let Action::FetchPartition { path, .. } = action;
+        let partition_path = Some(path.clone());
+
         // the schema should be the first message returned, else client should error
         match stream
             .message()
             .await
-            .map_err(|e| BallistaError::General(format!("{:?}", e)))?
+            .map_err(|e| action_error(action, e))?
         {
             Some(flight_data) => {
                 // convert FlightData to a stream
                 let schema = Arc::new(Schema::try_from(&flight_data)?);
 
                 // all the remaining stream messages should be dictionary and record batches
-                Ok(Box::pin(FlightDataStream::new(stream, schema)))
+                Ok(Box::pin(FlightDataStream::new(
+                    stream,
+                    schema,
+                    partition_path,
+                )))
             }
             None => Err(ballista_error(
                 "Did not receive schema batch from flight server",
@@ -134,44 +263,186 @@ impl BallistaClient {
     }
 }
 
-struct FlightDataStream {
-    stream: Streaming<FlightData>,
+/// Converts the error status of an action, keeping track of the shuffle partitions
+/// the executor found to be corrupted
+fn action_error(action: &Action, status: tonic::Status) -> BallistaError {
+    match (action, status.code()) {
+        (Action::FetchPartition { path, .. }, tonic::Code::DataLoss) => {
+            BallistaError::CorruptedPartition {
+                path: path.clone(),
+                reason: status.message().to_owned(),
+            }
+        }
+        _ => BallistaError::GrpcError(status),
+    }
+}
+
+/// Verifies the checksum an executor serving the shuffle partition at `path` sends
+/// in the app metadata of each IPC message, so that the messages corrupted in
+/// transfer fail the fetch with a [BallistaError::CorruptedPartition] error
+fn verify_message_checksum(path: &str, data: &FlightData) -> Result<()> {
+    let corrupted = |reason: String| BallistaError::CorruptedPartition {
+        path: path.to_owned(),
+        reason,
+    };
+    let expected = <[u8; 4]>::try_from(data.app_metadata.as_slice())
+        .map(u32::from_be_bytes)
+        .map_err(|_| corrupted("IPC message without a checksum".to_owned()))?;
+    let actual = ipc_message_checksum(&data.data_header, &data.data_body);
+    if actual != expected {
+        return Err(corrupted(format!(
+            "IPC message checksum is {:08x} instead of {:08x}",
+            actual, expected
+        )));
+    }
+    Ok(())
+}
+
+/// Decodes the record batches of a stream of flight data whose schema was sent in a
+/// previous message, such as the record batches pushed with Flight DoPut
+pub fn flight_data_stream<S, E>(stream: S, schema: SchemaRef) -> SendableRecordBatchStream
+where
+    S: Stream<Item = std::result::Result<FlightData, E>> + Send + Unpin + 'static,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    Box::pin(FlightDataStream::new(stream, schema, None))
+}
+
+struct FlightDataStream<S> {
+    stream: S,
     schema: SchemaRef,
+    /// the latest dictionary of each dictionary encoded field
+    dictionaries_by_field: Vec<Option<ArrayRef>>,
+    /// the path of the shuffle partition the messages are fetched from, if any,
+    /// whose checksums are verified
+    partition_path: Option<String>,
 }
 
-impl FlightDataStream {
-    pub fn new(stream: Streaming<FlightData>, schema: SchemaRef) -> Self {
-        Self { stream, schema }
+impl<S> FlightDataStream<S> {
+    pub fn new(stream: S, schema: SchemaRef, partition_path: Option<String>) -> Self {
+        let dictionaries_by_field = vec![None; schema.fields().len()];
+        Self {
+            stream,
+            schema,
+            dictionaries_by_field,
+            partition_path,
+        }
+    }
+
+    /// Converts a chunk of flight data to a record batch, or returns `None` if
+    /// it was a dictionary batch, which is kept to decode the next batches
+    fn convert_chunk(&mut self, data: &FlightData) -> ArrowResult<Option<RecordBatch>> {
+        if let Some(path) = &self.partition_path {
+            verify_message_checksum(path, data)
+                .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+        }
+        let message = ipc::root_as_message(&data.data_header[..]).map_err(|e| {
+            ArrowError::ParseError(format!("Unable to get root as message: {:?}", e))
+        })?;
+        if message.header_type() == ipc::MessageHeader::DictionaryBatch {
+            let dictionary_batch =
+                message.header_as_dictionary_batch().ok_or_else(|| {
+                    ArrowError::ParseError(
+                        "Unable to read IPC message as dictionary batch".to_owned(),
+                    )
+                })?;
+            ipc::reader::read_dictionary(
+                &data.data_body,
+                dictionary_batch,
+                &self.schema,
+                &mut self.dictionaries_by_field,
+            )?;
+            return Ok(None);
+        }
+        flight_data_to_arrow_batch(data, self.schema.clone(), &self.dictionaries_by_field)
+            .map(Some)
     }
 }
 
-impl Stream for FlightDataStream {
+impl<S, E> Stream for FlightDataStream<S>
+where
+    S: Stream<Item = std::result::Result<FlightData, E>> + Unpin,
+    E: std::error::Error + Send + Sync + 'static,
+{
     type Item = ArrowResult<RecordBatch>;
 
     fn poll_next(
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        self.stream.poll_next_unpin(cx).map(|x| match x {
-            Some(flight_data_chunk_result) => {
-                let converted_chunk = flight_data_chunk_result
-                    .map_err(|e| ArrowError::from_external_error(Box::new(e)))
-                    .and_then(|flight_data_chunk| {
-                        flight_data_to_arrow_batch(
-                            &flight_data_chunk,
-                            self.schema.clone(),
-                            &[],
-                        )
-                    });
-                Some(converted_chunk)
+        loop {
+            let flight_data_chunk = match self.stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(flight_data_chunk))) => flight_data_chunk,
+                Poll::Ready(Some(Err(e))) => {
+                    return Poll::Ready(Some(Err(ArrowError::from_external_error(
+                        Box::new(e),
+                    ))))
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            match self.convert_chunk(&flight_data_chunk) {
+                // dictionary batches precede the record batches using them
+                Ok(None) => continue,
+                Ok(Some(batch)) => return Poll::Ready(Some(Ok(batch))),
+                Err(e) => return Poll::Ready(Some(Err(e))),
             }
-            None => None,
-        })
+        }
     }
 }
 
-impl RecordBatchStream for FlightDataStream {
+impl<S, E> RecordBatchStream for FlightDataStream<S>
+where
+    S: Stream<Item = std::result::Result<FlightData, E>> + Unpin,
+    E: std::error::Error + Send + Sync + 'static,
+{
     fn schema(&self) -> SchemaRef {
         self.schema.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::Int32Array;
+    use datafusion::arrow::datatypes::{DataType, Field};
+
+    #[tokio::test]
+    async fn verify_fetched_message_checksums() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let (_, mut flight_data) =
+            flight_data_from_arrow_batch(&batch, &IpcWriteOptions::default());
+        flight_data.app_metadata =
+            ipc_message_checksum(&flight_data.data_header, &flight_data.data_body)
+                .to_be_bytes()
+                .to_vec();
+        let fetch = |flight_data: FlightData| {
+            FlightDataStream::new(
+                futures::stream::iter(vec![Ok::<_, tonic::Status>(flight_data)]),
+                schema.clone(),
+                Some("/tmp/job/1/0/data.arrow".to_owned()),
+            )
+        };
+
+        let fetched = fetch(flight_data.clone()).next().await.unwrap().unwrap();
+        assert_eq!(batch.columns(), fetched.columns());
+
+        // a message corrupted in transfer fails the fetch as a lost partition
+        let mut corrupted = flight_data.clone();
+        let last = corrupted.data_body.len() - 1;
+        corrupted.data_body[last] ^= 1;
+        let e = BallistaError::ArrowError(
+            fetch(corrupted).next().await.unwrap().unwrap_err(),
+        );
+        assert_eq!(Some("/tmp/job/1/0/data.arrow"), e.lost_partition_path());
+
+        let mut unchecked = flight_data;
+        unchecked.app_metadata.clear();
+        assert!(fetch(unchecked).next().await.unwrap().is_err());
+    }
+}
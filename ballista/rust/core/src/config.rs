@@ -18,7 +18,7 @@
 
 //! Ballista configuration
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::error::{BallistaError, Result};
 
@@ -26,13 +26,20 @@ use datafusion::arrow::datatypes::DataType;
 use log::warn;
 
 pub const BALLISTA_DEFAULT_SHUFFLE_PARTITIONS: &str = "ballista.shuffle.partitions";
+pub const BALLISTA_SCAN_SPLIT_SIZE: &str = "ballista.scan.split_size";
+pub const BALLISTA_ANSI_MODE: &str = "ballista.ansi_mode";
+
+/// Prefix of the settings that attach labels to the jobs submitted with a
+/// configuration, such as `ballista.job.label.team`. The scheduler stores the
+/// labels with the job metadata, to attribute the cost of jobs.
+pub const BALLISTA_JOB_LABEL_PREFIX: &str = "ballista.job.label.";
 
 /// Configuration option meta-data
 #[derive(Debug, Clone)]
 pub struct ConfigEntry {
     name: String,
     _description: String,
-    _data_type: DataType,
+    data_type: DataType,
     default_value: Option<String>,
 }
 
@@ -40,16 +47,30 @@ impl ConfigEntry {
     fn new(
         name: String,
         _description: String,
-        _data_type: DataType,
+        data_type: DataType,
         default_value: Option<String>,
     ) -> Self {
         Self {
             name,
             _description,
-            _data_type,
+            data_type,
             default_value,
         }
     }
+
+    /// Validates that `value` can be parsed as the data type of the setting
+    fn parse(&self, value: &str) -> std::result::Result<(), String> {
+        match self.data_type {
+            DataType::Boolean => value
+                .parse::<bool>()
+                .map(|_| ())
+                .map_err(|e| format!("{:?}", e)),
+            _ => value
+                .parse::<usize>()
+                .map(|_| ())
+                .map_err(|e| format!("{:?}", e)),
+        }
+    }
 }
 
 /// Ballista configuration builder
@@ -74,6 +95,11 @@ impl BallistaConfigBuilder {
         Self { settings }
     }
 
+    /// Create a new config with an additional label attached to the submitted jobs
+    pub fn label(&self, k: &str, v: &str) -> Self {
+        self.set(&format!("{}{}", BALLISTA_JOB_LABEL_PREFIX, k), v)
+    }
+
     pub fn build(&self) -> Result<BallistaConfig> {
         BallistaConfig::with_settings(self.settings.clone())
     }
@@ -103,9 +129,9 @@ impl BallistaConfig {
         for (name, entry) in &supported_entries {
             if let Some(v) = settings.get(name) {
                 // validate that we can parse the user-supplied value
-                let _ = v.parse::<usize>().map_err(|e| BallistaError::General(format!("Failed to parse user-supplied value '{}' for configuration setting '{}': {:?}", name, v, e)))?;
+                entry.parse(v).map_err(|e| BallistaError::General(format!("Failed to parse user-supplied value '{}' for configuration setting '{}': {}", name, v, e)))?;
             } else if let Some(v) = entry.default_value.clone() {
-                let _ = v.parse::<usize>().map_err(|e| BallistaError::General(format!("Failed to parse default value '{}' for configuration setting '{}': {:?}", name, v, e)))?;
+                entry.parse(&v).map_err(|e| BallistaError::General(format!("Failed to parse default value '{}' for configuration setting '{}': {}", name, v, e)))?;
             } else {
                 return Err(BallistaError::General(format!(
                     "No value specified for mandatory configuration setting '{}'",
@@ -123,6 +149,12 @@ impl BallistaConfig {
             ConfigEntry::new(BALLISTA_DEFAULT_SHUFFLE_PARTITIONS.to_string(),
                 "Sets the default number of partitions to create when repartitioning query stages".to_string(),
                 DataType::UInt16, Some("2".to_string())),
+            ConfigEntry::new(BALLISTA_SCAN_SPLIT_SIZE.to_string(),
+                "Sets the number of bytes the scheduler splits the files scanned by a query into, one task per split, or 0 to keep the partitions of the scans".to_string(),
+                DataType::UInt64, Some("0".to_string())),
+            ConfigEntry::new(BALLISTA_ANSI_MODE.to_string(),
+                "Makes integer arithmetic and integer sums error on overflow rather than wrap around".to_string(),
+                DataType::Boolean, Some("false".to_string())),
         ];
         entries
             .iter()
@@ -138,6 +170,39 @@ impl BallistaConfig {
         self.get_usize_setting(BALLISTA_DEFAULT_SHUFFLE_PARTITIONS)
     }
 
+    pub fn scan_split_size(&self) -> usize {
+        self.get_usize_setting(BALLISTA_SCAN_SPLIT_SIZE)
+    }
+
+    /// Whether integer arithmetic and integer sums error on overflow, see
+    /// [`ExecutionConfig::with_ansi_mode`](datafusion::prelude::ExecutionConfig::with_ansi_mode)
+    pub fn ansi_mode(&self) -> bool {
+        self.get_bool_setting(BALLISTA_ANSI_MODE)
+    }
+
+    /// The labels attached to the submitted jobs, by name
+    pub fn job_labels(&self) -> BTreeMap<String, String> {
+        self.settings
+            .iter()
+            .filter_map(|(k, v)| {
+                k.strip_prefix(BALLISTA_JOB_LABEL_PREFIX)
+                    .map(|name| (name.to_owned(), v.clone()))
+            })
+            .collect()
+    }
+
+    fn get_bool_setting(&self, key: &str) -> bool {
+        if let Some(v) = self.settings.get(key) {
+            // infallible because we validate all configs in the constructor
+            v.parse().unwrap()
+        } else {
+            let entries = Self::valid_entries();
+            // infallible because we validate all configs in the constructor
+            let v = entries.get(key).unwrap().default_value.as_ref().unwrap();
+            v.parse().unwrap()
+        }
+    }
+
     fn get_usize_setting(&self, key: &str) -> usize {
         if let Some(v) = self.settings.get(key) {
             // infallible because we validate all configs in the constructor
@@ -159,6 +224,8 @@ mod tests {
     fn default_config() -> Result<()> {
         let config = BallistaConfig::new()?;
         assert_eq!(2, config.default_shuffle_partitions());
+        assert_eq!(0, config.scan_split_size());
+        assert!(!config.ansi_mode());
         Ok(())
     }
 
@@ -166,8 +233,28 @@ mod tests {
     fn custom_config() -> Result<()> {
         let config = BallistaConfig::builder()
             .set(BALLISTA_DEFAULT_SHUFFLE_PARTITIONS, "123")
+            .set(BALLISTA_ANSI_MODE, "true")
             .build()?;
         assert_eq!(123, config.default_shuffle_partitions());
+        assert!(config.ansi_mode());
+        Ok(())
+    }
+
+    #[test]
+    fn job_labels() -> Result<()> {
+        let config = BallistaConfig::builder()
+            .label("team", "data")
+            .set("ballista.job.label.user", "alice")
+            .set(BALLISTA_DEFAULT_SHUFFLE_PARTITIONS, "4")
+            .build()?;
+        let labels = config.job_labels().into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("team".to_owned(), "data".to_owned()),
+                ("user".to_owned(), "alice".to_owned())
+            ],
+            labels
+        );
         Ok(())
     }
 
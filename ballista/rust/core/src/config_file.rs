@@ -0,0 +1,182 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Config files of the scheduler and executor binaries, passed with `--config <path>`
+//! and written in TOML, or in YAML for files with a `.yaml` or `.yml` extension.
+//!
+//! A config file sets the options of the binary by their names, e.g. `bind_port = 50051`,
+//! and overrides the default config file of the binary. Its options are overridden by
+//! the environment variables of the binary, e.g. `BALLISTA_EXECUTOR_BIND_PORT`, so that
+//! container deployments can override a shared config file, and by the command line
+//! arguments.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempPath;
+
+use crate::error::{BallistaError, Result};
+
+/// The argument the config file of the binaries is passed with
+pub const CONFIG_ARG: &str = "--config";
+
+/// A config file passed to a binary, as a TOML file. A YAML config file is converted
+/// into a temporary TOML file, which is removed when the config file is dropped.
+#[derive(Debug)]
+pub struct ConfigFile {
+    path: PathBuf,
+    _temp_path: Option<TempPath>,
+}
+
+impl ConfigFile {
+    /// The path of the TOML file with the options of the config file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Removes the `--config <path>` argument from the command line arguments of a binary,
+/// and returns the remaining arguments along with the TOML file with the options of
+/// the config file, to load after the default config file of the binary. The config
+/// file must be kept until the options are loaded.
+pub fn extract_config_arg<I>(args: I) -> Result<(Vec<OsString>, Option<ConfigFile>)>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut remaining = vec![];
+    let mut config_file = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let path = match arg.to_str() {
+            Some(CONFIG_ARG) => match args.next() {
+                Some(path) => PathBuf::from(path),
+                None => {
+                    return Err(BallistaError::General(format!(
+                        "Missing path after {}",
+                        CONFIG_ARG
+                    )))
+                }
+            },
+            Some(arg) if arg.starts_with("--config=") => {
+                PathBuf::from(&arg["--config=".len()..])
+            }
+            _ => {
+                remaining.push(arg);
+                continue;
+            }
+        };
+        config_file = Some(toml_config_file(&path)?);
+    }
+    Ok((remaining, config_file))
+}
+
+/// The TOML file with the options of the config file `path`
+fn toml_config_file(path: &Path) -> Result<ConfigFile> {
+    // unlike their default config files, the config file passed to the binaries
+    // must exist
+    if !path.is_file() {
+        return Err(BallistaError::General(format!(
+            "Config file {} does not exist",
+            path.display()
+        )));
+    }
+    let is_yaml = matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("yaml") | Some("yml")
+    );
+    if !is_yaml {
+        return Ok(ConfigFile {
+            path: path.to_owned(),
+            _temp_path: None,
+        });
+    }
+
+    let yaml = fs::read_to_string(path)?;
+    let options: toml::Value = serde_yaml::from_str(&yaml).map_err(|e| {
+        BallistaError::General(format!(
+            "Could not parse config file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let toml = toml::to_string(&options).map_err(|e| {
+        BallistaError::General(format!(
+            "Could not convert config file {} to TOML: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let mut file = tempfile::Builder::new()
+        .prefix("ballista-config-")
+        .suffix(".toml")
+        .tempfile()?;
+    file.write_all(toml.as_bytes())?;
+    let temp_path = file.into_temp_path();
+    Ok(ConfigFile {
+        path: temp_path.to_path_buf(),
+        _temp_path: Some(temp_path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn extract_toml_and_yaml_config() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let toml_path = dir.path().join("executor.toml");
+        fs::write(&toml_path, "bind_port = 50052\n")?;
+        let toml_arg = toml_path.to_str().unwrap();
+
+        let (remaining, config_file) =
+            extract_config_arg(args(&["executor", "--config", toml_arg, "-c", "2"]))?;
+        assert_eq!(remaining, args(&["executor", "-c", "2"]));
+        assert_eq!(config_file.unwrap().path(), toml_path);
+        let (remaining, config_file) =
+            extract_config_arg(args(&["executor", &format!("--config={}", toml_arg)]))?;
+        assert_eq!(remaining, args(&["executor"]));
+        assert_eq!(config_file.unwrap().path(), toml_path);
+        assert!(extract_config_arg(args(&["executor"]))?.1.is_none());
+        assert!(extract_config_arg(args(&["executor", "--config"])).is_err());
+        assert!(
+            extract_config_arg(args(&["executor", "--config", "missing.toml"])).is_err()
+        );
+
+        let yaml_path = dir.path().join("executor.yaml");
+        fs::write(&yaml_path, "bind_port: 50052\nwork_dir: /tmp/ballista\n")?;
+        let (_, config_file) =
+            extract_config_arg(vec!["--config".into(), yaml_path.into()])?;
+        let config_file = config_file.unwrap();
+        let toml: toml::Value = toml::from_str(&fs::read_to_string(config_file.path())?)
+            .map_err(|e| BallistaError::General(e.to_string()))?;
+        assert_eq!(toml["bind_port"].as_integer(), Some(50052));
+        assert_eq!(toml["work_dir"].as_str(), Some("/tmp/ballista"));
+
+        // the converted file is removed with the config file
+        let converted_path = config_file.path().to_owned();
+        drop(config_file);
+        assert!(!converted_path.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A table provider over the persisted output of a Ballista job.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::execution_plans::ShuffleReaderExec;
+use crate::serde::scheduler::PartitionLocation;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::datasource::TableProvider;
+use datafusion::error::Result;
+use datafusion::logical_plan::Expr;
+use datafusion::physical_plan::expressions::Column;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::{ExecutionPlan, PhysicalExpr};
+
+/// DatasetTable scans the output partitions of a completed job that were persisted as a
+/// dataset by the scheduler. The partitions are read back from the executors that hold
+/// them instead of recomputing the plan that produced them.
+#[derive(Debug, Clone)]
+pub struct DatasetTable {
+    dataset_id: String,
+    schema: SchemaRef,
+    partition: Vec<Vec<PartitionLocation>>,
+}
+
+impl DatasetTable {
+    /// Create a new DatasetTable
+    pub fn new(
+        dataset_id: String,
+        schema: SchemaRef,
+        partition: Vec<Vec<PartitionLocation>>,
+    ) -> Self {
+        Self {
+            dataset_id,
+            schema,
+            partition,
+        }
+    }
+
+    /// The id of the dataset, which is the id of the job that produced it
+    pub fn dataset_id(&self) -> &str {
+        &self.dataset_id
+    }
+
+    /// The locations of each partition of the dataset
+    pub fn partition(&self) -> &[Vec<PartitionLocation>] {
+        &self.partition
+    }
+}
+
+#[async_trait]
+impl TableProvider for DatasetTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        _batch_size: usize,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let scan = Arc::new(ShuffleReaderExec::try_new(
+            self.partition.clone(),
+            self.schema.clone(),
+        )?);
+        match projection {
+            None => Ok(scan),
+            Some(columns) => {
+                let expr = columns
+                    .iter()
+                    .map(|i| {
+                        let name = self.schema.field(*i).name();
+                        let column: Arc<dyn PhysicalExpr> =
+                            Arc::new(Column::new(name, *i));
+                        (column, name.to_owned())
+                    })
+                    .collect();
+                Ok(Arc::new(ProjectionExec::try_new(expr, scan)?))
+            }
+        }
+    }
+}
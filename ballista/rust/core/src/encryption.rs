@@ -0,0 +1,480 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Encryption at rest of the shuffle partitions and datasets written by the
+//! executors, with AES-256-GCM.
+//!
+//! An encrypted file starts with a header holding a magic number and the id of the
+//! key it is encrypted with, so that the keys can be rotated. The header is followed
+//! by segments of [`SEGMENT_SIZE`] bytes of plaintext, the last one being shorter,
+//! each encrypted with a random nonce. A segment is authenticated along with the
+//! header, its index and whether it is the last one, so that the segments of a file
+//! can neither be reordered nor truncated. As the segments have a fixed size, the
+//! encrypted files can be read at random positions, as the IPC file format does.
+
+use std::convert::TryInto;
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, NewAead, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use rand::RngCore;
+
+use crate::error::{BallistaError, Result};
+
+/// Number of bytes of plaintext encrypted in each segment of a file
+pub const SEGMENT_SIZE: usize = 64 * 1024;
+
+/// Magic number at the start of the encrypted files
+const ENCRYPTION_MAGIC: &[u8; 8] = b"BALLENC1";
+
+const NONCE_LEN: usize = 12;
+
+const TAG_LEN: usize = 16;
+
+/// Size of a segment of SEGMENT_SIZE bytes of plaintext once encrypted
+const ENCRYPTED_SEGMENT_SIZE: usize = NONCE_LEN + SEGMENT_SIZE + TAG_LEN;
+
+/// A 256 bits AES key
+pub type EncryptionKey = [u8; 32];
+
+/// Provides the keys the files are encrypted with, e.g. from the configuration of
+/// the executor or from a key management service. The keys are looked up every time
+/// a file is created or opened, so the providers calling a remote service should
+/// cache them.
+pub trait KeyProvider: Send + Sync + fmt::Debug {
+    /// Id of the key the new files are encrypted with, which is stored in the files
+    fn current_key_id(&self) -> Result<String>;
+
+    /// The key identified by `key_id`
+    fn key(&self, key_id: &str) -> Result<EncryptionKey>;
+}
+
+/// Provides a single key, e.g. read from the configuration of the executor
+#[derive(Clone)]
+pub struct StaticKeyProvider {
+    key_id: String,
+    key: EncryptionKey,
+}
+
+impl StaticKeyProvider {
+    /// Create a provider of `key`, identified by `key_id`
+    pub fn new(key_id: impl Into<String>, key: EncryptionKey) -> Self {
+        Self {
+            key_id: key_id.into(),
+            key,
+        }
+    }
+
+    /// Create a provider of the key whose 32 bytes are encoded as 64 hexadecimal
+    /// digits in `hex`, surrounding whitespace aside
+    pub fn from_hex(key_id: impl Into<String>, hex: &str) -> Result<Self> {
+        let hex = hex.trim();
+        let invalid = || {
+            BallistaError::General(
+                "Encryption keys must be 64 hexadecimal digits".to_owned(),
+            )
+        };
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let mut key = [0; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte =
+                u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(Self::new(key_id, key))
+    }
+}
+
+impl fmt::Debug for StaticKeyProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // never print the key
+        f.debug_struct("StaticKeyProvider")
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+impl KeyProvider for StaticKeyProvider {
+    fn current_key_id(&self) -> Result<String> {
+        Ok(self.key_id.clone())
+    }
+
+    fn key(&self, key_id: &str) -> Result<EncryptionKey> {
+        if key_id == self.key_id {
+            Ok(self.key)
+        } else {
+            Err(BallistaError::General(format!(
+                "Unknown encryption key '{}'",
+                key_id
+            )))
+        }
+    }
+}
+
+/// Encrypts and decrypts files with the keys of a [`KeyProvider`]
+#[derive(Debug, Clone)]
+pub struct ShuffleEncryption {
+    key_provider: Arc<dyn KeyProvider>,
+}
+
+impl ShuffleEncryption {
+    /// Create a new encryption with the keys of `key_provider`
+    pub fn new(key_provider: Arc<dyn KeyProvider>) -> Self {
+        Self { key_provider }
+    }
+
+    /// Write the header of an encrypted file to `inner`, and return a writer
+    /// encrypting the bytes written to it with the current key
+    pub fn encrypt<W: Write>(&self, mut inner: W) -> Result<EncryptingWriter<W>> {
+        let key_id = self.key_provider.current_key_id()?;
+        let key = self.key_provider.key(&key_id)?;
+        let key_id_len: u16 = key_id.len().try_into().map_err(|_| {
+            BallistaError::General(format!("Encryption key id '{}' is too long", key_id))
+        })?;
+        let mut header = ENCRYPTION_MAGIC.to_vec();
+        header.extend_from_slice(&key_id_len.to_le_bytes());
+        header.extend_from_slice(key_id.as_bytes());
+        inner.write_all(&header)?;
+        Ok(EncryptingWriter {
+            inner,
+            cipher: Aes256Gcm::new(Key::from_slice(&key)),
+            header,
+            segment: Vec::with_capacity(SEGMENT_SIZE),
+            num_segments: 0,
+            finished: false,
+        })
+    }
+
+    /// Read the header of the encrypted file `inner`, and return a reader of its
+    /// decrypted bytes
+    pub fn decrypt<R: Read + Seek>(&self, mut inner: R) -> Result<DecryptingReader<R>> {
+        let mut header = vec![0; ENCRYPTION_MAGIC.len() + 2];
+        inner.seek(SeekFrom::Start(0))?;
+        inner.read_exact(&mut header)?;
+        if header[..ENCRYPTION_MAGIC.len()] != ENCRYPTION_MAGIC[..] {
+            return Err(BallistaError::General(
+                "The file is not encrypted".to_owned(),
+            ));
+        }
+        let key_id_len = u16::from_le_bytes([header[8], header[9]]) as usize;
+        let mut key_id = vec![0; key_id_len];
+        inner.read_exact(&mut key_id)?;
+        header.extend_from_slice(&key_id);
+        let key_id = String::from_utf8(key_id).map_err(|_| {
+            BallistaError::General("Invalid encryption key id".to_owned())
+        })?;
+        let key = self.key_provider.key(&key_id)?;
+
+        let data_len = inner.seek(SeekFrom::End(0))? - header.len() as u64;
+        let num_segments = (data_len + ENCRYPTED_SEGMENT_SIZE as u64 - 1)
+            / ENCRYPTED_SEGMENT_SIZE as u64;
+        // the last segment is always written, even if it is empty
+        let last_segment_len = data_len
+            .checked_sub(num_segments.saturating_sub(1) * ENCRYPTED_SEGMENT_SIZE as u64)
+            .filter(|len| *len >= (NONCE_LEN + TAG_LEN) as u64)
+            .ok_or_else(|| {
+                BallistaError::General("The encrypted file is truncated".to_owned())
+            })?;
+        let len = (num_segments - 1) * SEGMENT_SIZE as u64 + last_segment_len
+            - (NONCE_LEN + TAG_LEN) as u64;
+
+        Ok(DecryptingReader {
+            inner,
+            cipher: Aes256Gcm::new(Key::from_slice(&key)),
+            header,
+            num_segments,
+            len,
+            position: 0,
+            segment: None,
+        })
+    }
+}
+
+/// Whether the file read by `reader` is encrypted. The reader is rewound to the
+/// start of the file.
+pub fn is_encrypted<R: Read + Seek>(reader: &mut R) -> Result<bool> {
+    let mut magic = [0; 8];
+    reader.seek(SeekFrom::Start(0))?;
+    let encrypted =
+        reader.read_exact(&mut magic).is_ok() && magic == ENCRYPTION_MAGIC[..];
+    reader.seek(SeekFrom::Start(0))?;
+    Ok(encrypted)
+}
+
+/// Additional data a segment is authenticated with
+fn segment_aad(header: &[u8], index: u64, last: bool) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.extend_from_slice(&index.to_le_bytes());
+    aad.push(last as u8);
+    aad
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Writer encrypting the bytes written to it. It must be finished with
+/// [`EncryptingWriter::finish`] once all the bytes are written, as the files whose
+/// last segment is missing are deemed truncated.
+pub struct EncryptingWriter<W: Write> {
+    inner: W,
+    cipher: Aes256Gcm,
+    header: Vec<u8>,
+    /// plaintext of the segment being written
+    segment: Vec<u8>,
+    num_segments: u64,
+    finished: bool,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+    fn write_segment(&mut self, last: bool) -> io::Result<()> {
+        let mut nonce = [0; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let aad = segment_aad(&self.header, self.num_segments, last);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &self.segment,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Failed to encrypt"))?;
+        self.inner.write_all(&nonce)?;
+        self.inner.write_all(&ciphertext)?;
+        self.segment.clear();
+        self.num_segments += 1;
+        Ok(())
+    }
+
+    /// Write the last segment and flush the underlying writer
+    pub fn finish(&mut self) -> io::Result<()> {
+        if !self.finished {
+            self.write_segment(true)?;
+            self.finished = true;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.finished {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Cannot write to a finished encrypted file",
+            ));
+        }
+        // a full segment is only written once more bytes follow it, so that the
+        // last segment is written by finish
+        if self.segment.len() == SEGMENT_SIZE {
+            self.write_segment(false)?;
+        }
+        let len = buf.len().min(SEGMENT_SIZE - self.segment.len());
+        self.segment.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader decrypting an encrypted file, one segment at a time
+pub struct DecryptingReader<R: Read + Seek> {
+    inner: R,
+    cipher: Aes256Gcm,
+    header: Vec<u8>,
+    num_segments: u64,
+    /// length of the plaintext
+    len: u64,
+    position: u64,
+    /// index and plaintext of the last decrypted segment
+    segment: Option<(u64, Vec<u8>)>,
+}
+
+impl<R: Read + Seek> DecryptingReader<R> {
+    /// Length of the decrypted file
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the decrypted file is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn load_segment(&mut self, index: u64) -> io::Result<&[u8]> {
+        if !matches!(&self.segment, Some((loaded, _)) if *loaded == index) {
+            let offset = self.header.len() as u64 + index * ENCRYPTED_SEGMENT_SIZE as u64;
+            let last = index + 1 == self.num_segments;
+            let len = if last {
+                (self.len - index * SEGMENT_SIZE as u64) as usize + NONCE_LEN + TAG_LEN
+            } else {
+                ENCRYPTED_SEGMENT_SIZE
+            };
+            let mut encrypted = vec![0; len];
+            self.inner.seek(SeekFrom::Start(offset))?;
+            self.inner.read_exact(&mut encrypted)?;
+            let aad = segment_aad(&self.header, index, last);
+            let plaintext = self
+                .cipher
+                .decrypt(
+                    Nonce::from_slice(&encrypted[..NONCE_LEN]),
+                    Payload {
+                        msg: &encrypted[NONCE_LEN..],
+                        aad: &aad,
+                    },
+                )
+                .map_err(|_| {
+                    invalid_data(format!(
+                        "Failed to decrypt segment {} of an encrypted file, which is \
+                         corrupted, truncated or encrypted with another key",
+                        index
+                    ))
+                })?;
+            self.segment = Some((index, plaintext));
+        }
+        Ok(self.segment.as_ref().unwrap().1.as_slice())
+    }
+}
+
+impl<R: Read + Seek> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let index = self.position / SEGMENT_SIZE as u64;
+        let offset = (self.position % SEGMENT_SIZE as u64) as usize;
+        let segment = self.load_segment(index)?;
+        let len = buf.len().min(segment.len() - offset);
+        buf[..len].copy_from_slice(&segment[offset..offset + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<R: Read + Seek> Seek for DecryptingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(position) => {
+                self.position = position;
+                return Ok(position);
+            }
+            SeekFrom::End(offset) => (self.len, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        let position = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encryption(key_id: &str, key: u8) -> ShuffleEncryption {
+        ShuffleEncryption::new(Arc::new(StaticKeyProvider::new(key_id, [key; 32])))
+    }
+
+    fn encrypt(encryption: &ShuffleEncryption, data: &[u8]) -> Result<Vec<u8>> {
+        let mut writer = encryption.encrypt(vec![])?;
+        writer.write_all(data)?;
+        writer.finish()?;
+        Ok(writer.inner)
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let encryption = encryption("k1", 7);
+        for len in [0, 10, SEGMENT_SIZE, 2 * SEGMENT_SIZE + 100] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let encrypted = encrypt(&encryption, &data)?;
+            let mut reader = encryption.decrypt(Cursor::new(&encrypted))?;
+            assert!(is_encrypted(&mut Cursor::new(&encrypted))?);
+            assert_eq!(len as u64, reader.len());
+            let mut decrypted = vec![];
+            reader.read_to_end(&mut decrypted)?;
+            assert_eq!(data, decrypted);
+
+            if len > 10 {
+                // random reads, as the IPC file reader does
+                reader.seek(SeekFrom::End(-10))?;
+                let mut tail = [0; 10];
+                reader.read_exact(&mut tail)?;
+                assert_eq!(&data[len - 10..], &tail[..]);
+            }
+        }
+        assert!(!is_encrypted(&mut Cursor::new(b"ARROW1"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_files() -> Result<()> {
+        let encryption = encryption("k1", 7);
+        let data = vec![1; 2 * SEGMENT_SIZE + 100];
+        let encrypted = encrypt(&encryption, &data)?;
+        let decrypt = |encrypted: &[u8], encryption: &ShuffleEncryption| -> Result<()> {
+            let mut reader = encryption.decrypt(Cursor::new(encrypted))?;
+            reader.read_to_end(&mut vec![])?;
+            Ok(())
+        };
+        decrypt(&encrypted, &encryption)?;
+
+        // a flipped byte
+        let mut corrupted = encrypted.clone();
+        corrupted[encrypted.len() / 2] ^= 1;
+        assert!(decrypt(&corrupted, &encryption).is_err());
+
+        // truncated at the end of a segment
+        let header_len = encrypted.len() - 3 * (NONCE_LEN + TAG_LEN) - data.len();
+        let truncated = &encrypted[..header_len + 2 * ENCRYPTED_SEGMENT_SIZE];
+        assert!(decrypt(truncated, &encryption).is_err());
+
+        // another key with the same id
+        assert!(decrypt(&encrypted, &self::encryption("k1", 8)).is_err());
+        // an unknown key
+        assert!(decrypt(&encrypted, &self::encryption("k2", 7)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn hex_keys() -> Result<()> {
+        let hex = format!("{}\n", "0f".repeat(32));
+        let provider = StaticKeyProvider::from_hex("k1", &hex)?;
+        assert_eq!([15; 32], provider.key("k1")?);
+        assert!(!format!("{:?}", provider).contains("15"));
+        assert!(StaticKeyProvider::from_hex("k1", "0f0f").is_err());
+        assert!(StaticKeyProvider::from_hex("k1", &"zz".repeat(32)).is_err());
+        Ok(())
+    }
+}
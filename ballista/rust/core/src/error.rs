@@ -47,6 +47,16 @@ pub enum BallistaError {
     TonicError(tonic::transport::Error),
     GrpcError(tonic::Status),
     TokioError(tokio::task::JoinError),
+    /// A shuffle partition file did not match the checksum computed when writing it
+    CorruptedPartition {
+        path: String,
+        reason: String,
+    },
+    /// A shuffle partition could not be fetched from its executor
+    FetchFailed {
+        path: String,
+        reason: String,
+    },
 }
 
 #[allow(clippy::from_over_into)]
@@ -166,8 +176,90 @@ impl Display for BallistaError {
                 write!(f, "Internal Ballista error: {}", desc)
             }
             BallistaError::TokioError(desc) => write!(f, "Tokio join error: {}", desc),
+            BallistaError::CorruptedPartition { path, reason } => {
+                write!(f, "Corrupted shuffle partition {}: {}", path, reason)
+            }
+            BallistaError::FetchFailed { path, reason } => {
+                write!(f, "Failed to fetch shuffle partition {}: {}", path, reason)
+            }
         }
     }
 }
 
 impl Error for BallistaError {}
+
+impl BallistaError {
+    /// The path of the corrupted or unreachable shuffle partition this error was
+    /// caused by, if any, looking through the DataFusion and Arrow errors it may be
+    /// wrapped in
+    pub fn lost_partition_path(&self) -> Option<&str> {
+        match self {
+            BallistaError::CorruptedPartition { path, .. }
+            | BallistaError::FetchFailed { path, .. } => Some(path),
+            BallistaError::DataFusionError(e) => datafusion_lost_partition_path(e),
+            BallistaError::ArrowError(e) => arrow_lost_partition_path(e),
+            _ => None,
+        }
+    }
+}
+
+fn datafusion_lost_partition_path(e: &DataFusionError) -> Option<&str> {
+    match e {
+        DataFusionError::External(e) => external_lost_partition_path(e.as_ref()),
+        DataFusionError::ArrowError(e) => arrow_lost_partition_path(e),
+        _ => None,
+    }
+}
+
+fn arrow_lost_partition_path(e: &ArrowError) -> Option<&str> {
+    match e {
+        ArrowError::ExternalError(e) => external_lost_partition_path(e.as_ref()),
+        _ => None,
+    }
+}
+
+fn external_lost_partition_path(e: &(dyn Error + Send + Sync + 'static)) -> Option<&str> {
+    if let Some(e) = e.downcast_ref::<BallistaError>() {
+        e.lost_partition_path()
+    } else if let Some(e) = e.downcast_ref::<DataFusionError>() {
+        datafusion_lost_partition_path(e)
+    } else if let Some(e) = e.downcast_ref::<ArrowError>() {
+        arrow_lost_partition_path(e)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lost_partition_path() {
+        let corrupted = BallistaError::CorruptedPartition {
+            path: "/tmp/job/1/0/data.arrow".to_owned(),
+            reason: "checksum mismatch".to_owned(),
+        };
+        let wrapped = BallistaError::DataFusionError(DataFusionError::ArrowError(
+            ArrowError::ExternalError(Box::new(DataFusionError::External(Box::new(
+                corrupted,
+            )))),
+        ));
+        assert_eq!(
+            Some("/tmp/job/1/0/data.arrow"),
+            wrapped.lost_partition_path()
+        );
+        let fetch_failed = BallistaError::FetchFailed {
+            path: "/tmp/job/1/1/data.arrow".to_owned(),
+            reason: "connection refused".to_owned(),
+        };
+        assert_eq!(
+            Some("/tmp/job/1/1/data.arrow"),
+            fetch_failed.lost_partition_path()
+        );
+        assert_eq!(
+            None,
+            BallistaError::General("error".to_owned()).lost_partition_path()
+        );
+    }
+}
@@ -23,30 +23,39 @@ use std::time::Duration;
 
 use crate::client::BallistaClient;
 use crate::config::BallistaConfig;
+use crate::dataset::DatasetTable;
+use crate::error::BallistaError;
+use crate::message_chunks::{encode_chunks, MESSAGE_CHUNK_SIZE};
 use crate::serde::protobuf::{
     execute_query_params::Query, job_status, scheduler_grpc_client::SchedulerGrpcClient,
-    ExecuteQueryParams, GetJobStatusParams, GetJobStatusResult, KeyValuePair,
-    PartitionLocation,
+    ExecuteQueryParams, GetJobMetricsParams, GetJobStatusParams, GetJobStatusResult,
+    KeyValuePair, PartitionLocation, PersistDatasetParams,
 };
-use crate::utils::WrappedStream;
 
+use datafusion::arrow::array::StringBuilder;
 use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::arrow::error::{ArrowError, Result as ArrowResult};
+use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::error::{DataFusionError, Result};
 use datafusion::logical_plan::LogicalPlan;
+use datafusion::physical_plan::cancellation;
+use datafusion::physical_plan::memory::MemoryStream;
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
 use datafusion::physical_plan::{
-    DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
-    SendableRecordBatchStream, Statistics,
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
 };
 
 use async_trait::async_trait;
-use futures::future;
 use futures::StreamExt;
 use log::{error, info};
+use prost::Message;
+use tokio::sync::mpsc;
+use tonic::transport::Channel;
 
 /// This operator sends a logial plan to a Ballista scheduler for execution and
-/// polls the scheduler until the query is complete and then fetches the resulting
-/// batches directly from the executors that hold the results from the final
-/// query stage.
+/// polls the scheduler while the query runs, fetching the resulting batches
+/// directly from the executors that hold the results from the final query stage
+/// as soon as the tasks of the final stage complete.
 #[derive(Debug, Clone)]
 pub struct DistributedQueryExec {
     /// Ballista scheduler URL
@@ -65,73 +74,86 @@ impl DistributedQueryExec {
             plan,
         }
     }
-}
-
-#[async_trait]
-impl ExecutionPlan for DistributedQueryExec {
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-
-    fn schema(&self) -> SchemaRef {
-        self.plan.schema().as_ref().clone().into()
-    }
-
-    fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(1)
-    }
 
-    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
-        vec![]
-    }
+    /// Executes the logical plan and registers its output with the scheduler as a
+    /// dataset, returning a table provider that scans the output partitions where the
+    /// executors wrote them.
+    pub async fn persist(&self) -> Result<DatasetTable> {
+        let mut scheduler = self.connect().await?;
+        let schema: Schema = self.plan.schema().as_ref().clone().into();
+        let (job_id, _) = self.run_job(&mut scheduler, &self.plan).await?;
 
-    fn with_new_children(
-        &self,
-        _children: Vec<Arc<dyn ExecutionPlan>>,
-    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
-        Ok(Arc::new(DistributedQueryExec::new(
-            self.scheduler_url.clone(),
-            self.config.clone(),
-            self.plan.clone(),
-        )))
+        let dataset = scheduler
+            .persist_dataset(PersistDatasetParams {
+                job_id,
+                schema: Some((&schema).into()),
+            })
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?
+            .into_inner()
+            .dataset
+            .ok_or_else(|| {
+                DataFusionError::Internal("Received empty dataset".to_owned())
+            })?;
+        dataset
+            .try_into()
+            .map_err(|e: BallistaError| DataFusionError::Execution(format!("{:?}", e)))
     }
 
-    async fn execute(
-        &self,
-        partition: usize,
-    ) -> datafusion::error::Result<SendableRecordBatchStream> {
-        assert_eq!(0, partition);
-
+    async fn connect(&self) -> Result<SchedulerGrpcClient<Channel>> {
         info!("Connecting to Ballista scheduler at {}", self.scheduler_url);
 
-        let mut scheduler = SchedulerGrpcClient::connect(self.scheduler_url.clone())
+        SchedulerGrpcClient::connect(self.scheduler_url.clone())
             .await
-            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
-
-        let schema: Schema = self.plan.schema().as_ref().clone().into();
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))
+    }
 
-        let job_id = scheduler
-            .execute_query(ExecuteQueryParams {
-                query: Some(Query::LogicalPlan(
-                    (&self.plan)
-                        .try_into()
-                        .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?,
-                )),
-                settings: self
-                    .config
-                    .settings()
-                    .iter()
-                    .map(|(k, v)| KeyValuePair {
-                        key: k.to_owned(),
-                        value: v.to_owned(),
-                    })
-                    .collect::<Vec<_>>(),
-            })
-            .await
+    /// Submits `plan` to the scheduler, returning the id of its job
+    async fn submit_job(
+        &self,
+        scheduler: &mut SchedulerGrpcClient<Channel>,
+        plan: &LogicalPlan,
+    ) -> Result<String> {
+        let params = ExecuteQueryParams {
+            query: Some(Query::LogicalPlan(
+                plan.try_into()
+                    .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?,
+            )),
+            settings: self
+                .config
+                .settings()
+                .iter()
+                .map(|(k, v)| KeyValuePair {
+                    key: k.to_owned(),
+                    value: v.to_owned(),
+                })
+                .collect::<Vec<_>>(),
+        };
+        // plans scanning many files are sent as compressed chunks, as they may not
+        // fit in a single message
+        let result = if params.encoded_len() > MESSAGE_CHUNK_SIZE {
+            let chunks = encode_chunks(&params, MESSAGE_CHUNK_SIZE)
+                .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+            scheduler
+                .execute_query_chunked(futures::stream::iter(chunks))
+                .await
+        } else {
+            scheduler.execute_query(params).await
+        };
+        Ok(result
             .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?
             .into_inner()
-            .job_id;
+            .job_id)
+    }
 
+    /// Submits `plan` to the scheduler and polls it until the job completes,
+    /// returning the job id and the locations of the output partitions
+    async fn run_job(
+        &self,
+        scheduler: &mut SchedulerGrpcClient<Channel>,
+        plan: &LogicalPlan,
+    ) -> Result<(String, Vec<PartitionLocation>)> {
+        let job_id = self.submit_job(scheduler, plan).await?;
         let mut prev_status: Option<job_status::Status> = None;
 
         loop {
@@ -168,26 +190,104 @@ impl ExecutionPlan for DistributedQueryExec {
                     break Err(DataFusionError::Execution(msg));
                 }
                 job_status::Status::Completed(completed) => {
-                    let result = future::join_all(
-                        completed
-                            .partition_location
-                            .into_iter()
-                            .map(fetch_partition),
-                    )
-                    .await
-                    .into_iter()
-                    .collect::<Result<Vec<_>>>()?;
-
-                    let result = WrappedStream::new(
-                        Box::pin(futures::stream::iter(result).flatten()),
-                        Arc::new(schema),
-                    );
-                    break Ok(Box::pin(result));
+                    let mut partition_location = completed.partition_location;
+                    partition_location.sort_by_key(output_partition_id);
+                    break Ok((job_id, partition_location));
                 }
             };
         }
     }
 
+    /// Returns the plans of the stages of a completed job, with the metrics of their
+    /// operators, in the `plan_type` and `plan` columns of `EXPLAIN ANALYZE`
+    async fn job_metrics(
+        &self,
+        scheduler: &mut SchedulerGrpcClient<Channel>,
+        job_id: String,
+    ) -> Result<SendableRecordBatchStream> {
+        let stages = scheduler
+            .get_job_metrics(GetJobMetricsParams { job_id })
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?
+            .into_inner()
+            .stages;
+        let mut type_builder = StringBuilder::new(stages.len());
+        let mut plan_builder = StringBuilder::new(stages.len());
+        for stage in stages {
+            type_builder
+                .append_value(format!("Stage {} Plan with Metrics", stage.stage_id))?;
+            plan_builder.append_value(stage.plan)?;
+        }
+        let schema: SchemaRef = Arc::new(self.plan.schema().as_ref().clone().into());
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(type_builder.finish()),
+                Arc::new(plan_builder.finish()),
+            ],
+        )?;
+        Ok(Box::pin(MemoryStream::try_new(vec![batch], schema, None)?))
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for DistributedQueryExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.plan.schema().as_ref().clone().into()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(DistributedQueryExec::new(
+            self.scheduler_url.clone(),
+            self.config.clone(),
+            self.plan.clone(),
+        )))
+    }
+
+    async fn execute(
+        &self,
+        partition: usize,
+    ) -> datafusion::error::Result<SendableRecordBatchStream> {
+        assert_eq!(0, partition);
+
+        let mut scheduler = self.connect().await?;
+        if let LogicalPlan::Analyze(analyze) = &self.plan {
+            // the input is executed by the cluster, which reports the metrics of
+            // every stage, and its results are discarded
+            let (job_id, _) = self.run_job(&mut scheduler, &analyze.input).await?;
+            return self.job_metrics(&mut scheduler, job_id).await;
+        }
+        let job_id = self.submit_job(&mut scheduler, &self.plan).await?;
+        let schema = self.schema();
+        let (sender, receiver) = mpsc::channel(2);
+        let join_handle = cancellation::spawn(async move {
+            if let Err(e) = stream_job_results(scheduler, job_id, &sender).await {
+                let arrow_error = ArrowError::ExternalError(Box::new(e));
+                sender.send(Err(arrow_error)).await.ok();
+            }
+        });
+        Ok(RecordBatchReceiverStream::create(
+            &schema,
+            receiver,
+            join_handle,
+        ))
+    }
+
     fn fmt_as(
         &self,
         t: DisplayFormatType,
@@ -212,6 +312,77 @@ impl ExecutionPlan for DistributedQueryExec {
     }
 }
 
+/// Returns the number of the output partition of the final stage at `location`,
+/// which is the order in which the partitions are returned, to preserve the order
+/// of results that are range partitioned or merged into a single sorted partition
+fn output_partition_id(location: &PartitionLocation) -> u32 {
+    location
+        .partition_id
+        .as_ref()
+        .map(|id| id.partition_id)
+        .unwrap_or_default()
+}
+
+/// Polls the scheduler until job `job_id` completes and sends the batches of its
+/// output partitions in order, fetching each partition as soon as the task that
+/// produced it completed and the earlier partitions were sent, while the later
+/// partitions are still being computed
+async fn stream_job_results(
+    mut scheduler: SchedulerGrpcClient<Channel>,
+    job_id: String,
+    sender: &mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
+    let mut next_partition = 0;
+    loop {
+        let GetJobStatusResult { status } = scheduler
+            .get_job_status(GetJobStatusParams {
+                job_id: job_id.clone(),
+            })
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?
+            .into_inner();
+        let status = status.and_then(|s| s.status).ok_or_else(|| {
+            DataFusionError::Internal("Received empty status message".to_owned())
+        })?;
+        let (mut partition_location, completed) = match status {
+            job_status::Status::Queued(_) => (vec![], false),
+            job_status::Status::Running(running) => (running.partition_location, false),
+            job_status::Status::Failed(err) => {
+                let msg = format!("Job {} failed: {}", job_id, err.error);
+                error!("{}", msg);
+                return Err(DataFusionError::Execution(msg));
+            }
+            job_status::Status::Completed(completed) => {
+                (completed.partition_location, true)
+            }
+        };
+        partition_location
+            .retain(|location| output_partition_id(location) >= next_partition);
+        partition_location.sort_by_key(output_partition_id);
+        for location in partition_location {
+            let partition = output_partition_id(&location);
+            if !completed && partition > next_partition {
+                // the earlier partitions are still being computed
+                break;
+            }
+            let mut stream = fetch_partition(location).await?;
+            while let Some(batch) = stream.next().await {
+                let failed = batch.is_err();
+                // if the send fails the results are no longer read
+                if sender.send(batch).await.is_err() || failed {
+                    return Ok(());
+                }
+            }
+            next_partition = partition + 1;
+        }
+        if completed {
+            info!("Job {} completed", job_id);
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
 async fn fetch_partition(
     location: PartitionLocation,
 ) -> Result<SendableRecordBatchStream> {
@@ -231,6 +402,7 @@ async fn fetch_partition(
             partition_id.stage_id as usize,
             partition_id.partition_id as usize,
             &location.path,
+            location.checksum.map(|checksum| checksum.crc32),
         )
         .await
         .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?)
@@ -19,11 +19,18 @@
 //! several Ballista executors.
 
 mod distributed_query;
+mod morsels;
+mod sample;
 mod shuffle_reader;
 mod shuffle_writer;
 mod unresolved_shuffle;
 
 pub use distributed_query::DistributedQueryExec;
-pub use shuffle_reader::ShuffleReaderExec;
-pub use shuffle_writer::ShuffleWriterExec;
+pub use morsels::{split_into_morsels, MorselPool};
+pub use sample::{SampleExec, DEFAULT_SAMPLE_SIZE};
+pub use shuffle_reader::{
+    set_shuffle_fetch_options, shuffle_fetch_options, ShuffleFetchOptions,
+    ShuffleReaderExec, TrackedShuffle,
+};
+pub use shuffle_writer::{RangeShufflePartitioning, ShuffleWriterExec};
 pub use unresolved_shuffle::UnresolvedShuffleExec;
@@ -0,0 +1,296 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Morsel-driven execution of the partitions of a query stage.
+//!
+//! A task executes one partition of a query stage. When that partition scans files
+//! with operators that process every batch independently of the others, it can be
+//! split into morsels, each reading some of the files or byte ranges of files, that
+//! are executed in parallel by the workers of a [`MorselPool`] shared by all the
+//! tasks of an executor. The output of the partition is then the output of all of
+//! its morsels, in no particular order.
+
+use std::sync::Arc;
+
+use datafusion::arrow::error::ArrowError;
+use datafusion::datasource::listing::{
+    split_files, split_files_by_range, MIN_FILE_RANGE_SIZE,
+};
+use datafusion::datasource::PartitionedFile;
+use datafusion::error::Result;
+use datafusion::physical_plan::cancellation;
+use datafusion::physical_plan::coalesce_batches::CoalesceBatchesExec;
+use datafusion::physical_plan::file_format::{
+    AvroExec, CsvExec, ParquetExec, PhysicalPlanConfig,
+};
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::{ExecutionPlan, SendableRecordBatchStream};
+use futures::StreamExt;
+use log::debug;
+use tokio::sync::{mpsc, Semaphore};
+
+/// A pool of workers, shared by the tasks of an executor, that execute the morsels
+/// of the partitions of the tasks.
+///
+/// Idle workers pick up the next morsel of any task, so that a single large task can
+/// use the workers that the other tasks leave idle, rather than one per task slot.
+#[derive(Debug, Clone)]
+pub struct MorselPool {
+    /// One permit per idle worker
+    workers: Arc<Semaphore>,
+    /// Maximum number of morsels a partition is split into
+    max_morsels: usize,
+}
+
+impl MorselPool {
+    /// Create a pool of `num_workers` workers, splitting every partition into at most
+    /// `max_morsels` morsels
+    pub fn new(num_workers: usize, max_morsels: usize) -> Self {
+        Self {
+            workers: Arc::new(Semaphore::new(num_workers.max(1))),
+            max_morsels: max_morsels.max(1),
+        }
+    }
+
+    /// Execute `partition` of `plan`, split into morsels that are executed by the
+    /// workers of the pool if possible
+    pub async fn execute(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        partition: usize,
+    ) -> Result<SendableRecordBatchStream> {
+        let morsels = match split_into_morsels(&plan, partition, self.max_morsels)? {
+            Some(morsels) => morsels,
+            None => return plan.execute(partition).await,
+        };
+        let num_morsels = morsels.output_partitioning().partition_count();
+        debug!(
+            "Executing partition {} in {} morsels",
+            partition, num_morsels
+        );
+
+        let schema = morsels.schema();
+        let (sender, receiver) = mpsc::channel(num_morsels);
+        let workers = self.workers.clone();
+        // the morsels are executed with the cancellation token of the task
+        let join_handle = cancellation::spawn(async move {
+            for morsel in 0..num_morsels {
+                // wait for a worker to be idle
+                let worker = match workers.clone().acquire_owned().await {
+                    Ok(worker) => worker,
+                    Err(_) => return,
+                };
+                let morsels = morsels.clone();
+                let sender = sender.clone();
+                cancellation::spawn(async move {
+                    let mut stream = match morsels.execute(morsel).await {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            let arrow_error = ArrowError::ExternalError(Box::new(e));
+                            sender.send(Err(arrow_error)).await.ok();
+                            return;
+                        }
+                    };
+                    while let Some(item) = stream.next().await {
+                        // if the send fails the partition is no longer read
+                        if sender.send(item).await.is_err() {
+                            break;
+                        }
+                    }
+                    drop(worker);
+                });
+            }
+        });
+        Ok(RecordBatchReceiverStream::create(
+            &schema,
+            receiver,
+            join_handle,
+        ))
+    }
+}
+
+/// Returns a plan whose partitions are the morsels of `partition` of `plan`, which
+/// together return the rows of that partition, or `None` if the partition can't be
+/// split into several morsels.
+///
+/// Only plans made of a file scan and of operators that process every batch
+/// independently of the others, i.e. filters, projections and partial aggregations,
+/// can be split.
+pub fn split_into_morsels(
+    plan: &Arc<dyn ExecutionPlan>,
+    partition: usize,
+    max_morsels: usize,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    let any = plan.as_any();
+    let processes_batches_independently = any.downcast_ref::<FilterExec>().is_some()
+        || any.downcast_ref::<ProjectionExec>().is_some()
+        || any.downcast_ref::<CoalesceBatchesExec>().is_some()
+        || matches!(
+            any.downcast_ref::<HashAggregateExec>(),
+            Some(aggregate) if *aggregate.mode() == AggregateMode::Partial
+        );
+    if processes_batches_independently {
+        let input = &plan.children()[0];
+        return match split_into_morsels(input, partition, max_morsels)? {
+            Some(morsels) => Ok(Some(plan.with_new_children(vec![morsels])?)),
+            None => Ok(None),
+        };
+    }
+
+    let scan: Option<Arc<dyn ExecutionPlan>> =
+        if let Some(scan) = any.downcast_ref::<ParquetExec>() {
+            split_file_group(scan.base_config(), partition, max_morsels, true)
+                .map(|groups| Arc::new(scan.with_file_groups(groups)) as _)
+        } else if let Some(scan) = any.downcast_ref::<CsvExec>() {
+            split_file_group(scan.base_config(), partition, max_morsels, true)
+                .map(|groups| Arc::new(scan.with_file_groups(groups)) as _)
+        } else if let Some(scan) = any.downcast_ref::<AvroExec>() {
+            split_file_group(scan.base_config(), partition, max_morsels, false)
+                .map(|groups| Arc::new(scan.with_file_groups(groups)) as _)
+        } else {
+            None
+        };
+    Ok(scan)
+}
+
+/// Splits the files that `partition` of a scan reads into morsels, which are byte
+/// ranges of the files if the file format is `splittable`
+fn split_file_group(
+    config: &PhysicalPlanConfig,
+    partition: usize,
+    max_morsels: usize,
+    splittable: bool,
+) -> Option<Vec<Vec<PartitionedFile>>> {
+    // every morsel would read up to the limit
+    if config.limit.is_some() {
+        return None;
+    }
+    let files = config.file_groups.get(partition)?.clone();
+    let morsels = if splittable {
+        split_files_by_range(files, max_morsels, MIN_FILE_RANGE_SIZE)
+    } else {
+        split_files(files, max_morsels)
+    };
+    if morsels.len() > 1 {
+        Some(morsels)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::datasource::object_store::local::LocalFileSystem;
+    use datafusion::logical_plan::Operator;
+    use datafusion::physical_plan::common;
+    use datafusion::physical_plan::expressions::{binary, col, lit, PhysicalSortExpr};
+    use datafusion::physical_plan::sort::SortExec;
+    use datafusion::physical_plan::Statistics;
+    use datafusion::scalar::ScalarValue;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn csv_scan(file_groups: Vec<Vec<PartitionedFile>>) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        Arc::new(CsvExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_schema: schema,
+                file_groups,
+                statistics: Statistics::default(),
+                projection: None,
+                nested_projection: vec![],
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+                bucket_columns: None,
+            },
+            false,
+            b',',
+        ))
+    }
+
+    #[test]
+    fn split_scan_into_morsels() -> Result<()> {
+        let file = |name: &str, size| PartitionedFile::new(name.to_owned(), size);
+        let scan = csv_scan(vec![
+            vec![file("a.csv", 64 * 1024 * 1024)],
+            vec![file("b.csv", 10), file("c.csv", 10)],
+            vec![file("d.csv", 10)],
+        ]);
+        let schema = scan.schema();
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(FilterExec::try_new(
+            binary(
+                col("a", &schema)?,
+                Operator::Gt,
+                lit(ScalarValue::Int32(Some(1))),
+                &schema,
+            )?,
+            scan,
+        )?);
+
+        // a large file is split into byte ranges
+        let morsels = split_into_morsels(&plan, 0, 4)?.unwrap();
+        assert_eq!(4, morsels.output_partitioning().partition_count());
+        // small files are read whole
+        let morsels = split_into_morsels(&plan, 1, 4)?.unwrap();
+        assert_eq!(2, morsels.output_partitioning().partition_count());
+        assert!(split_into_morsels(&plan, 2, 4)?.is_none());
+
+        // a sort needs all the rows of the partition
+        let sort: Arc<dyn ExecutionPlan> = Arc::new(SortExec::try_new(
+            vec![PhysicalSortExpr {
+                expr: col("a", &schema)?,
+                options: Default::default(),
+            }],
+            plan,
+        )?);
+        assert!(split_into_morsels(&sort, 0, 4)?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn execute_morsels() -> Result<()> {
+        let dir = TempDir::new()?;
+        let mut files = vec![];
+        for i in 0..3 {
+            let path = dir.path().join(format!("{}.csv", i));
+            let mut file = std::fs::File::create(&path)?;
+            for value in 0..100 {
+                writeln!(file, "{}", value)?;
+            }
+            let size = file.metadata()?.len();
+            files.push(PartitionedFile::new(
+                path.to_str().unwrap().to_owned(),
+                size,
+            ));
+        }
+        let scan = csv_scan(vec![files]);
+
+        let pool = MorselPool::new(2, 4);
+        let stream = pool.execute(scan, 0).await?;
+        let batches = common::collect(stream).await?;
+        let num_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(300, num_rows);
+        Ok(())
+    }
+}
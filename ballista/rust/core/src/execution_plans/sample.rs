@@ -0,0 +1,275 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! SampleExec draws a uniform random sample of the sort keys of each input partition. The
+//! samples are used to compute the boundaries of range partitioned shuffles.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::memory_stream::MemoryStream;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::{Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::physical_plan::expressions::PhysicalSortExpr;
+use datafusion::physical_plan::{
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
+};
+use datafusion::scalar::ScalarValue;
+use futures::StreamExt;
+
+/// Default number of sampled rows per input partition
+pub const DEFAULT_SAMPLE_SIZE: usize = 1000;
+
+/// SampleExec evaluates the sort expressions of a range partitioned shuffle on its input
+/// and returns a reservoir sample of at most `sample_size` of the resulting sort keys for
+/// each input partition. The output has one column per sort expression.
+#[derive(Debug, Clone)]
+pub struct SampleExec {
+    input: Arc<dyn ExecutionPlan>,
+    sort_exprs: Vec<PhysicalSortExpr>,
+    sample_size: usize,
+    schema: SchemaRef,
+}
+
+impl SampleExec {
+    /// Create a new SampleExec
+    pub fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        sort_exprs: Vec<PhysicalSortExpr>,
+        sample_size: usize,
+    ) -> Result<Self> {
+        let input_schema = input.schema();
+        let fields = sort_exprs
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                Ok(Field::new(
+                    &format!("sort_key_{}", i),
+                    e.expr.data_type(&input_schema)?,
+                    true,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            input,
+            sort_exprs,
+            sample_size,
+            schema: Arc::new(Schema::new(fields)),
+        })
+    }
+
+    /// The input plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// The sort expressions that are sampled
+    pub fn sort_exprs(&self) -> &[PhysicalSortExpr] {
+        &self.sort_exprs
+    }
+
+    /// Maximum number of sampled rows per input partition
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for SampleExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(SampleExec::try_new(
+                children[0].clone(),
+                self.sort_exprs.clone(),
+                self.sample_size,
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "SampleExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        let mut stream = self.input.execute(partition).await?;
+
+        // the seed only depends on the partition, so that retried tasks sample
+        // the same rows
+        let mut rng = XorShift::new(partition as u64);
+        let mut reservoir: Vec<Vec<ScalarValue>> = Vec::with_capacity(self.sample_size);
+        let mut num_rows = 0_u64;
+        while let Some(batch) = stream.next().await {
+            let batch = batch?;
+            let keys = self
+                .sort_exprs
+                .iter()
+                .map(|e| Ok(e.expr.evaluate(&batch)?.into_array(batch.num_rows())))
+                .collect::<Result<Vec<_>>>()?;
+            for row in 0..batch.num_rows() {
+                num_rows += 1;
+                let slot = if reservoir.len() < self.sample_size {
+                    None
+                } else {
+                    let slot = rng.next() % num_rows;
+                    if slot >= self.sample_size as u64 {
+                        continue;
+                    }
+                    Some(slot as usize)
+                };
+                let key = keys
+                    .iter()
+                    .map(|k| ScalarValue::try_from_array(k, row))
+                    .collect::<Result<Vec<_>>>()?;
+                match slot {
+                    Some(slot) => reservoir[slot] = key,
+                    None => reservoir.push(key),
+                }
+            }
+        }
+
+        let batches = if reservoir.is_empty() {
+            vec![]
+        } else {
+            let columns = (0..self.sort_exprs.len())
+                .map(|i| {
+                    ScalarValue::iter_to_array(reservoir.iter().map(|r| r[i].clone()))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            vec![RecordBatch::try_new(self.schema.clone(), columns)?]
+        };
+        Ok(Box::pin(MemoryStream::try_new(
+            batches,
+            self.schema.clone(),
+            None,
+        )?))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                let expr: Vec<String> =
+                    self.sort_exprs.iter().map(|e| e.to_string()).collect();
+                write!(
+                    f,
+                    "SampleExec: sample_size={}, [{}]",
+                    self.sample_size,
+                    expr.join(",")
+                )
+            }
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// Small deterministic pseudo random number generator for reservoir sampling
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // the state must never be zero
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+    use datafusion::arrow::array::{Array, Int32Array};
+    use datafusion::arrow::datatypes::DataType;
+    use datafusion::physical_plan::expressions::col;
+    use datafusion::physical_plan::memory::MemoryExec;
+
+    #[tokio::test]
+    async fn sample_sort_keys() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from((0..100).collect::<Vec<_>>()))],
+        )?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone(), batch], vec![]],
+            schema.clone(),
+            None,
+        )?);
+        let sort_exprs = vec![PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: Default::default(),
+        }];
+        let sample = SampleExec::try_new(input, sort_exprs, 10)?;
+        assert_eq!(2, sample.output_partitioning().partition_count());
+
+        let mut stream = sample.execute(0).await?;
+        let batches = utils::collect_stream(&mut stream)
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+        assert_eq!(1, batches.len());
+        assert_eq!("sort_key_0", batches[0].schema().field(0).name());
+        let keys = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap();
+        assert_eq!(10, keys.len());
+        assert!(keys.values().iter().all(|k| (0..100).contains(k)));
+
+        // empty partitions produce no samples
+        let mut stream = sample.execute(1).await?;
+        let batches = utils::collect_stream(&mut stream)
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+        assert!(batches.is_empty());
+        Ok(())
+    }
+}
@@ -16,10 +16,13 @@
 // under the License.
 
 use std::fmt::Formatter;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use std::{any::Any, pin::Pin};
 
 use crate::client::BallistaClient;
+use crate::error::BallistaError;
+use crate::map_output_tracker::map_output_tracker;
 use crate::memory_stream::MemoryStream;
 use crate::serde::scheduler::{PartitionLocation, PartitionStats};
 
@@ -38,10 +41,49 @@ use datafusion::{
     error::{DataFusionError, Result},
     physical_plan::RecordBatchStream,
 };
-use futures::{future, Stream, StreamExt};
+use futures::{Stream, StreamExt, TryStreamExt};
 use hashbrown::HashMap;
-use log::info;
+use lazy_static::lazy_static;
+use log::{info, warn};
 use std::time::Instant;
+use tonic::Code;
+
+/// Options of the fetches of the shuffle partitions from the executors
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShuffleFetchOptions {
+    /// Maximum number of shuffle partitions a task fetches concurrently
+    pub max_concurrent_fetches: usize,
+    /// Number of times a fetch failing with a transient error is retried
+    pub max_retries: usize,
+    /// Delay before retrying a failed fetch, doubled after each retry
+    pub retry_backoff: Duration,
+}
+
+impl Default for ShuffleFetchOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrent_fetches: 64,
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+lazy_static! {
+    static ref FETCH_OPTIONS: RwLock<ShuffleFetchOptions> =
+        RwLock::new(ShuffleFetchOptions::default());
+}
+
+/// Sets the options of the fetches of the shuffle partitions by all the shuffle
+/// readers executed by this process
+pub fn set_shuffle_fetch_options(options: ShuffleFetchOptions) {
+    *FETCH_OPTIONS.write().unwrap() = options;
+}
+
+/// Gets the options of the fetches of the shuffle partitions
+pub fn shuffle_fetch_options() -> ShuffleFetchOptions {
+    FETCH_OPTIONS.read().unwrap().clone()
+}
 
 /// ShuffleReaderExec reads partitions that have already been materialized by a ShuffleWriterExec
 /// being executed by an executor
@@ -50,10 +92,21 @@ pub struct ShuffleReaderExec {
     /// Each partition of a shuffle can read data from multiple locations
     pub(crate) partition: Vec<Vec<PartitionLocation>>,
     pub(crate) schema: SchemaRef,
+    /// Shuffle whose partition locations are queried from the map output tracker
+    /// when executing, in which case `partition` holds no locations
+    tracked_shuffle: Option<TrackedShuffle>,
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
 }
 
+/// Shuffle written by the tasks of a stage, whose partition locations are known to
+/// the map output tracker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedShuffle {
+    pub job_id: String,
+    pub stage_id: usize,
+}
+
 impl ShuffleReaderExec {
     /// Create a new ShuffleReaderExec
     pub fn try_new(
@@ -63,9 +116,67 @@ impl ShuffleReaderExec {
         Ok(Self {
             partition,
             schema,
+            tracked_shuffle: None,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    /// Create a new ShuffleReaderExec reading the `partition_count` partitions of the
+    /// shuffle written by the stage `stage_id` of the job `job_id`, whose locations
+    /// are queried from the map output tracker when executing
+    pub fn try_new_tracked(
+        job_id: impl Into<String>,
+        stage_id: usize,
+        partition_count: usize,
+        schema: SchemaRef,
+    ) -> Result<Self> {
+        Ok(Self {
+            partition: vec![vec![]; partition_count],
+            schema,
+            tracked_shuffle: Some(TrackedShuffle {
+                job_id: job_id.into(),
+                stage_id,
+            }),
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
+
+    /// The locations of the shuffle partitions read by each partition
+    pub fn partition(&self) -> &[Vec<PartitionLocation>] {
+        &self.partition
+    }
+
+    /// The shuffle whose partition locations are queried from the map output
+    /// tracker, if any
+    pub fn tracked_shuffle(&self) -> Option<&TrackedShuffle> {
+        self.tracked_shuffle.as_ref()
+    }
+
+    /// The locations of the shuffle partitions read by `partition`
+    async fn partition_locations(
+        &self,
+        partition: usize,
+    ) -> Result<Vec<PartitionLocation>> {
+        let shuffle = match &self.tracked_shuffle {
+            Some(shuffle) => shuffle,
+            None => return Ok(self.partition[partition].clone()),
+        };
+        let tracker = map_output_tracker().ok_or_else(|| {
+            DataFusionError::Execution(
+                "No map output tracker to get the shuffle partition locations from"
+                    .to_owned(),
+            )
+        })?;
+        tracker
+            .get_map_outputs(&shuffle.job_id, shuffle.stage_id, partition)
+            .await
+            .map_err(|e| {
+                DataFusionError::Execution(format!(
+                    "Could not get the locations of partition {} of stage {} of job {}: {:?}",
+                    partition, shuffle.stage_id, shuffle.job_id, e
+                ))
+            })
+    }
 }
 
 #[async_trait]
@@ -90,11 +201,18 @@ impl ExecutionPlan for ShuffleReaderExec {
 
     fn with_new_children(
         &self,
-        _children: Vec<Arc<dyn ExecutionPlan>>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        Err(DataFusionError::Plan(
-            "Ballista ShuffleReaderExec does not support with_new_children()".to_owned(),
-        ))
+        // leaf nodes are rebuilt without children when the scheduler rewrites plans
+        // that scan persisted datasets
+        if children.is_empty() {
+            Ok(Arc::new(self.clone()))
+        } else {
+            Err(DataFusionError::Plan(
+                "Ballista ShuffleReaderExec does not support with_new_children()"
+                    .to_owned(),
+            ))
+        }
     }
 
     async fn execute(
@@ -107,11 +225,16 @@ impl ExecutionPlan for ShuffleReaderExec {
             MetricBuilder::new(&self.metrics).subset_time("fetch_time", partition);
         let timer = fetch_time.timer();
 
-        let partition_locations = &self.partition[partition];
-        let result = future::join_all(partition_locations.iter().map(fetch_partition))
-            .await
-            .into_iter()
-            .collect::<Result<Vec<_>>>()?;
+        let options = shuffle_fetch_options();
+        let partition_locations = self.partition_locations(partition).await?;
+        let result = futures::stream::iter(
+            partition_locations
+                .iter()
+                .map(|location| fetch_partition(location, &options)),
+        )
+        .buffered(options.max_concurrent_fetches.max(1))
+        .try_collect::<Vec<_>>()
+        .await?;
         timer.done();
 
         let result = WrappedStream::new(
@@ -128,6 +251,15 @@ impl ExecutionPlan for ShuffleReaderExec {
     ) -> std::fmt::Result {
         match t {
             DisplayFormatType::Default => {
+                if let Some(shuffle) = &self.tracked_shuffle {
+                    return write!(
+                        f,
+                        "ShuffleReaderExec: tracked_shuffle(job={}, stage={}, partitions={})",
+                        shuffle.job_id,
+                        shuffle.stage_id,
+                        self.partition.len()
+                    );
+                }
                 let loc_str = self
                     .partition
                     .iter()
@@ -160,6 +292,10 @@ impl ExecutionPlan for ShuffleReaderExec {
     }
 
     fn statistics(&self) -> Statistics {
+        // the sizes of the tracked shuffle partitions are only known to the tracker
+        if self.tracked_shuffle.is_some() {
+            return Statistics::default();
+        }
         stats_for_partitions(
             self.partition
                 .iter()
@@ -192,29 +328,98 @@ fn stats_for_partitions(
     )
 }
 
+/// Fetches a shuffle partition, retrying the transient errors. Fails with a
+/// [BallistaError::FetchFailed] error, for the scheduler to execute the task writing
+/// the partition again, if its executor cannot be reached after the retries.
 async fn fetch_partition(
     location: &PartitionLocation,
+    options: &ShuffleFetchOptions,
 ) -> Result<Pin<Box<dyn RecordBatchStream + Send + Sync>>> {
+    let metadata = &location.executor_meta;
+    let mut backoff = options.retry_backoff;
+    let mut retries = 0;
+    loop {
+        let error = match try_fetch_partition(location).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) if !is_transient(&e) => return Err(to_datafusion_error(e)),
+            Err(e) => e,
+        };
+        // the connection may be broken, the next client opens a new one
+        BallistaClient::evict(&metadata.host, metadata.port);
+        if retries >= options.max_retries {
+            return Err(to_datafusion_error(BallistaError::FetchFailed {
+                path: location.path.clone(),
+                reason: error.to_string(),
+            }));
+        }
+        retries += 1;
+        warn!(
+            "Failed to fetch shuffle partition {} from {}:{}, retrying in {:?} ({}/{}): {}",
+            location.path,
+            metadata.host,
+            metadata.port,
+            backoff,
+            retries,
+            options.max_retries,
+            error
+        );
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
+
+async fn try_fetch_partition(
+    location: &PartitionLocation,
+) -> std::result::Result<Pin<Box<dyn RecordBatchStream + Send + Sync>>, BallistaError> {
     let metadata = &location.executor_meta;
     let partition_id = &location.partition_id;
-    let mut ballista_client =
-        BallistaClient::try_new(metadata.host.as_str(), metadata.port as u16)
-            .await
-            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
-    Ok(ballista_client
+    let mut ballista_client = BallistaClient::pooled(&metadata.host, metadata.port)
+        .await
+        .map_err(|e| BallistaError::FetchFailed {
+            path: location.path.clone(),
+            reason: e.to_string(),
+        })?;
+    ballista_client
         .fetch_partition(
             &partition_id.job_id,
             partition_id.stage_id as usize,
             partition_id.partition_id as usize,
             &location.path,
+            location.checksum,
         )
         .await
-        .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?)
+}
+
+/// Whether a fetch failing with `e` may succeed when retried
+fn is_transient(e: &BallistaError) -> bool {
+    match e {
+        BallistaError::FetchFailed { .. } | BallistaError::TonicError(_) => true,
+        BallistaError::GrpcError(status) => matches!(
+            status.code(),
+            Code::Unavailable
+                | Code::Unknown
+                | Code::DeadlineExceeded
+                | Code::ResourceExhausted
+                | Code::Aborted
+        ),
+        _ => false,
+    }
+}
+
+fn to_datafusion_error(e: BallistaError) -> DataFusionError {
+    match e {
+        // kept as is for the executor to report the lost partition
+        BallistaError::CorruptedPartition { .. } | BallistaError::FetchFailed { .. } => {
+            DataFusionError::External(Box::new(e))
+        }
+        _ => DataFusionError::Execution(format!("{:?}", e)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::serde::scheduler::{ExecutorMeta, PartitionId};
 
     #[tokio::test]
     async fn test_stats_for_partitions_empty() {
@@ -283,4 +488,49 @@ mod tests {
 
         assert_eq!(result, exptected);
     }
+
+    #[tokio::test]
+    async fn test_fetch_from_unreachable_executor() {
+        let location = PartitionLocation {
+            partition_id: PartitionId::new("job", 1, 0),
+            executor_meta: ExecutorMeta {
+                id: "executor".to_owned(),
+                // nothing listens on this port
+                host: "localhost".to_owned(),
+                port: 1,
+                labels: Default::default(),
+            },
+            partition_stats: PartitionStats::default(),
+            path: "/tmp/job/1/0/data.arrow".to_owned(),
+            checksum: None,
+        };
+        let options = ShuffleFetchOptions {
+            max_concurrent_fetches: 1,
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(1),
+        };
+        match fetch_partition(&location, &options).await {
+            Err(DataFusionError::External(e)) => {
+                let e = e.downcast_ref::<BallistaError>().unwrap();
+                assert!(matches!(e, BallistaError::FetchFailed { .. }));
+                assert_eq!(Some("/tmp/job/1/0/data.arrow"), e.lost_partition_path());
+            }
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Fetched a partition from an unreachable executor"),
+        }
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        assert!(is_transient(&BallistaError::GrpcError(
+            tonic::Status::unavailable("executor is gone")
+        )));
+        assert!(!is_transient(&BallistaError::GrpcError(
+            tonic::Status::not_found("no such partition")
+        )));
+        assert!(!is_transient(&BallistaError::CorruptedPartition {
+            path: "/tmp/job/1/0/data.arrow".to_owned(),
+            reason: "checksum mismatch".to_owned(),
+        }));
+    }
 }
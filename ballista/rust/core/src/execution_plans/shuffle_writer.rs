@@ -20,39 +20,42 @@
 //! partition is re-partitioned and streamed to disk in Arrow IPC format. Future stages of the query
 //! will use the ShuffleReaderExec to read these results.
 
-use std::fs::File;
 use std::iter::Iterator;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use std::{any::Any, pin::Pin};
 
-use crate::error::BallistaError;
+use crate::encryption::ShuffleEncryption;
+use crate::execution_plans::MorselPool;
 use crate::memory_stream::MemoryStream;
-use crate::utils;
+use crate::utils::{self, IpcWriter, ShuffleFormat};
 
-use crate::serde::protobuf::ShuffleWritePartition;
+use crate::serde::protobuf::{PartitionChecksum, ShuffleWritePartition};
 use crate::serde::scheduler::{PartitionLocation, PartitionStats};
 use async_trait::async_trait;
 use datafusion::arrow::array::{
     Array, ArrayBuilder, ArrayRef, StringBuilder, StructBuilder, UInt32Builder,
     UInt64Builder,
 };
-use datafusion::arrow::compute::take;
+use datafusion::arrow::compute::{concat, lexsort_to_indices, SortColumn};
 use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use datafusion::arrow::ipc::reader::FileReader;
-use datafusion::arrow::ipc::writer::FileWriter;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::error::{DataFusionError, Result};
-use datafusion::physical_plan::hash_utils::create_hashes;
+use datafusion::physical_plan::common;
+use datafusion::physical_plan::common::take_array;
+use datafusion::physical_plan::expressions::PhysicalSortExpr;
 use datafusion::physical_plan::metrics::{
     self, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
 };
-use datafusion::physical_plan::repartition::RepartitionExec;
+use datafusion::physical_plan::repartition::{BatchPartitioner, RepartitionExec};
 use datafusion::physical_plan::Partitioning::RoundRobinBatch;
 use datafusion::physical_plan::{
-    DisplayFormatType, ExecutionPlan, Metric, Partitioning, RecordBatchStream, Statistics,
+    DisplayFormatType, ExecutionPlan, Metric, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
 };
+use datafusion::scalar::ScalarValue;
 use futures::StreamExt;
 use hashbrown::HashMap;
 use log::{debug, info};
@@ -74,10 +77,36 @@ pub struct ShuffleWriterExec {
     work_dir: String,
     /// Optional shuffle output partitioning
     shuffle_output_partitioning: Option<Partitioning>,
+    /// Optional range partitioning of the shuffle output, used instead of
+    /// `shuffle_output_partitioning`
+    range_partitioning: Option<RangeShufflePartitioning>,
+    /// Optional pool of workers executing the input partitions in morsels
+    morsel_pool: Option<MorselPool>,
+    /// IPC format the output partitions are written in
+    shuffle_format: ShuffleFormat,
+    /// Whether to compute the checksums of the output partitions, which are
+    /// verified when they are fetched
+    checksums: bool,
+    /// Optional encryption of the output partitions
+    encryption: Option<ShuffleEncryption>,
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
 }
 
+/// Range partitioning of the shuffle output. The rows are assigned to `partition_count`
+/// partitions, ordered by the sort expressions, using boundaries that are computed from
+/// the sorted keys returned by the `samples` plan.
+#[derive(Debug, Clone)]
+pub struct RangeShufflePartitioning {
+    /// Sort expressions that define the order of the output partitions
+    pub sort_exprs: Vec<PhysicalSortExpr>,
+    /// Number of output partitions
+    pub partition_count: usize,
+    /// Plan returning a sample of the sort keys of the input, usually a
+    /// [`SampleExec`](super::SampleExec) of a previous query stage
+    pub samples: Arc<dyn ExecutionPlan>,
+}
+
 #[derive(Debug, Clone)]
 struct ShuffleWriteMetrics {
     /// Time spend writing batches to shuffle files
@@ -117,10 +146,78 @@ impl ShuffleWriterExec {
             plan,
             work_dir,
             shuffle_output_partitioning,
+            range_partitioning: None,
+            morsel_pool: None,
+            shuffle_format: ShuffleFormat::default(),
+            checksums: false,
+            encryption: None,
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
 
+    /// Create a new shuffle writer that range partitions its output
+    pub fn try_new_range_partitioned(
+        job_id: String,
+        stage_id: usize,
+        plan: Arc<dyn ExecutionPlan>,
+        work_dir: String,
+        range_partitioning: RangeShufflePartitioning,
+    ) -> Result<Self> {
+        Ok(Self {
+            range_partitioning: Some(range_partitioning),
+            ..Self::try_new(job_id, stage_id, plan, work_dir, None)?
+        })
+    }
+
+    /// Execute the input partitions in morsels with the workers of `morsel_pool`
+    pub fn with_morsel_pool(mut self, morsel_pool: MorselPool) -> Self {
+        self.morsel_pool = Some(morsel_pool);
+        self
+    }
+
+    /// Write the output partitions in the IPC `format`
+    pub fn with_shuffle_format(mut self, format: ShuffleFormat) -> Self {
+        self.shuffle_format = format;
+        self
+    }
+
+    /// Get the IPC format the output partitions are written in
+    pub fn shuffle_format(&self) -> ShuffleFormat {
+        self.shuffle_format
+    }
+
+    /// Compute the checksums of the output partitions if `checksums` is true
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Whether the checksums of the output partitions are computed
+    pub fn checksums(&self) -> bool {
+        self.checksums
+    }
+
+    /// Encrypt the output partitions with `encryption`
+    pub fn with_encryption(mut self, encryption: ShuffleEncryption) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    /// The encryption of the output partitions, if any
+    pub fn encryption(&self) -> Option<&ShuffleEncryption> {
+        self.encryption.as_ref()
+    }
+
+    /// The checksum of the output partition written at `path`, if they are computed
+    fn partition_checksum(&self, path: &str) -> Result<Option<PartitionChecksum>> {
+        if !self.checksums {
+            return Ok(None);
+        }
+        let crc32 = utils::partition_checksum(path)
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+        Ok(Some(PartitionChecksum { crc32 }))
+    }
+
     /// Get the Job ID for this query stage
     pub fn job_id(&self) -> &str {
         &self.job_id
@@ -136,13 +233,31 @@ impl ShuffleWriterExec {
         self.shuffle_output_partitioning.as_ref()
     }
 
+    /// Get the range partitioning of the output, if any
+    pub fn range_partitioning(&self) -> Option<&RangeShufflePartitioning> {
+        self.range_partitioning.as_ref()
+    }
+
+    /// Get the number of partitions written by each task, or `None` if every task
+    /// writes a single partition
+    pub fn shuffle_output_partition_count(&self) -> Option<usize> {
+        match (&self.shuffle_output_partitioning, &self.range_partitioning) {
+            (_, Some(range)) => Some(range.partition_count),
+            (Some(partitioning), None) => Some(partitioning.partition_count()),
+            (None, None) => None,
+        }
+    }
+
     pub async fn execute_shuffle_write(
         &self,
         input_partition: usize,
     ) -> Result<Vec<ShuffleWritePartition>> {
         let now = Instant::now();
 
-        let mut stream = self.plan.execute(input_partition).await?;
+        let mut stream = match &self.morsel_pool {
+            Some(pool) => pool.execute(self.plan.clone(), input_partition).await?,
+            None => self.plan.execute(input_partition).await?,
+        };
 
         let mut path = PathBuf::from(&self.work_dir);
         path.push(&self.job_id);
@@ -150,8 +265,8 @@ impl ShuffleWriterExec {
 
         let write_metrics = ShuffleWriteMetrics::new(input_partition, &self.metrics);
 
-        match &self.shuffle_output_partitioning {
-            None => {
+        match (&self.shuffle_output_partitioning, &self.range_partitioning) {
+            (None, None) => {
                 let timer = write_metrics.write_time.timer();
                 path.push(&format!("{}", input_partition));
                 std::fs::create_dir_all(&path)?;
@@ -163,6 +278,8 @@ impl ShuffleWriterExec {
                 let stats = utils::write_stream_to_disk(
                     &mut stream,
                     path,
+                    self.shuffle_format,
+                    self.encryption.as_ref(),
                     &write_metrics.write_time,
                 )
                 .await
@@ -189,129 +306,211 @@ impl ShuffleWriterExec {
                     num_batches: stats.num_batches.unwrap_or(0),
                     num_rows: stats.num_rows.unwrap_or(0),
                     num_bytes: stats.num_bytes.unwrap_or(0),
+                    checksum: self.partition_checksum(path)?,
                 }])
             }
 
-            Some(Partitioning::Hash(exprs, n)) => {
-                let num_output_partitions = *n;
+            (
+                Some(
+                    partitioning @ (Partitioning::Hash(_, _)
+                    | Partitioning::RangePartitioning(_, _)
+                    | Partitioning::Custom(_, _)),
+                ),
+                None,
+            ) => {
+                let partitioner = BatchPartitioner::try_new(
+                    partitioning.clone(),
+                    ahash::RandomState::with_seeds(0, 0, 0, 0),
+                )?;
+                self.write_partitioned(
+                    stream,
+                    path,
+                    input_partition,
+                    partitioning.partition_count(),
+                    &write_metrics,
+                    partitioner,
+                )
+                .await
+            }
+
+            (None, Some(range)) => {
+                let partitioner = BatchPartitioner::try_new(
+                    sampled_range_partitioning(range).await?,
+                    ahash::RandomState::with_seeds(0, 0, 0, 0),
+                )?;
+                self.write_partitioned(
+                    stream,
+                    path,
+                    input_partition,
+                    range.partition_count,
+                    &write_metrics,
+                    partitioner,
+                )
+                .await
+            }
 
-                // we won't necessary produce output for every possible partition, so we
-                // create writers on demand
-                let mut writers: Vec<Option<ShuffleWriter>> = vec![];
-                for _ in 0..num_output_partitions {
-                    writers.push(None);
-                }
+            _ => Err(DataFusionError::Execution(
+                "Invalid shuffle partitioning scheme".to_owned(),
+            )),
+        }
+    }
+
+    /// Writes every input batch to the output partitions that `partitioner` assigns
+    /// its rows to
+    async fn write_partitioned(
+        &self,
+        mut stream: SendableRecordBatchStream,
+        path: PathBuf,
+        input_partition: usize,
+        num_output_partitions: usize,
+        write_metrics: &ShuffleWriteMetrics,
+        mut partitioner: BatchPartitioner,
+    ) -> Result<Vec<ShuffleWritePartition>> {
+        // we won't necessary produce output for every possible partition, so we
+        // create writers on demand
+        let mut writers: Vec<Option<ShuffleWriter>> = vec![];
+        for _ in 0..num_output_partitions {
+            writers.push(None);
+        }
+
+        while let Some(result) = stream.next().await {
+            let input_batch = result?;
+
+            write_metrics.input_rows.add(input_batch.num_rows());
+
+            let indices = partitioner.partition_indices(&input_batch)?;
+            for (output_partition, partition_indices) in indices.into_iter().enumerate() {
+                let indices = partition_indices.into();
+
+                // Produce batches based on indices
+                let columns = input_batch
+                    .columns()
+                    .iter()
+                    .map(|c| {
+                        take_array(c.as_ref(), &indices)
+                            .map_err(|e| DataFusionError::Execution(e.to_string()))
+                    })
+                    .collect::<Result<Vec<Arc<dyn Array>>>>()?;
 
-                let hashes_buf = &mut vec![];
-                let random_state = ahash::RandomState::with_seeds(0, 0, 0, 0);
-
-                while let Some(result) = stream.next().await {
-                    let input_batch = result?;
-
-                    write_metrics.input_rows.add(input_batch.num_rows());
-
-                    let arrays = exprs
-                        .iter()
-                        .map(|expr| {
-                            Ok(expr
-                                .evaluate(&input_batch)?
-                                .into_array(input_batch.num_rows()))
-                        })
-                        .collect::<Result<Vec<_>>>()?;
-                    hashes_buf.clear();
-                    hashes_buf.resize(arrays[0].len(), 0);
-                    // Hash arrays and compute buckets based on number of partitions
-                    let hashes = create_hashes(&arrays, &random_state, hashes_buf)?;
-                    let mut indices = vec![vec![]; num_output_partitions];
-                    for (index, hash) in hashes.iter().enumerate() {
-                        indices[(*hash % num_output_partitions as u64) as usize]
-                            .push(index as u64)
+                let output_batch = RecordBatch::try_new(input_batch.schema(), columns)?;
+
+                // write non-empty batch out
+
+                //TODO optimize so we don't write or fetch empty partitions
+                //if output_batch.num_rows() > 0 {
+                let timer = write_metrics.write_time.timer();
+                match &mut writers[output_partition] {
+                    Some(w) => {
+                        w.write(&output_batch)?;
                     }
-                    for (output_partition, partition_indices) in
-                        indices.into_iter().enumerate()
-                    {
-                        let indices = partition_indices.into();
-
-                        // Produce batches based on indices
-                        let columns = input_batch
-                            .columns()
-                            .iter()
-                            .map(|c| {
-                                take(c.as_ref(), &indices, None).map_err(|e| {
-                                    DataFusionError::Execution(e.to_string())
-                                })
-                            })
-                            .collect::<Result<Vec<Arc<dyn Array>>>>()?;
-
-                        let output_batch =
-                            RecordBatch::try_new(input_batch.schema(), columns)?;
-
-                        // write non-empty batch out
-
-                        //TODO optimize so we don't write or fetch empty partitions
-                        //if output_batch.num_rows() > 0 {
-                        let timer = write_metrics.write_time.timer();
-                        match &mut writers[output_partition] {
-                            Some(w) => {
-                                w.write(&output_batch)?;
-                            }
-                            None => {
-                                let mut path = path.clone();
-                                path.push(&format!("{}", output_partition));
-                                std::fs::create_dir_all(&path)?;
-
-                                path.push(format!("data-{}.arrow", input_partition));
-                                let path = path.to_str().unwrap();
-                                info!("Writing results to {}", path);
-
-                                let mut writer =
-                                    ShuffleWriter::new(path, stream.schema().as_ref())?;
-
-                                writer.write(&output_batch)?;
-                                writers[output_partition] = Some(writer);
-                            }
-                        }
-                        write_metrics.output_rows.add(output_batch.num_rows());
-                        timer.done();
+                    None => {
+                        let mut path = path.clone();
+                        path.push(&format!("{}", output_partition));
+                        std::fs::create_dir_all(&path)?;
+
+                        path.push(format!("data-{}.arrow", input_partition));
+                        let path = path.to_str().unwrap();
+                        info!("Writing results to {}", path);
+
+                        let mut writer = ShuffleWriter::new(
+                            path,
+                            stream.schema().as_ref(),
+                            self.shuffle_format,
+                            self.encryption.as_ref(),
+                        )?;
+
+                        writer.write(&output_batch)?;
+                        writers[output_partition] = Some(writer);
                     }
                 }
+                write_metrics.output_rows.add(output_batch.num_rows());
+                timer.done();
+            }
+        }
 
-                let mut part_locs = vec![];
-
-                for (i, w) in writers.iter_mut().enumerate() {
-                    match w {
-                        Some(w) => {
-                            w.finish()?;
-                            info!(
-                                "Finished writing shuffle partition {} at {}. Batches: {}. Rows: {}. Bytes: {}.",
-                                i,
-                                w.path(),
-                                w.num_batches,
-                                w.num_rows,
-                                w.num_bytes
-                            );
-
-                            part_locs.push(ShuffleWritePartition {
-                                partition_id: i as u64,
-                                path: w.path().to_owned(),
-                                num_batches: w.num_batches,
-                                num_rows: w.num_rows,
-                                num_bytes: w.num_bytes,
-                            });
-                        }
-                        None => {}
-                    }
+        let mut part_locs = vec![];
+
+        for (i, w) in writers.iter_mut().enumerate() {
+            match w {
+                Some(w) => {
+                    w.finish()?;
+                    info!(
+                        "Finished writing shuffle partition {} at {}. Batches: {}. Rows: {}. Bytes: {}.",
+                        i,
+                        w.path(),
+                        w.num_batches,
+                        w.num_rows,
+                        w.num_bytes
+                    );
+
+                    part_locs.push(ShuffleWritePartition {
+                        partition_id: i as u64,
+                        path: w.path().to_owned(),
+                        num_batches: w.num_batches,
+                        num_rows: w.num_rows,
+                        num_bytes: w.num_bytes,
+                        checksum: self.partition_checksum(w.path())?,
+                    });
                 }
-                Ok(part_locs)
+                None => {}
             }
-
-            _ => Err(DataFusionError::Execution(
-                "Invalid shuffle partitioning scheme".to_owned(),
-            )),
         }
+        Ok(part_locs)
     }
 }
 
+/// Computes the range partitioning of a [`RangeShufflePartitioning`], picking evenly
+/// spaced quantiles of all partitions of the sampled sort keys as boundaries
+async fn sampled_range_partitioning(
+    range: &RangeShufflePartitioning,
+) -> Result<Partitioning> {
+    let mut samples = vec![];
+    for partition in 0..range.samples.output_partitioning().partition_count() {
+        let stream = range.samples.execute(partition).await?;
+        samples.extend(common::collect(stream).await?);
+    }
+
+    let num_samples: usize = samples.iter().map(|b| b.num_rows()).sum();
+    if num_samples == 0 {
+        return Ok(Partitioning::RangePartitioning(
+            range.sort_exprs.clone(),
+            vec![],
+        ));
+    }
+
+    let columns = (0..range.sort_exprs.len())
+        .map(|i| {
+            let arrays = samples
+                .iter()
+                .map(|b| b.column(i).as_ref())
+                .collect::<Vec<_>>();
+            Ok(concat(&arrays)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let sort_columns = columns
+        .iter()
+        .zip(range.sort_exprs.iter())
+        .map(|(values, e)| SortColumn {
+            values: values.clone(),
+            options: Some(e.options),
+        })
+        .collect::<Vec<_>>();
+    let sorted = lexsort_to_indices(&sort_columns, None)?;
+    let boundaries = (1..range.partition_count)
+        .map(|i| {
+            let row = sorted.value(i * num_samples / range.partition_count) as usize;
+            columns
+                .iter()
+                .map(|c| ScalarValue::try_from_array(c, row))
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Partitioning::RangePartitioning(
+        range.sort_exprs.clone(),
+        boundaries,
+    ))
+}
+
 #[async_trait]
 impl ExecutionPlan for ShuffleWriterExec {
     fn as_any(&self) -> &dyn Any {
@@ -331,21 +530,48 @@ impl ExecutionPlan for ShuffleWriterExec {
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
-        vec![self.plan.clone()]
+        match &self.range_partitioning {
+            Some(range) => vec![self.plan.clone(), range.samples.clone()],
+            None => vec![self.plan.clone()],
+        }
     }
 
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        assert!(children.len() == 1);
-        Ok(Arc::new(ShuffleWriterExec::try_new(
-            self.job_id.clone(),
-            self.stage_id,
-            children[0].clone(),
-            self.work_dir.clone(),
-            self.shuffle_output_partitioning.clone(),
-        )?))
+        let writer = match &self.range_partitioning {
+            Some(range) => {
+                assert!(children.len() == 2);
+                ShuffleWriterExec::try_new_range_partitioned(
+                    self.job_id.clone(),
+                    self.stage_id,
+                    children[0].clone(),
+                    self.work_dir.clone(),
+                    RangeShufflePartitioning {
+                        samples: children[1].clone(),
+                        ..range.clone()
+                    },
+                )?
+            }
+            None => {
+                assert!(children.len() == 1);
+                ShuffleWriterExec::try_new(
+                    self.job_id.clone(),
+                    self.stage_id,
+                    children[0].clone(),
+                    self.work_dir.clone(),
+                    self.shuffle_output_partitioning.clone(),
+                )?
+            }
+        };
+        let writer = writer
+            .with_shuffle_format(self.shuffle_format)
+            .with_checksums(self.checksums);
+        Ok(Arc::new(match &self.encryption {
+            Some(encryption) => writer.with_encryption(encryption.clone()),
+            None => writer,
+        }))
     }
 
     async fn execute(
@@ -408,13 +634,23 @@ impl ExecutionPlan for ShuffleWriterExec {
         f: &mut std::fmt::Formatter,
     ) -> std::fmt::Result {
         match t {
-            DisplayFormatType::Default => {
-                write!(
+            DisplayFormatType::Default => match &self.range_partitioning {
+                Some(range) => {
+                    let expr: Vec<String> =
+                        range.sort_exprs.iter().map(|e| e.to_string()).collect();
+                    write!(
+                        f,
+                        "ShuffleWriterExec: Range([{}], {})",
+                        expr.join(","),
+                        range.partition_count
+                    )
+                }
+                None => write!(
                     f,
                     "ShuffleWriterExec: {:?}",
                     self.shuffle_output_partitioning
-                )
-            }
+                ),
+            },
         }
     }
 
@@ -434,28 +670,27 @@ fn result_schema() -> SchemaRef {
 
 struct ShuffleWriter {
     path: String,
-    writer: FileWriter<File>,
+    writer: IpcWriter,
     num_batches: u64,
     num_rows: u64,
     num_bytes: u64,
 }
 
 impl ShuffleWriter {
-    fn new(path: &str, schema: &Schema) -> Result<Self> {
-        let file = File::create(path)
-            .map_err(|e| {
-                BallistaError::General(format!(
-                    "Failed to create partition file at {}: {:?}",
-                    path, e
-                ))
-            })
+    fn new(
+        path: &str,
+        schema: &Schema,
+        format: ShuffleFormat,
+        encryption: Option<&ShuffleEncryption>,
+    ) -> Result<Self> {
+        let writer = IpcWriter::try_new(path, schema, format, encryption)
             .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
         Ok(Self {
             num_batches: 0,
             num_rows: 0,
             num_bytes: 0,
             path: path.to_owned(),
-            writer: FileWriter::try_new(file, schema)?,
+            writer,
         })
     }
 
@@ -484,7 +719,13 @@ impl ShuffleWriter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use datafusion::arrow::array::{StringArray, StructArray, UInt32Array, UInt64Array};
+    use crate::encryption::StaticKeyProvider;
+    use crate::error::BallistaError;
+    use datafusion::arrow::array::{
+        DictionaryArray, StringArray, StructArray, UInt32Array, UInt64Array,
+    };
+    use datafusion::arrow::datatypes::Int32Type;
+    use datafusion::arrow::error::Result as ArrowResult;
     use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
     use datafusion::physical_plan::expressions::Column;
     use datafusion::physical_plan::limit::GlobalLimitExec;
@@ -578,6 +819,207 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_range_partitioned() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::UInt32, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(UInt32Array::from(vec![5, 1, 4, 2, 3, 6]))],
+        )?;
+        let input_plan =
+            Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None)?);
+        let sample_batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(UInt32Array::from(vec![6, 2, 4, 1, 5, 3]))],
+        )?;
+        let samples = Arc::new(MemoryExec::try_new(
+            &[vec![sample_batch], vec![]],
+            schema,
+            None,
+        )?);
+        let work_dir = TempDir::new()?;
+        let query_stage = ShuffleWriterExec::try_new_range_partitioned(
+            "jobOne".to_owned(),
+            1,
+            input_plan,
+            work_dir.into_path().to_str().unwrap().to_owned(),
+            RangeShufflePartitioning {
+                sort_exprs: vec![PhysicalSortExpr {
+                    expr: Arc::new(Column::new("a", 0)),
+                    options: Default::default(),
+                }],
+                partition_count: 3,
+                samples,
+            },
+        )?;
+        assert_eq!(2, query_stage.children().len());
+        assert_eq!(Some(3), query_stage.shuffle_output_partition_count());
+
+        let mut stream = query_stage.execute(0).await?;
+        let batches = utils::collect_stream(&mut stream)
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+        assert_eq!(1, batches.len());
+        let batch = &batches[0];
+        assert_eq!(3, batch.num_rows());
+        let stats = batch.columns()[2]
+            .as_any()
+            .downcast_ref::<StructArray>()
+            .unwrap();
+        let num_rows = stats
+            .column_by_name("num_rows")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        // the boundaries are 3 and 5
+        assert_eq!(3, num_rows.value(0));
+        assert_eq!(2, num_rows.value(1));
+        assert_eq!(1, num_rows.value(2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_checksums() -> Result<()> {
+        let input_plan = create_input_plan()?;
+        let work_dir = TempDir::new()?;
+        let query_stage = ShuffleWriterExec::try_new(
+            "jobOne".to_owned(),
+            1,
+            input_plan,
+            work_dir.into_path().to_str().unwrap().to_owned(),
+            Some(Partitioning::Hash(vec![Arc::new(Column::new("a", 0))], 2)),
+        )?
+        .with_checksums(true);
+        let partitions = query_stage.execute_shuffle_write(0).await?;
+        assert_eq!(2, partitions.len());
+
+        let partition = &partitions[0];
+        let crc32 = partition.checksum.as_ref().unwrap().crc32;
+        assert!(utils::verify_partition_checksum(&partition.path, crc32).is_ok());
+
+        // flip the last byte of the file
+        let mut bytes = std::fs::read(&partition.path)?;
+        *bytes.last_mut().unwrap() ^= 1;
+        std::fs::write(&partition.path, bytes)?;
+        match utils::verify_partition_checksum(&partition.path, crc32) {
+            Err(BallistaError::CorruptedPartition { path, .. }) => {
+                assert_eq!(partition.path, path)
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_encryption() -> Result<()> {
+        let encryption =
+            ShuffleEncryption::new(Arc::new(StaticKeyProvider::new("k1", [1; 32])));
+        for format in [ShuffleFormat::File, ShuffleFormat::Stream] {
+            let work_dir = TempDir::new()?;
+            let query_stage = ShuffleWriterExec::try_new(
+                "jobOne".to_owned(),
+                1,
+                create_input_plan()?,
+                work_dir.into_path().to_str().unwrap().to_owned(),
+                Some(Partitioning::Hash(vec![Arc::new(Column::new("a", 0))], 2)),
+            )?
+            .with_shuffle_format(format)
+            .with_checksums(true)
+            .with_encryption(encryption.clone());
+            let partitions = query_stage.execute_shuffle_write(0).await?;
+            assert_eq!(2, partitions.len());
+
+            for partition in &partitions {
+                let bytes = std::fs::read(&partition.path)?;
+                assert!(!bytes.windows(5).any(|window| window == b"hello"));
+                let crc32 = partition.checksum.as_ref().unwrap().crc32;
+                assert!(utils::verify_partition_checksum(&partition.path, crc32).is_ok());
+
+                assert!(utils::read_shuffle_partition(&partition.path, None).is_err());
+                let (_, reader) =
+                    utils::read_shuffle_partition(&partition.path, Some(&encryption))
+                        .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+                let num_rows: usize = reader
+                    .map(|batch| batch.map(|batch| batch.num_rows()))
+                    .sum::<ArrowResult<usize>>()?;
+                assert_eq!(partition.num_rows as usize, num_rows);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_format_keeps_dictionaries() -> Result<()> {
+        let dictionary_type =
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "a",
+            dictionary_type.clone(),
+            true,
+        )]));
+        // the dictionary changes between the batches
+        let batches = vec![vec!["a", "b", "a"], vec!["c", "d"]]
+            .into_iter()
+            .map(|values| {
+                let array: DictionaryArray<Int32Type> = values.into_iter().collect();
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(array)])
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        let input_plan = Arc::new(MemoryExec::try_new(&[batches], schema, None)?);
+        let work_dir = TempDir::new()?;
+        let query_stage = ShuffleWriterExec::try_new(
+            "jobOne".to_owned(),
+            1,
+            input_plan,
+            work_dir.into_path().to_str().unwrap().to_owned(),
+            None,
+        )?
+        .with_shuffle_format(ShuffleFormat::Stream);
+        assert_eq!(ShuffleFormat::Stream, query_stage.shuffle_format());
+
+        let mut stream = query_stage.execute(0).await?;
+        let batches = utils::collect_stream(&mut stream)
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+        let path = batches[0].columns()[1]
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .value(0);
+
+        let (schema, reader) = utils::read_shuffle_partition(path, None)
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
+        assert_eq!(&dictionary_type, schema.field(0).data_type());
+        let batches = reader.collect::<ArrowResult<Vec<_>>>()?;
+        let values = batches
+            .iter()
+            .flat_map(|batch| {
+                let array = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<DictionaryArray<Int32Type>>()
+                    .unwrap();
+                let dictionary = array
+                    .values()
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .unwrap();
+                array
+                    .keys()
+                    .iter()
+                    .map(|key| dictionary.value(key.unwrap() as usize).to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["a", "b", "a", "c", "d"], values);
+
+        Ok(())
+    }
+
     fn create_input_plan() -> Result<Arc<dyn ExecutionPlan>> {
         let schema = Arc::new(Schema::new(vec![
             Field::new("a", DataType::UInt32, true),
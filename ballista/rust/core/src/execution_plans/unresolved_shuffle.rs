@@ -50,6 +50,9 @@ pub struct UnresolvedShuffleExec {
 
     // The partition count this node will have once it is replaced with a ShuffleReaderExec
     pub output_partition_count: usize,
+
+    // The estimated statistics of the output of the query stage, which are not serialized
+    pub statistics: Statistics,
 }
 
 impl UnresolvedShuffleExec {
@@ -65,8 +68,16 @@ impl UnresolvedShuffleExec {
             schema,
             input_partition_count,
             output_partition_count,
+            statistics: Statistics::default(),
         }
     }
+
+    /// Sets the estimated statistics of the output of the query stage, used to
+    /// estimate the sizes of the stages reading it, e.g. by `EXPLAIN (COSTS)`
+    pub fn with_statistics(mut self, statistics: Statistics) -> Self {
+        self.statistics = statistics;
+        self
+    }
 }
 
 #[async_trait]
@@ -123,6 +134,6 @@ impl ExecutionPlan for UnresolvedShuffleExec {
     fn statistics(&self) -> Statistics {
         // The full statistics are computed in the `ShuffleReaderExec` node
         // that replaces this one once the previous stage is completed.
-        Statistics::default()
+        self.statistics.clone()
     }
 }
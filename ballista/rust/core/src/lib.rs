@@ -25,9 +25,16 @@ pub fn print_version() {
 
 pub mod client;
 pub mod config;
+pub mod config_file;
+pub mod dataset;
+pub mod encryption;
 pub mod error;
 pub mod execution_plans;
+pub mod map_output_tracker;
 pub mod memory_stream;
+pub mod message_chunks;
+pub mod result_encoding;
+pub mod statements;
 pub mod utils;
 
 #[macro_use]
@@ -0,0 +1,100 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Tracker of the locations of the shuffle partitions written by the stages of the
+//! jobs. The shuffle readers of tracked shuffles query it for the locations of the
+//! partitions they read when they are executed, so that the task definitions don't
+//! have to list them.
+
+use std::convert::TryInto;
+use std::sync::{Arc, RwLock};
+
+use crate::error::{BallistaError, Result};
+use crate::serde::protobuf::{
+    scheduler_grpc_client::SchedulerGrpcClient, GetMapOutputsParams,
+};
+use crate::serde::scheduler::PartitionLocation;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use tonic::transport::Channel;
+
+/// Source of the locations of the shuffle partitions
+#[async_trait]
+pub trait MapOutputTracker: Send + Sync {
+    /// Get the locations of the shuffle partition `partition_id` written by the
+    /// tasks of the stage `stage_id` of the job `job_id`
+    async fn get_map_outputs(
+        &self,
+        job_id: &str,
+        stage_id: usize,
+        partition_id: usize,
+    ) -> Result<Vec<PartitionLocation>>;
+}
+
+lazy_static! {
+    static ref MAP_OUTPUT_TRACKER: RwLock<Option<Arc<dyn MapOutputTracker>>> =
+        RwLock::new(None);
+}
+
+/// Sets the tracker queried by the shuffle readers executed by this process
+pub fn set_map_output_tracker(tracker: Arc<dyn MapOutputTracker>) {
+    *MAP_OUTPUT_TRACKER.write().unwrap() = Some(tracker);
+}
+
+/// Gets the tracker queried by the shuffle readers, if one was set
+pub fn map_output_tracker() -> Option<Arc<dyn MapOutputTracker>> {
+    MAP_OUTPUT_TRACKER.read().unwrap().clone()
+}
+
+/// Map output tracker querying the scheduler
+#[derive(Clone)]
+pub struct SchedulerMapOutputTracker {
+    scheduler: SchedulerGrpcClient<Channel>,
+}
+
+impl SchedulerMapOutputTracker {
+    pub fn new(scheduler: SchedulerGrpcClient<Channel>) -> Self {
+        Self { scheduler }
+    }
+}
+
+#[async_trait]
+impl MapOutputTracker for SchedulerMapOutputTracker {
+    async fn get_map_outputs(
+        &self,
+        job_id: &str,
+        stage_id: usize,
+        partition_id: usize,
+    ) -> Result<Vec<PartitionLocation>> {
+        let result = self
+            .scheduler
+            .clone()
+            .get_map_outputs(GetMapOutputsParams {
+                job_id: job_id.to_owned(),
+                stage_id: stage_id as u32,
+                partition_id: partition_id as u32,
+            })
+            .await
+            .map_err(BallistaError::GrpcError)?
+            .into_inner();
+        result
+            .location
+            .into_iter()
+            .map(|location| location.try_into())
+            .collect()
+    }
+}
@@ -0,0 +1,119 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Transmission of the protobuf messages too large to be sent in a single gRPC
+//! message, such as the plans scanning thousands of files, as compressed chunks
+//! sent over a client streaming call.
+
+use std::any::type_name;
+use std::io::{Read, Write};
+
+use crate::error::{BallistaError, Result};
+use crate::serde::protobuf::MessageChunk;
+use bytes::{Bytes, BytesMut};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use futures::{Stream, TryStreamExt};
+use prost::Message;
+
+/// Size of the chunks messages are sent as
+pub const MESSAGE_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Compresses the encoded `message` and splits it into chunks of `chunk_size` bytes,
+/// which share the buffer of the compressed message
+pub fn encode_chunks<T: Message>(
+    message: &T,
+    chunk_size: usize,
+) -> Result<Vec<MessageChunk>> {
+    let mut encoded = Vec::with_capacity(message.encoded_len());
+    message.encode(&mut encoded).map_err(|e| {
+        BallistaError::Internal(format!(
+            "Could not serialize {}: {}",
+            type_name::<T>(),
+            e
+        ))
+    })?;
+    let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+    encoder.write_all(&encoded)?;
+    let compressed = Bytes::from(encoder.finish()?);
+    Ok((0..compressed.len())
+        .step_by(chunk_size)
+        .map(|start| MessageChunk {
+            data: compressed.slice(start..compressed.len().min(start + chunk_size)),
+        })
+        .collect())
+}
+
+/// Decodes a message from the stream of its chunks
+pub async fn decode_chunks<T, S>(chunks: S) -> Result<T>
+where
+    T: Message + Default,
+    S: Stream<Item = std::result::Result<MessageChunk, tonic::Status>>,
+{
+    let compressed = chunks
+        .try_fold(BytesMut::new(), |mut compressed, chunk| async move {
+            compressed.extend_from_slice(&chunk.data);
+            Ok(compressed)
+        })
+        .await?;
+    let mut encoded = vec![];
+    DeflateDecoder::new(compressed.as_ref()).read_to_end(&mut encoded)?;
+    T::decode(encoded.as_slice()).map_err(|e| {
+        BallistaError::Internal(format!(
+            "Could not deserialize {}: {}",
+            type_name::<T>(),
+            e
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde::protobuf::{execute_query_params::Query, ExecuteQueryParams};
+
+    #[tokio::test]
+    async fn roundtrip_chunks() -> Result<()> {
+        let params = ExecuteQueryParams {
+            query: Some(Query::Sql(format!(
+                "SELECT * FROM t WHERE a IN ({})",
+                (0..10000)
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+            settings: vec![],
+        };
+        let chunks = encode_chunks(&params, 1024)?;
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.data.len() <= 1024));
+
+        let decoded: ExecuteQueryParams =
+            decode_chunks(futures::stream::iter(chunks.into_iter().map(Ok))).await?;
+        assert_eq!(params, decoded);
+
+        let error =
+            decode_chunks::<ExecuteQueryParams, _>(futures::stream::iter(vec![Err(
+                tonic::Status::cancelled("cancelled"),
+            )]))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, BallistaError::GrpcError(_)));
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Encodings of the record batches fetched from the executors with Flight, so that
+//! the clients without an Arrow implementation, such as browsers and scripts, can
+//! fetch the results of their queries as JSON or CSV rather than Arrow IPC.
+
+use std::fmt;
+use std::str::FromStr;
+
+use datafusion::arrow::csv;
+use datafusion::arrow::json::LineDelimitedWriter;
+use datafusion::arrow::record_batch::RecordBatch;
+
+use crate::error::{BallistaError, Result};
+
+/// How the record batches of a partition are sent by an executor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultEncoding {
+    /// Arrow IPC messages, the flight data of the batches
+    ArrowIpc,
+    /// Newline delimited JSON objects, one per row
+    Json,
+    /// CSV with a header line
+    Csv,
+}
+
+impl Default for ResultEncoding {
+    fn default() -> Self {
+        ResultEncoding::ArrowIpc
+    }
+}
+
+impl ResultEncoding {
+    /// The media type of the encoded batches
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ResultEncoding::ArrowIpc => "application/vnd.apache.arrow.stream",
+            ResultEncoding::Json => "application/x-ndjson",
+            ResultEncoding::Csv => "text/csv",
+        }
+    }
+
+    /// The serializer of the batches of a partition, or `None` for Arrow IPC, whose
+    /// batches are sent as flight data
+    pub fn serializer(&self) -> Option<Box<dyn ResultSerializer>> {
+        match self {
+            ResultEncoding::ArrowIpc => None,
+            ResultEncoding::Json => Some(Box::new(JsonSerializer)),
+            ResultEncoding::Csv => Some(Box::new(CsvSerializer::default())),
+        }
+    }
+}
+
+impl FromStr for ResultEncoding {
+    type Err = BallistaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "arrow" | "arrow_ipc" => Ok(ResultEncoding::ArrowIpc),
+            "json" => Ok(ResultEncoding::Json),
+            "csv" => Ok(ResultEncoding::Csv),
+            _ => Err(BallistaError::General(format!(
+                "Unknown result encoding '{}', expected arrow, json or csv",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ResultEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResultEncoding::ArrowIpc => write!(f, "arrow"),
+            ResultEncoding::Json => write!(f, "json"),
+            ResultEncoding::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+/// Serializes the record batches of a partition, one after the other
+pub trait ResultSerializer: Send {
+    /// The bytes of `batch`, which follow the bytes of the previous batches
+    fn serialize(&mut self, batch: &RecordBatch) -> Result<Vec<u8>>;
+}
+
+/// Serializes the rows of the batches to newline delimited JSON objects
+#[derive(Debug, Default)]
+pub struct JsonSerializer;
+
+impl ResultSerializer for JsonSerializer {
+    fn serialize(&mut self, batch: &RecordBatch) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        let mut writer = LineDelimitedWriter::new(&mut bytes);
+        writer.write_batches(&[batch.clone()])?;
+        writer.finish()?;
+        drop(writer);
+        Ok(bytes)
+    }
+}
+
+/// Serializes the rows of the batches to CSV, with a header line before the rows
+/// of the first batch
+#[derive(Debug, Default)]
+pub struct CsvSerializer {
+    /// whether the header line was serialized
+    header_written: bool,
+}
+
+impl ResultSerializer for CsvSerializer {
+    fn serialize(&mut self, batch: &RecordBatch) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(!self.header_written)
+                .build(&mut bytes);
+            writer.write(batch)?;
+        }
+        self.header_written = true;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{ArrayRef, Int32Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    #[test]
+    fn serialize_batches() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = |a: Vec<i32>, b: Vec<Option<&str>>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from(a)) as ArrayRef,
+                    Arc::new(StringArray::from(b)),
+                ],
+            )
+        };
+        let batches = vec![
+            batch(vec![1, 2], vec![Some("x"), None])?,
+            batch(vec![3], vec![Some("y")])?,
+        ];
+        let serialize = |encoding: ResultEncoding| -> Result<String> {
+            let mut serializer = encoding.serializer().unwrap();
+            let mut bytes = vec![];
+            for batch in &batches {
+                bytes.extend(serializer.serialize(batch)?);
+            }
+            Ok(String::from_utf8(bytes).unwrap())
+        };
+
+        assert_eq!(
+            serialize(ResultEncoding::Json)?,
+            "{\"a\":1,\"b\":\"x\"}\n{\"a\":2}\n{\"a\":3,\"b\":\"y\"}\n"
+        );
+        assert_eq!(serialize(ResultEncoding::Csv)?, "a,b\n1,x\n2,\n3,y\n");
+        assert!(ResultEncoding::ArrowIpc.serializer().is_none());
+        assert_eq!("CSV".parse::<ResultEncoding>()?, ResultEncoding::Csv);
+        assert!("xml".parse::<ResultEncoding>().is_err());
+        Ok(())
+    }
+}
@@ -17,26 +17,33 @@
 
 //! Serde code to convert from protocol buffers to Rust data structures.
 
+use crate::dataset::DatasetTable;
 use crate::error::BallistaError;
-use crate::serde::{from_proto_binary_op, proto_error, protobuf, str_to_byte};
+use crate::serde::{
+    from_proto_binary_op, parse_range_boundaries, proto_error, protobuf, str_to_byte,
+    udaf, udf,
+};
 use crate::{convert_box_required, convert_required};
 use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
 use datafusion::datasource::file_format::avro::AvroFormat;
 use datafusion::datasource::file_format::csv::CsvFormat;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::file_format::FileFormat;
-use datafusion::datasource::listing::{ListingOptions, ListingTable};
+use datafusion::datasource::listing::{Bucketing, ListingOptions, ListingTable};
 use datafusion::datasource::object_store::local::LocalFileSystem;
 use datafusion::datasource::object_store::{FileMeta, SizedFile};
+use datafusion::datasource::TableProvider;
+use datafusion::logical_plan::plan::Extension;
 use datafusion::logical_plan::window_frames::{
-    WindowFrame, WindowFrameBound, WindowFrameUnits,
+    WindowFrame, WindowFrameBound, WindowFrameExclusion, WindowFrameUnits,
 };
 use datafusion::logical_plan::{
     abs, acos, asin, atan, ceil, cos, digest, exp, floor, ln, log10, log2, round, signum,
-    sin, sqrt, tan, trunc, Column, CreateExternalTable, DFField, DFSchema, Expr,
-    JoinConstraint, JoinType, LogicalPlan, LogicalPlanBuilder, Operator,
+    sin, sqrt, tan, trunc, window, Column, CreateExternalTable, DFField, DFSchema, Expr,
+    JoinConstraint, JoinType, LogicalPlan, LogicalPlanBuilder, MaterializedCte, Operator,
 };
 use datafusion::physical_plan::aggregates::AggregateFunction;
+use datafusion::physical_plan::functions::BuiltinScalarFunction;
 use datafusion::physical_plan::window_functions::BuiltInWindowFunction;
 use datafusion::prelude::*;
 use datafusion::scalar::ScalarValue;
@@ -46,6 +53,7 @@ use protobuf::{logical_expr_node::ExprType, scalar_type};
 use std::{
     convert::{From, TryInto},
     sync::Arc,
+    time::Duration,
     unimplemented,
 };
 
@@ -195,11 +203,16 @@ impl TryInto<LogicalPlan> for &protobuf::LogicalPlanNode {
                     table_partition_cols: scan.table_partition_cols.clone(),
                     collect_stat: scan.collect_stat,
                     target_partitions: scan.target_partitions as usize,
+                    bucketing: scan.bucketing.as_ref().map(|b| b.into()),
+                    metadata_cache_ttl: (scan.metadata_cache_ttl_ms > 0)
+                        .then(|| Duration::from_millis(scan.metadata_cache_ttl_ms)),
+                    stat_collection_concurrency: scan.stat_collection_concurrency
+                        as usize,
                 };
 
-                let provider = ListingTable::new(
+                let provider = ListingTable::new_with_paths(
                     Arc::new(LocalFileSystem {}),
-                    scan.path.clone(),
+                    scan.paths.clone(),
                     Arc::new(schema),
                     options,
                 );
@@ -213,6 +226,28 @@ impl TryInto<LogicalPlan> for &protobuf::LogicalPlanNode {
                 .build()
                 .map_err(|e| e.into())
             }
+            LogicalPlanType::DatasetScan(scan) => {
+                let dataset: DatasetTable = scan
+                    .dataset
+                    .clone()
+                    .ok_or_else(|| proto_error("Missing required field in protobuf"))?
+                    .try_into()?;
+
+                let mut projection = None;
+                if let Some(columns) = &scan.projection {
+                    let schema = dataset.schema();
+                    let column_indices = columns
+                        .columns
+                        .iter()
+                        .map(|name| schema.index_of(name))
+                        .collect::<Result<Vec<usize>, _>>()?;
+                    projection = Some(column_indices);
+                }
+
+                LogicalPlanBuilder::scan(&scan.table_name, Arc::new(dataset), projection)?
+                    .build()
+                    .map_err(|e| e.into())
+            }
             LogicalPlanType::Sort(sort) => {
                 let input: LogicalPlan = convert_box_required!(sort.input)?;
                 let sort_expr: Vec<Expr> = sort
@@ -249,6 +284,23 @@ impl TryInto<LogicalPlan> for &protobuf::LogicalPlanNode {
                     PartitionMethod::RoundRobin(batch_size) => {
                         Partitioning::RoundRobinBatch(batch_size as usize)
                     }
+                    PartitionMethod::Range(protobuf::RangeRepartition {
+                        sort_expr,
+                        boundary,
+                    }) => Partitioning::RangePartitioning(
+                        sort_expr
+                            .iter()
+                            .map(|pb_expr| pb_expr.try_into())
+                            .collect::<Result<Vec<_>, _>>()?,
+                        parse_range_boundaries(&boundary)?,
+                    ),
+                    PartitionMethod::Custom(protobuf::CustomRepartition {
+                        partition_expr,
+                        partition_count,
+                    }) => Partitioning::Custom(
+                        convert_required!(partition_expr)?,
+                        partition_count as usize,
+                    ),
                 };
 
                 LogicalPlanBuilder::from(input)
@@ -277,19 +329,39 @@ impl TryInto<LogicalPlan> for &protobuf::LogicalPlanNode {
                     location: create_extern_table.location.clone(),
                     file_type: pb_file_type.into(),
                     has_header: create_extern_table.has_header,
+                    bucketing: create_extern_table.bucketing.as_ref().map(|b| b.into()),
                 }))
             }
             LogicalPlanType::Analyze(analyze) => {
                 let input: LogicalPlan = convert_box_required!(analyze.input)?;
+                let format = protobuf::ExplainFormat::from_i32(analyze.format)
+                    .ok_or_else(|| {
+                        proto_error(format!(
+                            "Received an AnalyzeNode message with unknown ExplainFormat {}",
+                            analyze.format
+                        ))
+                    })?;
                 LogicalPlanBuilder::from(input)
-                    .explain(analyze.verbose, true)?
+                    .explain_with_format(analyze.verbose, true, format.into())?
                     .build()
                     .map_err(|e| e.into())
             }
             LogicalPlanType::Explain(explain) => {
                 let input: LogicalPlan = convert_box_required!(explain.input)?;
+                let format = protobuf::ExplainFormat::from_i32(explain.format)
+                    .ok_or_else(|| {
+                        proto_error(format!(
+                            "Received an ExplainNode message with unknown ExplainFormat {}",
+                            explain.format
+                        ))
+                    })?;
                 LogicalPlanBuilder::from(input)
-                    .explain(explain.verbose, false)?
+                    .explain_with_costs(
+                        explain.verbose,
+                        false,
+                        format.into(),
+                        explain.costs,
+                    )?
                     .build()
                     .map_err(|e| e.into())
             }
@@ -347,6 +419,19 @@ impl TryInto<LogicalPlan> for &protobuf::LogicalPlanNode {
                     .build()
                     .map_err(|e| e.into())
             }
+            LogicalPlanType::MaterializedCte(cte) => {
+                let input = convert_box_required!(cte.input)?;
+
+                // the id is kept, so that the references to the CTE are still planned
+                // into a single stage
+                Ok(LogicalPlan::Extension(Extension {
+                    node: Arc::new(MaterializedCte {
+                        id: cte.id as usize,
+                        name: cte.name.clone(),
+                        input,
+                    }),
+                }))
+            }
         }
     }
 }
@@ -361,6 +446,12 @@ impl From<&protobuf::Column> for Column {
     }
 }
 
+impl From<&protobuf::Bucketing> for Bucketing {
+    fn from(b: &protobuf::Bucketing) -> Bucketing {
+        Bucketing::new(b.columns.clone(), b.num_buckets as usize)
+    }
+}
+
 impl TryInto<DFSchema> for &protobuf::DfSchema {
     type Error = BallistaError;
 
@@ -494,10 +585,10 @@ fn typechecked_scalar_value_conversion(
             ScalarValue::Date32(Some(*v))
         }
         (Value::TimeMicrosecondValue(v), PrimitiveScalarType::TimeMicrosecond) => {
-            ScalarValue::TimestampMicrosecond(Some(*v))
+            ScalarValue::TimestampMicrosecond(Some(*v), None)
         }
-        (Value::TimeNanosecondValue(v), PrimitiveScalarType::TimeMicrosecond) => {
-            ScalarValue::TimestampNanosecond(Some(*v))
+        (Value::TimeNanosecondValue(v), PrimitiveScalarType::TimeNanosecond) => {
+            ScalarValue::TimestampNanosecond(Some(*v), None)
         }
         (Value::Utf8Value(v), PrimitiveScalarType::Utf8) => {
             ScalarValue::Utf8(Some(v.to_owned()))
@@ -505,6 +596,22 @@ fn typechecked_scalar_value_conversion(
         (Value::LargeUtf8Value(v), PrimitiveScalarType::LargeUtf8) => {
             ScalarValue::LargeUtf8(Some(v.to_owned()))
         }
+        (Value::IntervalYearmonthValue(v), PrimitiveScalarType::IntervalYearmonth) => {
+            ScalarValue::IntervalYearMonth(Some(*v))
+        }
+        (Value::IntervalDaytimeValue(v), PrimitiveScalarType::IntervalDaytime) => {
+            ScalarValue::IntervalDayTime(Some(*v))
+        }
+        (
+            Value::IntervalMonthdaynanoValue(v),
+            PrimitiveScalarType::IntervalMonthdaynano,
+        ) => ScalarValue::new_interval_mdn(v.months, v.days, v.nanos),
+        (Value::BinaryValue(v), PrimitiveScalarType::Binary) => {
+            ScalarValue::Binary(Some(v.to_owned()))
+        }
+        (Value::LargeBinaryValue(v), PrimitiveScalarType::LargeBinary) => {
+            ScalarValue::LargeBinary(Some(v.to_owned()))
+        }
 
         (Value::NullValue(i32_enum), required_scalar_type) => {
             if *i32_enum == *required_scalar_type as i32 {
@@ -530,11 +637,22 @@ fn typechecked_scalar_value_conversion(
                     PrimitiveScalarType::LargeUtf8 => ScalarValue::LargeUtf8(None),
                     PrimitiveScalarType::Date32 => ScalarValue::Date32(None),
                     PrimitiveScalarType::TimeMicrosecond => {
-                        ScalarValue::TimestampMicrosecond(None)
+                        ScalarValue::TimestampMicrosecond(None, None)
                     }
                     PrimitiveScalarType::TimeNanosecond => {
-                        ScalarValue::TimestampNanosecond(None)
+                        ScalarValue::TimestampNanosecond(None, None)
+                    }
+                    PrimitiveScalarType::IntervalYearmonth => {
+                        ScalarValue::IntervalYearMonth(None)
                     }
+                    PrimitiveScalarType::IntervalDaytime => {
+                        ScalarValue::IntervalDayTime(None)
+                    }
+                    PrimitiveScalarType::IntervalMonthdaynano => {
+                        ScalarValue::IntervalMonthDayNano(None)
+                    }
+                    PrimitiveScalarType::Binary => ScalarValue::Binary(None),
+                    PrimitiveScalarType::LargeBinary => ScalarValue::LargeBinary(None),
                     PrimitiveScalarType::Null => {
                         return Err(proto_error(
                             "Untyped scalar null is not a valid scalar value",
@@ -593,11 +711,28 @@ impl TryInto<datafusion::scalar::ScalarValue> for &protobuf::scalar_value::Value
                 ScalarValue::Date32(Some(*v))
             }
             protobuf::scalar_value::Value::TimeMicrosecondValue(v) => {
-                ScalarValue::TimestampMicrosecond(Some(*v))
+                ScalarValue::TimestampMicrosecond(Some(*v), None)
             }
             protobuf::scalar_value::Value::TimeNanosecondValue(v) => {
-                ScalarValue::TimestampNanosecond(Some(*v))
+                ScalarValue::TimestampNanosecond(Some(*v), None)
+            }
+            protobuf::scalar_value::Value::TimestampValue(v) => v.try_into()?,
+            protobuf::scalar_value::Value::IntervalYearmonthValue(v) => {
+                ScalarValue::IntervalYearMonth(Some(*v))
             }
+            protobuf::scalar_value::Value::IntervalDaytimeValue(v) => {
+                ScalarValue::IntervalDayTime(Some(*v))
+            }
+            protobuf::scalar_value::Value::IntervalMonthdaynanoValue(v) => {
+                ScalarValue::new_interval_mdn(v.months, v.days, v.nanos)
+            }
+            protobuf::scalar_value::Value::BinaryValue(v) => {
+                ScalarValue::Binary(Some(v.to_owned()))
+            }
+            protobuf::scalar_value::Value::LargeBinaryValue(v) => {
+                ScalarValue::LargeBinary(Some(v.to_owned()))
+            }
+            protobuf::scalar_value::Value::FixedSizeBinaryValue(v) => v.into(),
             protobuf::scalar_value::Value::ListValue(v) => v.try_into()?,
             protobuf::scalar_value::Value::NullListValue(v) => {
                 ScalarValue::List(None, Box::new(v.try_into()?))
@@ -758,15 +893,62 @@ impl TryInto<datafusion::scalar::ScalarValue> for protobuf::PrimitiveScalarType
             protobuf::PrimitiveScalarType::LargeUtf8 => ScalarValue::LargeUtf8(None),
             protobuf::PrimitiveScalarType::Date32 => ScalarValue::Date32(None),
             protobuf::PrimitiveScalarType::TimeMicrosecond => {
-                ScalarValue::TimestampMicrosecond(None)
+                ScalarValue::TimestampMicrosecond(None, None)
             }
             protobuf::PrimitiveScalarType::TimeNanosecond => {
-                ScalarValue::TimestampNanosecond(None)
+                ScalarValue::TimestampNanosecond(None, None)
+            }
+            protobuf::PrimitiveScalarType::IntervalYearmonth => {
+                ScalarValue::IntervalYearMonth(None)
+            }
+            protobuf::PrimitiveScalarType::IntervalDaytime => {
+                ScalarValue::IntervalDayTime(None)
             }
+            protobuf::PrimitiveScalarType::IntervalMonthdaynano => {
+                ScalarValue::IntervalMonthDayNano(None)
+            }
+            protobuf::PrimitiveScalarType::Binary => ScalarValue::Binary(None),
+            protobuf::PrimitiveScalarType::LargeBinary => ScalarValue::LargeBinary(None),
         })
     }
 }
 
+impl From<&protobuf::FixedSizeBinaryValue> for ScalarValue {
+    fn from(value: &protobuf::FixedSizeBinaryValue) -> Self {
+        ScalarValue::FixedSizeBinary(
+            value.width,
+            if value.is_null {
+                None
+            } else {
+                Some(value.value.clone())
+            },
+        )
+    }
+}
+
+impl TryInto<ScalarValue> for &protobuf::ScalarTimestampValue {
+    type Error = BallistaError;
+    fn try_into(self) -> Result<ScalarValue, Self::Error> {
+        let value = if self.is_null { None } else { Some(self.value) };
+        let timezone = match self.timezone.is_empty() {
+            true => None,
+            false => Some(self.timezone.to_owned()),
+        };
+        Ok(
+            match protobuf::TimeUnit::from_i32_to_arrow(self.time_unit)? {
+                TimeUnit::Second => ScalarValue::TimestampSecond(value, timezone),
+                TimeUnit::Millisecond => {
+                    ScalarValue::TimestampMillisecond(value, timezone)
+                }
+                TimeUnit::Microsecond => {
+                    ScalarValue::TimestampMicrosecond(value, timezone)
+                }
+                TimeUnit::Nanosecond => ScalarValue::TimestampNanosecond(value, timezone),
+            },
+        )
+    }
+}
+
 impl TryInto<datafusion::scalar::ScalarValue> for &protobuf::ScalarValue {
     type Error = BallistaError;
     fn try_into(self) -> Result<datafusion::scalar::ScalarValue, Self::Error> {
@@ -811,11 +993,28 @@ impl TryInto<datafusion::scalar::ScalarValue> for &protobuf::ScalarValue {
                 ScalarValue::Date32(Some(*v))
             }
             protobuf::scalar_value::Value::TimeMicrosecondValue(v) => {
-                ScalarValue::TimestampMicrosecond(Some(*v))
+                ScalarValue::TimestampMicrosecond(Some(*v), None)
             }
             protobuf::scalar_value::Value::TimeNanosecondValue(v) => {
-                ScalarValue::TimestampNanosecond(Some(*v))
+                ScalarValue::TimestampNanosecond(Some(*v), None)
+            }
+            protobuf::scalar_value::Value::TimestampValue(v) => v.try_into()?,
+            protobuf::scalar_value::Value::IntervalYearmonthValue(v) => {
+                ScalarValue::IntervalYearMonth(Some(*v))
             }
+            protobuf::scalar_value::Value::IntervalDaytimeValue(v) => {
+                ScalarValue::IntervalDayTime(Some(*v))
+            }
+            protobuf::scalar_value::Value::IntervalMonthdaynanoValue(v) => {
+                ScalarValue::new_interval_mdn(v.months, v.days, v.nanos)
+            }
+            protobuf::scalar_value::Value::BinaryValue(v) => {
+                ScalarValue::Binary(Some(v.to_owned()))
+            }
+            protobuf::scalar_value::Value::LargeBinaryValue(v) => {
+                ScalarValue::LargeBinary(Some(v.to_owned()))
+            }
+            protobuf::scalar_value::Value::FixedSizeBinaryValue(v) => v.into(),
             protobuf::scalar_value::Value::ListValue(scalar_list) => {
                 let protobuf::ScalarListValue {
                     values,
@@ -905,6 +1104,11 @@ impl TryInto<Expr> for &protobuf::LogicalExprNode {
                         }
                     })
                     .transpose()?;
+                let extra_args = expr
+                    .args
+                    .iter()
+                    .map(|e| e.try_into())
+                    .collect::<Result<Vec<_>, _>>()?;
 
                 match window_function {
                     window_expr_node::WindowFunction::AggrFunction(i) => {
@@ -920,7 +1124,9 @@ impl TryInto<Expr> for &protobuf::LogicalExprNode {
                             fun: window_functions::WindowFunction::AggregateFunction(
                                 AggregateFunction::from(aggr_function),
                             ),
-                            args: vec![parse_required_expr(&expr.expr)?],
+                            args: std::iter::once(parse_required_expr(&expr.expr)?)
+                                .chain(extra_args)
+                                .collect(),
                             partition_by,
                             order_by,
                             window_frame,
@@ -941,7 +1147,11 @@ impl TryInto<Expr> for &protobuf::LogicalExprNode {
                             fun: window_functions::WindowFunction::BuiltInWindowFunction(
                                 BuiltInWindowFunction::from(built_in_function),
                             ),
-                            args: vec![parse_required_expr(&expr.expr)?],
+                            // functions such as row_number have no arguments
+                            args: parse_optional_expr(&expr.expr)?
+                                .into_iter()
+                                .chain(extra_args)
+                                .collect(),
                             partition_by,
                             order_by,
                             window_frame,
@@ -959,13 +1169,40 @@ impl TryInto<Expr> for &protobuf::LogicalExprNode {
                             ))
                         })?;
                 let fun = AggregateFunction::from(aggr_function);
+                let mut args = vec![parse_required_expr(&expr.expr)?];
+                for arg in &expr.args {
+                    args.push(arg.try_into()?);
+                }
+                let order_by = expr
+                    .order_by
+                    .iter()
+                    .map(|e| e.try_into())
+                    .collect::<Result<Vec<_>, _>>()?;
 
                 Ok(Expr::AggregateFunction {
                     fun,
-                    args: vec![parse_required_expr(&expr.expr)?],
-                    distinct: false, //TODO
+                    args,
+                    distinct: expr.distinct,
+                    filter: parse_optional_expr(&expr.filter)?.map(Box::new),
+                    order_by,
                 })
             }
+            ExprType::AggregateUdfExpr(expr) => Ok(Expr::AggregateUDF {
+                fun: udaf::get_udaf(&expr.fun_name)?,
+                args: expr
+                    .args
+                    .iter()
+                    .map(|e| e.try_into())
+                    .collect::<Result<Vec<_>, _>>()?,
+            }),
+            ExprType::ScalarUdfExpr(expr) => Ok(Expr::ScalarUDF {
+                fun: udf::get_udf(&expr.fun_name)?,
+                args: expr
+                    .args
+                    .iter()
+                    .map(|e| e.try_into())
+                    .collect::<Result<Vec<_>, _>>()?,
+            }),
             ExprType::Alias(alias) => Ok(Expr::Alias(
                 Box::new(parse_required_expr(&alias.expr)?),
                 alias.alias.clone(),
@@ -1108,6 +1345,16 @@ impl TryInto<Expr> for &protobuf::LogicalExprNode {
                     protobuf::ScalarFunction::Digest => {
                         Ok(digest((&args[0]).try_into()?, (&args[1]).try_into()?))
                     }
+                    protobuf::ScalarFunction::Datebin => Ok(Expr::ScalarFunction {
+                        fun: BuiltinScalarFunction::DateBin,
+                        args: args
+                            .iter()
+                            .map(|e| e.try_into())
+                            .collect::<Result<Vec<_>, _>>()?,
+                    }),
+                    protobuf::ScalarFunction::Window => {
+                        Ok(window((&args[0]).try_into()?, (&args[1]).try_into()?))
+                    }
                     _ => Err(proto_error(
                         "Protobuf deserialization error: Unsupported scalar function",
                     )),
@@ -1131,6 +1378,11 @@ impl TryInto<Schema> for &protobuf::Schema {
     type Error = BallistaError;
 
     fn try_into(self) -> Result<Schema, BallistaError> {
+        if self.interned_index != 0 {
+            return Err(proto_error(
+                "An interned schema can only be deserialized with the plan interning it",
+            ));
+        }
         let fields = self
             .columns
             .iter()
@@ -1248,19 +1500,34 @@ impl TryFrom<protobuf::WindowFrameBound> for WindowFrameBound {
                 Ok(WindowFrameBound::CurrentRow)
             }
             protobuf::WindowFrameBoundType::Preceding => {
-                // FIXME implement bound value parsing
-                // https://github.com/apache/arrow-datafusion/issues/361
-                Ok(WindowFrameBound::Preceding(Some(1)))
+                Ok(WindowFrameBound::Preceding(bound_value(bound.bound_value)))
             }
             protobuf::WindowFrameBoundType::Following => {
-                // FIXME implement bound value parsing
-                // https://github.com/apache/arrow-datafusion/issues/361
-                Ok(WindowFrameBound::Following(Some(1)))
+                Ok(WindowFrameBound::Following(bound_value(bound.bound_value)))
             }
         }
     }
 }
 
+fn bound_value(value: Option<protobuf::window_frame_bound::BoundValue>) -> Option<u64> {
+    value.map(|value| match value {
+        protobuf::window_frame_bound::BoundValue::Value(value) => value,
+    })
+}
+
+impl From<protobuf::WindowFrameExclusion> for WindowFrameExclusion {
+    fn from(exclusion: protobuf::WindowFrameExclusion) -> Self {
+        match exclusion {
+            protobuf::WindowFrameExclusion::NoOthers => WindowFrameExclusion::NoOthers,
+            protobuf::WindowFrameExclusion::CurrentRow => {
+                WindowFrameExclusion::CurrentRow
+            }
+            protobuf::WindowFrameExclusion::Group => WindowFrameExclusion::Group,
+            protobuf::WindowFrameExclusion::Ties => WindowFrameExclusion::Ties,
+        }
+    }
+}
+
 impl TryFrom<protobuf::WindowFrame> for WindowFrame {
     type Error = BallistaError;
 
@@ -1290,10 +1557,19 @@ impl TryFrom<protobuf::WindowFrame> for WindowFrame {
             })
             .transpose()?
             .unwrap_or(WindowFrameBound::CurrentRow);
+        let exclusion = protobuf::WindowFrameExclusion::from_i32(window.exclusion)
+            .ok_or_else(|| {
+                proto_error(format!(
+                    "Received a WindowFrame message with unknown WindowFrameExclusion {}",
+                    window.exclusion
+                ))
+            })?
+            .into();
         Ok(WindowFrame {
             units,
             start_bound,
             end_bound,
+            exclusion,
         })
     }
 }
@@ -0,0 +1,122 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Serde code to convert logical plans to and from human readable JSON, to
+//! capture, diff and replay plans.
+//!
+//! The JSON follows the schema of the `LogicalPlanNode` protobuf message,
+//! wrapped into an object that records the version of that schema:
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "plan": { "logical_plan_type": { "Projection": { ... } } }
+//! }
+//! ```
+
+use std::convert::TryInto;
+
+use datafusion::logical_plan::LogicalPlan;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BallistaError, Result};
+use crate::serde::protobuf;
+
+/// Version of the schema of the JSON plans written by [`logical_plan_to_json`].
+///
+/// It is increased whenever the `LogicalPlanNode` message changes in a way
+/// that plans written by previous versions can no longer be read.
+pub const PLAN_JSON_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct JsonPlan {
+    version: u32,
+    plan: protobuf::LogicalPlanNode,
+}
+
+/// Serializes `plan` as pretty-printed JSON
+pub fn logical_plan_to_json(plan: &LogicalPlan) -> Result<String> {
+    let plan = JsonPlan {
+        version: PLAN_JSON_VERSION,
+        plan: plan.try_into()?,
+    };
+    serde_json::to_string_pretty(&plan).map_err(|e| {
+        BallistaError::General(format!("Failed to serialize plan as JSON: {}", e))
+    })
+}
+
+/// Deserializes a plan written by [`logical_plan_to_json`]
+pub fn logical_plan_from_json(json: &str) -> Result<LogicalPlan> {
+    let plan: JsonPlan = serde_json::from_str(json).map_err(|e| {
+        BallistaError::General(format!("Failed to deserialize plan from JSON: {}", e))
+    })?;
+    if plan.version != PLAN_JSON_VERSION {
+        return Err(BallistaError::General(format!(
+            "Unsupported version {} of JSON plan, expected version {}",
+            plan.version, PLAN_JSON_VERSION
+        )));
+    }
+    (&plan.plan).try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::datasource::object_store::local::LocalFileSystem;
+    use datafusion::logical_plan::{col, lit, LogicalPlanBuilder};
+    use datafusion::prelude::CsvReadOptions;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn roundtrip_json() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("state", DataType::Utf8, false),
+            Field::new("salary", DataType::Int32, false),
+        ]);
+
+        let plan = LogicalPlanBuilder::scan_csv(
+            Arc::new(LocalFileSystem {}),
+            "employee.csv",
+            CsvReadOptions::new().schema(&schema).has_header(true),
+            None,
+            4,
+        )
+        .await
+        .and_then(|plan| plan.filter(col("state").eq(lit("CO"))))
+        .and_then(|plan| plan.project(vec![col("id"), col("salary")]))
+        .and_then(|plan| plan.sort(vec![col("salary")]))
+        .and_then(|plan| plan.build())?;
+
+        let json = logical_plan_to_json(&plan)?;
+        assert!(json.contains("\"version\": 1"), "{}", json);
+        assert!(json.contains("employee.csv"), "{}", json);
+
+        let round_trip = logical_plan_from_json(&json)?;
+        assert_eq!(format!("{:?}", plan), format!("{:?}", round_trip));
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let err = logical_plan_from_json(r#"{"version": 0, "plan": {}}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Unsupported version 0"), "{}", err);
+    }
+}
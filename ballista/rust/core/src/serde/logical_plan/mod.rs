@@ -16,23 +16,36 @@
 // under the License.
 
 pub mod from_proto;
+pub mod json;
 pub mod to_proto;
 
 #[cfg(test)]
 mod roundtrip_tests {
 
     use super::super::{super::error::Result, protobuf};
+    use crate::dataset::DatasetTable;
     use crate::error::BallistaError;
+    use crate::serde::scheduler::{
+        ExecutorMeta, PartitionId, PartitionLocation, PartitionStats,
+    };
     use core::panic;
     use datafusion::logical_plan::Repartition;
     use datafusion::{
+        arrow::array::ArrayRef,
         arrow::datatypes::{DataType, Field, IntervalUnit, Schema, TimeUnit},
+        datasource::listing::Bucketing,
         datasource::object_store::local::LocalFileSystem,
+        logical_plan::window_frames::{
+            WindowFrame, WindowFrameBound, WindowFrameExclusion, WindowFrameUnits,
+        },
         logical_plan::{
-            col, CreateExternalTable, Expr, LogicalPlan, LogicalPlanBuilder,
-            Partitioning, ToDFSchema,
+            col, create_udaf, create_udf, CreateExternalTable, Expr, LogicalPlan,
+            LogicalPlanBuilder, MaterializedCte, Partitioning, TableScan, ToDFSchema,
         },
-        physical_plan::functions::BuiltinScalarFunction::Sqrt,
+        physical_plan::expressions::AvgAccumulator,
+        physical_plan::functions::BuiltinScalarFunction::{self, Sqrt},
+        physical_plan::functions::{make_scalar_function, Volatility},
+        physical_plan::window_functions::{BuiltInWindowFunction, WindowFunction},
         prelude::*,
         scalar::ScalarValue,
         sql::parser::FileType,
@@ -120,6 +133,26 @@ mod roundtrip_tests {
             roundtrip_test!(roundtrip_plan);
         }
 
+        let range_repartition = Partitioning::RangePartitioning(
+            vec![col("salary").sort(false, true)],
+            vec![
+                vec![ScalarValue::Int32(Some(1000))],
+                vec![ScalarValue::Int32(None)],
+            ],
+        );
+        let roundtrip_plan = LogicalPlan::Repartition(Repartition {
+            input: plan.clone(),
+            partitioning_scheme: range_repartition,
+        });
+        roundtrip_test!(roundtrip_plan);
+
+        let custom_repartition = Partitioning::Custom(col("id").modulus(lit(4)), 4);
+        let roundtrip_plan = LogicalPlan::Repartition(Repartition {
+            input: plan.clone(),
+            partitioning_scheme: custom_repartition,
+        });
+        roundtrip_test!(roundtrip_plan);
+
         Ok(())
     }
 
@@ -216,8 +249,14 @@ mod roundtrip_tests {
             ScalarValue::LargeUtf8(None),
             ScalarValue::List(None, Box::new(DataType::Boolean)),
             ScalarValue::Date32(None),
-            ScalarValue::TimestampMicrosecond(None),
-            ScalarValue::TimestampNanosecond(None),
+            ScalarValue::TimestampMicrosecond(None, None),
+            ScalarValue::TimestampNanosecond(None, None),
+            ScalarValue::IntervalYearMonth(None),
+            ScalarValue::IntervalDayTime(None),
+            ScalarValue::IntervalMonthDayNano(None),
+            ScalarValue::IntervalYearMonth(Some(-14)),
+            ScalarValue::IntervalDayTime(Some(12899608388)),
+            ScalarValue::new_interval_mdn(-1, 2, -3_000),
             ScalarValue::Boolean(Some(true)),
             ScalarValue::Boolean(Some(false)),
             ScalarValue::Float32(Some(1.0)),
@@ -254,13 +293,18 @@ mod roundtrip_tests {
             ScalarValue::UInt64(Some(0)),
             ScalarValue::Utf8(Some(String::from("Test string   "))),
             ScalarValue::LargeUtf8(Some(String::from("Test Large utf8"))),
+            ScalarValue::Binary(Some(vec![0, 1, 255])),
+            ScalarValue::LargeBinary(Some(vec![])),
+            ScalarValue::FixedSizeBinary(2, Some(vec![1, 2])),
             ScalarValue::Date32(Some(0)),
             ScalarValue::Date32(Some(i32::MAX)),
-            ScalarValue::TimestampNanosecond(Some(0)),
-            ScalarValue::TimestampNanosecond(Some(i64::MAX)),
-            ScalarValue::TimestampMicrosecond(Some(0)),
-            ScalarValue::TimestampMicrosecond(Some(i64::MAX)),
-            ScalarValue::TimestampMicrosecond(None),
+            ScalarValue::TimestampNanosecond(Some(0), None),
+            ScalarValue::TimestampNanosecond(Some(i64::MAX), None),
+            ScalarValue::TimestampMicrosecond(Some(0), None),
+            ScalarValue::TimestampMicrosecond(Some(i64::MAX), None),
+            ScalarValue::TimestampMicrosecond(None, None),
+            ScalarValue::TimestampSecond(Some(1), Some("+08:00".to_owned())),
+            ScalarValue::TimestampMillisecond(None, Some("UTC".to_owned())),
             ScalarValue::List(
                 Some(Box::new(vec![
                     ScalarValue::Float32(Some(-213.1)),
@@ -335,6 +379,8 @@ mod roundtrip_tests {
             DataType::Time64(TimeUnit::Nanosecond),
             DataType::Utf8,
             DataType::LargeUtf8,
+            DataType::Binary,
+            DataType::LargeBinary,
             //Recursive list tests
             DataType::List(new_box_field("Level1", DataType::Boolean, true)),
             DataType::List(new_box_field(
@@ -362,11 +408,10 @@ mod roundtrip_tests {
             DataType::Duration(TimeUnit::Nanosecond),
             DataType::Interval(IntervalUnit::YearMonth),
             DataType::Interval(IntervalUnit::DayTime),
-            DataType::Binary,
+            DataType::Interval(IntervalUnit::MonthDayNano),
             DataType::FixedSizeBinary(0),
             DataType::FixedSizeBinary(1234),
             DataType::FixedSizeBinary(-432),
-            DataType::LargeBinary,
             DataType::Decimal(1345, 5431),
             //Recursive list tests
             DataType::List(new_box_field("Level1", DataType::Binary, true)),
@@ -505,6 +550,7 @@ mod roundtrip_tests {
             DataType::Duration(TimeUnit::Nanosecond),
             DataType::Interval(IntervalUnit::YearMonth),
             DataType::Interval(IntervalUnit::DayTime),
+            DataType::Interval(IntervalUnit::MonthDayNano),
             DataType::Binary,
             DataType::FixedSizeBinary(0),
             DataType::FixedSizeBinary(1234),
@@ -592,6 +638,17 @@ mod roundtrip_tests {
                     4,
                 )),
             ),
+            DataType::Map(
+                new_box_field(
+                    "entries",
+                    DataType::Struct(vec![
+                        Field::new("key", DataType::Utf8, false),
+                        Field::new("value", DataType::Int64, true),
+                    ]),
+                    false,
+                ),
+                false,
+            ),
         ];
 
         for test_case in test_cases.into_iter() {
@@ -618,9 +675,13 @@ mod roundtrip_tests {
             ScalarValue::UInt64(None),
             ScalarValue::Utf8(None),
             ScalarValue::LargeUtf8(None),
+            ScalarValue::Binary(None),
+            ScalarValue::LargeBinary(None),
+            ScalarValue::FixedSizeBinary(2, None),
             ScalarValue::Date32(None),
-            ScalarValue::TimestampMicrosecond(None),
-            ScalarValue::TimestampNanosecond(None),
+            ScalarValue::TimestampMicrosecond(None, None),
+            ScalarValue::TimestampNanosecond(None, None),
+            ScalarValue::TimestampSecond(None, Some("Europe/Paris".to_owned())),
             //ScalarValue::List(None, DataType::Boolean)
         ];
 
@@ -664,9 +725,22 @@ mod roundtrip_tests {
                     location: String::from("employee.csv"),
                     file_type: *file,
                     has_header: true,
+                    bucketing: None,
                 });
 
             roundtrip_test!(create_table_node);
+
+            let create_bucketed_table_node =
+                LogicalPlan::CreateExternalTable(CreateExternalTable {
+                    schema: df_schema_ref.clone(),
+                    name: String::from("TestName"),
+                    location: String::from("employee.csv"),
+                    file_type: *file,
+                    has_header: true,
+                    bucketing: Some(Bucketing::new(vec!["id".to_owned()], 4)),
+                });
+
+            roundtrip_test!(create_bucketed_table_node);
         }
 
         Ok(())
@@ -715,6 +789,37 @@ mod roundtrip_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn roundtrip_materialized_cte() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("salary", DataType::Int32, false),
+        ]);
+
+        let input = LogicalPlanBuilder::scan_csv(
+            Arc::new(LocalFileSystem {}),
+            "employee.csv",
+            CsvReadOptions::new().schema(&schema).has_header(true),
+            None,
+            4,
+        )
+        .await
+        .and_then(|plan| plan.build())
+        .map_err(BallistaError::DataFusionError)?;
+        let plan = MaterializedCte::new_plan("cte", input);
+
+        roundtrip_test!(plan);
+
+        let proto: protobuf::LogicalPlanNode = (&plan).try_into()?;
+        let round_trip: LogicalPlan = (&proto).try_into()?;
+        assert_eq!(
+            MaterializedCte::from_plan(&plan).unwrap().id,
+            MaterializedCte::from_plan(&round_trip).unwrap().id
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn roundtrip_explain() -> Result<()> {
         let schema = Schema::new(vec![
@@ -838,6 +943,46 @@ mod roundtrip_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn roundtrip_dataset_scan() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let location = PartitionLocation {
+            partition_id: PartitionId::new("job", 1, 0),
+            executor_meta: ExecutorMeta {
+                id: "executor".to_owned(),
+                host: "localhost".to_owned(),
+                port: 50051,
+                labels: Default::default(),
+            },
+            partition_stats: PartitionStats::default(),
+            path: "/tmp/job/1/0/data.arrow".to_owned(),
+            checksum: None,
+        };
+        let dataset = DatasetTable::new("job".to_owned(), schema, vec![vec![location]]);
+
+        let plan = LogicalPlanBuilder::scan("cached", Arc::new(dataset), Some(vec![1]))
+            .and_then(|plan| plan.build())
+            .map_err(BallistaError::DataFusionError)?;
+
+        roundtrip_test!(plan);
+
+        let proto: protobuf::LogicalPlanNode = (&plan).try_into()?;
+        let round_trip: LogicalPlan = (&proto).try_into()?;
+        match round_trip {
+            LogicalPlan::TableScan(TableScan { source, .. }) => {
+                let dataset = source.as_any().downcast_ref::<DatasetTable>().unwrap();
+                assert_eq!("job", dataset.dataset_id());
+                assert_eq!("/tmp/job/1/0/data.arrow", dataset.partition()[0][0].path);
+            }
+            other => panic!("expected a table scan, got {:?}", other),
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn roundtrip_logical_plan() -> Result<()> {
         let schema = Schema::new(vec![
@@ -956,6 +1101,43 @@ mod roundtrip_tests {
         Ok(())
     }
 
+    #[test]
+    fn roundtrip_aggregate_udf() -> Result<()> {
+        let my_avg = create_udaf(
+            "logical_roundtrip_avg",
+            DataType::Float64,
+            Arc::new(DataType::Float64),
+            Volatility::Immutable,
+            Arc::new(|| Ok(Box::new(AvgAccumulator::try_new(&DataType::Float64)?))),
+            Arc::new(vec![DataType::UInt64, DataType::Float64]),
+        );
+        crate::serde::udaf::register_udaf(my_avg.clone());
+
+        let test_expr = my_avg.call(vec![col("a")]);
+
+        roundtrip_test!(test_expr, protobuf::LogicalExprNode, Expr);
+
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_scalar_udf() -> Result<()> {
+        let my_identity = create_udf(
+            "logical_roundtrip_identity",
+            vec![DataType::Float64],
+            Arc::new(DataType::Float64),
+            Volatility::Immutable,
+            make_scalar_function(|args: &[ArrayRef]| Ok(args[0].clone())),
+        );
+        crate::serde::udf::register_udf(my_identity.clone());
+
+        let test_expr = my_identity.call(vec![col("a")]);
+
+        roundtrip_test!(test_expr, protobuf::LogicalExprNode, Expr);
+
+        Ok(())
+    }
+
     #[test]
     fn roundtrip_inlist() -> Result<()> {
         let test_expr = Expr::InList {
@@ -988,4 +1170,57 @@ mod roundtrip_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn roundtrip_date_bin() -> Result<()> {
+        let test_expr = Expr::ScalarFunction {
+            fun: BuiltinScalarFunction::DateBin,
+            args: vec![
+                lit(ScalarValue::IntervalDayTime(Some(60_000))),
+                col("ts"),
+                lit(ScalarValue::TimestampNanosecond(Some(0), None)),
+            ],
+        };
+        roundtrip_test!(test_expr, protobuf::LogicalExprNode, Expr);
+
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_window_functions() -> Result<()> {
+        let window_frame = WindowFrame {
+            units: WindowFrameUnits::Groups,
+            start_bound: WindowFrameBound::Preceding(Some(2)),
+            end_bound: WindowFrameBound::Following(Some(1)),
+            exclusion: WindowFrameExclusion::Ties,
+        };
+        let window = |fun, args| Expr::WindowFunction {
+            fun: WindowFunction::BuiltInWindowFunction(fun),
+            args,
+            partition_by: vec![col("col1")],
+            order_by: vec![col("col2").sort(true, false)],
+            window_frame: Some(window_frame),
+        };
+        let test_exprs = vec![
+            window(BuiltInWindowFunction::RowNumber, vec![]),
+            window(BuiltInWindowFunction::Ntile, vec![lit(4i64)]),
+            window(
+                BuiltInWindowFunction::Lag,
+                vec![col("col3"), lit(2i64), lit(-1i64)],
+            ),
+            window(
+                BuiltInWindowFunction::FirstValue,
+                vec![col("col3"), lit(true)],
+            ),
+            window(
+                BuiltInWindowFunction::NthValue,
+                vec![col("col3"), lit(2i64), lit(true)],
+            ),
+        ];
+        for test_expr in test_exprs {
+            roundtrip_test!(test_expr, protobuf::LogicalExprNode, Expr);
+        }
+
+        Ok(())
+    }
 }
@@ -20,7 +20,8 @@
 //! processes.
 
 use super::super::proto_error;
-use crate::serde::{byte_to_string, protobuf, BallistaError};
+use crate::dataset::DatasetTable;
+use crate::serde::{byte_to_string, protobuf, range_boundaries_to_proto, BallistaError};
 use datafusion::arrow::datatypes::{
     DataType, Field, IntervalUnit, Schema, SchemaRef, TimeUnit,
 };
@@ -29,15 +30,17 @@ use datafusion::datasource::file_format::csv::CsvFormat;
 use datafusion::datasource::TableProvider;
 
 use datafusion::datasource::file_format::parquet::ParquetFormat;
-use datafusion::datasource::listing::ListingTable;
+use datafusion::datasource::listing::{Bucketing, ListingTable};
 use datafusion::logical_plan::plan::{
     Aggregate, EmptyRelation, Filter, Join, Projection, Sort, Window,
 };
 use datafusion::logical_plan::{
     exprlist_to_fields,
-    window_frames::{WindowFrame, WindowFrameBound, WindowFrameUnits},
+    window_frames::{
+        WindowFrame, WindowFrameBound, WindowFrameExclusion, WindowFrameUnits,
+    },
     Column, CreateExternalTable, CrossJoin, Expr, JoinConstraint, JoinType, Limit,
-    LogicalPlan, Repartition, TableScan, Values,
+    LogicalPlan, MaterializedCte, Repartition, TableScan, Values,
 };
 use datafusion::physical_plan::aggregates::AggregateFunction;
 use datafusion::physical_plan::functions::BuiltinScalarFunction;
@@ -60,6 +63,7 @@ impl protobuf::IntervalUnit {
         match interval_unit {
             IntervalUnit::YearMonth => protobuf::IntervalUnit::YearMonth,
             IntervalUnit::DayTime => protobuf::IntervalUnit::DayTime,
+            IntervalUnit::MonthDayNano => protobuf::IntervalUnit::MonthDayNano,
         }
     }
 
@@ -71,6 +75,7 @@ impl protobuf::IntervalUnit {
             Some(interval_unit) => Ok(match interval_unit {
                 protobuf::IntervalUnit::YearMonth => IntervalUnit::YearMonth,
                 protobuf::IntervalUnit::DayTime => IntervalUnit::DayTime,
+                protobuf::IntervalUnit::MonthDayNano => IntervalUnit::MonthDayNano,
             }),
             None => Err(proto_error(
                 "Error converting i32 to DateUnit: Passed invalid variant",
@@ -261,6 +266,16 @@ impl TryInto<DataType> for &protobuf::ArrowType {
                     Box::new(pb_value.as_ref().try_into()?),
                 )
             }
+            protobuf::arrow_type::ArrowTypeEnum::Map(boxed_map) => {
+                let pb_entries = boxed_map
+                    .entries
+                    .as_ref()
+                    .ok_or_else(|| proto_error("Protobuf deserialization error: Map message was missing required field 'entries'"))?;
+                DataType::Map(
+                    Box::new(pb_entries.as_ref().try_into()?),
+                    boxed_map.keys_sorted,
+                )
+            }
         })
     }
 }
@@ -364,8 +379,11 @@ impl From<&DataType> for protobuf::arrow_type::ArrowTypeEnum {
                     fractional: *fractional as u64,
                 })
             }
-            DataType::Map(_, _) => {
-                unimplemented!("Ballista does not yet support Map data type")
+            DataType::Map(entries, keys_sorted) => {
+                ArrowTypeEnum::Map(Box::new(protobuf::Map {
+                    entries: Some(Box::new(entries.as_ref().into())),
+                    keys_sorted: *keys_sorted,
+                }))
             }
         }
     }
@@ -426,6 +444,8 @@ impl TryFrom<&DataType> for protobuf::scalar_type::Datatype {
             },
             DataType::Utf8 => scalar_type::Datatype::Scalar(PrimitiveScalarType::Utf8 as i32),
             DataType::LargeUtf8 => scalar_type::Datatype::Scalar(PrimitiveScalarType::LargeUtf8 as i32),
+            DataType::Binary => scalar_type::Datatype::Scalar(PrimitiveScalarType::Binary as i32),
+            DataType::LargeBinary => scalar_type::Datatype::Scalar(PrimitiveScalarType::LargeBinary as i32),
             DataType::List(field_type) => {
                 let mut field_names: Vec<String> = Vec::new();
                 let mut curr_field = field_type.as_ref();
@@ -489,9 +509,7 @@ impl TryFrom<&DataType> for protobuf::scalar_type::Datatype {
             | DataType::Time32(_)
             | DataType::Duration(_)
             | DataType::Interval(_)
-            | DataType::Binary
             | DataType::FixedSizeBinary(_)
-            | DataType::LargeBinary
             | DataType::FixedSizeList(_, _)
             | DataType::LargeList(_)
             | DataType::Struct(_)
@@ -573,6 +591,23 @@ impl TryFrom<&datafusion::scalar::ScalarValue> for protobuf::ScalarValue {
                     Value::LargeUtf8Value(s.to_owned())
                 })
             }
+            scalar::ScalarValue::Binary(val) => {
+                create_proto_scalar(val, PrimitiveScalarType::Binary, |s| {
+                    Value::BinaryValue(s.to_owned())
+                })
+            }
+            scalar::ScalarValue::LargeBinary(val) => {
+                create_proto_scalar(val, PrimitiveScalarType::LargeBinary, |s| {
+                    Value::LargeBinaryValue(s.to_owned())
+                })
+            }
+            scalar::ScalarValue::FixedSizeBinary(width, val) => protobuf::ScalarValue {
+                value: Some(Value::FixedSizeBinaryValue(protobuf::FixedSizeBinaryValue {
+                    width: *width,
+                    value: val.clone().unwrap_or_default(),
+                    is_null: val.is_none(),
+                })),
+            },
             scalar::ScalarValue::List(value, datatype) => {
                 println!("Current datatype of list: {:?}", datatype);
                 match value {
@@ -652,14 +687,36 @@ impl TryFrom<&datafusion::scalar::ScalarValue> for protobuf::ScalarValue {
             datafusion::scalar::ScalarValue::Date32(val) => {
                 create_proto_scalar(val, PrimitiveScalarType::Date32, |s| Value::Date32Value(*s))
             }
-            datafusion::scalar::ScalarValue::TimestampMicrosecond(val) => {
-                create_proto_scalar(val, PrimitiveScalarType::TimeMicrosecond, |s| {
-                    Value::TimeMicrosecondValue(*s)
+            datafusion::scalar::ScalarValue::TimestampSecond(val, tz) => {
+                create_proto_timestamp(val, &TimeUnit::Second, tz)
+            }
+            datafusion::scalar::ScalarValue::TimestampMillisecond(val, tz) => {
+                create_proto_timestamp(val, &TimeUnit::Millisecond, tz)
+            }
+            datafusion::scalar::ScalarValue::TimestampMicrosecond(val, tz) => {
+                create_proto_timestamp(val, &TimeUnit::Microsecond, tz)
+            }
+            datafusion::scalar::ScalarValue::TimestampNanosecond(val, tz) => {
+                create_proto_timestamp(val, &TimeUnit::Nanosecond, tz)
+            }
+            datafusion::scalar::ScalarValue::IntervalYearMonth(val) => {
+                create_proto_scalar(val, PrimitiveScalarType::IntervalYearmonth, |s| {
+                    Value::IntervalYearmonthValue(*s)
                 })
             }
-            datafusion::scalar::ScalarValue::TimestampNanosecond(val) => {
-                create_proto_scalar(val, PrimitiveScalarType::TimeNanosecond, |s| {
-                    Value::TimeNanosecondValue(*s)
+            datafusion::scalar::ScalarValue::IntervalDayTime(val) => {
+                create_proto_scalar(val, PrimitiveScalarType::IntervalDaytime, |s| {
+                    Value::IntervalDaytimeValue(*s)
+                })
+            }
+            datafusion::scalar::ScalarValue::IntervalMonthDayNano(val) => {
+                create_proto_scalar(val, PrimitiveScalarType::IntervalMonthdaynano, |s| {
+                    let (months, days, nanos) = scalar::interval_mdn_parts(*s);
+                    Value::IntervalMonthdaynanoValue(protobuf::IntervalMonthDayNanoValue {
+                        months,
+                        days,
+                        nanos,
+                    })
                 })
             }
             _ => {
@@ -763,7 +820,7 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
                                     .options()
                                     .table_partition_cols
                                     .clone(),
-                                path: listing_table.table_path().to_owned(),
+                                paths: listing_table.table_paths().to_vec(),
                                 schema: Some(schema),
                                 projection,
                                 filters,
@@ -771,6 +828,30 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
                                     .options()
                                     .target_partitions
                                     as u32,
+                                bucketing: listing_table
+                                    .options()
+                                    .bucketing
+                                    .as_ref()
+                                    .map(|b| b.into()),
+                                metadata_cache_ttl_ms: listing_table
+                                    .options()
+                                    .metadata_cache_ttl
+                                    .map(|ttl| ttl.as_millis() as u64)
+                                    .unwrap_or(0),
+                                stat_collection_concurrency: listing_table
+                                    .options()
+                                    .stat_collection_concurrency
+                                    as u32,
+                            },
+                        )),
+                    })
+                } else if let Some(dataset) = source.downcast_ref::<DatasetTable>() {
+                    Ok(protobuf::LogicalPlanNode {
+                        logical_plan_type: Some(LogicalPlanType::DatasetScan(
+                            protobuf::DatasetScanNode {
+                                table_name: table_name.to_owned(),
+                                dataset: Some(dataset.try_into()?),
+                                projection,
                             },
                         )),
                     })
@@ -928,6 +1009,21 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
                     Partitioning::RoundRobinBatch(batch_size) => {
                         PartitionMethod::RoundRobin(*batch_size as u64)
                     }
+                    Partitioning::RangePartitioning(exprs, boundaries) => {
+                        PartitionMethod::Range(protobuf::RangeRepartition {
+                            sort_expr: exprs
+                                .iter()
+                                .map(|expr| expr.try_into())
+                                .collect::<Result<Vec<_>, BallistaError>>()?,
+                            boundary: range_boundaries_to_proto(boundaries)?,
+                        })
+                    }
+                    Partitioning::Custom(expr, partition_count) => {
+                        PartitionMethod::Custom(protobuf::CustomRepartition {
+                            partition_expr: Some(expr.try_into()?),
+                            partition_count: *partition_count as u64,
+                        })
+                    }
                 };
 
                 Ok(protobuf::LogicalPlanNode {
@@ -954,6 +1050,7 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
                 file_type,
                 has_header,
                 schema: df_schema,
+                bucketing,
             }) => {
                 use datafusion::sql::parser::FileType;
 
@@ -972,6 +1069,7 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
                             file_type: pb_file_type as i32,
                             has_header: *has_header,
                             schema: Some(df_schema.into()),
+                            bucketing: bucketing.as_ref().map(|b| b.into()),
                         },
                     )),
                 })
@@ -983,6 +1081,7 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
                         protobuf::AnalyzeNode {
                             input: Some(Box::new(input)),
                             verbose: a.verbose,
+                            format: protobuf::ExplainFormat::from(a.format).into(),
                         },
                     ))),
                 })
@@ -994,11 +1093,29 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
                         protobuf::ExplainNode {
                             input: Some(Box::new(input)),
                             verbose: a.verbose,
+                            format: protobuf::ExplainFormat::from(a.format).into(),
+                            costs: a.costs,
                         },
                     ))),
                 })
             }
-            LogicalPlan::Extension { .. } => unimplemented!(),
+            LogicalPlan::Extension(_) => match MaterializedCte::from_plan(self) {
+                Some(cte) => {
+                    let input: protobuf::LogicalPlanNode = (&cte.input).try_into()?;
+                    Ok(protobuf::LogicalPlanNode {
+                        logical_plan_type: Some(LogicalPlanType::MaterializedCte(
+                            Box::new(protobuf::MaterializedCteNode {
+                                input: Some(Box::new(input)),
+                                name: cte.name.clone(),
+                                id: cte.id as u64,
+                            }),
+                        )),
+                    })
+                }
+                None => Err(proto_error(
+                    "Error converting Extension. Not yet supported in Ballista",
+                )),
+            },
             LogicalPlan::Union(_) => unimplemented!(),
             LogicalPlan::CrossJoin(CrossJoin { left, right, .. }) => {
                 let left: protobuf::LogicalPlanNode = left.as_ref().try_into()?;
@@ -1018,6 +1135,9 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
             LogicalPlan::DropTable(_) => Err(proto_error(
                 "Error converting DropTable. Not yet supported in Ballista",
             )),
+            LogicalPlan::InsertInto(_) => Err(proto_error(
+                "Error converting InsertInto. Not yet supported in Ballista",
+            )),
         }
     }
 }
@@ -1034,6 +1154,23 @@ fn create_proto_scalar<I, T: FnOnce(&I) -> protobuf::scalar_value::Value>(
     }
 }
 
+fn create_proto_timestamp(
+    v: &Option<i64>,
+    time_unit: &TimeUnit,
+    timezone: &Option<String>,
+) -> protobuf::ScalarValue {
+    protobuf::ScalarValue {
+        value: Some(protobuf::scalar_value::Value::TimestampValue(
+            protobuf::ScalarTimestampValue {
+                time_unit: protobuf::TimeUnit::from_arrow_time_unit(time_unit) as i32,
+                timezone: timezone.to_owned().unwrap_or_else(String::new),
+                value: v.unwrap_or_default(),
+                is_null: v.is_none(),
+            },
+        )),
+    }
+}
+
 impl TryInto<protobuf::LogicalExprNode> for &Expr {
     type Error = BallistaError;
 
@@ -1099,6 +1236,11 @@ impl TryInto<protobuf::LogicalExprNode> for &Expr {
                 } else {
                     None
                 };
+                let extra_args = args
+                    .iter()
+                    .skip(1)
+                    .map(|e| e.try_into())
+                    .collect::<Result<Vec<_>, _>>()?;
                 let partition_by = partition_by
                     .iter()
                     .map(|e| e.try_into())
@@ -1116,13 +1258,18 @@ impl TryInto<protobuf::LogicalExprNode> for &Expr {
                     partition_by,
                     order_by,
                     window_frame,
+                    args: extra_args,
                 });
                 Ok(protobuf::LogicalExprNode {
                     expr_type: Some(ExprType::WindowExpr(window_expr)),
                 })
             }
             Expr::AggregateFunction {
-                ref fun, ref args, ..
+                ref fun,
+                ref args,
+                distinct,
+                ref filter,
+                ref order_by,
             } => {
                 let aggr_function = match fun {
                     AggregateFunction::ApproxDistinct => {
@@ -1134,12 +1281,37 @@ impl TryInto<protobuf::LogicalExprNode> for &Expr {
                     AggregateFunction::Sum => protobuf::AggregateFunction::Sum,
                     AggregateFunction::Avg => protobuf::AggregateFunction::Avg,
                     AggregateFunction::Count => protobuf::AggregateFunction::Count,
+                    AggregateFunction::StringAgg => {
+                        protobuf::AggregateFunction::StringAgg
+                    }
+                    AggregateFunction::PercentileCont => {
+                        protobuf::AggregateFunction::PercentileCont
+                    }
+                    AggregateFunction::PercentileDisc => {
+                        protobuf::AggregateFunction::PercentileDisc
+                    }
                 };
 
                 let arg = &args[0];
+                let extra_args = args
+                    .iter()
+                    .skip(1)
+                    .map(|e| e.try_into())
+                    .collect::<Result<Vec<_>, _>>()?;
+                let order_by = order_by
+                    .iter()
+                    .map(|e| e.try_into())
+                    .collect::<Result<Vec<_>, _>>()?;
                 let aggregate_expr = Box::new(protobuf::AggregateExprNode {
                     aggr_function: aggr_function.into(),
                     expr: Some(Box::new(arg.try_into()?)),
+                    distinct: *distinct,
+                    filter: match filter {
+                        Some(filter) => Some(Box::new(filter.as_ref().try_into()?)),
+                        None => None,
+                    },
+                    args: extra_args,
+                    order_by,
                 });
                 Ok(protobuf::LogicalExprNode {
                     expr_type: Some(ExprType::AggregateExpr(aggregate_expr)),
@@ -1163,8 +1335,27 @@ impl TryInto<protobuf::LogicalExprNode> for &Expr {
                     ),
                 })
             }
-            Expr::ScalarUDF { .. } => unimplemented!(),
-            Expr::AggregateUDF { .. } => unimplemented!(),
+            Expr::ScalarUDF { fun, args } => Ok(protobuf::LogicalExprNode {
+                expr_type: Some(ExprType::ScalarUdfExpr(protobuf::ScalarUdfExprNode {
+                    fun_name: fun.name.clone(),
+                    args: args
+                        .iter()
+                        .map(|e| e.try_into())
+                        .collect::<Result<Vec<_>, BallistaError>>()?,
+                })),
+            }),
+            Expr::AggregateUDF { fun, args } => Ok(protobuf::LogicalExprNode {
+                expr_type: Some(ExprType::AggregateUdfExpr(
+                    protobuf::AggregateUdfExprNode {
+                        fun_name: fun.name.clone(),
+                        args: args.iter().map(|e| e.try_into()).collect::<Result<
+                            Vec<_>,
+                            BallistaError,
+                        >>(
+                        )?,
+                    },
+                )),
+            }),
             Expr::Not(expr) => {
                 let expr = Box::new(protobuf::Not {
                     expr: Some(Box::new(expr.as_ref().try_into()?)),
@@ -1310,6 +1501,15 @@ impl From<&Column> for protobuf::Column {
     }
 }
 
+impl From<&Bucketing> for protobuf::Bucketing {
+    fn from(b: &Bucketing) -> protobuf::Bucketing {
+        protobuf::Bucketing {
+            columns: b.columns.clone(),
+            num_buckets: b.num_buckets as u32,
+        }
+    }
+}
+
 #[allow(clippy::from_over_into)]
 impl Into<protobuf::Schema> for &Schema {
     fn into(self) -> protobuf::Schema {
@@ -1319,6 +1519,7 @@ impl Into<protobuf::Schema> for &Schema {
                 .iter()
                 .map(protobuf::Field::from)
                 .collect::<Vec<_>>(),
+            interned_index: 0,
         }
     }
 }
@@ -1332,6 +1533,7 @@ impl Into<protobuf::Schema> for SchemaRef {
                 .iter()
                 .map(protobuf::Field::from)
                 .collect::<Vec<_>>(),
+            interned_index: 0,
         }
     }
 }
@@ -1364,6 +1566,9 @@ impl From<&AggregateFunction> for protobuf::AggregateFunction {
             AggregateFunction::Count => Self::Count,
             AggregateFunction::ApproxDistinct => Self::ApproxDistinct,
             AggregateFunction::ArrayAgg => Self::ArrayAgg,
+            AggregateFunction::StringAgg => Self::StringAgg,
+            AggregateFunction::PercentileCont => Self::PercentileCont,
+            AggregateFunction::PercentileDisc => Self::PercentileDisc,
         }
     }
 }
@@ -1424,6 +1629,20 @@ impl From<WindowFrame> for protobuf::WindowFrame {
             end_bound: Some(protobuf::window_frame::EndBound::Bound(
                 window.end_bound.into(),
             )),
+            exclusion: protobuf::WindowFrameExclusion::from(window.exclusion).into(),
+        }
+    }
+}
+
+impl From<WindowFrameExclusion> for protobuf::WindowFrameExclusion {
+    fn from(exclusion: WindowFrameExclusion) -> Self {
+        match exclusion {
+            WindowFrameExclusion::NoOthers => protobuf::WindowFrameExclusion::NoOthers,
+            WindowFrameExclusion::CurrentRow => {
+                protobuf::WindowFrameExclusion::CurrentRow
+            }
+            WindowFrameExclusion::Group => protobuf::WindowFrameExclusion::Group,
+            WindowFrameExclusion::Ties => protobuf::WindowFrameExclusion::Ties,
         }
     }
 }
@@ -1474,6 +1693,8 @@ impl TryInto<protobuf::ScalarFunction> for &BuiltinScalarFunction {
             BuiltinScalarFunction::NullIf => Ok(protobuf::ScalarFunction::Nullif),
             BuiltinScalarFunction::DatePart => Ok(protobuf::ScalarFunction::Datepart),
             BuiltinScalarFunction::DateTrunc => Ok(protobuf::ScalarFunction::Datetrunc),
+            BuiltinScalarFunction::DateBin => Ok(protobuf::ScalarFunction::Datebin),
+            BuiltinScalarFunction::Window => Ok(protobuf::ScalarFunction::Window),
             BuiltinScalarFunction::MD5 => Ok(protobuf::ScalarFunction::Md5),
             BuiltinScalarFunction::SHA224 => Ok(protobuf::ScalarFunction::Sha224),
             BuiltinScalarFunction::SHA256 => Ok(protobuf::ScalarFunction::Sha256),
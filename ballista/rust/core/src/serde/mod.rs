@@ -18,11 +18,16 @@
 //! This crate contains code generated from the Ballista Protocol Buffer Definition as well
 //! as convenience code for interacting with the generated code.
 
-use std::{convert::TryInto, io::Cursor};
+use std::convert::{TryFrom, TryInto};
+use std::io::Cursor;
 
-use datafusion::logical_plan::{JoinConstraint, JoinType, Operator};
+use datafusion::logical_plan::plan::StringifiedPlan;
+use datafusion::logical_plan::{
+    ExplainFormat, JoinConstraint, JoinType, Operator, PlanType,
+};
 use datafusion::physical_plan::aggregates::AggregateFunction;
 use datafusion::physical_plan::window_functions::BuiltInWindowFunction;
+use datafusion::scalar::ScalarValue;
 
 use crate::{error::BallistaError, serde::scheduler::Action as BallistaAction};
 
@@ -37,6 +42,8 @@ pub mod protobuf {
 pub mod logical_plan;
 pub mod physical_plan;
 pub mod scheduler;
+pub mod udaf;
+pub mod udf;
 
 pub fn decode_protobuf(bytes: &[u8]) -> Result<BallistaAction, BallistaError> {
     let mut buf = Cursor::new(bytes);
@@ -83,6 +90,39 @@ macro_rules! convert_box_required {
     }};
 }
 
+/// Serializes the boundaries of a range partitioning
+pub(crate) fn range_boundaries_to_proto(
+    boundaries: &[Vec<ScalarValue>],
+) -> Result<Vec<protobuf::RangeBoundary>, BallistaError> {
+    boundaries
+        .iter()
+        .map(|boundary| {
+            Ok(protobuf::RangeBoundary {
+                value: boundary
+                    .iter()
+                    .map(|value| value.try_into())
+                    .collect::<Result<Vec<_>, BallistaError>>()?,
+            })
+        })
+        .collect()
+}
+
+/// Deserializes the boundaries of a range partitioning
+pub(crate) fn parse_range_boundaries(
+    boundaries: &[protobuf::RangeBoundary],
+) -> Result<Vec<Vec<ScalarValue>>, BallistaError> {
+    boundaries
+        .iter()
+        .map(|boundary| {
+            boundary
+                .value
+                .iter()
+                .map(|value| value.try_into())
+                .collect::<Result<Vec<_>, BallistaError>>()
+        })
+        .collect()
+}
+
 pub(crate) fn from_proto_binary_op(op: &str) -> Result<Operator, BallistaError> {
     match op {
         "And" => Ok(Operator::And),
@@ -118,6 +158,13 @@ impl From<protobuf::AggregateFunction> for AggregateFunction {
                 AggregateFunction::ApproxDistinct
             }
             protobuf::AggregateFunction::ArrayAgg => AggregateFunction::ArrayAgg,
+            protobuf::AggregateFunction::StringAgg => AggregateFunction::StringAgg,
+            protobuf::AggregateFunction::PercentileCont => {
+                AggregateFunction::PercentileCont
+            }
+            protobuf::AggregateFunction::PercentileDisc => {
+                AggregateFunction::PercentileDisc
+            }
         }
     }
 }
@@ -263,6 +310,15 @@ impl TryInto<datafusion::arrow::datatypes::DataType>
                 let value_datatype: DataType = pb_value_datatype.as_ref().try_into()?;
                 DataType::Dictionary(Box::new(key_datatype), Box::new(value_datatype))
             }
+            arrow_type::ArrowTypeEnum::Map(map) => {
+                let entries: &protobuf::Field = map
+                    .as_ref()
+                    .entries
+                    .as_ref()
+                    .ok_or_else(|| proto_error("Protobuf deserialization error: Map message missing required field 'entries'"))?
+                    .as_ref();
+                DataType::Map(Box::new(entries.try_into()?), map.keys_sorted)
+            }
         })
     }
 }
@@ -270,7 +326,7 @@ impl TryInto<datafusion::arrow::datatypes::DataType>
 #[allow(clippy::from_over_into)]
 impl Into<datafusion::arrow::datatypes::DataType> for protobuf::PrimitiveScalarType {
     fn into(self) -> datafusion::arrow::datatypes::DataType {
-        use datafusion::arrow::datatypes::{DataType, TimeUnit};
+        use datafusion::arrow::datatypes::{DataType, IntervalUnit, TimeUnit};
         match self {
             protobuf::PrimitiveScalarType::Bool => DataType::Boolean,
             protobuf::PrimitiveScalarType::Uint8 => DataType::UInt8,
@@ -293,6 +349,17 @@ impl Into<datafusion::arrow::datatypes::DataType> for protobuf::PrimitiveScalarT
                 DataType::Time64(TimeUnit::Nanosecond)
             }
             protobuf::PrimitiveScalarType::Null => DataType::Null,
+            protobuf::PrimitiveScalarType::IntervalYearmonth => {
+                DataType::Interval(IntervalUnit::YearMonth)
+            }
+            protobuf::PrimitiveScalarType::IntervalDaytime => {
+                DataType::Interval(IntervalUnit::DayTime)
+            }
+            protobuf::PrimitiveScalarType::IntervalMonthdaynano => {
+                DataType::Interval(IntervalUnit::MonthDayNano)
+            }
+            protobuf::PrimitiveScalarType::Binary => DataType::Binary,
+            protobuf::PrimitiveScalarType::LargeBinary => DataType::LargeBinary,
         }
     }
 }
@@ -306,6 +373,7 @@ impl From<protobuf::JoinType> for JoinType {
             protobuf::JoinType::Full => JoinType::Full,
             protobuf::JoinType::Semi => JoinType::Semi,
             protobuf::JoinType::Anti => JoinType::Anti,
+            protobuf::JoinType::NullAwareAnti => JoinType::NullAwareAnti,
         }
     }
 }
@@ -319,6 +387,7 @@ impl From<JoinType> for protobuf::JoinType {
             JoinType::Full => protobuf::JoinType::Full,
             JoinType::Semi => protobuf::JoinType::Semi,
             JoinType::Anti => protobuf::JoinType::Anti,
+            JoinType::NullAwareAnti => protobuf::JoinType::NullAwareAnti,
         }
     }
 }
@@ -341,6 +410,89 @@ impl From<JoinConstraint> for protobuf::JoinConstraint {
     }
 }
 
+impl From<protobuf::ExplainFormat> for ExplainFormat {
+    fn from(t: protobuf::ExplainFormat) -> Self {
+        match t {
+            protobuf::ExplainFormat::Indent => ExplainFormat::Indent,
+            protobuf::ExplainFormat::Json => ExplainFormat::Json,
+            protobuf::ExplainFormat::Graphviz => ExplainFormat::Graphviz,
+        }
+    }
+}
+
+impl From<ExplainFormat> for protobuf::ExplainFormat {
+    fn from(t: ExplainFormat) -> Self {
+        match t {
+            ExplainFormat::Indent => protobuf::ExplainFormat::Indent,
+            ExplainFormat::Json => protobuf::ExplainFormat::Json,
+            ExplainFormat::Graphviz => protobuf::ExplainFormat::Graphviz,
+        }
+    }
+}
+
+impl TryFrom<&protobuf::StringifiedPlan> for StringifiedPlan {
+    type Error = BallistaError;
+
+    fn try_from(plan: &protobuf::StringifiedPlan) -> Result<Self, Self::Error> {
+        use protobuf::plan_type::PlanTypeEnum;
+        let plan_type = plan
+            .plan_type
+            .as_ref()
+            .and_then(|plan_type| plan_type.plan_type_enum.as_ref())
+            .ok_or_else(|| {
+                proto_error("Received a StringifiedPlan without a plan type")
+            })?;
+        let plan_type = match plan_type {
+            PlanTypeEnum::InitialLogicalPlan(_) => PlanType::InitialLogicalPlan,
+            PlanTypeEnum::OptimizedLogicalPlan(optimized) => {
+                PlanType::OptimizedLogicalPlan {
+                    optimizer_name: optimized.optimizer_name.clone(),
+                }
+            }
+            PlanTypeEnum::FinalLogicalPlan(_) => PlanType::FinalLogicalPlan,
+            PlanTypeEnum::InitialPhysicalPlan(_) => PlanType::InitialPhysicalPlan,
+            PlanTypeEnum::OptimizedPhysicalPlan(optimized) => {
+                PlanType::OptimizedPhysicalPlan {
+                    optimizer_name: optimized.optimizer_name.clone(),
+                }
+            }
+            PlanTypeEnum::FinalPhysicalPlan(_) => PlanType::FinalPhysicalPlan,
+            PlanTypeEnum::DistributedPlan(_) => PlanType::DistributedPlan,
+        };
+        Ok(StringifiedPlan::new(plan_type, plan.plan.clone()))
+    }
+}
+
+impl From<&StringifiedPlan> for protobuf::StringifiedPlan {
+    fn from(plan: &StringifiedPlan) -> Self {
+        use protobuf::plan_type::PlanTypeEnum;
+        let empty = protobuf::EmptyMessage {};
+        let plan_type = match &plan.plan_type {
+            PlanType::InitialLogicalPlan => PlanTypeEnum::InitialLogicalPlan(empty),
+            PlanType::OptimizedLogicalPlan { optimizer_name } => {
+                PlanTypeEnum::OptimizedLogicalPlan(protobuf::OptimizedPlanType {
+                    optimizer_name: optimizer_name.clone(),
+                })
+            }
+            PlanType::FinalLogicalPlan => PlanTypeEnum::FinalLogicalPlan(empty),
+            PlanType::InitialPhysicalPlan => PlanTypeEnum::InitialPhysicalPlan(empty),
+            PlanType::OptimizedPhysicalPlan { optimizer_name } => {
+                PlanTypeEnum::OptimizedPhysicalPlan(protobuf::OptimizedPlanType {
+                    optimizer_name: optimizer_name.clone(),
+                })
+            }
+            PlanType::FinalPhysicalPlan => PlanTypeEnum::FinalPhysicalPlan(empty),
+            PlanType::DistributedPlan => PlanTypeEnum::DistributedPlan(empty),
+        };
+        protobuf::StringifiedPlan {
+            plan_type: Some(protobuf::PlanType {
+                plan_type_enum: Some(plan_type),
+            }),
+            plan: plan.plan.as_ref().clone(),
+        }
+    }
+}
+
 fn byte_to_string(b: u8) -> Result<String, BallistaError> {
     let b = &[b];
     let b = std::str::from_utf8(b)
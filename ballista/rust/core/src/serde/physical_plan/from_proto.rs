@@ -20,31 +20,44 @@
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
+use std::time::Duration;
 
+use super::interning;
 use crate::error::BallistaError;
 use crate::execution_plans::{
-    ShuffleReaderExec, ShuffleWriterExec, UnresolvedShuffleExec,
+    RangeShufflePartitioning, SampleExec, ShuffleReaderExec, ShuffleWriterExec,
+    UnresolvedShuffleExec,
 };
 use crate::serde::protobuf::repartition_exec_node::PartitionMethod;
 use crate::serde::protobuf::ShuffleReaderPartition;
 use crate::serde::scheduler::PartitionLocation;
-use crate::serde::{from_proto_binary_op, proto_error, protobuf, str_to_byte};
+use crate::serde::udaf::get_udaf;
+use crate::serde::udf::get_udf;
+use crate::serde::{
+    from_proto_binary_op, parse_range_boundaries, proto_error, protobuf, str_to_byte,
+};
 use crate::{convert_box_required, convert_required, into_required};
 use chrono::{TimeZone, Utc};
 use datafusion::arrow::datatypes::{DataType, Schema, SchemaRef};
 use datafusion::catalog::catalog::{
     CatalogList, CatalogProvider, MemoryCatalogList, MemoryCatalogProvider,
 };
+use datafusion::datasource::object_store::caching::CachingObjectStore;
 use datafusion::datasource::object_store::local::LocalFileSystem;
-use datafusion::datasource::object_store::{FileMeta, ObjectStoreRegistry, SizedFile};
-use datafusion::datasource::PartitionedFile;
+use datafusion::datasource::object_store::{
+    FileMeta, ObjectStore, ObjectStoreRegistry, SizedFile,
+};
+use datafusion::datasource::{FileRange, PartitionedFile};
 use datafusion::execution::context::{
     ExecutionConfig, ExecutionContextState, ExecutionProps,
 };
 use datafusion::logical_plan::{
-    window_frames::WindowFrame, DFSchema, Expr, JoinConstraint, JoinType,
+    plan::StringifiedPlan, window_frames::WindowFrame, DFSchema, Expr, JoinConstraint,
+    JoinType,
+};
+use datafusion::physical_plan::aggregates::{
+    create_ordered_aggregate_expr, AggregateFunction,
 };
-use datafusion::physical_plan::aggregates::{create_aggregate_expr, AggregateFunction};
 use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
 use datafusion::physical_plan::file_format::{
     AvroExec, CsvExec, ParquetExec, PhysicalPlanConfig,
@@ -61,10 +74,11 @@ use datafusion::physical_plan::{
     coalesce_batches::CoalesceBatchesExec,
     cross_join::CrossJoinExec,
     empty::EmptyExec,
+    explain::ExplainExec,
     expressions::{
-        col, Avg, BinaryExpr, CaseExpr, CastExpr, Column, InListExpr, IsNotNullExpr,
-        IsNullExpr, Literal, NegativeExpr, NotExpr, PhysicalSortExpr, TryCastExpr,
-        DEFAULT_DATAFUSION_CAST_OPTIONS,
+        col, Avg, BinaryExpr, CaseExpr, CastExpr, Column, DateTimeIntervalExpr,
+        FilteredAggregate, InListExpr, IsNotNullExpr, IsNullExpr, Literal, NegativeExpr,
+        NotExpr, PhysicalSortExpr, TryCastExpr, DEFAULT_DATAFUSION_CAST_OPTIONS,
     },
     filter::FilterExec,
     functions::{self, BuiltinScalarFunction, ScalarFunctionExpr},
@@ -73,7 +87,8 @@ use datafusion::physical_plan::{
     projection::ProjectionExec,
     repartition::RepartitionExec,
     sort::{SortExec, SortOptions},
-    Partitioning,
+    sort_preserving_merge::SortPreservingMergeExec,
+    udaf, Partitioning,
 };
 use datafusion::physical_plan::{
     AggregateExpr, ColumnStatistics, ExecutionPlan, PhysicalExpr, Statistics, WindowExpr,
@@ -181,6 +196,18 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                             ),
                         )?))
                     }
+                    Some(PartitionMethod::Range(ref range_part)) => {
+                        Ok(Arc::new(RepartitionExec::try_new(
+                            input,
+                            parse_range_partitioning(range_part)?,
+                        )?))
+                    }
+                    Some(PartitionMethod::Custom(ref custom_part)) => {
+                        Ok(Arc::new(RepartitionExec::try_new(
+                            input,
+                            parse_custom_partitioning(custom_part)?,
+                        )?))
+                    }
                     _ => Err(BallistaError::General(
                         "Invalid partitioning scheme".to_owned(),
                     )),
@@ -197,17 +224,12 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
             PhysicalPlanType::Window(window_agg) => {
                 let input: Arc<dyn ExecutionPlan> =
                     convert_box_required!(window_agg.input)?;
-                let input_schema = window_agg
-                    .input_schema
-                    .as_ref()
-                    .ok_or_else(|| {
-                        BallistaError::General(
-                            "input_schema in WindowAggrNode is missing.".to_owned(),
-                        )
-                    })?
-                    .clone();
-                let physical_schema: SchemaRef =
-                    SchemaRef::new((&input_schema).try_into()?);
+                let input_schema = window_agg.input_schema.as_ref().ok_or_else(|| {
+                    BallistaError::General(
+                        "input_schema in WindowAggrNode is missing.".to_owned(),
+                    )
+                })?;
+                let physical_schema = interning::decode_schema(input_schema)?;
 
                 let physical_window_expr: Vec<Arc<dyn WindowExpr>> = window_agg
                     .window_expr
@@ -219,15 +241,9 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                         })?;
 
                         match expr_type {
-                            ExprType::WindowExpr(window_node) => Ok(create_window_expr(
-                                &convert_required!(window_node.window_function)?,
-                                name.to_owned(),
-                                &[convert_box_required!(window_node.expr)?],
-                                &[],
-                                &[],
-                                Some(WindowFrame::default()),
-                                &physical_schema,
-                            )?),
+                            ExprType::WindowExpr(window_node) => {
+                                parse_window_expr(window_node, name, &physical_schema)
+                            }
                             _ => Err(BallistaError::General(
                                 "Invalid expression for WindowAggrExec".to_string(),
                             )),
@@ -235,11 +251,10 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     })
                     .collect::<Result<Vec<_>, _>>()?;
 
-                Ok(Arc::new(WindowAggExec::try_new(
-                    physical_window_expr,
-                    input,
-                    Arc::new((&input_schema).try_into()?),
-                )?))
+                Ok(Arc::new(
+                    WindowAggExec::try_new(physical_window_expr, input, physical_schema)?
+                        .with_sorted_input(window_agg.sorted_input),
+                ))
             }
             PhysicalPlanType::HashAggregate(hash_agg) => {
                 let input: Arc<dyn ExecutionPlan> =
@@ -266,17 +281,12 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     })
                     .collect::<Result<Vec<_>, _>>()?;
 
-                let input_schema = hash_agg
-                    .input_schema
-                    .as_ref()
-                    .ok_or_else(|| {
-                        BallistaError::General(
-                            "input_schema in HashAggregateNode is missing.".to_owned(),
-                        )
-                    })?
-                    .clone();
-                let physical_schema: SchemaRef =
-                    SchemaRef::new((&input_schema).try_into()?);
+                let input_schema = hash_agg.input_schema.as_ref().ok_or_else(|| {
+                    BallistaError::General(
+                        "input_schema in HashAggregateNode is missing.".to_owned(),
+                    )
+                })?;
+                let physical_schema = interning::decode_schema(input_schema)?;
 
                 let physical_aggr_expr: Vec<Arc<dyn AggregateExpr>> = hash_agg
                     .aggr_expr
@@ -302,13 +312,73 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                                         },
                                     )?;
 
-                                Ok(create_aggregate_expr(
+                                let mut args = vec![convert_box_required!(agg_node.expr)?];
+                                for arg in &agg_node.args {
+                                    args.push(arg.try_into()?);
+                                }
+                                let order_by = agg_node
+                                    .order_by
+                                    .iter()
+                                    .map(|e| {
+                                        Ok(PhysicalSortExpr {
+                                            expr: convert_box_required!(e.expr)?,
+                                            options: SortOptions {
+                                                descending: !e.asc,
+                                                nulls_first: e.nulls_first,
+                                            },
+                                        })
+                                    })
+                                    .collect::<Result<Vec<_>, BallistaError>>()?;
+                                let aggregate = create_ordered_aggregate_expr(
                                     &aggr_function.into(),
-                                    false,
-                                    &[convert_box_required!(agg_node.expr)?],
+                                    agg_node.distinct,
+                                    &args,
+                                    &order_by,
                                     &physical_schema,
                                     name.to_string(),
-                                )?)
+                                    agg_node.fail_on_overflow,
+                                )?;
+                                match &agg_node.filter {
+                                    Some(filter) => Ok(Arc::new(FilteredAggregate::new(
+                                        aggregate,
+                                        filter.as_ref().try_into()?,
+                                    ))
+                                        as Arc<dyn AggregateExpr>),
+                                    None => Ok(aggregate),
+                                }
+                            }
+                            ExprType::AggregateUdfExpr(udaf_node) => {
+                                let fun = get_udaf(&udaf_node.fun_name)?;
+                                let args = udaf_node
+                                    .args
+                                    .iter()
+                                    .map(|e| e.try_into())
+                                    .collect::<Result<Vec<_>, BallistaError>>()?;
+                                let aggregate = udaf::create_aggregate_expr(
+                                    &fun,
+                                    &args,
+                                    &physical_schema,
+                                    name.to_string(),
+                                )?;
+                                // the partial states are only understood by the final
+                                // aggregation if both use the same state types
+                                let state_types = udaf_node
+                                    .state_types
+                                    .iter()
+                                    .map(|arrow_type| arrow_type.try_into())
+                                    .collect::<Result<Vec<DataType>, BallistaError>>()?;
+                                let registered_state_types = aggregate
+                                    .state_fields()?
+                                    .iter()
+                                    .map(|field| field.data_type().clone())
+                                    .collect::<Vec<_>>();
+                                if state_types != registered_state_types {
+                                    return Err(proto_error(format!(
+                                        "Aggregate UDF '{}' was planned with the state types {:?}, but the registered one has the state types {:?}",
+                                        udaf_node.fun_name, state_types, registered_state_types
+                                    )));
+                                }
+                                Ok(aggregate)
                             }
                             _ => Err(BallistaError::General(
                                 "Invalid aggregate  expression for HashAggregateExec"
@@ -323,7 +393,7 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     group,
                     physical_aggr_expr,
                     input,
-                    Arc::new((&input_schema).try_into()?),
+                    physical_schema,
                 )?))
             }
             PhysicalPlanType::HashJoin(hashjoin) => {
@@ -378,20 +448,46 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                 let input: Arc<dyn ExecutionPlan> =
                     convert_box_required!(shuffle_writer.input)?;
 
-                let output_partitioning = parse_protobuf_hash_partitioning(
-                    shuffle_writer.output_partitioning.as_ref(),
-                )?;
+                let output_partitioning = match (
+                    &shuffle_writer.range_output_partitioning,
+                    &shuffle_writer.custom_output_partitioning,
+                ) {
+                    (Some(range_part), _) => Some(parse_range_partitioning(range_part)?),
+                    (None, Some(custom_part)) => {
+                        Some(parse_custom_partitioning(custom_part)?)
+                    }
+                    (None, None) => parse_protobuf_hash_partitioning(
+                        shuffle_writer.output_partitioning.as_ref(),
+                    )?,
+                };
 
-                Ok(Arc::new(ShuffleWriterExec::try_new(
-                    shuffle_writer.job_id.clone(),
-                    shuffle_writer.stage_id as usize,
-                    input,
-                    "".to_string(), // this is intentional but hacky - the executor will fill this in
-                    output_partitioning,
-                )?))
+                match &shuffle_writer.range_partitioning {
+                    Some(range) => {
+                        let samples: Arc<dyn ExecutionPlan> =
+                            convert_box_required!(range.samples)?;
+                        Ok(Arc::new(ShuffleWriterExec::try_new_range_partitioned(
+                            shuffle_writer.job_id.clone(),
+                            shuffle_writer.stage_id as usize,
+                            input,
+                            "".to_string(), // the executor will fill this in
+                            RangeShufflePartitioning {
+                                sort_exprs: parse_physical_sort_exprs(&range.sort_expr)?,
+                                partition_count: range.partition_count as usize,
+                                samples,
+                            },
+                        )?))
+                    }
+                    None => Ok(Arc::new(ShuffleWriterExec::try_new(
+                        shuffle_writer.job_id.clone(),
+                        shuffle_writer.stage_id as usize,
+                        input,
+                        "".to_string(), // this is intentional but hacky - the executor will fill this in
+                        output_partitioning,
+                    )?)),
+                }
             }
             PhysicalPlanType::ShuffleReader(shuffle_reader) => {
-                let schema = Arc::new(convert_required!(shuffle_reader.schema)?);
+                let schema = decode_required_schema(&shuffle_reader.schema)?;
                 let partition_location: Vec<Vec<PartitionLocation>> = shuffle_reader
                     .partition
                     .iter()
@@ -402,56 +498,66 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                             .collect::<Result<Vec<_>, _>>()
                     })
                     .collect::<Result<Vec<_>, BallistaError>>()?;
-                let shuffle_reader =
-                    ShuffleReaderExec::try_new(partition_location, schema)?;
+                let shuffle_reader = match &shuffle_reader.tracked_shuffle {
+                    Some(shuffle) => ShuffleReaderExec::try_new_tracked(
+                        shuffle.job_id.clone(),
+                        shuffle.stage_id as usize,
+                        partition_location.len(),
+                        schema,
+                    )?,
+                    None => ShuffleReaderExec::try_new(partition_location, schema)?,
+                };
                 Ok(Arc::new(shuffle_reader))
             }
             PhysicalPlanType::Empty(empty) => {
-                let schema = Arc::new(convert_required!(empty.schema)?);
+                let schema = decode_required_schema(&empty.schema)?;
                 Ok(Arc::new(EmptyExec::new(empty.produce_one_row, schema)))
             }
-            PhysicalPlanType::Sort(sort) => {
-                let input: Arc<dyn ExecutionPlan> = convert_box_required!(sort.input)?;
-                let exprs = sort
-                    .expr
+            PhysicalPlanType::Explain(explain) => {
+                let schema = decode_required_schema(&explain.schema)?;
+                let stringified_plans = explain
+                    .stringified_plans
                     .iter()
-                    .map(|expr| {
-                        let expr = expr.expr_type.as_ref().ok_or_else(|| {
-                            proto_error(format!(
-                                "physical_plan::from_proto() Unexpected expr {:?}",
-                                self
-                            ))
-                        })?;
-                        if let protobuf::physical_expr_node::ExprType::Sort(sort_expr) = expr {
-                            let expr = sort_expr
-                                .expr
-                                .as_ref()
-                                .ok_or_else(|| {
-                                    proto_error(format!(
-                                        "physical_plan::from_proto() Unexpected sort expr {:?}",
-                                        self
-                                    ))
-                                })?
-                                .as_ref();
-                            Ok(PhysicalSortExpr {
-                                expr: expr.try_into()?,
-                                options: SortOptions {
-                                    descending: !sort_expr.asc,
-                                    nulls_first: sort_expr.nulls_first,
-                                },
-                            })
-                        } else {
-                            Err(BallistaError::General(format!(
-                                "physical_plan::from_proto() {:?}",
-                                self
-                            )))
-                        }
-                    })
+                    .map(StringifiedPlan::try_from)
                     .collect::<Result<Vec<_>, _>>()?;
-                Ok(Arc::new(SortExec::try_new(exprs, input)?))
+                Ok(Arc::new(ExplainExec::new(
+                    schema,
+                    stringified_plans,
+                    explain.verbose,
+                )))
+            }
+            PhysicalPlanType::Sort(sort) => {
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(sort.input)?;
+                let exprs = parse_physical_sort_exprs(&sort.expr)?;
+                Ok(Arc::new(SortExec::new_with_partitioning(
+                    exprs,
+                    input,
+                    sort.preserve_partitioning,
+                )))
+            }
+            PhysicalPlanType::SortPreservingMerge(merge) => {
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(merge.input)?;
+                let exprs = parse_physical_sort_exprs(&merge.expr)?;
+                Ok(Arc::new(SortPreservingMergeExec::new(
+                    exprs,
+                    input,
+                    merge.target_batch_size as usize,
+                )))
+            }
+            PhysicalPlanType::Sample(sample) => {
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(sample.input)?;
+                let exprs = parse_physical_sort_exprs(&sample.expr)?;
+                Ok(Arc::new(SampleExec::try_new(
+                    input,
+                    exprs,
+                    sample.sample_size as usize,
+                )?))
+            }
+            PhysicalPlanType::InternedSchemas(interned) => {
+                interning::decode_interned(interned)
             }
             PhysicalPlanType::Unresolved(unresolved_shuffle) => {
-                let schema = Arc::new(convert_required!(unresolved_shuffle.schema)?);
+                let schema = decode_required_schema(&unresolved_shuffle.schema)?;
                 Ok(Arc::new(UnresolvedShuffleExec {
                     stage_id: unresolved_shuffle.stage_id as usize,
                     schema,
@@ -459,12 +565,64 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                         as usize,
                     output_partition_count: unresolved_shuffle.output_partition_count
                         as usize,
+                    statistics: Statistics::default(),
                 }))
             }
         }
     }
 }
 
+/// Deserializes a required schema, which may refer to the interned schemas of the
+/// plan
+fn decode_required_schema(
+    schema: &Option<protobuf::Schema>,
+) -> Result<SchemaRef, BallistaError> {
+    let schema = schema
+        .as_ref()
+        .ok_or_else(|| proto_error("Missing required field in protobuf"))?;
+    interning::decode_schema(schema)
+}
+
+fn parse_physical_sort_exprs(
+    exprs: &[protobuf::PhysicalExprNode],
+) -> Result<Vec<PhysicalSortExpr>, BallistaError> {
+    exprs
+        .iter()
+        .map(|expr| {
+            let expr_type = expr.expr_type.as_ref().ok_or_else(|| {
+                proto_error(format!(
+                    "physical_plan::from_proto() Unexpected expr {:?}",
+                    expr
+                ))
+            })?;
+            if let protobuf::physical_expr_node::ExprType::Sort(sort_expr) = expr_type {
+                let sort_expr_inner = sort_expr
+                    .expr
+                    .as_ref()
+                    .ok_or_else(|| {
+                        proto_error(format!(
+                            "physical_plan::from_proto() Unexpected sort expr {:?}",
+                            expr
+                        ))
+                    })?
+                    .as_ref();
+                Ok(PhysicalSortExpr {
+                    expr: sort_expr_inner.try_into()?,
+                    options: SortOptions {
+                        descending: !sort_expr.asc,
+                        nulls_first: sort_expr.nulls_first,
+                    },
+                })
+            } else {
+                Err(BallistaError::General(format!(
+                    "physical_plan::from_proto() {:?}",
+                    expr
+                )))
+            }
+        })
+        .collect()
+}
+
 impl From<&protobuf::PhysicalColumn> for Column {
     fn from(c: &protobuf::PhysicalColumn) -> Column {
         Column::new(&c.name, c.index as usize)
@@ -512,6 +670,8 @@ impl From<&protobuf::ScalarFunction> for BuiltinScalarFunction {
             ScalarFunction::Digest => BuiltinScalarFunction::Digest,
             ScalarFunction::Ln => BuiltinScalarFunction::Ln,
             ScalarFunction::Totimestampmillis => BuiltinScalarFunction::ToTimestampMillis,
+            ScalarFunction::Datebin => BuiltinScalarFunction::DateBin,
+            ScalarFunction::Window => BuiltinScalarFunction::Window,
         }
     }
 }
@@ -533,12 +693,20 @@ impl TryFrom<&protobuf::PhysicalExprNode> for Arc<dyn PhysicalExpr> {
             ExprType::Literal(scalar) => {
                 Arc::new(Literal::new(convert_required!(scalar.value)?))
             }
-            ExprType::BinaryExpr(binary_expr) => Arc::new(BinaryExpr::new(
-                convert_box_required!(&binary_expr.l)?,
-                from_proto_binary_op(&binary_expr.op)?,
-                convert_box_required!(&binary_expr.r)?,
+            ExprType::BinaryExpr(binary_expr) => Arc::new(
+                BinaryExpr::new(
+                    convert_box_required!(&binary_expr.l)?,
+                    from_proto_binary_op(&binary_expr.op)?,
+                    convert_box_required!(&binary_expr.r)?,
+                )
+                .with_fail_on_overflow(binary_expr.fail_on_overflow),
+            ),
+            ExprType::DateTimeIntervalExpr(expr) => Arc::new(DateTimeIntervalExpr::new(
+                convert_box_required!(&expr.l)?,
+                from_proto_binary_op(&expr.op)?,
+                convert_box_required!(&expr.r)?,
             )),
-            ExprType::AggregateExpr(_) => {
+            ExprType::AggregateExpr(_) | ExprType::AggregateUdfExpr(_) => {
                 return Err(BallistaError::General(
                     "Cannot convert aggregate expr node to physical expression"
                         .to_owned(),
@@ -623,9 +791,11 @@ impl TryFrom<&protobuf::PhysicalExprNode> for Arc<dyn PhysicalExpr> {
                     scalar_functions: Default::default(),
                     var_provider: Default::default(),
                     aggregate_functions: Default::default(),
+                    table_functions: Default::default(),
                     config: ExecutionConfig::new(),
                     execution_props: ExecutionProps::new(),
                     object_store_registry: Arc::new(ObjectStoreRegistry::new()),
+                    policy_registry: Default::default(),
                 };
 
                 let fun_expr = functions::create_physical_fun(
@@ -640,12 +810,77 @@ impl TryFrom<&protobuf::PhysicalExprNode> for Arc<dyn PhysicalExpr> {
                     &convert_required!(e.return_type)?,
                 ))
             }
+            ExprType::ScalarUdfExpr(e) => {
+                let fun = get_udf(&e.fun_name)?;
+                let args = e
+                    .args
+                    .iter()
+                    .map(|x| x.try_into())
+                    .collect::<Result<Vec<_>, _>>()?;
+                // the arguments were coerced when planning the call
+                Arc::new(ScalarFunctionExpr::new(
+                    &fun.name,
+                    fun.fun.clone(),
+                    args,
+                    &convert_required!(e.return_type)?,
+                ))
+            }
         };
 
         Ok(pexpr)
     }
 }
 
+fn parse_window_expr(
+    window_node: &protobuf::PhysicalWindowExprNode,
+    name: &str,
+    input_schema: &Schema,
+) -> Result<Arc<dyn WindowExpr>, BallistaError> {
+    // functions such as row_number have no arguments
+    let args = window_node
+        .expr
+        .iter()
+        .map(|e| e.as_ref().try_into())
+        .chain(window_node.args.iter().map(|e| e.try_into()))
+        .collect::<Result<Vec<Arc<dyn PhysicalExpr>>, BallistaError>>()?;
+    let partition_by = window_node
+        .partition_by
+        .iter()
+        .map(|e| e.try_into())
+        .collect::<Result<Vec<Arc<dyn PhysicalExpr>>, BallistaError>>()?;
+    let order_by = window_node
+        .order_by
+        .iter()
+        .map(|e| {
+            Ok(PhysicalSortExpr {
+                expr: convert_box_required!(e.expr)?,
+                options: SortOptions {
+                    descending: !e.asc,
+                    nulls_first: e.nulls_first,
+                },
+            })
+        })
+        .collect::<Result<Vec<_>, BallistaError>>()?;
+    let window_frame = window_node
+        .window_frame
+        .as_ref()
+        .map(|window_frame| match window_frame {
+            protobuf::physical_window_expr_node::WindowFrame::Frame(frame) => {
+                WindowFrame::try_from(frame.clone())
+            }
+        })
+        .transpose()?;
+    Ok(create_window_expr(
+        &convert_required!(window_node.window_function)?,
+        name.to_owned(),
+        &args,
+        &partition_by,
+        &order_by,
+        window_frame,
+        input_schema,
+    )?)
+}
+
 impl TryFrom<&protobuf::physical_window_expr_node::WindowFunction> for WindowFunction {
     type Error = BallistaError;
 
@@ -698,6 +933,25 @@ pub fn parse_protobuf_hash_partitioning(
     }
 }
 
+fn parse_range_partitioning(
+    range_part: &protobuf::PhysicalRangeRepartition,
+) -> Result<Partitioning, BallistaError> {
+    Ok(Partitioning::RangePartitioning(
+        parse_physical_sort_exprs(&range_part.sort_expr)?,
+        parse_range_boundaries(&range_part.boundary)?,
+    ))
+}
+
+fn parse_custom_partitioning(
+    custom_part: &protobuf::PhysicalCustomRepartition,
+) -> Result<Partitioning, BallistaError> {
+    let expr: Arc<dyn PhysicalExpr> = convert_required!(custom_part.partition_expr)?;
+    Ok(Partitioning::Custom(
+        expr,
+        custom_part.partition_count.try_into().unwrap(),
+    ))
+}
+
 impl TryFrom<&protobuf::PartitionedFile> for PartitionedFile {
     type Error = BallistaError;
 
@@ -719,6 +973,10 @@ impl TryFrom<&protobuf::PartitionedFile> for PartitionedFile {
                 .iter()
                 .map(|v| v.try_into())
                 .collect::<Result<Vec<_>, _>>()?,
+            range: val.range.as_ref().map(|r| FileRange {
+                start: r.start,
+                end: r.end,
+            }),
         })
     }
 }
@@ -772,7 +1030,7 @@ impl TryInto<PhysicalPlanConfig> for &protobuf::FileScanExecConf {
     type Error = BallistaError;
 
     fn try_into(self) -> Result<PhysicalPlanConfig, Self::Error> {
-        let schema = Arc::new(convert_required!(self.schema)?);
+        let schema = decode_required_schema(&self.schema)?;
         let projection = self
             .projection
             .iter()
@@ -784,9 +1042,17 @@ impl TryInto<PhysicalPlanConfig> for &protobuf::FileScanExecConf {
             Some(projection)
         };
         let statistics = convert_required!(self.statistics)?;
+        let object_store: Arc<dyn ObjectStore> = if self.metadata_cache_ttl_ms > 0 {
+            Arc::new(CachingObjectStore::new(
+                Arc::new(LocalFileSystem {}),
+                Duration::from_millis(self.metadata_cache_ttl_ms),
+            ))
+        } else {
+            Arc::new(LocalFileSystem {})
+        };
 
         Ok(PhysicalPlanConfig {
-            object_store: Arc::new(LocalFileSystem {}),
+            object_store,
             file_schema: schema,
             file_groups: self
                 .file_groups
@@ -795,9 +1061,15 @@ impl TryInto<PhysicalPlanConfig> for &protobuf::FileScanExecConf {
                 .collect::<Result<Vec<_>, _>>()?,
             statistics,
             projection,
+            nested_projection: self
+                .nested_projection
+                .iter()
+                .map(|path| path.fields.clone())
+                .collect(),
             batch_size: self.batch_size as usize,
             limit: self.limit.as_ref().map(|sl| sl.limit as usize),
             table_partition_cols: vec![],
+            bucket_columns: self.bucket_columns.as_ref().map(|b| b.columns.clone()),
         })
     }
 }
@@ -0,0 +1,209 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Interning of the schemas of serialized physical plans.
+//!
+//! The nodes of a plan often have the same schemas, such as the scan of a wide table
+//! and the aggregation above it. Rather than repeating them, a plan is serialized as
+//! an `InternedSchemasNode` listing its distinct schemas once, which the schemas of
+//! its nodes refer to by index. The deserialized nodes share the same `SchemaRef`.
+
+use std::cell::RefCell;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::physical_plan::ExecutionPlan;
+
+use crate::error::{BallistaError, Result};
+use crate::serde::proto_error;
+use crate::serde::protobuf::{self, physical_plan_node::PhysicalPlanType};
+
+thread_local! {
+    /// The schemas interned by the plan being serialized on this thread, if any
+    static ENCODED_SCHEMAS: RefCell<Option<Vec<(SchemaRef, protobuf::Schema)>>> =
+        RefCell::new(None);
+    /// The schemas interned by the plans being deserialized on this thread, the
+    /// innermost plan last
+    static DECODED_SCHEMAS: RefCell<Vec<Vec<SchemaRef>>> = RefCell::new(vec![]);
+}
+
+/// Whether a plan interning its schemas is being serialized on this thread
+pub(crate) fn is_encoding() -> bool {
+    ENCODED_SCHEMAS.with(|schemas| schemas.borrow().is_some())
+}
+
+/// Serializes a plan with `encode`, as an `InternedSchemasNode` listing the schemas
+/// serialized meanwhile by [`encode_schema`]
+pub(crate) fn encode_interned(
+    encode: impl FnOnce() -> Result<protobuf::PhysicalPlanNode>,
+) -> Result<protobuf::PhysicalPlanNode> {
+    /// Stops interning the schemas, even if `encode` panics
+    struct Scope;
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            ENCODED_SCHEMAS.with(|schemas| schemas.borrow_mut().take());
+        }
+    }
+
+    ENCODED_SCHEMAS.with(|schemas| *schemas.borrow_mut() = Some(vec![]));
+    let scope = Scope;
+    let plan = encode()?;
+    let schemas = ENCODED_SCHEMAS
+        .with(|schemas| schemas.borrow_mut().take())
+        .unwrap_or_default();
+    drop(scope);
+    if schemas.is_empty() {
+        return Ok(plan);
+    }
+    Ok(protobuf::PhysicalPlanNode {
+        physical_plan_type: Some(PhysicalPlanType::InternedSchemas(Box::new(
+            protobuf::InternedSchemasNode {
+                plan: Some(Box::new(plan)),
+                schemas: schemas.into_iter().map(|(_, schema)| schema).collect(),
+            },
+        ))),
+    })
+}
+
+/// Serializes `schema`, as a reference to the schemas interned by the plan being
+/// serialized if any
+pub(crate) fn encode_schema(schema: &SchemaRef) -> protobuf::Schema {
+    ENCODED_SCHEMAS.with(|schemas| match schemas.borrow_mut().as_mut() {
+        Some(schemas) => {
+            let index = schemas
+                .iter()
+                .position(|(interned, _)| {
+                    Arc::ptr_eq(interned, schema) || interned == schema
+                })
+                .unwrap_or_else(|| {
+                    schemas.push((schema.clone(), schema.as_ref().into()));
+                    schemas.len() - 1
+                });
+            protobuf::Schema {
+                columns: vec![],
+                interned_index: index as u32 + 1,
+            }
+        }
+        None => schema.as_ref().into(),
+    })
+}
+
+/// Deserializes the plan of an `InternedSchemasNode`, whose nodes refer to its
+/// schemas
+pub(crate) fn decode_interned(
+    node: &protobuf::InternedSchemasNode,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    /// Stops referring to the schemas of the plan, even if deserializing it panics
+    struct Scope;
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            DECODED_SCHEMAS.with(|schemas| schemas.borrow_mut().pop());
+        }
+    }
+
+    let schemas = node
+        .schemas
+        .iter()
+        .map(|schema| Ok(Arc::new(schema.try_into()?)))
+        .collect::<Result<Vec<SchemaRef>>>()?;
+    let plan = node
+        .plan
+        .as_ref()
+        .ok_or_else(|| proto_error("Missing required field in protobuf"))?;
+    DECODED_SCHEMAS.with(|decoded| decoded.borrow_mut().push(schemas));
+    let _scope = Scope;
+    plan.as_ref().try_into()
+}
+
+/// Deserializes `schema`, which may refer to the schemas interned by the plan being
+/// deserialized
+pub(crate) fn decode_schema(schema: &protobuf::Schema) -> Result<SchemaRef> {
+    if schema.interned_index == 0 {
+        return Ok(Arc::new(schema.try_into()?));
+    }
+    let index = schema.interned_index as usize - 1;
+    DECODED_SCHEMAS
+        .with(|schemas| {
+            schemas
+                .borrow()
+                .last()
+                .and_then(|schemas| schemas.get(index).cloned())
+        })
+        .ok_or_else(|| {
+            BallistaError::General(format!(
+                "The interned schema {} is not found in the schemas of the plan",
+                index
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::physical_plan::cross_join::CrossJoinExec;
+    use datafusion::physical_plan::empty::EmptyExec;
+    use datafusion::physical_plan::expressions::col;
+    use datafusion::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
+
+    #[test]
+    fn roundtrip_interned_schemas() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let other = Arc::new(Schema::new(vec![Field::new("c", DataType::Int64, true)]));
+        let aggregate = Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            vec![(col("a", &schema)?, "a".to_owned())],
+            vec![],
+            Arc::new(EmptyExec::new(false, schema.clone())),
+            Arc::new(schema.as_ref().clone()),
+        )?);
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(CrossJoinExec::try_new(
+            aggregate,
+            Arc::new(EmptyExec::new(false, other.clone())),
+        )?);
+
+        let node: protobuf::PhysicalPlanNode = plan.try_into()?;
+        let interned = match &node.physical_plan_type {
+            Some(PhysicalPlanType::InternedSchemas(interned)) => interned,
+            other => panic!("Unexpected plan {:?}", other),
+        };
+        // the equal schemas are serialized once
+        assert_eq!(interned.schemas.len(), 2);
+
+        let decoded: Arc<dyn ExecutionPlan> = (&node).try_into()?;
+        let aggregate = decoded.children()[0].clone();
+        let aggregate = aggregate
+            .as_any()
+            .downcast_ref::<HashAggregateExec>()
+            .unwrap();
+        assert!(Arc::ptr_eq(
+            &aggregate.input_schema(),
+            &aggregate.input().schema()
+        ));
+        assert_eq!(decoded.children()[1].schema(), other);
+
+        // the schemas referring to the interned schemas are only valid in their plan
+        let plan = interned.plan.as_ref().unwrap();
+        let result: Result<Arc<dyn ExecutionPlan>> = plan.as_ref().try_into();
+        assert!(result.is_err());
+        Ok(())
+    }
+}
@@ -16,6 +16,7 @@
 // under the License.
 
 pub mod from_proto;
+mod interning;
 pub mod to_proto;
 
 #[cfg(test)]
@@ -24,19 +25,37 @@ mod roundtrip_tests {
 
     use datafusion::{
         arrow::{
+            array::ArrayRef,
             compute::kernels::sort::SortOptions,
             datatypes::{DataType, Field, Schema},
         },
-        logical_plan::{JoinType, Operator},
+        logical_plan::{
+            create_udaf, create_udf,
+            plan::StringifiedPlan,
+            window_frames::{
+                WindowFrame, WindowFrameBound, WindowFrameExclusion, WindowFrameUnits,
+            },
+            JoinType, LogicalPlan, Operator, PlanType,
+        },
         physical_plan::{
+            aggregates::{
+                create_aggregate_expr, create_ordered_aggregate_expr, AggregateFunction,
+            },
             empty::EmptyExec,
+            explain::ExplainExec,
             expressions::{binary, col, lit, InListExpr, NotExpr},
-            expressions::{Avg, Column, PhysicalSortExpr},
+            expressions::{Avg, AvgAccumulator, Column, PhysicalSortExpr},
             filter::FilterExec,
+            functions::{make_scalar_function, Volatility},
             hash_aggregate::{AggregateMode, HashAggregateExec},
             hash_join::{HashJoinExec, PartitionMode},
             limit::{GlobalLimitExec, LocalLimitExec},
+            repartition::RepartitionExec,
             sort::SortExec,
+            sort_preserving_merge::SortPreservingMergeExec,
+            udaf, udf,
+            window_functions::{BuiltInWindowFunction, WindowFunction},
+            windows::{create_window_expr, WindowAggExec},
             AggregateExpr, ColumnarValue, Distribution, ExecutionPlan, Partitioning,
             PhysicalExpr,
         },
@@ -45,7 +64,12 @@ mod roundtrip_tests {
 
     use super::super::super::error::Result;
     use super::super::protobuf;
-    use crate::execution_plans::ShuffleWriterExec;
+    use super::super::udaf::register_udaf;
+    use super::super::udf::register_udf;
+    use crate::execution_plans::{
+        RangeShufflePartitioning, SampleExec, ShuffleReaderExec, ShuffleWriterExec,
+        DEFAULT_SAMPLE_SIZE,
+    };
 
     fn roundtrip_test(exec_plan: Arc<dyn ExecutionPlan>) -> Result<()> {
         let proto: protobuf::PhysicalPlanNode = exec_plan.clone().try_into()?;
@@ -62,6 +86,25 @@ mod roundtrip_tests {
         roundtrip_test(Arc::new(EmptyExec::new(false, Arc::new(Schema::empty()))))
     }
 
+    #[test]
+    fn roundtrip_explain() -> Result<()> {
+        let plans = vec![
+            StringifiedPlan::new(
+                PlanType::OptimizedLogicalPlan {
+                    optimizer_name: "filter_push_down".to_owned(),
+                },
+                "Projection: #a",
+            ),
+            StringifiedPlan::new(PlanType::FinalPhysicalPlan, "ProjectionExec"),
+            StringifiedPlan::new(PlanType::DistributedPlan, "ShuffleWriterExec"),
+        ];
+        roundtrip_test(Arc::new(ExplainExec::new(
+            LogicalPlan::explain_schema(),
+            plans,
+            true,
+        )))
+    }
+
     #[test]
     fn roundtrip_local_limit() -> Result<()> {
         roundtrip_test(Arc::new(LocalLimitExec::new(
@@ -97,6 +140,7 @@ mod roundtrip_tests {
             JoinType::Full,
             JoinType::Anti,
             JoinType::Semi,
+            JoinType::NullAwareAnti,
         ] {
             for partition_mode in
                 &[PartitionMode::Partitioned, PartitionMode::CollectLeft]
@@ -114,6 +158,67 @@ mod roundtrip_tests {
         Ok(())
     }
 
+    #[test]
+    fn roundtrip_window() -> Result<()> {
+        let field_a = Field::new("a", DataType::Int64, false);
+        let field_b = Field::new("b", DataType::Int64, false);
+        let schema = Arc::new(Schema::new(vec![field_a, field_b]));
+        let partition_by = vec![col("a", &schema)?];
+        let order_by = vec![PhysicalSortExpr {
+            expr: col("b", &schema)?,
+            options: SortOptions::default(),
+        }];
+        let window_frame = WindowFrame {
+            units: WindowFrameUnits::Rows,
+            start_bound: WindowFrameBound::Preceding(Some(1)),
+            end_bound: WindowFrameBound::CurrentRow,
+            exclusion: WindowFrameExclusion::NoOthers,
+        };
+
+        let window_expr = vec![
+            create_window_expr(
+                &WindowFunction::AggregateFunction(AggregateFunction::Sum),
+                "SUM(b)".to_owned(),
+                &[col("b", &schema)?],
+                &partition_by,
+                &order_by,
+                Some(window_frame),
+                &schema,
+            )?,
+            create_window_expr(
+                &WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::Lag),
+                "LAG(b,Int64(2),Int64(0))".to_owned(),
+                &[
+                    col("b", &schema)?,
+                    lit(ScalarValue::Int64(Some(2))),
+                    lit(ScalarValue::Int64(Some(0))),
+                ],
+                &partition_by,
+                &order_by,
+                None,
+                &schema,
+            )?,
+            create_window_expr(
+                &WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber),
+                "ROW_NUMBER()".to_owned(),
+                &[],
+                &partition_by,
+                &order_by,
+                None,
+                &schema,
+            )?,
+        ];
+
+        roundtrip_test(Arc::new(
+            WindowAggExec::try_new(
+                window_expr,
+                Arc::new(EmptyExec::new(false, schema.clone())),
+                schema,
+            )?
+            .with_sorted_input(true),
+        ))
+    }
+
     #[test]
     fn rountrip_hash_aggregate() -> Result<()> {
         let field_a = Field::new("a", DataType::Int64, false);
@@ -138,6 +243,135 @@ mod roundtrip_tests {
         )?))
     }
 
+    #[test]
+    fn roundtrip_distinct_hash_aggregate() -> Result<()> {
+        let field_a = Field::new("a", DataType::Int64, false);
+        let field_b = Field::new("b", DataType::Int64, false);
+        let schema = Arc::new(Schema::new(vec![field_a, field_b]));
+
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> =
+            vec![(col("a", &schema)?, "unused".to_string())];
+
+        let aggregates = vec![
+            create_aggregate_expr(
+                &AggregateFunction::Count,
+                true,
+                &[col("b", &schema)?],
+                &schema,
+                "COUNT(DISTINCT b)",
+            )?,
+            create_aggregate_expr(
+                &AggregateFunction::Sum,
+                true,
+                &[col("b", &schema)?],
+                &schema,
+                "SUM(DISTINCT b)",
+            )?,
+        ];
+
+        roundtrip_test(Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            groups,
+            aggregates,
+            Arc::new(EmptyExec::new(false, schema.clone())),
+            schema,
+        )?))
+    }
+
+    #[test]
+    fn roundtrip_ordered_hash_aggregate() -> Result<()> {
+        let field_a = Field::new("a", DataType::Int64, false);
+        let field_b = Field::new("b", DataType::Utf8, false);
+        let schema = Arc::new(Schema::new(vec![field_a, field_b]));
+        let sort_expr = |name: &str, descending: bool| -> Result<PhysicalSortExpr> {
+            Ok(PhysicalSortExpr {
+                expr: col(name, &schema)?,
+                options: SortOptions {
+                    descending,
+                    nulls_first: false,
+                },
+            })
+        };
+
+        let aggregates = vec![
+            create_ordered_aggregate_expr(
+                &AggregateFunction::StringAgg,
+                false,
+                &[col("b", &schema)?, lit(ScalarValue::from(","))],
+                &[sort_expr("a", true)?],
+                &schema,
+                "STRING_AGG(b, ',' ORDER BY a DESC)",
+                false,
+            )?,
+            create_ordered_aggregate_expr(
+                &AggregateFunction::ArrayAgg,
+                false,
+                &[col("a", &schema)?],
+                &[sort_expr("b", false)?],
+                &schema,
+                "ARRAY_AGG(a ORDER BY b)",
+                false,
+            )?,
+            create_ordered_aggregate_expr(
+                &AggregateFunction::PercentileDisc,
+                false,
+                &[lit(ScalarValue::Float64(Some(0.25)))],
+                &[sort_expr("a", true)?],
+                &schema,
+                "PERCENTILE_DISC(0.25) WITHIN GROUP (ORDER BY a DESC)",
+                false,
+            )?,
+        ];
+
+        roundtrip_test(Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            vec![],
+            aggregates,
+            Arc::new(EmptyExec::new(false, schema.clone())),
+            schema,
+        )?))
+    }
+
+    #[test]
+    fn roundtrip_udaf_hash_aggregate() -> Result<()> {
+        let field_a = Field::new("a", DataType::Float64, false);
+        let schema = Arc::new(Schema::new(vec![field_a]));
+        let my_avg = |state_types: Vec<DataType>| {
+            create_udaf(
+                "roundtrip_avg",
+                DataType::Float64,
+                Arc::new(DataType::Float64),
+                Volatility::Immutable,
+                Arc::new(|| Ok(Box::new(AvgAccumulator::try_new(&DataType::Float64)?))),
+                Arc::new(state_types),
+            )
+        };
+        let avg_state_types = vec![DataType::UInt64, DataType::Float64];
+        register_udaf(my_avg(avg_state_types.clone()));
+
+        let aggregates = vec![udaf::create_aggregate_expr(
+            &my_avg(avg_state_types),
+            &[col("a", &schema)?],
+            &schema,
+            "roundtrip_avg(a)",
+        )?];
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            vec![],
+            aggregates,
+            Arc::new(EmptyExec::new(false, schema.clone())),
+            schema,
+        )?);
+        roundtrip_test(plan.clone())?;
+
+        // the partial states can't be merged by a UDAF with other state types
+        let proto: protobuf::PhysicalPlanNode = plan.try_into()?;
+        register_udaf(my_avg(vec![DataType::Binary]));
+        let result: Result<Arc<dyn ExecutionPlan>> = (&proto).try_into();
+        assert!(result.is_err());
+        Ok(())
+    }
+
     #[test]
     fn roundtrip_filter_with_not_and_in_list() -> Result<()> {
         let field_a = Field::new("a", DataType::Boolean, false);
@@ -160,6 +394,33 @@ mod roundtrip_tests {
         )?))
     }
 
+    #[test]
+    fn roundtrip_filter_with_udf() -> Result<()> {
+        let field_a = Field::new("a", DataType::Int64, false);
+        let schema = Arc::new(Schema::new(vec![field_a]));
+        let my_identity = create_udf(
+            "roundtrip_identity",
+            vec![DataType::Int64],
+            Arc::new(DataType::Int64),
+            Volatility::Immutable,
+            make_scalar_function(|args: &[ArrayRef]| Ok(args[0].clone())),
+        );
+        register_udf(my_identity.clone());
+
+        let call =
+            udf::create_physical_expr(&my_identity, &[col("a", &schema)?], &schema)?;
+        let predicate = binary(
+            call,
+            Operator::Gt,
+            lit(ScalarValue::Int64(Some(0))),
+            &schema,
+        )?;
+        roundtrip_test(Arc::new(FilterExec::try_new(
+            predicate,
+            Arc::new(EmptyExec::new(false, schema.clone())),
+        )?))
+    }
+
     #[test]
     fn roundtrip_sort() -> Result<()> {
         let field_a = Field::new("a", DataType::Boolean, false);
@@ -187,6 +448,29 @@ mod roundtrip_tests {
         )?))
     }
 
+    #[test]
+    fn roundtrip_sort_preserving_merge() -> Result<()> {
+        let field_a = Field::new("a", DataType::Int64, true);
+        let schema = Arc::new(Schema::new(vec![field_a]));
+        let sort_exprs = vec![PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions {
+                descending: false,
+                nulls_first: true,
+            },
+        }];
+        let sort = SortExec::new_with_partitioning(
+            sort_exprs.clone(),
+            Arc::new(EmptyExec::new(false, schema)),
+            true,
+        );
+        roundtrip_test(Arc::new(SortPreservingMergeExec::new(
+            sort_exprs,
+            Arc::new(sort),
+            1024,
+        )))
+    }
+
     #[test]
     fn roundtrip_shuffle_writer() -> Result<()> {
         let field_a = Field::new("a", DataType::Int64, false);
@@ -201,4 +485,97 @@ mod roundtrip_tests {
             Some(Partitioning::Hash(vec![Arc::new(Column::new("a", 0))], 4)),
         )?))
     }
+
+    #[test]
+    fn roundtrip_tracked_shuffle_reader() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        roundtrip_test(Arc::new(ShuffleReaderExec::try_new_tracked(
+            "job123", 2, 4, schema,
+        )?))
+    }
+
+    #[test]
+    fn roundtrip_range_and_custom_repartition() -> Result<()> {
+        let field_a = Field::new("a", DataType::Int64, true);
+        let field_b = Field::new("b", DataType::Utf8, true);
+        let schema = Arc::new(Schema::new(vec![field_a, field_b]));
+        let sort_exprs = vec![
+            PhysicalSortExpr {
+                expr: col("a", &schema)?,
+                options: SortOptions {
+                    descending: false,
+                    nulls_first: true,
+                },
+            },
+            PhysicalSortExpr {
+                expr: col("b", &schema)?,
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: false,
+                },
+            },
+        ];
+        let boundaries = vec![
+            vec![ScalarValue::Int64(Some(10)), ScalarValue::Utf8(None)],
+            vec![
+                ScalarValue::Int64(Some(20)),
+                ScalarValue::Utf8(Some("x".to_owned())),
+            ],
+        ];
+        let input = Arc::new(EmptyExec::new(false, schema.clone()));
+
+        roundtrip_test(Arc::new(RepartitionExec::try_new(
+            input.clone(),
+            Partitioning::RangePartitioning(sort_exprs.clone(), boundaries.clone()),
+        )?))?;
+        roundtrip_test(Arc::new(RepartitionExec::try_new(
+            input.clone(),
+            Partitioning::Custom(col("a", &schema)?, 4),
+        )?))?;
+        roundtrip_test(Arc::new(ShuffleWriterExec::try_new(
+            "job123".to_string(),
+            123,
+            input.clone(),
+            "".to_string(),
+            Some(Partitioning::RangePartitioning(sort_exprs, boundaries)),
+        )?))?;
+        roundtrip_test(Arc::new(ShuffleWriterExec::try_new(
+            "job123".to_string(),
+            123,
+            input,
+            "".to_string(),
+            Some(Partitioning::Custom(col("a", &schema)?, 4)),
+        )?))
+    }
+
+    #[test]
+    fn roundtrip_range_shuffle_writer() -> Result<()> {
+        let field_a = Field::new("a", DataType::Int64, false);
+        let schema = Arc::new(Schema::new(vec![field_a]));
+        let sort_exprs = vec![PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        }];
+        let input = Arc::new(EmptyExec::new(false, schema));
+        let samples = Arc::new(SampleExec::try_new(
+            input.clone(),
+            sort_exprs.clone(),
+            DEFAULT_SAMPLE_SIZE,
+        )?);
+
+        roundtrip_test(Arc::new(ShuffleWriterExec::try_new_range_partitioned(
+            "job123".to_string(),
+            123,
+            input,
+            "".to_string(),
+            RangeShufflePartitioning {
+                sort_exprs,
+                partition_count: 4,
+                samples,
+            },
+        )?))
+    }
 }
@@ -30,6 +30,7 @@ use datafusion::physical_plan::hash_join::{HashJoinExec, PartitionMode};
 use datafusion::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
 use datafusion::physical_plan::projection::ProjectionExec;
 use datafusion::physical_plan::sort::SortExec;
+use datafusion::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
 use datafusion::physical_plan::{cross_join::CrossJoinExec, ColumnStatistics};
 use datafusion::physical_plan::{
     expressions::{
@@ -38,7 +39,7 @@ use datafusion::physical_plan::{
     Statistics,
 };
 use datafusion::physical_plan::{
-    expressions::{CastExpr, TryCastExpr},
+    expressions::{CastExpr, DateTimeIntervalExpr, PhysicalSortExpr, TryCastExpr},
     file_format::ParquetExec,
 };
 use datafusion::physical_plan::{file_format::AvroExec, filter::FilterExec};
@@ -54,31 +55,47 @@ use datafusion::{
     scalar::ScalarValue,
 };
 
+use datafusion::physical_plan::windows::{
+    AggregateWindowExpr, BuiltInWindowExpr, WindowAggExec,
+};
 use datafusion::physical_plan::{
     empty::EmptyExec,
-    expressions::{Avg, BinaryExpr, Column, Max, Min, Sum},
+    explain::ExplainExec,
+    expressions::{
+        lit, ArrayAgg, Avg, BinaryExpr, Column, FilteredAggregate, Max, Min,
+        OrderedAggregate, Percentile, StringAgg, Sum,
+    },
     Partitioning,
 };
-use datafusion::physical_plan::{AggregateExpr, ExecutionPlan, PhysicalExpr};
+use datafusion::physical_plan::{AggregateExpr, ExecutionPlan, PhysicalExpr, WindowExpr};
 
+use datafusion::physical_plan::distinct_expressions::{DistinctCount, DistinctSum};
 use datafusion::physical_plan::hash_aggregate::HashAggregateExec;
+use datafusion::physical_plan::udaf::AggregateFunctionExpr;
 use protobuf::physical_plan_node::PhysicalPlanType;
 
 use crate::serde::protobuf::repartition_exec_node::PartitionMethod;
 use crate::serde::scheduler::PartitionLocation;
-use crate::serde::{protobuf, BallistaError};
+use crate::serde::{protobuf, range_boundaries_to_proto, BallistaError};
 use crate::{
-    execution_plans::{ShuffleReaderExec, ShuffleWriterExec, UnresolvedShuffleExec},
+    execution_plans::{
+        SampleExec, ShuffleReaderExec, ShuffleWriterExec, UnresolvedShuffleExec,
+    },
     serde::byte_to_string,
 };
 use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
 use datafusion::physical_plan::functions::{BuiltinScalarFunction, ScalarFunctionExpr};
 use datafusion::physical_plan::repartition::RepartitionExec;
 
+use super::interning;
+
 impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
     type Error = BallistaError;
 
     fn try_into(self) -> Result<protobuf::PhysicalPlanNode, Self::Error> {
+        if !interning::is_encoding() {
+            return interning::encode_interned(|| self.try_into());
+        }
         let plan = self.as_any();
 
         if let Some(exec) = plan.downcast_ref::<ProjectionExec>() {
@@ -220,12 +237,37 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                         aggr_expr_name: agg_names,
                         mode: agg_mode as i32,
                         input: Some(Box::new(input)),
-                        input_schema: Some(input_schema.as_ref().into()),
+                        input_schema: Some(interning::encode_schema(&input_schema)),
+                    },
+                ))),
+            })
+        } else if let Some(exec) = plan.downcast_ref::<WindowAggExec>() {
+            let input: protobuf::PhysicalPlanNode = exec.input().to_owned().try_into()?;
+            let window_expr = exec
+                .window_expr()
+                .iter()
+                .map(|expr| expr.to_owned().try_into())
+                .collect::<Result<Vec<_>, BallistaError>>()?;
+            let window_expr_name = exec
+                .window_expr()
+                .iter()
+                .map(|expr| expr.name().to_owned())
+                .collect();
+            Ok(protobuf::PhysicalPlanNode {
+                physical_plan_type: Some(PhysicalPlanType::Window(Box::new(
+                    protobuf::WindowAggExecNode {
+                        input: Some(Box::new(input)),
+                        window_expr,
+                        window_expr_name,
+                        input_schema: Some(interning::encode_schema(
+                            &exec.input_schema(),
+                        )),
+                        sorted_input: exec.sorted_input(),
                     },
                 ))),
             })
         } else if let Some(empty) = plan.downcast_ref::<EmptyExec>() {
-            let schema = empty.schema().as_ref().into();
+            let schema = interning::encode_schema(&empty.schema());
             Ok(protobuf::PhysicalPlanNode {
                 physical_plan_type: Some(PhysicalPlanType::Empty(
                     protobuf::EmptyExecNode {
@@ -234,6 +276,20 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                     },
                 )),
             })
+        } else if let Some(explain) = plan.downcast_ref::<ExplainExec>() {
+            Ok(protobuf::PhysicalPlanNode {
+                physical_plan_type: Some(PhysicalPlanType::Explain(
+                    protobuf::ExplainExecNode {
+                        schema: Some(interning::encode_schema(&explain.schema())),
+                        stringified_plans: explain
+                            .stringified_plans()
+                            .iter()
+                            .map(|plan| plan.into())
+                            .collect(),
+                        verbose: explain.verbose(),
+                    },
+                )),
+            })
         } else if let Some(coalesce_batches) = plan.downcast_ref::<CoalesceBatchesExec>()
         {
             let input: protobuf::PhysicalPlanNode =
@@ -287,7 +343,13 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                 physical_plan_type: Some(PhysicalPlanType::ShuffleReader(
                     protobuf::ShuffleReaderExecNode {
                         partition,
-                        schema: Some(exec.schema().as_ref().into()),
+                        schema: Some(interning::encode_schema(&exec.schema())),
+                        tracked_shuffle: exec.tracked_shuffle().map(|shuffle| {
+                            protobuf::TrackedShuffle {
+                                job_id: shuffle.job_id.clone(),
+                                stage_id: shuffle.stage_id as u32,
+                            }
+                        }),
                     },
                 )),
             })
@@ -319,6 +381,14 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                 Partitioning::UnknownPartitioning(partition_count) => {
                     PartitionMethod::Unknown(*partition_count as u64)
                 }
+                Partitioning::RangePartitioning(sort_exprs, boundaries) => {
+                    PartitionMethod::Range(serialize_range_partitioning(
+                        sort_exprs, boundaries,
+                    )?)
+                }
+                Partitioning::Custom(expr, partition_count) => PartitionMethod::Custom(
+                    serialize_custom_partitioning(expr, *partition_count)?,
+                ),
             };
 
             Ok(protobuf::PhysicalPlanNode {
@@ -331,27 +401,34 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
             })
         } else if let Some(exec) = plan.downcast_ref::<SortExec>() {
             let input: protobuf::PhysicalPlanNode = exec.input().to_owned().try_into()?;
-            let expr = exec
-                .expr()
-                .iter()
-                .map(|expr| {
-                    let sort_expr = Box::new(protobuf::PhysicalSortExprNode {
-                        expr: Some(Box::new(expr.expr.to_owned().try_into()?)),
-                        asc: !expr.options.descending,
-                        nulls_first: expr.options.nulls_first,
-                    });
-                    Ok(protobuf::PhysicalExprNode {
-                        expr_type: Some(protobuf::physical_expr_node::ExprType::Sort(
-                            sort_expr,
-                        )),
-                    })
-                })
-                .collect::<Result<Vec<_>, Self::Error>>()?;
             Ok(protobuf::PhysicalPlanNode {
                 physical_plan_type: Some(PhysicalPlanType::Sort(Box::new(
                     protobuf::SortExecNode {
                         input: Some(Box::new(input)),
-                        expr,
+                        expr: serialize_physical_sort_exprs(exec.expr())?,
+                        preserve_partitioning: exec.preserve_partitioning(),
+                    },
+                ))),
+            })
+        } else if let Some(exec) = plan.downcast_ref::<SortPreservingMergeExec>() {
+            let input: protobuf::PhysicalPlanNode = exec.input().to_owned().try_into()?;
+            Ok(protobuf::PhysicalPlanNode {
+                physical_plan_type: Some(PhysicalPlanType::SortPreservingMerge(
+                    Box::new(protobuf::SortPreservingMergeExecNode {
+                        input: Some(Box::new(input)),
+                        expr: serialize_physical_sort_exprs(exec.expr())?,
+                        target_batch_size: exec.target_batch_size() as u32,
+                    }),
+                )),
+            })
+        } else if let Some(exec) = plan.downcast_ref::<SampleExec>() {
+            let input: protobuf::PhysicalPlanNode = exec.input().to_owned().try_into()?;
+            Ok(protobuf::PhysicalPlanNode {
+                physical_plan_type: Some(PhysicalPlanType::Sample(Box::new(
+                    protobuf::SampleExecNode {
+                        input: Some(Box::new(input)),
+                        expr: serialize_physical_sort_exprs(exec.sort_exprs())?,
+                        sample_size: exec.sample_size() as u64,
                     },
                 ))),
             })
@@ -360,6 +437,8 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                 exec.children()[0].to_owned().try_into()?;
             // note that we use shuffle_output_partitioning() rather than output_partitioning()
             // to get the true output partitioning
+            let mut range_output_partitioning = None;
+            let mut custom_output_partitioning = None;
             let output_partitioning = match exec.shuffle_output_partitioning() {
                 Some(Partitioning::Hash(exprs, partition_count)) => {
                     Some(protobuf::PhysicalHashRepartition {
@@ -370,6 +449,16 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                         partition_count: *partition_count as u64,
                     })
                 }
+                Some(Partitioning::RangePartitioning(sort_exprs, boundaries)) => {
+                    range_output_partitioning =
+                        Some(serialize_range_partitioning(sort_exprs, boundaries)?);
+                    None
+                }
+                Some(Partitioning::Custom(expr, partition_count)) => {
+                    custom_output_partitioning =
+                        Some(serialize_custom_partitioning(expr, *partition_count)?);
+                    None
+                }
                 None => None,
                 other => {
                     return Err(BallistaError::General(format!(
@@ -378,6 +467,14 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                     )))
                 }
             };
+            let range_partitioning = match exec.range_partitioning() {
+                Some(range) => Some(Box::new(protobuf::RangeShufflePartitioning {
+                    sort_expr: serialize_physical_sort_exprs(&range.sort_exprs)?,
+                    partition_count: range.partition_count as u64,
+                    samples: Some(Box::new(range.samples.clone().try_into()?)),
+                })),
+                None => None,
+            };
             Ok(protobuf::PhysicalPlanNode {
                 physical_plan_type: Some(PhysicalPlanType::ShuffleWriter(Box::new(
                     protobuf::ShuffleWriterExecNode {
@@ -385,6 +482,9 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                         stage_id: exec.stage_id() as u32,
                         input: Some(Box::new(input)),
                         output_partitioning,
+                        range_partitioning,
+                        range_output_partitioning,
+                        custom_output_partitioning,
                     },
                 ))),
             })
@@ -393,7 +493,7 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                 physical_plan_type: Some(PhysicalPlanType::Unresolved(
                     protobuf::UnresolvedShuffleExecNode {
                         stage_id: exec.stage_id as u32,
-                        schema: Some(exec.schema().as_ref().into()),
+                        schema: Some(interning::encode_schema(&exec.schema())),
                         input_partition_count: exec.input_partition_count as u32,
                         output_partition_count: exec.output_partition_count as u32,
                     },
@@ -408,36 +508,272 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
     }
 }
 
+fn serialize_physical_sort_exprs(
+    exprs: &[PhysicalSortExpr],
+) -> Result<Vec<protobuf::PhysicalExprNode>, BallistaError> {
+    exprs
+        .iter()
+        .map(|expr| {
+            let sort_expr = Box::new(protobuf::PhysicalSortExprNode {
+                expr: Some(Box::new(expr.expr.to_owned().try_into()?)),
+                asc: !expr.options.descending,
+                nulls_first: expr.options.nulls_first,
+            });
+            Ok(protobuf::PhysicalExprNode {
+                expr_type: Some(protobuf::physical_expr_node::ExprType::Sort(sort_expr)),
+            })
+        })
+        .collect()
+}
+
+fn serialize_range_partitioning(
+    sort_exprs: &[PhysicalSortExpr],
+    boundaries: &[Vec<ScalarValue>],
+) -> Result<protobuf::PhysicalRangeRepartition, BallistaError> {
+    Ok(protobuf::PhysicalRangeRepartition {
+        sort_expr: serialize_physical_sort_exprs(sort_exprs)?,
+        boundary: range_boundaries_to_proto(boundaries)?,
+    })
+}
+
+fn serialize_custom_partitioning(
+    expr: &Arc<dyn PhysicalExpr>,
+    partition_count: usize,
+) -> Result<protobuf::PhysicalCustomRepartition, BallistaError> {
+    Ok(protobuf::PhysicalCustomRepartition {
+        partition_expr: Some(expr.clone().try_into()?),
+        partition_count: partition_count as u64,
+    })
+}
+
+fn aggregate_function(
+    expr: &Arc<dyn AggregateExpr>,
+) -> Result<protobuf::AggregateFunction, BallistaError> {
+    if expr.as_any().downcast_ref::<Avg>().is_some() {
+        Ok(protobuf::AggregateFunction::Avg)
+    } else if expr.as_any().downcast_ref::<Sum>().is_some() {
+        Ok(protobuf::AggregateFunction::Sum)
+    } else if expr.as_any().downcast_ref::<Count>().is_some() {
+        Ok(protobuf::AggregateFunction::Count)
+    } else if expr.as_any().downcast_ref::<Min>().is_some() {
+        Ok(protobuf::AggregateFunction::Min)
+    } else if expr.as_any().downcast_ref::<Max>().is_some() {
+        Ok(protobuf::AggregateFunction::Max)
+    } else if expr.as_any().downcast_ref::<ArrayAgg>().is_some() {
+        Ok(protobuf::AggregateFunction::ArrayAgg)
+    } else if expr.as_any().downcast_ref::<StringAgg>().is_some() {
+        Ok(protobuf::AggregateFunction::StringAgg)
+    } else {
+        Err(BallistaError::NotImplemented(format!(
+            "Aggregate function not supported: {:?}",
+            expr
+        )))
+    }
+}
+
 impl TryInto<protobuf::PhysicalExprNode> for Arc<dyn AggregateExpr> {
     type Error = BallistaError;
 
     fn try_into(self) -> Result<protobuf::PhysicalExprNode, Self::Error> {
-        let aggr_function = if self.as_any().downcast_ref::<Avg>().is_some() {
-            Ok(protobuf::AggregateFunction::Avg.into())
-        } else if self.as_any().downcast_ref::<Sum>().is_some() {
-            Ok(protobuf::AggregateFunction::Sum.into())
-        } else if self.as_any().downcast_ref::<Count>().is_some() {
-            Ok(protobuf::AggregateFunction::Count.into())
-        } else if self.as_any().downcast_ref::<Min>().is_some() {
-            Ok(protobuf::AggregateFunction::Min.into())
-        } else if self.as_any().downcast_ref::<Max>().is_some() {
-            Ok(protobuf::AggregateFunction::Max.into())
-        } else {
-            Err(BallistaError::NotImplemented(format!(
-                "Aggregate function not supported: {:?}",
-                self
-            )))
-        }?;
+        if let Some(filtered) = self.as_any().downcast_ref::<FilteredAggregate>() {
+            let mut node: protobuf::PhysicalExprNode =
+                filtered.aggregate().clone().try_into()?;
+            if let Some(protobuf::physical_expr_node::ExprType::AggregateExpr(
+                aggregate,
+            )) = &mut node.expr_type
+            {
+                aggregate.filter =
+                    Some(Box::new(filtered.predicate().clone().try_into()?));
+            }
+            return Ok(node);
+        }
+        if let Some(ordered) = self.as_any().downcast_ref::<OrderedAggregate>() {
+            let mut node: protobuf::PhysicalExprNode =
+                ordered.aggregate().clone().try_into()?;
+            if let Some(protobuf::physical_expr_node::ExprType::AggregateExpr(
+                aggregate,
+            )) = &mut node.expr_type
+            {
+                aggregate.order_by = ordered
+                    .order_by()
+                    .iter()
+                    .map(|e| {
+                        Ok(protobuf::PhysicalSortExprNode {
+                            expr: Some(Box::new(e.expr.clone().try_into()?)),
+                            asc: !e.options.descending,
+                            nulls_first: e.options.nulls_first,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, BallistaError>>()?;
+            }
+            return Ok(node);
+        }
+        if let Some(percentile) = self.as_any().downcast_ref::<Percentile>() {
+            // the percentile is the argument, and the aggregated expression the
+            // WITHIN GROUP (ORDER BY ...) one
+            let aggr_function = match percentile.is_discrete() {
+                true => protobuf::AggregateFunction::PercentileDisc,
+                false => protobuf::AggregateFunction::PercentileCont,
+            };
+            let percentile_expr =
+                lit(ScalarValue::Float64(Some(percentile.percentile())));
+            return Ok(protobuf::PhysicalExprNode {
+                expr_type: Some(protobuf::physical_expr_node::ExprType::AggregateExpr(
+                    Box::new(protobuf::PhysicalAggregateExprNode {
+                        aggr_function: aggr_function.into(),
+                        expr: Some(Box::new(percentile_expr.try_into()?)),
+                        distinct: false,
+                        filter: None,
+                        args: vec![],
+                        order_by: vec![protobuf::PhysicalSortExprNode {
+                            expr: Some(Box::new(
+                                percentile.expressions()[0].clone().try_into()?,
+                            )),
+                            asc: !percentile.is_descending(),
+                            nulls_first: false,
+                        }],
+                        fail_on_overflow: false,
+                    }),
+                )),
+            });
+        }
+        if let Some(udaf) = self.as_any().downcast_ref::<AggregateFunctionExpr>() {
+            return Ok(protobuf::PhysicalExprNode {
+                expr_type: Some(
+                    protobuf::physical_expr_node::ExprType::AggregateUdfExpr(
+                        protobuf::PhysicalAggregateUdfExprNode {
+                            fun_name: udaf.fun().name.clone(),
+                            args: udaf
+                                .expressions()
+                                .iter()
+                                .map(|e| e.clone().try_into())
+                                .collect::<Result<Vec<_>, BallistaError>>()?,
+                            state_types: udaf
+                                .state_fields()?
+                                .iter()
+                                .map(|field| field.data_type().into())
+                                .collect(),
+                        },
+                    ),
+                ),
+            });
+        }
+        let (aggr_function, distinct) =
+            if self.as_any().downcast_ref::<DistinctCount>().is_some() {
+                (protobuf::AggregateFunction::Count, true)
+            } else if self.as_any().downcast_ref::<DistinctSum>().is_some() {
+                (protobuf::AggregateFunction::Sum, true)
+            } else {
+                (aggregate_function(&self)?, false)
+            };
+        let fail_on_overflow = match self.as_any().downcast_ref::<Sum>() {
+            Some(sum) => sum.fail_on_overflow(),
+            None => self
+                .as_any()
+                .downcast_ref::<DistinctSum>()
+                .map_or(false, |sum| sum.fail_on_overflow()),
+        };
         let expressions: Vec<protobuf::PhysicalExprNode> = self
             .expressions()
             .iter()
             .map(|e| e.clone().try_into())
             .collect::<Result<Vec<_>, BallistaError>>()?;
+        if expressions.len() != 1 {
+            return Err(BallistaError::NotImplemented(format!(
+                "Aggregate function with {} arguments not supported: {:?}",
+                expressions.len(),
+                self
+            )));
+        }
+        let args = match self.as_any().downcast_ref::<StringAgg>() {
+            Some(string_agg) => {
+                vec![
+                    lit(ScalarValue::Utf8(Some(string_agg.delimiter().to_owned())))
+                        .try_into()?,
+                ]
+            }
+            None => vec![],
+        };
         Ok(protobuf::PhysicalExprNode {
             expr_type: Some(protobuf::physical_expr_node::ExprType::AggregateExpr(
                 Box::new(protobuf::PhysicalAggregateExprNode {
-                    aggr_function,
+                    aggr_function: aggr_function.into(),
                     expr: Some(Box::new(expressions[0].clone())),
+                    distinct,
+                    filter: None,
+                    args,
+                    order_by: vec![],
+                    fail_on_overflow,
+                }),
+            )),
+        })
+    }
+}
+
+impl TryInto<protobuf::PhysicalExprNode> for Arc<dyn WindowExpr> {
+    type Error = BallistaError;
+
+    fn try_into(self) -> Result<protobuf::PhysicalExprNode, Self::Error> {
+        use protobuf::physical_window_expr_node::{WindowFrame, WindowFunction};
+
+        let (window_function, args, window_frame) =
+            if let Some(expr) = self.as_any().downcast_ref::<AggregateWindowExpr>() {
+                (
+                    WindowFunction::AggrFunction(
+                        aggregate_function(expr.aggregate())?.into(),
+                    ),
+                    expr.aggregate().expressions(),
+                    expr.window_frame(),
+                )
+            } else if let Some(expr) = self.as_any().downcast_ref::<BuiltInWindowExpr>() {
+                (
+                    WindowFunction::BuiltInFunction(
+                        protobuf::BuiltInWindowFunction::from(expr.fun()).into(),
+                    ),
+                    expr.args().to_vec(),
+                    expr.window_frame(),
+                )
+            } else {
+                return Err(BallistaError::NotImplemented(format!(
+                    "Window function not supported: {:?}",
+                    self
+                )));
+            };
+        let mut args = args
+            .into_iter()
+            .map(|e| e.try_into())
+            .collect::<Result<Vec<protobuf::PhysicalExprNode>, BallistaError>>()?;
+        let expr = if args.is_empty() {
+            None
+        } else {
+            Some(Box::new(args.remove(0)))
+        };
+        let partition_by = self
+            .partition_by()
+            .iter()
+            .map(|e| e.clone().try_into())
+            .collect::<Result<Vec<_>, BallistaError>>()?;
+        let order_by = self
+            .order_by()
+            .iter()
+            .map(|e| {
+                Ok(protobuf::PhysicalSortExprNode {
+                    expr: Some(Box::new(e.expr.clone().try_into()?)),
+                    asc: !e.options.descending,
+                    nulls_first: e.options.nulls_first,
+                })
+            })
+            .collect::<Result<Vec<_>, BallistaError>>()?;
+        Ok(protobuf::PhysicalExprNode {
+            expr_type: Some(protobuf::physical_expr_node::ExprType::WindowExpr(
+                Box::new(protobuf::PhysicalWindowExprNode {
+                    window_function: Some(window_function),
+                    expr,
+                    args,
+                    partition_by,
+                    order_by,
+                    window_frame: window_frame
+                        .map(|window_frame| WindowFrame::Frame(window_frame.into())),
                 }),
             )),
         })
@@ -464,6 +800,7 @@ impl TryFrom<Arc<dyn PhysicalExpr>> for protobuf::PhysicalExprNode {
                 l: Some(Box::new(expr.left().to_owned().try_into()?)),
                 r: Some(Box::new(expr.right().to_owned().try_into()?)),
                 op: format!("{:?}", expr.op()),
+                fail_on_overflow: expr.fail_on_overflow(),
             });
 
             Ok(protobuf::PhysicalExprNode {
@@ -471,6 +808,21 @@ impl TryFrom<Arc<dyn PhysicalExpr>> for protobuf::PhysicalExprNode {
                     binary_expr,
                 )),
             })
+        } else if let Some(expr) = expr.downcast_ref::<DateTimeIntervalExpr>() {
+            let date_time_interval_expr =
+                Box::new(protobuf::PhysicalDateTimeIntervalExprNode {
+                    l: Some(Box::new(expr.lhs().to_owned().try_into()?)),
+                    r: Some(Box::new(expr.rhs().to_owned().try_into()?)),
+                    op: format!("{:?}", expr.op()),
+                });
+
+            Ok(protobuf::PhysicalExprNode {
+                expr_type: Some(
+                    protobuf::physical_expr_node::ExprType::DateTimeIntervalExpr(
+                        date_time_interval_expr,
+                    ),
+                ),
+            })
         } else if let Some(expr) = expr.downcast_ref::<CaseExpr>() {
             Ok(protobuf::PhysicalExprNode {
                 expr_type: Some(
@@ -579,8 +931,28 @@ impl TryFrom<Arc<dyn PhysicalExpr>> for protobuf::PhysicalExprNode {
                 )),
             })
         } else if let Some(expr) = expr.downcast_ref::<ScalarFunctionExpr>() {
-            let fun: BuiltinScalarFunction =
-                BuiltinScalarFunction::from_str(expr.name())?;
+            // the scalar functions that are not built-in are UDFs, which are
+            // referred to by name
+            let fun = match BuiltinScalarFunction::from_str(expr.name()) {
+                Ok(fun) => fun,
+                Err(_) => {
+                    return Ok(protobuf::PhysicalExprNode {
+                        expr_type: Some(
+                            protobuf::physical_expr_node::ExprType::ScalarUdfExpr(
+                                protobuf::PhysicalScalarUdfExprNode {
+                                    fun_name: expr.name().to_string(),
+                                    args: expr
+                                        .args()
+                                        .iter()
+                                        .map(|e| e.to_owned().try_into())
+                                        .collect::<Result<Vec<_>, _>>()?,
+                                    return_type: Some(expr.return_type().into()),
+                                },
+                            ),
+                        ),
+                    })
+                }
+            };
             let fun: protobuf::ScalarFunction = (&fun).try_into()?;
             let args: Vec<protobuf::PhysicalExprNode> = expr
                 .args()
@@ -633,6 +1005,10 @@ impl TryFrom<&PartitionedFile> for protobuf::PartitionedFile {
                 .iter()
                 .map(|v| v.try_into())
                 .collect::<Result<Vec<_>, _>>()?,
+            range: pf.range.map(|r| protobuf::FileRange {
+                start: r.start,
+                end: r.end,
+            }),
         })
     }
 }
@@ -699,9 +1075,26 @@ impl TryFrom<&PhysicalPlanConfig> for protobuf::FileScanExecConf {
                 .iter()
                 .map(|n| *n as u32)
                 .collect(),
-            schema: Some(conf.file_schema.as_ref().into()),
+            nested_projection: conf
+                .nested_projection
+                .iter()
+                .map(|fields| protobuf::NestedFieldPath {
+                    fields: fields.clone(),
+                })
+                .collect(),
+            schema: Some(interning::encode_schema(&conf.file_schema)),
             batch_size: conf.batch_size as u32,
             table_partition_cols: conf.table_partition_cols.to_vec(),
+            bucket_columns: conf.bucket_columns.as_ref().map(|columns| {
+                protobuf::BucketColumns {
+                    columns: columns.clone(),
+                }
+            }),
+            metadata_cache_ttl_ms: conf
+                .object_store
+                .metadata_cache_ttl()
+                .map(|ttl| ttl.as_millis() as u64)
+                .unwrap_or(0),
         })
     }
 }
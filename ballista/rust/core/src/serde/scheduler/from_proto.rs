@@ -15,16 +15,27 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::{collections::HashMap, convert::TryInto};
+use std::{collections::HashMap, convert::TryInto, sync::Arc};
 
+use crate::convert_required;
+use crate::dataset::DatasetTable;
 use crate::error::BallistaError;
+use crate::result_encoding::ResultEncoding;
+use crate::serde::proto_error;
 use crate::serde::protobuf;
 use crate::serde::protobuf::action::ActionType;
+use crate::serde::protobuf::operator_metric;
 use crate::serde::scheduler::{
     Action, ExecutePartition, PartitionId, PartitionLocation, PartitionStats,
 };
 
+use chrono::{TimeZone, Utc};
+use datafusion::arrow::datatypes::Schema;
 use datafusion::logical_plan::LogicalPlan;
+use datafusion::physical_plan::metrics::{
+    Count, Label, MetricValue, MetricsSet, Time, Timestamp,
+};
+use datafusion::physical_plan::Metric;
 use uuid::Uuid;
 
 impl TryInto<Action> for protobuf::Action {
@@ -33,10 +44,19 @@ impl TryInto<Action> for protobuf::Action {
     fn try_into(self) -> Result<Action, Self::Error> {
         match self.action_type {
             Some(ActionType::FetchPartition(fetch)) => Ok(Action::FetchPartition {
+                encoding: protobuf::ResultEncoding::from_i32(fetch.encoding)
+                    .ok_or_else(|| {
+                        BallistaError::General(format!(
+                            "Unknown result encoding {}",
+                            fetch.encoding
+                        ))
+                    })?
+                    .into(),
                 job_id: fetch.job_id,
                 stage_id: fetch.stage_id as usize,
                 partition_id: fetch.partition_id as usize,
                 path: fetch.path,
+                checksum: fetch.checksum.map(|checksum| checksum.crc32),
             }),
             _ => Err(BallistaError::General(
                 "scheduler::from_proto(Action) invalid or missing action".to_owned(),
@@ -45,6 +65,16 @@ impl TryInto<Action> for protobuf::Action {
     }
 }
 
+impl From<protobuf::ResultEncoding> for ResultEncoding {
+    fn from(encoding: protobuf::ResultEncoding) -> Self {
+        match encoding {
+            protobuf::ResultEncoding::ArrowIpc => ResultEncoding::ArrowIpc,
+            protobuf::ResultEncoding::Json => ResultEncoding::Json,
+            protobuf::ResultEncoding::Csv => ResultEncoding::Csv,
+        }
+    }
+}
+
 impl TryInto<PartitionId> for protobuf::PartitionId {
     type Error = BallistaError;
 
@@ -106,6 +136,96 @@ impl TryInto<PartitionLocation> for protobuf::PartitionLocation {
                 })?
                 .into(),
             path: self.path,
+            checksum: self.checksum.map(|checksum| checksum.crc32),
         })
     }
 }
+
+impl TryInto<DatasetTable> for protobuf::Dataset {
+    type Error = BallistaError;
+
+    fn try_into(self) -> Result<DatasetTable, Self::Error> {
+        let schema: Schema = convert_required!(self.schema)?;
+        let partition = self
+            .partition
+            .into_iter()
+            .map(|p| {
+                p.location
+                    .into_iter()
+                    .map(|l| l.try_into())
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, BallistaError>>()?;
+        Ok(DatasetTable::new(
+            self.dataset_id,
+            Arc::new(schema),
+            partition,
+        ))
+    }
+}
+
+impl TryInto<MetricsSet> for protobuf::OperatorMetricsSet {
+    type Error = BallistaError;
+
+    fn try_into(self) -> Result<MetricsSet, Self::Error> {
+        let mut metrics = MetricsSet::new();
+        for metric in self.metrics {
+            let value = match metric.metric {
+                Some(operator_metric::Metric::OutputRows(value)) => {
+                    MetricValue::OutputRows(count(value))
+                }
+                Some(operator_metric::Metric::ElapsedCompute(value)) => {
+                    MetricValue::ElapsedCompute(time(value))
+                }
+                Some(operator_metric::Metric::Count(protobuf::NamedCount {
+                    name,
+                    value,
+                })) => MetricValue::Count {
+                    name: name.into(),
+                    count: count(value),
+                },
+                Some(operator_metric::Metric::Time(protobuf::NamedTime {
+                    name,
+                    value,
+                })) => MetricValue::Time {
+                    name: name.into(),
+                    time: time(value),
+                },
+                Some(operator_metric::Metric::StartTimestamp(nanos)) => {
+                    MetricValue::StartTimestamp(timestamp(nanos))
+                }
+                Some(operator_metric::Metric::EndTimestamp(nanos)) => {
+                    MetricValue::EndTimestamp(timestamp(nanos))
+                }
+                None => {
+                    return Err(proto_error("Received an empty operator metric"));
+                }
+            };
+            let labels = metric
+                .labels
+                .into_iter()
+                .map(|label| Label::new(label.key, label.value))
+                .collect();
+            metrics.push(Arc::new(Metric::new_with_labels(value, None, labels)));
+        }
+        Ok(metrics)
+    }
+}
+
+fn count(value: u64) -> Count {
+    let count = Count::new();
+    count.add(value as usize);
+    count
+}
+
+fn time(nanos: u64) -> Time {
+    let time = Time::new();
+    time.add_duration(std::time::Duration::from_nanos(nanos));
+    time
+}
+
+fn timestamp(nanos: i64) -> Timestamp {
+    let timestamp = Timestamp::new();
+    timestamp.set(Utc.timestamp_nanos(nanos));
+    timestamp
+}
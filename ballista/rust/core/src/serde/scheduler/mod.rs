@@ -15,7 +15,11 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+};
 
 use datafusion::arrow::array::{
     ArrayBuilder, ArrayRef, StructArray, StructBuilder, UInt64Array, UInt64Builder,
@@ -29,6 +33,7 @@ use uuid::Uuid;
 
 use super::protobuf;
 use crate::error::BallistaError;
+use crate::result_encoding::ResultEncoding;
 
 pub mod from_proto;
 pub mod to_proto;
@@ -42,6 +47,10 @@ pub enum Action {
         stage_id: usize,
         partition_id: usize,
         path: String,
+        /// CRC32 checksum the partition file is verified against, if any
+        checksum: Option<u32>,
+        /// How the record batches of the partition are sent
+        encoding: ResultEncoding,
     },
 }
 
@@ -69,14 +78,28 @@ pub struct PartitionLocation {
     pub executor_meta: ExecutorMeta,
     pub partition_stats: PartitionStats,
     pub path: String,
+    /// CRC32 checksum of the partition file, if it was computed when writing it
+    pub checksum: Option<u32>,
 }
 
+/// Label of an executor holding the topology zone of the node it runs on
+pub const ZONE_LABEL: &str = "topology.kubernetes.io/zone";
+
 /// Meta-data for an executor, used when fetching shuffle partitions from other executors
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct ExecutorMeta {
     pub id: String,
     pub host: String,
     pub port: u16,
+    /// Labels reported by the executor, such as the labels of its Kubernetes pod
+    pub labels: BTreeMap<String, String>,
+}
+
+impl ExecutorMeta {
+    /// The topology zone of the node the executor runs on, if it reported one
+    pub fn zone(&self) -> Option<&str> {
+        self.labels.get(ZONE_LABEL).map(|zone| zone.as_str())
+    }
 }
 
 #[allow(clippy::from_over_into)]
@@ -86,6 +109,11 @@ impl Into<protobuf::ExecutorMetadata> for ExecutorMeta {
             id: self.id,
             host: self.host,
             port: self.port as u32,
+            labels: self
+                .labels
+                .into_iter()
+                .map(|(key, value)| protobuf::KeyValuePair { key, value })
+                .collect(),
         }
     }
 }
@@ -96,6 +124,11 @@ impl From<protobuf::ExecutorMetadata> for ExecutorMeta {
             id: meta.id,
             host: meta.host,
             port: meta.port as u16,
+            labels: meta
+                .labels
+                .into_iter()
+                .map(|label| (label.key, label.value))
+                .collect(),
         }
     }
 }
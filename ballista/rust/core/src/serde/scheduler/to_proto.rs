@@ -17,12 +17,17 @@
 
 use std::convert::TryInto;
 
+use crate::dataset::DatasetTable;
 use crate::error::BallistaError;
+use crate::result_encoding::ResultEncoding;
 use crate::serde::protobuf;
 use crate::serde::protobuf::action::ActionType;
+use crate::serde::protobuf::operator_metric;
 use crate::serde::scheduler::{
     Action, ExecutePartition, PartitionId, PartitionLocation, PartitionStats,
 };
+use datafusion::datasource::TableProvider;
+use datafusion::physical_plan::metrics::{MetricValue, MetricsSet};
 use datafusion::physical_plan::Partitioning;
 
 impl TryInto<protobuf::Action> for Action {
@@ -35,12 +40,16 @@ impl TryInto<protobuf::Action> for Action {
                 stage_id,
                 partition_id,
                 path,
+                checksum,
+                encoding,
             } => Ok(protobuf::Action {
                 action_type: Some(ActionType::FetchPartition(protobuf::FetchPartition {
                     job_id,
                     stage_id: stage_id as u32,
                     partition_id: partition_id as u32,
                     path,
+                    checksum: checksum.map(|crc32| protobuf::PartitionChecksum { crc32 }),
+                    encoding: protobuf::ResultEncoding::from(encoding).into(),
                 })),
                 settings: vec![],
             }),
@@ -48,6 +57,16 @@ impl TryInto<protobuf::Action> for Action {
     }
 }
 
+impl From<ResultEncoding> for protobuf::ResultEncoding {
+    fn from(encoding: ResultEncoding) -> Self {
+        match encoding {
+            ResultEncoding::ArrowIpc => protobuf::ResultEncoding::ArrowIpc,
+            ResultEncoding::Json => protobuf::ResultEncoding::Json,
+            ResultEncoding::Csv => protobuf::ResultEncoding::Csv,
+        }
+    }
+}
+
 impl TryInto<protobuf::ExecutePartition> for ExecutePartition {
     type Error = BallistaError;
 
@@ -85,6 +104,33 @@ impl TryInto<protobuf::PartitionLocation> for PartitionLocation {
             executor_meta: Some(self.executor_meta.into()),
             partition_stats: Some(self.partition_stats.into()),
             path: self.path,
+            checksum: self
+                .checksum
+                .map(|crc32| protobuf::PartitionChecksum { crc32 }),
+        })
+    }
+}
+
+impl TryInto<protobuf::Dataset> for &DatasetTable {
+    type Error = BallistaError;
+
+    fn try_into(self) -> Result<protobuf::Dataset, Self::Error> {
+        let partition = self
+            .partition()
+            .iter()
+            .map(|locations| {
+                Ok(protobuf::ShuffleReaderPartition {
+                    location: locations
+                        .iter()
+                        .map(|l| l.clone().try_into())
+                        .collect::<Result<Vec<_>, BallistaError>>()?,
+                })
+            })
+            .collect::<Result<Vec<_>, BallistaError>>()?;
+        Ok(protobuf::Dataset {
+            dataset_id: self.dataset_id().to_owned(),
+            schema: Some(self.schema().as_ref().into()),
+            partition,
         })
     }
 }
@@ -102,6 +148,59 @@ impl Into<protobuf::PartitionStats> for PartitionStats {
     }
 }
 
+impl From<&MetricsSet> for protobuf::OperatorMetricsSet {
+    fn from(metrics: &MetricsSet) -> Self {
+        let metrics = metrics
+            .iter()
+            .filter_map(|metric| {
+                let value = match metric.value() {
+                    MetricValue::OutputRows(count) => {
+                        operator_metric::Metric::OutputRows(count.value() as u64)
+                    }
+                    MetricValue::ElapsedCompute(time) => {
+                        operator_metric::Metric::ElapsedCompute(time.value() as u64)
+                    }
+                    MetricValue::Count { name, count } => {
+                        operator_metric::Metric::Count(protobuf::NamedCount {
+                            name: name.to_string(),
+                            value: count.value() as u64,
+                        })
+                    }
+                    MetricValue::Time { name, time } => {
+                        operator_metric::Metric::Time(protobuf::NamedTime {
+                            name: name.to_string(),
+                            value: time.value() as u64,
+                        })
+                    }
+                    // the timestamps that were never recorded are not reported
+                    MetricValue::StartTimestamp(timestamp) => {
+                        operator_metric::Metric::StartTimestamp(
+                            timestamp.value()?.timestamp_nanos(),
+                        )
+                    }
+                    MetricValue::EndTimestamp(timestamp) => {
+                        operator_metric::Metric::EndTimestamp(
+                            timestamp.value()?.timestamp_nanos(),
+                        )
+                    }
+                };
+                Some(protobuf::OperatorMetric {
+                    metric: Some(value),
+                    labels: metric
+                        .labels()
+                        .iter()
+                        .map(|label| protobuf::KeyValuePair {
+                            key: label.name().to_owned(),
+                            value: label.value().to_owned(),
+                        })
+                        .collect(),
+                })
+            })
+            .collect();
+        protobuf::OperatorMetricsSet { metrics }
+    }
+}
+
 pub fn hash_partitioning_to_proto(
     output_partitioning: Option<&Partitioning>,
 ) -> Result<Option<protobuf::PhysicalHashRepartition>, BallistaError> {
@@ -115,6 +214,9 @@ pub fn hash_partitioning_to_proto(
                 partition_count: *partition_count as u64,
             }))
         }
+        // range and custom partitioning are only carried by the shuffle writer plan
+        Some(Partitioning::RangePartitioning(_, _))
+        | Some(Partitioning::Custom(_, _)) => Ok(None),
         None => Ok(None),
         other => {
             return Err(BallistaError::General(format!(
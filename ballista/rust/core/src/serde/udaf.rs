@@ -0,0 +1,62 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Registry of the user defined aggregate functions (UDAFs) that can be serialized
+//! in Ballista plans.
+//!
+//! Plans only refer to UDAFs by name, so the same UDAFs must be registered in the
+//! processes of the client, the scheduler and the executors. The partial states of
+//! a UDAF are shuffled between the partial and final aggregation stages as Arrow
+//! values of the types returned by its `state_type` function, which is checked to
+//! be the same in the process that planned the query and the one executing it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use datafusion::physical_plan::udaf::AggregateUDF;
+use lazy_static::lazy_static;
+
+use crate::error::{BallistaError, Result};
+
+lazy_static! {
+    static ref AGGREGATE_UDFS: RwLock<HashMap<String, Arc<AggregateUDF>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Registers a UDAF so that plans calling it can be deserialized, replacing any
+/// UDAF previously registered with the same name
+pub fn register_udaf(udaf: AggregateUDF) {
+    AGGREGATE_UDFS
+        .write()
+        .unwrap()
+        .insert(udaf.name.clone(), Arc::new(udaf));
+}
+
+/// Returns the UDAF registered with `name`
+pub fn get_udaf(name: &str) -> Result<Arc<AggregateUDF>> {
+    AGGREGATE_UDFS
+        .read()
+        .unwrap()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| {
+            BallistaError::General(format!(
+                "Aggregate UDF '{}' is not registered in this process",
+                name
+            ))
+        })
+}
@@ -0,0 +1,59 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Registry of the scalar user defined functions (UDFs) that can be serialized in
+//! Ballista plans.
+//!
+//! Plans only refer to UDFs by name, so the same UDFs must be registered in the
+//! processes of the client, the scheduler and the executors.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use datafusion::physical_plan::udf::ScalarUDF;
+use lazy_static::lazy_static;
+
+use crate::error::{BallistaError, Result};
+
+lazy_static! {
+    static ref SCALAR_UDFS: RwLock<HashMap<String, Arc<ScalarUDF>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Registers a UDF so that plans calling it can be deserialized, replacing any UDF
+/// previously registered with the same name
+pub fn register_udf(udf: ScalarUDF) {
+    SCALAR_UDFS
+        .write()
+        .unwrap()
+        .insert(udf.name.clone(), Arc::new(udf));
+}
+
+/// Returns the UDF registered with `name`
+pub fn get_udf(name: &str) -> Result<Arc<ScalarUDF>> {
+    SCALAR_UDFS
+        .read()
+        .unwrap()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| {
+            BallistaError::General(format!(
+                "Scalar UDF '{}' is not registered in this process",
+                name
+            ))
+        })
+}
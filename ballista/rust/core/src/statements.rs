@@ -0,0 +1,265 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! SQL statements managing the jobs of a Ballista cluster, `SHOW JOBS` and
+//! `KILL JOB '<job id>'`, supported by the contexts created with
+//! [`create_df_ctx_with_ballista_query_planner`](crate::utils::create_df_ctx_with_ballista_query_planner)
+
+use std::any::Any;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::DataType;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::{ExecutionContext, StatementExtension};
+use datafusion::logical_plan::{
+    col, lit, DFField, DFSchema, EmptyRelation, Expr, LogicalPlan, LogicalPlanBuilder,
+};
+use datafusion::scalar::ScalarValue;
+use datafusion::sql::parser::{CustomStatement, StatementParser};
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::Token;
+
+use crate::serde::protobuf::{
+    job_status, scheduler_grpc_client::SchedulerGrpcClient, CancelJobParams, FailedJob,
+    JobSummary, ListJobsParams,
+};
+
+/// A statement managing the jobs of the cluster
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatement {
+    /// `SHOW JOBS`, listing the jobs of the scheduler with their status
+    ShowJobs,
+    /// `KILL JOB '<job id>'`, cancelling a queued or running job
+    KillJob(String),
+}
+
+impl CustomStatement for JobStatement {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Parses the [`JobStatement`]s and executes them with the scheduler
+pub struct JobStatementExtension {
+    scheduler_url: String,
+}
+
+impl JobStatementExtension {
+    pub fn new(scheduler_url: String) -> Self {
+        Self { scheduler_url }
+    }
+
+    async fn connect(&self) -> Result<SchedulerGrpcClient<tonic::transport::Channel>> {
+        SchedulerGrpcClient::connect(self.scheduler_url.clone())
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))
+    }
+
+    async fn show_jobs(&self) -> Result<LogicalPlan> {
+        let mut jobs = self
+            .connect()
+            .await?
+            .list_jobs(ListJobsParams {})
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?
+            .into_inner()
+            .jobs;
+        jobs.sort_by(|left, right| left.job_id.cmp(&right.job_id));
+
+        let columns = ["job_id", "status", "error", "labels"];
+        if jobs.is_empty() {
+            let fields = columns
+                .iter()
+                .map(|name| DFField::new(None, name, DataType::Utf8, true))
+                .collect();
+            return Ok(LogicalPlan::EmptyRelation(EmptyRelation {
+                produce_one_row: false,
+                schema: Arc::new(DFSchema::new(fields)?),
+            }));
+        }
+        let rows = jobs.into_iter().map(job_row).collect();
+        LogicalPlanBuilder::values(rows)?
+            .project(
+                columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| col(&format!("column{}", i + 1)).alias(name)),
+            )?
+            .build()
+    }
+
+    async fn kill_job(&self, job_id: &str) -> Result<LogicalPlan> {
+        let cancelled = self
+            .connect()
+            .await?
+            .cancel_job(CancelJobParams {
+                job_id: job_id.to_owned(),
+            })
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?
+            .into_inner()
+            .cancelled;
+        if !cancelled {
+            return Err(DataFusionError::Execution(format!(
+                "Job {} already completed or failed",
+                job_id
+            )));
+        }
+        LogicalPlanBuilder::empty(false).build()
+    }
+}
+
+/// The row of a job listed by `SHOW JOBS`
+fn job_row(job: JobSummary) -> Vec<Expr> {
+    let (status, error) = match job.status.and_then(|status| status.status) {
+        Some(job_status::Status::Queued(_)) => ("queued", None),
+        Some(job_status::Status::Running(_)) => ("running", None),
+        Some(job_status::Status::Failed(FailedJob { error })) => ("failed", Some(error)),
+        Some(job_status::Status::Completed(_)) => ("completed", None),
+        None => ("unknown", None),
+    };
+    let labels = job
+        .labels
+        .map(|labels| {
+            labels
+                .labels
+                .iter()
+                .map(|label| format!("{}={}", label.key, label.value))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .unwrap_or_default();
+    vec![
+        lit(job.job_id),
+        lit(status),
+        lit(ScalarValue::Utf8(error)),
+        lit(labels),
+    ]
+}
+
+fn is_word(token: &Token, word: &str) -> bool {
+    matches!(token, Token::Word(w) if w.value.eq_ignore_ascii_case(word))
+}
+
+impl StatementParser for JobStatementExtension {
+    fn parse_statement(
+        &self,
+        parser: &mut Parser<'_>,
+    ) -> std::result::Result<Option<Arc<dyn CustomStatement>>, ParserError> {
+        if is_word(&parser.peek_token(), "SHOW") {
+            parser.next_token();
+            if is_word(&parser.peek_token(), "JOBS") {
+                parser.next_token();
+                return Ok(Some(Arc::new(JobStatement::ShowJobs)));
+            }
+            // e.g. `SHOW TABLES`
+            parser.prev_token();
+        } else if is_word(&parser.peek_token(), "KILL") {
+            parser.next_token();
+            if is_word(&parser.peek_token(), "JOB") {
+                parser.next_token();
+                let job_id = parser.parse_literal_string()?;
+                return Ok(Some(Arc::new(JobStatement::KillJob(job_id))));
+            }
+            parser.prev_token();
+        }
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl StatementExtension for JobStatementExtension {
+    async fn plan_statement(
+        &self,
+        statement: &dyn CustomStatement,
+        _ctx: &mut ExecutionContext,
+    ) -> Result<Option<LogicalPlan>> {
+        match statement.as_any().downcast_ref::<JobStatement>() {
+            Some(JobStatement::ShowJobs) => Ok(Some(self.show_jobs().await?)),
+            Some(JobStatement::KillJob(job_id)) => Ok(Some(self.kill_job(job_id).await?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde::protobuf::{JobLabels, JobStatus, KeyValuePair, QueuedJob};
+    use datafusion::sql::parser::{DFParser, Statement};
+    use sqlparser::dialect::GenericDialect;
+
+    fn parse(sql: &str) -> std::result::Result<Statement, ParserError> {
+        let extension = JobStatementExtension::new("http://localhost:50050".to_owned());
+        let mut statements = DFParser::parse_sql_with_statement_parser(
+            sql,
+            &GenericDialect {},
+            &extension,
+        )?;
+        Ok(statements.remove(0))
+    }
+
+    fn job_statement(statement: Statement) -> Option<JobStatement> {
+        match statement {
+            Statement::Custom(statement) => {
+                statement.as_any().downcast_ref::<JobStatement>().cloned()
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parse_job_statements() -> std::result::Result<(), ParserError> {
+        assert_eq!(
+            job_statement(parse("SHOW JOBS")?),
+            Some(JobStatement::ShowJobs)
+        );
+        assert_eq!(
+            job_statement(parse("kill job 'abc'")?),
+            Some(JobStatement::KillJob("abc".to_owned()))
+        );
+        assert_eq!(job_statement(parse("SHOW TABLES")?), None);
+        assert!(parse("KILL JOB abc").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn show_jobs_row() {
+        let job = JobSummary {
+            job_id: "abc".to_owned(),
+            status: Some(JobStatus {
+                status: Some(job_status::Status::Queued(QueuedJob {})),
+            }),
+            labels: Some(JobLabels {
+                labels: vec![KeyValuePair {
+                    key: "team".to_owned(),
+                    value: "data".to_owned(),
+                }],
+            }),
+        };
+        assert_eq!(
+            job_row(job),
+            vec![
+                lit("abc"),
+                lit("queued"),
+                lit(ScalarValue::Utf8(None)),
+                lit("team=data")
+            ]
+        );
+    }
+}
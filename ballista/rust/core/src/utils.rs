@@ -16,18 +16,21 @@
 // under the License.
 
 use std::collections::HashMap;
-use std::io::{BufWriter, Write};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::ops::Deref;
+use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::{fs::File, pin::Pin};
 
+use crate::encryption::{self, EncryptingWriter, ShuffleEncryption};
 use crate::error::{BallistaError, Result};
 use crate::execution_plans::{
     DistributedQueryExec, ShuffleWriterExec, UnresolvedShuffleExec,
 };
 use crate::memory_stream::MemoryStream;
 use crate::serde::scheduler::PartitionStats;
+use crate::statements::JobStatementExtension;
 
 use crate::config::BallistaConfig;
 use async_trait::async_trait;
@@ -38,10 +41,11 @@ use datafusion::arrow::{
         ArrayBuilder, ArrayRef, StructArray, StructBuilder, UInt64Array, UInt64Builder,
     },
     datatypes::{DataType, Field, SchemaRef},
-    ipc::reader::FileReader,
-    ipc::writer::FileWriter,
+    ipc::reader::{FileReader, StreamReader},
+    ipc::writer::{FileWriter, StreamWriter},
     record_batch::RecordBatch,
 };
+use datafusion::datasource::TableProvider;
 use datafusion::error::DataFusionError;
 use datafusion::execution::context::{
     ExecutionConfig, ExecutionContext, ExecutionContextState, QueryPlanner,
@@ -58,32 +62,237 @@ use datafusion::physical_plan::file_format::{CsvExec, ParquetExec};
 use datafusion::physical_plan::filter::FilterExec;
 use datafusion::physical_plan::hash_aggregate::HashAggregateExec;
 use datafusion::physical_plan::hash_join::HashJoinExec;
+use datafusion::physical_plan::metrics::MetricsSet;
 use datafusion::physical_plan::projection::ProjectionExec;
 use datafusion::physical_plan::sort::SortExec;
 use datafusion::physical_plan::{
-    metrics, AggregateExpr, ExecutionPlan, Metric, PhysicalExpr, RecordBatchStream,
+    metrics, AggregateExpr, DisplayFormatType, ExecutionPlan, Metric, PhysicalExpr,
+    RecordBatchStream,
 };
 use futures::{future, Stream, StreamExt};
 use std::time::Instant;
 
-/// Stream data to disk in Arrow IPC format
+/// Arrow IPC format the shuffle partitions are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShuffleFormat {
+    /// The IPC file format, in which the dictionaries of the dictionary encoded
+    /// columns cannot change from one batch to the next
+    File,
+    /// The IPC stream format, in which every batch may replace the dictionaries,
+    /// so that dictionary encoded columns stay encoded across shuffles
+    Stream,
+}
 
-pub async fn write_stream_to_disk(
-    stream: &mut Pin<Box<dyn RecordBatchStream + Send + Sync>>,
+impl Default for ShuffleFormat {
+    fn default() -> Self {
+        Self::File
+    }
+}
+
+impl FromStr for ShuffleFormat {
+    type Err = BallistaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "file" => Ok(Self::File),
+            "stream" => Ok(Self::Stream),
+            _ => Err(BallistaError::General(format!(
+                "Invalid shuffle format '{}', expected 'file' or 'stream'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Writes record batches to a shuffle partition file in either IPC format,
+/// optionally encrypted
+pub struct IpcWriter {
+    writer: IpcFormatWriter,
+    /// The encrypting writer the IPC writer writes to, if the file is encrypted,
+    /// which is finished once the IPC writer is
+    encrypting_writer: Option<SharedEncryptingWriter>,
+}
+
+enum IpcFormatWriter {
+    File(FileWriter<Box<dyn Write + Send>>),
+    Stream(StreamWriter<Box<dyn Write + Send>>),
+}
+
+impl IpcWriter {
+    /// Create the file at `path` and write the schema to it, encrypting the file
+    /// with `encryption` if any
+    pub fn try_new(
+        path: &str,
+        schema: &Schema,
+        format: ShuffleFormat,
+        encryption: Option<&ShuffleEncryption>,
+    ) -> Result<Self> {
+        let file = File::create(&path).map_err(|e| {
+            BallistaError::General(format!(
+                "Failed to create partition file at {}: {:?}",
+                path, e
+            ))
+        })?;
+        let (file, encrypting_writer): (Box<dyn Write + Send>, _) = match encryption {
+            Some(encryption) => {
+                let writer = SharedEncryptingWriter(Arc::new(Mutex::new(
+                    encryption.encrypt(file)?,
+                )));
+                (Box::new(writer.clone()), Some(writer))
+            }
+            None => (Box::new(file), None),
+        };
+        let writer = match format {
+            ShuffleFormat::File => {
+                IpcFormatWriter::File(FileWriter::try_new(file, schema)?)
+            }
+            ShuffleFormat::Stream => {
+                IpcFormatWriter::Stream(StreamWriter::try_new(file, schema)?)
+            }
+        };
+        Ok(Self {
+            writer,
+            encrypting_writer,
+        })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> ArrowResult<()> {
+        match &mut self.writer {
+            IpcFormatWriter::File(writer) => writer.write(batch),
+            IpcFormatWriter::Stream(writer) => writer.write(batch),
+        }
+    }
+
+    pub fn finish(&mut self) -> ArrowResult<()> {
+        match &mut self.writer {
+            IpcFormatWriter::File(writer) => writer.finish()?,
+            IpcFormatWriter::Stream(writer) => writer.finish()?,
+        }
+        if let Some(SharedEncryptingWriter(writer)) = &self.encrypting_writer {
+            writer.lock().unwrap().finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// Encrypting writer shared by an [IpcWriter] and its IPC writer, as the IPC
+/// writers do not give back the writer they write to once they are finished
+#[derive(Clone)]
+struct SharedEncryptingWriter(Arc<Mutex<EncryptingWriter<File>>>);
+
+impl Write for SharedEncryptingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+/// Record batches read from a shuffle partition file
+pub type ShufflePartitionReader =
+    Box<dyn Iterator<Item = ArrowResult<RecordBatch>> + Send>;
+
+/// Opens a shuffle partition file written in either IPC format, which is told
+/// apart by the magic number the IPC file format starts with. The encrypted files
+/// are decrypted with `encryption`.
+pub fn read_shuffle_partition(
     path: &str,
-    disk_write_metric: &metrics::Time,
-) -> Result<PartitionStats> {
-    let file = File::create(&path).map_err(|e| {
+    encryption: Option<&ShuffleEncryption>,
+) -> Result<(SchemaRef, ShufflePartitionReader)> {
+    let mut file = File::open(&path).map_err(|e| {
         BallistaError::General(format!(
-            "Failed to create partition file at {}: {:?}",
+            "Failed to open partition file at {}: {:?}",
             path, e
         ))
     })?;
+    if !encryption::is_encrypted(&mut file)? {
+        return read_ipc(file);
+    }
+    match encryption {
+        Some(encryption) => read_ipc(encryption.decrypt(file)?),
+        None => Err(BallistaError::General(format!(
+            "Partition file at {} is encrypted, but no encryption is configured",
+            path
+        ))),
+    }
+}
 
+fn read_ipc<R: Read + Seek + Send + 'static>(
+    mut reader: R,
+) -> Result<(SchemaRef, ShufflePartitionReader)> {
+    let mut magic = [0; 6];
+    let is_file_format =
+        reader.read_exact(&mut magic).is_ok() && magic == IPC_FILE_MAGIC[..];
+    reader.seek(SeekFrom::Start(0))?;
+    if is_file_format {
+        let reader = FileReader::try_new(reader)?;
+        Ok((reader.schema(), Box::new(reader)))
+    } else {
+        let reader = StreamReader::try_new(BufReader::new(reader))?;
+        Ok((reader.schema(), Box::new(reader)))
+    }
+}
+
+/// Magic number at the start of the files in the IPC file format
+const IPC_FILE_MAGIC: &[u8; 6] = b"ARROW1";
+
+/// Compute the CRC32 checksum of the shuffle partition file at `path`
+pub fn partition_checksum(path: &str) -> Result<u32> {
+    let mut file = BufReader::new(File::open(&path)?);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let len = file.read(&mut buf)?;
+        if len == 0 {
+            return Ok(hasher.finalize());
+        }
+        hasher.update(&buf[..len]);
+    }
+}
+
+/// Verify that the shuffle partition file at `path` has the `expected` checksum,
+/// returning a [BallistaError::CorruptedPartition] error otherwise.
+///
+/// The executor serving the partition verifies the whole file before sending it, so
+/// that the corruption of the file on its disk is detected. The IPC messages the
+/// batches are then sent in carry checksums of their own, computed with
+/// [ipc_message_checksum] and verified by the client fetching them.
+pub fn verify_partition_checksum(path: &str, expected: u32) -> Result<()> {
+    let actual = partition_checksum(path)?;
+    if actual != expected {
+        return Err(BallistaError::CorruptedPartition {
+            path: path.to_owned(),
+            reason: format!("checksum is {:08x} instead of {:08x}", actual, expected),
+        });
+    }
+    Ok(())
+}
+
+/// Compute the CRC32 checksum of an IPC message sent to fetch a shuffle partition,
+/// over its flatbuffer header and its body
+pub fn ipc_message_checksum(header: &[u8], body: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(header);
+    hasher.update(body);
+    hasher.finalize()
+}
+
+/// Stream data to disk in Arrow IPC format
+
+pub async fn write_stream_to_disk(
+    stream: &mut Pin<Box<dyn RecordBatchStream + Send + Sync>>,
+    path: &str,
+    format: ShuffleFormat,
+    encryption: Option<&ShuffleEncryption>,
+    disk_write_metric: &metrics::Time,
+) -> Result<PartitionStats> {
     let mut num_rows = 0;
     let mut num_batches = 0;
     let mut num_bytes = 0;
-    let mut writer = FileWriter::try_new(file, stream.schema().as_ref())?;
+    let mut writer =
+        IpcWriter::try_new(path, stream.schema().as_ref(), format, encryption)?;
 
     while let Some(result) = stream.next().await {
         let batch = result?;
@@ -238,8 +447,63 @@ fn build_exec_plan_diagram(
     Ok(node_id)
 }
 
+/// Collects the metrics of the operators of `plan`, in pre-order, summed over the
+/// partitions they executed
+pub fn collect_plan_metrics(plan: &dyn ExecutionPlan) -> Vec<MetricsSet> {
+    let mut metrics = vec![plan
+        .metrics()
+        .map(|metrics| metrics.aggregate_by_partition())
+        .unwrap_or_default()];
+    for child in plan.children() {
+        metrics.extend(collect_plan_metrics(child.as_ref()));
+    }
+    metrics
+}
+
+/// Formats `plan` like `EXPLAIN ANALYZE` does, one operator per line followed by its
+/// metrics, with `metrics` holding the metrics of the operators in pre-order
+pub fn format_plan_with_metrics(
+    plan: &dyn ExecutionPlan,
+    metrics: &[MetricsSet],
+) -> String {
+    struct Operator<'a>(&'a dyn ExecutionPlan);
+
+    impl std::fmt::Display for Operator<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            self.0.fmt_as(DisplayFormatType::Default, f)
+        }
+    }
+
+    fn format_operator<'a>(
+        plan: &dyn ExecutionPlan,
+        indent: usize,
+        metrics: &mut impl Iterator<Item = &'a MetricsSet>,
+        output: &mut String,
+    ) {
+        output.push_str(&format!(
+            "{:indent$}{}",
+            "",
+            Operator(plan),
+            indent = indent * 2
+        ));
+        if let Some(metrics) = metrics.next() {
+            let metrics = metrics.clone().sorted_for_display().timestamps_removed();
+            output.push_str(&format!(", metrics=[{}]", metrics));
+        }
+        output.push('\n');
+        for child in plan.children() {
+            format_operator(child.as_ref(), indent + 1, metrics, output);
+        }
+    }
+
+    let mut output = String::new();
+    format_operator(plan, 0, &mut metrics.iter(), &mut output);
+    output
+}
+
 /// Create a DataFusion context that uses the BallistaQueryPlanner to send logical plans
-/// to a Ballista scheduler
+/// to a Ballista scheduler, and that supports the statements managing the jobs of the
+/// scheduler, see [`JobStatement`](crate::statements::JobStatement)
 pub fn create_df_ctx_with_ballista_query_planner(
     scheduler_host: &str,
     scheduler_port: u16,
@@ -248,10 +512,12 @@ pub fn create_df_ctx_with_ballista_query_planner(
     let scheduler_url = format!("http://{}:{}", scheduler_host, scheduler_port);
     let config = ExecutionConfig::new()
         .with_query_planner(Arc::new(BallistaQueryPlanner::new(
-            scheduler_url,
+            scheduler_url.clone(),
             config.clone(),
         )))
-        .with_target_partitions(config.default_shuffle_partitions());
+        .with_target_partitions(config.default_shuffle_partitions())
+        .with_ansi_mode(config.ansi_mode())
+        .add_statement_extension(Arc::new(JobStatementExtension::new(scheduler_url)));
     ExecutionContext::with_config(config)
 }
 
@@ -288,6 +554,21 @@ impl QueryPlanner for BallistaQueryPlanner {
             ))),
         }
     }
+
+    async fn cache(
+        &self,
+        logical_plan: &LogicalPlan,
+        _ctx_state: &ExecutionContextState,
+    ) -> std::result::Result<Arc<dyn TableProvider>, DataFusionError> {
+        // the results are kept by the executors and registered with the scheduler, so
+        // that they are not collected into the memory of the client
+        let plan = DistributedQueryExec::new(
+            self.scheduler_url.clone(),
+            self.config.clone(),
+            logical_plan.clone(),
+        );
+        Ok(Arc::new(plan.persist().await?))
+    }
 }
 
 pub struct WrappedStream {
@@ -0,0 +1,92 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Executes again locally a task that failed on an executor, from the debug bundle
+//! the executor saved to its `task_debug_dir`, e.g. to run it under a debugger.
+//!
+//! The shuffle partitions read by the task are fetched from the executors that
+//! wrote them, which must still be running.
+
+use std::convert::TryInto;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use ballista_core::serde::physical_plan::from_proto::parse_protobuf_hash_partitioning;
+use ballista_executor::debug_bundle::read_debug_bundle;
+use ballista_executor::executor::Executor;
+use datafusion::physical_plan::ExecutionPlan;
+use tempfile::TempDir;
+
+const USAGE: &str = "Usage: ballista-debug run-task <bundle> [work_dir]";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (bundle, work_dir) = match args.as_slice() {
+        [command, bundle] if command == "run-task" => (bundle, None),
+        [command, bundle, work_dir] if command == "run-task" => {
+            (bundle, Some(work_dir.clone()))
+        }
+        _ => bail!(USAGE),
+    };
+
+    let bundle = read_debug_bundle(Path::new(bundle))?;
+    let task = bundle.task.context("The bundle has no task")?;
+    let task_id = task.task_id.context("The bundle has no task id")?;
+    println!(
+        "Task {}/{}/{} failed on executor {}: {}",
+        task_id.job_id,
+        task_id.stage_id,
+        task_id.partition_id,
+        bundle.executor_id,
+        bundle.error
+    );
+    for location in &bundle.input_partitions {
+        let executor = location.executor_meta.clone().unwrap_or_default();
+        println!(
+            "Input shuffle partition {} on {}:{}",
+            location.path, executor.host, executor.port
+        );
+    }
+
+    let plan: Arc<dyn ExecutionPlan> = task
+        .plan
+        .as_ref()
+        .context("The bundle has no plan")?
+        .try_into()?;
+    let output_partitioning =
+        parse_protobuf_hash_partitioning(task.output_partitioning.as_ref())?;
+    let work_dir = match work_dir {
+        Some(work_dir) => work_dir,
+        None => TempDir::new()?.into_path().to_string_lossy().into_owned(),
+    };
+
+    let partitions = Executor::new(&work_dir)
+        .execute_shuffle_write(
+            task_id.job_id,
+            task_id.stage_id as usize,
+            task_id.partition_id as usize,
+            plan,
+            output_partitioning,
+        )
+        .await?;
+    println!("Task completed, wrote {:?}", partitions);
+    Ok(())
+}
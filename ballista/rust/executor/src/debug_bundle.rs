@@ -0,0 +1,137 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Debug bundles of the failed tasks, holding their plan and the shuffle partitions
+//! they read, so that they can be executed again locally with `ballista-debug`.
+
+use std::convert::TryInto;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ballista_core::error::{BallistaError, Result};
+use ballista_core::execution_plans::ShuffleReaderExec;
+use ballista_core::serde::protobuf::{self, TaskDebugBundle, TaskDefinition};
+use datafusion::physical_plan::ExecutionPlan;
+use prost::Message;
+
+/// Saves a failed task to a debug bundle in `dir`, named after the task, and
+/// returns the path of the bundle
+pub fn write_debug_bundle(
+    dir: &Path,
+    task: TaskDefinition,
+    plan: &Arc<dyn ExecutionPlan>,
+    executor_id: &str,
+    error: &str,
+) -> Result<PathBuf> {
+    let task_id = task.task_id.clone().unwrap_or_default();
+    let bundle = TaskDebugBundle {
+        task: Some(task),
+        executor_id: executor_id.to_owned(),
+        error: error.to_owned(),
+        input_partitions: input_partitions(plan)?,
+    };
+    let mut buf = Vec::with_capacity(bundle.encoded_len());
+    bundle.encode(&mut buf).map_err(|e| {
+        BallistaError::Internal(format!("Could not encode task debug bundle: {}", e))
+    })?;
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "{}-{}-{}.task",
+        task_id.job_id, task_id.stage_id, task_id.partition_id
+    ));
+    fs::write(&path, buf)?;
+    Ok(path)
+}
+
+/// Reads a debug bundle written by [write_debug_bundle]
+pub fn read_debug_bundle(path: &Path) -> Result<TaskDebugBundle> {
+    let buf = fs::read(path)?;
+    TaskDebugBundle::decode(buf.as_slice()).map_err(|e| {
+        BallistaError::General(format!(
+            "Could not decode task debug bundle {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// The locations of the shuffle partitions read by a plan
+fn input_partitions(
+    plan: &Arc<dyn ExecutionPlan>,
+) -> Result<Vec<protobuf::PartitionLocation>> {
+    let mut locations = vec![];
+    if let Some(reader) = plan.as_any().downcast_ref::<ShuffleReaderExec>() {
+        for location in reader.partition().iter().flatten() {
+            locations.push(location.clone().try_into()?);
+        }
+    }
+    for child in plan.children() {
+        locations.extend(input_partitions(&child)?);
+    }
+    Ok(locations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ballista_core::serde::protobuf::PartitionId;
+    use ballista_core::serde::scheduler::{
+        ExecutorMeta, PartitionId as ShufflePartitionId, PartitionLocation,
+        PartitionStats,
+    };
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+
+    #[test]
+    fn write_and_read_debug_bundle() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let location = PartitionLocation {
+            partition_id: ShufflePartitionId::new("job", 1, 0),
+            executor_meta: ExecutorMeta {
+                id: "executor".to_owned(),
+                host: "localhost".to_owned(),
+                port: 50051,
+                labels: Default::default(),
+            },
+            partition_stats: PartitionStats::default(),
+            path: "/tmp/job/1/0/data.arrow".to_owned(),
+            checksum: None,
+        };
+        let plan: Arc<dyn ExecutionPlan> =
+            Arc::new(ShuffleReaderExec::try_new(vec![vec![location]], schema)?);
+        let task = TaskDefinition {
+            task_id: Some(PartitionId {
+                job_id: "job".to_owned(),
+                stage_id: 2,
+                partition_id: 3,
+            }),
+            plan: Some(plan.clone().try_into()?),
+            output_partitioning: None,
+        };
+
+        let dir = tempfile::TempDir::new()?;
+        let path = write_debug_bundle(dir.path(), task, &plan, "executor", "error")?;
+        assert!(path.ends_with("job-2-3.task"));
+        let bundle = read_debug_bundle(&path)?;
+        assert_eq!("error", bundle.error);
+        assert_eq!(1, bundle.input_partitions.len());
+        assert_eq!("/tmp/job/1/0/data.arrow", bundle.input_partitions[0].path);
+        assert!(bundle.task.unwrap().plan.is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Disk space used by the work dir of an executor, which refuses new tasks when the
+//! work dir exceeds its quota or the disk runs out of free space
+
+use std::io;
+use std::path::Path;
+
+use ballista_core::serde::protobuf::ExecutorDiskUsage;
+
+/// Limits on the disk space used by the work dir of an executor
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskQuota {
+    /// Max size of the files in the work dir, 0 for no limit
+    pub max_work_dir_bytes: u64,
+    /// Min free space of the disk of the work dir, below which the executor is
+    /// under disk pressure
+    pub min_free_bytes: u64,
+}
+
+impl DiskQuota {
+    /// Measures the disk space used by `work_dir` and whether it is under disk
+    /// pressure
+    pub fn usage(&self, work_dir: &Path) -> io::Result<ExecutorDiskUsage> {
+        let work_dir_bytes = dir_size(work_dir)?;
+        let available_bytes = fs2::available_space(work_dir)?;
+        let capacity_bytes = fs2::total_space(work_dir)?;
+        let over_quota =
+            self.max_work_dir_bytes > 0 && work_dir_bytes >= self.max_work_dir_bytes;
+        Ok(ExecutorDiskUsage {
+            work_dir_bytes,
+            quota_bytes: self.max_work_dir_bytes,
+            available_bytes,
+            capacity_bytes,
+            disk_pressure: over_quota || available_bytes < self.min_free_bytes,
+        })
+    }
+}
+
+/// Total size of the files in `dir` and its sub-directories
+pub fn dir_size(dir: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn work_dir_quota() -> io::Result<()> {
+        let work_dir = TempDir::new()?;
+        let job_dir = work_dir.path().join("job").join("1");
+        std::fs::create_dir_all(&job_dir)?;
+        std::fs::write(job_dir.join("data.arrow"), vec![0u8; 600])?;
+        std::fs::write(work_dir.path().join("data.arrow"), vec![0u8; 400])?;
+        assert_eq!(dir_size(work_dir.path())?, 1000);
+
+        let usage = DiskQuota::default().usage(work_dir.path())?;
+        assert_eq!(usage.work_dir_bytes, 1000);
+        assert!(usage.capacity_bytes >= usage.available_bytes);
+        assert!(!usage.disk_pressure);
+
+        let quota = DiskQuota {
+            max_work_dir_bytes: 1000,
+            min_free_bytes: 0,
+        };
+        assert!(quota.usage(work_dir.path())?.disk_pressure);
+
+        let quota = DiskQuota {
+            max_work_dir_bytes: 0,
+            min_free_bytes: u64::MAX,
+        };
+        assert!(quota.usage(work_dir.path())?.disk_pressure);
+        Ok(())
+    }
+}
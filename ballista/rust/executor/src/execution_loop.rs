@@ -18,6 +18,7 @@
 use std::convert::TryInto;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::time::Instant;
 use std::{sync::Arc, time::Duration};
 
 use datafusion::physical_plan::ExecutionPlan;
@@ -26,15 +27,24 @@ use tonic::transport::Channel;
 
 use ballista_core::serde::protobuf::ExecutorRegistration;
 use ballista_core::serde::protobuf::{
-    self, scheduler_grpc_client::SchedulerGrpcClient, task_status, FailedTask,
-    PartitionId, PollWorkParams, PollWorkResult, ShuffleWritePartition, TaskDefinition,
-    TaskStatus,
+    self, scheduler_grpc_client::SchedulerGrpcClient, task_status, ExecutorDiskUsage,
+    FailedTask, PartitionId, PollWorkParams, PollWorkResult, ShuffleWritePartition,
+    TaskDefinition, TaskStatus,
 };
 use protobuf::CompletedTask;
 
+use crate::debug_bundle::write_debug_bundle;
 use crate::executor::Executor;
+use crate::task_logs;
 use ballista_core::error::BallistaError;
 use ballista_core::serde::physical_plan::from_proto::parse_protobuf_hash_partitioning;
+use ballista_core::utils::collect_plan_metrics;
+
+/// Interval between the measures of the disk space used by the work dir
+const DISK_USAGE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Minimum interval between the polls of the scheduler that did not return a task
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 pub async fn poll_loop(
     mut scheduler: SchedulerGrpcClient<Channel>,
@@ -45,10 +55,22 @@ pub async fn poll_loop(
     let available_tasks_slots = Arc::new(AtomicUsize::new(concurrent_tasks));
     let (task_status_sender, mut task_status_receiver) =
         std::sync::mpsc::channel::<TaskStatus>();
+    let mut disk_usage: Option<ExecutorDiskUsage> = None;
+    let mut disk_usage_time: Option<Instant> = None;
 
     loop {
         debug!("Starting registration loop with scheduler");
 
+        // walking the work dir is too slow to measure it on every poll
+        if disk_usage_time.map_or(true, |time| time.elapsed() >= DISK_USAGE_INTERVAL) {
+            disk_usage = measure_disk_usage(&executor, disk_usage.as_ref());
+            disk_usage_time = Some(Instant::now());
+        }
+        // new tasks are refused under disk pressure, the running ones go on
+        let disk_pressure = disk_usage
+            .as_ref()
+            .map_or(false, |usage| usage.disk_pressure);
+
         let task_status: Vec<TaskStatus> =
             sample_tasks_status(&mut task_status_receiver).await;
 
@@ -56,27 +78,36 @@ pub async fn poll_loop(
         // to avoid going in sleep mode between polling
         let mut active_job = false;
 
+        // the slots are only taken by the tasks received from the polls, so that no
+        // more tasks than the free slots are assigned until the next poll
+        let available_task_slots = available_tasks_slots.load(Ordering::SeqCst);
+        let poll_start = Instant::now();
         let poll_work_result: anyhow::Result<
             tonic::Response<PollWorkResult>,
             tonic::Status,
         > = scheduler
             .poll_work(PollWorkParams {
                 metadata: Some(executor_meta.clone()),
-                can_accept_task: available_tasks_slots.load(Ordering::SeqCst) > 0,
+                can_accept_task: !disk_pressure && available_task_slots > 0,
                 task_status,
+                job_memory: executor.job_memory_usage(),
+                disk_usage: disk_usage.clone(),
+                available_task_slots: available_task_slots as u32,
             })
             .await;
 
-        let task_status_sender = task_status_sender.clone();
-
         match poll_work_result {
             Ok(result) => {
-                if let Some(task) = result.into_inner().task {
+                let result = result.into_inner();
+                for job_id in &result.cancelled_jobs {
+                    executor.cancel_job(job_id);
+                }
+                for task in result.task.into_iter().chain(result.additional_tasks) {
                     match run_received_tasks(
                         executor.clone(),
                         executor_meta.id.clone(),
                         available_tasks_slots.clone(),
-                        task_status_sender,
+                        task_status_sender.clone(),
                         task,
                     )
                     .await
@@ -86,23 +117,47 @@ pub async fn poll_loop(
                         }
                         Err(e) => {
                             warn!("Failed to run task: {:?}", e);
-                            active_job = false;
                         }
                     }
-                } else {
-                    active_job = false;
                 }
             }
             Err(error) => {
                 warn!("Executor registration failed. If this continues to happen the executor might be marked as dead by the scheduler. Error: {}", error);
             }
         }
+        // the scheduler holds the polls back while it has no task to assign, so
+        // that the executors are woken up as soon as tasks can be scheduled
         if !active_job {
-            tokio::time::sleep(Duration::from_millis(100)).await;
+            tokio::time::sleep(POLL_INTERVAL.saturating_sub(poll_start.elapsed())).await;
         }
     }
 }
 
+/// Measures the disk space used by the work dir, logging when the executor comes
+/// under disk pressure or recovers from it
+fn measure_disk_usage(
+    executor: &Executor,
+    previous: Option<&ExecutorDiskUsage>,
+) -> Option<ExecutorDiskUsage> {
+    let usage = match executor.disk_usage() {
+        Ok(usage) => usage,
+        Err(e) => {
+            warn!("Could not measure the disk usage of the work dir: {}", e);
+            return None;
+        }
+    };
+    let was_under_pressure = previous.map_or(false, |previous| previous.disk_pressure);
+    if usage.disk_pressure && !was_under_pressure {
+        warn!(
+            "Refusing new tasks under disk pressure: the work dir uses {} bytes of a quota of {} bytes, {} bytes are free",
+            usage.work_dir_bytes, usage.quota_bytes, usage.available_bytes
+        );
+    } else if !usage.disk_pressure && was_under_pressure {
+        info!("Accepting new tasks again, the disk pressure is relieved");
+    }
+    Some(usage)
+}
+
 async fn run_received_tasks(
     executor: Arc<Executor>,
     executor_id: String,
@@ -110,36 +165,59 @@ async fn run_received_tasks(
     task_status_sender: Sender<TaskStatus>,
     task: TaskDefinition,
 ) -> Result<(), BallistaError> {
-    let task_id = task.task_id.unwrap();
+    let task_id = task.task_id.clone().unwrap();
     let task_id_log = format!(
         "{}/{}/{}",
         task_id.job_id, task_id.stage_id, task_id.partition_id
     );
     info!("Received task {}", task_id_log);
     available_tasks_slots.fetch_sub(1, Ordering::SeqCst);
-    let plan: Arc<dyn ExecutionPlan> = (&task.plan.unwrap()).try_into().unwrap();
+    let plan: Arc<dyn ExecutionPlan> = (task.plan.as_ref().unwrap()).try_into().unwrap();
     let shuffle_output_partitioning =
         parse_protobuf_hash_partitioning(task.output_partitioning.as_ref())?;
+    // keep the task to save it if it fails
+    let debug_task = executor.task_debug_dir().map(|_| task.clone());
+
+    let task_key = task_logs::task_key(
+        &task_id.job_id,
+        task_id.stage_id as usize,
+        task_id.partition_id as usize,
+    );
 
-    tokio::spawn(async move {
+    tokio::spawn(task_logs::scope(task_key, async move {
         let execution_result = executor
             .execute_shuffle_write(
                 task_id.job_id.clone(),
                 task_id.stage_id as usize,
                 task_id.partition_id as usize,
-                plan,
+                plan.clone(),
                 shuffle_output_partitioning,
             )
             .await;
         info!("Done with task {}", task_id_log);
         debug!("Statistics: {:?}", execution_result);
+        if let (Err(error), Some(dir), Some(task)) =
+            (&execution_result, executor.task_debug_dir(), debug_task)
+        {
+            match write_debug_bundle(dir, task, &plan, &executor_id, &error.to_string()) {
+                Ok(path) => {
+                    info!("Saved failed task {} to {}", task_id_log, path.display())
+                }
+                Err(e) => warn!("Could not save failed task {}: {}", task_id_log, e),
+            }
+        }
         available_tasks_slots.fetch_add(1, Ordering::SeqCst);
+        let metrics = collect_plan_metrics(plan.as_ref())
+            .iter()
+            .map(|metrics| metrics.into())
+            .collect();
         let _ = task_status_sender.send(as_task_status(
             execution_result,
             executor_id,
             task_id,
+            metrics,
         ));
-    });
+    }));
 
     Ok(())
 }
@@ -148,6 +226,7 @@ fn as_task_status(
     execution_result: ballista_core::error::Result<Vec<ShuffleWritePartition>>,
     executor_id: String,
     task_id: PartitionId,
+    metrics: Vec<protobuf::OperatorMetricsSet>,
 ) -> TaskStatus {
     match execution_result {
         Ok(partitions) => {
@@ -158,6 +237,7 @@ fn as_task_status(
                 status: Some(task_status::Status::Completed(CompletedTask {
                     executor_id,
                     partitions,
+                    metrics,
                 })),
             }
         }
@@ -169,6 +249,11 @@ fn as_task_status(
                 partition_id: Some(task_id),
                 status: Some(task_status::Status::Failed(FailedTask {
                     error: format!("Task failed due to Tokio error: {}", error_msg),
+                    lost_partition_path: e
+                        .lost_partition_path()
+                        .unwrap_or_default()
+                        .to_owned(),
+                    executor_id,
                 })),
             }
         }
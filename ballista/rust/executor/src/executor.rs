@@ -17,19 +17,53 @@
 
 //! Ballista executor logic
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
+use ballista_core::encryption::ShuffleEncryption;
 use ballista_core::error::BallistaError;
-use ballista_core::execution_plans::ShuffleWriterExec;
+use ballista_core::execution_plans::{MorselPool, ShuffleWriterExec};
 use ballista_core::serde::protobuf;
+use ballista_core::serde::scheduler::PartitionStats;
+use ballista_core::utils::{partition_checksum, write_stream_to_disk, ShuffleFormat};
 use datafusion::error::DataFusionError;
+use datafusion::execution::memory_manager::MemoryManager;
+use datafusion::physical_plan::cancellation::CancellationToken;
 use datafusion::physical_plan::display::DisplayableExecutionPlan;
-use datafusion::physical_plan::{ExecutionPlan, Partitioning};
+use datafusion::physical_plan::{
+    metrics, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use log::info;
+use uuid::Uuid;
+
+use crate::disk_usage::DiskQuota;
+use crate::sandbox::sandbox_udfs;
 
 /// Ballista executor
 pub struct Executor {
     /// Directory for storing partial results
     work_dir: String,
+    /// Optional pool of workers shared by the tasks to execute them in morsels
+    morsel_pool: Option<MorselPool>,
+    /// Maximum number of bytes the tasks of a job may buffer at once on this executor
+    query_memory_limit: Option<usize>,
+    /// Cancellation tokens and memory managers of the running tasks, by job id, stage
+    /// id and partition. The tasks of a job share the same memory manager.
+    running_tasks:
+        Mutex<HashMap<(String, usize, usize), (CancellationToken, Arc<MemoryManager>)>>,
+    /// Optional directory to save the failed tasks to, to debug them
+    task_debug_dir: Option<PathBuf>,
+    /// IPC format the shuffle partitions are written in
+    shuffle_format: ShuffleFormat,
+    /// Whether to compute the checksums of the shuffle partitions
+    shuffle_checksums: bool,
+    /// Optional encryption of the shuffle partitions and datasets written to disk
+    shuffle_encryption: Option<ShuffleEncryption>,
+    /// Limits on the disk space used by the work dir
+    disk_quota: DiskQuota,
+    /// Optional program to start the processes running the aggregations calling UDAFs
+    udf_sandbox: Option<PathBuf>,
 }
 
 impl Executor {
@@ -37,8 +71,88 @@ impl Executor {
     pub fn new(work_dir: &str) -> Self {
         Self {
             work_dir: work_dir.to_owned(),
+            morsel_pool: None,
+            query_memory_limit: None,
+            running_tasks: Mutex::new(HashMap::new()),
+            task_debug_dir: None,
+            shuffle_format: ShuffleFormat::default(),
+            shuffle_checksums: false,
+            shuffle_encryption: None,
+            disk_quota: DiskQuota::default(),
+            udf_sandbox: None,
         }
     }
+
+    /// Fail the jobs whose tasks buffer more than `limit` bytes at once on this
+    /// executor
+    pub fn with_query_memory_limit(mut self, limit: usize) -> Self {
+        self.query_memory_limit = Some(limit);
+        self
+    }
+
+    /// Execute the tasks in morsels with a pool of `num_workers` workers shared by
+    /// all the tasks, so that a task can use the cores that the others leave idle
+    pub fn with_morsel_workers(mut self, num_workers: usize) -> Self {
+        self.morsel_pool = Some(MorselPool::new(num_workers, num_workers));
+        self
+    }
+
+    /// Save the failed tasks to `dir` as debug bundles, which can be executed again
+    /// locally with `ballista-debug run-task`
+    pub fn with_task_debug_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.task_debug_dir = Some(dir.into());
+        self
+    }
+
+    /// The directory the failed tasks are saved to, if any
+    pub fn task_debug_dir(&self) -> Option<&Path> {
+        self.task_debug_dir.as_deref()
+    }
+
+    /// Write the shuffle partitions in the IPC `format`
+    pub fn with_shuffle_format(mut self, format: ShuffleFormat) -> Self {
+        self.shuffle_format = format;
+        self
+    }
+
+    /// Compute the checksums of the shuffle partitions, which are verified when
+    /// they are fetched, if `checksums` is true
+    pub fn with_shuffle_checksums(mut self, checksums: bool) -> Self {
+        self.shuffle_checksums = checksums;
+        self
+    }
+
+    /// Encrypt the shuffle partitions and the dataset partitions written to disk
+    /// with `encryption`
+    pub fn with_shuffle_encryption(mut self, encryption: ShuffleEncryption) -> Self {
+        self.shuffle_encryption = Some(encryption);
+        self
+    }
+
+    /// The encryption of the partitions written to disk, if any
+    pub fn shuffle_encryption(&self) -> Option<&ShuffleEncryption> {
+        self.shuffle_encryption.as_ref()
+    }
+
+    /// Refuse new tasks when the work dir exceeds the limits of `quota`
+    pub fn with_disk_quota(mut self, quota: DiskQuota) -> Self {
+        self.disk_quota = quota;
+        self
+    }
+
+    /// Run the projections, filters and aggregations calling UDFs or UDAFs in
+    /// processes started from `program`, a copy of the executor calling
+    /// [`run_sandbox_process`](crate::sandbox::run_sandbox_process), so that a
+    /// crashing UDF only fails its task
+    pub fn with_udf_sandbox(mut self, program: impl Into<PathBuf>) -> Self {
+        self.udf_sandbox = Some(program.into());
+        self
+    }
+
+    /// The disk space used by the work dir, and whether it is under disk pressure
+    pub fn disk_usage(&self) -> std::io::Result<protobuf::ExecutorDiskUsage> {
+        self.disk_quota.usage(Path::new(&self.work_dir))
+    }
 }
 
 impl Executor {
@@ -56,14 +170,27 @@ impl Executor {
         let exec = if let Some(shuffle_writer) =
             plan.as_any().downcast_ref::<ShuffleWriterExec>()
         {
+            let input = match &self.udf_sandbox {
+                Some(program) => sandbox_udfs(plan.children()[0].clone(), program)?,
+                None => plan.children()[0].clone(),
+            };
             // recreate the shuffle writer with the correct working directory
-            ShuffleWriterExec::try_new(
-                job_id.clone(),
-                stage_id,
-                plan.children()[0].clone(),
-                self.work_dir.clone(),
-                shuffle_writer.shuffle_output_partitioning().cloned(),
-            )
+            match shuffle_writer.range_partitioning() {
+                Some(range) => ShuffleWriterExec::try_new_range_partitioned(
+                    job_id.clone(),
+                    stage_id,
+                    input,
+                    self.work_dir.clone(),
+                    range.clone(),
+                ),
+                None => ShuffleWriterExec::try_new(
+                    job_id.clone(),
+                    stage_id,
+                    input,
+                    self.work_dir.clone(),
+                    shuffle_writer.shuffle_output_partitioning().cloned(),
+                ),
+            }
         } else {
             Err(DataFusionError::Internal(
                 "Plan passed to execute_shuffle_write is not a ShuffleWriterExec"
@@ -71,7 +198,39 @@ impl Executor {
             ))
         }?;
 
-        let partitions = exec.execute_shuffle_write(part).await?;
+        let exec = exec
+            .with_shuffle_format(self.shuffle_format)
+            .with_checksums(self.shuffle_checksums);
+        let exec = match &self.morsel_pool {
+            Some(pool) => exec.with_morsel_pool(pool.clone()),
+            None => exec,
+        };
+        let exec = match &self.shuffle_encryption {
+            Some(encryption) => exec.with_encryption(encryption.clone()),
+            None => exec,
+        };
+
+        let task_id = (job_id.clone(), stage_id, part);
+        let token = CancellationToken::new();
+        let memory_manager = {
+            let mut running_tasks = self.running_tasks.lock().unwrap();
+            let memory_manager = running_tasks
+                .iter()
+                .find(|((task_job_id, _, _), _)| *task_job_id == job_id)
+                .map(|(_, (_, memory_manager))| memory_manager.clone())
+                .unwrap_or_else(|| Arc::new(MemoryManager::new(self.query_memory_limit)));
+            running_tasks
+                .insert(task_id.clone(), (token.clone(), memory_manager.clone()));
+            memory_manager
+        };
+        let partitions = token
+            .scope(MemoryManager::scope(
+                memory_manager,
+                exec.execute_shuffle_write(part),
+            ))
+            .await;
+        self.running_tasks.lock().unwrap().remove(&task_id);
+        let partitions = partitions?;
 
         println!(
             "=== [{}/{}/{}] Physical plan with metrics ===\n{}\n",
@@ -86,6 +245,75 @@ impl Executor {
         Ok(partitions)
     }
 
+    /// Write the record batches of `stream` to a new partition of the dataset
+    /// `dataset_id` kept by this executor, such as the record batches pushed with
+    /// Flight DoPut. Return the path of the partition, its statistics and its
+    /// checksum, if they are computed.
+    pub async fn write_dataset_partition(
+        &self,
+        dataset_id: &str,
+        stream: &mut SendableRecordBatchStream,
+    ) -> Result<(String, PartitionStats, Option<u32>), BallistaError> {
+        let mut path = PathBuf::from(&self.work_dir);
+        path.push("datasets");
+        path.push(dataset_id);
+        std::fs::create_dir_all(&path)?;
+        path.push(format!("{}.arrow", Uuid::new_v4()));
+        let path = path.to_str().unwrap().to_owned();
+        info!("Writing dataset partition to {}", path);
+
+        let stats = write_stream_to_disk(
+            stream,
+            &path,
+            self.shuffle_format,
+            self.shuffle_encryption.as_ref(),
+            &metrics::Time::new(),
+        )
+        .await;
+        let stats = match stats {
+            Ok(stats) => stats,
+            Err(e) => {
+                // do not leave the partial partition behind
+                let _ = std::fs::remove_file(&path);
+                return Err(e);
+            }
+        };
+        let checksum = if self.shuffle_checksums {
+            Some(partition_checksum(&path)?)
+        } else {
+            None
+        };
+        Ok((path, stats, checksum))
+    }
+
+    /// Cancel the running tasks of the job `job_id`, which then fail with a
+    /// cancellation error
+    pub fn cancel_job(&self, job_id: &str) {
+        let running_tasks = self.running_tasks.lock().unwrap();
+        for ((task_job_id, stage_id, part), (token, _)) in running_tasks.iter() {
+            if task_job_id == job_id {
+                info!("Cancelling task {}/{}/{}", job_id, stage_id, part);
+                token.cancel();
+            }
+        }
+    }
+
+    /// The memory used by the running tasks of each job
+    pub fn job_memory_usage(&self) -> Vec<protobuf::JobMemoryUsage> {
+        let running_tasks = self.running_tasks.lock().unwrap();
+        let mut usage: HashMap<&str, protobuf::JobMemoryUsage> = HashMap::new();
+        for ((job_id, _, _), (_, memory_manager)) in running_tasks.iter() {
+            usage
+                .entry(job_id)
+                .or_insert_with(|| protobuf::JobMemoryUsage {
+                    job_id: job_id.clone(),
+                    used_bytes: memory_manager.used() as u64,
+                    peak_bytes: memory_manager.peak() as u64,
+                });
+        }
+        usage.into_values().collect()
+    }
+
     pub fn work_dir(&self) -> &str {
         &self.work_dir
     }
@@ -0,0 +1,59 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Implementation of the gRPC service of the executor, queried by the scheduler
+
+use std::sync::Arc;
+
+use ballista_core::serde::protobuf::{
+    executor_grpc_server::ExecutorGrpc, GetTaskLogsParams, GetTaskLogsResult,
+};
+use tonic::{Request, Response, Status};
+
+use crate::task_logs::{task_key, TaskLogs};
+
+/// Service returning the logs of the tasks run by the executor
+pub struct BallistaExecutorService {
+    task_logs: Arc<TaskLogs>,
+}
+
+impl BallistaExecutorService {
+    pub fn new(task_logs: Arc<TaskLogs>) -> Self {
+        Self { task_logs }
+    }
+}
+
+#[tonic::async_trait]
+impl ExecutorGrpc for BallistaExecutorService {
+    async fn get_task_logs(
+        &self,
+        request: Request<GetTaskLogsParams>,
+    ) -> Result<Response<GetTaskLogsResult>, Status> {
+        let GetTaskLogsParams { task_id, max_lines } = request.into_inner();
+        let task_id =
+            task_id.ok_or_else(|| Status::invalid_argument("Missing task id"))?;
+        let lines = self.task_logs.get(
+            &task_key(
+                &task_id.job_id,
+                task_id.stage_id as usize,
+                task_id.partition_id as usize,
+            ),
+            max_lines as usize,
+        );
+        Ok(Response::new(GetTaskLogsResult { lines }))
+    }
+}
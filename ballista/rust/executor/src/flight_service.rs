@@ -17,15 +17,25 @@
 
 //! Implementation of the Apache Arrow Flight protocol that wraps an executor.
 
-use std::fs::File;
+use std::convert::TryFrom;
 use std::pin::Pin;
 use std::sync::Arc;
 
 use crate::executor::Executor;
-use arrow_flight::SchemaAsIpc;
+use arrow_flight::{flight_descriptor::DescriptorType, SchemaAsIpc};
+use ballista_core::client::flight_data_stream;
 use ballista_core::error::BallistaError;
+use ballista_core::result_encoding::{ResultEncoding, ResultSerializer};
 use ballista_core::serde::decode_protobuf;
+use ballista_core::serde::protobuf::{
+    self, scheduler_grpc_client::SchedulerGrpcClient, AppendDatasetParams,
+    PartitionChecksum,
+};
 use ballista_core::serde::scheduler::Action as BallistaAction;
+use ballista_core::utils::{
+    ipc_message_checksum, read_shuffle_partition, verify_partition_checksum,
+    ShufflePartitionReader,
+};
 
 use arrow_flight::{
     flight_service_server::FlightService, Action, ActionType, Criteria, Empty,
@@ -33,18 +43,19 @@ use arrow_flight::{
     PutResult, SchemaResult, Ticket,
 };
 use datafusion::arrow::{
-    error::ArrowError, ipc::reader::FileReader, ipc::writer::IpcWriteOptions,
+    datatypes::{Schema, SchemaRef},
+    error::ArrowError,
+    ipc::writer::IpcWriteOptions,
     record_batch::RecordBatch,
 };
-use futures::{Stream, StreamExt};
+use datafusion::execution::io_runtime::spawn_io;
+use futures::Stream;
 use log::{info, warn};
-use std::io::{Read, Seek};
+use prost::Message;
 use tokio::sync::mpsc::channel;
-use tokio::{
-    sync::mpsc::{Receiver, Sender},
-    task,
-};
+use tokio::sync::mpsc::{Receiver, Sender};
 use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::Channel;
 use tonic::{Request, Response, Status, Streaming};
 
 type FlightDataSender = Sender<Result<FlightData, Status>>;
@@ -54,12 +65,29 @@ type FlightDataReceiver = Receiver<Result<FlightData, Status>>;
 #[derive(Clone)]
 pub struct BallistaFlightService {
     /// Executor
-    _executor: Arc<Executor>,
+    executor: Arc<Executor>,
+    /// Scheduler the datasets pushed with DoPut are registered to, along with the id
+    /// this executor is registered with
+    scheduler: Option<(SchedulerGrpcClient<Channel>, String)>,
 }
 
 impl BallistaFlightService {
-    pub fn new(_executor: Arc<Executor>) -> Self {
-        Self { _executor }
+    pub fn new(executor: Arc<Executor>) -> Self {
+        Self {
+            executor,
+            scheduler: None,
+        }
+    }
+
+    /// Accept the record batches pushed with DoPut, which are kept by the executor
+    /// registered as `executor_id` and appended to a dataset of `scheduler`
+    pub fn with_scheduler(
+        mut self,
+        scheduler: SchedulerGrpcClient<Channel>,
+        executor_id: impl Into<String>,
+    ) -> Self {
+        self.scheduler = Some((scheduler, executor_id.into()));
+        self
     }
 }
 
@@ -86,24 +114,42 @@ impl FlightService for BallistaFlightService {
             decode_protobuf(&ticket.ticket).map_err(|e| from_ballista_err(&e))?;
 
         match &action {
-            BallistaAction::FetchPartition { path, .. } => {
+            BallistaAction::FetchPartition {
+                path,
+                checksum,
+                encoding,
+                ..
+            } => {
                 info!("FetchPartition reading {}", &path);
-                let file = File::open(&path)
-                    .map_err(|e| {
-                        BallistaError::General(format!(
-                            "Failed to open partition file at {}: {:?}",
-                            path, e
-                        ))
-                    })
-                    .map_err(|e| from_ballista_err(&e))?;
-                let reader = FileReader::try_new(file).map_err(|e| from_arrow_err(&e))?;
+                if let Some(checksum) = *checksum {
+                    let file_path = path.clone();
+                    spawn_io(move || verify_partition_checksum(&file_path, checksum))
+                        .await
+                        .map_err(|e| Status::internal(format!("{:?}", e)))?
+                        .map_err(|e| {
+                            warn!("FetchPartition failed to verify {}: {}", path, e);
+                            from_ballista_err(&e)
+                        })?;
+                }
+                // the partition may have been written in the IPC file or stream format
+                let (schema, reader) =
+                    read_shuffle_partition(path, self.executor.shuffle_encryption())
+                        .map_err(|e| from_ballista_err(&e))?;
 
                 let (tx, rx): (FlightDataSender, FlightDataReceiver) = channel(2);
 
                 // Arrow IPC reader does not implement Sync + Send so we need to use a channel
-                // to communicate
-                task::spawn(async move {
-                    if let Err(e) = stream_flight_data(reader, tx).await {
+                // to communicate. The partition is read on the IO thread pool so that
+                // slow reads don't block the executor's tasks.
+                let encoding = *encoding;
+                spawn_io(move || {
+                    let result = match encoding.serializer() {
+                        None => stream_flight_data(schema, reader, tx),
+                        Some(serializer) => {
+                            stream_encoded_data(encoding, serializer, reader, tx)
+                        }
+                    };
+                    if let Err(e) = result {
                         warn!("Error streaming results: {:?}", e);
                     }
                 });
@@ -147,13 +193,64 @@ impl FlightService for BallistaFlightService {
         &self,
         request: Request<Streaming<FlightData>>,
     ) -> Result<Response<Self::DoPutStream>, Status> {
+        let (mut scheduler, executor_id) = self.scheduler.clone().ok_or_else(|| {
+            Status::unimplemented("do_put requires an executor connected to a scheduler")
+        })?;
         let mut request = request.into_inner();
 
-        while let Some(data) = request.next().await {
-            let _data = data?;
-        }
-
-        Err(Status::unimplemented("do_put"))
+        // the first message describes the dataset and holds the schema of the batches
+        let flight_data = request.message().await?.ok_or_else(|| {
+            Status::invalid_argument("Missing schema of the record batches")
+        })?;
+        let dataset_id = dataset_id(flight_data.flight_descriptor.as_ref())?;
+        let schema =
+            Arc::new(Schema::try_from(&flight_data).map_err(|e| from_arrow_err(&e))?);
+
+        let mut batches = flight_data_stream(request, schema.clone());
+        let (path, stats, checksum) = self
+            .executor
+            .write_dataset_partition(&dataset_id, &mut batches)
+            .await
+            .map_err(|e| from_ballista_err(&e))?;
+        info!(
+            "DoPut wrote {} to dataset {} at {}",
+            stats, dataset_id, path
+        );
+
+        let params = AppendDatasetParams {
+            dataset_id: dataset_id.clone(),
+            schema: Some(schema.as_ref().into()),
+            executor_id,
+            location: vec![protobuf::PartitionLocation {
+                partition_id: None,
+                executor_meta: None,
+                partition_stats: Some(stats.into()),
+                path: path.clone(),
+                checksum: checksum.map(|crc32| PartitionChecksum { crc32 }),
+            }],
+        };
+        let dataset = match scheduler.append_dataset(params).await {
+            Ok(response) => response.into_inner().dataset,
+            Err(status) => {
+                warn!(
+                    "DoPut failed to append to dataset {}: {}",
+                    dataset_id, status
+                );
+                // the partition is not part of any dataset
+                let _ = std::fs::remove_file(&path);
+                return Err(status);
+            }
+        };
+
+        let mut app_metadata = vec![];
+        dataset
+            .unwrap_or_default()
+            .encode(&mut app_metadata)
+            .map_err(|e| Status::internal(format!("{:?}", e)))?;
+        let result = PutResult { app_metadata };
+        Ok(Response::new(
+            Box::pin(futures::stream::iter(vec![Ok(result)])) as Self::DoPutStream,
+        ))
     }
 
     async fn do_action(
@@ -183,8 +280,34 @@ impl FlightService for BallistaFlightService {
     }
 }
 
+/// The id of the dataset the record batches pushed with DoPut are appended to, which
+/// is the path of their descriptor, or its command as UTF-8. The id names a directory
+/// of the executor, so it cannot be a path.
+fn dataset_id(descriptor: Option<&FlightDescriptor>) -> Result<String, Status> {
+    let descriptor = descriptor
+        .ok_or_else(|| Status::invalid_argument("Missing flight descriptor"))?;
+    let dataset_id = if descriptor.r#type == DescriptorType::Path as i32 {
+        descriptor.path.join("/")
+    } else {
+        String::from_utf8(descriptor.cmd.clone()).map_err(|_| {
+            Status::invalid_argument("Flight descriptor command is not UTF-8")
+        })?
+    };
+    if dataset_id.is_empty()
+        || dataset_id == "."
+        || dataset_id == ".."
+        || dataset_id.contains(|c| c == '/' || c == '\\')
+    {
+        return Err(Status::invalid_argument(format!(
+            "Invalid dataset id '{}'",
+            dataset_id
+        )));
+    }
+    Ok(dataset_id)
+}
+
 /// Convert a single RecordBatch into an iterator of FlightData (containing
-/// dictionaries and batches)
+/// dictionaries and batches), whose app metadata is the checksum of the message
 fn create_flight_iter(
     batch: &RecordBatch,
     options: &IpcWriteOptions,
@@ -195,20 +318,25 @@ fn create_flight_iter(
         flight_dictionaries
             .into_iter()
             .chain(std::iter::once(flight_batch))
-            .map(Ok),
+            .map(|mut flight_data| {
+                let checksum = ipc_message_checksum(
+                    &flight_data.data_header,
+                    &flight_data.data_body,
+                );
+                flight_data.app_metadata = checksum.to_be_bytes().to_vec();
+                Ok(flight_data)
+            }),
     )
 }
 
-async fn stream_flight_data<T>(
-    reader: FileReader<T>,
+fn stream_flight_data(
+    schema: SchemaRef,
+    reader: ShufflePartitionReader,
     tx: FlightDataSender,
-) -> Result<(), Status>
-where
-    T: Read + Seek,
-{
+) -> Result<(), Status> {
     let options = arrow::ipc::writer::IpcWriteOptions::default();
-    let schema_flight_data = SchemaAsIpc::new(reader.schema().as_ref(), &options).into();
-    send_response(&tx, Ok(schema_flight_data)).await?;
+    let schema_flight_data = SchemaAsIpc::new(schema.as_ref(), &options).into();
+    send_response(&tx, Ok(schema_flight_data))?;
 
     let mut row_count = 0;
     for batch in reader {
@@ -219,19 +347,53 @@ where
             .map(|b| create_flight_iter(&b, &options).collect())
             .map_err(|e| from_arrow_err(&e))?;
         for batch in batch_flight_data.into_iter() {
-            send_response(&tx, batch).await?;
+            send_response(&tx, batch)?;
         }
     }
     info!("FetchPartition streamed {} rows", row_count);
     Ok(())
 }
 
-async fn send_response(
+/// Streams the record batches serialized with a text encoding, each in the body of
+/// a flight data message, after a first message whose app metadata is the content
+/// type of the encoding
+fn stream_encoded_data(
+    encoding: ResultEncoding,
+    mut serializer: Box<dyn ResultSerializer>,
+    reader: ShufflePartitionReader,
+    tx: FlightDataSender,
+) -> Result<(), Status> {
+    let content_type = FlightData {
+        app_metadata: encoding.content_type().as_bytes().to_vec(),
+        ..Default::default()
+    };
+    send_response(&tx, Ok(content_type))?;
+
+    let mut row_count = 0;
+    for batch in reader {
+        let batch = batch.map_err(|e| from_arrow_err(&e))?;
+        row_count += batch.num_rows();
+        let data_body = serializer
+            .serialize(&batch)
+            .map_err(|e| from_ballista_err(&e))?;
+        let flight_data = FlightData {
+            data_body,
+            ..Default::default()
+        };
+        send_response(&tx, Ok(flight_data))?;
+    }
+    info!(
+        "FetchPartition streamed {} rows encoded as {}",
+        row_count, encoding
+    );
+    Ok(())
+}
+
+fn send_response(
     tx: &FlightDataSender,
     data: Result<FlightData, Status>,
 ) -> Result<(), Status> {
-    tx.send(data)
-        .await
+    tx.blocking_send(data)
         .map_err(|e| Status::internal(format!("{:?}", e)))
 }
 
@@ -239,6 +401,11 @@ fn from_arrow_err(e: &ArrowError) -> Status {
     Status::internal(format!("ArrowError: {:?}", e))
 }
 
-fn from_ballista_err(e: &ballista_core::error::BallistaError) -> Status {
-    Status::internal(format!("Ballista Error: {:?}", e))
+fn from_ballista_err(e: &BallistaError) -> Status {
+    match e {
+        BallistaError::CorruptedPartition { reason, .. } => {
+            Status::data_loss(reason.clone())
+        }
+        _ => Status::internal(format!("Ballista Error: {:?}", e)),
+    }
 }
@@ -0,0 +1,105 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Labels the executor reports to the scheduler when registering, such as the
+//! labels of its Kubernetes pod and the zone of its node.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use ballista_core::error::{BallistaError, Result};
+
+/// Parses labels given as comma separated `key=value` pairs
+pub fn parse_labels(labels: &str) -> Result<BTreeMap<String, String>> {
+    labels
+        .split(',')
+        .map(|label| label.trim())
+        .filter(|label| !label.is_empty())
+        .map(|label| match label.split_once('=') {
+            Some((key, value)) => Ok((key.trim().to_owned(), value.trim().to_owned())),
+            None => Err(BallistaError::General(format!(
+                "Invalid executor label '{}', expected key=value",
+                label
+            ))),
+        })
+        .collect()
+}
+
+/// Reads the labels of the Kubernetes pod of the executor from the file the
+/// downward API writes them to, with one `key="value"` label per line
+pub fn read_pod_labels(path: &Path) -> Result<BTreeMap<String, String>> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        BallistaError::General(format!(
+            "Could not read the pod labels from {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_once('=') {
+            Some((key, value)) => Ok((key.to_owned(), unquote(value))),
+            None => Err(BallistaError::General(format!(
+                "Invalid pod label '{}' in {}",
+                line,
+                path.display()
+            ))),
+        })
+        .collect()
+}
+
+/// Removes the quotes around a value written by the downward API, along with
+/// the escaping of the quotes and backslashes within it
+fn unquote(value: &str) -> String {
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+    let mut unquoted = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn labels_and_pod_labels() -> Result<()> {
+        let labels = parse_labels("app=ballista, tier = executor,")?;
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels["tier"], "executor");
+        assert!(parse_labels("app").is_err());
+
+        let mut file = tempfile::NamedTempFile::new()?;
+        writeln!(file, "app=\"ballista\"")?;
+        writeln!(file, "description=\"say \\\"hi\\\"\"")?;
+        let labels = read_pod_labels(file.path())?;
+        assert_eq!(labels["app"], "ballista");
+        assert_eq!(labels["description"], "say \"hi\"");
+        Ok(())
+    }
+}
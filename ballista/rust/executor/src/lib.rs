@@ -18,9 +18,15 @@
 #![doc = include_str!("../README.md")]
 
 pub mod collect;
+pub mod debug_bundle;
+pub mod disk_usage;
 pub mod execution_loop;
 pub mod executor;
+pub mod executor_service;
 pub mod flight_service;
+pub mod labels;
+pub mod sandbox;
+pub mod task_logs;
 
 mod standalone;
 pub use standalone::new_standalone_executor;
@@ -17,24 +17,39 @@
 
 //! Ballista Rust executor binary.
 
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use arrow_flight::flight_service_server::FlightServiceServer;
-use ballista_executor::execution_loop;
+use ballista_executor::{execution_loop, labels};
 use log::info;
 use tempfile::TempDir;
 use tonic::transport::Server;
 use uuid::Uuid;
 
+use ballista_core::encryption::{ShuffleEncryption, StaticKeyProvider};
+use ballista_core::execution_plans::{set_shuffle_fetch_options, ShuffleFetchOptions};
+use ballista_core::map_output_tracker::{
+    set_map_output_tracker, SchedulerMapOutputTracker,
+};
 use ballista_core::serde::protobuf::{
-    executor_registration, scheduler_grpc_client::SchedulerGrpcClient,
-    ExecutorRegistration,
+    executor_grpc_server::ExecutorGrpcServer, executor_registration,
+    scheduler_grpc_client::SchedulerGrpcClient, ExecutorRegistration, KeyValuePair,
 };
-use ballista_core::{print_version, BALLISTA_VERSION};
+use ballista_core::serde::scheduler::ZONE_LABEL;
+use ballista_core::utils::ShuffleFormat;
+use ballista_core::{config_file, print_version, BALLISTA_VERSION};
+use ballista_executor::disk_usage::DiskQuota;
 use ballista_executor::executor::Executor;
+use ballista_executor::executor_service::BallistaExecutorService;
 use ballista_executor::flight_service::BallistaFlightService;
+use ballista_executor::sandbox;
+use ballista_executor::task_logs::{self, TaskLogs};
 use config::prelude::*;
+use datafusion::execution::io_runtime;
 
 #[macro_use]
 extern crate configure_me;
@@ -50,20 +65,46 @@ mod config {
 #[global_allocator]
 static ALLOC: snmalloc_rs::SnMalloc = snmalloc_rs::SnMalloc;
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    env_logger::init();
+fn main() -> Result<()> {
+    // the plans calling UDFs are run in copies of the executor when sandboxed
+    if sandbox::is_sandbox_process() {
+        env_logger::init();
+        return Ok(sandbox::run_sandbox_process()?);
+    }
 
     // parse command-line arguments
+    // the config file passed with --config may also be written in YAML
+    let (args, config_file) = config_file::extract_config_arg(std::env::args_os())
+        .context("Could not read the config file")?;
+    let config_files = std::iter::once(PathBuf::from("/etc/ballista/executor.toml"))
+        .chain(config_file.as_ref().map(|file| file.path().to_owned()));
     let (opt, _remaining_args) =
-        Config::including_optional_config_files(&["/etc/ballista/executor.toml"])
-            .unwrap_or_exit();
+        Config::custom_args_and_optional_files(args, config_files).unwrap_or_exit();
 
     if opt.version {
         print_version();
         std::process::exit(0);
     }
 
+    let task_logs = Arc::new(TaskLogs::new(opt.task_log_lines));
+    task_logs::init_logger(task_logs.clone()).context("Could not set the logger")?;
+
+    if opt.io_threads > 0 {
+        io_runtime::set_io_threads(opt.io_threads);
+    }
+
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    if opt.cpu_threads > 0 {
+        runtime.worker_threads(opt.cpu_threads);
+    }
+    runtime
+        .enable_all()
+        .build()
+        .context("Could not build the tokio runtime")?
+        .block_on(run(opt, task_logs))
+}
+
+async fn run(opt: Config, task_logs: Arc<TaskLogs>) -> Result<()> {
     let external_host = opt.external_host;
     let bind_host = opt.bind_host;
     let port = opt.bind_port;
@@ -86,30 +127,121 @@ async fn main() -> Result<()> {
     );
     info!("Running with config:");
     info!("work_dir: {}", work_dir);
+    info!("work_dir_quota: {}", opt.work_dir_quota);
+    info!("min_free_disk_space: {}", opt.min_free_disk_space);
     info!("concurrent_tasks: {}", opt.concurrent_tasks);
+    info!("morsel_workers: {}", opt.morsel_workers);
+    info!("query_memory_limit: {}", opt.query_memory_limit);
+    info!("cpu_threads: {}", opt.cpu_threads);
+    info!("io_threads: {}", opt.io_threads);
+    info!("shuffle_format: {}", opt.shuffle_format);
+    info!(
+        "shuffle_fetch_concurrency: {}",
+        opt.shuffle_fetch_concurrency
+    );
+    info!("shuffle_fetch_retries: {}", opt.shuffle_fetch_retries);
+    info!("task_log_lines: {}", opt.task_log_lines);
+    info!("sandbox_udfs: {}", opt.sandbox_udfs);
+
+    set_shuffle_fetch_options(ShuffleFetchOptions {
+        max_concurrent_fetches: opt.shuffle_fetch_concurrency,
+        max_retries: opt.shuffle_fetch_retries,
+        retry_backoff: Duration::from_millis(opt.shuffle_fetch_retry_backoff_ms),
+    });
+
+    let mut executor_labels = match &opt.pod_labels_file {
+        Some(path) => labels::read_pod_labels(Path::new(path))?,
+        None => BTreeMap::new(),
+    };
+    if let Some(labels) = &opt.labels {
+        executor_labels.extend(labels::parse_labels(labels)?);
+    }
+    if let Some(zone) = opt.zone {
+        executor_labels.insert(ZONE_LABEL.to_owned(), zone);
+    }
+    info!("labels: {:?}", executor_labels);
 
     let executor_meta = ExecutorRegistration {
-        id: Uuid::new_v4().to_string(), // assign this executor a unique ID
+        // assign this executor a unique ID, unless it is given a stable one
+        id: opt
+            .executor_id
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
         optional_host: external_host
             .clone()
             .map(executor_registration::OptionalHost::Host),
         port: port as u32,
+        labels: executor_labels
+            .into_iter()
+            .map(|(key, value)| KeyValuePair { key, value })
+            .collect(),
     };
 
     let scheduler = SchedulerGrpcClient::connect(scheduler_url)
         .await
         .context("Could not connect to scheduler")?;
+    // the shuffle readers of the tasks of a scheduler tracking map outputs query it
+    set_map_output_tracker(Arc::new(SchedulerMapOutputTracker::new(scheduler.clone())));
 
-    let executor = Arc::new(Executor::new(&work_dir));
+    let executor = match opt.morsel_workers {
+        0 => Executor::new(&work_dir),
+        workers => Executor::new(&work_dir).with_morsel_workers(workers),
+    };
+    let executor = match opt.query_memory_limit {
+        0 => executor,
+        limit => executor.with_query_memory_limit(limit),
+    };
+    let executor = executor.with_disk_quota(DiskQuota {
+        max_work_dir_bytes: opt.work_dir_quota,
+        min_free_bytes: opt.min_free_disk_space,
+    });
+    let shuffle_format: ShuffleFormat = opt.shuffle_format.parse()?;
+    let executor = executor
+        .with_shuffle_format(shuffle_format)
+        .with_shuffle_checksums(opt.shuffle_checksums);
+    let executor = if opt.sandbox_udfs {
+        let program = std::env::current_exe()
+            .context("Could not find the executable of the executor")?;
+        executor.with_udf_sandbox(program)
+    } else {
+        executor
+    };
+    let executor = match opt.task_debug_dir {
+        Some(dir) => executor.with_task_debug_dir(dir),
+        None => executor,
+    };
+    let executor = match opt.shuffle_encryption_key_file {
+        Some(key_file) => {
+            let key = std::fs::read_to_string(&key_file).with_context(|| {
+                format!("Could not read the encryption key file {}", key_file)
+            })?;
+            let key_provider =
+                StaticKeyProvider::from_hex(opt.shuffle_encryption_key_id, &key)?;
+            info!("shuffle encryption key: {:?}", key_provider);
+            executor
+                .with_shuffle_encryption(ShuffleEncryption::new(Arc::new(key_provider)))
+        }
+        None => executor,
+    };
+    let executor = Arc::new(executor);
 
-    let service = BallistaFlightService::new(executor.clone());
+    // the record batches pushed with DoPut are appended to datasets of the scheduler
+    let service = BallistaFlightService::new(executor.clone())
+        .with_scheduler(scheduler.clone(), executor_meta.id.clone());
 
     let server = FlightServiceServer::new(service);
+    // the scheduler queries the logs of the tasks
+    let executor_server =
+        ExecutorGrpcServer::new(BallistaExecutorService::new(task_logs));
     info!(
         "Ballista v{} Rust Executor listening on {:?}",
         BALLISTA_VERSION, addr
     );
-    let server_future = tokio::spawn(Server::builder().add_service(server).serve(addr));
+    let server_future = tokio::spawn(
+        Server::builder()
+            .add_service(server)
+            .add_service(executor_server)
+            .serve(addr),
+    );
     tokio::spawn(execution_loop::poll_loop(
         scheduler,
         executor,
@@ -0,0 +1,682 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Sandboxing of the plans calling user defined functions (UDFs and UDAFs).
+//!
+//! UDFs are plugins registered in the executor process, which a crash or a leak of
+//! one of them would take down along with all its tasks. The executor can rather
+//! run the projections, filters and aggregations calling UDFs in copies of itself,
+//! started with the [`SANDBOX_ENV`] environment variable set, which must call
+//! [`run_sandbox_process`] after registering their UDFs.
+//!
+//! The process connects back to the executor on the loopback address given by
+//! [`SANDBOX_ENV`] and presents the token given by [`SANDBOX_TOKEN_ENV`], its
+//! standard output and error being left to the UDFs, which may print to them. The
+//! executor writes to the connection the plan to run, with an empty input, then the
+//! record batches of its input, and the process writes back the record batches it
+//! outputs. The record batches are exchanged as Arrow IPC messages, in flight data,
+//! each message being prefixed by its length. A stream ends with an empty message,
+//! so that a stream cut short by a crash is never taken for a complete one.
+
+use std::any::Any;
+use std::convert::{TryFrom, TryInto};
+use std::io;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use arrow_flight::utils::flight_data_from_arrow_batch;
+use arrow_flight::FlightData;
+use async_trait::async_trait;
+use ballista_core::client::flight_data_stream;
+use ballista_core::error::{BallistaError, Result};
+use ballista_core::serde::protobuf::{
+    self, physical_expr_node::ExprType, PhysicalPlanNode,
+};
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::ipc::writer::IpcWriteOptions;
+use datafusion::error::DataFusionError;
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::hash_aggregate::HashAggregateExec;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::stream::RecordBatchReceiverStream;
+use datafusion::physical_plan::streaming::StreamingExec;
+use datafusion::physical_plan::{
+    DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+    SendableRecordBatchStream, Statistics,
+};
+use futures::{Stream, StreamExt};
+use prost::Message;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::Command;
+use uuid::Uuid;
+
+/// Environment variable set in the processes started by the executor to run the
+/// sandboxed plans, to the address they connect to
+pub const SANDBOX_ENV: &str = "BALLISTA_SANDBOX";
+
+/// Environment variable set in the processes started by the executor to run the
+/// sandboxed plans, to the token they present when connecting
+pub const SANDBOX_TOKEN_ENV: &str = "BALLISTA_SANDBOX_TOKEN";
+
+/// How long a connection to the executor has to present its token
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether this process was started by an executor to run a sandboxed plan
+pub fn is_sandbox_process() -> bool {
+    std::env::var_os(SANDBOX_ENV).is_some()
+}
+
+/// Connects to the executor which started this process, runs the plan it sends and
+/// sends back its output
+pub fn run_sandbox_process() -> Result<()> {
+    let env = |name: &str| {
+        std::env::var(name).map_err(|e| {
+            BallistaError::General(format!("Invalid {} variable: {}", name, e))
+        })
+    };
+    let address = env(SANDBOX_ENV)?;
+    let token = env(SANDBOX_TOKEN_ENV)?;
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async {
+        let mut stream = TcpStream::connect(address.as_str()).await?;
+        stream.write_all(token.as_bytes()).await?;
+        let (reader, writer) = stream.into_split();
+        run_sandboxed_plan(reader, writer).await
+    })
+}
+
+/// Runs the plan read from `input`, whose single child is replaced by the record
+/// batches read from `input` after it, and writes its output to `output`
+pub async fn run_sandboxed_plan<R, W>(mut input: R, mut output: W) -> Result<()>
+where
+    R: AsyncRead + Send + Unpin + 'static,
+    W: AsyncWrite + Unpin,
+{
+    let plan: PhysicalPlanNode = read_frame(&mut input).await?.ok_or_else(|| {
+        BallistaError::General("The executor did not send the plan to run".to_owned())
+    })?;
+    let plan: Arc<dyn ExecutionPlan> = (&plan).try_into()?;
+    let input_schema = match plan.children().as_slice() {
+        [child] => child.schema(),
+        _ => {
+            return Err(BallistaError::Internal(
+                "A sandboxed plan must have a single child".to_owned(),
+            ))
+        }
+    };
+    let batches = flight_data_stream(frame_stream(input), input_schema.clone());
+    let batches = StreamingExec::new(input_schema, Arc::new(Mutex::new(Some(batches))));
+    let plan = plan.with_new_children(vec![Arc::new(batches)])?;
+    write_batches(plan.execute(0).await?, &mut output).await
+}
+
+/// Wraps the projections, filters and aggregations of `plan` calling UDFs or UDAFs
+/// in [`SandboxExec`]s, which run them in processes started from `program`
+pub fn sandbox_udfs(
+    plan: Arc<dyn ExecutionPlan>,
+    program: &Path,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let children = plan.children();
+    let new_children = children
+        .iter()
+        .map(|child| sandbox_udfs(child.clone(), program))
+        .collect::<Result<Vec<_>>>()?;
+    let changed = children
+        .iter()
+        .zip(&new_children)
+        .any(|(child, new_child)| !Arc::ptr_eq(child, new_child));
+    let plan = if changed {
+        plan.with_new_children(new_children)?
+    } else {
+        plan
+    };
+    if calls_udf(plan.as_ref())? {
+        Ok(Arc::new(SandboxExec::new(plan, program)))
+    } else {
+        Ok(plan)
+    }
+}
+
+/// Whether the expressions of `plan` call UDFs or UDAFs, for the plans which can
+/// be sandboxed
+fn calls_udf(plan: &dyn ExecutionPlan) -> Result<bool> {
+    let any = plan.as_any();
+    // the expressions are looked into through their serialized form, which
+    // refers to the UDFs by name
+    let exprs = if let Some(projection) = any.downcast_ref::<ProjectionExec>() {
+        projection
+            .expr()
+            .iter()
+            .map(|(expr, _)| protobuf::PhysicalExprNode::try_from(expr.clone()))
+            .collect::<Result<Vec<_>>>()?
+    } else if let Some(filter) = any.downcast_ref::<FilterExec>() {
+        vec![protobuf::PhysicalExprNode::try_from(
+            filter.predicate().clone(),
+        )?]
+    } else if let Some(aggregate) = any.downcast_ref::<HashAggregateExec>() {
+        let group_expr = aggregate
+            .group_expr()
+            .iter()
+            .map(|(expr, _)| protobuf::PhysicalExprNode::try_from(expr.clone()));
+        let aggr_expr = aggregate
+            .aggr_expr()
+            .iter()
+            .map(|expr| TryInto::<protobuf::PhysicalExprNode>::try_into(expr.clone()));
+        group_expr.chain(aggr_expr).collect::<Result<Vec<_>>>()?
+    } else {
+        return Ok(false);
+    };
+    Ok(exprs.iter().any(expr_calls_udf))
+}
+
+fn expr_calls_udf(expr: &protobuf::PhysicalExprNode) -> bool {
+    let sort_calls_udf = |exprs: &[protobuf::PhysicalSortExprNode]| {
+        exprs
+            .iter()
+            .any(|e| e.expr.as_deref().map_or(false, expr_calls_udf))
+    };
+    match &expr.expr_type {
+        Some(ExprType::ScalarUdfExpr(_)) | Some(ExprType::AggregateUdfExpr(_)) => true,
+        Some(ExprType::Column(_)) | Some(ExprType::Literal(_)) | None => false,
+        Some(ExprType::BinaryExpr(e)) => {
+            e.l.as_deref()
+                .into_iter()
+                .chain(e.r.as_deref())
+                .any(expr_calls_udf)
+        }
+        Some(ExprType::DateTimeIntervalExpr(e)) => {
+            e.l.as_deref()
+                .into_iter()
+                .chain(e.r.as_deref())
+                .any(expr_calls_udf)
+        }
+        Some(ExprType::AggregateExpr(e)) => {
+            e.expr
+                .as_deref()
+                .into_iter()
+                .chain(e.filter.as_deref())
+                .chain(&e.args)
+                .any(expr_calls_udf)
+                || sort_calls_udf(&e.order_by)
+        }
+        Some(ExprType::WindowExpr(e)) => {
+            e.expr
+                .as_deref()
+                .into_iter()
+                .chain(&e.args)
+                .chain(&e.partition_by)
+                .any(expr_calls_udf)
+                || sort_calls_udf(&e.order_by)
+        }
+        Some(ExprType::Case(e)) => e
+            .expr
+            .as_deref()
+            .into_iter()
+            .chain(e.when_then_expr.iter().flat_map(|when_then| {
+                when_then
+                    .when_expr
+                    .as_ref()
+                    .into_iter()
+                    .chain(when_then.then_expr.as_ref())
+            }))
+            .chain(e.else_expr.as_deref())
+            .any(expr_calls_udf),
+        Some(ExprType::InList(e)) => e
+            .expr
+            .as_deref()
+            .into_iter()
+            .chain(&e.list)
+            .any(expr_calls_udf),
+        Some(ExprType::ScalarFunction(e)) => e.args.iter().any(expr_calls_udf),
+        Some(ExprType::IsNullExpr(e)) => e.expr.as_deref().map_or(false, expr_calls_udf),
+        Some(ExprType::IsNotNullExpr(e)) => {
+            e.expr.as_deref().map_or(false, expr_calls_udf)
+        }
+        Some(ExprType::NotExpr(e)) => e.expr.as_deref().map_or(false, expr_calls_udf),
+        Some(ExprType::Cast(e)) => e.expr.as_deref().map_or(false, expr_calls_udf),
+        Some(ExprType::TryCast(e)) => e.expr.as_deref().map_or(false, expr_calls_udf),
+        Some(ExprType::Sort(e)) => e.expr.as_deref().map_or(false, expr_calls_udf),
+        Some(ExprType::Negative(e)) => e.expr.as_deref().map_or(false, expr_calls_udf),
+    }
+}
+
+/// Execution plan running a plan with a single child in a process started from
+/// `program`, which is sent the output of the child
+#[derive(Debug)]
+pub struct SandboxExec {
+    plan: Arc<dyn ExecutionPlan>,
+    program: PathBuf,
+}
+
+impl SandboxExec {
+    /// Create a new SandboxExec running `plan` in processes started from `program`
+    pub fn new(plan: Arc<dyn ExecutionPlan>, program: impl Into<PathBuf>) -> Self {
+        Self {
+            plan,
+            program: program.into(),
+        }
+    }
+
+    /// The sandboxed plan
+    pub fn plan(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.plan
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for SandboxExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.plan.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.plan.output_partitioning()
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        self.plan.required_child_distribution()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        self.plan.children()
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(SandboxExec::new(
+            self.plan.with_new_children(children)?,
+            self.program.clone(),
+        )))
+    }
+
+    async fn execute(
+        &self,
+        partition: usize,
+    ) -> datafusion::error::Result<SendableRecordBatchStream> {
+        let input = self.plan.children()[0].clone();
+        // the process replaces the empty input with the batches it is sent
+        let plan: PhysicalPlanNode = self
+            .plan
+            .with_new_children(vec![Arc::new(EmptyExec::new(false, input.schema()))])?
+            .try_into()
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let input = input.execute(partition).await?;
+
+        // the batches are exchanged over a dedicated connection, as the UDFs may
+        // print to the standard output of the process
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let token = Uuid::new_v4().to_string();
+        let mut child = Command::new(&self.program)
+            .env(SANDBOX_ENV, listener.local_addr()?.to_string())
+            .env(SANDBOX_TOKEN_ENV, &token)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                DataFusionError::Execution(format!(
+                    "Could not start the sandbox process {}: {}",
+                    self.program.display(),
+                    e
+                ))
+            })?;
+
+        let schema = self.schema();
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        let join_handle = tokio::spawn(async move {
+            let connected = tokio::select! {
+                stream = accept_sandbox(&listener, &token) => stream,
+                status = child.wait() => Err(match status {
+                    Ok(status) => BallistaError::General(format!(
+                        "The sandbox process exited with {} before connecting",
+                        status
+                    )),
+                    Err(e) => e.into(),
+                }),
+            };
+            let written = match connected {
+                Ok(stream) => {
+                    let (reader, mut writer) = stream.into_split();
+                    // the input is written while the output is read, as the plan may
+                    // output batches before reading all its input
+                    let write_input = async move {
+                        write_frame(&mut writer, &plan).await?;
+                        write_batches(input, &mut writer).await
+                    };
+                    let mut output = flight_data_stream(frame_stream(reader), schema);
+                    let read_output = async {
+                        while let Some(batch) = output.next().await {
+                            if tx.send(batch).await.is_err() {
+                                // the receiver has been dropped
+                                break;
+                            }
+                        }
+                    };
+                    let (written, _) = futures::join!(write_input, read_output);
+                    written
+                }
+                Err(e) => Err(e),
+            };
+
+            let error = match child.wait().await {
+                Ok(status) if status.success() => written.err(),
+                Ok(status) => Some(BallistaError::General(format!(
+                    "The sandbox process failed with {}",
+                    status
+                ))),
+                Err(e) => Some(e.into()),
+            };
+            if let Some(e) = error {
+                let e = ArrowError::ExternalError(Box::new(e));
+                tx.send(Err(e)).await.ok();
+            }
+        });
+        Ok(RecordBatchReceiverStream::create(
+            &self.schema(),
+            rx,
+            join_handle,
+        ))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        write!(f, "SandboxExec: ")?;
+        self.plan.fmt_as(t, f)
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.plan.statistics()
+    }
+}
+
+/// Accepts the connection of the sandbox process presenting `token`, ignoring the
+/// other local processes connecting to `listener`
+async fn accept_sandbox(listener: &TcpListener, token: &str) -> Result<TcpStream> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut presented = vec![0; token.len()];
+        let read = tokio::time::timeout(TOKEN_TIMEOUT, stream.read_exact(&mut presented));
+        if let Ok(Ok(_)) = read.await {
+            if presented == token.as_bytes() {
+                return Ok(stream);
+            }
+        }
+    }
+}
+
+/// Writes `message` prefixed by its length
+async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &impl Message,
+) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(message.encoded_len());
+    message
+        .encode(&mut bytes)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    writer.write_u32_le(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await
+}
+
+/// Reads a message prefixed by its length, or returns `None` at the end of `reader`
+async fn read_frame<R: AsyncRead + Unpin, M: Message + Default>(
+    reader: &mut R,
+) -> io::Result<Option<M>> {
+    let len = match reader.read_u32_le().await {
+        Ok(len) => len,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut bytes = vec![0; len as usize];
+    reader.read_exact(&mut bytes).await?;
+    M::decode(bytes.as_slice())
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Writes the record batches of `stream` as flight data, followed by the empty
+/// message ending the stream
+async fn write_batches<W: AsyncWrite + Unpin>(
+    mut stream: SendableRecordBatchStream,
+    writer: &mut W,
+) -> Result<()> {
+    let options = IpcWriteOptions::default();
+    while let Some(batch) = stream.next().await {
+        let (dictionaries, batch) = flight_data_from_arrow_batch(&batch?, &options);
+        for data in dictionaries.iter().chain(std::iter::once(&batch)) {
+            write_frame(writer, data).await?;
+        }
+    }
+    write_frame(writer, &FlightData::default()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads the flight data written by [`write_batches`]
+fn frame_stream<R: AsyncRead + Send + Unpin + 'static>(
+    reader: R,
+) -> Pin<Box<dyn Stream<Item = io::Result<FlightData>> + Send>> {
+    Box::pin(futures::stream::unfold(Some(reader), |reader| async move {
+        let mut reader = reader?;
+        match read_frame::<_, FlightData>(&mut reader).await {
+            // the empty message ending the stream
+            Ok(Some(data)) if data.data_header.is_empty() => None,
+            Ok(Some(data)) => Some((Ok(data), Some(reader))),
+            Ok(None) => Some((
+                Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "The stream of record batches was cut short",
+                )),
+                None,
+            )),
+            Err(e) => Some((Err(e), None)),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ballista_core::serde::udaf::register_udaf;
+    use ballista_core::serde::udf::register_udf;
+    use datafusion::arrow::array::{ArrayRef, Float64Array, Int64Array, UInt64Array};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::logical_plan::{create_udaf, create_udf};
+    use datafusion::physical_plan::common::collect;
+    use datafusion::physical_plan::expressions::{col, AvgAccumulator};
+    use datafusion::physical_plan::functions::{make_scalar_function, Volatility};
+    use datafusion::physical_plan::hash_aggregate::AggregateMode;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::udaf::create_aggregate_expr;
+    use datafusion::physical_plan::udf;
+
+    /// Runs `plan` as the sandbox process would, with the output of its input
+    async fn run_sandboxed(plan: &Arc<dyn ExecutionPlan>) -> Result<Vec<RecordBatch>> {
+        let sandbox = plan.as_any().downcast_ref::<SandboxExec>().unwrap();
+        let input = plan.children()[0].clone();
+
+        // the messages exchanged by the executor and the sandbox process
+        let node: PhysicalPlanNode = sandbox
+            .plan()
+            .with_new_children(vec![Arc::new(EmptyExec::new(false, input.schema()))])?
+            .try_into()?;
+        let (mut to_sandbox, sandbox_input) = tokio::io::duplex(1 << 16);
+        let (sandbox_output, from_sandbox) = tokio::io::duplex(1 << 16);
+        let write_input = async move {
+            write_frame(&mut to_sandbox, &node).await?;
+            write_batches(input.execute(0).await?, &mut to_sandbox).await
+        };
+        let (written, ran) = futures::join!(
+            write_input,
+            run_sandboxed_plan(sandbox_input, sandbox_output)
+        );
+        written?;
+        ran?;
+
+        Ok(collect(flight_data_stream(
+            frame_stream(from_sandbox),
+            plan.schema(),
+        ))
+        .await?)
+    }
+
+    #[tokio::test]
+    async fn run_sandboxed_udaf() -> Result<()> {
+        let my_avg = create_udaf(
+            "sandboxed_avg",
+            DataType::Float64,
+            Arc::new(DataType::Float64),
+            Volatility::Immutable,
+            Arc::new(|| Ok(Box::new(AvgAccumulator::try_new(&DataType::Float64)?))),
+            Arc::new(vec![DataType::UInt64, DataType::Float64]),
+        );
+        register_udaf(my_avg.clone());
+
+        let schema =
+            Arc::new(Schema::new(vec![Field::new("a", DataType::Float64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0]))],
+        )?;
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None)?);
+        let aggregate: Arc<dyn ExecutionPlan> = Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            vec![],
+            vec![create_aggregate_expr(
+                &my_avg,
+                &[col("a", &schema)?],
+                &schema,
+                "avg",
+            )?],
+            input.clone(),
+            schema.clone(),
+        )?);
+        let plan = sandbox_udfs(aggregate, Path::new("ballista-executor"))?;
+
+        let output = run_sandboxed(&plan).await?;
+        assert_eq!(output.len(), 1);
+        let count = output[0].column(0);
+        let count = count.as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(count.value(0), 3);
+        let sum = output[0].column(1);
+        let sum = sum.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(sum.value(0), 6.0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_sandboxed_udf() -> Result<()> {
+        let my_double = create_udf(
+            "sandboxed_double",
+            vec![DataType::Int64],
+            Arc::new(DataType::Int64),
+            Volatility::Immutable,
+            make_scalar_function(|args: &[ArrayRef]| {
+                let values = args[0].as_any().downcast_ref::<Int64Array>().unwrap();
+                let doubled: Int64Array =
+                    values.iter().map(|v| v.map(|v| v * 2)).collect();
+                Ok(Arc::new(doubled) as ArrayRef)
+            }),
+        );
+        register_udf(my_double.clone());
+
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3]))],
+        )?;
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None)?);
+        let call = udf::create_physical_expr(&my_double, &[col("a", &schema)?], &schema)?;
+        let projection: Arc<dyn ExecutionPlan> = Arc::new(ProjectionExec::try_new(
+            vec![(call, "doubled".to_owned())],
+            input.clone(),
+        )?);
+        let plan = sandbox_udfs(projection, Path::new("ballista-executor"))?;
+
+        let output = run_sandboxed(&plan).await?;
+        assert_eq!(output.len(), 1);
+        let doubled = output[0].column(0);
+        let doubled = doubled.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(doubled.values(), &[2, 4, 6]);
+
+        // the plans not calling UDFs are left as they are
+        let projection: Arc<dyn ExecutionPlan> = Arc::new(ProjectionExec::try_new(
+            vec![(col("a", &schema)?, "a".to_owned())],
+            input,
+        )?);
+        let plan = sandbox_udfs(projection, Path::new("ballista-executor"))?;
+        assert!(plan.as_any().downcast_ref::<ProjectionExec>().is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn accept_sandbox_token() -> Result<()> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let address = listener.local_addr()?;
+        let token = Uuid::new_v4().to_string();
+        let connect = |presented: String, id: u8| async move {
+            let mut stream = TcpStream::connect(address).await?;
+            stream.write_all(presented.as_bytes()).await?;
+            stream.write_u8(id).await?;
+            io::Result::Ok(stream)
+        };
+
+        // another local process connecting with the wrong token is ignored
+        let _other = connect(Uuid::new_v4().to_string(), 1).await?;
+        let _sandbox = connect(token.clone(), 2).await?;
+        let mut stream = accept_sandbox(&listener, &token).await?;
+        assert_eq!(stream.read_u8().await?, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cut_short_stream() {
+        let (mut writer, reader) = tokio::io::duplex(1 << 16);
+        write_frame(&mut writer, &FlightData::default())
+            .await
+            .unwrap();
+        drop(writer);
+        // the empty message ends the stream
+        assert_eq!(frame_stream(reader).count().await, 0);
+
+        let (writer, reader) = tokio::io::duplex(1 << 16);
+        drop(writer);
+        let frames: Vec<_> = frame_stream(reader).collect().await;
+        assert_eq!(frames.len(), 1);
+        assert_eq!(
+            frames[0].as_ref().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+}
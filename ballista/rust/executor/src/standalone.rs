@@ -41,8 +41,10 @@ pub async fn new_standalone_executor(
         .into_string()
         .unwrap();
     let executor = Arc::new(Executor::new(&work_dir));
+    let executor_id = Uuid::new_v4().to_string(); // assign this executor a unique ID
 
-    let service = BallistaFlightService::new(executor.clone());
+    let service = BallistaFlightService::new(executor.clone())
+        .with_scheduler(scheduler.clone(), executor_id.clone());
 
     let server = FlightServiceServer::new(service);
     // Let the OS assign a random, free port
@@ -58,9 +60,10 @@ pub async fn new_standalone_executor(
         ),
     );
     let executor_meta = ExecutorRegistration {
-        id: Uuid::new_v4().to_string(), // assign this executor a unique ID
+        id: executor_id,
         optional_host: None,
         port: addr.port() as u32,
+        labels: vec![],
     };
     tokio::spawn(execution_loop::poll_loop(
         scheduler,
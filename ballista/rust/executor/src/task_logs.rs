@@ -0,0 +1,196 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Logs of the tasks run by the executor, kept in memory so that they can be
+//! retrieved through the scheduler, e.g. to debug a failed task without access
+//! to the executor.
+//!
+//! The records are attributed to the task whose future logs them, so the records
+//! logged by the futures spawned by its operators are only written to the logs of
+//! the executor.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use chrono::{SecondsFormat, Utc};
+use log::{Log, Metadata, Record, SetLoggerError};
+
+/// The number of tasks whose logs are kept, the logs of the oldest ones are dropped
+const MAX_LOGGED_TASKS: usize = 1000;
+
+tokio::task_local! {
+    /// The task being executed, see [task_key]
+    static CURRENT_TASK: String;
+}
+
+/// The key of the logs of a task
+pub fn task_key(job_id: &str, stage_id: usize, partition_id: usize) -> String {
+    format!("{}/{}/{}", job_id, stage_id, partition_id)
+}
+
+/// Runs `future`, executing the task with key `task_key`, attributing the records
+/// it logs to the task
+pub async fn scope<F: Future>(task_key: String, future: F) -> F::Output {
+    CURRENT_TASK.scope(task_key, future).await
+}
+
+/// The last lines logged by the most recent tasks
+pub struct TaskLogs {
+    /// The number of lines kept for each task
+    max_lines: usize,
+    logs: Mutex<LoggedTasks>,
+}
+
+#[derive(Default)]
+struct LoggedTasks {
+    /// The keys of the tasks in the order they first logged
+    tasks: VecDeque<String>,
+    lines: HashMap<String, VecDeque<String>>,
+}
+
+impl TaskLogs {
+    /// Keeps the last `max_lines` lines logged by each task
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            max_lines,
+            logs: Mutex::new(LoggedTasks::default()),
+        }
+    }
+
+    fn append(&self, task_key: &str, line: String) {
+        if self.max_lines == 0 {
+            return;
+        }
+        let mut logs = self.logs.lock().unwrap();
+        if !logs.lines.contains_key(task_key) {
+            if logs.tasks.len() == MAX_LOGGED_TASKS {
+                if let Some(oldest) = logs.tasks.pop_front() {
+                    logs.lines.remove(&oldest);
+                }
+            }
+            logs.tasks.push_back(task_key.to_owned());
+        }
+        let lines = logs.lines.entry(task_key.to_owned()).or_default();
+        if lines.len() == self.max_lines {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Returns the last `max_lines` lines logged by a task, or all the kept lines
+    /// if `max_lines` is 0
+    pub fn get(&self, task_key: &str, max_lines: usize) -> Vec<String> {
+        let logs = self.logs.lock().unwrap();
+        let lines = match logs.lines.get(task_key) {
+            Some(lines) => lines,
+            None => return vec![],
+        };
+        let skip = match max_lines {
+            0 => 0,
+            max_lines => lines.len().saturating_sub(max_lines),
+        };
+        lines.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// A logger writing the records like `env_logger` does, which also keeps the
+/// records logged by the tasks in their [TaskLogs]
+pub struct TaskLogger {
+    inner: env_logger::Logger,
+    logs: Arc<TaskLogs>,
+}
+
+impl Log for TaskLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.matches(record) {
+            let _ = CURRENT_TASK.try_with(|task_key| {
+                self.logs.append(
+                    task_key,
+                    format!(
+                        "[{} {} {}] {}",
+                        Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                        record.level(),
+                        record.target(),
+                        record.args()
+                    ),
+                )
+            });
+        }
+        self.inner.log(record)
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+/// Installs a [TaskLogger] keeping the logs of the tasks in `logs`, configured
+/// from the environment like `env_logger::init`
+pub fn init_logger(logs: Arc<TaskLogs>) -> Result<(), SetLoggerError> {
+    let inner = env_logger::Builder::from_default_env().build();
+    let max_level = inner.filter();
+    log::set_boxed_logger(Box::new(TaskLogger { inner, logs }))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn keep_task_logs() {
+        let logs = Arc::new(TaskLogs::new(2));
+        let logger = TaskLogger {
+            inner: env_logger::Builder::new()
+                .filter_level(log::LevelFilter::Info)
+                .build(),
+            logs: logs.clone(),
+        };
+        let log = |message: &str| {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("{}", message))
+                    .level(log::Level::Info)
+                    .target("test")
+                    .build(),
+            )
+        };
+
+        log("outside of a task");
+        scope(task_key("job", 1, 0), async {
+            log("first");
+            log("second");
+            log("third");
+        })
+        .await;
+        scope(task_key("job", 1, 1), async { log("other task") }).await;
+
+        let lines = logs.get("job/1/0", 0);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("INFO test] second"));
+        assert!(lines[1].ends_with("INFO test] third"));
+        assert_eq!(logs.get("job/1/0", 1), lines[1..].to_vec());
+        assert_eq!(logs.get("job/1/1", 0).len(), 1);
+        assert!(logs.get("job/1/2", 0).is_empty());
+    }
+}
@@ -10,9 +10,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::SchedulerServer;
+use std::collections::{BTreeMap, HashMap};
+
+use std::process::Stdio;
+
+use crate::api::plan_graph::job_graphviz;
+use crate::state::extract_job_id_from_task_key;
+use crate::{SchedulerServer, PRINCIPAL_METADATA_KEY};
+use ballista_core::serde::protobuf::execute_query_params::Query;
+use ballista_core::serde::protobuf::executor_grpc_client::ExecutorGrpcClient;
+use ballista_core::serde::protobuf::scheduler_grpc_server::SchedulerGrpc;
+use ballista_core::serde::protobuf::{
+    job_status, task_status, CompletedJob, CompletedTask, ExecuteQueryParams, FailedJob,
+    FailedTask, GetJobStatusParams, GetTaskLogsParams, KeyValuePair, PartitionId,
+    PartitionLocation, RunningJob, RunningTask,
+};
 use ballista_core::BALLISTA_VERSION;
-use warp::Rejection;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tonic::metadata::MetadataValue;
+use tonic::Code;
+use warp::http::{HeaderMap, StatusCode};
+use warp::{Rejection, Reply};
 
 #[derive(Debug, serde::Serialize)]
 struct StateResponse {
@@ -26,13 +45,31 @@ pub struct ExecutorMetaResponse {
     pub id: String,
     pub host: String,
     pub port: u16,
+    /// Labels reported by the executor, such as the labels of its Kubernetes pod
+    pub labels: BTreeMap<String, String>,
+    /// Topology zone of the node the executor runs on
+    pub zone: Option<String>,
     pub last_seen: u128,
+    /// Disk space used by the work dir, if reported by the executor
+    pub disk_usage: Option<DiskUsageResponse>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DiskUsageResponse {
+    pub work_dir_bytes: u64,
+    /// Max size of the work dir, 0 for no limit
+    pub quota_bytes: u64,
+    pub available_bytes: u64,
+    pub capacity_bytes: u64,
+    /// Whether the executor refuses new tasks for lack of disk space
+    pub disk_pressure: bool,
 }
 
 pub(crate) async fn scheduler_state(
     data_server: SchedulerServer,
 ) -> Result<impl warp::Reply, Rejection> {
     // TODO: Display last seen information in UI
+    let executor_disk = data_server.executor_disk.read().unwrap().clone();
     let executors: Vec<ExecutorMetaResponse> = data_server
         .state
         .get_executors_metadata()
@@ -40,9 +77,20 @@ pub(crate) async fn scheduler_state(
         .unwrap_or_default()
         .into_iter()
         .map(|(metadata, duration)| ExecutorMetaResponse {
+            zone: metadata.zone().map(|zone| zone.to_owned()),
+            disk_usage: executor_disk
+                .get(&metadata.id)
+                .map(|usage| DiskUsageResponse {
+                    work_dir_bytes: usage.work_dir_bytes,
+                    quota_bytes: usage.quota_bytes,
+                    available_bytes: usage.available_bytes,
+                    capacity_bytes: usage.capacity_bytes,
+                    disk_pressure: usage.disk_pressure,
+                }),
             id: metadata.id,
             host: metadata.host,
             port: metadata.port,
+            labels: metadata.labels,
             last_seen: duration.as_millis(),
         })
         .collect();
@@ -53,3 +101,435 @@ pub(crate) async fn scheduler_state(
     };
     Ok(warp::reply::json(&response))
 }
+
+#[derive(Debug, Default, serde::Serialize)]
+pub struct JobResponse {
+    pub id: String,
+    pub status: &'static str,
+    /// Labels attached to the job by the client, such as the user or team
+    pub labels: BTreeMap<String, String>,
+    pub completed_tasks: usize,
+    pub output_rows: u64,
+    pub output_bytes: u64,
+    /// Memory used by the running tasks of the job on all the executors
+    pub memory_used_bytes: u64,
+    /// Sum of the peak memory used by the tasks of the job on each executor
+    pub peak_memory_bytes: u64,
+}
+
+/// Lists the jobs with their labels and the output of their completed tasks,
+/// to attribute the cost of jobs to the labels
+pub(crate) async fn jobs(
+    data_server: SchedulerServer,
+) -> Result<impl warp::Reply, Rejection> {
+    let state = &data_server.state;
+    let mut jobs = HashMap::new();
+    for (id, status) in state.get_jobs_metadata().await.unwrap_or_default() {
+        let status = job_status_name(&status.status);
+        let labels = state
+            .get_job_labels(&id)
+            .await
+            .map(|labels| {
+                labels
+                    .labels
+                    .into_iter()
+                    .map(|kv| (kv.key, kv.value))
+                    .collect()
+            })
+            .unwrap_or_default();
+        jobs.insert(
+            id.clone(),
+            JobResponse {
+                id,
+                status,
+                labels,
+                ..Default::default()
+            },
+        );
+    }
+
+    for (key, task) in state.get_all_tasks().await.unwrap_or_default() {
+        let job = extract_job_id_from_task_key(&key)
+            .ok()
+            .and_then(|job_id| jobs.get_mut(job_id));
+        if let (
+            Some(job),
+            Some(task_status::Status::Completed(CompletedTask { partitions, .. })),
+        ) = (job, task.status)
+        {
+            job.completed_tasks += 1;
+            for partition in partitions {
+                job.output_rows += partition.num_rows;
+                job.output_bytes += partition.num_bytes;
+            }
+        }
+    }
+
+    for (job_id, executors) in data_server.job_memory.read().unwrap().iter() {
+        if let Some(job) = jobs.get_mut(job_id) {
+            for usage in executors.values() {
+                job.memory_used_bytes += usage.used_bytes;
+                job.peak_memory_bytes += usage.peak_bytes;
+            }
+        }
+    }
+
+    let mut jobs: Vec<JobResponse> = jobs.into_values().collect();
+    jobs.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(warp::reply::json(&jobs))
+}
+
+fn job_status_name(status: &Option<job_status::Status>) -> &'static str {
+    match status {
+        Some(job_status::Status::Queued(_)) => "queued",
+        Some(job_status::Status::Running(_)) => "running",
+        Some(job_status::Status::Failed(_)) => "failed",
+        Some(job_status::Status::Completed(_)) => "completed",
+        None => "unknown",
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SubmitQueryRequest {
+    sql: String,
+    /// Settings of the query, such as `ballista.job.label.<name>` labels
+    #[serde(default)]
+    settings: BTreeMap<String, String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct SubmitQueryResponse {
+    job_id: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Submits a SQL query as a job, like the `ExecuteQuery` gRPC method, for clients
+/// without a gRPC client library such as browsers and curl
+pub(crate) async fn submit_query(
+    headers: HeaderMap,
+    request: SubmitQueryRequest,
+    data_server: SchedulerServer,
+) -> Result<impl Reply, Rejection> {
+    // the principal is only set by the authenticator of the scheduler, never taken
+    // from the header of the same name sent by the client
+    let principal = match data_server.http_authenticator() {
+        Some(authenticator) => match authenticator.authenticate(&headers) {
+            Ok(principal) => principal,
+            Err(status) => return Ok(error_reply(status)),
+        },
+        None => None,
+    };
+    let mut grpc_request = tonic::Request::new(ExecuteQueryParams {
+        query: Some(Query::Sql(request.sql)),
+        settings: request
+            .settings
+            .into_iter()
+            .map(|(key, value)| KeyValuePair { key, value })
+            .collect(),
+    });
+    if let Some(principal) = principal {
+        match MetadataValue::from_str(&principal) {
+            Ok(principal) => {
+                grpc_request
+                    .metadata_mut()
+                    .insert(PRINCIPAL_METADATA_KEY, principal);
+            }
+            Err(_) => {
+                let msg = format!("Invalid {} header", PRINCIPAL_METADATA_KEY);
+                return Ok(error_reply(tonic::Status::invalid_argument(msg)));
+            }
+        }
+    }
+    match data_server.execute_query(grpc_request).await {
+        Ok(response) => {
+            let response = SubmitQueryResponse {
+                job_id: response.into_inner().job_id,
+            };
+            Ok(warp::reply::with_status(
+                warp::reply::json(&response),
+                StatusCode::OK,
+            ))
+        }
+        Err(status) => Ok(error_reply(status)),
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct JobStatusResponse {
+    pub job_id: String,
+    pub status: &'static str,
+    /// Error of the job if it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Output partitions of the job computed so far, all of them once it completed,
+    /// which can be fetched from the executors with Arrow Flight
+    pub partitions: Vec<PartitionLocationResponse>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct PartitionLocationResponse {
+    pub stage_id: u32,
+    pub partition_id: u32,
+    pub executor_id: String,
+    pub host: String,
+    pub port: u32,
+    pub path: String,
+    pub num_rows: Option<i64>,
+    pub num_bytes: Option<i64>,
+}
+
+impl From<PartitionLocation> for PartitionLocationResponse {
+    fn from(location: PartitionLocation) -> Self {
+        let partition_id = location.partition_id.unwrap_or_default();
+        let executor = location.executor_meta.unwrap_or_default();
+        let stats = location.partition_stats;
+        Self {
+            stage_id: partition_id.stage_id,
+            partition_id: partition_id.partition_id,
+            executor_id: executor.id,
+            host: executor.host,
+            port: executor.port,
+            path: location.path,
+            num_rows: stats.as_ref().map(|stats| stats.num_rows),
+            num_bytes: stats.as_ref().map(|stats| stats.num_bytes),
+        }
+    }
+}
+
+/// Returns the status of a job, like the `GetJobStatus` gRPC method, with the
+/// locations of its output partitions computed so far
+pub(crate) async fn job_status(
+    job_id: String,
+    data_server: SchedulerServer,
+) -> Result<impl Reply, Rejection> {
+    let request = tonic::Request::new(GetJobStatusParams {
+        job_id: job_id.clone(),
+    });
+    let status = match data_server.get_job_status(request).await {
+        Ok(response) => response
+            .into_inner()
+            .status
+            .and_then(|status| status.status),
+        Err(status) => return Ok(error_reply(status)),
+    };
+    let mut response = JobStatusResponse {
+        job_id,
+        status: job_status_name(&status),
+        error: None,
+        partitions: vec![],
+    };
+    match status {
+        Some(job_status::Status::Failed(FailedJob { error })) => {
+            response.error = Some(error);
+        }
+        Some(job_status::Status::Running(RunningJob { partition_location }))
+        | Some(job_status::Status::Completed(CompletedJob { partition_location })) => {
+            response.partitions =
+                partition_location.into_iter().map(Into::into).collect();
+        }
+        _ => {}
+    }
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        StatusCode::OK,
+    ))
+}
+
+/// Converts the status of a failed gRPC method to a JSON error with the
+/// corresponding HTTP status code
+fn error_reply(status: tonic::Status) -> warp::reply::WithStatus<warp::reply::Json> {
+    let code = match status.code() {
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let response = ErrorResponse {
+        error: status.message().to_owned(),
+    };
+    warp::reply::with_status(warp::reply::json(&response), code)
+}
+
+/// Renders the DAG of the stages of a job, with the operators of each stage and the
+/// metrics reported by its completed tasks, as `plan.dot` in the DOT language or as
+/// `plan.svg`, which requires the `dot` command of graphviz on the scheduler
+pub(crate) async fn job_plan(
+    job_id: String,
+    file: String,
+    data_server: SchedulerServer,
+) -> Result<Box<dyn Reply>, Rejection> {
+    let not_found = |msg: String| -> Result<Box<dyn Reply>, Rejection> {
+        Ok(Box::new(warp::reply::with_status(
+            msg,
+            StatusCode::NOT_FOUND,
+        )))
+    };
+    if file != "plan.dot" && file != "plan.svg" {
+        return not_found(format!("Unknown file {}", file));
+    }
+    let state = &data_server.state;
+    let stages = match state.get_job_metadata(&job_id).await {
+        Ok(Some(_)) => state.get_job_stages(&job_id).await,
+        Ok(None) => return not_found(format!("Job {} does not exist", job_id)),
+        Err(e) => Err(e),
+    };
+    let dot = match stages {
+        Ok(stages) => job_graphviz(&job_id, &stages),
+        Err(e) => {
+            let msg = format!("Error reading the stages of job {}: {}", job_id, e);
+            return Ok(Box::new(warp::reply::with_status(
+                msg,
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )));
+        }
+    };
+    if file == "plan.dot" {
+        return Ok(Box::new(warp::reply::with_header(
+            dot,
+            "content-type",
+            "text/vnd.graphviz",
+        )));
+    }
+    match render_svg(&dot).await {
+        Ok(svg) => Ok(Box::new(warp::reply::with_header(
+            svg,
+            "content-type",
+            "image/svg+xml",
+        ))),
+        Err(e) => Ok(Box::new(warp::reply::with_status(
+            format!("Could not render the plan with graphviz: {}", e),
+            StatusCode::NOT_IMPLEMENTED,
+        ))),
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct LogsQuery {
+    /// Number of the last lines to return, all the lines kept by the executor if unset
+    lines: Option<u32>,
+}
+
+/// Returns the last log lines of a task, which are kept in memory by the executor
+/// that ran it, as plain text
+pub(crate) async fn task_logs(
+    job_id: String,
+    stage_id: u32,
+    partition_id: u32,
+    query: LogsQuery,
+    data_server: SchedulerServer,
+) -> Result<Box<dyn Reply>, Rejection> {
+    let reply = |msg: String, status: StatusCode| -> Result<Box<dyn Reply>, Rejection> {
+        Ok(Box::new(warp::reply::with_status(msg, status)))
+    };
+    let state = &data_server.state;
+    let task = match state
+        ._get_task_status(&job_id, stage_id as usize, partition_id as usize)
+        .await
+    {
+        Ok(task) => task,
+        Err(e) => {
+            let msg = format!("Error reading the status of the task: {}", e);
+            return reply(msg, StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let executor_id = match task.and_then(|task| task.status) {
+        Some(task_status::Status::Running(RunningTask { executor_id }))
+        | Some(task_status::Status::Completed(CompletedTask { executor_id, .. }))
+        | Some(task_status::Status::Failed(FailedTask { executor_id, .. }))
+            if !executor_id.is_empty() =>
+        {
+            executor_id
+        }
+        _ => {
+            let msg = format!(
+                "Task {}/{}/{} did not run on any executor",
+                job_id, stage_id, partition_id
+            );
+            return reply(msg, StatusCode::NOT_FOUND);
+        }
+    };
+    let executor = state
+        .get_executors_metadata()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(metadata, _)| metadata)
+        .find(|metadata| metadata.id == executor_id);
+    let executor = match executor {
+        Some(executor) => executor,
+        None => {
+            let msg = format!("Executor {} is no longer alive", executor_id);
+            return reply(msg, StatusCode::NOT_FOUND);
+        }
+    };
+
+    let params = GetTaskLogsParams {
+        task_id: Some(PartitionId {
+            job_id,
+            stage_id,
+            partition_id,
+        }),
+        max_lines: query.lines.unwrap_or_default(),
+    };
+    let url = format!("http://{}:{}", executor.host, executor.port);
+    let lines = match ExecutorGrpcClient::connect(url).await {
+        Ok(mut client) => client
+            .get_task_logs(params)
+            .await
+            .map(|response| response.into_inner().lines)
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match lines {
+        Ok(lines) => {
+            let mut logs = lines.join("\n");
+            logs.push('\n');
+            Ok(Box::new(warp::reply::with_header(
+                logs,
+                "content-type",
+                "text/plain",
+            )))
+        }
+        Err(e) => {
+            let msg = format!(
+                "Could not get the logs from executor {}: {}",
+                executor_id, e
+            );
+            reply(msg, StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+/// Renders a graph in the DOT language as SVG with the `dot` command
+async fn render_svg(dot: &str) -> std::io::Result<Vec<u8>> {
+    let mut child = Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // dot reads the whole graph before writing it, closing stdin ends the input
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(dot.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+    Ok(output.stdout)
+}
@@ -11,6 +11,7 @@
 // limitations under the License.
 
 mod handlers;
+mod plan_graph;
 
 use crate::SchedulerServer;
 use anyhow::Result;
@@ -19,8 +20,20 @@ use std::{
     task::{Context as TaskContext, Poll},
 };
 use warp::filters::BoxedFilter;
+use warp::http::HeaderMap;
 use warp::{Buf, Filter, Reply};
 
+/// Authenticates the requests submitting queries through the REST API, as the
+/// interceptors of the gRPC server do for the `ExecuteQuery` method
+pub trait HttpAuthenticator: Send + Sync {
+    /// Returns the principal submitting a query with the request `headers`, `None`
+    /// when the request is not authenticated, or an error to reject it
+    fn authenticate(
+        &self,
+        headers: &HeaderMap,
+    ) -> std::result::Result<Option<String>, tonic::Status>;
+}
+
 pub enum EitherBody<A, B> {
     Left(A),
     Right(B),
@@ -79,9 +92,246 @@ fn with_data_server(
     warp::any().map(move || db.clone())
 }
 
-pub fn get_routes(scheduler_server: SchedulerServer) -> BoxedFilter<(impl Reply,)> {
-    let routes = warp::path("state")
-        .and(with_data_server(scheduler_server))
+/// Max size of the body of a request submitting a query
+const MAX_QUERY_REQUEST_BYTES: u64 = 1024 * 1024;
+
+/// The routes of the REST API. The browsers let the pages served from `cors_origins`,
+/// as `scheme://host[:port]`, read the state of the scheduler and of its jobs, but
+/// not submit queries, which can't be submitted cross-origin.
+pub fn get_routes(
+    scheduler_server: SchedulerServer,
+    cors_origins: &[String],
+) -> BoxedFilter<(impl Reply,)> {
+    let state = warp::path("state")
+        .and(with_data_server(scheduler_server.clone()))
         .and_then(handlers::scheduler_state);
-    routes.boxed()
+    let job_plan = warp::path!("jobs" / String / String)
+        .and(with_data_server(scheduler_server.clone()))
+        .and_then(handlers::job_plan);
+    let task_logs = warp::path!("jobs" / String / "tasks" / u32 / u32 / "logs")
+        .and(warp::query::<handlers::LogsQuery>())
+        .and(with_data_server(scheduler_server.clone()))
+        .and_then(handlers::task_logs);
+    let submit_query = warp::path!("jobs")
+        .and(warp::post())
+        .and(warp::header::headers_cloned())
+        .and(warp::body::content_length_limit(MAX_QUERY_REQUEST_BYTES))
+        .and(warp::body::json())
+        .and(with_data_server(scheduler_server.clone()))
+        .and_then(handlers::submit_query);
+    let job_status = warp::path!("jobs" / String)
+        .and(warp::get())
+        .and(with_data_server(scheduler_server.clone()))
+        .and_then(handlers::job_status);
+    let jobs = warp::path!("jobs")
+        .and(warp::get())
+        .and(with_data_server(scheduler_server))
+        .and_then(handlers::jobs);
+    // the dashboards served from the allowed origins can read the API, but submitting
+    // queries, which run arbitrary SQL as the principal of the request, is left out
+    // of CORS
+    let cors = warp::cors()
+        .allow_origins(cors_origins.iter().map(String::as_str))
+        .allow_methods(vec!["GET"])
+        .allow_headers(vec!["content-type"]);
+    state
+        .or(task_logs)
+        .or(job_plan)
+        .or(job_status)
+        .or(jobs)
+        .with(cors)
+        .or(submit_query)
+        .boxed()
+}
+
+#[cfg(all(test, feature = "sled"))]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::Arc;
+
+    use warp::http::StatusCode;
+
+    use datafusion::error::{DataFusionError, Result};
+    use datafusion::logical_plan::LogicalPlan;
+    use warp::http::HeaderMap;
+
+    use super::{get_routes, HttpAuthenticator};
+    use crate::plan_hook::PlanHook;
+    use crate::state::StandaloneClient;
+    use crate::{SchedulerServer, PRINCIPAL_METADATA_KEY};
+
+    #[tokio::test]
+    async fn submit_query_and_poll_status() {
+        let scheduler = SchedulerServer::new(
+            Arc::new(StandaloneClient::try_new_temporary().unwrap()),
+            "default".to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        );
+        let routes = get_routes(scheduler, &[]);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/jobs")
+            .json(&query_body("SELECT 1"))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        let job_id = body
+            .strip_prefix("{\"job_id\":\"")
+            .and_then(|body| body.strip_suffix("\"}"))
+            .expect("Received no job id");
+
+        let response = warp::test::request()
+            .path(&format!("/jobs/{}", job_id))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(body.contains(&format!("\"job_id\":\"{}\"", job_id)));
+
+        let response = warp::test::request()
+            .path("/jobs/missing")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/jobs")
+            .json(&query_body("SELECT * FROM missing"))
+            .reply(&routes)
+            .await;
+        assert!(!response.status().is_success());
+        let body = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(body.starts_with("{\"error\":"));
+    }
+
+    #[tokio::test]
+    async fn cors_only_allows_reads_from_configured_origins() {
+        let scheduler = SchedulerServer::new(
+            Arc::new(StandaloneClient::try_new_temporary().unwrap()),
+            "default".to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        );
+        let routes = get_routes(scheduler, &["http://dashboard:8080".to_owned()]);
+
+        let response = warp::test::request()
+            .path("/jobs")
+            .header("origin", "http://dashboard:8080")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "http://dashboard:8080"
+        );
+
+        let response = warp::test::request()
+            .path("/jobs")
+            .header("origin", "http://elsewhere")
+            .reply(&routes)
+            .await;
+        assert!(!response.status().is_success());
+
+        // queries can't be submitted from the browsers of the other origins
+        let response = warp::test::request()
+            .method("OPTIONS")
+            .path("/jobs")
+            .header("origin", "http://dashboard:8080")
+            .header("access-control-request-method", "POST")
+            .reply(&routes)
+            .await;
+        assert!(!response.status().is_success());
+        assert!(!response
+            .headers()
+            .contains_key("access-control-allow-origin"));
+    }
+
+    /// Rejects the queries not submitted by `principal`
+    struct ExpectPrincipal(Option<&'static str>);
+
+    impl PlanHook for ExpectPrincipal {
+        fn rewrite(
+            &self,
+            plan: LogicalPlan,
+            principal: Option<&str>,
+        ) -> Result<LogicalPlan> {
+            if principal == self.0 {
+                Ok(plan)
+            } else {
+                Err(DataFusionError::Plan(format!(
+                    "Unexpected principal {:?}",
+                    principal
+                )))
+            }
+        }
+    }
+
+    /// Authenticates the requests with a `user` header
+    struct UserHeader {}
+
+    impl HttpAuthenticator for UserHeader {
+        fn authenticate(
+            &self,
+            headers: &HeaderMap,
+        ) -> std::result::Result<Option<String>, tonic::Status> {
+            match headers.get("user") {
+                Some(user) => Ok(Some(user.to_str().unwrap().to_owned())),
+                None => Err(tonic::Status::unauthenticated("No user header")),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn submit_query_as_authenticated_principal() {
+        let scheduler = |principal, authenticator: Option<UserHeader>| {
+            let scheduler = SchedulerServer::new(
+                Arc::new(StandaloneClient::try_new_temporary().unwrap()),
+                "default".to_owned(),
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+            )
+            .with_plan_hook(Arc::new(ExpectPrincipal(principal)));
+            match authenticator {
+                Some(authenticator) => {
+                    scheduler.with_http_authenticator(Arc::new(authenticator))
+                }
+                None => scheduler,
+            }
+        };
+
+        // the principal sent by the client is never trusted
+        let routes = get_routes(scheduler(None, None), &[]);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/jobs")
+            .header(PRINCIPAL_METADATA_KEY, "admin")
+            .json(&query_body("SELECT 1"))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let routes = get_routes(scheduler(Some("alice"), Some(UserHeader {})), &[]);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/jobs")
+            .header("user", "alice")
+            .header(PRINCIPAL_METADATA_KEY, "admin")
+            .json(&query_body("SELECT 1"))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/jobs")
+            .json(&query_body("SELECT 1"))
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    fn query_body(sql: &str) -> std::collections::HashMap<&'static str, &str> {
+        vec![("sql", sql)].into_iter().collect()
+    }
 }
@@ -0,0 +1,199 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Renders the stages of a job as a graph in the `DOT` language, which can be
+//! visualized using software from [`graphviz`](https://graphviz.org/)
+
+use std::collections::HashMap;
+
+use ballista_core::execution_plans::UnresolvedShuffleExec;
+use datafusion::physical_plan::metrics::MetricsSet;
+use datafusion::physical_plan::{DisplayFormatType, ExecutionPlan};
+
+use crate::state::StageInfo;
+
+/// Renders the DAG of the stages of a job, one cluster per stage holding the tree
+/// of its operators with their metrics, and a dashed edge from each shuffle read
+/// to the stage writing the shuffle
+pub(crate) fn job_graphviz(job_id: &str, stages: &[StageInfo]) -> String {
+    let mut output = format!("digraph {{\n  graph[label={}]\n", quoted(job_id));
+    let mut next_id = 0;
+    // the node of the root operator of each stage
+    let mut stage_roots = HashMap::new();
+    // the nodes reading the shuffle written by a stage, with the id of the stage
+    let mut shuffle_reads = vec![];
+    for stage in stages {
+        output.push_str(&format!(
+            "  subgraph cluster_stage_{}\n  {{\n",
+            stage.stage_id
+        ));
+        output.push_str(&format!(
+            "    graph[label={}]\n",
+            quoted(&format!(
+                "Stage {}: {}/{} tasks completed",
+                stage.stage_id, stage.completed_tasks, stage.tasks
+            ))
+        ));
+        let root = format_operator(
+            stage.plan.as_ref(),
+            None,
+            &mut stage.metrics.iter(),
+            &mut next_id,
+            &mut shuffle_reads,
+            &mut output,
+        );
+        stage_roots.insert(stage.stage_id, root);
+        output.push_str("  }\n");
+    }
+    for (id, stage_id) in shuffle_reads {
+        if let Some(root) = stage_roots.get(&stage_id) {
+            output.push_str(&format!(
+                "  {} -> {} [arrowhead=none, arrowtail=normal, dir=back, style=dashed]\n",
+                id, root
+            ));
+        }
+    }
+    output.push_str("}\n");
+    output
+}
+
+/// Writes the node of `plan` and the subtree of its children, returning its id
+fn format_operator<'a>(
+    plan: &dyn ExecutionPlan,
+    parent_id: Option<usize>,
+    metrics: &mut impl Iterator<Item = &'a MetricsSet>,
+    next_id: &mut usize,
+    shuffle_reads: &mut Vec<(usize, usize)>,
+    output: &mut String,
+) -> usize {
+    struct Operator<'a>(&'a dyn ExecutionPlan);
+
+    impl std::fmt::Display for Operator<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            self.0.fmt_as(DisplayFormatType::Default, f)
+        }
+    }
+
+    *next_id += 1;
+    let id = *next_id;
+    let mut label = Operator(plan).to_string();
+    if let Some(metrics) = metrics.next() {
+        let metrics = metrics.clone().sorted_for_display().timestamps_removed();
+        label = format!(r"{}\nmetrics=[{}]", label, metrics);
+    }
+    output.push_str(&format!("    {}[shape=box label={}]\n", id, quoted(&label)));
+    if let Some(parent_id) = parent_id {
+        output.push_str(&format!(
+            "    {} -> {} [arrowhead=none, arrowtail=normal, dir=back]\n",
+            parent_id, id
+        ));
+    }
+    if let Some(shuffle) = plan.as_any().downcast_ref::<UnresolvedShuffleExec>() {
+        shuffle_reads.push((id, shuffle.stage_id));
+    }
+    for child in plan.children() {
+        format_operator(
+            child.as_ref(),
+            Some(id),
+            metrics,
+            next_id,
+            shuffle_reads,
+            output,
+        );
+    }
+    id
+}
+
+fn quoted(label: &str) -> String {
+    format!("\"{}\"", label.replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ballista_core::execution_plans::ShuffleWriterExec;
+    use datafusion::arrow::datatypes::Schema;
+    use datafusion::physical_plan::coalesce_batches::CoalesceBatchesExec;
+    use datafusion::physical_plan::empty::EmptyExec;
+    use datafusion::physical_plan::metrics::{Count, Metric, MetricValue};
+
+    use super::*;
+
+    #[test]
+    fn render_job_stages() -> datafusion::error::Result<()> {
+        let schema = Arc::new(Schema::empty());
+        let writer = |stage_id, plan: Arc<dyn ExecutionPlan>| {
+            ShuffleWriterExec::try_new(
+                "job".to_owned(),
+                stage_id,
+                plan,
+                "".to_owned(),
+                None,
+            )
+            .map(Arc::new)
+        };
+        let output_rows = |rows| {
+            let count = Count::new();
+            count.add(rows);
+            let mut metrics = MetricsSet::new();
+            metrics.push(Arc::new(Metric::new(MetricValue::OutputRows(count), None)));
+            metrics
+        };
+        let stages = vec![
+            StageInfo {
+                stage_id: 1,
+                plan: writer(1, Arc::new(EmptyExec::new(false, schema.clone())))?,
+                tasks: 2,
+                completed_tasks: 2,
+                metrics: vec![output_rows(3), output_rows(3)],
+            },
+            StageInfo {
+                stage_id: 2,
+                plan: writer(
+                    2,
+                    Arc::new(CoalesceBatchesExec::new(
+                        Arc::new(UnresolvedShuffleExec::new(1, schema, 2, 1)),
+                        1024,
+                    )),
+                )?,
+                tasks: 1,
+                completed_tasks: 0,
+                metrics: vec![],
+            },
+        ];
+
+        let expected = r#"digraph {
+  graph[label="job"]
+  subgraph cluster_stage_1
+  {
+    graph[label="Stage 1: 2/2 tasks completed"]
+    1[shape=box label="ShuffleWriterExec: None\nmetrics=[output_rows=3]"]
+    2[shape=box label="EmptyExec: produce_one_row=false\nmetrics=[output_rows=3]"]
+    1 -> 2 [arrowhead=none, arrowtail=normal, dir=back]
+  }
+  subgraph cluster_stage_2
+  {
+    graph[label="Stage 2: 0/1 tasks completed"]
+    3[shape=box label="ShuffleWriterExec: None"]
+    4[shape=box label="CoalesceBatchesExec: target_batch_size=1024"]
+    3 -> 4 [arrowhead=none, arrowtail=normal, dir=back]
+    5[shape=box label="UnresolvedShuffleExec"]
+    4 -> 5 [arrowhead=none, arrowtail=normal, dir=back]
+  }
+  5 -> 1 [arrowhead=none, arrowtail=normal, dir=back, style=dashed]
+}
+"#;
+        assert_eq!(job_graphviz("job", &stages), expected);
+        Ok(())
+    }
+}
@@ -18,16 +18,23 @@
 #![doc = include_str!("../README.md")]
 
 pub mod api;
+pub mod plan_hook;
 pub mod planner;
 #[cfg(feature = "sled")]
 mod standalone;
 pub mod state;
+mod table_statistics;
 
+use datafusion::arrow::datatypes::SchemaRef;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::file_format::FileFormat;
-use datafusion::datasource::object_store::{local::LocalFileSystem, ObjectStore};
+use datafusion::datasource::listing::{pruned_partition_list, ListingTable};
+use datafusion::datasource::object_store::{
+    local::LocalFileSystem, FileMeta, ObjectStore,
+};
+use datafusion::datasource::PartitionedFile;
 
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
 
 #[cfg(feature = "sled")]
 pub use standalone::new_standalone_scheduler;
@@ -41,21 +48,36 @@ pub mod externalscaler {
     include!(concat!(env!("OUT_DIR"), "/externalscaler.rs"));
 }
 
-use std::{convert::TryInto, sync::Arc};
+use std::collections::HashMap;
+use std::{convert::TryInto, sync::Arc, sync::RwLock};
 use std::{fmt, net::IpAddr};
 
 use ballista_core::serde::protobuf::{
     execute_query_params::Query, executor_registration::OptionalHost, job_status,
-    scheduler_grpc_server::SchedulerGrpc, task_status, ExecuteQueryParams,
-    ExecuteQueryResult, FailedJob, FileType, GetFileMetadataParams,
-    GetFileMetadataResult, GetJobStatusParams, GetJobStatusResult, JobStatus,
-    PartitionId, PollWorkParams, PollWorkResult, QueuedJob, RunningJob, TaskDefinition,
-    TaskStatus,
+    scheduler_grpc_server::SchedulerGrpc, task_status, AppendDatasetParams,
+    AppendDatasetResult, CancelJobParams, CancelJobResult, CompletedJob,
+    ExecuteQueryParams, ExecuteQueryResult, ExecutorDiskUsage, FailedJob, FileType,
+    GetDatasetParams, GetDatasetResult, GetFileMetadataParams, GetFileMetadataResult,
+    GetJobMetricsParams, GetJobMetricsResult, GetJobStatusParams, GetJobStatusResult,
+    GetMapOutputsParams, GetMapOutputsResult, GetTableStatisticsParams,
+    GetTableStatisticsResult, JobLabels, JobMemoryUsage, JobStatus, JobSummary,
+    KeyValuePair, ListJobsParams, ListJobsResult, MessageChunk, PartitionId,
+    PersistDatasetParams, PersistDatasetResult, PhysicalPlanNode, PollWorkParams,
+    PollWorkResult, QueuedJob, RunningJob, StageMetrics, TaskDefinition, TaskStatus,
 };
 use ballista_core::serde::scheduler::ExecutorMeta;
 
+use api::HttpAuthenticator;
 use clap::arg_enum;
-use datafusion::physical_plan::ExecutionPlan;
+use datafusion::catalog::authorization::TableAuthorizer;
+use datafusion::catalog::TableReference;
+use datafusion::error::DataFusionError;
+use datafusion::logical_plan::plan::{Explain, StringifiedPlan};
+use datafusion::logical_plan::{ExplainFormat, LogicalPlan, PlanType};
+use datafusion::physical_plan::explain::ExplainExec;
+use datafusion::physical_plan::{displayable, ExecutionPlan, Statistics};
+use plan_hook::PlanHook;
+use table_statistics::TableStatisticsCache;
 #[cfg(feature = "sled")]
 extern crate sled_package as sled;
 
@@ -83,20 +105,76 @@ use crate::planner::DistributedPlanner;
 
 use log::{debug, error, info, warn};
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
-use tonic::{Request, Response, Status};
+use tonic::{Request, Response, Status, Streaming};
 
-use self::state::{ConfigBackendClient, SchedulerState};
+use self::state::{
+    ConfigBackendClient, InMemoryJobQueue, JobQueue, SchedulerState, SubmittedJob,
+};
 use ballista_core::config::BallistaConfig;
+use ballista_core::error::BallistaError;
 use ballista_core::execution_plans::ShuffleWriterExec;
+use ballista_core::message_chunks::decode_chunks;
 use ballista_core::serde::scheduler::to_proto::hash_partitioning_to_proto;
 use datafusion::prelude::{ExecutionConfig, ExecutionContext};
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// How long `poll_work` waits for a task to become schedulable when none can be
+/// assigned to the polling executor, before returning without a task
+const POLL_WORK_WAIT: Duration = Duration::from_millis(100);
+
+/// Time to wait before taking a job from the job queue again after failing to
+const JOB_QUEUE_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The gRPC metadata key holding the principal submitting a query, which is
+/// passed to the table authorizer of the scheduler. It must be set by an
+/// interceptor that authenticated the request, and never trusted as sent by
+/// clients.
+pub const PRINCIPAL_METADATA_KEY: &str = "ballista-principal";
+
+/// Probes the executors registered in the config backend every `interval`, removing
+/// the ones that cannot be reached so that their tasks are rescheduled without
+/// waiting for their heartbeats to expire.
+///
+/// The future returned by this function never returns, so it is wise
+/// to [tokio::spawn] calls to this function.
+pub async fn probe_executors_loop(
+    config: Arc<dyn ConfigBackendClient>,
+    namespace: String,
+    interval: Duration,
+) {
+    SchedulerState::new(config, namespace)
+        .probe_executors_loop(interval)
+        .await
+}
 
 #[derive(Clone)]
 pub struct SchedulerServer {
     caller_ip: IpAddr,
     pub(crate) state: Arc<SchedulerState>,
     start_time: u128,
+    table_authorizer: Option<Arc<dyn TableAuthorizer>>,
+    plan_hooks: Vec<Arc<dyn PlanHook>>,
+    /// Authenticates the queries submitted through the REST API
+    http_authenticator: Option<Arc<dyn HttpAuthenticator>>,
+    /// Whether shuffle readers query the locations of their partitions when executed
+    track_map_outputs: bool,
+    /// Memory used by the tasks of each job, by job id and executor id
+    pub(crate) job_memory: Arc<RwLock<HashMap<String, HashMap<String, JobMemoryUsage>>>>,
+    /// Disk space used by the work dir of each executor, by executor id
+    pub(crate) executor_disk: Arc<RwLock<HashMap<String, ExecutorDiskUsage>>>,
+    /// Notified when tasks may have become schedulable, because the stages of a job
+    /// were planned or tasks ended, waking up the executors waiting for work
+    tasks_notify: Arc<Notify>,
+    /// The jobs submitted to the scheduler, waiting to be planned
+    job_queue: Arc<dyn JobQueue>,
+    /// How long `poll_work` waits for a task to become schedulable
+    poll_work_wait: Duration,
+    /// The context the contexts planning the SQL queries are copied from
+    sql_context: ExecutionContext,
+    /// The schemas and the file statistics of the tables whose statistics were
+    /// requested
+    table_statistics: Arc<TableStatisticsCache>,
 }
 
 impl SchedulerServer {
@@ -104,6 +182,22 @@ impl SchedulerServer {
         config: Arc<dyn ConfigBackendClient>,
         namespace: String,
         caller_ip: IpAddr,
+    ) -> Self {
+        Self::new_with_job_queue(
+            config,
+            namespace,
+            caller_ip,
+            Arc::new(InMemoryJobQueue::new()),
+        )
+    }
+
+    /// Creates a scheduler planning the jobs taken from `job_queue`, into which the
+    /// jobs submitted to it are pushed
+    pub fn new_with_job_queue(
+        config: Arc<dyn ConfigBackendClient>,
+        namespace: String,
+        caller_ip: IpAddr,
+        job_queue: Arc<dyn JobQueue>,
     ) -> Self {
         let state = Arc::new(SchedulerState::new(config, namespace));
         let state_clone = state.clone();
@@ -111,6 +205,13 @@ impl SchedulerServer {
         // TODO: we should elect a leader in the scheduler cluster and run this only in the leader
         tokio::spawn(async move { state_clone.synchronize_job_status_loop().await });
 
+        let tasks_notify = Arc::new(Notify::new());
+        tokio::spawn(plan_queued_jobs_loop(
+            state.clone(),
+            job_queue.clone(),
+            tasks_notify.clone(),
+        ));
+
         Self {
             caller_ip,
             state,
@@ -118,7 +219,428 @@ impl SchedulerServer {
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_millis(),
+            table_authorizer: None,
+            plan_hooks: vec![],
+            http_authenticator: None,
+            track_map_outputs: false,
+            job_memory: Arc::new(RwLock::new(HashMap::new())),
+            executor_disk: Arc::new(RwLock::new(HashMap::new())),
+            tasks_notify,
+            job_queue,
+            poll_work_wait: POLL_WORK_WAIT,
+            sql_context: ExecutionContext::new(),
+            table_statistics: Arc::new(TableStatisticsCache::default()),
+        }
+    }
+
+    /// Uses `caller_ip` as the host of the executors that poll the scheduler without
+    /// sending theirs, which is the address the requests are received from
+    pub fn with_caller_ip(mut self, caller_ip: IpAddr) -> Self {
+        self.caller_ip = caller_ip;
+        self
+    }
+
+    /// Checks with `authorizer` that the principal submitting a query may read
+    /// the tables referenced by the query
+    pub fn with_table_authorizer(mut self, authorizer: Arc<dyn TableAuthorizer>) -> Self {
+        self.table_authorizer = Some(authorizer);
+        self
+    }
+
+    /// Invokes `hook` on every submitted plan before optimizing it, after the
+    /// hooks added before it
+    pub fn with_plan_hook(mut self, hook: Arc<dyn PlanHook>) -> Self {
+        self.plan_hooks.push(hook);
+        self
+    }
+
+    /// Uses `authenticator` to find the principal submitting a query through the
+    /// REST API, whose queries are otherwise submitted without principal
+    pub fn with_http_authenticator(
+        mut self,
+        authenticator: Arc<dyn HttpAuthenticator>,
+    ) -> Self {
+        self.http_authenticator = Some(authenticator);
+        self
+    }
+
+    pub(crate) fn http_authenticator(&self) -> Option<&Arc<dyn HttpAuthenticator>> {
+        self.http_authenticator.as_ref()
+    }
+
+    /// Plans the SQL queries in copies of `template`, so that the tables and the
+    /// functions registered in it can be queried
+    pub fn with_sql_context(mut self, template: ExecutionContext) -> Self {
+        self.sql_context = template;
+        self
+    }
+
+    /// Sends tasks whose shuffle readers query the scheduler for the locations of
+    /// their partitions when executed, rather than listing them in the tasks, so
+    /// that partitions rewritten after a failure are read from their new locations
+    pub fn with_map_output_tracking(mut self, track_map_outputs: bool) -> Self {
+        self.track_map_outputs = track_map_outputs;
+        self
+    }
+
+    /// Assigns the next schedulable task to an executor, returning its definition,
+    /// while the state is locked
+    async fn assign_next_task(
+        &self,
+        executor_id: &str,
+    ) -> Result<Option<TaskDefinition>, Status> {
+        let plan = self
+            .state
+            .assign_next_schedulable_task(executor_id, self.track_map_outputs)
+            .await
+            .map_err(|e| {
+                let msg = format!("Error finding next assignable task: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            })?;
+        let (status, plan) = match plan {
+            Some(plan) => plan,
+            None => return Ok(None),
+        };
+        if let Some(partition_id) = status.partition_id.as_ref() {
+            info!(
+                "Sending new task to {}: {}/{}/{}",
+                executor_id,
+                partition_id.job_id,
+                partition_id.stage_id,
+                partition_id.partition_id
+            );
         }
+        let output_partitioning = if let Some(shuffle_writer) =
+            plan.as_any().downcast_ref::<ShuffleWriterExec>()
+        {
+            shuffle_writer.shuffle_output_partitioning()
+        } else {
+            return Err(Status::invalid_argument(format!(
+                "Task root plan was not a ShuffleWriterExec: {:?}",
+                plan
+            )));
+        };
+        let plan: PhysicalPlanNode = plan.try_into().map_err(|e| {
+            let msg = format!("Could not serialize task plan: {}", e);
+            error!("{}", msg);
+            Status::internal(msg)
+        })?;
+        Ok(Some(TaskDefinition {
+            plan: Some(plan),
+            task_id: status.partition_id,
+            output_partitioning: hash_partitioning_to_proto(output_partitioning)
+                .map_err(|e| {
+                    let msg =
+                        format!("Could not serialize shuffle output partitioning: {}", e);
+                    error!("{}", msg);
+                    Status::internal(msg)
+                })?,
+        }))
+    }
+
+    /// Assigns up to `max_tasks` schedulable tasks to an executor, while the state
+    /// is locked. The tasks are saved as running on the executor before the state
+    /// is unlocked, which reserves its slots for them, so that concurrent polls
+    /// never assign the same task twice.
+    async fn assign_next_tasks(
+        &self,
+        executor_id: &str,
+        max_tasks: usize,
+    ) -> Result<Vec<TaskDefinition>, Status> {
+        let mut tasks = vec![];
+        while tasks.len() < max_tasks {
+            match self.assign_next_task(executor_id).await? {
+                Some(task) => tasks.push(task),
+                None => break,
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Returns the schema and the statistics of the files at `path`, in the object
+    /// store of the template SQL context for its scheme
+    async fn path_statistics(
+        &self,
+        path: &str,
+        file_format: &dyn FileFormat,
+    ) -> datafusion::error::Result<(SchemaRef, Statistics)> {
+        let (obj_store, path) = self
+            .sql_context
+            .state
+            .lock()
+            .unwrap()
+            .object_store_registry
+            .get_by_uri(path)?;
+        let file_metas: Vec<FileMeta> =
+            obj_store.list_file(path).await?.try_collect().await?;
+        let schema = self
+            .table_statistics
+            .schema(&*obj_store, file_format, path, &file_metas)
+            .await?;
+        let files = file_metas
+            .into_iter()
+            .map(|file_meta| PartitionedFile {
+                file_meta,
+                partition_values: vec![],
+                range: None,
+            })
+            .collect();
+        let statistics = self
+            .table_statistics
+            .statistics(&*obj_store, file_format, schema.clone(), files)
+            .await?;
+        Ok((schema, statistics))
+    }
+
+    /// Returns the schema and the statistics of a table registered in the template
+    /// SQL context. The statistics of the files of the listing tables are cached,
+    /// the other tables are asked for theirs.
+    async fn registered_table_statistics(
+        &self,
+        table_name: &str,
+    ) -> datafusion::error::Result<(SchemaRef, Statistics)> {
+        // Ballista resolves tables in the default catalog and schema
+        let table = self
+            .sql_context
+            .state
+            .lock()
+            .unwrap()
+            .catalog_list
+            .catalog("datafusion")
+            .and_then(|catalog| catalog.schema("public"))
+            .and_then(|schema| schema.table(table_name))
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!("Table {} is not registered", table_name))
+            })?;
+        let schema = table.schema();
+        let listing = match table.as_any().downcast_ref::<ListingTable>() {
+            Some(listing) => listing,
+            None => {
+                let batch_size = ExecutionConfig::new().batch_size;
+                let plan = table.scan(&None, batch_size, &[], None).await?;
+                return Ok((schema, plan.statistics()));
+            }
+        };
+        let options = listing.options();
+        let mut files = vec![];
+        for table_path in listing.table_paths() {
+            let table_files = pruned_partition_list(
+                listing.object_store().as_ref(),
+                table_path,
+                &[],
+                &options.file_extension,
+                &options.table_partition_cols,
+            )
+            .await?;
+            files.extend(table_files.try_collect::<Vec<_>>().await?);
+        }
+        let statistics = self
+            .table_statistics
+            .statistics(
+                listing.object_store().as_ref(),
+                &*options.format,
+                schema.clone(),
+                files,
+            )
+            .await?;
+        Ok((schema, statistics))
+    }
+
+    async fn lock_state(&self) -> Result<Box<dyn state::Lock>, Status> {
+        self.state.lock().await.map_err(|e| {
+            let msg = format!("Could not lock the state: {}", e);
+            error!("{}", msg);
+            tonic::Status::internal(msg)
+        })
+    }
+
+    /// Creates the context planning a submitted SQL query, as a copy of the tables,
+    /// the functions and the policies of the template SQL context. Every query is planned in a
+    /// context of its own, so that the queries are planned concurrently and the
+    /// tables registered by the statements of a query are not visible to the
+    /// other queries.
+    fn create_sql_context(
+        &self,
+        config: &BallistaConfig,
+        principal: Option<&str>,
+    ) -> datafusion::error::Result<ExecutionContext> {
+        let mut exec_config = ExecutionConfig::new()
+            .with_target_partitions(config.default_shuffle_partitions())
+            .with_ansi_mode(config.ansi_mode());
+        if let Some(authorizer) = &self.table_authorizer {
+            exec_config = exec_config.with_table_authorizer(authorizer.clone());
+        }
+        if let Some(principal) = principal {
+            exec_config = exec_config.with_principal(principal);
+        }
+        let mut ctx = ExecutionContext::with_config(exec_config);
+
+        let template = self.sql_context.state.lock().unwrap();
+        {
+            let mut state = ctx.state.lock().unwrap();
+            state
+                .scalar_functions
+                .extend(template.scalar_functions.clone());
+            state
+                .aggregate_functions
+                .extend(template.aggregate_functions.clone());
+            state
+                .table_functions
+                .extend(template.table_functions.clone());
+            // the row policies and the column masks of the tables
+            state.policy_registry = template.policy_registry.clone();
+        }
+        // Ballista resolves tables in the default catalog and schema
+        let schema = template
+            .catalog_list
+            .catalog("datafusion")
+            .and_then(|catalog| catalog.schema("public"));
+        if let Some(schema) = schema {
+            for name in schema.table_names() {
+                if let Some(table) = schema.table(&name) {
+                    ctx.register_table(name.as_str(), table)?;
+                }
+            }
+        }
+        Ok(ctx)
+    }
+
+    /// Applies the row policies and the column masks registered in the template
+    /// SQL context for `principal` to the tables scanned by `plan`. Queries
+    /// submitted as SQL are planned with the policies applied instead.
+    fn apply_policies(
+        &self,
+        plan: &LogicalPlan,
+        principal: Option<&str>,
+    ) -> datafusion::error::Result<LogicalPlan> {
+        let registry = self
+            .sql_context
+            .state
+            .lock()
+            .unwrap()
+            .policy_registry
+            .clone();
+        // Ballista resolves tables in the default catalog and schema
+        registry.apply_to_plan(plan, principal, "datafusion", "public")
+    }
+
+    /// Checks that `principal` may read the tables scanned by `plan`. Queries
+    /// submitted as SQL are checked by DataFusion while planning them instead.
+    fn authorize_plan(
+        &self,
+        plan: &LogicalPlan,
+        principal: Option<&str>,
+    ) -> datafusion::error::Result<()> {
+        let authorizer = match &self.table_authorizer {
+            Some(authorizer) => authorizer,
+            None => return Ok(()),
+        };
+        if let LogicalPlan::TableScan(scan) = plan {
+            // Ballista resolves tables in the default catalog and schema
+            let table = TableReference::from(scan.table_name.as_str())
+                .resolve("datafusion", "public");
+            authorizer.authorize_select(principal, table)?;
+        }
+        plan.inputs()
+            .into_iter()
+            .try_for_each(|input| self.authorize_plan(input, principal))
+    }
+
+    /// Records the memory used by the jobs running on an executor, and returns the
+    /// ones that failed, whose tasks the executor must cancel
+    async fn record_job_memory(
+        &self,
+        executor_id: &str,
+        job_memory: Vec<JobMemoryUsage>,
+    ) -> Vec<String> {
+        let mut cancelled_jobs = vec![];
+        for usage in &job_memory {
+            if let Ok(Some(JobStatus {
+                status: Some(job_status::Status::Failed(_)),
+            })) = self.state.get_job_metadata(&usage.job_id).await
+            {
+                info!(
+                    "Cancelling the tasks of failed job {} on {}",
+                    usage.job_id, executor_id
+                );
+                cancelled_jobs.push(usage.job_id.clone());
+            }
+        }
+
+        let mut memory = self.job_memory.write().unwrap();
+        // the jobs that are no longer reported have no running task on the executor
+        for executors in memory.values_mut() {
+            if let Some(usage) = executors.get_mut(executor_id) {
+                usage.used_bytes = 0;
+            }
+        }
+        for usage in job_memory {
+            let executors = memory.entry(usage.job_id.clone()).or_default();
+            let peak_bytes = executors
+                .get(executor_id)
+                .map(|previous| previous.peak_bytes.max(usage.peak_bytes))
+                .unwrap_or(usage.peak_bytes);
+            executors.insert(
+                executor_id.to_owned(),
+                JobMemoryUsage {
+                    peak_bytes,
+                    ..usage
+                },
+            );
+        }
+        cancelled_jobs
+    }
+
+    /// Records the disk space used by the work dir of an executor, and returns
+    /// whether it is under disk pressure
+    fn record_disk_usage(
+        &self,
+        executor_id: &str,
+        disk_usage: Option<ExecutorDiskUsage>,
+    ) -> bool {
+        let mut executor_disk = self.executor_disk.write().unwrap();
+        match disk_usage {
+            Some(usage) => {
+                let was_under_pressure = executor_disk
+                    .get(executor_id)
+                    .map_or(false, |previous| previous.disk_pressure);
+                if usage.disk_pressure && !was_under_pressure {
+                    warn!(
+                        "Executor {} is under disk pressure, {} bytes are free",
+                        executor_id, usage.available_bytes
+                    );
+                }
+                let disk_pressure = usage.disk_pressure;
+                executor_disk.insert(executor_id.to_owned(), usage);
+                disk_pressure
+            }
+            None => {
+                executor_disk.remove(executor_id);
+                false
+            }
+        }
+    }
+}
+
+/// Converts an error planning a query to a gRPC status, keeping permission
+/// errors distinct so that clients can tell them apart
+fn planning_error_status(context: &str, e: DataFusionError) -> Status {
+    let msg = format!("{}: {}", context, e);
+    error!("{}", msg);
+    match e {
+        DataFusionError::PermissionDenied { .. } => Status::permission_denied(msg),
+        _ => Status::internal(msg),
+    }
+}
+
+/// Converts the error of a plan hook rejecting a query to a gRPC status
+fn rejected_query_status(e: DataFusionError) -> Status {
+    let msg = format!("Query rejected: {}", e);
+    warn!("{}", msg);
+    match e {
+        DataFusionError::PermissionDenied { .. } => Status::permission_denied(msg),
+        _ => Status::invalid_argument(msg),
     }
 }
 
@@ -181,6 +703,9 @@ impl SchedulerGrpc for SchedulerServer {
             metadata: Some(metadata),
             can_accept_task,
             task_status,
+            job_memory,
+            disk_usage,
+            available_task_slots,
         } = request.into_inner()
         {
             debug!("Received poll_work request for {:?}", metadata);
@@ -193,12 +718,13 @@ impl SchedulerGrpc for SchedulerServer {
                     })
                     .unwrap_or_else(|| self.caller_ip.to_string()),
                 port: metadata.port as u16,
+                labels: metadata
+                    .labels
+                    .into_iter()
+                    .map(|label| (label.key, label.value))
+                    .collect(),
             };
-            let mut lock = self.state.lock().await.map_err(|e| {
-                let msg = format!("Could not lock the state: {}", e);
-                error!("{}", msg);
-                tonic::Status::internal(msg)
-            })?;
+            let mut lock = self.lock_state().await?;
             self.state
                 .save_executor_metadata(metadata.clone())
                 .await
@@ -207,65 +733,68 @@ impl SchedulerGrpc for SchedulerServer {
                     error!("{}", msg);
                     tonic::Status::internal(msg)
                 })?;
+            let tasks_ended = !task_status.is_empty();
+            let mut statuses = Vec::with_capacity(task_status.len());
             for task_status in task_status {
-                self.state
-                    .save_task_status(&task_status)
-                    .await
-                    .map_err(|e| {
-                        let msg = format!("Could not save task status: {}", e);
-                        error!("{}", msg);
-                        tonic::Status::internal(msg)
-                    })?;
-            }
-            let task: Result<Option<_>, Status> = if can_accept_task {
-                let plan = self
+                // the tasks that failed to read a corrupted or unreachable shuffle
+                // partition are executed again along with the task that wrote it
+                let rescheduled = self
                     .state
-                    .assign_next_schedulable_task(&metadata.id)
+                    .reschedule_lost_partition(&task_status)
                     .await
                     .map_err(|e| {
-                        let msg = format!("Error finding next assignable task: {}", e);
+                        let msg = format!("Could not reschedule task: {}", e);
                         error!("{}", msg);
                         tonic::Status::internal(msg)
                     })?;
-                if let Some((task, _plan)) = &plan {
-                    let partition_id = task.partition_id.as_ref().unwrap();
-                    info!(
-                        "Sending new task to {}: {}/{}/{}",
-                        metadata.id,
-                        partition_id.job_id,
-                        partition_id.stage_id,
-                        partition_id.partition_id
-                    );
-                }
-                match plan {
-                    Some((status, plan)) => {
-                        let plan_clone = plan.clone();
-                        let output_partitioning = if let Some(shuffle_writer) =
-                            plan_clone.as_any().downcast_ref::<ShuffleWriterExec>()
-                        {
-                            shuffle_writer.shuffle_output_partitioning()
-                        } else {
-                            return Err(Status::invalid_argument(format!(
-                                "Task root plan was not a ShuffleWriterExec: {:?}",
-                                plan_clone
-                            )));
-                        };
-                        Ok(Some(TaskDefinition {
-                            plan: Some(plan.try_into().unwrap()),
-                            task_id: status.partition_id,
-                            output_partitioning: hash_partitioning_to_proto(
-                                output_partitioning,
-                            )
-                            .map_err(|_| Status::internal("TBD".to_string()))?,
-                        }))
-                    }
-                    None => Ok(None),
+                if !rescheduled {
+                    statuses.push(task_status);
                 }
+            }
+            self.state
+                .save_task_statuses(&statuses)
+                .await
+                .map_err(|e| {
+                    let msg = format!("Could not save task status: {}", e);
+                    error!("{}", msg);
+                    tonic::Status::internal(msg)
+                })?;
+            if tasks_ended {
+                self.tasks_notify.notify_waiters();
+            }
+            let cancelled_jobs = self.record_job_memory(&metadata.id, job_memory).await;
+            // no task is assigned to an executor under disk pressure
+            let disk_pressure = self.record_disk_usage(&metadata.id, disk_usage);
+            let max_tasks = if can_accept_task && !disk_pressure {
+                // the executors that do not send their slots are assigned a single task
+                (available_task_slots as usize).max(1)
             } else {
-                Ok(None)
+                0
             };
+            let tasks = self.assign_next_tasks(&metadata.id, max_tasks).await;
+            // created while the state is locked, so that no notification is missed
+            let notified = self.tasks_notify.notified();
             lock.unlock().await;
-            Ok(Response::new(PollWorkResult { task: task? }))
+            let mut tasks = tasks?;
+            if tasks.is_empty() && max_tasks > 0 {
+                // waits for a task to become schedulable rather than having the
+                // executor poll again
+                if tokio::time::timeout(self.poll_work_wait, notified)
+                    .await
+                    .is_ok()
+                {
+                    let mut lock = self.lock_state().await?;
+                    let assigned = self.assign_next_tasks(&metadata.id, max_tasks).await;
+                    lock.unlock().await;
+                    tasks = assigned?;
+                }
+            }
+            let mut tasks = tasks.into_iter();
+            Ok(Response::new(PollWorkResult {
+                task: tasks.next(),
+                cancelled_jobs,
+                additional_tasks: tasks.collect(),
+            }))
         } else {
             warn!("Received invalid executor poll_work request");
             Err(tonic::Status::invalid_argument(
@@ -320,10 +849,54 @@ impl SchedulerGrpc for SchedulerServer {
         }))
     }
 
+    async fn get_table_statistics(
+        &self,
+        request: Request<GetTableStatisticsParams>,
+    ) -> std::result::Result<Response<GetTableStatisticsResult>, tonic::Status> {
+        let GetTableStatisticsParams {
+            path,
+            file_type,
+            table_name,
+        } = request.into_inner();
+
+        let (schema, statistics) = if table_name.is_empty() {
+            let file_type: FileType = file_type.try_into().map_err(|e| {
+                let msg = format!("Error reading request: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            })?;
+            let file_format: Arc<dyn FileFormat> = match file_type {
+                FileType::Parquet => Ok(Arc::new(ParquetFormat::default())),
+                // the options of the other formats are known for the registered tables
+                _ => Err(tonic::Status::unimplemented(
+                    "get_table_statistics unsupported file type",
+                )),
+            }?;
+            self.path_statistics(&path, &*file_format).await
+        } else {
+            self.registered_table_statistics(&table_name).await
+        }
+        .map_err(|e| {
+            let msg = format!("Error reading statistics: {}", e);
+            error!("{}", msg);
+            tonic::Status::internal(msg)
+        })?;
+
+        Ok(Response::new(GetTableStatisticsResult {
+            schema: Some(schema.as_ref().into()),
+            statistics: Some((&statistics).into()),
+        }))
+    }
+
     async fn execute_query(
         &self,
         request: Request<ExecuteQueryParams>,
     ) -> std::result::Result<Response<ExecuteQueryResult>, tonic::Status> {
+        let principal = request
+            .metadata()
+            .get(PRINCIPAL_METADATA_KEY)
+            .and_then(|principal| principal.to_str().ok())
+            .map(str::to_owned);
         if let ExecuteQueryParams {
             query: Some(query),
             settings,
@@ -343,24 +916,39 @@ impl SchedulerGrpc for SchedulerServer {
             let plan = match query {
                 Query::LogicalPlan(logical_plan) => {
                     // parse protobuf
-                    (&logical_plan).try_into().map_err(|e| {
+                    let plan = (&logical_plan).try_into().map_err(|e| {
                         let msg = format!("Could not parse logical plan protobuf: {}", e);
                         error!("{}", msg);
                         tonic::Status::internal(msg)
-                    })?
+                    })?;
+                    self.authorize_plan(&plan, principal.as_deref())
+                        .map_err(|e| {
+                            planning_error_status("Error authorizing plan", e)
+                        })?;
+                    // the policies of the SQL queries apply to the plans as well
+                    self.apply_policies(&plan, principal.as_deref())
+                        .map_err(|e| {
+                            planning_error_status("Error applying policies", e)
+                        })?
                 }
                 Query::Sql(sql) => {
-                    //TODO we can't just create a new context because we need a context that has
-                    // tables registered from previous SQL statements that have been executed
-                    let mut ctx = create_datafusion_context(&config);
-                    let df = ctx.sql(&sql).await.map_err(|e| {
-                        let msg = format!("Error parsing SQL: {}", e);
-                        error!("{}", msg);
-                        tonic::Status::internal(msg)
-                    })?;
+                    let mut ctx = self
+                        .create_sql_context(&config, principal.as_deref())
+                        .map_err(|e| {
+                            planning_error_status("Error creating SQL context", e)
+                        })?;
+                    let df = ctx
+                        .sql(&sql)
+                        .await
+                        .map_err(|e| planning_error_status("Error parsing SQL", e))?;
                     df.to_logical_plan()
                 }
             };
+            let plan = self
+                .plan_hooks
+                .iter()
+                .try_fold(plan, |plan, hook| hook.rewrite(plan, principal.as_deref()))
+                .map_err(rejected_query_status)?;
             debug!("Received plan for execution: {:?}", plan);
             let job_id: String = {
                 let mut rng = thread_rng();
@@ -384,122 +972,38 @@ impl SchedulerGrpc for SchedulerServer {
                     tonic::Status::internal(format!("Could not save job metadata: {}", e))
                 })?;
 
-            let state = self.state.clone();
-            let job_id_spawn = job_id.clone();
-            tokio::spawn(async move {
-                // create physical plan using DataFusion
-                let datafusion_ctx = create_datafusion_context(&config);
-                macro_rules! fail_job {
-                    ($code :expr) => {{
-                        match $code {
-                            Err(error) => {
-                                warn!("Job {} failed with {}", job_id_spawn, error);
-                                state
-                                    .save_job_metadata(
-                                        &job_id_spawn,
-                                        &JobStatus {
-                                            status: Some(job_status::Status::Failed(
-                                                FailedJob {
-                                                    error: format!("{}", error),
-                                                },
-                                            )),
-                                        },
-                                    )
-                                    .await
-                                    .unwrap();
-                                return;
-                            }
-                            Ok(value) => value,
-                        }
-                    }};
-                }
-
-                let start = Instant::now();
-
-                let optimized_plan =
-                    fail_job!(datafusion_ctx.optimize(&plan).map_err(|e| {
-                        let msg =
-                            format!("Could not create optimized logical plan: {}", e);
-                        error!("{}", msg);
-                        tonic::Status::internal(msg)
-                    }));
-
-                debug!("Calculated optimized plan: {:?}", optimized_plan);
-
-                let plan = fail_job!(datafusion_ctx
-                    .create_physical_plan(&optimized_plan)
+            let labels = JobLabels {
+                labels: config
+                    .job_labels()
+                    .into_iter()
+                    .map(|(key, value)| KeyValuePair { key, value })
+                    .collect(),
+            };
+            if !labels.labels.is_empty() {
+                info!("Job {} submitted with labels {:?}", job_id, labels.labels);
+                self.state
+                    .save_job_labels(&job_id, &labels)
                     .await
                     .map_err(|e| {
-                        let msg = format!("Could not create physical plan: {}", e);
-                        error!("{}", msg);
-                        tonic::Status::internal(msg)
-                    }));
-
-                info!(
-                    "DataFusion created physical plan in {} milliseconds",
-                    start.elapsed().as_millis(),
-                );
+                        tonic::Status::internal(format!(
+                            "Could not save job labels: {}",
+                            e
+                        ))
+                    })?;
+            }
 
-                // create distributed physical plan using Ballista
-                if let Err(e) = state
-                    .save_job_metadata(
-                        &job_id_spawn,
-                        &JobStatus {
-                            status: Some(job_status::Status::Running(RunningJob {})),
-                        },
-                    )
-                    .await
-                {
-                    warn!(
-                        "Could not update job {} status to running: {}",
-                        job_id_spawn, e
-                    );
-                }
-                let mut planner = DistributedPlanner::new();
-                let stages = fail_job!(planner
-                    .plan_query_stages(&job_id_spawn, plan)
-                    .await
-                    .map_err(|e| {
-                        let msg = format!("Could not plan query stages: {}", e);
-                        error!("{}", msg);
-                        tonic::Status::internal(msg)
-                    }));
-
-                // save stages into state
-                for shuffle_writer in stages {
-                    fail_job!(state
-                        .save_stage_plan(
-                            &job_id_spawn,
-                            shuffle_writer.stage_id(),
-                            shuffle_writer.clone()
-                        )
-                        .await
-                        .map_err(|e| {
-                            let msg = format!("Could not save stage plan: {}", e);
-                            error!("{}", msg);
-                            tonic::Status::internal(msg)
-                        }));
-                    let num_partitions =
-                        shuffle_writer.output_partitioning().partition_count();
-                    for partition_id in 0..num_partitions {
-                        let pending_status = TaskStatus {
-                            partition_id: Some(PartitionId {
-                                job_id: job_id_spawn.clone(),
-                                stage_id: shuffle_writer.stage_id() as u32,
-                                partition_id: partition_id as u32,
-                            }),
-                            status: None,
-                        };
-                        fail_job!(state.save_task_status(&pending_status).await.map_err(
-                            |e| {
-                                let msg = format!("Could not save task status: {}", e);
-                                error!("{}", msg);
-                                tonic::Status::internal(msg)
-                            }
-                        ));
-                    }
-                }
-            });
+            self.job_queue
+                .push(SubmittedJob {
+                    job_id: job_id.clone(),
+                    plan,
+                    config,
+                })
+                .await
+                .map_err(|e| {
+                    let msg = format!("Could not queue job: {}", e);
+                    error!("{}", msg);
+                    tonic::Status::internal(msg)
+                })?;
 
             Ok(Response::new(ExecuteQueryResult { job_id }))
         } else {
@@ -507,27 +1011,502 @@ impl SchedulerGrpc for SchedulerServer {
         }
     }
 
+    async fn execute_query_chunked(
+        &self,
+        request: Request<Streaming<MessageChunk>>,
+    ) -> std::result::Result<Response<ExecuteQueryResult>, tonic::Status> {
+        let metadata = request.metadata().clone();
+        let params: ExecuteQueryParams =
+            decode_chunks(request.into_inner()).await.map_err(|e| {
+                let msg = format!("Could not receive query: {}", e);
+                error!("{}", msg);
+                tonic::Status::invalid_argument(msg)
+            })?;
+        let mut request = Request::new(params);
+        *request.metadata_mut() = metadata;
+        self.execute_query(request).await
+    }
+
     async fn get_job_status(
         &self,
         request: Request<GetJobStatusParams>,
     ) -> std::result::Result<Response<GetJobStatusResult>, tonic::Status> {
         let job_id = request.into_inner().job_id;
         debug!("Received get_job_status request for job {}", job_id);
-        let job_meta = self.state.get_job_metadata(&job_id).await.map_err(|e| {
+        let job_meta = self
+            .state
+            .get_job_metadata(&job_id)
+            .await
+            .map_err(|e| {
+                let msg = format!("Error reading job metadata: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            })?
+            .ok_or_else(|| {
+                tonic::Status::not_found(format!("Job {} does not exist", job_id))
+            })?;
+        Ok(Response::new(GetJobStatusResult {
+            status: Some(job_meta),
+        }))
+    }
+
+    async fn list_jobs(
+        &self,
+        _request: Request<ListJobsParams>,
+    ) -> std::result::Result<Response<ListJobsResult>, tonic::Status> {
+        debug!("Received list_jobs request");
+        let read_error = |e: BallistaError| {
             let msg = format!("Error reading job metadata: {}", e);
             error!("{}", msg);
             tonic::Status::internal(msg)
+        };
+        let jobs = self.state.get_jobs_metadata().await.map_err(read_error)?;
+        let mut summaries = Vec::with_capacity(jobs.len());
+        for (job_id, status) in jobs {
+            let labels = self
+                .state
+                .get_job_labels(&job_id)
+                .await
+                .map_err(read_error)?;
+            summaries.push(JobSummary {
+                job_id,
+                status: Some(status),
+                labels: Some(labels),
+            });
+        }
+        Ok(Response::new(ListJobsResult { jobs: summaries }))
+    }
+
+    async fn cancel_job(
+        &self,
+        request: Request<CancelJobParams>,
+    ) -> std::result::Result<Response<CancelJobResult>, tonic::Status> {
+        let job_id = request.into_inner().job_id;
+        info!("Received cancel_job request for job {}", job_id);
+        if self
+            .state
+            .get_job_metadata(&job_id)
+            .await
+            .map_err(|e| {
+                let msg = format!("Error reading job metadata: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            })?
+            .is_none()
+        {
+            return Err(tonic::Status::not_found(format!(
+                "Job {} does not exist",
+                job_id
+            )));
+        }
+        let mut lock = self.state.lock().await.map_err(|e| {
+            let msg = format!("Could not lock the state: {}", e);
+            error!("{}", msg);
+            tonic::Status::internal(msg)
         })?;
-        Ok(Response::new(GetJobStatusResult {
-            status: Some(job_meta),
+        let cancelled = self.state.cancel_job(&job_id).await;
+        lock.unlock().await;
+        let cancelled = cancelled.map_err(|e| {
+            let msg = format!("Could not cancel job {}: {}", job_id, e);
+            error!("{}", msg);
+            tonic::Status::internal(msg)
+        })?;
+        Ok(Response::new(CancelJobResult { cancelled }))
+    }
+
+    async fn get_job_metrics(
+        &self,
+        request: Request<GetJobMetricsParams>,
+    ) -> std::result::Result<Response<GetJobMetricsResult>, tonic::Status> {
+        let job_id = request.into_inner().job_id;
+        debug!("Received get_job_metrics request for job {}", job_id);
+        let stages = self.state.get_job_metrics(&job_id).await.map_err(|e| {
+            let msg = format!("Error reading metrics of job {}: {}", job_id, e);
+            error!("{}", msg);
+            tonic::Status::internal(msg)
+        })?;
+        Ok(Response::new(GetJobMetricsResult {
+            stages: stages
+                .into_iter()
+                .map(|(stage_id, plan)| StageMetrics {
+                    stage_id: stage_id as u32,
+                    plan,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_map_outputs(
+        &self,
+        request: Request<GetMapOutputsParams>,
+    ) -> std::result::Result<Response<GetMapOutputsResult>, tonic::Status> {
+        let GetMapOutputsParams {
+            job_id,
+            stage_id,
+            partition_id,
+        } = request.into_inner();
+        debug!(
+            "Received get_map_outputs request for job {} stage {} partition {}",
+            job_id, stage_id, partition_id
+        );
+        let locations = self
+            .state
+            .get_map_outputs(&job_id, stage_id as usize, partition_id as usize)
+            .await
+            .map_err(|e| {
+                let msg = format!("Error reading map outputs: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            })?
+            .ok_or_else(|| {
+                tonic::Status::unavailable(format!(
+                    "Outputs of stage {} of job {} are not available",
+                    stage_id, job_id
+                ))
+            })?;
+        let location = locations
+            .into_iter()
+            .map(|location| location.try_into())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: BallistaError| tonic::Status::internal(e.to_string()))?;
+        Ok(Response::new(GetMapOutputsResult { location }))
+    }
+
+    async fn persist_dataset(
+        &self,
+        request: Request<PersistDatasetParams>,
+    ) -> std::result::Result<Response<PersistDatasetResult>, tonic::Status> {
+        let PersistDatasetParams { job_id, schema } = request.into_inner();
+        debug!("Received persist_dataset request for job {}", job_id);
+        let schema = schema
+            .ok_or_else(|| tonic::Status::invalid_argument("Missing dataset schema"))?;
+        let job_meta = self
+            .state
+            .get_job_metadata(&job_id)
+            .await
+            .map_err(|e| {
+                let msg = format!("Error reading job metadata: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            })?
+            .ok_or_else(|| {
+                tonic::Status::not_found(format!("Job {} does not exist", job_id))
+            })?;
+        let partition_location = match job_meta.status {
+            Some(job_status::Status::Completed(CompletedJob { partition_location })) => {
+                partition_location
+            }
+            _ => {
+                return Err(tonic::Status::failed_precondition(format!(
+                    "Job {} has not completed and cannot be persisted",
+                    job_id
+                )))
+            }
+        };
+        let dataset = self
+            .state
+            .persist_dataset(&job_id, partition_location, schema)
+            .await
+            .map_err(|e| {
+                let msg = format!("Could not persist dataset: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            })?;
+        Ok(Response::new(PersistDatasetResult {
+            dataset: Some(dataset),
+        }))
+    }
+
+    async fn get_dataset(
+        &self,
+        request: Request<GetDatasetParams>,
+    ) -> std::result::Result<Response<GetDatasetResult>, tonic::Status> {
+        let dataset_id = request.into_inner().dataset_id;
+        debug!("Received get_dataset request for dataset {}", dataset_id);
+        let dataset = self
+            .state
+            .get_dataset(&dataset_id)
+            .await
+            .map_err(|e| {
+                let msg = format!("Error reading dataset: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            })?
+            .ok_or_else(|| {
+                tonic::Status::not_found(format!("Dataset {} does not exist", dataset_id))
+            })?;
+        Ok(Response::new(GetDatasetResult {
+            dataset: Some(dataset),
+        }))
+    }
+
+    async fn append_dataset(
+        &self,
+        request: Request<AppendDatasetParams>,
+    ) -> std::result::Result<Response<AppendDatasetResult>, tonic::Status> {
+        let AppendDatasetParams {
+            dataset_id,
+            schema,
+            executor_id,
+            mut location,
+        } = request.into_inner();
+        debug!(
+            "Received append_dataset request for dataset {} from executor {}",
+            dataset_id, executor_id
+        );
+        let schema = schema
+            .ok_or_else(|| tonic::Status::invalid_argument("Missing dataset schema"))?;
+        // the partitions are fetched from the executor at the address it registered
+        let executor_meta = self
+            .state
+            .get_executors_metadata()
+            .await
+            .map_err(|e| {
+                let msg = format!("Error reading executor metadata: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            })?
+            .into_iter()
+            .map(|(meta, _)| meta)
+            .find(|meta| meta.id == executor_id)
+            .ok_or_else(|| {
+                tonic::Status::failed_precondition(format!(
+                    "Executor {} is not registered",
+                    executor_id
+                ))
+            })?;
+        for location in &mut location {
+            location.executor_meta = Some(executor_meta.clone().into());
+        }
+
+        let mut lock = self.state.lock().await.map_err(|e| {
+            let msg = format!("Could not lock the state: {}", e);
+            error!("{}", msg);
+            tonic::Status::internal(msg)
+        })?;
+        let dataset = self
+            .state
+            .append_dataset(&dataset_id, location, schema)
+            .await;
+        lock.unlock().await;
+        let dataset = dataset.map_err(|e| {
+            let msg = format!("Could not append to dataset {}: {}", dataset_id, e);
+            error!("{}", msg);
+            tonic::Status::failed_precondition(msg)
+        })?;
+        Ok(Response::new(AppendDatasetResult {
+            dataset: Some(dataset),
         }))
     }
 }
 
+/// Plans the jobs taken from `job_queue`, in the order they were queued.
+///
+/// The future returned by this function never returns, so it is wise
+/// to [tokio::spawn] calls to this function.
+async fn plan_queued_jobs_loop(
+    state: Arc<SchedulerState>,
+    job_queue: Arc<dyn JobQueue>,
+    tasks_notify: Arc<Notify>,
+) {
+    loop {
+        match job_queue.pop().await {
+            Ok(job) => {
+                tokio::spawn(plan_job(state.clone(), tasks_notify.clone(), job));
+            }
+            Err(e) => {
+                error!("Could not take the next job from the queue: {}", e);
+                tokio::time::sleep(JOB_QUEUE_RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Plans the stages of a job and saves them along with their pending tasks, waking
+/// up the executors waiting for tasks
+async fn plan_job(
+    state: Arc<SchedulerState>,
+    tasks_notify: Arc<Notify>,
+    job: SubmittedJob,
+) {
+    let SubmittedJob {
+        job_id,
+        plan,
+        config,
+    } = job;
+    // create physical plan using DataFusion
+    let datafusion_ctx = create_datafusion_context(&config);
+    macro_rules! fail_job {
+        ($code :expr) => {{
+            match $code {
+                Err(error) => {
+                    warn!("Job {} failed with {}", job_id, error);
+                    if let Err(e) = state
+                        .save_job_metadata(
+                            &job_id,
+                            &JobStatus {
+                                status: Some(job_status::Status::Failed(FailedJob {
+                                    error: format!("{}", error),
+                                })),
+                            },
+                        )
+                        .await
+                    {
+                        error!("Could not save the failure of job {}: {}", job_id, e);
+                    }
+                    return;
+                }
+                Ok(value) => value,
+            }
+        }};
+    }
+
+    let start = Instant::now();
+
+    let optimized_plan = fail_job!(datafusion_ctx.optimize(&plan).map_err(|e| {
+        let msg = format!("Could not create optimized logical plan: {}", e);
+        error!("{}", msg);
+        tonic::Status::internal(msg)
+    }));
+
+    debug!("Calculated optimized plan: {:?}", optimized_plan);
+
+    let plan = fail_job!(datafusion_ctx
+        .create_physical_plan(&optimized_plan)
+        .await
+        .map_err(|e| {
+            let msg = format!("Could not create physical plan: {}", e);
+            error!("{}", msg);
+            tonic::Status::internal(msg)
+        }));
+
+    info!(
+        "DataFusion created physical plan in {} milliseconds",
+        start.elapsed().as_millis(),
+    );
+
+    let plan = match &optimized_plan {
+        LogicalPlan::Explain(explain) => fail_job!(explain_query_stages(
+            &datafusion_ctx,
+            &job_id,
+            &config,
+            explain,
+            plan
+        )
+        .await
+        .map_err(|e| {
+            let msg = format!("Could not explain query stages: {}", e);
+            error!("{}", msg);
+            tonic::Status::internal(msg)
+        })),
+        _ => plan,
+    };
+
+    // create distributed physical plan using Ballista
+    if let Err(e) = state
+        .save_job_metadata(
+            &job_id,
+            &JobStatus {
+                status: Some(job_status::Status::Running(RunningJob::default())),
+            },
+        )
+        .await
+    {
+        warn!("Could not update job {} status to running: {}", job_id, e);
+    }
+    let mut planner =
+        DistributedPlanner::new().with_scan_split_size(config.scan_split_size() as u64);
+    let stages = fail_job!(planner.plan_query_stages(&job_id, plan).await.map_err(|e| {
+        let msg = format!("Could not plan query stages: {}", e);
+        error!("{}", msg);
+        tonic::Status::internal(msg)
+    }));
+
+    // save stages into state, then the tasks of all the stages, so that the tasks of
+    // the final stage, whose completed partitions are reported to the clients while
+    // the job runs, are known as soon as any task runs
+    let mut pending_statuses = vec![];
+    for shuffle_writer in stages {
+        fail_job!(state
+            .save_stage_plan(&job_id, shuffle_writer.stage_id(), shuffle_writer.clone())
+            .await
+            .map_err(|e| {
+                let msg = format!("Could not save stage plan: {}", e);
+                error!("{}", msg);
+                tonic::Status::internal(msg)
+            }));
+        let num_partitions = shuffle_writer.output_partitioning().partition_count();
+        pending_statuses.extend((0..num_partitions).map(|partition_id| TaskStatus {
+            partition_id: Some(PartitionId {
+                job_id: job_id.clone(),
+                stage_id: shuffle_writer.stage_id() as u32,
+                partition_id: partition_id as u32,
+            }),
+            status: None,
+        }));
+    }
+    fail_job!(state
+        .save_task_statuses(&pending_statuses)
+        .await
+        .map_err(|e| {
+            let msg = format!("Could not save task status: {}", e);
+            error!("{}", msg);
+            tonic::Status::internal(msg)
+        }));
+    tasks_notify.notify_waiters();
+}
+
+/// Adds the query stages the explained query is split into to the plans shown by the
+/// `plan` of an EXPLAIN, where the root of each stage shows the estimated size of the
+/// output of the stage if the costs are explained
+async fn explain_query_stages(
+    ctx: &ExecutionContext,
+    job_id: &str,
+    config: &BallistaConfig,
+    explain: &Explain,
+    plan: Arc<dyn ExecutionPlan>,
+) -> Result<Arc<dyn ExecutionPlan>, BallistaError> {
+    let explain_exec = match plan.as_any().downcast_ref::<ExplainExec>() {
+        Some(explain_exec) => explain_exec,
+        None => return Ok(plan),
+    };
+    let input = ctx.create_physical_plan(&explain.plan).await?;
+    let mut planner =
+        DistributedPlanner::new().with_scan_split_size(config.scan_split_size() as u64);
+    let stages = planner.plan_query_stages(job_id, input).await?;
+
+    let stages: Vec<String> = stages
+        .iter()
+        .map(|stage| {
+            let stage_plan = displayable(stage.as_ref()).show_estimates(explain.costs);
+            match explain.format {
+                ExplainFormat::Indent => {
+                    format!("Stage {}:\n{}", stage.stage_id(), stage_plan.indent())
+                }
+                ExplainFormat::Json => stage_plan.json().to_string(),
+                ExplainFormat::Graphviz => stage_plan.graphviz().to_string(),
+            }
+        })
+        .collect();
+    let stages = match explain.format {
+        ExplainFormat::Json => format!("[{}]", stages.join(", ")),
+        _ => stages.join("\n"),
+    };
+
+    let mut stringified_plans = explain_exec.stringified_plans().to_vec();
+    stringified_plans.push(StringifiedPlan::new(PlanType::DistributedPlan, stages));
+    Ok(Arc::new(ExplainExec::new(
+        explain_exec.schema(),
+        stringified_plans,
+        explain_exec.verbose(),
+    )))
+}
+
 /// Create a DataFusion context that is compatible with Ballista
 pub fn create_datafusion_context(config: &BallistaConfig) -> ExecutionContext {
     let config = ExecutionConfig::new()
-        .with_target_partitions(config.default_shuffle_partitions());
+        .with_target_partitions(config.default_shuffle_partitions())
+        .with_ansi_mode(config.ansi_mode());
     ExecutionContext::with_config(config)
 }
 
@@ -536,17 +1515,31 @@ mod test {
     use std::{
         net::{IpAddr, Ipv4Addr},
         sync::Arc,
+        time::{Duration, Instant},
     };
 
-    use tonic::Request;
+    use tonic::{Code, Request};
 
+    use ballista_core::config::BallistaConfig;
     use ballista_core::error::BallistaError;
     use ballista_core::serde::protobuf::{
-        executor_registration::OptionalHost, ExecutorRegistration, PollWorkParams,
+        executor_registration::OptionalHost, job_status, AppendDatasetParams,
+        CancelJobParams, ExecutorDiskUsage, ExecutorRegistration, FileType,
+        GetDatasetParams, GetJobStatusParams, GetTableStatisticsParams, JobMemoryUsage,
+        JobStatus, ListJobsParams, PartitionLocation, PersistDatasetParams,
+        PollWorkParams, QueuedJob, Schema,
     };
+    use datafusion::arrow::array::{Int32Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema as ArrowSchema};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_eq;
+    use datafusion::datasource::MemTable;
+    use datafusion::logical_plan::LogicalPlanBuilder;
+    use datafusion::prelude::{col, lit, ExecutionContext};
 
     use super::{
-        state::{SchedulerState, StandaloneClient},
+        state::{SchedulerState, StandaloneClient, SubmittedJob},
+        test_utils::datafusion_test_context,
         SchedulerGrpc, SchedulerServer,
     };
 
@@ -564,11 +1557,15 @@ mod test {
             id: "abc".to_owned(),
             optional_host: Some(OptionalHost::Host("".to_owned())),
             port: 0,
+            labels: vec![],
         };
         let request: Request<PollWorkParams> = Request::new(PollWorkParams {
             metadata: Some(exec_meta.clone()),
             can_accept_task: false,
             task_status: vec![],
+            job_memory: vec![],
+            disk_usage: None,
+            available_task_slots: 0,
         });
         let response = scheduler
             .poll_work(request)
@@ -584,6 +1581,18 @@ mod test {
             metadata: Some(exec_meta.clone()),
             can_accept_task: true,
             task_status: vec![],
+            job_memory: vec![JobMemoryUsage {
+                job_id: "job".to_owned(),
+                used_bytes: 10,
+                peak_bytes: 20,
+            }],
+            disk_usage: Some(ExecutorDiskUsage {
+                work_dir_bytes: 100,
+                available_bytes: 10,
+                disk_pressure: true,
+                ..Default::default()
+            }),
+            available_task_slots: 4,
         });
         let response = scheduler
             .poll_work(request)
@@ -592,8 +1601,453 @@ mod test {
             .into_inner();
         // still no response task since there are no tasks in the scheduelr
         assert!(response.task.is_none());
+        // the job is not failed, its tasks are not cancelled
+        assert!(response.cancelled_jobs.is_empty());
+        assert_eq!(
+            scheduler.job_memory.read().unwrap()["job"]["abc"].peak_bytes,
+            20
+        );
+        assert!(scheduler.executor_disk.read().unwrap()["abc"].disk_pressure);
         // executor should be registered
         assert_eq!(state.get_executors_metadata().await.unwrap().len(), 1);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_poll_work_waits_for_planned_job() -> Result<(), BallistaError> {
+        let state = Arc::new(StandaloneClient::try_new_temporary()?);
+        let mut scheduler = SchedulerServer::new(
+            state,
+            "default".to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        );
+        scheduler.poll_work_wait = Duration::from_secs(30);
+        let mut ctx = datafusion_test_context("testdata").await?;
+        let plan = ctx
+            .sql("select l_returnflag from lineitem")
+            .await?
+            .to_logical_plan();
+        let config = BallistaConfig::new()?;
+
+        let start = Instant::now();
+        let poll = scheduler.poll_work(Request::new(PollWorkParams {
+            metadata: Some(ExecutorRegistration {
+                id: "abc".to_owned(),
+                optional_host: Some(OptionalHost::Host("".to_owned())),
+                port: 0,
+                labels: vec![],
+            }),
+            can_accept_task: true,
+            task_status: vec![],
+            job_memory: vec![],
+            disk_usage: None,
+            available_task_slots: 1,
+        }));
+        // the job is planned while the executor waits for a task
+        let submit = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            scheduler
+                .job_queue
+                .push(SubmittedJob {
+                    job_id: "job".to_owned(),
+                    plan,
+                    config,
+                })
+                .await
+        };
+        let (response, submitted) = tokio::join!(poll, submit);
+        submitted?;
+        let response = response.expect("Received error response").into_inner();
+        assert_eq!(response.task.unwrap().task_id.unwrap().job_id, "job");
+        assert!(start.elapsed() < scheduler.poll_work_wait);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_poll_work_assigns_free_slots() -> Result<(), BallistaError> {
+        let state = Arc::new(StandaloneClient::try_new_temporary()?);
+        let mut scheduler = SchedulerServer::new(
+            state,
+            "default".to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        );
+        scheduler.poll_work_wait = Duration::from_secs(30);
+        let mut ctx = datafusion_test_context("testdata").await?;
+        // a single stage of 4 tasks, one per file of each input of the union
+        let plan = ctx
+            .sql(
+                "select l_returnflag from lineitem \
+                union all select l_returnflag from lineitem",
+            )
+            .await?
+            .to_logical_plan();
+        scheduler
+            .job_queue
+            .push(SubmittedJob {
+                job_id: "job".to_owned(),
+                plan,
+                config: BallistaConfig::new()?,
+            })
+            .await?;
+
+        let poll = |available_task_slots| {
+            scheduler.poll_work(Request::new(PollWorkParams {
+                metadata: Some(ExecutorRegistration {
+                    id: "abc".to_owned(),
+                    optional_host: Some(OptionalHost::Host("".to_owned())),
+                    port: 0,
+                    labels: vec![],
+                }),
+                can_accept_task: true,
+                task_status: vec![],
+                job_memory: vec![],
+                disk_usage: None,
+                available_task_slots,
+            }))
+        };
+        // an executor is assigned as many tasks as it has free slots
+        let mut partitions = vec![];
+        for (slots, tasks) in [(3, 3), (1, 1)] {
+            let response = poll(slots).await.expect("Received error response");
+            let response = response.into_inner();
+            let assigned = response
+                .task
+                .into_iter()
+                .chain(response.additional_tasks)
+                .map(|task| task.task_id.unwrap().partition_id)
+                .collect::<Vec<_>>();
+            assert_eq!(assigned.len(), tasks);
+            partitions.extend(assigned);
+        }
+        // every task was assigned once
+        partitions.sort_unstable();
+        assert_eq!(partitions, vec![0, 1, 2, 3]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sql_context_per_query() -> Result<(), BallistaError> {
+        let template = datafusion_test_context("testdata").await?;
+        let scheduler = SchedulerServer::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "default".to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        )
+        .with_sql_context(template.clone());
+        let config = BallistaConfig::new()?;
+
+        // the tables of the template are visible to the queries
+        let mut ctx = scheduler.create_sql_context(&config, None)?;
+        ctx.sql("select l_returnflag from lineitem").await?;
+        ctx.sql(
+            "CREATE EXTERNAL TABLE registered (a INT) STORED AS CSV \
+            LOCATION 'testdata/region'",
+        )
+        .await?;
+        ctx.sql("select a from registered").await?;
+
+        // unlike the tables registered by the other queries
+        let mut ctx = scheduler.create_sql_context(&config, None)?;
+        assert!(ctx.sql("select a from registered").await.is_err());
+        assert!(template
+            .state
+            .lock()
+            .unwrap()
+            .catalog_list
+            .catalog("datafusion")
+            .and_then(|catalog| catalog.schema("public"))
+            .and_then(|schema| schema.table("registered"))
+            .is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sql_context_policies() -> Result<(), BallistaError> {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["x", "y"])),
+            ],
+        )?;
+        let mut template = ExecutionContext::new();
+        template.register_table(
+            "t",
+            Arc::new(MemTable::try_new(schema, vec![vec![batch]])?),
+        )?;
+        template.add_row_policy("t", None, col("a").gt(lit(1)));
+        template.add_column_mask("t", "b", None, lit("***"));
+        let scheduler = SchedulerServer::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "default".to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        )
+        .with_sql_context(template);
+        let config = BallistaConfig::new()?;
+
+        let mut ctx = scheduler.create_sql_context(&config, Some("bob"))?;
+        let batches = ctx.sql("select a, b from t").await?.collect().await?;
+        let expected = vec![
+            "+---+-----+",
+            "| a | b   |",
+            "+---+-----+",
+            "| 2 | *** |",
+            "+---+-----+",
+        ];
+        assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_logical_plan_policies() -> Result<(), BallistaError> {
+        let schema = ArrowSchema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, false),
+        ]);
+        let template = ExecutionContext::new();
+        template.add_column_mask("t", "b", None, lit("***"));
+        let scheduler = SchedulerServer::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "default".to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        )
+        .with_sql_context(template);
+
+        // a plan submitted without the policies applied
+        let plan = LogicalPlanBuilder::scan_empty(Some("t"), &schema, None)?
+            .project(vec![col("b")])?
+            .build()?;
+        let plan = scheduler.apply_policies(&plan, Some("bob"))?;
+        let expected = "Projection: #t.b\
+        \n  Projection: #t.a, Utf8(\"***\") AS b, alias=t\
+        \n    TableScan: t projection=None";
+        assert_eq!(expected, format!("{:?}", plan));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_job_and_dataset() -> Result<(), BallistaError> {
+        let state = Arc::new(StandaloneClient::try_new_temporary()?);
+        let namespace = "default";
+        let scheduler = SchedulerServer::new(
+            state.clone(),
+            namespace.to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        );
+        let state = SchedulerState::new(state, namespace.to_string());
+
+        let status = scheduler
+            .get_job_status(Request::new(GetJobStatusParams {
+                job_id: "job".to_owned(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        let status = scheduler
+            .get_dataset(Request::new(GetDatasetParams {
+                dataset_id: "job".to_owned(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+
+        // a job that has not completed cannot be persisted
+        let queued = JobStatus {
+            status: Some(job_status::Status::Queued(QueuedJob {})),
+        };
+        state.save_job_metadata("job", &queued).await?;
+        let status = scheduler
+            .persist_dataset(Request::new(PersistDatasetParams {
+                job_id: "job".to_owned(),
+                schema: Some(Schema { columns: vec![] }),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_and_cancel_jobs() -> Result<(), BallistaError> {
+        let state = Arc::new(StandaloneClient::try_new_temporary()?);
+        let namespace = "default";
+        let scheduler = SchedulerServer::new(
+            state.clone(),
+            namespace.to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        );
+        let state = SchedulerState::new(state, namespace.to_string());
+        let queued = JobStatus {
+            status: Some(job_status::Status::Queued(QueuedJob {})),
+        };
+        state.save_job_metadata("job", &queued).await?;
+
+        let jobs = scheduler
+            .list_jobs(Request::new(ListJobsParams {}))
+            .await
+            .expect("Received error response")
+            .into_inner()
+            .jobs;
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].job_id, "job");
+        assert_eq!(jobs[0].status, Some(queued));
+
+        let cancel = |job_id: &str| {
+            scheduler.cancel_job(Request::new(CancelJobParams {
+                job_id: job_id.to_owned(),
+            }))
+        };
+        assert!(cancel("job").await.unwrap().into_inner().cancelled);
+        assert!(!cancel("job").await.unwrap().into_inner().cancelled);
+        assert_eq!(cancel("missing").await.unwrap_err().code(), Code::NotFound);
+
+        // the executors running tasks of the cancelled job are told to cancel them
+        let response = scheduler
+            .poll_work(Request::new(PollWorkParams {
+                metadata: Some(ExecutorRegistration {
+                    id: "abc".to_owned(),
+                    optional_host: Some(OptionalHost::Host("".to_owned())),
+                    port: 0,
+                    labels: vec![],
+                }),
+                can_accept_task: false,
+                task_status: vec![],
+                job_memory: vec![JobMemoryUsage {
+                    job_id: "job".to_owned(),
+                    used_bytes: 10,
+                    peak_bytes: 20,
+                }],
+                disk_usage: None,
+                available_task_slots: 0,
+            }))
+            .await
+            .expect("Received error response")
+            .into_inner();
+        assert_eq!(response.cancelled_jobs, vec!["job".to_owned()]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_append_dataset() -> Result<(), BallistaError> {
+        let state = Arc::new(StandaloneClient::try_new_temporary()?);
+        let scheduler = SchedulerServer::new(
+            state,
+            "default".to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        );
+        let params = || AppendDatasetParams {
+            dataset_id: "staging".to_owned(),
+            schema: Some(Schema { columns: vec![] }),
+            executor_id: "abc".to_owned(),
+            location: vec![PartitionLocation {
+                partition_id: None,
+                executor_meta: None,
+                partition_stats: None,
+                path: "/tmp/datasets/staging/0.arrow".to_owned(),
+                checksum: None,
+            }],
+        };
+
+        // the partitions are kept by an executor that has to be registered
+        let status = scheduler
+            .append_dataset(Request::new(params()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        scheduler
+            .poll_work(Request::new(PollWorkParams {
+                metadata: Some(ExecutorRegistration {
+                    id: "abc".to_owned(),
+                    optional_host: Some(OptionalHost::Host("executor".to_owned())),
+                    port: 50051,
+                    labels: vec![],
+                }),
+                can_accept_task: false,
+                task_status: vec![],
+                job_memory: vec![],
+                disk_usage: None,
+                available_task_slots: 0,
+            }))
+            .await
+            .expect("Received error response");
+        scheduler.append_dataset(Request::new(params())).await?;
+        let dataset = scheduler
+            .get_dataset(Request::new(GetDatasetParams {
+                dataset_id: "staging".to_owned(),
+            }))
+            .await?
+            .into_inner()
+            .dataset
+            .unwrap();
+        assert_eq!(dataset.partition.len(), 1);
+        let executor_meta = dataset.partition[0].location[0]
+            .executor_meta
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            ("executor", 50051),
+            (executor_meta.host.as_str(), executor_meta.port)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_statistics() -> Result<(), BallistaError> {
+        let scheduler = SchedulerServer::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "default".to_owned(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        );
+        let path = format!(
+            "{}/alltypes_plain.parquet",
+            datafusion::test_util::parquet_test_data()
+        );
+        for _ in 0..2 {
+            let result = scheduler
+                .get_table_statistics(Request::new(GetTableStatisticsParams {
+                    path: path.clone(),
+                    file_type: FileType::Parquet as i32,
+                    table_name: String::new(),
+                }))
+                .await
+                .expect("Received error response")
+                .into_inner();
+            assert_eq!(result.schema.unwrap().columns.len(), 11);
+            let statistics = result.statistics.unwrap();
+            assert_eq!(statistics.num_rows, 8);
+            assert_eq!(statistics.total_byte_size, 671);
+        }
+        // the statistics of the file are read once
+        assert_eq!(scheduler.table_statistics.cached_files(), 1);
+
+        // the registered tables are resolved by name
+        let mut template = ExecutionContext::new();
+        template.register_parquet("alltypes", &path).await?;
+        let scheduler = scheduler.with_sql_context(template);
+        let result = scheduler
+            .get_table_statistics(Request::new(GetTableStatisticsParams {
+                table_name: "alltypes".to_owned(),
+                ..Default::default()
+            }))
+            .await
+            .expect("Received error response")
+            .into_inner();
+        assert_eq!(result.schema.unwrap().columns.len(), 11);
+        assert_eq!(result.statistics.unwrap().num_rows, 8);
+        assert_eq!(scheduler.table_statistics.cached_files(), 1);
+
+        let result = scheduler
+            .get_table_statistics(Request::new(GetTableStatisticsParams {
+                table_name: "missing".to_owned(),
+                ..Default::default()
+            }))
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
 }
@@ -22,20 +22,27 @@ use ballista_scheduler::externalscaler::external_scaler_server::ExternalScalerSe
 use futures::future::{self, Either, TryFutureExt};
 use hyper::{server::conn::AddrStream, service::make_service_fn, Server};
 use std::convert::Infallible;
+use std::path::PathBuf;
+use std::time::Duration;
 use std::{net::SocketAddr, sync::Arc};
 use tonic::transport::Server as TonicServer;
 use tower::Service;
 
 use ballista_core::BALLISTA_VERSION;
 use ballista_core::{
-    print_version, serde::protobuf::scheduler_grpc_server::SchedulerGrpcServer,
+    config_file, print_version,
+    serde::protobuf::scheduler_grpc_server::SchedulerGrpcServer,
 };
 use ballista_scheduler::api::{get_routes, EitherBody, Error};
+use ballista_scheduler::plan_hook::{LimitRows, PlanHook, RejectUnfilteredCrossJoins};
 #[cfg(feature = "etcd")]
 use ballista_scheduler::state::EtcdClient;
 #[cfg(feature = "sled")]
 use ballista_scheduler::state::StandaloneClient;
-use ballista_scheduler::{state::ConfigBackendClient, ConfigBackend, SchedulerServer};
+use ballista_scheduler::state::{ConfigBackendJobQueue, InMemoryJobQueue, JobQueue};
+use ballista_scheduler::{
+    probe_executors_loop, state::ConfigBackendClient, ConfigBackend, SchedulerServer,
+};
 
 use log::info;
 
@@ -57,19 +64,33 @@ async fn start_server(
     config_backend: Arc<dyn ConfigBackendClient>,
     namespace: String,
     addr: SocketAddr,
+    plan_hooks: Vec<Arc<dyn PlanHook>>,
+    track_map_outputs: bool,
+    job_queue: Arc<dyn JobQueue>,
+    cors_origins: Vec<String>,
 ) -> Result<()> {
     info!(
         "Ballista v{} Scheduler listening on {:?}",
         BALLISTA_VERSION, addr
     );
 
+    // the connections share the scheduler, so that the executors waiting for tasks
+    // are woken up by the jobs submitted on the other connections
+    let scheduler_server = plan_hooks.iter().fold(
+        SchedulerServer::new_with_job_queue(
+            config_backend,
+            namespace,
+            addr.ip(),
+            job_queue,
+        )
+        .with_map_output_tracking(track_map_outputs),
+        |server, hook| server.with_plan_hook(hook.clone()),
+    );
     Ok(Server::bind(&addr)
         .serve(make_service_fn(move |request: &AddrStream| {
-            let scheduler_server = SchedulerServer::new(
-                config_backend.clone(),
-                namespace.clone(),
-                request.remote_addr().ip(),
-            );
+            let scheduler_server = scheduler_server
+                .clone()
+                .with_caller_ip(request.remote_addr().ip());
             let scheduler_grpc_server =
                 SchedulerGrpcServer::new(scheduler_server.clone());
 
@@ -79,12 +100,19 @@ async fn start_server(
                 .add_service(scheduler_grpc_server)
                 .add_service(keda_scaler)
                 .into_service();
-            let mut warp = warp::service(get_routes(scheduler_server));
+            let mut warp = warp::service(get_routes(scheduler_server, &cors_origins));
 
             future::ok::<_, Infallible>(tower::service_fn(
                 move |req: hyper::Request<hyper::Body>| {
-                    let header = req.headers().get(hyper::header::ACCEPT);
-                    if header.is_some() && header.unwrap().eq("application/json") {
+                    // gRPC requests are sent with the application/grpc content type,
+                    // all the other requests are served by the REST API
+                    let is_grpc = req
+                        .headers()
+                        .get(hyper::header::CONTENT_TYPE)
+                        .and_then(|content_type| content_type.to_str().ok())
+                        .map(|content_type| content_type.starts_with("application/grpc"))
+                        .unwrap_or(false);
+                    if !is_grpc {
                         return Either::Left(
                             warp.call(req)
                                 .map_ok(|res| res.map(EitherBody::Left))
@@ -109,9 +137,13 @@ async fn main() -> Result<()> {
     env_logger::init();
 
     // parse options
+    // the config file passed with --config may also be written in YAML
+    let (args, config_file) = config_file::extract_config_arg(std::env::args_os())
+        .context("Could not read the config file")?;
+    let config_files = std::iter::once(PathBuf::from("/etc/ballista/scheduler.toml"))
+        .chain(config_file.as_ref().map(|file| file.path().to_owned()));
     let (opt, _remaining_args) =
-        Config::including_optional_config_files(&["/etc/ballista/scheduler.toml"])
-            .unwrap_or_exit();
+        Config::custom_args_and_optional_files(args, config_files).unwrap_or_exit();
 
     if opt.version {
         print_version();
@@ -158,6 +190,50 @@ async fn main() -> Result<()> {
             )
         }
     };
-    start_server(client, namespace, addr).await?;
+
+    if opt.executor_probe_interval_seconds > 0 {
+        let interval = Duration::from_secs(opt.executor_probe_interval_seconds);
+        tokio::spawn(probe_executors_loop(
+            client.clone(),
+            namespace.clone(),
+            interval,
+        ));
+    }
+
+    let mut plan_hooks: Vec<Arc<dyn PlanHook>> = vec![];
+    if opt.reject_unfiltered_cross_joins {
+        plan_hooks.push(Arc::new(RejectUnfilteredCrossJoins::default()));
+    }
+    if opt.max_result_rows > 0 {
+        plan_hooks.push(Arc::new(LimitRows::new(opt.max_result_rows)));
+    }
+
+    let job_queue: Arc<dyn JobQueue> = if opt.persistent_job_queue {
+        Arc::new(ConfigBackendJobQueue::new(
+            client.clone(),
+            namespace.clone(),
+        ))
+    } else {
+        Arc::new(InMemoryJobQueue::new())
+    };
+
+    let cors_origins = opt
+        .cors_allowed_origins
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(str::to_owned)
+        .collect();
+
+    start_server(
+        client,
+        namespace,
+        addr,
+        plan_hooks,
+        opt.map_output_tracker,
+        job_queue,
+        cors_origins,
+    )
+    .await?;
     Ok(())
 }
@@ -0,0 +1,141 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Hooks invoked on every plan submitted to the scheduler before it is optimized,
+//! to reject dangerous queries or rewrite them, e.g. to limit their results or
+//! to qualify their table references per tenant.
+
+use std::sync::Arc;
+
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_plan::{Limit, LogicalPlan};
+
+/// Decides if a query submitted to the scheduler is executed, and may rewrite it
+pub trait PlanHook: Send + Sync {
+    /// Returns the plan to execute in place of `plan`, or an error to reject the
+    /// query. `principal` is `None` when the query was not authenticated.
+    fn rewrite(&self, plan: LogicalPlan, principal: Option<&str>) -> Result<LogicalPlan>;
+}
+
+/// Rejects the queries joining tables without any join condition or filter,
+/// whose output is the product of the sizes of the joined tables
+#[derive(Debug, Default)]
+pub struct RejectUnfilteredCrossJoins {}
+
+impl RejectUnfilteredCrossJoins {
+    fn check(plan: &LogicalPlan, filtered: bool) -> Result<()> {
+        match plan {
+            LogicalPlan::CrossJoin(_) if !filtered => Err(DataFusionError::Plan(
+                "Cross joins without a filter are not allowed".to_owned(),
+            )),
+            LogicalPlan::Filter(filter) => Self::check(&filter.input, true),
+            // a filter above a join does not apply to the joins within its inputs
+            LogicalPlan::CrossJoin(_) | LogicalPlan::Join(_) => plan
+                .inputs()
+                .into_iter()
+                .try_for_each(|input| Self::check(input, false)),
+            _ => plan
+                .inputs()
+                .into_iter()
+                .try_for_each(|input| Self::check(input, filtered)),
+        }
+    }
+}
+
+impl PlanHook for RejectUnfilteredCrossJoins {
+    fn rewrite(
+        &self,
+        plan: LogicalPlan,
+        _principal: Option<&str>,
+    ) -> Result<LogicalPlan> {
+        Self::check(&plan, false)?;
+        Ok(plan)
+    }
+}
+
+/// Limits the number of rows returned by every query
+#[derive(Debug)]
+pub struct LimitRows {
+    max_rows: usize,
+}
+
+impl LimitRows {
+    /// Create a hook limiting the queries to `max_rows` rows
+    pub fn new(max_rows: usize) -> Self {
+        Self { max_rows }
+    }
+}
+
+impl PlanHook for LimitRows {
+    fn rewrite(
+        &self,
+        plan: LogicalPlan,
+        _principal: Option<&str>,
+    ) -> Result<LogicalPlan> {
+        match &plan {
+            LogicalPlan::Limit(Limit { n, .. }) if *n <= self.max_rows => Ok(plan),
+            LogicalPlan::CreateExternalTable(_)
+            | LogicalPlan::CreateMemoryTable(_)
+            | LogicalPlan::DropTable(_)
+            | LogicalPlan::Explain(_)
+            | LogicalPlan::Analyze(_) => Ok(plan),
+            _ => Ok(LogicalPlan::Limit(Limit {
+                n: self.max_rows,
+                input: Arc::new(plan),
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::logical_plan::{col, lit, LogicalPlanBuilder};
+
+    fn scan(name: &str) -> Result<LogicalPlanBuilder> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        LogicalPlanBuilder::scan_empty(Some(name), &schema, None)
+    }
+
+    #[test]
+    fn reject_unfiltered_cross_joins() -> Result<()> {
+        let hook = RejectUnfilteredCrossJoins::default();
+        let cross_join = scan("t1")?.cross_join(&scan("t2")?.build()?)?;
+        assert!(hook.rewrite(cross_join.build()?, None).is_err());
+
+        let filtered = cross_join.filter(col("t1.a").eq(col("t2.a")))?.build()?;
+        hook.rewrite(filtered, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn limit_rows() -> Result<()> {
+        let hook = LimitRows::new(100);
+        let limited = |plan: LogicalPlan| match hook.rewrite(plan, None) {
+            Ok(LogicalPlan::Limit(Limit { n, input })) => {
+                Some((n, matches!(input.as_ref(), LogicalPlan::Limit(_))))
+            }
+            _ => None,
+        };
+        let filter = scan("t")?.filter(col("a").gt(lit(1)))?;
+        assert_eq!(limited(filter.build()?), Some((100, false)));
+        assert_eq!(limited(filter.limit(10)?.build()?), Some((10, false)));
+        assert_eq!(limited(filter.limit(1000)?.build()?), Some((100, true)));
+        Ok(())
+    }
+}
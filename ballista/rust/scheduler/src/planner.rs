@@ -24,11 +24,28 @@ use std::sync::Arc;
 
 use ballista_core::error::{BallistaError, Result};
 use ballista_core::{
-    execution_plans::{ShuffleReaderExec, ShuffleWriterExec, UnresolvedShuffleExec},
+    execution_plans::{
+        RangeShufflePartitioning, SampleExec, ShuffleReaderExec, ShuffleWriterExec,
+        UnresolvedShuffleExec, DEFAULT_SAMPLE_SIZE,
+    },
     serde::scheduler::PartitionLocation,
 };
+use datafusion::datasource::{FileRange, PartitionedFile};
+use datafusion::physical_plan::coalesce_batches::CoalesceBatchesExec;
 use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use datafusion::physical_plan::estimation::estimate_statistics;
+use datafusion::physical_plan::expressions::Column;
+use datafusion::physical_plan::file_format::{
+    AvroExec, CsvExec, ParquetExec, PhysicalPlanConfig,
+};
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
+use datafusion::physical_plan::hash_join::{HashJoinExec, PartitionMode};
+use datafusion::physical_plan::materialize::MaterializeExec;
+use datafusion::physical_plan::projection::ProjectionExec;
 use datafusion::physical_plan::repartition::RepartitionExec;
+use datafusion::physical_plan::sort::SortExec;
+use datafusion::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
 use datafusion::physical_plan::windows::WindowAggExec;
 use datafusion::physical_plan::{ExecutionPlan, Partitioning};
 use futures::future::BoxFuture;
@@ -39,11 +56,30 @@ type PartialQueryStageResult = (Arc<dyn ExecutionPlan>, Vec<Arc<ShuffleWriterExe
 
 pub struct DistributedPlanner {
     next_stage_id: usize,
+    /// Number of bytes the file scans are split into, or 0 to keep their partitions
+    scan_split_size: u64,
+    /// The shuffles read by the references to the materialized CTEs planned so far,
+    /// by the ids of the CTEs
+    materialized_ctes: HashMap<usize, Arc<UnresolvedShuffleExec>>,
 }
 
 impl DistributedPlanner {
     pub fn new() -> Self {
-        Self { next_stage_id: 0 }
+        Self {
+            next_stage_id: 0,
+            scan_split_size: 0,
+            materialized_ctes: HashMap::new(),
+        }
+    }
+
+    /// Splits the files scanned by the stages into splits of about `split_size` bytes
+    /// rather than keeping the partitions of the scans, so that every split is
+    /// executed by a task, assigned to the next executor polling for work. Only the
+    /// scans whose partitions are read by stages whose partitions don't matter, i.e.
+    /// that are shuffled or return the result of the query, are split.
+    pub fn with_scan_split_size(mut self, split_size: u64) -> Self {
+        self.scan_split_size = split_size;
+        self
     }
 }
 
@@ -63,8 +99,15 @@ impl DistributedPlanner {
         execution_plan: Arc<dyn ExecutionPlan>,
     ) -> Result<Vec<Arc<ShuffleWriterExec>>> {
         info!("planning query stages");
+        self.materialized_ctes.clear();
+        let execution_plan = remove_bucketed_repartitions(execution_plan)?;
+        let execution_plan = if self.scan_split_size > 0 {
+            split_scans(execution_plan, self.scan_split_size, true)?
+        } else {
+            execution_plan
+        };
         let (new_plan, mut stages) = self
-            .plan_query_stages_internal(job_id, execution_plan)
+            .plan_query_stages_internal(job_id, execution_plan, true)
             .await?;
         stages.push(create_shuffle_writer(
             job_id,
@@ -77,84 +120,128 @@ impl DistributedPlanner {
 
     /// Returns a potentially modified version of the input execution_plan along with the resulting query stages.
     /// This function is needed because the input execution_plan might need to be modified, but it might not hold a
-    /// complete query stage (its parent might also belong to the same stage)
+    /// complete query stage (its parent might also belong to the same stage).
+    /// `total_order` is true if the order of the output partitions of `execution_plan`
+    /// is preserved up to the final result of the query.
     fn plan_query_stages_internal<'a>(
         &'a mut self,
         job_id: &'a str,
         execution_plan: Arc<dyn ExecutionPlan>,
+        total_order: bool,
     ) -> BoxFuture<'a, Result<PartialQueryStageResult>> {
         async move {
             // recurse down and replace children
             if execution_plan.children().is_empty() {
                 return Ok((execution_plan, vec![]));
             }
+            if let Some(materialize) =
+                execution_plan.as_any().downcast_ref::<MaterializeExec>()
+            {
+                return self.plan_materialized_cte(job_id, materialize).await;
+            }
 
+            let preserves_order = execution_plan.as_any().is::<ProjectionExec>()
+                || execution_plan.as_any().is::<FilterExec>()
+                || execution_plan.as_any().is::<CoalesceBatchesExec>();
             let mut stages = vec![];
             let mut children = vec![];
             for child in execution_plan.children() {
                 let (new_child, mut child_stages) = self
-                    .plan_query_stages_internal(job_id, child.clone())
+                    .plan_query_stages_internal(
+                        job_id,
+                        child.clone(),
+                        total_order && preserves_order,
+                    )
                     .await?;
                 children.push(new_child);
                 stages.append(&mut child_stages);
             }
 
-            if let Some(coalesce) = execution_plan
+            if execution_plan
                 .as_any()
                 .downcast_ref::<CoalescePartitionsExec>()
+                .is_some()
+                || execution_plan
+                    .as_any()
+                    .downcast_ref::<SortPreservingMergeExec>()
+                    .is_some()
             {
+                // the input partitions are written without repartitioning, so that
+                // every partition is read back from a single file in the order it
+                // was written and a merge of sorted partitions stays correct
                 let shuffle_writer = create_shuffle_writer(
                     job_id,
                     self.next_stage_id(),
                     children[0].clone(),
                     None,
                 )?;
-                let unresolved_shuffle = Arc::new(UnresolvedShuffleExec::new(
-                    shuffle_writer.stage_id(),
-                    shuffle_writer.schema(),
-                    shuffle_writer.output_partitioning().partition_count(),
-                    shuffle_writer
-                        .shuffle_output_partitioning()
-                        .map(|p| p.partition_count())
-                        .unwrap_or_else(|| {
-                            shuffle_writer.output_partitioning().partition_count()
-                        }),
-                ));
+                let unresolved_shuffle = Arc::new(
+                    UnresolvedShuffleExec::new(
+                        shuffle_writer.stage_id(),
+                        shuffle_writer.schema(),
+                        shuffle_writer.output_partitioning().partition_count(),
+                        shuffle_writer
+                            .shuffle_output_partition_count()
+                            .unwrap_or_else(|| {
+                                shuffle_writer.output_partitioning().partition_count()
+                            }),
+                    )
+                    .with_statistics(estimate_statistics(shuffle_writer.as_ref())),
+                );
                 stages.push(shuffle_writer);
                 Ok((
-                    coalesce.with_new_children(vec![unresolved_shuffle])?,
+                    execution_plan.with_new_children(vec![unresolved_shuffle])?,
                     stages,
                 ))
             } else if let Some(repart) =
                 execution_plan.as_any().downcast_ref::<RepartitionExec>()
             {
                 match repart.output_partitioning() {
-                    Partitioning::Hash(_, _) => {
+                    Partitioning::Hash(_, _)
+                    | Partitioning::RangePartitioning(_, _)
+                    | Partitioning::Custom(_, _) => {
                         let shuffle_writer = create_shuffle_writer(
                             job_id,
                             self.next_stage_id(),
                             children[0].clone(),
                             Some(repart.partitioning().to_owned()),
                         )?;
-                        let unresolved_shuffle = Arc::new(UnresolvedShuffleExec::new(
-                            shuffle_writer.stage_id(),
-                            shuffle_writer.schema(),
-                            shuffle_writer.output_partitioning().partition_count(),
-                            shuffle_writer
-                                .shuffle_output_partitioning()
-                                .map(|p| p.partition_count())
-                                .unwrap_or_else(|| {
-                                    shuffle_writer.output_partitioning().partition_count()
-                                }),
-                        ));
+                        let unresolved_shuffle = Arc::new(
+                            UnresolvedShuffleExec::new(
+                                shuffle_writer.stage_id(),
+                                shuffle_writer.schema(),
+                                shuffle_writer.output_partitioning().partition_count(),
+                                shuffle_writer
+                                    .shuffle_output_partition_count()
+                                    .unwrap_or_else(|| {
+                                        shuffle_writer
+                                            .output_partitioning()
+                                            .partition_count()
+                                    }),
+                            )
+                            .with_statistics(
+                                estimate_statistics(shuffle_writer.as_ref()),
+                            ),
+                        );
                         stages.push(shuffle_writer);
                         Ok((unresolved_shuffle, stages))
                     }
                     _ => {
-                        // remove any non-hash repartition from the distributed plan
+                        // remove any other repartition from the distributed plan
                         Ok((children[0].clone(), stages))
                     }
                 }
+            } else if let Some(sort) = execution_plan.as_any().downcast_ref::<SortExec>()
+            {
+                match coalesced_unresolved_shuffle(&children[0]) {
+                    Some(input) if total_order && !sort.preserve_partitioning() => {
+                        let (new_plan, mut sort_stages) =
+                            self.plan_range_partitioned_sort(job_id, sort, input)?;
+                        stages.append(&mut sort_stages);
+                        Ok((new_plan, stages))
+                    }
+                    _ => Ok((execution_plan.with_new_children(children)?, stages)),
+                }
             } else if let Some(window) =
                 execution_plan.as_any().downcast_ref::<WindowAggExec>()
             {
@@ -169,6 +256,98 @@ impl DistributedPlanner {
         .boxed()
     }
 
+    /// Writes the result of a materialized CTE in a stage of its own, read by the
+    /// shuffles that replace all the references to the CTE, so that it is executed
+    /// once per query.
+    async fn plan_materialized_cte(
+        &mut self,
+        job_id: &str,
+        materialize: &MaterializeExec,
+    ) -> Result<PartialQueryStageResult> {
+        if let Some(unresolved_shuffle) = self.materialized_ctes.get(&materialize.id()) {
+            return Ok((unresolved_shuffle.clone(), vec![]));
+        }
+        let (input, mut stages) = self
+            .plan_query_stages_internal(job_id, materialize.input().clone(), false)
+            .await?;
+        let shuffle_writer =
+            create_shuffle_writer(job_id, self.next_stage_id(), input, None)?;
+        let unresolved_shuffle = Arc::new(
+            UnresolvedShuffleExec::new(
+                shuffle_writer.stage_id(),
+                shuffle_writer.schema(),
+                shuffle_writer.output_partitioning().partition_count(),
+                shuffle_writer
+                    .shuffle_output_partition_count()
+                    .unwrap_or_else(|| {
+                        shuffle_writer.output_partitioning().partition_count()
+                    }),
+            )
+            .with_statistics(estimate_statistics(shuffle_writer.as_ref())),
+        );
+        stages.push(shuffle_writer);
+        self.materialized_ctes
+            .insert(materialize.id(), unresolved_shuffle.clone());
+        Ok((unresolved_shuffle, stages))
+    }
+
+    /// Sorts all partitions of a previous query stage without collecting them into a
+    /// single partition. One stage samples the sort keys of every partition, and another
+    /// range partitions the rows using boundaries computed from the samples, so that
+    /// sorting each range partition separately produces a total order.
+    fn plan_range_partitioned_sort(
+        &mut self,
+        job_id: &str,
+        sort: &SortExec,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<PartialQueryStageResult> {
+        let partition_count = input.output_partitioning().partition_count();
+
+        let sample = Arc::new(SampleExec::try_new(
+            input.clone(),
+            sort.expr().to_vec(),
+            DEFAULT_SAMPLE_SIZE,
+        )?);
+        let sample_writer =
+            create_shuffle_writer(job_id, self.next_stage_id(), sample, None)?;
+        let samples = Arc::new(UnresolvedShuffleExec::new(
+            sample_writer.stage_id(),
+            sample_writer.schema(),
+            partition_count,
+            partition_count,
+        ));
+
+        let range_writer = Arc::new(ShuffleWriterExec::try_new_range_partitioned(
+            job_id.to_owned(),
+            self.next_stage_id(),
+            input,
+            "".to_owned(), // executor will decide on the work_dir path
+            RangeShufflePartitioning {
+                sort_exprs: sort.expr().to_vec(),
+                partition_count,
+                samples,
+            },
+        )?);
+        let ranges = Arc::new(
+            UnresolvedShuffleExec::new(
+                range_writer.stage_id(),
+                range_writer.schema(),
+                partition_count,
+                partition_count,
+            )
+            .with_statistics(estimate_statistics(range_writer.as_ref())),
+        );
+
+        Ok((
+            Arc::new(SortExec::new_with_partitioning(
+                sort.expr().to_vec(),
+                ranges,
+                true,
+            )),
+            vec![sample_writer, range_writer],
+        ))
+    }
+
     /// Generate a new stage ID
     fn next_stage_id(&mut self) -> usize {
         self.next_stage_id += 1;
@@ -229,6 +408,295 @@ pub fn remove_unresolved_shuffles(
     Ok(stage.with_new_children(new_children)?)
 }
 
+/// Replaces the unresolved shuffles of the stage of the job `job_id` with shuffle
+/// readers querying the map output tracker for the locations of their partitions,
+/// so that the task definitions don't list the locations of wide shuffles
+pub fn track_unresolved_shuffles(
+    stage: &dyn ExecutionPlan,
+    job_id: &str,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let mut new_children: Vec<Arc<dyn ExecutionPlan>> = vec![];
+    for child in stage.children() {
+        if let Some(unresolved_shuffle) =
+            child.as_any().downcast_ref::<UnresolvedShuffleExec>()
+        {
+            new_children.push(Arc::new(ShuffleReaderExec::try_new_tracked(
+                job_id,
+                unresolved_shuffle.stage_id,
+                unresolved_shuffle.output_partition_count,
+                unresolved_shuffle.schema(),
+            )?))
+        } else {
+            new_children.push(track_unresolved_shuffles(child.as_ref(), job_id)?);
+        }
+    }
+    Ok(stage.with_new_children(new_children)?)
+}
+
+/// Returns the plan of the task executing the partition `partition` of `stage`, in
+/// which the file scans only list the files read by that partition, rather than the
+/// files of every partition of the stage, to keep the task definitions small for
+/// tables of many files.
+///
+/// Only the scans read by operators that execute each of their partitions from the
+/// same partition of their inputs are pruned, as the other operators may read any
+/// partition of their inputs.
+pub fn prune_task_scans(
+    stage: Arc<dyn ExecutionPlan>,
+    partition: usize,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let any = stage.as_any();
+    let reads_same_partition = any.downcast_ref::<ShuffleWriterExec>().is_some()
+        || any.downcast_ref::<FilterExec>().is_some()
+        || any.downcast_ref::<ProjectionExec>().is_some()
+        || any.downcast_ref::<CoalesceBatchesExec>().is_some()
+        || any.downcast_ref::<HashAggregateExec>().is_some()
+        || matches!(
+            any.downcast_ref::<HashJoinExec>(),
+            Some(join) if *join.partition_mode() == PartitionMode::Partitioned
+        );
+    if reads_same_partition {
+        let children = stage
+            .children()
+            .into_iter()
+            .map(|child| prune_task_scans(child, partition))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(stage.with_new_children(children)?);
+    }
+
+    // the scans keep their partitions, only the one executed by the task reading files
+    let prune = |file_groups: &[Vec<PartitionedFile>]| -> Vec<Vec<PartitionedFile>> {
+        file_groups
+            .iter()
+            .enumerate()
+            .map(|(i, files)| {
+                if i == partition {
+                    files.clone()
+                } else {
+                    vec![]
+                }
+            })
+            .collect()
+    };
+    Ok(if let Some(scan) = any.downcast_ref::<ParquetExec>() {
+        Arc::new(scan.with_file_groups(prune(&scan.base_config().file_groups)))
+    } else if let Some(scan) = any.downcast_ref::<CsvExec>() {
+        Arc::new(scan.with_file_groups(prune(&scan.base_config().file_groups)))
+    } else if let Some(scan) = any.downcast_ref::<AvroExec>() {
+        Arc::new(scan.with_file_groups(prune(&scan.base_config().file_groups)))
+    } else {
+        stage
+    })
+}
+
+/// Splits the files of the scans of `plan` into splits of about `split_size` bytes,
+/// one partition per split. `any_partitioning` is true if the partitions of `plan`
+/// may change, i.e. if its parent reads all of them.
+fn split_scans(
+    plan: Arc<dyn ExecutionPlan>,
+    split_size: u64,
+    any_partitioning: bool,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let any = plan.as_any();
+    if any_partitioning {
+        let scan: Option<Arc<dyn ExecutionPlan>> =
+            if let Some(scan) = any.downcast_ref::<ParquetExec>() {
+                split_scan_files(scan.base_config(), split_size, true)
+                    .map(|groups| Arc::new(scan.with_file_groups(groups)) as _)
+            } else if let Some(scan) = any.downcast_ref::<CsvExec>() {
+                split_scan_files(scan.base_config(), split_size, true)
+                    .map(|groups| Arc::new(scan.with_file_groups(groups)) as _)
+            } else if let Some(scan) = any.downcast_ref::<AvroExec>() {
+                split_scan_files(scan.base_config(), split_size, false)
+                    .map(|groups| Arc::new(scan.with_file_groups(groups)) as _)
+            } else {
+                None
+            };
+        if let Some(scan) = scan {
+            return Ok(scan);
+        }
+    }
+    let children = plan.children();
+    if children.is_empty() {
+        return Ok(plan);
+    }
+
+    // operators executing each partition from the same partition of their input
+    // have as many partitions as their input
+    let reads_same_partition = any.downcast_ref::<FilterExec>().is_some()
+        || any.downcast_ref::<ProjectionExec>().is_some()
+        || any.downcast_ref::<CoalesceBatchesExec>().is_some()
+        || matches!(
+            any.downcast_ref::<HashAggregateExec>(),
+            Some(aggregate) if *aggregate.mode() == AggregateMode::Partial
+        );
+    let reads_all_partitions = any.downcast_ref::<RepartitionExec>().is_some()
+        || any.downcast_ref::<CoalescePartitionsExec>().is_some()
+        || any.downcast_ref::<SortPreservingMergeExec>().is_some();
+    let children_any_partitioning =
+        reads_all_partitions || (any_partitioning && reads_same_partition);
+    let children = children
+        .into_iter()
+        .map(|child| split_scans(child, split_size, children_any_partitioning))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(plan.with_new_children(children)?)
+}
+
+/// Splits the files of a scan, returning `None` if the scan is bucketed, as its
+/// partitions are the buckets, or doesn't read any file
+fn split_scan_files(
+    config: &PhysicalPlanConfig,
+    split_size: u64,
+    splittable: bool,
+) -> Option<Vec<Vec<PartitionedFile>>> {
+    if config.bucket_columns.is_some() {
+        return None;
+    }
+    let files: Vec<_> = config.file_groups.iter().flatten().cloned().collect();
+    if files.is_empty() {
+        return None;
+    }
+    Some(generate_splits(files, split_size, splittable))
+}
+
+/// Splits `files` into groups of about `split_size` bytes. The files larger than
+/// `split_size` are split into byte ranges if their format is `splittable`, and the
+/// pieces are assigned to the groups from the largest one, each to the smallest group.
+fn generate_splits(
+    files: Vec<PartitionedFile>,
+    split_size: u64,
+    splittable: bool,
+) -> Vec<Vec<PartitionedFile>> {
+    let mut pieces = vec![];
+    for file in files {
+        let range = file.range.unwrap_or(FileRange {
+            start: 0,
+            end: file.file_meta.size(),
+        });
+        if !splittable || range.end - range.start <= split_size {
+            pieces.push((range.end - range.start, file));
+            continue;
+        }
+        let mut start = range.start;
+        while start < range.end {
+            let end = range.end.min(start + split_size);
+            pieces.push((
+                end - start,
+                PartitionedFile {
+                    range: Some(FileRange { start, end }),
+                    ..file.clone()
+                },
+            ));
+            start = end;
+        }
+    }
+
+    let total_size: u64 = pieces.iter().map(|(size, _)| size).sum();
+    // effectively this is div with rounding up instead of truncating
+    let num_splits = ((total_size + split_size - 1) / split_size)
+        .max(1)
+        .min(pieces.len() as u64) as usize;
+    pieces.sort_by(|(left, _), (right, _)| right.cmp(left));
+    let mut splits = vec![(0, vec![]); num_splits];
+    for (size, file) in pieces {
+        let split = splits
+            .iter_mut()
+            .min_by_key(|(group_size, _)| *group_size)
+            .unwrap();
+        split.0 += size;
+        split.1.push(file);
+    }
+    splits.into_iter().map(|(_, files)| files).collect()
+}
+
+/// Removes the hash repartitions of inputs that are already hash partitioned by the
+/// same columns, e.g. scans of bucketed tables, so that no shuffle is needed for them.
+/// Both inputs of a partitioned hash join must be partitioned the same way, so their
+/// repartitions are only removed if both are co-bucketed on the join keys.
+fn remove_bucketed_repartitions(
+    plan: Arc<dyn ExecutionPlan>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let children = plan
+        .children()
+        .into_iter()
+        .map(remove_bucketed_repartitions)
+        .collect::<Result<Vec<_>>>()?;
+    if children.is_empty() {
+        return Ok(plan);
+    }
+
+    if let Some(join) = plan.as_any().downcast_ref::<HashJoinExec>() {
+        if *join.partition_mode() == PartitionMode::Partitioned {
+            if let (Some((left, left_count)), Some((right, right_count))) = (
+                prepartitioned_input(&children[0]),
+                prepartitioned_input(&children[1]),
+            ) {
+                // the same keys only hash to the same buckets if they have the same type
+                let same_key_types = join.on().iter().all(|(l, r)| {
+                    left.schema().field(l.index()).data_type()
+                        == right.schema().field(r.index()).data_type()
+                });
+                if left_count == right_count && same_key_types {
+                    return Ok(plan.with_new_children(vec![left, right])?);
+                }
+            }
+        }
+    } else if let Some(aggregate) = plan.as_any().downcast_ref::<HashAggregateExec>() {
+        if *aggregate.mode() == AggregateMode::FinalPartitioned {
+            if let Some((input, _)) = prepartitioned_input(&children[0]) {
+                return Ok(plan.with_new_children(vec![input])?);
+            }
+        }
+    }
+    Ok(plan.with_new_children(children)?)
+}
+
+/// Returns the input of a hash repartition, which may be wrapped in a
+/// `CoalesceBatchesExec`, together with its partition count if the input is already
+/// hash partitioned by the same columns
+fn prepartitioned_input(
+    plan: &Arc<dyn ExecutionPlan>,
+) -> Option<(Arc<dyn ExecutionPlan>, usize)> {
+    let plan = match plan.as_any().downcast_ref::<CoalesceBatchesExec>() {
+        Some(coalesce) => coalesce.input(),
+        None => plan,
+    };
+    let repart = plan.as_any().downcast_ref::<RepartitionExec>()?;
+    let input = repart.input();
+    match (repart.partitioning(), input.output_partitioning()) {
+        (Partitioning::Hash(exprs, _), Partitioning::Hash(input_exprs, n))
+            if exprs.len() == input_exprs.len()
+                && exprs.iter().zip(&input_exprs).all(|(e, i)| {
+                    match (
+                        e.as_any().downcast_ref::<Column>(),
+                        i.as_any().downcast_ref::<Column>(),
+                    ) {
+                        (Some(e), Some(i)) => e == i,
+                        _ => false,
+                    }
+                }) =>
+        {
+            Some((input.clone(), n))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the unresolved shuffle below a `CoalescePartitionsExec` if the shuffle has
+/// more than one output partition
+fn coalesced_unresolved_shuffle(
+    plan: &Arc<dyn ExecutionPlan>,
+) -> Option<Arc<dyn ExecutionPlan>> {
+    plan.as_any().downcast_ref::<CoalescePartitionsExec>()?;
+    let input = plan.children()[0].clone();
+    let shuffle = input.as_any().downcast_ref::<UnresolvedShuffleExec>()?;
+    if shuffle.output_partition_count > 1 {
+        Some(input)
+    } else {
+        None
+    }
+}
+
 fn create_shuffle_writer(
     job_id: &str,
     stage_id: usize,
@@ -247,18 +715,29 @@ fn create_shuffle_writer(
 #[cfg(test)]
 mod test {
     use crate::planner::DistributedPlanner;
-    use crate::test_utils::datafusion_test_context;
+    use crate::test_utils::{datafusion_test_context, get_tpch_schema};
     use ballista_core::error::BallistaError;
-    use ballista_core::execution_plans::UnresolvedShuffleExec;
+    use ballista_core::execution_plans::{
+        SampleExec, ShuffleReaderExec, UnresolvedShuffleExec,
+    };
     use ballista_core::serde::protobuf;
+    use datafusion::arrow::array::Int32Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::datasource::listing::{Bucketing, ListingOptions};
+    use datafusion::datasource::{MemTable, PartitionedFile};
+    use datafusion::execution::context::{ExecutionConfig, ExecutionContext};
     use datafusion::physical_plan::coalesce_batches::CoalesceBatchesExec;
+    use datafusion::physical_plan::estimation::estimate_statistics;
+    use datafusion::physical_plan::expressions::{col, PhysicalSortExpr};
+    use datafusion::physical_plan::file_format::CsvExec;
     use datafusion::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
     use datafusion::physical_plan::hash_join::HashJoinExec;
-    use datafusion::physical_plan::sort::SortExec;
-    use datafusion::physical_plan::{
-        coalesce_partitions::CoalescePartitionsExec, projection::ProjectionExec,
-    };
+    use datafusion::physical_plan::projection::ProjectionExec;
+    use datafusion::physical_plan::sort::{SortExec, SortOptions};
+    use datafusion::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
     use datafusion::physical_plan::{displayable, ExecutionPlan};
+    use datafusion::prelude::CsvReadOptions;
     use std::convert::TryInto;
     use std::sync::Arc;
     use uuid::Uuid;
@@ -269,6 +748,223 @@ mod test {
         };
     }
 
+    #[tokio::test]
+    async fn track_unresolved_shuffles() -> Result<(), BallistaError> {
+        let mut ctx = datafusion_test_context("testdata").await?;
+        let df = ctx
+            .sql("select l_returnflag, count(*) from lineitem group by l_returnflag")
+            .await?;
+        let plan = ctx.optimize(&df.to_logical_plan())?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        let mut planner = DistributedPlanner::new();
+        let stages = planner.plan_query_stages("job", plan).await?;
+
+        // stage 1 reads the shuffle written by stage 0
+        let stage = super::track_unresolved_shuffles(stages[1].as_ref(), "job")?;
+        let projection = stage.children()[0].clone();
+        let final_hash = projection.children()[0].clone();
+        let coalesce_batches = final_hash.children()[0].clone();
+        let reader = coalesce_batches.children()[0].clone();
+        let reader = downcast_exec!(reader, ShuffleReaderExec);
+        let shuffle = reader.tracked_shuffle().unwrap();
+        assert_eq!("job", shuffle.job_id);
+        assert_eq!(stages[0].stage_id(), shuffle.stage_id);
+        assert_eq!(2, reader.output_partitioning().partition_count());
+        assert!(reader
+            .partition()
+            .iter()
+            .all(|locations| locations.is_empty()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn estimate_shuffled_stages() -> Result<(), BallistaError> {
+        let mut ctx = datafusion_test_context("testdata").await?;
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from((0..300).collect::<Vec<_>>()))],
+        )?;
+        ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+        let df = ctx.sql("select a, count(*) from t group by a").await?;
+        let plan = ctx.optimize(&df.to_logical_plan())?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        let mut planner = DistributedPlanner::new();
+        let stages = planner.plan_query_stages("job", plan).await?;
+
+        // stage 1 reads the partial aggregates of stage 0, at most a group per row
+        let projection = stages[1].children()[0].clone();
+        let final_hash = projection.children()[0].clone();
+        let coalesce_batches = final_hash.children()[0].clone();
+        let unresolved_shuffle = coalesce_batches.children()[0].clone();
+        let unresolved_shuffle =
+            downcast_exec!(unresolved_shuffle, UnresolvedShuffleExec);
+        assert_eq!(stages[0].stage_id(), unresolved_shuffle.stage_id);
+        assert_eq!(Some(300), unresolved_shuffle.statistics().num_rows);
+        assert_eq!(Some(300), estimate_statistics(stages[1].as_ref()).num_rows);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn materialized_cte_stage() -> Result<(), BallistaError> {
+        let config = ExecutionConfig::new()
+            .with_target_partitions(2)
+            .with_materialize_ctes(true);
+        let mut ctx = ExecutionContext::with_config(config);
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from((0..300).collect::<Vec<_>>()))],
+        )?;
+        ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+        let df = ctx
+            .sql(
+                "with c as (select a, count(*) as n from t group by a)
+                select c.a, d.n from c join c as d on c.a = d.a",
+            )
+            .await?;
+        let plan = ctx.optimize(&df.to_logical_plan())?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        let mut planner = DistributedPlanner::new();
+        let stages = planner.plan_query_stages("job", plan).await?;
+
+        // the CTE is aggregated by a single stage, read by both sides of the join
+        let final_aggregates = stages
+            .iter()
+            .filter(|stage| {
+                displayable(stage.as_ref())
+                    .indent()
+                    .to_string()
+                    .contains("mode=FinalPartitioned")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(1, final_aggregates.len());
+        let cte_stage_id = final_aggregates[0].stage_id();
+        let mut reads = vec![];
+        for stage in &stages {
+            collect_unresolved_shuffles(stage.clone(), &mut reads);
+        }
+        assert_eq!(
+            2,
+            reads
+                .iter()
+                .filter(|shuffle| shuffle.stage_id == cte_stage_id)
+                .count()
+        );
+
+        Ok(())
+    }
+
+    fn collect_unresolved_shuffles(
+        plan: Arc<dyn ExecutionPlan>,
+        shuffles: &mut Vec<UnresolvedShuffleExec>,
+    ) {
+        if let Some(shuffle) = plan.as_any().downcast_ref::<UnresolvedShuffleExec>() {
+            shuffles.push(shuffle.clone());
+        }
+        for child in plan.children() {
+            collect_unresolved_shuffles(child, shuffles);
+        }
+    }
+
+    #[tokio::test]
+    async fn prune_task_scans() -> Result<(), BallistaError> {
+        let mut ctx = datafusion_test_context("testdata").await?;
+        let df = ctx
+            .sql("select l_returnflag, count(*) from lineitem group by l_returnflag")
+            .await?;
+        let plan = ctx.optimize(&df.to_logical_plan())?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        let mut planner = DistributedPlanner::new();
+        let stages = planner.plan_query_stages("job", plan).await?;
+
+        let paths = |stage: Arc<dyn ExecutionPlan>| {
+            let partial_hash = stage.children()[0].clone();
+            let scan = partial_hash.children()[0].clone();
+            let scan = downcast_exec!(scan, CsvExec);
+            scan.base_config()
+                .file_groups
+                .iter()
+                .map(|files| {
+                    files
+                        .iter()
+                        .map(|file| file.file_meta.path().to_owned())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let stage: Arc<dyn ExecutionPlan> = stages[0].clone();
+        let stage_paths = paths(stage.clone());
+        assert_eq!(2, stage_paths.len());
+        assert!(!stage_paths[1].is_empty());
+
+        // only the files of the partition of the task are listed
+        let task_paths = paths(super::prune_task_scans(stage, 1)?);
+        assert_eq!(vec![vec![], stage_paths[1].clone()], task_paths);
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_splits() {
+        let file = |name: &str, size| PartitionedFile::new(name.to_owned(), size);
+        let split_sizes = |splits: Vec<Vec<PartitionedFile>>| {
+            splits
+                .iter()
+                .map(|files| {
+                    files
+                        .iter()
+                        .map(|file| match &file.range {
+                            Some(range) => range.end - range.start,
+                            None => file.file_meta.size(),
+                        })
+                        .sum::<u64>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let files = vec![file("a", 250), file("b", 40), file("c", 60), file("d", 50)];
+        // the large file is split into ranges, the small ones fill the other split
+        let splits = super::generate_splits(files.clone(), 100, true);
+        assert_eq!(vec![100, 100, 100, 100], split_sizes(splits));
+        // files that can't be split are kept whole
+        let splits = super::generate_splits(files, 100, false);
+        assert_eq!(vec![250, 60, 50, 40], split_sizes(splits));
+    }
+
+    #[tokio::test]
+    async fn split_scans() -> Result<(), BallistaError> {
+        let mut ctx = datafusion_test_context("testdata").await?;
+        let df = ctx
+            .sql("select l_returnflag, count(*) from lineitem group by l_returnflag")
+            .await?;
+        let plan = ctx.optimize(&df.to_logical_plan())?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+
+        // the two files of the table fit in a single split
+        let mut planner = DistributedPlanner::new().with_scan_split_size(1 << 20);
+        let stages = planner.plan_query_stages("job", plan).await?;
+        let partial_hash = stages[0].children()[0].clone();
+        let scan = partial_hash.children()[0].clone();
+        let scan = downcast_exec!(scan, CsvExec);
+        assert_eq!(1, scan.base_config().file_groups.len());
+        assert_eq!(2, scan.base_config().file_groups[0].len());
+        assert_eq!(1, stages[0].output_partitioning().partition_count());
+
+        // the next stage reads the shuffle written by a single task
+        let projection = stages[1].children()[0].clone();
+        let final_hash = projection.children()[0].clone();
+        let coalesce_batches = final_hash.children()[0].clone();
+        let unresolved_shuffle = coalesce_batches.children()[0].clone();
+        let unresolved_shuffle =
+            downcast_exec!(unresolved_shuffle, UnresolvedShuffleExec);
+        assert_eq!(1, unresolved_shuffle.input_partition_count);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn distributed_hash_aggregate_plan() -> Result<(), BallistaError> {
         let mut ctx = datafusion_test_context("testdata").await?;
@@ -308,13 +1004,20 @@ mod test {
               CoalesceBatchesExec: target_batch_size=4096
                 UnresolvedShuffleExec
 
+        ShuffleWriterExec: None
+          SampleExec: sample_size=1000, [l_returnflag@0 ASC]
+            UnresolvedShuffleExec
+
+        ShuffleWriterExec: Range([l_returnflag@0 ASC], 2)
+          UnresolvedShuffleExec
+          UnresolvedShuffleExec
+
         ShuffleWriterExec: None
           SortExec: [l_returnflag@0 ASC]
-            CoalescePartitionsExec
-              UnresolvedShuffleExec
+            UnresolvedShuffleExec
         */
 
-        assert_eq!(3, stages.len());
+        assert_eq!(5, stages.len());
 
         // verify stage 0
         let stage0 = stages[0].children()[0].clone();
@@ -338,21 +1041,68 @@ mod test {
 
         // verify stage 2
         let stage2 = stages[2].children()[0].clone();
-        let sort = downcast_exec!(stage2, SortExec);
-        let coalesce_partitions = sort.children()[0].clone();
-        let coalesce_partitions =
-            downcast_exec!(coalesce_partitions, CoalescePartitionsExec);
-        assert_eq!(
-            coalesce_partitions.output_partitioning().partition_count(),
-            1
-        );
-        let unresolved_shuffle = coalesce_partitions.children()[0].clone();
+        let sample = downcast_exec!(stage2, SampleExec);
+        assert_eq!("l_returnflag@0 ASC", sample.sort_exprs()[0].to_string());
+        let unresolved_shuffle = sample.children()[0].clone();
+        let unresolved_shuffle =
+            downcast_exec!(unresolved_shuffle, UnresolvedShuffleExec);
+        assert_eq!(unresolved_shuffle.stage_id, 2);
+        assert_eq!(unresolved_shuffle.input_partition_count, 2);
+        assert_eq!(unresolved_shuffle.output_partition_count, 2);
+
+        // verify stage 3
+        let range = stages[3].range_partitioning().unwrap();
+        assert_eq!(2, range.partition_count);
+        assert_eq!(Some(2), stages[3].shuffle_output_partition_count());
+        let samples = downcast_exec!(range.samples, UnresolvedShuffleExec);
+        assert_eq!(samples.stage_id, 3);
+        let unresolved_shuffle = stages[3].children()[0].clone();
         let unresolved_shuffle =
             downcast_exec!(unresolved_shuffle, UnresolvedShuffleExec);
         assert_eq!(unresolved_shuffle.stage_id, 2);
+
+        // verify stage 4
+        let stage4 = stages[4].children()[0].clone();
+        let sort = downcast_exec!(stage4, SortExec);
+        assert!(sort.preserve_partitioning());
+        assert_eq!(2, sort.output_partitioning().partition_count());
+        let unresolved_shuffle = sort.children()[0].clone();
+        let unresolved_shuffle =
+            downcast_exec!(unresolved_shuffle, UnresolvedShuffleExec);
+        assert_eq!(unresolved_shuffle.stage_id, 4);
         assert_eq!(unresolved_shuffle.input_partition_count, 2);
         assert_eq!(unresolved_shuffle.output_partition_count, 2);
 
+        let stage3_serde = roundtrip_operator(stages[3].clone())?;
+        assert_eq!(format!("{:?}", stages[3]), format!("{:?}", stage3_serde));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn distributed_sort_with_limit_plan() -> Result<(), BallistaError> {
+        let mut ctx = datafusion_test_context("testdata").await?;
+
+        let df = ctx
+            .sql("select l_orderkey from lineitem order by l_orderkey limit 10")
+            .await?;
+
+        let plan = df.to_logical_plan();
+        let plan = ctx.optimize(&plan)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+
+        let mut planner = DistributedPlanner::new();
+        let job_uuid = Uuid::new_v4();
+        let stages = planner
+            .plan_query_stages(&job_uuid.to_string(), plan)
+            .await?;
+
+        // a limit needs all rows in a single partition, so the sort is not range
+        // partitioned
+        assert!(stages
+            .iter()
+            .all(|stage| stage.range_partitioning().is_none()));
+
         Ok(())
     }
 
@@ -435,13 +1185,20 @@ order by
               CoalesceBatchesExec: target_batch_size=4096
                 UnresolvedShuffleExec
 
+        ShuffleWriterExec: None
+          SampleExec: sample_size=1000, [l_shipmode@0 ASC]
+            UnresolvedShuffleExec
+
+        ShuffleWriterExec: Range([l_shipmode@0 ASC], 2)
+          UnresolvedShuffleExec
+          UnresolvedShuffleExec
+
         ShuffleWriterExec: None
           SortExec: [l_shipmode@0 ASC]
-            CoalescePartitionsExec
-              UnresolvedShuffleExec
+            UnresolvedShuffleExec
         */
 
-        assert_eq!(5, stages.len());
+        assert_eq!(7, stages.len());
 
         // verify partitioning for each stage
 
@@ -519,15 +1276,160 @@ order by
         );
         assert!(stages[3].shuffle_output_partitioning().is_none());
 
-        // coalesce partitions and sort
+        // sample of the sort keys
         assert_eq!(
-            1,
+            2,
             stages[4].children()[0]
                 .output_partitioning()
                 .partition_count()
         );
         assert!(stages[4].shuffle_output_partitioning().is_none());
 
+        // range partitioning
+        assert_eq!(Some(2), stages[5].shuffle_output_partition_count());
+
+        // sort of every range partition
+        assert_eq!(
+            2,
+            stages[6].children()[0]
+                .output_partitioning()
+                .partition_count()
+        );
+        assert!(stages[6].shuffle_output_partitioning().is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn distributed_bucketed_join_plan() -> Result<(), BallistaError> {
+        let mut ctx = datafusion_test_context("testdata").await?;
+
+        // the files partition0.tbl and partition1.tbl hold the two buckets
+        let schema = get_tpch_schema("lineitem");
+        for table in ["l1", "l2"] {
+            let options = ListingOptions {
+                bucketing: Some(Bucketing::new(vec!["l_orderkey".to_owned()], 2)),
+                metadata_cache_ttl: None,
+                ..CsvReadOptions::new()
+                    .schema(&schema)
+                    .delimiter(b'|')
+                    .has_header(false)
+                    .file_extension(".tbl")
+                    .to_listing_options(2)
+            };
+            ctx.register_listing_table(
+                table,
+                "testdata/lineitem",
+                options,
+                Some(Arc::new(schema.clone())),
+            )
+            .await?;
+        }
+
+        let df = ctx
+            .sql(
+                "select l2.l_orderkey, count(*)
+                from l1 join l2 on l1.l_orderkey = l2.l_orderkey
+                group by l2.l_orderkey",
+            )
+            .await?;
+
+        let plan = df.to_logical_plan();
+        let plan = ctx.optimize(&plan)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        assert!(displayable(plan.as_ref())
+            .indent()
+            .to_string()
+            .contains("RepartitionExec"));
+
+        let mut planner = DistributedPlanner::new();
+        let job_uuid = Uuid::new_v4();
+        let stages = planner
+            .plan_query_stages(&job_uuid.to_string(), plan)
+            .await?;
+        for stage in &stages {
+            println!("{}", displayable(stage.as_ref()).indent().to_string());
+        }
+
+        // both join inputs and the aggregation are co-bucketed, so nothing is shuffled
+        assert_eq!(1, stages.len());
+        let stage = displayable(stages[0].as_ref()).indent().to_string();
+        assert!(!stage.contains("RepartitionExec"));
+        assert!(!stage.contains("UnresolvedShuffleExec"));
+        assert!(stage.contains("HashJoinExec: mode=Partitioned"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn distributed_sort_preserving_merge_plan() -> Result<(), BallistaError> {
+        let mut ctx = datafusion_test_context("testdata").await?;
+
+        let df = ctx
+            .sql("select l_orderkey, l_shipdate from lineitem")
+            .await?;
+        let plan = df.to_logical_plan();
+        let plan = ctx.optimize(&plan)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        assert_eq!(2, plan.output_partitioning().partition_count());
+
+        let sort_exprs = vec![PhysicalSortExpr {
+            expr: col("l_shipdate", &plan.schema())?,
+            options: SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        }];
+        let sort = Arc::new(SortExec::new_with_partitioning(
+            sort_exprs.clone(),
+            plan,
+            true,
+        ));
+        let plan = Arc::new(SortPreservingMergeExec::new(sort_exprs, sort, 4096));
+
+        let mut planner = DistributedPlanner::new();
+        let job_uuid = Uuid::new_v4();
+        let stages = planner
+            .plan_query_stages(&job_uuid.to_string(), plan)
+            .await?;
+
+        /* Expected result:
+
+        ShuffleWriterExec: None
+          SortExec: [l_shipdate@1 DESC NULLS LAST]
+            ProjectionExec: expr=[l_orderkey@0 as l_orderkey, l_shipdate@1 as l_shipdate]
+              CsvExec: source=Path(testdata/lineitem: [testdata/lineitem/partition0.tbl,testdata/lineitem/partition1.tbl]), has_header=false
+
+        ShuffleWriterExec: None
+          SortPreservingMergeExec: [l_shipdate@1 DESC NULLS LAST]
+            UnresolvedShuffleExec
+        */
+
+        assert_eq!(2, stages.len());
+
+        // the partitions are sorted separately and written without repartitioning
+        let sort = stages[0].children()[0].clone();
+        let sort = downcast_exec!(sort, SortExec);
+        assert!(sort.preserve_partitioning());
+        assert!(stages[0].shuffle_output_partitioning().is_none());
+
+        // and merged by the final stage, keeping the sort specification
+        let merge = stages[1].children()[0].clone();
+        let merge = downcast_exec!(merge, SortPreservingMergeExec);
+        assert_eq!(1, merge.output_partitioning().partition_count());
+        assert_eq!("l_shipdate@1 DESC NULLS LAST", merge.expr()[0].to_string());
+        let unresolved_shuffle = merge.children()[0].clone();
+        let unresolved_shuffle =
+            downcast_exec!(unresolved_shuffle, UnresolvedShuffleExec);
+        assert_eq!(unresolved_shuffle.input_partition_count, 2);
+        assert_eq!(unresolved_shuffle.output_partition_count, 2);
+
+        let merge_serde = roundtrip_operator(stages[1].children()[0].clone())?;
+        assert_eq!(
+            format!("{:?}", stages[1].children()[0]),
+            format!("{:?}", merge_serde)
+        );
+
         Ok(())
     }
 
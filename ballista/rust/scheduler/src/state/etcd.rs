@@ -22,12 +22,17 @@ use std::task::Poll;
 use crate::state::ConfigBackendClient;
 use ballista_core::error::{ballista_error, Result};
 
-use etcd_client::{GetOptions, LockResponse, WatchOptions, WatchStream, Watcher};
+use etcd_client::{
+    GetOptions, LockResponse, Txn, TxnOp, WatchOptions, WatchStream, Watcher,
+};
 use futures::{Stream, StreamExt};
 use log::warn;
 
 use super::{Lock, Watch, WatchEvent};
 
+/// The maximum number of operations of a transaction accepted by default by etcd
+const MAX_TXN_OPS: usize = 128;
+
 /// A [`ConfigBackendClient`] implementation that uses etcd to save cluster configuration.
 #[derive(Clone)]
 pub struct EtcdClient {
@@ -79,6 +84,34 @@ impl ConfigBackendClient for EtcdClient {
             .map(|_| ())
     }
 
+    async fn put_batch(&self, entries: Vec<(String, Vec<u8>)>) -> Result<()> {
+        let mut etcd = self.etcd.clone();
+        // each chunk is saved in its own transaction: the batches of more than
+        // MAX_TXN_OPS entries are not saved atomically
+        for chunk in entries.chunks(MAX_TXN_OPS) {
+            let ops: Vec<TxnOp> = chunk
+                .iter()
+                .map(|(key, value)| TxnOp::put(key.clone(), value.clone(), None))
+                .collect();
+            etcd.txn(Txn::new().and_then(ops)).await.map_err(|e| {
+                warn!("etcd put failed: {}", e);
+                ballista_error("etcd put failed")
+            })?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let mut etcd = self.etcd.clone();
+        etcd.delete(key, None)
+            .await
+            .map_err(|e| {
+                warn!("etcd delete failed: {}", e);
+                ballista_error("etcd delete failed")
+            })
+            .map(|_| ())
+    }
+
     async fn lock(&self) -> Result<Box<dyn Lock>> {
         let mut etcd = self.etcd.clone();
         // TODO: make this a namespaced-lock
@@ -0,0 +1,259 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Queues of the jobs submitted to the scheduler, waiting to be planned.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ballista_core::config::BallistaConfig;
+use ballista_core::error::{BallistaError, Result};
+use ballista_core::serde::protobuf::{self, KeyValuePair};
+use datafusion::logical_plan::LogicalPlan;
+use futures::StreamExt;
+use tokio::sync::{mpsc, Mutex};
+
+use super::{
+    decode_protobuf, encode_protobuf, get_job_queue_prefix, ConfigBackendClient,
+};
+
+/// A job submitted to the scheduler, waiting to be planned
+#[derive(Debug, Clone)]
+pub struct SubmittedJob {
+    pub job_id: String,
+    pub plan: LogicalPlan,
+    pub config: BallistaConfig,
+}
+
+/// A queue of the jobs submitted to the scheduler, from which the scheduler takes
+/// the jobs to plan in the order they were submitted.
+#[tonic::async_trait]
+pub trait JobQueue: Send + Sync {
+    /// Adds a job at the back of the queue.
+    async fn push(&self, job: SubmittedJob) -> Result<()>;
+
+    /// Removes the job at the front of the queue, waiting for a job to be pushed if
+    /// the queue is empty.
+    async fn pop(&self) -> Result<SubmittedJob>;
+}
+
+/// A [JobQueue] held in the memory of the scheduler, whose jobs are lost when the
+/// scheduler stops.
+pub struct InMemoryJobQueue {
+    sender: mpsc::UnboundedSender<SubmittedJob>,
+    receiver: Mutex<mpsc::UnboundedReceiver<SubmittedJob>>,
+}
+
+impl InMemoryJobQueue {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+impl Default for InMemoryJobQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[tonic::async_trait]
+impl JobQueue for InMemoryJobQueue {
+    async fn push(&self, job: SubmittedJob) -> Result<()> {
+        self.sender
+            .send(job)
+            .map_err(|_| BallistaError::General("The job queue is closed".to_owned()))
+    }
+
+    async fn pop(&self) -> Result<SubmittedJob> {
+        self.receiver
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| BallistaError::General("The job queue is closed".to_owned()))
+    }
+}
+
+/// A [JobQueue] saved in the config backend, whose jobs survive the restarts of the
+/// scheduler, and which is shared by the schedulers of a namespace. A job is removed
+/// from the queue when a scheduler starts planning it.
+pub struct ConfigBackendJobQueue {
+    config_client: Arc<dyn ConfigBackendClient>,
+    namespace: String,
+}
+
+impl ConfigBackendJobQueue {
+    pub fn new(config_client: Arc<dyn ConfigBackendClient>, namespace: String) -> Self {
+        Self {
+            config_client,
+            namespace,
+        }
+    }
+
+    /// Removes the job at the front of the queue, if any, while the config backend
+    /// is locked so that every job is taken by a single scheduler
+    async fn try_pop(&self) -> Result<Option<SubmittedJob>> {
+        let mut lock = self.config_client.lock().await?;
+        let job = self.take_front().await;
+        lock.unlock().await;
+        job
+    }
+
+    async fn take_front(&self) -> Result<Option<SubmittedJob>> {
+        let prefix = get_job_queue_prefix(&self.namespace);
+        // the keys are ordered by the time the jobs were pushed
+        let jobs = self.config_client.get_from_prefix(&prefix).await?;
+        match jobs.into_iter().next() {
+            Some((key, value)) => {
+                self.config_client.delete(&key).await?;
+                decode_submitted_job(&value).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl JobQueue for ConfigBackendJobQueue {
+    async fn push(&self, job: SubmittedJob) -> Result<()> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_nanos();
+        let key = format!(
+            "{}/{:020}/{}",
+            get_job_queue_prefix(&self.namespace),
+            time,
+            job.job_id
+        );
+        let value = encode_submitted_job(&job)?;
+        self.config_client.put(key, value).await
+    }
+
+    async fn pop(&self) -> Result<SubmittedJob> {
+        let prefix = get_job_queue_prefix(&self.namespace);
+        loop {
+            // watched before taking a job, so that no job pushed meanwhile is missed
+            let mut watch = self.config_client.watch(prefix.clone()).await?;
+            if let Some(job) = self.try_pop().await? {
+                watch.cancel().await?;
+                return Ok(job);
+            }
+            let event = watch.next().await;
+            watch.cancel().await?;
+            if event.is_none() {
+                return Err(BallistaError::General(
+                    "The watch of the job queue ended".to_owned(),
+                ));
+            }
+        }
+    }
+}
+
+fn encode_submitted_job(job: &SubmittedJob) -> Result<Vec<u8>> {
+    let plan: protobuf::LogicalPlanNode = (&job.plan).try_into()?;
+    encode_protobuf(&protobuf::QueuedJobPlan {
+        job_id: job.job_id.clone(),
+        plan: Some(plan),
+        settings: job
+            .config
+            .settings()
+            .iter()
+            .map(|(key, value)| KeyValuePair {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect(),
+    })
+}
+
+fn decode_submitted_job(bytes: &[u8]) -> Result<SubmittedJob> {
+    let job: protobuf::QueuedJobPlan = decode_protobuf(bytes)?;
+    let plan = job.plan.as_ref().ok_or_else(|| {
+        BallistaError::Internal(format!("Queued job {} has no plan", job.job_id))
+    })?;
+    let settings: HashMap<String, String> = job
+        .settings
+        .into_iter()
+        .map(|setting| (setting.key, setting.value))
+        .collect();
+    Ok(SubmittedJob {
+        plan: plan.try_into()?,
+        config: BallistaConfig::with_settings(settings)?,
+        job_id: job.job_id,
+    })
+}
+
+#[cfg(all(test, feature = "sled"))]
+mod tests {
+    use std::sync::Arc;
+
+    use ballista_core::config::BallistaConfig;
+    use ballista_core::error::Result;
+    use datafusion::logical_plan::LogicalPlanBuilder;
+
+    use super::{ConfigBackendJobQueue, InMemoryJobQueue, JobQueue, SubmittedJob};
+    use crate::state::{ConfigBackendClient, StandaloneClient};
+
+    fn submitted_job(job_id: &str) -> Result<SubmittedJob> {
+        Ok(SubmittedJob {
+            job_id: job_id.to_owned(),
+            plan: LogicalPlanBuilder::empty(false).build()?,
+            config: BallistaConfig::builder()
+                .set("ballista.shuffle.partitions", "4")
+                .build()?,
+        })
+    }
+
+    #[tokio::test]
+    async fn in_memory_job_queue() -> Result<()> {
+        let queue = InMemoryJobQueue::new();
+        queue.push(submitted_job("a")?).await?;
+        queue.push(submitted_job("b")?).await?;
+        assert_eq!(queue.pop().await?.job_id, "a");
+        assert_eq!(queue.pop().await?.job_id, "b");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn config_backend_job_queue() -> Result<()> {
+        let config_client: Arc<dyn ConfigBackendClient> =
+            Arc::new(StandaloneClient::try_new_temporary()?);
+        let queue = ConfigBackendJobQueue::new(config_client.clone(), "test".to_owned());
+        queue.push(submitted_job("a")?).await?;
+        queue.push(submitted_job("b")?).await?;
+        let job = queue.pop().await?;
+        assert_eq!(job.job_id, "a");
+        assert_eq!(job.config.default_shuffle_partitions(), 4);
+
+        // the queue of a restarted scheduler holds the jobs that were not taken
+        let restarted = ConfigBackendJobQueue::new(config_client, "test".to_owned());
+        assert_eq!(restarted.pop().await?.job_id, "b");
+
+        // a job pushed while waiting for one is taken
+        let waiting = tokio::spawn(async move { restarted.pop().await });
+        queue.push(submitted_job("c")?).await?;
+        assert_eq!(waiting.await.unwrap()?.job_id, "c");
+        Ok(())
+    }
+}
@@ -17,33 +17,52 @@
 
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{
-    any::type_name, collections::HashMap, convert::TryInto, sync::Arc, time::Duration,
+    any::type_name,
+    collections::{BTreeMap, HashMap},
+    convert::TryInto,
+    sync::Arc,
+    time::Duration,
 };
 
+use datafusion::physical_plan::metrics::MetricsSet;
 use datafusion::physical_plan::ExecutionPlan;
+use futures::future::join_all;
 use futures::{Stream, StreamExt};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use prost::Message;
 use tokio::sync::OwnedMutexGuard;
 
+use ballista_core::client::BallistaClient;
 use ballista_core::serde::protobuf::{
     self, job_status, task_status, CompletedJob, CompletedTask, ExecutorHeartbeat,
-    ExecutorMetadata, FailedJob, FailedTask, JobStatus, PhysicalPlanNode, RunningJob,
-    RunningTask, TaskStatus,
+    ExecutorMetadata, FailedJob, FailedTask, JobLabels, JobStatus, PhysicalPlanNode,
+    RunningJob, RunningTask, ShuffleWritePartition, TaskStatus,
 };
-use ballista_core::serde::scheduler::PartitionStats;
+use ballista_core::serde::scheduler::{PartitionId, PartitionLocation, PartitionStats};
+use ballista_core::utils::format_plan_with_metrics;
 use ballista_core::{error::BallistaError, serde::scheduler::ExecutorMeta};
 use ballista_core::{error::Result, execution_plans::UnresolvedShuffleExec};
 
-use super::planner::remove_unresolved_shuffles;
+use super::planner::{
+    prune_task_scans, remove_unresolved_shuffles, track_unresolved_shuffles,
+};
 
 #[cfg(feature = "etcd")]
 mod etcd;
+mod job_queue;
 #[cfg(feature = "sled")]
 mod standalone;
 
+/// Time after which an executor that did not poll the scheduler is considered dead
+const EXECUTOR_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Number of times a task is executed again because a shuffle partition it wrote was
+/// lost, before failing its job
+pub const MAX_LOST_PARTITION_RESCHEDULES: u32 = 3;
+
 #[cfg(feature = "etcd")]
 pub use etcd::EtcdClient;
+pub use job_queue::{ConfigBackendJobQueue, InMemoryJobQueue, JobQueue, SubmittedJob};
 #[cfg(feature = "sled")]
 pub use standalone::StandaloneClient;
 
@@ -61,6 +80,16 @@ pub trait ConfigBackendClient: Send + Sync {
     /// Saves the value into the provided key, overriding any previous data that might have been associated to that key.
     async fn put(&self, key: String, value: Vec<u8>) -> Result<()>;
 
+    /// Saves the values into the provided keys with as few writes as possible, as
+    /// [ConfigBackendClient::put] does for each of them. The values are only saved
+    /// atomically if the backend saves them in a single write: etcd saves them in
+    /// transactions of at most 128 operations, so that a failure may leave some
+    /// of them saved.
+    async fn put_batch(&self, entries: Vec<(String, Vec<u8>)>) -> Result<()>;
+
+    /// Removes the data associated with a specific key, if any.
+    async fn delete(&self, key: &str) -> Result<()>;
+
     async fn lock(&self) -> Result<Box<dyn Lock>>;
 
     /// Watch all events that happen on a specific prefix.
@@ -82,6 +111,18 @@ pub enum WatchEvent {
     Delete(String),
 }
 
+/// A stage of a job, see [`SchedulerState::get_job_stages`]
+pub struct StageInfo {
+    pub stage_id: usize,
+    pub plan: Arc<dyn ExecutionPlan>,
+    /// The number of tasks of the stage, one per input partition
+    pub tasks: usize,
+    pub completed_tasks: usize,
+    /// The metrics of the operators of the plan in pre-order, summed over the
+    /// completed tasks, which are empty if no task completed
+    pub metrics: Vec<MetricsSet>,
+}
+
 #[derive(Clone)]
 pub(super) struct SchedulerState {
     config_client: Arc<dyn ConfigBackendClient>,
@@ -108,7 +149,9 @@ impl SchedulerState {
             .expect("Time went backwards");
         for (_key, entry) in entries {
             let heartbeat: ExecutorHeartbeat = decode_protobuf(&entry)?;
-            let meta = heartbeat.meta.unwrap();
+            let meta = heartbeat.meta.ok_or_else(|| {
+                BallistaError::General("Executor heartbeat without metadata".to_owned())
+            })?;
             let ts = Duration::from_secs(heartbeat.timestamp);
             let time_since_last_seen = now_epoch_ts
                 .checked_sub(ts)
@@ -147,6 +190,47 @@ impl SchedulerState {
         self.config_client.put(key, value).await
     }
 
+    pub async fn remove_executor_metadata(&self, executor_id: &str) -> Result<()> {
+        let key = get_executor_key(&self.namespace, executor_id);
+        self.config_client.delete(&key).await
+    }
+
+    /// Connects to the flight service of every executor, and removes the executors
+    /// that cannot be reached within `timeout` so that their tasks are rescheduled
+    /// without waiting for their heartbeats to expire. Returns the removed executors.
+    pub async fn probe_executors(&self, timeout: Duration) -> Result<Vec<String>> {
+        let executors = self.get_alive_executors_metadata(EXECUTOR_TIMEOUT).await?;
+        let probes = executors.iter().map(|executor| async move {
+            let probe = BallistaClient::try_new(&executor.host, executor.port);
+            match tokio::time::timeout(timeout, probe).await {
+                Ok(Ok(_)) => None,
+                Ok(Err(e)) => Some((executor, e.to_string())),
+                Err(_) => Some((executor, "timed out".to_owned())),
+            }
+        });
+        let mut removed = vec![];
+        for (executor, error) in join_all(probes).await.into_iter().flatten() {
+            warn!(
+                "Removing executor {} at {}:{} that failed its health probe: {}",
+                executor.id, executor.host, executor.port, error
+            );
+            self.remove_executor_metadata(&executor.id).await?;
+            removed.push(executor.id.clone());
+        }
+        Ok(removed)
+    }
+
+    /// Probes the executors every `interval`, see [SchedulerState::probe_executors].
+    pub async fn probe_executors_loop(&self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.probe_executors(interval).await {
+                error!("Could not probe the executors: {}", e);
+            }
+        }
+    }
+
     pub async fn save_job_metadata(
         &self,
         job_id: &str,
@@ -158,29 +242,279 @@ impl SchedulerState {
         self.config_client.put(key, value).await
     }
 
-    pub async fn get_job_metadata(&self, job_id: &str) -> Result<JobStatus> {
+    /// Returns the metadata of a job, or `None` if the job does not exist
+    pub async fn get_job_metadata(&self, job_id: &str) -> Result<Option<JobStatus>> {
         let key = get_job_key(&self.namespace, job_id);
         let value = &self.config_client.get(&key).await?;
         if value.is_empty() {
-            return Err(BallistaError::General(format!(
-                "No job metadata found for {}",
-                key
-            )));
+            return Ok(None);
         }
         let value: JobStatus = decode_protobuf(value)?;
-        Ok(value)
+        Ok(Some(value))
+    }
+
+    /// Returns the metadata of all the jobs, by job id
+    pub async fn get_jobs_metadata(&self) -> Result<Vec<(String, JobStatus)>> {
+        let prefix = get_job_prefix(&self.namespace);
+        self.config_client
+            .get_from_prefix(&prefix)
+            .await?
+            .into_iter()
+            .map(|(key, bytes)| {
+                let job_id = key.trim_start_matches(&prefix).trim_start_matches('/');
+                Ok((job_id.to_owned(), decode_protobuf(&bytes)?))
+            })
+            .collect()
+    }
+
+    /// Cancels a queued or running job by failing it along with its pending tasks, so
+    /// that no more of its tasks are scheduled and the executors cancel the running
+    /// ones. Returns false, without saving anything, if the job already completed or
+    /// failed.
+    pub async fn cancel_job(&self, job_id: &str) -> Result<bool> {
+        match self.get_job_metadata(job_id).await? {
+            Some(JobStatus {
+                status:
+                    Some(job_status::Status::Completed(_))
+                    | Some(job_status::Status::Failed(_)),
+            }) => return Ok(false),
+            None => {
+                return Err(BallistaError::General(format!(
+                    "Job {} does not exist",
+                    job_id
+                )))
+            }
+            _ => (),
+        }
+        self.fail_job(job_id, "Job cancelled".to_owned()).await?;
+        Ok(true)
+    }
+
+    /// Fails a job along with its pending tasks
+    async fn fail_job(&self, job_id: &str, error: String) -> Result<()> {
+        self.save_job_metadata(
+            job_id,
+            &JobStatus {
+                status: Some(job_status::Status::Failed(FailedJob {
+                    error: error.clone(),
+                })),
+            },
+        )
+        .await?;
+        let tasks = self
+            .config_client
+            .get_from_prefix(&get_task_prefix_for_job(&self.namespace, job_id))
+            .await?;
+        let mut failed = vec![];
+        for (_key, bytes) in tasks {
+            let mut task: TaskStatus = decode_protobuf(&bytes)?;
+            if task.status.is_none() {
+                task.status = Some(task_status::Status::Failed(FailedTask {
+                    error: error.clone(),
+                    ..Default::default()
+                }));
+                failed.push(task);
+            }
+        }
+        self.save_task_statuses(&failed).await
+    }
+
+    /// Saves the labels that the client attached to a job
+    pub async fn save_job_labels(&self, job_id: &str, labels: &JobLabels) -> Result<()> {
+        let key = get_job_labels_key(&self.namespace, job_id);
+        let value = encode_protobuf(labels)?;
+        self.config_client.put(key, value).await
+    }
+
+    /// Returns the labels that the client attached to a job, which are empty if
+    /// none were attached
+    pub async fn get_job_labels(&self, job_id: &str) -> Result<JobLabels> {
+        let key = get_job_labels_key(&self.namespace, job_id);
+        let value = &self.config_client.get(&key).await?;
+        decode_protobuf(value)
+    }
+
+    /// Registers the output partitions of a completed job as a dataset, so that they
+    /// can be scanned by later queries instead of recomputing the job.
+    pub async fn persist_dataset(
+        &self,
+        job_id: &str,
+        mut partition_location: Vec<protobuf::PartitionLocation>,
+        schema: protobuf::Schema,
+    ) -> Result<protobuf::Dataset> {
+        partition_location.sort_by_key(|location| {
+            location
+                .partition_id
+                .as_ref()
+                .map(|id| id.partition_id)
+                .unwrap_or_default()
+        });
+        let dataset = protobuf::Dataset {
+            dataset_id: job_id.to_owned(),
+            schema: Some(schema),
+            partition: partition_location
+                .into_iter()
+                .map(|location| protobuf::ShuffleReaderPartition {
+                    location: vec![location],
+                })
+                .collect(),
+        };
+        let key = get_dataset_key(&self.namespace, job_id);
+        let value = encode_protobuf(&dataset)?;
+        self.config_client.put(key, value).await?;
+        Ok(dataset)
+    }
+
+    /// Returns a persisted dataset, or `None` if the dataset does not exist
+    pub async fn get_dataset(
+        &self,
+        dataset_id: &str,
+    ) -> Result<Option<protobuf::Dataset>> {
+        let key = get_dataset_key(&self.namespace, dataset_id);
+        let value = &self.config_client.get(&key).await?;
+        if value.is_empty() {
+            return Ok(None);
+        }
+        let value: protobuf::Dataset = decode_protobuf(value)?;
+        Ok(Some(value))
+    }
+
+    /// Appends partitions to a dataset, creating the dataset if it does not exist yet.
+    /// The partitions are numbered after the ones of the dataset, and must have its
+    /// schema.
+    pub async fn append_dataset(
+        &self,
+        dataset_id: &str,
+        partition_location: Vec<protobuf::PartitionLocation>,
+        schema: protobuf::Schema,
+    ) -> Result<protobuf::Dataset> {
+        let mut dataset = match self.get_dataset(dataset_id).await? {
+            Some(dataset) => {
+                if dataset.schema.as_ref() != Some(&schema) {
+                    return Err(BallistaError::General(format!(
+                        "Cannot append partitions of a different schema to dataset {}",
+                        dataset_id
+                    )));
+                }
+                dataset
+            }
+            None => protobuf::Dataset {
+                dataset_id: dataset_id.to_owned(),
+                schema: Some(schema),
+                partition: vec![],
+            },
+        };
+        for mut location in partition_location {
+            location.partition_id = Some(protobuf::PartitionId {
+                job_id: dataset_id.to_owned(),
+                stage_id: 0,
+                partition_id: dataset.partition.len() as u32,
+            });
+            dataset.partition.push(protobuf::ShuffleReaderPartition {
+                location: vec![location],
+            });
+        }
+        let key = get_dataset_key(&self.namespace, dataset_id);
+        let value = encode_protobuf(&dataset)?;
+        self.config_client.put(key, value).await?;
+        Ok(dataset)
     }
 
     pub async fn save_task_status(&self, status: &TaskStatus) -> Result<()> {
-        let partition_id = status.partition_id.as_ref().unwrap();
-        let key = get_task_status_key(
+        self.save_task_statuses(std::slice::from_ref(status)).await
+    }
+
+    /// Saves the statuses of many tasks at once, in as few writes to the config
+    /// backend as possible. The statuses are not saved atomically when the backend
+    /// splits the writes, see [ConfigBackendClient::put_batch]
+    pub async fn save_task_statuses(&self, statuses: &[TaskStatus]) -> Result<()> {
+        let entries = statuses
+            .iter()
+            .map(|status| Ok((self.task_status_key(status)?, encode_protobuf(status)?)))
+            .collect::<Result<Vec<_>>>()?;
+        self.config_client.put_batch(entries).await
+    }
+
+    /// The key of the status of a task, which may have been sent by an executor without
+    /// its partition
+    fn task_status_key(&self, status: &TaskStatus) -> Result<String> {
+        let partition_id = task_partition_id(status)?;
+        Ok(get_task_status_key(
             &self.namespace,
             &partition_id.job_id,
             partition_id.stage_id as usize,
             partition_id.partition_id as usize,
-        );
-        let value = encode_protobuf(status)?;
-        self.config_client.put(key, value).await
+        ))
+    }
+
+    /// Saves a task that failed to read a corrupted or unreachable shuffle partition as
+    /// pending again, along with the task that wrote the partition, so that both are
+    /// executed again. Returns false, without saving anything, if the task did not fail
+    /// because of a lost partition or if the task that wrote it cannot be found.
+    ///
+    /// The partitions written by a task are only rescheduled
+    /// [MAX_LOST_PARTITION_RESCHEDULES] times: the job fails the next time one of them
+    /// is lost, and false is returned.
+    pub async fn reschedule_lost_partition(&self, status: &TaskStatus) -> Result<bool> {
+        let path = match &status.status {
+            Some(task_status::Status::Failed(FailedTask {
+                lost_partition_path,
+                ..
+            })) if !lost_partition_path.is_empty() => lost_partition_path,
+            _ => return Ok(false),
+        };
+        let job_id = &task_partition_id(status)?.job_id;
+        let tasks = self
+            .config_client
+            .get_from_prefix(&get_task_prefix_for_job(&self.namespace, job_id))
+            .await?;
+        for (_key, bytes) in tasks {
+            let mut task: TaskStatus = decode_protobuf(&bytes)?;
+            let wrote_partition = matches!(
+                &task.status,
+                Some(task_status::Status::Completed(CompletedTask { partitions, .. }))
+                    if partitions.iter().any(|partition| &partition.path == path)
+            );
+            if wrote_partition {
+                let writer = task_partition_id(&task)?;
+                let attempts_key = get_lost_partition_attempts_key(
+                    &self.namespace,
+                    job_id,
+                    writer.stage_id as usize,
+                    writer.partition_id as usize,
+                );
+                let attempts = self.config_client.get(&attempts_key).await?;
+                let attempts = if attempts.is_empty() {
+                    0
+                } else {
+                    decode_attempts(&attempts)?
+                };
+                if attempts >= MAX_LOST_PARTITION_RESCHEDULES {
+                    let error = format!(
+                        "Shuffle partition {} is lost, after rescheduling the task {:?} \
+                        that wrote it {} times",
+                        path, writer, attempts
+                    );
+                    warn!("{}. Failing job {}", error, job_id);
+                    self.fail_job(job_id, error).await?;
+                    return Ok(false);
+                }
+                warn!(
+                    "Shuffle partition {} is lost. Rescheduling task {:?} that wrote it",
+                    path, writer
+                );
+                self.config_client
+                    .put(attempts_key, (attempts + 1).to_string().into_bytes())
+                    .await?;
+                task.status = None;
+                self.save_task_status(&task).await?;
+                let mut status = status.clone();
+                status.status = None;
+                self.save_task_status(&status).await?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
     }
 
     pub async fn _get_task_status(
@@ -188,17 +522,14 @@ impl SchedulerState {
         job_id: &str,
         stage_id: usize,
         partition_id: usize,
-    ) -> Result<TaskStatus> {
+    ) -> Result<Option<TaskStatus>> {
         let key = get_task_status_key(&self.namespace, job_id, stage_id, partition_id);
         let value = &self.config_client.clone().get(&key).await?;
         if value.is_empty() {
-            return Err(BallistaError::General(format!(
-                "No task status found for {}",
-                key
-            )));
+            return Ok(None);
         }
         let value: TaskStatus = decode_protobuf(value)?;
-        Ok(value)
+        Ok(Some(value))
     }
 
     // "Unnecessary" lifetime syntax due to https://github.com/rust-lang/rust/issues/63033
@@ -233,6 +564,74 @@ impl SchedulerState {
         Ok((&value).try_into()?)
     }
 
+    /// Returns the plan of each stage of the job with completed tasks, formatted with
+    /// the metrics of its operators summed over the completed tasks, by stage id
+    pub async fn get_job_metrics(&self, job_id: &str) -> Result<Vec<(usize, String)>> {
+        Ok(self
+            .get_job_stages(job_id)
+            .await?
+            .into_iter()
+            .filter(|stage| stage.completed_tasks > 0)
+            .map(|stage| {
+                (
+                    stage.stage_id,
+                    format_plan_with_metrics(stage.plan.as_ref(), &stage.metrics),
+                )
+            })
+            .collect())
+    }
+
+    /// Returns the stages of a job ordered by stage id, with the metrics reported by
+    /// their completed tasks
+    pub async fn get_job_stages(&self, job_id: &str) -> Result<Vec<StageInfo>> {
+        let tasks = self
+            .config_client
+            .get_from_prefix(&get_task_prefix_for_job(&self.namespace, job_id))
+            .await?;
+        // the number of tasks, of completed tasks and the metrics of each stage
+        let mut stages: BTreeMap<usize, (usize, usize, Vec<MetricsSet>)> =
+            BTreeMap::new();
+        for (_key, bytes) in tasks {
+            let task: TaskStatus = decode_protobuf(&bytes)?;
+            let stage_id = task_partition_id(&task)?.stage_id as usize;
+            let (tasks, completed_tasks, stage_metrics) =
+                stages.entry(stage_id).or_default();
+            *tasks += 1;
+            let task_metrics = match task.status {
+                Some(task_status::Status::Completed(CompletedTask {
+                    metrics, ..
+                })) => metrics,
+                _ => continue,
+            };
+            *completed_tasks += 1;
+            for (i, operator_metrics) in task_metrics.into_iter().enumerate() {
+                let operator_metrics: MetricsSet = operator_metrics.try_into()?;
+                if stage_metrics.len() <= i {
+                    stage_metrics.push(MetricsSet::new());
+                }
+                for metric in operator_metrics.iter() {
+                    stage_metrics[i].push(metric.clone());
+                }
+            }
+        }
+
+        let mut result = Vec::with_capacity(stages.len());
+        for (stage_id, (tasks, completed_tasks, stage_metrics)) in stages {
+            let plan = self.get_stage_plan(job_id, stage_id).await?;
+            result.push(StageInfo {
+                stage_id,
+                plan,
+                tasks,
+                completed_tasks,
+                metrics: stage_metrics
+                    .iter()
+                    .map(|metrics| metrics.aggregate_by_partition())
+                    .collect(),
+            });
+        }
+        Ok(result)
+    }
+
     pub async fn get_all_tasks(&self) -> Result<HashMap<String, TaskStatus>> {
         self.config_client
             .get_from_prefix(&get_task_prefix(&self.namespace))
@@ -264,8 +663,7 @@ impl SchedulerState {
         if task_is_dead {
             info!(
                 "Executor {} isn't alive. Rescheduling task {:?}",
-                executor_id,
-                task_status.partition_id.as_ref().unwrap()
+                executor_id, task_status.partition_id
             );
             // Task was handled in an executor that isn't alive anymore, so we can't resolve it
             // We mark the task as pending again and continue
@@ -279,15 +677,21 @@ impl SchedulerState {
     pub async fn assign_next_schedulable_task(
         &self,
         executor_id: &str,
+        track_map_outputs: bool,
     ) -> Result<Option<(TaskStatus, Arc<dyn ExecutionPlan>)>> {
         let tasks = self.get_all_tasks().await?;
         // TODO: Make the duration a configurable parameter
-        let executors = self
-            .get_alive_executors_metadata(Duration::from_secs(60))
-            .await?;
+        let executors = self.get_alive_executors_metadata(EXECUTOR_TIMEOUT).await?;
+        let zone = executors
+            .iter()
+            .find(|exec| exec.id == executor_id)
+            .and_then(|exec| exec.zone());
+        // The first task reading shuffle partitions from executors in another zone,
+        // only assigned if no task reads them from the zone of the executor
+        let mut remote_task = None;
         'tasks: for (_key, status) in tasks.iter() {
             if status.status.is_none() {
-                let partition = status.partition_id.as_ref().unwrap();
+                let partition = task_partition_id(status)?;
                 let plan = self
                     .get_stage_plan(&partition.job_id, partition.stage_id as usize)
                     .await?;
@@ -297,8 +701,8 @@ impl SchedulerState {
                 let mut partition_locations: HashMap<
                     usize, // stage id
                     HashMap<
-                        usize, // shuffle output partition id
-                        Vec<ballista_core::serde::scheduler::PartitionLocation>, // shuffle partitions
+                        usize,                  // shuffle output partition id
+                        Vec<PartitionLocation>, // shuffle partitions
                     >,
                 > = HashMap::new();
                 for unresolved_shuffle in unresolved_shuffles {
@@ -307,14 +711,19 @@ impl SchedulerState {
                     for shuffle_input_partition_id in
                         0..unresolved_shuffle.input_partition_count
                     {
-                        let referenced_task = tasks
-                            .get(&get_task_status_key(
-                                &self.namespace,
-                                &partition.job_id,
-                                unresolved_shuffle.stage_id,
-                                shuffle_input_partition_id,
-                            ))
-                            .unwrap();
+                        let referenced_task_key = get_task_status_key(
+                            &self.namespace,
+                            &partition.job_id,
+                            unresolved_shuffle.stage_id,
+                            shuffle_input_partition_id,
+                        );
+                        let referenced_task =
+                            tasks.get(&referenced_task_key).ok_or_else(|| {
+                                BallistaError::Internal(format!(
+                                    "No task status for the shuffle input {}",
+                                    referenced_task_key
+                                ))
+                            })?;
                         let task_is_dead = self
                             .reschedule_dead_task(referenced_task, &executors)
                             .await?;
@@ -324,6 +733,7 @@ impl SchedulerState {
                             CompletedTask {
                                 executor_id,
                                 partitions,
+                                ..
                             },
                         )) = &referenced_task.status
                         {
@@ -337,32 +747,24 @@ impl SchedulerState {
                             let executor_meta = executors
                                 .iter()
                                 .find(|exec| exec.id == *executor_id)
-                                .unwrap()
+                                .ok_or_else(|| {
+                                    BallistaError::Internal(format!(
+                                        "Executor {} is not alive",
+                                        executor_id
+                                    ))
+                                })?
                                 .clone();
 
                             for shuffle_write_partition in partitions {
                                 let temp = stage_shuffle_partition_locations
                                     .entry(shuffle_write_partition.partition_id as usize)
                                     .or_insert_with(Vec::new);
-                                let executor_meta = executor_meta.clone();
-                                let partition_location =
-                                    ballista_core::serde::scheduler::PartitionLocation {
-                                        partition_id:
-                                            ballista_core::serde::scheduler::PartitionId {
-                                                job_id: partition.job_id.clone(),
-                                                stage_id: unresolved_shuffle.stage_id,
-                                                partition_id: shuffle_write_partition
-                                                    .partition_id
-                                                    as usize,
-                                            },
-                                        executor_meta,
-                                        partition_stats: PartitionStats::new(
-                                            Some(shuffle_write_partition.num_rows),
-                                            Some(shuffle_write_partition.num_batches),
-                                            Some(shuffle_write_partition.num_bytes),
-                                        ),
-                                        path: shuffle_write_partition.path.clone(),
-                                    };
+                                let partition_location = to_partition_location(
+                                    &partition.job_id,
+                                    unresolved_shuffle.stage_id,
+                                    &executor_meta,
+                                    shuffle_write_partition,
+                                );
                                 debug!(
                                     "Scheduler storing stage {} output partition {} path: {}",
                                     unresolved_shuffle.stage_id,
@@ -381,19 +783,97 @@ impl SchedulerState {
                     }
                 }
 
-                let plan =
-                    remove_unresolved_shuffles(plan.as_ref(), &partition_locations)?;
+                // Tracked shuffle readers query the locations of their partitions when
+                // executed rather than listing them in the task definition
+                let plan = if track_map_outputs {
+                    track_unresolved_shuffles(plan.as_ref(), &partition.job_id)?
+                } else {
+                    remove_unresolved_shuffles(plan.as_ref(), &partition_locations)?
+                };
+                let plan = prune_task_scans(plan, partition.partition_id as usize)?;
 
                 // If we get here, there are no more unresolved shuffled and the task can be run
-                let mut status = status.clone();
-                status.status = Some(task_status::Status::Running(RunningTask {
-                    executor_id: executor_id.to_owned(),
-                }));
-                self.save_task_status(&status).await?;
-                return Ok(Some((status, plan)));
+                let is_remote = zone.map_or(false, |zone| {
+                    partition_locations
+                        .values()
+                        .flat_map(|locations| locations.values().flatten())
+                        .any(|location| {
+                            location
+                                .executor_meta
+                                .zone()
+                                .map_or(false, |location_zone| location_zone != zone)
+                        })
+                });
+                if !is_remote {
+                    return self.assign_task(executor_id, status, plan).await;
+                } else if remote_task.is_none() {
+                    remote_task = Some((status, plan));
+                }
+            }
+        }
+        match remote_task {
+            Some((status, plan)) => self.assign_task(executor_id, status, plan).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the locations of the shuffle partition `partition_id` written by the
+    /// tasks of the stage `stage_id` of the job `job_id`, or None if any of these
+    /// tasks has not completed or ran on an executor that is no longer alive.
+    pub async fn get_map_outputs(
+        &self,
+        job_id: &str,
+        stage_id: usize,
+        partition_id: usize,
+    ) -> Result<Option<Vec<PartitionLocation>>> {
+        let executors = self.get_alive_executors_metadata(EXECUTOR_TIMEOUT).await?;
+        let tasks = self
+            .config_client
+            .get_from_prefix(&get_task_prefix_for_job(&self.namespace, job_id))
+            .await?;
+        let mut locations = vec![];
+        for (_key, bytes) in tasks {
+            let task: TaskStatus = decode_protobuf(&bytes)?;
+            if task_partition_id(&task)?.stage_id as usize != stage_id {
+                continue;
             }
+            let (executor_id, partitions) = match &task.status {
+                Some(task_status::Status::Completed(CompletedTask {
+                    executor_id,
+                    partitions,
+                    ..
+                })) => (executor_id, partitions),
+                _ => return Ok(None),
+            };
+            let executor_meta =
+                match executors.iter().find(|exec| &exec.id == executor_id) {
+                    Some(executor_meta) => executor_meta,
+                    None => return Ok(None),
+                };
+            locations.extend(
+                partitions
+                    .iter()
+                    .filter(|partition| partition.partition_id as usize == partition_id)
+                    .map(|partition| {
+                        to_partition_location(job_id, stage_id, executor_meta, partition)
+                    }),
+            );
         }
-        Ok(None)
+        Ok(Some(locations))
+    }
+
+    async fn assign_task(
+        &self,
+        executor_id: &str,
+        status: &TaskStatus,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> Result<Option<(TaskStatus, Arc<dyn ExecutionPlan>)>> {
+        let mut status = status.clone();
+        status.status = Some(task_status::Status::Running(RunningTask {
+            executor_id: executor_id.to_owned(),
+        }));
+        self.save_task_status(&status).await?;
+        Ok(Some((status, plan)))
     }
 
     // Global lock for the state. We should get rid of this to be able to scale.
@@ -416,7 +896,13 @@ impl SchedulerState {
                 WatchEvent::Put(key, _value) => key,
                 WatchEvent::Delete(key) => key
             };
-            let job_id = extract_job_id_from_task_key(&key).unwrap();
+            let job_id = match extract_job_id_from_task_key(&key) {
+                Ok(job_id) => job_id,
+                Err(e) => {
+                    error!("Could not update job status for task {}. Error: {}", key, e);
+                    return;
+                }
+            };
             match self.lock().await {
                 Ok(mut lock) => {
                     if let Err(e) = self.synchronize_job_status(job_id).await {
@@ -443,6 +929,10 @@ impl SchedulerState {
             .map(|(meta, _)| (meta.id.to_string(), meta))
             .collect();
         let status: JobStatus = decode_protobuf(&value)?;
+        if let Some(job_status::Status::Failed(_)) = status.status {
+            // e.g. a cancelled job, whose running tasks may still complete
+            return Ok(());
+        }
         let new_status = self.get_job_status_from_tasks(job_id, &executors).await?;
         if let Some(new_status) = new_status {
             if status != new_status {
@@ -475,70 +965,76 @@ impl SchedulerState {
         }
 
         // Check for job completion
-        let last_stage = statuses
-            .iter()
-            .map(|task| task.partition_id.as_ref().unwrap().stage_id)
-            .max()
-            .unwrap();
+        let mut last_stage = 0;
+        for status in &statuses {
+            last_stage = last_stage.max(task_partition_id(status)?.stage_id);
+        }
         let statuses: Vec<_> = statuses
             .into_iter()
-            .filter(|task| task.partition_id.as_ref().unwrap().stage_id == last_stage)
-            .collect();
-        let mut job_status = statuses
-            .iter()
-            .map(|status| match &status.status {
-                Some(task_status::Status::Completed(CompletedTask {
-                    executor_id,
-                    partitions,
-                })) => Ok((status, executor_id, partitions)),
-                _ => Err(BallistaError::General("Task not completed".to_string())),
+            .filter(|task| {
+                task.partition_id
+                    .as_ref()
+                    .map_or(false, |partition| partition.stage_id == last_stage)
             })
-            .collect::<Result<Vec<_>>>()
-            .ok()
-            .map(|info| {
-                let mut partition_location = vec![];
-                for (status, executor_id, partitions) in info {
-                    let input_partition_id = status.partition_id.as_ref().unwrap(); //TODO unwrap
-                    let executor_meta =
-                        executors.get(executor_id).map(|e| e.clone().into());
-                    for shuffle_write_partition in partitions {
-                        let shuffle_input_partition_id = Some(protobuf::PartitionId {
-                            job_id: input_partition_id.job_id.clone(),
-                            stage_id: input_partition_id.stage_id,
-                            partition_id: input_partition_id.partition_id,
-                        });
-                        partition_location.push(protobuf::PartitionLocation {
-                            partition_id: shuffle_input_partition_id.clone(),
-                            executor_meta: executor_meta.clone(),
-                            partition_stats: Some(protobuf::PartitionStats {
-                                num_batches: shuffle_write_partition.num_batches as i64,
-                                num_rows: shuffle_write_partition.num_rows as i64,
-                                num_bytes: shuffle_write_partition.num_bytes as i64,
-                                column_stats: vec![],
-                            }),
-                            path: shuffle_write_partition.path.clone(),
-                        });
-                    }
-                }
-                job_status::Status::Completed(CompletedJob { partition_location })
-            });
-
-        if job_status.is_none() {
-            // Update other statuses
-            for status in statuses {
-                match status.status {
-                    Some(task_status::Status::Failed(FailedTask { error })) => {
-                        job_status =
-                            Some(job_status::Status::Failed(FailedJob { error }));
-                        break;
-                    }
-                    Some(task_status::Status::Running(_)) if job_status == None => {
-                        job_status = Some(job_status::Status::Running(RunningJob {}));
-                    }
-                    _ => (),
+            .collect();
+        // the output partitions of the completed tasks of the final stage, which are
+        // reported while the job runs so that clients can fetch them before it completes
+        let mut partition_location = vec![];
+        let mut completed_tasks = 0;
+        for status in &statuses {
+            if let Some(task_status::Status::Completed(CompletedTask {
+                executor_id,
+                partitions,
+                ..
+            })) = &status.status
+            {
+                completed_tasks += 1;
+                let input_partition_id = task_partition_id(status)?;
+                let executor_meta = executors.get(executor_id).map(|e| e.clone().into());
+                for shuffle_write_partition in partitions {
+                    let shuffle_input_partition_id = Some(protobuf::PartitionId {
+                        job_id: input_partition_id.job_id.clone(),
+                        stage_id: input_partition_id.stage_id,
+                        partition_id: input_partition_id.partition_id,
+                    });
+                    partition_location.push(protobuf::PartitionLocation {
+                        partition_id: shuffle_input_partition_id.clone(),
+                        executor_meta: executor_meta.clone(),
+                        partition_stats: Some(protobuf::PartitionStats {
+                            num_batches: shuffle_write_partition.num_batches as i64,
+                            num_rows: shuffle_write_partition.num_rows as i64,
+                            num_bytes: shuffle_write_partition.num_bytes as i64,
+                            column_stats: vec![],
+                        }),
+                        path: shuffle_write_partition.path.clone(),
+                        checksum: shuffle_write_partition.checksum.clone(),
+                    });
                 }
             }
         }
+
+        let failed = statuses.iter().find_map(|status| match &status.status {
+            Some(task_status::Status::Failed(FailedTask { error, .. })) => {
+                Some(error.clone())
+            }
+            _ => None,
+        });
+        let running = statuses
+            .iter()
+            .any(|status| matches!(status.status, Some(task_status::Status::Running(_))));
+        let job_status = if completed_tasks == statuses.len() {
+            Some(job_status::Status::Completed(CompletedJob {
+                partition_location,
+            }))
+        } else if let Some(error) = failed {
+            Some(job_status::Status::Failed(FailedJob { error }))
+        } else if running || completed_tasks > 0 {
+            Some(job_status::Status::Running(RunningJob {
+                partition_location,
+            }))
+        } else {
+            None
+        };
         Ok(job_status.map(|status| JobStatus {
             status: Some(status),
         }))
@@ -575,6 +1071,32 @@ fn find_unresolved_shuffles(
     }
 }
 
+fn to_partition_location(
+    job_id: &str,
+    stage_id: usize,
+    executor_meta: &ExecutorMeta,
+    shuffle_write_partition: &ShuffleWritePartition,
+) -> PartitionLocation {
+    PartitionLocation {
+        partition_id: PartitionId {
+            job_id: job_id.to_owned(),
+            stage_id,
+            partition_id: shuffle_write_partition.partition_id as usize,
+        },
+        executor_meta: executor_meta.clone(),
+        partition_stats: PartitionStats::new(
+            Some(shuffle_write_partition.num_rows),
+            Some(shuffle_write_partition.num_batches),
+            Some(shuffle_write_partition.num_bytes),
+        ),
+        path: shuffle_write_partition.path.clone(),
+        checksum: shuffle_write_partition
+            .checksum
+            .as_ref()
+            .map(|checksum| checksum.crc32),
+    }
+}
+
 fn get_executors_prefix(namespace: &str) -> String {
     format!("/ballista/{}/executors", namespace)
 }
@@ -591,6 +1113,18 @@ fn get_job_key(namespace: &str, id: &str) -> String {
     format!("{}/{}", get_job_prefix(namespace), id)
 }
 
+fn get_job_queue_prefix(namespace: &str) -> String {
+    format!("/ballista/{}/job_queue", namespace)
+}
+
+fn get_job_labels_key(namespace: &str, id: &str) -> String {
+    format!("/ballista/{}/job_labels/{}", namespace, id)
+}
+
+fn get_dataset_key(namespace: &str, id: &str) -> String {
+    format!("/ballista/{}/datasets/{}", namespace, id)
+}
+
 fn get_task_prefix(namespace: &str) -> String {
     format!("/ballista/{}/tasks", namespace)
 }
@@ -613,12 +1147,42 @@ fn get_task_status_key(
     )
 }
 
-fn extract_job_id_from_task_key(job_key: &str) -> Result<&str> {
+pub(crate) fn extract_job_id_from_task_key(job_key: &str) -> Result<&str> {
     job_key.split('/').nth(4).ok_or_else(|| {
         BallistaError::Internal(format!("Unexpected task key: {}", job_key))
     })
 }
 
+/// The key of the number of times a task was executed again because a shuffle
+/// partition it wrote was lost
+fn get_lost_partition_attempts_key(
+    namespace: &str,
+    job_id: &str,
+    stage_id: usize,
+    partition_id: usize,
+) -> String {
+    format!(
+        "/ballista/{}/lost_partitions/{}/{}/{}",
+        namespace, job_id, stage_id, partition_id
+    )
+}
+
+fn decode_attempts(bytes: &[u8]) -> Result<u32> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|attempts| attempts.parse().ok())
+        .ok_or_else(|| {
+            BallistaError::Internal("Could not decode lost partition attempts".to_owned())
+        })
+}
+
+/// The partition of a task status, which is sent by the executors
+fn task_partition_id(status: &TaskStatus) -> Result<&protobuf::PartitionId> {
+    status.partition_id.as_ref().ok_or_else(|| {
+        BallistaError::General("Task status without a partition id".to_owned())
+    })
+}
+
 fn get_stage_plan_key(namespace: &str, job_id: &str, stage_id: usize) -> String {
     format!("/ballista/{}/stages/{}/{}", namespace, job_id, stage_id,)
 }
@@ -648,16 +1212,25 @@ fn encode_protobuf<T: Message + Default>(msg: &T) -> Result<Vec<u8>> {
 #[cfg(all(test, feature = "sled"))]
 mod test {
     use std::sync::Arc;
+    use std::time::Duration;
 
     use ballista_core::serde::protobuf::{
-        job_status, task_status, CompletedTask, FailedTask, JobStatus, PartitionId,
-        QueuedJob, RunningJob, RunningTask, TaskStatus,
+        self, job_status, operator_metric, task_status, CompletedJob, CompletedTask,
+        FailedJob, FailedTask, JobLabels, JobStatus, KeyValuePair, PartitionId,
+        PartitionLocation, QueuedJob, RunningJob, RunningTask, ShuffleWritePartition,
+        TaskStatus,
+    };
+    use ballista_core::{
+        error::BallistaError,
+        serde::scheduler::{ExecutorMeta, ZONE_LABEL},
     };
-    use ballista_core::{error::BallistaError, serde::scheduler::ExecutorMeta};
+    use datafusion::arrow::datatypes::Schema;
+    use datafusion::physical_plan::coalesce_batches::CoalesceBatchesExec;
+    use datafusion::physical_plan::empty::EmptyExec;
 
     use super::{
-        extract_job_id_from_task_key, get_task_status_key, SchedulerState,
-        StandaloneClient,
+        encode_protobuf, extract_job_id_from_task_key, get_task_status_key,
+        SchedulerState, StandaloneClient, MAX_LOST_PARTITION_RESCHEDULES,
     };
 
     #[tokio::test]
@@ -670,6 +1243,23 @@ mod test {
             id: "123".to_owned(),
             host: "localhost".to_owned(),
             port: 123,
+            labels: vec![(ZONE_LABEL.to_owned(), "zone-a".to_owned())]
+                .into_iter()
+                .collect(),
+        };
+        state.save_executor_metadata(meta.clone()).await?;
+        let result: Vec<_> = state
+            .get_executors_metadata()
+            .await?
+            .into_iter()
+            .map(|(meta, _)| meta)
+            .collect();
+        assert_eq!(vec![meta.clone()], result);
+
+        // a restarted executor registering from another IP replaces its metadata
+        let meta = ExecutorMeta {
+            host: "10.0.0.2".to_owned(),
+            ..meta
         };
         state.save_executor_metadata(meta.clone()).await?;
         let result: Vec<_> = state
@@ -682,6 +1272,25 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn probe_unreachable_executor() -> Result<(), BallistaError> {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let meta = ExecutorMeta {
+            id: "123".to_owned(),
+            host: "localhost".to_owned(),
+            port: 1,
+            labels: Default::default(),
+        };
+        state.save_executor_metadata(meta).await?;
+        let removed = state.probe_executors(Duration::from_secs(5)).await?;
+        assert_eq!(removed, vec!["123".to_owned()]);
+        assert!(state.get_executors_metadata().await?.is_empty());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn job_metadata() -> Result<(), BallistaError> {
         let state = SchedulerState::new(
@@ -692,7 +1301,7 @@ mod test {
             status: Some(job_status::Status::Queued(QueuedJob {})),
         };
         state.save_job_metadata("job", &meta).await?;
-        let result = state.get_job_metadata("job").await?;
+        let result = state.get_job_metadata("job").await?.unwrap();
         assert!(result.status.is_some());
         match result.status.unwrap() {
             job_status::Status::Queued(_) => (),
@@ -711,8 +1320,177 @@ mod test {
             status: Some(job_status::Status::Queued(QueuedJob {})),
         };
         state.save_job_metadata("job", &meta).await?;
-        let result = state.get_job_metadata("job2").await;
-        assert!(result.is_err());
+        let result = state.get_job_metadata("job2").await?;
+        assert!(result.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn job_labels() -> Result<(), BallistaError> {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let meta = JobStatus {
+            status: Some(job_status::Status::Queued(QueuedJob {})),
+        };
+        let labels = JobLabels {
+            labels: vec![KeyValuePair {
+                key: "team".to_owned(),
+                value: "data".to_owned(),
+            }],
+        };
+        state.save_job_metadata("job", &meta).await?;
+        state.save_job_labels("job", &labels).await?;
+        state.save_job_metadata("job2", &meta).await?;
+
+        assert_eq!(labels, state.get_job_labels("job").await?);
+        assert!(state.get_job_labels("job2").await?.labels.is_empty());
+        let mut job_ids: Vec<_> = state
+            .get_jobs_metadata()
+            .await?
+            .into_iter()
+            .map(|(job_id, _)| job_id)
+            .collect();
+        job_ids.sort();
+        assert_eq!(vec!["job", "job2"], job_ids);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn persist_dataset() -> Result<(), BallistaError> {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let location = |partition_id| PartitionLocation {
+            partition_id: Some(PartitionId {
+                job_id: "job".to_owned(),
+                stage_id: 1,
+                partition_id,
+            }),
+            executor_meta: None,
+            partition_stats: None,
+            path: format!("/tmp/job/1/{}", partition_id),
+            checksum: None,
+        };
+        state
+            .persist_dataset(
+                "job",
+                vec![location(1), location(0)],
+                protobuf::Schema {
+                    columns: vec![],
+                    interned_index: 0,
+                },
+            )
+            .await?;
+
+        let dataset = state.get_dataset("job").await?.unwrap();
+        assert_eq!("job", dataset.dataset_id);
+        let paths: Vec<_> = dataset
+            .partition
+            .iter()
+            .map(|p| p.location[0].path.as_str())
+            .collect();
+        assert_eq!(vec!["/tmp/job/1/0", "/tmp/job/1/1"], paths);
+        assert!(state.get_dataset("job2").await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn append_dataset() -> Result<(), BallistaError> {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let location = |path: &str| PartitionLocation {
+            partition_id: None,
+            executor_meta: None,
+            partition_stats: None,
+            path: path.to_owned(),
+            checksum: None,
+        };
+        let schema = |name: &str| protobuf::Schema {
+            columns: vec![protobuf::Field {
+                name: name.to_owned(),
+                nullable: true,
+                ..Default::default()
+            }],
+            interned_index: 0,
+        };
+        state
+            .append_dataset("staging", vec![location("/tmp/a")], schema("c"))
+            .await?;
+        let dataset = state
+            .append_dataset("staging", vec![location("/tmp/b")], schema("c"))
+            .await?;
+        assert_eq!(dataset, state.get_dataset("staging").await?.unwrap());
+        let partitions: Vec<_> = dataset
+            .partition
+            .iter()
+            .map(|p| {
+                let location = &p.location[0];
+                let partition_id = location.partition_id.as_ref().unwrap();
+                (partition_id.partition_id, location.path.as_str())
+            })
+            .collect();
+        assert_eq!(vec![(0, "/tmp/a"), (1, "/tmp/b")], partitions);
+
+        // the partitions of a dataset have the same schema
+        assert!(state
+            .append_dataset("staging", vec![location("/tmp/c")], schema("d"))
+            .await
+            .is_err());
+        assert_eq!(
+            2,
+            state.get_dataset("staging").await?.unwrap().partition.len()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn job_metrics() -> Result<(), BallistaError> {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let plan = Arc::new(CoalesceBatchesExec::new(
+            Arc::new(EmptyExec::new(false, Arc::new(Schema::empty()))),
+            1024,
+        ));
+        state.save_stage_plan("job", 1, plan).await?;
+        let output_rows = |rows| protobuf::OperatorMetricsSet {
+            metrics: vec![protobuf::OperatorMetric {
+                metric: Some(operator_metric::Metric::OutputRows(rows)),
+                labels: vec![],
+            }],
+        };
+        for (partition_id, rows) in [(0, 2), (1, 3)] {
+            let task = TaskStatus {
+                status: Some(task_status::Status::Completed(CompletedTask {
+                    executor_id: "executor".to_owned(),
+                    partitions: vec![],
+                    metrics: vec![output_rows(rows), output_rows(rows * 10)],
+                })),
+                partition_id: Some(PartitionId {
+                    job_id: "job".to_owned(),
+                    stage_id: 1,
+                    partition_id,
+                }),
+            };
+            state.save_task_status(&task).await?;
+        }
+
+        let stages = state.get_job_metrics("job").await?;
+        assert_eq!(
+            stages,
+            vec![(
+                1,
+                "CoalesceBatchesExec: target_batch_size=1024, metrics=[output_rows=5]\n  \
+                 EmptyExec: produce_one_row=false, metrics=[output_rows=50]\n"
+                    .to_owned()
+            )]
+        );
         Ok(())
     }
 
@@ -725,6 +1503,7 @@ mod test {
         let meta = TaskStatus {
             status: Some(task_status::Status::Failed(FailedTask {
                 error: "error".to_owned(),
+                ..Default::default()
             })),
             partition_id: Some(PartitionId {
                 job_id: "job".to_owned(),
@@ -733,7 +1512,7 @@ mod test {
             }),
         };
         state.save_task_status(&meta).await?;
-        let result = state._get_task_status("job", 1, 2).await?;
+        let result = state._get_task_status("job", 1, 2).await?.unwrap();
         assert!(result.status.is_some());
         match result.status.unwrap() {
             task_status::Status::Failed(_) => (),
@@ -742,6 +1521,164 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn reschedule_lost_partition() -> Result<(), BallistaError> {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let path = "/tmp/job/1/0/data-0.arrow";
+        let map_task = TaskStatus {
+            status: Some(task_status::Status::Completed(CompletedTask {
+                executor_id: "executor".to_owned(),
+                partitions: vec![ShuffleWritePartition {
+                    partition_id: 0,
+                    path: path.to_owned(),
+                    ..Default::default()
+                }],
+                metrics: vec![],
+            })),
+            partition_id: Some(PartitionId {
+                job_id: "job".to_owned(),
+                stage_id: 1,
+                partition_id: 0,
+            }),
+        };
+        state.save_task_status(&map_task).await?;
+        let map_task_completed = map_task.clone();
+        let failed_task = |lost_partition_path: &str| TaskStatus {
+            status: Some(task_status::Status::Failed(FailedTask {
+                error: "error".to_owned(),
+                lost_partition_path: lost_partition_path.to_owned(),
+                ..Default::default()
+            })),
+            partition_id: Some(PartitionId {
+                job_id: "job".to_owned(),
+                stage_id: 2,
+                partition_id: 0,
+            }),
+        };
+
+        assert!(!state.reschedule_lost_partition(&failed_task("")).await?);
+        assert!(state.reschedule_lost_partition(&failed_task(path)).await?);
+        let map_task = state._get_task_status("job", 1, 0).await?.unwrap();
+        assert_eq!(None, map_task.status);
+        let reduce_task = state._get_task_status("job", 2, 0).await?.unwrap();
+        assert_eq!(None, reduce_task.status);
+
+        // the job fails once the partitions of the task were lost too many times
+        for _ in 1..MAX_LOST_PARTITION_RESCHEDULES {
+            state.save_task_status(&map_task_completed).await?;
+            assert!(state.reschedule_lost_partition(&failed_task(path)).await?);
+        }
+        state.save_task_status(&map_task_completed).await?;
+        assert!(!state.reschedule_lost_partition(&failed_task(path)).await?);
+        match state
+            .get_job_metadata("job")
+            .await?
+            .and_then(|job| job.status)
+        {
+            Some(job_status::Status::Failed(FailedJob { error })) => {
+                assert!(error.contains("is lost"), "{}", error)
+            }
+            status => panic!("Unexpected job status {:?}", status),
+        }
+
+        // the statuses sent by the executors may have no partition
+        let mut no_partition = failed_task(path);
+        no_partition.partition_id = None;
+        assert!(state
+            .reschedule_lost_partition(&no_partition)
+            .await
+            .is_err());
+        assert!(state.save_task_status(&no_partition).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_map_outputs() -> Result<(), BallistaError> {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let meta = ExecutorMeta {
+            id: "executor".to_owned(),
+            host: "localhost".to_owned(),
+            port: 123,
+            labels: Default::default(),
+        };
+        state.save_executor_metadata(meta.clone()).await?;
+        let map_task = |partition_id, status| TaskStatus {
+            status,
+            partition_id: Some(PartitionId {
+                job_id: "job".to_owned(),
+                stage_id: 1,
+                partition_id,
+            }),
+        };
+        let completed = |map_partition_id| {
+            Some(task_status::Status::Completed(CompletedTask {
+                executor_id: "executor".to_owned(),
+                partitions: (0..2)
+                    .map(|partition_id| ShuffleWritePartition {
+                        partition_id,
+                        path: format!("/tmp/job/1/{}/{}", partition_id, map_partition_id),
+                        ..Default::default()
+                    })
+                    .collect(),
+                metrics: vec![],
+            }))
+        };
+        state.save_task_status(&map_task(0, completed(0))).await?;
+        state.save_task_status(&map_task(1, None)).await?;
+        assert!(state.get_map_outputs("job", 1, 1).await?.is_none());
+
+        state.save_task_status(&map_task(1, completed(1))).await?;
+        let mut paths: Vec<_> = state
+            .get_map_outputs("job", 1, 1)
+            .await?
+            .unwrap()
+            .into_iter()
+            .map(|location| {
+                assert_eq!(meta, location.executor_meta);
+                location.path
+            })
+            .collect();
+        paths.sort();
+        assert_eq!(vec!["/tmp/job/1/1/0", "/tmp/job/1/1/1"], paths);
+
+        state.remove_executor_metadata("executor").await?;
+        assert!(state.get_map_outputs("job", 1, 1).await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn task_status_without_partition_id() -> Result<(), BallistaError> {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let status = TaskStatus {
+            status: None,
+            partition_id: None,
+        };
+        state
+            .config_client
+            .put(
+                get_task_status_key(&state.namespace, "job", 0, 0),
+                encode_protobuf(&status)?,
+            )
+            .await?;
+
+        // a malformed task status fails the executors polling for work
+        assert!(state
+            .assign_next_schedulable_task("executor", false)
+            .await
+            .is_err());
+        assert!(state.get_job_stages("job").await.is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn task_status_non_existant() -> Result<(), BallistaError> {
         let state = SchedulerState::new(
@@ -751,6 +1688,7 @@ mod test {
         let meta = TaskStatus {
             status: Some(task_status::Status::Failed(FailedTask {
                 error: "error".to_owned(),
+                ..Default::default()
             })),
             partition_id: Some(PartitionId {
                 job_id: "job".to_owned(),
@@ -759,8 +1697,8 @@ mod test {
             }),
         };
         state.save_task_status(&meta).await?;
-        let result = state._get_task_status("job", 25, 2).await;
-        assert!(result.is_err());
+        let result = state._get_task_status("job", 25, 2).await?;
+        assert!(result.is_none());
         Ok(())
     }
 
@@ -776,7 +1714,7 @@ mod test {
         };
         state.save_job_metadata(job_id, &job_status).await?;
         state.synchronize_job_status(job_id).await?;
-        let result = state.get_job_metadata(job_id).await?;
+        let result = state.get_job_metadata(job_id).await?.unwrap();
         assert_eq!(result, job_status);
         Ok(())
     }
@@ -789,13 +1727,14 @@ mod test {
         );
         let job_id = "job";
         let job_status = JobStatus {
-            status: Some(job_status::Status::Running(RunningJob {})),
+            status: Some(job_status::Status::Running(RunningJob::default())),
         };
         state.save_job_metadata(job_id, &job_status).await?;
         let meta = TaskStatus {
             status: Some(task_status::Status::Completed(CompletedTask {
                 executor_id: "".to_owned(),
                 partitions: vec![],
+                metrics: vec![],
             })),
             partition_id: Some(PartitionId {
                 job_id: job_id.to_owned(),
@@ -816,7 +1755,7 @@ mod test {
         };
         state.save_task_status(&meta).await?;
         state.synchronize_job_status(job_id).await?;
-        let result = state.get_job_metadata(job_id).await?;
+        let result = state.get_job_metadata(job_id).await?.unwrap();
         assert_eq!(result, job_status);
         Ok(())
     }
@@ -829,13 +1768,14 @@ mod test {
         );
         let job_id = "job";
         let job_status = JobStatus {
-            status: Some(job_status::Status::Running(RunningJob {})),
+            status: Some(job_status::Status::Running(RunningJob::default())),
         };
         state.save_job_metadata(job_id, &job_status).await?;
         let meta = TaskStatus {
             status: Some(task_status::Status::Completed(CompletedTask {
                 executor_id: "".to_owned(),
                 partitions: vec![],
+                metrics: vec![],
             })),
             partition_id: Some(PartitionId {
                 job_id: job_id.to_owned(),
@@ -854,11 +1794,63 @@ mod test {
         };
         state.save_task_status(&meta).await?;
         state.synchronize_job_status(job_id).await?;
-        let result = state.get_job_metadata(job_id).await?;
+        let result = state.get_job_metadata(job_id).await?.unwrap();
         assert_eq!(result, job_status);
         Ok(())
     }
 
+    #[tokio::test]
+    async fn task_synchronize_job_status_running_partitions() -> Result<(), BallistaError>
+    {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let job_id = "job";
+        state
+            .save_job_metadata(
+                job_id,
+                &JobStatus {
+                    status: Some(job_status::Status::Running(RunningJob::default())),
+                },
+            )
+            .await?;
+        let task = |partition_id, status| TaskStatus {
+            status,
+            partition_id: Some(PartitionId {
+                job_id: job_id.to_owned(),
+                stage_id: 1,
+                partition_id,
+            }),
+        };
+        let completed = Some(task_status::Status::Completed(CompletedTask {
+            executor_id: "".to_owned(),
+            partitions: vec![ShuffleWritePartition {
+                partition_id: 0,
+                path: "/tmp/job/1/1".to_owned(),
+                num_rows: 10,
+                ..Default::default()
+            }],
+            metrics: vec![],
+        }));
+        state.save_task_status(&task(0, None)).await?;
+        state.save_task_status(&task(1, completed)).await?;
+        state.synchronize_job_status(job_id).await?;
+
+        // the completed partition of the final stage is reported while the job runs
+        let result = state.get_job_metadata(job_id).await?.unwrap();
+        let partition_location = match result.status.unwrap() {
+            job_status::Status::Running(running) => running.partition_location,
+            status => panic!("Received status: {:?}", status),
+        };
+        assert_eq!(partition_location.len(), 1);
+        let location = &partition_location[0];
+        assert_eq!(location.partition_id.as_ref().unwrap().partition_id, 1);
+        assert_eq!(location.path, "/tmp/job/1/1");
+        assert_eq!(location.partition_stats.as_ref().unwrap().num_rows, 10);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn task_synchronize_job_status_completed() -> Result<(), BallistaError> {
         let state = SchedulerState::new(
@@ -867,13 +1859,14 @@ mod test {
         );
         let job_id = "job";
         let job_status = JobStatus {
-            status: Some(job_status::Status::Running(RunningJob {})),
+            status: Some(job_status::Status::Running(RunningJob::default())),
         };
         state.save_job_metadata(job_id, &job_status).await?;
         let meta = TaskStatus {
             status: Some(task_status::Status::Completed(CompletedTask {
                 executor_id: "".to_owned(),
                 partitions: vec![],
+                metrics: vec![],
             })),
             partition_id: Some(PartitionId {
                 job_id: job_id.to_owned(),
@@ -886,6 +1879,7 @@ mod test {
             status: Some(task_status::Status::Completed(CompletedTask {
                 executor_id: "".to_owned(),
                 partitions: vec![],
+                metrics: vec![],
             })),
             partition_id: Some(PartitionId {
                 job_id: job_id.to_owned(),
@@ -895,7 +1889,7 @@ mod test {
         };
         state.save_task_status(&meta).await?;
         state.synchronize_job_status(job_id).await?;
-        let result = state.get_job_metadata(job_id).await?;
+        let result = state.get_job_metadata(job_id).await?.unwrap();
         match result.status.unwrap() {
             job_status::Status::Completed(_) => (),
             status => panic!("Received status: {:?}", status),
@@ -918,6 +1912,7 @@ mod test {
             status: Some(task_status::Status::Completed(CompletedTask {
                 executor_id: "".to_owned(),
                 partitions: vec![],
+                metrics: vec![],
             })),
             partition_id: Some(PartitionId {
                 job_id: job_id.to_owned(),
@@ -930,6 +1925,7 @@ mod test {
             status: Some(task_status::Status::Completed(CompletedTask {
                 executor_id: "".to_owned(),
                 partitions: vec![],
+                metrics: vec![],
             })),
             partition_id: Some(PartitionId {
                 job_id: job_id.to_owned(),
@@ -939,7 +1935,7 @@ mod test {
         };
         state.save_task_status(&meta).await?;
         state.synchronize_job_status(job_id).await?;
-        let result = state.get_job_metadata(job_id).await?;
+        let result = state.get_job_metadata(job_id).await?.unwrap();
         match result.status.unwrap() {
             job_status::Status::Completed(_) => (),
             status => panic!("Received status: {:?}", status),
@@ -955,13 +1951,14 @@ mod test {
         );
         let job_id = "job";
         let job_status = JobStatus {
-            status: Some(job_status::Status::Running(RunningJob {})),
+            status: Some(job_status::Status::Running(RunningJob::default())),
         };
         state.save_job_metadata(job_id, &job_status).await?;
         let meta = TaskStatus {
             status: Some(task_status::Status::Completed(CompletedTask {
                 executor_id: "".to_owned(),
                 partitions: vec![],
+                metrics: vec![],
             })),
             partition_id: Some(PartitionId {
                 job_id: job_id.to_owned(),
@@ -973,6 +1970,7 @@ mod test {
         let meta = TaskStatus {
             status: Some(task_status::Status::Failed(FailedTask {
                 error: "".to_owned(),
+                ..Default::default()
             })),
             partition_id: Some(PartitionId {
                 job_id: job_id.to_owned(),
@@ -991,7 +1989,7 @@ mod test {
         };
         state.save_task_status(&meta).await?;
         state.synchronize_job_status(job_id).await?;
-        let result = state.get_job_metadata(job_id).await?;
+        let result = state.get_job_metadata(job_id).await?.unwrap();
         match result.status.unwrap() {
             job_status::Status::Failed(_) => (),
             status => panic!("Received status: {:?}", status),
@@ -999,6 +1997,79 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn cancel_job() -> Result<(), BallistaError> {
+        let state = SchedulerState::new(
+            Arc::new(StandaloneClient::try_new_temporary()?),
+            "test".to_string(),
+        );
+        let job_id = "job";
+        let job_status = JobStatus {
+            status: Some(job_status::Status::Running(RunningJob::default())),
+        };
+        state.save_job_metadata(job_id, &job_status).await?;
+        let running = TaskStatus {
+            status: Some(task_status::Status::Running(RunningTask {
+                executor_id: "".to_owned(),
+            })),
+            partition_id: Some(PartitionId {
+                job_id: job_id.to_owned(),
+                stage_id: 0,
+                partition_id: 0,
+            }),
+        };
+        state.save_task_status(&running).await?;
+        let pending = TaskStatus {
+            status: None,
+            partition_id: Some(PartitionId {
+                job_id: job_id.to_owned(),
+                stage_id: 0,
+                partition_id: 1,
+            }),
+        };
+        state.save_task_status(&pending).await?;
+
+        assert!(state.cancel_job(job_id).await?);
+        // the pending task is no longer scheduled
+        assert!(state
+            .assign_next_schedulable_task("", false)
+            .await?
+            .is_none());
+        assert_eq!(state._get_task_status(job_id, 0, 0).await?, Some(running));
+
+        // the running task completing does not complete the cancelled job
+        let completed = TaskStatus {
+            status: Some(task_status::Status::Completed(CompletedTask {
+                executor_id: "".to_owned(),
+                partitions: vec![],
+                metrics: vec![],
+            })),
+            partition_id: Some(PartitionId {
+                job_id: job_id.to_owned(),
+                stage_id: 0,
+                partition_id: 0,
+            }),
+        };
+        state.save_task_status(&completed).await?;
+        state.synchronize_job_status(job_id).await?;
+        match state
+            .get_job_metadata(job_id)
+            .await?
+            .unwrap()
+            .status
+            .unwrap()
+        {
+            job_status::Status::Failed(FailedJob { error }) => {
+                assert_eq!(error, "Job cancelled")
+            }
+            status => panic!("Received status: {:?}", status),
+        }
+
+        assert!(!state.cancel_job(job_id).await?);
+        assert!(state.cancel_job("missing").await.is_err());
+        Ok(())
+    }
+
     #[test]
     fn task_extract_job_id_from_task_key() {
         let job_id = "foo";
@@ -99,6 +99,27 @@ impl ConfigBackendClient for StandaloneClient {
             .map(|_| ())
     }
 
+    async fn put_batch(&self, entries: Vec<(String, Vec<u8>)>) -> Result<()> {
+        let mut batch = sled::Batch::default();
+        for (key, value) in entries {
+            batch.insert(key.as_bytes(), value);
+        }
+        self.db.apply_batch(batch).map_err(|e| {
+            warn!("sled insert failed: {}", e);
+            ballista_error("sled insert failed")
+        })
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.db
+            .remove(key)
+            .map_err(|e| {
+                warn!("sled remove failed: {}", e);
+                ballista_error("sled remove failed")
+            })
+            .map(|_| ())
+    }
+
     async fn lock(&self) -> Result<Box<dyn Lock>> {
         Ok(Box::new(self.lock.clone().lock_owned().await))
     }
@@ -169,6 +190,21 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn put_batch_read() -> Result<(), Box<dyn std::error::Error>> {
+        let client = create_instance()?;
+        let value = "value".as_bytes();
+        client
+            .put_batch(vec![
+                ("key/1".to_owned(), value.to_vec()),
+                ("key/2".to_owned(), value.to_vec()),
+            ])
+            .await?;
+        assert_eq!(client.get("key/1").await?, value);
+        assert_eq!(client.get("key/2").await?, value);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn read_empty() -> Result<(), Box<dyn std::error::Error>> {
         let client = create_instance()?;
@@ -178,6 +214,19 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn put_delete() -> Result<(), Box<dyn std::error::Error>> {
+        let client = create_instance()?;
+        let key = "key";
+        client
+            .put(key.to_owned(), "value".as_bytes().to_vec())
+            .await?;
+        client.delete(key).await?;
+        let empty: &[u8] = &[];
+        assert_eq!(client.get(key).await?, empty);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn read_prefix() -> Result<(), Box<dyn std::error::Error>> {
         let client = create_instance()?;
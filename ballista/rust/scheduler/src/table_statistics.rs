@@ -0,0 +1,203 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cache of the schemas and statistics of the tables whose statistics are requested
+//! from the scheduler, so that the footers of their files are only read again when
+//! the files change.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::object_store::{FileMeta, ObjectStore};
+use datafusion::datasource::{get_statistics_with_limit, PartitionedFile};
+use datafusion::error::Result;
+use datafusion::physical_plan::Statistics;
+
+/// Number of files whose statistics are cached
+const MAX_CACHED_FILES: usize = 10_000;
+
+/// Number of paths whose schema is cached
+const MAX_CACHED_SCHEMAS: usize = 1_000;
+
+/// Cache of at most `capacity` entries, evicting the least recently used one
+#[derive(Debug)]
+struct LruCache<K, V> {
+    capacity: usize,
+    /// Incremented on every access, to order the entries by their last access
+    clock: u64,
+    entries: HashMap<K, (u64, V)>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> LruCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(key).map(|(accessed, value)| {
+            *accessed = clock;
+            value.clone()
+        })
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let least_recently_used = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (accessed, _))| *accessed)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = least_recently_used {
+                self.entries.remove(&key);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(key, (self.clock, value));
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// The schemas of the paths and the statistics of the files read by the scheduler,
+/// keyed by path. They are read again when the files are not the ones they were
+/// read from.
+#[derive(Debug)]
+pub(crate) struct TableStatisticsCache {
+    files: Mutex<LruCache<String, (FileMeta, Statistics)>>,
+    schemas: Mutex<LruCache<String, (Vec<FileMeta>, SchemaRef)>>,
+}
+
+impl Default for TableStatisticsCache {
+    fn default() -> Self {
+        Self {
+            files: Mutex::new(LruCache::new(MAX_CACHED_FILES)),
+            schemas: Mutex::new(LruCache::new(MAX_CACHED_SCHEMAS)),
+        }
+    }
+}
+
+impl TableStatisticsCache {
+    /// Returns the schema inferred from the files listed at `path`
+    pub(crate) async fn schema(
+        &self,
+        obj_store: &dyn ObjectStore,
+        file_format: &dyn FileFormat,
+        path: &str,
+        files: &[FileMeta],
+    ) -> Result<SchemaRef> {
+        let cached = self.schemas.lock().unwrap().get(&path.to_owned());
+        if let Some((cached_files, schema)) = cached {
+            if cached_files == files {
+                return Ok(schema);
+            }
+        }
+        let readers = files
+            .iter()
+            .map(|file| obj_store.file_reader(file.sized_file.clone()))
+            .collect::<Vec<_>>();
+        let schema = file_format
+            .infer_schema(Box::pin(futures::stream::iter(readers)))
+            .await?;
+        self.schemas
+            .lock()
+            .unwrap()
+            .insert(path.to_owned(), (files.to_vec(), schema.clone()));
+        Ok(schema)
+    }
+
+    /// Returns the statistics of the table of `schema` made of `files`
+    pub(crate) async fn statistics(
+        &self,
+        obj_store: &dyn ObjectStore,
+        file_format: &dyn FileFormat,
+        schema: SchemaRef,
+        files: Vec<PartitionedFile>,
+    ) -> Result<Statistics> {
+        let mut file_statistics = Vec::with_capacity(files.len());
+        for file in files {
+            let statistics = self
+                .file_statistics(obj_store, file_format, &file.file_meta)
+                .await?;
+            file_statistics.push(Ok((file, statistics)));
+        }
+        let (_, statistics) = get_statistics_with_limit(
+            futures::stream::iter(file_statistics),
+            schema,
+            None,
+        )
+        .await?;
+        Ok(statistics)
+    }
+
+    /// Returns the statistics of a file
+    async fn file_statistics(
+        &self,
+        obj_store: &dyn ObjectStore,
+        file_format: &dyn FileFormat,
+        file_meta: &FileMeta,
+    ) -> Result<Statistics> {
+        let cached = self.files.lock().unwrap().get(&file_meta.path().to_owned());
+        if let Some((cached_meta, statistics)) = cached {
+            if &cached_meta == file_meta {
+                return Ok(statistics);
+            }
+        }
+        let reader = obj_store.file_reader(file_meta.sized_file.clone())?;
+        let statistics = file_format.infer_stats(reader).await?;
+        self.files.lock().unwrap().insert(
+            file_meta.path().to_owned(),
+            (file_meta.clone(), statistics.clone()),
+        );
+        Ok(statistics)
+    }
+
+    /// Number of files whose statistics are cached
+    #[cfg(test)]
+    pub(crate) fn cached_files(&self) -> usize {
+        self.files.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LruCache;
+
+    #[test]
+    fn evict_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert_eq!(cache.get(&"a"), Some(1));
+        cache.insert("c", 3);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"c"), Some(3));
+    }
+}
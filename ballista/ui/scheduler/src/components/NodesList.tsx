@@ -28,6 +28,8 @@ export interface NodeInfo {
   id: string;
   host: string;
   port: number;
+  zone?: string;
+  labels: Record<string, string>;
   status: NodeStatus;
   started: string;
 }
@@ -45,6 +47,10 @@ const columns: Column<any>[] = [
     Header: "Port",
     accessor: "port",
   },
+  {
+    Header: "Zone",
+    accessor: "zone",
+  },
   {
     Header: "Status",
     accessor: "status",
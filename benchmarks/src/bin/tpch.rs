@@ -46,7 +46,7 @@ use datafusion::{
 use datafusion::{
     arrow::util::pretty,
     datasource::{
-        listing::{ListingOptions, ListingTable},
+        listing::{ListingOptions, ListingTable, DEFAULT_STAT_COLLECTION_CONCURRENCY},
         object_store::local::LocalFileSystem,
     },
 };
@@ -495,6 +495,9 @@ fn get_table(
         target_partitions,
         collect_stat: true,
         table_partition_cols: vec![],
+        bucketing: None,
+        metadata_cache_ttl: None,
+        stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
     };
 
     Ok(Arc::new(ListingTable::new(
@@ -0,0 +1,78 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+/*
+ * Runs a query through the C API and checks its result, e.g.
+ *
+ *   cargo build -p datafusion-c
+ *   cc -I datafusion-c/include datafusion-c/examples/query.c \
+ *     -L target/debug -ldatafusion_c -o query
+ *   LD_LIBRARY_PATH=target/debug ./query
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "datafusion.h"
+
+static int fail(const char* what) {
+  const char* error = datafusion_last_error();
+  fprintf(stderr, "%s: %s\n", what, error ? error : "unknown error");
+  return EXIT_FAILURE;
+}
+
+int main(void) {
+  DataFusionContext* ctx = datafusion_context_new();
+  if (!ctx) {
+    return fail("Could not create the context");
+  }
+
+  if (datafusion_sql(ctx, "SELECT * FROM missing")) {
+    fprintf(stderr, "Queried a missing table\n");
+    return EXIT_FAILURE;
+  }
+
+  DataFusionResult* result = datafusion_sql(ctx, "SELECT 1 AS a, 'x' AS b");
+  if (!result) {
+    return fail("Could not run the query");
+  }
+  if (datafusion_result_num_batches(result) != 1) {
+    fprintf(stderr, "Expected a single record batch\n");
+    return EXIT_FAILURE;
+  }
+
+  struct ArrowArray array;
+  struct ArrowSchema schema;
+  if (datafusion_result_export_batch(result, 0, &array, &schema) != 0) {
+    return fail("Could not export the record batch");
+  }
+  // the batch is exported as a struct array of its columns
+  int ok = array.length == 1 && array.n_children == 2 && schema.n_children == 2;
+  array.release(&array);
+  schema.release(&schema);
+
+  datafusion_result_free(result);
+  datafusion_context_free(ctx);
+  if (!ok) {
+    fprintf(stderr, "Unexpected record batch\n");
+    return EXIT_FAILURE;
+  }
+  printf("OK\n");
+  return EXIT_SUCCESS;
+}
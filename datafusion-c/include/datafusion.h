@@ -0,0 +1,143 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one
+ * or more contributor license agreements.  See the NOTICE file
+ * distributed with this work for additional information
+ * regarding copyright ownership.  The ASF licenses this file
+ * to you under the Apache License, Version 2.0 (the
+ * "License"); you may not use this file except in compliance
+ * with the License.  You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing,
+ * software distributed under the License is distributed on an
+ * "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied.  See the License for the
+ * specific language governing permissions and limitations
+ * under the License.
+ */
+
+/*
+ * C API of DataFusion, exchanging record batches through the Arrow C Data
+ * Interface, as defined in datafusion/src/ffi.rs. The functions are exported by
+ * the libraries of the datafusion-c crate, libdatafusion_c.so and
+ * libdatafusion_c.a.
+ *
+ * A record batch is exchanged as a struct array, whose fields are the columns
+ * of the batch. The host allocates the ArrowArray and ArrowSchema structs, and
+ * they are moved from the producer to the consumer, which releases them once
+ * done. The functions failing return NULL or -1, and datafusion_last_error
+ * returns the error.
+ */
+
+#ifndef DATAFUSION_H
+#define DATAFUSION_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#ifndef ARROW_C_DATA_INTERFACE
+#define ARROW_C_DATA_INTERFACE
+
+#define ARROW_FLAG_DICTIONARY_ORDERED 1
+#define ARROW_FLAG_NULLABLE 2
+#define ARROW_FLAG_MAP_KEYS_SORTED 4
+
+struct ArrowSchema {
+  // Array type description
+  const char* format;
+  const char* name;
+  const char* metadata;
+  int64_t flags;
+  int64_t n_children;
+  struct ArrowSchema** children;
+  struct ArrowSchema* dictionary;
+
+  // Release callback
+  void (*release)(struct ArrowSchema*);
+  // Opaque producer-specific data
+  void* private_data;
+};
+
+struct ArrowArray {
+  // Array data description
+  int64_t length;
+  int64_t null_count;
+  int64_t offset;
+  int64_t n_buffers;
+  int64_t n_children;
+  const void** buffers;
+  struct ArrowArray** children;
+  struct ArrowArray* dictionary;
+
+  // Release callback
+  void (*release)(struct ArrowArray*);
+  // Opaque producer-specific data
+  void* private_data;
+};
+
+#endif  // ARROW_C_DATA_INTERFACE
+
+/* Execution context, with the runtime its queries are run on */
+typedef struct DataFusionContext DataFusionContext;
+
+/* Record batches output by a query */
+typedef struct DataFusionResult DataFusionResult;
+
+/*
+ * Returns the error of the last function called by this thread which failed,
+ * valid until the next failure, or NULL if none failed.
+ */
+const char* datafusion_last_error(void);
+
+/*
+ * Creates an execution context, freed by datafusion_context_free, or returns
+ * NULL on error.
+ */
+DataFusionContext* datafusion_context_new(void);
+
+/* Frees a context, which may be NULL */
+void datafusion_context_free(DataFusionContext* ctx);
+
+/*
+ * Registers the count record batches exported to arrays and schemas as the
+ * table name, of the schema of the first one, and returns 0, or -1 on error.
+ * The structs are moved, leaving them released, unless the arguments are NULL.
+ */
+int datafusion_register_record_batches(DataFusionContext* ctx, const char* name,
+                                       struct ArrowArray* arrays,
+                                       struct ArrowSchema* schemas, size_t count);
+
+/*
+ * Runs the SQL query sql and returns its result, freed by
+ * datafusion_result_free, or NULL on error.
+ */
+DataFusionResult* datafusion_sql(DataFusionContext* ctx, const char* sql);
+
+/* Returns the number of record batches of result */
+size_t datafusion_result_num_batches(const DataFusionResult* result);
+
+/*
+ * Exports the record batch index of result to out_array and out_schema, which
+ * the host releases once done, and returns 0, or -1 on error. The buffers of
+ * the batch are shared rather than copied.
+ */
+int datafusion_result_export_batch(const DataFusionResult* result, size_t index,
+                                   struct ArrowArray* out_array,
+                                   struct ArrowSchema* out_schema);
+
+/*
+ * Frees a result, which may be NULL, the exported batches staying valid until
+ * they are released.
+ */
+void datafusion_result_free(DataFusionResult* result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif  // DATAFUSION_H
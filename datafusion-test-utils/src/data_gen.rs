@@ -0,0 +1,154 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Generation of random tables whose values are drawn from small domains, so that
+//! the queries over them find duplicate keys to group and join on, and whose
+//! floating point values are exact sums of quarters, so that their aggregates do
+//! not depend on the order they are computed in.
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, StringArray,
+};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::Result;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// The strings of the `s` column
+pub const STRINGS: &[&str] = &["", "a", "b", "bar", "foo", "foobar"];
+
+/// Probability of a value to be null
+const NULL_PROBABILITY: f64 = 0.1;
+
+/// Schema of the generated tables, with a nullable column of each type the
+/// expressions of the generated queries are built from:
+///
+/// * `i`, an `Int32` between -10 and 10
+/// * `j`, an `Int64` between -1000 and 1000
+/// * `f`, a `Float64` multiple of 0.25 between -10 and 10
+/// * `s`, a `Utf8` among [`STRINGS`]
+/// * `b`, a `Boolean`
+pub fn table_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("i", DataType::Int32, true),
+        Field::new("j", DataType::Int64, true),
+        Field::new("f", DataType::Float64, true),
+        Field::new("s", DataType::Utf8, true),
+        Field::new("b", DataType::Boolean, true),
+    ]))
+}
+
+/// Generates `num_rows` random rows of [`table_schema`], in batches of at most
+/// `max_batch_size` rows, some of which are empty
+pub fn generate_batches<R: Rng>(
+    rng: &mut R,
+    num_rows: usize,
+    max_batch_size: usize,
+) -> Result<Vec<RecordBatch>> {
+    let mut batches = vec![];
+    let mut remaining = num_rows;
+    while remaining > 0 {
+        let batch_size = rng.gen_range(0..=max_batch_size.max(1).min(remaining));
+        batches.push(generate_batch(rng, batch_size)?);
+        remaining -= batch_size;
+    }
+    Ok(batches)
+}
+
+/// Generates a batch of `num_rows` random rows of [`table_schema`]
+pub fn generate_batch<R: Rng>(rng: &mut R, num_rows: usize) -> Result<RecordBatch> {
+    let i: Int32Array = (0..num_rows)
+        .map(|_| nullable(rng, |rng| rng.gen_range(-10..=10)))
+        .collect();
+    let j: Int64Array = (0..num_rows)
+        .map(|_| nullable(rng, |rng| rng.gen_range(-1000..=1000)))
+        .collect();
+    let f: Float64Array = (0..num_rows)
+        .map(|_| nullable(rng, |rng| rng.gen_range(-40..=40) as f64 / 4.0))
+        .collect();
+    let s: StringArray = (0..num_rows)
+        .map(|_| nullable(rng, |rng| *STRINGS.choose(rng).unwrap()))
+        .collect();
+    let b: BooleanArray = (0..num_rows)
+        .map(|_| nullable(rng, |rng| rng.gen_bool(0.5)))
+        .collect();
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(i),
+        Arc::new(j),
+        Arc::new(f),
+        Arc::new(s),
+        Arc::new(b),
+    ];
+    Ok(RecordBatch::try_new(table_schema(), columns)?)
+}
+
+/// Distributes `batches` over `num_partitions` partitions, at random, so that
+/// some partitions may have no batch
+pub fn partition_batches<R: Rng>(
+    rng: &mut R,
+    batches: Vec<RecordBatch>,
+    num_partitions: usize,
+) -> Vec<Vec<RecordBatch>> {
+    let mut partitions = vec![vec![]; num_partitions];
+    for batch in batches {
+        partitions[rng.gen_range(0..num_partitions)].push(batch);
+    }
+    partitions
+}
+
+fn nullable<R: Rng, T>(rng: &mut R, value: impl FnOnce(&mut R) -> T) -> Option<T> {
+    if rng.gen_bool(NULL_PROBABILITY) {
+        None
+    } else {
+        Some(value(rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn generate_partitioned_batches() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(7);
+        let batches = generate_batches(&mut rng, 100, 16)?;
+        assert!(batches.iter().all(|batch| batch.num_rows() <= 16));
+        assert_eq!(
+            batches.iter().map(|batch| batch.num_rows()).sum::<usize>(),
+            100
+        );
+
+        let num_batches = batches.len();
+        let partitions = partition_batches(&mut rng, batches, 4);
+        assert_eq!(partitions.len(), 4);
+        assert_eq!(
+            partitions.iter().map(|p| p.len()).sum::<usize>(),
+            num_batches
+        );
+
+        // the same seed generates the same rows
+        let mut other_rng = StdRng::seed_from_u64(7);
+        let other_batches = generate_batches(&mut other_rng, 100, 16)?;
+        assert_eq!(other_batches.len(), num_batches);
+        Ok(())
+    }
+}
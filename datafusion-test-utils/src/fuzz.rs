@@ -0,0 +1,220 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Differential execution of the random queries of
+//! [`query_gen`](crate::query_gen) over the random tables of
+//! [`data_gen`](crate::data_gen).
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::array_value_to_string;
+use datafusion::datasource::MemTable;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::physical_plan::coalesce_batches::concat_batches;
+use datafusion::prelude::{ExecutionConfig, ExecutionContext};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::data_gen::{generate_batches, partition_batches, table_schema};
+use crate::query_gen::QueryGenerator;
+
+/// Configuration of a fuzzing run, whose defaults are small enough for the run
+/// to take a few seconds and little memory, as in CI
+#[derive(Debug, Clone)]
+pub struct FuzzConfig {
+    /// Seed of the random tables and queries, so that a failing run can be
+    /// reproduced
+    pub seed: u64,
+    /// Number of generated queries
+    pub num_queries: usize,
+    /// Number of rows of the table `t`, the table `u` having a tenth of them
+    pub num_rows: usize,
+    /// Maximum number of rows of the batches of the tables
+    pub max_batch_size: usize,
+    /// Number of partitions of the tables and target partitions of the
+    /// parallel execution
+    pub target_partitions: usize,
+    /// Maximum depth of the generated expressions
+    pub max_expr_depth: usize,
+    /// Memory limit of the queries of the parallel execution, in bytes
+    pub memory_limit: usize,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            num_queries: 100,
+            num_rows: 1000,
+            max_batch_size: 64,
+            target_partitions: 4,
+            max_expr_depth: 3,
+            memory_limit: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl FuzzConfig {
+    /// The default configuration, whose seed and number of queries are overridden
+    /// by the `DATAFUSION_FUZZ_SEED` and `DATAFUSION_FUZZ_QUERIES` environment
+    /// variables, to reproduce a failure or to run longer outside of CI
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+        let default = Self::default();
+        Self {
+            seed: var("DATAFUSION_FUZZ_SEED").unwrap_or(default.seed),
+            num_queries: var("DATAFUSION_FUZZ_QUERIES")
+                .map(|n| n as usize)
+                .unwrap_or(default.num_queries),
+            ..default
+        }
+    }
+}
+
+/// Outcome of a successful fuzzing run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzReport {
+    /// Number of queries whose results were compared
+    pub queries_run: usize,
+    /// Number of queries skipped, as their reference execution failed, such as
+    /// the queries using features DataFusion does not support
+    pub queries_skipped: usize,
+}
+
+/// Runs the queries generated for `config` with both the reference and the
+/// parallel executions, and fails on the first query whose results differ, or
+/// that only fails in the parallel execution.
+///
+/// The reference execution runs over a single partition and batch, with a
+/// single target partition, and the parallel execution runs over the same rows
+/// split into partitions and small batches, with the repartitions of the
+/// physical optimizer and a memory limit.
+pub async fn run_fuzz(config: &FuzzConfig) -> Result<FuzzReport> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let t = generate_batches(&mut rng, config.num_rows, config.max_batch_size)?;
+    let u = generate_batches(&mut rng, config.num_rows / 10, config.max_batch_size)?;
+
+    let mut reference = ExecutionContext::with_config(
+        ExecutionConfig::new()
+            .with_target_partitions(1)
+            .with_batch_size(config.num_rows.max(1)),
+    );
+    reference.register_table("t", Arc::new(single_partition(&t)?))?;
+    reference.register_table("u", Arc::new(single_partition(&u)?))?;
+
+    let batch_size = rng.gen_range(1..=config.max_batch_size.max(1));
+    let mut parallel = ExecutionContext::with_config(
+        ExecutionConfig::new()
+            .with_target_partitions(config.target_partitions)
+            .with_batch_size(batch_size)
+            .with_memory_limit(config.memory_limit),
+    );
+    for (name, batches) in [("t", t), ("u", u)] {
+        let partitions =
+            partition_batches(&mut rng, batches, config.target_partitions.max(1));
+        let table = MemTable::try_new(table_schema(), partitions)?;
+        parallel.register_table(name, Arc::new(table))?;
+    }
+
+    let generator = QueryGenerator::new(config.max_expr_depth);
+    let mut report = FuzzReport {
+        queries_run: 0,
+        queries_skipped: 0,
+    };
+    for _ in 0..config.num_queries {
+        let sql = generator.generate(&mut rng);
+        let expected = match execute(&mut reference, &sql).await {
+            Ok(expected) => expected,
+            Err(_) => {
+                report.queries_skipped += 1;
+                continue;
+            }
+        };
+        let actual = execute(&mut parallel, &sql).await.map_err(|e| {
+            DataFusionError::Execution(format!(
+                "Fuzzing with seed {} (batch size {}): the query `{}` failed, \
+                 although its reference execution succeeded: {}",
+                config.seed, batch_size, sql, e
+            ))
+        })?;
+        if actual != expected {
+            let mismatch = expected
+                .iter()
+                .zip(&actual)
+                .find(|(expected, actual)| expected != actual);
+            return Err(DataFusionError::Execution(format!(
+                "Fuzzing with seed {} (batch size {}): the query `{}` returned {} \
+                 rows instead of {}, first mismatch (expected, actual): {:?}",
+                config.seed,
+                batch_size,
+                sql,
+                actual.len(),
+                expected.len(),
+                mismatch
+            )));
+        }
+        report.queries_run += 1;
+    }
+    Ok(report)
+}
+
+fn single_partition(batches: &[RecordBatch]) -> Result<MemTable> {
+    let schema = table_schema();
+    let num_rows = batches.iter().map(|batch| batch.num_rows()).sum();
+    let batch = concat_batches(&schema, batches, num_rows)?;
+    MemTable::try_new(schema, vec![vec![batch]])
+}
+
+/// Executes `sql`, returning the rows of its result formatted and sorted
+async fn execute(ctx: &mut ExecutionContext, sql: &str) -> Result<Vec<Vec<String>>> {
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let mut rows = vec![];
+    for batch in &batches {
+        for row in 0..batch.num_rows() {
+            let values = batch
+                .columns()
+                .iter()
+                .map(|column| format_value(column, row))
+                .collect::<Result<Vec<_>>>()?;
+            rows.push(values);
+        }
+    }
+    rows.sort();
+    Ok(rows)
+}
+
+fn format_value(column: &ArrayRef, row: usize) -> Result<String> {
+    if column.is_null(row) {
+        return Ok("NULL".to_owned());
+    }
+    let value = array_value_to_string(column, row)?;
+    // -0.0 and 0.0 are equal, so the minimum or maximum of values with both
+    // depends on the order the values are read in
+    match column.data_type() {
+        DataType::Float32 | DataType::Float64 if value.parse::<f64>() == Ok(0.0) => {
+            Ok("0".to_owned())
+        }
+        _ => Ok(value),
+    }
+}
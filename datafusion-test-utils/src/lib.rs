@@ -0,0 +1,46 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Utilities to test DataFusion.
+//!
+//! The [`fuzz`] module is a differential SQL fuzzer: it generates random tables
+//! and queries over them, and runs each query both with the simplest physical
+//! plan, over a table of a single partition and batch, and with the plan of a
+//! parallel execution, over the same rows split into many partitions and small
+//! batches. The results of both executions must be the same rows, so that the
+//! regressions of the operators that split or merge their input are caught
+//! without writing the expected results of the queries.
+//!
+//! The [`sqllogictest`] module runs the `.slt` scripts of the sqllogictest format
+//! against an `ExecutionContext`, or a `BallistaContext` with the `ballista`
+//! feature, to compare their results with the expected ones.
+//!
+//! ```no_run
+//! # use datafusion::error::Result;
+//! # use datafusion_test_utils::fuzz::{run_fuzz, FuzzConfig};
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let report = run_fuzz(&FuzzConfig::from_env()).await?;
+//! println!("ran {} queries", report.queries_run);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod data_gen;
+pub mod fuzz;
+pub mod query_gen;
+pub mod sqllogictest;
@@ -0,0 +1,399 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Generation of random SQL queries over the tables of
+//! [`data_gen`](crate::data_gen): filters, projections, aggregations, sorts with
+//! limits, joins, distinct projections and window functions, whose expressions are
+//! random trees of arithmetic, comparisons, boolean connectives, `CASE`, `LIKE`,
+//! `IN` and `BETWEEN` expressions.
+//!
+//! The generated queries have a single result, whatever the order their input is
+//! read in: they only use `LIMIT` after sorting by all their columns, and avoid
+//! the expressions that may overflow or fail, such as divisions, so that the
+//! queries over the generated tables are expected to succeed.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::data_gen::STRINGS;
+
+/// Generator of random SQL queries over the table `t` and the smaller table `u`
+/// of the same schema
+#[derive(Debug, Clone)]
+pub struct QueryGenerator {
+    /// Maximum depth of the generated expressions
+    max_expr_depth: usize,
+}
+
+impl QueryGenerator {
+    /// Create a new generator of queries whose expressions are at most
+    /// `max_expr_depth` deep
+    pub fn new(max_expr_depth: usize) -> Self {
+        Self { max_expr_depth }
+    }
+
+    /// Generates a random query
+    pub fn generate<R: Rng>(&self, rng: &mut R) -> String {
+        match rng.gen_range(0..6) {
+            0 => self.projection(rng),
+            1 => self.aggregation(rng),
+            2 => self.sort_limit(rng),
+            3 => self.join(rng),
+            4 => self.distinct(rng),
+            _ => self.window(rng),
+        }
+    }
+
+    fn projection<R: Rng>(&self, rng: &mut R) -> String {
+        let t = Exprs::new(rng, self.max_expr_depth, None);
+        format!(
+            "SELECT {} FROM t WHERE {}",
+            t.select_list(rng),
+            t.predicate(rng, 0)
+        )
+    }
+
+    fn aggregation<R: Rng>(&self, rng: &mut R) -> String {
+        let t = Exprs::new(rng, self.max_expr_depth, None);
+        let mut group_by: Vec<&str> = ["i", "s", "b"]
+            .iter()
+            .copied()
+            .filter(|_| rng.gen_bool(0.5))
+            .collect();
+        group_by.shuffle(rng);
+        let int = t.int(rng, 0);
+        let float = t.float(rng, 0);
+        let mut select_list = group_by.clone();
+        let aggregates = [
+            "COUNT(*) AS count_all".to_owned(),
+            format!("COUNT({}) AS count", t.column(rng)),
+            format!("SUM({}) AS sum_int", int),
+            format!("MIN({}) AS min_int", int),
+            format!("MAX({}) AS max_int", int),
+            format!("AVG({}) AS avg_int", int),
+            format!("SUM({}) AS sum_float", float),
+            format!("MAX({}) AS max_float", float),
+            format!("COUNT(DISTINCT {}) AS count_distinct", t.column(rng)),
+        ];
+        let num_aggregates = rng.gen_range(1..=aggregates.len());
+        let aggregates: Vec<_> =
+            aggregates.choose_multiple(rng, num_aggregates).collect();
+        select_list.extend(aggregates.iter().map(|aggregate| aggregate.as_str()));
+
+        let mut sql = format!("SELECT {} FROM t", select_list.join(", "));
+        if rng.gen_bool(0.5) {
+            sql += &format!(" WHERE {}", t.predicate(rng, 0));
+        }
+        if !group_by.is_empty() {
+            sql += &format!(" GROUP BY {}", group_by.join(", "));
+        }
+        sql
+    }
+
+    fn sort_limit<R: Rng>(&self, rng: &mut R) -> String {
+        let t = Exprs::new(rng, self.max_expr_depth, None);
+        let num_columns = rng.gen_range(1..=3);
+        let select_list = (0..num_columns)
+            .map(|i| format!("{} AS c{}", t.any(rng, 0), i))
+            .collect::<Vec<_>>();
+        // sorted by all the columns, so that the rows within the limit are known
+        let order_by = (0..num_columns)
+            .map(|i| {
+                let direction = if rng.gen_bool(0.5) { "ASC" } else { "DESC" };
+                format!("c{} {}", i, direction)
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "SELECT {} FROM t WHERE {} ORDER BY {} LIMIT {}",
+            select_list.join(", "),
+            t.predicate(rng, 0),
+            order_by.join(", "),
+            rng.gen_range(1..=50)
+        )
+    }
+
+    fn join<R: Rng>(&self, rng: &mut R) -> String {
+        let t = Exprs::new(rng, self.max_expr_depth, Some("t"));
+        let u = Exprs::new(rng, self.max_expr_depth, Some("u"));
+        let join_type = ["JOIN", "LEFT JOIN", "RIGHT JOIN", "FULL JOIN"]
+            .choose(rng)
+            .unwrap();
+        let mut on = vec!["t.i = u.i"];
+        if rng.gen_bool(0.3) {
+            on.push("t.b = u.b");
+        }
+        format!(
+            "SELECT {} AS c0, {} AS c1, u.j AS c2 FROM t {} u ON {} WHERE {}",
+            t.any(rng, 0),
+            u.any(rng, 0),
+            join_type,
+            on.join(" AND "),
+            t.predicate(rng, 0)
+        )
+    }
+
+    fn distinct<R: Rng>(&self, rng: &mut R) -> String {
+        let t = Exprs::new(rng, self.max_expr_depth, None);
+        format!(
+            "SELECT DISTINCT {} AS c0, {} AS c1 FROM t WHERE {}",
+            t.any(rng, 0),
+            t.column(rng),
+            t.predicate(rng, 0)
+        )
+    }
+
+    fn window<R: Rng>(&self, rng: &mut R) -> String {
+        let t = Exprs::new(rng, self.max_expr_depth, None);
+        let partition_by = ["i", "s", "b"].choose(rng).unwrap();
+        let function = ["COUNT", "SUM", "MIN", "MAX"].choose(rng).unwrap();
+        format!(
+            "SELECT i, j, s, {}({}) OVER (PARTITION BY {}) AS w FROM t WHERE {}",
+            function,
+            t.int(rng, 0),
+            partition_by,
+            t.predicate(rng, 0)
+        )
+    }
+}
+
+/// Generator of the expressions over the columns of a table
+struct Exprs {
+    max_depth: usize,
+    /// qualifier of the columns
+    qualifier: Option<&'static str>,
+}
+
+impl Exprs {
+    fn new<R: Rng>(
+        rng: &mut R,
+        max_depth: usize,
+        qualifier: Option<&'static str>,
+    ) -> Self {
+        Self {
+            max_depth: rng.gen_range(0..=max_depth),
+            qualifier,
+        }
+    }
+
+    fn qualified(&self, column: &str) -> String {
+        match self.qualifier {
+            Some(qualifier) => format!("{}.{}", qualifier, column),
+            None => column.to_owned(),
+        }
+    }
+
+    fn column<R: Rng>(&self, rng: &mut R) -> String {
+        self.qualified(["i", "j", "f", "s", "b"].choose(rng).unwrap())
+    }
+
+    fn select_list<R: Rng>(&self, rng: &mut R) -> String {
+        (0..rng.gen_range(1..=3))
+            .map(|i| format!("{} AS c{}", self.any(rng, 0), i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// An expression of any type
+    fn any<R: Rng>(&self, rng: &mut R, depth: usize) -> String {
+        match rng.gen_range(0..4) {
+            0 => self.int(rng, depth),
+            1 => self.float(rng, depth),
+            2 => self.string(rng),
+            _ => self.predicate(rng, depth),
+        }
+    }
+
+    /// An integer expression. Only columns and literals are multiplied, so that
+    /// the expressions of the generated depths cannot overflow
+    fn int<R: Rng>(&self, rng: &mut R, depth: usize) -> String {
+        if depth >= self.max_depth || rng.gen_bool(0.3) {
+            return self.int_leaf(rng);
+        }
+        match rng.gen_range(0..5) {
+            0 => format!("({} * {})", self.int_leaf(rng), self.int_leaf(rng)),
+            1 => format!(
+                "({} + {})",
+                self.int(rng, depth + 1),
+                self.int(rng, depth + 1)
+            ),
+            2 => format!(
+                "({} - {})",
+                self.int(rng, depth + 1),
+                self.int(rng, depth + 1)
+            ),
+            3 => format!("(-{})", self.int(rng, depth + 1)),
+            _ => format!(
+                "CASE WHEN {} THEN {} ELSE {} END",
+                self.predicate(rng, depth + 1),
+                self.int(rng, depth + 1),
+                self.int(rng, depth + 1)
+            ),
+        }
+    }
+
+    fn int_leaf<R: Rng>(&self, rng: &mut R) -> String {
+        match rng.gen_range(0..3) {
+            0 => self.qualified("i"),
+            1 => self.qualified("j"),
+            _ => format!("({})", rng.gen_range(-10..=10)),
+        }
+    }
+
+    /// A floating point expression, whose values are exact multiples of powers
+    /// of two, as the columns and literals are
+    fn float<R: Rng>(&self, rng: &mut R, depth: usize) -> String {
+        if depth >= self.max_depth || rng.gen_bool(0.3) {
+            return self.float_leaf(rng);
+        }
+        match rng.gen_range(0..4) {
+            0 => format!("({} * {})", self.float_leaf(rng), self.float_leaf(rng)),
+            1 => format!(
+                "({} + {})",
+                self.float(rng, depth + 1),
+                self.float(rng, depth + 1)
+            ),
+            2 => format!(
+                "({} - CAST({} AS DOUBLE))",
+                self.float(rng, depth + 1),
+                self.int(rng, depth + 1)
+            ),
+            _ => format!(
+                "CASE WHEN {} THEN {} ELSE {} END",
+                self.predicate(rng, depth + 1),
+                self.float(rng, depth + 1),
+                self.float(rng, depth + 1)
+            ),
+        }
+    }
+
+    fn float_leaf<R: Rng>(&self, rng: &mut R) -> String {
+        if rng.gen_bool(0.6) {
+            self.qualified("f")
+        } else {
+            format!("({:.2})", rng.gen_range(-40..=40) as f64 / 4.0)
+        }
+    }
+
+    fn string<R: Rng>(&self, rng: &mut R) -> String {
+        match rng.gen_range(0..3) {
+            0 => self.qualified("s"),
+            1 => format!("upper({})", self.qualified("s")),
+            _ => format!("concat({}, {})", self.qualified("s"), string_literal(rng)),
+        }
+    }
+
+    /// A boolean expression
+    fn predicate<R: Rng>(&self, rng: &mut R, depth: usize) -> String {
+        if depth >= self.max_depth || rng.gen_bool(0.3) {
+            return self.predicate_leaf(rng, depth);
+        }
+        match rng.gen_range(0..3) {
+            0 => format!(
+                "({} AND {})",
+                self.predicate(rng, depth + 1),
+                self.predicate(rng, depth + 1)
+            ),
+            1 => format!(
+                "({} OR {})",
+                self.predicate(rng, depth + 1),
+                self.predicate(rng, depth + 1)
+            ),
+            _ => format!("(NOT {})", self.predicate(rng, depth + 1)),
+        }
+    }
+
+    fn predicate_leaf<R: Rng>(&self, rng: &mut R, depth: usize) -> String {
+        let comparison = ["=", "<>", "<", "<=", ">", ">="].choose(rng).unwrap();
+        match rng.gen_range(0..9) {
+            0 => format!(
+                "{} {} {}",
+                self.int(rng, depth + 1),
+                comparison,
+                self.int(rng, depth + 1)
+            ),
+            1 => format!(
+                "{} {} {}",
+                self.float(rng, depth + 1),
+                comparison,
+                self.float(rng, depth + 1)
+            ),
+            2 => format!(
+                "{} {} {}",
+                self.string(rng),
+                comparison,
+                string_literal(rng)
+            ),
+            3 => format!(
+                "{} LIKE '{}%'",
+                self.qualified("s"),
+                STRINGS.choose(rng).unwrap()
+            ),
+            4 => format!(
+                "{} {}",
+                self.column(rng),
+                if rng.gen_bool(0.5) {
+                    "IS NULL"
+                } else {
+                    "IS NOT NULL"
+                }
+            ),
+            5 => {
+                let low = rng.gen_range(-10..=10);
+                format!(
+                    "{} BETWEEN {} AND {}",
+                    self.qualified("i"),
+                    low,
+                    low + rng.gen_range(0..=10)
+                )
+            }
+            6 => {
+                let list = (0..rng.gen_range(1..=4))
+                    .map(|_| rng.gen_range(-10..=10).to_string())
+                    .collect::<Vec<_>>();
+                format!("{} IN ({})", self.qualified("i"), list.join(", "))
+            }
+            7 => self.qualified("b"),
+            _ => if rng.gen_bool(0.5) { "true" } else { "false" }.to_owned(),
+        }
+    }
+}
+
+fn string_literal<R: Rng>(rng: &mut R) -> String {
+    format!("'{}'", STRINGS.choose(rng).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn generate_reproducible_queries() {
+        let generator = QueryGenerator::new(3);
+        let queries = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..20)
+                .map(|_| generator.generate(&mut rng))
+                .collect::<Vec<_>>()
+        };
+        let first = queries(1);
+        assert!(first.iter().all(|query| query.starts_with("SELECT ")));
+        assert_eq!(first, queries(1));
+        assert_ne!(first, queries(2));
+    }
+}
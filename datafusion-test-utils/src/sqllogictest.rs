@@ -0,0 +1,548 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Runner of [sqllogictest] scripts, the `.slt` files of SQL statements and
+//! queries along with their expected results, so that the correctness suites of
+//! other engines can validate the results of DataFusion, and the same suites can
+//! check that distributed and local executions agree.
+//!
+//! The scripts are sequences of records separated by blank lines:
+//!
+//! ```text
+//! # comment
+//! statement ok
+//! CREATE TABLE t AS VALUES (1, 'a'), (2, 'b')
+//!
+//! statement error not found
+//! SELECT * FROM missing
+//!
+//! query IT rowsort
+//! SELECT column1, column2 FROM t
+//! ----
+//! 1 a
+//! 2 b
+//!
+//! skipif ballista
+//! query I
+//! SELECT COUNT(*) FROM t
+//! ----
+//! 2
+//!
+//! halt
+//! ```
+//!
+//! The types of the columns of a query are `I` for integers, `R` for floating
+//! point numbers, printed with three decimals, and `T` for texts, where nulls are
+//! printed as `NULL` and empty strings as `(empty)`. Its rows are compared in the
+//! order they are returned in with `nosort`, the default, or sorted with
+//! `rowsort`, or its values are all sorted with `valuesort`. The expected values
+//! are either one row per line, separated by spaces, one value per line, or the
+//! `N values hashing to H` MD5 hash of the values, each followed by a newline.
+//!
+//! [sqllogictest]: https://www.sqlite.org/sqllogictest/doc/trunk/about.wiki
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use datafusion::arrow::array::Array;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::array_value_to_string;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::prelude::ExecutionContext;
+use md5::{Digest, Md5};
+
+/// An engine the statements and queries of the scripts are run against
+#[async_trait]
+pub trait SqlEngine: Send {
+    /// Name of the engine, as in the `skipif` and `onlyif` conditions
+    fn name(&self) -> &str;
+
+    /// Runs a statement or query, returning its result
+    async fn run(&mut self, sql: &str) -> Result<Vec<RecordBatch>>;
+}
+
+#[async_trait]
+impl SqlEngine for ExecutionContext {
+    fn name(&self) -> &str {
+        "datafusion"
+    }
+
+    async fn run(&mut self, sql: &str) -> Result<Vec<RecordBatch>> {
+        self.sql(sql).await?.collect().await
+    }
+}
+
+#[cfg(feature = "ballista")]
+#[async_trait]
+impl SqlEngine for ballista_client::context::BallistaContext {
+    fn name(&self) -> &str {
+        "ballista"
+    }
+
+    async fn run(&mut self, sql: &str) -> Result<Vec<RecordBatch>> {
+        self.sql(sql).await?.collect().await
+    }
+}
+
+/// Condition on the engine a record is run against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// The record is skipped by the engine of this name
+    SkipIf(String),
+    /// The record is only run by the engine of this name
+    OnlyIf(String),
+}
+
+/// Order the rows of a query are compared in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    /// The rows are compared in the order they are returned in
+    NoSort,
+    /// The rows are sorted before being compared
+    RowSort,
+    /// All the values are sorted before being compared
+    ValueSort,
+}
+
+/// A record of a script
+#[derive(Debug, Clone, PartialEq)]
+pub enum Record {
+    /// A statement, expected to succeed or to fail
+    Statement {
+        /// Line of the record in the script
+        line: usize,
+        conditions: Vec<Condition>,
+        sql: String,
+        /// Whether the statement is expected to fail, with an error whose
+        /// message contains the given text if any
+        expected_error: Option<Option<String>>,
+    },
+    /// A query, expected to return the given results or to fail
+    Query {
+        /// Line of the record in the script
+        line: usize,
+        conditions: Vec<Condition>,
+        sql: String,
+        /// Type of each column, `I`, `R` or `T`
+        types: String,
+        sort_mode: SortMode,
+        /// Whether the query is expected to fail, as with `query error`
+        expected_error: bool,
+        /// Lines of the expected results
+        expected: Vec<String>,
+    },
+    /// The end of the records that are run
+    Halt {
+        /// Line of the record in the script
+        line: usize,
+    },
+}
+
+/// Outcome of a successful run of a script
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunReport {
+    /// Number of statements and queries that were run
+    pub records_run: usize,
+    /// Number of statements and queries skipped by their conditions
+    pub records_skipped: usize,
+}
+
+/// Parses the records of a script
+pub fn parse(script: &str) -> Result<Vec<Record>> {
+    let mut records = vec![];
+    let mut lines = script.lines().enumerate().map(|(i, line)| (i + 1, line));
+    let mut conditions = vec![];
+    while let Some((line, text)) = lines.next() {
+        let text = text.trim_end();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let tokens: Vec<_> = text.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["skipif", engine] => conditions.push(Condition::SkipIf(engine.to_string())),
+            ["onlyif", engine] => conditions.push(Condition::OnlyIf(engine.to_string())),
+            ["hash-threshold", _] => {}
+            ["halt"] => {
+                records.push(Record::Halt { line });
+                conditions.clear();
+            }
+            ["statement", "ok"] | ["statement", "error", ..] => {
+                let expected_error = match tokens[1] {
+                    "ok" => None,
+                    _ => {
+                        let message = text
+                            .splitn(3, char::is_whitespace)
+                            .nth(2)
+                            .map(|message| message.trim().to_owned());
+                        Some(message)
+                    }
+                };
+                let (sql, _) = read_block(&mut lines, false);
+                records.push(Record::Statement {
+                    line,
+                    conditions: std::mem::take(&mut conditions),
+                    sql,
+                    expected_error,
+                });
+            }
+            ["query", types, options @ ..] => {
+                let expected_error = *types == "error";
+                let sort_mode = match options.first() {
+                    None | Some(&"nosort") => SortMode::NoSort,
+                    Some(&"rowsort") => SortMode::RowSort,
+                    Some(&"valuesort") => SortMode::ValueSort,
+                    // a label, to compare the results of queries with each other
+                    Some(_) if options.len() == 1 => SortMode::NoSort,
+                    Some(mode) => {
+                        return Err(DataFusionError::Plan(format!(
+                            "line {}: unknown sort mode {}",
+                            line, mode
+                        )))
+                    }
+                };
+                let (sql, expected) = read_block(&mut lines, true);
+                records.push(Record::Query {
+                    line,
+                    conditions: std::mem::take(&mut conditions),
+                    sql,
+                    types: if expected_error {
+                        String::new()
+                    } else {
+                        types.to_string()
+                    },
+                    sort_mode,
+                    expected_error,
+                    expected,
+                });
+            }
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "line {}: unknown record `{}`",
+                    line, text
+                )))
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Reads the SQL of a record up to the next blank line, or up to the `----`
+/// separator of its expected results, which are then read up to the next blank
+/// line
+fn read_block<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    with_results: bool,
+) -> (String, Vec<String>) {
+    let mut sql = vec![];
+    let mut results = vec![];
+    let mut in_results = false;
+    for (_, text) in lines {
+        let text = text.trim_end();
+        if text.is_empty() {
+            break;
+        }
+        if with_results && !in_results && text == "----" {
+            in_results = true;
+        } else if in_results {
+            results.push(text.to_owned());
+        } else {
+            sql.push(text);
+        }
+    }
+    (sql.join("\n"), results)
+}
+
+/// Runs the records of the script `name` against `engine`, failing on the first
+/// record whose outcome is not the expected one
+pub async fn run_script<E: SqlEngine>(
+    engine: &mut E,
+    name: &str,
+    script: &str,
+) -> Result<RunReport> {
+    let mut report = RunReport::default();
+    for record in parse(script).map_err(|e| context(name, e))? {
+        let (line, conditions) = match &record {
+            Record::Statement {
+                line, conditions, ..
+            }
+            | Record::Query {
+                line, conditions, ..
+            } => (*line, conditions),
+            Record::Halt { .. } => break,
+        };
+        let skipped = conditions.iter().any(|condition| match condition {
+            Condition::SkipIf(skipped) => skipped == engine.name(),
+            Condition::OnlyIf(only) => only != engine.name(),
+        });
+        if skipped {
+            report.records_skipped += 1;
+            continue;
+        }
+        run_record(engine, &record).await.map_err(|e| {
+            context(
+                name,
+                DataFusionError::Execution(format!("line {}: {}", line, e)),
+            )
+        })?;
+        report.records_run += 1;
+    }
+    Ok(report)
+}
+
+/// Runs the script of the file at `path` against `engine`
+pub async fn run_file<E: SqlEngine>(
+    engine: &mut E,
+    path: impl AsRef<Path>,
+) -> Result<RunReport> {
+    let path = path.as_ref();
+    let script = std::fs::read_to_string(path)?;
+    run_script(engine, &path.display().to_string(), &script).await
+}
+
+fn context(name: &str, e: DataFusionError) -> DataFusionError {
+    DataFusionError::Execution(format!("{}: {}", name, e))
+}
+
+async fn run_record<E: SqlEngine>(engine: &mut E, record: &Record) -> Result<()> {
+    match record {
+        Record::Statement {
+            sql,
+            expected_error,
+            ..
+        } => match (engine.run(sql).await, expected_error) {
+            (Ok(_), None) => Ok(()),
+            (Err(e), None) => Err(DataFusionError::Execution(format!(
+                "statement failed: {}",
+                e
+            ))),
+            (Ok(_), Some(_)) => Err(DataFusionError::Execution(
+                "statement succeeded, although it is expected to fail".to_owned(),
+            )),
+            (Err(e), Some(Some(message))) if !e.to_string().contains(message) => {
+                Err(DataFusionError::Execution(format!(
+                    "statement failed with `{}` instead of `{}`",
+                    e, message
+                )))
+            }
+            (Err(_), Some(_)) => Ok(()),
+        },
+        Record::Query {
+            sql,
+            types,
+            sort_mode,
+            expected_error,
+            expected,
+            ..
+        } => match engine.run(sql).await {
+            Ok(_) if *expected_error => Err(DataFusionError::Execution(
+                "query succeeded, although it is expected to fail".to_owned(),
+            )),
+            Err(_) if *expected_error => Ok(()),
+            Ok(batches) => {
+                let rows = format_rows(&batches, types)?;
+                check_results(rows, *sort_mode, expected)
+            }
+            Err(e) => Err(DataFusionError::Execution(format!("query failed: {}", e))),
+        },
+        Record::Halt { .. } => Ok(()),
+    }
+}
+
+/// Formats the values of `batches` with the column `types` of a query
+fn format_rows(batches: &[RecordBatch], types: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    for batch in batches {
+        if batch.num_columns() != types.len() {
+            return Err(DataFusionError::Execution(format!(
+                "query returned {} columns instead of {}",
+                batch.num_columns(),
+                types.len()
+            )));
+        }
+        for row in 0..batch.num_rows() {
+            let values = batch
+                .columns()
+                .iter()
+                .zip(types.chars())
+                .map(|(column, column_type)| {
+                    if column.is_null(row) {
+                        return Ok("NULL".to_owned());
+                    }
+                    let value = array_value_to_string(column, row)?;
+                    Ok(format_value(value, column_type))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            rows.push(values);
+        }
+    }
+    Ok(rows)
+}
+
+fn format_value(value: String, column_type: char) -> String {
+    match (column_type, value.parse::<f64>()) {
+        ('R', Ok(number)) => format!("{:.3}", number),
+        ('I', Ok(number)) if value.parse::<i64>().is_err() => {
+            format!("{}", number.trunc() as i64)
+        }
+        _ if value.is_empty() => "(empty)".to_owned(),
+        _ => value,
+    }
+}
+
+fn check_results(
+    mut rows: Vec<Vec<String>>,
+    sort_mode: SortMode,
+    expected: &[String],
+) -> Result<()> {
+    let values: Vec<String> = match sort_mode {
+        SortMode::NoSort => rows.iter().flatten().cloned().collect(),
+        SortMode::RowSort => {
+            rows.sort();
+            rows.iter().flatten().cloned().collect()
+        }
+        SortMode::ValueSort => {
+            let mut values: Vec<_> = rows.iter().flatten().cloned().collect();
+            values.sort();
+            values
+        }
+    };
+
+    if let [hashed] = expected {
+        let tokens: Vec<_> = hashed.split_whitespace().collect();
+        if let [count, "values", "hashing", "to", hash] = tokens.as_slice() {
+            let mut hasher = Md5::new();
+            for value in &values {
+                hasher.update(value.as_bytes());
+                hasher.update(b"\n");
+            }
+            let actual =
+                format!("{} values hashing to {:x}", values.len(), hasher.finalize());
+            return if actual == format!("{} values hashing to {}", count, hash) {
+                Ok(())
+            } else {
+                Err(DataFusionError::Execution(format!(
+                    "query returned `{}` instead of `{}`",
+                    actual, hashed
+                )))
+            };
+        }
+    }
+
+    // one row per line, unless the values of the rows are on a line each
+    let actual = if expected.len() == rows.len() && sort_mode != SortMode::ValueSort {
+        rows.iter().map(|row| row.join(" ")).collect::<Vec<_>>()
+    } else {
+        values
+    };
+    let expected: Vec<_> = expected.iter().map(|line| line.trim().to_owned()).collect();
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(DataFusionError::Execution(format!(
+            "query returned\n{}\ninstead of\n{}",
+            actual.join("\n"),
+            expected.join("\n")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_records() -> Result<()> {
+        let script = "# comment\n\
+                      statement ok\n\
+                      CREATE TABLE t\n\
+                      AS VALUES (1)\n\
+                      \n\
+                      skipif ballista\n\
+                      query IT rowsort\n\
+                      SELECT 1, 'a'\n\
+                      ----\n\
+                      1 a\n\
+                      \n\
+                      query error\n\
+                      SELECT x\n\
+                      \n\
+                      halt\n";
+        let records = parse(script)?;
+        assert_eq!(
+            records,
+            vec![
+                Record::Statement {
+                    line: 2,
+                    conditions: vec![],
+                    sql: "CREATE TABLE t\nAS VALUES (1)".to_owned(),
+                    expected_error: None,
+                },
+                Record::Query {
+                    line: 7,
+                    conditions: vec![Condition::SkipIf("ballista".to_owned())],
+                    sql: "SELECT 1, 'a'".to_owned(),
+                    types: "IT".to_owned(),
+                    sort_mode: SortMode::RowSort,
+                    expected_error: false,
+                    expected: vec!["1 a".to_owned()],
+                },
+                Record::Query {
+                    line: 12,
+                    conditions: vec![],
+                    sql: "SELECT x".to_owned(),
+                    types: "".to_owned(),
+                    sort_mode: SortMode::NoSort,
+                    expected_error: true,
+                    expected: vec![],
+                },
+                Record::Halt { line: 15 },
+            ]
+        );
+
+        assert!(parse("unknown record").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn compare_results() -> Result<()> {
+        let rows = vec![
+            vec!["2".to_owned(), "b".to_owned()],
+            vec!["1".to_owned(), "a".to_owned()],
+        ];
+        let lines =
+            |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+
+        check_results(rows.clone(), SortMode::NoSort, &lines(&["2 b", "1 a"]))?;
+        check_results(rows.clone(), SortMode::RowSort, &lines(&["1 a", "2 b"]))?;
+        check_results(
+            rows.clone(),
+            SortMode::ValueSort,
+            &lines(&["1", "2", "a", "b"]),
+        )?;
+        check_results(
+            rows.clone(),
+            SortMode::NoSort,
+            &lines(&["4 values hashing to c6582c03e7603a670e1510be9ab3bf16"]),
+        )?;
+        assert!(check_results(rows, SortMode::NoSort, &lines(&["1 a", "2 b"])).is_err());
+
+        assert_eq!(format_value("1.5".to_owned(), 'R'), "1.500");
+        assert_eq!(format_value("1.5".to_owned(), 'I'), "1");
+        assert_eq!(format_value("".to_owned(), 'T'), "(empty)");
+        Ok(())
+    }
+}
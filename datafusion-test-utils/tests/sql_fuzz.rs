@@ -0,0 +1,27 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datafusion::error::Result;
+use datafusion_test_utils::fuzz::{run_fuzz, FuzzConfig};
+
+#[tokio::test]
+async fn sql_fuzz() -> Result<()> {
+    let report = run_fuzz(&FuzzConfig::from_env()).await?;
+    // most of the generated queries are expected to be supported
+    assert!(report.queries_run > report.queries_skipped);
+    Ok(())
+}
@@ -0,0 +1,56 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::path::PathBuf;
+
+use datafusion::error::Result;
+use datafusion::prelude::ExecutionContext;
+use datafusion_test_utils::sqllogictest::run_file;
+
+/// The scripts of the `sqllogictest` directory, in name order
+fn scripts() -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/sqllogictest");
+    let mut scripts: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map(|ext| ext == "slt").unwrap_or(false))
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+#[tokio::test]
+async fn sqllogictest_datafusion() -> Result<()> {
+    for script in scripts() {
+        let report = run_file(&mut ExecutionContext::new(), &script).await?;
+        assert!(report.records_run > 0, "{:?} ran no record", script);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ballista")]
+#[tokio::test]
+async fn sqllogictest_ballista() -> Result<()> {
+    use ballista_client::prelude::{BallistaConfig, BallistaContext};
+
+    for script in scripts() {
+        let config = BallistaConfig::new().unwrap();
+        let mut engine = BallistaContext::standalone(&config, 2).await.unwrap();
+        run_file(&mut engine, &script).await?;
+    }
+    Ok(())
+}
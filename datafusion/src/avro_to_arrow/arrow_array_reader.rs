@@ -52,14 +52,15 @@ use std::sync::Arc;
 
 type RecordSlice<'a> = &'a [&'a Vec<(String, Value)>];
 
-pub struct AvroArrowArrayReader<'a, R: Read> {
-    reader: AvroReader<'a, R>,
+/// Reads the avro records of `values`, e.g. the ones of an avro file, as record batches
+pub struct AvroArrowArrayReader<I> {
+    values: I,
     schema: SchemaRef,
     projection: Option<Vec<String>>,
     schema_lookup: HashMap<String, usize>,
 }
 
-impl<'a, R: Read> AvroArrowArrayReader<'a, R> {
+impl<'a, R: Read> AvroArrowArrayReader<AvroReader<'a, R>> {
     pub fn try_new(
         reader: R,
         schema: SchemaRef,
@@ -67,9 +68,21 @@ impl<'a, R: Read> AvroArrowArrayReader<'a, R> {
     ) -> Result<Self> {
         let reader = AvroReader::new(reader)?;
         let writer_schema = reader.writer_schema().clone();
-        let schema_lookup = Self::schema_lookup(writer_schema)?;
+        Self::try_new_from_values(reader, writer_schema, schema, projection)
+    }
+}
+
+impl<I: Iterator<Item = AvroResult<Value>>> AvroArrowArrayReader<I> {
+    /// Create a reader of the records of `values`, which have the `avro_schema`
+    pub fn try_new_from_values(
+        values: I,
+        avro_schema: AvroSchema,
+        schema: SchemaRef,
+        projection: Option<Vec<String>>,
+    ) -> Result<Self> {
+        let schema_lookup = Self::schema_lookup(avro_schema)?;
         Ok(Self {
-            reader,
+            values,
             schema,
             projection,
             schema_lookup,
@@ -92,7 +105,7 @@ impl<'a, R: Read> AvroArrowArrayReader<'a, R> {
     #[allow(clippy::should_implement_trait)]
     pub fn next_batch(&mut self, batch_size: usize) -> ArrowResult<Option<RecordBatch>> {
         let rows = self
-            .reader
+            .values
             .by_ref()
             .take(batch_size)
             .map(|value| match value {
@@ -748,6 +761,9 @@ impl<'a, R: Read> AvroArrowArrayReader<'a, R> {
                             }
                         }
                     }
+                    DataType::Map(ref entries, _) => {
+                        self.build_map_array(rows, field, entries)
+                    }
                     DataType::Dictionary(ref key_ty, ref val_ty) => self
                         .build_string_dictionary_array(
                             rows,
@@ -796,6 +812,67 @@ impl<'a, R: Read> AvroArrowArrayReader<'a, R> {
         arrays
     }
 
+    /// Builds a map array of the Avro maps of the `map_field` field of the rows,
+    /// with the entries of each map sorted by key
+    fn build_map_array(
+        &self,
+        rows: RecordSlice,
+        map_field: &Field,
+        entries: &Field,
+    ) -> ArrowResult<ArrayRef> {
+        let value_field = match entries.data_type() {
+            DataType::Struct(fields) if fields.len() == 2 => &fields[1],
+            other => {
+                return Err(ArrowError::SchemaError(format!(
+                    "Map entries of type {:?} not supported",
+                    other
+                )))
+            }
+        };
+        // the values of each map are read as the items of a list, whose offsets and
+        // validity are the ones of the maps
+        let mut keys = vec![];
+        let value_rows = rows
+            .iter()
+            .map(|row| match self.field_lookup(map_field.name(), row) {
+                Some(value) => match maybe_resolve_union(value) {
+                    Value::Map(map) => {
+                        let mut map_entries = map.iter().collect::<Vec<_>>();
+                        map_entries.sort_by(|left, right| left.0.cmp(right.0));
+                        keys.extend(map_entries.iter().map(|(key, _)| key.as_str()));
+                        Value::Array(
+                            map_entries
+                                .into_iter()
+                                .map(|(_, value)| value.clone())
+                                .collect(),
+                        )
+                    }
+                    _ => Value::Null,
+                },
+                None => Value::Null,
+            })
+            .collect::<Vec<Value>>();
+        let value_rows = value_rows.iter().collect::<Vec<&Value>>();
+        let values = self.build_nested_list_array::<i32>(&value_rows, value_field)?;
+        let list_data = values.data();
+
+        let entries_data = ArrayDataBuilder::new(entries.data_type().clone())
+            .len(keys.len())
+            .child_data(vec![
+                StringArray::from(keys).data().clone(),
+                list_data.child_data()[0].clone(),
+            ])
+            .build()?;
+        let mut builder = ArrayDataBuilder::new(map_field.data_type().clone())
+            .len(rows.len())
+            .add_buffer(list_data.buffers()[0].clone())
+            .add_child_data(entries_data);
+        if let Some(nulls) = list_data.null_buffer() {
+            builder = builder.null_bit_buffer(nulls.clone());
+        }
+        Ok(make_array(builder.build()?))
+    }
+
     /// Read the primitive list's values into ArrayData
     fn read_primitive_list_values<T>(&self, rows: &[&Value]) -> ArrayData
     where
@@ -984,10 +1061,18 @@ where
 mod test {
     use crate::arrow::array::Array;
     use crate::arrow::datatypes::{Field, TimeUnit};
+    use crate::avro_to_arrow::arrow_array_reader::AvroArrowArrayReader;
+    use crate::avro_to_arrow::schema::to_arrow_schema;
     use crate::avro_to_arrow::{Reader, ReaderBuilder};
-    use arrow::array::{Int32Array, Int64Array, ListArray, TimestampMicrosecondArray};
+    use arrow::array::{
+        Int32Array, Int64Array, ListArray, MapArray, StringArray,
+        TimestampMicrosecondArray,
+    };
     use arrow::datatypes::DataType;
+    use avro_rs::{types::Value, Schema as AvroSchema};
+    use std::collections::HashMap;
     use std::fs::File;
+    use std::sync::Arc;
 
     fn build_reader(name: &str, batch_size: usize) -> Reader<File> {
         let testdata = crate::test_util::arrow_test_data();
@@ -1101,4 +1186,58 @@ mod test {
         assert_eq!(2, num_batches);
         assert_eq!(28, sum_id);
     }
+
+    #[test]
+    fn test_avro_map() {
+        let avro_schema = AvroSchema::parse_str(
+            r#"{"type": "record", "name": "r", "fields": [
+                {"name": "m", "type": ["null", {"type": "map", "values": "long"}]}
+            ]}"#,
+        )
+        .unwrap();
+        let schema = Arc::new(to_arrow_schema(&avro_schema).unwrap());
+        let record = |map: Option<Vec<(&str, i64)>>| {
+            let value = match map {
+                Some(map) => Value::Map(
+                    map.into_iter()
+                        .map(|(key, value)| (key.to_owned(), Value::Long(value)))
+                        .collect::<HashMap<_, _>>(),
+                ),
+                None => Value::Null,
+            };
+            Ok(Value::Record(vec![(
+                "m".to_owned(),
+                Value::Union(Box::new(value)),
+            )]))
+        };
+        let values = vec![
+            record(Some(vec![("b", 2), ("a", 1)])),
+            record(None),
+            record(Some(vec![])),
+            record(Some(vec![("c", 3)])),
+        ];
+        let mut reader = AvroArrowArrayReader::try_new_from_values(
+            values.into_iter(),
+            avro_schema,
+            schema.clone(),
+            None,
+        )
+        .unwrap();
+        let batch = reader.next_batch(10).unwrap().unwrap();
+        assert_eq!(schema, batch.schema());
+
+        let map = batch.column(0).as_any().downcast_ref::<MapArray>().unwrap();
+        assert_eq!(4, map.len());
+        assert!(map.is_null(1));
+        assert_eq!(vec![0, 2, 2, 2, 3], map.value_offsets().to_vec());
+        let keys = map.keys();
+        let keys = keys.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(
+            vec!["a", "b", "c"],
+            (0..keys.len()).map(|i| keys.value(i)).collect::<Vec<_>>()
+        );
+        let values = map.values();
+        let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(vec![1, 2, 3], values.values().to_vec());
+    }
 }
@@ -23,11 +23,15 @@ mod arrow_array_reader;
 mod reader;
 #[cfg(feature = "avro")]
 mod schema;
+#[cfg(feature = "avro")]
+mod schema_registry;
 
 use crate::arrow::datatypes::Schema;
 use crate::error::Result;
 #[cfg(feature = "avro")]
 pub use reader::{Reader, ReaderBuilder};
+#[cfg(feature = "avro")]
+pub use schema_registry::{ConfluentSchemaRegistry, RegistryAvroDecoder, SchemaRegistry};
 use std::io::Read;
 
 #[cfg(feature = "avro")]
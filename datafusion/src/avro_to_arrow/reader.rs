@@ -119,7 +119,7 @@ impl ReaderBuilder {
 
 /// Avro file record  reader
 pub struct Reader<'a, R: Read> {
-    array_reader: AvroArrowArrayReader<'a, R>,
+    array_reader: AvroArrowArrayReader<avro_rs::Reader<'a, R>>,
     schema: SchemaRef,
     batch_size: usize,
 }
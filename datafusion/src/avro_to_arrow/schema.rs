@@ -75,10 +75,12 @@ fn schema_to_field_with_props(
         AvroSchema::Map(value_schema) => {
             let value_field =
                 schema_to_field_with_props(value_schema, Some("value"), false, None)?;
-            DataType::Dictionary(
-                Box::new(DataType::Utf8),
-                Box::new(value_field.data_type().clone()),
-            )
+            // the keys of avro maps are strings
+            let entries = DataType::Struct(vec![
+                Field::new("key", DataType::Utf8, false),
+                value_field,
+            ]);
+            DataType::Map(Box::new(Field::new("entries", entries, false)), false)
         }
         AvroSchema::Union(us) => {
             // If there are only two variants and one of them is null, set the other type as the field data type
@@ -201,6 +203,7 @@ fn default_field_name(dt: &DataType) -> &str {
         DataType::Interval(unit) => match unit {
             IntervalUnit::YearMonth => "intervalyear",
             IntervalUnit::DayTime => "intervalmonth",
+            IntervalUnit::MonthDayNano => "intervalmonthdaynano",
         },
         DataType::Binary => "varbinary",
         DataType::FixedSizeBinary(_) => "fixedsizebinary",
@@ -213,7 +216,7 @@ fn default_field_name(dt: &DataType) -> &str {
         DataType::Struct(_) => "struct",
         DataType::Union(_) => "union",
         DataType::Dictionary(_, _) => "map",
-        DataType::Map(_, _) => unimplemented!("Map support not implemented"),
+        DataType::Map(_, _) => "map",
         DataType::Decimal(_, _) => "decimal",
     }
 }
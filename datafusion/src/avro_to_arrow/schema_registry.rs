@@ -0,0 +1,386 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Decoding of the Avro messages prefixed with the id of their schema in a schema
+//! registry, such as the messages produced to Kafka topics by the Confluent
+//! serializers
+
+use super::arrow_array_reader::AvroArrowArrayReader;
+use super::schema::to_arrow_schema;
+use crate::arrow::datatypes::SchemaRef;
+use crate::arrow::record_batch::RecordBatch;
+use crate::error::{DataFusionError, Result};
+use avro_rs::{from_avro_datum, types::Value, Schema as AvroSchema};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+
+/// Byte the messages prefixed with the id of their schema start with
+const MAGIC_BYTE: u8 = 0;
+
+/// Length of the prefix of the messages: the magic byte and the schema id
+const PREFIX_LEN: usize = 5;
+
+/// A registry of the Avro schemas the messages are written with, by id
+pub trait SchemaRegistry: Send + Sync {
+    /// Returns the definition of the schema with the given id, as JSON
+    fn schema(&self, id: u32) -> Result<String>;
+}
+
+/// Client of a [Confluent Schema Registry], reached over plain HTTP
+///
+/// [Confluent Schema Registry]: https://docs.confluent.io/platform/current/schema-registry/develop/api.html
+#[derive(Debug, Clone)]
+pub struct ConfluentSchemaRegistry {
+    host: String,
+    port: u16,
+    /// path of the API on the server, without the trailing slash
+    base_path: String,
+}
+
+impl ConfluentSchemaRegistry {
+    /// Create a client of the registry at `url`, e.g. `http://localhost:8081`
+    pub fn try_new(url: &str) -> Result<Self> {
+        let address = url.strip_prefix("http://").ok_or_else(|| {
+            DataFusionError::NotImplemented(format!(
+                "Only schema registries reached over http are supported, not {}",
+                url
+            ))
+        })?;
+        let (authority, base_path) = match address.find('/') {
+            Some(i) => address.split_at(i),
+            None => (address, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port.parse().map_err(|_| {
+                    DataFusionError::Plan(format!(
+                        "Invalid port in schema registry url {}",
+                        url
+                    ))
+                })?;
+                (host, port)
+            }
+            None => (authority, 80),
+        };
+        Ok(Self {
+            host: host.to_owned(),
+            port,
+            base_path: base_path.trim_end_matches('/').to_owned(),
+        })
+    }
+
+    /// Returns the id and the definition of the latest schema registered under
+    /// `subject`, e.g. `<topic>-value` for the values of the messages of a topic
+    pub fn latest_schema(&self, subject: &str) -> Result<(u32, String)> {
+        let response = self.get(&format!("/subjects/{}/versions/latest", subject))?;
+        let id = response["id"]
+            .as_u64()
+            .and_then(|id| id.try_into().ok())
+            .ok_or_else(|| self.unexpected_response(&response))?;
+        Ok((id, self.schema_of(&response)?))
+    }
+
+    /// Sends a GET request to the registry and returns the JSON it answers
+    fn get(&self, path: &str) -> Result<serde_json::Value> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        // an HTTP/1.0 request is answered with the whole body, without chunking, and
+        // the connection is closed at its end
+        write!(
+            stream,
+            "GET {}{} HTTP/1.0\r\nHost: {}\r\nAccept: application/vnd.schemaregistry.v1+json\r\n\r\n",
+            self.base_path, path, self.host
+        )?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+
+        let (head, body) = response.split_once("\r\n\r\n").ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "Invalid response of the schema registry to GET {}",
+                path
+            ))
+        })?;
+        let status = head.split_whitespace().nth(1).unwrap_or_default();
+        if status != "200" {
+            return Err(DataFusionError::Execution(format!(
+                "Schema registry answered {} to GET {}: {}",
+                status, path, body
+            )));
+        }
+        serde_json::from_str(body).map_err(|e| {
+            DataFusionError::Execution(format!(
+                "Invalid response of the schema registry to GET {}: {}",
+                path, e
+            ))
+        })
+    }
+
+    fn schema_of(&self, response: &serde_json::Value) -> Result<String> {
+        response["schema"]
+            .as_str()
+            .map(|schema| schema.to_owned())
+            .ok_or_else(|| self.unexpected_response(response))
+    }
+
+    fn unexpected_response(&self, response: &serde_json::Value) -> DataFusionError {
+        DataFusionError::Execution(format!(
+            "Unexpected response of the schema registry: {}",
+            response
+        ))
+    }
+}
+
+impl SchemaRegistry for ConfluentSchemaRegistry {
+    fn schema(&self, id: u32) -> Result<String> {
+        let response = self.get(&format!("/schemas/ids/{}", id))?;
+        self.schema_of(&response)
+    }
+}
+
+/// Decodes the Avro messages prefixed with the id of the schema they are written
+/// with into record batches of a table schema.
+///
+/// The schemas the messages are written with are fetched from the registry the
+/// first time they are seen and then cached. The records are resolved to the
+/// reader schema with the Avro schema resolution rules, so that the messages
+/// written with the previous or next compatible versions of the schema can be
+/// read, while the ones written with incompatible schemas fail to decode.
+pub struct RegistryAvroDecoder {
+    registry: Arc<dyn SchemaRegistry>,
+    reader_schema: AvroSchema,
+    schema: SchemaRef,
+    writer_schemas: Mutex<HashMap<u32, Arc<AvroSchema>>>,
+}
+
+impl RegistryAvroDecoder {
+    /// Create a decoder of the messages whose schemas are registered in
+    /// `registry` into record batches of the Avro `reader_schema`, given as JSON
+    pub fn try_new(
+        registry: Arc<dyn SchemaRegistry>,
+        reader_schema: &str,
+    ) -> Result<Self> {
+        let reader_schema = AvroSchema::parse_str(reader_schema)?;
+        let schema = Arc::new(to_arrow_schema(&reader_schema)?);
+        Ok(Self {
+            registry,
+            reader_schema,
+            schema,
+            writer_schemas: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The schema of the decoded record batches
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Decodes `messages` into a record batch
+    pub fn decode<M: AsRef<[u8]>>(&self, messages: &[M]) -> Result<RecordBatch> {
+        let values = messages
+            .iter()
+            .map(|message| self.decode_value(message.as_ref()))
+            .collect::<Result<Vec<_>>>()?;
+        let mut reader = AvroArrowArrayReader::try_new_from_values(
+            values.into_iter().map(Ok),
+            self.reader_schema.clone(),
+            self.schema.clone(),
+            None,
+        )?;
+        match reader.next_batch(messages.len())? {
+            Some(batch) => Ok(batch),
+            None => Ok(RecordBatch::new_empty(self.schema.clone())),
+        }
+    }
+
+    fn decode_value(&self, message: &[u8]) -> Result<Value> {
+        if message.len() < PREFIX_LEN || message[0] != MAGIC_BYTE {
+            return Err(DataFusionError::Execution(
+                "Avro message is not prefixed with the id of its schema".to_owned(),
+            ));
+        }
+        let id = u32::from_be_bytes(message[1..PREFIX_LEN].try_into().unwrap());
+        let writer_schema = self.writer_schema(id)?;
+        let mut datum = &message[PREFIX_LEN..];
+        from_avro_datum(&writer_schema, &mut datum, Some(&self.reader_schema)).map_err(
+            |e| {
+                DataFusionError::Execution(format!(
+                    "Could not decode Avro message written with schema {}: {}",
+                    id, e
+                ))
+            },
+        )
+    }
+
+    fn writer_schema(&self, id: u32) -> Result<Arc<AvroSchema>> {
+        if let Some(schema) = self.writer_schemas.lock().unwrap().get(&id) {
+            return Ok(schema.clone());
+        }
+        // the lock is not held while fetching the schema, as two fetches of the
+        // same schema are harmless
+        let schema = Arc::new(AvroSchema::parse_str(&self.registry.schema(id)?)?);
+        self.writer_schemas
+            .lock()
+            .unwrap()
+            .insert(id, schema.clone());
+        Ok(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrow::array::{Array, Int64Array, StringArray};
+    use avro_rs::to_avro_datum;
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const V1: &str = r#"{"type": "record", "name": "event", "fields": [
+        {"name": "a", "type": "int"}
+    ]}"#;
+
+    const V2: &str = r#"{"type": "record", "name": "event", "fields": [
+        {"name": "a", "type": "long"},
+        {"name": "b", "type": "string", "default": "none"}
+    ]}"#;
+
+    /// Registry of the schemas v1 and v2, counting the schemas it is asked for
+    #[derive(Default)]
+    struct TestRegistry {
+        requests: AtomicUsize,
+    }
+
+    impl SchemaRegistry for TestRegistry {
+        fn schema(&self, id: u32) -> Result<String> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            match id {
+                1 => Ok(V1.to_owned()),
+                2 => Ok(V2.to_owned()),
+                _ => Err(DataFusionError::Execution(format!("No schema {}", id))),
+            }
+        }
+    }
+
+    fn message(id: u32, schema: &str, fields: Vec<(&str, Value)>) -> Vec<u8> {
+        let schema = AvroSchema::parse_str(schema).unwrap();
+        let record = Value::Record(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name.to_owned(), value))
+                .collect(),
+        );
+        let mut message = vec![MAGIC_BYTE];
+        message.extend_from_slice(&id.to_be_bytes());
+        message.extend(to_avro_datum(&schema, record).unwrap());
+        message
+    }
+
+    #[test]
+    fn decode_evolved_messages() -> Result<()> {
+        let registry = Arc::new(TestRegistry::default());
+        let decoder = RegistryAvroDecoder::try_new(registry.clone(), V2)?;
+        let messages = vec![
+            message(1, V1, vec![("a", Value::Int(1))]),
+            message(
+                2,
+                V2,
+                vec![("a", Value::Long(2)), ("b", Value::String("x".to_owned()))],
+            ),
+            message(1, V1, vec![("a", Value::Int(3))]),
+        ];
+
+        let batch = decoder.decode(&messages)?;
+        assert_eq!(decoder.schema(), batch.schema());
+        let a = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(vec![1, 2, 3], a.values().to_vec());
+        let b = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        // the field missing from v1 gets its default value
+        assert_eq!(
+            vec!["none", "x", "none"],
+            (0..b.len()).map(|i| b.value(i)).collect::<Vec<_>>()
+        );
+        // the writer schemas are fetched once
+        assert_eq!(2, registry.requests.load(Ordering::SeqCst));
+        decoder.decode(&messages)?;
+        assert_eq!(2, registry.requests.load(Ordering::SeqCst));
+
+        // a field without default cannot be read from the messages missing it
+        let decoder = RegistryAvroDecoder::try_new(
+            registry.clone(),
+            r#"{"type": "record", "name": "event", "fields": [
+                {"name": "a", "type": "long"},
+                {"name": "c", "type": "string"}
+            ]}"#,
+        )?;
+        assert!(decoder.decode(&messages[..1]).is_err());
+        // nor the messages of an unknown schema or without prefix
+        assert!(decoder
+            .decode(&[message(3, V1, vec![("a", Value::Int(1))])])
+            .is_err());
+        assert!(decoder.decode(&[vec![1, 2, 3]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn confluent_schema_registry() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let server = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for response in [
+                r#"{"schema": "{\"type\": \"string\"}"}"#,
+                r#"{"subject": "t-value", "id": 7, "version": 2, "schema": "\"long\""}"#,
+            ] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 1024];
+                let len = stream.read(&mut request).unwrap();
+                requests.push(String::from_utf8_lossy(&request[..len]).into_owned());
+                write!(
+                    stream,
+                    "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
+                    response
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        let registry =
+            ConfluentSchemaRegistry::try_new(&format!("http://127.0.0.1:{}/api/", port))?;
+        assert_eq!(r#"{"type": "string"}"#, registry.schema(3)?);
+        assert_eq!(
+            (7, r#""long""#.to_owned()),
+            registry.latest_schema("t-value")?
+        );
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /api/schemas/ids/3 HTTP/1.0\r\n"));
+        assert!(requests[1].starts_with("GET /api/subjects/t-value/versions/latest "));
+
+        assert!(ConfluentSchemaRegistry::try_new("https://localhost:8081").is_err());
+        Ok(())
+    }
+}
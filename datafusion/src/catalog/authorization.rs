@@ -0,0 +1,35 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Authorization of the access of queries to the tables of the catalogs
+
+use super::ResolvedTableReference;
+use crate::error::Result;
+
+/// Decides if a principal may read the tables referenced by a query. It is
+/// invoked while resolving the tables of a query to a logical plan, both from
+/// SQL and from [`ExecutionContext::table`](crate::execution::context::ExecutionContext::table).
+pub trait TableAuthorizer: Send + Sync {
+    /// Returns `Ok(())` if `principal` may SELECT from `table`, or a
+    /// [`DataFusionError::PermissionDenied`](crate::error::DataFusionError::PermissionDenied)
+    /// otherwise. `principal` is `None` when the query was not authenticated.
+    fn authorize_select(
+        &self,
+        principal: Option<&str>,
+        table: ResolvedTableReference,
+    ) -> Result<()>;
+}
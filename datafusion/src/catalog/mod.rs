@@ -18,15 +18,18 @@
 //! This module contains interfaces and default implementations
 //! of table namespacing concepts, including catalogs and schemas.
 
+pub mod authorization;
 pub mod catalog;
 pub mod information_schema;
+pub mod policy;
 pub mod schema;
 
 use crate::error::DataFusionError;
 use std::convert::TryFrom;
+use std::fmt;
 
 /// Represents a resolved path to a table of the form "catalog.schema.table"
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct ResolvedTableReference<'a> {
     /// The catalog (aka database) containing the table
     pub catalog: &'a str,
@@ -36,6 +39,12 @@ pub struct ResolvedTableReference<'a> {
     pub table: &'a str,
 }
 
+impl<'a> fmt::Display for ResolvedTableReference<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.catalog, self.schema, self.table)
+    }
+}
+
 /// Represents a path to a table that may require further resolution
 #[derive(Clone, Copy)]
 pub enum TableReference<'a> {
@@ -0,0 +1,340 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Row level security and column masking policies, which restrict the rows
+//! and the values of the columns of tables that principals can read
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use super::{ResolvedTableReference, TableReference};
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::{
+    and, DFSchema, Expr, LogicalPlan, LogicalPlanBuilder, TableScan,
+};
+use crate::optimizer::utils;
+
+/// A policy applying to a single principal, or to all of them
+#[derive(Debug, Clone)]
+struct Policy {
+    principal: Option<String>,
+    expr: Expr,
+}
+
+impl Policy {
+    fn applies_to(&self, principal: Option<&str>) -> bool {
+        self.principal.is_none() || self.principal.as_deref() == principal
+    }
+}
+
+/// The policies of a single table
+#[derive(Debug, Clone, Default)]
+struct TablePolicies {
+    row_filters: Vec<Policy>,
+    /// The masks of the columns, by column name
+    column_masks: Vec<(String, Policy)>,
+}
+
+/// A registry of the row level security and column masking policies of the
+/// tables of the catalogs. The policies are injected by the planner in every
+/// query scanning the tables, so that they apply to SQL and DataFrame queries
+/// alike.
+#[derive(Default)]
+pub struct PolicyRegistry {
+    /// The policies, by fully qualified table name
+    tables: RwLock<HashMap<String, TablePolicies>>,
+}
+
+impl fmt::Debug for PolicyRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PolicyRegistry")
+            .field(
+                "tables",
+                &self.tables.read().unwrap().keys().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl PolicyRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only lets `principal`, or all principals if `None`, read the rows of
+    /// `table` for which `filter` is true. When several row policies apply to
+    /// a principal, the rows must pass all of them.
+    pub fn add_row_policy(
+        &self,
+        table: ResolvedTableReference,
+        principal: Option<&str>,
+        filter: Expr,
+    ) {
+        let mut tables = self.tables.write().unwrap();
+        tables
+            .entry(table.to_string())
+            .or_default()
+            .row_filters
+            .push(Policy {
+                principal: principal.map(str::to_owned),
+                expr: filter,
+            });
+    }
+
+    /// Replaces the values of `column` of `table` read by `principal`, or by
+    /// all principals if `None`, with `mask`, which may reference the columns
+    /// of the table. A mask for the principal takes precedence over a mask for
+    /// all principals.
+    pub fn add_column_mask(
+        &self,
+        table: ResolvedTableReference,
+        column: &str,
+        principal: Option<&str>,
+        mask: Expr,
+    ) {
+        let mut tables = self.tables.write().unwrap();
+        tables
+            .entry(table.to_string())
+            .or_default()
+            .column_masks
+            .push((
+                column.to_owned(),
+                Policy {
+                    principal: principal.map(str::to_owned),
+                    expr: mask,
+                },
+            ));
+    }
+
+    /// Removes all the policies of `table`, returning true if it had any
+    pub fn remove_policies(&self, table: ResolvedTableReference) -> bool {
+        let mut tables = self.tables.write().unwrap();
+        tables.remove(&table.to_string()).is_some()
+    }
+
+    /// Applies the policies of `table` for `principal` to `scan`, the scan of
+    /// the table, filtering its rows and masking its columns
+    pub fn apply(
+        &self,
+        table: ResolvedTableReference,
+        principal: Option<&str>,
+        scan: LogicalPlan,
+    ) -> Result<LogicalPlan> {
+        let tables = self.tables.read().unwrap();
+        let policies = match tables.get(&table.to_string()) {
+            Some(policies) => policies,
+            None => return Ok(scan),
+        };
+
+        let mut builder = LogicalPlanBuilder::from(scan);
+        let filter = policies
+            .row_filters
+            .iter()
+            .filter(|policy| policy.applies_to(principal))
+            .map(|policy| policy.expr.clone())
+            .reduce(and);
+        if let Some(filter) = filter {
+            builder = builder.filter(filter)?;
+        }
+
+        let mut masks: HashMap<&str, &Policy> = HashMap::new();
+        for (column, policy) in &policies.column_masks {
+            if !policy.applies_to(principal) {
+                continue;
+            }
+            match masks.get(column.as_str()) {
+                Some(mask) if mask.principal.is_some() => {}
+                _ => {
+                    masks.insert(column.as_str(), policy);
+                }
+            }
+        }
+        if masks.is_empty() {
+            return builder.build();
+        }
+
+        let schema = builder.schema().clone();
+        for column in masks.keys() {
+            if schema.field_with_unqualified_name(column).is_err() {
+                return Err(DataFusionError::Plan(format!(
+                    "Masked column '{}' not found in table '{}'",
+                    column, table
+                )));
+            }
+        }
+        // the masked columns keep the qualifier of the table, so that
+        // queries reference them as any other column
+        let qualifier = schema.fields().first().and_then(|f| f.qualifier().cloned());
+        let exprs =
+            schema
+                .fields()
+                .iter()
+                .map(|field| match masks.get(field.name().as_str()) {
+                    Some(mask) => mask.expr.clone().alias(field.name()),
+                    None => Expr::Column(field.qualified_column()),
+                });
+        builder.project_with_alias(exprs, qualifier)?.build()
+    }
+
+    /// Applies the policies for `principal` to the table scans of `plan`, a plan
+    /// which was not built by a context applying them, such as a plan received by
+    /// a server. The names of the tables are resolved in `default_catalog` and
+    /// `default_schema`.
+    pub fn apply_to_plan(
+        &self,
+        plan: &LogicalPlan,
+        principal: Option<&str>,
+        default_catalog: &str,
+        default_schema: &str,
+    ) -> Result<LogicalPlan> {
+        let scan = match plan {
+            LogicalPlan::TableScan(scan) => scan,
+            _ => {
+                let inputs = plan
+                    .inputs()
+                    .into_iter()
+                    .map(|input| {
+                        self.apply_to_plan(
+                            input,
+                            principal,
+                            default_catalog,
+                            default_schema,
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                return utils::from_plan(plan, &plan.expressions(), &inputs);
+            }
+        };
+        let table = TableReference::from(scan.table_name.as_str())
+            .resolve(default_catalog, default_schema);
+        if !self.tables.read().unwrap().contains_key(&table.to_string()) {
+            return Ok(plan.clone());
+        }
+        if scan.projection.is_none() {
+            return self.apply(table, principal, plan.clone());
+        }
+        if !scan.nested_projection.is_empty() {
+            return Err(DataFusionError::Plan(format!(
+                "Cannot apply the policies of table '{}' to a scan of nested fields",
+                table
+            )));
+        }
+        // the policies may reference the columns the scan does not read, so they
+        // are applied to a scan of all the columns, which is then projected
+        let schema = DFSchema::try_from_qualified_schema(
+            &scan.table_name,
+            scan.source.schema().as_ref(),
+        )?;
+        let full_scan = LogicalPlan::TableScan(TableScan {
+            projection: None,
+            projected_schema: Arc::new(schema),
+            ..scan.clone()
+        });
+        LogicalPlanBuilder::from(self.apply(table, principal, full_scan)?)
+            .project(
+                scan.projected_schema
+                    .fields()
+                    .iter()
+                    .map(|field| Expr::Column(field.qualified_column())),
+            )?
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lit, LogicalPlanBuilder};
+    use crate::test::test_table_scan;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    fn table() -> ResolvedTableReference<'static> {
+        TableReference::from("test").resolve("datafusion", "public")
+    }
+
+    #[test]
+    fn apply_policies() -> Result<()> {
+        let registry = PolicyRegistry::new();
+        registry.add_row_policy(table(), None, col("a").gt(lit(1)));
+        registry.add_row_policy(table(), Some("bob"), col("b").lt(lit(10)));
+        registry.add_column_mask(table(), "c", None, lit(0u32));
+        registry.add_column_mask(table(), "c", Some("alice"), col("c"));
+
+        let plan = registry.apply(table(), Some("bob"), test_table_scan()?)?;
+        let expected = "Projection: #test.a, #test.b, UInt32(0) AS c, alias=test\
+        \n  Filter: #test.a > Int32(1) AND #test.b < Int32(10)\
+        \n    TableScan: test projection=None";
+        assert_eq!(expected, format!("{:?}", plan));
+
+        let plan = registry.apply(table(), Some("alice"), test_table_scan()?)?;
+        let expected = "Projection: #test.a, #test.b, #test.c AS c, alias=test\
+        \n  Filter: #test.a > Int32(1)\
+        \n    TableScan: test projection=None";
+        assert_eq!(expected, format!("{:?}", plan));
+
+        // the masked columns can be referenced with the table qualifier
+        LogicalPlanBuilder::from(plan)
+            .project(vec![col("test.c")])?
+            .build()?;
+
+        assert!(registry.remove_policies(table()));
+        let plan = registry.apply(table(), Some("bob"), test_table_scan()?)?;
+        assert_eq!("TableScan: test projection=None", format!("{:?}", plan));
+        Ok(())
+    }
+
+    #[test]
+    fn apply_policies_to_plan() -> Result<()> {
+        let registry = PolicyRegistry::new();
+        registry.add_row_policy(table(), None, col("c").gt(lit(1u32)));
+        registry.add_column_mask(table(), "a", None, lit(0u32));
+
+        // the scan of a submitted plan may not read the columns of the policies
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::UInt32, false),
+            Field::new("b", DataType::UInt32, false),
+            Field::new("c", DataType::UInt32, false),
+        ]);
+        let plan = LogicalPlanBuilder::scan_empty(Some("test"), &schema, Some(vec![0]))?
+            .project(vec![col("a")])?
+            .build()?;
+        let plan = registry.apply_to_plan(&plan, None, "datafusion", "public")?;
+        let expected = "Projection: #test.a\
+        \n  Projection: #test.a\
+        \n    Projection: UInt32(0) AS a, #test.b, #test.c, alias=test\
+        \n      Filter: #test.c > UInt32(1)\
+        \n        TableScan: test projection=None";
+        assert_eq!(expected, format!("{:?}", plan));
+        Ok(())
+    }
+
+    #[test]
+    fn mask_unknown_column() -> Result<()> {
+        let registry = PolicyRegistry::new();
+        registry.add_column_mask(table(), "x", None, lit(0));
+        let err = registry
+            .apply(table(), None, test_table_scan()?)
+            .unwrap_err();
+        assert_eq!(
+            "Error during planning: Masked column 'x' not found in table 'datafusion.public.test'",
+            err.to_string()
+        );
+        Ok(())
+    }
+}
@@ -23,6 +23,7 @@ use crate::logical_plan::{
     DFSchema, Expr, FunctionRegistry, JoinType, LogicalPlan, Partitioning,
 };
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::physical_plan::SendableRecordBatchStream;
 use async_trait::async_trait;
@@ -124,6 +125,32 @@ pub trait DataFrame: Send + Sync {
         aggr_expr: Vec<Expr>,
     ) -> Result<Arc<dyn DataFrame>>;
 
+    /// Perform an aggregate query over an unbounded input, such as a
+    /// [`StreamingTable`](crate::datasource::streaming::StreamingTable). Instead of
+    /// waiting for the end of the input, the returned stream emits the aggregates of
+    /// the rows received during every processing time window of length `window`.
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new()).await?;
+    /// let stream = df
+    ///     .stream_aggregates(vec![col("a")], vec![min(col("b"))], Duration::from_secs(1))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn stream_aggregates(
+        &self,
+        group_expr: Vec<Expr>,
+        aggr_expr: Vec<Expr>,
+        window: Duration,
+    ) -> Result<SendableRecordBatchStream>;
+
     /// Limit the number of rows returned from this DataFrame.
     ///
     /// ```
@@ -139,6 +166,23 @@ pub trait DataFrame: Send + Sync {
     /// ```
     fn limit(&self, n: usize) -> Result<Arc<dyn DataFrame>>;
 
+    /// Return a random sample of the rows of this DataFrame, where each row is
+    /// returned with probability `fraction`. Samples with the same `seed` return
+    /// the same rows.
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new()).await?;
+    /// let df = df.sample(0.1, 42)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn sample(&self, fraction: f64, seed: u64) -> Result<Arc<dyn DataFrame>>;
+
     /// Calculate the union two [`DataFrame`]s.  The two [`DataFrame`]s must have exactly the same schema
     ///
     /// ```
@@ -405,4 +449,21 @@ pub trait DataFrame: Send + Sync {
     /// # }
     /// ```
     fn except(&self, dataframe: Arc<dyn DataFrame>) -> Result<Arc<dyn DataFrame>>;
+
+    /// Executes this DataFrame and returns a new DataFrame that scans the materialized
+    /// results, so that they are not recomputed by every query built on top of it.
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new()).await?;
+    /// let df = df.filter(col("a").lt_eq(col("b")))?.cache().await?;
+    /// let batches = df.limit(100)?.collect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn cache(&self) -> Result<Arc<dyn DataFrame>>;
 }
@@ -23,7 +23,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 
 use crate::arrow::datatypes::SchemaRef;
-use crate::error::Result;
+use crate::error::{DataFusionError, Result};
 use crate::logical_plan::Expr;
 use crate::physical_plan::ExecutionPlan;
 
@@ -42,6 +42,11 @@ pub enum TableProviderFilterPushDown {
     /// filter expression. The Filter plan node containing this expression
     /// will be removed.
     Exact,
+    /// The provider only guarantees that all returned data satisfies a part
+    /// of this filter expression, e.g. some of the conditions of a disjunction.
+    /// The Filter plan node is preserved with the given residual expression,
+    /// that returned tuples must still satisfy, instead of this expression.
+    Residual(Expr),
 }
 
 /// Indicates the type of this table for metadata/catalog purposes.
@@ -86,8 +91,76 @@ pub trait TableProvider: Sync + Send {
         limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>>;
 
+    /// Create an ExecutionPlan that will scan the table like [`TableProvider::scan`],
+    /// only reading the fields of the struct columns on the paths of
+    /// `nested_projection`, see [`nested_projection`](super::nested_projection).
+    /// The struct columns of the schema of the plan are projected with
+    /// [`project_schema`](super::nested_projection::project_schema).
+    ///
+    /// This is only called when [`TableProvider::supports_nested_projection`]
+    /// returns true.
+    async fn scan_nested(
+        &self,
+        projection: &Option<Vec<usize>>,
+        _nested_projection: &[Vec<String>],
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.scan(projection, batch_size, filters, limit).await
+    }
+
+    /// Tests whether the table provider can only read some of the fields of the
+    /// struct columns, with [`TableProvider::scan_nested`].
+    fn supports_nested_projection(&self) -> bool {
+        false
+    }
+
+    /// Create an ExecutionPlan that will compute the aggregate expressions
+    /// `aggr_expr` over the rows of the table satisfying `filters`, grouped by
+    /// `group_expr`, instead of scanning these rows. The plan outputs the values of
+    /// the group expressions followed by the ones of the aggregate expressions,
+    /// with the given `schema`.
+    ///
+    /// This is only called when [`TableProvider::supports_aggregate_pushdown`]
+    /// returns true for these expressions.
+    async fn scan_aggregate(
+        &self,
+        _group_expr: &[Expr],
+        _aggr_expr: &[Expr],
+        _filters: &[Expr],
+        _schema: SchemaRef,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Err(DataFusionError::NotImplemented(
+            "Aggregate pushdown is not supported by this table provider".to_owned(),
+        ))
+    }
+
+    /// Tests whether the table provider can compute the aggregate expressions
+    /// `aggr_expr` grouped by `group_expr` itself, with
+    /// [`TableProvider::scan_aggregate`], e.g. from the metadata of its files or
+    /// by a remote database.
+    fn supports_aggregate_pushdown(
+        &self,
+        _group_expr: &[Expr],
+        _aggr_expr: &[Expr],
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Tests whether the plans created by [`TableProvider::scan`] return at most
+    /// `limit` rows, so that the `LIMIT` does not need to be applied again to
+    /// the scanned data.
+    fn supports_limit_pushdown(&self) -> bool {
+        false
+    }
+
     /// Tests whether the table provider can make use of a filter expression
-    /// to optimise data retrieval.
+    /// to optimise data retrieval. The filters that are not
+    /// [`Unsupported`](TableProviderFilterPushDown::Unsupported) are passed to
+    /// [`TableProvider::scan`], and only the filters that are not
+    /// [`Exact`](TableProviderFilterPushDown::Exact), or their residuals, are
+    /// re-applied to the scanned data.
     fn supports_filter_pushdown(
         &self,
         _filter: &Expr,
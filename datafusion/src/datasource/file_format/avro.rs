@@ -357,9 +357,11 @@ mod tests {
                     file_groups,
                     statistics,
                     projection: projection.clone(),
+                    nested_projection: vec![],
                     batch_size,
                     limit,
                     table_partition_cols: vec![],
+                    bucket_columns: None,
                 },
                 &[],
             )
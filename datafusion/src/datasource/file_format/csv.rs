@@ -121,6 +121,10 @@ impl FileFormat for CsvFormat {
         Ok(Statistics::default())
     }
 
+    fn is_splittable(&self) -> bool {
+        true
+    }
+
     async fn create_physical_plan(
         &self,
         conf: PhysicalPlanConfig,
@@ -269,9 +273,11 @@ mod tests {
                     file_groups,
                     statistics,
                     projection: projection.clone(),
+                    nested_projection: vec![],
                     batch_size,
                     limit,
                     table_partition_cols: vec![],
+                    bucket_columns: None,
                 },
                 &[],
             )
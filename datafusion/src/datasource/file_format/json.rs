@@ -83,6 +83,10 @@ impl FileFormat for JsonFormat {
         Ok(Statistics::default())
     }
 
+    fn is_splittable(&self) -> bool {
+        true
+    }
+
     async fn create_physical_plan(
         &self,
         conf: PhysicalPlanConfig,
@@ -214,9 +218,11 @@ mod tests {
                     file_groups,
                     statistics,
                     projection: projection.clone(),
+                    nested_projection: vec![],
                     batch_size,
                     limit,
                     table_partition_cols: vec![],
+                    bucket_columns: None,
                 },
                 &[],
             )
@@ -55,6 +55,20 @@ pub trait FileFormat: Send + Sync + fmt::Debug {
     /// estimated statistics might vary greatly between file formats.
     async fn infer_stats(&self, reader: Arc<dyn ObjectReader>) -> Result<Statistics>;
 
+    /// Returns true if the physical plan of this format is able to read a
+    /// byte range of a file (see `PartitionedFile::range`) instead of the
+    /// whole file, allowing large files to be scanned by several partitions.
+    fn is_splittable(&self) -> bool {
+        false
+    }
+
+    /// Returns true if the physical plan of this format is able to only read
+    /// the fields of the struct columns on the paths of
+    /// `PhysicalPlanConfig::nested_projection`, instead of ignoring them.
+    fn supports_nested_projection(&self) -> bool {
+        false
+    }
+
     /// Take a list of files and convert it to the appropriate executor
     /// according to this file format.
     async fn create_physical_plan(
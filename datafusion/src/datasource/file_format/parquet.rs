@@ -21,18 +21,25 @@ use std::any::Any;
 use std::io::Read;
 use std::sync::Arc;
 
+use arrow::array::{make_array, Array, ArrayData, ArrayRef};
 use arrow::datatypes::Schema;
 use arrow::datatypes::SchemaRef;
+use arrow::error::{ArrowError, Result as ArrowResult};
 use async_trait::async_trait;
 use futures::stream::StreamExt;
 use parquet::arrow::ArrowReader;
 use parquet::arrow::ParquetFileArrowReader;
+use parquet::basic::ConvertedType;
 use parquet::errors::ParquetError;
 use parquet::errors::Result as ParquetResult;
+use parquet::file::metadata::{FileMetaData, ParquetMetaData};
 use parquet::file::reader::ChunkReader;
 use parquet::file::reader::Length;
+use parquet::file::reader::{FileReader, RowGroupReader};
 use parquet::file::serialized_reader::SerializedFileReader;
 use parquet::file::statistics::Statistics as ParquetStatistics;
+use parquet::record::reader::RowIter;
+use parquet::schema::types::{SchemaDescriptor, Type as SchemaType, TypePtr};
 
 use super::FileFormat;
 use super::PhysicalPlanConfig;
@@ -102,6 +109,14 @@ impl FileFormat for ParquetFormat {
         Ok(stats)
     }
 
+    fn is_splittable(&self) -> bool {
+        true
+    }
+
+    fn supports_nested_projection(&self) -> bool {
+        true
+    }
+
     async fn create_physical_plan(
         &self,
         conf: PhysicalPlanConfig,
@@ -245,9 +260,11 @@ fn summarize_min_max(
 /// Read and parse the schema of the Parquet file at location `path`
 fn fetch_schema(object_reader: Arc<dyn ObjectReader>) -> Result<Schema> {
     let obj_reader = ChunkObjectReader(object_reader);
-    let file_reader = Arc::new(SerializedFileReader::new(obj_reader)?);
-    let mut arrow_reader = ParquetFileArrowReader::new(file_reader);
-    let schema = arrow_reader.get_schema()?;
+    let file_reader =
+        MapAsListFileReader::try_new(SerializedFileReader::new(obj_reader)?)?;
+    let map_columns = file_reader.map_columns().to_vec();
+    let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(file_reader));
+    let schema = lists_as_maps(arrow_reader.get_schema()?, &map_columns);
 
     Ok(schema)
 }
@@ -255,12 +272,17 @@ fn fetch_schema(object_reader: Arc<dyn ObjectReader>) -> Result<Schema> {
 /// Read and parse the statistics of the Parquet file at location `path`
 fn fetch_statistics(object_reader: Arc<dyn ObjectReader>) -> Result<Statistics> {
     let obj_reader = ChunkObjectReader(object_reader);
-    let file_reader = Arc::new(SerializedFileReader::new(obj_reader)?);
-    let mut arrow_reader = ParquetFileArrowReader::new(file_reader);
-    let schema = arrow_reader.get_schema()?;
+    let file_reader =
+        MapAsListFileReader::try_new(SerializedFileReader::new(obj_reader)?)?;
+    let map_columns = file_reader.map_columns().to_vec();
+    let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(file_reader));
+    let schema = lists_as_maps(arrow_reader.get_schema()?, &map_columns);
     let num_fields = schema.fields().len();
     let fields = schema.fields().to_vec();
     let meta_data = arrow_reader.get_metadata();
+    // the statistics of the leaf columns are the ones of the fields when there is
+    // no nested field, made of several leaf columns
+    let is_flat = meta_data.file_metadata().schema_descr().num_columns() == num_fields;
 
     let mut num_rows = 0;
     let mut total_byte_size = 0;
@@ -273,6 +295,9 @@ fn fetch_statistics(object_reader: Arc<dyn ObjectReader>) -> Result<Statistics>
         num_rows += row_group_meta.num_rows();
         total_byte_size += row_group_meta.total_byte_size();
 
+        if !is_flat {
+            continue;
+        }
         let columns_null_counts = row_group_meta
             .columns()
             .iter()
@@ -311,6 +336,196 @@ fn fetch_statistics(object_reader: Arc<dyn ObjectReader>) -> Result<Statistics>
     Ok(statistics)
 }
 
+/// A reader of a Parquet file presenting the MAP groups of its schema as lists of
+/// key and value structs, which have the same layout. The Arrow reader of the
+/// parquet crate does not read maps, but reads these lists, which are turned back
+/// into maps with [`list_to_map`].
+pub struct MapAsListFileReader<R: FileReader> {
+    inner: R,
+    /// the metadata of the file, with the relabeled schema if it has maps
+    metadata: Option<ParquetMetaData>,
+    /// the names of the top level columns that are maps
+    map_columns: Vec<String>,
+}
+
+impl<R: FileReader> MapAsListFileReader<R> {
+    /// Create a reader of the file read by `inner`
+    pub fn try_new(inner: R) -> Result<Self> {
+        let file_metadata = inner.metadata().file_metadata();
+        let root = file_metadata.schema_descr().root_schema_ptr();
+        if !has_maps(&root) {
+            return Ok(Self {
+                inner,
+                metadata: None,
+                map_columns: vec![],
+            });
+        }
+        let map_columns = root
+            .get_fields()
+            .iter()
+            .filter(|field| is_map(field))
+            .map(|field| field.name().to_owned())
+            .collect();
+        let schema_descr = Arc::new(SchemaDescriptor::new(relabel_maps(&root)?));
+        // the Arrow schema stored in the file describes maps rather than lists
+        let key_value_metadata = file_metadata.key_value_metadata().as_ref().map(|kvs| {
+            kvs.iter()
+                .filter(|kv| kv.key != "ARROW:schema")
+                .cloned()
+                .collect()
+        });
+        let metadata = ParquetMetaData::new(
+            FileMetaData::new(
+                file_metadata.version(),
+                file_metadata.num_rows(),
+                file_metadata.created_by().clone(),
+                key_value_metadata,
+                schema_descr,
+                file_metadata.column_orders().cloned(),
+            ),
+            inner.metadata().row_groups().to_vec(),
+        );
+        Ok(Self {
+            inner,
+            metadata: Some(metadata),
+            map_columns,
+        })
+    }
+
+    /// The names of the top level columns of the file that are maps, read as lists
+    pub fn map_columns(&self) -> &[String] {
+        &self.map_columns
+    }
+}
+
+impl<R: FileReader> FileReader for MapAsListFileReader<R> {
+    fn metadata(&self) -> &ParquetMetaData {
+        self.metadata
+            .as_ref()
+            .unwrap_or_else(|| self.inner.metadata())
+    }
+
+    fn num_row_groups(&self) -> usize {
+        self.inner.num_row_groups()
+    }
+
+    fn get_row_group(&self, i: usize) -> ParquetResult<Box<dyn RowGroupReader + '_>> {
+        self.inner.get_row_group(i)
+    }
+
+    fn get_row_iter(&self, projection: Option<SchemaType>) -> ParquetResult<RowIter> {
+        self.inner.get_row_iter(projection)
+    }
+}
+
+fn is_map(parquet_type: &SchemaType) -> bool {
+    matches!(
+        parquet_type.get_basic_info().converted_type(),
+        ConvertedType::MAP | ConvertedType::MAP_KEY_VALUE
+    )
+}
+
+fn has_maps(parquet_type: &SchemaType) -> bool {
+    parquet_type.is_group()
+        && (is_map(parquet_type)
+            || parquet_type
+                .get_fields()
+                .iter()
+                .any(|field| has_maps(field)))
+}
+
+/// Annotates the MAP groups of `parquet_type` as lists, and their repeated key and
+/// value groups as plain groups
+fn relabel_maps(parquet_type: &TypePtr) -> ParquetResult<TypePtr> {
+    if parquet_type.is_primitive() {
+        return Ok(parquet_type.clone());
+    }
+    let info = parquet_type.get_basic_info();
+    let converted_type = match info.converted_type() {
+        ConvertedType::MAP => ConvertedType::LIST,
+        ConvertedType::MAP_KEY_VALUE => ConvertedType::NONE,
+        other => other,
+    };
+    let mut fields = parquet_type
+        .get_fields()
+        .iter()
+        .map(relabel_maps)
+        .collect::<ParquetResult<Vec<_>>>()?;
+    let mut builder = SchemaType::group_type_builder(info.name())
+        .with_converted_type(converted_type)
+        .with_fields(&mut fields);
+    if converted_type == info.converted_type() {
+        builder = builder.with_logical_type(info.logical_type());
+    }
+    if info.has_repetition() {
+        builder = builder.with_repetition(info.repetition());
+    }
+    if info.has_id() {
+        builder = builder.with_id(info.id());
+    }
+    Ok(Arc::new(builder.build()?))
+}
+
+/// Turns the columns of `schema` named in `map_columns`, read as lists of key and
+/// value structs by [`MapAsListFileReader`], into maps
+pub fn lists_as_maps(schema: Schema, map_columns: &[String]) -> Schema {
+    if map_columns.is_empty() {
+        return schema;
+    }
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| match field.data_type() {
+            DataType::List(entries) if map_columns.contains(field.name()) => {
+                let entries =
+                    Field::new(entries.name(), entries.data_type().clone(), false);
+                Field::new(
+                    field.name(),
+                    DataType::Map(Box::new(entries), false),
+                    field.is_nullable(),
+                )
+            }
+            _ => field.clone(),
+        })
+        .collect();
+    Schema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+/// Turns a list of key and value structs read by [`MapAsListFileReader`] into a
+/// map of `map_type`, sharing the buffers of the list
+pub fn list_to_map(array: &ArrayRef, map_type: &DataType) -> ArrowResult<ArrayRef> {
+    let entries_type = match map_type {
+        DataType::Map(entries, _) => entries.data_type(),
+        other => {
+            return Err(ArrowError::SchemaError(format!(
+                "Cannot read a list as a map of type {:?}",
+                other
+            )))
+        }
+    };
+    let data = array.data();
+    let entries = &data.child_data()[0];
+    // the names and nullability of the fields of the entries may differ
+    let entries = ArrayData::builder(entries_type.clone())
+        .len(entries.len())
+        .offset(entries.offset())
+        .buffers(entries.buffers().to_vec())
+        .child_data(entries.child_data().to_vec());
+    let entries = match data.child_data()[0].null_buffer() {
+        Some(nulls) => entries.null_bit_buffer(nulls.clone()),
+        None => entries,
+    };
+    let mut builder = ArrayData::builder(map_type.clone())
+        .len(data.len())
+        .offset(data.offset())
+        .buffers(data.buffers().to_vec())
+        .add_child_data(entries.build()?);
+    if let Some(nulls) = data.null_buffer() {
+        builder = builder.null_bit_buffer(nulls.clone());
+    }
+    Ok(make_array(builder.build()?))
+}
+
 /// A wrapper around the object reader to make it implement `ChunkReader`
 pub struct ChunkObjectReader(pub Arc<dyn ObjectReader>);
 
@@ -612,9 +827,11 @@ mod tests {
                     file_groups,
                     statistics,
                     projection: projection.clone(),
+                    nested_projection: vec![],
                     batch_size,
                     limit,
                     table_partition_cols: vec![],
+                    bucket_columns: None,
                 },
                 &[],
             )
@@ -0,0 +1,131 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Tables of the series of integers, dates or timestamps returned by the built-in
+//! `range` and `generate_series` table functions
+
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use async_trait::async_trait;
+
+use crate::datasource::TableProvider;
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Expr;
+use crate::physical_plan::generate_series::{GenerateSeriesExec, Series};
+use crate::physical_plan::udtf::{TableFunctionImplementation, TableUDF};
+use crate::physical_plan::ExecutionPlan;
+use crate::scalar::ScalarValue;
+
+/// A table with a single column, the values of a [`Series`], which is generated
+/// in partitions when scanned
+pub struct SeriesTable {
+    series: Series,
+    schema: SchemaRef,
+    partitions: usize,
+}
+
+impl SeriesTable {
+    /// Create a new table of the values of `series` in the column `column_name`,
+    /// generated in `partitions` partitions
+    pub fn new(series: Series, column_name: &str, partitions: usize) -> Self {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            column_name,
+            series.data_type().clone(),
+            false,
+        )]));
+        Self {
+            series,
+            schema,
+            partitions,
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for SeriesTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        _projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // the only column is always projected
+        let series = match limit {
+            Some(limit) => self.series.clone().limit(limit as u64),
+            None => self.series.clone(),
+        };
+        Ok(Arc::new(GenerateSeriesExec::new(
+            series,
+            self.schema.clone(),
+            self.partitions,
+            batch_size,
+        )))
+    }
+
+    fn supports_limit_pushdown(&self) -> bool {
+        true
+    }
+}
+
+/// Creates the `range` table function: `range(start, stop, step)` returns the
+/// integers, dates or timestamps from `start` to `stop` excluded by `step`, in a
+/// column named `range`. The step of integers defaults to 1, and `range(stop)`
+/// starts from 0.
+pub fn range_function(partitions: usize) -> TableUDF {
+    series_function("range", false, partitions)
+}
+
+/// Creates the `generate_series` table function: `generate_series(start, stop,
+/// step)` returns the integers, dates or timestamps from `start` to `stop`
+/// included by `step`, in a column named `generate_series`. The step of integers
+/// defaults to 1.
+pub fn generate_series_function(partitions: usize) -> TableUDF {
+    series_function("generate_series", true, partitions)
+}
+
+fn series_function(name: &'static str, inclusive: bool, partitions: usize) -> TableUDF {
+    let fun: TableFunctionImplementation = Arc::new(move |args| {
+        let one = ScalarValue::Int64(Some(1));
+        let (start, stop, step) = match args {
+            [stop] if !inclusive => (ScalarValue::Int64(Some(0)), stop.clone(), one),
+            [start, stop] => (start.clone(), stop.clone(), one),
+            [start, stop, step] => (start.clone(), stop.clone(), step.clone()),
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "Table function '{}' expects {} to 3 arguments, got {}",
+                    name,
+                    if inclusive { 2 } else { 1 },
+                    args.len()
+                )))
+            }
+        };
+        let series = Series::try_new(&start, &stop, &step, inclusive)?;
+        Ok(Arc::new(SeriesTable::new(series, name, partitions)))
+    });
+    TableUDF::new(name, &fun)
+}
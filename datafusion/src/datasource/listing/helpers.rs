@@ -17,6 +17,7 @@
 
 //! Helper functions for the table implementation
 
+use std::borrow::Cow;
 use std::sync::Arc;
 
 use arrow::{
@@ -29,13 +30,14 @@ use arrow::{
 };
 use chrono::{TimeZone, Utc};
 use futures::{
+    future,
     stream::{self},
     StreamExt, TryStreamExt,
 };
 use log::debug;
 
 use crate::{
-    error::Result,
+    error::{DataFusionError, Result},
     execution::context::ExecutionContext,
     logical_plan::{self, Expr, ExpressionVisitor, Recursion},
     physical_plan::functions::Volatility,
@@ -43,8 +45,8 @@ use crate::{
 };
 
 use crate::datasource::{
-    object_store::{FileMeta, ObjectStore, SizedFile},
-    MemTable, PartitionedFile, PartitionedFileStream,
+    object_store::{FileMeta, FileMetaStream, ObjectStore, SizedFile},
+    FileRange, MemTable, PartitionedFile, PartitionedFileStream,
 };
 
 const FILE_SIZE_COLUMN_NAME: &str = "_df_part_file_size_";
@@ -147,10 +149,199 @@ pub fn split_files(
         .collect()
 }
 
+/// Partition the list of files into at most `n` groups, splitting files
+/// into byte ranges when there are fewer files than groups. Files that are
+/// already restricted to a byte range are split within that range.
+///
+/// Ranges are never smaller than `min_range_size` bytes, so small files
+/// are kept whole. This must only be used for file formats that are able
+/// to read a byte range of a file (see `FileFormat::is_splittable`).
+pub fn split_files_by_range(
+    partitioned_files: Vec<PartitionedFile>,
+    n: usize,
+    min_range_size: u64,
+) -> Vec<Vec<PartitionedFile>> {
+    if partitioned_files.len() >= n {
+        return split_files(partitioned_files, n);
+    }
+
+    let file_range = |file: &PartitionedFile| {
+        file.range.unwrap_or(FileRange {
+            start: 0,
+            end: file.file_meta.size(),
+        })
+    };
+    let total_size: u64 = partitioned_files
+        .iter()
+        .map(|f| {
+            let range = file_range(f);
+            range.end - range.start
+        })
+        .sum();
+    // effectively this is div with rounding up instead of truncating
+    let range_size = ((total_size + n as u64 - 1) / n as u64).max(min_range_size);
+
+    let ranges = partitioned_files
+        .into_iter()
+        .flat_map(|file| {
+            let range = file_range(&file);
+            if range.end - range.start <= range_size {
+                return vec![file];
+            }
+            (range.start..range.end)
+                .step_by(range_size as usize)
+                .map(|start| PartitionedFile {
+                    range: Some(FileRange {
+                        start,
+                        end: (start + range_size).min(range.end),
+                    }),
+                    ..file.clone()
+                })
+                .collect()
+        })
+        .collect();
+
+    split_files(ranges, n)
+}
+
+/// Group the list of files by the bucket they hold into `num_buckets` groups.
+///
+/// The bucket of a file is the first number in its file name, e.g. the files
+/// `part-0.parquet` to `part-7.parquet` written from a plan that is hash partitioned
+/// into 8 partitions hold the buckets 0 to 7. There is a group for every bucket,
+/// even if no file holds it, so that the scan is partitioned like the table.
+pub fn split_files_by_bucket(
+    partitioned_files: Vec<PartitionedFile>,
+    num_buckets: usize,
+) -> Result<Vec<Vec<PartitionedFile>>> {
+    let mut buckets = vec![vec![]; num_buckets];
+    for file in partitioned_files {
+        let path = file.file_meta.path();
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let bucket = file_name
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|digits| !digits.is_empty())
+            .and_then(|digits| digits.parse::<usize>().ok())
+            .filter(|bucket| *bucket < num_buckets)
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "File {} does not hold one of the {} buckets of the table",
+                    path, num_buckets
+                ))
+            })?;
+        buckets[bucket].push(file);
+    }
+    Ok(buckets)
+}
+
+/// Splits a table path into the directory to list and, if the path is a glob
+/// pattern, the pattern that the listed files must match. The directory is the
+/// part of the path before the first component with a wildcard.
+pub fn split_glob(table_path: &str) -> (&str, Option<&str>) {
+    match table_path.find(|c| matches!(c, '*' | '?' | '[')) {
+        Some(wildcard) => {
+            let dir = match table_path[..wildcard].rfind('/') {
+                Some(0) => "/",
+                Some(separator) => &table_path[..separator],
+                None => "",
+            };
+            (dir, Some(table_path))
+        }
+        None => (table_path, None),
+    }
+}
+
+/// Returns true if `path` matches the glob `pattern`, where `?` matches any
+/// character, `*` any sequence of characters within a path component, `**` any
+/// sequence of characters across components, and `[...]` any of the characters
+/// of the set (`[!...]` any of the others)
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    fn matches(pattern: &[char], path: &[char]) -> bool {
+        match pattern.split_first() {
+            None => path.is_empty(),
+            Some(('*', rest)) => {
+                let (across_components, rest) = match rest.split_first() {
+                    Some(('*', rest)) => (true, rest),
+                    _ => (false, rest),
+                };
+                for skipped in 0..=path.len() {
+                    if matches(rest, &path[skipped..]) {
+                        return true;
+                    }
+                    if skipped < path.len() && path[skipped] == '/' && !across_components
+                    {
+                        return false;
+                    }
+                }
+                false
+            }
+            Some(('?', rest)) => match path.split_first() {
+                Some((c, path)) => *c != '/' && matches(rest, path),
+                None => false,
+            },
+            Some(('[', rest)) => {
+                let (negated, rest) = match rest.split_first() {
+                    Some(('!', rest)) => (true, rest),
+                    _ => (false, rest),
+                };
+                let end = match rest.iter().position(|c| *c == ']') {
+                    Some(end) => end,
+                    // not a set, matches a literal '['
+                    None => {
+                        return path.first() == Some(&'[')
+                            && matches(&pattern[1..], &path[1..])
+                    }
+                };
+                match path.split_first() {
+                    Some((c, path)) => {
+                        rest[..end].contains(c) != negated
+                            && matches(&rest[end + 1..], path)
+                    }
+                    None => false,
+                }
+            }
+            Some((c, rest)) => path.first() == Some(c) && matches(rest, &path[1..]),
+        }
+    }
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let path = path.chars().collect::<Vec<_>>();
+    matches(&pattern, &path)
+}
+
+/// Lists the files of a table path that have the given suffix: the files below
+/// the path, or the files matching it if it is a glob pattern (see [`split_glob`])
+pub async fn list_table_files(
+    store: &dyn ObjectStore,
+    table_path: &str,
+    suffix: &str,
+) -> Result<FileMetaStream> {
+    let (dir, pattern) = split_glob(table_path);
+    let pattern = match pattern {
+        Some(pattern) => pattern.to_owned(),
+        None => return store.list_file_with_suffix(dir, suffix).await,
+    };
+    // relative paths of the current directory are listed with a `./` prefix
+    let (dir, prefix) = if dir.is_empty() {
+        (".", "./")
+    } else {
+        (dir, "")
+    };
+    let files = store.list_file_with_suffix(dir, suffix).await?;
+    Ok(Box::pin(files.try_filter(move |file| {
+        let path = file.path();
+        future::ready(glob_matches(
+            &pattern,
+            path.strip_prefix(prefix).unwrap_or(path),
+        ))
+    })))
+}
+
 /// Discover the partitions on the given path and prune out files
 /// that belong to irrelevant partitions using `filters` expressions.
 /// `filters` might contain expressions that can be resolved only at the
 /// file level (e.g. Parquet row group pruning).
+/// If the path is a glob pattern, the partitions are discovered below the
+/// directory that is listed for it (see [`split_glob`]).
 ///
 /// TODO for tables with many files (10k+), it will usually more efficient
 /// to first list the folders relative to the first partition dimension,
@@ -165,13 +356,13 @@ pub async fn pruned_partition_list(
     // if no partition col => simply list all the files
     if table_partition_cols.is_empty() {
         return Ok(Box::pin(
-            store
-                .list_file_with_suffix(table_path, file_extension)
+            list_table_files(store, table_path, file_extension)
                 .await?
                 .map(|f| {
                     Ok(PartitionedFile {
                         partition_values: vec![],
                         file_meta: f?,
+                        range: None,
                     })
                 }),
         ));
@@ -181,7 +372,8 @@ pub async fn pruned_partition_list(
         .iter()
         .filter(|f| expr_applicable_for_cols(table_partition_cols, f))
         .collect();
-    let stream_path = table_path.to_owned();
+    // the partitions are relative to the listed directory
+    let stream_path = split_glob(table_path).0.to_owned();
     if applicable_filters.is_empty() {
         // Parse the partition values while listing all the files
         // Note: We might avoid parsing the partition values if they are not used in any projection,
@@ -189,8 +381,7 @@ pub async fn pruned_partition_list(
         // the object store.
         let table_partition_cols_stream = table_partition_cols.to_vec();
         Ok(Box::pin(
-            store
-                .list_file_with_suffix(table_path, file_extension)
+            list_table_files(store, table_path, file_extension)
                 .await?
                 .filter_map(move |f| {
                     let stream_path = stream_path.clone();
@@ -207,7 +398,10 @@ pub async fn pruned_partition_list(
                         )
                         .map(|p| {
                             p.iter()
-                                .map(|&pn| ScalarValue::Utf8(Some(pn.to_owned())))
+                                .map(|&pn| {
+                                    let value = unescape_partition_path_name(pn);
+                                    ScalarValue::Utf8(Some(value.into_owned()))
+                                })
                                 .collect()
                         });
 
@@ -215,6 +409,7 @@ pub async fn pruned_partition_list(
                             Ok(PartitionedFile {
                                 partition_values,
                                 file_meta,
+                                range: None,
                             })
                         })
                     }
@@ -223,18 +418,20 @@ pub async fn pruned_partition_list(
     } else {
         // parse the partition values and serde them as a RecordBatch to filter them
         // TODO avoid collecting but have a streaming memory table instead
-        let batches: Vec<RecordBatch> = store
-            .list_file_with_suffix(table_path, file_extension)
-            .await?
-            // TODO we set an arbitrary high batch size here, it does not matter as we list
-            // all the files anyway. This number will need to be adjusted according to the object
-            // store if we switch to a streaming-stlye pruning of the files. For instance S3 lists
-            // 1000 items at a time so batches of 1000 would be ideal with S3 as store.
-            .chunks(1024)
-            .map(|v| v.into_iter().collect::<Result<Vec<_>>>())
-            .map(move |metas| paths_to_batch(table_partition_cols, &stream_path, &metas?))
-            .try_collect()
-            .await?;
+        let batches: Vec<RecordBatch> =
+            list_table_files(store, table_path, file_extension)
+                .await?
+                // TODO we set an arbitrary high batch size here, it does not matter as we list
+                // all the files anyway. This number will need to be adjusted according to the object
+                // store if we switch to a streaming-stlye pruning of the files. For instance S3 lists
+                // 1000 items at a time so batches of 1000 would be ideal with S3 as store.
+                .chunks(1024)
+                .map(|v| v.into_iter().collect::<Result<Vec<_>>>())
+                .map(move |metas| {
+                    paths_to_batch(table_partition_cols, &stream_path, &metas?)
+                })
+                .try_collect()
+                .await?;
 
         let mem_table = MemTable::try_new(batches[0].schema(), vec![batches])?;
 
@@ -284,7 +481,8 @@ fn paths_to_batch(
                 None => modified_builder.append_null()?,
             }
             for (i, part_val) in partition_values.iter().enumerate() {
-                partition_builders[i].append_value(part_val)?;
+                partition_builders[i]
+                    .append_value(unescape_partition_path_name(part_val))?;
             }
         } else {
             debug!("No partitioning for path {}", file_meta.path());
@@ -352,13 +550,74 @@ fn batches_to_paths(batches: &[RecordBatch]) -> Vec<PartitionedFile> {
                         ScalarValue::try_from_array(batch.column(col), row).unwrap()
                     })
                     .collect(),
+                range: None,
             })
         })
         .collect()
 }
 
+/// Whether Hive escapes a character of the names and values of the partition
+/// directories
+fn needs_escape(c: char) -> bool {
+    c.is_ascii_control()
+        || matches!(
+            c,
+            '"' | '#'
+                | '%'
+                | '\''
+                | '*'
+                | '/'
+                | ':'
+                | '='
+                | '?'
+                | '\\'
+                | '{'
+                | '['
+                | ']'
+                | '^'
+        )
+}
+
+/// Escapes the name or the value of a partition column in the name of a partition
+/// directory like Hive does, e.g. `a=b/c` is escaped as `a%3Db%2Fc`
+pub fn escape_partition_path_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if needs_escape(c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Unescapes the name or the value of a partition column escaped by
+/// [escape_partition_path_name], keeping the `%` not followed by two hex digits
+pub fn unescape_partition_path_name(name: &str) -> Cow<str> {
+    if !name.contains('%') {
+        return Cow::Borrowed(name);
+    }
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.char_indices();
+    while let Some((i, c)) = chars.next() {
+        let code = name
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match code {
+            Some(code) if c == '%' => {
+                unescaped.push(char::from(code));
+                chars.nth(1);
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
 /// Extract the partition values for the given `file_path` (in the given `table_path`)
-/// associated to the partitions defined by `table_partition_cols`
+/// associated to the partitions defined by `table_partition_cols`. The values are
+/// still escaped, see [unescape_partition_path_name].
 fn parse_partitions_for_path<'a>(
     table_path: &str,
     file_path: &'a str,
@@ -375,7 +634,9 @@ fn parse_partitions_for_path<'a>(
     let mut part_values = vec![];
     for (path, pn) in subpath.split('/').zip(table_partition_cols) {
         match path.split_once('=') {
-            Some((name, val)) if name == pn => part_values.push(val),
+            Some((name, val)) if unescape_partition_path_name(name) == pn.as_str() => {
+                part_values.push(val)
+            }
             _ => return None,
         }
     }
@@ -431,6 +692,160 @@ mod tests {
         assert_eq!(0, chunks.len());
     }
 
+    #[test]
+    fn test_split_files_by_range() {
+        // a single big file is split in as many ranges as partitions
+        let files = vec![PartitionedFile::new("a".to_owned(), 100)];
+        let chunks = split_files_by_range(files, 4, 10);
+        assert_eq!(4, chunks.len());
+        let ranges: Vec<_> = chunks.iter().map(|c| c[0].range.unwrap()).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                FileRange { start: 0, end: 25 },
+                FileRange { start: 25, end: 50 },
+                FileRange { start: 50, end: 75 },
+                FileRange {
+                    start: 75,
+                    end: 100
+                },
+            ]
+        );
+
+        // ranges are not smaller than the minimum size
+        let files = vec![PartitionedFile::new("a".to_owned(), 100)];
+        let chunks = split_files_by_range(files, 4, 40);
+        assert_eq!(3, chunks.len());
+        assert_eq!(
+            Some(FileRange {
+                start: 80,
+                end: 100
+            }),
+            chunks[2][0].range
+        );
+
+        // small files are kept whole
+        let files = vec![
+            PartitionedFile::new("a".to_owned(), 10),
+            PartitionedFile::new("b".to_owned(), 90),
+        ];
+        let chunks = split_files_by_range(files, 4, 1);
+        assert_eq!(3, chunks.len());
+        assert_eq!(None, chunks[0][0].range);
+        assert_eq!(Some(FileRange { start: 0, end: 25 }), chunks[0][1].range);
+        assert_eq!(Some(FileRange { start: 75, end: 90 }), chunks[2][0].range);
+
+        // a range of a file is split within the range
+        let files = vec![PartitionedFile {
+            range: Some(FileRange { start: 50, end: 90 }),
+            ..PartitionedFile::new("a".to_owned(), 100)
+        }];
+        let chunks = split_files_by_range(files, 2, 1);
+        assert_eq!(2, chunks.len());
+        assert_eq!(Some(FileRange { start: 50, end: 70 }), chunks[0][0].range);
+        assert_eq!(Some(FileRange { start: 70, end: 90 }), chunks[1][0].range);
+
+        // enough files, no need to split
+        let files = vec![
+            PartitionedFile::new("a".to_owned(), 100),
+            PartitionedFile::new("b".to_owned(), 100),
+        ];
+        let chunks = split_files_by_range(files, 2, 1);
+        assert_eq!(2, chunks.len());
+        assert!(chunks.iter().all(|c| c[0].range.is_none()));
+    }
+
+    #[test]
+    fn test_split_files_by_bucket() -> Result<()> {
+        let new_partitioned_file = |path: &str| PartitionedFile::new(path.to_owned(), 10);
+        let files = vec![
+            new_partitioned_file("table/year=2021/part-1.parquet"),
+            new_partitioned_file("table/year=2021/part-0.parquet"),
+            new_partitioned_file("table/year=2022/part-1.parquet"),
+            new_partitioned_file("table2/part-10.parquet"),
+        ];
+
+        let buckets = split_files_by_bucket(files.clone(), 11)?;
+        assert_eq!(11, buckets.len());
+        assert_eq!(1, buckets[0].len());
+        assert_eq!(2, buckets[1].len());
+        assert!(buckets[2].is_empty());
+        assert_eq!("table2/part-10.parquet", buckets[10][0].file_meta.path());
+
+        let err = split_files_by_bucket(files, 4).unwrap_err();
+        assert!(err.to_string().contains("table2/part-10.parquet"));
+
+        // an empty table still has all its buckets
+        let buckets = split_files_by_bucket(vec![], 4)?;
+        assert_eq!(4, buckets.len());
+        assert!(buckets.iter().all(|bucket| bucket.is_empty()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_glob() {
+        assert_eq!(split_glob("/data/table"), ("/data/table", None));
+        assert_eq!(
+            split_glob("/data/2023-*/**.parquet"),
+            ("/data", Some("/data/2023-*/**.parquet"))
+        );
+        assert_eq!(split_glob("/*.csv"), ("/", Some("/*.csv")));
+        assert_eq!(split_glob("file?.csv"), ("", Some("file?.csv")));
+
+        let pattern = "/data/2023-*/**.parquet";
+        assert!(glob_matches(pattern, "/data/2023-01/a.parquet"));
+        assert!(glob_matches(pattern, "/data/2023-01/x=1/a.parquet"));
+        assert!(!glob_matches(pattern, "/data/2023-01/a.csv"));
+        assert!(!glob_matches(pattern, "/data/2022-01/a.parquet"));
+        assert!(!glob_matches("/data/*.parquet", "/data/2023/a.parquet"));
+        assert!(glob_matches("/data/file?.csv", "/data/file1.csv"));
+        assert!(!glob_matches("/data/file?.csv", "/data/file12.csv"));
+        assert!(glob_matches("/data/file[12].csv", "/data/file2.csv"));
+        assert!(!glob_matches("/data/file[!12].csv", "/data/file2.csv"));
+        assert!(glob_matches("/data/file[!12].csv", "/data/file3.csv"));
+    }
+
+    #[tokio::test]
+    async fn test_pruned_partition_list_glob() -> Result<()> {
+        let store = TestObjectStore::new_arc(&[
+            ("tablepath/day=01/mypartition=val1/file.parquet", 100),
+            ("tablepath/day=01/mypartition=val2/file.parquet", 100),
+            ("tablepath/day=02/mypartition=val1/file.parquet", 100),
+            ("tablepath/day=11/mypartition=val1/file.parquet", 100),
+        ]);
+        let filter = Expr::eq(col("mypartition"), lit("val1"));
+        let pruned = pruned_partition_list(
+            store.as_ref(),
+            "tablepath/day=0*/**",
+            &[filter],
+            ".parquet",
+            &[String::from("day"), String::from("mypartition")],
+        )
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        // the partitions are discovered below the listed directory
+        let paths = pruned
+            .iter()
+            .map(|f| (f.file_meta.path(), f.partition_values.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                (
+                    "tablepath/day=01/mypartition=val1/file.parquet",
+                    vec![ScalarValue::from("01"), ScalarValue::from("val1")]
+                ),
+                (
+                    "tablepath/day=02/mypartition=val1/file.parquet",
+                    vec![ScalarValue::from("02"), ScalarValue::from("val1")]
+                ),
+            ]
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_pruned_partition_list_empty() {
         let store = TestObjectStore::new_arc(&[
@@ -611,6 +1026,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_escape_partition_path_name() {
+        for name in [
+            "v1",
+            "",
+            "a=b/c",
+            "100%",
+            "%2F",
+            "x:y?*",
+            "\tquote\"",
+            "été",
+        ] {
+            let escaped = escape_partition_path_name(name);
+            assert!(!escaped.contains(&['/', '=', ':'][..]));
+            assert_eq!(unescape_partition_path_name(&escaped), name);
+        }
+        assert_eq!(escape_partition_path_name("a=b/c"), "a%3Db%2Fc");
+        // the invalid escapes are kept
+        assert_eq!(unescape_partition_path_name("100%"), "100%");
+        assert_eq!(unescape_partition_path_name("%zz%4"), "%zz%4");
+        assert_eq!(
+            Some(vec!["a%3Db"]),
+            parse_partitions_for_path(
+                "bucket/mytable",
+                "bucket/mytable/my%3Dpartition=a%3Db/file.csv",
+                &[String::from("my=partition")]
+            )
+        );
+    }
+
     #[test]
     fn test_path_batch_roundtrip_no_partiton() {
         let files = vec![
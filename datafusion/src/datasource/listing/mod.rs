@@ -21,4 +21,11 @@
 mod helpers;
 mod table;
 
-pub use table::{ListingOptions, ListingTable};
+pub use helpers::{
+    escape_partition_path_name, pruned_partition_list, split_files, split_files_by_range,
+    unescape_partition_path_name,
+};
+pub use table::{
+    Bucketing, ListingOptions, ListingTable, DEFAULT_STAT_COLLECTION_CONCURRENCY,
+    MIN_FILE_RANGE_SIZE,
+};
@@ -17,11 +17,11 @@
 
 //! The table implementation.
 
-use std::{any::Any, sync::Arc};
+use std::{any::Any, sync::Arc, time::Duration};
 
 use arrow::datatypes::{Field, Schema, SchemaRef};
 use async_trait::async_trait;
-use futures::StreamExt;
+use futures::{stream, StreamExt};
 
 use crate::{
     error::Result,
@@ -34,13 +34,28 @@ use crate::{
 };
 
 use crate::datasource::{
-    datasource::TableProviderFilterPushDown, file_format::FileFormat,
-    get_statistics_with_limit, object_store::ObjectStore, PartitionedFile, TableProvider,
+    datasource::TableProviderFilterPushDown,
+    file_format::FileFormat,
+    get_statistics_with_limit,
+    nested_projection::project_column,
+    object_store::{caching::CachingObjectStore, ObjectStore},
+    PartitionedFile, TableProvider,
 };
 
-use super::helpers::{expr_applicable_for_cols, pruned_partition_list, split_files};
+use super::helpers::{
+    expr_applicable_for_cols, list_table_files, pruned_partition_list, split_files,
+    split_files_by_bucket, split_files_by_range,
+};
+
+/// The minimum size of the byte ranges that large files are split into when
+/// the file format supports it
+pub const MIN_FILE_RANGE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// The default maximum number of files whose statistics are collected concurrently
+pub const DEFAULT_STAT_COLLECTION_CONCURRENCY: usize = 16;
 
 /// Options for creating a `ListingTable`
+#[derive(Clone)]
 pub struct ListingOptions {
     /// A suffix on which files should be filtered (leave empty to
     /// keep all files on the path)
@@ -62,6 +77,40 @@ pub struct ListingOptions {
     /// Group files to avoid that the number of partitions exceeds
     /// this limit
     pub target_partitions: usize,
+    /// The bucketing of the files. If set, the files are grouped by the
+    /// bucket they hold instead of by `target_partitions`, so that scans
+    /// of the table are known to be hash partitioned by the bucket columns.
+    pub bucketing: Option<Bucketing>,
+    /// If set, the file listings of the table and the footers of its files, such as
+    /// the Parquet metadata, are cached for this long, so that repeated queries
+    /// don't list the files and read their footers again.
+    pub metadata_cache_ttl: Option<Duration>,
+    /// The maximum number of files whose statistics are collected concurrently
+    /// when `collect_stat` is set. The statistics are collected when the table
+    /// is scanned, and only for as many files as the limit of the scan requires.
+    pub stat_collection_concurrency: usize,
+}
+
+/// Declares that the files of a table are bucketed: the rows are hash partitioned by
+/// the bucket columns like `Partitioning::Hash` does, and every file holds the rows of
+/// one bucket. Such files are written e.g. by writing a plan that is hash repartitioned
+/// into `num_buckets` partitions. The bucket of a file is the first number in its name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucketing {
+    /// The names of the columns that the rows are hash partitioned by
+    pub columns: Vec<String>,
+    /// The number of buckets
+    pub num_buckets: usize,
+}
+
+impl Bucketing {
+    /// Create a bucketing of `num_buckets` buckets by the given columns
+    pub fn new(columns: Vec<String>, num_buckets: usize) -> Self {
+        Self {
+            columns,
+            num_buckets,
+        }
+    }
 }
 
 impl ListingOptions {
@@ -71,6 +120,9 @@ impl ListingOptions {
     /// - no input partition to discover
     /// - one target partition
     /// - no stat collection
+    /// - no bucketing
+    /// - no metadata cache
+    /// - statistics of `DEFAULT_STAT_COLLECTION_CONCURRENCY` files collected concurrently
     pub fn new(format: Arc<dyn FileFormat>) -> Self {
         Self {
             file_extension: String::new(),
@@ -78,6 +130,9 @@ impl ListingOptions {
             table_partition_cols: vec![],
             collect_stat: true,
             target_partitions: 1,
+            bucketing: None,
+            metadata_cache_ttl: None,
+            stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
         }
     }
 
@@ -87,25 +142,59 @@ impl ListingOptions {
     /// This method will not be called by the table itself but before creating it.
     /// This way when creating the logical plan we can decide to resolve the schema
     /// locally or ask a remote service to do it (e.g a scheduler).
+    ///
+    /// The path can be a glob pattern, e.g. `/data/2023-*/**.parquet`.
     pub async fn infer_schema<'a>(
         &'a self,
         object_store: Arc<dyn ObjectStore>,
         path: &'a str,
     ) -> Result<SchemaRef> {
-        let file_stream = object_store
-            .list_file_with_suffix(path, &self.file_extension)
-            .await?
+        self.infer_schema_of_paths(object_store, &[path.to_owned()])
+            .await
+    }
+
+    /// Infer the schema of the files at all the given paths, like `infer_schema`
+    pub async fn infer_schema_of_paths<'a>(
+        &'a self,
+        object_store: Arc<dyn ObjectStore>,
+        paths: &'a [String],
+    ) -> Result<SchemaRef> {
+        let object_store = self.cached_object_store(object_store);
+        let mut file_lists = Vec::with_capacity(paths.len());
+        for path in paths {
+            file_lists.push(
+                list_table_files(object_store.as_ref(), path, &self.file_extension)
+                    .await?,
+            );
+        }
+        let file_stream = stream::iter(file_lists)
+            .flatten()
             .map(move |file_meta| object_store.file_reader(file_meta?.sized_file));
         let file_schema = self.format.infer_schema(Box::pin(file_stream)).await?;
         Ok(file_schema)
     }
+
+    /// The object store caching the metadata of the files of `object_store` if the
+    /// table caches them
+    fn cached_object_store(
+        &self,
+        object_store: Arc<dyn ObjectStore>,
+    ) -> Arc<dyn ObjectStore> {
+        match self.metadata_cache_ttl {
+            Some(ttl) if object_store.metadata_cache_ttl() != Some(ttl) => {
+                Arc::new(CachingObjectStore::new(object_store, ttl))
+            }
+            _ => object_store,
+        }
+    }
 }
 
 /// An implementation of `TableProvider` that uses the object store
 /// or file system listing capability to get the list of files.
 pub struct ListingTable {
     object_store: Arc<dyn ObjectStore>,
-    table_path: String,
+    /// The root paths or glob patterns of the files of the table
+    table_paths: Vec<String>,
     /// File fields only
     file_schema: SchemaRef,
     /// File fields + partition columns
@@ -118,11 +207,26 @@ impl ListingTable {
     /// The provided `schema` must be resolved before creating the table
     /// and should contain the fields of the file without the table
     /// partitioning columns.
+    ///
+    /// The table path is either the root path of the files, or a glob pattern
+    /// matching them, e.g. `/data/2023-*/**.parquet`.
     pub fn new(
         object_store: Arc<dyn ObjectStore>,
         table_path: String,
         file_schema: SchemaRef,
         options: ListingOptions,
+    ) -> Self {
+        Self::new_with_paths(object_store, vec![table_path], file_schema, options)
+    }
+
+    /// Create new table whose files are the ones of all the given paths, each of
+    /// them a root path or a glob pattern like in `new`. There must be at least
+    /// one path.
+    pub fn new_with_paths(
+        object_store: Arc<dyn ObjectStore>,
+        table_paths: Vec<String>,
+        file_schema: SchemaRef,
+        options: ListingOptions,
     ) -> Self {
         // Add the partition columns to the file schema
         let mut table_fields = file_schema.fields().clone();
@@ -135,8 +239,8 @@ impl ListingTable {
         }
 
         Self {
-            object_store,
-            table_path,
+            object_store: options.cached_object_store(object_store),
+            table_paths,
             file_schema,
             table_schema: Arc::new(Schema::new(table_fields)),
             options,
@@ -147,9 +251,13 @@ impl ListingTable {
     pub fn object_store(&self) -> &Arc<dyn ObjectStore> {
         &self.object_store
     }
-    /// Get path ref
+    /// Get path ref, the first one if the table has several paths
     pub fn table_path(&self) -> &str {
-        &self.table_path
+        &self.table_paths[0]
+    }
+    /// Get paths ref
+    pub fn table_paths(&self) -> &[String] {
+        &self.table_paths
     }
     /// Get options ref
     pub fn options(&self) -> &ListingOptions {
@@ -173,6 +281,18 @@ impl TableProvider for ListingTable {
         batch_size: usize,
         filters: &[Expr],
         limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.scan_nested(projection, &[], batch_size, filters, limit)
+            .await
+    }
+
+    async fn scan_nested(
+        &self,
+        projection: &Option<Vec<usize>>,
+        nested_projection: &[Vec<String>],
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         let (partitioned_file_lists, statistics) =
             self.list_files_for_scan(filters, limit).await?;
@@ -180,12 +300,16 @@ impl TableProvider for ListingTable {
         // if no files need to be read, return an `EmptyExec`
         if partitioned_file_lists.is_empty() {
             let schema = self.schema();
-            let projected_schema = match &projection {
-                None => schema,
-                Some(p) => Arc::new(Schema::new(
-                    p.iter().map(|i| schema.field(*i).clone()).collect(),
-                )),
+            let fields = match &projection {
+                None => schema.fields().clone(),
+                Some(p) => p.iter().map(|i| schema.field(*i).clone()).collect(),
             };
+            let projected_schema = Arc::new(Schema::new(
+                fields
+                    .iter()
+                    .map(|field| project_column(field, nested_projection))
+                    .collect(),
+            ));
             return Ok(Arc::new(EmptyExec::new(false, projected_schema)));
         }
 
@@ -199,15 +323,25 @@ impl TableProvider for ListingTable {
                     file_groups: partitioned_file_lists,
                     statistics,
                     projection: projection.clone(),
+                    nested_projection: nested_projection.to_vec(),
                     batch_size,
                     limit,
                     table_partition_cols: self.options.table_partition_cols.clone(),
+                    bucket_columns: self
+                        .options
+                        .bucketing
+                        .as_ref()
+                        .map(|bucketing| bucketing.columns.clone()),
                 },
                 filters,
             )
             .await
     }
 
+    fn supports_nested_projection(&self) -> bool {
+        self.options.format.supports_nested_projection()
+    }
+
     fn supports_filter_pushdown(
         &self,
         filter: &Expr,
@@ -232,40 +366,59 @@ impl ListingTable {
         filters: &'a [Expr],
         limit: Option<usize>,
     ) -> Result<(Vec<Vec<PartitionedFile>>, Statistics)> {
-        // list files (with partitions)
-        let file_list = pruned_partition_list(
-            self.object_store.as_ref(),
-            &self.table_path,
-            filters,
-            &self.options.file_extension,
-            &self.options.table_partition_cols,
-        )
-        .await?;
+        // list files (with partitions) of all the paths of the table
+        let mut file_lists = Vec::with_capacity(self.table_paths.len());
+        for table_path in &self.table_paths {
+            file_lists.push(
+                pruned_partition_list(
+                    self.object_store.as_ref(),
+                    table_path,
+                    filters,
+                    &self.options.file_extension,
+                    &self.options.table_partition_cols,
+                )
+                .await?,
+            );
+        }
+        let file_list = stream::iter(file_lists).flatten();
 
-        // collect the statistics if required by the config
+        // collect the statistics if required by the config, of several files
+        // concurrently but in listing order, so that the collection stops at the
+        // same files once the limit is reached
         let object_store = Arc::clone(&self.object_store);
-        let files = file_list.then(move |part_file| {
-            let object_store = object_store.clone();
-            async move {
-                let part_file = part_file?;
-                let statistics = if self.options.collect_stat {
-                    let object_reader = object_store
-                        .file_reader(part_file.file_meta.sized_file.clone())?;
-                    self.options.format.infer_stats(object_reader).await?
-                } else {
-                    Statistics::default()
-                };
-                Ok((part_file, statistics)) as Result<(PartitionedFile, Statistics)>
-            }
-        });
+        let files = file_list
+            .map(move |part_file| {
+                let object_store = object_store.clone();
+                async move {
+                    let part_file = part_file?;
+                    let statistics = if self.options.collect_stat {
+                        let object_reader = object_store
+                            .file_reader(part_file.file_meta.sized_file.clone())?;
+                        self.options.format.infer_stats(object_reader).await?
+                    } else {
+                        Statistics::default()
+                    };
+                    Ok((part_file, statistics)) as Result<(PartitionedFile, Statistics)>
+                }
+            })
+            .buffered(self.options.stat_collection_concurrency.max(1));
 
         let (files, statistics) =
             get_statistics_with_limit(files, self.schema(), limit).await?;
 
-        Ok((
-            split_files(files, self.options.target_partitions),
-            statistics,
-        ))
+        let file_groups = if let Some(bucketing) = &self.options.bucketing {
+            split_files_by_bucket(files, bucketing.num_buckets)?
+        } else if self.options.format.is_splittable() {
+            split_files_by_range(
+                files,
+                self.options.target_partitions,
+                MIN_FILE_RANGE_SIZE,
+            )
+        } else {
+            split_files(files, self.options.target_partitions)
+        };
+
+        Ok((file_groups, statistics))
     }
 }
 
@@ -279,6 +432,7 @@ mod tests {
             object_store::local::LocalFileSystem,
         },
         logical_plan::{col, lit},
+        physical_plan::Partitioning,
         test::{columns, object_store::TestObjectStore},
     };
 
@@ -320,6 +474,40 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn load_table_stats_concurrently() -> Result<()> {
+        let testdata = crate::test_util::parquet_test_data();
+        let tmp_dir = tempfile::TempDir::new()?;
+        for i in 0..4 {
+            std::fs::copy(
+                format!("{}/alltypes_plain.parquet", testdata),
+                tmp_dir.path().join(format!("file{}.parquet", i)),
+            )?;
+        }
+        let path = tmp_dir.path().to_str().unwrap().to_owned();
+        let opt = ListingOptions {
+            stat_collection_concurrency: 2,
+            ..ListingOptions::new(Arc::new(ParquetFormat::default()))
+        };
+        let schema = opt
+            .infer_schema(Arc::new(LocalFileSystem {}), &path)
+            .await?;
+        let table = ListingTable::new(Arc::new(LocalFileSystem {}), path, schema, opt);
+
+        let (files, statistics) = table.list_files_for_scan(&[], None).await?;
+        assert_eq!(files.iter().map(|group| group.len()).sum::<usize>(), 4);
+        assert_eq!(statistics.num_rows, Some(32));
+        assert!(statistics.is_exact);
+
+        // only the statistics of the files required by the limit are collected
+        let (files, statistics) = table.list_files_for_scan(&[], Some(10)).await?;
+        assert_eq!(files.iter().map(|group| group.len()).sum::<usize>(), 2);
+        assert_eq!(statistics.num_rows, Some(16));
+        assert!(!statistics.is_exact);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn read_empty_table() -> Result<()> {
         let store = TestObjectStore::new_arc(&[("table/p1=v1/file.avro", 100)]);
@@ -330,6 +518,9 @@ mod tests {
             table_partition_cols: vec![String::from("p1")],
             target_partitions: 4,
             collect_stat: true,
+            bucketing: None,
+            metadata_cache_ttl: None,
+            stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
         };
 
         let file_schema = Schema::new(vec![Field::new("a", DataType::Boolean, false)]);
@@ -422,6 +613,50 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn bucketed_table_scan() -> Result<()> {
+        let mock_store = TestObjectStore::new_arc(&[
+            ("bucket/key-prefix/part-0.avro", 10),
+            ("bucket/key-prefix/part-2.avro", 10),
+            ("bucket/key-prefix/part-3.avro", 10),
+        ]);
+        let opt = ListingOptions {
+            bucketing: Some(Bucketing::new(vec!["b".to_owned()], 4)),
+            ..ListingOptions::new(Arc::new(AvroFormat {}))
+        };
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Boolean, false),
+            Field::new("b", DataType::Int32, false),
+        ]);
+        let table = ListingTable::new(
+            mock_store,
+            "bucket/key-prefix/".to_owned(),
+            Arc::new(schema),
+            opt,
+        );
+
+        let (file_list, _) = table.list_files_for_scan(&[], None).await?;
+        let group_sizes = file_list.iter().map(|g| g.len()).collect::<Vec<_>>();
+        assert_eq!(vec![1, 0, 1, 1], group_sizes);
+
+        let exec = table.scan(&Some(vec![1]), 1024, &[], None).await?;
+        match exec.output_partitioning() {
+            Partitioning::Hash(exprs, 4) => {
+                assert_eq!(1, exprs.len());
+                assert_eq!("b@0", exprs[0].to_string());
+            }
+            other => panic!("unexpected partitioning {:?}", other),
+        }
+
+        // without the bucket column, the partitioning of the scan is unknown
+        let exec = table.scan(&Some(vec![0]), 1024, &[], None).await?;
+        assert!(matches!(
+            exec.output_partitioning(),
+            Partitioning::UnknownPartitioning(4)
+        ));
+        Ok(())
+    }
+
     async fn load_table(name: &str) -> Result<Arc<dyn TableProvider>> {
         let testdata = crate::test_util::parquet_test_data();
         let filename = format!("{}/{}", testdata, name);
@@ -431,6 +666,9 @@ mod tests {
             table_partition_cols: vec![],
             target_partitions: 2,
             collect_stat: true,
+            bucketing: None,
+            metadata_cache_ttl: None,
+            stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
         };
         // here we resolve the schema locally
         let schema = opt
@@ -461,6 +699,9 @@ mod tests {
             table_partition_cols: vec![],
             target_partitions,
             collect_stat: true,
+            bucketing: None,
+            metadata_cache_ttl: None,
+            stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
         };
 
         let schema = Schema::new(vec![Field::new("a", DataType::Boolean, false)]);
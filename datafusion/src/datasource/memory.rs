@@ -30,9 +30,9 @@ use async_trait::async_trait;
 use crate::datasource::TableProvider;
 use crate::error::{DataFusionError, Result};
 use crate::logical_plan::Expr;
-use crate::physical_plan::common;
 use crate::physical_plan::memory::MemoryExec;
 use crate::physical_plan::ExecutionPlan;
+use crate::physical_plan::{cancellation, common};
 use crate::physical_plan::{repartition::RepartitionExec, Partitioning};
 
 /// In-memory table
@@ -73,7 +73,7 @@ impl MemTable {
         let tasks = (0..partition_count)
             .map(|part_i| {
                 let exec = exec.clone();
-                tokio::spawn(async move {
+                cancellation::spawn(async move {
                     let stream = exec.execute(part_i).await?;
                     common::collect(stream).await
                 })
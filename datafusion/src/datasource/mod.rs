@@ -20,9 +20,12 @@
 pub mod datasource;
 pub mod empty;
 pub mod file_format;
+pub mod generate_series;
 pub mod listing;
 pub mod memory;
+pub mod nested_projection;
 pub mod object_store;
+pub mod streaming;
 
 use futures::Stream;
 
@@ -40,7 +43,8 @@ use std::pin::Pin;
 /// Get all files as well as the file level summary statistics (no statistic for partition columns).
 /// If the optional `limit` is provided, includes only sufficient files.
 /// Needed to read up to `limit` number of rows.
-/// TODO fix case where `num_rows` and `total_byte_size` are not defined (stat should be None instead of Some(0))
+/// The `num_rows` and `total_byte_size` statistics are only defined if they are
+/// known for every file.
 pub async fn get_statistics_with_limit(
     all_files: impl Stream<Item = Result<(PartitionedFile, Statistics)>>,
     file_schema: SchemaRef,
@@ -54,6 +58,8 @@ pub async fn get_statistics_with_limit(
     let (mut max_values, mut min_values) = create_max_min_accs(&file_schema);
 
     let mut num_rows = 0;
+    let mut has_num_rows = true;
+    let mut has_total_byte_size = true;
     let mut is_exact = true;
     // fusing the stream allows us to call next safely even once it is finished
     let mut all_files = Box::pin(all_files.fuse());
@@ -61,6 +67,8 @@ pub async fn get_statistics_with_limit(
         let (file, file_stats) = res?;
         result_files.push(file);
         is_exact &= file_stats.is_exact;
+        has_num_rows &= file_stats.num_rows.is_some();
+        has_total_byte_size &= file_stats.total_byte_size.is_some();
         num_rows += file_stats.num_rows.unwrap_or(0);
         total_byte_size += file_stats.total_byte_size.unwrap_or(0);
         if let Some(vec) = &file_stats.column_statistics {
@@ -114,8 +122,16 @@ pub async fn get_statistics_with_limit(
     };
 
     let statistics = Statistics {
-        num_rows: Some(num_rows as usize),
-        total_byte_size: Some(total_byte_size as usize),
+        num_rows: if has_num_rows {
+            Some(num_rows as usize)
+        } else {
+            None
+        },
+        total_byte_size: if has_total_byte_size {
+            Some(total_byte_size as usize)
+        } else {
+            None
+        },
         column_statistics: column_stats,
         is_exact,
     };
@@ -131,7 +147,30 @@ pub struct PartitionedFile {
     pub file_meta: FileMeta,
     /// Values of partition columns to be appended to each row
     pub partition_values: Vec<ScalarValue>,
-    // We may include row group range here for a more fine-grained parallel execution
+    /// An optional byte range of the file that should be read. When set,
+    /// only the records that start within this range are scanned, which
+    /// allows a single large file to be read by several partitions.
+    pub range: Option<FileRange>,
+}
+
+/// A byte range `[start, end)` within a file.
+///
+/// For line delimited formats (CSV, JSON) the range reads all the lines
+/// that start inside of it. For Parquet it reads all the row groups whose
+/// first page starts inside of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FileRange {
+    /// Offset of the first byte of the range
+    pub start: u64,
+    /// Offset of the first byte after the range
+    pub end: u64,
+}
+
+impl FileRange {
+    /// Returns true if `offset` falls within this range
+    pub fn contains(&self, offset: u64) -> bool {
+        offset >= self.start && offset < self.end
+    }
 }
 
 impl PartitionedFile {
@@ -143,6 +182,15 @@ impl PartitionedFile {
                 last_modified: None,
             },
             partition_values: vec![],
+            range: None,
+        }
+    }
+
+    /// Create a file that only reads the bytes of `[start, end)`
+    pub fn new_with_range(path: String, size: u64, start: u64, end: u64) -> Self {
+        Self {
+            range: Some(FileRange { start, end }),
+            ..Self::new(path, size)
         }
     }
 }
@@ -153,7 +201,14 @@ pub type PartitionedFileStream =
 
 impl std::fmt::Display for PartitionedFile {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.file_meta)
+        match &self.range {
+            Some(range) => write!(
+                f,
+                "{} (range: {}..{})",
+                self.file_meta, range.start, range.end
+            ),
+            None => write!(f, "{}", self.file_meta),
+        }
     }
 }
 
@@ -0,0 +1,228 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Nested projections, so that the scans of struct columns only read the fields
+//! that queries use.
+//!
+//! A nested projection is a list of paths of field names, each starting with the
+//! name of a column, e.g. `["s", "a", "b"]` for the field `b` of the struct field
+//! `a` of the struct column `s`. A struct column is projected to the fields on the
+//! paths starting with its name, and the columns without any path are read
+//! entirely. The paths to fields that do not exist are ignored, like the columns
+//! of a projection that are not in the schema.
+
+use std::sync::Arc;
+
+use arrow::array::{make_array, Array, ArrayData, ArrayRef, StructArray};
+use arrow::datatypes::{DataType, Field, Schema};
+
+use crate::error::{DataFusionError, Result};
+
+/// Projects the struct columns of `schema` to the fields of `nested_projection`
+pub fn project_schema(schema: &Schema, nested_projection: &[Vec<String>]) -> Schema {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| project_column(field, nested_projection))
+        .collect();
+    Schema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+/// Projects the column `field` to its fields on the paths of `nested_projection`
+pub fn project_column(field: &Field, nested_projection: &[Vec<String>]) -> Field {
+    project_field(field, &column_paths(field.name(), nested_projection))
+}
+
+/// The paths of `nested_projection` starting with the column `name`, without it
+fn column_paths<'a>(
+    name: &str,
+    nested_projection: &'a [Vec<String>],
+) -> Vec<&'a [String]> {
+    nested_projection
+        .iter()
+        .filter(|path| path.first().map(|first| first == name).unwrap_or(false))
+        .map(|path| &path[1..])
+        .collect()
+}
+
+/// Projects `field` to the nested fields on `paths`, relative to the field. The
+/// field is kept entirely if there is no path, or an empty one.
+pub fn project_field(field: &Field, paths: &[&[String]]) -> Field {
+    if paths.is_empty() || paths.iter().any(|path| path.is_empty()) {
+        return field.clone();
+    }
+    let children = match field.data_type() {
+        DataType::Struct(children) => children,
+        // only the fields of structs can be projected
+        _ => return field.clone(),
+    };
+    let children = children
+        .iter()
+        .filter_map(|child| {
+            let child_paths: Vec<_> = paths
+                .iter()
+                .filter(|path| &path[0] == child.name())
+                .map(|path| &path[1..])
+                .collect();
+            if child_paths.is_empty() {
+                None
+            } else {
+                Some(project_field(child, &child_paths))
+            }
+        })
+        .collect();
+    let mut projected = Field::new(
+        field.name(),
+        DataType::Struct(children),
+        field.is_nullable(),
+    );
+    projected.set_metadata(field.metadata().clone());
+    projected
+}
+
+/// Projects the struct arrays of `array` to the fields of `data_type`, which is
+/// `array`'s data type projected with [`project_field`]
+pub fn project_array(array: &ArrayRef, data_type: &DataType) -> Result<ArrayRef> {
+    if array.data_type() == data_type {
+        return Ok(array.clone());
+    }
+    let (fields, struct_array) =
+        match (data_type, array.as_any().downcast_ref::<StructArray>()) {
+            (DataType::Struct(fields), Some(struct_array)) => (fields, struct_array),
+            _ => {
+                return Err(DataFusionError::Internal(format!(
+                    "Cannot project an array of type {:?} to {:?}",
+                    array.data_type(),
+                    data_type
+                )))
+            }
+        };
+    let children = fields
+        .iter()
+        .map(|field| {
+            let child = struct_array.column_by_name(field.name()).ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "Struct array has no field named {}",
+                    field.name()
+                ))
+            })?;
+            Ok(project_array(child, field.data_type())?.data().clone())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let builder = ArrayData::builder(data_type.clone())
+        .len(array.len())
+        .offset(array.offset())
+        .child_data(children);
+    let builder = match array.data().null_buffer() {
+        Some(nulls) => builder.null_bit_buffer(nulls.clone()),
+        None => builder,
+    };
+    Ok(make_array(builder.build()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+
+    fn paths(paths: &[&str]) -> Vec<Vec<String>> {
+        paths
+            .iter()
+            .map(|path| path.split('.').map(|name| name.to_owned()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn project_nested_fields() {
+        let a = DataType::Struct(vec![
+            Field::new("b", DataType::Int32, true),
+            Field::new("c", DataType::Utf8, true),
+        ]);
+        let schema = Schema::new(vec![
+            Field::new("i", DataType::Int32, false),
+            Field::new(
+                "s",
+                DataType::Struct(vec![
+                    Field::new("a", a.clone(), true),
+                    Field::new("d", DataType::Int32, true),
+                ]),
+                true,
+            ),
+        ]);
+
+        let projected = project_schema(&schema, &paths(&["s.a.b"]));
+        let expected = Schema::new(vec![
+            Field::new("i", DataType::Int32, false),
+            Field::new(
+                "s",
+                DataType::Struct(vec![Field::new(
+                    "a",
+                    DataType::Struct(vec![Field::new("b", DataType::Int32, true)]),
+                    true,
+                )]),
+                true,
+            ),
+        ]);
+        assert_eq!(expected, projected);
+
+        // a whole field along with one of its fields
+        let projected = project_schema(&schema, &paths(&["s.a", "s.a.b"]));
+        let expected = DataType::Struct(vec![Field::new("a", a, true)]);
+        assert_eq!(&expected, projected.field(1).data_type());
+
+        // the whole column
+        assert_eq!(schema, project_schema(&schema, &paths(&["s", "s.a.b"])));
+        // unknown fields are ignored
+        let projected = project_schema(&schema, &paths(&["s.d", "s.x"]));
+        let expected = DataType::Struct(vec![Field::new("d", DataType::Int32, true)]);
+        assert_eq!(&expected, projected.field(1).data_type());
+    }
+
+    #[test]
+    fn project_struct_array() -> Result<()> {
+        let a: ArrayRef = Arc::new(StructArray::from(vec![
+            (
+                Field::new("b", DataType::Int32, true),
+                Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+            ),
+            (
+                Field::new("c", DataType::Utf8, true),
+                Arc::new(StringArray::from(vec!["x", "y", "z"])) as ArrayRef,
+            ),
+        ]));
+        let s: ArrayRef = Arc::new(StructArray::from(vec![(
+            Field::new("a", a.data_type().clone(), true),
+            a,
+        )]));
+
+        let field = Field::new("s", s.data_type().clone(), true);
+        let path = vec!["a".to_owned(), "c".to_owned()];
+        let projected_field = project_field(&field, &[path.as_slice()]);
+        let projected = project_array(&s, projected_field.data_type())?;
+        assert_eq!(projected_field.data_type(), projected.data_type());
+
+        let projected = projected.as_any().downcast_ref::<StructArray>().unwrap();
+        let a = projected
+            .column(0)
+            .as_any()
+            .downcast_ref::<StructArray>()
+            .unwrap();
+        let c = a.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(vec!["x", "y", "z"], c.iter().flatten().collect::<Vec<_>>());
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Object store caching the file listings and the footers of the files of another
+//! object store, such as the Parquet metadata that the schemas and statistics of the
+//! files are read from, so that repeated queries over the same files don't list them
+//! and read their footers again.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{stream, AsyncRead, TryStreamExt};
+use lazy_static::lazy_static;
+
+use super::{
+    FileMeta, FileMetaStream, ListEntryStream, ObjectReader, ObjectStore, SizedFile,
+};
+use crate::error::Result;
+
+/// Reads of the last bytes of the files up to this size are cached, which includes
+/// the footers of Parquet files unless their metadata is larger
+pub const MAX_CACHED_FOOTER_SIZE: usize = 1024 * 1024;
+
+/// Number of cached footers above which the expired ones are evicted
+const MAX_CACHED_FOOTERS: usize = 10_000;
+
+lazy_static! {
+    static ref METADATA_CACHE: MetadataCache = MetadataCache::default();
+}
+
+/// Footer of a file, read from `start` to the end of the file. The files are identified
+/// by their path, size and, if they were listed, last modification time, so that the
+/// footers of files written again are not read from the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FooterKey {
+    path: String,
+    size: u64,
+    last_modified: Option<DateTime<Utc>>,
+    start: u64,
+    length: usize,
+}
+
+/// Cache of file listings and footers shared by the caching object stores of a
+/// process. The entries are keyed by the paths of the files, which are assumed to be
+/// unique across the object stores.
+#[derive(Debug, Default)]
+struct MetadataCache {
+    listings: Mutex<HashMap<String, (Instant, Vec<FileMeta>)>>,
+    /// The last modification time of the listed files, by path
+    last_modified: Mutex<HashMap<String, DateTime<Utc>>>,
+    footers: Mutex<HashMap<FooterKey, (Instant, Arc<Vec<u8>>)>>,
+}
+
+impl MetadataCache {
+    fn listing(&self, prefix: &str, ttl: Duration) -> Option<Vec<FileMeta>> {
+        let listings = self.listings.lock().unwrap();
+        match listings.get(prefix) {
+            Some((cached_at, files)) if cached_at.elapsed() < ttl => Some(files.clone()),
+            _ => None,
+        }
+    }
+
+    fn insert_listing(&self, prefix: &str, files: Vec<FileMeta>) {
+        let mut last_modified = self.last_modified.lock().unwrap();
+        for file in &files {
+            if let Some(modified) = file.last_modified {
+                last_modified.insert(file.path().to_owned(), modified);
+            }
+        }
+        let mut listings = self.listings.lock().unwrap();
+        listings.insert(prefix.to_owned(), (Instant::now(), files));
+    }
+
+    fn footer_key(&self, file: &SizedFile, start: u64, length: usize) -> FooterKey {
+        FooterKey {
+            path: file.path.clone(),
+            size: file.size,
+            last_modified: self.last_modified.lock().unwrap().get(&file.path).cloned(),
+            start,
+            length,
+        }
+    }
+
+    fn footer(&self, key: &FooterKey, ttl: Duration) -> Option<Arc<Vec<u8>>> {
+        let footers = self.footers.lock().unwrap();
+        match footers.get(key) {
+            Some((cached_at, footer)) if cached_at.elapsed() < ttl => {
+                Some(footer.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn insert_footer(&self, key: FooterKey, footer: Arc<Vec<u8>>, ttl: Duration) {
+        let mut footers = self.footers.lock().unwrap();
+        if footers.len() >= MAX_CACHED_FOOTERS {
+            footers.retain(|_, (cached_at, _)| cached_at.elapsed() < ttl);
+        }
+        footers.insert(key, (Instant::now(), footer));
+    }
+}
+
+/// Object store caching the file listings and footers of the files of another object
+/// store for `ttl`, in a cache shared by all the caching object stores of the process
+#[derive(Debug)]
+pub struct CachingObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    ttl: Duration,
+}
+
+impl CachingObjectStore {
+    /// Create an object store caching the metadata of the files of `inner` for `ttl`
+    pub fn new(inner: Arc<dyn ObjectStore>, ttl: Duration) -> Self {
+        Self { inner, ttl }
+    }
+
+    /// The object store whose file metadata are cached
+    pub fn inner(&self) -> &Arc<dyn ObjectStore> {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl ObjectStore for CachingObjectStore {
+    async fn list_file(&self, prefix: &str) -> Result<FileMetaStream> {
+        let files = match METADATA_CACHE.listing(prefix, self.ttl) {
+            Some(files) => files,
+            None => {
+                let files: Vec<FileMeta> =
+                    self.inner.list_file(prefix).await?.try_collect().await?;
+                METADATA_CACHE.insert_listing(prefix, files.clone());
+                files
+            }
+        };
+        Ok(Box::pin(stream::iter(files.into_iter().map(Ok))))
+    }
+
+    async fn list_dir(
+        &self,
+        prefix: &str,
+        delimiter: Option<String>,
+    ) -> Result<ListEntryStream> {
+        self.inner.list_dir(prefix, delimiter).await
+    }
+
+    fn file_reader(&self, file: SizedFile) -> Result<Arc<dyn ObjectReader>> {
+        Ok(Arc::new(CachingObjectReader {
+            inner: self.inner.file_reader(file.clone())?,
+            file,
+            ttl: self.ttl,
+        }))
+    }
+
+    fn metadata_cache_ttl(&self) -> Option<Duration> {
+        Some(self.ttl)
+    }
+}
+
+struct CachingObjectReader {
+    inner: Arc<dyn ObjectReader>,
+    file: SizedFile,
+    ttl: Duration,
+}
+
+#[async_trait]
+impl ObjectReader for CachingObjectReader {
+    async fn chunk_reader(
+        &self,
+        start: u64,
+        length: usize,
+    ) -> Result<Box<dyn AsyncRead>> {
+        self.inner.chunk_reader(start, length).await
+    }
+
+    fn sync_chunk_reader(
+        &self,
+        start: u64,
+        length: usize,
+    ) -> Result<Box<dyn Read + Send + Sync>> {
+        let in_footer = length <= MAX_CACHED_FOOTER_SIZE
+            && start + MAX_CACHED_FOOTER_SIZE as u64 >= self.file.size;
+        if !in_footer {
+            return self.inner.sync_chunk_reader(start, length);
+        }
+        let key = METADATA_CACHE.footer_key(&self.file, start, length);
+        let footer = match METADATA_CACHE.footer(&key, self.ttl) {
+            Some(footer) => footer,
+            None => {
+                let mut footer = Vec::with_capacity(length);
+                self.inner
+                    .sync_chunk_reader(start, length)?
+                    .read_to_end(&mut footer)?;
+                let footer = Arc::new(footer);
+                METADATA_CACHE.insert_footer(key, footer.clone(), self.ttl);
+                footer
+            }
+        };
+        Ok(Box::new(Cursor::new(footer.as_ref().clone())))
+    }
+
+    fn length(&self) -> u64 {
+        self.inner.length()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::object_store::local::LocalFileSystem;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    async fn list(store: &dyn ObjectStore, prefix: &str) -> Result<Vec<String>> {
+        let mut paths: Vec<_> = store
+            .list_file(prefix)
+            .await?
+            .map_ok(|file| file.path().to_owned())
+            .try_collect()
+            .await?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn read_footer(store: &dyn ObjectStore, path: &str) -> Result<String> {
+        let size = std::fs::metadata(path)?.len();
+        let reader = store.file_reader(SizedFile {
+            path: path.to_owned(),
+            size,
+        })?;
+        let mut footer = String::new();
+        reader
+            .sync_chunk_reader(size - 3, 3)?
+            .read_to_string(&mut footer)?;
+        Ok(footer)
+    }
+
+    #[tokio::test]
+    async fn cache_listings_and_footers() -> Result<()> {
+        let dir = TempDir::new()?;
+        let prefix = dir.path().to_str().unwrap();
+        let path = format!("{}/a.csv", prefix);
+        write!(std::fs::File::create(&path)?, "a\n1\n2")?;
+
+        let store = CachingObjectStore::new(
+            Arc::new(LocalFileSystem {}),
+            Duration::from_secs(3600),
+        );
+        assert_eq!(vec![path.clone()], list(&store, prefix).await?);
+        assert_eq!("1\n2", read_footer(&store, &path)?);
+
+        // the cached listing and footer are returned until they expire
+        std::fs::File::create(format!("{}/b.csv", prefix))?;
+        let mut file = std::fs::OpenOptions::new().write(true).open(&path)?;
+        write!(file, "a\n3\n4")?;
+        assert_eq!(vec![path.clone()], list(&store, prefix).await?);
+        assert_eq!("1\n2", read_footer(&store, &path)?);
+
+        let store = CachingObjectStore::new(Arc::new(LocalFileSystem {}), Duration::ZERO);
+        assert_eq!(2, list(&store, prefix).await?.len());
+        assert_eq!("3\n4", read_footer(&store, &path)?);
+        Ok(())
+    }
+}
@@ -17,6 +17,7 @@
 
 //! Object Store abstracts access to an underlying file/object storage.
 
+pub mod caching;
 pub mod local;
 
 use std::collections::HashMap;
@@ -24,6 +25,7 @@ use std::fmt::{self, Debug};
 use std::io::Read;
 use std::pin::Pin;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -158,6 +160,12 @@ pub trait ObjectStore: Sync + Send + Debug {
 
     /// Get object reader for one file
     fn file_reader(&self, file: SizedFile) -> Result<Arc<dyn ObjectReader>>;
+
+    /// How long the file listings and footers are cached by this store, if it
+    /// caches them (see [`caching::CachingObjectStore`])
+    fn metadata_cache_ttl(&self) -> Option<Duration> {
+        None
+    }
 }
 
 static LOCAL_SCHEME: &str = "file";
@@ -0,0 +1,230 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Data source for presenting an unbounded stream of record batches, e.g. records
+//! consumed from a message queue or CSV data read from stdin, as a table that can be
+//! queried by DataFusion. The stream is consumed by the first query that scans it.
+
+use std::any::Any;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+use arrow::csv;
+use arrow::datatypes::SchemaRef;
+use async_trait::async_trait;
+
+#[cfg(feature = "avro")]
+use crate::avro_to_arrow::RegistryAvroDecoder;
+use crate::datasource::TableProvider;
+use crate::error::Result;
+use crate::execution::io_runtime::spawn_io;
+use crate::logical_plan::Expr;
+use crate::physical_plan::expressions::Column;
+use crate::physical_plan::projection::ProjectionExec;
+use crate::physical_plan::stream::RecordBatchReceiverStream;
+use crate::physical_plan::streaming::StreamingExec;
+use crate::physical_plan::{ExecutionPlan, PhysicalExpr, SendableRecordBatchStream};
+
+/// Table over a stream of record batches
+pub struct StreamingTable {
+    schema: SchemaRef,
+    stream: Arc<Mutex<Option<SendableRecordBatchStream>>>,
+}
+
+impl StreamingTable {
+    /// Create a new table from the provided stream of record batches
+    pub fn new(stream: SendableRecordBatchStream) -> Self {
+        Self {
+            schema: stream.schema(),
+            stream: Arc::new(Mutex::new(Some(stream))),
+        }
+    }
+
+    /// Create a new table from CSV data that is incrementally read from `reader`, e.g.
+    /// `std::io::stdin()`. The reader is consumed on a blocking thread of the tokio
+    /// runtime, so this must be called from within a runtime.
+    pub fn csv<R: Read + Send + 'static>(
+        reader: R,
+        schema: SchemaRef,
+        has_header: bool,
+        delimiter: u8,
+        batch_size: usize,
+    ) -> Self {
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        let csv_reader = csv::Reader::new(
+            reader,
+            schema.clone(),
+            has_header,
+            Some(delimiter),
+            batch_size,
+            None,
+            None,
+        );
+        let join_handle = spawn_io(move || {
+            for batch in csv_reader {
+                if tx.blocking_send(batch).is_err() {
+                    // the receiver has been dropped
+                    break;
+                }
+            }
+        });
+        Self::new(RecordBatchReceiverStream::create(&schema, rx, join_handle))
+    }
+
+    /// Create a new table from Avro `messages` prefixed with the id of the schema they
+    /// are written with in a schema registry, e.g. the values of the messages consumed
+    /// from a Kafka topic. The messages are decoded by `decoder` in batches of up to
+    /// `batch_size` messages on a blocking thread of the tokio runtime, so this must be
+    /// called from within a runtime.
+    #[cfg(feature = "avro")]
+    pub fn avro<I>(
+        messages: I,
+        decoder: Arc<RegistryAvroDecoder>,
+        batch_size: usize,
+    ) -> Self
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+        I::IntoIter: Send + 'static,
+    {
+        let schema = decoder.schema();
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        let mut messages = messages.into_iter();
+        let join_handle = spawn_io(move || loop {
+            let messages: Vec<_> = messages.by_ref().take(batch_size.max(1)).collect();
+            if messages.is_empty() {
+                break;
+            }
+            let batch = decoder
+                .decode(&messages)
+                .map_err(|e| arrow::error::ArrowError::ExternalError(Box::new(e)));
+            if tx.blocking_send(batch).is_err() {
+                // the receiver has been dropped
+                break;
+            }
+        });
+        Self::new(RecordBatchReceiverStream::create(&schema, rx, join_handle))
+    }
+}
+
+#[async_trait]
+impl TableProvider for StreamingTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        _batch_size: usize,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let exec = Arc::new(StreamingExec::new(self.schema(), self.stream.clone()));
+        match projection {
+            None => Ok(exec),
+            Some(columns) => {
+                let expr = columns
+                    .iter()
+                    .map(|i| {
+                        let name = self.schema.field(*i).name();
+                        let column: Arc<dyn PhysicalExpr> =
+                            Arc::new(Column::new(name, *i));
+                        (column, name.to_owned())
+                    })
+                    .collect();
+                Ok(Arc::new(ProjectionExec::try_new(expr, exec)?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::collect;
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn scan_csv_stream() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Int64, false),
+        ]));
+        let data = Cursor::new(b"a,b\n1,2\n3,4\n5,6\n".to_vec());
+        let table = StreamingTable::csv(data, schema, true, b',', 2);
+
+        let exec = table.scan(&Some(vec![1]), 1024, &[], None).await?;
+        let batches = collect(exec).await?;
+        assert_eq!(2, batches.len());
+        let values: Vec<i64> = batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column(0);
+                let column = column.as_any().downcast_ref::<Int64Array>().unwrap();
+                column.values().to_vec()
+            })
+            .collect();
+        assert_eq!(vec![2, 4, 6], values);
+
+        // the stream has been consumed by the first scan
+        let exec = table.scan(&None, 1024, &[], None).await?;
+        assert!(collect(exec).await.is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "avro")]
+    #[tokio::test]
+    async fn scan_avro_stream() -> Result<()> {
+        use crate::avro_to_arrow::SchemaRegistry;
+        use avro_rs::{to_avro_datum, types::Value, Schema as AvroSchema};
+
+        const SCHEMA: &str = r#"{"type": "record", "name": "event", "fields": [
+            {"name": "a", "type": "long"}
+        ]}"#;
+        struct Registry;
+        impl SchemaRegistry for Registry {
+            fn schema(&self, _id: u32) -> Result<String> {
+                Ok(SCHEMA.to_owned())
+            }
+        }
+
+        let avro_schema = AvroSchema::parse_str(SCHEMA).unwrap();
+        let messages: Vec<_> = (1..=5)
+            .map(|a| {
+                let record = Value::Record(vec![("a".to_owned(), Value::Long(a))]);
+                let mut message = vec![0, 0, 0, 0, 1];
+                message.extend(to_avro_datum(&avro_schema, record).unwrap());
+                message
+            })
+            .collect();
+        let decoder = Arc::new(RegistryAvroDecoder::try_new(Arc::new(Registry), SCHEMA)?);
+        let table = StreamingTable::avro(messages, decoder, 2);
+
+        let batches = collect(table.scan(&None, 1024, &[], None).await?).await?;
+        assert_eq!(
+            vec![2, 2, 1],
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
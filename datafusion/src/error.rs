@@ -61,6 +61,22 @@ pub enum DataFusionError {
     /// Error returned during execution of the query.
     /// Examples include files not found, errors in parsing certain types.
     Execution(String),
+    /// Error returned when the principal running a query is not allowed to
+    /// access one of the tables referenced by the query.
+    PermissionDenied {
+        /// The principal running the query, if it was authenticated
+        principal: Option<String>,
+        /// The action that was denied, such as `SELECT`
+        action: String,
+        /// The fully qualified name of the table
+        table: String,
+    },
+    /// Error returned by the operators of a query once the query is cancelled,
+    /// see [`CancellationToken`](crate::physical_plan::cancellation::CancellationToken)
+    Cancelled,
+    /// Error returned when a query uses more memory than its limit, see
+    /// [`MemoryManager`](crate::execution::memory_manager::MemoryManager)
+    ResourcesExhausted(String),
 }
 
 impl DataFusionError {
@@ -129,6 +145,26 @@ impl Display for DataFusionError {
             DataFusionError::Execution(ref desc) => {
                 write!(f, "Execution error: {}", desc)
             }
+            DataFusionError::PermissionDenied {
+                ref principal,
+                ref action,
+                ref table,
+            } => match principal {
+                Some(principal) => write!(
+                    f,
+                    "Permission denied: '{}' may not {} from table '{}'",
+                    principal, action, table
+                ),
+                None => write!(
+                    f,
+                    "Permission denied: anonymous users may not {} from table '{}'",
+                    action, table
+                ),
+            },
+            DataFusionError::Cancelled => write!(f, "The query was cancelled"),
+            DataFusionError::ResourcesExhausted(ref desc) => {
+                write!(f, "Resources exhausted: {}", desc)
+            }
         }
     }
 }
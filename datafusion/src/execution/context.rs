@@ -18,10 +18,14 @@
 //! ExecutionContext contains methods for registering data sources and executing queries
 use crate::{
     catalog::{
+        authorization::TableAuthorizer,
         catalog::{CatalogList, MemoryCatalogList},
         information_schema::CatalogWithInformationSchema,
+        policy::PolicyRegistry,
+    },
+    datasource::listing::{
+        ListingOptions, ListingTable, DEFAULT_STAT_COLLECTION_CONCURRENCY,
     },
-    datasource::listing::{ListingOptions, ListingTable},
     datasource::{
         file_format::{
             avro::AvroFormat,
@@ -39,33 +43,34 @@ use crate::{
     },
 };
 use log::debug;
-use std::fs;
 use std::path::Path;
 use std::string::String;
 use std::sync::Arc;
+use std::time::Duration;
 use std::{
     collections::{HashMap, HashSet},
     sync::Mutex,
 };
 
-use futures::{StreamExt, TryStreamExt};
-use tokio::task::{self, JoinHandle};
+use futures::{future::BoxFuture, FutureExt};
 
-use arrow::{csv, datatypes::SchemaRef};
+use arrow::datatypes::SchemaRef;
 
 use crate::catalog::{
     catalog::{CatalogProvider, MemoryCatalogProvider},
     schema::{MemorySchemaProvider, SchemaProvider},
     ResolvedTableReference, TableReference,
 };
+use crate::datasource::generate_series::{generate_series_function, range_function};
 use crate::datasource::object_store::{ObjectStore, ObjectStoreRegistry};
 use crate::datasource::TableProvider;
 use crate::error::{DataFusionError, Result};
 use crate::execution::dataframe_impl::DataFrameImpl;
 use crate::logical_plan::{
-    CreateExternalTable, CreateMemoryTable, DropTable, FunctionRegistry, LogicalPlan,
-    LogicalPlanBuilder, UNNAMED_TABLE,
+    CreateExternalTable, CreateMemoryTable, DropTable, Expr, FunctionRegistry,
+    InsertInto, LogicalPlan, LogicalPlanBuilder, WriteMode, UNNAMED_TABLE,
 };
+use crate::optimizer::aggregate_push_down::AggregatePushDown;
 use crate::optimizer::common_subexpr_eliminate::CommonSubexprEliminate;
 use crate::optimizer::filter_push_down::FilterPushDown;
 use crate::optimizer::limit_push_down::LimitPushDown;
@@ -73,27 +78,39 @@ use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::projection_push_down::ProjectionPushDown;
 use crate::optimizer::simplify_expressions::SimplifyExpressions;
 use crate::physical_optimizer::coalesce_batches::CoalesceBatches;
+use crate::physical_optimizer::deterministic::DeterministicExecution;
+use crate::physical_optimizer::jit::JitCompilation;
 use crate::physical_optimizer::merge_exec::AddCoalescePartitionsExec;
 use crate::physical_optimizer::repartition::Repartition;
 
 use crate::logical_plan::plan::Explain;
 use crate::optimizer::single_distinct_to_groupby::SingleDistinctToGroupBy;
+use crate::physical_plan::hash_aggregate::SkipPartialAggregation;
 use crate::physical_plan::planner::DefaultPhysicalPlanner;
 use crate::physical_plan::udf::ScalarUDF;
-use crate::physical_plan::ExecutionPlan;
+use crate::physical_plan::udtf::TableUDF;
 use crate::physical_plan::PhysicalPlanner;
+use crate::physical_plan::{collect_partitioned, ExecutionPlan};
+use crate::scalar::ScalarValue;
 use crate::sql::{
-    parser::{DFParser, FileType},
-    planner::{ContextProvider, SqlToRel},
+    parser::{
+        CustomStatement, DFParser, FileType, Statement as DFStatement, StatementParser,
+    },
+    planner::{ContextProvider, IdentifierNormalization, SqlToRel},
+    subquery::{collect_subqueries, replace_subqueries, scalar_to_sql},
 };
 use crate::variable::{VarProvider, VarType};
 use crate::{dataframe::DataFrame, physical_plan::udaf::AggregateUDF};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use parquet::arrow::ArrowWriter;
 use parquet::file::properties::WriterProperties;
+use sqlparser::ast::{Expr as SQLExpr, Query, Statement as SQLStatement};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::{Parser, ParserError};
 
+use super::io_runtime;
 use super::options::{AvroReadOptions, CsvReadOptions};
+use super::write::{self, WriteFormat};
 
 /// ExecutionContext is the main interface for executing queries with DataFusion. The context
 /// provides the following functionality:
@@ -151,6 +168,10 @@ impl ExecutionContext {
     pub fn with_config(config: ExecutionConfig) -> Self {
         let catalog_list = Arc::new(MemoryCatalogList::new()) as Arc<dyn CatalogList>;
 
+        if let Some(io_threads) = config.io_threads {
+            io_runtime::set_io_threads(io_threads);
+        }
+
         if config.create_default_catalog_and_schema {
             let default_catalog = MemoryCatalogProvider::new();
 
@@ -178,9 +199,17 @@ impl ExecutionContext {
                 scalar_functions: HashMap::new(),
                 var_provider: HashMap::new(),
                 aggregate_functions: HashMap::new(),
+                table_functions: [
+                    range_function(config.target_partitions),
+                    generate_series_function(config.target_partitions),
+                ]
+                .into_iter()
+                .map(|f| (f.name.clone(), Arc::new(f)))
+                .collect(),
                 config,
                 execution_props: ExecutionProps::new(),
                 object_store_registry: Arc::new(ObjectStoreRegistry::new()),
+                policy_registry: Arc::new(PolicyRegistry::new()),
             })),
         }
     }
@@ -190,7 +219,16 @@ impl ExecutionContext {
     /// This method is `async` because queries of type `CREATE EXTERNAL TABLE`
     /// might require the schema to be inferred.
     pub async fn sql(&mut self, sql: &str) -> Result<Arc<dyn DataFrame>> {
-        let plan = self.create_logical_plan(sql)?;
+        let mut statement = self.parse_statement(sql)?;
+        if let DFStatement::Custom(statement) = &statement {
+            let plan = self.custom_statement_to_plan(statement.as_ref()).await?;
+            return Ok(Arc::new(DataFrameImpl::new(
+                self.state.clone(),
+                &self.optimize(&plan)?,
+            )));
+        }
+        self.fold_subqueries(&mut statement).await?;
+        let plan = self.statement_to_plan(&statement)?;
         match plan {
             LogicalPlan::CreateExternalTable(CreateExternalTable {
                 ref schema,
@@ -198,6 +236,7 @@ impl ExecutionContext {
                 ref location,
                 ref file_type,
                 ref has_header,
+                ref bucketing,
             }) => {
                 let file_format = match file_type {
                     FileType::CSV => {
@@ -218,7 +257,9 @@ impl ExecutionContext {
 
                 let options = ListingOptions {
                     format: file_format,
-                    collect_stat: false,
+                    // the statistics of Parquet files are read from their footers, and
+                    // e.g. answer `COUNT(*)` without scanning the files
+                    collect_stat: matches!(file_type, FileType::Parquet),
                     file_extension: String::new(),
                     target_partitions: self
                         .state
@@ -227,6 +268,9 @@ impl ExecutionContext {
                         .config
                         .target_partitions,
                     table_partition_cols: vec![],
+                    bucketing: bucketing.clone(),
+                    metadata_cache_ttl: None,
+                    stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
                 };
 
                 // TODO make schema in CreateExternalTable optional instead of empty
@@ -257,6 +301,48 @@ impl ExecutionContext {
                 Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
             }
 
+            LogicalPlan::InsertInto(InsertInto {
+                table_name,
+                mode,
+                input,
+            }) => {
+                let table = self
+                    .state
+                    .lock()
+                    .unwrap()
+                    .get_table_provider(table_name.as_str().into())
+                    .ok_or_else(|| {
+                        DataFusionError::Plan(format!("No table named '{}'", table_name))
+                    })?;
+                let table = table
+                    .as_any()
+                    .downcast_ref::<ListingTable>()
+                    .filter(|table| {
+                        table.options().format.as_any().is::<ParquetFormat>()
+                            && table.table_paths().len() == 1
+                    })
+                    .ok_or_else(|| {
+                        DataFusionError::NotImplemented(format!(
+                            "Cannot write to table '{}', only the Parquet tables of a \
+                             single directory can be overwritten",
+                            table_name
+                        ))
+                    })?;
+
+                let plan = self.optimize(&input)?;
+                let plan = self.create_physical_plan(&plan).await?;
+                self.write_parquet_with_mode(
+                    plan,
+                    table.table_path(),
+                    &table.options().table_partition_cols,
+                    None,
+                    &mode,
+                )
+                .await?;
+                let plan = LogicalPlanBuilder::empty(false).build()?;
+                Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
+            }
+
             LogicalPlan::DropTable(DropTable { name, if_exist, .. }) => {
                 let returned = self.deregister_table(name.as_str())?;
                 if !if_exist && returned.is_none() {
@@ -281,18 +367,124 @@ impl ExecutionContext {
     ///
     /// This function is intended for internal use and should not be called directly.
     pub fn create_logical_plan(&self, sql: &str) -> Result<LogicalPlan> {
-        let statements = DFParser::parse_sql(sql)?;
+        let statement = self.parse_statement(sql)?;
+        self.statement_to_plan(&statement)
+    }
+
+    /// Parses a single SQL statement, parsing its custom statements with the
+    /// statement extensions of the context, e.g. to tell whether it must be
+    /// planned with [`sql`](Self::sql) rather than
+    /// [`create_logical_plan`](Self::create_logical_plan).
+    pub fn parse_statement(&self, sql: &str) -> Result<DFStatement> {
+        let extensions = self
+            .state
+            .lock()
+            .unwrap()
+            .config
+            .statement_extensions
+            .clone();
+        let mut statements = DFParser::parse_sql_with_statement_parser(
+            sql,
+            &GenericDialect {},
+            &StatementExtensionsParser(&extensions),
+        )?;
 
         if statements.len() != 1 {
             return Err(DataFusionError::NotImplemented(
                 "The context currently only supports a single SQL statement".to_string(),
             ));
         }
+        Ok(statements.remove(0))
+    }
 
+    /// Plans the custom statement `statement` with the statement extension that
+    /// parsed it
+    async fn custom_statement_to_plan(
+        &mut self,
+        statement: &dyn CustomStatement,
+    ) -> Result<LogicalPlan> {
+        let extensions = self
+            .state
+            .lock()
+            .unwrap()
+            .config
+            .statement_extensions
+            .clone();
+        for extension in extensions {
+            if let Some(plan) = extension.plan_statement(statement, self).await? {
+                return Ok(plan);
+            }
+        }
+        Err(DataFusionError::NotImplemented(format!(
+            "No statement extension plans the custom statement {:?}",
+            statement
+        )))
+    }
+
+    fn statement_to_plan(&self, statement: &DFStatement) -> Result<LogicalPlan> {
         // create a query planner
         let state = self.state.lock().unwrap().clone();
-        let query_planner = SqlToRel::new(&state);
-        query_planner.statement_to_plan(&statements[0])
+        let query_planner = SqlToRel::new(&state)
+            .with_materialize_ctes(state.config.materialize_ctes)
+            .with_identifier_normalization(state.config.identifier_normalization);
+        query_planner.statement_to_plan(statement)
+    }
+
+    /// Evaluates the uncorrelated scalar and IN subqueries of `statement`
+    /// returning at most `max_folded_subquery_rows` rows, and replaces them
+    /// with the literals they return, so that they can be used to prune scans
+    /// instead of being joined
+    fn fold_subqueries<'a>(
+        &'a self,
+        statement: &'a mut DFStatement,
+    ) -> BoxFuture<'a, Result<()>> {
+        async move {
+            let subqueries = collect_subqueries(statement);
+            if subqueries.is_empty() {
+                return Ok(());
+            }
+            let mut rows = Vec::with_capacity(subqueries.len());
+            for subquery in subqueries {
+                rows.push(self.evaluate_subquery(subquery).await?);
+            }
+            replace_subqueries(statement, rows)
+        }
+        .boxed()
+    }
+
+    /// Returns the values of the single column returned by `subquery`, or
+    /// `None` if it can not be folded
+    async fn evaluate_subquery(&self, subquery: Query) -> Result<Option<Vec<SQLExpr>>> {
+        let mut statement =
+            DFStatement::Statement(Box::new(SQLStatement::Query(Box::new(subquery))));
+        self.fold_subqueries(&mut statement).await?;
+        // correlated subqueries reference columns of the outer query, which
+        // can not be resolved
+        let plan = match self.statement_to_plan(&statement) {
+            Ok(plan) if plan.schema().fields().len() == 1 => plan,
+            _ => return Ok(None),
+        };
+        let max_rows = self.state.lock().unwrap().config.max_folded_subquery_rows;
+        let plan = LogicalPlanBuilder::from(plan)
+            .limit(max_rows + 1)?
+            .build()?;
+        let batches = DataFrameImpl::new(self.state.clone(), &plan)
+            .collect()
+            .await?;
+        if batches.iter().map(|b| b.num_rows()).sum::<usize>() > max_rows {
+            return Ok(None);
+        }
+
+        let mut values = vec![];
+        for batch in &batches {
+            for row in 0..batch.num_rows() {
+                match scalar_to_sql(&ScalarValue::try_from_array(batch.column(0), row)?) {
+                    Some(value) => values.push(value),
+                    None => return Ok(None),
+                }
+            }
+        }
+        Ok(Some(values))
     }
 
     /// Registers a variable provider within this context.
@@ -338,6 +530,19 @@ impl ExecutionContext {
             .insert(f.name.clone(), Arc::new(f));
     }
 
+    /// Registers a table function within this context, that SQL queries can
+    /// select from, e.g. `SELECT * FROM my_range(1, 1000)`.
+    ///
+    /// Note in SQL queries, table function names are looked up using
+    /// lowercase unless the query uses quotes, as table names are.
+    pub fn register_udtf(&mut self, f: TableUDF) {
+        self.state
+            .lock()
+            .unwrap()
+            .table_functions
+            .insert(f.name.clone(), Arc::new(f));
+    }
+
     /// Creates a DataFrame for reading an Avro data source.
 
     pub async fn read_avro(
@@ -425,6 +630,9 @@ impl ExecutionContext {
     /// Registers a table that uses the listing feature of the object store to
     /// find the files to be processed
     /// This is async because it might need to resolve the schema.
+    ///
+    /// The URI can be a glob pattern, e.g. `/data/2023-*/**.parquet`, or a comma
+    /// separated list of URIs of the same object store.
     pub async fn register_listing_table<'a>(
         &'a mut self,
         name: &'a str,
@@ -432,21 +640,48 @@ impl ExecutionContext {
         options: ListingOptions,
         provided_schema: Option<SchemaRef>,
     ) -> Result<()> {
-        let (object_store, path) = self.object_store(uri)?;
+        let (object_store, paths) = self.object_store_paths(uri)?;
         let resolved_schema = match provided_schema {
             None => {
                 options
-                    .infer_schema(Arc::clone(&object_store), path)
+                    .infer_schema_of_paths(Arc::clone(&object_store), &paths)
                     .await?
             }
             Some(s) => s,
         };
         let table =
-            ListingTable::new(object_store, path.to_owned(), resolved_schema, options);
+            ListingTable::new_with_paths(object_store, paths, resolved_schema, options);
         self.register_table(name, Arc::new(table))?;
         Ok(())
     }
 
+    /// Get the object store and the paths in it of a comma separated list of URIs
+    fn object_store_paths(
+        &self,
+        uris: &str,
+    ) -> Result<(Arc<dyn ObjectStore>, Vec<String>)> {
+        let scheme = |uri: &str| {
+            uri.split_once("://")
+                .map(|(scheme, _)| scheme.to_lowercase())
+        };
+        let uris = uris.split(',').map(str::trim).collect::<Vec<_>>();
+        let (object_store, _) = self.object_store(uris[0])?;
+        let paths = uris
+            .iter()
+            .map(|uri| {
+                if scheme(uri) != scheme(uris[0]) {
+                    return Err(DataFusionError::Plan(format!(
+                        "The URIs of a table must be of the same object store, {} and {} are not",
+                        uris[0], uri
+                    )));
+                }
+                let (_, path) = self.object_store(uri)?;
+                Ok(path.to_owned())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok((object_store, paths))
+    }
+
     /// Registers a CSV data source so that it can be referenced from SQL statements
     /// executed against this context.
     pub async fn register_csv(
@@ -484,6 +719,9 @@ impl ExecutionContext {
             file_extension: DEFAULT_PARQUET_EXTENSION.to_owned(),
             target_partitions,
             table_partition_cols: vec![],
+            bucketing: None,
+            metadata_cache_ttl: None,
+            stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
         };
 
         self.register_listing_table(name, uri, listing_options, None)
@@ -568,6 +806,40 @@ impl ExecutionContext {
             .get_by_uri(uri)
     }
 
+    /// Only lets `principal`, or all principals if `None`, read the rows of
+    /// the table for which `filter` is true
+    pub fn add_row_policy<'a>(
+        &self,
+        table_ref: impl Into<TableReference<'a>>,
+        principal: Option<&str>,
+        filter: Expr,
+    ) {
+        let state = self.state.lock().unwrap();
+        state.policy_registry.add_row_policy(
+            state.resolve_table_ref(table_ref),
+            principal,
+            filter,
+        )
+    }
+
+    /// Replaces the values of `column` of the table read by `principal`, or by
+    /// all principals if `None`, with `mask`
+    pub fn add_column_mask<'a>(
+        &self,
+        table_ref: impl Into<TableReference<'a>>,
+        column: &str,
+        principal: Option<&str>,
+        mask: Expr,
+    ) {
+        let state = self.state.lock().unwrap();
+        state.policy_registry.add_column_mask(
+            state.resolve_table_ref(table_ref),
+            column,
+            principal,
+            mask,
+        )
+    }
+
     /// Registers a table using a custom `TableProvider` so that
     /// it can be referenced from SQL statements executed against this
     /// context.
@@ -611,15 +883,24 @@ impl ExecutionContext {
         table_ref: impl Into<TableReference<'a>>,
     ) -> Result<Arc<dyn DataFrame>> {
         let table_ref = table_ref.into();
-        let schema = self.state.lock().unwrap().schema_for_ref(table_ref)?;
+        let schema = {
+            let state = self.state.lock().unwrap();
+            state.authorize_select(table_ref)?;
+            state.schema_for_ref(table_ref)?
+        };
         match schema.table(table_ref.table()) {
             Some(ref provider) => {
-                let plan = LogicalPlanBuilder::scan(
+                let scan = LogicalPlanBuilder::scan(
                     table_ref.table(),
                     Arc::clone(provider),
                     None,
                 )?
                 .build()?;
+                let plan = self
+                    .state
+                    .lock()
+                    .unwrap()
+                    .apply_table_policies(table_ref, scan)?;
                 Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
             }
             _ => Err(DataFusionError::Plan(format!(
@@ -660,13 +941,17 @@ impl ExecutionContext {
                 self.optimize_internal(e.plan.as_ref(), |optimized_plan, optimizer| {
                     let optimizer_name = optimizer.name().to_string();
                     let plan_type = PlanType::OptimizedLogicalPlan { optimizer_name };
-                    stringified_plans.push(optimized_plan.to_stringified(plan_type));
+                    stringified_plans.push(
+                        optimized_plan.to_stringified_with_format(plan_type, e.format),
+                    );
                 })?;
 
             Ok(LogicalPlan::Explain(Explain {
                 verbose: e.verbose,
                 plan: Arc::new(plan),
                 stringified_plans,
+                format: e.format,
+                costs: e.costs,
                 schema: e.schema.clone(),
             }))
         } else {
@@ -698,86 +983,88 @@ impl ExecutionContext {
         planner.create_physical_plan(logical_plan, &state).await
     }
 
+    /// Executes a logical plan and returns a table provider over its materialized
+    /// results, so that they can be reused by subsequent queries without recomputing
+    /// the plan.
+    pub async fn cache(
+        &self,
+        logical_plan: &LogicalPlan,
+    ) -> Result<Arc<dyn TableProvider>> {
+        let (state, planner) = {
+            let mut state = self.state.lock().unwrap();
+            state.execution_props.start_execution();
+            (state.clone(), Arc::clone(&state.config.query_planner))
+        };
+
+        planner.cache(logical_plan, &state).await
+    }
+
     /// Executes a query and writes the results to a partitioned CSV file.
+    ///
+    /// The file of each partition is written to a directory of its own, and the
+    /// files are only moved to `path` once all of them were written, so that a
+    /// failed query leaves no file behind.
     pub async fn write_csv(
         &self,
         plan: Arc<dyn ExecutionPlan>,
         path: impl AsRef<str>,
     ) -> Result<()> {
-        let path = path.as_ref();
-        // create directory to contain the CSV files (one per partition)
-        let fs_path = Path::new(path);
-        match fs::create_dir(fs_path) {
-            Ok(()) => {
-                let mut tasks = vec![];
-                for i in 0..plan.output_partitioning().partition_count() {
-                    let plan = plan.clone();
-                    let filename = format!("part-{}.csv", i);
-                    let path = fs_path.join(&filename);
-                    let file = fs::File::create(path)?;
-                    let mut writer = csv::Writer::new(file);
-                    let stream = plan.execute(i).await?;
-                    let handle: JoinHandle<Result<()>> = task::spawn(async move {
-                        stream
-                            .map(|batch| writer.write(&batch?))
-                            .try_collect()
-                            .await
-                            .map_err(DataFusionError::from)
-                    });
-                    tasks.push(handle);
-                }
-                futures::future::join_all(tasks).await;
-                Ok(())
-            }
-            Err(e) => Err(DataFusionError::Execution(format!(
-                "Could not create directory {}: {:?}",
-                path, e
-            ))),
-        }
+        write::write_table(
+            plan,
+            Path::new(path.as_ref()),
+            &[],
+            WriteFormat::Csv,
+            &WriteMode::ErrorIfExists,
+        )
+        .await
     }
 
     /// Executes a query and writes the results to a partitioned Parquet file.
+    ///
+    /// The writer properties can be created from user facing
+    /// [`ParquetWriteOptions`](super::options::ParquetWriteOptions), e.g. to set
+    /// the compression codecs, dictionary encoding and statistics of the columns,
+    /// and the size of the row groups.
     pub async fn write_parquet(
         &self,
         plan: Arc<dyn ExecutionPlan>,
         path: impl AsRef<str>,
         writer_properties: Option<WriterProperties>,
     ) -> Result<()> {
-        let path = path.as_ref();
-        // create directory to contain the Parquet files (one per partition)
-        let fs_path = Path::new(path);
-        match fs::create_dir(fs_path) {
-            Ok(()) => {
-                let mut tasks = vec![];
-                for i in 0..plan.output_partitioning().partition_count() {
-                    let plan = plan.clone();
-                    let filename = format!("part-{}.parquet", i);
-                    let path = fs_path.join(&filename);
-                    let file = fs::File::create(path)?;
-                    let mut writer = ArrowWriter::try_new(
-                        file.try_clone().unwrap(),
-                        plan.schema(),
-                        writer_properties.clone(),
-                    )?;
-                    let stream = plan.execute(i).await?;
-                    let handle: JoinHandle<Result<()>> = task::spawn(async move {
-                        stream
-                            .map(|batch| writer.write(&batch?))
-                            .try_collect()
-                            .await
-                            .map_err(DataFusionError::from)?;
-                        writer.close().map_err(DataFusionError::from).map(|_| ())
-                    });
-                    tasks.push(handle);
-                }
-                futures::future::join_all(tasks).await;
-                Ok(())
-            }
-            Err(e) => Err(DataFusionError::Execution(format!(
-                "Could not create directory {}: {:?}",
-                path, e
-            ))),
-        }
+        self.write_parquet_with_mode(
+            plan,
+            path,
+            &[],
+            writer_properties,
+            &WriteMode::ErrorIfExists,
+        )
+        .await
+    }
+
+    /// Executes a query and writes the results to the directory of a Parquet
+    /// table, in Hive style directories of the `partition_cols` columns, e.g.
+    /// `path/year=2021/part-0.parquet`, replacing the existing data of the
+    /// directory as specified by `mode`.
+    ///
+    /// The files are written to a staging directory next to `path`, which then
+    /// replaces the whole directory, or each of the written partition directories,
+    /// so that a failed write leaves the existing data unchanged.
+    pub async fn write_parquet_with_mode(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        path: impl AsRef<str>,
+        partition_cols: &[String],
+        writer_properties: Option<WriterProperties>,
+        mode: &WriteMode,
+    ) -> Result<()> {
+        write::write_table(
+            plan,
+            Path::new(path.as_ref()),
+            partition_cols,
+            WriteFormat::Parquet(writer_properties),
+            mode,
+        )
+        .await
     }
 
     /// Optimizes the logical plan by applying optimizer rules, and
@@ -792,6 +1079,7 @@ impl ExecutionContext {
     {
         let state = &mut self.state.lock().unwrap();
         let execution_props = &mut state.execution_props.clone();
+        execution_props.ansi_mode = state.config.ansi_mode;
         let optimizers = &state.config.optimizers;
 
         let execution_props = execution_props.start_execution();
@@ -836,6 +1124,59 @@ pub trait QueryPlanner {
         logical_plan: &LogicalPlan,
         ctx_state: &ExecutionContextState,
     ) -> Result<Arc<dyn ExecutionPlan>>;
+
+    /// Given a `LogicalPlan`, execute it and return a `TableProvider` over the results.
+    ///
+    /// The default implementation collects the results into a [`MemTable`] that keeps
+    /// the partitioning of the physical plan.
+    async fn cache(
+        &self,
+        logical_plan: &LogicalPlan,
+        ctx_state: &ExecutionContextState,
+    ) -> Result<Arc<dyn TableProvider>> {
+        let plan = self.create_physical_plan(logical_plan, ctx_state).await?;
+        let schema = plan.schema();
+        let partitions = collect_partitioned(plan).await?;
+        Ok(Arc::new(MemTable::try_new(schema, partitions)?))
+    }
+}
+
+/// Extends the SQL of a context with custom statements, e.g. `VACUUM t`,
+/// `OPTIMIZE TABLE t` or `SHOW JOBS`, which produce custom logical plan nodes or
+/// only have side effects.
+///
+/// The extensions of a context parse the statements before the SQL parser of
+/// DataFusion does, in the order they were added with
+/// [`ExecutionConfig::add_statement_extension`].
+#[async_trait]
+pub trait StatementExtension: StatementParser + Send + Sync {
+    /// Plans the custom statement `statement` if it was parsed by this
+    /// extension, returning `None` otherwise. The returned plan is the result of
+    /// the statement, e.g. an [`Extension`](LogicalPlan::Extension) node, or an
+    /// empty relation for statements whose side effects are performed by this
+    /// method.
+    async fn plan_statement(
+        &self,
+        statement: &dyn CustomStatement,
+        ctx: &mut ExecutionContext,
+    ) -> Result<Option<LogicalPlan>>;
+}
+
+/// Parses the custom statements of the statement extensions of a context
+struct StatementExtensionsParser<'a>(&'a [Arc<dyn StatementExtension>]);
+
+impl StatementParser for StatementExtensionsParser<'_> {
+    fn parse_statement(
+        &self,
+        parser: &mut Parser<'_>,
+    ) -> std::result::Result<Option<Arc<dyn CustomStatement>>, ParserError> {
+        for extension in self.0 {
+            if let Some(statement) = extension.parse_statement(parser)? {
+                return Ok(Some(statement));
+            }
+        }
+        Ok(None)
+    }
 }
 
 /// The query planner used if no user defined planner is provided
@@ -867,6 +1208,8 @@ pub struct ExecutionConfig {
     pub physical_optimizers: Vec<Arc<dyn PhysicalOptimizerRule + Send + Sync>>,
     /// Responsible for planning `LogicalPlan`s, and `ExecutionPlan`
     query_planner: Arc<dyn QueryPlanner + Send + Sync>,
+    /// Parse and plan the custom statements of SQL queries
+    statement_extensions: Vec<Arc<dyn StatementExtension>>,
     /// Default catalog name for table resolution
     default_catalog: String,
     /// Default schema name for table resolution
@@ -887,6 +1230,66 @@ pub struct ExecutionConfig {
     pub repartition_windows: bool,
     /// Should Datafusion parquet reader using the predicate to prune data
     parquet_pruning: bool,
+    /// Should DataFusion use the statistics of the inputs (row counts and byte sizes) to
+    /// choose how many partitions they are repartitioned into, rather than always using
+    /// `target_partitions`
+    pub repartition_statistics: bool,
+    /// The amount of input bytes each partition should process when the number of
+    /// partitions is derived from statistics
+    pub repartition_bytes_per_partition: usize,
+    /// The maximum number of partitions large inputs can be repartitioned into when
+    /// `repartition_statistics` is enabled. Defaults to `target_partitions`.
+    pub max_partitions: Option<usize>,
+    /// Should common table expressions that are referenced more than once be executed
+    /// only once, buffering their result in memory for all references
+    pub materialize_ctes: bool,
+    /// How the SQL planner treats the case of identifiers
+    pub identifier_normalization: IdentifierNormalization,
+    /// Should comparisons, IN lists and CASE expressions error instead of
+    /// implicitly casting strings to numbers or temporal values, dates to
+    /// timestamps, and CASE branches to a common type
+    pub strict_type_coercion: bool,
+    /// The maximum number of rows of the uncorrelated scalar and IN
+    /// subqueries of SQL queries that are evaluated while planning the
+    /// queries and folded into literals
+    pub max_folded_subquery_rows: usize,
+    /// When partial aggregations that barely reduce their input are skipped
+    /// at runtime, `None` to never skip them
+    pub skip_partial_aggregation: Option<SkipPartialAggregation>,
+    /// The number of groups above which grouped aggregations switch from hashing
+    /// to sorting their groups, spilling them to disk, rather than growing their
+    /// hash tables further. `None` to always hash.
+    pub max_aggregation_groups: Option<usize>,
+    /// The maximum number of threads of the IO thread pool that file scans read on,
+    /// separately from the threads of the tokio runtime. The pool is shared by the
+    /// process and can only be sized before the first scan. `None` for twice the
+    /// number of cores.
+    pub io_threads: Option<usize>,
+    /// The maximum duration of the execution of the queries of DataFrames, after
+    /// which they are cancelled. `None` for no timeout.
+    pub statement_timeout: Option<Duration>,
+    /// The maximum number of bytes the operators of the queries of DataFrames may
+    /// buffer, after which the queries fail. `None` for no limit.
+    pub memory_limit: Option<usize>,
+    /// Should the results of queries be deterministic, the batches of parallel
+    /// partitions being merged and repartitioned in the order of the partitions
+    /// rather than as they are produced, so that the same query over the same data
+    /// returns the same rows in the same order across runs
+    pub deterministic: bool,
+    /// Should the filter and projection expressions over numeric columns be
+    /// compiled to native code once they evaluated enough rows. Requires the `jit`
+    /// feature, the expressions are interpreted without it
+    pub jit: bool,
+    /// Should integer arithmetic and integer SUMs error on overflow, as in the ANSI
+    /// mode of SQL. Otherwise their results wrap around in two's complement, e.g.
+    /// the 64 bit sum of `i64::MAX` and 1 is `i64::MIN`, in debug and release
+    /// builds alike
+    pub ansi_mode: bool,
+    /// Decides which tables the principal of the context may read
+    table_authorizer: Option<Arc<dyn TableAuthorizer>>,
+    /// The principal running the queries of the context, as authenticated by
+    /// the application
+    principal: Option<String>,
 }
 
 impl Default for ExecutionConfig {
@@ -904,6 +1307,7 @@ impl Default for ExecutionConfig {
                 Arc::new(FilterPushDown::new()),
                 Arc::new(LimitPushDown::new()),
                 Arc::new(SingleDistinctToGroupBy::new()),
+                Arc::new(AggregatePushDown::new()),
             ],
             physical_optimizers: vec![
                 Arc::new(AggregateStatistics::new()),
@@ -911,8 +1315,11 @@ impl Default for ExecutionConfig {
                 Arc::new(CoalesceBatches::new()),
                 Arc::new(Repartition::new()),
                 Arc::new(AddCoalescePartitionsExec::new()),
+                Arc::new(DeterministicExecution::new()),
+                Arc::new(JitCompilation::new()),
             ],
             query_planner: Arc::new(DefaultQueryPlanner {}),
+            statement_extensions: vec![],
             default_catalog: "datafusion".to_owned(),
             default_schema: "public".to_owned(),
             create_default_catalog_and_schema: true,
@@ -921,6 +1328,23 @@ impl Default for ExecutionConfig {
             repartition_aggregations: true,
             repartition_windows: true,
             parquet_pruning: true,
+            repartition_statistics: true,
+            repartition_bytes_per_partition: 64 * 1024 * 1024,
+            max_partitions: None,
+            materialize_ctes: false,
+            identifier_normalization: IdentifierNormalization::CaseSensitive,
+            strict_type_coercion: false,
+            max_folded_subquery_rows: 1000,
+            skip_partial_aggregation: Some(SkipPartialAggregation::default()),
+            max_aggregation_groups: None,
+            io_threads: None,
+            statement_timeout: None,
+            memory_limit: None,
+            deterministic: false,
+            jit: false,
+            ansi_mode: false,
+            table_authorizer: None,
+            principal: None,
         }
     }
 }
@@ -956,6 +1380,15 @@ impl ExecutionConfig {
         self
     }
 
+    /// Adds a new [`StatementExtension`], parsing and planning custom statements
+    pub fn add_statement_extension(
+        mut self,
+        statement_extension: Arc<dyn StatementExtension>,
+    ) -> Self {
+        self.statement_extensions.push(statement_extension);
+        self
+    }
+
     /// Replace the optimizer rules
     pub fn with_optimizer_rules(
         mut self,
@@ -1038,6 +1471,133 @@ impl ExecutionConfig {
         self.parquet_pruning = enabled;
         self
     }
+
+    /// Enables or disables the use of input statistics to choose partition counts
+    pub fn with_repartition_statistics(mut self, enabled: bool) -> Self {
+        self.repartition_statistics = enabled;
+        self
+    }
+
+    /// Customize the amount of input bytes per partition when repartitioning
+    /// based on statistics
+    pub fn with_repartition_bytes_per_partition(mut self, n: usize) -> Self {
+        // partition size must be greater than zero
+        assert!(n > 0);
+        self.repartition_bytes_per_partition = n;
+        self
+    }
+
+    /// Customize the maximum number of partitions large inputs can be split into
+    pub fn with_max_partitions(mut self, n: usize) -> Self {
+        // partition count must be greater than zero
+        assert!(n > 0);
+        self.max_partitions = Some(n);
+        self
+    }
+
+    /// Enables or disables the materialization of common table expressions
+    /// referenced more than once
+    pub fn with_materialize_ctes(mut self, enabled: bool) -> Self {
+        self.materialize_ctes = enabled;
+        self
+    }
+
+    /// Customize the case handling of identifiers in SQL statements
+    pub fn with_identifier_normalization(
+        mut self,
+        identifier_normalization: IdentifierNormalization,
+    ) -> Self {
+        self.identifier_normalization = identifier_normalization;
+        self
+    }
+
+    /// Enables or disables strict type coercion
+    pub fn with_strict_type_coercion(mut self, enabled: bool) -> Self {
+        self.strict_type_coercion = enabled;
+        self
+    }
+
+    /// Sets the maximum number of rows of the subqueries folded into
+    /// literals. Subqueries returning more rows are not supported.
+    pub fn with_max_folded_subquery_rows(mut self, n: usize) -> Self {
+        self.max_folded_subquery_rows = n;
+        self
+    }
+
+    /// Sets when partial aggregations that barely reduce their input, e.g.
+    /// because their group keys are nearly unique, are skipped at runtime
+    pub fn with_skip_partial_aggregation(
+        mut self,
+        skip_partial: Option<SkipPartialAggregation>,
+    ) -> Self {
+        self.skip_partial_aggregation = skip_partial;
+        self
+    }
+
+    /// Sets the number of groups above which grouped aggregations switch from
+    /// hashing to sorting and spilling their groups at runtime, `None` to always
+    /// hash them
+    pub fn with_max_aggregation_groups(mut self, max_groups: Option<usize>) -> Self {
+        self.max_aggregation_groups = max_groups;
+        self
+    }
+
+    /// Sets the maximum number of threads of the IO thread pool that file scans
+    /// read on
+    pub fn with_io_threads(mut self, io_threads: usize) -> Self {
+        self.io_threads = Some(io_threads);
+        self
+    }
+
+    /// Cancels the execution of the queries of DataFrames that run for longer
+    /// than `timeout`
+    pub fn with_statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
+    /// Fails the queries of DataFrames whose operators buffer more than
+    /// `memory_limit` bytes
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = Some(memory_limit);
+        self
+    }
+
+    /// Enables or disables the deterministic execution of queries, whose results are
+    /// then the same rows in the same order across runs, at the cost of buffering the
+    /// batches of the partitions that are ahead of the ones being merged
+    pub fn with_deterministic(mut self, enabled: bool) -> Self {
+        self.deterministic = enabled;
+        self
+    }
+
+    /// Enables or disables the compilation of filter and projection expressions to
+    /// native code, when DataFusion is built with the `jit` feature
+    pub fn with_jit(mut self, enabled: bool) -> Self {
+        self.jit = enabled;
+        self
+    }
+
+    /// Enables or disables the ANSI mode, in which integer arithmetic and integer
+    /// SUMs error on overflow rather than wrapping around
+    pub fn with_ansi_mode(mut self, enabled: bool) -> Self {
+        self.ansi_mode = enabled;
+        self
+    }
+
+    /// Checks with `authorizer` that the principal may read the tables
+    /// referenced by queries, when planning them
+    pub fn with_table_authorizer(mut self, authorizer: Arc<dyn TableAuthorizer>) -> Self {
+        self.table_authorizer = Some(authorizer);
+        self
+    }
+
+    /// Sets the authenticated principal running the queries, passed to the
+    /// table authorizer
+    pub fn with_principal(mut self, principal: impl Into<String>) -> Self {
+        self.principal = Some(principal.into());
+        self
+    }
 }
 
 /// Holds per-execution properties and data (such as starting timestamps, etc).
@@ -1047,6 +1607,9 @@ impl ExecutionConfig {
 #[derive(Clone)]
 pub struct ExecutionProps {
     pub(crate) query_execution_start_time: DateTime<Utc>,
+    /// Whether constant expressions are folded with the overflow checks of the
+    /// ANSI mode, see [`ExecutionConfig::with_ansi_mode`]
+    pub(crate) ansi_mode: bool,
 }
 
 /// Execution context for registering data sources and executing queries
@@ -1060,12 +1623,16 @@ pub struct ExecutionContextState {
     pub var_provider: HashMap<VarType, Arc<dyn VarProvider + Send + Sync>>,
     /// Aggregate functions registered in the context
     pub aggregate_functions: HashMap<String, Arc<AggregateUDF>>,
+    /// Table functions registered in the context
+    pub table_functions: HashMap<String, Arc<TableUDF>>,
     /// Context configuration
     pub config: ExecutionConfig,
     /// Execution properties
     pub execution_props: ExecutionProps,
     /// Object Store that are registered with the context
     pub object_store_registry: Arc<ObjectStoreRegistry>,
+    /// Row level security and column masking policies of the tables
+    pub policy_registry: Arc<PolicyRegistry>,
 }
 
 impl ExecutionProps {
@@ -1073,6 +1640,7 @@ impl ExecutionProps {
     pub fn new() -> Self {
         ExecutionProps {
             query_execution_start_time: chrono::Utc::now(),
+            ansi_mode: false,
         }
     }
 
@@ -1091,9 +1659,11 @@ impl ExecutionContextState {
             scalar_functions: HashMap::new(),
             var_provider: HashMap::new(),
             aggregate_functions: HashMap::new(),
+            table_functions: HashMap::new(),
             config: ExecutionConfig::new(),
             execution_props: ExecutionProps::new(),
             object_store_registry: Arc::new(ObjectStoreRegistry::new()),
+            policy_registry: Arc::new(PolicyRegistry::new()),
         }
     }
 
@@ -1137,6 +1707,28 @@ impl ContextProvider for ExecutionContextState {
         schema.table(resolved_ref.table)
     }
 
+    fn apply_table_policies(
+        &self,
+        name: TableReference,
+        scan: LogicalPlan,
+    ) -> Result<LogicalPlan> {
+        self.policy_registry.apply(
+            self.resolve_table_ref(name),
+            self.config.principal.as_deref(),
+            scan,
+        )
+    }
+
+    fn authorize_select(&self, name: TableReference) -> Result<()> {
+        match &self.config.table_authorizer {
+            Some(authorizer) => authorizer.authorize_select(
+                self.config.principal.as_deref(),
+                self.resolve_table_ref(name),
+            ),
+            None => Ok(()),
+        }
+    }
+
     fn get_function_meta(&self, name: &str) -> Option<Arc<ScalarUDF>> {
         self.scalar_functions.get(name).cloned()
     }
@@ -1144,6 +1736,10 @@ impl ContextProvider for ExecutionContextState {
     fn get_aggregate_meta(&self, name: &str) -> Option<Arc<AggregateUDF>> {
         self.aggregate_functions.get(name).cloned()
     }
+
+    fn get_table_function_meta(&self, name: &str) -> Option<Arc<TableUDF>> {
+        self.table_functions.get(name).cloned()
+    }
 }
 
 impl FunctionRegistry for ExecutionContextState {
@@ -1177,11 +1773,12 @@ impl FunctionRegistry for ExecutionContextState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::options::ParquetWriteOptions;
     use crate::logical_plan::plan::Projection;
     use crate::logical_plan::TableScan;
     use crate::logical_plan::{binary_expr, lit, Operator};
+    use crate::physical_plan::collect;
     use crate::physical_plan::functions::{make_scalar_function, Volatility};
-    use crate::physical_plan::{collect, collect_partitioned};
     use crate::test;
     use crate::variable::VarType;
     use crate::{
@@ -1203,7 +1800,13 @@ mod tests {
     use arrow::datatypes::*;
     use arrow::record_batch::RecordBatch;
     use async_trait::async_trait;
-    use std::fs::File;
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::{Compression, Encoding};
+    use parquet::file::reader::FileReader;
+    use parquet::file::serialized_reader::SerializedFileReader;
+    use sqlparser::tokenizer::Token;
+    use std::any::Any;
+    use std::fs::{self, File};
     use std::sync::Weak;
     use std::thread::{self, JoinHandle};
     use std::{io::prelude::*, sync::Mutex};
@@ -1304,6 +1907,39 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn deterministic_execution() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let schema = populate_csv_partitions(&tmp_dir, 4, ".csv")?;
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_target_partitions(8)
+                .with_batch_size(3)
+                .with_deterministic(true),
+        );
+        ctx.register_csv(
+            "test",
+            tmp_dir.path().to_str().unwrap(),
+            CsvReadOptions::new().schema(&schema),
+        )
+        .await?;
+
+        for sql in [
+            "SELECT c1, c2 FROM test WHERE c2 > 2",
+            "SELECT c2 % 3 AS k, SUM(c1) FROM test GROUP BY c2 % 3",
+        ] {
+            let first = plan_and_collect(&mut ctx, sql).await?;
+            let first = arrow::util::pretty::pretty_format_batches(&first)?;
+            for _ in 0..5 {
+                let results = plan_and_collect(&mut ctx, sql).await?;
+                let results = arrow::util::pretty::pretty_format_batches(&results)?;
+                assert_eq!(first, results, "results of {} differ", sql);
+            }
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn create_variable_expr() -> Result<()> {
         let tmp_dir = TempDir::new()?;
@@ -1348,6 +1984,41 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn register_glob_and_multiple_paths() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let schema = populate_csv_partitions(&tmp_dir, 4, ".csv")?;
+        let dir = tmp_dir.path().to_str().unwrap();
+
+        let mut ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "test",
+            &format!("{}/partition-[01]*, {}/partition-3..csv", dir, dir),
+            CsvReadOptions::new().schema(&schema),
+        )
+        .await?;
+        let results =
+            plan_and_collect(&mut ctx, "SELECT DISTINCT c1 FROM test ORDER BY c1")
+                .await?;
+        let expected = vec![
+            "+----+", "| c1 |", "+----+", "| 0  |", "| 1  |", "| 3  |", "+----+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        // the URIs of a table must be of the same object store
+        let err = ctx
+            .register_csv(
+                "test2",
+                &format!("{}, s3://bucket/table", dir),
+                CsvReadOptions::new().schema(&schema),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("same object store"));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn parallel_query_with_filter() -> Result<()> {
         let tmp_dir = TempDir::new()?;
@@ -3090,6 +3761,115 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn write_parquet_with_options() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let mut ctx = create_ctx(&tmp_dir, 4).await?;
+
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
+        let options = ParquetWriteOptions::new()
+            .column_compression("c1", Compression::GZIP)
+            .dictionary_enabled(false)
+            .max_row_group_size(4);
+        write_parquet(
+            &mut ctx,
+            "SELECT c1, c2 FROM test",
+            &out_dir,
+            Some(options.to_writer_properties()),
+        )
+        .await?;
+
+        let mut num_rows = 0;
+        for entry in fs::read_dir(&out_dir)? {
+            let reader = SerializedFileReader::new(File::open(entry?.path())?)?;
+            for row_group in reader.metadata().row_groups() {
+                assert!(row_group.num_rows() <= 4);
+                num_rows += row_group.num_rows();
+
+                let c1 = row_group.column(0);
+                assert_eq!(c1.compression(), Compression::GZIP);
+                assert!(c1.statistics().is_some());
+                assert!(!c1.encodings().contains(&Encoding::RLE_DICTIONARY));
+                assert!(!c1.encodings().contains(&Encoding::PLAIN_DICTIONARY));
+                assert_eq!(row_group.column(1).compression(), Compression::SNAPPY);
+            }
+        }
+        assert_eq!(num_rows, 40);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn insert_overwrite_parquet_tables() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let mut ctx = create_ctx(&tmp_dir, 4).await?;
+
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
+        write_parquet(&mut ctx, "SELECT c1, c2 FROM test", &out_dir, None).await?;
+        ctx.register_parquet("t", &out_dir).await?;
+        ctx.sql("INSERT OVERWRITE TABLE t SELECT c2, c1 FROM test WHERE c1 = 1")
+            .await?;
+        let results =
+            plan_and_collect(&mut ctx, "SELECT MIN(c1), MAX(c1), COUNT(*) FROM t")
+                .await?;
+        let expected = vec![
+            "+-----------+-----------+-----------------+",
+            "| MIN(t.c1) | MAX(t.c1) | COUNT(UInt8(1)) |",
+            "+-----------+-----------+-----------------+",
+            "| 1         | 10        | 10              |",
+            "+-----------+-----------+-----------------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        // a table partitioned by c1
+        let partitioned_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/p";
+        let plan = ctx.create_logical_plan("SELECT c2, c1 FROM test")?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+        ctx.write_parquet_with_mode(
+            plan,
+            &partitioned_dir,
+            &["c1".to_owned()],
+            None,
+            &WriteMode::ErrorIfExists,
+        )
+        .await?;
+        let options = ListingOptions {
+            table_partition_cols: vec!["c1".to_owned()],
+            ..ListingOptions::new(Arc::new(ParquetFormat::default()))
+        };
+        ctx.register_listing_table("p", &partitioned_dir, options, None)
+            .await?;
+
+        // only the partitions of the written rows are replaced
+        ctx.sql(
+            "INSERT OVERWRITE TABLE p PARTITION (c1) \
+             SELECT c2 * 10, c1 FROM test WHERE c1 < 2",
+        )
+        .await?;
+        let results = plan_and_collect(
+            &mut ctx,
+            "SELECT c1, COUNT(*), MAX(c2) FROM p GROUP BY c1 ORDER BY c1",
+        )
+        .await?;
+        let expected = vec![
+            "+----+-----------------+-----------+",
+            "| c1 | COUNT(UInt8(1)) | MAX(p.c2) |",
+            "+----+-----------------+-----------+",
+            "| 0  | 10              | 100       |",
+            "| 1  | 10              | 100       |",
+            "| 2  | 10              | 10        |",
+            "| 3  | 10              | 10        |",
+            "+----+-----------------+-----------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        // the staging and backup directories were removed
+        for entry in fs::read_dir(&tmp_dir)?.chain(fs::read_dir(&partitioned_dir)?) {
+            assert!(!entry?.file_name().to_string_lossy().starts_with('.'));
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     async fn query_csv_with_custom_partition_extension() -> Result<()> {
         let tmp_dir = TempDir::new()?;
@@ -3368,6 +4148,119 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn custom_statements() -> Result<()> {
+        #[derive(Debug)]
+        enum JobStatement {
+            /// `SHOW JOBS`
+            Show,
+            /// `CANCEL JOB <id>`
+            Cancel(u64),
+        }
+
+        impl CustomStatement for JobStatement {
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+        }
+
+        /// Jobs 1 to 3, that can be cancelled
+        #[derive(Default)]
+        struct Jobs {
+            cancelled: Mutex<Vec<u64>>,
+        }
+
+        fn is_word(token: &Token, word: &str) -> bool {
+            matches!(token, Token::Word(w) if w.value.eq_ignore_ascii_case(word))
+        }
+
+        impl StatementParser for Jobs {
+            fn parse_statement(
+                &self,
+                parser: &mut Parser<'_>,
+            ) -> std::result::Result<Option<Arc<dyn CustomStatement>>, ParserError>
+            {
+                if is_word(&parser.peek_token(), "CANCEL") {
+                    parser.next_token();
+                    if !is_word(&parser.next_token(), "JOB") {
+                        return Err(ParserError::ParserError("Expected JOB".to_string()));
+                    }
+                    let id = parser.parse_literal_uint()?;
+                    return Ok(Some(Arc::new(JobStatement::Cancel(id))));
+                }
+                if is_word(&parser.peek_token(), "SHOW") {
+                    parser.next_token();
+                    if is_word(&parser.peek_token(), "JOBS") {
+                        parser.next_token();
+                        return Ok(Some(Arc::new(JobStatement::Show)));
+                    }
+                    // e.g. `SHOW TABLES`
+                    parser.prev_token();
+                }
+                Ok(None)
+            }
+        }
+
+        #[async_trait]
+        impl StatementExtension for Jobs {
+            async fn plan_statement(
+                &self,
+                statement: &dyn CustomStatement,
+                _ctx: &mut ExecutionContext,
+            ) -> Result<Option<LogicalPlan>> {
+                let plan = match statement.as_any().downcast_ref::<JobStatement>() {
+                    Some(JobStatement::Show) => {
+                        let cancelled = self.cancelled.lock().unwrap();
+                        let rows = (1..=3)
+                            .map(|id| {
+                                let status = if cancelled.contains(&id) {
+                                    "cancelled"
+                                } else {
+                                    "running"
+                                };
+                                vec![lit(id), lit(status)]
+                            })
+                            .collect();
+                        LogicalPlanBuilder::values(rows)?.build()?
+                    }
+                    Some(JobStatement::Cancel(id)) => {
+                        self.cancelled.lock().unwrap().push(*id);
+                        LogicalPlanBuilder::empty(false).build()?
+                    }
+                    None => return Ok(None),
+                };
+                Ok(Some(plan))
+            }
+        }
+
+        let jobs = Arc::new(Jobs::default());
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_information_schema(true)
+                .add_statement_extension(jobs.clone()),
+        );
+
+        plan_and_collect(&mut ctx, "cancel job 2").await?;
+        assert_eq!(*jobs.cancelled.lock().unwrap(), vec![2]);
+        let result = plan_and_collect(&mut ctx, "SHOW JOBS").await?;
+        let expected = vec![
+            "+---------+-----------+",
+            "| column1 | column2   |",
+            "+---------+-----------+",
+            "| 1       | running   |",
+            "| 2       | cancelled |",
+            "| 3       | running   |",
+            "+---------+-----------+",
+        ];
+        assert_batches_eq!(expected, &result);
+
+        // other statements are still parsed by DataFusion
+        plan_and_collect(&mut ctx, "SHOW TABLES").await?;
+        assert!(ctx.create_logical_plan("SHOW JOBS").is_err());
+        assert!(plan_and_collect(&mut ctx, "CANCEL JOBS").await.is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn information_schema_tables_not_exist_by_default() {
         let mut ctx = ExecutionContext::new();
@@ -3381,6 +4274,107 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn table_authorizer() -> Result<()> {
+        /// Only allows alice to read the table `t`
+        struct AliceOnly {}
+
+        impl TableAuthorizer for AliceOnly {
+            fn authorize_select(
+                &self,
+                principal: Option<&str>,
+                table: ResolvedTableReference,
+            ) -> Result<()> {
+                if principal == Some("alice") || table.table != "t" {
+                    Ok(())
+                } else {
+                    Err(DataFusionError::PermissionDenied {
+                        principal: principal.map(str::to_owned),
+                        action: "SELECT".to_owned(),
+                        table: table.to_string(),
+                    })
+                }
+            }
+        }
+
+        let create_ctx = |config: ExecutionConfig| {
+            let mut ctx = ExecutionContext::with_config(
+                config.with_table_authorizer(Arc::new(AliceOnly {})),
+            );
+            ctx.register_table("t", test::table_with_sequence(1, 3).unwrap())
+                .unwrap();
+            ctx.register_table("u", test::table_with_sequence(1, 3).unwrap())
+                .unwrap();
+            ctx
+        };
+
+        let mut ctx = create_ctx(ExecutionConfig::new().with_principal("alice"));
+        let result = plan_and_collect(&mut ctx, "SELECT * FROM t").await?;
+        assert_eq!(3, result.iter().map(|b| b.num_rows()).sum::<usize>());
+        ctx.table("t")?;
+
+        let mut ctx = create_ctx(ExecutionConfig::new().with_principal("bob"));
+        plan_and_collect(&mut ctx, "SELECT * FROM u").await?;
+        // CTEs shadowing the table are not checked
+        plan_and_collect(&mut ctx, "WITH t AS (SELECT * FROM u) SELECT * FROM t").await?;
+        let err = plan_and_collect(&mut ctx, "SELECT * FROM u JOIN t ON u.i = t.i")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            DataFusionError::PermissionDenied { principal: Some(p), table, .. }
+                if p == "bob" && table == "datafusion.public.t"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Permission denied: 'bob' may not SELECT from table 'datafusion.public.t'"
+        );
+        assert!(ctx.table("t").is_err());
+
+        let mut ctx = create_ctx(ExecutionConfig::new());
+        let err = plan_and_collect(&mut ctx, "SELECT * FROM t")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Permission denied: anonymous users may not SELECT from table 'datafusion.public.t'"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn table_policies() -> Result<()> {
+        let create_ctx = |config: ExecutionConfig| {
+            let mut ctx = ExecutionContext::with_config(config);
+            ctx.register_table("t", test::table_with_sequence(1, 5).unwrap())
+                .unwrap();
+            ctx.add_row_policy("t", None, col("i").lt_eq(lit(4)));
+            ctx.add_row_policy("t", Some("bob"), col("i").gt_eq(lit(2)));
+            ctx.add_column_mask("t", "i", Some("bob"), col("i") * lit(10));
+            ctx
+        };
+
+        let mut ctx = create_ctx(ExecutionConfig::new().with_principal("alice"));
+        let result = plan_and_collect(&mut ctx, "SELECT t.i FROM t").await?;
+        let expected = vec![
+            "+---+", "| i |", "+---+", "| 1 |", "| 2 |", "| 3 |", "| 4 |", "+---+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+
+        let mut ctx = create_ctx(ExecutionConfig::new().with_principal("bob"));
+        let result =
+            plan_and_collect(&mut ctx, "SELECT i FROM t AS x WHERE x.i > 20").await?;
+        let expected = vec!["+----+", "| i  |", "+----+", "| 30 |", "| 40 |", "+----+"];
+        assert_batches_sorted_eq!(expected, &result);
+
+        let result = ctx.table("t")?.collect().await?;
+        let expected = vec![
+            "+----+", "| i  |", "+----+", "| 20 |", "| 30 |", "| 40 |", "+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn information_schema_tables_no_tables() {
         let mut ctx = ExecutionContext::with_config(
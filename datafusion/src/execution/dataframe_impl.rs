@@ -17,14 +17,16 @@
 
 //! Implementation of DataFrame API.
 
+use std::future::Future;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::arrow::record_batch::RecordBatch;
 use crate::error::Result;
 use crate::execution::context::{ExecutionContext, ExecutionContextState};
 use crate::logical_plan::{
     col, DFSchema, Expr, FunctionRegistry, JoinType, LogicalPlan, LogicalPlanBuilder,
-    Partitioning,
+    Partitioning, Sample, SampleMethod, UNNAMED_TABLE,
 };
 use crate::{
     dataframe::*,
@@ -32,6 +34,10 @@ use crate::{
 };
 
 use crate::arrow::util::pretty;
+use crate::execution::memory_manager::MemoryManager;
+use crate::physical_plan::cancellation::CancellationToken;
+use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use crate::physical_plan::streaming_aggregate::plan_streaming_aggregate;
 use crate::physical_plan::{
     execute_stream, execute_stream_partitioned, ExecutionPlan, SendableRecordBatchStream,
 };
@@ -60,6 +66,37 @@ impl DataFrameImpl {
         let plan = ctx.optimize(&self.plan)?;
         ctx.create_physical_plan(&plan).await
     }
+
+    /// Creates the physical plan whose partitions are merged into a single one,
+    /// which are merged in the order of the partitions when the execution is
+    /// deterministic
+    async fn create_merged_physical_plan(&self) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = self.create_physical_plan().await?;
+        let deterministic = self.ctx_state.lock().unwrap().config.deterministic;
+        if deterministic && plan.output_partitioning().partition_count() > 1 {
+            Ok(Arc::new(CoalescePartitionsExec::new_ordered(plan)))
+        } else {
+            Ok(plan)
+        }
+    }
+
+    /// Runs `execution`, executing the physical plan, with a cancellation token
+    /// cancelled once the statement timeout of the context expires, and a memory
+    /// manager enforcing the memory limit of the context
+    async fn scoped<F: Future>(&self, execution: F) -> F::Output {
+        let (statement_timeout, memory_limit) = {
+            let state = self.ctx_state.lock().unwrap();
+            (state.config.statement_timeout, state.config.memory_limit)
+        };
+        let token = CancellationToken::current().child();
+        if let Some(timeout) = statement_timeout {
+            token.cancel_after(timeout);
+        }
+        let memory_manager = Arc::new(MemoryManager::new(memory_limit));
+        token
+            .scope(MemoryManager::scope(memory_manager, execution))
+            .await
+    }
 }
 
 #[async_trait]
@@ -109,6 +146,21 @@ impl DataFrame for DataFrameImpl {
         Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
     }
 
+    async fn stream_aggregates(
+        &self,
+        group_expr: Vec<Expr>,
+        aggr_expr: Vec<Expr>,
+        window: Duration,
+    ) -> Result<SendableRecordBatchStream> {
+        let plan = LogicalPlanBuilder::from(self.to_logical_plan())
+            .aggregate(group_expr, aggr_expr)?
+            .build()?;
+        let plan = DataFrameImpl::new(self.ctx_state.clone(), &plan)
+            .create_physical_plan()
+            .await?;
+        execute_stream(plan_streaming_aggregate(plan, window)?).await
+    }
+
     /// Limit the number of rows
     fn limit(&self, n: usize) -> Result<Arc<dyn DataFrame>> {
         let plan = LogicalPlanBuilder::from(self.to_logical_plan())
@@ -117,6 +169,16 @@ impl DataFrame for DataFrameImpl {
         Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
     }
 
+    fn sample(&self, fraction: f64, seed: u64) -> Result<Arc<dyn DataFrame>> {
+        let plan = Sample::try_new_plan(
+            self.to_logical_plan(),
+            SampleMethod::Bernoulli,
+            fraction,
+            Some(seed),
+        )?;
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
+
     /// Sort by specified sorting expressions
     fn sort(&self, expr: Vec<Expr>) -> Result<Arc<dyn DataFrame>> {
         let plan = LogicalPlanBuilder::from(self.to_logical_plan())
@@ -161,8 +223,8 @@ impl DataFrame for DataFrameImpl {
     /// Convert the logical plan represented by this DataFrame into a physical plan and
     /// execute it, collecting all resulting batches into memory
     async fn collect(&self) -> Result<Vec<RecordBatch>> {
-        let plan = self.create_physical_plan().await?;
-        Ok(collect(plan).await?)
+        let plan = self.create_merged_physical_plan().await?;
+        Ok(self.scoped(collect(plan)).await?)
     }
 
     /// Print results.
@@ -180,8 +242,8 @@ impl DataFrame for DataFrameImpl {
     /// Convert the logical plan represented by this DataFrame into a physical plan and
     /// execute it, returning a stream over a single partition
     async fn execute_stream(&self) -> Result<SendableRecordBatchStream> {
-        let plan = self.create_physical_plan().await?;
-        execute_stream(plan).await
+        let plan = self.create_merged_physical_plan().await?;
+        self.scoped(execute_stream(plan)).await
     }
 
     /// Convert the logical plan represented by this DataFrame into a physical plan and
@@ -189,14 +251,14 @@ impl DataFrame for DataFrameImpl {
     /// partitioning
     async fn collect_partitioned(&self) -> Result<Vec<Vec<RecordBatch>>> {
         let plan = self.create_physical_plan().await?;
-        Ok(collect_partitioned(plan).await?)
+        Ok(self.scoped(collect_partitioned(plan)).await?)
     }
 
     /// Convert the logical plan represented by this DataFrame into a physical plan and
     /// execute it, returning a stream for each partition
     async fn execute_stream_partitioned(&self) -> Result<Vec<SendableRecordBatchStream>> {
         let plan = self.create_physical_plan().await?;
-        Ok(execute_stream_partitioned(plan).await?)
+        Ok(self.scoped(execute_stream_partitioned(plan)).await?)
     }
 
     /// Returns the schema from the logical plan
@@ -249,6 +311,15 @@ impl DataFrame for DataFrameImpl {
             &LogicalPlanBuilder::except(left_plan, right_plan, true)?,
         )))
     }
+
+    async fn cache(&self) -> Result<Arc<dyn DataFrame>> {
+        let state = self.ctx_state.lock().unwrap().clone();
+        let ctx = ExecutionContext::from(Arc::new(Mutex::new(state)));
+        let plan = ctx.optimize(&self.plan)?;
+        let table = ctx.cache(&plan).await?;
+        let plan = LogicalPlanBuilder::scan(UNNAMED_TABLE, table, None)?.build()?;
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
 }
 
 #[cfg(test)]
@@ -257,6 +328,7 @@ mod tests {
 
     use super::*;
     use crate::execution::options::CsvReadOptions;
+    use crate::physical_plan::common;
     use crate::physical_plan::functions::ScalarFunctionImplementation;
     use crate::physical_plan::functions::Volatility;
     use crate::physical_plan::{window_functions, ColumnarValue};
@@ -355,6 +427,36 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn stream_aggregates() -> Result<()> {
+        let df = test_table().await?;
+        let stream = df
+            .stream_aggregates(
+                vec![col("c1")],
+                vec![min(col("c12")), count(col("c12"))],
+                Duration::from_secs(60),
+            )
+            .await?;
+
+        // the end of the bounded input closes the only window
+        let results = common::collect(stream).await?;
+        assert_batches_sorted_eq!(
+            vec![
+                "+----+-----------------------------+-------------------------------+",
+                "| c1 | MIN(aggregate_test_100.c12) | COUNT(aggregate_test_100.c12) |",
+                "+----+-----------------------------+-------------------------------+",
+                "| a  | 0.02182578039211991         | 21                            |",
+                "| b  | 0.04893135681998029         | 19                            |",
+                "| c  | 0.0494924465469434          | 21                            |",
+                "| d  | 0.061029375346466685        | 18                            |",
+                "| e  | 0.01479305307777301         | 21                            |",
+                "+----+-----------------------------+-------------------------------+",
+            ],
+            &results
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn join() -> Result<()> {
         let left = test_table().await?.select_columns(&["c1", "c2"])?;
@@ -371,6 +473,31 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn cache() -> Result<()> {
+        let df = test_table()
+            .await?
+            .select_columns(&["c1", "c2"])?
+            .filter(col("c2").eq(lit(1)))?;
+        let cached = df.cache().await?;
+
+        // the cached DataFrame scans the materialized results
+        match cached.to_logical_plan() {
+            LogicalPlan::TableScan(TableScan { table_name, .. }) => {
+                assert_eq!(UNNAMED_TABLE, table_name)
+            }
+            plan => panic!("expected a table scan, got {:?}", plan),
+        }
+
+        let expected_rows = df.collect().await?;
+        let cached_rows = cached.filter(col("c2").eq(lit(1)))?.collect().await?;
+        assert_eq!(
+            expected_rows.iter().map(|x| x.num_rows()).sum::<usize>(),
+            cached_rows.iter().map(|x| x.num_rows()).sum::<usize>()
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn limit() -> Result<()> {
         // build query using Table API
@@ -388,6 +515,35 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn sample() -> Result<()> {
+        let t = test_table().await?;
+        let num_rows = |batches: Vec<RecordBatch>| {
+            batches.iter().map(|b| b.num_rows()).sum::<usize>()
+        };
+
+        let sorted_rows = |batches: &[RecordBatch]| -> Result<Vec<String>> {
+            let formatted = pretty::pretty_format_batches(batches)?;
+            let mut rows = formatted.lines().map(String::from).collect::<Vec<_>>();
+            rows.sort();
+            Ok(rows)
+        };
+
+        let sampled = t.sample(0.5, 42)?.collect().await?;
+        let sampled_rows = num_rows(sampled.clone());
+        assert!(sampled_rows > 0 && sampled_rows < 100);
+        // the same seed returns the same rows, in any order
+        assert_eq!(
+            sorted_rows(&sampled)?,
+            sorted_rows(&t.sample(0.5, 42)?.collect().await?)?
+        );
+
+        assert_eq!(0, num_rows(t.sample(0.0, 42)?.collect().await?));
+        assert_eq!(100, num_rows(t.sample(1.0, 42)?.collect().await?));
+        assert!(t.sample(1.5, 42).is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn explain() -> Result<()> {
         // build query using Table API
@@ -0,0 +1,132 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A thread pool for the blocking IO of file scans, separate from the tokio runtime
+//! that executes the CPU bound operators, so that slow reads from disks or object
+//! stores don't starve the compute tasks.
+//!
+//! The pool is shared by the whole process and is started by the first scan, so its
+//! size can only be changed before any scan is executed.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use futures::executor::block_on_stream;
+use lazy_static::lazy_static;
+use log::warn;
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::physical_plan::cancellation::CancellationToken;
+use crate::physical_plan::stream::RecordBatchReceiverStream;
+use crate::physical_plan::SendableRecordBatchStream;
+
+/// The number of IO threads, 0 for twice the number of cores
+static IO_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the IO thread pool is started and can no longer be resized
+static IO_RUNTIME_STARTED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref IO_RUNTIME: Runtime = {
+        IO_RUNTIME_STARTED.store(true, Ordering::SeqCst);
+        let io_threads = match IO_THREADS.load(Ordering::SeqCst) {
+            0 => 2 * num_cpus::get(),
+            n => n,
+        };
+        Builder::new_multi_thread()
+            .worker_threads(1)
+            .max_blocking_threads(io_threads)
+            .thread_name("datafusion-io")
+            .build()
+            .expect("failed to start the IO thread pool")
+    };
+}
+
+/// Sets the maximum number of threads of the IO thread pool. Returns false, and
+/// leaves the pool unchanged, if it was already started by a scan.
+pub fn set_io_threads(io_threads: usize) -> bool {
+    if IO_RUNTIME_STARTED.load(Ordering::SeqCst) {
+        warn!(
+            "The IO thread pool is already started, ignoring its new size {}",
+            io_threads
+        );
+        return false;
+    }
+    IO_THREADS.store(io_threads, Ordering::SeqCst);
+    true
+}
+
+/// Runs the blocking function `f` on the IO thread pool
+pub fn spawn_io<F, R>(f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    IO_RUNTIME.spawn_blocking(f)
+}
+
+/// Polls `stream`, which blocks on IO when polled, on the IO thread pool, and returns
+/// a stream of its batches that doesn't block. Stops polling `stream` once the query
+/// executed by the current task is cancelled.
+pub fn spawn_io_stream(stream: SendableRecordBatchStream) -> SendableRecordBatchStream {
+    let schema = stream.schema();
+    let (tx, rx) = mpsc::channel::<ArrowResult<RecordBatch>>(2);
+    let cancellation = CancellationToken::current();
+    let join_handle = spawn_io(move || {
+        for batch in block_on_stream(stream) {
+            if let Err(e) = cancellation.check() {
+                tx.blocking_send(Err(e.into_arrow_external_error())).ok();
+                break;
+            }
+            if tx.blocking_send(batch).is_err() {
+                // the receiver has been dropped
+                break;
+            }
+        }
+    });
+    RecordBatchReceiverStream::create(&schema, rx, join_handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::common;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::ExecutionPlan;
+    use crate::test;
+
+    #[tokio::test]
+    async fn io_stream() -> crate::error::Result<()> {
+        let batch = test::make_partition(10);
+        let schema = batch.schema();
+        let exec = MemoryExec::try_new(
+            &[vec![batch.clone()], vec![batch.clone(), batch]],
+            schema,
+            None,
+        )?;
+
+        let stream = spawn_io_stream(exec.execute(1).await?);
+        let batches = common::collect(stream).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 20);
+
+        assert_eq!(spawn_io(|| 1 + 1).await.unwrap(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Accounting of the memory used by the operators of a query, to fail the queries
+//! that exceed a memory limit rather than the process running them.
+//!
+//! The [`MemoryManager`] of a query is scoped to the futures executing it, like its
+//! [`CancellationToken`], and the operators buffering their input reserve the memory
+//! of the buffered batches with a [`MemoryReservation`].
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::cancellation::CancellationToken;
+
+tokio::task_local! {
+    static CURRENT: Arc<MemoryManager>;
+}
+
+/// Tracks the memory used by the operators of a query, and cancels the query once
+/// it uses more than its limit
+#[derive(Debug, Default)]
+pub struct MemoryManager {
+    limit: Option<usize>,
+    used: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl MemoryManager {
+    /// Create a memory manager failing the query once it uses more than `limit`
+    /// bytes, `None` to only track the memory of the query
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    /// The memory manager of the query executed by the current task, if any
+    pub fn current() -> Option<Arc<Self>> {
+        CURRENT.try_with(|manager| manager.clone()).ok()
+    }
+
+    /// Runs `future` with `manager` as the memory manager of the current task
+    pub async fn scope<F: Future>(manager: Arc<Self>, future: F) -> F::Output {
+        CURRENT.scope(manager, future).await
+    }
+
+    /// The maximum number of bytes the query may use
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// The number of bytes currently used by the query
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::SeqCst)
+    }
+
+    /// The maximum number of bytes the query used at once
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+
+    /// Reserves `bytes` more bytes for `consumer`, the operator buffering them. If
+    /// the query would exceed its limit, the reservation fails with a
+    /// [`DataFusionError::ResourcesExhausted`] error and the query is cancelled.
+    fn try_grow(&self, consumer: &str, bytes: usize) -> Result<()> {
+        let used = self.used.fetch_add(bytes, Ordering::SeqCst) + bytes;
+        if let Some(limit) = self.limit {
+            if used > limit {
+                self.used.fetch_sub(bytes, Ordering::SeqCst);
+                // stop the other operators of the query
+                CancellationToken::current().cancel();
+                return Err(DataFusionError::ResourcesExhausted(format!(
+                    "{} could not reserve {} bytes, the query already uses {} of its \
+                     {} bytes memory limit",
+                    consumer,
+                    bytes,
+                    used - bytes,
+                    limit
+                )));
+            }
+        }
+        self.peak.fetch_max(used, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn shrink(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::SeqCst);
+    }
+}
+
+/// Memory reserved by an operator from the memory manager of the current query,
+/// released when the reservation is dropped. Reservations made outside of a query
+/// with a memory manager are not accounted.
+#[derive(Debug)]
+pub struct MemoryReservation {
+    manager: Option<Arc<MemoryManager>>,
+    consumer: String,
+    size: usize,
+}
+
+impl MemoryReservation {
+    /// Create an empty reservation of the operator `consumer`, which identifies the
+    /// operator in the errors of the query
+    pub fn new(consumer: impl Into<String>) -> Self {
+        Self {
+            manager: MemoryManager::current(),
+            consumer: consumer.into(),
+            size: 0,
+        }
+    }
+
+    /// The number of bytes reserved
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Reserves `bytes` more bytes
+    pub fn try_grow(&mut self, bytes: usize) -> Result<()> {
+        if let Some(manager) = &self.manager {
+            manager.try_grow(&self.consumer, bytes)?;
+        }
+        self.size += bytes;
+        Ok(())
+    }
+
+    /// Reserves the memory of `batch`
+    pub fn try_grow_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.try_grow(batch_memory_size(batch))
+    }
+
+    /// Releases `bytes` of the reserved bytes
+    pub fn shrink(&mut self, bytes: usize) {
+        let bytes = bytes.min(self.size);
+        if let Some(manager) = &self.manager {
+            manager.shrink(bytes);
+        }
+        self.size -= bytes;
+    }
+
+    /// Releases all the reserved bytes
+    pub fn free(&mut self) {
+        if let Some(manager) = &self.manager {
+            manager.shrink(self.size);
+        }
+        self.size = 0;
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.free();
+    }
+}
+
+/// The number of bytes of the buffers of the columns of `batch`
+pub fn batch_memory_size(batch: &RecordBatch) -> usize {
+    batch
+        .columns()
+        .iter()
+        .map(|array| array.get_array_memory_size())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test;
+
+    #[tokio::test]
+    async fn reserve_within_and_over_limit() {
+        let batch = test::make_partition(100);
+        let size = batch_memory_size(&batch);
+        let manager = Arc::new(MemoryManager::new(Some(size * 2)));
+        let token = CancellationToken::new();
+
+        let result = token
+            .scope(MemoryManager::scope(manager.clone(), async {
+                let mut first = MemoryReservation::new("first");
+                first.try_grow_batch(&batch)?;
+                {
+                    let mut second = MemoryReservation::new("second");
+                    second.try_grow_batch(&batch)?;
+                    assert_eq!(second.size(), size);
+                }
+                let mut third = MemoryReservation::new("third");
+                third.try_grow_batch(&batch)?;
+                third.try_grow_batch(&batch)
+            }))
+            .await;
+
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("third could not reserve"), "{}", message);
+        assert!(token.is_cancelled());
+        assert_eq!(manager.used(), 0);
+        assert_eq!(manager.peak(), size * 2);
+
+        // reservations outside of a query are not accounted
+        let mut reservation = MemoryReservation::new("unaccounted");
+        reservation.try_grow(usize::MAX).unwrap();
+    }
+}
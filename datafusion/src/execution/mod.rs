@@ -19,4 +19,7 @@
 
 pub mod context;
 pub mod dataframe_impl;
+pub mod io_runtime;
+pub mod memory_manager;
 pub mod options;
+pub mod write;
@@ -15,15 +15,19 @@
 // specific language governing permissions and limitations
 // under the License.
 
-//! User facing options for the file formats readers
+//! User facing options for the file formats readers and writers
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use arrow::datatypes::{Schema, SchemaRef};
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use parquet::schema::types::ColumnPath;
 
 use crate::datasource::{
     file_format::{avro::AvroFormat, csv::CsvFormat},
-    listing::ListingOptions,
+    listing::{ListingOptions, DEFAULT_STAT_COLLECTION_CONCURRENCY},
 };
 
 /// CSV file read option
@@ -109,6 +113,9 @@ impl<'a> CsvReadOptions<'a> {
             file_extension: self.file_extension.to_owned(),
             target_partitions,
             table_partition_cols: vec![],
+            bucketing: None,
+            metadata_cache_ttl: None,
+            stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
         }
     }
 }
@@ -144,6 +151,9 @@ impl<'a> AvroReadOptions<'a> {
             file_extension: self.file_extension.to_owned(),
             target_partitions,
             table_partition_cols: vec![],
+            bucketing: None,
+            metadata_cache_ttl: None,
+            stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
         }
     }
 }
@@ -171,3 +181,128 @@ impl<'a> Default for NdJsonReadOptions<'a> {
         }
     }
 }
+
+/// Parquet file write options, see `ExecutionContext::write_parquet`. The columns
+/// are referred to by name, with the names of nested fields separated by `.`.
+#[derive(Clone, Debug)]
+pub struct ParquetWriteOptions {
+    /// The compression codec of the columns. Defaults to `Compression::SNAPPY`.
+    pub compression: Compression,
+    /// The compression codecs of specific columns, overriding `compression`
+    pub column_compression: HashMap<String, Compression>,
+    /// Whether the columns are dictionary encoded. Defaults to true.
+    pub dictionary_enabled: bool,
+    /// Whether specific columns are dictionary encoded, overriding `dictionary_enabled`
+    pub column_dictionary_enabled: HashMap<String, bool>,
+    /// The maximum number of rows of the row groups. Defaults to 1024 * 1024.
+    pub max_row_group_size: usize,
+    /// Whether the min, max and null count statistics of the columns are written,
+    /// for readers to prune row groups. Defaults to true.
+    pub statistics_enabled: bool,
+    /// Whether the statistics of specific columns are written, overriding
+    /// `statistics_enabled`
+    pub column_statistics_enabled: HashMap<String, bool>,
+}
+
+impl Default for ParquetWriteOptions {
+    fn default() -> Self {
+        Self {
+            compression: Compression::SNAPPY,
+            column_compression: HashMap::new(),
+            dictionary_enabled: true,
+            column_dictionary_enabled: HashMap::new(),
+            max_row_group_size: 1024 * 1024,
+            statistics_enabled: true,
+            column_statistics_enabled: HashMap::new(),
+        }
+    }
+}
+
+impl ParquetWriteOptions {
+    /// Create a Parquet write option with default presets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the compression codec of the columns
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Specify the compression codec of a column
+    pub fn column_compression(
+        mut self,
+        column: impl Into<String>,
+        compression: Compression,
+    ) -> Self {
+        self.column_compression.insert(column.into(), compression);
+        self
+    }
+
+    /// Configure whether the columns are dictionary encoded
+    pub fn dictionary_enabled(mut self, enabled: bool) -> Self {
+        self.dictionary_enabled = enabled;
+        self
+    }
+
+    /// Configure whether a column is dictionary encoded
+    pub fn column_dictionary_enabled(
+        mut self,
+        column: impl Into<String>,
+        enabled: bool,
+    ) -> Self {
+        self.column_dictionary_enabled
+            .insert(column.into(), enabled);
+        self
+    }
+
+    /// Specify the maximum number of rows of the row groups
+    pub fn max_row_group_size(mut self, max_row_group_size: usize) -> Self {
+        self.max_row_group_size = max_row_group_size;
+        self
+    }
+
+    /// Configure whether the statistics of the columns are written
+    pub fn statistics_enabled(mut self, enabled: bool) -> Self {
+        self.statistics_enabled = enabled;
+        self
+    }
+
+    /// Configure whether the statistics of a column are written
+    pub fn column_statistics_enabled(
+        mut self,
+        column: impl Into<String>,
+        enabled: bool,
+    ) -> Self {
+        self.column_statistics_enabled
+            .insert(column.into(), enabled);
+        self
+    }
+
+    /// Helper to convert these user facing options to the properties of the Parquet
+    /// writer
+    pub fn to_writer_properties(&self) -> WriterProperties {
+        let mut builder = WriterProperties::builder()
+            .set_compression(self.compression)
+            .set_dictionary_enabled(self.dictionary_enabled)
+            .set_max_row_group_size(self.max_row_group_size)
+            .set_statistics_enabled(self.statistics_enabled);
+        for (column, compression) in &self.column_compression {
+            builder = builder.set_column_compression(column_path(column), *compression);
+        }
+        for (column, enabled) in &self.column_dictionary_enabled {
+            builder =
+                builder.set_column_dictionary_enabled(column_path(column), *enabled);
+        }
+        for (column, enabled) in &self.column_statistics_enabled {
+            builder =
+                builder.set_column_statistics_enabled(column_path(column), *enabled);
+        }
+        builder.build()
+    }
+}
+
+fn column_path(column: &str) -> ColumnPath {
+    ColumnPath::new(column.split('.').map(str::to_owned).collect())
+}
@@ -0,0 +1,575 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Writes the results of queries to the directories of tables, with a commit
+//! protocol making the write all or nothing.
+//!
+//! * the task writing a partition of the results writes its files to a directory
+//!   of its own attempt, `_temporary/{partition}-{attempt}` in a staging directory
+//!   next to the table, and deletes it if it fails.
+//! * once all its files are written and closed, the task commits them by moving
+//!   them to the staging directory.
+//! * once all the tasks are committed, the write is committed by replacing the data
+//!   of the table, or of the written partitions, by the staging directory.
+//!
+//! A failed write deletes the staging directory, so that it leaves no file behind
+//! and the existing data unchanged.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use arrow::array::{ArrayRef, UInt32Array};
+use arrow::compute::take;
+use arrow::csv;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
+use futures::StreamExt;
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use tokio::task::JoinHandle;
+
+use crate::datasource::listing::escape_partition_path_name;
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::WriteMode;
+use crate::physical_plan::{cancellation, ExecutionPlan};
+
+/// The name of the partition directories of the rows whose partition value is null
+pub const DEFAULT_PARTITION_NAME: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// The directory of the staging directory holding the files of the task attempts
+const TEMPORARY_DIR: &str = "_temporary";
+
+/// The format of the written files
+#[derive(Clone)]
+pub(crate) enum WriteFormat {
+    Csv,
+    Parquet(Option<WriterProperties>),
+}
+
+impl WriteFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            WriteFormat::Csv => "csv",
+            WriteFormat::Parquet(_) => "parquet",
+        }
+    }
+
+    fn create_writer(
+        &self,
+        file: fs::File,
+        schema: SchemaRef,
+    ) -> Result<Box<dyn FileWriter>> {
+        Ok(match self {
+            WriteFormat::Csv => Box::new(csv::Writer::new(file)),
+            WriteFormat::Parquet(writer_properties) => Box::new(ArrowWriter::try_new(
+                file,
+                schema,
+                writer_properties.clone(),
+            )?),
+        })
+    }
+}
+
+/// Writes the batches of a file
+trait FileWriter: Send {
+    fn write(&mut self, batch: &RecordBatch) -> Result<()>;
+
+    /// Completes the file, e.g. writes the footer of a Parquet file
+    fn close(self: Box<Self>) -> Result<()>;
+}
+
+impl FileWriter for csv::Writer<fs::File> {
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        Ok(csv::Writer::write(self, batch)?)
+    }
+
+    fn close(self: Box<Self>) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl FileWriter for ArrowWriter<fs::File> {
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        Ok(ArrowWriter::write(self, batch)?)
+    }
+
+    fn close(mut self: Box<Self>) -> Result<()> {
+        ArrowWriter::<fs::File>::close(&mut self)?;
+        Ok(())
+    }
+}
+
+/// Writes the partitions of `plan` to files of `format` in `path`, in Hive style
+/// directories of the `partition_cols` columns, and replaces the existing data of
+/// `path` as specified by `mode`
+pub(crate) async fn write_table(
+    plan: Arc<dyn ExecutionPlan>,
+    path: &Path,
+    partition_cols: &[String],
+    format: WriteFormat,
+    mode: &WriteMode,
+) -> Result<()> {
+    match mode {
+        WriteMode::ErrorIfExists if path.exists() => {
+            return Err(DataFusionError::Execution(format!(
+                "Could not create directory {}: it already exists",
+                path.display()
+            )));
+        }
+        WriteMode::OverwritePartitions(columns) if columns != partition_cols => {
+            return Err(DataFusionError::Plan(format!(
+                "Cannot overwrite the partitions of {:?}, the table is partitioned by {:?}",
+                columns, partition_cols
+            )));
+        }
+        _ => {}
+    }
+
+    let staging = hidden_sibling(path, "staging")?;
+    fs::create_dir(&staging).map_err(|e| {
+        DataFusionError::Execution(format!(
+            "Could not create directory {}: {:?}",
+            staging.display(),
+            e
+        ))
+    })?;
+    let result = match write_files(plan, &staging, partition_cols, format).await {
+        Ok(()) => commit_job(&staging, path, partition_cols.len(), mode),
+        Err(e) => Err(e),
+    };
+    // the staging directory of a failed write, or what was not moved to the table
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    result
+}
+
+/// Writes each partition of `plan` to a `part-{i}` file in `dir`, or to such files
+/// in the partition directories of its rows, with a task committing the files of
+/// each partition
+async fn write_files(
+    plan: Arc<dyn ExecutionPlan>,
+    dir: &Path,
+    partition_cols: &[String],
+    format: WriteFormat,
+) -> Result<()> {
+    let schema = plan.schema();
+    let partition_indices = partition_cols
+        .iter()
+        .map(|column| schema.index_of(column))
+        .collect::<ArrowResult<Vec<_>>>()?;
+    // the partition values are in the paths of the files rather than in the files
+    let file_schema = Arc::new(Schema::new(
+        schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !partition_indices.contains(i))
+            .map(|(_, field)| field.clone())
+            .collect(),
+    ));
+
+    let mut tasks = vec![];
+    for i in 0..plan.output_partitioning().partition_count() {
+        let plan = plan.clone();
+        let attempt_dir =
+            dir.join(TEMPORARY_DIR)
+                .join(format!("{}-{}", i, unique_suffix()));
+        let dir = dir.to_owned();
+        let writer = PartitionedWriter {
+            dir: attempt_dir.clone(),
+            file_name: format!("part-{}.{}", i, format.extension()),
+            partition_indices: partition_indices.clone(),
+            file_schema: file_schema.clone(),
+            format: format.clone(),
+            writers: HashMap::new(),
+        };
+        let handle: JoinHandle<Result<()>> = cancellation::spawn(async move {
+            match write_partition(plan, i, writer).await {
+                Ok(()) => commit_task(&attempt_dir, &dir),
+                Err(e) => {
+                    // the task is aborted
+                    if attempt_dir.exists() {
+                        fs::remove_dir_all(&attempt_dir)?;
+                    }
+                    Err(e)
+                }
+            }
+        });
+        tasks.push(handle);
+    }
+    let mut result = Ok(());
+    for task_result in futures::future::join_all(tasks).await {
+        let task_result = task_result
+            .map_err(|e| DataFusionError::Execution(e.to_string()))
+            .and_then(|task_result| task_result);
+        if result.is_ok() {
+            result = task_result;
+        }
+    }
+    result?;
+
+    let temporary_dir = dir.join(TEMPORARY_DIR);
+    if temporary_dir.exists() {
+        fs::remove_dir_all(temporary_dir)?;
+    }
+    Ok(())
+}
+
+/// Writes a partition of `plan` with `writer`, and closes its files
+async fn write_partition(
+    plan: Arc<dyn ExecutionPlan>,
+    partition: usize,
+    mut writer: PartitionedWriter,
+) -> Result<()> {
+    if writer.partition_indices.is_empty() {
+        // a file is written even if the partition is empty
+        writer.writer(PathBuf::new())?;
+    }
+    let mut stream = plan.execute(partition).await?;
+    while let Some(batch) = stream.next().await {
+        writer.write(&batch?)?;
+    }
+    writer.close()
+}
+
+/// Writes the batches of a partition of a plan to the files of the partition
+/// directories of their rows
+struct PartitionedWriter {
+    dir: PathBuf,
+    file_name: String,
+    partition_indices: Vec<usize>,
+    file_schema: SchemaRef,
+    format: WriteFormat,
+    /// the writers of the files, by partition directory
+    writers: HashMap<PathBuf, Box<dyn FileWriter>>,
+}
+
+impl PartitionedWriter {
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        if self.partition_indices.is_empty() {
+            return self.writer(PathBuf::new())?.write(batch);
+        }
+
+        let mut rows: HashMap<PathBuf, Vec<u32>> = HashMap::new();
+        for row in 0..batch.num_rows() {
+            rows.entry(self.partition_dir(batch, row)?)
+                .or_default()
+                .push(row as u32);
+        }
+        let columns: Vec<&ArrayRef> = batch
+            .columns()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.partition_indices.contains(i))
+            .map(|(_, column)| column)
+            .collect();
+        for (dir, indices) in rows {
+            let indices = UInt32Array::from(indices);
+            let columns = columns
+                .iter()
+                .map(|column| take(column.as_ref(), &indices, None))
+                .collect::<ArrowResult<Vec<_>>>()?;
+            let batch = RecordBatch::try_new(self.file_schema.clone(), columns)?;
+            self.writer(dir)?.write(&batch)?;
+        }
+        Ok(())
+    }
+
+    /// The path of the partition directory of a row, e.g. `year=2021/month=12`, with
+    /// the names and values escaped like Hive does so that they are read back as is
+    fn partition_dir(&self, batch: &RecordBatch, row: usize) -> Result<PathBuf> {
+        let schema = batch.schema();
+        let mut dir = PathBuf::new();
+        for i in &self.partition_indices {
+            let column = batch.column(*i);
+            let value = if column.is_null(row) {
+                DEFAULT_PARTITION_NAME.to_owned()
+            } else {
+                array_value_to_string(column, row)?
+            };
+            if value.is_empty() {
+                return Err(DataFusionError::Execution(format!(
+                    "Invalid value '{}' of partition column {}",
+                    value,
+                    schema.field(*i).name()
+                )));
+            }
+            dir.push(format!(
+                "{}={}",
+                escape_partition_path_name(schema.field(*i).name()),
+                escape_partition_path_name(&value)
+            ));
+        }
+        Ok(dir)
+    }
+
+    /// The writer of the file of a partition directory, created on first use
+    fn writer(&mut self, dir: PathBuf) -> Result<&mut Box<dyn FileWriter>> {
+        match self.writers.entry(dir) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let dir = self.dir.join(entry.key());
+                fs::create_dir_all(&dir)?;
+                let file = fs::File::create(dir.join(&self.file_name))?;
+                let writer = self.format.create_writer(file, self.file_schema.clone())?;
+                Ok(entry.insert(writer))
+            }
+        }
+    }
+
+    fn close(self) -> Result<()> {
+        for (_, writer) in self.writers {
+            writer.close()?;
+        }
+        Ok(())
+    }
+}
+
+/// Commits the files written by a task attempt to `attempt_dir`, by moving them to
+/// the same paths in `dir`
+fn commit_task(attempt_dir: &Path, dir: &Path) -> Result<()> {
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative_dir) = dirs.pop() {
+        for entry in fs::read_dir(attempt_dir.join(&relative_dir))? {
+            let entry = entry?;
+            let path = relative_dir.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                fs::create_dir_all(dir.join(&path))?;
+                dirs.push(path);
+            } else {
+                fs::rename(entry.path(), dir.join(&path))?;
+            }
+        }
+    }
+    Ok(fs::remove_dir_all(attempt_dir)?)
+}
+
+/// Replaces the data of `path` by the files committed to `staging`, the whole
+/// directory or each of the partition directories written to `staging`
+fn commit_job(
+    staging: &Path,
+    path: &Path,
+    partition_depth: usize,
+    mode: &WriteMode,
+) -> Result<()> {
+    match mode {
+        WriteMode::ErrorIfExists => Ok(fs::rename(staging, path)?),
+        WriteMode::Overwrite => swap_dir(staging, path),
+        WriteMode::OverwritePartitions(_) => {
+            for dir in partition_dirs(staging, partition_depth)? {
+                let target = path.join(&dir);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                swap_dir(&staging.join(&dir), &target)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Moves the `source` directory to `target`, replacing the existing `target`
+/// directory if any. The existing directory is moved aside first, and restored if
+/// `source` can't be moved.
+fn swap_dir(source: &Path, target: &Path) -> Result<()> {
+    if !target.exists() {
+        return Ok(fs::rename(source, target)?);
+    }
+    let backup = hidden_sibling(target, "backup")?;
+    fs::rename(target, &backup)?;
+    if let Err(e) = fs::rename(source, target) {
+        fs::rename(&backup, target)?;
+        return Err(e.into());
+    }
+    Ok(fs::remove_dir_all(&backup)?)
+}
+
+/// The paths of the directories `depth` levels below `root`, relative to `root`
+fn partition_dirs(root: &Path, depth: usize) -> Result<Vec<PathBuf>> {
+    let mut dirs = vec![PathBuf::new()];
+    for _ in 0..depth {
+        let mut children = vec![];
+        for dir in dirs {
+            for entry in fs::read_dir(root.join(&dir))? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    children.push(dir.join(entry.file_name()));
+                }
+            }
+        }
+        dirs = children;
+    }
+    Ok(dirs)
+}
+
+/// A unique hidden path next to `path`, e.g. `.table.staging-1234-5678` for
+/// `table`, on the same file system so that it can be renamed to `path`
+fn hidden_sibling(path: &Path, kind: &str) -> Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        DataFusionError::Execution(format!("Invalid directory {}", path.display()))
+    })?;
+    Ok(path.with_file_name(format!(
+        ".{}.{}-{}",
+        name.to_string_lossy(),
+        kind,
+        unique_suffix()
+    )))
+}
+
+/// A suffix making the names of the directories of a process unique
+fn unique_suffix() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    format!("{}-{}", std::process::id(), nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::file_format::parquet::ParquetFormat;
+    use crate::datasource::listing::ListingOptions;
+    use crate::datasource::MemTable;
+    use crate::execution::context::ExecutionContext;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn failed_write_leaves_nothing() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let batch = |a: Vec<i32>, b: Vec<i32>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from(a)) as ArrayRef,
+                    Arc::new(Int32Array::from(b)),
+                ],
+            )
+        };
+        // the second partition fails, dividing by zero
+        let table = MemTable::try_new(
+            schema.clone(),
+            vec![
+                vec![batch(vec![1, 2], vec![1, 2])?],
+                vec![batch(vec![3, 4], vec![0, 1])?],
+            ],
+        )?;
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", Arc::new(table))?;
+        let plan = ctx.create_logical_plan("SELECT a / b AS c FROM t")?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+
+        let tmp_dir = TempDir::new()?;
+        let out_dir = tmp_dir.path().join("out");
+        let result = ctx.write_csv(plan.clone(), out_dir.to_str().unwrap()).await;
+        assert!(result.is_err());
+        assert_eq!(fs::read_dir(&tmp_dir)?.count(), 0);
+
+        // the existing data of an overwritten directory is kept
+        fs::create_dir(&out_dir)?;
+        fs::write(out_dir.join("part-0.parquet"), "existing")?;
+        let result = ctx
+            .write_parquet_with_mode(
+                plan,
+                out_dir.to_str().unwrap(),
+                &[],
+                None,
+                &WriteMode::Overwrite,
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(fs::read_dir(&tmp_dir)?.count(), 1);
+        assert_eq!(fs::read_dir(&out_dir)?.count(), 1);
+        assert_eq!(
+            fs::read_to_string(out_dir.join("part-0.parquet"))?,
+            "existing"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn partition_values_are_escaped() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("p", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["a=b/c", "100%", "a=b/c"])),
+            ],
+        )?;
+        let table = MemTable::try_new(schema, vec![vec![batch]])?;
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", Arc::new(table))?;
+        let plan = ctx.create_logical_plan("SELECT a, p FROM t")?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+
+        let tmp_dir = TempDir::new()?;
+        let out_dir = tmp_dir.path().join("out");
+        let out_dir = out_dir.to_str().unwrap();
+        ctx.write_parquet_with_mode(
+            plan,
+            out_dir,
+            &["p".to_owned()],
+            None,
+            &WriteMode::ErrorIfExists,
+        )
+        .await?;
+        let mut dirs = fs::read_dir(out_dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<Vec<_>>>()?;
+        dirs.sort();
+        assert_eq!(dirs, vec!["p=100%25", "p=a%3Db%2Fc"]);
+
+        // the partition values are read back as they were written
+        let options = ListingOptions {
+            table_partition_cols: vec!["p".to_owned()],
+            ..ListingOptions::new(Arc::new(ParquetFormat::default()))
+        };
+        ctx.register_listing_table("w", out_dir, options, None)
+            .await?;
+        let results = ctx
+            .sql("SELECT p, SUM(a) FROM w GROUP BY p ORDER BY p")
+            .await?
+            .collect()
+            .await?;
+        let expected = vec![
+            "+-------+----------+",
+            "| p     | SUM(w.a) |",
+            "+-------+----------+",
+            "| 100%  | 2        |",
+            "| a=b/c | 4        |",
+            "+-------+----------+",
+        ];
+        crate::assert_batches_eq!(expected, &results);
+        Ok(())
+    }
+}
@@ -0,0 +1,502 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Exchange of record batches with the hosts embedding DataFusion, such as Python,
+//! Java or C++ processes, through the [Arrow C Data Interface], without copying
+//! their buffers.
+//!
+//! A record batch is exchanged as a struct array, whose fields are the columns of
+//! the batch, the same way `pyarrow.RecordBatch._export_to_c` does. The host
+//! allocates the `FFI_ArrowArray` and `FFI_ArrowSchema` structs, and they are
+//! moved from the producer to the consumer, which releases them once done.
+//!
+//! ```
+//! # use datafusion::prelude::*;
+//! # use datafusion::error::Result;
+//! # use datafusion::arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
+//! # use datafusion::ffi::{export_record_batch, import_record_batch};
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let mut ctx = ExecutionContext::new();
+//! let df = ctx.sql("SELECT 1 AS a").await?;
+//! let batches = df.collect().await?;
+//!
+//! // the structs are usually allocated by the host
+//! let mut array = FFI_ArrowArray::empty();
+//! let mut schema = FFI_ArrowSchema::empty();
+//! unsafe {
+//!     export_record_batch(&batches[0], &mut array, &mut schema)?;
+//!     let batch = import_record_batch(&mut array, &mut schema)?;
+//!     assert_eq!(batch.num_rows(), 1);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The `datafusion_*` functions expose the same to C hosts, which link the
+//! libraries of the `datafusion-c` crate and include its `datafusion.h` header:
+//! they create contexts, register the exported batches as tables and run SQL
+//! queries whose results are exported back. The functions failing return `NULL`
+//! or `-1`, and [`datafusion_last_error`] returns the error.
+//!
+//! [Arrow C Data Interface]: https://arrow.apache.org/docs/format/CDataInterface.html
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayData, StructArray};
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::ffi::{ArrowArray, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::record_batch::RecordBatch;
+
+use crate::datasource::MemTable;
+use crate::error::{DataFusionError, Result};
+use crate::execution::context::ExecutionContext;
+
+/// Exports `batch` to the C data interface structs pointed to by `out_array` and
+/// `out_schema`, which the consumer releases once it is done with the batch. The
+/// buffers of the batch are shared with the consumer rather than copied.
+///
+/// # Safety
+///
+/// `out_array` and `out_schema` must be valid for writes, and must not point to
+/// structs that were not released yet, as they are overwritten.
+pub unsafe fn export_record_batch(
+    batch: &RecordBatch,
+    out_array: *mut FFI_ArrowArray,
+    out_schema: *mut FFI_ArrowSchema,
+) -> Result<()> {
+    if out_array.is_null() || out_schema.is_null() {
+        return Err(DataFusionError::Execution(
+            "Cannot export a record batch to null pointers".to_owned(),
+        ));
+    }
+    let array = StructArray::from(batch.clone());
+    let array = ArrowArray::try_from(array.data().clone())?;
+    let (array, schema) = ArrowArray::into_raw(array);
+    // the structs were just created, so there is no other reference to them
+    let array = Arc::try_unwrap(Arc::from_raw(array)).ok().unwrap();
+    let schema = Arc::try_unwrap(Arc::from_raw(schema)).ok().unwrap();
+    std::ptr::write(out_array, array);
+    std::ptr::write(out_schema, schema);
+    Ok(())
+}
+
+/// Imports the record batch exported to the C data interface structs pointed to by
+/// `array` and `schema`. The structs are moved, which leaves them released, and the
+/// buffers of the batch stay owned by the producer until the batch is dropped.
+///
+/// # Safety
+///
+/// `array` and `schema` must point to valid C data interface structs exporting a
+/// struct array, as defined by the Arrow C data interface.
+pub unsafe fn import_record_batch(
+    array: *mut FFI_ArrowArray,
+    schema: *mut FFI_ArrowSchema,
+) -> Result<RecordBatch> {
+    if array.is_null() || schema.is_null() {
+        return Err(DataFusionError::Execution(
+            "Cannot import a record batch from null pointers".to_owned(),
+        ));
+    }
+    let array = std::ptr::replace(array, FFI_ArrowArray::empty());
+    let schema = std::ptr::replace(schema, FFI_ArrowSchema::empty());
+    let array = ArrowArray::try_from_raw(
+        Arc::into_raw(Arc::new(array)),
+        Arc::into_raw(Arc::new(schema)),
+    )?;
+    let data = ArrayData::try_from(array)?;
+    match data.data_type() {
+        DataType::Struct(_) => Ok(RecordBatch::from(&StructArray::from(data))),
+        other => Err(DataFusionError::Execution(format!(
+            "Cannot import a record batch from an array of type {:?} instead of a struct",
+            other
+        ))),
+    }
+}
+
+/// Imports the record batches exported to pairs of C data interface structs as an
+/// in-memory table of `schema`, with one partition, that can be registered to an
+/// [`ExecutionContext`](crate::execution::context::ExecutionContext) as the input
+/// of queries.
+///
+/// # Safety
+///
+/// Each pair must point to valid C data interface structs, as for
+/// [`import_record_batch`].
+pub unsafe fn import_mem_table(
+    schema: SchemaRef,
+    batches: &[(*mut FFI_ArrowArray, *mut FFI_ArrowSchema)],
+) -> Result<MemTable> {
+    let batches = batches
+        .iter()
+        .map(|(array, batch_schema)| {
+            let batch = import_record_batch(*array, *batch_schema)?;
+            // the imported batch does not keep the metadata of the schema
+            Ok(RecordBatch::try_new(
+                schema.clone(),
+                batch.columns().to_vec(),
+            )?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    MemTable::try_new(schema, vec![batches])
+}
+
+/// Execution context created by [`datafusion_context_new`], with the runtime its
+/// queries are run on
+pub struct DataFusionContext {
+    ctx: ExecutionContext,
+    runtime: tokio::runtime::Runtime,
+}
+
+/// Record batches output by a query run by [`datafusion_sql`]
+pub struct DataFusionResult {
+    batches: Vec<RecordBatch>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Runs `f`, recording its error as the last error of the thread, and catching
+/// its panics as they must not unwind into the host
+fn ffi_call<T>(f: impl FnOnce() -> Result<T>) -> Option<T> {
+    let error = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => return Some(value),
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => "DataFusion panicked".to_owned(),
+    };
+    // the message cannot be passed to C with its nul bytes
+    let error = CString::new(error.replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(error));
+    None
+}
+
+/// Reads the nul terminated UTF-8 string `s`
+unsafe fn read_str<'a>(s: *const c_char, what: &str) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(DataFusionError::Execution(format!("The {} is null", what)));
+    }
+    CStr::from_ptr(s).to_str().map_err(|e| {
+        DataFusionError::Execution(format!("The {} is not valid UTF-8: {}", what, e))
+    })
+}
+
+/// Returns the error of the last function called by this thread which failed,
+/// valid until the next failure, or `NULL` if none failed
+#[no_mangle]
+pub extern "C" fn datafusion_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |error| error.as_ptr())
+    })
+}
+
+/// Creates an execution context, freed by [`datafusion_context_free`], or returns
+/// `NULL` on error
+#[no_mangle]
+pub extern "C" fn datafusion_context_new() -> *mut DataFusionContext {
+    ffi_call(|| {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        Ok(Box::into_raw(Box::new(DataFusionContext {
+            ctx: ExecutionContext::new(),
+            runtime,
+        })))
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Frees a context created by [`datafusion_context_new`]
+///
+/// # Safety
+///
+/// `ctx` must be `NULL` or a context which was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_context_free(ctx: *mut DataFusionContext) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+/// Registers the `count` record batches exported to `arrays` and `schemas` as the
+/// table `name`, of the schema of the first one, and returns 0, or -1 on error.
+/// The structs are moved, as for [`import_record_batch`], unless the arguments
+/// are null.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context, `name` a nul terminated string, and `arrays` and
+/// `schemas` must point to `count` valid C data interface structs each.
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_register_record_batches(
+    ctx: *mut DataFusionContext,
+    name: *const c_char,
+    arrays: *mut FFI_ArrowArray,
+    schemas: *mut FFI_ArrowSchema,
+    count: usize,
+) -> c_int {
+    let registered = ffi_call(|| {
+        let ctx = ctx.as_mut().ok_or_else(|| {
+            DataFusionError::Execution("The context is null".to_owned())
+        })?;
+        let name = read_str(name, "table name")?;
+        if count == 0 || arrays.is_null() || schemas.is_null() {
+            return Err(DataFusionError::Execution(
+                "At least one record batch is needed to know the schema of the table"
+                    .to_owned(),
+            ));
+        }
+        // all the structs are imported, so that they are released even on error
+        let batches = (0..count)
+            .map(|i| import_record_batch(arrays.add(i), schemas.add(i)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        let table = MemTable::try_new(batches[0].schema(), vec![batches])?;
+        ctx.ctx.register_table(name, Arc::new(table))?;
+        Ok(())
+    });
+    registered.map_or(-1, |_| 0)
+}
+
+/// Runs the SQL query `sql` and returns its result, freed by
+/// [`datafusion_result_free`], or `NULL` on error
+///
+/// # Safety
+///
+/// `ctx` must be a valid context and `sql` a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_sql(
+    ctx: *mut DataFusionContext,
+    sql: *const c_char,
+) -> *mut DataFusionResult {
+    ffi_call(|| {
+        let ctx = ctx.as_mut().ok_or_else(|| {
+            DataFusionError::Execution("The context is null".to_owned())
+        })?;
+        let sql = read_str(sql, "query")?;
+        let DataFusionContext { ctx, runtime } = ctx;
+        let batches = runtime.block_on(async { ctx.sql(sql).await?.collect().await })?;
+        Ok(Box::into_raw(Box::new(DataFusionResult { batches })))
+    })
+    .unwrap_or(std::ptr::null_mut())
+}
+
+/// Returns the number of record batches of `result`
+///
+/// # Safety
+///
+/// `result` must be a valid result.
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_result_num_batches(
+    result: *const DataFusionResult,
+) -> usize {
+    result.as_ref().map_or(0, |result| result.batches.len())
+}
+
+/// Exports the record batch `index` of `result`, as [`export_record_batch`] does,
+/// and returns 0, or -1 on error
+///
+/// # Safety
+///
+/// `result` must be a valid result, and `out_array` and `out_schema` must be as
+/// for [`export_record_batch`].
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_result_export_batch(
+    result: *const DataFusionResult,
+    index: usize,
+    out_array: *mut FFI_ArrowArray,
+    out_schema: *mut FFI_ArrowSchema,
+) -> c_int {
+    let exported = ffi_call(|| {
+        let batch = result
+            .as_ref()
+            .and_then(|result| result.batches.get(index))
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "The result has no record batch {}",
+                    index
+                ))
+            })?;
+        export_record_batch(batch, out_array, out_schema)
+    });
+    exported.map_or(-1, |_| 0)
+}
+
+/// Frees a result returned by [`datafusion_sql`], the exported batches staying
+/// valid until they are released
+///
+/// # Safety
+///
+/// `result` must be `NULL` or a result which was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_result_free(result: *mut DataFusionResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_batches_sorted_eq;
+    use crate::execution::context::ExecutionContext;
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+
+    #[tokio::test]
+    async fn query_imported_table() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = |a: Vec<i32>, b: Vec<Option<&str>>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from(a)) as ArrayRef,
+                    Arc::new(StringArray::from(b)),
+                ],
+            )
+        };
+        let batches = vec![
+            batch(vec![1, 2], vec![Some("x"), None])?,
+            batch(vec![3], vec![Some("y")])?,
+        ];
+
+        // the structs a host would allocate
+        let mut structs: Vec<_> = batches
+            .iter()
+            .map(|_| (FFI_ArrowArray::empty(), FFI_ArrowSchema::empty()))
+            .collect();
+        let table = unsafe {
+            for (batch, (array, schema)) in batches.iter().zip(&mut structs) {
+                export_record_batch(batch, array, schema)?;
+            }
+            let pointers: Vec<_> = structs
+                .iter_mut()
+                .map(|(array, schema)| (array as *mut _, schema as *mut _))
+                .collect();
+            import_mem_table(schema.clone(), &pointers)?
+        };
+
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", Arc::new(table))?;
+        let results = ctx
+            .sql("SELECT a * 2 AS a2, b FROM t WHERE a > 1")
+            .await?
+            .collect()
+            .await?;
+
+        let mut exported = Vec::with_capacity(results.len());
+        for result in &results {
+            let mut array = FFI_ArrowArray::empty();
+            let mut schema = FFI_ArrowSchema::empty();
+            unsafe {
+                export_record_batch(result, &mut array, &mut schema)?;
+                exported.push(import_record_batch(&mut array, &mut schema)?);
+            }
+        }
+        let expected = vec![
+            "+----+---+",
+            "| a2 | b |",
+            "+----+---+",
+            "| 4  |   |",
+            "| 6  | y |",
+            "+----+---+",
+        ];
+        assert_batches_sorted_eq!(expected, &exported);
+        Ok(())
+    }
+
+    #[test]
+    fn query_through_c_functions() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )?;
+        let mut array = FFI_ArrowArray::empty();
+        let mut schema = FFI_ArrowSchema::empty();
+        let name = CString::new("t").unwrap();
+        let sql = CString::new("SELECT SUM(a) AS s FROM t").unwrap();
+        let unknown = CString::new("SELECT * FROM unknown").unwrap();
+
+        let batches = unsafe {
+            let ctx = datafusion_context_new();
+            assert!(!ctx.is_null());
+            export_record_batch(&batch, &mut array, &mut schema)?;
+            let registered = datafusion_register_record_batches(
+                ctx,
+                name.as_ptr(),
+                &mut array,
+                &mut schema,
+                1,
+            );
+            assert_eq!(registered, 0);
+
+            assert!(datafusion_sql(ctx, unknown.as_ptr()).is_null());
+            let error = CStr::from_ptr(datafusion_last_error());
+            assert!(error.to_str().unwrap().contains("unknown"));
+
+            let result = datafusion_sql(ctx, sql.as_ptr());
+            assert!(!result.is_null());
+            let mut batches = vec![];
+            for i in 0..datafusion_result_num_batches(result) {
+                let mut array = FFI_ArrowArray::empty();
+                let mut schema = FFI_ArrowSchema::empty();
+                let exported =
+                    datafusion_result_export_batch(result, i, &mut array, &mut schema);
+                assert_eq!(exported, 0);
+                batches.push(import_record_batch(&mut array, &mut schema)?);
+            }
+            let mut array = FFI_ArrowArray::empty();
+            let mut schema = FFI_ArrowSchema::empty();
+            let exported =
+                datafusion_result_export_batch(result, 42, &mut array, &mut schema);
+            assert_eq!(exported, -1);
+            datafusion_result_free(result);
+            datafusion_context_free(ctx);
+            batches
+        };
+        let expected = vec!["+---+", "| s |", "+---+", "| 6 |", "+---+"];
+        assert_batches_sorted_eq!(expected, &batches);
+        Ok(())
+    }
+
+    #[test]
+    fn import_non_struct_array() -> Result<()> {
+        let data = Int32Array::from(vec![1, 2]).data().clone();
+        let (array, schema) = ArrowArray::into_raw(ArrowArray::try_from(data)?);
+        let mut array = Arc::try_unwrap(unsafe { Arc::from_raw(array) })
+            .ok()
+            .unwrap();
+        let mut schema = Arc::try_unwrap(unsafe { Arc::from_raw(schema) })
+            .ok()
+            .unwrap();
+        let result = unsafe { import_record_batch(&mut array, &mut schema) };
+        assert!(result.is_err());
+        Ok(())
+    }
+}
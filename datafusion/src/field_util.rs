@@ -17,15 +17,17 @@
 
 //! Utility functions for complex field access
 
+use arrow::compute::can_cast_types;
 use arrow::datatypes::{DataType, Field};
 
 use crate::error::{DataFusionError, Result};
 use crate::scalar::ScalarValue;
 
-/// Returns the field access indexed by `key` from a [`DataType::List`] or [`DataType::Struct`]
+/// Returns the field access indexed by `key` from a [`DataType::List`], [`DataType::Struct`]
+/// or [`DataType::Map`]
 /// # Error
 /// Errors if
-/// * the `data_type` is not a Struct or,
+/// * the `data_type` is not a List, Struct or Map or,
 /// * there is no field key is not of the required index type
 pub fn get_indexed_field(data_type: &DataType, key: &ScalarValue) -> Result<Field> {
     match (data_type, key) {
@@ -55,6 +57,26 @@ pub fn get_indexed_field(data_type: &DataType, key: &ScalarValue) -> Result<Fiel
                 }
             }
         }
+        (DataType::Map(entries, _), key) if !key.is_null() => {
+            let (key_field, value_field) = map_entry_fields(entries)?;
+            if can_cast_types(&key.get_datatype(), key_field.data_type()) {
+                // the value of a key missing from the map is null
+                Ok(Field::new(
+                    &key.to_string(),
+                    value_field.data_type().clone(),
+                    true,
+                ))
+            } else {
+                Err(DataFusionError::Plan(format!(
+                    "Map with {:?} keys cannot be indexed with {:?}",
+                    key_field.data_type(),
+                    key.get_datatype()
+                )))
+            }
+        }
+        (DataType::Map(_, _), _) => Err(DataFusionError::Plan(
+            "Map based indexed access requires a non null key".to_string(),
+        )),
         (DataType::Struct(_), _) => Err(DataFusionError::Plan(
             "Only utf8 strings are valid as an indexed field in a struct".to_string(),
         )),
@@ -62,8 +84,19 @@ pub fn get_indexed_field(data_type: &DataType, key: &ScalarValue) -> Result<Fiel
             "Only ints are valid as an indexed field in a list".to_string(),
         )),
         _ => Err(DataFusionError::Plan(
-            "The expression to get an indexed field is only valid for `List` types"
+            "The expression to get an indexed field is only valid for `List`, `Struct` and `Map` types"
                 .to_string(),
         )),
     }
 }
+
+/// Returns the key and value fields of the `entries` struct field of a [`DataType::Map`]
+pub fn map_entry_fields(entries: &Field) -> Result<(&Field, &Field)> {
+    match entries.data_type() {
+        DataType::Struct(fields) if fields.len() == 2 => Ok((&fields[0], &fields[1])),
+        other => Err(DataFusionError::Internal(format!(
+            "The entries of a map must be a struct of a key and a value, not {:?}",
+            other
+        ))),
+    }
+}
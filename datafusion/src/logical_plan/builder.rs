@@ -20,7 +20,7 @@
 use crate::datasource::{
     empty::EmptyTable,
     file_format::parquet::{ParquetFormat, DEFAULT_PARQUET_EXTENSION},
-    listing::{ListingOptions, ListingTable},
+    listing::{ListingOptions, ListingTable, DEFAULT_STAT_COLLECTION_CONCURRENCY},
     object_store::ObjectStore,
     MemTable, TableProvider,
 };
@@ -30,6 +30,7 @@ use crate::logical_plan::plan::{
     TableScan, ToStringifiedPlan, Union, Window,
 };
 use crate::optimizer::utils;
+use crate::physical_plan::window_functions::{BuiltInWindowFunction, WindowFunction};
 use crate::prelude::*;
 use crate::scalar::ScalarValue;
 use arrow::{
@@ -44,10 +45,13 @@ use std::{
 };
 
 use super::dfschema::ToDFSchema;
-use super::{exprlist_to_fields, Expr, JoinConstraint, JoinType, LogicalPlan, PlanType};
+use super::{
+    exprlist_to_fields, ExplainFormat, Expr, JoinConstraint, JoinType, LogicalPlan,
+    PlanType,
+};
 use crate::logical_plan::{
-    columnize_expr, normalize_col, normalize_cols, Column, CrossJoin, DFField, DFSchema,
-    DFSchemaRef, Limit, Partitioning, Repartition, Values,
+    columnize_expr, normalize_col, normalize_cols, when, Column, CrossJoin, DFField,
+    DFSchema, DFSchemaRef, Limit, Partitioning, Repartition, Values,
 };
 use crate::sql::utils::group_window_expr_by_sort_keys;
 
@@ -283,6 +287,9 @@ impl LogicalPlanBuilder {
             file_extension: DEFAULT_PARQUET_EXTENSION.to_owned(),
             target_partitions,
             table_partition_cols: vec![],
+            bucketing: None,
+            metadata_cache_ttl: None,
+            stat_collection_concurrency: DEFAULT_STAT_COLLECTION_CONCURRENCY,
         };
 
         let path: String = path.into();
@@ -401,6 +408,7 @@ impl LogicalPlanBuilder {
             source: provider,
             projected_schema: Arc::new(projected_schema),
             projection,
+            nested_projection: vec![],
             filters,
             limit: None,
         });
@@ -790,6 +798,35 @@ impl LogicalPlanBuilder {
     ///
     /// if `verbose` is true, prints out additional details.
     pub fn explain(&self, verbose: bool, analyze: bool) -> Result<Self> {
+        self.explain_with_format(verbose, analyze, ExplainFormat::Indent)
+    }
+
+    /// Create an expression to represent the explanation of the plan,
+    /// with the plans formatted as `format`.
+    ///
+    /// See [`Self::explain`] for `verbose` and `analyze`.
+    pub fn explain_with_format(
+        &self,
+        verbose: bool,
+        analyze: bool,
+        format: ExplainFormat,
+    ) -> Result<Self> {
+        self.explain_with_costs(verbose, analyze, format, false)
+    }
+
+    /// Create an expression to represent the explanation of the plan, with the
+    /// plans formatted as `format`, where the physical plans show the estimated
+    /// rows and bytes of each node if `costs` is true.
+    ///
+    /// See [`Self::explain`] for `verbose` and `analyze`. The costs are not shown
+    /// when analyzing, as the actual metrics are shown instead.
+    pub fn explain_with_costs(
+        &self,
+        verbose: bool,
+        analyze: bool,
+        format: ExplainFormat,
+        costs: bool,
+    ) -> Result<Self> {
         let schema = LogicalPlan::explain_schema();
         let schema = schema.to_dfschema_ref()?;
 
@@ -797,16 +834,20 @@ impl LogicalPlanBuilder {
             Ok(Self::from(LogicalPlan::Analyze(Analyze {
                 verbose,
                 input: Arc::new(self.plan.clone()),
+                format,
                 schema,
             })))
         } else {
-            let stringified_plans =
-                vec![self.plan.to_stringified(PlanType::InitialLogicalPlan)];
+            let stringified_plans = vec![self
+                .plan
+                .to_stringified_with_format(PlanType::InitialLogicalPlan, format)];
 
             Ok(Self::from(LogicalPlan::Explain(Explain {
                 verbose,
                 plan: Arc::new(self.plan.clone()),
                 stringified_plans,
+                format,
+                costs,
                 schema,
             })))
         }
@@ -841,13 +882,33 @@ impl LogicalPlanBuilder {
     }
 
     /// Process intersect or except
+    ///
+    /// The ALL variants keep duplicate rows: for a row that occurs `m` times in the left
+    /// and `n` times in the right input, INTERSECT ALL returns `min(m, n)` and EXCEPT ALL
+    /// returns `max(m - n, 0)` copies of the row. They are planned as a semi or anti join
+    /// that also matches the occurrence number of the rows, computed with a
+    /// `ROW_NUMBER()` window partitioned by all columns.
     fn intersect_or_except(
         left_plan: LogicalPlan,
         right_plan: LogicalPlan,
         join_type: JoinType,
         is_all: bool,
     ) -> Result<LogicalPlan> {
-        let join_keys = left_plan
+        let left_len = left_plan.schema().fields().len();
+        let right_len = right_plan.schema().fields().len();
+        if left_len != right_len {
+            return Err(DataFusionError::Plan(format!(
+                "{} requires both inputs to have the same number of columns, got {} and {}",
+                if join_type == JoinType::Semi {
+                    "INTERSECT"
+                } else {
+                    "EXCEPT"
+                },
+                left_len,
+                right_len
+            )));
+        }
+        let (mut left_keys, mut right_keys): (Vec<Column>, Vec<Column>) = left_plan
             .schema()
             .fields()
             .iter()
@@ -860,13 +921,27 @@ impl LogicalPlanBuilder {
             })
             .unzip();
         if is_all {
-            LogicalPlanBuilder::from(left_plan)
-                .join_detailed(&right_plan, join_type, join_keys, true)?
+            let output_columns = left_plan
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| Expr::Column(f.qualified_column()))
+                .collect::<Vec<_>>();
+            left_keys.push(Column::from_name(OCCURRENCE_COLUMN));
+            right_keys.push(Column::from_name(OCCURRENCE_COLUMN));
+            LogicalPlanBuilder::from(with_occurrence_number(left_plan)?)
+                .join_detailed(
+                    &with_occurrence_number(right_plan)?,
+                    join_type,
+                    (left_keys, right_keys),
+                    true,
+                )?
+                .project(output_columns)?
                 .build()
         } else {
             LogicalPlanBuilder::from(left_plan)
                 .distinct()?
-                .join_detailed(&right_plan, join_type, join_keys, true)?
+                .join_detailed(&right_plan, join_type, (left_keys, right_keys), true)?
                 .build()
         }
     }
@@ -877,6 +952,32 @@ impl LogicalPlanBuilder {
     }
 }
 
+/// Name of the column that numbers the duplicates of each row for INTERSECT ALL and
+/// EXCEPT ALL
+const OCCURRENCE_COLUMN: &str = "__occurrence";
+
+/// Adds the [`OCCURRENCE_COLUMN`] to `plan`, which numbers the occurrences of each
+/// distinct row from 1
+fn with_occurrence_number(plan: LogicalPlan) -> Result<LogicalPlan> {
+    let columns = plan
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| Expr::Column(f.qualified_column()))
+        .collect();
+    let row_number = Expr::WindowFunction {
+        fun: WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber),
+        args: vec![],
+        partition_by: columns,
+        order_by: vec![],
+        window_frame: None,
+    }
+    .alias(OCCURRENCE_COLUMN);
+    LogicalPlanBuilder::from(plan)
+        .window(vec![row_number])?
+        .build()
+}
+
 /// Creates a schema for a join operation.
 /// The fields from the left side are first
 pub fn build_join_schema(
@@ -891,7 +992,7 @@ pub fn build_join_schema(
             // left then right
             left_fields.chain(right_fields).cloned().collect()
         }
-        JoinType::Semi | JoinType::Anti => {
+        JoinType::Semi | JoinType::Anti | JoinType::NullAwareAnti => {
             // Only use the left side for the schema
             left.fields().clone()
         }
@@ -1003,44 +1104,86 @@ pub fn project_with_alias(
 }
 
 /// Resolves an `Expr::Wildcard` to a collection of `Expr::Column`'s.
+///
+/// The join columns of a USING join are only expanded once, see
+/// [`using_column_expr`].
 pub(crate) fn expand_wildcard(
     schema: &DFSchema,
     plan: &LogicalPlan,
 ) -> Result<Vec<Expr>> {
-    let using_columns = plan.using_columns()?;
-    let columns_to_skip = using_columns
-        .into_iter()
-        // For each USING JOIN condition, only expand to one column in projection
-        .map(|cols| {
-            let mut cols = cols.into_iter().collect::<Vec<_>>();
-            // sort join columns to make sure we consistently keep the same
-            // qualified column
-            cols.sort();
-            cols.into_iter().skip(1)
+    let using_columns = using_column_exprs(plan)?;
+    Ok(schema
+        .fields()
+        .iter()
+        .filter_map(|f| {
+            let col = f.qualified_column();
+            match using_columns.get(&col) {
+                Some(expr) => expr.clone(),
+                None => Some(Expr::Column(col)),
+            }
         })
-        .flatten()
-        .collect::<HashSet<_>>();
+        .collect::<Vec<Expr>>())
+}
 
-    if columns_to_skip.is_empty() {
-        Ok(schema
-            .fields()
-            .iter()
-            .map(|f| Expr::Column(f.qualified_column()))
-            .collect::<Vec<Expr>>())
-    } else {
-        Ok(schema
-            .fields()
-            .iter()
-            .filter_map(|f| {
-                let col = f.qualified_column();
-                if !columns_to_skip.contains(&col) {
-                    Some(Expr::Column(col))
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<Expr>>())
+/// Resolves a qualified wildcard such as `t.*` to the columns of the relation
+/// `qualifier`.
+pub(crate) fn expand_qualified_wildcard(
+    qualifier: &str,
+    schema: &DFSchema,
+) -> Result<Vec<Expr>> {
+    let columns = schema
+        .fields()
+        .iter()
+        .filter(|f| f.qualifier().map(|q| q == qualifier).unwrap_or(false))
+        .map(|f| Expr::Column(f.qualified_column()))
+        .collect::<Vec<Expr>>();
+    if columns.is_empty() {
+        return Err(DataFusionError::Plan(format!(
+            "Invalid qualifier {} in wildcard",
+            qualifier
+        )));
+    }
+    Ok(columns)
+}
+
+/// Resolves a join column of a USING join of `plan` to the single column it is
+/// exposed as: the left column of inner and left joins, the right column of
+/// right joins and the first non-null of both columns for full joins.
+///
+/// Expressions other than USING join columns are returned unchanged.
+pub(crate) fn using_column_expr(expr: Expr, plan: &LogicalPlan) -> Result<Expr> {
+    match expr {
+        Expr::Column(col) => match using_column_exprs(plan)?.remove(&col) {
+            Some(Some(expr)) => Ok(expr),
+            _ => Ok(Expr::Column(col)),
+        },
+        _ => Ok(expr),
+    }
+}
+
+/// Maps the join columns of all USING joins of `plan` to the expression they
+/// are exposed as, or to `None` if they are hidden by the other join column.
+fn using_column_exprs(plan: &LogicalPlan) -> Result<HashMap<Column, Option<Expr>>> {
+    let mut exprs = HashMap::new();
+    // outer joins come first, so that their join columns take precedence over
+    // the ones of the joins they consume
+    for (join_type, on) in plan.using_joins()? {
+        for (l, r) in on {
+            let expr = match join_type {
+                JoinType::Right => Expr::Column(r.clone()),
+                JoinType::Full => when(
+                    Expr::Column(l.clone()).is_not_null(),
+                    Expr::Column(l.clone()),
+                )
+                .otherwise(Expr::Column(r.clone()))?
+                .alias(&l.name),
+                _ => Expr::Column(l.clone()),
+            };
+            exprs.entry(l).or_insert(Some(expr));
+            exprs.entry(r).or_insert(None);
+        }
     }
+    Ok(exprs)
 }
 
 #[cfg(test)]
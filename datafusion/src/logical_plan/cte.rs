@@ -0,0 +1,147 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Logical node for common table expressions whose result is computed once
+//! and shared between all of their references
+
+use super::extension::UserDefinedLogicalNode;
+use super::plan::Extension;
+use super::{DFSchemaRef, Expr, LogicalPlan};
+use crate::error::Result;
+use crate::optimizer::utils;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+static NEXT_CTE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A common table expression that is materialized once during execution.
+///
+/// All references to the same CTE share the same `id`. The physical planner
+/// plans them as a single [`MaterializeExec`](crate::physical_plan::materialize::MaterializeExec)
+/// whose output is buffered and replayed to each consumer.
+#[derive(Debug, Clone)]
+pub struct MaterializedCte {
+    /// Identifier shared by all references to the CTE
+    pub id: usize,
+    /// Name of the CTE
+    pub name: String,
+    /// The plan of the CTE
+    pub input: LogicalPlan,
+}
+
+impl MaterializedCte {
+    /// Wraps the plan of the CTE `name` into a new [`LogicalPlan::Extension`]
+    pub fn new_plan(name: impl Into<String>, input: LogicalPlan) -> LogicalPlan {
+        LogicalPlan::Extension(Extension {
+            node: Arc::new(Self {
+                id: NEXT_CTE_ID.fetch_add(1, Ordering::Relaxed),
+                name: name.into(),
+                input,
+            }),
+        })
+    }
+
+    /// Returns the [`MaterializedCte`] node of `plan`, if any
+    pub fn from_plan(plan: &LogicalPlan) -> Option<&Self> {
+        match plan {
+            LogicalPlan::Extension(Extension { node }) => {
+                node.as_any().downcast_ref::<Self>()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl UserDefinedLogicalNode for MaterializedCte {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        self.input.schema()
+    }
+
+    /// All columns are reported as used, so that the projection push down
+    /// optimizer produces the same plan for every reference of the CTE
+    fn expressions(&self) -> Vec<Expr> {
+        self.schema()
+            .fields()
+            .iter()
+            .map(|f| Expr::Column(f.qualified_column()))
+            .collect()
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MaterializedCte: {}", self.name)
+    }
+
+    fn from_template(
+        &self,
+        _exprs: &[Expr],
+        inputs: &[LogicalPlan],
+    ) -> Arc<dyn UserDefinedLogicalNode + Send + Sync> {
+        Arc::new(Self {
+            id: self.id,
+            name: self.name.clone(),
+            input: inputs[0].clone(),
+        })
+    }
+}
+
+/// Replaces the [`MaterializedCte`] nodes that are referenced only once in
+/// `plan` by their input, as there is nothing to share for them.
+pub(crate) fn inline_single_use_ctes(plan: LogicalPlan) -> Result<LogicalPlan> {
+    let mut references = HashMap::new();
+    count_references(&plan, &mut references);
+    if references.values().all(|count| *count > 1) {
+        return Ok(plan);
+    }
+    inline_ctes(&plan, &references)
+}
+
+fn count_references(plan: &LogicalPlan, references: &mut HashMap<usize, usize>) {
+    if let Some(cte) = MaterializedCte::from_plan(plan) {
+        *references.entry(cte.id).or_insert(0) += 1;
+    }
+    plan.inputs()
+        .into_iter()
+        .for_each(|input| count_references(input, references));
+}
+
+fn inline_ctes(
+    plan: &LogicalPlan,
+    references: &HashMap<usize, usize>,
+) -> Result<LogicalPlan> {
+    if let Some(cte) = MaterializedCte::from_plan(plan) {
+        if references[&cte.id] < 2 {
+            return inline_ctes(&cte.input, references);
+        }
+    }
+    let new_inputs = plan
+        .inputs()
+        .into_iter()
+        .map(|input| inline_ctes(input, references))
+        .collect::<Result<Vec<_>>>()?;
+    utils::from_plan(plan, &plan.expressions(), &new_inputs)
+}
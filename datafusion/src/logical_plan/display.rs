@@ -75,6 +75,84 @@ impl<'a, 'b> PlanVisitor for IndentVisitor<'a, 'b> {
     }
 }
 
+/// Formats plans as a single JSON object. For example:
+///
+/// ```text
+/// {"node": "Projection: #id", "schema": "[id:Int32]", "inputs": [
+///   {"node": "TableScan: employee.csv projection=Some([0])", "schema": "[id:Int32]", "inputs": []}]}
+/// ```
+///
+/// (without the line break, which is only added here for readability)
+pub struct JsonVisitor<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    /// The number of inputs written so far for each of the ancestors of the
+    /// current node
+    inputs_written: Vec<usize>,
+}
+
+impl<'a, 'b> JsonVisitor<'a, 'b> {
+    /// Create a visitor that will write a LogicalPlan as JSON to f
+    pub fn new(f: &'a mut fmt::Formatter<'b>) -> Self {
+        Self {
+            f,
+            inputs_written: vec![],
+        }
+    }
+}
+
+impl<'a, 'b> PlanVisitor for JsonVisitor<'a, 'b> {
+    type Error = fmt::Error;
+
+    fn pre_visit(&mut self, plan: &LogicalPlan) -> std::result::Result<bool, fmt::Error> {
+        if let Some(written) = self.inputs_written.last_mut() {
+            if *written > 0 {
+                write!(self.f, ", ")?;
+            }
+            *written += 1;
+        }
+        write!(
+            self.f,
+            "{{\"node\": {}, \"schema\": {}, \"inputs\": [",
+            json_quoted(&plan.display().to_string()),
+            json_quoted(
+                &display_schema(&plan.schema().as_ref().to_owned().into()).to_string()
+            )
+        )?;
+
+        self.inputs_written.push(0);
+        Ok(true)
+    }
+
+    fn post_visit(
+        &mut self,
+        _plan: &LogicalPlan,
+    ) -> std::result::Result<bool, fmt::Error> {
+        self.inputs_written.pop();
+        write!(self.f, "]}}")?;
+        Ok(true)
+    }
+}
+
+/// Quotes `s` as a JSON string, escaping the characters that can not appear
+/// in JSON strings as is
+pub(crate) fn json_quoted(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 /// Print the schema in a compact representation to `buf`
 ///
 /// For example: `foo:Utf8` if `foo` can not be null, and
@@ -120,30 +198,34 @@ pub fn display_schema(schema: &Schema) -> impl fmt::Display + '_ {
 
 /// Logic related to creating DOT language graphs.
 #[derive(Default)]
-struct GraphvizBuilder {
+pub(crate) struct GraphvizBuilder {
     id_gen: usize,
 }
 
 impl GraphvizBuilder {
-    fn next_id(&mut self) -> usize {
+    pub(crate) fn next_id(&mut self) -> usize {
         self.id_gen += 1;
         self.id_gen
     }
 
     // write out the start of the subgraph cluster
-    fn start_cluster(&mut self, f: &mut fmt::Formatter, title: &str) -> fmt::Result {
+    pub(crate) fn start_cluster(
+        &mut self,
+        f: &mut fmt::Formatter,
+        title: &str,
+    ) -> fmt::Result {
         writeln!(f, "  subgraph cluster_{}", self.next_id())?;
         writeln!(f, "  {{")?;
         writeln!(f, "    graph[label={}]", Self::quoted(title))
     }
 
     // write out the end of the subgraph cluster
-    fn end_cluster(&mut self, f: &mut fmt::Formatter) -> fmt::Result {
+    pub(crate) fn end_cluster(&mut self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "  }}")
     }
 
     /// makes a quoted string suitable for inclusion in a graphviz chart
-    fn quoted(label: &str) -> String {
+    pub(crate) fn quoted(label: &str) -> String {
         let label = label.replace('"', "_");
         format!("\"{}\"", label)
     }
@@ -260,4 +342,12 @@ mod tests {
             format!("{}", display_schema(&schema))
         );
     }
+
+    #[test]
+    fn test_json_quoted() {
+        assert_eq!(
+            r#""a \"b\"\\c\nd\u0001""#,
+            json_quoted("a \"b\"\\c\nd\u{1}")
+        );
+    }
 }
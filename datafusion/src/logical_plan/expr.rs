@@ -24,7 +24,10 @@ use crate::field_util::get_indexed_field;
 use crate::logical_plan::{window_frames, DFField, DFSchema, LogicalPlan};
 use crate::physical_plan::functions::Volatility;
 use crate::physical_plan::{
-    aggregates, expressions::binary_operator_data_type, functions, udf::ScalarUDF,
+    aggregates,
+    expressions::{binary_operator_data_type, case_coercion},
+    functions,
+    udf::ScalarUDF,
     window_functions,
 };
 use crate::{physical_plan::udaf::AggregateUDF, scalar::ScalarValue};
@@ -336,6 +339,14 @@ pub enum Expr {
         args: Vec<Expr>,
         /// Whether this is a DISTINCT aggregation or not
         distinct: bool,
+        /// Optional predicate of a `FILTER (WHERE ...)` clause, only the rows
+        /// for which it is true are aggregated
+        filter: Option<Box<Expr>>,
+        /// Sort expressions the rows are aggregated in the order of, e.g.
+        /// `ARRAY_AGG(a ORDER BY b)`. For ordered-set aggregates, the single
+        /// expression of their `WITHIN GROUP (ORDER BY ...)` clause, which
+        /// they aggregate.
+        order_by: Vec<Expr>,
     },
     /// Represents the call of a window function with arguments.
     WindowFunction {
@@ -384,7 +395,11 @@ impl Expr {
             Expr::Column(c) => Ok(schema.field_from_column(c)?.data_type().clone()),
             Expr::ScalarVariable(_) => Ok(DataType::Utf8),
             Expr::Literal(l) => Ok(l.get_datatype()),
-            Expr::Case { when_then_expr, .. } => when_then_expr[0].1.get_type(schema),
+            Expr::Case {
+                when_then_expr,
+                else_expr,
+                ..
+            } => case_result_type(when_then_expr, else_expr.as_deref(), schema),
             Expr::Cast { data_type, .. } => Ok(data_type.clone()),
             Expr::TryCast { data_type, .. } => Ok(data_type.clone()),
             Expr::ScalarUDF { fun, args } => {
@@ -408,8 +423,18 @@ impl Expr {
                     .collect::<Result<Vec<_>>>()?;
                 window_functions::return_type(fun, &data_types)
             }
-            Expr::AggregateFunction { fun, args, .. } => {
-                let data_types = args
+            Expr::AggregateFunction {
+                fun,
+                args,
+                order_by,
+                ..
+            } => {
+                // ordered-set aggregates aggregate the expression they are ordered by
+                let inputs = match aggregates::is_ordered_set_aggregate(fun) {
+                    true => order_by.iter().chain(args.iter()).collect::<Vec<_>>(),
+                    false => args.iter().collect(),
+                };
+                let data_types = inputs
                     .iter()
                     .map(|e| e.get_type(schema))
                     .collect::<Result<Vec<_>>>()?;
@@ -771,9 +796,24 @@ impl Expr {
                     .try_fold(visitor, |visitor, arg| arg.accept(visitor))?;
                 Ok(visitor)
             }
-            Expr::AggregateFunction { args, .. } => args
-                .iter()
-                .try_fold(visitor, |visitor, arg| arg.accept(visitor)),
+            Expr::AggregateFunction {
+                args,
+                filter,
+                order_by,
+                ..
+            } => {
+                let visitor = args
+                    .iter()
+                    .try_fold(visitor, |visitor, arg| arg.accept(visitor))?;
+                let visitor = if let Some(filter) = filter {
+                    filter.accept(visitor)?
+                } else {
+                    visitor
+                };
+                order_by
+                    .iter()
+                    .try_fold(visitor, |visitor, arg| arg.accept(visitor))
+            }
             Expr::AggregateUDF { args, .. } => args
                 .iter()
                 .try_fold(visitor, |visitor, arg| arg.accept(visitor)),
@@ -925,10 +965,14 @@ impl Expr {
                 args,
                 fun,
                 distinct,
+                filter,
+                order_by,
             } => Expr::AggregateFunction {
                 args: rewrite_vec(args, rewriter)?,
                 fun,
                 distinct,
+                filter: rewrite_option_box(filter, rewriter)?,
+                order_by: rewrite_vec(order_by, rewriter)?,
             },
             Expr::AggregateUDF { args, fun } => Expr::AggregateUDF {
                 args: rewrite_vec(args, rewriter)?,
@@ -982,7 +1026,20 @@ impl std::fmt::Display for Expr {
                 ref args,
                 /// Whether this is a DISTINCT aggregation or not
                 ref distinct,
-            } => fmt_function(f, &fun.to_string(), *distinct, args, true),
+                /// Predicate of the FILTER clause
+                ref filter,
+                /// Sort expressions of the aggregation
+                ref order_by,
+            } => {
+                fmt_function(f, &fun.to_string(), *distinct, args, true)?;
+                if !order_by.is_empty() {
+                    write!(f, " ORDER BY {:?}", order_by)?;
+                }
+                if let Some(filter) = filter {
+                    write!(f, " FILTER (WHERE {})", filter)?;
+                }
+                Ok(())
+            }
             Expr::ScalarFunction {
                 /// Name of the function
                 ref fun,
@@ -1147,6 +1204,40 @@ impl CaseBuilder {
     }
 }
 
+/// Returns the type of a CASE expression, which all of its THEN and ELSE branches
+/// are casted to. NULL literals take the type of the other branches.
+pub(crate) fn case_result_type(
+    when_then_expr: &[(Box<Expr>, Box<Expr>)],
+    else_expr: Option<&Expr>,
+    schema: &DFSchema,
+) -> Result<DataType> {
+    let branches = when_then_expr
+        .iter()
+        .map(|(_, then)| then.as_ref())
+        .chain(else_expr);
+    let mut result_type: Option<DataType> = None;
+    for branch in branches {
+        if matches!(branch, Expr::Literal(value) if value.is_null()) {
+            continue;
+        }
+        let branch_type = branch.get_type(schema)?;
+        result_type = Some(match result_type {
+            None => branch_type,
+            Some(result_type) => case_coercion(&result_type, &branch_type)
+                .ok_or_else(|| {
+                    DataFusionError::Plan(format!(
+                        "CASE branches of types {:?} and {:?} can't be coerced to a common type",
+                        result_type, branch_type
+                    ))
+                })?,
+        });
+    }
+    match result_type {
+        Some(result_type) => Ok(result_type),
+        None => when_then_expr[0].1.get_type(schema),
+    }
+}
+
 /// Create a CASE WHEN statement with literal WHEN expressions for comparison to the base expression.
 pub fn case(expr: Expr) -> CaseBuilder {
     CaseBuilder {
@@ -1364,6 +1455,8 @@ pub fn min(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Min,
         distinct: false,
         args: vec![expr],
+        filter: None,
+        order_by: vec![],
     }
 }
 
@@ -1373,6 +1466,8 @@ pub fn max(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Max,
         distinct: false,
         args: vec![expr],
+        filter: None,
+        order_by: vec![],
     }
 }
 
@@ -1382,6 +1477,8 @@ pub fn sum(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Sum,
         distinct: false,
         args: vec![expr],
+        filter: None,
+        order_by: vec![],
     }
 }
 
@@ -1391,6 +1488,8 @@ pub fn avg(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Avg,
         distinct: false,
         args: vec![expr],
+        filter: None,
+        order_by: vec![],
     }
 }
 
@@ -1400,6 +1499,8 @@ pub fn count(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Count,
         distinct: false,
         args: vec![expr],
+        filter: None,
+        order_by: vec![],
     }
 }
 
@@ -1409,6 +1510,8 @@ pub fn count_distinct(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Count,
         distinct: true,
         args: vec![expr],
+        filter: None,
+        order_by: vec![],
     }
 }
 
@@ -1478,9 +1581,10 @@ macro_rules! make_timestamp_literal {
         #[doc = $DOC]
         impl TimestampLiteral for $TYPE {
             fn lit_timestamp_nano(&self) -> Expr {
-                Expr::Literal(ScalarValue::TimestampNanosecond(Some(
-                    (self.clone()).into(),
-                )))
+                Expr::Literal(ScalarValue::TimestampNanosecond(
+                    Some((self.clone()).into()),
+                    None,
+                ))
             }
         }
     };
@@ -1556,6 +1660,8 @@ pub fn approx_distinct(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::ApproxDistinct,
         distinct: false,
         args: vec![expr],
+        filter: None,
+        order_by: vec![],
     }
 }
 
@@ -1645,9 +1751,11 @@ unary_scalar_expr!(Trim, trim);
 unary_scalar_expr!(Upper, upper);
 
 // date functions
+binary_scalar_expr!(DateBin, date_bin);
 binary_scalar_expr!(DatePart, date_part);
 binary_scalar_expr!(DateTrunc, date_trunc);
 binary_scalar_expr!(Digest, digest);
+binary_scalar_expr!(Window, window);
 
 /// returns an array of fixed size with each argument on it.
 pub fn array(args: Vec<Expr>) -> Expr {
@@ -1657,6 +1765,22 @@ pub fn array(args: Vec<Expr>) -> Expr {
     }
 }
 
+/// returns the list of the keys of a map.
+pub fn map_keys(map: Expr) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::MapKeys,
+        args: vec![map],
+    }
+}
+
+/// returns the list of the values of a map.
+pub fn map_values(map: Expr) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::MapValues,
+        args: vec![map],
+    }
+}
+
 /// Creates a new UDF with a specific signature and specific return type.
 /// This is a helper function to create a new UDF.
 /// The function `create_udf` returns a subset of all possible `ScalarFunction`:
@@ -1795,13 +1919,7 @@ impl fmt::Debug for Expr {
                     write!(f, " ORDER BY {:?}", order_by)?;
                 }
                 if let Some(window_frame) = window_frame {
-                    write!(
-                        f,
-                        " {} BETWEEN {} AND {}",
-                        window_frame.units,
-                        window_frame.start_bound,
-                        window_frame.end_bound
-                    )?;
+                    write!(f, " {}", window_frame)?;
                 }
                 Ok(())
             }
@@ -1809,8 +1927,18 @@ impl fmt::Debug for Expr {
                 fun,
                 distinct,
                 ref args,
-                ..
-            } => fmt_function(f, &fun.to_string(), *distinct, args, true),
+                filter,
+                order_by,
+            } => {
+                fmt_function(f, &fun.to_string(), *distinct, args, true)?;
+                if !order_by.is_empty() {
+                    write!(f, " ORDER BY {:?}", order_by)?;
+                }
+                if let Some(filter) = filter {
+                    write!(f, " FILTER (WHERE {:?})", filter)?;
+                }
+                Ok(())
+            }
             Expr::AggregateUDF { fun, ref args, .. } => {
                 fmt_function(f, &fun.name, false, args, false)
             }
@@ -1959,8 +2087,23 @@ fn create_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
             fun,
             distinct,
             args,
-            ..
-        } => create_function_name(&fun.to_string(), *distinct, args, input_schema),
+            filter,
+            order_by,
+        } => {
+            let mut name =
+                create_function_name(&fun.to_string(), *distinct, args, input_schema)?;
+            if !order_by.is_empty() {
+                name = format!("{} ORDER BY {:?}", name, order_by);
+            }
+            match filter {
+                Some(filter) => Ok(format!(
+                    "{} FILTER (WHERE {})",
+                    name,
+                    create_name(filter, input_schema)?
+                )),
+                None => Ok(name),
+            }
+        }
         Expr::AggregateUDF { fun, args } => {
             let mut names = Vec::with_capacity(args.len());
             for e in args {
@@ -2037,7 +2180,8 @@ mod tests {
     #[test]
     fn test_lit_timestamp_nano() {
         let expr = col("time").eq(lit_timestamp_nano(10)); // 10 is an implicit i32
-        let expected = col("time").eq(lit(ScalarValue::TimestampNanosecond(Some(10))));
+        let expected =
+            col("time").eq(lit(ScalarValue::TimestampNanosecond(Some(10), None)));
         assert_eq!(expr, expected);
 
         let i: i64 = 10;
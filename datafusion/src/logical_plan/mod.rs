@@ -22,38 +22,45 @@
 //! physical query plans and executed.
 
 pub(crate) mod builder;
+mod cte;
 mod dfschema;
-mod display;
+pub(crate) mod display;
 mod expr;
 mod extension;
 mod operators;
 pub mod plan;
 mod registry;
+mod sample;
 pub mod window_frames;
 pub use builder::{
     build_join_schema, union_with_alias, LogicalPlanBuilder, UNNAMED_TABLE,
 };
+pub(crate) use cte::inline_single_use_ctes;
+pub use cte::MaterializedCte;
 pub use dfschema::{DFField, DFSchema, DFSchemaRef, ToDFSchema};
 pub use display::display_schema;
+pub(crate) use expr::case_result_type;
 pub use expr::{
     abs, acos, and, approx_distinct, array, ascii, asin, atan, avg, binary_expr,
     bit_length, btrim, case, ceil, character_length, chr, col, columnize_expr,
     combine_filters, concat, concat_ws, cos, count, count_distinct, create_udaf,
-    create_udf, date_part, date_trunc, digest, exp, exprlist_to_fields, floor, in_list,
-    initcap, left, length, lit, lit_timestamp_nano, ln, log10, log2, lower, lpad, ltrim,
-    max, md5, min, normalize_col, normalize_cols, now, octet_length, or, random,
-    regexp_match, regexp_replace, repeat, replace, replace_col, reverse, right, round,
-    rpad, rtrim, sha224, sha256, sha384, sha512, signum, sin, split_part, sqrt,
-    starts_with, strpos, substr, sum, tan, to_hex, translate, trim, trunc, unalias,
-    unnormalize_col, unnormalize_cols, upper, when, Column, Expr, ExprRewriter,
-    ExpressionVisitor, Literal, Recursion, RewriteRecursion,
+    create_udf, date_bin, date_part, date_trunc, digest, exp, exprlist_to_fields, floor,
+    in_list, initcap, left, length, lit, lit_timestamp_nano, ln, log10, log2, lower,
+    lpad, ltrim, map_keys, map_values, max, md5, min, normalize_col, normalize_cols, now,
+    octet_length, or, random, regexp_match, regexp_replace, repeat, replace, replace_col,
+    reverse, right, round, rpad, rtrim, sha224, sha256, sha384, sha512, signum, sin,
+    split_part, sqrt, starts_with, strpos, substr, sum, tan, to_hex, translate, trim,
+    trunc, unalias, unnormalize_col, unnormalize_cols, upper, when, window, Column, Expr,
+    ExprRewriter, ExpressionVisitor, Literal, Recursion, RewriteRecursion,
 };
 pub use extension::UserDefinedLogicalNode;
 pub use operators::Operator;
 pub use plan::{
     CreateExternalTable, CreateMemoryTable, CrossJoin, DropTable, EmptyRelation,
-    JoinConstraint, JoinType, Limit, LogicalPlan, Partitioning, PlanType, PlanVisitor,
-    Repartition, TableScan, Union, Values,
+    ExplainFormat, InsertInto, JoinConstraint, JoinType, Limit, LogicalPlan,
+    Partitioning, PlanType, PlanVisitor, Repartition, TableScan, Union, Values,
+    WriteMode,
 };
 pub(crate) use plan::{StringifiedPlan, ToStringifiedPlan};
 pub use registry::FunctionRegistry;
+pub use sample::{Sample, SampleMethod};
@@ -17,17 +17,20 @@
 //! This module contains the  `LogicalPlan` enum that describes queries
 //! via a logical query plan.
 
-use super::display::{GraphvizVisitor, IndentVisitor};
+use super::display::{GraphvizVisitor, IndentVisitor, JsonVisitor};
 use super::expr::{Column, Expr};
 use super::extension::UserDefinedLogicalNode;
+use crate::datasource::listing::Bucketing;
 use crate::datasource::TableProvider;
 use crate::error::DataFusionError;
 use crate::logical_plan::dfschema::DFSchemaRef;
+use crate::scalar::ScalarValue;
 use crate::sql::parser::FileType;
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use std::{
     collections::HashSet,
     fmt::{self, Display},
+    str::FromStr,
     sync::Arc,
 };
 
@@ -46,6 +49,11 @@ pub enum JoinType {
     Semi,
     /// Anti Join
     Anti,
+    /// Null aware anti join, with the semantics of `NOT IN` on a single key: the
+    /// left rows without a match on the right, unless the right has a null key, in
+    /// which case no row is returned, and without the left rows with a null key,
+    /// unless the right is empty
+    NullAwareAnti,
 }
 
 /// Join constraint
@@ -107,6 +115,10 @@ pub struct TableScan {
     pub source: Arc<dyn TableProvider>,
     /// Optional column indices to use as a projection
     pub projection: Option<Vec<usize>>,
+    /// Paths of the fields of the struct columns to read, starting with the name
+    /// of the column, e.g. `["s", "a", "b"]`. The struct columns without any path
+    /// are read entirely.
+    pub nested_projection: Vec<Vec<String>>,
     /// The schema description of the output
     pub projected_schema: DFSchemaRef,
     /// Optional expressions to be used as filters by the table provider
@@ -168,6 +180,8 @@ pub struct CreateExternalTable {
     pub file_type: FileType,
     /// Whether the CSV file contains a header
     pub has_header: bool,
+    /// The bucketing of the files, if any
+    pub bucketing: Option<Bucketing>,
 }
 
 /// Drops a table.
@@ -181,6 +195,30 @@ pub struct DropTable {
     pub schema: DFSchemaRef,
 }
 
+/// How a write replaces the existing data of a table
+#[derive(Clone, Debug, PartialEq)]
+pub enum WriteMode {
+    /// Fails if the table already exists
+    ErrorIfExists,
+    /// Replaces all the data of the table
+    Overwrite,
+    /// Replaces the data of the partitions that the written rows belong to, and
+    /// keeps the other partitions of the table, i.e. a dynamic partition
+    /// overwrite. The table is partitioned by the given columns.
+    OverwritePartitions(Vec<String>),
+}
+
+/// Writes the rows of its input to a table, replacing its data.
+#[derive(Clone)]
+pub struct InsertInto {
+    /// The table name
+    pub table_name: String,
+    /// How the existing data of the table is replaced
+    pub mode: WriteMode,
+    /// The logical plan of the rows, whose columns are the ones of the table
+    pub input: Arc<LogicalPlan>,
+}
+
 /// Produces a relation with string representations of
 /// various parts of the plan
 #[derive(Clone)]
@@ -191,6 +229,10 @@ pub struct Explain {
     pub plan: Arc<LogicalPlan>,
     /// Represent the various stages plans have gone through
     pub stringified_plans: Vec<StringifiedPlan>,
+    /// How the plans are formatted
+    pub format: ExplainFormat,
+    /// Should the physical plans show the estimated rows and bytes of each node?
+    pub costs: bool,
     /// The output schema of the explain (2 columns of text)
     pub schema: DFSchemaRef,
 }
@@ -203,6 +245,8 @@ pub struct Analyze {
     pub verbose: bool,
     /// The logical plan that is being EXPLAIN ANALYZE'd
     pub input: Arc<LogicalPlan>,
+    /// How the plan with metrics is formatted
+    pub format: ExplainFormat,
     /// The output schema of the explain (2 columns of text)
     pub schema: DFSchemaRef,
 }
@@ -333,6 +377,8 @@ pub enum LogicalPlan {
     CreateMemoryTable(CreateMemoryTable),
     /// Drops a table.
     DropTable(DropTable),
+    /// Writes rows to a table.
+    InsertInto(InsertInto),
     /// Values expression. See
     /// [Postgres VALUES](https://www.postgresql.org/docs/current/queries-values.html)
     /// documentation for more details.
@@ -376,6 +422,7 @@ impl LogicalPlan {
                 input.schema()
             }
             LogicalPlan::DropTable(DropTable { schema, .. }) => schema,
+            LogicalPlan::InsertInto(InsertInto { input, .. }) => input.schema(),
         }
     }
 
@@ -423,6 +470,7 @@ impl LogicalPlan {
             | LogicalPlan::Repartition(Repartition { input, .. })
             | LogicalPlan::Sort(Sort { input, .. })
             | LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. })
+            | LogicalPlan::InsertInto(InsertInto { input, .. })
             | LogicalPlan::Filter(Filter { input, .. }) => input.all_schemas(),
             LogicalPlan::DropTable(_) => vec![],
         }
@@ -451,6 +499,8 @@ impl LogicalPlan {
                 ..
             }) => match partitioning_scheme {
                 Partitioning::Hash(expr, _) => expr.clone(),
+                Partitioning::RangePartitioning(expr, _) => expr.clone(),
+                Partitioning::Custom(expr, _) => vec![expr.clone()],
                 _ => vec![],
             },
             LogicalPlan::Window(Window { window_expr, .. }) => window_expr.clone(),
@@ -472,6 +522,7 @@ impl LogicalPlan {
             | LogicalPlan::CreateExternalTable(_)
             | LogicalPlan::CreateMemoryTable(_)
             | LogicalPlan::DropTable(_)
+            | LogicalPlan::InsertInto(_)
             | LogicalPlan::CrossJoin(_)
             | LogicalPlan::Analyze { .. }
             | LogicalPlan::Explain { .. }
@@ -498,9 +549,8 @@ impl LogicalPlan {
             LogicalPlan::Union(Union { inputs, .. }) => inputs.iter().collect(),
             LogicalPlan::Explain(explain) => vec![&explain.plan],
             LogicalPlan::Analyze(analyze) => vec![&analyze.input],
-            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. }) => {
-                vec![input]
-            }
+            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. })
+            | LogicalPlan::InsertInto(InsertInto { input, .. }) => vec![input],
             // plans without inputs
             LogicalPlan::TableScan { .. }
             | LogicalPlan::EmptyRelation { .. }
@@ -512,37 +562,49 @@ impl LogicalPlan {
 
     /// returns all `Using` join columns in a logical plan
     pub fn using_columns(&self) -> Result<Vec<HashSet<Column>>, DataFusionError> {
-        struct UsingJoinColumnVisitor {
-            using_columns: Vec<HashSet<Column>>,
+        Ok(self
+            .using_joins()?
+            .into_iter()
+            .map(|(_, on)| {
+                on.into_iter()
+                    .map(|(l, r)| [l, r])
+                    .flatten()
+                    .collect::<HashSet<Column>>()
+            })
+            .collect())
+    }
+
+    /// returns the join type and the join column pairs of all `Using` joins in a
+    /// logical plan, starting with the outermost join
+    pub fn using_joins(
+        &self,
+    ) -> Result<Vec<(JoinType, Vec<(Column, Column)>)>, DataFusionError> {
+        struct UsingJoinVisitor {
+            using_joins: Vec<(JoinType, Vec<(Column, Column)>)>,
         }
 
-        impl PlanVisitor for UsingJoinColumnVisitor {
+        impl PlanVisitor for UsingJoinVisitor {
             type Error = DataFusionError;
 
             fn pre_visit(&mut self, plan: &LogicalPlan) -> Result<bool, Self::Error> {
                 if let LogicalPlan::Join(Join {
                     join_constraint: JoinConstraint::Using,
+                    join_type,
                     on,
                     ..
                 }) = plan
                 {
-                    self.using_columns.push(
-                        on.iter()
-                            .map(|entry| [&entry.0, &entry.1])
-                            .flatten()
-                            .cloned()
-                            .collect::<HashSet<Column>>(),
-                    );
+                    self.using_joins.push((*join_type, on.clone()));
                 }
                 Ok(true)
             }
         }
 
-        let mut visitor = UsingJoinColumnVisitor {
-            using_columns: vec![],
+        let mut visitor = UsingJoinVisitor {
+            using_joins: vec![],
         };
         self.accept(&mut visitor)?;
-        Ok(visitor.using_columns)
+        Ok(visitor.using_joins)
     }
 }
 
@@ -555,6 +617,13 @@ pub enum Partitioning {
     /// of partitions.
     /// This partitioning scheme is not yet fully supported. See <https://issues.apache.org/jira/browse/ARROW-11011>
     Hash(Vec<Expr>, usize),
+    /// Allocate rows to ranges of one or more sort expressions (`Expr::Sort`). Every
+    /// boundary holds one value per sort expression and is the inclusive upper bound of
+    /// a partition, so there is one more partition than there are boundaries.
+    RangePartitioning(Vec<Expr>, Vec<Vec<ScalarValue>>),
+    /// Allocate rows to the partition returned by an integer expression and the
+    /// specified number of partitions
+    Custom(Expr, usize),
 }
 
 /// Trait that implements the [Visitor
@@ -641,7 +710,8 @@ impl LogicalPlan {
                 true
             }
             LogicalPlan::Limit(Limit { input, .. }) => input.accept(visitor)?,
-            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. }) => {
+            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. })
+            | LogicalPlan::InsertInto(InsertInto { input, .. }) => {
                 input.accept(visitor)?
             }
             LogicalPlan::Extension(extension) => {
@@ -817,6 +887,39 @@ impl LogicalPlan {
         Wrapper(self)
     }
 
+    /// Return a `format`able structure that produces the plan as a single
+    /// JSON object, meant to be consumed by tools such as plan visualizers.
+    /// Each node is an object with its description in `node`, its output
+    /// schema in `schema` and its children in `inputs`.
+    ///
+    /// ```
+    /// use arrow::datatypes::{Field, Schema, DataType};
+    /// use datafusion::logical_plan::{lit, col, LogicalPlanBuilder};
+    /// let schema = Schema::new(vec![
+    ///     Field::new("id", DataType::Int32, false),
+    /// ]);
+    /// let plan = LogicalPlanBuilder::scan_empty(Some("foo_csv"), &schema, None).unwrap()
+    ///     .filter(col("id").eq(lit(5))).unwrap()
+    ///     .build().unwrap();
+    ///
+    /// let expected = "{\"node\": \"Filter: #foo_csv.id = Int32(5)\", \"schema\": \"[id:Int32]\", \"inputs\": [\
+    ///                 {\"node\": \"TableScan: foo_csv projection=None\", \"schema\": \"[id:Int32]\", \"inputs\": []}]}";
+    /// assert_eq!(expected, format!("{}", plan.display_json()));
+    /// ```
+    pub fn display_json(&self) -> impl fmt::Display + '_ {
+        // Boilerplate structure to wrap LogicalPlan with something
+        // that that can be formatted
+        struct Wrapper<'a>(&'a LogicalPlan);
+        impl<'a> fmt::Display for Wrapper<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let mut visitor = JsonVisitor::new(f);
+                self.0.accept(&mut visitor)?;
+                Ok(())
+            }
+        }
+        Wrapper(self)
+    }
+
     /// Return a `format`able structure with the a human readable
     /// description of this LogicalPlan node per node, not including
     /// children. For example:
@@ -868,6 +971,7 @@ impl LogicalPlan {
                     LogicalPlan::TableScan(TableScan {
                         ref table_name,
                         ref projection,
+                        ref nested_projection,
                         ref filters,
                         ref limit,
                         ..
@@ -878,6 +982,14 @@ impl LogicalPlan {
                             table_name, projection
                         )?;
 
+                        if !nested_projection.is_empty() {
+                            let paths: Vec<_> = nested_projection
+                                .iter()
+                                .map(|path| path.join("."))
+                                .collect();
+                            write!(f, ", nested_projection=[{}]", paths.join(", "))?;
+                        }
+
                         if !filters.is_empty() {
                             write!(f, ", filters={:?}", filters)?;
                         }
@@ -969,6 +1081,21 @@ impl LogicalPlan {
                                 n
                             )
                         }
+                        Partitioning::RangePartitioning(expr, boundaries) => {
+                            let sort_expr: Vec<String> =
+                                expr.iter().map(|e| format!("{:?}", e)).collect();
+                            write!(
+                                f,
+                                "Repartition: Range({}) partition_count={}",
+                                sort_expr.join(", "),
+                                boundaries.len() + 1
+                            )
+                        }
+                        Partitioning::Custom(expr, n) => write!(
+                            f,
+                            "Repartition: Custom({:?}) partition_count={}",
+                            expr, n
+                        ),
                     },
                     LogicalPlan::Limit(Limit { ref n, .. }) => write!(f, "Limit: {}", n),
                     LogicalPlan::CreateExternalTable(CreateExternalTable {
@@ -985,6 +1112,11 @@ impl LogicalPlan {
                     LogicalPlan::DropTable(DropTable { name, if_exist, .. }) => {
                         write!(f, "DropTable: {:?} if not exist:={}", name, if_exist)
                     }
+                    LogicalPlan::InsertInto(InsertInto {
+                        table_name, mode, ..
+                    }) => {
+                        write!(f, "InsertInto: {:?} mode={:?}", table_name, mode)
+                    }
                     LogicalPlan::Explain { .. } => write!(f, "Explain"),
                     LogicalPlan::Analyze { .. } => write!(f, "Analyze"),
                     LogicalPlan::Union(_) => write!(f, "Union"),
@@ -1024,6 +1156,9 @@ pub enum PlanType {
     },
     /// The final, fully optimized physical which would be executed
     FinalPhysicalPlan,
+    /// The final physical plan split into the query stages of a distributed
+    /// engine, such as Ballista, which would be executed instead
+    DistributedPlan,
 }
 
 impl fmt::Display for PlanType {
@@ -1039,6 +1174,51 @@ impl fmt::Display for PlanType {
                 write!(f, "physical_plan after {}", optimizer_name)
             }
             PlanType::FinalPhysicalPlan => write!(f, "physical_plan"),
+            PlanType::DistributedPlan => write!(f, "distributed_plan"),
+        }
+    }
+}
+
+/// How the plans shown by `EXPLAIN` are formatted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainFormat {
+    /// One line per node, indented by depth (the default)
+    Indent,
+    /// A JSON object per plan, where each node has its description in `node`
+    /// and its children in `inputs`
+    Json,
+    /// A graph in the [`DOT`](https://graphviz.org/doc/info/lang.html) language
+    Graphviz,
+}
+
+impl Default for ExplainFormat {
+    fn default() -> Self {
+        Self::Indent
+    }
+}
+
+impl FromStr for ExplainFormat {
+    type Err = DataFusionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "TEXT" | "INDENT" => Ok(Self::Indent),
+            "JSON" => Ok(Self::Json),
+            "DOT" | "GRAPHVIZ" => Ok(Self::Graphviz),
+            other => Err(DataFusionError::Plan(format!(
+                "Unsupported EXPLAIN format {}, expected one of TEXT, JSON or DOT",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ExplainFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Indent => write!(f, "TEXT"),
+            Self::Json => write!(f, "JSON"),
+            Self::Graphviz => write!(f, "DOT"),
         }
     }
 }
@@ -1067,7 +1247,9 @@ impl StringifiedPlan {
     /// `verbose_mode = true` will display all available plans
     pub fn should_display(&self, verbose_mode: bool) -> bool {
         match self.plan_type {
-            PlanType::FinalLogicalPlan | PlanType::FinalPhysicalPlan => true,
+            PlanType::FinalLogicalPlan
+            | PlanType::FinalPhysicalPlan
+            | PlanType::DistributedPlan => true,
             _ => verbose_mode,
         }
     }
@@ -1076,12 +1258,30 @@ impl StringifiedPlan {
 /// Trait for something that can be formatted as a stringified plan
 pub trait ToStringifiedPlan {
     /// Create a stringified plan with the specified type
-    fn to_stringified(&self, plan_type: PlanType) -> StringifiedPlan;
+    fn to_stringified(&self, plan_type: PlanType) -> StringifiedPlan {
+        self.to_stringified_with_format(plan_type, ExplainFormat::Indent)
+    }
+
+    /// Create a stringified plan with the specified type, formatted as `format`
+    fn to_stringified_with_format(
+        &self,
+        plan_type: PlanType,
+        format: ExplainFormat,
+    ) -> StringifiedPlan;
 }
 
 impl ToStringifiedPlan for LogicalPlan {
-    fn to_stringified(&self, plan_type: PlanType) -> StringifiedPlan {
-        StringifiedPlan::new(plan_type, self.display_indent().to_string())
+    fn to_stringified_with_format(
+        &self,
+        plan_type: PlanType,
+        format: ExplainFormat,
+    ) -> StringifiedPlan {
+        let plan = match format {
+            ExplainFormat::Indent => self.display_indent().to_string(),
+            ExplainFormat::Json => self.display_json().to_string(),
+            ExplainFormat::Graphviz => self.display_graphviz().to_string(),
+        };
+        StringifiedPlan::new(plan_type, plan)
     }
 }
 
@@ -1137,6 +1337,17 @@ mod tests {
         assert_eq!(expected, format!("{}", plan.display_indent_schema()));
     }
 
+    #[test]
+    fn test_display_json() {
+        let plan = display_plan();
+
+        let expected = "{\"node\": \"Projection: #employee_csv.id\", \"schema\": \"[id:Int32]\", \"inputs\": [\
+                        {\"node\": \"Filter: #employee_csv.state = Utf8(\\\"CO\\\")\", \"schema\": \"[id:Int32, state:Utf8]\", \"inputs\": [\
+                        {\"node\": \"TableScan: employee_csv projection=Some([0, 3])\", \"schema\": \"[id:Int32, state:Utf8]\", \"inputs\": []}]}]}";
+
+        assert_eq!(expected, format!("{}", plan.display_json()));
+    }
+
     #[test]
     fn test_display_graphviz() {
         let plan = display_plan();
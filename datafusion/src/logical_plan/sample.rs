@@ -0,0 +1,159 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Logical node that returns a random sample of the rows of its input, as
+//! produced by `TABLESAMPLE` clauses and `DataFrame::sample`
+
+use super::extension::UserDefinedLogicalNode;
+use super::plan::Extension;
+use super::{DFSchemaRef, Expr, LogicalPlan};
+use crate::error::{DataFusionError, Result};
+use std::any::Any;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// How the rows of a [`Sample`] are chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleMethod {
+    /// Every row is returned independently with the sampling probability
+    Bernoulli,
+    /// Every block of rows is returned independently with the sampling
+    /// probability. Scans of files skip the files, or the row groups of Parquet
+    /// files, that are not sampled instead of reading them.
+    System,
+}
+
+impl FromStr for SampleMethod {
+    type Err = DataFusionError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_uppercase().as_str() {
+            "BERNOULLI" => Ok(Self::Bernoulli),
+            "SYSTEM" => Ok(Self::System),
+            other => Err(DataFusionError::Plan(format!(
+                "Unsupported sampling method {}, expected BERNOULLI or SYSTEM",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SampleMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bernoulli => write!(f, "BERNOULLI"),
+            Self::System => write!(f, "SYSTEM"),
+        }
+    }
+}
+
+/// Returns a random sample of the rows of its input.
+///
+/// The physical planner plans it as a
+/// [`SampleExec`](crate::physical_plan::sample::SampleExec), or pushes
+/// [`SampleMethod::System`] samples into the scan of files.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// How the rows are chosen
+    pub method: SampleMethod,
+    /// Probability with which each row or block is returned, between 0 and 1
+    pub fraction: f64,
+    /// Seed of the random choice. Samples of the same input with the same seed
+    /// return the same rows; a random seed is chosen for each execution if unset.
+    pub seed: Option<u64>,
+    /// The sampled plan
+    pub input: LogicalPlan,
+}
+
+impl Sample {
+    /// Wraps `input` into a new [`LogicalPlan::Extension`] that samples its rows
+    pub fn try_new_plan(
+        input: LogicalPlan,
+        method: SampleMethod,
+        fraction: f64,
+        seed: Option<u64>,
+    ) -> Result<LogicalPlan> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(DataFusionError::Plan(format!(
+                "Sampling fraction must be between 0 and 1, got {}",
+                fraction
+            )));
+        }
+        Ok(LogicalPlan::Extension(Extension {
+            node: Arc::new(Self {
+                method,
+                fraction,
+                seed,
+                input,
+            }),
+        }))
+    }
+
+    /// Returns the [`Sample`] node of `plan`, if any
+    pub fn from_plan(plan: &LogicalPlan) -> Option<&Self> {
+        match plan {
+            LogicalPlan::Extension(Extension { node }) => {
+                node.as_any().downcast_ref::<Self>()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl UserDefinedLogicalNode for Sample {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        self.input.schema()
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Sample: method={}, fraction={}",
+            self.method, self.fraction
+        )?;
+        if let Some(seed) = self.seed {
+            write!(f, ", seed={}", seed)?;
+        }
+        Ok(())
+    }
+
+    fn from_template(
+        &self,
+        _exprs: &[Expr],
+        inputs: &[LogicalPlan],
+    ) -> Arc<dyn UserDefinedLogicalNode + Send + Sync> {
+        Arc::new(Self {
+            method: self.method,
+            fraction: self.fraction,
+            seed: self.seed,
+            input: inputs[0].clone(),
+        })
+    }
+}
@@ -24,6 +24,7 @@
 //! - An EXCLUDE clause.
 
 use crate::error::{DataFusionError, Result};
+use arrow::datatypes::DataType;
 use sqlparser::ast;
 use std::cmp::Ordering;
 use std::convert::{From, TryFrom};
@@ -42,6 +43,50 @@ pub struct WindowFrame {
     pub start_bound: WindowFrameBound,
     /// An ending frame boundary
     pub end_bound: WindowFrameBound,
+    /// The rows around the current row removed from the frame
+    pub exclusion: WindowFrameExclusion,
+}
+
+impl WindowFrame {
+    /// Returns a copy of this frame with the given EXCLUDE clause
+    pub fn with_exclusion(mut self, exclusion: WindowFrameExclusion) -> Self {
+        self.exclusion = exclusion;
+        self
+    }
+
+    /// Returns whether a bound of this frame is an offset from the current row
+    pub(crate) fn has_offset(&self) -> bool {
+        [self.start_bound, self.end_bound].iter().any(|bound| {
+            matches!(
+                bound,
+                WindowFrameBound::Preceding(Some(_))
+                    | WindowFrameBound::Following(Some(_))
+            )
+        })
+    }
+}
+
+/// Checks the type of the ORDER BY expression of a RANGE frame with offsets, which
+/// must be numeric like the offsets: the INTERVAL offsets that temporal types need
+/// are not supported.
+pub(crate) fn check_range_offset_type(data_type: &DataType) -> Result<()> {
+    match data_type {
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64
+        | DataType::Float16
+        | DataType::Float32
+        | DataType::Float64 => Ok(()),
+        data_type => Err(DataFusionError::Plan(format!(
+            "RANGE window frames with offsets require a numeric ORDER BY expression, got {:?}",
+            data_type
+        ))),
+    }
 }
 
 impl fmt::Display for WindowFrame {
@@ -51,6 +96,9 @@ impl fmt::Display for WindowFrame {
             "{} BETWEEN {} AND {}",
             self.units, self.start_bound, self.end_bound
         )?;
+        if self.exclusion != WindowFrameExclusion::NoOthers {
+            write!(f, " {}", self.exclusion)?;
+        }
         Ok(())
     }
 }
@@ -81,27 +129,11 @@ impl TryFrom<ast::WindowFrame> for WindowFrame {
             start_bound, end_bound
         )))
         } else {
-            let units = value.units.into();
-            if units == WindowFrameUnits::Range {
-                for bound in &[start_bound, end_bound] {
-                    match bound {
-                        WindowFrameBound::Preceding(Some(v))
-                        | WindowFrameBound::Following(Some(v))
-                            if *v > 0 =>
-                        {
-                            Err(DataFusionError::NotImplemented(format!(
-                                "With WindowFrameUnits={}, the bound cannot be {} PRECEDING or FOLLOWING at the moment",
-                                units, v
-                            )))
-                        }
-                        _ => Ok(()),
-                    }?;
-                }
-            }
             Ok(Self {
-                units,
+                units: value.units.into(),
                 start_bound,
                 end_bound,
+                exclusion: WindowFrameExclusion::NoOthers,
             })
         }
     }
@@ -113,6 +145,7 @@ impl Default for WindowFrame {
             units: WindowFrameUnits::Range,
             start_bound: WindowFrameBound::Preceding(None),
             end_bound: WindowFrameBound::CurrentRow,
+            exclusion: WindowFrameExclusion::NoOthers,
         }
     }
 }
@@ -238,6 +271,32 @@ impl fmt::Display for WindowFrameUnits {
     }
 }
 
+/// The EXCLUDE clause removes rows around the current row from its frame, even if they
+/// are within the frame boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd)]
+pub enum WindowFrameExclusion {
+    /// EXCLUDE NO OTHERS, the default: no rows are excluded.
+    NoOthers,
+    /// EXCLUDE CURRENT ROW: the current row is excluded from the frame.
+    CurrentRow,
+    /// EXCLUDE GROUP: the current row and all its peers are excluded from the frame.
+    Group,
+    /// EXCLUDE TIES: the peers of the current row are excluded from the frame, but not
+    /// the current row itself.
+    Ties,
+}
+
+impl fmt::Display for WindowFrameExclusion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            WindowFrameExclusion::NoOthers => "EXCLUDE NO OTHERS",
+            WindowFrameExclusion::CurrentRow => "EXCLUDE CURRENT ROW",
+            WindowFrameExclusion::Group => "EXCLUDE GROUP",
+            WindowFrameExclusion::Ties => "EXCLUDE TIES",
+        })
+    }
+}
+
 impl From<ast::WindowFrameUnits> for WindowFrameUnits {
     fn from(value: ast::WindowFrameUnits) -> Self {
         match value {
@@ -293,10 +352,7 @@ mod tests {
             end_bound: Some(ast::WindowFrameBound::Preceding(Some(1))),
         };
         let result = WindowFrame::try_from(window_frame);
-        assert_eq!(
-            result.err().unwrap().to_string(),
-            "This feature is not implemented: With WindowFrameUnits=RANGE, the bound cannot be 2 PRECEDING or FOLLOWING at the moment".to_owned()
-        );
+        assert!(result.is_ok());
 
         let window_frame = ast::WindowFrame {
             units: ast::WindowFrameUnits::Rows,
@@ -308,6 +364,20 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_display_exclusion() {
+        let window_frame = WindowFrame::default();
+        assert_eq!(
+            window_frame.to_string(),
+            "RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW"
+        );
+        let window_frame = window_frame.with_exclusion(WindowFrameExclusion::Ties);
+        assert_eq!(
+            window_frame.to_string(),
+            "RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW EXCLUDE TIES"
+        );
+    }
+
     #[test]
     fn test_eq() {
         assert_eq!(
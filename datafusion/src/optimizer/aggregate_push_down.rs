@@ -0,0 +1,243 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimizer rule to push aggregates down into the table providers that can
+//! compute them without returning the aggregated rows, e.g. from the metadata
+//! of their files or by a remote database.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use async_trait::async_trait;
+
+use super::utils;
+use crate::datasource::TableProvider;
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::plan::Aggregate;
+use crate::logical_plan::{unnormalize_cols, Expr, LogicalPlan, TableScan};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::physical_plan::expressions::Column;
+use crate::physical_plan::projection::ProjectionExec;
+use crate::physical_plan::{ExecutionPlan, PhysicalExpr};
+
+/// Optimization rule that replaces an aggregate of a table scan with a scan of
+/// the aggregates computed by the table provider, when it supports it, see
+/// [`TableProvider::supports_aggregate_pushdown`]
+pub struct AggregatePushDown {}
+
+impl AggregatePushDown {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for AggregatePushDown {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        if let LogicalPlan::Aggregate(Aggregate {
+            group_expr,
+            aggr_expr,
+            schema,
+            input,
+        }) = plan
+        {
+            // the filters of the scan are all exact, as the other ones would be
+            // applied by a filter between the aggregate and the scan
+            if let LogicalPlan::TableScan(TableScan {
+                table_name,
+                source,
+                filters,
+                limit: None,
+                ..
+            }) = input.as_ref()
+            {
+                // the provider doesn't know how the relation was referred to
+                let group_expr = unnormalize_cols(group_expr.iter().cloned());
+                let aggr_expr = unnormalize_cols(aggr_expr.iter().cloned());
+                if source.supports_aggregate_pushdown(&group_expr, &aggr_expr)? {
+                    let table = AggregateTable {
+                        source: source.clone(),
+                        group_expr,
+                        aggr_expr,
+                        filters: unnormalize_cols(filters.iter().cloned()),
+                        schema: Arc::new(schema.as_ref().into()),
+                    };
+                    return Ok(LogicalPlan::TableScan(TableScan {
+                        table_name: table_name.clone(),
+                        source: Arc::new(table),
+                        projection: None,
+                        nested_projection: vec![],
+                        projected_schema: schema.clone(),
+                        filters: vec![],
+                        limit: None,
+                    }));
+                }
+            }
+        }
+        utils::optimize_children(self, plan, execution_props)
+    }
+
+    fn name(&self) -> &str {
+        "aggregate_push_down"
+    }
+}
+
+/// The table of the aggregates of the rows of a table, computed by its provider
+struct AggregateTable {
+    source: Arc<dyn TableProvider>,
+    group_expr: Vec<Expr>,
+    aggr_expr: Vec<Expr>,
+    filters: Vec<Expr>,
+    schema: SchemaRef,
+}
+
+#[async_trait]
+impl TableProvider for AggregateTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        _batch_size: usize,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = self
+            .source
+            .scan_aggregate(
+                &self.group_expr,
+                &self.aggr_expr,
+                &self.filters,
+                self.schema.clone(),
+            )
+            .await?;
+        match projection {
+            None => Ok(plan),
+            Some(projection) => {
+                let expr = projection
+                    .iter()
+                    .map(|i| {
+                        let name = self.schema.field(*i).name();
+                        let column = Arc::new(Column::new(name, *i));
+                        (column as Arc<dyn PhysicalExpr>, name.clone())
+                    })
+                    .collect();
+                Ok(Arc::new(ProjectionExec::try_new(expr, plan)?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, max, LogicalPlanBuilder};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    /// A table computing the aggregates without group expressions
+    struct AggregatingTable {}
+
+    #[async_trait]
+    impl TableProvider for AggregatingTable {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn schema(&self) -> SchemaRef {
+            Arc::new(Schema::new(vec![
+                Field::new("a", DataType::UInt32, false),
+                Field::new("b", DataType::UInt32, false),
+            ]))
+        }
+
+        async fn scan(
+            &self,
+            _: &Option<Vec<usize>>,
+            _: usize,
+            _: &[Expr],
+            _: Option<usize>,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            unimplemented!()
+        }
+
+        fn supports_aggregate_pushdown(
+            &self,
+            group_expr: &[Expr],
+            _aggr_expr: &[Expr],
+        ) -> Result<bool> {
+            Ok(group_expr.is_empty())
+        }
+    }
+
+    fn optimize(plan: &LogicalPlan) -> LogicalPlan {
+        AggregatePushDown::new()
+            .optimize(plan, &ExecutionProps::new())
+            .expect("failed to optimize plan")
+    }
+
+    #[test]
+    fn push_down_aggregate() -> Result<()> {
+        let table_scan =
+            LogicalPlanBuilder::scan("test", Arc::new(AggregatingTable {}), None)?
+                .build()?;
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .aggregate(Vec::<Expr>::new(), vec![max(col("b"))])?
+            .build()?;
+
+        let optimized_plan = optimize(&plan);
+        let expected = "TableScan: test projection=None";
+        assert_eq!(expected, format!("{:?}", optimized_plan));
+        assert_eq!(plan.schema(), optimized_plan.schema());
+
+        let table = match &optimized_plan {
+            LogicalPlan::TableScan(TableScan { source, .. }) => source
+                .as_any()
+                .downcast_ref::<AggregateTable>()
+                .expect("the aggregate is computed by the provider"),
+            _ => unreachable!(),
+        };
+        assert_eq!(vec![max(col("b"))], table.aggr_expr);
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_aggregate() -> Result<()> {
+        let table_scan =
+            LogicalPlanBuilder::scan("test", Arc::new(AggregatingTable {}), None)?
+                .build()?;
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .aggregate(vec![col("a")], vec![max(col("b"))])?
+            .build()?;
+
+        let expected = "Aggregate: groupBy=[[#test.a]], aggr=[[MAX(#test.b)]]\
+        \n  TableScan: test projection=None";
+        assert_eq!(expected, format!("{:?}", optimize(&plan)));
+        Ok(())
+    }
+}
@@ -23,8 +23,8 @@ use crate::logical_plan::plan::{Filter, Projection, Window};
 use crate::logical_plan::{
     col,
     plan::{Aggregate, Sort},
-    DFField, DFSchema, Expr, ExprRewriter, ExpressionVisitor, LogicalPlan, Recursion,
-    RewriteRecursion,
+    DFField, DFSchema, DFSchemaRef, Expr, ExprRewriter, ExpressionVisitor, LogicalPlan,
+    Recursion, RewriteRecursion,
 };
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
@@ -51,8 +51,9 @@ type Identifier = String;
 
 /// Perform Common Sub-expression Elimination optimization.
 ///
-/// Currently only common sub-expressions within one logical plan will
-/// be eliminated.
+/// Common sub-expressions within one logical plan are eliminated, as
+/// well as the ones shared by a projection and the filter right below
+/// it (e.g. `SELECT f(a) FROM t WHERE f(a) > 0`).
 pub struct CommonSubexprEliminate {}
 
 impl OptimizerRule for CommonSubexprEliminate {
@@ -86,6 +87,18 @@ fn optimize(plan: &LogicalPlan, execution_props: &ExecutionProps) -> Result<Logi
             schema,
             alias,
         }) => {
+            if let LogicalPlan::Filter(filter) = input.as_ref() {
+                if let Some(plan) = optimize_projection_over_filter(
+                    expr,
+                    filter,
+                    schema,
+                    alias,
+                    execution_props,
+                )? {
+                    return Ok(plan);
+                }
+            }
+
             let arrays = to_arrays(expr, input, &mut expr_set)?;
 
             let (mut new_expr, new_input) = rewrite_expr(
@@ -210,6 +223,7 @@ fn optimize(plan: &LogicalPlan, execution_props: &ExecutionProps) -> Result<Logi
         | LogicalPlan::Analyze { .. }
         | LogicalPlan::CreateMemoryTable(_)
         | LogicalPlan::DropTable(_)
+        | LogicalPlan::InsertInto(_)
         | LogicalPlan::Extension { .. } => {
             // apply the optimization to all inputs of the plan
             let expr = plan.expressions();
@@ -224,6 +238,69 @@ fn optimize(plan: &LogicalPlan, execution_props: &ExecutionProps) -> Result<Logi
     }
 }
 
+/// Compute the sub-expressions shared by a projection and the filter below it
+/// only once, in a projection below the filter.
+///
+/// Only the sub-expressions that appear in the filter predicate are moved below
+/// the filter, as the other ones would be evaluated on rows that the filter
+/// discards. Returns `None` if the projection and filter share no sub-expression.
+fn optimize_projection_over_filter(
+    expr: &[Expr],
+    filter: &Filter,
+    schema: &DFSchemaRef,
+    alias: &Option<String>,
+    execution_props: &ExecutionProps,
+) -> Result<Option<LogicalPlan>> {
+    let mut expr_set = ExprSet::new();
+    let predicate = [filter.predicate.clone()];
+    let filter_input = filter.input.as_ref();
+
+    let expr_arrays = to_arrays(expr, filter_input, &mut expr_set)?;
+    let predicate_arrays = to_arrays(&predicate, filter_input, &mut expr_set)?;
+
+    let predicate_ids = predicate_arrays
+        .iter()
+        .flatten()
+        .filter(|(_, id)| !id.is_empty())
+        .map(|(_, id)| id.clone())
+        .collect::<HashSet<_>>();
+    let is_shared = expr_arrays
+        .iter()
+        .flatten()
+        .any(|(_, id)| predicate_ids.contains(id));
+    if !is_shared {
+        return Ok(None);
+    }
+
+    // don't extract the sub-expressions that are only repeated in the projection
+    expr_set
+        .iter_mut()
+        .filter(|(id, _)| !predicate_ids.contains(*id))
+        .for_each(|(_, (_, counter, _))| *counter = 1);
+
+    let (mut new_expr, new_input) = rewrite_expr(
+        &[expr, &predicate],
+        &[&expr_arrays, &predicate_arrays],
+        filter_input,
+        &mut expr_set,
+        filter_input.schema(),
+        execution_props,
+    )?;
+    // note the reversed pop order.
+    let new_predicate = new_expr.pop().unwrap().pop().unwrap();
+    let new_expr = new_expr.pop().unwrap();
+
+    Ok(Some(LogicalPlan::Projection(Projection {
+        expr: new_expr,
+        input: Arc::new(LogicalPlan::Filter(Filter {
+            predicate: new_predicate,
+            input: Arc::new(new_input),
+        })),
+        schema: schema.clone(),
+        alias: alias.clone(),
+    })))
+}
+
 fn to_arrays(
     expr: &[Expr],
     input: &LogicalPlan,
@@ -802,4 +879,49 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn projection_and_filter_subexpr() -> Result<()> {
+        let table_scan = test_table_scan()?;
+
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .filter(binary_expr(
+                binary_expr(col("a"), Operator::Plus, lit(1)),
+                Operator::Gt,
+                lit(10),
+            ))?
+            .project(vec![binary_expr(col("a"), Operator::Plus, lit(1))])?
+            .build()?;
+
+        let expected = "Projection: #BinaryExpr-+Literal1Column-test.a AS test.a + Int32(1)\
+        \n  Filter: #BinaryExpr-+Literal1Column-test.a AS test.a + Int32(1) > Int32(10)\
+        \n    Projection: #test.a + Int32(1) AS BinaryExpr-+Literal1Column-test.a, #test.a, #test.b, #test.c\
+        \n      TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn projection_only_subexpr_not_moved_below_filter() -> Result<()> {
+        let table_scan = test_table_scan()?;
+
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .filter(binary_expr(col("b"), Operator::Gt, lit(10)))?
+            .project(vec![
+                binary_expr(col("a"), Operator::Plus, lit(1)).alias("first"),
+                binary_expr(col("a"), Operator::Plus, lit(1)).alias("second"),
+            ])?
+            .build()?;
+
+        let expected = "Projection: #BinaryExpr-+Literal1Column-test.a AS test.a + Int32(1) AS first, #BinaryExpr-+Literal1Column-test.a AS test.a + Int32(1) AS second\
+        \n  Projection: #test.a + Int32(1) AS BinaryExpr-+Literal1Column-test.a, #test.a, #test.b, #test.c\
+        \n    Filter: #test.b > Int32(10)\
+        \n      TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+
+        Ok(())
+    }
 }
@@ -18,7 +18,7 @@ use crate::datasource::datasource::TableProviderFilterPushDown;
 use crate::execution::context::ExecutionProps;
 use crate::logical_plan::plan::{Aggregate, Filter, Join, Projection};
 use crate::logical_plan::{
-    and, replace_col, Column, CrossJoin, Limit, LogicalPlan, TableScan,
+    and, replace_col, Column, CrossJoin, JoinType, Limit, LogicalPlan, TableScan,
 };
 use crate::logical_plan::{DFSchema, Expr};
 use crate::optimizer::optimizer::OptimizerRule;
@@ -54,6 +54,11 @@ use std::{
 /// and when it reaches a node that does not commute with it, it adds the filter to that place.
 /// When it passes through a projection, it re-writes the filter's expression taking into accoun that projection.
 /// When multiple filters would have been written, it `AND` their expressions into a single expression.
+///
+/// When it reaches an equi-join, filters on a join key are also copied to the key of the other side
+/// (`a.k > 1` implies `b.k > 1` for `a.k = b.k`). Outer joins whose null-supplying side is filtered by a
+/// null-rejecting predicate are converted to inner joins; filters referencing only the null-supplying
+/// side of a remaining outer join are kept above the join.
 pub struct FilterPushDown {}
 
 #[derive(Debug, Clone, Default)]
@@ -159,6 +164,121 @@ fn get_join_predicates<'a>(
     (pushable_to_left, pushable_to_right, keep)
 }
 
+/// returns all columns of `schema`, both qualified and unqualified
+fn schema_columns(schema: &DFSchema) -> HashSet<Column> {
+    schema
+        .fields()
+        .iter()
+        .map(|f| [f.qualified_column(), f.unqualified_column()])
+        .flatten()
+        .collect()
+}
+
+/// returns whether the rows of the (left, right) inputs of a join of type `join_type`
+/// are preserved when they have no match on the other side
+fn preserved_sides(join_type: JoinType) -> (bool, bool) {
+    match join_type {
+        JoinType::Inner => (true, true),
+        JoinType::Left => (true, false),
+        JoinType::Right => (false, true),
+        JoinType::Full => (false, false),
+        // the output of semi and anti joins only contains rows of the left input
+        JoinType::Semi | JoinType::Anti => (true, true),
+        // filtering the right input could remove the null keys that make the join
+        // return no rows
+        JoinType::NullAwareAnti => (true, false),
+    }
+}
+
+/// returns the join type of `join` after removing the outer sides that are filtered
+/// by a null-rejecting predicate in `state`.
+///
+/// A predicate is null-rejecting for one side of the join if it can not evaluate to true
+/// when all columns of that side are null. Null-extended rows of that side are then
+/// always filtered out after the join, so the join does not need to produce them.
+fn eliminate_outer_join(state: &State, join: &Join) -> JoinType {
+    let rejects_nulls = |plan: &LogicalPlan| {
+        let columns = schema_columns(plan.schema());
+        state
+            .filters
+            .iter()
+            .any(|(predicate, _)| is_null_rejecting(predicate, &columns))
+    };
+
+    match join.join_type {
+        JoinType::Left if rejects_nulls(&join.right) => JoinType::Inner,
+        JoinType::Right if rejects_nulls(&join.left) => JoinType::Inner,
+        JoinType::Full => match (rejects_nulls(&join.left), rejects_nulls(&join.right)) {
+            (true, true) => JoinType::Inner,
+            (true, false) => JoinType::Left,
+            (false, true) => JoinType::Right,
+            (false, false) => JoinType::Full,
+        },
+        join_type => join_type,
+    }
+}
+
+/// returns true if `predicate` is false or null whenever all `columns` are null
+fn is_null_rejecting(predicate: &Expr, columns: &HashSet<Column>) -> bool {
+    match predicate {
+        Expr::Alias(expr, _) => is_null_rejecting(expr, columns),
+        Expr::Column(column) => columns.contains(column),
+        Expr::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        } => is_null_rejecting(left, columns) || is_null_rejecting(right, columns),
+        Expr::BinaryExpr {
+            left,
+            op: Operator::Or,
+            right,
+        } => is_null_rejecting(left, columns) && is_null_rejecting(right, columns),
+        Expr::BinaryExpr { left, op, right } => match op {
+            Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+            | Operator::Like
+            | Operator::NotLike
+            | Operator::RegexMatch
+            | Operator::RegexIMatch
+            | Operator::RegexNotMatch
+            | Operator::RegexNotIMatch => {
+                is_null_propagating(left, columns) || is_null_propagating(right, columns)
+            }
+            _ => false,
+        },
+        Expr::IsNotNull(expr)
+        | Expr::Between { expr, .. }
+        | Expr::InList { expr, .. } => is_null_propagating(expr, columns),
+        _ => false,
+    }
+}
+
+/// returns true if `expr` evaluates to null whenever all `columns` are null
+fn is_null_propagating(expr: &Expr, columns: &HashSet<Column>) -> bool {
+    match expr {
+        Expr::Column(column) => columns.contains(column),
+        Expr::Alias(expr, _)
+        | Expr::Cast { expr, .. }
+        | Expr::TryCast { expr, .. }
+        | Expr::Negative(expr)
+        | Expr::Not(expr) => is_null_propagating(expr, columns),
+        Expr::BinaryExpr { left, op, right } => match op {
+            Operator::And
+            | Operator::Or
+            | Operator::IsDistinctFrom
+            | Operator::IsNotDistinctFrom => false,
+            _ => {
+                is_null_propagating(left, columns) || is_null_propagating(right, columns)
+            }
+        },
+        _ => false,
+    }
+}
+
 /// Optimizes the plan
 fn push_down(state: &State, plan: &LogicalPlan) -> Result<LogicalPlan> {
     let new_inputs = plan
@@ -398,9 +518,22 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
         LogicalPlan::CrossJoin(CrossJoin { left, right, .. }) => {
             optimize_join(state, plan, left, right)
         }
-        LogicalPlan::Join(Join {
-            left, right, on, ..
-        }) => {
+        LogicalPlan::Join(join) => {
+            let join_type = eliminate_outer_join(&state, join);
+            let rewritten;
+            let plan = if join_type != join.join_type {
+                rewritten = LogicalPlan::Join(Join {
+                    join_type,
+                    ..join.clone()
+                });
+                &rewritten
+            } else {
+                plan
+            };
+            let Join {
+                left, right, on, ..
+            } = join;
+
             // duplicate filters for joined columns so filters can be pushed down to both sides.
             // Take the following query as an example:
             //
@@ -413,9 +546,20 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             //
             // Join clauses with `Using` constraints also take advantage of this logic to make sure
             // predicates reference the shared join columns are pushed to both sides.
+            //
+            // Only the predicates on the preserved sides of the join are duplicated: one on the
+            // null-supplying side of an outer join may keep its null-extended rows, e.g.
+            // `t2.id IS NULL` after `t1 LEFT JOIN t2`, which `t1.id IS NULL` would drop.
+            let (left_preserved, right_preserved) = preserved_sides(join_type);
+            let left_columns = schema_columns(left.schema());
+            let right_columns = schema_columns(right.schema());
             let join_side_filters = state
                 .filters
                 .iter()
+                .filter(|(_, columns)| {
+                    (left_preserved || columns.is_disjoint(&left_columns))
+                        && (right_preserved || columns.is_disjoint(&right_columns))
+                })
                 .filter_map(|(predicate, columns)| {
                     let mut join_cols_to_replace = HashMap::new();
                     for col in columns.iter() {
@@ -454,55 +598,97 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                     Some(Ok((join_side_predicate, join_side_columns)))
                 })
                 .collect::<Result<Vec<_>>>()?;
-            state.filters.extend(join_side_filters);
 
-            optimize_join(state, plan, left, right)
+            // Predicates that only reference the null-supplying side of an outer join would
+            // also filter the null-extended rows, so they must be evaluated after the join.
+            // The predicates derived above from the preserved side only filter rows of the
+            // other side that could not have matched, so they are pushed down.
+            let mut post_join_filters = vec![];
+            if !left_preserved || !right_preserved {
+                let (post_join, filters): (Vec<_>, Vec<_>) =
+                    state.filters.into_iter().partition(|(_, columns)| {
+                        (!left_preserved && columns.is_subset(&left_columns))
+                            || (!right_preserved && columns.is_subset(&right_columns))
+                    });
+                state.filters = filters;
+                post_join_filters = post_join;
+            }
+            // the derived predicates would filter the null keys of the right input of a
+            // null aware anti join, which make it return no rows
+            if join_type != JoinType::NullAwareAnti {
+                state.filters.extend(join_side_filters);
+            }
+
+            let plan = optimize_join(state, plan, left, right)?;
+            if post_join_filters.is_empty() {
+                Ok(plan)
+            } else {
+                let predicates = post_join_filters
+                    .iter()
+                    .map(|(predicate, _)| predicate)
+                    .collect::<Vec<_>>();
+                Ok(add_filter(plan, &predicates))
+            }
         }
         LogicalPlan::TableScan(TableScan {
             source,
             projected_schema,
             filters,
             projection,
+            nested_projection,
             table_name,
             limit,
         }) => {
-            let mut used_columns = HashSet::new();
+            // the filters that the provider does not fully handle, or their
+            // residuals, are applied to the output of the scan
+            let mut residual_filters = vec![];
             let mut new_filters = filters.clone();
 
-            for (filter_expr, cols) in &state.filters {
-                let (preserve_filter_node, add_to_provider) =
+            for (filter_expr, _) in &state.filters {
+                let (residual_filter, add_to_provider) =
                     match source.supports_filter_pushdown(filter_expr)? {
-                        TableProviderFilterPushDown::Unsupported => (true, false),
-                        TableProviderFilterPushDown::Inexact => (true, true),
-                        TableProviderFilterPushDown::Exact => (false, true),
+                        TableProviderFilterPushDown::Unsupported => {
+                            (Some(filter_expr.clone()), false)
+                        }
+                        TableProviderFilterPushDown::Inexact => {
+                            (Some(filter_expr.clone()), true)
+                        }
+                        TableProviderFilterPushDown::Exact => (None, true),
+                        TableProviderFilterPushDown::Residual(residual) => {
+                            (Some(residual), true)
+                        }
                     };
 
-                if preserve_filter_node {
-                    used_columns.extend(cols.clone());
+                if let Some(residual_filter) = residual_filter {
+                    if !residual_filters.contains(&residual_filter) {
+                        residual_filters.push(residual_filter);
+                    }
                 }
 
-                if add_to_provider {
-                    // Don't add expression again if it's already present in
-                    // pushed down filters.
-                    if new_filters.contains(filter_expr) {
-                        break;
-                    }
+                // Don't add expression again if it's already present in
+                // pushed down filters.
+                if add_to_provider && !new_filters.contains(filter_expr) {
                     new_filters.push(filter_expr.clone());
                 }
             }
 
-            issue_filters(
-                state,
-                used_columns,
-                &LogicalPlan::TableScan(TableScan {
-                    source: source.clone(),
-                    projection: projection.clone(),
-                    projected_schema: projected_schema.clone(),
-                    table_name: table_name.clone(),
-                    filters: new_filters,
-                    limit: *limit,
-                }),
-            )
+            let plan = LogicalPlan::TableScan(TableScan {
+                source: source.clone(),
+                projection: projection.clone(),
+                nested_projection: nested_projection.clone(),
+                projected_schema: projected_schema.clone(),
+                table_name: table_name.clone(),
+                filters: new_filters,
+                limit: *limit,
+            });
+            if residual_filters.is_empty() {
+                Ok(plan)
+            } else {
+                Ok(add_filter(
+                    plan,
+                    &residual_filters.iter().collect::<Vec<_>>(),
+                ))
+            }
         }
         _ => {
             // all other plans are _not_ filter-commutable
@@ -1138,8 +1324,143 @@ mod tests {
         Ok(())
     }
 
+    fn optimized_join_type(plan: &LogicalPlan) -> JoinType {
+        match plan {
+            LogicalPlan::Join(Join { join_type, .. }) => *join_type,
+            plan => optimized_join_type(plan.inputs()[0]),
+        }
+    }
+
+    fn outer_join_plan(join_type: JoinType, predicate: Expr) -> Result<LogicalPlan> {
+        let left = LogicalPlanBuilder::from(test_table_scan()?)
+            .project(vec![col("a"), col("b")])?
+            .build()?;
+        let right = LogicalPlanBuilder::from(test_table_scan_with_name("test2")?)
+            .project(vec![col("a"), col("c")])?
+            .build()?;
+        LogicalPlanBuilder::from(left)
+            .join(
+                &right,
+                join_type,
+                (vec![Column::from_name("a")], vec![Column::from_name("a")]),
+            )?
+            .filter(predicate)?
+            .build()
+    }
+
+    /// a null-rejecting predicate on the right side turns a left join into an inner join
+    #[test]
+    fn filter_left_join_on_right_side_to_inner() -> Result<()> {
+        let plan = outer_join_plan(JoinType::Left, col("test2.c").lt_eq(lit(1i64)))?;
+
+        let expected = "\
+        Join: #test.a = #test2.a\
+        \n  Projection: #test.a, #test.b\
+        \n    TableScan: test projection=None\
+        \n  Projection: #test2.a, #test2.c\
+        \n    Filter: #test2.c <= Int64(1)\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(optimized_join_type(&optimize_plan(&plan)), JoinType::Inner);
+        Ok(())
+    }
+
+    /// `IS NULL` keeps the null-extended rows, so the join stays a left join and the
+    /// predicate is evaluated after it
+    #[test]
+    fn filter_left_join_on_right_side_is_null() -> Result<()> {
+        let plan = outer_join_plan(JoinType::Left, col("test2.c").is_null())?;
+
+        let expected = "\
+        Filter: #test2.c IS NULL\
+        \n  Join: #test.a = #test2.a\
+        \n    Projection: #test.a, #test.b\
+        \n      TableScan: test projection=None\
+        \n    Projection: #test2.a, #test2.c\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(optimized_join_type(&optimize_plan(&plan)), JoinType::Left);
+        Ok(())
+    }
+
+    /// predicates on the preserved side of a left join are pushed to that side, and
+    /// the predicates derived for the join key of the other side as well
+    #[test]
+    fn filter_left_join_on_join_key() -> Result<()> {
+        let plan = outer_join_plan(JoinType::Left, col("test.a").lt_eq(lit(1i64)))?;
+
+        let expected = "\
+        Join: #test.a = #test2.a\
+        \n  Projection: #test.a, #test.b\
+        \n    Filter: #test.a <= Int64(1)\
+        \n      TableScan: test projection=None\
+        \n  Projection: #test2.a, #test2.c\
+        \n    Filter: #test2.a <= Int64(1)\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(optimized_join_type(&optimize_plan(&plan)), JoinType::Left);
+        Ok(())
+    }
+
+    /// `IS NULL` on the join key of the null-supplying side keeps the unmatched rows of
+    /// the preserved side, so it is not duplicated for the key of that side
+    #[test]
+    fn filter_left_join_on_right_join_key_is_null() -> Result<()> {
+        let plan = outer_join_plan(JoinType::Left, col("test2.a").is_null())?;
+
+        let expected = "\
+        Filter: #test2.a IS NULL\
+        \n  Join: #test.a = #test2.a\
+        \n    Projection: #test.a, #test.b\
+        \n      TableScan: test projection=None\
+        \n    Projection: #test2.a, #test2.c\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(optimized_join_type(&optimize_plan(&plan)), JoinType::Left);
+        Ok(())
+    }
+
+    /// a full join filtered on one side only becomes a one-sided outer join
+    #[test]
+    fn filter_full_join_on_one_side() -> Result<()> {
+        let plan = outer_join_plan(JoinType::Full, col("test.b").gt(lit(1i64)))?;
+
+        let expected = "\
+        Join: #test.a = #test2.a\
+        \n  Projection: #test.a, #test.b\
+        \n    Filter: #test.b > Int64(1)\
+        \n      TableScan: test projection=None\
+        \n  Projection: #test2.a, #test2.c\
+        \n    TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(optimized_join_type(&optimize_plan(&plan)), JoinType::Left);
+        Ok(())
+    }
+
+    /// a disjunction is only null-rejecting if all its members are
+    #[test]
+    fn filter_full_join_on_disjunction() -> Result<()> {
+        let plan = outer_join_plan(
+            JoinType::Full,
+            col("test.b").gt(lit(1i64)).or(col("test2.c").gt(lit(1i64))),
+        )?;
+
+        let expected = "\
+        Filter: #test.b > Int64(1) OR #test2.c > Int64(1)\
+        \n  Join: #test.a = #test2.a\
+        \n    Projection: #test.a, #test.b\
+        \n      TableScan: test projection=None\
+        \n    Projection: #test2.a, #test2.c\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(optimized_join_type(&optimize_plan(&plan)), JoinType::Full);
+        Ok(())
+    }
+
+    type FilterSupport = Box<dyn Fn(&Expr) -> TableProviderFilterPushDown + Send + Sync>;
+
     struct PushDownProvider {
-        pub filter_support: TableProviderFilterPushDown,
+        pub filter_support: FilterSupport,
     }
 
     #[async_trait]
@@ -1166,9 +1487,9 @@ mod tests {
 
         fn supports_filter_pushdown(
             &self,
-            _: &Expr,
+            filter: &Expr,
         ) -> Result<TableProviderFilterPushDown> {
-            Ok(self.filter_support.clone())
+            Ok((self.filter_support)(filter))
         }
 
         fn as_any(&self) -> &dyn std::any::Any {
@@ -1179,22 +1500,27 @@ mod tests {
     fn table_scan_with_pushdown_provider(
         filter_support: TableProviderFilterPushDown,
     ) -> Result<LogicalPlan> {
+        LogicalPlanBuilder::from(pushdown_table_scan(Box::new(move |_| {
+            filter_support.clone()
+        }))?)
+        .filter(col("a").eq(lit(1i64)))?
+        .build()
+    }
+
+    fn pushdown_table_scan(filter_support: FilterSupport) -> Result<LogicalPlan> {
         let test_provider = PushDownProvider { filter_support };
 
-        let table_scan = LogicalPlan::TableScan(TableScan {
+        Ok(LogicalPlan::TableScan(TableScan {
             table_name: "test".to_string(),
             filters: vec![],
             projected_schema: Arc::new(DFSchema::try_from(
                 (*test_provider.schema()).clone(),
             )?),
             projection: None,
+            nested_projection: vec![],
             source: Arc::new(test_provider),
             limit: None,
-        });
-
-        LogicalPlanBuilder::from(table_scan)
-            .filter(col("a").eq(lit(1i64)))?
-            .build()
+        }))
     }
 
     #[test]
@@ -1247,4 +1573,40 @@ mod tests {
         assert_optimized_plan_eq(&plan, expected);
         Ok(())
     }
+
+    #[test]
+    fn filter_with_table_provider_residual() -> Result<()> {
+        let plan = table_scan_with_pushdown_provider(
+            TableProviderFilterPushDown::Residual(col("a").is_not_null()),
+        )?;
+
+        let expected = "\
+        Filter: #a IS NOT NULL\
+        \n  TableScan: test projection=None, filters=[#a = Int64(1)]";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_with_table_provider_exact_and_inexact() -> Result<()> {
+        // only the inexact filter is re-applied, even though both filters are
+        // on the same column
+        let exact_filter = col("a").eq(lit(1i64));
+        let table_scan = pushdown_table_scan(Box::new(move |filter| {
+            if filter == &exact_filter {
+                TableProviderFilterPushDown::Exact
+            } else {
+                TableProviderFilterPushDown::Inexact
+            }
+        }))?;
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .filter(and(col("a").eq(lit(1i64)), col("a").gt(lit(0i64))))?
+            .build()?;
+
+        let expected = "\
+        Filter: #a > Int64(0)\
+        \n  TableScan: test projection=None, filters=[#a = Int64(1), #a > Int64(0)]";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
 }
@@ -46,22 +46,28 @@ fn limit_push_down(
     match (plan, upper_limit) {
         (LogicalPlan::Limit(Limit { n, input }), upper_limit) => {
             let smallest = upper_limit.map(|x| std::cmp::min(x, *n)).unwrap_or(*n);
-            Ok(LogicalPlan::Limit(Limit {
-                n: smallest,
-                // push down limit to plan (minimum of upper limit and current limit)
-                input: Arc::new(limit_push_down(
-                    optimizer,
-                    Some(smallest),
-                    input.as_ref(),
-                    execution_props,
-                )?),
-            }))
+            // push down limit to plan (minimum of upper limit and current limit)
+            let input = limit_push_down(
+                optimizer,
+                Some(smallest),
+                input.as_ref(),
+                execution_props,
+            )?;
+            if is_limited_by_scan(&input, smallest) {
+                Ok(input)
+            } else {
+                Ok(LogicalPlan::Limit(Limit {
+                    n: smallest,
+                    input: Arc::new(input),
+                }))
+            }
         }
         (
             LogicalPlan::TableScan(TableScan {
                 table_name,
                 source,
                 projection,
+                nested_projection,
                 filters,
                 limit,
                 projected_schema,
@@ -71,6 +77,7 @@ fn limit_push_down(
             table_name: table_name.clone(),
             source: source.clone(),
             projection: projection.clone(),
+            nested_projection: nested_projection.clone(),
             filters: filters.clone(),
             limit: limit
                 .map(|x| std::cmp::min(x, upper_limit))
@@ -145,6 +152,20 @@ fn limit_push_down(
     }
 }
 
+/// Whether `plan` returns at most `n` rows, as its table scan applies the limit
+fn is_limited_by_scan(plan: &LogicalPlan, n: usize) -> bool {
+    match plan {
+        LogicalPlan::TableScan(TableScan {
+            source,
+            limit: Some(limit),
+            ..
+        }) => *limit <= n && source.supports_limit_pushdown(),
+        // projections don't change the number of rows
+        LogicalPlan::Projection(Projection { input, .. }) => is_limited_by_scan(input, n),
+        _ => false,
+    }
+}
+
 impl OptimizerRule for LimitPushDown {
     fn optimize(
         &self,
@@ -18,22 +18,24 @@
 //! Projection Push Down optimizer rule ensures that only referenced columns are
 //! loaded into memory
 
+use crate::datasource::nested_projection::project_schema;
 use crate::error::{DataFusionError, Result};
 use crate::execution::context::ExecutionProps;
 use crate::logical_plan::plan::{
     Aggregate, Analyze, Join, Projection, TableScan, Window,
 };
 use crate::logical_plan::{
-    build_join_schema, Column, DFField, DFSchema, DFSchemaRef, LogicalPlan,
-    LogicalPlanBuilder, ToDFSchema, Union,
+    build_join_schema, Column, DFField, DFSchema, DFSchemaRef, Expr, ExpressionVisitor,
+    LogicalPlan, LogicalPlanBuilder, Recursion, ToDFSchema, Union,
 };
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
+use crate::scalar::ScalarValue;
 use crate::sql::utils::find_sort_exprs;
-use arrow::datatypes::{Field, Schema};
+use arrow::datatypes::{DataType, Field, Schema};
 use arrow::error::Result as ArrowResult;
 use std::{
-    collections::{BTreeSet, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     sync::Arc,
 };
 
@@ -54,7 +56,19 @@ impl OptimizerRule for ProjectionPushDown {
             .iter()
             .map(|f| f.qualified_column())
             .collect::<HashSet<Column>>();
-        optimize_plan(self, plan, &required_columns, false, execution_props)
+        let mut nested_usage = NestedUsage::new();
+        collect_nested_usage(plan, &mut nested_usage)?;
+        for column in &required_columns {
+            nested_usage.insert(column.clone(), None);
+        }
+        optimize_plan(
+            self,
+            plan,
+            &required_columns,
+            false,
+            &nested_usage,
+            execution_props,
+        )
     }
 
     fn name(&self) -> &str {
@@ -123,12 +137,129 @@ fn get_projected_schema(
     Ok((projection, projected_fields.to_dfschema_ref()?))
 }
 
+/// The paths of the fields of the struct columns referenced by a plan, by column,
+/// or `None` for the columns that are referenced entirely
+type NestedUsage = HashMap<Column, Option<BTreeSet<Vec<String>>>>;
+
+/// Collects the paths of the fields referenced in a plan and its inputs
+fn collect_nested_usage(plan: &LogicalPlan, usage: &mut NestedUsage) -> Result<()> {
+    match plan {
+        LogicalPlan::TableScan(TableScan { filters, .. }) => {
+            for expr in filters {
+                expr.accept(NestedUsageVisitor { usage })?;
+            }
+        }
+        // the columns of the inputs of these plans are used as a whole
+        LogicalPlan::Union(_)
+        | LogicalPlan::Extension(_)
+        | LogicalPlan::Analyze(_)
+        | LogicalPlan::Explain(_)
+        | LogicalPlan::CreateMemoryTable(_)
+        | LogicalPlan::InsertInto(_) => {
+            for input in plan.inputs() {
+                for field in input.schema().fields() {
+                    usage.insert(field.qualified_column(), None);
+                }
+            }
+        }
+        _ => {}
+    }
+    for expr in plan.expressions() {
+        expr.accept(NestedUsageVisitor { usage })?;
+    }
+    for input in plan.inputs() {
+        collect_nested_usage(input, usage)?;
+    }
+    Ok(())
+}
+
+struct NestedUsageVisitor<'a> {
+    usage: &'a mut NestedUsage,
+}
+
+impl ExpressionVisitor for NestedUsageVisitor<'_> {
+    fn pre_visit(self, expr: &Expr) -> Result<Recursion<Self>> {
+        match expr {
+            Expr::Column(column) => {
+                self.usage.insert(column.clone(), None);
+            }
+            Expr::GetIndexedField { .. } => {
+                if let Some((column, path)) = field_path(expr) {
+                    let paths = self
+                        .usage
+                        .entry(column)
+                        .or_insert_with(|| Some(BTreeSet::new()));
+                    if let Some(paths) = paths {
+                        paths.insert(path);
+                    }
+                    return Ok(Recursion::Stop(self));
+                }
+            }
+            _ => {}
+        }
+        Ok(Recursion::Continue(self))
+    }
+}
+
+/// The column and the path of the struct field accessed by `expr`, if it only
+/// accesses struct fields by name
+fn field_path(expr: &Expr) -> Option<(Column, Vec<String>)> {
+    match expr {
+        Expr::Column(column) => Some((column.clone(), vec![])),
+        Expr::GetIndexedField {
+            expr,
+            key: ScalarValue::Utf8(Some(key)),
+        } => {
+            let (column, mut path) = field_path(expr)?;
+            path.push(key.clone());
+            Some((column, path))
+        }
+        _ => None,
+    }
+}
+
+/// The paths of the fields of the struct columns of a scan of `table_name` that
+/// are referenced, for the columns that are not referenced entirely
+fn get_nested_projection(
+    table_name: &str,
+    projected_schema: &DFSchema,
+    nested_usage: &NestedUsage,
+) -> Vec<Vec<String>> {
+    let mut nested_projection = BTreeSet::new();
+    for field in projected_schema.fields() {
+        if !matches!(field.data_type(), DataType::Struct(_)) {
+            continue;
+        }
+        let usages = [
+            Column {
+                relation: Some(table_name.to_owned()),
+                name: field.name().clone(),
+            },
+            Column::from_name(field.name()),
+        ]
+        .iter()
+        .filter_map(|column| nested_usage.get(column))
+        .collect::<Vec<_>>();
+        // columns that are not referenced, e.g. to count rows, are read entirely
+        if usages.is_empty() || usages.iter().any(|paths| paths.is_none()) {
+            continue;
+        }
+        for path in usages.into_iter().flatten().flatten() {
+            let mut column_path = vec![field.name().clone()];
+            column_path.extend(path.iter().cloned());
+            nested_projection.insert(column_path);
+        }
+    }
+    nested_projection.into_iter().collect()
+}
+
 /// Recursively transverses the logical plan removing expressions and that are not needed.
 fn optimize_plan(
     optimizer: &ProjectionPushDown,
     plan: &LogicalPlan,
     required_columns: &HashSet<Column>, // set of columns required up to this step
     has_projection: bool,
+    nested_usage: &NestedUsage,
     execution_props: &ExecutionProps,
 ) -> Result<LogicalPlan> {
     let mut new_required_columns = required_columns.clone();
@@ -168,6 +299,7 @@ fn optimize_plan(
                 input,
                 &new_required_columns,
                 true,
+                nested_usage,
                 execution_props,
             )?;
 
@@ -211,6 +343,7 @@ fn optimize_plan(
                 left,
                 &new_required_columns,
                 true,
+                nested_usage,
                 execution_props,
             )?);
 
@@ -219,6 +352,7 @@ fn optimize_plan(
                 right,
                 &new_required_columns,
                 true,
+                nested_usage,
                 execution_props,
             )?);
 
@@ -272,6 +406,7 @@ fn optimize_plan(
                 input,
                 &new_required_columns,
                 true,
+                nested_usage,
                 execution_props,
             )?)
             .window(new_window_expr)?
@@ -323,6 +458,7 @@ fn optimize_plan(
                     input,
                     &new_required_columns,
                     true,
+                    nested_usage,
                     execution_props,
                 )?),
                 schema: DFSchemaRef::new(new_schema),
@@ -337,17 +473,31 @@ fn optimize_plan(
             limit,
             ..
         }) => {
-            let (projection, projected_schema) = get_projected_schema(
+            let (projection, mut projected_schema) = get_projected_schema(
                 Some(table_name),
                 &source.schema(),
                 required_columns,
                 has_projection,
             )?;
+            // only read the referenced fields of the struct columns
+            let nested_projection = if source.supports_nested_projection() {
+                get_nested_projection(table_name, &projected_schema, nested_usage)
+            } else {
+                vec![]
+            };
+            if !nested_projection.is_empty() {
+                let schema: Schema = projected_schema.as_ref().into();
+                projected_schema = Arc::new(DFSchema::try_from_qualified_schema(
+                    table_name,
+                    &project_schema(&schema, &nested_projection),
+                )?);
+            }
             // return the table scan with projection
             Ok(LogicalPlan::TableScan(TableScan {
                 table_name: table_name.clone(),
                 source: source.clone(),
                 projection: Some(projection),
+                nested_projection,
                 projected_schema,
                 filters: filters.clone(),
                 limit: *limit,
@@ -372,9 +522,11 @@ fn optimize_plan(
                     &a.input,
                     &required_columns,
                     false,
+                    nested_usage,
                     execution_props,
                 )?),
                 verbose: a.verbose,
+                format: a.format,
                 schema: a.schema.clone(),
             }))
         }
@@ -408,6 +560,7 @@ fn optimize_plan(
                         input_plan,
                         &new_required_columns,
                         has_projection,
+                        nested_usage,
                         execution_props,
                     )
                 })
@@ -437,6 +590,7 @@ fn optimize_plan(
         | LogicalPlan::CreateExternalTable(_)
         | LogicalPlan::CreateMemoryTable(_)
         | LogicalPlan::DropTable(_)
+        | LogicalPlan::InsertInto(_)
         | LogicalPlan::CrossJoin(_)
         | LogicalPlan::Extension { .. } => {
             let expr = plan.expressions();
@@ -453,6 +607,7 @@ fn optimize_plan(
                         input_plan,
                         &new_required_columns,
                         has_projection,
+                        nested_usage,
                         execution_props,
                     )
                 })
@@ -17,12 +17,12 @@
 
 //! Simplify expressions optimizer rule
 
-use arrow::array::new_null_array;
+use arrow::array::{new_null_array, Array};
 use arrow::datatypes::{DataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
 
 use crate::error::DataFusionError;
-use crate::execution::context::{ExecutionContextState, ExecutionProps};
+use crate::execution::context::{ExecutionConfig, ExecutionContextState, ExecutionProps};
 use crate::logical_plan::{lit, DFSchemaRef, Expr};
 use crate::logical_plan::{DFSchema, ExprRewriter, LogicalPlan, RewriteRecursion};
 use crate::optimizer::optimizer::OptimizerRule;
@@ -427,6 +427,7 @@ impl ConstEvaluator {
         let planner = DefaultPhysicalPlanner::default();
         let ctx_state = ExecutionContextState {
             execution_props: execution_props.clone(),
+            config: ExecutionConfig::new().with_ansi_mode(execution_props.ansi_mode),
             ..ExecutionContextState::new()
         };
         let input_schema = DFSchema::empty();
@@ -534,6 +535,13 @@ impl ConstEvaluator {
 /// * `false = true` and `true = false` to `false`
 /// * `!!expr` to `expr`
 /// * `expr = null` and `expr != null` to `null`
+/// * `expr IN (v)` to `expr = v` and `expr NOT IN (v)` to `expr != v`
+/// * `CAST(expr AS t)` to `expr` when `expr` is already of type `t`
+/// * `CAST(CAST(expr AS t1) AS t0)` to `expr` when `expr` is of type `t0`
+///   and `t1` is a lossless widening of `t0`
+/// * `CAST(int_col AS wider_int) op lit` to `int_col op CAST(lit)` when the
+///   literal fits into the column type, so that the predicate can be used
+///   for scan-level pruning
 pub(crate) struct Simplifier<'a> {
     /// input schemas
     schemas: Vec<&'a DFSchemaRef>,
@@ -554,6 +562,87 @@ impl<'a> Simplifier<'a> {
         false
     }
 
+    fn get_type(&self, expr: &Expr) -> Option<DataType> {
+        self.schemas
+            .iter()
+            .find_map(|schema| expr.get_type(schema).ok())
+    }
+
+    /// Removes casts that do not change the value of their input
+    fn simplify_cast(&self, expr: Box<Expr>, data_type: DataType) -> Expr {
+        match self.get_type(&expr) {
+            // CAST(expr AS t) where expr: t --> expr
+            Some(input_type) if input_type == data_type => *expr,
+            _ => match *expr {
+                // CAST(CAST(expr AS t1) AS t0) where expr: t0 and t1 wider than t0 --> expr
+                Expr::Cast {
+                    expr: inner,
+                    data_type: inner_type,
+                } if self.get_type(&inner).as_ref() == Some(&data_type)
+                    && is_lossless_int_widening(&data_type, &inner_type) =>
+                {
+                    *inner
+                }
+                expr => Expr::Cast {
+                    expr: Box::new(expr),
+                    data_type,
+                },
+            },
+        }
+    }
+
+    /// Rewrites `CAST(col AS t) op literal` to `col op literal'` if the cast
+    /// only widens an integer column and the literal can be represented
+    /// exactly in the column type. The cast-free form can be evaluated
+    /// against column statistics by the pruning predicates of the scans.
+    fn unwrap_cast_in_comparison(
+        &self,
+        left: Box<Expr>,
+        op: Operator,
+        right: Box<Expr>,
+    ) -> Result<Expr> {
+        let (cast_expr, literal, op, cast_on_left) = match (*left, *right) {
+            (cast @ Expr::Cast { .. }, Expr::Literal(value)) => (cast, value, op, true),
+            (Expr::Literal(value), cast @ Expr::Cast { .. }) => {
+                (cast, value, reverse_comparison(op), false)
+            }
+            (left, right) => {
+                return Ok(Expr::BinaryExpr {
+                    left: Box::new(left),
+                    op,
+                    right: Box::new(right),
+                })
+            }
+        };
+
+        if let Expr::Cast { expr, data_type } = &cast_expr {
+            if let Some(input_type) = self.get_type(expr) {
+                if is_lossless_int_widening(&input_type, data_type) && !literal.is_null()
+                {
+                    if let Some(narrowed) = narrow_literal(&literal, &input_type)? {
+                        return Ok(Expr::BinaryExpr {
+                            left: expr.clone(),
+                            op,
+                            right: Box::new(Expr::Literal(narrowed)),
+                        });
+                    }
+                }
+            }
+        }
+
+        // not rewritable, restore the original operand order
+        let (left, right, op) = if cast_on_left {
+            (cast_expr, Expr::Literal(literal), op)
+        } else {
+            (Expr::Literal(literal), cast_expr, reverse_comparison(op))
+        };
+        Ok(Expr::BinaryExpr {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        })
+    }
+
     fn boolean_folding_for_or(
         const_bool: &Option<bool>,
         bool_expr: Box<Expr>,
@@ -646,6 +735,13 @@ impl<'a> Simplifier<'a> {
 impl<'a> ExprRewriter for Simplifier<'a> {
     /// rewrite the expression simplifying any constant expressions
     fn mutate(&mut self, expr: Expr) -> Result<Expr> {
+        let expr = match expr {
+            Expr::BinaryExpr { left, op, right } if is_comparison(op) => {
+                self.unwrap_cast_in_comparison(left, op, right)?
+            }
+            expr => expr,
+        };
+
         let new_expr = match expr {
             Expr::BinaryExpr { left, op, right } => match op {
                 Operator::Eq => match (left.as_ref(), right.as_ref()) {
@@ -760,6 +856,21 @@ impl<'a> ExprRewriter for Simplifier<'a> {
                     Expr::Not(inner)
                 }
             }
+            // expr IN (v) --> expr = v, expr NOT IN (v) --> expr != v
+            Expr::InList {
+                expr,
+                mut list,
+                negated,
+            } if list.len() == 1 => Expr::BinaryExpr {
+                left: expr,
+                op: if negated {
+                    Operator::NotEq
+                } else {
+                    Operator::Eq
+                },
+                right: Box::new(list.remove(0)),
+            },
+            Expr::Cast { expr, data_type } => self.simplify_cast(expr, data_type),
             expr => {
                 // no additional rewrites possible
                 expr
@@ -769,6 +880,80 @@ impl<'a> ExprRewriter for Simplifier<'a> {
     }
 }
 
+fn is_comparison(op: Operator) -> bool {
+    matches!(
+        op,
+        Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+    )
+}
+
+/// Returns the operator to use when swapping the operands of a comparison
+fn reverse_comparison(op: Operator) -> Operator {
+    match op {
+        Operator::Lt => Operator::Gt,
+        Operator::Gt => Operator::Lt,
+        Operator::LtEq => Operator::GtEq,
+        Operator::GtEq => Operator::LtEq,
+        _ => op,
+    }
+}
+
+/// Returns the (signedness, bit width) of an integer type
+fn int_type_width(data_type: &DataType) -> Option<(bool, u8)> {
+    match data_type {
+        DataType::Int8 => Some((true, 8)),
+        DataType::Int16 => Some((true, 16)),
+        DataType::Int32 => Some((true, 32)),
+        DataType::Int64 => Some((true, 64)),
+        DataType::UInt8 => Some((false, 8)),
+        DataType::UInt16 => Some((false, 16)),
+        DataType::UInt32 => Some((false, 32)),
+        DataType::UInt64 => Some((false, 64)),
+        _ => None,
+    }
+}
+
+/// Returns true if every value of integer type `from` is represented exactly
+/// by integer type `to`
+fn is_lossless_int_widening(from: &DataType, to: &DataType) -> bool {
+    match (int_type_width(from), int_type_width(to)) {
+        (Some((from_signed, from_width)), Some((to_signed, to_width))) => {
+            if from_signed == to_signed {
+                from_width <= to_width
+            } else {
+                !from_signed && from_width < to_width
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Casts `value` to `data_type`, returning `None` if the value can not be
+/// represented exactly in the target type
+fn narrow_literal(
+    value: &ScalarValue,
+    data_type: &DataType,
+) -> Result<Option<ScalarValue>> {
+    let source_type = value.get_datatype();
+    if int_type_width(&source_type).is_none() {
+        return Ok(None);
+    }
+    let narrowed = arrow::compute::cast(&value.to_array(), data_type)?;
+    if narrowed.is_null(0) {
+        return Ok(None);
+    }
+    let round_trip = arrow::compute::cast(&narrowed, &source_type)?;
+    if ScalarValue::try_from_array(&round_trip, 0)? != *value {
+        return Ok(None);
+    }
+    Ok(Some(ScalarValue::try_from_array(&narrowed, 0)?))
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -1159,6 +1344,7 @@ mod tests {
     ) {
         let execution_props = ExecutionProps {
             query_execution_start_time: *date_time,
+            ansi_mode: false,
         };
 
         let mut const_evaluator = ConstEvaluator::new(&execution_props);
@@ -1189,11 +1375,19 @@ mod tests {
         expr.rewrite(&mut rewriter).expect("expected to simplify")
     }
 
+    fn cast(expr: Expr, data_type: DataType) -> Expr {
+        Expr::Cast {
+            expr: Box::new(expr),
+            data_type,
+        }
+    }
+
     fn expr_test_schema() -> DFSchemaRef {
         Arc::new(
             DFSchema::new(vec![
                 DFField::new(None, "c1", DataType::Utf8, true),
                 DFField::new(None, "c2", DataType::Boolean, true),
+                DFField::new(None, "c3", DataType::Int32, true),
             ])
             .unwrap(),
         )
@@ -1233,6 +1427,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn simplify_expr_in_list_single() {
+        // c1 IN ('a') -> c1 = 'a'
+        assert_eq!(
+            do_simplify(col("c1").in_list(vec![lit("a")], false)),
+            col("c1").eq(lit("a")),
+        );
+
+        // c1 NOT IN ('a') -> c1 != 'a'
+        assert_eq!(
+            do_simplify(col("c1").in_list(vec![lit("a")], true)),
+            col("c1").not_eq(lit("a")),
+        );
+
+        // lists with several items are kept
+        let expr = col("c1").in_list(vec![lit("a"), lit("b")], false);
+        assert_eq!(do_simplify(expr.clone()), expr);
+    }
+
+    #[test]
+    fn simplify_expr_cast() {
+        // CAST(c3 AS Int32) -> c3
+        assert_eq!(do_simplify(cast(col("c3"), DataType::Int32)), col("c3"));
+
+        // CAST(CAST(c3 AS Int64) AS Int32) -> c3
+        assert_eq!(
+            do_simplify(cast(cast(col("c3"), DataType::Int64), DataType::Int32)),
+            col("c3"),
+        );
+
+        // CAST(CAST(c3 AS Int16) AS Int32) may truncate and is kept
+        let expr = cast(cast(col("c3"), DataType::Int16), DataType::Int32);
+        assert_eq!(do_simplify(expr.clone()), expr);
+
+        // CAST(c3 AS Utf8) is kept
+        let expr = cast(col("c3"), DataType::Utf8);
+        assert_eq!(do_simplify(expr.clone()), expr);
+    }
+
+    #[test]
+    fn simplify_expr_unwrap_cast_in_comparison() {
+        // CAST(c3 AS Int64) > Int64(5) -> c3 > Int32(5)
+        assert_eq!(
+            do_simplify(cast(col("c3"), DataType::Int64).gt(lit(5i64))),
+            col("c3").gt(lit(5i32)),
+        );
+
+        // Int64(5) <= CAST(c3 AS Int64) -> c3 >= Int32(5)
+        assert_eq!(
+            do_simplify(lit(5i64).lt_eq(cast(col("c3"), DataType::Int64))),
+            col("c3").gt_eq(lit(5i32)),
+        );
+
+        // literals that do not fit into the column type are kept
+        let expr = cast(col("c3"), DataType::Int64).eq(lit(i64::MAX));
+        assert_eq!(do_simplify(expr.clone()), expr);
+
+        // non integer casts are kept
+        let expr = cast(col("c3"), DataType::Float64).lt(lit(5.5f64));
+        assert_eq!(do_simplify(expr.clone()), expr);
+    }
+
     #[test]
     fn simplify_expr_eq() {
         let schema = expr_test_schema();
@@ -1668,6 +1924,7 @@ mod tests {
         let rule = SimplifyExpressions::new();
         let execution_props = ExecutionProps {
             query_execution_start_time: *date_time,
+            ansi_mode: false,
         };
 
         let err = rule
@@ -1684,6 +1941,7 @@ mod tests {
         let rule = SimplifyExpressions::new();
         let execution_props = ExecutionProps {
             query_execution_start_time: *date_time,
+            ansi_mode: false,
         };
 
         let optimized_plan = rule
@@ -75,6 +75,8 @@ fn optimize(plan: &LogicalPlan) -> Result<LogicalPlan> {
                                 fun: fun.clone(),
                                 args: args.clone(),
                                 distinct: false,
+                                filter: None,
+                                order_by: vec![],
                             }
                         }
                         _ => agg_expr.clone(),
@@ -159,8 +161,17 @@ fn is_single_distinct_agg(plan: &LogicalPlan) -> bool {
                 .iter()
                 .filter(|expr| {
                     let mut is_distinct = false;
-                    if let Expr::AggregateFunction { distinct, args, .. } = expr {
-                        is_distinct = *distinct;
+                    if let Expr::AggregateFunction {
+                        distinct,
+                        args,
+                        filter,
+                        order_by,
+                        ..
+                    } = expr
+                    {
+                        // the filter must apply before the values are deduplicated
+                        is_distinct =
+                            *distinct && filter.is_none() && order_by.is_empty();
                         args.iter().for_each(|expr| {
                             fields_set.insert(expr.name(input.schema()).unwrap());
                         })
@@ -288,6 +299,8 @@ mod tests {
                         fun: aggregates::AggregateFunction::Max,
                         distinct: true,
                         args: vec![col("b")],
+                        filter: None,
+                        order_by: vec![],
                     },
                 ],
             )?
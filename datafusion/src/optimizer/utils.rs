@@ -23,8 +23,9 @@ use crate::logical_plan::plan::{
     Aggregate, Analyze, Extension, Filter, Join, Projection, Sort, Window,
 };
 use crate::logical_plan::{
-    build_join_schema, Column, CreateMemoryTable, DFSchemaRef, Expr, Limit, LogicalPlan,
-    LogicalPlanBuilder, Operator, Partitioning, Recursion, Repartition, Union, Values,
+    build_join_schema, Column, CreateMemoryTable, DFSchemaRef, Expr, InsertInto, Limit,
+    LogicalPlan, LogicalPlanBuilder, Operator, Partitioning, Recursion, Repartition,
+    Union, Values,
 };
 use crate::prelude::lit;
 use crate::scalar::ScalarValue;
@@ -173,6 +174,19 @@ pub fn from_plan(
                 partitioning_scheme: Partitioning::Hash(expr.to_owned(), *n),
                 input: Arc::new(inputs[0].clone()),
             })),
+            Partitioning::RangePartitioning(_, boundaries) => {
+                Ok(LogicalPlan::Repartition(Repartition {
+                    partitioning_scheme: Partitioning::RangePartitioning(
+                        expr.to_owned(),
+                        boundaries.clone(),
+                    ),
+                    input: Arc::new(inputs[0].clone()),
+                }))
+            }
+            Partitioning::Custom(_, n) => Ok(LogicalPlan::Repartition(Repartition {
+                partitioning_scheme: Partitioning::Custom(expr[0].clone(), *n),
+                input: Arc::new(inputs[0].clone()),
+            })),
         },
         LogicalPlan::Window(Window {
             window_expr,
@@ -229,6 +243,13 @@ pub fn from_plan(
                 name: name.clone(),
             }))
         }
+        LogicalPlan::InsertInto(InsertInto {
+            table_name, mode, ..
+        }) => Ok(LogicalPlan::InsertInto(InsertInto {
+            table_name: table_name.clone(),
+            mode: mode.clone(),
+            input: Arc::new(inputs[0].clone()),
+        })),
         LogicalPlan::Extension(e) => Ok(LogicalPlan::Extension(Extension {
             node: e.node.from_template(expr, inputs),
         })),
@@ -244,6 +265,7 @@ pub fn from_plan(
             assert_eq!(inputs.len(), 1);
             Ok(LogicalPlan::Analyze(Analyze {
                 verbose: a.verbose,
+                format: a.format,
                 schema: a.schema.clone(),
                 input: Arc::new(inputs[0].clone()),
             }))
@@ -299,7 +321,20 @@ pub fn expr_sub_expressions(expr: &Expr) -> Result<Vec<Expr>> {
             expr_list.extend(order_by.clone());
             Ok(expr_list)
         }
-        Expr::AggregateFunction { args, .. } => Ok(args.clone()),
+        Expr::AggregateFunction {
+            args,
+            filter,
+            order_by,
+            ..
+        } => {
+            // the filter, if any, follows the arguments, before the sort expressions
+            let mut expr_list = args.clone();
+            if let Some(filter) = filter {
+                expr_list.push(filter.as_ref().to_owned());
+            }
+            expr_list.extend(order_by.clone());
+            Ok(expr_list)
+        }
         Expr::AggregateUDF { args, .. } => Ok(args.clone()),
         Expr::Case {
             expr,
@@ -415,11 +450,30 @@ pub fn rewrite_expression(expr: &Expr, expressions: &[Expr]) -> Result<Expr> {
                 })
             }
         }
-        Expr::AggregateFunction { fun, distinct, .. } => Ok(Expr::AggregateFunction {
-            fun: fun.clone(),
-            args: expressions.to_vec(),
-            distinct: *distinct,
-        }),
+        Expr::AggregateFunction {
+            fun,
+            distinct,
+            filter,
+            order_by,
+            ..
+        } => {
+            let (expressions, order_by) =
+                expressions.split_at(expressions.len() - order_by.len());
+            let (args, filter) = match filter {
+                Some(_) => {
+                    let (filter, args) = expressions.split_last().unwrap();
+                    (args.to_vec(), Some(Box::new(filter.clone())))
+                }
+                None => (expressions.to_vec(), None),
+            };
+            Ok(Expr::AggregateFunction {
+                fun: fun.clone(),
+                args,
+                distinct: *distinct,
+                filter,
+                order_by: order_by.to_vec(),
+            })
+        }
         Expr::AggregateUDF { fun, .. } => Ok(Expr::AggregateUDF {
             fun: fun.clone(),
             args: expressions.to_vec(),
@@ -212,7 +212,9 @@ fn take_optimizable_min(
                     ..
                 } = &col_stats[col_expr.index()]
                 {
-                    return Some((val.clone(), format!("MIN({})", col_expr.name())));
+                    if has_type_of(val, agg_expr) {
+                        return Some((val.clone(), format!("MIN({})", col_expr.name())));
+                    }
                 }
             }
         }
@@ -240,7 +242,9 @@ fn take_optimizable_max(
                     ..
                 } = &col_stats[col_expr.index()]
                 {
-                    return Some((val.clone(), format!("MAX({})", col_expr.name())));
+                    if has_type_of(val, agg_expr) {
+                        return Some((val.clone(), format!("MAX({})", col_expr.name())));
+                    }
                 }
             }
         }
@@ -248,6 +252,16 @@ fn take_optimizable_max(
     None
 }
 
+/// Whether a value from the statistics has the type of the result of `agg_expr`,
+/// which is not the case e.g. of dictionary encoded columns, so that it can
+/// replace the result
+fn has_type_of(value: &ScalarValue, agg_expr: &dyn AggregateExpr) -> bool {
+    match agg_expr.field() {
+        Ok(field) => &value.get_datatype() == field.data_type(),
+        Err(_) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -521,4 +535,20 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_statistics_of_other_type() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, false)]);
+        let min = expressions::Min::new(
+            expressions::col("a", &schema)?,
+            "MIN(a)",
+            DataType::Int64,
+        );
+
+        // e.g. statistics of the physical type of a column rather than its logical type
+        assert!(!has_type_of(&ScalarValue::Int32(Some(1)), &min));
+        assert!(has_type_of(&ScalarValue::Int64(Some(1)), &min));
+
+        Ok(())
+    }
 }
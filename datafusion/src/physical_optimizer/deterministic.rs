@@ -0,0 +1,135 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! DeterministicExecution optimizer that makes the operators merging or
+//! repartitioning parallel partitions read them in the order of the partitions, so
+//! that the results of queries are the same across runs
+
+use super::optimizer::PhysicalOptimizerRule;
+use crate::execution::context::ExecutionConfig;
+use crate::{
+    error::Result,
+    physical_plan::{
+        coalesce_partitions::CoalescePartitionsExec,
+        cross_join::CrossJoinExec,
+        hash_join::{HashJoinExec, PartitionMode},
+        repartition::RepartitionExec,
+        ExecutionPlan,
+    },
+};
+use std::sync::Arc;
+
+/// Optimizer that replaces the merges and repartitions of partitions by the ones
+/// preserving the order of the partitions when the execution is deterministic
+pub struct DeterministicExecution {}
+
+impl DeterministicExecution {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl PhysicalOptimizerRule for DeterministicExecution {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ExecutionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if !config.deterministic || plan.children().is_empty() {
+            return Ok(plan);
+        }
+        let mut children = plan
+            .children()
+            .iter()
+            .map(|child| self.optimize(child.clone(), config))
+            .collect::<Result<Vec<_>>>()?;
+
+        let plan_any = plan.as_any();
+        if plan_any.downcast_ref::<CoalescePartitionsExec>().is_some() {
+            return Ok(Arc::new(CoalescePartitionsExec::new_ordered(
+                children[0].clone(),
+            )));
+        }
+        if let Some(repartition) = plan_any.downcast_ref::<RepartitionExec>() {
+            return Ok(Arc::new(RepartitionExec::try_new_ordered(
+                children[0].clone(),
+                repartition.partitioning().clone(),
+            )?));
+        }
+        // the joins collecting their build side merge its partitions when executed,
+        // so they are merged in order beforehand
+        let collects_left = plan_any.downcast_ref::<CrossJoinExec>().is_some()
+            || plan_any
+                .downcast_ref::<HashJoinExec>()
+                .map(|join| *join.partition_mode() == PartitionMode::CollectLeft)
+                .unwrap_or(false);
+        if collects_left && children[0].output_partitioning().partition_count() > 1 {
+            children[0] =
+                Arc::new(CoalescePartitionsExec::new_ordered(children[0].clone()));
+        }
+        plan.with_new_children(children)
+    }
+
+    fn name(&self) -> &str {
+        "deterministic_execution"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::displayable;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::Partitioning;
+    use crate::test::make_partition;
+
+    #[test]
+    fn preserve_partition_order() -> Result<()> {
+        let batches = vec![make_partition(3), make_partition(2)];
+        let schema = batches[0].schema();
+        let input = Arc::new(MemoryExec::try_new(
+            &[batches.clone(), batches],
+            schema,
+            None,
+        )?);
+        let plan = Arc::new(CoalescePartitionsExec::new(Arc::new(
+            RepartitionExec::try_new(input, Partitioning::RoundRobinBatch(3))?,
+        )));
+
+        let rule = DeterministicExecution::new();
+        let unchanged = rule.optimize(plan.clone(), &ExecutionConfig::new())?;
+        let expected = "CoalescePartitionsExec\
+            \n  RepartitionExec: partitioning=RoundRobinBatch(3)\
+            \n    MemoryExec: partitions=2, partition_sizes=[2, 2]\n";
+        assert_eq!(
+            displayable(unchanged.as_ref()).indent().to_string(),
+            expected
+        );
+
+        let config = ExecutionConfig::new().with_deterministic(true);
+        let optimized = rule.optimize(plan, &config)?;
+        let expected = "CoalescePartitionsExec: preserve_partition_order=true\
+            \n  RepartitionExec: partitioning=RoundRobinBatch(3), preserve_input_order=true\
+            \n    MemoryExec: partitions=2, partition_sizes=[2, 2]\n";
+        assert_eq!(
+            displayable(optimized.as_ref()).indent().to_string(),
+            expected
+        );
+        Ok(())
+    }
+}
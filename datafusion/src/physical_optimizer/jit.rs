@@ -0,0 +1,160 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! JitCompilation optimizer that compiles the expressions of filters and
+//! projections to native code when the `jit` feature is enabled
+
+use super::optimizer::PhysicalOptimizerRule;
+use crate::execution::context::ExecutionConfig;
+use crate::{error::Result, physical_plan::ExecutionPlan};
+use std::sync::Arc;
+
+/// Optimizer that replaces the predicates of filters and the expressions of
+/// projections that the JIT supports by [`JitExpr`]s, when enabled in the
+/// configuration
+///
+/// [`JitExpr`]: crate::physical_plan::jit::JitExpr
+pub struct JitCompilation {}
+
+impl JitCompilation {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl PhysicalOptimizerRule for JitCompilation {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ExecutionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if !config.jit {
+            return Ok(plan);
+        }
+        compile_expressions(plan)
+    }
+
+    fn name(&self) -> &str {
+        "jit_compilation"
+    }
+}
+
+#[cfg(feature = "jit")]
+fn compile_expressions(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+    use crate::physical_plan::{
+        filter::FilterExec, jit::JitExpr, projection::ProjectionExec, PhysicalExpr,
+    };
+
+    let children = plan
+        .children()
+        .into_iter()
+        .map(compile_expressions)
+        .collect::<Result<Vec<_>>>()?;
+    let plan = if children.is_empty() {
+        plan
+    } else {
+        plan.with_new_children(children)?
+    };
+
+    if let Some(filter) = plan.as_any().downcast_ref::<FilterExec>() {
+        let input = filter.input();
+        if let Some(predicate) =
+            JitExpr::try_new(filter.predicate().clone(), &input.schema())
+        {
+            return Ok(Arc::new(FilterExec::try_new(
+                Arc::new(predicate),
+                input.clone(),
+            )?));
+        }
+    } else if let Some(projection) = plan.as_any().downcast_ref::<ProjectionExec>() {
+        let input = projection.input();
+        let schema = input.schema();
+        let mut compiled = false;
+        let expr = projection
+            .expr()
+            .iter()
+            .map(
+                |(expr, name)| match JitExpr::try_new(expr.clone(), &schema) {
+                    Some(jit) => {
+                        compiled = true;
+                        (Arc::new(jit) as Arc<dyn PhysicalExpr>, name.clone())
+                    }
+                    None => (expr.clone(), name.clone()),
+                },
+            )
+            .collect();
+        if compiled {
+            return Ok(Arc::new(ProjectionExec::try_new(expr, input.clone())?));
+        }
+    }
+    Ok(plan)
+}
+
+/// Without the `jit` feature, the expressions stay interpreted
+#[cfg(not(feature = "jit"))]
+fn compile_expressions(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+    Ok(plan)
+}
+
+#[cfg(all(test, feature = "jit"))]
+mod tests {
+    use super::*;
+    use crate::assert_batches_eq;
+    use crate::execution::context::ExecutionContext;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::filter::FilterExec;
+    use crate::physical_plan::jit::JitExpr;
+    use crate::physical_plan::projection::ProjectionExec;
+
+    #[tokio::test]
+    async fn compile_filter_and_projection() -> Result<()> {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_target_partitions(1)
+                .with_jit(true),
+        );
+        let df = ctx
+            .sql(
+                "SELECT column1 * 2 AS doubled, column2 FROM (VALUES (1, 'a'), (2, 'b'), (3, 'c')) \
+                 WHERE column1 > 1",
+            )
+            .await?;
+        let plan = ctx.create_physical_plan(&df.to_logical_plan()).await?;
+
+        let projection = plan.as_any().downcast_ref::<ProjectionExec>().unwrap();
+        assert!(projection.expr()[0].0.as_any().is::<JitExpr>());
+        assert!(!projection.expr()[1].0.as_any().is::<JitExpr>());
+        let mut input = projection.input().clone();
+        while !input.as_any().is::<FilterExec>() {
+            input = input.children()[0].clone();
+        }
+        let filter = input.as_any().downcast_ref::<FilterExec>().unwrap();
+        assert!(filter.predicate().as_any().is::<JitExpr>());
+
+        let expected = vec![
+            "+---------+---------+",
+            "| doubled | column2 |",
+            "+---------+---------+",
+            "| 4       | b       |",
+            "| 6       | c       |",
+            "+---------+---------+",
+        ];
+        assert_batches_eq!(expected, &collect(plan).await?);
+        Ok(())
+    }
+}
@@ -28,6 +28,7 @@
 //! https://github.com/apache/arrow-datafusion/issues/363 it will
 //! be genericized.
 
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::{collections::HashSet, sync::Arc};
 
@@ -42,7 +43,10 @@ use crate::{
     execution::context::ExecutionContextState,
     logical_plan::{Column, DFSchema, Expr, Operator},
     optimizer::utils,
-    physical_plan::{planner::DefaultPhysicalPlanner, ColumnarValue, PhysicalExpr},
+    physical_plan::{
+        expressions::IN_LIST_HASH_SET_THRESHOLD, planner::DefaultPhysicalPlanner,
+        ColumnarValue, PhysicalExpr,
+    },
 };
 
 /// Interface to pass statistics information to [`PruningPredicates`]
@@ -599,6 +603,18 @@ fn build_predicate_expression(
     // conditions are joined using AND such as: column > 10 AND TRUE
     let unhandled = logical_plan::lit(true);
 
+    if let Expr::InList {
+        expr,
+        list,
+        negated,
+    } = expr
+    {
+        return match rewrite_in_list(expr, list, *negated) {
+            Some(expr) => build_predicate_expression(&expr, schema, required_columns),
+            None => Ok(unhandled),
+        };
+    }
+
     // predicate expression can only be a binary expression
     let (left, op, right) = match expr {
         Expr::BinaryExpr { left, op, right } => (left, *op, right),
@@ -643,6 +659,59 @@ fn build_predicate_expression(
     Ok(statistics_expr)
 }
 
+/// Rewrites `expr [NOT] IN (<literals>)` into comparisons with the literals:
+/// `expr = <literal> OR ...`, or `expr != <literal> AND ...` if negated. The large
+/// lists are rewritten into `expr >= <min literal> AND expr <= <max literal>` instead,
+/// and are not rewritten if negated. Returns `None` if the list can not be rewritten.
+fn rewrite_in_list(expr: &Expr, list: &[Expr], negated: bool) -> Option<Expr> {
+    let mut values = vec![];
+    for value in list {
+        match value {
+            // a null is never equal to the expression
+            Expr::Literal(value) if value.is_null() && !negated => {}
+            Expr::Literal(value) if !value.is_null() => values.push(value.clone()),
+            _ => return None,
+        }
+    }
+
+    if values.len() > IN_LIST_HASH_SET_THRESHOLD {
+        if negated {
+            return None;
+        }
+        let mut min = &values[0];
+        let mut max = &values[0];
+        for value in &values[1..] {
+            if value.partial_cmp(min)? == Ordering::Less {
+                min = value;
+            }
+            if value.partial_cmp(max)? == Ordering::Greater {
+                max = value;
+            }
+        }
+        let min = Expr::Literal(min.clone());
+        let max = Expr::Literal(max.clone());
+        return Some(expr.clone().gt_eq(min).and(expr.clone().lt_eq(max)));
+    }
+
+    values
+        .into_iter()
+        .map(|value| {
+            let value = Expr::Literal(value);
+            if negated {
+                expr.clone().not_eq(value)
+            } else {
+                expr.clone().eq(value)
+            }
+        })
+        .reduce(|left, right| {
+            if negated {
+                left.and(right)
+            } else {
+                left.or(right)
+            }
+        })
+}
+
 fn build_statistics_expr(expr_builder: &mut PruningExpressionBuilder) -> Result<Expr> {
     let statistics_expr =
         match expr_builder.op() {
@@ -710,6 +779,7 @@ mod tests {
 
     use super::*;
     use crate::logical_plan::{col, lit};
+    use crate::scalar::ScalarValue;
     use crate::{assert_batches_eq, physical_optimizer::pruning::StatisticsType};
     use arrow::{
         array::{BinaryArray, Int32Array, Int64Array, StringArray},
@@ -1501,4 +1571,38 @@ mod tests {
         let result = p.prune(&statistics).unwrap();
         assert_eq!(result, expected_ret);
     }
+
+    #[test]
+    fn prune_int32_col_in_list() {
+        let (schema, statistics) = int32_setup();
+
+        // Expression "i IN (-20, 8, NULL)"
+        // i [-5, 5] ==> no rows can pass (not keep)
+        // i [1, 11] ==> some rows could pass (must keep)
+        // i [-11, -1] ==>  no rows can pass (not keep)
+        // i [NULL, NULL]  ==> unknown (must keep)
+        // i [1, NULL]  ==> some rows could pass (must keep)
+        let expr = col("i")
+            .in_list(vec![lit(-20), lit(8), lit(ScalarValue::Int32(None))], false);
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![false, true, false, true, true]);
+
+        // Expression "i IN (20, 22, ...)", a large list pruned with the range of its
+        // values [20, 20 + 2 * IN_LIST_HASH_SET_THRESHOLD]
+        let list = (0..=IN_LIST_HASH_SET_THRESHOLD as i32)
+            .map(|i| lit(20 + 2 * i))
+            .collect();
+        let expr = col("i").in_list(list, false);
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![false, false, false, true, true]);
+
+        // Expression "i NOT IN (1, 2)"
+        // only the containers of i [1, 1] could be pruned
+        let expr = col("i").in_list(vec![lit(1), lit(2)], true);
+        let p = PruningPredicate::try_new(&expr, schema).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true, true, true, true, true]);
+    }
 }
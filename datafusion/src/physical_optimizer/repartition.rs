@@ -20,7 +20,7 @@ use std::sync::Arc;
 
 use super::optimizer::PhysicalOptimizerRule;
 use crate::physical_plan::{
-    empty::EmptyExec, repartition::RepartitionExec, ExecutionPlan,
+    empty::EmptyExec, repartition::RepartitionExec, ExecutionPlan, Statistics,
 };
 use crate::physical_plan::{Distribution, Partitioning::*};
 use crate::{error::Result, execution::context::ExecutionConfig};
@@ -35,8 +35,47 @@ impl Repartition {
     }
 }
 
+/// Returns the number of partitions an input with the given `statistics` should
+/// be repartitioned into.
+///
+/// Without statistics (or if `repartition_statistics` is disabled) this is
+/// `target_partitions`. Inputs that are known to hold only a few batches get fewer
+/// partitions, as a single batch cannot be processed in parallel, and inputs that
+/// are larger than `target_partitions * repartition_bytes_per_partition` bytes get
+/// more partitions, up to `max_partitions`.
+pub fn partition_count_for_statistics(
+    statistics: &Statistics,
+    config: &ExecutionConfig,
+) -> usize {
+    let target_partitions = config.target_partitions;
+    if !config.repartition_statistics {
+        return target_partitions;
+    }
+    let max_partitions = config
+        .max_partitions
+        .unwrap_or(target_partitions)
+        .max(target_partitions);
+
+    // effectively these are divs with rounding up instead of truncating
+    let by_size = statistics
+        .total_byte_size
+        .map(|bytes| {
+            let per_partition = config.repartition_bytes_per_partition;
+            ((bytes + per_partition - 1) / per_partition)
+                .clamp(target_partitions, max_partitions)
+        })
+        .unwrap_or(target_partitions);
+    match statistics.num_rows {
+        Some(num_rows) => {
+            let num_batches = (num_rows + config.batch_size - 1) / config.batch_size;
+            by_size.min(num_batches.max(1))
+        }
+        None => by_size,
+    }
+}
+
 fn optimize_partitions(
-    target_partitions: usize,
+    config: &ExecutionConfig,
     requires_single_partition: bool,
     plan: Arc<dyn ExecutionPlan>,
 ) -> Result<Arc<dyn ExecutionPlan>> {
@@ -51,7 +90,7 @@ fn optimize_partitions(
             .iter()
             .map(|child| {
                 optimize_partitions(
-                    target_partitions,
+                    config,
                     matches!(
                         plan.required_child_distribution(),
                         Distribution::SinglePartition
@@ -63,13 +102,15 @@ fn optimize_partitions(
         plan.with_new_children(children)?
     };
 
+    let target_partitions =
+        partition_count_for_statistics(&new_plan.statistics(), config);
     let perform_repartition = match new_plan.output_partitioning() {
-        // Apply when underlying node has less than `self.target_partitions` amount of concurrency
+        // Apply when underlying node has less than `target_partitions` amount of concurrency
         RoundRobinBatch(x) => x < target_partitions,
         UnknownPartitioning(x) => x < target_partitions,
-        // we don't want to introduce partitioning after hash partitioning
-        // as the plan will likely depend on this
-        Hash(_, _) => false,
+        // we don't want to introduce partitioning after hash, range or custom
+        // partitioning as the plan will likely depend on this
+        Hash(_, _) | RangePartitioning(_, _) | Custom(_, _) => false,
     };
 
     // TODO: EmptyExec causes failures with RepartitionExec
@@ -92,11 +133,12 @@ impl PhysicalOptimizerRule for Repartition {
         plan: Arc<dyn ExecutionPlan>,
         config: &ExecutionConfig,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        // Don't run optimizer if target_partitions == 1
-        if config.target_partitions == 1 {
+        let max_partitions = config.max_partitions.unwrap_or(1);
+        // Don't run optimizer if there can only be a single partition
+        if config.target_partitions == 1 && max_partitions <= 1 {
             Ok(plan)
         } else {
-            optimize_partitions(config.target_partitions, true, plan)
+            optimize_partitions(config, true, plan)
         }
     }
 
@@ -127,9 +169,11 @@ mod tests {
                     file_groups: vec![vec![PartitionedFile::new("x".to_string(), 100)]],
                     statistics: Statistics::default(),
                     projection: None,
+                    nested_projection: vec![],
                     batch_size: 2048,
                     limit: None,
                     table_partition_cols: vec![],
+                    bucket_columns: None,
                 },
                 None,
             )),
@@ -152,6 +196,124 @@ mod tests {
         Ok(())
     }
 
+    fn parquet_exec_with_statistics(statistics: Statistics) -> Arc<dyn ExecutionPlan> {
+        Arc::new(ParquetExec::new(
+            PhysicalPlanConfig {
+                object_store: TestObjectStore::new_arc(&[("x", 100)]),
+                file_schema: Arc::new(Schema::empty()),
+                file_groups: vec![vec![PartitionedFile::new("x".to_string(), 100)]],
+                statistics,
+                projection: None,
+                nested_projection: vec![],
+                batch_size: 2048,
+                limit: None,
+                table_partition_cols: vec![],
+                bucket_columns: None,
+            },
+            None,
+        ))
+    }
+
+    #[test]
+    fn partition_count_from_statistics() {
+        let config = ExecutionConfig::new()
+            .with_target_partitions(4)
+            .with_batch_size(100)
+            .with_repartition_bytes_per_partition(1000)
+            .with_max_partitions(16);
+        let stats = |num_rows, total_byte_size| Statistics {
+            num_rows,
+            total_byte_size,
+            ..Default::default()
+        };
+
+        // unknown statistics
+        assert_eq!(
+            4,
+            partition_count_for_statistics(&stats(None, None), &config)
+        );
+        // a single batch
+        assert_eq!(
+            1,
+            partition_count_for_statistics(&stats(Some(10), None), &config)
+        );
+        // fewer batches than partitions
+        assert_eq!(
+            3,
+            partition_count_for_statistics(&stats(Some(250), Some(100)), &config)
+        );
+        // huge input
+        assert_eq!(
+            10,
+            partition_count_for_statistics(&stats(None, Some(10_000)), &config)
+        );
+        assert_eq!(
+            16,
+            partition_count_for_statistics(
+                &stats(Some(1_000_000), Some(1_000_000)),
+                &config
+            )
+        );
+        // disabled
+        let config = config.with_repartition_statistics(false);
+        assert_eq!(
+            4,
+            partition_count_for_statistics(&stats(None, Some(1_000_000)), &config)
+        );
+    }
+
+    #[test]
+    fn no_repartition_for_tiny_input() -> Result<()> {
+        let parquet_project = ProjectionExec::try_new(
+            vec![],
+            parquet_exec_with_statistics(Statistics {
+                num_rows: Some(10),
+                ..Default::default()
+            }),
+        )?;
+
+        let optimized = Repartition {}.optimize(
+            Arc::new(parquet_project),
+            &ExecutionConfig::new().with_target_partitions(10),
+        )?;
+
+        assert_eq!(
+            optimized.children()[0]
+                .output_partitioning()
+                .partition_count(),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn more_partitions_for_huge_input() -> Result<()> {
+        let parquet_project = ProjectionExec::try_new(
+            vec![],
+            parquet_exec_with_statistics(Statistics {
+                total_byte_size: Some(1 << 40),
+                ..Default::default()
+            }),
+        )?;
+
+        let optimized = Repartition {}.optimize(
+            Arc::new(parquet_project),
+            &ExecutionConfig::new()
+                .with_target_partitions(10)
+                .with_max_partitions(40),
+        )?;
+
+        assert_eq!(
+            optimized.children()[0]
+                .output_partitioning()
+                .partition_count(),
+            40
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn repartition_deepest_node() -> Result<()> {
         let file_schema = Arc::new(Schema::empty());
@@ -169,9 +331,11 @@ mod tests {
                         )]],
                         statistics: Statistics::default(),
                         projection: None,
+                        nested_projection: vec![],
                         batch_size: 2048,
                         limit: None,
                         table_partition_cols: vec![],
+                        bucket_columns: None,
                     },
                     None,
                 )),
@@ -34,6 +34,8 @@ use crate::error::{DataFusionError, Result};
 use crate::physical_plan::coercion_rule::aggregate_rule::{coerce_exprs, coerce_types};
 use crate::physical_plan::distinct_expressions;
 use crate::physical_plan::expressions;
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::scalar::ScalarValue;
 use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
 use expressions::{avg_return_type, sum_return_type};
 use std::{fmt, str::FromStr, sync::Arc};
@@ -64,6 +66,12 @@ pub enum AggregateFunction {
     ApproxDistinct,
     /// array_agg
     ArrayAgg,
+    /// string_agg
+    StringAgg,
+    /// percentile_cont
+    PercentileCont,
+    /// percentile_disc
+    PercentileDisc,
 }
 
 impl fmt::Display for AggregateFunction {
@@ -84,6 +92,9 @@ impl FromStr for AggregateFunction {
             "sum" => AggregateFunction::Sum,
             "approx_distinct" => AggregateFunction::ApproxDistinct,
             "array_agg" => AggregateFunction::ArrayAgg,
+            "string_agg" => AggregateFunction::StringAgg,
+            "percentile_cont" => AggregateFunction::PercentileCont,
+            "percentile_disc" => AggregateFunction::PercentileDisc,
             _ => {
                 return Err(DataFusionError::Plan(format!(
                     "There is no built-in function named {}",
@@ -94,6 +105,20 @@ impl FromStr for AggregateFunction {
     }
 }
 
+/// Returns whether `fun` is an ordered-set aggregate function, e.g.
+/// `PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY a)`.
+///
+/// Ordered-set aggregates aggregate the single expression of their
+/// `WITHIN GROUP (ORDER BY ...)` clause, and their arguments are constants.
+/// Their input expressions are the aggregated expression followed by the
+/// arguments.
+pub fn is_ordered_set_aggregate(fun: &AggregateFunction) -> bool {
+    matches!(
+        fun,
+        AggregateFunction::PercentileCont | AggregateFunction::PercentileDisc
+    )
+}
+
 /// Returns the datatype of the aggregate function.
 /// This is used to get the returned data type for aggregate expr.
 pub fn return_type(
@@ -122,6 +147,9 @@ pub fn return_type(
             coerced_data_types[0].clone(),
             true,
         )))),
+        AggregateFunction::StringAgg => Ok(coerced_data_types[0].clone()),
+        AggregateFunction::PercentileCont => Ok(DataType::Float64),
+        AggregateFunction::PercentileDisc => Ok(coerced_data_types[0].clone()),
     }
 }
 
@@ -133,6 +161,27 @@ pub fn create_aggregate_expr(
     input_phy_exprs: &[Arc<dyn PhysicalExpr>],
     input_schema: &Schema,
     name: impl Into<String>,
+) -> Result<Arc<dyn AggregateExpr>> {
+    create_aggregate_expr_with_overflow_checks(
+        fun,
+        distinct,
+        input_phy_exprs,
+        input_schema,
+        name,
+        false,
+    )
+}
+
+/// Create a physical aggregation expression like [`create_aggregate_expr`], whose
+/// integer sums error on overflow if `fail_on_overflow`, as in the ANSI mode,
+/// rather than wrapping around.
+fn create_aggregate_expr_with_overflow_checks(
+    fun: &AggregateFunction,
+    distinct: bool,
+    input_phy_exprs: &[Arc<dyn PhysicalExpr>],
+    input_schema: &Schema,
+    name: impl Into<String>,
+    fail_on_overflow: bool,
 ) -> Result<Arc<dyn AggregateExpr>> {
     let name = name.into();
     // get the coerced phy exprs if some expr need to be wrapped with the try cast.
@@ -170,16 +219,19 @@ pub fn create_aggregate_expr(
                 return_type,
             ))
         }
-        (AggregateFunction::Sum, false) => Arc::new(expressions::Sum::new(
-            coerced_phy_exprs[0].clone(),
-            name,
-            return_type,
-        )),
-        (AggregateFunction::Sum, true) => {
-            return Err(DataFusionError::NotImplemented(
-                "SUM(DISTINCT) aggregations are not available".to_string(),
-            ));
-        }
+        (AggregateFunction::Sum, false) => Arc::new(
+            expressions::Sum::new(coerced_phy_exprs[0].clone(), name, return_type)
+                .with_fail_on_overflow(fail_on_overflow),
+        ),
+        (AggregateFunction::Sum, true) => Arc::new(
+            distinct_expressions::DistinctSum::new(
+                coerced_exprs_types[0].clone(),
+                coerced_phy_exprs[0].clone(),
+                name,
+                return_type,
+            )
+            .with_fail_on_overflow(fail_on_overflow),
+        ),
         (AggregateFunction::ApproxDistinct, _) => {
             Arc::new(expressions::ApproxDistinct::new(
                 coerced_phy_exprs[0].clone(),
@@ -192,6 +244,18 @@ pub fn create_aggregate_expr(
             name,
             coerced_exprs_types[0].clone(),
         )),
+        (AggregateFunction::StringAgg, _) => Arc::new(expressions::StringAgg::new(
+            coerced_phy_exprs[0].clone(),
+            string_agg_delimiter(&input_phy_exprs[1], &name)?,
+            name,
+            return_type,
+        )),
+        (AggregateFunction::PercentileCont | AggregateFunction::PercentileDisc, _) => {
+            return Err(DataFusionError::Plan(format!(
+                "{} requires a WITHIN GROUP (ORDER BY ...) clause",
+                name
+            )));
+        }
         (AggregateFunction::Min, _) => Arc::new(expressions::Min::new(
             coerced_phy_exprs[0].clone(),
             name,
@@ -215,6 +279,115 @@ pub fn create_aggregate_expr(
     })
 }
 
+/// Create a physical aggregation expression aggregating its input in the order
+/// of `order_by`, e.g. `ARRAY_AGG(a ORDER BY b)`.
+///
+/// The `order_by` of an ordered-set aggregate, which [`create_aggregate_expr`]
+/// does not support, is its single `WITHIN GROUP (ORDER BY ...)` expression.
+/// Integer sums error on overflow if `fail_on_overflow`, as in the ANSI mode.
+pub fn create_ordered_aggregate_expr(
+    fun: &AggregateFunction,
+    distinct: bool,
+    input_phy_exprs: &[Arc<dyn PhysicalExpr>],
+    order_by: &[PhysicalSortExpr],
+    input_schema: &Schema,
+    name: impl Into<String>,
+    fail_on_overflow: bool,
+) -> Result<Arc<dyn AggregateExpr>> {
+    let name = name.into();
+    if is_ordered_set_aggregate(fun) {
+        let sort_expr = match order_by {
+            [sort_expr] if !distinct => sort_expr,
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "{} requires a WITHIN GROUP (ORDER BY ...) clause with a single expression",
+                    name
+                )))
+            }
+        };
+        let input_phy_exprs = std::iter::once(sort_expr.expr.clone())
+            .chain(input_phy_exprs.iter().cloned())
+            .collect::<Vec<_>>();
+        let coerced_phy_exprs =
+            coerce_exprs(fun, &input_phy_exprs, input_schema, &signature(fun))?;
+        let input_phy_types = input_phy_exprs
+            .iter()
+            .map(|e| e.data_type(input_schema))
+            .collect::<Result<Vec<_>>>()?;
+        let return_type = return_type(fun, &input_phy_types)?;
+        return Ok(Arc::new(expressions::Percentile::new(
+            coerced_phy_exprs[0].clone(),
+            percentile_fraction(&input_phy_exprs[1], &name)?,
+            *fun == AggregateFunction::PercentileDisc,
+            sort_expr.options.descending,
+            name,
+            return_type,
+        )));
+    }
+
+    let aggregate = create_aggregate_expr_with_overflow_checks(
+        fun,
+        distinct,
+        input_phy_exprs,
+        input_schema,
+        &name,
+        fail_on_overflow,
+    )?;
+    if order_by.is_empty() {
+        return Ok(aggregate);
+    }
+    if distinct {
+        return Err(DataFusionError::NotImplemented(format!(
+            "ORDER BY is not supported in DISTINCT aggregations: '{}'",
+            name
+        )));
+    }
+    Ok(Arc::new(expressions::OrderedAggregate::try_new(
+        aggregate,
+        order_by.to_vec(),
+        input_schema,
+    )?))
+}
+
+/// Returns the constant delimiter argument of STRING_AGG
+fn string_agg_delimiter(expr: &Arc<dyn PhysicalExpr>, name: &str) -> Result<String> {
+    match expr.as_any().downcast_ref::<expressions::Literal>() {
+        Some(literal) => match literal.value() {
+            ScalarValue::Utf8(Some(delimiter))
+            | ScalarValue::LargeUtf8(Some(delimiter)) => Ok(delimiter.clone()),
+            _ => Err(DataFusionError::Plan(format!(
+                "The delimiter of {} must be a string",
+                name
+            ))),
+        },
+        None => Err(DataFusionError::NotImplemented(format!(
+            "The delimiter of {} must be a constant",
+            name
+        ))),
+    }
+}
+
+/// Returns the constant percentile argument of an ordered-set aggregate
+fn percentile_fraction(expr: &Arc<dyn PhysicalExpr>, name: &str) -> Result<f64> {
+    let percentile = match expr
+        .as_any()
+        .downcast_ref::<expressions::Literal>()
+        .map(|literal| literal.value())
+    {
+        Some(ScalarValue::Float64(Some(percentile))) => Some(*percentile),
+        Some(ScalarValue::Float32(Some(percentile))) => Some(*percentile as f64),
+        Some(ScalarValue::Int64(Some(percentile))) => Some(*percentile as f64),
+        _ => None,
+    };
+    match percentile {
+        Some(percentile) if (0.0..=1.0).contains(&percentile) => Ok(percentile),
+        _ => Err(DataFusionError::Plan(format!(
+            "The percentile of {} must be a constant between 0 and 1",
+            name
+        ))),
+    }
+}
+
 static STRINGS: &[DataType] = &[DataType::Utf8, DataType::LargeUtf8];
 
 static NUMERICS: &[DataType] = &[
@@ -259,6 +432,9 @@ pub fn signature(fun: &AggregateFunction) -> Signature {
         AggregateFunction::Avg | AggregateFunction::Sum => {
             Signature::uniform(1, NUMERICS.to_vec(), Volatility::Immutable)
         }
+        AggregateFunction::StringAgg
+        | AggregateFunction::PercentileCont
+        | AggregateFunction::PercentileDisc => Signature::any(2, Volatility::Immutable),
     }
 }
 
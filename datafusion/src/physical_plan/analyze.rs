@@ -22,6 +22,7 @@ use std::{any::Any, time::Instant};
 
 use crate::{
     error::{DataFusionError, Result},
+    logical_plan::ExplainFormat,
     physical_plan::{
         display::DisplayableExecutionPlan, DisplayFormatType, ExecutionPlan,
         Partitioning, Statistics,
@@ -30,7 +31,10 @@ use crate::{
 use arrow::{array::StringBuilder, datatypes::SchemaRef, record_batch::RecordBatch};
 use futures::StreamExt;
 
-use super::{stream::RecordBatchReceiverStream, Distribution, SendableRecordBatchStream};
+use super::{
+    cancellation, stream::RecordBatchReceiverStream, Distribution,
+    SendableRecordBatchStream,
+};
 use async_trait::async_trait;
 
 /// `EXPLAIN ANALYZE` execution plan operator. This operator runs its input,
@@ -41,6 +45,8 @@ pub struct AnalyzeExec {
     verbose: bool,
     /// The input plan (the plan being analyzed)
     input: Arc<dyn ExecutionPlan>,
+    /// How the annotated plans are formatted
+    format: ExplainFormat,
     /// The output schema for RecordBatches of this exec node
     schema: SchemaRef,
 }
@@ -51,9 +57,30 @@ impl AnalyzeExec {
         AnalyzeExec {
             verbose,
             input,
+            format: ExplainFormat::Indent,
             schema,
         }
     }
+
+    /// Format the annotated plans as `format` rather than indented text
+    pub fn with_format(mut self, format: ExplainFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// How the annotated plans are formatted
+    pub fn format(&self) -> ExplainFormat {
+        self.format
+    }
+}
+
+/// Formats `plan` as `format`
+fn format_plan(plan: DisplayableExecutionPlan<'_>, format: ExplainFormat) -> String {
+    match format {
+        ExplainFormat::Indent => plan.indent().to_string(),
+        ExplainFormat::Json => plan.json().to_string(),
+        ExplainFormat::Graphviz => plan.graphviz().to_string(),
+    }
 }
 
 #[async_trait]
@@ -86,11 +113,10 @@ impl ExecutionPlan for AnalyzeExec {
         mut children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         if children.len() == 1 {
-            Ok(Arc::new(Self::new(
-                self.verbose,
-                children.pop().unwrap(),
-                self.schema.clone(),
-            )))
+            Ok(Arc::new(
+                Self::new(self.verbose, children.pop().unwrap(), self.schema.clone())
+                    .with_format(self.format),
+            ))
         } else {
             Err(DataFusionError::Internal(format!(
                 "Invalid child count for AnalyzeExec. Expected 1 got {}",
@@ -122,10 +148,11 @@ impl ExecutionPlan for AnalyzeExec {
         let mut input_stream = captured_input.execute(0).await?;
         let captured_schema = self.schema.clone();
         let verbose = self.verbose;
+        let format = self.format;
 
         // Task reads batches the input and when complete produce a
         // RecordBatch with a report that is written to `tx` when done
-        let join_handle = tokio::task::spawn(async move {
+        let join_handle = cancellation::spawn(async move {
             let start = Instant::now();
             let mut total_rows = 0;
 
@@ -157,10 +184,10 @@ impl ExecutionPlan for AnalyzeExec {
             // TODO use some sort of enum rather than strings?
             type_builder.append_value("Plan with Metrics").unwrap();
 
-            let annotated_plan =
-                DisplayableExecutionPlan::with_metrics(captured_input.as_ref())
-                    .indent()
-                    .to_string();
+            let annotated_plan = format_plan(
+                DisplayableExecutionPlan::with_metrics(captured_input.as_ref()),
+                format,
+            );
             plan_builder.append_value(annotated_plan).unwrap();
 
             // Verbose output
@@ -168,10 +195,10 @@ impl ExecutionPlan for AnalyzeExec {
             if verbose {
                 type_builder.append_value("Plan with Full Metrics").unwrap();
 
-                let annotated_plan =
-                    DisplayableExecutionPlan::with_full_metrics(captured_input.as_ref())
-                        .indent()
-                        .to_string();
+                let annotated_plan = format_plan(
+                    DisplayableExecutionPlan::with_full_metrics(captured_input.as_ref()),
+                    format,
+                );
                 plan_builder.append_value(annotated_plan).unwrap();
 
                 type_builder.append_value("Output Rows").unwrap();
@@ -208,7 +235,11 @@ impl ExecutionPlan for AnalyzeExec {
     ) -> std::fmt::Result {
         match t {
             DisplayFormatType::Default => {
-                write!(f, "AnalyzeExec verbose={}", self.verbose)
+                write!(f, "AnalyzeExec verbose={}", self.verbose)?;
+                if self.format != ExplainFormat::Indent {
+                    write!(f, ", format={}", self.format)?;
+                }
+                Ok(())
             }
         }
     }
@@ -18,8 +18,9 @@
 //! Array expressions
 
 use crate::error::{DataFusionError, Result};
+use crate::field_util::map_entry_fields;
 use arrow::array::*;
-use arrow::datatypes::DataType;
+use arrow::datatypes::{DataType, Field};
 use std::sync::Arc;
 
 use super::ColumnarValue;
@@ -125,3 +126,55 @@ pub static SUPPORTED_ARRAY_TYPES: &[DataType] = &[
     DataType::Utf8,
     DataType::LargeUtf8,
 ];
+
+/// Returns the type of the lists of the keys, or of the values if `values`, of the
+/// maps of `data_type`
+pub fn map_entries_list_type(data_type: &DataType, values: bool) -> Result<DataType> {
+    match data_type {
+        DataType::Map(entries, _) => {
+            let (key_field, value_field) = map_entry_fields(entries)?;
+            let field = if values { value_field } else { key_field };
+            Ok(DataType::List(Box::new(Field::new(
+                "item",
+                field.data_type().clone(),
+                field.is_nullable(),
+            ))))
+        }
+        other => Err(DataFusionError::Plan(format!(
+            "The keys and values can only be extracted from maps, not {:?}",
+            other
+        ))),
+    }
+}
+
+/// Returns the keys of each map, as a list
+pub fn map_keys(args: &[ArrayRef]) -> Result<ArrayRef> {
+    map_entries_list(&args[0], false)
+}
+
+/// Returns the values of each map, as a list
+pub fn map_values(args: &[ArrayRef]) -> Result<ArrayRef> {
+    map_entries_list(&args[0], true)
+}
+
+/// The lists of the keys or values of the maps share the offsets and validity of
+/// the maps, so that they are built without copying the entries
+fn map_entries_list(array: &ArrayRef, values: bool) -> Result<ArrayRef> {
+    let data_type = map_entries_list_type(array.data_type(), values)?;
+    let map_array = array.as_any().downcast_ref::<MapArray>().unwrap();
+    let entries = if values {
+        map_array.values()
+    } else {
+        map_array.keys()
+    };
+    let data = array.data();
+    let mut builder = ArrayData::builder(data_type)
+        .len(data.len())
+        .offset(data.offset())
+        .add_buffer(data.buffers()[0].clone())
+        .add_child_data(entries.data().clone());
+    if let Some(nulls) = data.null_buffer() {
+        builder = builder.null_bit_buffer(nulls.clone());
+    }
+    Ok(make_array(builder.build()?))
+}
@@ -0,0 +1,284 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cooperative cancellation of the execution of physical plans.
+//!
+//! Dropping the stream of a plan aborts the tasks executing it at their next await
+//! point, but neither the CPU bound loops of the operators, such as building the hash
+//! table of a join or sorting, nor the scans reading files on the IO thread pool.
+//! These check a [`CancellationToken`] instead, which is cancelled when the stream
+//! returned by [`execute_stream`](super::execute_stream) is dropped, when the statement
+//! timeout of the query expires, or by the application.
+//!
+//! The token of a query is scoped to the futures executing it with
+//! [`CancellationToken::scope`], and operators capture the current token when their
+//! partitions are executed.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use futures::{Stream, StreamExt};
+use tokio::task::JoinHandle;
+
+use super::{RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+use crate::execution::memory_manager::MemoryManager;
+
+tokio::task_local! {
+    static CURRENT: CancellationToken;
+}
+
+/// A token to cancel the execution of a query. Cancelling a token also cancels the
+/// tokens that were created as its children.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<TokenState>,
+    parent: Option<Box<CancellationToken>>,
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    /// the tasks to wake when the token is cancelled
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    /// Create a new token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that is cancelled along with this one, and can also be
+    /// cancelled on its own
+    pub fn child(&self) -> Self {
+        Self {
+            state: Arc::default(),
+            parent: Some(Box::new(self.clone())),
+        }
+    }
+
+    /// The token of the query executed by the current task, or a token that is
+    /// never cancelled if the task is not executing a query
+    pub fn current() -> Self {
+        CURRENT.try_with(|token| token.clone()).unwrap_or_default()
+    }
+
+    /// Cancel the token, and the execution of the queries it is the token of
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(&mut *self.state.wakers.lock().unwrap());
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Cancel the token once `timeout` has elapsed. Must be called from within a
+    /// tokio runtime.
+    pub fn cancel_after(&self, timeout: Duration) {
+        let token = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            token.cancel();
+        });
+    }
+
+    /// Whether the token, or one of its parents, was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+            || self
+                .parent
+                .as_ref()
+                .map(|parent| parent.is_cancelled())
+                .unwrap_or(false)
+    }
+
+    /// Returns a [`DataFusionError::Cancelled`] error if the token was cancelled
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(DataFusionError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Wakes the task of `waker` when the token, or one of its parents, is cancelled
+    fn register(&self, waker: &Waker) {
+        {
+            let mut wakers = self.state.wakers.lock().unwrap();
+            if !wakers.iter().any(|w| w.will_wake(waker)) {
+                wakers.push(waker.clone());
+            }
+        }
+        if let Some(parent) = &self.parent {
+            parent.register(waker);
+        }
+    }
+
+    /// Runs `future` with this token as the token of the current task, so that the
+    /// partitions it executes are cancelled along with the token
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        CURRENT.scope(self.clone(), future).await
+    }
+}
+
+/// Spawns `future` on the tokio runtime with the token and the memory manager of the
+/// current task, so that the partitions it executes are cancelled and accounted
+/// along with the ones of the current task
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let token = CancellationToken::current();
+    match MemoryManager::current() {
+        Some(memory_manager) => tokio::spawn(async move {
+            token
+                .scope(MemoryManager::scope(memory_manager, future))
+                .await
+        }),
+        None => tokio::spawn(async move { token.scope(future).await }),
+    }
+}
+
+/// A stream over the batches of a query that ends with a cancellation error once its
+/// token is cancelled, and cancels the token when it is dropped
+pub struct CancellableStream {
+    inner: SendableRecordBatchStream,
+    token: CancellationToken,
+    finished: bool,
+}
+
+impl CancellableStream {
+    /// Create a stream over the batches of `inner` cancelled with `token`
+    pub fn new(inner: SendableRecordBatchStream, token: CancellationToken) -> Self {
+        Self {
+            inner,
+            token,
+            finished: false,
+        }
+    }
+}
+
+impl Stream for CancellableStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+        // registered before checking the token so that a cancellation is not missed
+        // while the inner stream is pending
+        self.token.register(cx.waker());
+        if let Err(e) = self.token.check() {
+            self.finished = true;
+            return Poll::Ready(Some(Err(e.into_arrow_external_error())));
+        }
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl RecordBatchStream for CancellableStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl Drop for CancellableStream {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::{collect, common, ExecutionPlan};
+    use crate::test;
+    use crate::test::exec::{assert_strong_count_converges_to_zero, BlockingExec};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    #[tokio::test]
+    async fn cancel_token_and_children() {
+        let token = CancellationToken::new();
+        let child = token.child();
+        assert!(child.check().is_ok());
+        assert!(!CancellationToken::current().is_cancelled());
+
+        let current = child
+            .scope(async { spawn(async { CancellationToken::current() }).await })
+            .await
+            .unwrap();
+        token.cancel();
+        assert!(child.is_cancelled());
+        assert!(current.is_cancelled());
+        assert!(matches!(child.check(), Err(DataFusionError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn cancellable_stream() -> Result<()> {
+        let batch = test::make_partition(10);
+        let schema = batch.schema();
+        let exec = MemoryExec::try_new(&[vec![batch.clone(), batch]], schema, None)?;
+        let token = CancellationToken::new();
+
+        let mut stream = CancellableStream::new(exec.execute(0).await?, token.clone());
+        assert!(stream.next().await.unwrap().is_ok());
+        token.cancel();
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+
+        // dropping the stream cancels the query
+        let token = CancellationToken::new();
+        let stream = CancellableStream::new(exec.execute(0).await?, token.clone());
+        drop(stream);
+        assert!(token.is_cancelled());
+
+        let token = CancellationToken::new();
+        let stream = CancellableStream::new(exec.execute(0).await?, token.clone());
+        assert_eq!(common::collect(Box::pin(stream)).await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cancel_blocked_query() -> Result<()> {
+        let schema =
+            Arc::new(Schema::new(vec![Field::new("a", DataType::Float32, true)]));
+        let blocking = Arc::new(BlockingExec::new(schema, 2));
+        let refs = blocking.refs();
+
+        let token = CancellationToken::new();
+        token.cancel_after(Duration::from_millis(10));
+        let result = token.scope(collect(blocking)).await;
+        assert!(
+            matches!(&result, Err(DataFusionError::ArrowError(e)) if e.to_string().contains("cancelled")),
+            "{:?}",
+            result
+        );
+        assert_strong_count_converges_to_zero(refs).await;
+        Ok(())
+    }
+}
@@ -23,7 +23,7 @@ use std::sync::Arc;
 use std::task::Poll;
 
 use futures::channel::mpsc;
-use futures::Stream;
+use futures::{Stream, StreamExt};
 
 use async_trait::async_trait;
 
@@ -38,14 +38,17 @@ use crate::physical_plan::{DisplayFormatType, ExecutionPlan, Partitioning};
 
 use super::SendableRecordBatchStream;
 use crate::physical_plan::common::spawn_execution;
-use pin_project_lite::pin_project;
 
 /// Merge execution plan executes partitions in parallel and combines them into a single
-/// partition. No guarantees are made about the order of the resulting partition.
+/// partition. No guarantees are made about the order of the resulting partition, unless
+/// it preserves the order of the partitions.
 #[derive(Debug)]
 pub struct CoalescePartitionsExec {
     /// Input execution plan
     input: Arc<dyn ExecutionPlan>,
+    /// Whether the batches of each partition are output after those of the previous
+    /// partitions, rather than as they are produced
+    preserve_partition_order: bool,
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
 }
@@ -55,6 +58,18 @@ impl CoalescePartitionsExec {
     pub fn new(input: Arc<dyn ExecutionPlan>) -> Self {
         CoalescePartitionsExec {
             input,
+            preserve_partition_order: false,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    /// Create a new CoalescePartitionsExec that outputs the batches of the partitions
+    /// one partition after the other, in the order of the partitions, so that its
+    /// output is deterministic. The partitions are still executed in parallel.
+    pub fn new_ordered(input: Arc<dyn ExecutionPlan>) -> Self {
+        CoalescePartitionsExec {
+            input,
+            preserve_partition_order: true,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
@@ -63,6 +78,11 @@ impl CoalescePartitionsExec {
     pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
         &self.input
     }
+
+    /// Whether the batches of the partitions are output in the order of the partitions
+    pub fn preserves_partition_order(&self) -> bool {
+        self.preserve_partition_order
+    }
 }
 
 #[async_trait]
@@ -90,7 +110,11 @@ impl ExecutionPlan for CoalescePartitionsExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(CoalescePartitionsExec::new(children[0].clone()))),
+            1 => Ok(Arc::new(CoalescePartitionsExec {
+                input: children[0].clone(),
+                preserve_partition_order: self.preserve_partition_order,
+                metrics: ExecutionPlanMetricsSet::new(),
+            })),
             _ => Err(DataFusionError::Internal(
                 "CoalescePartitionsExec wrong number of children".to_string(),
             )),
@@ -129,18 +153,31 @@ impl ExecutionPlan for CoalescePartitionsExec {
                     mpsc::channel::<ArrowResult<RecordBatch>>(input_partitions);
 
                 // spawn independent tasks whose resulting streams (of batches)
-                // are sent to the channel for consumption.
+                // are sent to the channel for consumption, or to a channel per
+                // partition consumed in order when preserving their order
+                let mut inputs = vec![receiver];
                 let mut join_handles = Vec::with_capacity(input_partitions);
                 for part_i in 0..input_partitions {
+                    let sender = if self.preserve_partition_order {
+                        let (sender, receiver) = mpsc::channel(1);
+                        inputs.push(receiver);
+                        sender
+                    } else {
+                        sender.clone()
+                    };
                     join_handles.push(spawn_execution(
                         self.input.clone(),
-                        sender.clone(),
+                        sender,
                         part_i,
                     ));
                 }
+                // the shared channel is only used by the partitions when not
+                // preserving their order
+                drop(sender);
 
                 Ok(Box::pin(MergeStream {
-                    input: receiver,
+                    inputs,
+                    current: 0,
                     schema: self.schema(),
                     baseline_metrics,
                     drop_helper: AbortOnDropMany(join_handles),
@@ -156,7 +193,11 @@ impl ExecutionPlan for CoalescePartitionsExec {
     ) -> std::fmt::Result {
         match t {
             DisplayFormatType::Default => {
-                write!(f, "CoalescePartitionsExec")
+                write!(f, "CoalescePartitionsExec")?;
+                if self.preserve_partition_order {
+                    write!(f, ": preserve_partition_order=true")?;
+                }
+                Ok(())
             }
         }
     }
@@ -170,26 +211,36 @@ impl ExecutionPlan for CoalescePartitionsExec {
     }
 }
 
-pin_project! {
-    struct MergeStream {
-        schema: SchemaRef,
-        #[pin]
-        input: mpsc::Receiver<ArrowResult<RecordBatch>>,
-        baseline_metrics: BaselineMetrics,
-        drop_helper: AbortOnDropMany<()>,
-    }
+struct MergeStream {
+    schema: SchemaRef,
+    /// The channels the batches are received from, one after the other
+    inputs: Vec<mpsc::Receiver<ArrowResult<RecordBatch>>>,
+    /// Index of the channel currently received from
+    current: usize,
+    baseline_metrics: BaselineMetrics,
+    #[allow(dead_code)]
+    drop_helper: AbortOnDropMany<()>,
 }
 
 impl Stream for MergeStream {
     type Item = ArrowResult<RecordBatch>;
 
     fn poll_next(
-        self: std::pin::Pin<&mut Self>,
+        mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        let this = self.project();
-        let poll = this.input.poll_next(cx);
-        this.baseline_metrics.record_poll(poll)
+        let poll = loop {
+            let current = self.current;
+            match self.inputs.get_mut(current) {
+                Some(input) => match input.poll_next_unpin(cx) {
+                    // the channel is done, receive from the next one
+                    Poll::Ready(None) => self.current += 1,
+                    poll => break poll,
+                },
+                None => break Poll::Ready(None),
+            }
+        };
+        self.baseline_metrics.record_poll(poll)
     }
 }
 
@@ -208,6 +259,7 @@ mod tests {
     use super::*;
     use crate::datasource::object_store::local::LocalFileSystem;
     use crate::physical_plan::file_format::{CsvExec, PhysicalPlanConfig};
+    use crate::physical_plan::memory::MemoryExec;
     use crate::physical_plan::{collect, common};
     use crate::test::exec::{assert_strong_count_converges_to_zero, BlockingExec};
     use crate::test::{self, assert_is_pending};
@@ -227,9 +279,11 @@ mod tests {
                 file_groups: files,
                 statistics: Statistics::default(),
                 projection: None,
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -255,6 +309,25 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn merge_ordered() -> Result<()> {
+        let partitions: Vec<_> = (1..=4)
+            .map(|size| vec![test::make_partition(size), test::make_partition(size)])
+            .collect();
+        let schema = partitions[0][0].schema();
+        let input = Arc::new(MemoryExec::try_new(&partitions, schema, None)?);
+
+        let merge = CoalescePartitionsExec::new_ordered(input);
+        assert!(merge.preserves_partition_order());
+        let batches = common::collect(merge.execute(0).await?).await?;
+
+        // the batches of each partition follow the ones of the previous partition
+        let sizes: Vec<_> = batches.iter().map(|batch| batch.num_rows()).collect();
+        assert_eq!(sizes, vec![1, 1, 2, 2, 3, 3, 4, 4]);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_drop_cancel() -> Result<()> {
         let schema =
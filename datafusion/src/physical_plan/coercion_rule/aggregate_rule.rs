@@ -59,6 +59,39 @@ pub(crate) fn coerce_types(
             Ok(input_types.to_vec())
         }
         AggregateFunction::ArrayAgg => Ok(input_types.to_vec()),
+        AggregateFunction::StringAgg => {
+            // the strings to concatenate and their delimiter
+            if !input_types.iter().all(is_string_type) {
+                return Err(DataFusionError::Plan(format!(
+                    "The function {:?} does not support inputs of type {:?}.",
+                    agg_fun, input_types
+                )));
+            }
+            // large strings are concatenated into a large string
+            let strings_type = match &input_types[0] {
+                DataType::LargeUtf8 => DataType::LargeUtf8,
+                _ => DataType::Utf8,
+            };
+            Ok(vec![strings_type, DataType::Utf8])
+        }
+        AggregateFunction::PercentileCont | AggregateFunction::PercentileDisc => {
+            // the ordered values and the percentile
+            if !is_numeric_type(&input_types[1])
+                || (agg_fun == &AggregateFunction::PercentileCont
+                    && !is_numeric_type(&input_types[0]))
+            {
+                return Err(DataFusionError::Plan(format!(
+                    "The function {:?} does not support inputs of type {:?}.",
+                    agg_fun, input_types
+                )));
+            }
+            // PERCENTILE_CONT interpolates between the values as floats
+            let value_type = match agg_fun {
+                AggregateFunction::PercentileCont => DataType::Float64,
+                _ => input_types[0].clone(),
+            };
+            Ok(vec![value_type, DataType::Float64])
+        }
         AggregateFunction::Min | AggregateFunction::Max => {
             // min and max support the dictionary data type
             // unpack the dictionary to get the value
@@ -89,6 +122,26 @@ pub(crate) fn coerce_types(
     }
 }
 
+fn is_string_type(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Utf8 | DataType::LargeUtf8)
+}
+
+fn is_numeric_type(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+    )
+}
+
 fn get_min_max_result_type(input_types: &[DataType]) -> Result<Vec<DataType>> {
     // make sure that the input types only has one element.
     assert_eq!(input_types.len(), 1);
@@ -203,5 +256,38 @@ mod tests {
                 assert_eq!(*input_type, result.unwrap());
             }
         }
+
+        // test string_agg, percentile_cont, percentile_disc
+        let cases = vec![
+            (
+                AggregateFunction::StringAgg,
+                vec![DataType::LargeUtf8, DataType::Utf8],
+                vec![DataType::LargeUtf8, DataType::Utf8],
+            ),
+            (
+                AggregateFunction::StringAgg,
+                vec![DataType::Utf8, DataType::LargeUtf8],
+                vec![DataType::Utf8, DataType::Utf8],
+            ),
+            (
+                AggregateFunction::PercentileCont,
+                vec![DataType::Int32, DataType::Int64],
+                vec![DataType::Float64, DataType::Float64],
+            ),
+            (
+                AggregateFunction::PercentileDisc,
+                vec![DataType::Utf8, DataType::Float64],
+                vec![DataType::Utf8, DataType::Float64],
+            ),
+        ];
+        for (fun, input_types, expected) in cases {
+            let signature = aggregates::signature(&fun);
+            let result = coerce_types(&fun, &input_types, &signature);
+            assert_eq!(expected, result.unwrap());
+        }
+        let fun = AggregateFunction::PercentileCont;
+        let signature = aggregates::signature(&fun);
+        let result = coerce_types(&fun, &[DataType::Utf8, DataType::Float64], &signature);
+        assert!(result.is_err());
     }
 }
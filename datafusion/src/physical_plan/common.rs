@@ -17,11 +17,14 @@
 
 //! Defines common code used in execution plans
 
-use super::{RecordBatchStream, SendableRecordBatchStream};
+use super::{cancellation, RecordBatchStream, SendableRecordBatchStream};
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{ColumnStatistics, ExecutionPlan, Statistics};
-use arrow::compute::concat;
-use arrow::datatypes::{Schema, SchemaRef};
+use arrow::array::{
+    make_array, Array, ArrayData, ArrayRef, BooleanArray, MutableArrayData, UInt64Array,
+};
+use arrow::compute::{concat, take};
+use arrow::datatypes::{DataType, Schema, SchemaRef};
 use arrow::error::ArrowError;
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
@@ -108,6 +111,73 @@ pub(crate) fn combine_batches(
     }
 }
 
+/// Spreads `truthy`, the values of the rows of a batch where `mask` is true,
+/// to an array with a row for each row of the batch, which is null where
+/// `mask` is false. `mask` must not contain nulls.
+pub(crate) fn scatter(mask: &BooleanArray, truthy: &dyn Array) -> Result<ArrayRef> {
+    let truthy_data = truthy.data();
+    let mut data = MutableArrayData::new(vec![truthy_data], true, mask.len());
+
+    // copy the runs of selected rows, padding the other rows with nulls
+    let mut filled = 0;
+    let mut taken = 0;
+    let mut row = 0;
+    while row < mask.len() {
+        if !mask.value(row) {
+            row += 1;
+            continue;
+        }
+        let start = row;
+        while row < mask.len() && mask.value(row) {
+            row += 1;
+        }
+        let len = row - start;
+        data.extend_nulls(start - filled);
+        data.extend(0, taken, taken + len);
+        filled = row;
+        taken += len;
+    }
+    data.extend_nulls(mask.len() - filled);
+
+    if taken != truthy.len() {
+        return Err(DataFusionError::Internal(format!(
+            "Scattered {} values of an array of {} values",
+            taken,
+            truthy.len()
+        )));
+    }
+    Ok(make_array(data.freeze()))
+}
+
+/// Takes the values of `array` at `indices` with the `take` kernel, which does not
+/// support maps, so that maps are taken as the lists of their entries, which have
+/// the same layout
+pub fn take_array(array: &dyn Array, indices: &UInt64Array) -> ArrowResult<ArrayRef> {
+    match array.data_type() {
+        DataType::Map(entries, _) => {
+            let list = with_layout_type(array, DataType::List(entries.clone()))?;
+            let taken = take(list.as_ref(), indices, None)?;
+            with_layout_type(taken.as_ref(), array.data_type().clone())
+        }
+        _ => take(array, indices, None),
+    }
+}
+
+/// Returns an array of `data_type` with the data of `array`, whose type must have the
+/// same layout
+fn with_layout_type(array: &dyn Array, data_type: DataType) -> ArrowResult<ArrayRef> {
+    let data = array.data();
+    let mut builder = ArrayData::builder(data_type)
+        .len(data.len())
+        .offset(data.offset())
+        .buffers(data.buffers().to_vec())
+        .child_data(data.child_data().to_vec());
+    if let Some(nulls) = data.null_buffer() {
+        builder = builder.null_bit_buffer(nulls.clone());
+    }
+    Ok(make_array(builder.build()?))
+}
+
 /// Recursively builds a list of files in a directory with a given extension
 pub fn build_checked_file_list(dir: &str, ext: &str) -> Result<Vec<String>> {
     let mut filenames: Vec<String> = Vec::new();
@@ -164,7 +234,7 @@ pub(crate) fn spawn_execution(
     mut output: mpsc::Sender<ArrowResult<RecordBatch>>,
     partition: usize,
 ) -> JoinHandle<()> {
-    tokio::spawn(async move {
+    cancellation::spawn(async move {
         let mut stream = match input.execute(partition).await {
             Err(e) => {
                 // If send fails, plan being torn
@@ -277,11 +347,27 @@ impl<T> Drop for AbortOnDropMany<T> {
 mod tests {
     use super::*;
     use arrow::{
-        array::{Float32Array, Float64Array},
+        array::{Float32Array, Float64Array, Int32Array},
         datatypes::{DataType, Field, Schema},
         record_batch::RecordBatch,
     };
 
+    #[test]
+    fn test_scatter() -> Result<()> {
+        let mask = BooleanArray::from(vec![false, true, true, false, true, false]);
+        let truthy = Int32Array::from(vec![Some(1), None, Some(3)]);
+        let result = scatter(&mask, &truthy)?;
+        let expected = Int32Array::from(vec![None, Some(1), None, None, Some(3), None]);
+        assert_eq!(
+            &expected,
+            result.as_any().downcast_ref::<Int32Array>().unwrap()
+        );
+
+        let err = scatter(&mask, &Int32Array::from(vec![1])).unwrap_err();
+        assert!(err.to_string().contains("Scattered 3 values"));
+        Ok(())
+    }
+
     #[test]
     fn test_combine_batches_empty() -> Result<()> {
         let schema = Arc::new(Schema::new(vec![
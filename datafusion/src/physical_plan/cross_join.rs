@@ -39,8 +39,12 @@ use async_trait::async_trait;
 use std::time::Instant;
 
 use super::{
-    coalesce_batches::concat_batches, memory::MemoryStream, DisplayFormatType,
-    ExecutionPlan, Partitioning, RecordBatchStream, SendableRecordBatchStream,
+    coalesce_batches::concat_batches,
+    memory::MemoryStream,
+    metrics::{self, BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
+    stream::ObservedStream,
+    DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream,
 };
 use log::debug;
 
@@ -59,6 +63,8 @@ pub struct CrossJoinExec {
     schema: SchemaRef,
     /// Build-side data
     build_side: Arc<Mutex<Option<JoinLeftData>>>,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl CrossJoinExec {
@@ -89,6 +95,7 @@ impl CrossJoinExec {
             right,
             schema,
             build_side: Arc::new(Mutex::new(None)),
+            metrics: ExecutionPlanMetricsSet::new(),
         })
     }
 
@@ -174,16 +181,17 @@ impl ExecutionPlan for CrossJoinExec {
         };
 
         let stream = self.right.execute(partition).await?;
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
 
         if left_data.num_rows() == 0 {
-            return Ok(Box::pin(MemoryStream::try_new(
-                vec![],
-                self.schema.clone(),
-                None,
-            )?));
+            let stream = MemoryStream::try_new(vec![], self.schema.clone(), None)?;
+            return Ok(Box::pin(ObservedStream::new(
+                Box::pin(stream),
+                baseline_metrics,
+            )));
         }
 
-        Ok(Box::pin(CrossJoinStream {
+        let stream = CrossJoinStream {
             schema: self.schema.clone(),
             left_data,
             right: stream,
@@ -194,7 +202,16 @@ impl ExecutionPlan for CrossJoinExec {
             num_output_batches: 0,
             num_output_rows: 0,
             join_time: 0,
-        }))
+            elapsed_compute: baseline_metrics.elapsed_compute().clone(),
+        };
+        Ok(Box::pin(ObservedStream::new(
+            Box::pin(stream),
+            baseline_metrics,
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
     }
 
     fn fmt_as(
@@ -305,6 +322,8 @@ struct CrossJoinStream {
     num_output_rows: usize,
     /// total time for joining probe-side batches to the build-side batches
     join_time: usize,
+    /// elapsed compute of the join, building the output batches
+    elapsed_compute: metrics::Time,
 }
 
 impl RecordBatchStream for CrossJoinStream {
@@ -356,6 +375,7 @@ impl Stream for CrossJoinStream {
             let result =
                 build_batch(self.left_index, &right_batch, &self.left_data, &self.schema);
             self.num_input_rows += right_batch.num_rows();
+            self.elapsed_compute.add_elapsed(start);
             if let Ok(ref batch) = result {
                 self.join_time += start.elapsed().as_millis() as usize;
                 self.num_output_batches += 1;
@@ -376,6 +396,7 @@ impl Stream for CrossJoinStream {
                         &self.left_data,
                         &self.schema,
                     );
+                    self.elapsed_compute.add_elapsed(start);
                     self.num_input_batches += 1;
                     self.num_input_rows += batch.num_rows();
                     if let Ok(ref batch) = result {
@@ -18,23 +18,27 @@
 //! DateTime expressions
 use std::sync::Arc;
 
+use super::expressions::IntervalParts;
 use super::ColumnarValue;
 use crate::{
     error::{DataFusionError, Result},
     scalar::{ScalarType, ScalarValue},
 };
 use arrow::{
-    array::{Array, ArrayRef, GenericStringArray, PrimitiveArray, StringOffsetSizeTrait},
+    array::{
+        Array, ArrayRef, GenericStringArray, PrimitiveArray, StringOffsetSizeTrait,
+        StructArray,
+    },
     compute::kernels::cast_utils::string_to_timestamp_nanos,
     datatypes::{
-        ArrowPrimitiveType, DataType, TimestampMicrosecondType, TimestampMillisecondType,
-        TimestampNanosecondType, TimestampSecondType,
+        ArrowPrimitiveType, DataType, Field, TimestampMicrosecondType,
+        TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType,
     },
 };
 use arrow::{
     array::{
-        Date32Array, Date64Array, TimestampMicrosecondArray, TimestampMillisecondArray,
-        TimestampNanosecondArray, TimestampSecondArray,
+        Date32Array, Date64Array, Int32Array, TimestampMicrosecondArray,
+        TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray,
     },
     compute::kernels::temporal,
     datatypes::TimeUnit,
@@ -180,7 +184,7 @@ pub fn make_now(
     let now_ts = Some(now_ts.timestamp_nanos());
     move |_arg| {
         Ok(ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(
-            now_ts,
+            now_ts, None,
         )))
     }
 }
@@ -240,8 +244,8 @@ pub fn date_trunc(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     let f = |x: Option<i64>| x.map(|x| date_trunc_single(granularity, x)).transpose();
 
     Ok(match array {
-        ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(v)) => {
-            ColumnarValue::Scalar(ScalarValue::TimestampNanosecond((f)(*v)?))
+        ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(v, _)) => {
+            ColumnarValue::Scalar(ScalarValue::TimestampNanosecond((f)(*v)?, None))
         }
         ColumnarValue::Array(array) => {
             let array = array
@@ -263,6 +267,162 @@ pub fn date_trunc(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     })
 }
 
+/// Returns the length in nanoseconds of the `stride` argument of `name`, which must
+/// be a positive, non-null scalar interval without months
+fn stride_nanos(stride: &ColumnarValue, name: &str) -> Result<i64> {
+    const NANOS_PER_DAY: i64 = 86_400_000_000_000;
+
+    let stride = match stride {
+        ColumnarValue::Scalar(v) if !v.is_null() => {
+            IntervalParts::from_array(v.to_array().as_ref(), 0)?
+        }
+        _ => {
+            return Err(DataFusionError::Execution(format!(
+                "Stride of `{}` must be a non-null scalar interval",
+                name
+            )));
+        }
+    };
+    if stride.months != 0 {
+        return Err(DataFusionError::NotImplemented(format!(
+            "`{}` does not support strides of months or years",
+            name
+        )));
+    }
+    match (stride.days as i64)
+        .checked_mul(NANOS_PER_DAY)
+        .and_then(|days| days.checked_add(stride.nanos))
+    {
+        Some(nanos) if nanos > 0 => Ok(nanos),
+        _ => Err(DataFusionError::Execution(format!(
+            "Stride of `{}` must be a positive interval",
+            name
+        ))),
+    }
+}
+
+/// Returns the start of the bin of width `stride` nanoseconds that `value` falls
+/// in, where bins are aligned to `origin`
+fn date_bin_single(stride: i64, origin: i64, value: i64) -> Result<i64> {
+    let (stride, origin) = (stride as i128, origin as i128);
+    let bin = (value as i128 - origin).div_euclid(stride) * stride + origin;
+    i64::try_from(bin).map_err(|_| {
+        DataFusionError::Execution(format!("Bin of timestamp {} is out of range", value))
+    })
+}
+
+/// Applies `f` to every timestamp of the `Timestamp(Nanosecond)` argument `array`
+/// of `name`
+fn map_timestamps<F>(array: &ColumnarValue, name: &str, f: F) -> Result<ColumnarValue>
+where
+    F: Fn(Option<i64>) -> Result<Option<i64>>,
+{
+    Ok(match array {
+        ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(v, _)) => {
+            ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(f(*v)?, None))
+        }
+        ColumnarValue::Array(array) => {
+            let array = array
+                .as_any()
+                .downcast_ref::<TimestampNanosecondArray>()
+                .unwrap();
+            let array = array
+                .iter()
+                .map(f)
+                .collect::<Result<TimestampNanosecondArray>>()?;
+
+            ColumnarValue::Array(Arc::new(array))
+        }
+        _ => {
+            return Err(DataFusionError::Execution(format!(
+                "Source of `{}` must be a timestamp",
+                name
+            )));
+        }
+    })
+}
+
+/// date_bin SQL function: `date_bin(stride, source[, origin])` truncates `source`
+/// to the start of the bin of width `stride` it falls in. Bins are aligned to
+/// `origin`, which defaults to the Unix epoch.
+pub fn date_bin(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let stride = stride_nanos(&args[0], "date_bin")?;
+    let origin = match args.get(2) {
+        None => 0,
+        Some(ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(Some(v), _))) => *v,
+        Some(_) => {
+            return Err(DataFusionError::Execution(
+                "Origin of `date_bin` must be a non-null scalar timestamp".to_string(),
+            ));
+        }
+    };
+
+    map_timestamps(&args[1], "date_bin", |x| {
+        x.map(|x| date_bin_single(stride, origin, x)).transpose()
+    })
+}
+
+/// The fields of the struct returned by the `window` SQL function
+pub fn window_fields() -> Vec<Field> {
+    let timestamp = DataType::Timestamp(TimeUnit::Nanosecond, None);
+    vec![
+        Field::new("start", timestamp.clone(), true),
+        Field::new("end", timestamp, true),
+    ]
+}
+
+/// window SQL function: `window(source, duration)` returns the tumbling window of
+/// width `duration` that `source` falls in, as a struct of its `start` (inclusive)
+/// and `end` (exclusive) timestamps. Windows are aligned to the Unix epoch.
+pub fn window(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let duration = stride_nanos(&args[1], "window")?;
+    let end = |start: Option<i64>| {
+        start
+            .map(|start| {
+                start.checked_add(duration).ok_or_else(|| {
+                    DataFusionError::Execution(format!(
+                        "End of window starting at {} is out of range",
+                        start
+                    ))
+                })
+            })
+            .transpose()
+    };
+
+    let start = map_timestamps(&args[0], "window", |x| {
+        x.map(|x| date_bin_single(duration, 0, x)).transpose()
+    })?;
+    Ok(match start {
+        ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(start, _)) => {
+            let values = start.map(|start| {
+                Ok(Box::new(vec![
+                    ScalarValue::TimestampNanosecond(Some(start), None),
+                    ScalarValue::TimestampNanosecond(end(Some(start))?, None),
+                ]))
+            });
+            ColumnarValue::Scalar(ScalarValue::Struct(
+                values.transpose()?,
+                Box::new(window_fields()),
+            ))
+        }
+        ColumnarValue::Array(start) => {
+            let ends = start
+                .as_any()
+                .downcast_ref::<TimestampNanosecondArray>()
+                .unwrap()
+                .iter()
+                .map(end)
+                .collect::<Result<TimestampNanosecondArray>>()?;
+            let fields = window_fields();
+            ColumnarValue::Array(Arc::new(StructArray::from(vec![
+                (fields[0].clone(), start),
+                (fields[1].clone(), Arc::new(ends) as ArrayRef),
+            ])))
+        }
+        _ => unreachable!(),
+    })
+}
+
 macro_rules! extract_date_part {
     ($ARRAY: expr, $FN:expr) => {
         match $ARRAY.data_type() {
@@ -312,6 +472,47 @@ macro_rules! extract_date_part {
     };
 }
 
+/// Extracts `date_part` from every interval of `array` the way PostgreSQL does:
+/// the months are split into years and months and the time into hours, minutes
+/// and seconds, but days are not converted into months nor hours into days
+fn extract_interval_part(array: &ArrayRef, date_part: &str) -> Result<Int32Array> {
+    const NANOS_PER_MICRO: i64 = 1_000;
+    const NANOS_PER_MILLI: i64 = 1_000 * NANOS_PER_MICRO;
+    const NANOS_PER_SECOND: i64 = 1_000 * NANOS_PER_MILLI;
+    const NANOS_PER_MINUTE: i64 = 60 * NANOS_PER_SECOND;
+    const NANOS_PER_HOUR: i64 = 60 * NANOS_PER_MINUTE;
+
+    let extract: fn(&IntervalParts) -> i64 = match date_part.to_lowercase().as_str() {
+        "year" | "years" => |i| i.months as i64 / 12,
+        "month" | "months" => |i| i.months as i64 % 12,
+        "day" | "days" => |i| i.days as i64,
+        "hour" | "hours" => |i| i.nanos / NANOS_PER_HOUR,
+        "minute" | "minutes" => |i| i.nanos % NANOS_PER_HOUR / NANOS_PER_MINUTE,
+        "second" | "seconds" => |i| i.nanos % NANOS_PER_MINUTE / NANOS_PER_SECOND,
+        "millisecond" | "milliseconds" => {
+            |i| i.nanos % NANOS_PER_MINUTE / NANOS_PER_MILLI
+        }
+        "microsecond" | "microseconds" => {
+            |i| i.nanos % NANOS_PER_MINUTE / NANOS_PER_MICRO
+        }
+        _ => {
+            return Err(DataFusionError::Execution(format!(
+                "Date part '{}' not supported for intervals",
+                date_part
+            )))
+        }
+    };
+    (0..array.len())
+        .map(|index| {
+            if array.is_null(index) {
+                return Ok(None);
+            }
+            let interval = IntervalParts::from_array(array.as_ref(), index)?;
+            Ok(Some(extract(&interval) as i32))
+        })
+        .collect()
+}
+
 /// DATE_PART SQL function
 pub fn date_part(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     if args.len() != 2 {
@@ -336,6 +537,18 @@ pub fn date_part(args: &[ColumnarValue]) -> Result<ColumnarValue> {
         ColumnarValue::Scalar(scalar) => scalar.to_array(),
     };
 
+    if let DataType::Interval(_) = array.data_type() {
+        let arr = extract_interval_part(&array, date_part)?;
+        return Ok(if is_scalar {
+            ColumnarValue::Scalar(ScalarValue::try_from_array(
+                &(Arc::new(arr) as ArrayRef),
+                0,
+            )?)
+        } else {
+            ColumnarValue::Array(Arc::new(arr))
+        });
+    }
+
     let arr = match date_part.to_lowercase().as_str() {
         "hour" => extract_date_part!(array, temporal::hour),
         "year" => extract_date_part!(array, temporal::year),
@@ -448,6 +661,137 @@ mod tests {
         });
     }
 
+    #[test]
+    fn date_bin_test() -> Result<()> {
+        let cases = vec![
+            (
+                "2020-09-08T13:42:29.190855Z",
+                ScalarValue::IntervalDayTime(Some(15 * 60 * 1000)),
+                None,
+                "2020-09-08T13:30:00.000000Z",
+            ),
+            (
+                "2020-09-08T13:42:29.190855Z",
+                ScalarValue::new_interval_mdn(0, 1, 0),
+                None,
+                "2020-09-08T00:00:00.000000Z",
+            ),
+            (
+                "2020-09-08T13:42:29.190855Z",
+                ScalarValue::IntervalDayTime(Some(15 * 60 * 1000)),
+                Some("2020-09-08T13:05:00.000000Z"),
+                "2020-09-08T13:35:00.000000Z",
+            ),
+            // timestamps before the origin fall in earlier bins
+            (
+                "2020-09-08T13:02:00.000000Z",
+                ScalarValue::IntervalDayTime(Some(15 * 60 * 1000)),
+                Some("2020-09-08T13:05:00.000000Z"),
+                "2020-09-08T12:50:00.000000Z",
+            ),
+        ];
+
+        for (source, stride, origin, expected) in cases {
+            let source = string_to_timestamp_nanos(source).unwrap();
+            let mut args = vec![
+                ColumnarValue::Scalar(stride),
+                ColumnarValue::Array(Arc::new(TimestampNanosecondArray::from(vec![
+                    Some(source),
+                    None,
+                ]))),
+            ];
+            if let Some(origin) = origin {
+                let origin = string_to_timestamp_nanos(origin).unwrap();
+                args.push(ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(
+                    Some(origin),
+                    None,
+                )));
+            }
+            let expected = string_to_timestamp_nanos(expected).unwrap();
+            match date_bin(&args)? {
+                ColumnarValue::Array(array) => assert_eq!(
+                    &TimestampNanosecondArray::from(vec![Some(expected), None]),
+                    array
+                        .as_any()
+                        .downcast_ref::<TimestampNanosecondArray>()
+                        .unwrap()
+                ),
+                _ => panic!("Expected a columnar array"),
+            }
+        }
+
+        let source =
+            ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(Some(0), None));
+        let months = ColumnarValue::Scalar(ScalarValue::IntervalYearMonth(Some(1)));
+        let err = date_bin(&[months, source.clone()]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("does not support strides of months"));
+        let zero = ColumnarValue::Scalar(ScalarValue::IntervalDayTime(Some(0)));
+        let err = date_bin(&[zero, source]).unwrap_err();
+        assert!(err.to_string().contains("must be a positive interval"));
+        Ok(())
+    }
+
+    #[test]
+    fn window_test() -> Result<()> {
+        let five_minutes =
+            ColumnarValue::Scalar(ScalarValue::IntervalDayTime(Some(5 * 60 * 1000)));
+        let source = string_to_timestamp_nanos("2020-09-08T13:42:29.190855Z").unwrap();
+        let start = string_to_timestamp_nanos("2020-09-08T13:40:00Z").unwrap();
+        let end = string_to_timestamp_nanos("2020-09-08T13:45:00Z").unwrap();
+
+        let args = vec![
+            ColumnarValue::Array(Arc::new(TimestampNanosecondArray::from(vec![
+                Some(source),
+                None,
+            ]))),
+            five_minutes.clone(),
+        ];
+        let array = match window(&args)? {
+            ColumnarValue::Array(array) => array,
+            _ => panic!("Expected a columnar array"),
+        };
+        let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+        assert_eq!(
+            &TimestampNanosecondArray::from(vec![Some(start), None]),
+            array
+                .column_by_name("start")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<TimestampNanosecondArray>()
+                .unwrap()
+        );
+        assert_eq!(
+            &TimestampNanosecondArray::from(vec![Some(end), None]),
+            array
+                .column_by_name("end")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<TimestampNanosecondArray>()
+                .unwrap()
+        );
+
+        let args = vec![
+            ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(Some(source), None)),
+            five_minutes,
+        ];
+        match window(&args)? {
+            ColumnarValue::Scalar(v) => assert_eq!(
+                ScalarValue::Struct(
+                    Some(Box::new(vec![
+                        ScalarValue::TimestampNanosecond(Some(start), None),
+                        ScalarValue::TimestampNanosecond(Some(end), None),
+                    ])),
+                    Box::new(window_fields()),
+                ),
+                v
+            ),
+            _ => panic!("Expected a scalar"),
+        }
+        Ok(())
+    }
+
     #[test]
     fn to_timestamp_invalid_input_type() -> Result<()> {
         // pass the wrong type of input array to to_timestamp and test
@@ -0,0 +1,123 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Dictionary-aware kernels, which evaluate dictionary arrays on their values,
+//! once per distinct value, rather than unpacking them into an array of their
+//! value type with one value per row
+
+use crate::error::{DataFusionError, Result};
+use arrow::array::{make_array, Array, ArrayData, ArrayRef, UInt64Array};
+use arrow::compute::{cast, take};
+use arrow::datatypes::DataType;
+
+/// Returns true if `data_type` is a dictionary type
+pub fn is_dictionary(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Dictionary(_, _))
+}
+
+/// Returns the keys of the dictionary array `array`, as indices of its values,
+/// which are null for the null keys, and its values
+pub fn dictionary_keys_and_values(array: &dyn Array) -> Result<(UInt64Array, ArrayRef)> {
+    let data = array.data();
+    let key_type = match data.data_type() {
+        DataType::Dictionary(key_type, _) => key_type.as_ref().clone(),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Expected a dictionary array, not an array of type {:?}",
+                other
+            )))
+        }
+    };
+    // the keys have the layout of a primitive array of the key type
+    let mut builder = ArrayData::builder(key_type)
+        .len(data.len())
+        .offset(data.offset())
+        .buffers(data.buffers().to_vec());
+    if let Some(nulls) = data.null_buffer() {
+        builder = builder.null_bit_buffer(nulls.clone());
+    }
+    let keys = cast(&make_array(builder.build()?), &DataType::UInt64)?;
+    let keys = UInt64Array::from(keys.data().clone());
+    Ok((keys, make_array(data.child_data()[0].clone())))
+}
+
+/// Unpacks the dictionary array `array` into an array of its value type
+pub fn unpack_dictionary(array: &dyn Array) -> Result<ArrayRef> {
+    let (keys, values) = dictionary_keys_and_values(array)?;
+    Ok(take(values.as_ref(), &keys, None)?)
+}
+
+/// Evaluates `f` on the values of the dictionary array `array` instead of its
+/// rows, and returns the results of the rows, which are null for the null keys.
+/// `f` must return one result for each of the values it is given, and should
+/// not fail on values that no row refers to.
+pub fn evaluate_on_values(
+    array: &dyn Array,
+    f: impl FnOnce(ArrayRef) -> Result<ArrayRef>,
+) -> Result<ArrayRef> {
+    let (keys, values) = dictionary_keys_and_values(array)?;
+    let num_values = values.len();
+    let results = f(values)?;
+    if results.len() != num_values {
+        return Err(DataFusionError::Internal(format!(
+            "Expected {} results for the values of a dictionary, got {}",
+            num_values,
+            results.len()
+        )));
+    }
+    Ok(take(results.as_ref(), &keys, None)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{BooleanArray, DictionaryArray, StringArray};
+    use arrow::compute::kernels::comparison::eq_utf8_scalar;
+    use arrow::datatypes::Int8Type;
+
+    #[test]
+    fn evaluate_dictionary_values() -> Result<()> {
+        let dict: DictionaryArray<Int8Type> = vec![Some("a"), None, Some("b"), Some("a")]
+            .into_iter()
+            .collect();
+        let (keys, values) = dictionary_keys_and_values(&dict)?;
+        assert_eq!(
+            keys,
+            UInt64Array::from(vec![Some(0), None, Some(1), Some(0)])
+        );
+        assert_eq!(values.len(), 2);
+
+        let unpacked = unpack_dictionary(&dict)?;
+        assert_eq!(
+            unpacked.as_any().downcast_ref::<StringArray>().unwrap(),
+            &StringArray::from(vec![Some("a"), None, Some("b"), Some("a")])
+        );
+
+        let results = evaluate_on_values(&dict, |values| {
+            let values = values.as_any().downcast_ref::<StringArray>().unwrap();
+            Ok(std::sync::Arc::new(eq_utf8_scalar(values, "a")?))
+        })?;
+        assert_eq!(
+            results.as_any().downcast_ref::<BooleanArray>().unwrap(),
+            &BooleanArray::from(vec![Some(true), None, Some(false), Some(true)])
+        );
+
+        assert!(is_dictionary(dict.data_type()));
+        assert!(!is_dictionary(unpacked.data_type()));
+        Ok(())
+    }
+}
@@ -21,8 +21,11 @@
 
 use std::fmt;
 
-use crate::logical_plan::{StringifiedPlan, ToStringifiedPlan};
+use crate::logical_plan::display::{json_quoted, GraphvizBuilder};
+use crate::logical_plan::{ExplainFormat, StringifiedPlan, ToStringifiedPlan};
 
+use super::estimation::estimate_statistics;
+use super::metrics::MetricsSet;
 use super::{accept, ExecutionPlan, ExecutionPlanVisitor};
 
 /// Options for controlling how each [`ExecutionPlan`] should format itself
@@ -37,6 +40,8 @@ pub struct DisplayableExecutionPlan<'a> {
     inner: &'a dyn ExecutionPlan,
     /// How to show metrics
     show_metrics: ShowMetrics,
+    /// Whether to show the estimated number of rows and bytes of each node
+    show_estimates: bool,
 }
 
 impl<'a> DisplayableExecutionPlan<'a> {
@@ -46,6 +51,7 @@ impl<'a> DisplayableExecutionPlan<'a> {
         Self {
             inner,
             show_metrics: ShowMetrics::None,
+            show_estimates: false,
         }
     }
 
@@ -56,6 +62,7 @@ impl<'a> DisplayableExecutionPlan<'a> {
         Self {
             inner,
             show_metrics: ShowMetrics::Aggregated,
+            show_estimates: false,
         }
     }
 
@@ -66,9 +73,17 @@ impl<'a> DisplayableExecutionPlan<'a> {
         Self {
             inner,
             show_metrics: ShowMetrics::Full,
+            show_estimates: false,
         }
     }
 
+    /// Also show the number of rows and bytes each node is estimated to produce,
+    /// see [`estimate_statistics`]
+    pub fn show_estimates(mut self, show_estimates: bool) -> Self {
+        self.show_estimates = show_estimates;
+        self
+    }
+
     /// Return a `format`able structure that produces a single line
     /// per node.
     ///
@@ -83,6 +98,7 @@ impl<'a> DisplayableExecutionPlan<'a> {
         struct Wrapper<'a> {
             plan: &'a dyn ExecutionPlan,
             show_metrics: ShowMetrics,
+            show_estimates: bool,
         }
         impl<'a> fmt::Display for Wrapper<'a> {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -92,6 +108,7 @@ impl<'a> DisplayableExecutionPlan<'a> {
                     f,
                     indent: 0,
                     show_metrics: self.show_metrics,
+                    show_estimates: self.show_estimates,
                 };
                 accept(self.plan, &mut visitor)
             }
@@ -99,6 +116,81 @@ impl<'a> DisplayableExecutionPlan<'a> {
         Wrapper {
             plan: self.inner,
             show_metrics: self.show_metrics,
+            show_estimates: self.show_estimates,
+        }
+    }
+
+    /// Return a `format`able structure that produces the plan as a single
+    /// JSON object, where each node has its description in `node`, its
+    /// metrics (if shown) in `metrics` and its children in `inputs`.
+    ///
+    /// ```text
+    /// {"node": "ProjectionExec: expr=[a]", "inputs": [
+    ///   {"node": "CsvExec: source=...", "inputs": []}]}
+    /// ```
+    pub fn json(&self) -> impl fmt::Display + 'a {
+        struct Wrapper<'a> {
+            plan: &'a dyn ExecutionPlan,
+            show_metrics: ShowMetrics,
+            show_estimates: bool,
+        }
+        impl<'a> fmt::Display for Wrapper<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let mut visitor = JsonVisitor {
+                    t: DisplayFormatType::Default,
+                    f,
+                    inputs_written: vec![],
+                    show_metrics: self.show_metrics,
+                    show_estimates: self.show_estimates,
+                };
+                accept(self.plan, &mut visitor)
+            }
+        }
+        Wrapper {
+            plan: self.inner,
+            show_metrics: self.show_metrics,
+            show_estimates: self.show_estimates,
+        }
+    }
+
+    /// Return a `format`able structure that produces the plan as a graph in
+    /// the `DOT` language, which can be visualized using software from
+    /// [`graphviz`](https://graphviz.org/)
+    pub fn graphviz(&self) -> impl fmt::Display + 'a {
+        struct Wrapper<'a> {
+            plan: &'a dyn ExecutionPlan,
+            show_metrics: ShowMetrics,
+            show_estimates: bool,
+        }
+        impl<'a> fmt::Display for Wrapper<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                writeln!(
+                    f,
+                    "// Begin DataFusion GraphViz Plan (see https://graphviz.org)"
+                )?;
+                writeln!(f, "digraph {{")?;
+
+                let mut graphviz_builder = GraphvizBuilder::default();
+                graphviz_builder.start_cluster(f, "ExecutionPlan")?;
+                let mut visitor = GraphvizVisitor {
+                    t: DisplayFormatType::Default,
+                    f,
+                    graphviz_builder,
+                    parent_ids: vec![],
+                    show_metrics: self.show_metrics,
+                    show_estimates: self.show_estimates,
+                };
+                accept(self.plan, &mut visitor)?;
+                visitor.graphviz_builder.end_cluster(visitor.f)?;
+
+                writeln!(visitor.f, "}}")?;
+                writeln!(visitor.f, "// End DataFusion GraphViz Plan")
+            }
+        }
+        Wrapper {
+            plan: self.inner,
+            show_metrics: self.show_metrics,
+            show_estimates: self.show_estimates,
         }
     }
 }
@@ -115,6 +207,26 @@ enum ShowMetrics {
     Full,
 }
 
+impl ShowMetrics {
+    /// The metrics of `plan` to show, if any
+    fn metrics(&self, plan: &dyn ExecutionPlan) -> Option<MetricsSet> {
+        match self {
+            Self::None => None,
+            Self::Aggregated => Some(
+                plan.metrics()
+                    .map(|metrics| {
+                        metrics
+                            .aggregate_by_partition()
+                            .sorted_for_display()
+                            .timestamps_removed()
+                    })
+                    .unwrap_or_default(),
+            ),
+            Self::Full => Some(plan.metrics().unwrap_or_default()),
+        }
+    }
+}
+
 /// Formats plans with a single line per node.
 struct IndentVisitor<'a, 'b> {
     /// How to format each node
@@ -125,6 +237,8 @@ struct IndentVisitor<'a, 'b> {
     indent: usize,
     /// How to show metrics
     show_metrics: ShowMetrics,
+    /// Whether to show the estimated number of rows and bytes
+    show_estimates: bool,
 }
 
 impl<'a, 'b> ExecutionPlanVisitor for IndentVisitor<'a, 'b> {
@@ -135,27 +249,16 @@ impl<'a, 'b> ExecutionPlanVisitor for IndentVisitor<'a, 'b> {
     ) -> std::result::Result<bool, Self::Error> {
         write!(self.f, "{:indent$}", "", indent = self.indent * 2)?;
         plan.fmt_as(self.t, self.f)?;
-        match self.show_metrics {
-            ShowMetrics::None => {}
-            ShowMetrics::Aggregated => {
-                if let Some(metrics) = plan.metrics() {
-                    let metrics = metrics
-                        .aggregate_by_partition()
-                        .sorted_for_display()
-                        .timestamps_removed();
-
-                    write!(self.f, ", metrics=[{}]", metrics)?;
-                } else {
-                    write!(self.f, ", metrics=[]")?;
-                }
-            }
-            ShowMetrics::Full => {
-                if let Some(metrics) = plan.metrics() {
-                    write!(self.f, ", metrics=[{}]", metrics)?;
-                } else {
-                    write!(self.f, ", metrics=[]")?;
-                }
-            }
+        if let Some(metrics) = self.show_metrics.metrics(plan) {
+            write!(self.f, ", metrics=[{}]", metrics)?;
+        }
+        if self.show_estimates {
+            let estimates = Estimates::of(plan);
+            write!(
+                self.f,
+                ", estimated_rows={}, estimated_bytes={}",
+                estimates.num_rows, estimates.total_byte_size
+            )?;
         }
         writeln!(self.f)?;
         self.indent += 1;
@@ -168,11 +271,212 @@ impl<'a, 'b> ExecutionPlanVisitor for IndentVisitor<'a, 'b> {
     }
 }
 
+/// Formats plans as a single JSON object
+struct JsonVisitor<'a, 'b> {
+    /// How to format each node
+    t: DisplayFormatType,
+    /// Write to this formatter
+    f: &'a mut fmt::Formatter<'b>,
+    /// The number of inputs written so far for each of the ancestors of the
+    /// current node
+    inputs_written: Vec<usize>,
+    /// How to show metrics
+    show_metrics: ShowMetrics,
+    /// Whether to show the estimated number of rows and bytes
+    show_estimates: bool,
+}
+
+impl<'a, 'b> ExecutionPlanVisitor for JsonVisitor<'a, 'b> {
+    type Error = fmt::Error;
+    fn pre_visit(
+        &mut self,
+        plan: &dyn ExecutionPlan,
+    ) -> std::result::Result<bool, Self::Error> {
+        if let Some(written) = self.inputs_written.last_mut() {
+            if *written > 0 {
+                write!(self.f, ", ")?;
+            }
+            *written += 1;
+        }
+        let node = NodeDisplay { t: self.t, plan }.to_string();
+        write!(self.f, "{{\"node\": {}", json_quoted(&node))?;
+        if let Some(metrics) = self.show_metrics.metrics(plan) {
+            write!(self.f, ", \"metrics\": [")?;
+            for (i, metric) in metrics.iter().enumerate() {
+                if i > 0 {
+                    write!(self.f, ", ")?;
+                }
+                write!(
+                    self.f,
+                    "{{\"name\": {}, \"value\": {}",
+                    json_quoted(metric.value().name()),
+                    metric.value().as_usize()
+                )?;
+                if let Some(partition) = metric.partition() {
+                    write!(self.f, ", \"partition\": {}", partition)?;
+                }
+                if !metric.labels().is_empty() {
+                    write!(self.f, ", \"labels\": {{")?;
+                    for (i, label) in metric.labels().iter().enumerate() {
+                        if i > 0 {
+                            write!(self.f, ", ")?;
+                        }
+                        write!(
+                            self.f,
+                            "{}: {}",
+                            json_quoted(label.name()),
+                            json_quoted(label.value())
+                        )?;
+                    }
+                    write!(self.f, "}}")?;
+                }
+                write!(self.f, "}}")?;
+            }
+            write!(self.f, "]")?;
+        }
+        if self.show_estimates {
+            let estimates = Estimates::of(plan);
+            write!(
+                self.f,
+                ", \"estimated_rows\": {}, \"estimated_bytes\": {}",
+                estimates.num_rows.json(),
+                estimates.total_byte_size.json()
+            )?;
+        }
+        write!(self.f, ", \"inputs\": [")?;
+        self.inputs_written.push(0);
+        Ok(true)
+    }
+
+    fn post_visit(&mut self, _plan: &dyn ExecutionPlan) -> Result<bool, Self::Error> {
+        self.inputs_written.pop();
+        write!(self.f, "]}}")?;
+        Ok(true)
+    }
+}
+
+/// Formats plans for graphical display using the `DOT` language
+struct GraphvizVisitor<'a, 'b> {
+    /// How to format each node
+    t: DisplayFormatType,
+    /// Write to this formatter
+    f: &'a mut fmt::Formatter<'b>,
+    graphviz_builder: GraphvizBuilder,
+    /// Holds the ids (as generated from `graphviz_builder` of all
+    /// parent nodes
+    parent_ids: Vec<usize>,
+    /// How to show metrics
+    show_metrics: ShowMetrics,
+    /// Whether to show the estimated number of rows and bytes
+    show_estimates: bool,
+}
+
+impl<'a, 'b> ExecutionPlanVisitor for GraphvizVisitor<'a, 'b> {
+    type Error = fmt::Error;
+    fn pre_visit(
+        &mut self,
+        plan: &dyn ExecutionPlan,
+    ) -> std::result::Result<bool, Self::Error> {
+        let id = self.graphviz_builder.next_id();
+
+        let mut label = NodeDisplay { t: self.t, plan }.to_string();
+        if let Some(metrics) = self.show_metrics.metrics(plan) {
+            label = format!(r"{}\nMetrics: [{}]", label, metrics);
+        }
+        if self.show_estimates {
+            let estimates = Estimates::of(plan);
+            label = format!(
+                r"{}\nEstimated rows: {}, bytes: {}",
+                label, estimates.num_rows, estimates.total_byte_size
+            );
+        }
+        writeln!(
+            self.f,
+            "    {}[shape=box label={}]",
+            id,
+            GraphvizBuilder::quoted(&label)
+        )?;
+
+        // Create an edge to our parent node, if any
+        if let Some(parent_id) = self.parent_ids.last() {
+            writeln!(
+                self.f,
+                "    {} -> {} [arrowhead=none, arrowtail=normal, dir=back]",
+                parent_id, id
+            )?;
+        }
+
+        self.parent_ids.push(id);
+        Ok(true)
+    }
+
+    fn post_visit(&mut self, _plan: &dyn ExecutionPlan) -> Result<bool, Self::Error> {
+        // always be non-empty as pre_visit always pushes
+        self.parent_ids.pop().unwrap();
+        Ok(true)
+    }
+}
+
+/// The estimated number of rows and bytes produced by a node
+struct Estimates {
+    num_rows: Estimate,
+    total_byte_size: Estimate,
+}
+
+impl Estimates {
+    fn of(plan: &dyn ExecutionPlan) -> Self {
+        let statistics = estimate_statistics(plan);
+        Self {
+            num_rows: Estimate(statistics.num_rows),
+            total_byte_size: Estimate(statistics.total_byte_size),
+        }
+    }
+}
+
+/// An estimate, displayed as `unknown` when it cannot be estimated
+struct Estimate(Option<usize>);
+
+impl Estimate {
+    /// The estimate as a JSON value, `null` when it cannot be estimated
+    fn json(&self) -> String {
+        self.0
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "null".to_owned())
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(value) => write!(f, "{}", value),
+            None => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Formats the description of a single node, not including its children
+struct NodeDisplay<'a> {
+    t: DisplayFormatType,
+    plan: &'a dyn ExecutionPlan,
+}
+
+impl<'a> fmt::Display for NodeDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.plan.fmt_as(self.t, f)
+    }
+}
+
 impl<'a> ToStringifiedPlan for DisplayableExecutionPlan<'a> {
-    fn to_stringified(
+    fn to_stringified_with_format(
         &self,
         plan_type: crate::logical_plan::PlanType,
+        format: ExplainFormat,
     ) -> StringifiedPlan {
-        StringifiedPlan::new(plan_type, self.indent().to_string())
+        let plan = match format {
+            ExplainFormat::Indent => self.indent().to_string(),
+            ExplainFormat::Json => self.json().to_string(),
+            ExplainFormat::Graphviz => self.graphviz().to_string(),
+        };
+        StringifiedPlan::new(plan_type, plan)
     }
 }
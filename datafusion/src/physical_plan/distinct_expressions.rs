@@ -18,6 +18,7 @@
 //! Implementations for DISTINCT expressions, e.g. `COUNT(DISTINCT c)`
 
 use std::any::Any;
+use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::sync::Arc;
@@ -28,6 +29,7 @@ use ahash::RandomState;
 use std::collections::HashSet;
 
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::sum_scalars;
 use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
 use crate::scalar::ScalarValue;
 
@@ -207,9 +209,145 @@ impl Accumulator for DistinctCountAccumulator {
     }
 }
 
+/// Expression for a SUM(DISTINCT) aggregation.
+#[derive(Debug)]
+pub struct DistinctSum {
+    /// Column name
+    name: String,
+    /// The DataType for the final sum
+    data_type: DataType,
+    /// The DataType used to hold the state of the input
+    state_data_type: DataType,
+    /// The input argument
+    expr: Arc<dyn PhysicalExpr>,
+    /// Whether integer sums error on overflow rather than wrapping around
+    fail_on_overflow: bool,
+}
+
+impl DistinctSum {
+    /// Create a new SUM(DISTINCT) aggregate function.
+    pub fn new(
+        input_data_type: DataType,
+        expr: Arc<dyn PhysicalExpr>,
+        name: String,
+        data_type: DataType,
+    ) -> Self {
+        Self {
+            name,
+            data_type,
+            state_data_type: state_type(input_data_type),
+            expr,
+            fail_on_overflow: false,
+        }
+    }
+
+    /// Makes integer sums error on overflow if `fail_on_overflow`, as in the ANSI
+    /// mode, rather than wrap around in two's complement
+    pub fn with_fail_on_overflow(mut self, fail_on_overflow: bool) -> Self {
+        self.fail_on_overflow = fail_on_overflow;
+        self
+    }
+
+    /// Whether integer sums error on overflow rather than wrapping around
+    pub fn fail_on_overflow(&self) -> bool {
+        self.fail_on_overflow
+    }
+}
+
+impl AggregateExpr for DistinctSum {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "sum distinct"),
+            DataType::List(Box::new(Field::new(
+                "item",
+                self.state_data_type.clone(),
+                true,
+            ))),
+            false,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(DistinctSumAccumulator {
+            values: HashSet::default(),
+            state_data_type: self.state_data_type.clone(),
+            sum_data_type: self.data_type.clone(),
+            fail_on_overflow: self.fail_on_overflow,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Keeps the distinct values until the aggregation is evaluated, so that
+/// the states of partial aggregations can be merged after a shuffle
+#[derive(Debug)]
+struct DistinctSumAccumulator {
+    values: HashSet<ScalarValue, RandomState>,
+    state_data_type: DataType,
+    sum_data_type: DataType,
+    fail_on_overflow: bool,
+}
+
+impl Accumulator for DistinctSumAccumulator {
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        // NULLs are not included in the sum
+        if !values[0].is_null() {
+            self.values.insert(values[0].clone());
+        }
+
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        match &states[0] {
+            ScalarValue::List(Some(values), _) => values
+                .iter()
+                .try_for_each(|value| self.update(std::slice::from_ref(value))),
+            ScalarValue::List(None, _) => Ok(()),
+            state => Err(DataFusionError::Internal(format!(
+                "Unexpected accumulator state {:?}",
+                state
+            ))),
+        }
+    }
+
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let values = self.values.iter().cloned().collect::<Vec<_>>();
+        Ok(vec![ScalarValue::List(
+            Some(Box::new(values)),
+            Box::new(self.state_data_type.clone()),
+        )])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        self.values
+            .iter()
+            .try_fold(ScalarValue::try_from(&self.sum_data_type)?, |sum, value| {
+                sum_scalars(&sum, value, self.fail_on_overflow)
+            })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::physical_plan::expressions::lit;
 
     use arrow::array::{
         ArrayRef, BooleanArray, Float32Array, Float64Array, Int16Array, Int32Array,
@@ -705,4 +843,38 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn sum_distinct_update_and_merge() -> Result<()> {
+        let agg = DistinctSum::new(
+            DataType::Int32,
+            lit(ScalarValue::Int32(None)),
+            String::from("__col_name__"),
+            DataType::Int64,
+        );
+
+        let mut partial1 = agg.create_accumulator()?;
+        partial1.update_batch(&[Arc::new(Int32Array::from(vec![
+            Some(1),
+            Some(2),
+            None,
+            Some(2),
+        ])) as ArrayRef])?;
+        assert_eq!(partial1.evaluate()?, ScalarValue::Int64(Some(3)));
+
+        let mut partial2 = agg.create_accumulator()?;
+        partial2
+            .update_batch(&[Arc::new(Int32Array::from(vec![2, 3, 3])) as ArrayRef])?;
+
+        let mut merged = agg.create_accumulator()?;
+        merged.merge(&partial1.state()?)?;
+        merged.merge(&partial2.state()?)?;
+        assert_eq!(merged.evaluate()?, ScalarValue::Int64(Some(6)));
+
+        // an empty sum is NULL
+        let empty = agg.create_accumulator()?;
+        assert_eq!(empty.evaluate()?, ScalarValue::Int64(None));
+
+        Ok(())
+    }
 }
@@ -22,7 +22,10 @@ use std::sync::Arc;
 
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{
-    memory::MemoryStream, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+    memory::MemoryStream,
+    metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
+    stream::ObservedStream,
+    DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
 };
 use arrow::array::NullArray;
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
@@ -39,6 +42,8 @@ pub struct EmptyExec {
     produce_one_row: bool,
     /// The schema for the produced row
     schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl EmptyExec {
@@ -47,6 +52,7 @@ impl EmptyExec {
         EmptyExec {
             produce_one_row,
             schema,
+            metrics: ExecutionPlanMetricsSet::new(),
         }
     }
 
@@ -118,11 +124,19 @@ impl ExecutionPlan for EmptyExec {
             )));
         }
 
-        Ok(Box::pin(MemoryStream::try_new(
+        let stream = Box::pin(MemoryStream::try_new(
             self.data()?,
             self.schema.clone(),
             None,
-        )?))
+        )?);
+        Ok(Box::pin(ObservedStream::new_with_compute(
+            stream,
+            BaselineMetrics::new(&self.metrics, partition),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
     }
 
     fn fmt_as(
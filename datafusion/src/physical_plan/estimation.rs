@@ -0,0 +1,269 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Estimation of the number of rows and bytes produced by the operators of a
+//! physical plan, shown by `EXPLAIN (COSTS)` to sanity-check plans before
+//! running them.
+//!
+//! The statistics of an operator are used when they know its number of rows.
+//! Otherwise the number of rows is derived from the estimates of its inputs,
+//! using fixed selectivities for the predicates of filters and joins, and the
+//! number of bytes from the width of the rows of its schema.
+
+use std::sync::Arc;
+
+use super::cross_join::CrossJoinExec;
+use super::expressions::{
+    BinaryExpr, InListExpr, IsNotNullExpr, IsNullExpr, Literal, NotExpr,
+};
+use super::filter::FilterExec;
+use super::hash_aggregate::{AggregateMode, HashAggregateExec};
+use super::hash_join::HashJoinExec;
+use super::limit::{GlobalLimitExec, LocalLimitExec};
+use super::row_format::fixed_width;
+use super::sample::SampleExec;
+use super::{ExecutionPlan, PhysicalExpr, Statistics};
+use crate::logical_plan::{JoinType, Operator};
+use crate::scalar::ScalarValue;
+use arrow::datatypes::Schema;
+
+/// Selectivity of an equality predicate, e.g. `a = 5`
+const EQUALITY_SELECTIVITY: f64 = 0.1;
+/// Selectivity of a range predicate, e.g. `a < 5`
+const RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+/// Selectivity of the predicates without a better estimate, e.g. `a LIKE 'b%'`,
+/// and of the semi and anti joins
+const DEFAULT_SELECTIVITY: f64 = 0.5;
+/// Width assumed for the values of variable width types, e.g. strings
+const VARIABLE_WIDTH: usize = 32;
+
+/// Returns the statistics of `plan`, where the number of rows and bytes are
+/// estimated when the operator does not know them. The estimated statistics are
+/// never exact.
+pub fn estimate_statistics(plan: &dyn ExecutionPlan) -> Statistics {
+    let statistics = plan.statistics();
+    let (num_rows, is_exact) = match statistics.num_rows {
+        Some(num_rows) => (Some(num_rows), statistics.is_exact),
+        None => (estimate_num_rows(plan), false),
+    };
+    let (total_byte_size, is_exact) = match statistics.total_byte_size {
+        Some(total_byte_size) if statistics.num_rows.is_some() => {
+            (Some(total_byte_size), is_exact)
+        }
+        _ => (
+            num_rows.map(|num_rows| num_rows.saturating_mul(row_width(&plan.schema()))),
+            false,
+        ),
+    };
+    Statistics {
+        num_rows,
+        total_byte_size,
+        column_statistics: statistics.column_statistics,
+        is_exact,
+    }
+}
+
+/// Estimates the fraction of the rows for which `predicate` is true
+pub fn predicate_selectivity(predicate: &dyn PhysicalExpr) -> f64 {
+    let any = predicate.as_any();
+    if let Some(binary) = any.downcast_ref::<BinaryExpr>() {
+        match binary.op() {
+            Operator::And => {
+                predicate_selectivity(binary.left().as_ref())
+                    * predicate_selectivity(binary.right().as_ref())
+            }
+            Operator::Or => {
+                let left = predicate_selectivity(binary.left().as_ref());
+                let right = predicate_selectivity(binary.right().as_ref());
+                left + right - left * right
+            }
+            Operator::Eq | Operator::IsNotDistinctFrom => EQUALITY_SELECTIVITY,
+            Operator::NotEq | Operator::IsDistinctFrom => 1.0 - EQUALITY_SELECTIVITY,
+            Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq => {
+                RANGE_SELECTIVITY
+            }
+            _ => DEFAULT_SELECTIVITY,
+        }
+    } else if let Some(not) = any.downcast_ref::<NotExpr>() {
+        1.0 - predicate_selectivity(not.arg().as_ref())
+    } else if let Some(in_list) = any.downcast_ref::<InListExpr>() {
+        let selectivity = (in_list.list().len() as f64 * EQUALITY_SELECTIVITY).min(1.0);
+        if in_list.negated() {
+            1.0 - selectivity
+        } else {
+            selectivity
+        }
+    } else if any.is::<IsNullExpr>() {
+        EQUALITY_SELECTIVITY
+    } else if any.is::<IsNotNullExpr>() {
+        1.0 - EQUALITY_SELECTIVITY
+    } else if let Some(literal) = any.downcast_ref::<Literal>() {
+        match literal.value() {
+            ScalarValue::Boolean(Some(true)) => 1.0,
+            ScalarValue::Boolean(_) => 0.0,
+            _ => DEFAULT_SELECTIVITY,
+        }
+    } else {
+        DEFAULT_SELECTIVITY
+    }
+}
+
+/// Estimates the number of rows of an operator whose statistics do not know it
+fn estimate_num_rows(plan: &dyn ExecutionPlan) -> Option<usize> {
+    let any = plan.as_any();
+    if let Some(filter) = any.downcast_ref::<FilterExec>() {
+        let selectivity = predicate_selectivity(filter.predicate().as_ref());
+        input_num_rows(filter.input()).map(|num_rows| scale(num_rows, selectivity))
+    } else if let Some(sample) = any.downcast_ref::<SampleExec>() {
+        input_num_rows(sample.input()).map(|num_rows| scale(num_rows, sample.fraction()))
+    } else if let Some(aggregate) = any.downcast_ref::<HashAggregateExec>() {
+        if aggregate.group_expr().is_empty() {
+            // a single row, computed by each partition for partial aggregations
+            match aggregate.mode() {
+                AggregateMode::Partial => {
+                    Some(plan.output_partitioning().partition_count())
+                }
+                _ => Some(1),
+            }
+        } else {
+            // at most a group per input row
+            input_num_rows(aggregate.input())
+        }
+    } else if let Some(limit) = any.downcast_ref::<GlobalLimitExec>() {
+        input_num_rows(limit.input()).map(|num_rows| num_rows.min(limit.limit()))
+    } else if let Some(limit) = any.downcast_ref::<LocalLimitExec>() {
+        let partitions = plan.output_partitioning().partition_count();
+        input_num_rows(limit.input())
+            .map(|num_rows| num_rows.min(limit.limit().saturating_mul(partitions)))
+    } else if let Some(join) = any.downcast_ref::<HashJoinExec>() {
+        let left = input_num_rows(join.left())?;
+        let right = input_num_rows(join.right())?;
+        Some(match join.join_type() {
+            // each row of the larger side matches a row of the other side, as
+            // when joining a foreign key with a primary key
+            JoinType::Inner | JoinType::Left | JoinType::Right => left.max(right),
+            JoinType::Full => left.saturating_add(right),
+            JoinType::Semi | JoinType::Anti | JoinType::NullAwareAnti => {
+                scale(left, DEFAULT_SELECTIVITY)
+            }
+        })
+    } else if let Some(join) = any.downcast_ref::<CrossJoinExec>() {
+        let left = input_num_rows(join.left())?;
+        let right = input_num_rows(join.right())?;
+        Some(left.saturating_mul(right))
+    } else {
+        // the other operators return the rows of their inputs, e.g. projections,
+        // sorts and unions, while the leaves without statistics are unknown
+        let children = plan.children();
+        if children.is_empty() {
+            return None;
+        }
+        children
+            .iter()
+            .map(input_num_rows)
+            .try_fold(0usize, |total, num_rows| {
+                Some(total.saturating_add(num_rows?))
+            })
+    }
+}
+
+fn input_num_rows(input: &Arc<dyn ExecutionPlan>) -> Option<usize> {
+    estimate_statistics(input.as_ref()).num_rows
+}
+
+fn scale(num_rows: usize, selectivity: f64) -> usize {
+    (num_rows as f64 * selectivity).round() as usize
+}
+
+/// The estimated number of bytes of a row of `schema`
+fn row_width(schema: &Schema) -> usize {
+    schema
+        .fields()
+        .iter()
+        .map(|field| fixed_width(field.data_type()).unwrap_or(VARIABLE_WIDTH))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use crate::physical_plan::expressions::{binary, col, lit};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::projection::ProjectionExec;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field};
+    use arrow::record_batch::RecordBatch;
+
+    #[test]
+    fn estimate_filtered_plan() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from((0..300).collect::<Vec<_>>())),
+                Arc::new(StringArray::from(vec![Some("x"); 300])),
+            ],
+        )?;
+        let memory = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None)?);
+        let statistics = estimate_statistics(memory.as_ref());
+        assert_eq!(statistics.num_rows, Some(300));
+        assert!(statistics.total_byte_size.is_some());
+
+        // a > 10 AND (b = 'y' OR b = 'z')
+        let predicate = binary(
+            binary(
+                col("a", &schema)?,
+                Operator::Gt,
+                lit(ScalarValue::from(10i32)),
+                &schema,
+            )?,
+            Operator::And,
+            binary(
+                binary(
+                    col("b", &schema)?,
+                    Operator::Eq,
+                    lit(ScalarValue::from("y")),
+                    &schema,
+                )?,
+                Operator::Or,
+                binary(
+                    col("b", &schema)?,
+                    Operator::Eq,
+                    lit(ScalarValue::from("z")),
+                    &schema,
+                )?,
+                &schema,
+            )?,
+            &schema,
+        )?;
+        let selectivity = predicate_selectivity(predicate.as_ref());
+        assert!((selectivity - (1.0 / 3.0) * 0.19).abs() < 1e-9);
+
+        let filter = Arc::new(FilterExec::try_new(predicate, memory)?);
+        let projection =
+            ProjectionExec::try_new(vec![(col("a", &schema)?, "a".to_owned())], filter)?;
+        let statistics = estimate_statistics(&projection);
+        assert_eq!(statistics.num_rows, Some(19));
+        assert_eq!(statistics.total_byte_size, Some(19 * 4));
+        assert!(!statistics.is_exact);
+        Ok(())
+    }
+}
@@ -64,6 +64,11 @@ impl ExplainExec {
     pub fn stringified_plans(&self) -> &[StringifiedPlan] {
         &self.stringified_plans
     }
+
+    /// Whether all the plans are printed, rather than only the final ones
+    pub fn verbose(&self) -> bool {
+        self.verbose
+    }
 }
 
 #[async_trait]
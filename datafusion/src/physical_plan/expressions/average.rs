@@ -31,7 +31,7 @@ use arrow::{
     datatypes::Field,
 };
 
-use super::{format_state_name, sum};
+use super::{count, format_state_name, sum};
 
 /// AVG aggregate expression
 #[derive(Debug)]
@@ -131,6 +131,16 @@ impl AggregateExpr for Avg {
         vec![self.expr.clone()]
     }
 
+    fn row_states(&self, values: &[ArrayRef]) -> Result<Option<Vec<ArrayRef>>> {
+        if !compute::can_cast_types(values[0].data_type(), &DataType::Float64) {
+            return Ok(None);
+        }
+        Ok(Some(vec![
+            count::count_rows(&values[0]),
+            compute::cast(&values[0], &DataType::Float64)?,
+        ]))
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
@@ -163,7 +173,7 @@ impl Accumulator for AvgAccumulator {
         let values = &values[0];
 
         self.count += (!values.is_null()) as u64;
-        self.sum = sum::sum(&self.sum, values)?;
+        self.sum = sum::sum(&self.sum, values, false)?;
 
         Ok(())
     }
@@ -172,7 +182,7 @@ impl Accumulator for AvgAccumulator {
         let values = &values[0];
 
         self.count += (values.len() - values.data().null_count()) as u64;
-        self.sum = sum::sum(&self.sum, &sum::sum_batch(values)?)?;
+        self.sum = sum::sum(&self.sum, &sum::sum_batch(values, false)?, false)?;
         Ok(())
     }
 
@@ -186,7 +196,7 @@ impl Accumulator for AvgAccumulator {
         };
 
         // sums are summed
-        self.sum = sum::sum(&self.sum, &states[1])?;
+        self.sum = sum::sum(&self.sum, &states[1], false)?;
         Ok(())
     }
 
@@ -196,7 +206,7 @@ impl Accumulator for AvgAccumulator {
         self.count += compute::sum(counts).unwrap_or(0);
 
         // sums are summed
-        self.sum = sum::sum(&self.sum, &sum::sum_batch(&states[1])?)?;
+        self.sum = sum::sum(&self.sum, &sum::sum_batch(&states[1], false)?, false)?;
         Ok(())
     }
 
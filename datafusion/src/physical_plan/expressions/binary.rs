@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::cmp::Ordering;
 use std::{any::Any, sync::Arc};
 
 use arrow::array::TimestampMillisecondArray;
@@ -41,16 +42,21 @@ use arrow::compute::kernels::comparison::{
     regexp_is_match_utf8_scalar,
 };
 use arrow::datatypes::{ArrowNumericType, DataType, Schema, TimeUnit};
+use arrow::error::ArrowError;
 use arrow::record_batch::RecordBatch;
 
 use crate::error::{DataFusionError, Result};
 use crate::logical_plan::Operator;
-use crate::physical_plan::expressions::try_cast;
+use crate::physical_plan::dictionary::{
+    evaluate_on_values, is_dictionary, unpack_dictionary,
+};
+use crate::physical_plan::expressions::{try_cast, DateTimeIntervalExpr};
 use crate::physical_plan::{ColumnarValue, PhysicalExpr};
 use crate::scalar::ScalarValue;
 
 use super::coercion::{
-    eq_coercion, like_coercion, numerical_coercion, order_coercion, string_coercion,
+    eq_coercion, is_binary, like_coercion, numerical_coercion, order_coercion,
+    string_coercion, temporal_arithmetic_coercion,
 };
 
 // Simple (low performance) kernels until optimized kernels are added to arrow
@@ -85,6 +91,8 @@ pub struct BinaryExpr {
     left: Arc<dyn PhysicalExpr>,
     op: Operator,
     right: Arc<dyn PhysicalExpr>,
+    /// whether integer arithmetic errors on overflow rather than wrapping around
+    fail_on_overflow: bool,
 }
 
 impl BinaryExpr {
@@ -94,7 +102,24 @@ impl BinaryExpr {
         op: Operator,
         right: Arc<dyn PhysicalExpr>,
     ) -> Self {
-        Self { left, op, right }
+        Self {
+            left,
+            op,
+            right,
+            fail_on_overflow: false,
+        }
+    }
+
+    /// Makes integer arithmetic error on overflow if `fail_on_overflow`, as in
+    /// the ANSI mode, rather than wrap around in two's complement
+    pub fn with_fail_on_overflow(mut self, fail_on_overflow: bool) -> Self {
+        self.fail_on_overflow = fail_on_overflow;
+        self
+    }
+
+    /// Whether integer arithmetic errors on overflow rather than wrapping around
+    pub fn fail_on_overflow(&self) -> bool {
+        self.fail_on_overflow
     }
 
     /// Get the left side of the binary expression
@@ -141,7 +166,9 @@ macro_rules! compute_utf8_op_scalar {
             .as_any()
             .downcast_ref::<$DT>()
             .expect("compute_op failed to downcast array");
-        if let ScalarValue::Utf8(Some(string_value)) = $RIGHT {
+        if let ScalarValue::Utf8(Some(string_value))
+        | ScalarValue::LargeUtf8(Some(string_value)) = $RIGHT
+        {
             Ok(Arc::new(paste::expr! {[<$OP _utf8_scalar>]}(
                 &ll,
                 &string_value,
@@ -242,6 +269,9 @@ macro_rules! binary_string_array_op_scalar {
     ($LEFT:expr, $RIGHT:expr, $OP:ident) => {{
         let result: Result<Arc<dyn Array>> = match $LEFT.data_type() {
             DataType::Utf8 => compute_utf8_op_scalar!($LEFT, $RIGHT, $OP, StringArray),
+            DataType::LargeUtf8 => {
+                compute_utf8_op_scalar!($LEFT, $RIGHT, $OP, LargeStringArray)
+            }
             other => Err(DataFusionError::Internal(format!(
                 "Data type {:?} not supported for scalar operation '{}' on string array",
                 other, stringify!($OP)
@@ -255,6 +285,7 @@ macro_rules! binary_string_array_op {
     ($LEFT:expr, $RIGHT:expr, $OP:ident) => {{
         match $LEFT.data_type() {
             DataType::Utf8 => compute_utf8_op!($LEFT, $RIGHT, $OP, StringArray),
+            DataType::LargeUtf8 => compute_utf8_op!($LEFT, $RIGHT, $OP, LargeStringArray),
             other => Err(DataFusionError::Internal(format!(
                 "Data type {:?} not supported for binary operation '{}' on string arrays",
                 other, stringify!($OP)
@@ -329,16 +360,19 @@ macro_rules! binary_array_op_scalar {
             DataType::Float32 => compute_op_scalar!($LEFT, $RIGHT, $OP, Float32Array),
             DataType::Float64 => compute_op_scalar!($LEFT, $RIGHT, $OP, Float64Array),
             DataType::Utf8 => compute_utf8_op_scalar!($LEFT, $RIGHT, $OP, StringArray),
-            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+            DataType::LargeUtf8 => {
+                compute_utf8_op_scalar!($LEFT, $RIGHT, $OP, LargeStringArray)
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
                 compute_op_scalar!($LEFT, $RIGHT, $OP, TimestampNanosecondArray)
             }
-            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
                 compute_op_scalar!($LEFT, $RIGHT, $OP, TimestampMicrosecondArray)
             }
-            DataType::Timestamp(TimeUnit::Millisecond, None) => {
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
                 compute_op_scalar!($LEFT, $RIGHT, $OP, TimestampMillisecondArray)
             }
-            DataType::Timestamp(TimeUnit::Second, None) => {
+            DataType::Timestamp(TimeUnit::Second, _) => {
                 compute_op_scalar!($LEFT, $RIGHT, $OP, TimestampSecondArray)
             }
             DataType::Date32 => {
@@ -374,16 +408,17 @@ macro_rules! binary_array_op {
             DataType::Float32 => compute_op!($LEFT, $RIGHT, $OP, Float32Array),
             DataType::Float64 => compute_op!($LEFT, $RIGHT, $OP, Float64Array),
             DataType::Utf8 => compute_utf8_op!($LEFT, $RIGHT, $OP, StringArray),
-            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+            DataType::LargeUtf8 => compute_utf8_op!($LEFT, $RIGHT, $OP, LargeStringArray),
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
                 compute_op!($LEFT, $RIGHT, $OP, TimestampNanosecondArray)
             }
-            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
                 compute_op!($LEFT, $RIGHT, $OP, TimestampMicrosecondArray)
             }
-            DataType::Timestamp(TimeUnit::Millisecond, None) => {
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
                 compute_op!($LEFT, $RIGHT, $OP, TimestampMillisecondArray)
             }
-            DataType::Timestamp(TimeUnit::Second, None) => {
+            DataType::Timestamp(TimeUnit::Second, _) => {
                 compute_op!($LEFT, $RIGHT, $OP, TimestampSecondArray)
             }
             DataType::Date32 => {
@@ -484,7 +519,9 @@ macro_rules! compute_utf8_flag_op_scalar {
             .downcast_ref::<$ARRAYTYPE>()
             .expect("compute_utf8_flag_op_scalar failed to downcast array");
 
-        if let ScalarValue::Utf8(Some(string_value)) = $RIGHT {
+        if let ScalarValue::Utf8(Some(string_value))
+        | ScalarValue::LargeUtf8(Some(string_value)) = $RIGHT
+        {
             let flag = if $FLAG { Some("i") } else { None };
             let mut array =
                 paste::expr! {[<$OP _utf8_scalar>]}(&ll, &string_value, flag)?;
@@ -525,11 +562,11 @@ fn common_binary_type(
         }
         // for math expressions, the final value of the coercion is also the return type
         // because coercion favours higher information types
-        Operator::Plus
-        | Operator::Minus
-        | Operator::Modulo
-        | Operator::Divide
-        | Operator::Multiply => numerical_coercion(lhs_type, rhs_type),
+        Operator::Plus | Operator::Minus => numerical_coercion(lhs_type, rhs_type)
+            .or_else(|| temporal_arithmetic_coercion(lhs_type, op, rhs_type)),
+        Operator::Modulo | Operator::Divide | Operator::Multiply => {
+            numerical_coercion(lhs_type, rhs_type)
+        }
         Operator::RegexMatch
         | Operator::RegexIMatch
         | Operator::RegexNotMatch
@@ -611,10 +648,42 @@ impl PhysicalExpr for BinaryExpr {
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let left_value = self.left.evaluate(batch)?;
-        let right_value = self.right.evaluate(batch)?;
+        let right_value = match self.short_circuit_selection(&left_value) {
+            Some(selection) => self.right.evaluate_selection(batch, &selection)?,
+            None => self.right.evaluate(batch)?,
+        };
+        if is_dictionary(&left_value.data_type())
+            || is_dictionary(&right_value.data_type())
+        {
+            return self
+                .evaluate_dictionary(left_value, right_value, batch.num_rows())
+                .map(|a| ColumnarValue::Array(a));
+        }
+        self.evaluate_values(left_value, right_value, batch.num_rows())
+            .map(|a| ColumnarValue::Array(a))
+    }
+}
+
+impl BinaryExpr {
+    /// Evaluates the expression on the values of its inputs, which are not
+    /// dictionaries
+    fn evaluate_values(
+        &self,
+        left_value: ColumnarValue,
+        right_value: ColumnarValue,
+        num_rows: usize,
+    ) -> Result<ArrayRef> {
         let left_data_type = left_value.data_type();
         let right_data_type = right_value.data_type();
 
+        if is_binary(&left_data_type) && is_binary(&right_data_type) {
+            let (left, right) = (
+                left_value.into_array(num_rows),
+                right_value.into_array(num_rows),
+            );
+            return compare_binary(&left, &right, &self.op);
+        }
+
         if left_data_type != right_data_type {
             return Err(DataFusionError::Internal(format!(
                 "Cannot evaluate binary expression {:?} with types {:?} and {:?}",
@@ -636,20 +705,96 @@ impl PhysicalExpr for BinaryExpr {
         };
 
         if let Some(result) = scalar_result {
-            return result.map(|a| ColumnarValue::Array(a));
+            return result;
         }
 
         // if both arrays or both literals - extract arrays and continue execution
         let (left, right) = (
-            left_value.into_array(batch.num_rows()),
-            right_value.into_array(batch.num_rows()),
+            left_value.into_array(num_rows),
+            right_value.into_array(num_rows),
         );
         self.evaluate_with_resolved_args(left, &left_data_type, right, &right_data_type)
-            .map(|a| ColumnarValue::Array(a))
     }
-}
 
-impl BinaryExpr {
+    /// Evaluates the expression when one of its inputs is a dictionary array. The
+    /// comparison of a dictionary array with a literal is evaluated once for each
+    /// value of the dictionary, otherwise the dictionaries are unpacked.
+    fn evaluate_dictionary(
+        &self,
+        left_value: ColumnarValue,
+        right_value: ColumnarValue,
+        num_rows: usize,
+    ) -> Result<ArrayRef> {
+        if is_comparison_operator(&self.op) {
+            if let (ColumnarValue::Array(array), ColumnarValue::Scalar(_)) =
+                (&left_value, &right_value)
+            {
+                if is_dictionary(array.data_type()) {
+                    return evaluate_on_values(array.as_ref(), |values| {
+                        let num_values = values.len();
+                        self.evaluate_values(
+                            ColumnarValue::Array(values),
+                            right_value.clone(),
+                            num_values,
+                        )
+                    });
+                }
+            }
+            if let (ColumnarValue::Scalar(_), ColumnarValue::Array(array)) =
+                (&left_value, &right_value)
+            {
+                if is_dictionary(array.data_type()) {
+                    return evaluate_on_values(array.as_ref(), |values| {
+                        let num_values = values.len();
+                        self.evaluate_values(
+                            left_value.clone(),
+                            ColumnarValue::Array(values),
+                            num_values,
+                        )
+                    });
+                }
+            }
+        }
+
+        let unpack = |value: ColumnarValue| -> Result<ColumnarValue> {
+            Ok(match value {
+                ColumnarValue::Array(array) if is_dictionary(array.data_type()) => {
+                    ColumnarValue::Array(unpack_dictionary(array.as_ref())?)
+                }
+                value => value,
+            })
+        };
+        self.evaluate_values(unpack(left_value)?, unpack(right_value)?, num_rows)
+    }
+    /// Returns the rows for which the right side of an `AND` or an `OR` has
+    /// to be evaluated, if the left side already decides the result of some
+    /// rows: false for `AND` and true for `OR`, even when the right side is
+    /// null.
+    fn short_circuit_selection(
+        &self,
+        left_value: &ColumnarValue,
+    ) -> Option<BooleanArray> {
+        let decided = match self.op {
+            Operator::And => false,
+            Operator::Or => true,
+            _ => return None,
+        };
+        let left = match left_value {
+            ColumnarValue::Array(array) => {
+                array.as_any().downcast_ref::<BooleanArray>()?
+            }
+            ColumnarValue::Scalar(_) => return None,
+        };
+        if left.iter().all(|v| v != Some(decided)) {
+            return None;
+        }
+        Some(
+            left.iter()
+                .map(|v| Some(v != Some(decided)))
+                .collect::<BooleanArray>(),
+        )
+    }
+
     /// Evaluate the expression of the left input is an array and
     /// right is literal - use scalar operations
     fn evaluate_array_scalar(
@@ -676,10 +821,11 @@ impl BinaryExpr {
             Operator::NotLike => {
                 binary_string_array_op_scalar!(array, scalar.clone(), nlike)
             }
-            Operator::Divide => {
+            // the integer kernels handle overflows and divisions by zero
+            Operator::Divide if !is_integer(array.data_type()) => {
                 binary_primitive_array_op_scalar!(array, scalar.clone(), divide)
             }
-            Operator::Modulo => {
+            Operator::Modulo if !is_integer(array.data_type()) => {
                 binary_primitive_array_op_scalar!(array, scalar.clone(), modulus)
             }
             Operator::RegexMatch => binary_string_array_flag_op_scalar!(
@@ -763,6 +909,15 @@ impl BinaryExpr {
             Operator::IsNotDistinctFrom => {
                 binary_array_op!(left, right, is_not_distinct_from)
             }
+            Operator::Plus
+            | Operator::Minus
+            | Operator::Multiply
+            | Operator::Divide
+            | Operator::Modulo
+                if is_integer(left_data_type) =>
+            {
+                integer_arithmetic(&left, &right, &self.op, self.fail_on_overflow)
+            }
             Operator::Plus => binary_primitive_array_op!(left, right, add),
             Operator::Minus => binary_primitive_array_op!(left, right, subtract),
             Operator::Multiply => binary_primitive_array_op!(left, right, multiply),
@@ -884,8 +1039,236 @@ pub fn binary(
     rhs: Arc<dyn PhysicalExpr>,
     input_schema: &Schema,
 ) -> Result<Arc<dyn PhysicalExpr>> {
+    binary_with_overflow_checks(lhs, op, rhs, input_schema, false)
+}
+
+/// Create a binary expression like [`binary`], whose integer arithmetic errors on
+/// overflow if `fail_on_overflow`, as in the ANSI mode, rather than wrapping around
+pub fn binary_with_overflow_checks(
+    lhs: Arc<dyn PhysicalExpr>,
+    op: Operator,
+    rhs: Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+    fail_on_overflow: bool,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let lhs_type = lhs.data_type(input_schema)?;
+    let rhs_type = rhs.data_type(input_schema)?;
+    if matches!(lhs_type, DataType::Interval(_))
+        || matches!(rhs_type, DataType::Interval(_))
+    {
+        // intervals are not casted, but evaluated by their own kernels
+        binary_operator_data_type(&lhs_type, &op, &rhs_type)?;
+        return Ok(Arc::new(DateTimeIntervalExpr::new(lhs, op, rhs)));
+    }
+    if is_binary(&lhs_type) && is_binary(&rhs_type) {
+        // the binary types are compared by their bytes, without casts, as
+        // there are no cast kernels between them
+        binary_operator_data_type(&lhs_type, &op, &rhs_type)?;
+        return Ok(Arc::new(BinaryExpr::new(lhs, op, rhs)));
+    }
+    if is_comparison_operator(&op)
+        && (is_dictionary(&lhs_type) || is_dictionary(&rhs_type))
+    {
+        // dictionaries are compared on their values rather than unpacked, so
+        // only the other side is casted to the type of their values
+        let value_type = common_binary_type(&lhs_type, &op, &rhs_type)?;
+        return Ok(Arc::new(BinaryExpr::new(
+            dictionary_cast(lhs, &value_type, input_schema)?,
+            op,
+            dictionary_cast(rhs, &value_type, input_schema)?,
+        )));
+    }
     let (l, r) = binary_cast(lhs, &op, rhs, input_schema)?;
-    Ok(Arc::new(BinaryExpr::new(l, op, r)))
+    Ok(Arc::new(
+        BinaryExpr::new(l, op, r).with_fail_on_overflow(fail_on_overflow),
+    ))
+}
+
+/// Returns true if `data_type` is an integer type
+fn is_integer(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+    )
+}
+
+/// Applies the arithmetic operator `op` to the values of two integer arrays of the
+/// same type. The results that overflow the type error if `fail_on_overflow`, and
+/// wrap around otherwise, while divisions by zero always error.
+fn integer_arithmetic(
+    left: &ArrayRef,
+    right: &ArrayRef,
+    op: &Operator,
+    fail_on_overflow: bool,
+) -> Result<ArrayRef> {
+    macro_rules! arithmetic {
+        ($ARRAY_TYPE:ident) => {{
+            let left = left.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+            let right = right.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+            let result = left
+                .iter()
+                .zip(right.iter())
+                .map(|(l, r)| {
+                    let (l, r) = match (l, r) {
+                        (Some(l), Some(r)) => (l, r),
+                        _ => return Ok(None),
+                    };
+                    if r == 0 && matches!(op, Operator::Divide | Operator::Modulo) {
+                        return Err(DataFusionError::ArrowError(
+                            ArrowError::DivideByZero,
+                        ));
+                    }
+                    let (checked, wrapped) = match op {
+                        Operator::Plus => (l.checked_add(r), l.wrapping_add(r)),
+                        Operator::Minus => (l.checked_sub(r), l.wrapping_sub(r)),
+                        Operator::Multiply => (l.checked_mul(r), l.wrapping_mul(r)),
+                        Operator::Divide => (l.checked_div(r), l.wrapping_div(r)),
+                        Operator::Modulo => (l.checked_rem(r), l.wrapping_rem(r)),
+                        other => {
+                            return Err(DataFusionError::Internal(format!(
+                                "Operator {} is not an arithmetic operator",
+                                other
+                            )))
+                        }
+                    };
+                    match checked {
+                        Some(value) => Ok(Some(value)),
+                        None if fail_on_overflow => {
+                            Err(DataFusionError::Execution(format!(
+                                "Arithmetic overflow: {} {} {} is out of the range of {:?}",
+                                l,
+                                op,
+                                r,
+                                left.data_type()
+                            )))
+                        }
+                        None => Ok(Some(wrapped)),
+                    }
+                })
+                .collect::<Result<$ARRAY_TYPE>>()?;
+            Ok(Arc::new(result) as ArrayRef)
+        }};
+    }
+
+    match left.data_type() {
+        DataType::Int8 => arithmetic!(Int8Array),
+        DataType::Int16 => arithmetic!(Int16Array),
+        DataType::Int32 => arithmetic!(Int32Array),
+        DataType::Int64 => arithmetic!(Int64Array),
+        DataType::UInt8 => arithmetic!(UInt8Array),
+        DataType::UInt16 => arithmetic!(UInt16Array),
+        DataType::UInt32 => arithmetic!(UInt32Array),
+        DataType::UInt64 => arithmetic!(UInt64Array),
+        other => Err(DataFusionError::Internal(format!(
+            "Integer arithmetic is not supported on {:?}",
+            other
+        ))),
+    }
+}
+
+/// Returns true if `op` compares its inputs, with a null result for null inputs
+fn is_comparison_operator(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+            | Operator::Like
+            | Operator::NotLike
+            | Operator::RegexMatch
+            | Operator::RegexIMatch
+            | Operator::RegexNotMatch
+            | Operator::RegexNotIMatch
+    )
+}
+
+/// Casts `expr` to `value_type`, unless it is a dictionary with values of
+/// that type
+fn dictionary_cast(
+    expr: Arc<dyn PhysicalExpr>,
+    value_type: &DataType,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    match expr.data_type(input_schema)? {
+        DataType::Dictionary(_, dict_value_type) if *dict_value_type == *value_type => {
+            Ok(expr)
+        }
+        _ => try_cast(expr, input_schema, value_type.clone()),
+    }
+}
+
+/// The values of an array of one of the binary types
+fn binary_values(array: &ArrayRef) -> Result<Vec<Option<&[u8]>>> {
+    macro_rules! values {
+        ($ARRAY_TYPE:ident) => {{
+            let array = array.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+            (0..array.len())
+                .map(|i| {
+                    if array.is_null(i) {
+                        None
+                    } else {
+                        Some(array.value(i))
+                    }
+                })
+                .collect()
+        }};
+    }
+    Ok(match array.data_type() {
+        DataType::Binary => values!(BinaryArray),
+        DataType::LargeBinary => values!(LargeBinaryArray),
+        DataType::FixedSizeBinary(_) => values!(FixedSizeBinaryArray),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Data type {:?} is not a binary type",
+                other
+            )))
+        }
+    })
+}
+
+/// Compares the values of two arrays of binary types, of possibly different
+/// widths, byte by byte
+fn compare_binary(left: &ArrayRef, right: &ArrayRef, op: &Operator) -> Result<ArrayRef> {
+    let left = binary_values(left)?;
+    let right = binary_values(right)?;
+    let values = left.iter().zip(right.iter());
+    let result: BooleanArray = match op {
+        Operator::IsDistinctFrom => values.map(|(l, r)| Some(l != r)).collect(),
+        Operator::IsNotDistinctFrom => values.map(|(l, r)| Some(l == r)).collect(),
+        _ => {
+            let matches: fn(Ordering) -> bool = match op {
+                Operator::Eq => |o| o == Ordering::Equal,
+                Operator::NotEq => |o| o != Ordering::Equal,
+                Operator::Lt => |o| o == Ordering::Less,
+                Operator::LtEq => |o| o != Ordering::Greater,
+                Operator::Gt => |o| o == Ordering::Greater,
+                Operator::GtEq => |o| o != Ordering::Less,
+                other => {
+                    return Err(DataFusionError::Internal(format!(
+                        "Operator {} is not supported on binary values",
+                        other
+                    )))
+                }
+            };
+            values
+                .map(|(l, r)| match (l, r) {
+                    (Some(l), Some(r)) => Some(matches(l.cmp(r))),
+                    _ => None,
+                })
+                .collect()
+        }
+    };
+    Ok(Arc::new(result))
 }
 
 #[cfg(test)]
@@ -907,6 +1290,66 @@ mod tests {
         Arc::new(BinaryExpr::new(l, op, r))
     }
 
+    #[test]
+    fn binary_type_comparison() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Binary, true),
+            Field::new("b", DataType::FixedSizeBinary(2), true),
+        ]);
+        let a = BinaryArray::from(vec![
+            Some(&b"ab"[..]),
+            Some(&b"b"[..]),
+            Some(&b"a"[..]),
+            None,
+        ]);
+        let b = FixedSizeBinaryArray::try_from_sparse_iter(
+            vec![Some(b"ab"), Some(b"ab"), Some(b"ab"), Some(b"ab")].into_iter(),
+        )?;
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(a), Arc::new(b)],
+        )?;
+
+        let cases = vec![
+            (
+                Operator::Eq,
+                vec![Some(true), Some(false), Some(false), None],
+            ),
+            (
+                Operator::Lt,
+                vec![Some(false), Some(false), Some(true), None],
+            ),
+            (
+                Operator::GtEq,
+                vec![Some(true), Some(true), Some(false), None],
+            ),
+            (
+                Operator::IsDistinctFrom,
+                vec![Some(false), Some(true), Some(true), Some(true)],
+            ),
+        ];
+        for (op, expected) in cases {
+            let expr = binary(col("a", &schema)?, op, col("b", &schema)?, &schema)?;
+            assert_eq!(expr.data_type(&schema)?, DataType::Boolean);
+            let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            let result = result.as_any().downcast_ref::<BooleanArray>().unwrap();
+            assert_eq!(result, &BooleanArray::from(expected));
+        }
+
+        // with a literal
+        let expr = binary(
+            col("b", &schema)?,
+            Operator::Eq,
+            lit(ScalarValue::Binary(Some(b"ab".to_vec()))),
+            &schema,
+        )?;
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert_eq!(result, &BooleanArray::from(vec![true, true, true, true]));
+
+        Ok(())
+    }
+
     #[test]
     fn binary_comparison() -> Result<()> {
         let schema = Schema::new(vec![
@@ -1252,7 +1695,7 @@ mod tests {
             StringArray::from(vec![Some("not one"), Some("two"), None, Some("four")]);
 
         let schema = Arc::new(Schema::new(vec![
-            Field::new("dict", dict_type, true),
+            Field::new("dict", dict_type.clone(), true),
             Field::new("str", string_type, true),
         ]));
 
@@ -1300,6 +1743,32 @@ mod tests {
         // verify that the result itself is correct
         assert_eq!(expected, array_to_string(&result)?);
 
+        // Test 3: dict < literal and literal = dict are evaluated on the
+        // values of the dictionary, which is not casted
+        let expression = binary(
+            col("dict", &schema)?,
+            Operator::Lt,
+            lit(ScalarValue::from("three")),
+            &schema,
+        )?;
+        let left = expression
+            .as_any()
+            .downcast_ref::<BinaryExpr>()
+            .unwrap()
+            .left();
+        assert_eq!(left.data_type(&schema)?, dict_type);
+        let result = expression.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!("true\n\nfalse\ntrue", array_to_string(&result)?);
+
+        let expression = binary(
+            lit(ScalarValue::from("four")),
+            Operator::Eq,
+            col("dict", &schema)?,
+            &schema,
+        )?;
+        let result = expression.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!("false\n\nfalse\ntrue", array_to_string(&result)?);
+
         Ok(())
     }
 
@@ -1415,6 +1884,48 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn integer_overflow() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let a = Arc::new(Int32Array::from(vec![Some(i32::MAX), None, Some(i32::MIN)]));
+        let b = Arc::new(Int32Array::from(vec![Some(1), Some(1), Some(-1)]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![a, b])?;
+
+        let cases = vec![
+            (Operator::Plus, vec![Some(i32::MIN), None, Some(i32::MAX)]),
+            (
+                Operator::Multiply,
+                vec![Some(i32::MAX), None, Some(i32::MIN)],
+            ),
+            (Operator::Divide, vec![Some(i32::MAX), None, Some(i32::MIN)]),
+        ];
+        for (op, expected) in cases {
+            let expr = BinaryExpr::new(col("a", &schema)?, op, col("b", &schema)?);
+            let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            assert_eq!(result.as_ref(), &Int32Array::from(expected));
+
+            // the same arithmetic errors in the ANSI mode
+            let expr = BinaryExpr::new(col("a", &schema)?, op, col("b", &schema)?)
+                .with_fail_on_overflow(true);
+            let result = expr.evaluate(&batch);
+            assert!(
+                matches!(result, Err(DataFusionError::Execution(_))),
+                "{:?}",
+                result
+            );
+        }
+
+        // dividing by zero errors whether overflows are checked or not
+        let zero = lit(ScalarValue::Int32(Some(0)));
+        let expr = BinaryExpr::new(col("a", &schema)?, Operator::Modulo, zero);
+        assert!(expr.evaluate(&batch).is_err());
+
+        Ok(())
+    }
+
     fn apply_arithmetic<T: ArrowNumericType>(
         schema: SchemaRef,
         data: Vec<ArrayRef>,
@@ -1481,6 +1992,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn and_or_short_circuit() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+        let a = Int32Array::from(vec![Some(2), Some(0), None, Some(20)]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(a)])?;
+
+        // 100 / a would fail for the row where a is 0, if it was evaluated
+        let ratio = binary_simple(
+            lit(ScalarValue::Int32(Some(100))),
+            Operator::Divide,
+            col("a", &schema)?,
+        );
+        let ratio_gt =
+            binary_simple(ratio, Operator::Gt, lit(ScalarValue::Int32(Some(10))));
+        let zero = lit(ScalarValue::Int32(Some(0)));
+
+        let non_zero = binary_simple(col("a", &schema)?, Operator::NotEq, zero.clone());
+        let and = binary_simple(non_zero, Operator::And, ratio_gt.clone());
+        let result = and.evaluate(&batch)?.into_array(batch.num_rows());
+        let expected =
+            BooleanArray::from(vec![Some(true), Some(false), None, Some(false)]);
+        assert_eq!(result.as_ref(), &expected);
+
+        let is_zero = binary_simple(col("a", &schema)?, Operator::Eq, zero);
+        let or = binary_simple(is_zero, Operator::Or, ratio_gt);
+        let result = or.evaluate(&batch)?.into_array(batch.num_rows());
+        let expected =
+            BooleanArray::from(vec![Some(true), Some(true), None, Some(false)]);
+        assert_eq!(result.as_ref(), &expected);
+
+        Ok(())
+    }
+
     #[test]
     fn and_with_nulls_op() -> Result<()> {
         let schema = Schema::new(vec![
@@ -227,6 +227,13 @@ fn if_then_else(
             true_values,
             false_values
         ),
+        DataType::LargeUtf8 => if_then_else!(
+            array::LargeStringBuilder,
+            array::LargeStringArray,
+            bools,
+            true_values,
+            false_values
+        ),
         DataType::Boolean => if_then_else!(
             array::BooleanBuilder,
             array::BooleanArray,
@@ -298,6 +305,9 @@ fn array_equals(
         DataType::Utf8 => {
             array_equals!(array::StringArray, when_value, base_value, eq_utf8)
         }
+        DataType::LargeUtf8 => {
+            array_equals!(array::LargeStringArray, when_value, base_value, eq_utf8)
+        }
         other => Err(DataFusionError::Execution(format!(
             "CASE does not support '{:?}'",
             other
@@ -17,7 +17,9 @@
 
 //! Coercion rules used to coerce types to match existing expressions' implementations
 
-use arrow::datatypes::DataType;
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Operator;
+use arrow::datatypes::{DataType, IntervalUnit, TimeUnit};
 
 /// Determine if a DataType is signed numeric or not
 pub fn is_signed_numeric(dt: &DataType) -> bool {
@@ -55,11 +57,10 @@ fn dictionary_value_coercion(
 /// Coercion rules for Dictionaries: the type that both lhs and rhs
 /// can be casted to for the purpose of a computation.
 ///
-/// It would likely be preferable to cast primitive values to
-/// dictionaries, and thus avoid unpacking dictionary as well as doing
-/// faster comparisons. However, the arrow compute kernels (e.g. eq)
-/// don't have DictionaryArray support yet, so fall back to unpacking
-/// the dictionaries
+/// This is the type of the values of the dictionaries. Comparisons do
+/// not cast dictionaries with values of this type, but evaluate them on
+/// their values, so that only the other side is casted (see
+/// [`binary`](super::binary)).
 pub fn dictionary_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
     match (lhs_type, rhs_type) {
         (
@@ -89,6 +90,26 @@ pub fn string_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataT
     }
 }
 
+/// Coercion rules for the binary types, which are compared by their bytes
+/// whatever their widths, without casting them to a common type
+pub fn binary_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (LargeBinary, Binary | LargeBinary | FixedSizeBinary(_))
+        | (Binary | FixedSizeBinary(_), LargeBinary) => Some(LargeBinary),
+        (Binary | FixedSizeBinary(_), Binary | FixedSizeBinary(_)) => Some(Binary),
+        _ => None,
+    }
+}
+
+/// Returns true if `data_type` is one of the binary types
+pub fn is_binary(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_)
+    )
+}
+
 /// coercion rules for like operations.
 /// This is a union of string coercion rules and dictionary coercion rules
 pub fn like_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
@@ -109,6 +130,182 @@ pub fn temporal_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<Dat
     }
 }
 
+/// Coercion rules for timestamps of different units or timezones: the values of
+/// timestamps are instants since the UNIX epoch in UTC whatever their timezone,
+/// so they are compared in the finer of both units, in the timezone of the lhs,
+/// or of the rhs if the lhs has none
+pub fn timestamp_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (Timestamp(lhs_unit, lhs_tz), Timestamp(rhs_unit, rhs_tz)) => {
+            let unit = if unit_rank(lhs_unit) >= unit_rank(rhs_unit) {
+                lhs_unit
+            } else {
+                rhs_unit
+            };
+            Some(Timestamp(
+                unit.clone(),
+                lhs_tz.clone().or_else(|| rhs_tz.clone()),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Rank of a time unit, from the coarsest to the finest
+fn unit_rank(unit: &TimeUnit) -> u8 {
+    match unit {
+        TimeUnit::Second => 0,
+        TimeUnit::Millisecond => 1,
+        TimeUnit::Microsecond => 2,
+        TimeUnit::Nanosecond => 3,
+    }
+}
+
+/// Coercion rules between different temporal types: dates are widened to
+/// timestamps and `Date32` to `Date64`
+fn temporal_widening_coercion(
+    lhs_type: &DataType,
+    rhs_type: &DataType,
+) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (Date32, Date64) | (Date64, Date32) => Some(Date64),
+        (Date32 | Date64, Timestamp(unit, tz))
+        | (Timestamp(unit, tz), Date32 | Date64) => {
+            Some(Timestamp(unit.clone(), tz.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Coercion rules for intervals: intervals of different units are compared
+/// and added as `MonthDayNano` intervals
+pub fn interval_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (Interval(lhs_unit), Interval(rhs_unit)) if lhs_unit == rhs_unit => {
+            Some(lhs_type.clone())
+        }
+        (Interval(_), Interval(_)) => Some(Interval(IntervalUnit::MonthDayNano)),
+        _ => None,
+    }
+}
+
+/// Coercion rules for the arithmetic of dates and timestamps with intervals:
+/// the type of adding an interval to, or subtracting it from, a date or
+/// timestamp, or of adding and subtracting intervals
+pub fn temporal_arithmetic_coercion(
+    lhs_type: &DataType,
+    op: &Operator,
+    rhs_type: &DataType,
+) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, op, rhs_type) {
+        (
+            Date32 | Date64 | Timestamp(_, _),
+            Operator::Plus | Operator::Minus,
+            Interval(_),
+        ) => Some(lhs_type.clone()),
+        (Interval(_), Operator::Plus, Date32 | Date64 | Timestamp(_, _)) => {
+            Some(rhs_type.clone())
+        }
+        (Interval(_), Operator::Plus | Operator::Minus, Interval(_)) => {
+            interval_coercion(lhs_type, rhs_type)
+        }
+        _ => None,
+    }
+}
+
+/// Coercion rules for the implicit casts that ANSI SQL applies to comparisons
+/// of otherwise incompatible types: strings are compared to numbers as numbers
+/// and to temporal values as temporal values, and dates are compared to
+/// timestamps as timestamps.
+///
+/// These casts are not applied with strict type coercion.
+pub fn implicit_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (Utf8 | LargeUtf8, other) | (other, Utf8 | LargeUtf8)
+            if is_numeric(other)
+                || matches!(other, Date32 | Date64 | Timestamp(_, _)) =>
+        {
+            Some(other.clone())
+        }
+        _ => temporal_widening_coercion(lhs_type, rhs_type),
+    }
+}
+
+/// Coercion rules for comparisons, such as the values of an IN list: the type
+/// that both lhs and rhs can be casted to for the purpose of a comparison.
+///
+/// The implicit casts of [`implicit_coercion`] are only applied if `strict` is
+/// false.
+pub fn comparison_coercion(
+    lhs_type: &DataType,
+    rhs_type: &DataType,
+    strict: bool,
+) -> Option<DataType> {
+    if lhs_type == rhs_type {
+        return Some(lhs_type.clone());
+    }
+    let coerced = numerical_coercion(lhs_type, rhs_type)
+        .or_else(|| string_coercion(lhs_type, rhs_type))
+        .or_else(|| dictionary_coercion(lhs_type, rhs_type))
+        .or_else(|| temporal_coercion(lhs_type, rhs_type))
+        .or_else(|| timestamp_coercion(lhs_type, rhs_type))
+        .or_else(|| interval_coercion(lhs_type, rhs_type));
+    if strict {
+        coerced
+    } else {
+        coerced.or_else(|| implicit_coercion(lhs_type, rhs_type))
+    }
+}
+
+/// Coercion rules for the results of the branches of a CASE expression: the
+/// type that both lhs and rhs can be casted to without losing information.
+pub fn case_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    if lhs_type == rhs_type {
+        return Some(lhs_type.clone());
+    }
+    numerical_coercion(lhs_type, rhs_type)
+        .or_else(|| string_coercion(lhs_type, rhs_type))
+        .or_else(|| dictionary_coercion(lhs_type, rhs_type))
+        .or_else(|| timestamp_coercion(lhs_type, rhs_type))
+        .or_else(|| temporal_widening_coercion(lhs_type, rhs_type))
+}
+
+/// Returns an error if `op` compares `lhs_type` to `rhs_type` and this needs one
+/// of the implicit casts of [`implicit_coercion`], which strict type coercion
+/// does not allow.
+pub fn check_strict_coercion(
+    lhs_type: &DataType,
+    op: &Operator,
+    rhs_type: &DataType,
+) -> Result<()> {
+    let is_comparison = matches!(
+        op,
+        Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+            | Operator::IsDistinctFrom
+            | Operator::IsNotDistinctFrom
+    );
+    if is_comparison
+        && comparison_coercion(lhs_type, rhs_type, true).is_none()
+        && binary_coercion(lhs_type, rhs_type).is_none()
+    {
+        return Err(DataFusionError::Plan(format!(
+            "'{:?} {} {:?}' can't be evaluated without an implicit cast, which strict type coercion does not allow",
+            lhs_type, op, rhs_type
+        )));
+    }
+    Ok(())
+}
+
 /// Coercion rule for numerical types: The type that both lhs and rhs
 /// can be casted to for numerical calculation, while maintaining
 /// maximum precision
@@ -149,8 +346,13 @@ pub fn eq_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType>
         return Some(lhs_type.clone());
     }
     numerical_coercion(lhs_type, rhs_type)
+        .or_else(|| string_coercion(lhs_type, rhs_type))
         .or_else(|| dictionary_coercion(lhs_type, rhs_type))
         .or_else(|| temporal_coercion(lhs_type, rhs_type))
+        .or_else(|| timestamp_coercion(lhs_type, rhs_type))
+        .or_else(|| interval_coercion(lhs_type, rhs_type))
+        .or_else(|| binary_coercion(lhs_type, rhs_type))
+        .or_else(|| implicit_coercion(lhs_type, rhs_type))
 }
 
 // coercion rules that assume an ordered set, such as "less than".
@@ -165,6 +367,10 @@ pub fn order_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataTy
         .or_else(|| string_coercion(lhs_type, rhs_type))
         .or_else(|| dictionary_coercion(lhs_type, rhs_type))
         .or_else(|| temporal_coercion(lhs_type, rhs_type))
+        .or_else(|| timestamp_coercion(lhs_type, rhs_type))
+        .or_else(|| interval_coercion(lhs_type, rhs_type))
+        .or_else(|| binary_coercion(lhs_type, rhs_type))
+        .or_else(|| implicit_coercion(lhs_type, rhs_type))
 }
 
 #[cfg(test)]
@@ -192,4 +398,78 @@ mod tests {
         let rhs_type = Dictionary(Box::new(Int8), Box::new(Utf8));
         assert_eq!(dictionary_coercion(&lhs_type, &rhs_type), Some(Utf8));
     }
+
+    #[test]
+    fn test_comparison_coercion() {
+        use DataType::*;
+
+        let timestamp = Timestamp(TimeUnit::Nanosecond, None);
+        assert_eq!(comparison_coercion(&Utf8, &Int32, false), Some(Int32));
+        assert_eq!(
+            comparison_coercion(&Float64, &LargeUtf8, false),
+            Some(Float64)
+        );
+        assert_eq!(
+            comparison_coercion(&Utf8, &timestamp, false),
+            Some(timestamp.clone())
+        );
+        assert_eq!(
+            comparison_coercion(&Date32, &timestamp, false),
+            Some(timestamp.clone())
+        );
+        assert_eq!(comparison_coercion(&Boolean, &Int32, false), None);
+
+        // strict coercion does not cast implicitly
+        assert_eq!(comparison_coercion(&Utf8, &Int32, true), None);
+        assert_eq!(comparison_coercion(&Date32, &timestamp, true), None);
+        assert_eq!(comparison_coercion(&Int8, &Int32, true), Some(Int32));
+        assert_eq!(comparison_coercion(&Utf8, &Date32, true), Some(Date32));
+
+        // timestamps are compared in the finer unit, keeping a timezone
+        let utc_seconds = Timestamp(TimeUnit::Second, Some("UTC".to_owned()));
+        assert_eq!(
+            comparison_coercion(&utc_seconds, &timestamp, true),
+            Some(Timestamp(TimeUnit::Nanosecond, Some("UTC".to_owned())))
+        );
+        assert_eq!(
+            case_coercion(&timestamp, &utc_seconds),
+            Some(Timestamp(TimeUnit::Nanosecond, Some("UTC".to_owned())))
+        );
+        let paris = Timestamp(TimeUnit::Second, Some("Europe/Paris".to_owned()));
+        assert_eq!(eq_coercion(&paris, &utc_seconds), Some(paris.clone()));
+        assert!(check_strict_coercion(&paris, &Operator::Lt, &timestamp).is_ok());
+
+        // intervals of different units are compared as MonthDayNano intervals
+        let year_month = Interval(IntervalUnit::YearMonth);
+        let day_time = Interval(IntervalUnit::DayTime);
+        assert_eq!(
+            comparison_coercion(&year_month, &day_time, true),
+            Some(Interval(IntervalUnit::MonthDayNano))
+        );
+        assert_eq!(
+            temporal_arithmetic_coercion(&Date32, &Operator::Minus, &day_time),
+            Some(Date32)
+        );
+        assert_eq!(
+            temporal_arithmetic_coercion(&year_month, &Operator::Plus, &timestamp),
+            Some(timestamp.clone())
+        );
+        assert_eq!(
+            temporal_arithmetic_coercion(&year_month, &Operator::Minus, &timestamp),
+            None
+        );
+
+        assert!(check_strict_coercion(&Utf8, &Operator::Lt, &Int32).is_err());
+        assert!(check_strict_coercion(&Utf8, &Operator::Plus, &Int32).is_ok());
+    }
+
+    #[test]
+    fn test_case_coercion() {
+        use DataType::*;
+
+        assert_eq!(case_coercion(&Int32, &Float64), Some(Float64));
+        assert_eq!(case_coercion(&Utf8, &LargeUtf8), Some(LargeUtf8));
+        assert_eq!(case_coercion(&Date32, &Date64), Some(Date64));
+        assert_eq!(case_coercion(&Utf8, &Int32), None);
+    }
 }
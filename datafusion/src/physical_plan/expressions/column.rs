@@ -20,6 +20,7 @@
 use std::sync::Arc;
 
 use arrow::{
+    array::BooleanArray,
     datatypes::{DataType, Schema},
     record_batch::RecordBatch,
 };
@@ -85,6 +86,15 @@ impl PhysicalExpr for Column {
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         Ok(ColumnarValue::Array(batch.column(self.index).clone()))
     }
+
+    /// The column is returned as is, as it is cheaper than selecting its rows
+    fn evaluate_selection(
+        &self,
+        batch: &RecordBatch,
+        _selection: &BooleanArray,
+    ) -> Result<ColumnarValue> {
+        self.evaluate(batch)
+    }
 }
 
 /// Create a column expression
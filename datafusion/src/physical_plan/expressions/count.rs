@@ -26,7 +26,7 @@ use crate::scalar::ScalarValue;
 use arrow::compute;
 use arrow::datatypes::DataType;
 use arrow::{
-    array::{ArrayRef, UInt64Array},
+    array::{Array, ArrayRef, UInt64Array},
     datatypes::Field,
 };
 
@@ -88,11 +88,27 @@ impl AggregateExpr for Count {
         Ok(Box::new(CountAccumulator::new()))
     }
 
+    fn row_states(&self, values: &[ArrayRef]) -> Result<Option<Vec<ArrayRef>>> {
+        if self.data_type != DataType::UInt64 {
+            return Ok(None);
+        }
+        Ok(Some(vec![count_rows(&values[0])]))
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
 }
 
+/// The count of every row on its own: 1 for non null values, 0 otherwise
+pub(super) fn count_rows(values: &ArrayRef) -> ArrayRef {
+    Arc::new(
+        (0..values.len())
+            .map(|i| Some(values.is_valid(i) as u64))
+            .collect::<UInt64Array>(),
+    )
+}
+
 #[derive(Debug)]
 struct CountAccumulator {
     count: u64,
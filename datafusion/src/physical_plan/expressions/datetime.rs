@@ -0,0 +1,653 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Arithmetic of dates and timestamps with intervals, and arithmetic and
+//! comparisons of intervals
+
+use std::any::Any;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::datatypes::{DataType, IntervalUnit, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+
+use super::binary_operator_data_type;
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Operator;
+use crate::physical_plan::{ColumnarValue, PhysicalExpr};
+use crate::scalar::{
+    interval_dt_parts, interval_dt_value, interval_mdn_parts, interval_mdn_value,
+    ScalarValue,
+};
+
+const NANOS_PER_MILLI: i64 = 1_000_000;
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+const NANOS_PER_DAY: i64 = 86_400 * NANOS_PER_SECOND;
+
+/// The months, days and nanoseconds of an interval of any unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntervalParts {
+    /// Number of months
+    pub months: i32,
+    /// Number of days
+    pub days: i32,
+    /// Number of nanoseconds
+    pub nanos: i64,
+}
+
+impl IntervalParts {
+    /// Reads the interval at `index` of the interval `array`, which must not be null
+    pub fn from_array(array: &dyn Array, index: usize) -> Result<Self> {
+        match array.data_type() {
+            DataType::Interval(IntervalUnit::YearMonth) => {
+                let array = downcast::<IntervalYearMonthArray>(array)?;
+                Ok(Self {
+                    months: array.value(index),
+                    days: 0,
+                    nanos: 0,
+                })
+            }
+            DataType::Interval(IntervalUnit::DayTime) => {
+                let array = downcast::<IntervalDayTimeArray>(array)?;
+                let (days, millis) = interval_dt_parts(array.value(index));
+                Ok(Self {
+                    months: 0,
+                    days,
+                    nanos: millis as i64 * NANOS_PER_MILLI,
+                })
+            }
+            DataType::Interval(IntervalUnit::MonthDayNano) => {
+                let array = downcast::<IntervalMonthDayNanoArray>(array)?;
+                let (months, days, nanos) = interval_mdn_parts(array.value(index));
+                Ok(Self {
+                    months,
+                    days,
+                    nanos,
+                })
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Expected an interval, got {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn negate(self) -> Self {
+        Self {
+            months: -self.months,
+            days: -self.days,
+            nanos: -self.nanos,
+        }
+    }
+
+    fn checked_add(self, other: Self) -> Result<Self> {
+        let months = self.months.checked_add(other.months);
+        let days = self.days.checked_add(other.days);
+        let nanos = self.nanos.checked_add(other.nanos);
+        match (months, days, nanos) {
+            (Some(months), Some(days), Some(nanos)) => Ok(Self {
+                months,
+                days,
+                nanos,
+            }),
+            _ => Err(overflow()),
+        }
+    }
+
+    /// The length of the interval in nanoseconds, assuming that a month has 30
+    /// days and a day 24 hours as PostgreSQL does to compare intervals
+    pub(crate) fn normalized_nanos(&self) -> i128 {
+        (self.months as i128 * 30 + self.days as i128) * NANOS_PER_DAY as i128
+            + self.nanos as i128
+    }
+}
+
+/// Adds intervals to dates and timestamps or subtracts intervals from them, adds
+/// and subtracts intervals, and compares intervals.
+///
+/// Dates stay dates when an interval is added to them: for `Date32` the time
+/// of the interval is truncated to whole days.
+#[derive(Debug)]
+pub struct DateTimeIntervalExpr {
+    lhs: Arc<dyn PhysicalExpr>,
+    op: Operator,
+    rhs: Arc<dyn PhysicalExpr>,
+}
+
+impl DateTimeIntervalExpr {
+    /// Create a new DateTimeIntervalExpr, whose types are validated by [`super::binary`]
+    pub fn new(
+        lhs: Arc<dyn PhysicalExpr>,
+        op: Operator,
+        rhs: Arc<dyn PhysicalExpr>,
+    ) -> Self {
+        Self { lhs, op, rhs }
+    }
+
+    /// Get the left side of the expression
+    pub fn lhs(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.lhs
+    }
+
+    /// Get the operator of the expression
+    pub fn op(&self) -> &Operator {
+        &self.op
+    }
+
+    /// Get the right side of the expression
+    pub fn rhs(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.rhs
+    }
+
+    fn evaluate_arrays(&self, lhs: &ArrayRef, rhs: &ArrayRef) -> Result<ArrayRef> {
+        match (lhs.data_type(), &self.op, rhs.data_type()) {
+            (DataType::Interval(_), _, DataType::Interval(_))
+                if is_comparison(&self.op) =>
+            {
+                compare_intervals(lhs, &self.op, rhs)
+            }
+            (
+                DataType::Interval(lhs_unit),
+                Operator::Plus | Operator::Minus,
+                DataType::Interval(rhs_unit),
+            ) => {
+                let unit = if lhs_unit == rhs_unit {
+                    lhs_unit.clone()
+                } else {
+                    IntervalUnit::MonthDayNano
+                };
+                let values = (0..lhs.len())
+                    .map(|index| {
+                        if lhs.is_null(index) || rhs.is_null(index) {
+                            return Ok(None);
+                        }
+                        let lhs = IntervalParts::from_array(lhs.as_ref(), index)?;
+                        let rhs = IntervalParts::from_array(rhs.as_ref(), index)?;
+                        let rhs = match self.op {
+                            Operator::Minus => rhs.negate(),
+                            _ => rhs,
+                        };
+                        lhs.checked_add(rhs).map(Some)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                interval_array(&unit, values)
+            }
+            (_, Operator::Plus | Operator::Minus, DataType::Interval(_)) => {
+                shift_temporal(lhs, rhs, self.op == Operator::Minus)
+            }
+            (DataType::Interval(_), Operator::Plus, _) => shift_temporal(rhs, lhs, false),
+            (lhs_type, op, rhs_type) => Err(DataFusionError::Internal(format!(
+                "Cannot evaluate interval expression {:?} with types {:?} and {:?}",
+                op, lhs_type, rhs_type
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for DateTimeIntervalExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.lhs, self.op, self.rhs)
+    }
+}
+
+impl PhysicalExpr for DateTimeIntervalExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        binary_operator_data_type(
+            &self.lhs.data_type(input_schema)?,
+            &self.op,
+            &self.rhs.data_type(input_schema)?,
+        )
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        Ok(self.lhs.nullable(input_schema)? || self.rhs.nullable(input_schema)?)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let lhs = self.lhs.evaluate(batch)?;
+        let rhs = self.rhs.evaluate(batch)?;
+        let is_scalar = matches!(
+            (&lhs, &rhs),
+            (ColumnarValue::Scalar(_), ColumnarValue::Scalar(_))
+        );
+        let num_rows = if is_scalar { 1 } else { batch.num_rows() };
+        let result =
+            self.evaluate_arrays(&lhs.into_array(num_rows), &rhs.into_array(num_rows))?;
+        if is_scalar {
+            Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                &result, 0,
+            )?))
+        } else {
+            Ok(ColumnarValue::Array(result))
+        }
+    }
+}
+
+/// Negates every interval of the interval `array`
+pub(crate) fn negate_intervals(array: &ArrayRef) -> Result<ArrayRef> {
+    let unit = match array.data_type() {
+        DataType::Interval(unit) => unit.clone(),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Expected an interval, got {:?}",
+                other
+            )))
+        }
+    };
+    let values = (0..array.len())
+        .map(|index| {
+            if array.is_null(index) {
+                Ok(None)
+            } else {
+                IntervalParts::from_array(array.as_ref(), index).map(|i| Some(i.negate()))
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    interval_array(&unit, values)
+}
+
+fn is_comparison(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+            | Operator::IsDistinctFrom
+            | Operator::IsNotDistinctFrom
+    )
+}
+
+/// Compares intervals of any units by their normalized length
+fn compare_intervals(lhs: &ArrayRef, op: &Operator, rhs: &ArrayRef) -> Result<ArrayRef> {
+    let compare: fn(&Option<i128>, &Option<i128>) -> bool = match op {
+        Operator::Eq | Operator::IsNotDistinctFrom => |l, r| l == r,
+        Operator::NotEq | Operator::IsDistinctFrom => |l, r| l != r,
+        Operator::Lt => |l, r| l < r,
+        Operator::LtEq => |l, r| l <= r,
+        Operator::Gt => |l, r| l > r,
+        Operator::GtEq => |l, r| l >= r,
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Intervals can't be compared with {:?}",
+                other
+            )))
+        }
+    };
+    let null_aware = matches!(op, Operator::IsDistinctFrom | Operator::IsNotDistinctFrom);
+    let normalized = |array: &ArrayRef, index: usize| -> Result<Option<i128>> {
+        if array.is_null(index) {
+            Ok(None)
+        } else {
+            IntervalParts::from_array(array.as_ref(), index)
+                .map(|i| Some(i.normalized_nanos()))
+        }
+    };
+
+    let result = (0..lhs.len())
+        .map(|index| {
+            let lhs = normalized(lhs, index)?;
+            let rhs = normalized(rhs, index)?;
+            if !null_aware && (lhs.is_none() || rhs.is_none()) {
+                Ok(None)
+            } else {
+                Ok(Some(compare(&lhs, &rhs)))
+            }
+        })
+        .collect::<Result<BooleanArray>>()?;
+    Ok(Arc::new(result))
+}
+
+/// Adds the intervals of `intervals` to the dates or timestamps of `temporal`, or
+/// subtracts them if `negate` is true
+fn shift_temporal(
+    temporal: &ArrayRef,
+    intervals: &ArrayRef,
+    negate: bool,
+) -> Result<ArrayRef> {
+    let data_type = temporal.data_type();
+    let values = (0..temporal.len())
+        .map(|index| {
+            if temporal.is_null(index) || intervals.is_null(index) {
+                return Ok(None);
+            }
+            let value = temporal_value(temporal.as_ref(), index)?;
+            let interval = IntervalParts::from_array(intervals.as_ref(), index)?;
+            let interval = if negate { interval.negate() } else { interval };
+            add_interval(value, data_type, interval).map(Some)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    temporal_array(data_type, values)
+}
+
+/// Adds `interval` to the date or timestamp `value` of type `data_type`
+pub(crate) fn add_interval(
+    value: i64,
+    data_type: &DataType,
+    interval: IntervalParts,
+) -> Result<i64> {
+    match data_type {
+        DataType::Date32 => {
+            let date = epoch()
+                .date()
+                .checked_add_signed(Duration::days(value))
+                .ok_or_else(overflow)?;
+            let date = shift_months(date, interval.months)?;
+            let days = interval.days as i64 + interval.nanos.div_euclid(NANOS_PER_DAY);
+            let date = date
+                .checked_add_signed(Duration::days(days))
+                .ok_or_else(overflow)?;
+            let days = date.signed_duration_since(epoch().date()).num_days();
+            i32::try_from(days)
+                .map(|days| days as i64)
+                .map_err(|_| overflow())
+        }
+        DataType::Date64 => {
+            add_interval_to_timestamp(value, &TimeUnit::Millisecond, interval)
+        }
+        DataType::Timestamp(unit, _) => add_interval_to_timestamp(value, unit, interval),
+        other => Err(DataFusionError::Internal(format!(
+            "Intervals can't be added to {:?}",
+            other
+        ))),
+    }
+}
+
+fn add_interval_to_timestamp(
+    value: i64,
+    unit: &TimeUnit,
+    interval: IntervalParts,
+) -> Result<i64> {
+    let nanos_per_unit = match unit {
+        TimeUnit::Second => NANOS_PER_SECOND,
+        TimeUnit::Millisecond => NANOS_PER_MILLI,
+        TimeUnit::Microsecond => 1_000,
+        TimeUnit::Nanosecond => 1,
+    };
+    let units_per_second = NANOS_PER_SECOND / nanos_per_unit;
+    let datetime = NaiveDateTime::from_timestamp_opt(
+        value.div_euclid(units_per_second),
+        (value.rem_euclid(units_per_second) * nanos_per_unit) as u32,
+    )
+    .ok_or_else(overflow)?;
+    let datetime = NaiveDateTime::new(
+        shift_months(datetime.date(), interval.months)?,
+        datetime.time(),
+    );
+    let datetime = datetime
+        .checked_add_signed(Duration::days(interval.days as i64))
+        .and_then(|datetime| {
+            datetime.checked_add_signed(Duration::nanoseconds(interval.nanos))
+        })
+        .ok_or_else(overflow)?;
+    datetime
+        .timestamp()
+        .checked_mul(units_per_second)
+        .and_then(|value| {
+            value.checked_add(datetime.timestamp_subsec_nanos() as i64 / nanos_per_unit)
+        })
+        .ok_or_else(overflow)
+}
+
+/// Adds `months` to `date`, clamping its day to the last day of the resulting month
+fn shift_months(date: NaiveDate, months: i32) -> Result<NaiveDate> {
+    if months == 0 {
+        return Ok(date);
+    }
+    let month0 = date.year() as i64 * 12 + date.month0() as i64 + months as i64;
+    let year = i32::try_from(month0.div_euclid(12)).map_err(|_| overflow())?;
+    let month = month0.rem_euclid(12) as u32 + 1;
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    let last_day = NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|date| date.pred_opt())
+        .ok_or_else(overflow)?
+        .day();
+    NaiveDate::from_ymd_opt(year, month, date.day().min(last_day)).ok_or_else(overflow)
+}
+
+fn epoch() -> NaiveDateTime {
+    NaiveDateTime::from_timestamp(0, 0)
+}
+
+fn overflow() -> DataFusionError {
+    DataFusionError::Execution("Date or interval arithmetic overflowed".to_string())
+}
+
+fn downcast<T: 'static>(array: &dyn Array) -> Result<&T> {
+    array.as_any().downcast_ref::<T>().ok_or_else(|| {
+        DataFusionError::Internal(format!(
+            "Failed to downcast array of type {:?}",
+            array.data_type()
+        ))
+    })
+}
+
+/// Reads the date or timestamp at `index` of `array`, which must not be null
+pub(crate) fn temporal_value(array: &dyn Array, index: usize) -> Result<i64> {
+    Ok(match array.data_type() {
+        DataType::Date32 => downcast::<Date32Array>(array)?.value(index) as i64,
+        DataType::Date64 => downcast::<Date64Array>(array)?.value(index),
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            downcast::<TimestampSecondArray>(array)?.value(index)
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            downcast::<TimestampMillisecondArray>(array)?.value(index)
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            downcast::<TimestampMicrosecondArray>(array)?.value(index)
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            downcast::<TimestampNanosecondArray>(array)?.value(index)
+        }
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Intervals can't be added to {:?}",
+                other
+            )))
+        }
+    })
+}
+
+/// Creates an array of the dates or timestamps `values` of type `data_type`
+pub(crate) fn temporal_array(
+    data_type: &DataType,
+    values: Vec<Option<i64>>,
+) -> Result<ArrayRef> {
+    Ok(match data_type {
+        DataType::Date32 => Arc::new(
+            values
+                .into_iter()
+                .map(|v| v.map(|v| v as i32))
+                .collect::<Date32Array>(),
+        ),
+        DataType::Date64 => Arc::new(Date64Array::from(values)),
+        DataType::Timestamp(TimeUnit::Second, tz) => {
+            Arc::new(TimestampSecondArray::from_opt_vec(values, tz.clone()))
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, tz) => {
+            Arc::new(TimestampMillisecondArray::from_opt_vec(values, tz.clone()))
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, tz) => {
+            Arc::new(TimestampMicrosecondArray::from_opt_vec(values, tz.clone()))
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, tz) => {
+            Arc::new(TimestampNanosecondArray::from_opt_vec(values, tz.clone()))
+        }
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Intervals can't be added to {:?}",
+                other
+            )))
+        }
+    })
+}
+
+fn interval_array(
+    unit: &IntervalUnit,
+    values: Vec<Option<IntervalParts>>,
+) -> Result<ArrayRef> {
+    Ok(match unit {
+        IntervalUnit::YearMonth => Arc::new(
+            values
+                .into_iter()
+                .map(|v| v.map(|v| v.months))
+                .collect::<IntervalYearMonthArray>(),
+        ),
+        IntervalUnit::DayTime => Arc::new(
+            values
+                .into_iter()
+                .map(|v| -> Result<Option<i64>> {
+                    match v {
+                        Some(v) => {
+                            let millis = i32::try_from(v.nanos / NANOS_PER_MILLI)
+                                .map_err(|_| overflow())?;
+                            Ok(Some(interval_dt_value(v.days, millis)))
+                        }
+                        None => Ok(None),
+                    }
+                })
+                .collect::<Result<IntervalDayTimeArray>>()?,
+        ),
+        IntervalUnit::MonthDayNano => Arc::new(
+            values
+                .into_iter()
+                .map(|v| v.map(|v| interval_mdn_value(v.months, v.days, v.nanos)))
+                .collect::<IntervalMonthDayNanoArray>(),
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{binary, col, lit};
+    use arrow::datatypes::Field;
+    use arrow::util::display::array_value_to_string;
+
+    fn evaluate(
+        value: ArrayRef,
+        op: Operator,
+        interval: ScalarValue,
+    ) -> Result<Vec<String>> {
+        let schema = Schema::new(vec![Field::new("a", value.data_type().clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![value])?;
+        let expr = binary(col("a", &schema)?, op, lit(interval), &schema)?;
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        (0..result.len())
+            .map(|i| Ok(array_value_to_string(&result, i)?))
+            .collect()
+    }
+
+    #[test]
+    fn add_interval_to_date() -> Result<()> {
+        // 2020-01-31, 2020-02-29, NULL
+        let dates: ArrayRef =
+            Arc::new(Date32Array::from(vec![Some(18292), Some(18321), None]));
+        let month = ScalarValue::IntervalYearMonth(Some(1));
+        assert_eq!(
+            evaluate(dates.clone(), Operator::Plus, month.clone())?,
+            vec!["2020-02-29", "2020-03-29", ""]
+        );
+        assert_eq!(
+            evaluate(dates.clone(), Operator::Minus, month)?,
+            vec!["2019-12-31", "2020-01-29", ""]
+        );
+
+        let one_day = ScalarValue::new_interval_mdn(0, 1, NANOS_PER_DAY / 2);
+        assert_eq!(
+            evaluate(dates, Operator::Plus, one_day)?,
+            vec!["2020-02-01", "2020-03-01", ""]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn add_interval_to_timestamp() -> Result<()> {
+        // 2020-01-31T10:00:00
+        let timestamps: ArrayRef = Arc::new(TimestampNanosecondArray::from_opt_vec(
+            vec![Some(1_580_464_800_000_000_000), None],
+            None,
+        ));
+        let interval = ScalarValue::new_interval_mdn(1, 2, 3_600 * NANOS_PER_SECOND + 1);
+        assert_eq!(
+            evaluate(timestamps.clone(), Operator::Plus, interval.clone())?,
+            vec!["2020-03-02 11:00:00.000000001", ""]
+        );
+        assert_eq!(
+            evaluate(timestamps, Operator::Minus, interval)?,
+            vec!["2019-12-29 08:59:59.999999999", ""]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn interval_arithmetic_and_comparisons() -> Result<()> {
+        let schema = Schema::new(vec![]);
+        let batch = RecordBatch::new_empty(Arc::new(schema.clone()));
+        let month = lit(ScalarValue::IntervalYearMonth(Some(1)));
+        let days = |days| {
+            lit(ScalarValue::IntervalDayTime(Some(interval_dt_value(
+                days, 0,
+            ))))
+        };
+
+        let sum = binary(month.clone(), Operator::Plus, days(2), &schema)?;
+        assert_eq!(
+            sum.data_type(&schema)?,
+            DataType::Interval(IntervalUnit::MonthDayNano)
+        );
+        match sum.evaluate(&batch)? {
+            ColumnarValue::Scalar(value) => {
+                assert_eq!(value, ScalarValue::new_interval_mdn(1, 2, 0))
+            }
+            _ => panic!("expected a scalar"),
+        }
+
+        let cases = vec![
+            (Operator::Gt, days(29), true),
+            (Operator::Lt, days(31), true),
+            (Operator::Eq, days(30), true),
+            (Operator::NotEq, days(30), false),
+        ];
+        for (op, rhs, expected) in cases {
+            let expr = binary(month.clone(), op, rhs, &schema)?;
+            match expr.evaluate(&batch)? {
+                ColumnarValue::Scalar(value) => {
+                    assert_eq!(value, ScalarValue::Boolean(Some(expected)))
+                }
+                _ => panic!("expected a scalar"),
+            }
+        }
+
+        let err = binary(month, Operator::Multiply, days(1), &schema).unwrap_err();
+        assert!(err.to_string().contains("can't be evaluated"));
+        Ok(())
+    }
+}
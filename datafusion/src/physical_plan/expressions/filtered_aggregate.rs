@@ -0,0 +1,200 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the `FILTER (WHERE ...)` clause of aggregate functions
+
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::{Array, ArrayRef, BooleanArray};
+use arrow::compute;
+use arrow::datatypes::Field;
+
+/// An aggregate function that only aggregates the rows for which a predicate
+/// is true, e.g. `SUM(a) FILTER (WHERE b > 1)`.
+///
+/// The predicate is evaluated as the last of the expressions of the aggregate.
+/// The states of the aggregate are the ones of the filtered aggregate function,
+/// as the predicate is only applied when updating them from the input rows.
+#[derive(Debug)]
+pub struct FilteredAggregate {
+    aggregate: Arc<dyn AggregateExpr>,
+    predicate: Arc<dyn PhysicalExpr>,
+}
+
+impl FilteredAggregate {
+    /// Create a new aggregate function aggregating the rows for which
+    /// `predicate` is true with `aggregate`
+    pub fn new(
+        aggregate: Arc<dyn AggregateExpr>,
+        predicate: Arc<dyn PhysicalExpr>,
+    ) -> Self {
+        Self {
+            aggregate,
+            predicate,
+        }
+    }
+
+    /// The filtered aggregate function
+    pub fn aggregate(&self) -> &Arc<dyn AggregateExpr> {
+        &self.aggregate
+    }
+
+    /// The predicate of the filter
+    pub fn predicate(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.predicate
+    }
+}
+
+impl AggregateExpr for FilteredAggregate {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        self.aggregate.field()
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(FilteredAccumulator {
+            accumulator: self.aggregate.create_accumulator()?,
+        }))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        self.aggregate.state_fields()
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        let mut expressions = self.aggregate.expressions();
+        expressions.push(self.predicate.clone());
+        expressions
+    }
+
+    fn name(&self) -> &str {
+        self.aggregate.name()
+    }
+}
+
+#[derive(Debug)]
+struct FilteredAccumulator {
+    accumulator: Box<dyn Accumulator>,
+}
+
+impl Accumulator for FilteredAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        self.accumulator.state()
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        let (predicate, values) = values.split_last().unwrap();
+        match predicate {
+            ScalarValue::Boolean(Some(true)) => self.accumulator.update(values),
+            ScalarValue::Boolean(_) => Ok(()),
+            other => Err(DataFusionError::Internal(format!(
+                "Aggregate filter predicate evaluated to {:?} instead of a boolean",
+                other
+            ))),
+        }
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let (predicate, values) = values.split_last().unwrap();
+        let predicate = predicate
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(
+                    "Aggregate filter predicate did not evaluate to a boolean array"
+                        .to_owned(),
+                )
+            })?;
+        // rows for which the predicate is NULL are not aggregated either
+        let selected;
+        let predicate = if predicate.null_count() > 0 {
+            selected = predicate
+                .iter()
+                .map(|v| Some(v == Some(true)))
+                .collect::<BooleanArray>();
+            &selected
+        } else {
+            predicate
+        };
+        let values = values
+            .iter()
+            .map(|array| compute::filter(array.as_ref(), predicate))
+            .collect::<arrow::error::Result<Vec<_>>>()?;
+        self.accumulator.update_batch(&values)
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        self.accumulator.merge(states)
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        self.accumulator.merge_batch(states)
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        self.accumulator.evaluate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{col, Sum};
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Schema};
+
+    #[test]
+    fn filtered_sum() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Boolean, true),
+        ]);
+        let sum = Arc::new(Sum::new(col("a", &schema)?, "SUM(a)", DataType::Int64));
+        let agg = FilteredAggregate::new(sum, col("b", &schema)?);
+        assert_eq!(agg.expressions().len(), 2);
+
+        let mut accumulator = agg.create_accumulator()?;
+        accumulator.update_batch(&[
+            Arc::new(Int32Array::from(vec![Some(1), Some(2), Some(4), None])) as ArrayRef,
+            Arc::new(BooleanArray::from(vec![
+                Some(true),
+                None,
+                Some(true),
+                Some(true),
+            ])) as ArrayRef,
+        ])?;
+        accumulator.update(&[
+            ScalarValue::Int32(Some(8)),
+            ScalarValue::Boolean(Some(false)),
+        ])?;
+        assert_eq!(accumulator.evaluate()?, ScalarValue::Int64(Some(5)));
+
+        // the states are merged without filtering
+        let mut merged = agg.create_accumulator()?;
+        merged.merge(&accumulator.state()?)?;
+        assert_eq!(merged.evaluate()?, ScalarValue::Int64(Some(5)));
+
+        Ok(())
+    }
+}
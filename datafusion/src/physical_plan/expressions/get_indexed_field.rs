@@ -15,7 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
-//! get field of a `ListArray`
+//! get field of a `ListArray`, `StructArray` or `MapArray`
 
 use std::convert::TryInto;
 use std::{any::Any, sync::Arc};
@@ -25,8 +25,8 @@ use arrow::{
     record_batch::RecordBatch,
 };
 
-use crate::arrow::array::Array;
-use crate::arrow::compute::concat;
+use crate::arrow::array::{Array, ArrayRef, MapArray, UInt32Array};
+use crate::arrow::compute::{cast, concat, take};
 use crate::scalar::ScalarValue;
 use crate::{
     error::DataFusionError,
@@ -107,6 +107,9 @@ impl PhysicalExpr for GetIndexedFieldExpr {
                         Some(col) => Ok(ColumnarValue::Array(col.clone()))
                     }
                 }
+                (DataType::Map(_, _), key) if !key.is_null() => {
+                    Ok(ColumnarValue::Array(get_map_value(&array, key)?))
+                }
                 (dt, key) => Err(DataFusionError::NotImplemented(format!("get indexed field is only possible on lists with int64 indexes. Tried {} with {} index", dt, key))),
             },
             ColumnarValue::Scalar(_) => Err(DataFusionError::NotImplemented(
@@ -116,12 +119,37 @@ impl PhysicalExpr for GetIndexedFieldExpr {
     }
 }
 
+/// Returns the value of `key` in each map of `array`, or null if the map does not
+/// contain the key
+fn get_map_value(array: &ArrayRef, key: &ScalarValue) -> Result<ArrayRef> {
+    let map_array = array.as_any().downcast_ref::<MapArray>().unwrap();
+    let keys = map_array.keys();
+    // the key is compared as a scalar of the type of the keys of the map
+    let key = ScalarValue::try_from_array(&cast(&key.to_array(), keys.data_type())?, 0)?;
+    let offsets = map_array.value_offsets();
+    let indices = (0..map_array.len())
+        .map(|i| {
+            if map_array.is_null(i) {
+                return Ok(None);
+            }
+            for j in offsets[i] as usize..offsets[i + 1] as usize {
+                if ScalarValue::try_from_array(&keys, j)? == key {
+                    return Ok(Some(j as u32));
+                }
+            }
+            Ok(None)
+        })
+        .collect::<Result<UInt32Array>>()?;
+    Ok(take(map_array.values().as_ref(), &indices, None)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::arrow::array::GenericListArray;
     use crate::error::Result;
     use crate::physical_plan::expressions::{col, lit};
+    use crate::test::make_map_array;
     use arrow::array::{
         Int64Array, Int64Builder, ListBuilder, StringBuilder, StructArray, StructBuilder,
     };
@@ -365,4 +393,41 @@ mod tests {
         )?;
         Ok(())
     }
+
+    #[test]
+    fn get_indexed_field_map() -> Result<()> {
+        let map = make_map_array(vec![
+            Some(vec![("a", Some(1)), ("b", Some(2))]),
+            Some(vec![("b", None), ("c", Some(3))]),
+            None,
+            Some(vec![]),
+        ]);
+        let schema = Schema::new(vec![Field::new("m", map.data_type().clone(), true)]);
+        let expr = col("m", &schema).unwrap();
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(map)])?;
+
+        let cases = vec![
+            ("a", vec![Some(1), None, None, None]),
+            ("b", vec![Some(2), None, None, None]),
+            ("c", vec![None, Some(3), None, None]),
+            ("d", vec![None, None, None, None]),
+        ];
+        for (key, expected) in cases {
+            let key = ScalarValue::Utf8(Some(key.to_string()));
+            let expr = Arc::new(GetIndexedFieldExpr::new(expr.clone(), key));
+            assert_eq!(expr.data_type(&schema)?, DataType::Int64);
+            assert!(expr.nullable(&schema)?);
+            let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            let result = result
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .expect("failed to downcast to Int64Array");
+            assert_eq!(&Int64Array::from(expected), result);
+        }
+
+        // a map cannot be indexed with a null key
+        let expr = GetIndexedFieldExpr::new(expr, ScalarValue::Utf8(None));
+        assert!(expr.data_type(&schema).is_err());
+        Ok(())
+    }
 }
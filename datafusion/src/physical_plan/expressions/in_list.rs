@@ -18,6 +18,7 @@
 //! InList expression
 
 use std::any::Any;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use arrow::array::GenericStringArray;
@@ -32,6 +33,7 @@ use arrow::{
     record_batch::RecordBatch,
 };
 
+use super::{CastExpr, Literal, TryCastExpr};
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{ColumnarValue, PhysicalExpr};
 use crate::scalar::ScalarValue;
@@ -62,12 +64,91 @@ macro_rules! compare_op_scalar {
     }};
 }
 
+/// Number of values of a list of literals above which it is evaluated with lookups
+/// in a hash set rather than comparisons with each value, and pruned by the range
+/// of its values rather than by each of them
+pub const IN_LIST_HASH_SET_THRESHOLD: usize = 30;
+
 /// InList
 #[derive(Debug)]
 pub struct InListExpr {
     expr: Arc<dyn PhysicalExpr>,
     list: Vec<Arc<dyn PhysicalExpr>>,
     negated: bool,
+    /// the values of the list, if it is a large list of literals
+    set: Option<InSet>,
+}
+
+/// The values of a list of literals, looked up in a hash set
+#[derive(Debug)]
+struct InSet {
+    /// the non null values
+    values: HashSet<ScalarValue>,
+    /// whether the list has a null
+    contains_null: bool,
+}
+
+impl InSet {
+    /// Returns the set of the values of `list` if they are all literals
+    fn try_new(list: &[Arc<dyn PhysicalExpr>]) -> Option<Self> {
+        let mut values = HashSet::with_capacity(list.len());
+        let mut contains_null = false;
+        for expr in list {
+            let value = static_value(expr)?;
+            if value.is_null() {
+                contains_null = true;
+            } else {
+                values.insert(value);
+            }
+        }
+        Some(Self {
+            values,
+            contains_null,
+        })
+    }
+
+    fn evaluate(&self, array: &ArrayRef, negated: bool) -> Result<BooleanArray> {
+        (0..array.len())
+            .map(|i| {
+                if array.is_null(i) {
+                    return Ok(None);
+                }
+                let value = ScalarValue::try_from_array(array, i)?;
+                Ok(match (self.values.contains(&value), self.contains_null) {
+                    (true, _) => Some(!negated),
+                    // the value may be the null of the list
+                    (false, true) => None,
+                    (false, false) => Some(negated),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Returns the value of `expr` if it is a literal, possibly cast
+fn static_value(expr: &Arc<dyn PhysicalExpr>) -> Option<ScalarValue> {
+    let any = expr.as_any();
+    if let Some(literal) = any.downcast_ref::<Literal>() {
+        return Some(literal.value().clone());
+    }
+    let input = match (
+        any.downcast_ref::<CastExpr>(),
+        any.downcast_ref::<TryCastExpr>(),
+    ) {
+        (Some(cast), _) => cast.expr(),
+        (_, Some(try_cast)) => try_cast.expr(),
+        _ => return None,
+    };
+    static_value(input)?;
+    // casts of literals do not read their input batch
+    let batch = RecordBatch::new_empty(Arc::new(Schema::empty()));
+    match expr.evaluate(&batch).ok()? {
+        ColumnarValue::Scalar(value) => Some(value),
+        ColumnarValue::Array(array) if array.len() == 1 => {
+            ScalarValue::try_from_array(&array, 0).ok()
+        }
+        ColumnarValue::Array(_) => None,
+    }
 }
 
 macro_rules! make_contains {
@@ -226,10 +307,16 @@ impl InListExpr {
         list: Vec<Arc<dyn PhysicalExpr>>,
         negated: bool,
     ) -> Self {
+        let set = if list.len() > IN_LIST_HASH_SET_THRESHOLD {
+            InSet::try_new(&list)
+        } else {
+            None
+        };
         Self {
             expr,
             list,
             negated,
+            set,
         }
     }
 
@@ -248,6 +335,11 @@ impl InListExpr {
         self.negated
     }
 
+    /// Whether the list is evaluated with lookups in a hash set
+    pub fn is_hash_set(&self) -> bool {
+        self.set.is_some()
+    }
+
     /// Compare for specific utf8 types
     #[allow(clippy::unnecessary_wraps)]
     fn compare_utf8<T: StringOffsetSizeTrait>(
@@ -340,6 +432,12 @@ impl PhysicalExpr for InListExpr {
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let value = self.expr.evaluate(batch)?;
+        if let Some(set) = &self.set {
+            let array = value.into_array(batch.num_rows());
+            return Ok(ColumnarValue::Array(Arc::new(
+                set.evaluate(&array, self.negated)?,
+            )));
+        }
         let value_data_type = value.data_type();
         let list_values = self
             .list
@@ -473,7 +571,7 @@ mod tests {
 
     use super::*;
     use crate::error::Result;
-    use crate::physical_plan::expressions::{col, lit};
+    use crate::physical_plan::expressions::{cast, col, lit};
 
     // applies the in_list expr to an input batch and list
     macro_rules! in_list {
@@ -712,4 +810,64 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn in_list_hash_set() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+        let a = Int64Array::from(vec![Some(0), Some(100), None]);
+        let col_a = col("a", &schema)?;
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(a)])?;
+
+        // expression: "a in (0, 2, .., 2 * IN_LIST_HASH_SET_THRESHOLD)", with the
+        // last value cast
+        let list = || {
+            let mut list = (0..IN_LIST_HASH_SET_THRESHOLD as i64)
+                .map(|v| lit(ScalarValue::Int64(Some(v * 2))))
+                .collect::<Vec<_>>();
+            list.push(
+                cast(
+                    lit(ScalarValue::Int32(Some(
+                        2 * IN_LIST_HASH_SET_THRESHOLD as i32,
+                    ))),
+                    &Schema::empty(),
+                    DataType::Int64,
+                )
+                .unwrap(),
+            );
+            list
+        };
+        assert!(InListExpr::new(col_a.clone(), list(), false).is_hash_set());
+        in_list!(
+            batch,
+            list(),
+            &false,
+            vec![Some(true), Some(false), None],
+            col_a.clone()
+        );
+        in_list!(
+            batch,
+            list(),
+            &true,
+            vec![Some(false), Some(true), None],
+            col_a.clone()
+        );
+
+        // expression: "a in (0, 2, .., NULL)"
+        let mut with_null = list();
+        with_null.push(lit(ScalarValue::Int64(None)));
+        in_list!(
+            batch,
+            with_null,
+            &false,
+            vec![Some(true), None, None],
+            col_a.clone()
+        );
+
+        // lists with columns are not hashed
+        let mut with_column = list();
+        with_column.push(col_a.clone());
+        assert!(!InListExpr::new(col_a.clone(), with_column, false).is_hash_set());
+
+        Ok(())
+    }
 }
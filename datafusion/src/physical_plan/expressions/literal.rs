@@ -21,6 +21,7 @@ use std::any::Any;
 use std::sync::Arc;
 
 use arrow::{
+    array::BooleanArray,
     datatypes::{DataType, Schema},
     record_batch::RecordBatch,
 };
@@ -69,6 +70,14 @@ impl PhysicalExpr for Literal {
     fn evaluate(&self, _batch: &RecordBatch) -> Result<ColumnarValue> {
         Ok(ColumnarValue::Scalar(self.value.clone()))
     }
+
+    fn evaluate_selection(
+        &self,
+        batch: &RecordBatch,
+        _selection: &BooleanArray,
+    ) -> Result<ColumnarValue> {
+        self.evaluate(batch)
+    }
 }
 
 /// Create a literal expression
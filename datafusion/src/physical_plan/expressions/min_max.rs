@@ -107,6 +107,13 @@ impl AggregateExpr for Max {
         Ok(Box::new(MaxAccumulator::try_new(&self.data_type)?))
     }
 
+    fn row_states(&self, values: &[ArrayRef]) -> Result<Option<Vec<ArrayRef>>> {
+        if values[0].data_type() != &self.data_type {
+            return Ok(None);
+        }
+        Ok(Some(vec![values[0].clone()]))
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
@@ -129,6 +136,11 @@ macro_rules! typed_min_max_batch {
         let value = compute::$OP(array);
         ScalarValue::$SCALAR(value)
     }};
+    ($VALUES:expr, $ARRAYTYPE:ident, $SCALAR:ident, $OP:ident, $TZ:expr) => {{
+        let array = $VALUES.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
+        let value = compute::$OP(array);
+        ScalarValue::$SCALAR(value, $TZ.clone())
+    }};
 }
 
 // TODO implement this in arrow-rs with simd
@@ -189,26 +201,33 @@ macro_rules! min_max_batch {
             DataType::UInt32 => typed_min_max_batch!($VALUES, UInt32Array, UInt32, $OP),
             DataType::UInt16 => typed_min_max_batch!($VALUES, UInt16Array, UInt16, $OP),
             DataType::UInt8 => typed_min_max_batch!($VALUES, UInt8Array, UInt8, $OP),
-            DataType::Timestamp(TimeUnit::Second, _) => {
-                typed_min_max_batch!($VALUES, TimestampSecondArray, TimestampSecond, $OP)
-            }
-            DataType::Timestamp(TimeUnit::Millisecond, _) => typed_min_max_batch!(
+            DataType::Timestamp(TimeUnit::Second, tz) => typed_min_max_batch!(
+                $VALUES,
+                TimestampSecondArray,
+                TimestampSecond,
+                $OP,
+                tz
+            ),
+            DataType::Timestamp(TimeUnit::Millisecond, tz) => typed_min_max_batch!(
                 $VALUES,
                 TimestampMillisecondArray,
                 TimestampMillisecond,
-                $OP
+                $OP,
+                tz
             ),
-            DataType::Timestamp(TimeUnit::Microsecond, _) => typed_min_max_batch!(
+            DataType::Timestamp(TimeUnit::Microsecond, tz) => typed_min_max_batch!(
                 $VALUES,
                 TimestampMicrosecondArray,
                 TimestampMicrosecond,
-                $OP
+                $OP,
+                tz
             ),
-            DataType::Timestamp(TimeUnit::Nanosecond, _) => typed_min_max_batch!(
+            DataType::Timestamp(TimeUnit::Nanosecond, tz) => typed_min_max_batch!(
                 $VALUES,
                 TimestampNanosecondArray,
                 TimestampNanosecond,
-                $OP
+                $OP,
+                tz
             ),
             DataType::Date32 => typed_min_max_batch!($VALUES, Date32Array, Date32, $OP),
             DataType::Date64 => typed_min_max_batch!($VALUES, Date64Array, Date64, $OP),
@@ -273,6 +292,17 @@ macro_rules! typed_min_max {
             (Some(a), Some(b)) => Some((*a).$OP(*b)),
         })
     }};
+    ($VALUE:expr, $DELTA:expr, $SCALAR:ident, $OP:ident, $TZ:expr) => {{
+        ScalarValue::$SCALAR(
+            match ($VALUE, $DELTA) {
+                (None, None) => None,
+                (Some(a), None) => Some(a.clone()),
+                (None, Some(b)) => Some(b.clone()),
+                (Some(a), Some(b)) => Some((*a).$OP(*b)),
+            },
+            $TZ.clone(),
+        )
+    }};
 }
 
 // min/max of two scalar string values.
@@ -337,26 +367,29 @@ macro_rules! min_max {
             (ScalarValue::LargeUtf8(lhs), ScalarValue::LargeUtf8(rhs)) => {
                 typed_min_max_string!(lhs, rhs, LargeUtf8, $OP)
             }
-            (ScalarValue::TimestampSecond(lhs), ScalarValue::TimestampSecond(rhs)) => {
-                typed_min_max!(lhs, rhs, TimestampSecond, $OP)
+            (
+                ScalarValue::TimestampSecond(lhs, tz),
+                ScalarValue::TimestampSecond(rhs, _),
+            ) => {
+                typed_min_max!(lhs, rhs, TimestampSecond, $OP, tz)
             }
             (
-                ScalarValue::TimestampMillisecond(lhs),
-                ScalarValue::TimestampMillisecond(rhs),
+                ScalarValue::TimestampMillisecond(lhs, tz),
+                ScalarValue::TimestampMillisecond(rhs, _),
             ) => {
-                typed_min_max!(lhs, rhs, TimestampMillisecond, $OP)
+                typed_min_max!(lhs, rhs, TimestampMillisecond, $OP, tz)
             }
             (
-                ScalarValue::TimestampMicrosecond(lhs),
-                ScalarValue::TimestampMicrosecond(rhs),
+                ScalarValue::TimestampMicrosecond(lhs, tz),
+                ScalarValue::TimestampMicrosecond(rhs, _),
             ) => {
-                typed_min_max!(lhs, rhs, TimestampMicrosecond, $OP)
+                typed_min_max!(lhs, rhs, TimestampMicrosecond, $OP, tz)
             }
             (
-                ScalarValue::TimestampNanosecond(lhs),
-                ScalarValue::TimestampNanosecond(rhs),
+                ScalarValue::TimestampNanosecond(lhs, tz),
+                ScalarValue::TimestampNanosecond(rhs, _),
             ) => {
-                typed_min_max!(lhs, rhs, TimestampNanosecond, $OP)
+                typed_min_max!(lhs, rhs, TimestampNanosecond, $OP, tz)
             }
             (
                 ScalarValue::Date32(lhs),
@@ -479,6 +512,13 @@ impl AggregateExpr for Min {
         Ok(Box::new(MinAccumulator::try_new(&self.data_type)?))
     }
 
+    fn row_states(&self, values: &[ArrayRef]) -> Result<Option<Vec<ArrayRef>>> {
+        if values[0].data_type() != &self.data_type {
+            return Ok(None);
+        }
+        Ok(Some(vec![values[0].clone()]))
+    }
+
     fn state_fields(&self) -> Result<Vec<Field>> {
         Ok(vec![Field::new(
             &format_state_name(&self.name, "min"),
@@ -36,6 +36,8 @@ mod coercion;
 mod column;
 mod count;
 mod cume_dist;
+mod datetime;
+mod filtered_aggregate;
 mod get_indexed_field;
 mod in_list;
 mod is_not_null;
@@ -47,9 +49,13 @@ mod min_max;
 mod negative;
 mod not;
 mod nth_value;
+mod ntile;
 mod nullif;
+mod ordered_aggregate;
+mod percentile;
 mod rank;
 mod row_number;
+mod string_agg;
 mod sum;
 mod try_cast;
 
@@ -62,16 +68,24 @@ pub use approx_distinct::ApproxDistinct;
 pub use array_agg::ArrayAgg;
 pub(crate) use average::is_avg_support_arg_type;
 pub use average::{avg_return_type, Avg, AvgAccumulator};
-pub use binary::{binary, binary_operator_data_type, BinaryExpr};
+pub use binary::{
+    binary, binary_operator_data_type, binary_with_overflow_checks, BinaryExpr,
+};
 pub use case::{case, CaseExpr};
 pub use cast::{
     cast, cast_column, cast_with_options, CastExpr, DEFAULT_DATAFUSION_CAST_OPTIONS,
 };
+pub use coercion::{case_coercion, check_strict_coercion, comparison_coercion};
 pub use column::{col, Column};
 pub use count::Count;
 pub use cume_dist::cume_dist;
+pub(crate) use datetime::{
+    add_interval, negate_intervals, temporal_array, temporal_value,
+};
+pub use datetime::{DateTimeIntervalExpr, IntervalParts};
+pub use filtered_aggregate::FilteredAggregate;
 pub use get_indexed_field::GetIndexedFieldExpr;
-pub use in_list::{in_list, InListExpr};
+pub use in_list::{in_list, InListExpr, IN_LIST_HASH_SET_THRESHOLD};
 pub use is_not_null::{is_not_null, IsNotNullExpr};
 pub use is_null::{is_null, IsNullExpr};
 pub use lead_lag::{lag, lead};
@@ -81,10 +95,14 @@ pub(crate) use min_max::{MaxAccumulator, MinAccumulator};
 pub use negative::{negative, NegativeExpr};
 pub use not::{not, NotExpr};
 pub use nth_value::NthValue;
+pub use ntile::Ntile;
 pub use nullif::{nullif_func, SUPPORTED_NULLIF_TYPES};
+pub use ordered_aggregate::OrderedAggregate;
+pub use percentile::Percentile;
 pub use rank::{dense_rank, percent_rank, rank};
 pub use row_number::RowNumber;
-pub(crate) use sum::is_sum_support_arg_type;
+pub use string_agg::StringAgg;
+pub(crate) use sum::{is_sum_support_arg_type, sum as sum_scalars};
 pub use sum::{sum_return_type, Sum};
 pub use try_cast::{try_cast, TryCastExpr};
 
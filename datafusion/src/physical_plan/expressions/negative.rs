@@ -32,6 +32,7 @@ use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{ColumnarValue, PhysicalExpr};
 
 use super::coercion;
+use super::negate_intervals;
 
 /// Invoke a compute kernel on array(s)
 macro_rules! compute_op {
@@ -95,6 +96,7 @@ impl PhysicalExpr for NegativeExpr {
                     DataType::Int64 => compute_op!(array, negate, Int64Array),
                     DataType::Float32 => compute_op!(array, negate, Float32Array),
                     DataType::Float64 => compute_op!(array, negate, Float64Array),
+                    DataType::Interval(_) => negate_intervals(&array),
                     _ => Err(DataFusionError::Internal(format!(
                         "(- '{:?}') can't be evaluated because the expression's type is {:?}, not signed numeric",
                         self,
@@ -114,13 +116,15 @@ impl PhysicalExpr for NegativeExpr {
 ///
 /// # Errors
 ///
-/// This function errors when the argument's type is not signed numeric
+/// This function errors when the argument's type is not signed numeric or an interval
 pub fn negative(
     arg: Arc<dyn PhysicalExpr>,
     input_schema: &Schema,
 ) -> Result<Arc<dyn PhysicalExpr>> {
     let data_type = arg.data_type(input_schema)?;
-    if !coercion::is_signed_numeric(&data_type) {
+    if !coercion::is_signed_numeric(&data_type)
+        && !matches!(data_type, DataType::Interval(_))
+    {
         Err(DataFusionError::Internal(
             format!(
                 "(- '{:?}') can't be evaluated because the expression's type is {:?}, not signed numeric",
@@ -22,12 +22,11 @@ use crate::error::{DataFusionError, Result};
 use crate::physical_plan::window_functions::PartitionEvaluator;
 use crate::physical_plan::{window_functions::BuiltInWindowFunctionExpr, PhysicalExpr};
 use crate::scalar::ScalarValue;
-use arrow::array::{new_null_array, ArrayRef};
-use arrow::compute::kernels::window::shift;
+use arrow::array::ArrayRef;
 use arrow::datatypes::{DataType, Field};
 use arrow::record_batch::RecordBatch;
 use std::any::Any;
-use std::iter;
+use std::convert::TryFrom;
 use std::ops::Range;
 use std::sync::Arc;
 
@@ -46,6 +45,7 @@ pub struct NthValue {
     expr: Arc<dyn PhysicalExpr>,
     data_type: DataType,
     kind: NthValueKind,
+    ignore_nulls: bool,
 }
 
 impl NthValue {
@@ -60,6 +60,7 @@ impl NthValue {
             expr,
             data_type,
             kind: NthValueKind::First,
+            ignore_nulls: false,
         }
     }
 
@@ -74,6 +75,7 @@ impl NthValue {
             expr,
             data_type,
             kind: NthValueKind::Last,
+            ignore_nulls: false,
         }
     }
 
@@ -93,9 +95,16 @@ impl NthValue {
                 expr,
                 data_type,
                 kind: NthValueKind::Nth(n),
+                ignore_nulls: false,
             }),
         }
     }
+
+    /// Skip the rows of the window frame whose value is null (`IGNORE NULLS`)
+    pub fn with_ignore_nulls(mut self, ignore_nulls: bool) -> Self {
+        self.ignore_nulls = ignore_nulls;
+        self
+    }
 }
 
 impl BuiltInWindowFunctionExpr for NthValue {
@@ -129,6 +138,7 @@ impl BuiltInWindowFunctionExpr for NthValue {
             .collect::<Result<Vec<_>>>()?;
         Ok(Box::new(NthValueEvaluator {
             kind: self.kind,
+            ignore_nulls: self.ignore_nulls,
             values,
         }))
     }
@@ -137,59 +147,43 @@ impl BuiltInWindowFunctionExpr for NthValue {
 /// Value evaluator for nth_value functions
 pub(crate) struct NthValueEvaluator {
     kind: NthValueKind,
+    ignore_nulls: bool,
     values: Vec<ArrayRef>,
 }
 
+impl NthValueEvaluator {
+    /// the index of the row of `rows` whose value is returned
+    fn select(&self, mut rows: impl Iterator<Item = usize>) -> Option<usize> {
+        match self.kind {
+            NthValueKind::First => rows.next(),
+            NthValueKind::Last => rows.last(),
+            NthValueKind::Nth(n) => rows.nth((n as usize) - 1),
+        }
+    }
+}
+
 impl PartitionEvaluator for NthValueEvaluator {
-    fn include_rank(&self) -> bool {
+    fn uses_window_frame(&self) -> bool {
         true
     }
 
     fn evaluate_partition(&self, _partition: Range<usize>) -> Result<ArrayRef> {
-        unreachable!("first, last, and nth_value evaluation must be called with evaluate_partition_with_rank")
+        unreachable!(
+            "first, last, and nth_value evaluation must be called with evaluate_frame"
+        )
     }
 
-    fn evaluate_partition_with_rank(
-        &self,
-        partition: Range<usize>,
-        ranks_in_partition: &[Range<usize>],
-    ) -> Result<ArrayRef> {
+    fn evaluate_frame(&self, frame: &[Range<usize>]) -> Result<ScalarValue> {
         let arr = &self.values[0];
-        let num_rows = partition.end - partition.start;
-        match self.kind {
-            NthValueKind::First => {
-                let value = ScalarValue::try_from_array(arr, partition.start)?;
-                Ok(value.to_array_of_size(num_rows))
-            }
-            NthValueKind::Last => {
-                // because the default window frame is between unbounded preceding and current
-                // row with peer evaluation, hence the last rows expands until the end of the peers
-                let values = ranks_in_partition
-                    .iter()
-                    .map(|range| {
-                        let len = range.end - range.start;
-                        let value = ScalarValue::try_from_array(arr, range.end - 1)?;
-                        Ok(iter::repeat(value).take(len))
-                    })
-                    .collect::<Result<Vec<_>>>()?
-                    .into_iter()
-                    .flatten();
-                ScalarValue::iter_to_array(values)
-            }
-            NthValueKind::Nth(n) => {
-                let index = (n as usize) - 1;
-                if index >= num_rows {
-                    Ok(new_null_array(arr.data_type(), num_rows))
-                } else {
-                    let value =
-                        ScalarValue::try_from_array(arr, partition.start + index)?;
-                    let arr = value.to_array_of_size(num_rows);
-                    // because the default window frame is between unbounded preceding and current
-                    // row, hence the shift because for values with indices < index they should be
-                    // null. This changes when window frames other than default is implemented
-                    shift(arr.as_ref(), index as i64).map_err(DataFusionError::ArrowError)
-                }
-            }
+        let rows = frame.iter().flat_map(|range| range.clone());
+        let index = if self.ignore_nulls {
+            self.select(rows.filter(|row| arr.is_valid(*row)))
+        } else {
+            self.select(rows)
+        };
+        match index {
+            Some(index) => ScalarValue::try_from_array(arr, index),
+            None => ScalarValue::try_from(arr.data_type()),
         }
     }
 }
@@ -202,20 +196,43 @@ mod tests {
     use arrow::record_batch::RecordBatch;
     use arrow::{array::*, datatypes::*};
 
-    fn test_i32_result(expr: NthValue, expected: Int32Array) -> Result<()> {
-        let arr: ArrayRef = Arc::new(Int32Array::from(vec![1, -2, 3, -4, 5, -6, 7, 8]));
+    fn test_i32_result(
+        expr: NthValue,
+        frames: Vec<Vec<Range<usize>>>,
+        expected: Int32Array,
+    ) -> Result<()> {
+        let arr: ArrayRef = Arc::new(Int32Array::from(vec![
+            None,
+            Some(-2),
+            Some(3),
+            None,
+            Some(5),
+            Some(-6),
+            Some(7),
+            None,
+        ]));
         let values = vec![arr];
-        let schema = Schema::new(vec![Field::new("arr", DataType::Int32, false)]);
+        let schema = Schema::new(vec![Field::new("arr", DataType::Int32, true)]);
         let batch = RecordBatch::try_new(Arc::new(schema), values.clone())?;
-        let result = expr
-            .create_evaluator(&batch)?
-            .evaluate_with_rank(vec![0..8], vec![0..8])?;
-        assert_eq!(1, result.len());
-        let result = result[0].as_any().downcast_ref::<Int32Array>().unwrap();
+        let evaluator = expr.create_evaluator(&batch)?;
+        assert!(evaluator.uses_window_frame());
+        let result = frames
+            .iter()
+            .map(|frame| evaluator.evaluate_frame(frame))
+            .collect::<Result<Vec<_>>>()?;
+        let result = ScalarValue::iter_to_array(result)?;
+        let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
         assert_eq!(expected, *result);
         Ok(())
     }
 
+    /// the frames of `ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING` over 8 rows
+    fn sliding_frames() -> Vec<Vec<Range<usize>>> {
+        (0..8usize)
+            .map(|row| vec![row.saturating_sub(1)..(row + 2).min(8)])
+            .collect()
+    }
+
     #[test]
     fn first_value() -> Result<()> {
         let first_value = NthValue::first(
@@ -223,7 +240,45 @@ mod tests {
             Arc::new(Column::new("arr", 0)),
             DataType::Int32,
         );
-        test_i32_result(first_value, Int32Array::from_iter_values(vec![1; 8]))?;
+        test_i32_result(
+            first_value,
+            sliding_frames(),
+            Int32Array::from(vec![
+                None,
+                None,
+                Some(-2),
+                Some(3),
+                None,
+                Some(5),
+                Some(-6),
+                Some(7),
+            ]),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn first_value_ignore_nulls() -> Result<()> {
+        let first_value = NthValue::first(
+            "first_value".to_owned(),
+            Arc::new(Column::new("arr", 0)),
+            DataType::Int32,
+        )
+        .with_ignore_nulls(true);
+        test_i32_result(
+            first_value,
+            sliding_frames(),
+            Int32Array::from(vec![
+                Some(-2),
+                Some(-2),
+                Some(-2),
+                Some(3),
+                Some(5),
+                Some(5),
+                Some(-6),
+                Some(7),
+            ]),
+        )?;
         Ok(())
     }
 
@@ -234,7 +289,45 @@ mod tests {
             Arc::new(Column::new("arr", 0)),
             DataType::Int32,
         );
-        test_i32_result(last_value, Int32Array::from_iter_values(vec![8; 8]))?;
+        test_i32_result(
+            last_value,
+            sliding_frames(),
+            Int32Array::from(vec![
+                Some(-2),
+                Some(3),
+                None,
+                Some(5),
+                Some(-6),
+                Some(7),
+                None,
+                None,
+            ]),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn last_value_ignore_nulls() -> Result<()> {
+        let last_value = NthValue::last(
+            "last_value".to_owned(),
+            Arc::new(Column::new("arr", 0)),
+            DataType::Int32,
+        )
+        .with_ignore_nulls(true);
+        test_i32_result(
+            last_value,
+            sliding_frames(),
+            Int32Array::from(vec![
+                Some(-2),
+                Some(3),
+                Some(3),
+                Some(5),
+                Some(-6),
+                Some(7),
+                Some(7),
+                Some(7),
+            ]),
+        )?;
         Ok(())
     }
 
@@ -246,7 +339,11 @@ mod tests {
             DataType::Int32,
             1,
         )?;
-        test_i32_result(nth_value, Int32Array::from_iter_values(vec![1; 8]))?;
+        test_i32_result(
+            nth_value,
+            vec![vec![0..8]; 8],
+            Int32Array::from(vec![None::<i32>; 8]),
+        )?;
         Ok(())
     }
 
@@ -258,8 +355,11 @@ mod tests {
             DataType::Int32,
             2,
         )?;
+        // frames of the default window frame, with every row in its own peer group
+        let frames = (0..8).map(|row| vec![0..row + 1]).collect();
         test_i32_result(
             nth_value,
+            frames,
             Int32Array::from(vec![
                 None,
                 Some(-2),
@@ -273,4 +373,32 @@ mod tests {
         )?;
         Ok(())
     }
+
+    #[test]
+    fn nth_value_ignore_nulls() -> Result<()> {
+        let nth_value = NthValue::nth(
+            "nth_value".to_owned(),
+            Arc::new(Column::new("arr", 0)),
+            DataType::Int32,
+            2,
+        )?
+        .with_ignore_nulls(true);
+        // frame with the current row excluded
+        let frames = (0..8).map(|row| vec![0..row, row + 1..8]).collect();
+        test_i32_result(
+            nth_value,
+            frames,
+            Int32Array::from(vec![
+                Some(3),
+                Some(5),
+                Some(5),
+                Some(3),
+                Some(3),
+                Some(3),
+                Some(3),
+                Some(3),
+            ]),
+        )?;
+        Ok(())
+    }
 }
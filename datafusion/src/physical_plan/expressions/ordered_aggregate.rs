@@ -0,0 +1,333 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the `ORDER BY` clause of order sensitive aggregate functions
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use super::{format_state_name, PhysicalSortExpr};
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::compute::kernels::sort::SortOptions;
+use arrow::datatypes::{DataType, Field, Schema};
+
+/// An aggregate function that aggregates its input rows in the order of sort
+/// expressions, e.g. `ARRAY_AGG(a ORDER BY b DESC)`.
+///
+/// The input rows are buffered along with their sort keys, and are only fed to
+/// the ordered aggregate function, sorted, when it is evaluated. The states are
+/// the buffered rows sorted by their sort keys, one list per column, so that
+/// merging partial states is a merge of sorted runs rather than a full sort.
+#[derive(Debug)]
+pub struct OrderedAggregate {
+    aggregate: Arc<dyn AggregateExpr>,
+    order_by: Vec<PhysicalSortExpr>,
+    /// types of the expressions of the aggregate followed by the sort keys
+    input_types: Vec<DataType>,
+}
+
+impl OrderedAggregate {
+    /// Create a new aggregate function aggregating its input with `aggregate`
+    /// in the order of `order_by`
+    pub fn try_new(
+        aggregate: Arc<dyn AggregateExpr>,
+        order_by: Vec<PhysicalSortExpr>,
+        input_schema: &Schema,
+    ) -> Result<Self> {
+        let input_types = aggregate
+            .expressions()
+            .iter()
+            .chain(order_by.iter().map(|sort_expr| &sort_expr.expr))
+            .map(|expr| expr.data_type(input_schema))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            aggregate,
+            order_by,
+            input_types,
+        })
+    }
+
+    /// The ordered aggregate function
+    pub fn aggregate(&self) -> &Arc<dyn AggregateExpr> {
+        &self.aggregate
+    }
+
+    /// The sort expressions the input is aggregated in the order of
+    pub fn order_by(&self) -> &[PhysicalSortExpr] {
+        &self.order_by
+    }
+}
+
+impl AggregateExpr for OrderedAggregate {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        self.aggregate.field()
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(OrderedAccumulator {
+            aggregate: self.aggregate.clone(),
+            input_types: self.input_types.clone(),
+            sort_options: self
+                .order_by
+                .iter()
+                .map(|sort_expr| sort_expr.options)
+                .collect(),
+            rows: vec![],
+            sorted: true,
+        }))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(self
+            .input_types
+            .iter()
+            .enumerate()
+            .map(|(i, data_type)| {
+                Field::new(
+                    &format_state_name(self.name(), &format!("ordered_{}", i)),
+                    DataType::List(Box::new(Field::new("item", data_type.clone(), true))),
+                    false,
+                )
+            })
+            .collect())
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        let mut expressions = self.aggregate.expressions();
+        expressions.extend(self.order_by.iter().map(|sort_expr| sort_expr.expr.clone()));
+        expressions
+    }
+
+    fn name(&self) -> &str {
+        self.aggregate.name()
+    }
+}
+
+#[derive(Debug)]
+struct OrderedAccumulator {
+    aggregate: Arc<dyn AggregateExpr>,
+    input_types: Vec<DataType>,
+    sort_options: Vec<SortOptions>,
+    /// the buffered rows, the values of the expressions of the aggregate
+    /// followed by the sort keys
+    rows: Vec<Vec<ScalarValue>>,
+    /// whether `rows` are sorted by their sort keys
+    sorted: bool,
+}
+
+impl OrderedAccumulator {
+    /// position of the first sort key in the rows
+    fn sort_keys_offset(&self) -> usize {
+        self.input_types.len() - self.sort_options.len()
+    }
+
+    fn compare(&self, left: &[ScalarValue], right: &[ScalarValue]) -> Ordering {
+        let offset = self.sort_keys_offset();
+        compare_sort_keys(&left[offset..], &right[offset..], &self.sort_options)
+    }
+
+    fn sort(&mut self) {
+        if !self.sorted {
+            let mut rows = std::mem::take(&mut self.rows);
+            // stable, rows with equal sort keys stay in input order
+            rows.sort_by(|left, right| self.compare(left, right));
+            self.rows = rows;
+            self.sorted = true;
+        }
+    }
+
+    fn sorted_rows(&self) -> Vec<Vec<ScalarValue>> {
+        let mut rows = self.rows.clone();
+        if !self.sorted {
+            rows.sort_by(|left, right| self.compare(left, right));
+        }
+        rows
+    }
+}
+
+impl Accumulator for OrderedAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let rows = self.sorted_rows();
+        Ok(self
+            .input_types
+            .iter()
+            .enumerate()
+            .map(|(i, data_type)| {
+                ScalarValue::List(
+                    Some(Box::new(rows.iter().map(|row| row[i].clone()).collect())),
+                    Box::new(data_type.clone()),
+                )
+            })
+            .collect())
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        self.rows.push(values.to_vec());
+        self.sorted = false;
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        let columns = states
+            .iter()
+            .map(|state| match state {
+                ScalarValue::List(Some(values), _) => Ok(values.as_slice()),
+                ScalarValue::List(None, _) => Ok(&[][..]),
+                other => Err(DataFusionError::Internal(format!(
+                    "Unexpected state of an ordered aggregate: {:?}",
+                    other
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let num_rows = columns.first().map(|column| column.len()).unwrap_or(0);
+        // the partial states are sorted runs of rows
+        let run = (0..num_rows)
+            .map(|row| columns.iter().map(|column| column[row].clone()).collect())
+            .collect::<Vec<Vec<_>>>();
+
+        self.sort();
+        let rows = std::mem::take(&mut self.rows);
+        self.rows = merge_sorted_runs(rows, run, |left, right| self.compare(left, right));
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let num_values = self.sort_keys_offset();
+        let mut accumulator = self.aggregate.create_accumulator()?;
+        for row in self.sorted_rows() {
+            accumulator.update(&row[..num_values])?;
+        }
+        accumulator.evaluate()
+    }
+}
+
+/// Compares sort keys with the options of each of them
+pub(super) fn compare_sort_keys(
+    left: &[ScalarValue],
+    right: &[ScalarValue],
+    sort_options: &[SortOptions],
+) -> Ordering {
+    for ((left, right), options) in left.iter().zip(right).zip(sort_options) {
+        let ordering = match (left.is_null(), right.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) if options.nulls_first => Ordering::Less,
+            (true, false) => Ordering::Greater,
+            (false, true) if options.nulls_first => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => {
+                let ordering = left.partial_cmp(right).unwrap_or(Ordering::Equal);
+                if options.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Merges two runs sorted with `compare` into one, keeping the items of `left`
+/// before the equal ones of `right`
+pub(super) fn merge_sorted_runs<T>(
+    left: Vec<T>,
+    right: Vec<T>,
+    compare: impl Fn(&T, &T) -> Ordering,
+) -> Vec<T> {
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    loop {
+        let take_left = match (left.peek(), right.peek()) {
+            (Some(l), Some(r)) => compare(l, r) != Ordering::Greater,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => return merged,
+        };
+        merged.extend(if take_left { left.next() } else { right.next() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{col, ArrayAgg};
+    use arrow::array::{ArrayRef, Int32Array};
+
+    #[test]
+    fn ordered_array_agg() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]);
+        let array_agg = Arc::new(ArrayAgg::new(
+            col("a", &schema)?,
+            "ARRAY_AGG(a)",
+            DataType::Int32,
+        ));
+        let agg = OrderedAggregate::try_new(
+            array_agg,
+            vec![PhysicalSortExpr {
+                expr: col("b", &schema)?,
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: false,
+                },
+            }],
+            &schema,
+        )?;
+        assert_eq!(agg.expressions().len(), 2);
+        assert_eq!(agg.state_fields()?.len(), 2);
+
+        let mut first = agg.create_accumulator()?;
+        first.update_batch(&[
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+            Arc::new(Int32Array::from(vec![Some(10), None, Some(30)])) as ArrayRef,
+        ])?;
+        let mut second = agg.create_accumulator()?;
+        second.update_batch(&[
+            Arc::new(Int32Array::from(vec![4, 5])) as ArrayRef,
+            Arc::new(Int32Array::from(vec![20, 40])) as ArrayRef,
+        ])?;
+
+        let mut merged = agg.create_accumulator()?;
+        merged.merge(&first.state()?)?;
+        merged.merge(&second.state()?)?;
+        let expected = ScalarValue::List(
+            Some(Box::new(
+                vec![5, 3, 4, 1, 2]
+                    .into_iter()
+                    .map(|v| ScalarValue::Int32(Some(v)))
+                    .collect(),
+            )),
+            Box::new(DataType::Int32),
+        );
+        assert_eq!(merged.evaluate()?, expected);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the PERCENTILE_CONT and PERCENTILE_DISC ordered-set aggregate expressions
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use super::format_state_name;
+use super::ordered_aggregate::merge_sorted_runs;
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::datatypes::{DataType, Field};
+
+/// PERCENTILE_CONT and PERCENTILE_DISC aggregate expressions, computing a
+/// percentile of the values of their `WITHIN GROUP (ORDER BY ...)` expression,
+/// e.g. `PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY a)`.
+///
+/// The state is the list of the non null values sorted in the order of the
+/// aggregate, so that merging partial states is a merge of sorted runs.
+#[derive(Debug)]
+pub struct Percentile {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    percentile: f64,
+    discrete: bool,
+    descending: bool,
+    data_type: DataType,
+}
+
+impl Percentile {
+    /// Create a new PERCENTILE_CONT aggregate function, or PERCENTILE_DISC
+    /// one if `discrete`
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        percentile: f64,
+        discrete: bool,
+        descending: bool,
+        name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            expr,
+            percentile,
+            discrete,
+            descending,
+            data_type,
+        }
+    }
+
+    /// The percentile to compute, between 0 and 1
+    pub fn percentile(&self) -> f64 {
+        self.percentile
+    }
+
+    /// Whether this is PERCENTILE_DISC, returning the first value whose position
+    /// in the ordering reaches the percentile, rather than PERCENTILE_CONT,
+    /// interpolating between the adjacent values
+    pub fn is_discrete(&self) -> bool {
+        self.discrete
+    }
+
+    /// Whether the values are ordered in descending order
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+}
+
+impl AggregateExpr for Percentile {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(PercentileAccumulator {
+            percentile: self.percentile,
+            discrete: self.discrete,
+            descending: self.descending,
+            data_type: self.data_type.clone(),
+            values: vec![],
+            sorted: true,
+        }))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "percentile"),
+            DataType::List(Box::new(Field::new("item", self.data_type.clone(), true))),
+            false,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug)]
+struct PercentileAccumulator {
+    percentile: f64,
+    discrete: bool,
+    descending: bool,
+    data_type: DataType,
+    /// the non null values
+    values: Vec<ScalarValue>,
+    /// whether `values` are sorted in the order of the aggregate
+    sorted: bool,
+}
+
+impl PercentileAccumulator {
+    fn compare(&self, left: &ScalarValue, right: &ScalarValue) -> Ordering {
+        let ordering = left.partial_cmp(right).unwrap_or(Ordering::Equal);
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    fn sorted_values(&self) -> Vec<ScalarValue> {
+        let mut values = self.values.clone();
+        if !self.sorted {
+            values.sort_by(|left, right| self.compare(left, right));
+        }
+        values
+    }
+}
+
+impl Accumulator for PercentileAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![ScalarValue::List(
+            Some(Box::new(self.sorted_values())),
+            Box::new(self.data_type.clone()),
+        )])
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        if !values[0].is_null() {
+            self.values.push(values[0].clone());
+            self.sorted = false;
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        let run = match &states[0] {
+            ScalarValue::List(Some(values), _) => values.to_vec(),
+            ScalarValue::List(None, _) => return Ok(()),
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unexpected state of a percentile aggregate: {:?}",
+                    other
+                )))
+            }
+        };
+        let values = self.sorted_values();
+        self.values =
+            merge_sorted_runs(values, run, |left, right| self.compare(left, right));
+        self.sorted = true;
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let values = self.sorted_values();
+        if values.is_empty() {
+            return ScalarValue::try_from(&self.data_type);
+        }
+        if self.discrete {
+            // the first value whose cumulative distribution reaches the percentile
+            let position = (self.percentile * values.len() as f64).ceil() as usize;
+            return Ok(values[position.clamp(1, values.len()) - 1].clone());
+        }
+
+        let position = self.percentile * (values.len() - 1) as f64;
+        let lower = position.floor();
+        let lower_value = as_f64(&values[lower as usize])?;
+        let upper_value = as_f64(&values[position.ceil() as usize])?;
+        Ok(ScalarValue::Float64(Some(
+            lower_value + (upper_value - lower_value) * (position - lower),
+        )))
+    }
+}
+
+fn as_f64(value: &ScalarValue) -> Result<f64> {
+    match value {
+        ScalarValue::Float64(Some(value)) => Ok(*value),
+        other => Err(DataFusionError::Internal(format!(
+            "PERCENTILE_CONT expects Float64 values, not {:?}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use arrow::array::{ArrayRef, Float64Array};
+    use arrow::datatypes::Schema;
+
+    #[test]
+    fn percentile_update_and_merge() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Float64, true)]);
+        let cases = vec![
+            (false, false, 0.5, ScalarValue::Float64(Some(2.5))),
+            (false, false, 0.75, ScalarValue::Float64(Some(3.25))),
+            (false, true, 0.75, ScalarValue::Float64(Some(1.75))),
+            (true, false, 0.5, ScalarValue::Float64(Some(2.0))),
+            (true, true, 0.5, ScalarValue::Float64(Some(3.0))),
+            (true, false, 0.0, ScalarValue::Float64(Some(1.0))),
+        ];
+        for (discrete, descending, percentile, expected) in cases {
+            let agg = Percentile::new(
+                col("a", &schema)?,
+                percentile,
+                discrete,
+                descending,
+                "p",
+                DataType::Float64,
+            );
+            let mut first = agg.create_accumulator()?;
+            first.update_batch(&[Arc::new(Float64Array::from(vec![
+                Some(4.0),
+                None,
+                Some(1.0),
+            ])) as ArrayRef])?;
+            let mut second = agg.create_accumulator()?;
+            second.update_batch(&[
+                Arc::new(Float64Array::from(vec![3.0, 2.0])) as ArrayRef
+            ])?;
+
+            let mut merged = agg.create_accumulator()?;
+            merged.merge(&first.state()?)?;
+            merged.merge(&second.state()?)?;
+            assert_eq!(merged.evaluate()?, expected);
+        }
+
+        let empty = Percentile::new(
+            col("a", &schema)?,
+            0.5,
+            false,
+            false,
+            "p",
+            DataType::Float64,
+        )
+        .create_accumulator()?;
+        assert_eq!(empty.evaluate()?, ScalarValue::Float64(None));
+
+        Ok(())
+    }
+}
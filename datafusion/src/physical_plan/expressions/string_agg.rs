@@ -0,0 +1,199 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the STRING_AGG aggregate expression
+
+use std::any::Any;
+use std::sync::Arc;
+
+use super::format_state_name;
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::datatypes::{DataType, Field};
+
+/// STRING_AGG aggregate expression, concatenating the non null input strings
+/// separated by a delimiter, into a Utf8 or LargeUtf8 string
+#[derive(Debug)]
+pub struct StringAgg {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    delimiter: String,
+    data_type: DataType,
+}
+
+impl StringAgg {
+    /// Create a new StringAgg aggregate function
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        delimiter: impl Into<String>,
+        name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            expr,
+            delimiter: delimiter.into(),
+            data_type,
+        }
+    }
+
+    /// The delimiter between the concatenated strings
+    pub fn delimiter(&self) -> &str {
+        &self.delimiter
+    }
+}
+
+impl AggregateExpr for StringAgg {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(StringAggAccumulator {
+            delimiter: self.delimiter.clone(),
+            large: self.data_type == DataType::LargeUtf8,
+            value: None,
+        }))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "string_agg"),
+            self.data_type.clone(),
+            true,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug)]
+struct StringAggAccumulator {
+    delimiter: String,
+    /// whether the result is a LargeUtf8 string
+    large: bool,
+    value: Option<String>,
+}
+
+impl StringAggAccumulator {
+    fn append(&mut self, value: &ScalarValue) -> Result<()> {
+        match value {
+            ScalarValue::Utf8(Some(string)) | ScalarValue::LargeUtf8(Some(string)) => {
+                match &mut self.value {
+                    Some(value) => {
+                        value.push_str(&self.delimiter);
+                        value.push_str(string);
+                    }
+                    None => self.value = Some(string.clone()),
+                };
+                Ok(())
+            }
+            ScalarValue::Utf8(None) | ScalarValue::LargeUtf8(None) => Ok(()),
+            other => Err(DataFusionError::Internal(format!(
+                "STRING_AGG expects Utf8 or LargeUtf8 values, not {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn scalar(&self) -> ScalarValue {
+        if self.large {
+            ScalarValue::LargeUtf8(self.value.clone())
+        } else {
+            ScalarValue::Utf8(self.value.clone())
+        }
+    }
+}
+
+impl Accumulator for StringAggAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![self.scalar()])
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        self.append(&values[0])
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        self.append(&states[0])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(self.scalar())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use arrow::array::{ArrayRef, LargeStringArray, StringArray};
+    use arrow::datatypes::Schema;
+
+    #[test]
+    fn string_agg_update_and_merge() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Utf8, true)]);
+        let agg =
+            StringAgg::new(col("a", &schema)?, ", ", "STRING_AGG(a)", DataType::Utf8);
+
+        let mut first = agg.create_accumulator()?;
+        first.update_batch(&[Arc::new(StringArray::from(vec![
+            Some("a"),
+            None,
+            Some("b"),
+        ])) as ArrayRef])?;
+        let mut second = agg.create_accumulator()?;
+        assert_eq!(second.state()?, vec![ScalarValue::Utf8(None)]);
+        second.update(&[ScalarValue::Utf8(Some("c".to_owned()))])?;
+
+        let mut merged = agg.create_accumulator()?;
+        merged.merge(&first.state()?)?;
+        merged.merge(&second.state()?)?;
+        assert_eq!(merged.evaluate()?, ScalarValue::from("a, b, c"));
+
+        let schema = Schema::new(vec![Field::new("a", DataType::LargeUtf8, true)]);
+        let agg = StringAgg::new(
+            col("a", &schema)?,
+            "-",
+            "STRING_AGG(a)",
+            DataType::LargeUtf8,
+        );
+        let mut accumulator = agg.create_accumulator()?;
+        accumulator.update_batch(&[Arc::new(LargeStringArray::from(vec![
+            Some("a"),
+            None,
+            Some("b"),
+        ])) as ArrayRef])?;
+        assert_eq!(
+            accumulator.evaluate()?,
+            ScalarValue::LargeUtf8(Some("a-b".to_owned()))
+        );
+
+        Ok(())
+    }
+}
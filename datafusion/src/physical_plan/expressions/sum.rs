@@ -43,6 +43,7 @@ pub struct Sum {
     data_type: DataType,
     expr: Arc<dyn PhysicalExpr>,
     nullable: bool,
+    fail_on_overflow: bool,
 }
 
 /// function return type of a sum
@@ -91,8 +92,21 @@ impl Sum {
             expr,
             data_type,
             nullable: true,
+            fail_on_overflow: false,
         }
     }
+
+    /// Makes integer sums error on overflow if `fail_on_overflow`, as in the ANSI
+    /// mode, rather than wrap around in two's complement
+    pub fn with_fail_on_overflow(mut self, fail_on_overflow: bool) -> Self {
+        self.fail_on_overflow = fail_on_overflow;
+        self
+    }
+
+    /// Whether integer sums error on overflow rather than wrapping around
+    pub fn fail_on_overflow(&self) -> bool {
+        self.fail_on_overflow
+    }
 }
 
 impl AggregateExpr for Sum {
@@ -122,7 +136,17 @@ impl AggregateExpr for Sum {
     }
 
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
-        Ok(Box::new(SumAccumulator::try_new(&self.data_type)?))
+        Ok(Box::new(SumAccumulator::try_new(
+            &self.data_type,
+            self.fail_on_overflow,
+        )?))
+    }
+
+    fn row_states(&self, values: &[ArrayRef]) -> Result<Option<Vec<ArrayRef>>> {
+        if !compute::can_cast_types(values[0].data_type(), &self.data_type) {
+            return Ok(None);
+        }
+        Ok(Some(vec![compute::cast(&values[0], &self.data_type)?]))
     }
 
     fn name(&self) -> &str {
@@ -133,17 +157,39 @@ impl AggregateExpr for Sum {
 #[derive(Debug)]
 struct SumAccumulator {
     sum: ScalarValue,
+    fail_on_overflow: bool,
 }
 
 impl SumAccumulator {
     /// new sum accumulator
-    pub fn try_new(data_type: &DataType) -> Result<Self> {
+    pub fn try_new(data_type: &DataType, fail_on_overflow: bool) -> Result<Self> {
         Ok(Self {
             sum: ScalarValue::try_from(data_type)?,
+            fail_on_overflow,
         })
     }
 }
 
+// adds two integers of $TYPE, erroring on overflow if $FAIL_ON_OVERFLOW
+// and wrapping around otherwise
+macro_rules! integer_add {
+    ($LHS:expr, $RHS:expr, $TYPE:ident, $FAIL_ON_OVERFLOW:expr) => {{
+        let (lhs, rhs): ($TYPE, $TYPE) = ($LHS, $RHS);
+        match lhs.checked_add(rhs) {
+            Some(sum) => sum,
+            None if $FAIL_ON_OVERFLOW => {
+                return Err(DataFusionError::Execution(format!(
+                    "Arithmetic overflow: the sum of {} and {} is out of the range of {}",
+                    lhs,
+                    rhs,
+                    stringify!($TYPE)
+                )))
+            }
+            None => lhs.wrapping_add(rhs),
+        }
+    }};
+}
+
 // returns the new value after sum with the new values, taking nullability into account
 macro_rules! typed_sum_delta_batch {
     ($VALUES:expr, $ARRAYTYPE:ident, $SCALAR:ident) => {{
@@ -153,19 +199,53 @@ macro_rules! typed_sum_delta_batch {
     }};
 }
 
-// sums the array and returns a ScalarValue of its corresponding type.
-pub(super) fn sum_batch(values: &ArrayRef) -> Result<ScalarValue> {
+// sums the integer array into the 64 bits integer $TYPE, as the array type could
+// overflow before the sum is widened
+macro_rules! typed_integer_sum_delta_batch {
+    ($VALUES:expr, $ARRAYTYPE:ident, $SCALAR:ident, $TYPE:ident, $FAIL_ON_OVERFLOW:expr) => {{
+        let array = $VALUES.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
+        let mut delta: Option<$TYPE> = None;
+        for value in array.iter().flatten() {
+            delta = Some(integer_add!(
+                delta.unwrap_or(0),
+                value as $TYPE,
+                $TYPE,
+                $FAIL_ON_OVERFLOW
+            ));
+        }
+        ScalarValue::$SCALAR(delta)
+    }};
+}
+
+// sums the array and returns a ScalarValue of its corresponding type, or of the
+// 64 bits type of the same signedness for integers.
+pub(super) fn sum_batch(
+    values: &ArrayRef,
+    fail_on_overflow: bool,
+) -> Result<ScalarValue> {
+    macro_rules! integer_sum {
+        ($ARRAYTYPE:ident, $SCALAR:ident, $TYPE:ident) => {
+            typed_integer_sum_delta_batch!(
+                values,
+                $ARRAYTYPE,
+                $SCALAR,
+                $TYPE,
+                fail_on_overflow
+            )
+        };
+    }
+
     Ok(match values.data_type() {
         DataType::Float64 => typed_sum_delta_batch!(values, Float64Array, Float64),
         DataType::Float32 => typed_sum_delta_batch!(values, Float32Array, Float32),
-        DataType::Int64 => typed_sum_delta_batch!(values, Int64Array, Int64),
-        DataType::Int32 => typed_sum_delta_batch!(values, Int32Array, Int32),
-        DataType::Int16 => typed_sum_delta_batch!(values, Int16Array, Int16),
-        DataType::Int8 => typed_sum_delta_batch!(values, Int8Array, Int8),
-        DataType::UInt64 => typed_sum_delta_batch!(values, UInt64Array, UInt64),
-        DataType::UInt32 => typed_sum_delta_batch!(values, UInt32Array, UInt32),
-        DataType::UInt16 => typed_sum_delta_batch!(values, UInt16Array, UInt16),
-        DataType::UInt8 => typed_sum_delta_batch!(values, UInt8Array, UInt8),
+        DataType::Int64 => integer_sum!(Int64Array, Int64, i64),
+        DataType::Int32 => integer_sum!(Int32Array, Int64, i64),
+        DataType::Int16 => integer_sum!(Int16Array, Int64, i64),
+        DataType::Int8 => integer_sum!(Int8Array, Int64, i64),
+        DataType::UInt64 => integer_sum!(UInt64Array, UInt64, u64),
+        DataType::UInt32 => integer_sum!(UInt32Array, UInt64, u64),
+        DataType::UInt16 => integer_sum!(UInt16Array, UInt64, u64),
+        DataType::UInt8 => integer_sum!(UInt8Array, UInt64, u64),
         e => {
             return Err(DataFusionError::Internal(format!(
                 "Sum is not expected to receive the type {:?}",
@@ -187,7 +267,26 @@ macro_rules! typed_sum {
     }};
 }
 
-pub(super) fn sum(lhs: &ScalarValue, rhs: &ScalarValue) -> Result<ScalarValue> {
+// returns the sum of two integer scalar values, including coercion into $TYPE,
+// erroring on overflow if $FAIL_ON_OVERFLOW and wrapping around otherwise.
+macro_rules! typed_integer_sum {
+    ($OLD_VALUE:expr, $DELTA:expr, $SCALAR:ident, $TYPE:ident, $FAIL_ON_OVERFLOW:expr) => {{
+        ScalarValue::$SCALAR(match ($OLD_VALUE, $DELTA) {
+            (None, None) => None,
+            (Some(a), None) => Some(*a),
+            (None, Some(b)) => Some(*b as $TYPE),
+            (Some(a), Some(b)) => {
+                Some(integer_add!(*a, *b as $TYPE, $TYPE, $FAIL_ON_OVERFLOW))
+            }
+        })
+    }};
+}
+
+pub(crate) fn sum(
+    lhs: &ScalarValue,
+    rhs: &ScalarValue,
+    fail_on_overflow: bool,
+) -> Result<ScalarValue> {
     Ok(match (lhs, rhs) {
         // float64 coerces everything to f64
         (ScalarValue::Float64(lhs), ScalarValue::Float64(rhs)) => {
@@ -226,29 +325,29 @@ pub(super) fn sum(lhs: &ScalarValue, rhs: &ScalarValue) -> Result<ScalarValue> {
         }
         // u64 coerces u* to u64
         (ScalarValue::UInt64(lhs), ScalarValue::UInt64(rhs)) => {
-            typed_sum!(lhs, rhs, UInt64, u64)
+            typed_integer_sum!(lhs, rhs, UInt64, u64, fail_on_overflow)
         }
         (ScalarValue::UInt64(lhs), ScalarValue::UInt32(rhs)) => {
-            typed_sum!(lhs, rhs, UInt64, u64)
+            typed_integer_sum!(lhs, rhs, UInt64, u64, fail_on_overflow)
         }
         (ScalarValue::UInt64(lhs), ScalarValue::UInt16(rhs)) => {
-            typed_sum!(lhs, rhs, UInt64, u64)
+            typed_integer_sum!(lhs, rhs, UInt64, u64, fail_on_overflow)
         }
         (ScalarValue::UInt64(lhs), ScalarValue::UInt8(rhs)) => {
-            typed_sum!(lhs, rhs, UInt64, u64)
+            typed_integer_sum!(lhs, rhs, UInt64, u64, fail_on_overflow)
         }
         // i64 coerces i* to u64
         (ScalarValue::Int64(lhs), ScalarValue::Int64(rhs)) => {
-            typed_sum!(lhs, rhs, Int64, i64)
+            typed_integer_sum!(lhs, rhs, Int64, i64, fail_on_overflow)
         }
         (ScalarValue::Int64(lhs), ScalarValue::Int32(rhs)) => {
-            typed_sum!(lhs, rhs, Int64, i64)
+            typed_integer_sum!(lhs, rhs, Int64, i64, fail_on_overflow)
         }
         (ScalarValue::Int64(lhs), ScalarValue::Int16(rhs)) => {
-            typed_sum!(lhs, rhs, Int64, i64)
+            typed_integer_sum!(lhs, rhs, Int64, i64, fail_on_overflow)
         }
         (ScalarValue::Int64(lhs), ScalarValue::Int8(rhs)) => {
-            typed_sum!(lhs, rhs, Int64, i64)
+            typed_integer_sum!(lhs, rhs, Int64, i64, fail_on_overflow)
         }
         e => {
             return Err(DataFusionError::Internal(format!(
@@ -262,13 +361,14 @@ pub(super) fn sum(lhs: &ScalarValue, rhs: &ScalarValue) -> Result<ScalarValue> {
 impl Accumulator for SumAccumulator {
     fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
         let values = &values[0];
-        self.sum = sum(&self.sum, &sum_batch(values)?)?;
+        let delta = sum_batch(values, self.fail_on_overflow)?;
+        self.sum = sum(&self.sum, &delta, self.fail_on_overflow)?;
         Ok(())
     }
 
     fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
         // sum(v1, v2, v3) = v1 + v2 + v3
-        self.sum = sum(&self.sum, &values[0])?;
+        self.sum = sum(&self.sum, &values[0], self.fail_on_overflow)?;
         Ok(())
     }
 
@@ -380,6 +480,28 @@ mod tests {
         )
     }
 
+    #[test]
+    fn sum_integer_overflow() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, false)]);
+        let a: ArrayRef = Arc::new(Int64Array::from(vec![i64::MAX, 1]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![a])?;
+
+        // wraps around by default
+        let agg = Arc::new(Sum::new(col("a", &schema)?, "SUM(a)", DataType::Int64));
+        assert_eq!(aggregate(&batch, agg)?, ScalarValue::Int64(Some(i64::MIN)));
+
+        // and errors in the ANSI mode, when summing batches as well as merging sums
+        let agg = Arc::new(
+            Sum::new(col("a", &schema)?, "SUM(a)", DataType::Int64)
+                .with_fail_on_overflow(true),
+        );
+        assert!(aggregate(&batch, agg.clone()).is_err());
+        let mut accum = agg.create_accumulator()?;
+        accum.merge(&[ScalarValue::Int64(Some(i64::MAX))])?;
+        assert!(accum.merge(&[ScalarValue::Int64(Some(1))]).is_err());
+        Ok(())
+    }
+
     fn aggregate(
         batch: &RecordBatch,
         agg: Arc<dyn AggregateExpr>,
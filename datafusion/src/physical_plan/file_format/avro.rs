@@ -19,8 +19,12 @@
 #[cfg(feature = "avro")]
 use crate::avro_to_arrow;
 use crate::error::{DataFusionError, Result};
+#[cfg(feature = "avro")]
+use crate::physical_plan::{metrics::BaselineMetrics, stream::ObservedStream};
 use crate::physical_plan::{
-    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
+    metrics::{ExecutionPlanMetricsSet, MetricsSet},
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+    Statistics,
 };
 use arrow::datatypes::SchemaRef;
 #[cfg(feature = "avro")]
@@ -30,6 +34,12 @@ use async_trait::async_trait;
 use std::any::Any;
 use std::sync::Arc;
 
+#[cfg(feature = "avro")]
+use crate::datasource::FileRange;
+use crate::datasource::PartitionedFile;
+#[cfg(feature = "avro")]
+use crate::execution::io_runtime::spawn_io_stream;
+
 #[cfg(feature = "avro")]
 use super::file_stream::{BatchIter, FileStream};
 use super::PhysicalPlanConfig;
@@ -40,6 +50,8 @@ pub struct AvroExec {
     base_config: PhysicalPlanConfig,
     projected_statistics: Statistics,
     projected_schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl AvroExec {
@@ -51,12 +63,20 @@ impl AvroExec {
             base_config,
             projected_schema,
             projected_statistics,
+            metrics: ExecutionPlanMetricsSet::new(),
         }
     }
     /// Ref to the base configs
     pub fn base_config(&self) -> &PhysicalPlanConfig {
         &self.base_config
     }
+
+    /// A copy of this scan reading `file_groups` instead, one partition per group
+    pub fn with_file_groups(&self, file_groups: Vec<Vec<PartitionedFile>>) -> Self {
+        let mut exec = self.clone();
+        exec.base_config.file_groups = file_groups;
+        exec
+    }
 }
 
 #[async_trait]
@@ -70,7 +90,7 @@ impl ExecutionPlan for AvroExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        self.base_config.output_partitioning()
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -106,7 +126,7 @@ impl ExecutionPlan for AvroExec {
         let file_schema = Arc::clone(&self.base_config.file_schema);
 
         // The avro reader cannot limit the number of records, so `remaining` is ignored.
-        let fun = move |file, _remaining: &Option<usize>| {
+        let fun = move |file, _remaining: &Option<usize>, _range: &Option<FileRange>| {
             let reader_res = avro_to_arrow::Reader::try_new(
                 file,
                 Arc::clone(&file_schema),
@@ -121,14 +141,23 @@ impl ExecutionPlan for AvroExec {
             }
         };
 
-        Ok(Box::pin(FileStream::new(
+        let stream = Box::pin(FileStream::new(
             Arc::clone(&self.base_config.object_store),
             self.base_config.file_groups[partition].clone(),
             fun,
             Arc::clone(&self.projected_schema),
             self.base_config.limit,
             self.base_config.table_partition_cols.clone(),
-        )))
+        ));
+        // the files are read and decoded while polling the stream
+        Ok(spawn_io_stream(Box::pin(ObservedStream::new_with_compute(
+            stream,
+            BaselineMetrics::new(&self.metrics, partition),
+        ))))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
     }
 
     fn fmt_as(
@@ -179,9 +208,11 @@ mod tests {
                 .await?,
             statistics: Statistics::default(),
             projection: Some(vec![0, 1, 2]),
+            nested_projection: vec![],
             batch_size: 1024,
             limit: None,
             table_partition_cols: vec![],
+            bucket_columns: None,
         });
         assert_eq!(avro_exec.output_partitioning().partition_count(), 1);
 
@@ -236,6 +267,7 @@ mod tests {
             // select specific columns of the files as well as the partitioning
             // column which is supposed to be the last column in the table schema.
             projection: Some(vec![0, 1, file_schema.fields().len(), 2]),
+            nested_projection: vec![],
             object_store: Arc::new(LocalFileSystem {}),
             file_groups: vec![vec![partitioned_file]],
             file_schema: file_schema,
@@ -243,6 +275,7 @@ mod tests {
             batch_size: 1024,
             limit: None,
             table_partition_cols: vec!["date".to_owned()],
+            bucket_columns: None,
         });
         assert_eq!(avro_exec.output_partitioning().partition_count(), 1);
 
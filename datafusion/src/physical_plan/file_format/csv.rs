@@ -17,9 +17,14 @@
 
 //! Execution plan for reading CSV files
 
+use crate::datasource::{FileRange, PartitionedFile};
 use crate::error::{DataFusionError, Result};
+use crate::execution::io_runtime::spawn_io_stream;
 use crate::physical_plan::{
-    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
+    metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
+    stream::ObservedStream,
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+    Statistics,
 };
 
 use arrow::csv;
@@ -40,6 +45,8 @@ pub struct CsvExec {
     projected_schema: SchemaRef,
     has_header: bool,
     delimiter: u8,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl CsvExec {
@@ -53,6 +60,7 @@ impl CsvExec {
             projected_statistics,
             has_header,
             delimiter,
+            metrics: ExecutionPlanMetricsSet::new(),
         }
     }
 
@@ -68,6 +76,13 @@ impl CsvExec {
     pub fn delimiter(&self) -> u8 {
         self.delimiter
     }
+
+    /// A copy of this scan reading `file_groups` instead, one partition per group
+    pub fn with_file_groups(&self, file_groups: Vec<Vec<PartitionedFile>>) -> Self {
+        let mut exec = self.clone();
+        exec.base_config.file_groups = file_groups;
+        exec
+    }
 }
 
 #[async_trait]
@@ -84,7 +99,7 @@ impl ExecutionPlan for CsvExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        self.base_config.output_partitioning()
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -112,9 +127,11 @@ impl ExecutionPlan for CsvExec {
         let file_projection = self.base_config.file_column_projection_indices();
         let has_header = self.has_header;
         let delimiter = self.delimiter;
-        let start_line = if has_header { 1 } else { 0 };
 
-        let fun = move |file, remaining: &Option<usize>| {
+        let fun = move |file, remaining: &Option<usize>, range: &Option<FileRange>| {
+            // only the range at the start of the file contains the header
+            let has_header = has_header && range.map(|r| r.start == 0).unwrap_or(true);
+            let start_line = if has_header { 1 } else { 0 };
             let bounds = remaining.map(|x| (0, x + start_line));
             Box::new(csv::Reader::new(
                 file,
@@ -127,14 +144,23 @@ impl ExecutionPlan for CsvExec {
             )) as BatchIter
         };
 
-        Ok(Box::pin(FileStream::new(
+        let stream = Box::pin(FileStream::new(
             Arc::clone(&self.base_config.object_store),
             self.base_config.file_groups[partition].clone(),
             fun,
             Arc::clone(&self.projected_schema),
             self.base_config.limit,
             self.base_config.table_partition_cols.clone(),
-        )))
+        ));
+        // the files are read and decoded while polling the stream
+        Ok(spawn_io_stream(Box::pin(ObservedStream::new_with_compute(
+            stream,
+            BaselineMetrics::new(&self.metrics, partition),
+        ))))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
     }
 
     fn fmt_as(
@@ -165,7 +191,10 @@ impl ExecutionPlan for CsvExec {
 mod tests {
     use super::*;
     use crate::{
-        datasource::object_store::local::{local_unpartitioned_file, LocalFileSystem},
+        datasource::{
+            object_store::local::{local_unpartitioned_file, LocalFileSystem},
+            PartitionedFile,
+        },
         scalar::ScalarValue,
         test_util::aggr_test_schema,
     };
@@ -184,9 +213,11 @@ mod tests {
                 file_groups: vec![vec![local_unpartitioned_file(path)]],
                 statistics: Statistics::default(),
                 projection: Some(vec![0, 2, 4]),
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -230,9 +261,11 @@ mod tests {
                 file_groups: vec![vec![local_unpartitioned_file(path)]],
                 statistics: Statistics::default(),
                 projection: None,
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: Some(5),
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -279,6 +312,7 @@ mod tests {
                 // we should be able to project on the partition column
                 // wich is supposed to be after the file fields
                 projection: Some(vec![0, file_schema.fields().len()]),
+                nested_projection: vec![],
                 object_store: Arc::new(LocalFileSystem {}),
                 file_schema,
                 file_groups: vec![vec![partitioned_file]],
@@ -286,6 +320,7 @@ mod tests {
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec!["date".to_owned()],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -314,4 +349,60 @@ mod tests {
         crate::assert_batches_eq!(expected, &[batch.slice(0, 5)]);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn csv_exec_with_file_ranges() -> Result<()> {
+        let file_schema = aggr_test_schema();
+        let testdata = crate::test_util::arrow_test_data();
+        let filename = "aggregate_test_100.csv";
+        let path = format!("{}/csv/{}", testdata, filename);
+        let file = local_unpartitioned_file(path);
+        let size = file.file_meta.size();
+
+        // split the file into 3 ranges that are read by different partitions
+        let range_size = size / 3 + 1;
+        let file_groups = (0..size)
+            .step_by(range_size as usize)
+            .map(|start| {
+                vec![PartitionedFile {
+                    range: Some(FileRange {
+                        start,
+                        end: (start + range_size).min(size),
+                    }),
+                    ..file.clone()
+                }]
+            })
+            .collect::<Vec<_>>();
+        let csv = CsvExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_schema,
+                file_groups,
+                statistics: Statistics::default(),
+                projection: Some(vec![0]),
+                nested_projection: vec![],
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+                bucket_columns: None,
+            },
+            true,
+            b',',
+        );
+        assert_eq!(3, csv.output_partitioning().partition_count());
+
+        let mut num_rows = 0;
+        for partition in 0..3 {
+            let mut stream = csv.execute(partition).await?;
+            while let Some(batch) = stream.next().await {
+                let batch = batch?;
+                assert!(batch.num_rows() > 0);
+                num_rows += batch.num_rows();
+            }
+        }
+        // every line is read exactly once and the header only once
+        assert_eq!(100, num_rows);
+
+        Ok(())
+    }
 }
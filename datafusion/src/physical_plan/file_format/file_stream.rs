@@ -22,7 +22,11 @@
 //! compliant with the `SendableRecordBatchStream` trait.
 
 use crate::{
-    datasource::{object_store::ObjectStore, PartitionedFile},
+    datasource::{
+        object_store::{ObjectReader, ObjectStore},
+        FileRange, PartitionedFile,
+    },
+    error::Result,
     physical_plan::RecordBatchStream,
     scalar::ScalarValue,
 };
@@ -33,7 +37,7 @@ use arrow::{
 };
 use futures::Stream;
 use std::{
-    io::Read,
+    io::{self, BufRead, BufReader, Read},
     iter,
     pin::Pin,
     sync::Arc,
@@ -45,15 +49,22 @@ use super::PartitionColumnProjector;
 pub type FileIter = Box<dyn Iterator<Item = PartitionedFile> + Send + Sync>;
 pub type BatchIter = Box<dyn Iterator<Item = ArrowResult<RecordBatch>> + Send + Sync>;
 
-/// A closure that creates a file format reader (iterator over `RecordBatch`) from a `Read` object
-/// and an optional number of required records.
+/// A closure that creates a file format reader (iterator over `RecordBatch`) from a `Read` object,
+/// an optional number of required records and the optional byte range of the file being read.
 pub trait FormatReaderOpener:
-    FnMut(Box<dyn Read + Send + Sync>, &Option<usize>) -> BatchIter + Send + Unpin + 'static
+    FnMut(Box<dyn Read + Send + Sync>, &Option<usize>, &Option<FileRange>) -> BatchIter
+    + Send
+    + Unpin
+    + 'static
 {
 }
 
 impl<T> FormatReaderOpener for T where
-    T: FnMut(Box<dyn Read + Send + Sync>, &Option<usize>) -> BatchIter
+    T: FnMut(
+            Box<dyn Read + Send + Sync>,
+            &Option<usize>,
+            &Option<FileRange>,
+        ) -> BatchIter
         + Send
         + Unpin
         + 'static
@@ -121,12 +132,16 @@ impl<F: FormatReaderOpener> FileStream<F> {
             None => match self.file_iter.next() {
                 Some(f) => {
                     self.partition_values = f.partition_values;
+                    let range = f.range;
                     self.object_store
                         .file_reader(f.file_meta.sized_file)
-                        .and_then(|r| r.sync_reader())
+                        .and_then(|r| match &range {
+                            Some(range) => line_range_reader(r.as_ref(), range),
+                            None => r.sync_reader(),
+                        })
                         .map_err(|e| ArrowError::ExternalError(Box::new(e)))
                         .and_then(|f| {
-                            self.batch_iter = (self.file_reader)(f, &self.remain);
+                            self.batch_iter = (self.file_reader)(f, &self.remain, &range);
                             self.next_batch().transpose()
                         })
                         .transpose()
@@ -137,6 +152,83 @@ impl<F: FormatReaderOpener> FileStream<F> {
     }
 }
 
+/// Open a reader over the lines of a newline delimited file that start
+/// within `range`.
+fn line_range_reader(
+    reader: &dyn ObjectReader,
+    range: &FileRange,
+) -> Result<Box<dyn Read + Send + Sync>> {
+    // start one byte early to detect whether a line starts exactly at `range.start`
+    let offset = range.start.saturating_sub(1);
+    let length = reader.length().saturating_sub(offset) as usize;
+    let inner = reader.sync_chunk_reader(offset, length)?;
+    Ok(Box::new(LineRangeReader::try_new(inner, offset, *range)?))
+}
+
+/// A reader that only yields the lines that start within a byte range.
+///
+/// The first (partial) line is skipped unless the range starts at the
+/// beginning of the file, and the last line is read past the end of the
+/// range until its terminating newline, so that consecutive ranges of a
+/// file read each line exactly once.
+struct LineRangeReader<R: Read> {
+    inner: BufReader<R>,
+    /// The offset in the file of the next byte returned by `inner`
+    pos: u64,
+    /// The end of the range in the file
+    end: u64,
+    /// True once the last line of the range was fully read
+    done: bool,
+}
+
+impl<R: Read> LineRangeReader<R> {
+    /// Create a reader over `inner`, which reads the file from `offset`
+    fn try_new(inner: R, offset: u64, range: FileRange) -> io::Result<Self> {
+        let mut reader = Self {
+            inner: BufReader::new(inner),
+            pos: offset,
+            end: range.end,
+            done: false,
+        };
+        if range.start > 0 {
+            // skip the line that started before the range
+            let mut skipped = vec![];
+            reader.pos += reader.inner.read_until(b'\n', &mut skipped)? as u64;
+        }
+        reader.done = reader.pos >= reader.end;
+        Ok(reader)
+    }
+}
+
+impl<R: Read> Read for LineRangeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.pos < self.end {
+            let max = buf.len().min((self.end - self.pos) as usize);
+            let read = self.inner.read(&mut buf[..max])?;
+            self.pos += read as u64;
+            // a line starting at `end` belongs to the next range
+            self.done = read == 0 || (self.pos == self.end && buf[read - 1] == b'\n');
+            return Ok(read);
+        }
+
+        // finish the line that started within the range
+        let available = self.inner.fill_buf()?;
+        let (len, eol) = match available.iter().position(|b| *b == b'\n') {
+            Some(idx) if idx < buf.len() => (idx + 1, true),
+            _ => (available.len().min(buf.len()), false),
+        };
+        buf[..len].copy_from_slice(&available[..len]);
+        self.inner.consume(len);
+        self.pos += len as u64;
+        self.done = eol || len == 0;
+        Ok(len)
+    }
+}
+
 impl<F: FormatReaderOpener> Stream for FileStream<F> {
     type Item = ArrowResult<RecordBatch>;
 
@@ -199,7 +291,7 @@ mod tests {
 
         let source_schema = records[0].schema();
 
-        let reader = move |_file, _remain: &Option<usize>| {
+        let reader = move |_file, _remain: &Option<usize>, _range: &Option<FileRange>| {
             // this reader returns the same batch regardless of the file
             Box::new(records.clone().into_iter().map(Ok)) as BatchIter
         };
@@ -266,6 +358,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn read_line_ranges() -> Result<()> {
+        let data = b"a,1\nbb,22\nccc,333\ndddd,4444\n";
+        let size = data.len() as u64;
+
+        // whatever the range boundaries, each line should be read exactly once
+        for range_size in 1..=size {
+            let mut lines = String::new();
+            for start in (0..size).step_by(range_size as usize) {
+                let range = FileRange {
+                    start,
+                    end: (start + range_size).min(size),
+                };
+                let offset = start.saturating_sub(1);
+                let mut reader =
+                    LineRangeReader::try_new(&data[offset as usize..], offset, range)?;
+                reader.read_to_string(&mut lines)?;
+            }
+            assert_eq!(std::str::from_utf8(data).unwrap(), lines);
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn with_limit_at_middle_of_batch() -> Result<()> {
         let batches = create_and_collect(Some(6)).await;
@@ -19,13 +19,19 @@
 use async_trait::async_trait;
 
 use crate::error::{DataFusionError, Result};
+use crate::execution::io_runtime::spawn_io_stream;
 use crate::physical_plan::{
-    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
+    metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
+    stream::ObservedStream,
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+    Statistics,
 };
 use arrow::{datatypes::SchemaRef, json};
 use std::any::Any;
 use std::sync::Arc;
 
+use crate::datasource::FileRange;
+
 use super::file_stream::{BatchIter, FileStream};
 use super::PhysicalPlanConfig;
 
@@ -35,6 +41,8 @@ pub struct NdJsonExec {
     base_config: PhysicalPlanConfig,
     projected_statistics: Statistics,
     projected_schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl NdJsonExec {
@@ -46,8 +54,14 @@ impl NdJsonExec {
             base_config,
             projected_schema,
             projected_statistics,
+            metrics: ExecutionPlanMetricsSet::new(),
         }
     }
+
+    /// Ref to the base configs
+    pub fn base_config(&self) -> &PhysicalPlanConfig {
+        &self.base_config
+    }
 }
 
 #[async_trait]
@@ -61,7 +75,7 @@ impl ExecutionPlan for NdJsonExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        self.base_config.output_partitioning()
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -89,7 +103,7 @@ impl ExecutionPlan for NdJsonExec {
         let file_schema = Arc::clone(&self.base_config.file_schema);
 
         // The json reader cannot limit the number of records, so `remaining` is ignored.
-        let fun = move |file, _remaining: &Option<usize>| {
+        let fun = move |file, _remaining: &Option<usize>, _range: &Option<FileRange>| {
             Box::new(json::Reader::new(
                 file,
                 Arc::clone(&file_schema),
@@ -98,14 +112,23 @@ impl ExecutionPlan for NdJsonExec {
             )) as BatchIter
         };
 
-        Ok(Box::pin(FileStream::new(
+        let stream = Box::pin(FileStream::new(
             Arc::clone(&self.base_config.object_store),
             self.base_config.file_groups[partition].clone(),
             fun,
             Arc::clone(&self.projected_schema),
             self.base_config.limit,
             self.base_config.table_partition_cols.clone(),
-        )))
+        ));
+        // the files are read and decoded while polling the stream
+        Ok(spawn_io_stream(Box::pin(ObservedStream::new_with_compute(
+            stream,
+            BaselineMetrics::new(&self.metrics, partition),
+        ))))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
     }
 
     fn fmt_as(
@@ -162,9 +185,11 @@ mod tests {
             file_schema: infer_schema(path).await?,
             statistics: Statistics::default(),
             projection: None,
+            nested_projection: vec![],
             batch_size: 1024,
             limit: Some(3),
             table_partition_cols: vec![],
+            bucket_columns: None,
         });
 
         // TODO: this is not where schema inference should be tested
@@ -216,9 +241,11 @@ mod tests {
             file_schema: infer_schema(path).await?,
             statistics: Statistics::default(),
             projection: Some(vec![0, 2]),
+            nested_projection: vec![],
             batch_size: 1024,
             limit: None,
             table_partition_cols: vec![],
+            bucket_columns: None,
         });
         let inferred_schema = exec.schema();
         assert_eq!(inferred_schema.fields().len(), 2);
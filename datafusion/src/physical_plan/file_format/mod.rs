@@ -36,7 +36,9 @@ pub use csv::CsvExec;
 pub use json::NdJsonExec;
 
 use crate::{
-    datasource::{object_store::ObjectStore, PartitionedFile},
+    datasource::{
+        nested_projection::project_column, object_store::ObjectStore, PartitionedFile,
+    },
     scalar::ScalarValue,
 };
 use lazy_static::lazy_static;
@@ -47,7 +49,9 @@ use std::{
     vec,
 };
 
-use super::{ColumnStatistics, Statistics};
+use super::expressions::Column;
+use super::sample::is_block_sampled;
+use super::{ColumnStatistics, Partitioning, PhysicalExpr, Statistics};
 
 lazy_static! {
     /// The datatype used for all partitioning columns for now
@@ -70,15 +74,76 @@ pub struct PhysicalPlanConfig {
     /// Columns on which to project the data. Indexes that are higher than the
     /// number of columns of `file_schema` refer to `table_partition_cols`.
     pub projection: Option<Vec<usize>>,
+    /// Paths of the fields of the struct columns to read, see
+    /// [`nested_projection`](crate::datasource::nested_projection). It is empty
+    /// unless the format supports nested projections.
+    pub nested_projection: Vec<Vec<String>>,
     /// The maximum number of records per arrow column
     pub batch_size: usize,
     /// The minimum number of records required from this source plan
     pub limit: Option<usize>,
     /// The partitioning column names
     pub table_partition_cols: Vec<String>,
+    /// The columns that the files are bucketed by. If set, every file group holds the
+    /// rows of one bucket, i.e. the rows are hash partitioned by these columns like
+    /// `Partitioning::Hash` does into as many partitions as there are file groups.
+    pub bucket_columns: Option<Vec<String>>,
 }
 
 impl PhysicalPlanConfig {
+    /// The partitioning of the output of a scan of the file groups
+    fn output_partitioning(&self) -> Partitioning {
+        let partition_count = self.file_groups.len();
+        let bucket_columns = match &self.bucket_columns {
+            // a plan can't be hash partitioned into no partitions
+            Some(bucket_columns) if partition_count > 0 => bucket_columns,
+            _ => return Partitioning::UnknownPartitioning(partition_count),
+        };
+        // the partitioning is only known if all bucket columns are projected
+        let (schema, _) = self.project();
+        let exprs = bucket_columns
+            .iter()
+            .map(|name| {
+                schema.index_of(name).ok().map(|index| {
+                    Arc::new(Column::new(name, index)) as Arc<dyn PhysicalExpr>
+                })
+            })
+            .collect::<Option<Vec<_>>>();
+        match exprs {
+            Some(exprs) => Partitioning::Hash(exprs, partition_count),
+            None => Partitioning::UnknownPartitioning(partition_count),
+        }
+    }
+
+    /// Returns a copy of this configuration that only scans a sample of the files,
+    /// or of the ranges of files that are split, where each is kept with
+    /// probability `fraction`. The number of file groups is not changed.
+    pub fn sample_files(&self, fraction: f64, seed: u64) -> Self {
+        let file_groups = self
+            .file_groups
+            .iter()
+            .map(|files| {
+                files
+                    .iter()
+                    .filter(|file| {
+                        let key = (
+                            file.file_meta.path(),
+                            file.range.as_ref().map(|range| range.start),
+                        );
+                        is_block_sampled(key, fraction, seed)
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+        Self {
+            file_groups,
+            // the statistics of the skipped files are unknown
+            statistics: Statistics::default(),
+            ..self.clone()
+        }
+    }
+
     /// Project the schema and the statistics on the given column indices
     fn project(&self) -> (SchemaRef, Statistics) {
         if self.projection.is_none() && self.table_partition_cols.is_empty() {
@@ -96,7 +161,10 @@ impl PhysicalPlanConfig {
         let mut table_cols_stats = vec![];
         for idx in proj_iter {
             if idx < self.file_schema.fields().len() {
-                table_fields.push(self.file_schema.field(idx).clone());
+                table_fields.push(project_column(
+                    self.file_schema.field(idx),
+                    &self.nested_projection,
+                ));
                 if let Some(file_cols_stats) = &self.statistics.column_statistics {
                     table_cols_stats.push(file_cols_stats[idx].clone())
                 } else {
@@ -109,8 +177,12 @@ impl PhysicalPlanConfig {
                     DEFAULT_PARTITION_COLUMN_DATATYPE.clone(),
                     false,
                 ));
-                // TODO provide accurate stat for partition column (#1186)
-                table_cols_stats.push(ColumnStatistics::default())
+                // the partition values are never null, so that e.g. counts of a
+                // partition column can be answered from the number of rows
+                table_cols_stats.push(ColumnStatistics {
+                    null_count: Some(0),
+                    ..ColumnStatistics::default()
+                })
             }
         }
 
@@ -483,8 +555,10 @@ mod tests {
             limit: None,
             object_store: TestObjectStore::new_arc(&[]),
             projection,
+            nested_projection: vec![],
             statistics,
             table_partition_cols,
+            bucket_columns: None,
         }
     }
 }
@@ -18,19 +18,27 @@
 //! Execution plan for reading Parquet files
 
 use std::fmt;
+use std::io::Read;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{any::Any, convert::TryInto};
 
-use crate::datasource::file_format::parquet::ChunkObjectReader;
+use crate::datasource::file_format::parquet::{
+    list_to_map, ChunkObjectReader, MapAsListFileReader,
+};
+use crate::datasource::nested_projection::{project_array, project_column};
 use crate::datasource::object_store::ObjectStore;
 use crate::datasource::PartitionedFile;
+use crate::execution::io_runtime::spawn_io;
 use crate::{
     error::{DataFusionError, Result},
     logical_plan::{Column, Expr},
     physical_optimizer::pruning::{PruningPredicate, PruningStatistics},
     physical_plan::{
+        cancellation::CancellationToken,
         file_format::PhysicalPlanConfig,
         metrics::{self, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet},
+        sample::is_block_sampled,
         stream::RecordBatchReceiverStream,
         DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
         Statistics,
@@ -40,24 +48,23 @@ use crate::{
 
 use arrow::{
     array::ArrayRef,
-    datatypes::{Schema, SchemaRef},
+    datatypes::{DataType, Field, Schema, SchemaRef},
     error::{ArrowError, Result as ArrowResult},
     record_batch::RecordBatch,
 };
 use log::debug;
+use parquet::errors::Result as ParquetResult;
 use parquet::file::{
     metadata::RowGroupMetaData,
-    reader::{FileReader, SerializedFileReader},
+    reader::{ChunkReader, FileReader, Length, SerializedFileReader},
     statistics::Statistics as ParquetStatistics,
 };
+use parquet::schema::types::SchemaDescriptor;
 
 use fmt::Debug;
 use parquet::arrow::{ArrowReader, ParquetFileArrowReader};
 
-use tokio::{
-    sync::mpsc::{channel, Receiver, Sender},
-    task,
-};
+use tokio::sync::mpsc::{channel, Receiver, Sender};
 
 use async_trait::async_trait;
 
@@ -73,6 +80,8 @@ pub struct ParquetExec {
     metrics: ExecutionPlanMetricsSet,
     /// Optional predicate builder
     predicate_builder: Option<PruningPredicate>,
+    /// Optional fraction and seed of a sample of the row groups to read
+    row_group_sample: Option<(f64, u64)>,
 }
 
 /// Stores metrics about the parquet execution for a particular parquet file
@@ -84,6 +93,23 @@ struct ParquetFileMetrics {
     pub row_groups_pruned: metrics::Count,
 }
 
+/// Stores metrics about the parquet execution of a partition, over all its files
+#[derive(Debug, Clone)]
+struct ParquetPartitionMetrics {
+    /// Number of files listed for the partition
+    pub files_listed: metrics::Count,
+    /// Number of files skipped as the predicate pruned all their row groups
+    pub files_pruned: metrics::Count,
+    /// Number of row groups read after pruning
+    pub row_groups_scanned: metrics::Count,
+    /// Number of bytes read from the object store
+    pub bytes_scanned: metrics::Count,
+    /// Time spent waiting for the object store to return the bytes
+    pub io_time: metrics::Time,
+    /// Time spent decoding the bytes into batches, excluding `io_time`
+    pub decode_time: metrics::Time,
+}
+
 impl ParquetExec {
     /// Create a new Parquet reader execution plan provided file list and schema.
     /// Even if `limit` is set, ParquetExec rounds up the number of records to the next `batch_size`.
@@ -120,13 +146,30 @@ impl ParquetExec {
             projected_statistics,
             metrics,
             predicate_builder,
+            row_group_sample: None,
         }
     }
 
+    /// Only read a sample of the row groups, where each row group is read with
+    /// probability `fraction`, and the others are skipped
+    pub fn with_row_group_sample(mut self, fraction: f64, seed: u64) -> Self {
+        self.row_group_sample = Some((fraction, seed));
+        // the statistics of the skipped row groups are unknown
+        self.projected_statistics = Statistics::default();
+        self
+    }
+
     /// Ref to the base configs
     pub fn base_config(&self) -> &PhysicalPlanConfig {
         &self.base_config
     }
+
+    /// A copy of this scan reading `file_groups` instead, one partition per group
+    pub fn with_file_groups(&self, file_groups: Vec<Vec<PartitionedFile>>) -> Self {
+        let mut exec = self.clone();
+        exec.base_config.file_groups = file_groups;
+        exec
+    }
 }
 
 impl ParquetFileMetrics {
@@ -151,6 +194,65 @@ impl ParquetFileMetrics {
     }
 }
 
+impl ParquetPartitionMetrics {
+    /// Create new metrics
+    pub fn new(partition: usize, metrics: &ExecutionPlanMetricsSet) -> Self {
+        Self {
+            files_listed: MetricBuilder::new(metrics).counter("files_listed", partition),
+            files_pruned: MetricBuilder::new(metrics).counter("files_pruned", partition),
+            row_groups_scanned: MetricBuilder::new(metrics)
+                .counter("row_groups_scanned", partition),
+            bytes_scanned: MetricBuilder::new(metrics)
+                .counter("bytes_scanned", partition),
+            io_time: MetricBuilder::new(metrics).subset_time("io_time", partition),
+            decode_time: MetricBuilder::new(metrics)
+                .subset_time("decode_time", partition),
+        }
+    }
+}
+
+/// A [`ChunkReader`] recording the bytes it reads from the object store, and the
+/// time spent waiting for them
+struct MeteredChunkReader {
+    inner: ChunkObjectReader,
+    bytes_scanned: metrics::Count,
+    io_time: metrics::Time,
+}
+
+impl Length for MeteredChunkReader {
+    fn len(&self) -> u64 {
+        self.inner.len()
+    }
+}
+
+impl ChunkReader for MeteredChunkReader {
+    type T = Box<dyn Read + Send + Sync>;
+
+    fn get_read(&self, start: u64, length: usize) -> ParquetResult<Self::T> {
+        let _timer = self.io_time.timer();
+        Ok(Box::new(MeteredRead {
+            inner: self.inner.get_read(start, length)?,
+            bytes_scanned: self.bytes_scanned.clone(),
+            io_time: self.io_time.clone(),
+        }))
+    }
+}
+
+struct MeteredRead {
+    inner: Box<dyn Read + Send + Sync>,
+    bytes_scanned: metrics::Count,
+    io_time: metrics::Time,
+}
+
+impl Read for MeteredRead {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let _timer = self.io_time.timer();
+        let read = self.inner.read(buf)?;
+        self.bytes_scanned.add(read);
+        Ok(read)
+    }
+}
+
 #[async_trait]
 impl ExecutionPlan for ParquetExec {
     /// Return a reference to Any that can be used for downcasting
@@ -169,7 +271,7 @@ impl ExecutionPlan for ParquetExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        self.base_config.output_partitioning()
     }
 
     fn with_new_children(
@@ -200,7 +302,18 @@ impl ExecutionPlan for ParquetExec {
             Some(proj) => proj,
             None => (0..self.base_config.file_schema.fields().len()).collect(),
         };
+        // the file columns to read, with only the projected fields of the structs
+        let projected_fields: Vec<_> = projection
+            .iter()
+            .map(|i| {
+                project_column(
+                    self.base_config.file_schema.field(*i),
+                    &self.base_config.nested_projection,
+                )
+            })
+            .collect();
         let predicate_builder = self.predicate_builder.clone();
+        let row_group_sample = self.row_group_sample;
         let batch_size = self.base_config.batch_size;
         let limit = self.base_config.limit;
         let object_store = Arc::clone(&self.base_config.object_store);
@@ -208,19 +321,22 @@ impl ExecutionPlan for ParquetExec {
             Arc::clone(&self.projected_schema),
             &self.base_config.table_partition_cols,
         );
+        let cancellation = CancellationToken::current();
 
-        let join_handle = task::spawn_blocking(move || {
+        let join_handle = spawn_io(move || {
             if let Err(e) = read_partition(
                 object_store.as_ref(),
                 partition_index,
                 partition,
                 metrics,
-                &projection,
+                &projected_fields,
                 &predicate_builder,
+                row_group_sample,
                 batch_size,
                 response_tx,
                 limit,
                 partition_col_proj,
+                cancellation,
             ) {
                 println!("Parquet reader thread terminated due to error: {:?}", e);
             }
@@ -246,7 +362,11 @@ impl ExecutionPlan for ParquetExec {
                     self.base_config.batch_size,
                     self.base_config.limit,
                     super::FileGroupsDisplay(&self.base_config.file_groups)
-                )
+                )?;
+                if let Some((fraction, seed)) = self.row_group_sample {
+                    write!(f, ", row_group_sample={} (seed={})", fraction, seed)?;
+                }
+                Ok(())
             }
         }
     }
@@ -391,14 +511,18 @@ fn read_partition(
     partition_index: usize,
     partition: Vec<PartitionedFile>,
     metrics: ExecutionPlanMetricsSet,
-    projection: &[usize],
+    projected_fields: &[Field],
     predicate_builder: &Option<PruningPredicate>,
+    row_group_sample: Option<(f64, u64)>,
     batch_size: usize,
     response_tx: Sender<ArrowResult<RecordBatch>>,
     limit: Option<usize>,
     mut partition_column_projector: PartitionColumnProjector,
+    cancellation: CancellationToken,
 ) -> Result<()> {
     let mut total_rows = 0;
+    let partition_metrics = ParquetPartitionMetrics::new(partition_index, &metrics);
+    partition_metrics.files_listed.add(partition.len());
     'outer: for partitioned_file in partition {
         let file_metrics = ParquetFileMetrics::new(
             partition_index,
@@ -407,25 +531,79 @@ fn read_partition(
         );
         let object_reader =
             object_store.file_reader(partitioned_file.file_meta.sized_file.clone())?;
-        let mut file_reader =
-            SerializedFileReader::new(ChunkObjectReader(object_reader))?;
+        let mut file_reader = SerializedFileReader::new(MeteredChunkReader {
+            inner: ChunkObjectReader(object_reader),
+            bytes_scanned: partition_metrics.bytes_scanned.clone(),
+            io_time: partition_metrics.io_time.clone(),
+        })?;
+        if let Some(range) = &partitioned_file.range {
+            // a row group belongs to the range in which its first page starts
+            file_reader.filter_row_groups(&|row_group: &RowGroupMetaData, _| {
+                let column = row_group.column(0);
+                let offset = column
+                    .dictionary_page_offset()
+                    .unwrap_or_else(|| column.data_page_offset());
+                range.contains(offset as u64)
+            });
+        }
+        if let Some((fraction, seed)) = row_group_sample {
+            // row groups are identified by the offset of their first page, as
+            // their index depends on the row groups that were filtered before
+            let path = partitioned_file.file_meta.path().to_owned();
+            file_reader.filter_row_groups(&|row_group: &RowGroupMetaData, _| {
+                let offset = row_group.column(0).data_page_offset();
+                is_block_sampled((&path, offset), fraction, seed)
+            });
+        }
         if let Some(predicate_builder) = predicate_builder {
+            let num_row_groups = file_reader.metadata().num_row_groups();
             let row_group_predicate = build_row_group_predicate(
                 predicate_builder,
                 file_metrics,
                 file_reader.metadata().row_groups(),
             );
             file_reader.filter_row_groups(&row_group_predicate);
+            if num_row_groups > 0 && file_reader.metadata().num_row_groups() == 0 {
+                partition_metrics.files_pruned.add(1);
+                continue;
+            }
         }
+        partition_metrics
+            .row_groups_scanned
+            .add(file_reader.metadata().num_row_groups());
+        let leaves = leaf_column_indices(
+            file_reader.metadata().file_metadata().schema_descr(),
+            projected_fields,
+        );
+        let file_reader = MapAsListFileReader::try_new(file_reader)?;
         let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(file_reader));
-        let mut batch_reader = arrow_reader
-            .get_record_reader_by_columns(projection.to_owned(), batch_size)?;
+        let mut batch_reader =
+            arrow_reader.get_record_reader_by_columns(leaves, batch_size)?;
         loop {
-            match batch_reader.next() {
+            if let Err(e) = cancellation.check() {
+                // the query was cancelled, stop reading
+                send_result(&response_tx, Err(e.into_arrow_external_error()))?;
+                return Ok(());
+            }
+            // the time spent in the reader that was not spent waiting for the
+            // object store is spent decoding
+            let io_nanos = partition_metrics.io_time.value();
+            let start = Instant::now();
+            let next_batch = batch_reader.next();
+            let io_duration = Duration::from_nanos(
+                (partition_metrics.io_time.value() - io_nanos) as u64,
+            );
+            partition_metrics
+                .decode_time
+                .add_duration(start.elapsed().saturating_sub(io_duration));
+            match next_batch {
                 Some(Ok(batch)) => {
                     total_rows += batch.num_rows();
-                    let proj_batch = partition_column_projector
-                        .project(batch, &partitioned_file.partition_values);
+                    let proj_batch = project_file_batch(batch, projected_fields)
+                        .and_then(|batch| {
+                            partition_column_projector
+                                .project(batch, &partitioned_file.partition_values)
+                        });
 
                     send_result(&response_tx, proj_batch)?;
                     if limit.map(|l| total_rows >= l).unwrap_or(false) {
@@ -458,6 +636,72 @@ fn read_partition(
     Ok(())
 }
 
+/// The indices of the leaf columns of a Parquet file to read the columns of
+/// `projected_fields`, whose structs may only keep some of their fields
+fn leaf_column_indices(
+    schema: &SchemaDescriptor,
+    projected_fields: &[Field],
+) -> Vec<usize> {
+    schema
+        .columns()
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| {
+            let path = column.path().parts();
+            projected_fields
+                .iter()
+                .any(|field| is_on_field_path(field, path))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Whether the leaf column on `path` belongs to `field`
+fn is_on_field_path(field: &Field, path: &[String]) -> bool {
+    match path.split_first() {
+        Some((name, rest)) if name == field.name() => {
+            match (field.data_type(), rest.is_empty()) {
+                (DataType::Struct(children), false) => {
+                    children.iter().any(|child| is_on_field_path(child, rest))
+                }
+                // the other nested types are read entirely
+                _ => true,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Orders the columns of a batch read from a file like `projected_fields`,
+/// projects its struct columns to the fields of `projected_fields`, and turns its
+/// map columns, read as lists, back into maps
+fn project_file_batch(
+    batch: RecordBatch,
+    projected_fields: &[Field],
+) -> ArrowResult<RecordBatch> {
+    let schema = batch.schema();
+    if schema.fields().as_slice() == projected_fields {
+        return Ok(batch);
+    }
+    let columns = projected_fields
+        .iter()
+        .map(|field| {
+            let column = batch.column(schema.index_of(field.name())?);
+            match field.data_type() {
+                DataType::Struct(_) => project_array(column, field.data_type())
+                    .map_err(DataFusionError::into_arrow_external_error),
+                DataType::Map(_, _)
+                    if matches!(column.data_type(), DataType::List(_)) =>
+                {
+                    list_to_map(column, field.data_type())
+                }
+                _ => Ok(column.clone()),
+            }
+        })
+        .collect::<ArrowResult<Vec<_>>>()?;
+    RecordBatch::try_new(Arc::new(Schema::new(projected_fields.to_vec())), columns)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::datasource::{
@@ -465,6 +709,7 @@ mod tests {
         object_store::local::{
             local_object_reader_stream, local_unpartitioned_file, LocalFileSystem,
         },
+        FileRange,
     };
 
     use super::*;
@@ -489,9 +734,11 @@ mod tests {
                     .await?,
                 statistics: Statistics::default(),
                 projection: Some(vec![0, 1, 2]),
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             None,
         );
@@ -520,6 +767,99 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn parquet_exec_with_range() -> Result<()> {
+        let testdata = crate::test_util::parquet_test_data();
+        let filename = format!("{}/alltypes_plain.parquet", testdata);
+        let file = local_unpartitioned_file(filename.clone());
+        let size = file.file_meta.size();
+        let file_schema = ParquetFormat::default()
+            .infer_schema(local_object_reader_stream(vec![filename]))
+            .await?;
+
+        // the file has a single row group which starts after the magic bytes
+        let file_groups = vec![
+            vec![PartitionedFile {
+                range: Some(FileRange { start: 0, end: 4 }),
+                ..file.clone()
+            }],
+            vec![PartitionedFile {
+                range: Some(FileRange {
+                    start: 4,
+                    end: size,
+                }),
+                ..file
+            }],
+        ];
+        let parquet_exec = ParquetExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_groups,
+                file_schema,
+                statistics: Statistics::default(),
+                projection: Some(vec![0]),
+                nested_projection: vec![],
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+                bucket_columns: None,
+            },
+            None,
+        );
+        assert_eq!(parquet_exec.output_partitioning().partition_count(), 2);
+
+        let mut results = parquet_exec.execute(0).await?;
+        assert!(results.next().await.is_none());
+
+        let mut results = parquet_exec.execute(1).await?;
+        let batch = results.next().await.unwrap()?;
+        assert_eq!(8, batch.num_rows());
+        assert!(results.next().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parquet_exec_with_row_group_sample() -> Result<()> {
+        let testdata = crate::test_util::parquet_test_data();
+        let filename = format!("{}/alltypes_plain.parquet", testdata);
+        let file_schema = ParquetFormat::default()
+            .infer_schema(local_object_reader_stream(vec![filename.clone()]))
+            .await?;
+        let parquet_exec = ParquetExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_groups: vec![vec![local_unpartitioned_file(filename)]],
+                file_schema,
+                statistics: Statistics::default(),
+                projection: Some(vec![0]),
+                nested_projection: vec![],
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+                bucket_columns: None,
+            },
+            None,
+        );
+
+        // the file has a single row group, which is either read or skipped
+        let mut results = parquet_exec
+            .clone()
+            .with_row_group_sample(1.0, 42)
+            .execute(0)
+            .await?;
+        assert_eq!(8, results.next().await.unwrap()?.num_rows());
+        assert!(results.next().await.is_none());
+
+        let mut results = parquet_exec
+            .with_row_group_sample(0.0, 42)
+            .execute(0)
+            .await?;
+        assert!(results.next().await.is_none());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn parquet_exec_with_partition() -> Result<()> {
         let testdata = crate::test_util::parquet_test_data();
@@ -540,6 +880,7 @@ mod tests {
                 statistics: Statistics::default(),
                 // file has 10 cols so index 12 should be month
                 projection: Some(vec![0, 1, 2, 12]),
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![
@@ -547,6 +888,7 @@ mod tests {
                     "month".to_owned(),
                     "day".to_owned(),
                 ],
+                bucket_columns: None,
             },
             None,
         );
@@ -247,9 +247,11 @@ mod tests {
                 file_groups: files,
                 statistics: Statistics::default(),
                 projection: None,
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -49,7 +49,7 @@ use arrow::{
     array::{ArrayRef, NullArray},
     compute::kernels::length::{bit_length, length},
     datatypes::TimeUnit,
-    datatypes::{DataType, Field, Int32Type, Int64Type, Schema},
+    datatypes::{DataType, Field, Int32Type, Int64Type, IntervalUnit, Schema},
     record_batch::RecordBatch,
 };
 use fmt::{Debug, Formatter};
@@ -215,6 +215,10 @@ pub enum BuiltinScalarFunction {
     // string functions
     /// construct an array from columns
     Array,
+    /// map_keys
+    MapKeys,
+    /// map_values
+    MapValues,
     /// ascii
     Ascii,
     /// bit_length
@@ -229,6 +233,8 @@ pub enum BuiltinScalarFunction {
     Concat,
     /// concat_ws
     ConcatWithSeparator,
+    /// date_bin
+    DateBin,
     /// date_part
     DatePart,
     /// date_trunc
@@ -299,6 +305,8 @@ pub enum BuiltinScalarFunction {
     Trim,
     /// upper
     Upper,
+    /// window
+    Window,
     /// regexp_match
     RegexpMatch,
 }
@@ -335,6 +343,8 @@ impl BuiltinScalarFunction {
             BuiltinScalarFunction::Tan => Volatility::Immutable,
             BuiltinScalarFunction::Trunc => Volatility::Immutable,
             BuiltinScalarFunction::Array => Volatility::Immutable,
+            BuiltinScalarFunction::MapKeys => Volatility::Immutable,
+            BuiltinScalarFunction::MapValues => Volatility::Immutable,
             BuiltinScalarFunction::Ascii => Volatility::Immutable,
             BuiltinScalarFunction::BitLength => Volatility::Immutable,
             BuiltinScalarFunction::Btrim => Volatility::Immutable,
@@ -342,6 +352,7 @@ impl BuiltinScalarFunction {
             BuiltinScalarFunction::Chr => Volatility::Immutable,
             BuiltinScalarFunction::Concat => Volatility::Immutable,
             BuiltinScalarFunction::ConcatWithSeparator => Volatility::Immutable,
+            BuiltinScalarFunction::DateBin => Volatility::Immutable,
             BuiltinScalarFunction::DatePart => Volatility::Immutable,
             BuiltinScalarFunction::DateTrunc => Volatility::Immutable,
             BuiltinScalarFunction::InitCap => Volatility::Immutable,
@@ -376,6 +387,7 @@ impl BuiltinScalarFunction {
             BuiltinScalarFunction::Translate => Volatility::Immutable,
             BuiltinScalarFunction::Trim => Volatility::Immutable,
             BuiltinScalarFunction::Upper => Volatility::Immutable,
+            BuiltinScalarFunction::Window => Volatility::Immutable,
             BuiltinScalarFunction::RegexpMatch => Volatility::Immutable,
 
             //Stable builtin functions
@@ -420,6 +432,8 @@ impl FromStr for BuiltinScalarFunction {
 
             // string functions
             "array" => BuiltinScalarFunction::Array,
+            "map_keys" => BuiltinScalarFunction::MapKeys,
+            "map_values" => BuiltinScalarFunction::MapValues,
             "ascii" => BuiltinScalarFunction::Ascii,
             "bit_length" => BuiltinScalarFunction::BitLength,
             "btrim" => BuiltinScalarFunction::Btrim,
@@ -428,6 +442,7 @@ impl FromStr for BuiltinScalarFunction {
             "concat" => BuiltinScalarFunction::Concat,
             "concat_ws" => BuiltinScalarFunction::ConcatWithSeparator,
             "chr" => BuiltinScalarFunction::Chr,
+            "date_bin" => BuiltinScalarFunction::DateBin,
             "date_part" | "datepart" => BuiltinScalarFunction::DatePart,
             "date_trunc" | "datetrunc" => BuiltinScalarFunction::DateTrunc,
             "initcap" => BuiltinScalarFunction::InitCap,
@@ -465,6 +480,7 @@ impl FromStr for BuiltinScalarFunction {
             "translate" => BuiltinScalarFunction::Translate,
             "trim" => BuiltinScalarFunction::Trim,
             "upper" => BuiltinScalarFunction::Upper,
+            "window" => BuiltinScalarFunction::Window,
             "regexp_match" => BuiltinScalarFunction::RegexpMatch,
             _ => {
                 return Err(DataFusionError::Plan(format!(
@@ -523,6 +539,12 @@ pub fn return_type(
             Box::new(Field::new("item", input_expr_types[0].clone(), true)),
             input_expr_types.len() as i32,
         )),
+        BuiltinScalarFunction::MapKeys => {
+            array_expressions::map_entries_list_type(&input_expr_types[0], false)
+        }
+        BuiltinScalarFunction::MapValues => {
+            array_expressions::map_entries_list_type(&input_expr_types[0], true)
+        }
         BuiltinScalarFunction::Ascii => Ok(DataType::Int32),
         BuiltinScalarFunction::BitLength => {
             utf8_to_int_type(&input_expr_types[0], "bit_length")
@@ -534,6 +556,9 @@ pub fn return_type(
         BuiltinScalarFunction::Chr => Ok(DataType::Utf8),
         BuiltinScalarFunction::Concat => Ok(DataType::Utf8),
         BuiltinScalarFunction::ConcatWithSeparator => Ok(DataType::Utf8),
+        BuiltinScalarFunction::DateBin => {
+            Ok(DataType::Timestamp(TimeUnit::Nanosecond, None))
+        }
         BuiltinScalarFunction::DatePart => Ok(DataType::Int32),
         BuiltinScalarFunction::DateTrunc => {
             Ok(DataType::Timestamp(TimeUnit::Nanosecond, None))
@@ -618,6 +643,9 @@ pub fn return_type(
         }
         BuiltinScalarFunction::Trim => utf8_to_str_type(&input_expr_types[0], "trim"),
         BuiltinScalarFunction::Upper => utf8_to_str_type(&input_expr_types[0], "upper"),
+        BuiltinScalarFunction::Window => {
+            Ok(DataType::Struct(datetime_expressions::window_fields()))
+        }
         BuiltinScalarFunction::RegexpMatch => Ok(match input_expr_types[0] {
             DataType::LargeUtf8 => {
                 DataType::List(Box::new(Field::new("item", DataType::LargeUtf8, true)))
@@ -745,6 +773,12 @@ pub fn create_physical_fun(
         BuiltinScalarFunction::Trunc => Arc::new(math_expressions::trunc),
         // string functions
         BuiltinScalarFunction::Array => Arc::new(array_expressions::array),
+        BuiltinScalarFunction::MapKeys => {
+            make_scalar_function(array_expressions::map_keys)
+        }
+        BuiltinScalarFunction::MapValues => {
+            make_scalar_function(array_expressions::map_values)
+        }
         BuiltinScalarFunction::Ascii => Arc::new(|args| match args[0].data_type() {
             DataType::Utf8 => {
                 make_scalar_function(string_expressions::ascii::<i32>)(args)
@@ -812,6 +846,7 @@ pub fn create_physical_fun(
         BuiltinScalarFunction::ConcatWithSeparator => {
             Arc::new(|args| make_scalar_function(string_expressions::concat_ws)(args))
         }
+        BuiltinScalarFunction::DateBin => Arc::new(datetime_expressions::date_bin),
         BuiltinScalarFunction::DatePart => Arc::new(datetime_expressions::date_part),
         BuiltinScalarFunction::DateTrunc => Arc::new(datetime_expressions::date_trunc),
         BuiltinScalarFunction::Now => {
@@ -1139,6 +1174,7 @@ pub fn create_physical_fun(
             ))),
         }),
         BuiltinScalarFunction::Upper => Arc::new(string_expressions::upper),
+        BuiltinScalarFunction::Window => Arc::new(datetime_expressions::window),
         _ => {
             return Err(DataFusionError::Internal(format!(
                 "create_physical_fun: Unsupported scalar function {:?}",
@@ -1272,6 +1308,9 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
             array_expressions::SUPPORTED_ARRAY_TYPES.to_vec(),
             fun.volatility(),
         ),
+        BuiltinScalarFunction::MapKeys | BuiltinScalarFunction::MapValues => {
+            Signature::any(1, fun.volatility())
+        }
         BuiltinScalarFunction::Concat | BuiltinScalarFunction::ConcatWithSeparator => {
             Signature::variadic(vec![DataType::Utf8], fun.volatility())
         }
@@ -1395,10 +1434,59 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
             ],
             fun.volatility(),
         ),
+        BuiltinScalarFunction::DateBin => {
+            let timestamp = DataType::Timestamp(TimeUnit::Nanosecond, None);
+            let mut signatures = vec![];
+            for unit in [
+                IntervalUnit::YearMonth,
+                IntervalUnit::DayTime,
+                IntervalUnit::MonthDayNano,
+            ] {
+                let stride = DataType::Interval(unit);
+                signatures.push(TypeSignature::Exact(vec![
+                    stride.clone(),
+                    timestamp.clone(),
+                ]));
+                signatures.push(TypeSignature::Exact(vec![
+                    stride,
+                    timestamp.clone(),
+                    timestamp.clone(),
+                ]));
+            }
+            Signature::one_of(signatures, fun.volatility())
+        }
+        BuiltinScalarFunction::Window => Signature::one_of(
+            [
+                IntervalUnit::YearMonth,
+                IntervalUnit::DayTime,
+                IntervalUnit::MonthDayNano,
+            ]
+            .iter()
+            .map(|unit| {
+                TypeSignature::Exact(vec![
+                    DataType::Timestamp(TimeUnit::Nanosecond, None),
+                    DataType::Interval(unit.clone()),
+                ])
+            })
+            .collect(),
+            fun.volatility(),
+        ),
         BuiltinScalarFunction::DatePart => Signature::one_of(
             vec![
                 TypeSignature::Exact(vec![DataType::Utf8, DataType::Date32]),
                 TypeSignature::Exact(vec![DataType::Utf8, DataType::Date64]),
+                TypeSignature::Exact(vec![
+                    DataType::Utf8,
+                    DataType::Interval(IntervalUnit::YearMonth),
+                ]),
+                TypeSignature::Exact(vec![
+                    DataType::Utf8,
+                    DataType::Interval(IntervalUnit::DayTime),
+                ]),
+                TypeSignature::Exact(vec![
+                    DataType::Utf8,
+                    DataType::Interval(IntervalUnit::MonthDayNano),
+                ]),
                 TypeSignature::Exact(vec![
                     DataType::Utf8,
                     DataType::Timestamp(TimeUnit::Second, None),
@@ -1700,7 +1788,7 @@ mod tests {
     use arrow::{
         array::{
             Array, ArrayRef, BinaryArray, BooleanArray, FixedSizeListArray, Float32Array,
-            Float64Array, Int32Array, StringArray, UInt32Array, UInt64Array,
+            Float64Array, Int32Array, ListArray, StringArray, UInt32Array, UInt64Array,
         },
         datatypes::Field,
         record_batch::RecordBatch,
@@ -4008,6 +4096,71 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_map_keys_and_values() -> Result<()> {
+        let map = crate::test::make_map_array(vec![
+            Some(vec![("a", Some(1)), ("b", None)]),
+            None,
+            Some(vec![]),
+            Some(vec![("c", Some(3))]),
+        ]);
+        let schema = Schema::new(vec![Field::new("m", map.data_type().clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(map)])?;
+        let ctx_state = ExecutionContextState::new();
+
+        let expr = create_physical_expr(
+            &BuiltinScalarFunction::MapKeys,
+            &[col("m", &schema)?],
+            &schema,
+            &ctx_state,
+        )?;
+        assert_eq!(
+            expr.data_type(&schema)?,
+            DataType::List(Box::new(Field::new("item", DataType::Utf8, false)))
+        );
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(
+            format!("{:?}", result.value(0)),
+            "StringArray\n[\n  \"a\",\n  \"b\",\n]"
+        );
+        assert!(result.is_null(1));
+        assert_eq!(result.value_length(2), 0);
+        assert_eq!(
+            format!("{:?}", result.value(3)),
+            "StringArray\n[\n  \"c\",\n]"
+        );
+
+        let expr = create_physical_expr(
+            &BuiltinScalarFunction::MapValues,
+            &[col("m", &schema)?],
+            &schema,
+            &ctx_state,
+        )?;
+        assert_eq!(
+            expr.data_type(&schema)?,
+            DataType::List(Box::new(Field::new("item", DataType::Int64, true)))
+        );
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(
+            format!("{:?}", result.value(0)),
+            "PrimitiveArray<Int64>\n[\n  1,\n  null,\n]"
+        );
+        assert!(result.is_null(1));
+
+        // only maps have keys and values
+        let schema = Schema::new(vec![Field::new("a", DataType::Utf8, true)]);
+        assert!(create_physical_expr(
+            &BuiltinScalarFunction::MapKeys,
+            &[col("a", &schema)?],
+            &schema,
+            &ctx_state,
+        )
+        .is_err());
+        Ok(())
+    }
+
     #[test]
     #[cfg(feature = "regex_expressions")]
     fn test_regexp_match() -> Result<()> {
@@ -0,0 +1,561 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Execution plan generating a series of integers, dates or timestamps, such as
+//! the tables of the `range` and `generate_series` table functions
+
+use std::any::Any;
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use arrow::array::{Array, ArrayRef, Int64Array};
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::Stream;
+
+use super::expressions::{add_interval, temporal_array, temporal_value, IntervalParts};
+use super::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use super::stream::ObservedStream;
+use super::{
+    DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
+};
+use crate::error::{DataFusionError, Result};
+use crate::scalar::ScalarValue;
+
+/// The step between the consecutive values of a [`Series`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeriesStep {
+    /// Step of a series of integers
+    Integer(i64),
+    /// Step of a series of dates or timestamps
+    Interval(IntervalParts),
+}
+
+/// A series of integers, dates or timestamps, whose `k`th value is
+/// `start + k * step`.
+///
+/// Adding a multiple of the step to the start, rather than adding the step to
+/// the previous value, keeps the day of the month of series stepping by months,
+/// and lets any value be computed independently, to generate the series in
+/// partitions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    /// `Int64`, `Date32` or a timestamp type
+    data_type: DataType,
+    /// the native value of the start of the series
+    start: i64,
+    step: SeriesStep,
+    /// the number of values of the series
+    len: u64,
+}
+
+impl Series {
+    /// Creates the series of the values from `start` to `stop`, by `step`, which
+    /// includes `stop` if `inclusive`. The series is empty when any of them is
+    /// null.
+    ///
+    /// `start` and `stop` must be both `Int64`, with an `Int64` step, or be of
+    /// the same date or timestamp type, with an interval step.
+    pub fn try_new(
+        start: &ScalarValue,
+        stop: &ScalarValue,
+        step: &ScalarValue,
+        inclusive: bool,
+    ) -> Result<Self> {
+        let data_type = start.get_datatype();
+        if stop.get_datatype() != data_type {
+            return Err(DataFusionError::Plan(format!(
+                "The start and stop of a series must have the same type, not {:?} and {:?}",
+                data_type,
+                stop.get_datatype()
+            )));
+        }
+        let step = match (&data_type, step) {
+            (DataType::Int64, ScalarValue::Int64(step)) => step.map(SeriesStep::Integer),
+            (
+                DataType::Date32 | DataType::Timestamp(_, _),
+                ScalarValue::IntervalYearMonth(_)
+                | ScalarValue::IntervalDayTime(_)
+                | ScalarValue::IntervalMonthDayNano(_),
+            ) => {
+                let step = step.to_array();
+                if step.is_null(0) {
+                    None
+                } else {
+                    Some(SeriesStep::Interval(IntervalParts::from_array(
+                        step.as_ref(),
+                        0,
+                    )?))
+                }
+            }
+            (DataType::Int64 | DataType::Date32 | DataType::Timestamp(_, _), _) => {
+                return Err(DataFusionError::Plan(format!(
+                    "The step of a series of {:?} can't be {:?}",
+                    data_type, step
+                )))
+            }
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "Series of {:?} are not supported",
+                    data_type
+                )))
+            }
+        };
+        let direction = match step {
+            Some(SeriesStep::Integer(step)) => step.signum(),
+            Some(SeriesStep::Interval(step)) => step.normalized_nanos().signum() as i64,
+            None => 1,
+        };
+        if direction == 0 {
+            return Err(DataFusionError::Plan(
+                "The step of a series can't be zero".to_string(),
+            ));
+        }
+
+        let (start, stop) = (start.to_array(), stop.to_array());
+        let (start, stop, step) = match step {
+            Some(step) if !start.is_null(0) && !stop.is_null(0) => (
+                temporal_or_integer_value(start.as_ref())?,
+                temporal_or_integer_value(stop.as_ref())?,
+                step,
+            ),
+            _ => {
+                return Ok(Self {
+                    data_type,
+                    start: 0,
+                    step: SeriesStep::Integer(1),
+                    len: 0,
+                })
+            }
+        };
+        let mut series = Self {
+            data_type,
+            start,
+            step,
+            len: 0,
+        };
+        // the values are monotonic, so the values up to the stop are a prefix
+        let len = prefix_len(|k| match series.value(k) {
+            Some(value) if direction > 0 && inclusive => value <= stop,
+            Some(value) if direction > 0 => value < stop,
+            Some(value) if inclusive => value >= stop,
+            Some(value) => value > stop,
+            // past the range of the type
+            None => false,
+        });
+        series.len = len;
+        Ok(series)
+    }
+
+    /// The type of the values of the series
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    /// The step between the consecutive values of the series
+    pub fn step(&self) -> SeriesStep {
+        self.step
+    }
+
+    /// The number of values of the series
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the series has no values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Keeps the first `n` values of the series only
+    pub fn limit(mut self, n: u64) -> Self {
+        self.len = self.len.min(n);
+        self
+    }
+
+    /// The native value of the `k`th value of the series, or `None` if it is out
+    /// of the range of its type
+    fn value(&self, k: u64) -> Option<i64> {
+        let k = i64::try_from(k).ok()?;
+        match self.step {
+            SeriesStep::Integer(step) => self.start.checked_add(step.checked_mul(k)?),
+            SeriesStep::Interval(step) => {
+                let step = IntervalParts {
+                    months: checked_mul_i32(step.months, k)?,
+                    days: checked_mul_i32(step.days, k)?,
+                    nanos: step.nanos.checked_mul(k)?,
+                };
+                add_interval(self.start, &self.data_type, step).ok()
+            }
+        }
+    }
+
+    /// The values of the series at the positions `positions`
+    fn values(&self, positions: Range<u64>) -> Result<ArrayRef> {
+        let values = positions
+            .map(|k| {
+                self.value(k).ok_or_else(|| {
+                    DataFusionError::Execution(format!(
+                        "Value {} of the series overflowed",
+                        k
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        match self.data_type {
+            DataType::Int64 => Ok(Arc::new(Int64Array::from(values))),
+            _ => temporal_array(&self.data_type, values.into_iter().map(Some).collect()),
+        }
+    }
+}
+
+fn temporal_or_integer_value(array: &dyn Array) -> Result<i64> {
+    match array.as_any().downcast_ref::<Int64Array>() {
+        Some(array) => Ok(array.value(0)),
+        None => temporal_value(array, 0),
+    }
+}
+
+fn checked_mul_i32(value: i32, k: i64) -> Option<i32> {
+    if value == 0 {
+        Some(0)
+    } else {
+        value.checked_mul(i32::try_from(k).ok()?)
+    }
+}
+
+/// The number of values `k` for which `predicate` holds, which must hold for the
+/// values lower than any value it holds for
+fn prefix_len(predicate: impl Fn(u64) -> bool) -> u64 {
+    if !predicate(0) {
+        return 0;
+    }
+    // find a bound the predicate doesn't hold for, then bisect
+    let mut high = 1u64;
+    while predicate(high) {
+        match high.checked_mul(2) {
+            Some(next) => high = next,
+            None => return u64::MAX,
+        }
+    }
+    let mut low = high / 2;
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        if predicate(middle) {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    low + 1
+}
+
+/// Execution plan generating the values of a [`Series`], in consecutive ranges
+/// of about the same size for each partition
+#[derive(Debug)]
+pub struct GenerateSeriesExec {
+    series: Series,
+    /// The schema of the series, with a single column
+    schema: SchemaRef,
+    partitions: usize,
+    batch_size: usize,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl GenerateSeriesExec {
+    /// Create a new GenerateSeriesExec generating `series` in `partitions`
+    /// partitions, whose batches have `batch_size` rows
+    pub fn new(
+        series: Series,
+        schema: SchemaRef,
+        partitions: usize,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            series,
+            schema,
+            partitions: partitions.max(1),
+            batch_size: batch_size.max(1),
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    /// The generated series
+    pub fn series(&self) -> &Series {
+        &self.series
+    }
+
+    /// The positions of the values of the series generated by `partition`
+    fn partition_positions(&self, partition: usize) -> Range<u64> {
+        let bound = |partition: usize| {
+            (self.series.len() as u128 * partition as u128 / self.partitions as u128)
+                as u64
+        };
+        bound(partition)..bound(partition + 1)
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for GenerateSeriesExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.partitions)
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            0 => Ok(Arc::new(GenerateSeriesExec::new(
+                self.series.clone(),
+                self.schema.clone(),
+                self.partitions,
+                self.batch_size,
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "GenerateSeriesExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if partition >= self.partitions {
+            return Err(DataFusionError::Internal(format!(
+                "GenerateSeriesExec invalid partition {} (expected less than {})",
+                partition, self.partitions
+            )));
+        }
+        let stream = Box::pin(SeriesStream {
+            series: self.series.clone(),
+            schema: self.schema.clone(),
+            positions: self.partition_positions(partition),
+            batch_size: self.batch_size as u64,
+        });
+        Ok(Box::pin(ObservedStream::new_with_compute(
+            stream,
+            BaselineMetrics::new(&self.metrics, partition),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(
+                    f,
+                    "GenerateSeriesExec: len={}, partitions={}",
+                    self.series.len(),
+                    self.partitions
+                )
+            }
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics {
+            num_rows: usize::try_from(self.series.len()).ok(),
+            total_byte_size: None,
+            column_statistics: None,
+            is_exact: true,
+        }
+    }
+}
+
+/// Stream generating the values of a series at consecutive positions
+struct SeriesStream {
+    series: Series,
+    schema: SchemaRef,
+    /// the positions of the values left to generate
+    positions: Range<u64>,
+    batch_size: u64,
+}
+
+impl Stream for SeriesStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.positions.is_empty() {
+            return Poll::Ready(None);
+        }
+        let start = self.positions.start;
+        let end = self
+            .positions
+            .end
+            .min(start.saturating_add(self.batch_size));
+        self.positions.start = end;
+        let batch = self
+            .series
+            .values(start..end)
+            .map_err(DataFusionError::into_arrow_external_error)
+            .and_then(|values| RecordBatch::try_new(self.schema.clone(), vec![values]));
+        Poll::Ready(Some(batch))
+    }
+}
+
+impl RecordBatchStream for SeriesStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::common;
+    use arrow::datatypes::{Field, Schema, TimeUnit};
+    use arrow::util::display::array_value_to_string;
+
+    fn series_values(series: &Series) -> Result<Vec<String>> {
+        let values = series.values(0..series.len())?;
+        (0..values.len())
+            .map(|i| Ok(array_value_to_string(&values, i)?))
+            .collect()
+    }
+
+    #[test]
+    fn integer_series() -> Result<()> {
+        let int = |v: i64| ScalarValue::Int64(Some(v));
+        let cases = vec![
+            (0, 5, 1, false, vec!["0", "1", "2", "3", "4"]),
+            (0, 5, 2, true, vec!["0", "2", "4"]),
+            (0, 6, 2, true, vec!["0", "2", "4", "6"]),
+            (5, 0, -2, false, vec!["5", "3", "1"]),
+            (5, 0, 1, true, vec![]),
+            (
+                i64::MAX - 1,
+                i64::MAX,
+                1,
+                true,
+                vec!["9223372036854775806", "9223372036854775807"],
+            ),
+        ];
+        for (start, stop, step, inclusive, expected) in cases {
+            let series = Series::try_new(&int(start), &int(stop), &int(step), inclusive)?;
+            assert_eq!(series_values(&series)?, expected);
+        }
+
+        let series = Series::try_new(&int(0), &ScalarValue::Int64(None), &int(1), true)?;
+        assert!(series.is_empty());
+        assert!(Series::try_new(&int(0), &int(5), &int(0), true).is_err());
+        assert!(
+            Series::try_new(&int(0), &ScalarValue::from(5.0), &int(1), true).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn temporal_series() -> Result<()> {
+        // 2021-01-31 and 2021-05-01
+        let (start, stop) = (
+            ScalarValue::Date32(Some(18658)),
+            ScalarValue::Date32(Some(18748)),
+        );
+        let month = ScalarValue::IntervalYearMonth(Some(1));
+        let series = Series::try_new(&start, &stop, &month, true)?;
+        assert_eq!(
+            series_values(&series)?,
+            vec!["2021-01-31", "2021-02-28", "2021-03-31", "2021-04-30"]
+        );
+
+        // 2021-01-01T00:00:00 to 2021-01-01T01:00:00 by 25 minutes
+        let timestamp = |v: i64| ScalarValue::TimestampSecond(Some(v), None);
+        let step = ScalarValue::IntervalDayTime(Some(25 * 60 * 1000));
+        let series = Series::try_new(
+            &timestamp(1609459200),
+            &timestamp(1609462800),
+            &step,
+            false,
+        )?;
+        assert_eq!(
+            series.data_type(),
+            &DataType::Timestamp(TimeUnit::Second, None)
+        );
+        assert_eq!(
+            series_values(&series)?,
+            vec![
+                "2021-01-01T00:00:00",
+                "2021-01-01T00:25:00",
+                "2021-01-01T00:50:00"
+            ]
+        );
+
+        assert!(
+            Series::try_new(&start, &stop, &ScalarValue::Int64(Some(1)), true).is_err()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn generate_partitions() -> Result<()> {
+        let int = |v: i64| ScalarValue::Int64(Some(v));
+        let series = Series::try_new(&int(0), &int(10), &int(1), false)?;
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "range",
+            DataType::Int64,
+            false,
+        )]));
+        let exec = GenerateSeriesExec::new(series, schema, 3, 2);
+        assert_eq!(exec.output_partitioning().partition_count(), 3);
+        assert_eq!(exec.statistics().num_rows, Some(10));
+
+        let mut values = vec![];
+        for partition in 0..3 {
+            let batches = common::collect(exec.execute(partition).await?).await?;
+            assert!(batches.iter().all(|batch| batch.num_rows() <= 2));
+            for batch in batches {
+                let array = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                values.extend(array.values().iter().copied());
+            }
+        }
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+        assert!(exec.execute(3).await.is_err());
+        Ok(())
+    }
+}
@@ -18,6 +18,8 @@
 //! Defines the execution plan for the hash aggregate operation
 
 use std::any::Any;
+use std::cmp::Ordering;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::vec;
@@ -29,30 +31,37 @@ use futures::{
 };
 
 use crate::error::{DataFusionError, Result};
+use crate::execution::memory_manager::MemoryReservation;
+use crate::physical_plan::dictionary::{dictionary_keys_and_values, is_dictionary};
 use crate::physical_plan::hash_utils::create_hashes;
+use crate::physical_plan::row_format::RowKeys;
 use crate::physical_plan::{
-    Accumulator, AggregateExpr, DisplayFormatType, Distribution, ExecutionPlan,
-    Partitioning, PhysicalExpr,
+    cancellation, Accumulator, AggregateExpr, DisplayFormatType, Distribution,
+    ExecutionPlan, Partitioning, PhysicalExpr,
 };
 use crate::scalar::ScalarValue;
 
 use arrow::{array::ArrayRef, compute, compute::cast};
 use arrow::{
-    array::{Array, UInt32Builder},
+    array::{new_empty_array, Array, UInt32Builder},
     error::{ArrowError, Result as ArrowResult},
 };
 use arrow::{
     datatypes::{Field, Schema, SchemaRef},
+    ipc::{reader::StreamReader, writer::StreamWriter},
     record_batch::RecordBatch,
 };
 use hashbrown::raw::RawTable;
 use pin_project_lite::pin_project;
+use tokio::sync::mpsc::Sender;
+use tokio_stream::wrappers::ReceiverStream;
 
 use async_trait::async_trait;
 
 use super::common::AbortOnDropSingle;
 use super::metrics::{
-    self, BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet, RecordOutput,
+    self, BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
+    RecordOutput,
 };
 use super::Statistics;
 use super::{expressions::Column, RecordBatchStream, SendableRecordBatchStream};
@@ -73,6 +82,29 @@ pub enum AggregateMode {
     FinalPartitioned,
 }
 
+/// Thresholds for adaptively skipping a partial aggregation that barely
+/// reduces its input, e.g. because its group keys are nearly unique.
+///
+/// Once skipped, the partial aggregation emits the groups built so far and
+/// then passes every further input row through as a group of its own, which
+/// saves the cost of hashing and of maintaining the groups.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkipPartialAggregation {
+    /// The number of input rows aggregated before checking the reduction
+    pub probe_rows: usize,
+    /// The ratio of groups to input rows above which the aggregation is skipped
+    pub min_ratio: f64,
+}
+
+impl Default for SkipPartialAggregation {
+    fn default() -> Self {
+        Self {
+            probe_rows: 100_000,
+            min_ratio: 0.8,
+        }
+    }
+}
+
 /// Hash aggregate execution plan
 #[derive(Debug)]
 pub struct HashAggregateExec {
@@ -90,6 +122,11 @@ pub struct HashAggregateExec {
     /// same as input.schema() but for the final aggregate it will be the same as the input
     /// to the partial aggregate
     input_schema: SchemaRef,
+    /// When to skip a partial aggregation that barely reduces its input
+    skip_partial: Option<SkipPartialAggregation>,
+    /// The number of groups above which the aggregation stops growing its hash
+    /// table, see [`HashAggregateExec::with_max_groups`]
+    max_groups: Option<usize>,
     /// Execution Metrics
     metrics: ExecutionPlanMetricsSet,
 }
@@ -147,10 +184,44 @@ impl HashAggregateExec {
             input,
             schema,
             input_schema,
+            skip_partial: Some(SkipPartialAggregation::default()),
+            max_groups: None,
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
 
+    /// Sets when a partial aggregation that barely reduces its input is
+    /// skipped, `None` to never skip it. Ignored by final aggregations.
+    pub fn with_skip_partial_aggregation(
+        mut self,
+        skip_partial: Option<SkipPartialAggregation>,
+    ) -> Self {
+        self.skip_partial = skip_partial;
+        self
+    }
+
+    /// When a partial aggregation that barely reduces its input is skipped
+    pub fn skip_partial_aggregation(&self) -> Option<SkipPartialAggregation> {
+        self.skip_partial
+    }
+
+    /// Sets the number of groups above which the aggregation switches from
+    /// hashing to sorting, `None` to always hash. Once it holds more groups, a
+    /// partial aggregation emits them and starts over, as the final aggregation
+    /// merges the groups emitted several times, while a final aggregation sorts
+    /// them by their keys and spills them to disk. The final aggregation then
+    /// merges the sorted runs, aggregating the groups of equal keys.
+    pub fn with_max_groups(mut self, max_groups: Option<usize>) -> Self {
+        self.max_groups = max_groups;
+        self
+    }
+
+    /// The number of groups above which the aggregation switches from hashing
+    /// to sorting
+    pub fn max_groups(&self) -> Option<usize> {
+        self.max_groups
+    }
+
     /// Aggregation mode (full, partial)
     pub fn mode(&self) -> &AggregateMode {
         &self.mode
@@ -204,7 +275,8 @@ impl ExecutionPlan for HashAggregateExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        self.input.output_partitioning()
+        // the group columns are the first output columns
+        self.input.output_partitioning().project(&self.group_expr)
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
@@ -222,13 +294,25 @@ impl ExecutionPlan for HashAggregateExec {
                 baseline_metrics,
             )))
         } else {
+            let skip_partial = match self.mode {
+                AggregateMode::Partial => self.skip_partial,
+                AggregateMode::Final | AggregateMode::FinalPartitioned => None,
+            };
+            let skipped_rows = MetricBuilder::new(&self.metrics)
+                .counter("skipped_aggregation_rows", partition);
+            let spilled_groups = MetricBuilder::new(&self.metrics)
+                .counter("spilled_aggregation_groups", partition);
             Ok(Box::pin(GroupedHashAggregateStream::new(
                 self.mode,
                 self.schema.clone(),
                 group_expr,
                 self.aggr_expr.clone(),
                 input,
+                skip_partial,
+                self.max_groups,
                 baseline_metrics,
+                skipped_rows,
+                spilled_groups,
             )))
         }
     }
@@ -238,13 +322,17 @@ impl ExecutionPlan for HashAggregateExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(HashAggregateExec::try_new(
-                self.mode,
-                self.group_expr.clone(),
-                self.aggr_expr.clone(),
-                children[0].clone(),
-                self.input_schema.clone(),
-            )?)),
+            1 => Ok(Arc::new(
+                HashAggregateExec::try_new(
+                    self.mode,
+                    self.group_expr.clone(),
+                    self.aggr_expr.clone(),
+                    children[0].clone(),
+                    self.input_schema.clone(),
+                )?
+                .with_skip_partial_aggregation(self.skip_partial)
+                .with_max_groups(self.max_groups),
+            )),
             _ => Err(DataFusionError::Internal(
                 "HashAggregateExec wrong number of children".to_string(),
             )),
@@ -338,8 +426,7 @@ pin_project! {
     struct GroupedHashAggregateStream {
         schema: SchemaRef,
         #[pin]
-        output: futures::channel::oneshot::Receiver<ArrowResult<RecordBatch>>,
-        finished: bool,
+        output: ReceiverStream<ArrowResult<RecordBatch>>,
         drop_helper: AbortOnDropSingle<()>,
     }
 }
@@ -371,19 +458,47 @@ fn group_aggregate_batch(
     // 1.1 Calculate the group keys for the group values
     let mut batch_hashes = vec![0; batch.num_rows()];
     create_hashes(&group_values, random_state, &mut batch_hashes)?;
+    // the keys are compared in the row format, if the types of the group
+    // values support it
+    let row_keys = RowKeys::try_new(&group_values)?;
+    // when grouping by a single dictionary column, the rows with the same key
+    // are in the same group, so each key only has to be looked up once
+    let dictionary_keys = match group_values.as_slice() {
+        [column] if is_dictionary(column.data_type()) => {
+            Some(dictionary_keys_and_values(column.as_ref())?.0)
+        }
+        _ => None,
+    };
+    let mut key_groups = std::collections::HashMap::new();
 
     for (row, hash) in batch_hashes.into_iter().enumerate() {
         let Accumulators { map, group_states } = &mut accumulators;
 
+        let key = dictionary_keys
+            .as_ref()
+            .filter(|keys| keys.is_valid(row))
+            .map(|keys| keys.value(row));
+        if let Some(group_idx) = key.and_then(|key| key_groups.get(&key)) {
+            let group_state = &mut group_states[*group_idx];
+            if group_state.indices.is_empty() {
+                groups_with_rows.push(*group_idx);
+            };
+            group_state.indices.push(row as u32);
+            continue;
+        }
+
         let entry = map.get_mut(hash, |(_hash, group_idx)| {
             // verify that a group that we are inserting with hash is
             // actually the same key value as the group in
             // existing_idx  (aka group_values @ row)
             let group_state = &group_states[*group_idx];
-            group_values
-                .iter()
-                .zip(group_state.group_by_values.iter())
-                .all(|(array, scalar)| scalar.eq_array(array, row))
+            match &row_keys {
+                Some(row_keys) => group_state.group_key.as_ref() == row_keys.row(row),
+                None => group_values
+                    .iter()
+                    .zip(group_state.group_by_values.iter())
+                    .all(|(array, scalar)| scalar.eq_array(array, row)),
+            }
         });
 
         match entry {
@@ -395,6 +510,9 @@ fn group_aggregate_batch(
                     groups_with_rows.push(*group_idx);
                 };
                 group_state.indices.push(row as u32); // remember this row
+                if let Some(key) = key {
+                    key_groups.insert(key, *group_idx);
+                }
             }
             //  1.2 Need to create new entry
             None => {
@@ -410,12 +528,19 @@ fn group_aggregate_batch(
                 // Add new entry to group_states and save newly created index
                 let group_state = GroupState {
                     group_by_values: group_by_values.into_boxed_slice(),
+                    group_key: row_keys
+                        .as_ref()
+                        .map(|row_keys| row_keys.row(row).into())
+                        .unwrap_or_default(),
                     accumulator_set,
                     indices: vec![row as u32], // 1.3
                 };
                 let group_idx = group_states.len();
                 group_states.push(group_state);
                 groups_with_rows.push(group_idx);
+                if let Some(key) = key {
+                    key_groups.insert(key, group_idx);
+                }
 
                 // for hasher function, use precomputed hash value
                 map.insert(hash, (hash, group_idx), |(hash, _group_idx)| *hash);
@@ -498,14 +623,21 @@ fn group_aggregate_batch(
     Ok(accumulators)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn compute_grouped_hash_aggregate(
     mode: AggregateMode,
     schema: SchemaRef,
     group_expr: Vec<Arc<dyn PhysicalExpr>>,
     aggr_expr: Vec<Arc<dyn AggregateExpr>>,
     mut input: SendableRecordBatchStream,
-    elapsed_compute: metrics::Time,
-) -> ArrowResult<RecordBatch> {
+    mut skip_partial: Option<SkipPartialAggregation>,
+    max_groups: Option<usize>,
+    baseline_metrics: &BaselineMetrics,
+    skipped_rows: metrics::Count,
+    spilled_groups: metrics::Count,
+    tx: &Sender<ArrowResult<RecordBatch>>,
+) -> ArrowResult<()> {
+    let elapsed_compute = baseline_metrics.elapsed_compute();
     let timer = elapsed_compute.timer();
     // The expressions to evaluate the batch, one vec of expressions per aggregation.
     // Assume create_schema() always put group columns in front of aggr columns, we set
@@ -514,14 +646,37 @@ async fn compute_grouped_hash_aggregate(
         aggregate_expressions(&aggr_expr, &mode, group_expr.len())
             .map_err(DataFusionError::into_arrow_external_error)?;
 
-    let random_state = RandomState::new();
+    // fixed seeds, so that the hashes of the group keys are the same across runs
+    let random_state = RandomState::with_seeds(0, 0, 0, 0);
 
     // iterate over all input batches and update the accumulators
     let mut accumulators = Accumulators::default();
+    // the memory of the groups is reserved after each batch for its new groups
+    let mut reservation = MemoryReservation::new("HashAggregateExec");
+    let mut reserved_groups = 0;
+    let mut input_rows = 0;
+    let mut skipping = false;
+    // the sorted runs of the groups spilled by a final aggregation
+    let mut runs: Vec<SortedRun> = vec![];
     timer.done();
     while let Some(batch) = input.next().await {
         let batch = batch?;
         let timer = elapsed_compute.timer();
+        if skipping {
+            let batch = pass_through_batch(
+                &group_expr,
+                &aggr_expr,
+                &aggregate_expressions,
+                &batch,
+                &schema,
+            )?;
+            skipped_rows.add(batch.num_rows());
+            timer.done();
+            send_output(tx, Ok(batch), baseline_metrics).await?;
+            continue;
+        }
+
+        input_rows += batch.num_rows();
         accumulators = group_aggregate_batch(
             &mode,
             &random_state,
@@ -532,50 +687,463 @@ async fn compute_grouped_hash_aggregate(
             &aggregate_expressions,
         )
         .map_err(DataFusionError::into_arrow_external_error)?;
+        let new_groups = &accumulators.group_states[reserved_groups..];
+        reservation
+            .try_grow(new_groups.iter().map(group_state_memory_size).sum())
+            .map_err(DataFusionError::into_arrow_external_error)?;
+        reserved_groups = accumulators.group_states.len();
+
+        // decide once, after probing enough rows, whether aggregating is
+        // worth it
+        if let Some(threshold) = skip_partial {
+            if input_rows >= threshold.probe_rows {
+                skip_partial = None;
+                let ratio = accumulators.group_states.len() as f64 / input_rows as f64;
+                skipping = ratio > threshold.min_ratio
+                    && supports_pass_through(
+                        &aggr_expr,
+                        &aggregate_expressions,
+                        &input.schema(),
+                    )
+                    .map_err(DataFusionError::into_arrow_external_error)?;
+            }
+        }
+        timer.done();
+
+        let too_many_groups = max_groups
+            .map(|max_groups| accumulators.group_states.len() > max_groups)
+            .unwrap_or(false);
+        if too_many_groups && !skipping && mode != AggregateMode::Partial {
+            let timer = elapsed_compute.timer();
+            spilled_groups.add(accumulators.group_states.len());
+            let state_schema = state_schema(&schema, group_expr.len(), &aggr_expr)
+                .map_err(DataFusionError::into_arrow_external_error)?;
+            runs.push(spill_groups(accumulators, group_expr.len(), &state_schema)?);
+            accumulators = Accumulators::default();
+            reservation.free();
+            reserved_groups = 0;
+            timer.done();
+        } else if skipping || too_many_groups {
+            let timer = elapsed_compute.timer();
+            let batch = create_batch_from_map(
+                &mode,
+                &accumulators.group_states,
+                group_expr.len(),
+                &schema,
+            );
+            accumulators = Accumulators::default();
+            reservation.free();
+            reserved_groups = 0;
+            timer.done();
+            send_output(tx, batch, baseline_metrics).await?;
+        }
+    }
+
+    if skipping {
+        return Ok(());
+    }
+    if runs.is_empty() {
+        let timer = elapsed_compute.timer();
+        let batch = create_batch_from_map(
+            &mode,
+            &accumulators.group_states,
+            group_expr.len(),
+            &schema,
+        );
         timer.done();
+        return send_output(tx, batch, baseline_metrics).await;
     }
 
+    // the groups still in memory are the last sorted run
     let timer = elapsed_compute.timer();
-    let batch = create_batch_from_map(&mode, &accumulators, group_expr.len(), &schema);
+    let state_schema = state_schema(&schema, group_expr.len(), &aggr_expr)
+        .map_err(DataFusionError::into_arrow_external_error)?;
+    let mut group_states = accumulators.group_states;
+    group_states.sort_by(|left, right| {
+        compare_group_values(&left.group_by_values, &right.group_by_values)
+    });
+    let batches = group_states
+        .chunks(SPILL_BATCH_GROUPS)
+        .map(|groups| {
+            create_batch_from_map(
+                &AggregateMode::Partial,
+                groups,
+                group_expr.len(),
+                &state_schema,
+            )
+        })
+        .collect::<Vec<_>>();
+    drop(group_states);
+    reservation.free();
+    runs.push(Box::new(batches.into_iter()));
     timer.done();
-    batch
+
+    merge_sorted_runs(
+        mode,
+        runs,
+        group_expr.len(),
+        &aggr_expr,
+        &schema,
+        baseline_metrics,
+        tx,
+    )
+    .await
+}
+
+/// The estimated number of bytes of the state of an accumulator, whose actual size
+/// is not known
+const ACCUMULATOR_MEMORY_SIZE: usize = 64;
+
+/// The estimated number of bytes of a group, with its entry in the hash table. The
+/// accumulators whose state grows with their input, such as the ones of distinct
+/// aggregates, are accounted with their initial size.
+fn group_state_memory_size(group_state: &GroupState) -> usize {
+    std::mem::size_of::<GroupState>()
+        + std::mem::size_of::<(u64, usize)>()
+        + group_state.group_key.len()
+        + group_state
+            .group_by_values
+            .iter()
+            .map(scalar_memory_size)
+            .sum::<usize>()
+        + group_state.accumulator_set.len() * ACCUMULATOR_MEMORY_SIZE
+}
+
+/// The number of bytes of a scalar with the bytes of its string or binary value
+fn scalar_memory_size(value: &ScalarValue) -> usize {
+    let heap_size = match value {
+        ScalarValue::Utf8(Some(value)) | ScalarValue::LargeUtf8(Some(value)) => {
+            value.len()
+        }
+        ScalarValue::Binary(Some(value)) | ScalarValue::LargeBinary(Some(value)) => {
+            value.len()
+        }
+        _ => 0,
+    };
+    std::mem::size_of::<ScalarValue>() + heap_size
+}
+
+/// The number of groups of the batches of the sorted runs, and of the output
+/// batches of their merge
+const SPILL_BATCH_GROUPS: usize = 8192;
+
+/// The batches of the groups of an aggregation, sorted by their keys
+type SortedRun = Box<dyn Iterator<Item = ArrowResult<RecordBatch>> + Send>;
+
+/// The schema of the spilled groups, their group values followed by the states
+/// of their accumulators
+fn state_schema(
+    output_schema: &Schema,
+    num_group_expr: usize,
+    aggr_expr: &[Arc<dyn AggregateExpr>],
+) -> Result<SchemaRef> {
+    let mut fields = output_schema.fields()[..num_group_expr].to_vec();
+    for expr in aggr_expr {
+        fields.extend(expr.state_fields()?);
+    }
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+/// Compares the group values of two groups, in the order of the sorted runs
+fn compare_group_values(left: &[ScalarValue], right: &[ScalarValue]) -> Ordering {
+    left.iter()
+        .zip(right)
+        .map(|(left, right)| left.partial_cmp(right).unwrap_or(Ordering::Equal))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Sorts the groups by their keys and writes their states to a temporary file,
+/// which is deleted once the returned run is dropped
+fn spill_groups(
+    accumulators: Accumulators,
+    num_group_expr: usize,
+    state_schema: &SchemaRef,
+) -> ArrowResult<SortedRun> {
+    let mut group_states = accumulators.group_states;
+    group_states.sort_by(|left, right| {
+        compare_group_values(&left.group_by_values, &right.group_by_values)
+    });
+
+    let mut file = BufWriter::new(tempfile::tempfile()?);
+    {
+        let mut writer = StreamWriter::try_new(&mut file, state_schema)?;
+        for groups in group_states.chunks(SPILL_BATCH_GROUPS) {
+            let batch = create_batch_from_map(
+                &AggregateMode::Partial,
+                groups,
+                num_group_expr,
+                state_schema,
+            )?;
+            writer.write(&batch)?;
+        }
+        writer.finish()?;
+    }
+    let mut file = file
+        .into_inner()
+        .map_err(|e| ArrowError::from(e.into_error()))?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(Box::new(StreamReader::try_new(BufReader::new(file))?))
+}
+
+/// The current group of a sorted run being merged
+struct RunCursor {
+    run: SortedRun,
+    batch: RecordBatch,
+    row: usize,
+    /// the group values of the current row
+    group_by_values: Vec<ScalarValue>,
+}
+
+impl RunCursor {
+    /// Positions a cursor on the first group of `run`, if any
+    fn try_new(run: SortedRun, num_group_expr: usize) -> ArrowResult<Option<Self>> {
+        let batch = RecordBatch::new_empty(Arc::new(Schema::empty()));
+        let cursor = Self {
+            run,
+            batch,
+            row: 0,
+            group_by_values: vec![],
+        };
+        cursor.next_batch(num_group_expr)
+    }
+
+    /// Moves the cursor to the next group, if any
+    fn advance(mut self, num_group_expr: usize) -> ArrowResult<Option<Self>> {
+        self.row += 1;
+        if self.row < self.batch.num_rows() {
+            self.group_by_values = self.values(0..num_group_expr)?;
+            return Ok(Some(self));
+        }
+        self.next_batch(num_group_expr)
+    }
+
+    fn next_batch(mut self, num_group_expr: usize) -> ArrowResult<Option<Self>> {
+        loop {
+            match self.run.next() {
+                Some(batch) => {
+                    self.batch = batch?;
+                    if self.batch.num_rows() > 0 {
+                        self.row = 0;
+                        self.group_by_values = self.values(0..num_group_expr)?;
+                        return Ok(Some(self));
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// The values of the columns `columns` in the current row
+    fn values(&self, columns: std::ops::Range<usize>) -> ArrowResult<Vec<ScalarValue>> {
+        columns
+            .map(|i| ScalarValue::try_from_array(self.batch.column(i), self.row))
+            .collect::<Result<Vec<_>>>()
+            .map_err(DataFusionError::into_arrow_external_error)
+    }
+}
+
+/// Merges the sorted runs of groups, aggregating the states of the groups of
+/// equal keys, and sends the groups in the output of the aggregation
+async fn merge_sorted_runs(
+    mode: AggregateMode,
+    runs: Vec<SortedRun>,
+    num_group_expr: usize,
+    aggr_expr: &[Arc<dyn AggregateExpr>],
+    schema: &SchemaRef,
+    baseline_metrics: &BaselineMetrics,
+    tx: &Sender<ArrowResult<RecordBatch>>,
+) -> ArrowResult<()> {
+    let elapsed_compute = baseline_metrics.elapsed_compute();
+    let timer = elapsed_compute.timer();
+    let num_states = aggr_expr
+        .iter()
+        .map(|expr| expr.state_fields().map(|fields| fields.len()))
+        .collect::<Result<Vec<_>>>()
+        .map_err(DataFusionError::into_arrow_external_error)?;
+    let num_columns = num_group_expr + num_states.iter().sum::<usize>();
+    let mut cursors = vec![];
+    for run in runs {
+        cursors.extend(RunCursor::try_new(run, num_group_expr)?);
+    }
+    timer.done();
+
+    let mut groups: Vec<GroupState> = vec![];
+    loop {
+        let timer = elapsed_compute.timer();
+        let next = cursors.iter().enumerate().min_by(|(_, left), (_, right)| {
+            compare_group_values(&left.group_by_values, &right.group_by_values)
+        });
+        let index = match next {
+            Some((index, _)) => index,
+            None => break,
+        };
+        let cursor = cursors.swap_remove(index);
+
+        let same_group = groups
+            .last()
+            .map(|group| {
+                compare_group_values(&group.group_by_values, &cursor.group_by_values)
+                    == Ordering::Equal
+            })
+            .unwrap_or(false);
+        let mut output = None;
+        if !same_group {
+            if groups.len() == SPILL_BATCH_GROUPS {
+                output = Some(create_batch_from_map(
+                    &mode,
+                    &groups,
+                    num_group_expr,
+                    schema,
+                ));
+                groups.clear();
+            }
+            groups.push(GroupState {
+                group_by_values: cursor.group_by_values.clone().into_boxed_slice(),
+                group_key: Box::default(),
+                accumulator_set: create_accumulators(aggr_expr)
+                    .map_err(DataFusionError::into_arrow_external_error)?,
+                indices: vec![],
+            });
+        }
+
+        let states = cursor.values(num_group_expr..num_columns)?;
+        let group = groups.last_mut().unwrap();
+        let mut offset = 0;
+        for (accumulator, num_states) in group.accumulator_set.iter_mut().zip(&num_states)
+        {
+            accumulator
+                .merge(&states[offset..offset + num_states])
+                .map_err(DataFusionError::into_arrow_external_error)?;
+            offset += num_states;
+        }
+        cursors.extend(cursor.advance(num_group_expr)?);
+        timer.done();
+
+        if let Some(batch) = output {
+            send_output(tx, batch, baseline_metrics).await?;
+        }
+    }
+
+    if !groups.is_empty() {
+        let batch = create_batch_from_map(&mode, &groups, num_group_expr, schema);
+        send_output(tx, batch, baseline_metrics).await?;
+    }
+    Ok(())
+}
+
+/// Sends an output batch of the aggregation, failing if the receiver is gone
+async fn send_output(
+    tx: &Sender<ArrowResult<RecordBatch>>,
+    batch: ArrowResult<RecordBatch>,
+    baseline_metrics: &BaselineMetrics,
+) -> ArrowResult<()> {
+    let batch = batch?.record_output(baseline_metrics);
+    tx.send(Ok(batch)).await.map_err(|e| {
+        DataFusionError::Execution(format!("Aggregation output receiver is gone: {}", e))
+            .into_arrow_external_error()
+    })
+}
+
+/// Returns true if all the aggregates can convert their input rows to
+/// states, which is required to pass rows through a partial aggregation
+fn supports_pass_through(
+    aggr_expr: &[Arc<dyn AggregateExpr>],
+    aggregate_expressions: &[Vec<Arc<dyn PhysicalExpr>>],
+    input_schema: &Schema,
+) -> Result<bool> {
+    for (expr, values) in aggr_expr.iter().zip(aggregate_expressions) {
+        let values = values
+            .iter()
+            .map(|e| Ok(new_empty_array(&e.data_type(input_schema)?)))
+            .collect::<Result<Vec<_>>>()?;
+        if expr.row_states(&values)?.is_none() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Passes the rows of `batch` through a partial aggregation, each row as a
+/// group of its own
+fn pass_through_batch(
+    group_expr: &[Arc<dyn PhysicalExpr>],
+    aggr_expr: &[Arc<dyn AggregateExpr>],
+    aggregate_expressions: &[Vec<Arc<dyn PhysicalExpr>>],
+    batch: &RecordBatch,
+    output_schema: &SchemaRef,
+) -> ArrowResult<RecordBatch> {
+    let mut columns = evaluate(group_expr, batch)
+        .map_err(DataFusionError::into_arrow_external_error)?;
+    let aggr_input_values = evaluate_many(aggregate_expressions, batch)
+        .map_err(DataFusionError::into_arrow_external_error)?;
+    for (expr, values) in aggr_expr.iter().zip(aggr_input_values) {
+        let states = expr
+            .row_states(&values)
+            .map_err(DataFusionError::into_arrow_external_error)?
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "{} can not pass rows through a partial aggregation",
+                    expr.name()
+                ))
+                .into_arrow_external_error()
+            })?;
+        columns.extend(states);
+    }
+
+    let columns = columns
+        .iter()
+        .zip(output_schema.fields().iter())
+        .map(|(col, desired_field)| cast(col, desired_field.data_type()))
+        .collect::<ArrowResult<Vec<_>>>()?;
+
+    RecordBatch::try_new(output_schema.clone(), columns)
 }
 
 impl GroupedHashAggregateStream {
     /// Create a new HashAggregateStream
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         mode: AggregateMode,
         schema: SchemaRef,
         group_expr: Vec<Arc<dyn PhysicalExpr>>,
         aggr_expr: Vec<Arc<dyn AggregateExpr>>,
         input: SendableRecordBatchStream,
+        skip_partial: Option<SkipPartialAggregation>,
+        max_groups: Option<usize>,
         baseline_metrics: BaselineMetrics,
+        skipped_rows: metrics::Count,
+        spilled_groups: metrics::Count,
     ) -> Self {
-        let (tx, rx) = futures::channel::oneshot::channel();
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
 
         let schema_clone = schema.clone();
-        let elapsed_compute = baseline_metrics.elapsed_compute().clone();
 
-        let join_handle = tokio::spawn(async move {
+        let join_handle = cancellation::spawn(async move {
             let result = compute_grouped_hash_aggregate(
                 mode,
                 schema_clone,
                 group_expr,
                 aggr_expr,
                 input,
-                elapsed_compute,
+                skip_partial,
+                max_groups,
+                &baseline_metrics,
+                skipped_rows,
+                spilled_groups,
+                &tx,
             )
-            .await
-            .record_output(&baseline_metrics);
+            .await;
 
-            // failing here is OK, the receiver is gone and does not care about the result
-            tx.send(result).ok();
+            if let Err(e) = result {
+                // failing here is OK, the receiver is gone and does not care about the result
+                tx.send(Err(e)).await.ok();
+            }
         });
 
         Self {
             schema,
-            output: rx,
-            finished: false,
+            output: ReceiverStream::new(rx),
             drop_helper: AbortOnDropSingle::new(join_handle),
         }
     }
@@ -589,6 +1157,10 @@ struct GroupState {
     /// The actual group by values, one for each group column
     group_by_values: Box<[ScalarValue]>,
 
+    /// The group by values encoded in the row format, if their types support
+    /// it. The types are the same for all the batches.
+    group_key: Box<[u8]>,
+
     // Accumulator state, one for each aggregate
     accumulator_set: Vec<AccumulatorItem>,
 
@@ -631,28 +1203,7 @@ impl Stream for GroupedHashAggregateStream {
         self: std::pin::Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        if self.finished {
-            return Poll::Ready(None);
-        }
-
-        // is the output ready?
-        let this = self.project();
-        let output_poll = this.output.poll(cx);
-
-        match output_poll {
-            Poll::Ready(result) => {
-                *this.finished = true;
-
-                // check for error in receiving channel and unwrap actual result
-                let result = match result {
-                    Err(e) => Err(ArrowError::ExternalError(Box::new(e))), // error receiving
-                    Ok(result) => result,
-                };
-
-                Poll::Ready(Some(result))
-            }
-            Poll::Pending => Poll::Pending,
-        }
+        self.project().output.poll_next(cx)
     }
 }
 
@@ -788,7 +1339,7 @@ impl HashAggregateStream {
 
         let schema_clone = schema.clone();
         let elapsed_compute = baseline_metrics.elapsed_compute().clone();
-        let join_handle = tokio::spawn(async move {
+        let join_handle = cancellation::spawn(async move {
             let result = compute_hash_aggregate(
                 mode,
                 schema_clone,
@@ -885,14 +1436,14 @@ impl RecordBatchStream for HashAggregateStream {
 /// Create a RecordBatch with all group keys and accumulator' states or values.
 fn create_batch_from_map(
     mode: &AggregateMode,
-    accumulators: &Accumulators,
+    group_states: &[GroupState],
     num_group_expr: usize,
     output_schema: &Schema,
 ) -> ArrowResult<RecordBatch> {
-    if accumulators.group_states.is_empty() {
+    if group_states.is_empty() {
         return Ok(RecordBatch::new_empty(Arc::new(output_schema.to_owned())));
     }
-    let accs = &accumulators.group_states[0].accumulator_set;
+    let accs = &group_states[0].accumulator_set;
     let mut acc_data_types: Vec<usize> = vec![];
 
     // Calculate number/shape of state arrays
@@ -913,8 +1464,7 @@ fn create_batch_from_map(
     let mut columns = (0..num_group_expr)
         .map(|i| {
             ScalarValue::iter_to_array(
-                accumulators
-                    .group_states
+                group_states
                     .iter()
                     .map(|group_state| group_state.group_by_values[i].clone()),
             )
@@ -927,22 +1477,20 @@ fn create_batch_from_map(
         for y in 0..state_len {
             match mode {
                 AggregateMode::Partial => {
-                    let res = ScalarValue::iter_to_array(
-                        accumulators.group_states.iter().map(|group_state| {
+                    let res = ScalarValue::iter_to_array(group_states.iter().map(
+                        |group_state| {
                             let x = group_state.accumulator_set[x].state().unwrap();
                             x[y].clone()
-                        }),
-                    )
+                        },
+                    ))
                     .map_err(DataFusionError::into_arrow_external_error)?;
 
                     columns.push(res);
                 }
                 AggregateMode::Final | AggregateMode::FinalPartitioned => {
-                    let res = ScalarValue::iter_to_array(
-                        accumulators.group_states.iter().map(|group_state| {
-                            group_state.accumulator_set[x].evaluate().unwrap()
-                        }),
-                    )
+                    let res = ScalarValue::iter_to_array(group_states.iter().map(
+                        |group_state| group_state.accumulator_set[x].evaluate().unwrap(),
+                    ))
                     .map_err(DataFusionError::into_arrow_external_error)?;
                     columns.push(res);
                 }
@@ -1012,9 +1560,10 @@ mod tests {
     use crate::physical_plan::expressions::{col, Avg};
     use crate::test::assert_is_pending;
     use crate::test::exec::{assert_strong_count_converges_to_zero, BlockingExec};
-    use crate::{assert_batches_sorted_eq, physical_plan::common};
+    use crate::{assert_batches_eq, assert_batches_sorted_eq, physical_plan::common};
 
     use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+    use crate::physical_plan::memory::MemoryExec;
 
     /// some mock data to aggregates
     fn some_data() -> (Arc<Schema>, Vec<RecordBatch>) {
@@ -1235,6 +1784,139 @@ mod tests {
         check_aggregates(input).await
     }
 
+    #[tokio::test]
+    async fn skip_partial_aggregation() -> Result<()> {
+        let (schema, batches) = some_data();
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None)?);
+
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> =
+            vec![(col("a", &schema)?, "a".to_string())];
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Avg::new(
+            col("b", &schema)?,
+            "AVG(b)".to_string(),
+            DataType::Float64,
+        ))];
+
+        // the first batch has 3 groups for 4 rows, the second one is passed through
+        let partial_aggregate = Arc::new(
+            HashAggregateExec::try_new(
+                AggregateMode::Partial,
+                groups.clone(),
+                aggregates.clone(),
+                input,
+                schema.clone(),
+            )?
+            .with_skip_partial_aggregation(Some(SkipPartialAggregation {
+                probe_rows: 4,
+                min_ratio: 0.5,
+            })),
+        );
+
+        let result = common::collect(partial_aggregate.execute(0).await?).await?;
+        let expected = vec![
+            "+---+---------------+-------------+",
+            "| a | AVG(b)[count] | AVG(b)[sum] |",
+            "+---+---------------+-------------+",
+            "| 2 | 1             | 1           |",
+            "| 2 | 1             | 1           |",
+            "| 3 | 1             | 2           |",
+            "| 3 | 1             | 2           |",
+            "| 3 | 1             | 3           |",
+            "| 4 | 1             | 4           |",
+            "| 4 | 2             | 7           |",
+            "+---+---------------+-------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+
+        let metrics = partial_aggregate.metrics().unwrap();
+        let skipped_rows = metrics
+            .sum(|m| m.value().name() == "skipped_aggregation_rows")
+            .map(|v| v.as_usize());
+        assert_eq!(Some(4), skipped_rows);
+
+        let final_aggregate = Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Final,
+            vec![(col("a", &partial_aggregate.schema())?, "a".to_string())],
+            aggregates,
+            partial_aggregate,
+            schema,
+        )?);
+
+        let result = common::collect(final_aggregate.execute(0).await?).await?;
+        let expected = vec![
+            "+---+--------------------+",
+            "| a | AVG(b)             |",
+            "+---+--------------------+",
+            "| 2 | 1                  |",
+            "| 3 | 2.3333333333333335 |",
+            "| 4 | 3.6666666666666665 |",
+            "+---+--------------------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn switch_to_sorted_aggregation() -> Result<()> {
+        let (schema, batches) = some_data();
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None)?);
+
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> =
+            vec![(col("a", &schema)?, "a".to_string())];
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Avg::new(
+            col("b", &schema)?,
+            "AVG(b)".to_string(),
+            DataType::Float64,
+        ))];
+
+        // each batch has 3 groups, which the partial aggregation emits right away
+        let partial_aggregate = Arc::new(
+            HashAggregateExec::try_new(
+                AggregateMode::Partial,
+                groups,
+                aggregates.clone(),
+                input,
+                schema.clone(),
+            )?
+            .with_max_groups(Some(1)),
+        );
+        let result = common::collect(partial_aggregate.execute(0).await?).await?;
+        let num_rows: usize = result.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(num_rows, 6);
+
+        // and the final aggregation spills
+        let final_aggregate = Arc::new(
+            HashAggregateExec::try_new(
+                AggregateMode::Final,
+                vec![(col("a", &partial_aggregate.schema())?, "a".to_string())],
+                aggregates,
+                partial_aggregate,
+                schema,
+            )?
+            .with_max_groups(Some(1)),
+        );
+        let result = common::collect(final_aggregate.execute(0).await?).await?;
+        let expected = vec![
+            "+---+--------------------+",
+            "| a | AVG(b)             |",
+            "+---+--------------------+",
+            "| 2 | 1                  |",
+            "| 3 | 2.3333333333333335 |",
+            "| 4 | 3.6666666666666665 |",
+            "+---+--------------------+",
+        ];
+        assert_batches_eq!(&expected, &result);
+
+        let metrics = final_aggregate.metrics().unwrap();
+        let spilled_groups = metrics
+            .sum(|m| m.value().name() == "spilled_aggregation_groups")
+            .map(|v| v.as_usize());
+        assert_eq!(Some(6), spilled_groups);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_drop_cancel_without_groups() -> Result<()> {
         let schema =
@@ -35,7 +35,7 @@ use std::{any::Any, usize};
 use std::{time::Instant, vec};
 
 use async_trait::async_trait;
-use futures::{Stream, StreamExt, TryStreamExt};
+use futures::{Stream, StreamExt};
 use tokio::sync::Mutex;
 
 use arrow::array::Array;
@@ -45,13 +45,17 @@ use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 
 use arrow::array::{
-    Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
-    StringArray, TimestampNanosecondArray, UInt16Array, UInt32Array, UInt64Array,
-    UInt8Array,
+    BinaryArray, FixedSizeBinaryArray, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, Int8Array, LargeBinaryArray, StringArray,
+    TimestampNanosecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
 };
 
 use hashbrown::raw::RawTable;
 
+use super::{
+    cancellation::CancellationToken, hash_utils::create_hashes, row_format::RowKeys,
+    Statistics,
+};
 use super::{
     coalesce_partitions::CoalescePartitionsExec,
     join_utils::{build_join_schema, check_join_is_valid, ColumnIndex, JoinOn, JoinSide},
@@ -60,17 +64,19 @@ use super::{
     expressions::Column,
     metrics::{self, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet},
 };
-use super::{hash_utils::create_hashes, Statistics};
 use crate::error::{DataFusionError, Result};
+use crate::execution::memory_manager::MemoryReservation;
 use crate::logical_plan::JoinType;
 
 use super::{
-    DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+    DisplayFormatType, Distribution, ExecutionPlan, Partitioning, RecordBatchStream,
     SendableRecordBatchStream,
 };
 use crate::arrow::datatypes::TimeUnit;
 use crate::physical_plan::coalesce_batches::concat_batches;
+use crate::physical_plan::dictionary::is_dictionary;
 use crate::physical_plan::PhysicalExpr;
+use crate::scalar::ScalarValue;
 use log::debug;
 use std::fmt;
 
@@ -94,7 +100,9 @@ impl fmt::Debug for JoinHashMap {
     }
 }
 
-type JoinLeftData = Arc<(JoinHashMap, RecordBatch)>;
+/// The hash map, the batch and the keys of the batch in the row format, if
+/// their types support it, of the build side
+type JoinLeftData = Arc<(JoinHashMap, RecordBatch, Option<RowKeys>, MemoryReservation)>;
 
 /// join execution plan executes partitions in parallel and combines them into a set of
 /// partitions.
@@ -187,6 +195,12 @@ impl HashJoinExec {
         let left_schema = left.schema();
         let right_schema = right.schema();
         check_join_is_valid(&left_schema, &right_schema, &on)?;
+        if *join_type == JoinType::NullAwareAnti && (on.len() != 1 || *null_equals_null) {
+            return Err(DataFusionError::Plan(
+                "A null aware anti join must have exactly one key, on which null != null"
+                    .to_string(),
+            ));
+        }
 
         let (schema, column_indices) =
             build_join_schema(&left_schema, &right_schema, join_type);
@@ -272,12 +286,48 @@ impl ExecutionPlan for HashJoinExec {
         }
     }
 
+    fn required_child_distribution(&self) -> Distribution {
+        match self.join_type {
+            // whether a left row is in the output depends on all the right rows, which
+            // must all be probed against all the left rows
+            JoinType::NullAwareAnti => Distribution::SinglePartition,
+            _ => Distribution::UnspecifiedDistribution,
+        }
+    }
+
     fn output_partitioning(&self) -> Partitioning {
-        self.right.output_partitioning()
+        match (self.join_type, self.right.output_partitioning()) {
+            // every row of the right input stays in its partition, and the columns of
+            // the right input follow the columns of the left input
+            (JoinType::Inner | JoinType::Right, Partitioning::Hash(exprs, n)) => {
+                let left_columns = self.left.schema().fields().len();
+                let exprs = exprs
+                    .iter()
+                    .map(|expr| {
+                        let column = expr.as_any().downcast_ref::<Column>()?;
+                        Some(Arc::new(Column::new(
+                            column.name(),
+                            column.index() + left_columns,
+                        )) as Arc<dyn PhysicalExpr>)
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match exprs {
+                    Some(exprs) => Partitioning::Hash(exprs, n),
+                    None => Partitioning::UnknownPartitioning(n),
+                }
+            }
+            (JoinType::Inner | JoinType::Right, partitioning) => partitioning,
+            (_, partitioning) => {
+                Partitioning::UnknownPartitioning(partitioning.partition_count())
+            }
+        }
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
         let on_left = self.on.iter().map(|on| on.0.clone()).collect::<Vec<_>>();
+        // building the hash table is CPU bound, check between batches whether the
+        // query was cancelled
+        let cancellation = CancellationToken::current();
         // we only want to compute the build side once for PartitionMode::CollectLeft
         let left_data = {
             match self.mode {
@@ -292,47 +342,22 @@ impl ExecutionPlan for HashJoinExec {
                             // merge all left parts into a single stream
                             let merge = CoalescePartitionsExec::new(self.left.clone());
                             let stream = merge.execute(0).await?;
-
-                            // This operation performs 2 steps at once:
-                            // 1. creates a [JoinHashMap] of all batches from the stream
-                            // 2. stores the batches in a vector.
-                            let initial = (0, Vec::new());
-                            let (num_rows, batches) = stream
-                                .try_fold(initial, |mut acc, batch| async {
-                                    acc.0 += batch.num_rows();
-                                    acc.1.push(batch);
-                                    Ok(acc)
-                                })
-                                .await?;
-                            let mut hashmap =
-                                JoinHashMap(RawTable::with_capacity(num_rows));
-                            let mut hashes_buffer = Vec::new();
-                            let mut offset = 0;
-                            for batch in batches.iter() {
-                                hashes_buffer.clear();
-                                hashes_buffer.resize(batch.num_rows(), 0);
-                                update_hash(
-                                    &on_left,
-                                    batch,
-                                    &mut hashmap,
-                                    offset,
-                                    &self.random_state,
-                                    &mut hashes_buffer,
-                                )?;
-                                offset += batch.num_rows();
-                            }
-                            // Merge all batches into a single batch, so we
-                            // can directly index into the arrays
-                            let single_batch =
-                                concat_batches(&self.left.schema(), &batches, num_rows)?;
-
-                            let left_side = Arc::new((hashmap, single_batch));
+                            let reservation =
+                                MemoryReservation::new("HashJoinExec build side");
+                            let left_side = build_left_side(
+                                stream,
+                                &on_left,
+                                &self.random_state,
+                                &cancellation,
+                                reservation,
+                            )
+                            .await?;
 
                             *build_side = Some(left_side.clone());
 
                             debug!(
                                 "Built build-side of hash join containing {} rows in {} ms",
-                                num_rows,
+                                left_side.1.num_rows(),
                                 start.elapsed().as_millis()
                             );
 
@@ -345,45 +370,23 @@ impl ExecutionPlan for HashJoinExec {
 
                     // Load 1 partition of left side in memory
                     let stream = self.left.execute(partition).await?;
-
-                    // This operation performs 2 steps at once:
-                    // 1. creates a [JoinHashMap] of all batches from the stream
-                    // 2. stores the batches in a vector.
-                    let initial = (0, Vec::new());
-                    let (num_rows, batches) = stream
-                        .try_fold(initial, |mut acc, batch| async {
-                            acc.0 += batch.num_rows();
-                            acc.1.push(batch);
-                            Ok(acc)
-                        })
-                        .await?;
-                    let mut hashmap = JoinHashMap(RawTable::with_capacity(num_rows));
-                    let mut hashes_buffer = Vec::new();
-                    let mut offset = 0;
-                    for batch in batches.iter() {
-                        hashes_buffer.clear();
-                        hashes_buffer.resize(batch.num_rows(), 0);
-                        update_hash(
-                            &on_left,
-                            batch,
-                            &mut hashmap,
-                            offset,
-                            &self.random_state,
-                            &mut hashes_buffer,
-                        )?;
-                        offset += batch.num_rows();
-                    }
-                    // Merge all batches into a single batch, so we
-                    // can directly index into the arrays
-                    let single_batch =
-                        concat_batches(&self.left.schema(), &batches, num_rows)?;
-
-                    let left_side = Arc::new((hashmap, single_batch));
+                    let reservation = MemoryReservation::new(format!(
+                        "HashJoinExec build side {}",
+                        partition
+                    ));
+                    let left_side = build_left_side(
+                        stream,
+                        &on_left,
+                        &self.random_state,
+                        &cancellation,
+                        reservation,
+                    )
+                    .await?;
 
                     debug!(
                         "Built build-side {} of hash join containing {} rows in {} ms",
                         partition,
-                        num_rows,
+                        left_side.1.num_rows(),
                         start.elapsed().as_millis()
                     );
 
@@ -400,9 +403,11 @@ impl ExecutionPlan for HashJoinExec {
 
         let num_rows = left_data.1.num_rows();
         let visited_left_side = match self.join_type {
-            JoinType::Left | JoinType::Full | JoinType::Semi | JoinType::Anti => {
-                vec![false; num_rows]
-            }
+            JoinType::Left
+            | JoinType::Full
+            | JoinType::Semi
+            | JoinType::Anti
+            | JoinType::NullAwareAnti => vec![false; num_rows],
             JoinType::Inner | JoinType::Right => vec![],
         };
         Ok(Box::pin(HashJoinStream::new(
@@ -450,6 +455,57 @@ impl ExecutionPlan for HashJoinExec {
 
 /// Updates `hash` with new entries from [RecordBatch] evaluated against the expressions `on`,
 /// assuming that the [RecordBatch] corresponds to the `index`th
+/// Loads the build side of the join in memory and builds the [JoinHashMap] of its
+/// keys. The memory of each batch is reserved before the batch is kept, and the
+/// memory of the hash table and of the combined batch before they are built.
+async fn build_left_side(
+    mut stream: SendableRecordBatchStream,
+    on_left: &[Column],
+    random_state: &RandomState,
+    cancellation: &CancellationToken,
+    mut reservation: MemoryReservation,
+) -> Result<JoinLeftData> {
+    let schema = stream.schema();
+    let mut batches = vec![];
+    let mut num_rows = 0;
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        reservation.try_grow_batch(&batch)?;
+        num_rows += batch.num_rows();
+        batches.push(batch);
+    }
+    let batches_size = reservation.size();
+
+    reservation.try_grow(num_rows * std::mem::size_of::<(u64, SmallVec<[u64; 1]>)>())?;
+    let mut hashmap = JoinHashMap(RawTable::with_capacity(num_rows));
+    let mut hashes_buffer = Vec::new();
+    let mut offset = 0;
+    for batch in batches.iter() {
+        cancellation.check()?;
+        hashes_buffer.clear();
+        hashes_buffer.resize(batch.num_rows(), 0);
+        update_hash(
+            on_left,
+            batch,
+            &mut hashmap,
+            offset,
+            random_state,
+            &mut hashes_buffer,
+        )?;
+        offset += batch.num_rows();
+    }
+
+    // Merge all batches into a single batch, so we can directly index into the
+    // arrays. The batches are copied, and released once merged.
+    reservation.try_grow(batches_size)?;
+    let single_batch = concat_batches(&schema, &batches, num_rows)?;
+    drop(batches);
+    reservation.shrink(batches_size);
+
+    let left_keys = row_keys(on_left, &single_batch)?;
+    Ok(Arc::new((hashmap, single_batch, left_keys, reservation)))
+}
+
 fn update_hash(
     on: &[Column],
     batch: &RecordBatch,
@@ -506,6 +562,10 @@ struct HashJoinStream {
     // TODO: use a more memory efficient data structure, https://github.com/apache/arrow-datafusion/issues/240
     /// There is nothing to process anymore and left side is processed in case of left join
     is_exhausted: bool,
+    /// Whether a right row has a null key, in case of null aware anti join
+    right_has_null_key: bool,
+    /// Number of right rows, in case of null aware anti join
+    right_rows: usize,
     /// Metrics
     join_metrics: HashJoinMetrics,
     /// Information of index and left / right placement of columns
@@ -540,12 +600,46 @@ impl HashJoinStream {
             random_state,
             visited_left_side,
             is_exhausted: false,
+            right_has_null_key: false,
+            right_rows: 0,
             join_metrics,
             null_equals_null,
         }
     }
 }
 
+impl HashJoinStream {
+    /// Records whether the keys of a right batch have nulls, for null aware anti join
+    fn update_right_keys(&mut self, batch: &RecordBatch) -> Result<()> {
+        let keys = self.on_right[0]
+            .evaluate(batch)?
+            .into_array(batch.num_rows());
+        self.right_has_null_key |= keys.null_count() > 0;
+        self.right_rows += batch.num_rows();
+        Ok(())
+    }
+
+    /// Marks the left rows that must not be output by a null aware anti join as
+    /// visited: all of them if a right key is null, as `x NOT IN (.., NULL)` is
+    /// never true, else those with a null key, unless the right side is empty.
+    fn exclude_null_aware_rows(&mut self) -> Result<()> {
+        if self.right_has_null_key {
+            self.visited_left_side.iter_mut().for_each(|v| *v = true);
+        } else if self.right_rows > 0 {
+            let left_batch = &self.left_data.1;
+            let keys = self.on_left[0]
+                .evaluate(left_batch)?
+                .into_array(left_batch.num_rows());
+            for (row, visited) in self.visited_left_side.iter_mut().enumerate() {
+                if keys.is_null(row) {
+                    *visited = true;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 impl RecordBatchStream for HashJoinStream {
     fn schema(&self) -> SchemaRef {
         self.schema.clone()
@@ -609,7 +703,10 @@ fn build_batch(
     )
     .unwrap();
 
-    if matches!(join_type, JoinType::Semi | JoinType::Anti) {
+    if matches!(
+        join_type,
+        JoinType::Semi | JoinType::Anti | JoinType::NullAwareAnti
+    ) {
         return Ok((
             RecordBatch::new_empty(Arc::new(schema.clone())),
             left_indices,
@@ -674,8 +771,27 @@ fn build_join_indexes(
     let hash_values = create_hashes(&keys_values, random_state, hashes_buffer)?;
     let left = &left_data.0;
 
+    // the keys are compared in the row format, if their types support it
+    let right_keys = match &left_data.2 {
+        Some(_) => RowKeys::try_new(&keys_values)?,
+        None => None,
+    };
+    let keys_equal = |left_row: usize, right_row: usize| match (&left_data.2, &right_keys)
+    {
+        (Some(left_keys), Some(right_keys)) => {
+            Ok(left_keys.row_equals(left_row, right_keys, right_row, *null_equals_null))
+        }
+        _ => equal_rows(
+            left_row,
+            right_row,
+            &left_join_values,
+            &keys_values,
+            *null_equals_null,
+        ),
+    };
+
     match join_type {
-        JoinType::Inner | JoinType::Semi | JoinType::Anti => {
+        JoinType::Inner | JoinType::Semi | JoinType::Anti | JoinType::NullAwareAnti => {
             // Using a buffer builder to avoid slower normal builder
             let mut left_indices = UInt64BufferBuilder::new(0);
             let mut right_indices = UInt32BufferBuilder::new(0);
@@ -692,13 +808,7 @@ fn build_join_indexes(
                 {
                     for &i in indices {
                         // Check hash collisions
-                        if equal_rows(
-                            i as usize,
-                            row,
-                            &left_join_values,
-                            &keys_values,
-                            *null_equals_null,
-                        )? {
+                        if keys_equal(i as usize, row)? {
                             left_indices.append(i);
                             right_indices.append(row as u32);
                         }
@@ -732,13 +842,7 @@ fn build_join_indexes(
                 {
                     for &i in indices {
                         // Collision check
-                        if equal_rows(
-                            i as usize,
-                            row,
-                            &left_join_values,
-                            &keys_values,
-                            *null_equals_null,
-                        )? {
+                        if keys_equal(i as usize, row)? {
                             left_indices.append_value(i)?;
                             right_indices.append_value(row as u32)?;
                         }
@@ -756,13 +860,7 @@ fn build_join_indexes(
                     Some((_, indices)) => {
                         let mut no_match = true;
                         for &i in indices {
-                            if equal_rows(
-                                i as usize,
-                                row,
-                                &left_join_values,
-                                &keys_values,
-                                *null_equals_null,
-                            )? {
+                            if keys_equal(i as usize, row)? {
                                 left_indices.append_value(i)?;
                                 right_indices.append_value(row as u32)?;
                                 no_match = false;
@@ -800,6 +898,15 @@ macro_rules! equal_rows_elem {
     }};
 }
 
+/// Encodes the join keys `on` of `batch` in the row format
+fn row_keys(on: &[Column], batch: &RecordBatch) -> Result<Option<RowKeys>> {
+    let values = on
+        .iter()
+        .map(|c| Ok(c.evaluate(batch)?.into_array(batch.num_rows())))
+        .collect::<Result<Vec<_>>>()?;
+    RowKeys::try_new(&values)
+}
+
 /// Left and right row have equal values
 fn equal_rows(
     left: usize,
@@ -813,6 +920,21 @@ fn equal_rows(
         .iter()
         .zip(right_arrays)
         .all(|(l, r)| match l.data_type() {
+            _ if is_dictionary(l.data_type()) || is_dictionary(r.data_type()) => {
+                // the keys of dictionaries are compared by their values, as the
+                // other side may not have the same dictionary, or be a dictionary
+                match (
+                    ScalarValue::try_from_array(l, left),
+                    ScalarValue::try_from_array(r, right),
+                ) {
+                    (Ok(l), Ok(r)) if l.is_null() && r.is_null() => null_equals_null,
+                    (Ok(l), Ok(r)) => !l.is_null() && l == r,
+                    (Err(e), _) | (_, Err(e)) => {
+                        err = Some(Err(e));
+                        false
+                    }
+                }
+            }
             DataType::Null => true,
             DataType::Boolean => {
                 equal_rows_elem!(BooleanArray, l, r, left, right, null_equals_null)
@@ -847,7 +969,7 @@ fn equal_rows(
             DataType::Float64 => {
                 equal_rows_elem!(Float64Array, l, r, left, right, null_equals_null)
             }
-            DataType::Timestamp(time_unit, None) => match time_unit {
+            DataType::Timestamp(time_unit, _) => match time_unit {
                 TimeUnit::Second => {
                     equal_rows_elem!(
                         TimestampSecondArray,
@@ -895,6 +1017,22 @@ fn equal_rows(
             DataType::LargeUtf8 => {
                 equal_rows_elem!(LargeStringArray, l, r, left, right, null_equals_null)
             }
+            DataType::Binary => {
+                equal_rows_elem!(BinaryArray, l, r, left, right, null_equals_null)
+            }
+            DataType::LargeBinary => {
+                equal_rows_elem!(LargeBinaryArray, l, r, left, right, null_equals_null)
+            }
+            DataType::FixedSizeBinary(_) => {
+                equal_rows_elem!(
+                    FixedSizeBinaryArray,
+                    l,
+                    r,
+                    left,
+                    right,
+                    null_equals_null
+                )
+            }
             _ => {
                 // This is internal because we should have caught this before.
                 err = Some(Err(DataFusionError::Internal(
@@ -967,6 +1105,11 @@ impl Stream for HashJoinStream {
             .map(|maybe_batch| match maybe_batch {
                 Some(Ok(batch)) => {
                     let timer = self.join_metrics.join_time.timer();
+                    if self.join_type == JoinType::NullAwareAnti {
+                        if let Err(e) = self.update_right_keys(&batch) {
+                            return Some(Err(e.into_arrow_external_error()));
+                        }
+                    }
                     let result = build_batch(
                         &batch,
                         &self.left_data,
@@ -989,7 +1132,8 @@ impl Stream for HashJoinStream {
                             JoinType::Left
                             | JoinType::Full
                             | JoinType::Semi
-                            | JoinType::Anti => {
+                            | JoinType::Anti
+                            | JoinType::NullAwareAnti => {
                                 left_side.iter().flatten().for_each(|x| {
                                     self.visited_left_side[x as usize] = true;
                                 });
@@ -1007,8 +1151,14 @@ impl Stream for HashJoinStream {
                         | JoinType::Full
                         | JoinType::Semi
                         | JoinType::Anti
+                        | JoinType::NullAwareAnti
                             if !self.is_exhausted =>
                         {
+                            if self.join_type == JoinType::NullAwareAnti {
+                                if let Err(e) = self.exclude_null_aware_rows() {
+                                    return Some(Err(e.into_arrow_external_error()));
+                                }
+                            }
                             let result = produce_from_matched(
                                 &self.visited_left_side,
                                 &self.schema,
@@ -1032,6 +1182,7 @@ impl Stream for HashJoinStream {
                         | JoinType::Full
                         | JoinType::Semi
                         | JoinType::Anti
+                        | JoinType::NullAwareAnti
                         | JoinType::Inner
                         | JoinType::Right => {}
                     }
@@ -1053,6 +1204,8 @@ mod tests {
     };
 
     use super::*;
+    use arrow::array::DictionaryArray;
+    use arrow::datatypes::{Field, Int8Type};
     use std::sync::Arc;
 
     fn build_table(
@@ -1755,6 +1908,81 @@ mod tests {
         Ok(())
     }
 
+    fn build_nullable_table(
+        a: (&str, Vec<i32>),
+        b: (&str, Vec<Option<i32>>),
+    ) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(a.0, DataType::Int32, false),
+            Field::new(b.0, DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(a.1)),
+                Arc::new(Int32Array::from(b.1)),
+            ],
+        )
+        .unwrap();
+        Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn join_null_aware_anti() -> Result<()> {
+        let left = || {
+            build_nullable_table(
+                ("a1", vec![1, 2, 3]),
+                ("b1", vec![Some(4), None, Some(7)]),
+            )
+        };
+        let cases = vec![
+            // the left row with a null key is not output
+            (vec![Some(4), Some(5)], vec!["| 3  | 7  |"]),
+            // no left row is output when a right key is null
+            (vec![Some(4), None], vec![]),
+            // all the left rows are output when the right side is empty
+            (vec![], vec!["| 1  | 4  |", "| 2  |    |", "| 3  | 7  |"]),
+        ];
+        for (right_keys, rows) in cases {
+            let right = build_nullable_table(
+                ("a2", (0..right_keys.len() as i32).collect()),
+                ("b2", right_keys),
+            );
+            let on = vec![(
+                Column::new_with_schema("b1", &left().schema())?,
+                Column::new_with_schema("b2", &right.schema())?,
+            )];
+
+            let (columns, batches) =
+                join_collect(left(), right, on, &JoinType::NullAwareAnti, false).await?;
+            assert_eq!(columns, vec!["a1", "b1"]);
+
+            if rows.is_empty() {
+                assert!(batches.iter().all(|batch| batch.num_rows() == 0));
+            } else {
+                let mut expected = vec!["+----+----+", "| a1 | b1 |", "+----+----+"];
+                expected.extend(rows);
+                expected.push("+----+----+");
+                assert_batches_sorted_eq!(expected, &batches);
+            }
+        }
+
+        // only a single key is supported
+        let left = left();
+        let on = vec![
+            (
+                Column::new_with_schema("a1", &left.schema())?,
+                Column::new_with_schema("a1", &left.schema())?,
+            ),
+            (
+                Column::new_with_schema("b1", &left.schema())?,
+                Column::new_with_schema("b1", &left.schema())?,
+            ),
+        ];
+        assert!(join(left.clone(), left, on, &JoinType::NullAwareAnti, false).is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn join_right_one() -> Result<()> {
         let left = build_table(
@@ -1893,7 +2121,12 @@ mod tests {
             ("c", &vec![30, 40]),
         );
 
-        let left_data = JoinLeftData::new((JoinHashMap(hashmap_left), left));
+        let left_data = JoinLeftData::new((
+            JoinHashMap(hashmap_left),
+            left,
+            None,
+            MemoryReservation::new("test"),
+        ));
         let (l, r) = build_join_indexes(
             &left_data,
             &right,
@@ -1957,4 +2190,63 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn join_dictionary_keys() -> Result<()> {
+        let left_schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "d",
+                DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8)),
+                true,
+            ),
+            Field::new("a", DataType::Int32, false),
+        ]));
+        let dict: DictionaryArray<Int8Type> = vec![Some("x"), Some("y"), None, Some("x")]
+            .into_iter()
+            .collect();
+        let left_batch = RecordBatch::try_new(
+            left_schema.clone(),
+            vec![Arc::new(dict), Arc::new(Int32Array::from(vec![1, 2, 3, 4]))],
+        )?;
+        let right_schema = Arc::new(Schema::new(vec![
+            Field::new("s", DataType::Utf8, true),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let right_batch = RecordBatch::try_new(
+            right_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![Some("x"), Some("z"), None])),
+                Arc::new(Int32Array::from(vec![10, 20, 30])),
+            ],
+        )?;
+        let left = Arc::new(MemoryExec::try_new(
+            &[vec![left_batch]],
+            left_schema.clone(),
+            None,
+        )?);
+        let right = Arc::new(MemoryExec::try_new(
+            &[vec![right_batch]],
+            right_schema.clone(),
+            None,
+        )?);
+        // the dictionary keys are joined with the strings they encode
+        let on = vec![(
+            Column::new_with_schema("d", &left_schema)?,
+            Column::new_with_schema("s", &right_schema)?,
+        )];
+
+        let (_, batches) = join_collect(left, right, on, &JoinType::Inner, false).await?;
+
+        let expected = vec![
+            "+---+---+---+----+",
+            "| d | a | s | b  |",
+            "+---+---+---+----+",
+            "| x | 1 | x | 10 |",
+            "| x | 4 | x | 10 |",
+            "+---+---+---+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        Ok(())
+    }
 }
@@ -20,10 +20,11 @@
 use crate::error::{DataFusionError, Result};
 use ahash::{CallHasher, RandomState};
 use arrow::array::{
-    Array, ArrayRef, BooleanArray, Date32Array, Date64Array, DictionaryArray,
-    Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
-    LargeStringArray, StringArray, TimestampMicrosecondArray, TimestampMillisecondArray,
-    TimestampNanosecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+    Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Date64Array,
+    DictionaryArray, FixedSizeBinaryArray, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, Int8Array, LargeBinaryArray, LargeStringArray, StringArray,
+    TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+    TimestampSecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
 };
 use arrow::datatypes::{
     ArrowDictionaryKeyType, ArrowNativeType, DataType, Int16Type, Int32Type, Int64Type,
@@ -349,7 +350,17 @@ pub fn create_hashes<'a>(
                     multi_col
                 );
             }
-            DataType::Timestamp(TimeUnit::Millisecond, None) => {
+            DataType::Timestamp(TimeUnit::Second, _) => {
+                hash_array_primitive!(
+                    TimestampSecondArray,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
                 hash_array_primitive!(
                     TimestampMillisecondArray,
                     col,
@@ -359,7 +370,7 @@ pub fn create_hashes<'a>(
                     multi_col
                 );
             }
-            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
                 hash_array_primitive!(
                     TimestampMicrosecondArray,
                     col,
@@ -369,7 +380,7 @@ pub fn create_hashes<'a>(
                     multi_col
                 );
             }
-            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
                 hash_array_primitive!(
                     TimestampNanosecondArray,
                     col,
@@ -429,6 +440,29 @@ pub fn create_hashes<'a>(
                     multi_col
                 );
             }
+            DataType::Binary => {
+                hash_array!(BinaryArray, col, u8, hashes_buffer, random_state, multi_col);
+            }
+            DataType::LargeBinary => {
+                hash_array!(
+                    LargeBinaryArray,
+                    col,
+                    u8,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::FixedSizeBinary(_) => {
+                hash_array!(
+                    FixedSizeBinaryArray,
+                    col,
+                    u8,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
             DataType::Dictionary(index_type, _) => match **index_type {
                 DataType::Int8 => {
                     create_hashes_dictionary::<Int8Type>(
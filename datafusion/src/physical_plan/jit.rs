@@ -0,0 +1,795 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Compilation of filter and projection expressions to native code with
+//! [cranelift], enabled with the `jit` feature.
+//!
+//! An expression tree over non null numeric columns is compiled into a loop
+//! computing its value for each row of a batch, rather than evaluating each
+//! node of the tree on whole arrays, which saves the intermediate arrays. The
+//! expression is only compiled once it evaluated enough rows to be worth it,
+//! and it falls back to the interpreted evaluation for the batches with nulls,
+//! or when it cannot be compiled.
+//!
+//! [cranelift]: https://github.com/bytecodealliance/wasmtime/tree/main/cranelift
+
+use std::any::Any;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, Float32Array, Float64Array, Int32Array, Int64Array,
+};
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use cranelift::codegen::binemit::{NullStackMapSink, NullTrapSink};
+use cranelift::prelude::{
+    types, AbiParam, FloatCC, FunctionBuilder, FunctionBuilderContext, InstBuilder,
+    IntCC, MemFlags, Type, Value,
+};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Linkage, Module};
+use log::warn;
+
+use super::expressions::{
+    BinaryExpr, CastExpr, Column, Literal, NegativeExpr, NotExpr, TryCastExpr,
+};
+use super::{ColumnarValue, PhysicalExpr};
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Operator;
+use crate::scalar::ScalarValue;
+
+/// Number of rows an expression evaluates before it is compiled
+pub const JIT_THRESHOLD_ROWS: usize = 64 * 1024;
+
+/// An expression evaluated with native code compiled from its tree once it
+/// is hot, and with the interpreted expression otherwise
+#[derive(Debug)]
+pub struct JitExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    tree: Node,
+    /// the referenced columns, by index in the input
+    columns: Vec<(usize, JitType)>,
+    threshold: usize,
+    state: Mutex<JitState>,
+}
+
+#[derive(Debug)]
+enum JitState {
+    Interpreted { rows: usize },
+    Compiled(Arc<CompiledExpr>),
+    Failed,
+}
+
+impl JitExpr {
+    /// Create a JIT compiled expression for `expr`, or `None` if `expr` is not
+    /// supported by the JIT or would not benefit from it, such as a column or a
+    /// literal
+    pub fn try_new(expr: Arc<dyn PhysicalExpr>, input_schema: &Schema) -> Option<Self> {
+        if expr.as_any().is::<JitExpr>()
+            || expr.as_any().is::<Column>()
+            || expr.as_any().is::<Literal>()
+        {
+            return None;
+        }
+        let mut columns = vec![];
+        let tree = Node::try_new(&expr, input_schema, &mut columns)?;
+        Some(Self {
+            expr,
+            tree,
+            columns,
+            threshold: JIT_THRESHOLD_ROWS,
+            state: Mutex::new(JitState::Interpreted { rows: 0 }),
+        })
+    }
+
+    /// Compile the expression once it evaluated `threshold` rows rather than
+    /// [`JIT_THRESHOLD_ROWS`]
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// The interpreted expression
+    pub fn expr(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.expr
+    }
+
+    /// Whether the expression was compiled
+    pub fn is_compiled(&self) -> bool {
+        matches!(*self.state.lock().unwrap(), JitState::Compiled(_))
+    }
+
+    /// The compiled expression, compiling it if it is hot enough
+    fn compiled(&self, rows: usize) -> Option<Arc<CompiledExpr>> {
+        let mut state = self.state.lock().unwrap();
+        match &mut *state {
+            JitState::Compiled(compiled) => Some(compiled.clone()),
+            JitState::Failed => None,
+            JitState::Interpreted { rows: evaluated } => {
+                *evaluated += rows;
+                if *evaluated < self.threshold {
+                    return None;
+                }
+                match CompiledExpr::try_new(&self.tree, self.columns.clone()) {
+                    Ok(compiled) => {
+                        let compiled = Arc::new(compiled);
+                        *state = JitState::Compiled(compiled.clone());
+                        Some(compiled)
+                    }
+                    Err(e) => {
+                        warn!("Could not compile expression {}: {}", self.expr, e);
+                        *state = JitState::Failed;
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for JitExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
+
+impl PhysicalExpr for JitExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        self.expr.data_type(input_schema)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        self.expr.nullable(input_schema)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        if let Some(compiled) = self.compiled(batch.num_rows()) {
+            if let Some(array) = compiled.evaluate(batch)? {
+                return Ok(ColumnarValue::Array(array));
+            }
+        }
+        self.expr.evaluate(batch)
+    }
+}
+
+/// The types of the values the compiled code computes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JitType {
+    Boolean,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+}
+
+impl JitType {
+    fn try_new(data_type: &DataType) -> Option<Self> {
+        match data_type {
+            DataType::Boolean => Some(Self::Boolean),
+            DataType::Int32 => Some(Self::Int32),
+            DataType::Int64 => Some(Self::Int64),
+            DataType::Float32 => Some(Self::Float32),
+            DataType::Float64 => Some(Self::Float64),
+            _ => None,
+        }
+    }
+
+    fn data_type(self) -> DataType {
+        match self {
+            Self::Boolean => DataType::Boolean,
+            Self::Int32 => DataType::Int32,
+            Self::Int64 => DataType::Int64,
+            Self::Float32 => DataType::Float32,
+            Self::Float64 => DataType::Float64,
+        }
+    }
+
+    /// booleans are bytes, 0 or 1, rather than bits
+    fn native(self) -> Type {
+        match self {
+            Self::Boolean => types::I8,
+            Self::Int32 => types::I32,
+            Self::Int64 => types::I64,
+            Self::Float32 => types::F32,
+            Self::Float64 => types::F64,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        matches!(self, Self::Int32 | Self::Int64)
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, Self::Float32 | Self::Float64)
+    }
+}
+
+/// The expression tree the code is generated from
+#[derive(Debug)]
+enum Node {
+    /// the column at the given position in the inputs of the compiled code
+    Column(usize, JitType),
+    Literal(ScalarValue, JitType),
+    Binary(Box<Node>, Operator, Box<Node>, JitType),
+    Cast(Box<Node>, JitType),
+    Not(Box<Node>),
+    Negative(Box<Node>),
+}
+
+impl Node {
+    fn try_new(
+        expr: &Arc<dyn PhysicalExpr>,
+        input_schema: &Schema,
+        columns: &mut Vec<(usize, JitType)>,
+    ) -> Option<Self> {
+        let data_type = JitType::try_new(&expr.data_type(input_schema).ok()?)?;
+        let any = expr.as_any();
+        if let Some(column) = any.downcast_ref::<Column>() {
+            if data_type == JitType::Boolean {
+                // boolean arrays are bitmaps
+                return None;
+            }
+            let position = match columns
+                .iter()
+                .position(|(index, _)| *index == column.index())
+            {
+                Some(position) => position,
+                None => {
+                    columns.push((column.index(), data_type));
+                    columns.len() - 1
+                }
+            };
+            return Some(Self::Column(position, data_type));
+        }
+        if let Some(literal) = any.downcast_ref::<Literal>() {
+            return match literal.value() {
+                value if value.is_null() => None,
+                value => Some(Self::Literal(value.clone(), data_type)),
+            };
+        }
+        if let Some(binary) = any.downcast_ref::<BinaryExpr>() {
+            let left = Self::try_new(binary.left(), input_schema, columns)?;
+            let right = Self::try_new(binary.right(), input_schema, columns)?;
+            let operand_type = left.jit_type();
+            if operand_type != right.jit_type() {
+                return None;
+            }
+            let supported = match binary.op() {
+                // the compiled integer arithmetic wraps around on overflow
+                Operator::Plus | Operator::Minus | Operator::Multiply => {
+                    operand_type.is_float()
+                        || (operand_type != JitType::Boolean
+                            && !binary.fail_on_overflow())
+                }
+                // integer division by zero is an error rather than a trap
+                Operator::Divide => operand_type.is_float(),
+                Operator::Eq
+                | Operator::NotEq
+                | Operator::Lt
+                | Operator::LtEq
+                | Operator::Gt
+                | Operator::GtEq => operand_type != JitType::Boolean,
+                Operator::And | Operator::Or => operand_type == JitType::Boolean,
+                _ => false,
+            };
+            return supported.then(|| {
+                Self::Binary(Box::new(left), *binary.op(), Box::new(right), operand_type)
+            });
+        }
+        let cast = any
+            .downcast_ref::<CastExpr>()
+            .map(|cast| cast.expr())
+            .or_else(|| any.downcast_ref::<TryCastExpr>().map(|cast| cast.expr()));
+        if let Some(input) = cast {
+            let input = Self::try_new(input, input_schema, columns)?;
+            let widening = matches!(
+                (input.jit_type(), data_type),
+                (JitType::Int32, JitType::Int64)
+                    | (
+                        JitType::Int32 | JitType::Int64,
+                        JitType::Float32 | JitType::Float64
+                    )
+                    | (JitType::Float32, JitType::Float64)
+            );
+            return if input.jit_type() == data_type {
+                Some(input)
+            } else {
+                widening.then(|| Self::Cast(Box::new(input), data_type))
+            };
+        }
+        if let Some(not) = any.downcast_ref::<NotExpr>() {
+            let arg = Self::try_new(not.arg(), input_schema, columns)?;
+            return (arg.jit_type() == JitType::Boolean)
+                .then(|| Self::Not(Box::new(arg)));
+        }
+        if let Some(negative) = any.downcast_ref::<NegativeExpr>() {
+            let arg = Self::try_new(negative.arg(), input_schema, columns)?;
+            return (arg.jit_type() != JitType::Boolean)
+                .then(|| Self::Negative(Box::new(arg)));
+        }
+        None
+    }
+
+    fn jit_type(&self) -> JitType {
+        match self {
+            Self::Column(_, jit_type)
+            | Self::Literal(_, jit_type)
+            | Self::Cast(_, jit_type) => *jit_type,
+            Self::Binary(_, op, _, operand_type) => match op {
+                Operator::Plus
+                | Operator::Minus
+                | Operator::Multiply
+                | Operator::Divide => *operand_type,
+                _ => JitType::Boolean,
+            },
+            Self::Not(_) => JitType::Boolean,
+            Self::Negative(arg) => arg.jit_type(),
+        }
+    }
+}
+
+/// Signature of the compiled code, which computes the values of the `len` rows
+/// from the values of the input columns into `output`
+type EvaluateFn =
+    unsafe extern "C" fn(inputs: *const *const u8, len: i64, output: *mut u8);
+
+/// An expression compiled to native code
+struct CompiledExpr {
+    /// the module owning the memory of the code
+    module: Option<JITModule>,
+    function: EvaluateFn,
+    columns: Vec<(usize, JitType)>,
+    output_type: JitType,
+}
+
+// SAFETY: the compiled code only reads its arguments, and the module is only
+// used to free its memory once the expression is dropped
+unsafe impl Send for CompiledExpr {}
+unsafe impl Sync for CompiledExpr {}
+
+impl fmt::Debug for CompiledExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CompiledExpr")
+            .field("columns", &self.columns)
+            .field("output_type", &self.output_type)
+            .finish()
+    }
+}
+
+impl Drop for CompiledExpr {
+    fn drop(&mut self) {
+        if let Some(module) = self.module.take() {
+            // SAFETY: the code is not referenced anymore
+            unsafe { module.free_memory() };
+        }
+    }
+}
+
+impl CompiledExpr {
+    fn try_new(tree: &Node, columns: Vec<(usize, JitType)>) -> Result<Self> {
+        let mut module = JITModule::new(JITBuilder::new(default_libcall_names()));
+        let pointer = module.target_config().pointer_type();
+        if pointer != types::I64 {
+            return Err(DataFusionError::NotImplemented(
+                "JIT compilation of expressions on 32 bit targets".to_owned(),
+            ));
+        }
+        let output_type = tree.jit_type();
+
+        let mut ctx = module.make_context();
+        let params = &mut ctx.func.signature.params;
+        params.push(AbiParam::new(pointer));
+        params.push(AbiParam::new(types::I64));
+        params.push(AbiParam::new(pointer));
+        let mut builder_context = FunctionBuilderContext::new();
+        let mut builder = FunctionBuilder::new(&mut ctx.func, &mut builder_context);
+        let entry = builder.create_block();
+        let header = builder.create_block();
+        let body = builder.create_block();
+        let exit = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.append_block_param(header, types::I64);
+
+        builder.switch_to_block(entry);
+        builder.seal_block(entry);
+        let params = builder.block_params(entry).to_vec();
+        let (inputs, len, output) = (params[0], params[1], params[2]);
+        let inputs = (0..columns.len())
+            .map(|i| {
+                builder
+                    .ins()
+                    .load(pointer, MemFlags::trusted(), inputs, (i * 8) as i32)
+            })
+            .collect::<Vec<_>>();
+        let zero = builder.ins().iconst(types::I64, 0);
+        builder.ins().jump(header, &[zero]);
+
+        // loop over the rows
+        builder.switch_to_block(header);
+        let row = builder.block_params(header)[0];
+        let done = builder
+            .ins()
+            .icmp(IntCC::SignedGreaterThanOrEqual, row, len);
+        builder.ins().brnz(done, exit, &[]);
+        builder.ins().jump(body, &[]);
+        builder.seal_block(body);
+
+        builder.switch_to_block(body);
+        let mut codegen = Codegen {
+            builder: &mut builder,
+            inputs: &inputs,
+            row,
+        };
+        let value = codegen.value(tree)?;
+        let address = codegen.address(output, row, output_type);
+        builder.ins().store(MemFlags::trusted(), value, address, 0);
+        let next = builder.ins().iadd_imm(row, 1);
+        builder.ins().jump(header, &[next]);
+        builder.seal_block(header);
+
+        builder.switch_to_block(exit);
+        builder.seal_block(exit);
+        builder.ins().return_(&[]);
+        builder.finalize();
+
+        let id = module
+            .declare_function("evaluate", Linkage::Export, &ctx.func.signature)
+            .map_err(jit_error)?;
+        module
+            .define_function(id, &mut ctx, &mut NullTrapSink {}, &mut NullStackMapSink {})
+            .map_err(jit_error)?;
+        module.clear_context(&mut ctx);
+        module.finalize_definitions();
+        let code = module.get_finalized_function(id);
+        // SAFETY: the function was generated with the signature of `EvaluateFn`
+        let function = unsafe { std::mem::transmute::<*const u8, EvaluateFn>(code) };
+        Ok(Self {
+            module: Some(module),
+            function,
+            columns,
+            output_type,
+        })
+    }
+
+    /// Evaluates the expression on `batch`, or returns `None` when it cannot,
+    /// because of nulls in the referenced columns
+    fn evaluate(&self, batch: &RecordBatch) -> Result<Option<ArrayRef>> {
+        let mut inputs = Vec::with_capacity(self.columns.len());
+        for (index, jit_type) in &self.columns {
+            let array = batch.column(*index);
+            if array.null_count() > 0 {
+                return Ok(None);
+            }
+            if array.data_type() != &jit_type.data_type() {
+                return Err(DataFusionError::Internal(format!(
+                    "Compiled expression expects column {} of type {:?}, not {:?}",
+                    index,
+                    jit_type.data_type(),
+                    array.data_type()
+                )));
+            }
+            let data = array.data();
+            let width = jit_type.native().bytes() as usize;
+            // SAFETY: the offset is within the values of the array
+            inputs.push(unsafe { data.buffers()[0].as_ptr().add(data.offset() * width) });
+        }
+
+        let len = batch.num_rows();
+        let array: ArrayRef = match self.output_type {
+            JitType::Boolean => {
+                Arc::new(BooleanArray::from(self.run::<bool>(&inputs, len)))
+            }
+            JitType::Int32 => Arc::new(Int32Array::from(self.run::<i32>(&inputs, len))),
+            JitType::Int64 => Arc::new(Int64Array::from(self.run::<i64>(&inputs, len))),
+            JitType::Float32 => {
+                Arc::new(Float32Array::from(self.run::<f32>(&inputs, len)))
+            }
+            JitType::Float64 => {
+                Arc::new(Float64Array::from(self.run::<f64>(&inputs, len)))
+            }
+        };
+        Ok(Some(array))
+    }
+
+    /// Runs the compiled code, whose output are values of type `T`
+    fn run<T: Default + Clone>(&self, inputs: &[*const u8], len: usize) -> Vec<T> {
+        let mut output = vec![T::default(); len];
+        // SAFETY: the inputs have `len` values of the types the code was compiled
+        // for, booleans are written as 0 or 1, and the output has `len` values
+        unsafe {
+            (self.function)(inputs.as_ptr(), len as i64, output.as_mut_ptr() as *mut u8)
+        };
+        output
+    }
+}
+
+/// Generates the code computing the value of a node for the current row
+struct Codegen<'a, 'b> {
+    builder: &'a mut FunctionBuilder<'b>,
+    /// the pointers to the values of the input columns
+    inputs: &'a [Value],
+    row: Value,
+}
+
+impl Codegen<'_, '_> {
+    /// The address of the value of the current row in `values`
+    fn address(&mut self, values: Value, row: Value, jit_type: JitType) -> Value {
+        let offset = self
+            .builder
+            .ins()
+            .imul_imm(row, jit_type.native().bytes() as i64);
+        self.builder.ins().iadd(values, offset)
+    }
+
+    fn value(&mut self, node: &Node) -> Result<Value> {
+        Ok(match node {
+            Node::Column(position, jit_type) => {
+                let (values, row) = (self.inputs[*position], self.row);
+                let address = self.address(values, row, *jit_type);
+                self.builder.ins().load(
+                    jit_type.native(),
+                    MemFlags::trusted(),
+                    address,
+                    0,
+                )
+            }
+            Node::Literal(value, _) => {
+                let ins = self.builder.ins();
+                match value {
+                    ScalarValue::Boolean(Some(v)) => ins.iconst(types::I8, *v as i64),
+                    ScalarValue::Int32(Some(v)) => ins.iconst(types::I32, *v as i64),
+                    ScalarValue::Int64(Some(v)) => ins.iconst(types::I64, *v),
+                    ScalarValue::Float32(Some(v)) => ins.f32const(*v),
+                    ScalarValue::Float64(Some(v)) => ins.f64const(*v),
+                    other => {
+                        return Err(DataFusionError::Internal(format!(
+                            "Unsupported literal {:?} in compiled expression",
+                            other
+                        )))
+                    }
+                }
+            }
+            Node::Binary(left, op, right, operand_type) => {
+                let left = self.value(left)?;
+                let right = self.value(right)?;
+                self.binary(left, op, right, *operand_type)?
+            }
+            Node::Cast(input, jit_type) => {
+                let from = input.jit_type();
+                let value = self.value(input)?;
+                let ins = self.builder.ins();
+                match (from, jit_type) {
+                    (JitType::Int32, JitType::Int64) => ins.sextend(types::I64, value),
+                    (JitType::Float32, JitType::Float64) => {
+                        ins.fpromote(types::F64, value)
+                    }
+                    (from, to) if from.is_integer() && to.is_float() => {
+                        ins.fcvt_from_sint(to.native(), value)
+                    }
+                    (from, to) => {
+                        return Err(DataFusionError::Internal(format!(
+                            "Unsupported cast from {:?} to {:?} in compiled expression",
+                            from, to
+                        )))
+                    }
+                }
+            }
+            Node::Not(arg) => {
+                let arg = self.value(arg)?;
+                self.builder.ins().bxor_imm(arg, 1)
+            }
+            Node::Negative(arg) => {
+                let is_float = arg.jit_type().is_float();
+                let arg = self.value(arg)?;
+                if is_float {
+                    self.builder.ins().fneg(arg)
+                } else {
+                    self.builder.ins().ineg(arg)
+                }
+            }
+        })
+    }
+
+    fn binary(
+        &mut self,
+        left: Value,
+        op: &Operator,
+        right: Value,
+        operand_type: JitType,
+    ) -> Result<Value> {
+        let float = operand_type.is_float();
+        let ins = self.builder.ins();
+        let comparison = |int: IntCC, float: FloatCC| (int, float);
+        let (int_cc, float_cc) = match op {
+            Operator::Plus if float => return Ok(ins.fadd(left, right)),
+            Operator::Plus => return Ok(ins.iadd(left, right)),
+            Operator::Minus if float => return Ok(ins.fsub(left, right)),
+            Operator::Minus => return Ok(ins.isub(left, right)),
+            Operator::Multiply if float => return Ok(ins.fmul(left, right)),
+            Operator::Multiply => return Ok(ins.imul(left, right)),
+            Operator::Divide if float => return Ok(ins.fdiv(left, right)),
+            Operator::And => return Ok(ins.band(left, right)),
+            Operator::Or => return Ok(ins.bor(left, right)),
+            Operator::Eq => comparison(IntCC::Equal, FloatCC::Equal),
+            Operator::NotEq => comparison(IntCC::NotEqual, FloatCC::NotEqual),
+            Operator::Lt => comparison(IntCC::SignedLessThan, FloatCC::LessThan),
+            Operator::LtEq => {
+                comparison(IntCC::SignedLessThanOrEqual, FloatCC::LessThanOrEqual)
+            }
+            Operator::Gt => comparison(IntCC::SignedGreaterThan, FloatCC::GreaterThan),
+            Operator::GtEq => {
+                comparison(IntCC::SignedGreaterThanOrEqual, FloatCC::GreaterThanOrEqual)
+            }
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unsupported operator {} in compiled expression",
+                    other
+                )))
+            }
+        };
+        let condition = if float {
+            ins.fcmp(float_cc, left, right)
+        } else {
+            ins.icmp(int_cc, left, right)
+        };
+        Ok(self.builder.ins().bint(types::I8, condition))
+    }
+}
+
+fn jit_error(e: impl std::error::Error) -> DataFusionError {
+    DataFusionError::Execution(format!("JIT compilation failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{binary, cast, col, lit, not};
+    use arrow::datatypes::Field;
+
+    fn batch(a: Vec<Option<i32>>, b: Vec<f64>) -> Result<RecordBatch> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Float64, false),
+        ]));
+        Ok(RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(a)),
+                Arc::new(Float64Array::from(b)),
+            ],
+        )?)
+    }
+
+    fn assert_same_results(
+        expr: Arc<dyn PhysicalExpr>,
+        batch: &RecordBatch,
+    ) -> Result<()> {
+        let schema = batch.schema();
+        let jit = JitExpr::try_new(expr.clone(), &schema)
+            .expect("supported expression")
+            .with_threshold(0);
+        let expected = expr.evaluate(batch)?.into_array(batch.num_rows());
+        let actual = jit.evaluate(batch)?.into_array(batch.num_rows());
+        assert!(jit.is_compiled());
+        assert_eq!(expected.as_ref(), actual.as_ref());
+        Ok(())
+    }
+
+    #[test]
+    fn compiled_predicate() -> Result<()> {
+        let batch = batch(
+            vec![Some(1), Some(-2), Some(3), Some(4)],
+            vec![1.5, 0.0, 3.0, 2.5],
+        )?;
+        let schema = batch.schema();
+        // NOT (CAST(a AS Float64) * 2.0 > b) OR a = -2
+        let a = col("a", &schema)?;
+        let doubled = binary(
+            cast(a.clone(), &schema, DataType::Float64)?,
+            Operator::Multiply,
+            lit(ScalarValue::Float64(Some(2.0))),
+            &schema,
+        )?;
+        let predicate = binary(
+            not(
+                binary(doubled, Operator::Gt, col("b", &schema)?, &schema)?,
+                &schema,
+            )?,
+            Operator::Or,
+            binary(a, Operator::Eq, lit(ScalarValue::Int32(Some(-2))), &schema)?,
+            &schema,
+        )?;
+        assert_same_results(predicate, &batch)
+    }
+
+    #[test]
+    fn compiled_projection() -> Result<()> {
+        let batch = batch(vec![Some(1), Some(-2), Some(3)], vec![1.5, 0.25, -3.0])?;
+        let schema = batch.schema();
+        // (b - 1.0) / b + b
+        let b = col("b", &schema)?;
+        let expr = binary(
+            binary(
+                binary(
+                    b.clone(),
+                    Operator::Minus,
+                    lit(ScalarValue::Float64(Some(1.0))),
+                    &schema,
+                )?,
+                Operator::Divide,
+                b.clone(),
+                &schema,
+            )?,
+            Operator::Plus,
+            b,
+            &schema,
+        )?;
+        assert_same_results(expr, &batch)?;
+
+        // a * a - CAST(a AS Int64) on a sliced batch
+        let a = col("a", &schema)?;
+        let expr = binary(
+            cast(
+                binary(a.clone(), Operator::Multiply, a.clone(), &schema)?,
+                &schema,
+                DataType::Int64,
+            )?,
+            Operator::Minus,
+            cast(a, &schema, DataType::Int64)?,
+            &schema,
+        )?;
+        assert_same_results(expr, &batch.slice(1, 2))
+    }
+
+    #[test]
+    fn interpreted_with_nulls() -> Result<()> {
+        let batch = batch(vec![Some(1), None], vec![1.0, 2.0])?;
+        let schema = batch.schema();
+        let a = col("a", &schema)?;
+        let expr = binary(a.clone(), Operator::Plus, a, &schema)?;
+        let jit = JitExpr::try_new(expr, &schema).unwrap().with_threshold(0);
+        let result = jit.evaluate(&batch)?.into_array(2);
+        assert!(jit.is_compiled());
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![Some(2), None]));
+        assert_eq!(result.as_ref(), expected.as_ref());
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_expressions() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("s", DataType::Utf8, true),
+        ]);
+        let a = col("a", &schema)?;
+        // a bare column is not worth compiling
+        assert!(JitExpr::try_new(a.clone(), &schema).is_none());
+        // integer division
+        let divide = binary(a.clone(), Operator::Divide, a, &schema)?;
+        assert!(JitExpr::try_new(divide, &schema).is_none());
+        // strings
+        let s = col("s", &schema)?;
+        let eq = binary(s.clone(), Operator::Eq, s, &schema)?;
+        assert!(JitExpr::try_new(eq, &schema).is_none());
+        Ok(())
+    }
+}
@@ -127,7 +127,7 @@ pub fn build_join_schema(
             // left then right
             left_fields.chain(right_fields).unzip()
         }
-        JoinType::Semi | JoinType::Anti => left
+        JoinType::Semi | JoinType::Anti | JoinType::NullAwareAnti => left
             .fields()
             .iter()
             .cloned()
@@ -0,0 +1,286 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the materialization plan, which executes its input once and replays the
+//! buffered result to every consumer of a common table expression
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use super::common;
+use super::memory::MemoryStream;
+use super::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use super::{
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
+};
+use crate::error::{DataFusionError, Result};
+
+type MaterializedPartition = Mutex<Option<Arc<Vec<RecordBatch>>>>;
+
+/// Execution plan that buffers the output of its input in memory the first time
+/// each partition is executed, and returns the buffered batches to all later
+/// executions of that partition.
+///
+/// The same `MaterializeExec` instance is referenced by every consumer of a
+/// materialized common table expression, see [`share_materialized_plans`].
+#[derive(Debug)]
+pub struct MaterializeExec {
+    /// Identifier of the materialized common table expression
+    id: usize,
+    /// Name of the materialized common table expression
+    name: String,
+    /// Input execution plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Buffered output of each input partition
+    partitions: Vec<MaterializedPartition>,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl MaterializeExec {
+    /// Create a new MaterializeExec
+    pub fn new(
+        id: usize,
+        name: impl Into<String>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Self {
+        let partitions = (0..input.output_partitioning().partition_count())
+            .map(|_| Mutex::new(None))
+            .collect();
+        Self {
+            id,
+            name: name.into(),
+            input,
+            partitions,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    /// Identifier of the materialized common table expression
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Name of the materialized common table expression
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for MaterializeExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(MaterializeExec::new(
+                self.id,
+                self.name.clone(),
+                children[0].clone(),
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "MaterializeExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        let materialized = self.partitions.get(partition).ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "MaterializeExec invalid partition {}",
+                partition
+            ))
+        })?;
+
+        // the lock is held while the input is executed, so that concurrent
+        // consumers wait for the first one instead of executing the input again
+        let mut materialized = materialized.lock().await;
+        let batches = match materialized.as_ref() {
+            Some(batches) => batches.clone(),
+            None => {
+                let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+                let stream = self.input.execute(partition).await?;
+                let batches = Arc::new(common::collect(stream).await?);
+                let num_rows = batches.iter().map(|b| b.num_rows()).sum();
+                baseline_metrics.record_output(num_rows);
+                baseline_metrics.done();
+                *materialized = Some(batches.clone());
+                batches
+            }
+        };
+
+        Ok(Box::pin(MemoryStream::try_new(
+            batches.as_ref().clone(),
+            self.schema(),
+            None,
+        )?))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(f, "MaterializeExec: cte={}", self.name)
+            }
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+}
+
+/// Replaces all [`MaterializeExec`]s of `plan` that materialize the same common
+/// table expression by a single shared instance, so that the expression is only
+/// executed once.
+///
+/// Instances are only shared if their inputs produce the same schema and number
+/// of partitions, which is the case unless the physical optimizer rewrote the
+/// references differently.
+pub fn share_materialized_plans(
+    plan: Arc<dyn ExecutionPlan>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    share_materialized_plans_impl(plan, &mut HashMap::new())
+}
+
+fn share_materialized_plans_impl(
+    plan: Arc<dyn ExecutionPlan>,
+    materialized: &mut HashMap<usize, Arc<dyn ExecutionPlan>>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let id = plan
+        .as_any()
+        .downcast_ref::<MaterializeExec>()
+        .map(|m| m.id());
+    if let Some(shared) = id.and_then(|id| materialized.get(&id)) {
+        if shared.schema() == plan.schema()
+            && shared.output_partitioning().partition_count()
+                == plan.output_partitioning().partition_count()
+        {
+            return Ok(shared.clone());
+        }
+    }
+
+    let children = plan.children();
+    let new_children = children
+        .iter()
+        .map(|child| share_materialized_plans_impl(child.clone(), materialized))
+        .collect::<Result<Vec<_>>>()?;
+    let changed = children
+        .iter()
+        .zip(new_children.iter())
+        .any(|(child, new_child)| !Arc::ptr_eq(child, new_child));
+    let plan = if changed {
+        plan.with_new_children(new_children)?
+    } else {
+        plan
+    };
+
+    if let Some(id) = id {
+        materialized.entry(id).or_insert_with(|| plan.clone());
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::union::UnionExec;
+    use crate::test;
+
+    #[tokio::test]
+    async fn materialize_once() -> Result<()> {
+        let batch = test::make_partition(5);
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()], vec![batch.clone()]],
+            batch.schema(),
+            None,
+        )?);
+        let materialize = MaterializeExec::new(0, "t", input);
+        assert_eq!(materialize.output_partitioning().partition_count(), 2);
+
+        let first = common::collect(materialize.execute(1).await?).await?;
+        assert!(materialize.partitions[0].lock().await.is_none());
+        assert!(materialize.partitions[1].lock().await.is_some());
+
+        let second = common::collect(materialize.execute(1).await?).await?;
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert_eq!(first[0].column(0), second[0].column(0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn share_materialized() -> Result<()> {
+        let batch = test::make_partition(5);
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()]],
+            batch.schema(),
+            None,
+        )?);
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(UnionExec::new(vec![
+            Arc::new(MaterializeExec::new(0, "t", input.clone())),
+            Arc::new(MaterializeExec::new(0, "t", input.clone())),
+            Arc::new(MaterializeExec::new(1, "u", input)),
+        ]));
+
+        let plan = share_materialized_plans(plan)?;
+        let children = plan.children();
+        assert!(Arc::ptr_eq(&children[0], &children[1]));
+        assert!(!Arc::ptr_eq(&children[0], &children[2]));
+
+        assert_eq!(collect(plan).await?.len(), 3);
+        Ok(())
+    }
+}
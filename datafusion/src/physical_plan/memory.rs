@@ -23,7 +23,10 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use super::{
-    common, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+    common,
+    metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
+    stream::ObservedStream,
+    DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
     SendableRecordBatchStream, Statistics,
 };
 use crate::error::{DataFusionError, Result};
@@ -44,6 +47,8 @@ pub struct MemoryExec {
     projected_schema: SchemaRef,
     /// Optional projection
     projection: Option<Vec<usize>>,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl fmt::Debug for MemoryExec {
@@ -87,11 +92,19 @@ impl ExecutionPlan for MemoryExec {
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        Ok(Box::pin(MemoryStream::try_new(
+        let stream = Box::pin(MemoryStream::try_new(
             self.partitions[partition].clone(),
             self.projected_schema.clone(),
             self.projection.clone(),
-        )?))
+        )?);
+        Ok(Box::pin(ObservedStream::new_with_compute(
+            stream,
+            BaselineMetrics::new(&self.metrics, partition),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
     }
 
     fn fmt_as(
@@ -154,6 +167,7 @@ impl MemoryExec {
             schema,
             projected_schema,
             projection,
+            metrics: ExecutionPlanMetricsSet::new(),
         })
     }
 }
@@ -224,6 +238,7 @@ impl RecordBatchStream for MemoryStream {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::physical_plan::metrics::MetricValue;
     use crate::physical_plan::ColumnStatistics;
     use arrow::array::Int32Array;
     use arrow::datatypes::{DataType, Field, Schema};
@@ -332,4 +347,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_baseline_metrics() -> Result<()> {
+        let (schema, batch) = mock_data()?;
+
+        let executor = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()], vec![batch]],
+            schema,
+            None,
+        )?);
+        crate::physical_plan::collect(executor.clone()).await?;
+
+        let metrics = executor.metrics().unwrap();
+        assert_eq!(metrics.output_rows().unwrap(), 6);
+        for metric in metrics.iter() {
+            if let MetricValue::StartTimestamp(timestamp)
+            | MetricValue::EndTimestamp(timestamp) = metric.value()
+            {
+                assert!(timestamp.value().is_some());
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -360,6 +360,16 @@ impl Label {
         let value = value.into();
         Self { name, value }
     }
+
+    /// Return the name of this label
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    /// Return the value of this label
+    pub fn value(&self) -> &str {
+        self.value.as_ref()
+    }
 }
 
 impl Display for Label {
@@ -17,22 +17,27 @@
 
 //! Traits for physical query plan, supporting parallel execution for partitioned relations.
 
+use self::cancellation::{CancellableStream, CancellationToken};
 pub use self::metrics::Metric;
 use self::metrics::MetricsSet;
 use self::{
     coalesce_partitions::CoalescePartitionsExec, display::DisplayableExecutionPlan,
 };
-use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::physical_plan::expressions::{Column, PhysicalSortExpr};
 use crate::{
     error::{DataFusionError, Result},
     scalar::ScalarValue,
 };
+use arrow::compute::filter_record_batch;
 use arrow::compute::kernels::partition::lexicographical_partition_ranges;
 use arrow::compute::kernels::sort::{SortColumn, SortOptions};
 use arrow::datatypes::{DataType, Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
-use arrow::{array::ArrayRef, datatypes::Field};
+use arrow::{
+    array::{ArrayRef, BooleanArray},
+    datatypes::Field,
+};
 use async_trait::async_trait;
 pub use display::DisplayFormatType;
 use futures::stream::Stream;
@@ -315,21 +320,29 @@ pub async fn collect(plan: Arc<dyn ExecutionPlan>) -> Result<Vec<RecordBatch>> {
     common::collect(stream).await
 }
 
-/// Execute the [ExecutionPlan] and return a single stream of results
+/// Execute the [ExecutionPlan] and return a single stream of results. The execution
+/// is cancelled when the stream is dropped, or along with the token of the current task.
 pub async fn execute_stream(
     plan: Arc<dyn ExecutionPlan>,
 ) -> Result<SendableRecordBatchStream> {
-    match plan.output_partitioning().partition_count() {
-        0 => Ok(Box::pin(EmptyRecordBatchStream::new(plan.schema()))),
-        1 => plan.execute(0).await,
-        _ => {
-            // merge into a single partition
-            let plan = CoalescePartitionsExec::new(plan.clone());
-            // CoalescePartitionsExec must produce a single partition
-            assert_eq!(1, plan.output_partitioning().partition_count());
-            plan.execute(0).await
-        }
-    }
+    let token = CancellationToken::current().child();
+    let stream = token
+        .scope(async {
+            match plan.output_partitioning().partition_count() {
+                0 => Ok(Box::pin(EmptyRecordBatchStream::new(plan.schema()))
+                    as SendableRecordBatchStream),
+                1 => plan.execute(0).await,
+                _ => {
+                    // merge into a single partition
+                    let plan = CoalescePartitionsExec::new(plan.clone());
+                    // CoalescePartitionsExec must produce a single partition
+                    assert_eq!(1, plan.output_partitioning().partition_count());
+                    plan.execute(0).await
+                }
+            }
+        })
+        .await?;
+    Ok(Box::pin(CancellableStream::new(stream, token)))
 }
 
 /// Execute the [ExecutionPlan] and collect the results in memory
@@ -344,14 +357,20 @@ pub async fn collect_partitioned(
     Ok(batches)
 }
 
-/// Execute the [ExecutionPlan] and return a vec with one stream per output partition
+/// Execute the [ExecutionPlan] and return a vec with one stream per output partition.
+/// The execution of a partition is cancelled when its stream is dropped, or along with
+/// the token of the current task.
 pub async fn execute_stream_partitioned(
     plan: Arc<dyn ExecutionPlan>,
 ) -> Result<Vec<SendableRecordBatchStream>> {
     let num_partitions = plan.output_partitioning().partition_count();
     let mut streams = Vec::with_capacity(num_partitions);
     for i in 0..num_partitions {
-        streams.push(plan.execute(i).await?);
+        let token = CancellationToken::current().child();
+        let stream = token.scope(plan.execute(i)).await?;
+        streams
+            .push(Box::pin(CancellableStream::new(stream, token))
+                as SendableRecordBatchStream);
     }
     Ok(streams)
 }
@@ -364,6 +383,15 @@ pub enum Partitioning {
     /// Allocate rows based on a hash of one of more expressions and the specified number of
     /// partitions
     Hash(Vec<Arc<dyn PhysicalExpr>>, usize),
+    /// Allocate rows to ranges of one or more sort expressions. Every boundary holds one
+    /// value per sort expression and is the inclusive upper bound of a partition, so
+    /// there is one more partition than there are boundaries. The boundaries must be
+    /// sorted by the sort expressions.
+    RangePartitioning(Vec<PhysicalSortExpr>, Vec<Vec<ScalarValue>>),
+    /// Allocate rows to the partition returned by an integer expression and the
+    /// specified number of partitions. Rows for which the expression is null are
+    /// allocated to the first partition.
+    Custom(Arc<dyn PhysicalExpr>, usize),
     /// Unknown partitioning scheme with a known number of partitions
     UnknownPartitioning(usize),
 }
@@ -375,9 +403,39 @@ impl Partitioning {
         match self {
             RoundRobinBatch(n) => *n,
             Hash(_, n) => *n,
+            RangePartitioning(_, boundaries) => boundaries.len() + 1,
+            Custom(_, n) => *n,
             UnknownPartitioning(n) => *n,
         }
     }
+
+    /// Returns the partitioning of the output of an operator that evaluates `exprs`
+    /// on every row of an input with this partitioning, where the output column `i`
+    /// is `exprs[i]`. A hash partitioning is kept if all partitioning columns are
+    /// output columns.
+    pub(crate) fn project(self, exprs: &[(Arc<dyn PhysicalExpr>, String)]) -> Self {
+        match self {
+            Partitioning::Hash(hash_exprs, n) => {
+                let projected = hash_exprs
+                    .iter()
+                    .map(|hash_expr| {
+                        let column = hash_expr.as_any().downcast_ref::<Column>()?;
+                        exprs.iter().enumerate().find_map(|(i, (expr, name))| {
+                            let input = expr.as_any().downcast_ref::<Column>()?;
+                            (input.index() == column.index()).then(|| {
+                                Arc::new(Column::new(name, i)) as Arc<dyn PhysicalExpr>
+                            })
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match projected {
+                    Some(projected) => Partitioning::Hash(projected, n),
+                    None => Partitioning::UnknownPartitioning(n),
+                }
+            }
+            other => other,
+        }
+    }
 }
 
 /// Distribution schemes
@@ -430,6 +488,35 @@ pub trait PhysicalExpr: Send + Sync + Display + Debug {
     fn nullable(&self, input_schema: &Schema) -> Result<bool>;
     /// Evaluate an expression against a RecordBatch
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue>;
+    /// Evaluate an expression against the rows of a RecordBatch where
+    /// `selection`, which must not contain nulls, is true. The result has a
+    /// value for each row of the batch, and the values of the rows that are not
+    /// selected are unspecified.
+    ///
+    /// This lets expressions skip the work for rows whose result is already
+    /// known, such as the rows where the left side of an `AND` is false. The
+    /// default implementation evaluates the expression on the selected rows
+    /// only, and spreads the result back to the rows of the batch.
+    fn evaluate_selection(
+        &self,
+        batch: &RecordBatch,
+        selection: &BooleanArray,
+    ) -> Result<ColumnarValue> {
+        let selected_rows = selection
+            .values()
+            .count_set_bits_offset(selection.offset(), selection.len());
+        if selected_rows == batch.num_rows() {
+            return self.evaluate(batch);
+        }
+        let selected = filter_record_batch(batch, selection)?;
+        match self.evaluate(&selected)? {
+            ColumnarValue::Array(array) => Ok(ColumnarValue::Array(common::scatter(
+                selection,
+                array.as_ref(),
+            )?)),
+            scalar => Ok(scalar),
+        }
+    }
 }
 
 /// An aggregate expression that:
@@ -463,6 +550,14 @@ pub trait AggregateExpr: Send + Sync + Debug {
     fn name(&self) -> &str {
         "AggregateExpr: default name"
     }
+
+    /// Converts every input row into the state of a group containing only
+    /// that row, with the same description as `state_fields`. This allows a
+    /// partial aggregation to pass rows through without building groups.
+    /// Returns `None` if the aggregate does not support it.
+    fn row_states(&self, _values: &[ArrayRef]) -> Result<Option<Vec<ArrayRef>>> {
+        Ok(None)
+    }
 }
 
 /// A window expression that:
@@ -606,6 +701,7 @@ pub trait Accumulator: Send + Sync + Debug {
 pub mod aggregates;
 pub mod analyze;
 pub mod array_expressions;
+pub mod cancellation;
 pub mod coalesce_batches;
 pub mod coalesce_partitions;
 mod coercion_rule;
@@ -614,20 +710,26 @@ pub mod cross_join;
 #[cfg(feature = "crypto_expressions")]
 pub mod crypto_expressions;
 pub mod datetime_expressions;
+pub mod dictionary;
 pub mod display;
 pub mod distinct_expressions;
 pub mod empty;
+pub mod estimation;
 pub mod explain;
 pub mod expressions;
 pub mod file_format;
 pub mod filter;
 pub mod functions;
+pub mod generate_series;
 pub mod hash_aggregate;
 pub mod hash_join;
 pub mod hash_utils;
 pub(crate) mod hyperloglog;
+#[cfg(feature = "jit")]
+pub mod jit;
 pub mod join_utils;
 pub mod limit;
+pub mod materialize;
 pub mod math_expressions;
 pub mod memory;
 pub mod metrics;
@@ -636,13 +738,18 @@ pub mod projection;
 #[cfg(feature = "regex_expressions")]
 pub mod regex_expressions;
 pub mod repartition;
+pub mod row_format;
+pub mod sample;
 pub mod sort;
 pub mod sort_preserving_merge;
 pub mod stream;
+pub mod streaming;
+pub mod streaming_aggregate;
 pub mod string_expressions;
 pub mod type_coercion;
 pub mod udaf;
 pub mod udf;
+pub mod udtf;
 #[cfg(feature = "unicode_expressions")]
 pub mod unicode_expressions;
 pub mod union;
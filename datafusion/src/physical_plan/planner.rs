@@ -27,24 +27,28 @@ use crate::logical_plan::plan::{
     Aggregate, EmptyRelation, Filter, Join, Projection, Sort, TableScan, Window,
 };
 use crate::logical_plan::{
-    unalias, unnormalize_cols, CrossJoin, DFSchema, Expr, LogicalPlan, Operator,
-    Partitioning as LogicalPartitioning, PlanType, Repartition, ToStringifiedPlan, Union,
-    UserDefinedLogicalNode,
+    case_result_type, unalias, unnormalize_cols, CrossJoin, DFSchema, Expr, LogicalPlan,
+    Operator, Partitioning as LogicalPartitioning, PlanType, Repartition,
+    ToStringifiedPlan, Union, UserDefinedLogicalNode,
 };
-use crate::logical_plan::{Limit, Values};
+use crate::logical_plan::{JoinType, Limit, MaterializedCte, Sample, Values};
 use crate::physical_optimizer::optimizer::PhysicalOptimizerRule;
+use crate::physical_optimizer::repartition::partition_count_for_statistics;
 use crate::physical_plan::cross_join::CrossJoinExec;
 use crate::physical_plan::explain::ExplainExec;
 use crate::physical_plan::expressions;
 use crate::physical_plan::expressions::{
-    CaseExpr, Column, GetIndexedFieldExpr, Literal, PhysicalSortExpr,
+    binary_with_overflow_checks, check_strict_coercion, comparison_coercion, CaseExpr,
+    Column, FilteredAggregate, GetIndexedFieldExpr, Literal, PhysicalSortExpr,
 };
 use crate::physical_plan::filter::FilterExec;
 use crate::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
 use crate::physical_plan::hash_join::HashJoinExec;
 use crate::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
+use crate::physical_plan::materialize::{share_materialized_plans, MaterializeExec};
 use crate::physical_plan::projection::ProjectionExec;
 use crate::physical_plan::repartition::RepartitionExec;
+use crate::physical_plan::sample::create_sample_plan;
 use crate::physical_plan::sort::SortExec;
 use crate::physical_plan::udf;
 use crate::physical_plan::windows::WindowAggExec;
@@ -164,8 +168,43 @@ fn create_physical_name(e: &Expr, is_first_expr: bool) -> Result<String> {
             fun,
             distinct,
             args,
-            ..
-        } => create_function_physical_name(&fun.to_string(), *distinct, args),
+            filter,
+            order_by,
+        } => {
+            let mut name =
+                create_function_physical_name(&fun.to_string(), *distinct, args)?;
+            if !order_by.is_empty() {
+                let order_by = order_by
+                    .iter()
+                    .map(|e| match e {
+                        Expr::Sort {
+                            expr,
+                            asc,
+                            nulls_first,
+                        } => Ok(format!(
+                            "{} {} {}",
+                            create_physical_name(expr, false)?,
+                            if *asc { "ASC" } else { "DESC" },
+                            if *nulls_first {
+                                "NULLS FIRST"
+                            } else {
+                                "NULLS LAST"
+                            }
+                        )),
+                        _ => create_physical_name(e, false),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                name = format!("{} ORDER BY [{}]", name, order_by.join(", "));
+            }
+            match filter {
+                Some(filter) => Ok(format!(
+                    "{} FILTER (WHERE {})",
+                    name,
+                    create_physical_name(filter, false)?
+                )),
+                None => Ok(name),
+            }
+        }
         Expr::AggregateUDF { fun, args } => {
             let mut names = Vec::with_capacity(args.len());
             for e in args {
@@ -279,7 +318,8 @@ impl PhysicalPlanner for DefaultPhysicalPlanner {
             Some(plan) => Ok(plan),
             None => {
                 let plan = self.create_initial_plan(logical_plan, ctx_state).await?;
-                self.optimize_internal(plan, ctx_state, |_, _| {})
+                let plan = self.optimize_internal(plan, ctx_state, |_, _| {})?;
+                share_materialized_plans(plan)
             }
         }
     }
@@ -333,6 +373,7 @@ impl DefaultPhysicalPlanner {
                 LogicalPlan::TableScan (TableScan {
                     source,
                     projection,
+                    nested_projection,
                     filters,
                     limit,
                     ..
@@ -342,7 +383,13 @@ impl DefaultPhysicalPlanner {
                     // referred to in the query
                     let filters = unnormalize_cols(filters.iter().cloned());
                     let unaliased: Vec<Expr> = filters.into_iter().map(unalias).collect();
-                    source.scan(projection, batch_size, &unaliased, *limit).await
+                    if nested_projection.is_empty() {
+                        source.scan(projection, batch_size, &unaliased, *limit).await
+                    } else {
+                        source
+                            .scan_nested(projection, nested_projection, batch_size, &unaliased, *limit)
+                            .await
+                    }
                 }
                 LogicalPlan::Values(Values {
                     values,
@@ -431,6 +478,9 @@ impl DefaultPhysicalPlanner {
 
                     let logical_input_schema = input.schema();
 
+                    // the window functions can be evaluated one group of partitions at a
+                    // time if the input is sorted on the partition by keys
+                    let sorted_input = !sort_keys.is_empty();
                     let input_exec = if sort_keys.is_empty() {
                         input_exec
                     } else {
@@ -475,11 +525,14 @@ impl DefaultPhysicalPlanner {
                         })
                         .collect::<Result<Vec<_>>>()?;
 
-                    Ok(Arc::new(WindowAggExec::try_new(
-                        window_expr,
-                        input_exec,
-                        physical_input_schema,
-                    )?) )
+                    Ok(Arc::new(
+                        WindowAggExec::try_new(
+                            window_expr,
+                            input_exec,
+                            physical_input_schema,
+                        )?
+                        .with_sorted_input(sorted_input),
+                    ))
                 }
                 LogicalPlan::Aggregate(Aggregate {
                     input,
@@ -518,29 +571,34 @@ impl DefaultPhysicalPlanner {
                         })
                         .collect::<Result<Vec<_>>>()?;
 
-                    let initial_aggr = Arc::new(HashAggregateExec::try_new(
-                        AggregateMode::Partial,
-                        groups.clone(),
-                        aggregates.clone(),
-                        input_exec,
-                        physical_input_schema.clone(),
-                    )?);
+                    // the partial aggregation outputs at most as many rows as its input
+                    let shuffle_partitions = partition_count_for_statistics(
+                        &input_exec.statistics(),
+                        &ctx_state.config,
+                    );
+
+                    let initial_aggr = Arc::new(
+                        HashAggregateExec::try_new(
+                            AggregateMode::Partial,
+                            groups.clone(),
+                            aggregates.clone(),
+                            input_exec,
+                            physical_input_schema.clone(),
+                        )?
+                        .with_skip_partial_aggregation(
+                            ctx_state.config.skip_partial_aggregation,
+                        )
+                        .with_max_groups(ctx_state.config.max_aggregation_groups),
+                    );
 
                     // update group column indices based on partial aggregate plan evaluation
                     let final_group: Vec<Arc<dyn PhysicalExpr>> = (0..groups.len())
                         .map(|i| col(&groups[i].1, &initial_aggr.schema()))
                         .collect::<Result<_>>()?;
 
-                    // TODO: dictionary type not yet supported in Hash Repartition
-                    let contains_dict = groups
-                        .iter()
-                        .flat_map(|x| x.0.data_type(physical_input_schema.as_ref()))
-                        .any(|x| matches!(x, DataType::Dictionary(_, _)));
-
                     let can_repartition = !groups.is_empty()
                         && ctx_state.config.target_partitions > 1
-                        && ctx_state.config.repartition_aggregations
-                        && !contains_dict;
+                        && ctx_state.config.repartition_aggregations;
 
                     let (initial_aggr, next_partition_mode): (
                         Arc<dyn ExecutionPlan>,
@@ -549,10 +607,7 @@ impl DefaultPhysicalPlanner {
                         // Divide partial hash aggregates into multiple partitions by hash key
                         let hash_repartition = Arc::new(RepartitionExec::try_new(
                             initial_aggr,
-                            Partitioning::Hash(
-                                final_group.clone(),
-                                ctx_state.config.target_partitions,
-                            ),
+                            Partitioning::Hash(final_group.clone(), shuffle_partitions),
                         )?);
                         // Combine hash aggregates within the partition
                         (hash_repartition, AggregateMode::FinalPartitioned)
@@ -572,7 +627,8 @@ impl DefaultPhysicalPlanner {
                         aggregates,
                         initial_aggr,
                         physical_input_schema.clone(),
-                    )?) )
+                    )?
+                    .with_max_groups(ctx_state.config.max_aggregation_groups)))
                 }
                 LogicalPlan::Projection(Projection { input, expr, .. }) => {
                     let input_exec = self.create_initial_plan(input, ctx_state).await?;
@@ -672,6 +728,41 @@ impl DefaultPhysicalPlanner {
                                 .collect::<Result<Vec<_>>>()?;
                             Partitioning::Hash(runtime_expr, *n)
                         }
+                        LogicalPartitioning::RangePartitioning(expr, boundaries) => {
+                            let sort_expr = expr
+                                .iter()
+                                .map(|e| match e {
+                                    Expr::Sort {
+                                        expr,
+                                        asc,
+                                        nulls_first,
+                                    } => self.create_physical_sort_expr(
+                                        expr,
+                                        input_dfschema,
+                                        &input_schema,
+                                        SortOptions {
+                                            descending: !*asc,
+                                            nulls_first: *nulls_first,
+                                        },
+                                        ctx_state,
+                                    ),
+                                    _ => Err(DataFusionError::Plan(
+                                        "Range partitioning only accepts sort expressions"
+                                            .to_string(),
+                                    )),
+                                })
+                                .collect::<Result<Vec<_>>>()?;
+                            Partitioning::RangePartitioning(sort_expr, boundaries.clone())
+                        }
+                        LogicalPartitioning::Custom(expr, n) => {
+                            let runtime_expr = self.create_physical_expr(
+                                expr,
+                                input_dfschema,
+                                &input_schema,
+                                ctx_state,
+                            )?;
+                            Partitioning::Custom(runtime_expr, *n)
+                        }
                     };
                     Ok(Arc::new(RepartitionExec::try_new(
                         physical_input,
@@ -728,8 +819,10 @@ impl DefaultPhysicalPlanner {
                         })
                         .collect::<Result<join_utils::JoinOn>>()?;
 
+                    // a null aware anti join reads both sides in a single partition
                     if ctx_state.config.target_partitions > 1
                         && ctx_state.config.repartition_joins
+                        && *join_type != JoinType::NullAwareAnti
                     {
                         let (left_expr, right_expr) = join_on
                             .iter()
@@ -741,21 +834,25 @@ impl DefaultPhysicalPlanner {
                             })
                             .unzip();
 
+                        // both sides must be hash partitioned the same way
+                        let shuffle_partitions = partition_count_for_statistics(
+                            &physical_left.statistics(),
+                            &ctx_state.config,
+                        )
+                        .max(partition_count_for_statistics(
+                            &physical_right.statistics(),
+                            &ctx_state.config,
+                        ));
+
                         // Use hash partition by default to parallelize hash joins
                         Ok(Arc::new(HashJoinExec::try_new(
                             Arc::new(RepartitionExec::try_new(
                                 physical_left,
-                                Partitioning::Hash(
-                                    left_expr,
-                                    ctx_state.config.target_partitions,
-                                ),
+                                Partitioning::Hash(left_expr, shuffle_partitions),
                             )?),
                             Arc::new(RepartitionExec::try_new(
                                 physical_right,
-                                Partitioning::Hash(
-                                    right_expr,
-                                    ctx_state.config.target_partitions,
-                                ),
+                                Partitioning::Hash(right_expr, shuffle_partitions),
                             )?),
                             join_on,
                             join_type,
@@ -809,6 +906,13 @@ impl DefaultPhysicalPlanner {
                         "Unsupported logical plan: CreateExternalTable".to_string(),
                     ))
                 }
+                LogicalPlan::InsertInto(_) => {
+                    // the rows are written to the files of the table, which is done
+                    // at a higher level, like for "CREATE EXTERNAL TABLE"
+                    Err(DataFusionError::Internal(
+                        "Unsupported logical plan: InsertInto".to_string(),
+                    ))
+                }
                 | LogicalPlan::CreateMemoryTable(_) | LogicalPlan::DropTable (_) => {
                     // Create a dummy exec.
                     Ok(Arc::new(EmptyExec::new(
@@ -822,9 +926,25 @@ impl DefaultPhysicalPlanner {
                 LogicalPlan::Analyze(a) => {
                     let input = self.create_initial_plan(&a.input, ctx_state).await?;
                     let schema = SchemaRef::new((*a.schema).clone().into());
-                    Ok(Arc::new(AnalyzeExec::new(a.verbose, input, schema)))
+                    Ok(Arc::new(
+                        AnalyzeExec::new(a.verbose, input, schema).with_format(a.format),
+                    ))
                 }
                 LogicalPlan::Extension(e) => {
+                    if let Some(cte) = e.node.as_any().downcast_ref::<MaterializedCte>() {
+                        let input = self.create_initial_plan(&cte.input, ctx_state).await?;
+                        return Ok(Arc::new(MaterializeExec::new(
+                            cte.id,
+                            cte.name.clone(),
+                            input,
+                        )) as Arc<dyn ExecutionPlan>);
+                    }
+                    if let Some(sample) = e.node.as_any().downcast_ref::<Sample>() {
+                        let input = self.create_initial_plan(&sample.input, ctx_state).await?;
+                        let seed = sample.seed.unwrap_or_else(rand::random);
+                        return create_sample_plan(input, sample.method, sample.fraction, seed);
+                    }
+
                     let physical_inputs = futures::stream::iter(e.node.inputs())
                         .then(|lp| self.create_initial_plan(lp, ctx_state))
                         .try_collect::<Vec<_>>()
@@ -926,7 +1046,20 @@ impl DefaultPhysicalPlanner {
                     input_schema,
                     ctx_state,
                 )?;
-                binary(lhs, *op, rhs, input_schema)
+                if ctx_state.config.strict_type_coercion {
+                    check_strict_coercion(
+                        &lhs.data_type(input_schema)?,
+                        op,
+                        &rhs.data_type(input_schema)?,
+                    )?;
+                }
+                binary_with_overflow_checks(
+                    lhs,
+                    *op,
+                    rhs,
+                    input_schema,
+                    ctx_state.config.ansi_mode,
+                )
             }
             Expr::Case {
                 expr,
@@ -934,55 +1067,71 @@ impl DefaultPhysicalPlanner {
                 else_expr,
                 ..
             } => {
-                let expr: Option<Arc<dyn PhysicalExpr>> = if let Some(e) = expr {
-                    Some(self.create_physical_expr(
-                        e.as_ref(),
-                        input_dfschema,
-                        input_schema,
-                        ctx_state,
-                    )?)
-                } else {
-                    None
+                let strict = ctx_state.config.strict_type_coercion;
+                let create = |e: &Expr| {
+                    self.create_physical_expr(e, input_dfschema, input_schema, ctx_state)
                 };
+
+                let expr = expr.as_ref().map(|e| create(e)).transpose()?;
                 let when_expr = when_then_expr
                     .iter()
-                    .map(|(w, _)| {
-                        self.create_physical_expr(
-                            w.as_ref(),
-                            input_dfschema,
-                            input_schema,
-                            ctx_state,
-                        )
-                    })
+                    .map(|(w, _)| create(w))
                     .collect::<Result<Vec<_>>>()?;
+                // the base expression is compared to the WHEN values in their common type
+                let (expr, when_expr) = match expr {
+                    Some(expr) => {
+                        let mut compare_type = expr.data_type(input_schema)?;
+                        for w in &when_expr {
+                            let when_type = w.data_type(input_schema)?;
+                            compare_type =
+                                comparison_coercion(&compare_type, &when_type, strict)
+                                    .ok_or_else(|| {
+                                        DataFusionError::Plan(format!(
+                                            "CASE expression of type {:?} can't be compared to WHEN value of type {:?}",
+                                            compare_type, when_type
+                                        ))
+                                    })?;
+                        }
+                        let when_expr = when_expr
+                            .into_iter()
+                            .map(|w| {
+                                expressions::cast(w, input_schema, compare_type.clone())
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        (
+                            Some(expressions::cast(expr, input_schema, compare_type)?),
+                            when_expr,
+                        )
+                    }
+                    None => (None, when_expr),
+                };
+
+                // all branches are casted to the type of the CASE expression
+                let result_type = case_result_type(
+                    when_then_expr,
+                    else_expr.as_deref(),
+                    input_dfschema,
+                )?;
+                let create_branch = |e: &Expr| {
+                    let branch = create(e)?;
+                    let branch_type = branch.data_type(input_schema)?;
+                    let is_null = matches!(e, Expr::Literal(value) if value.is_null());
+                    if strict && !is_null && branch_type != result_type {
+                        return Err(DataFusionError::Plan(format!(
+                            "CASE branch of type {:?} can't be casted to {:?} implicitly, which strict type coercion does not allow",
+                            branch_type, result_type
+                        )));
+                    }
+                    expressions::cast(branch, input_schema, result_type.clone())
+                };
                 let then_expr = when_then_expr
                     .iter()
-                    .map(|(_, t)| {
-                        self.create_physical_expr(
-                            t.as_ref(),
-                            input_dfschema,
-                            input_schema,
-                            ctx_state,
-                        )
-                    })
+                    .map(|(_, t)| create_branch(t))
                     .collect::<Result<Vec<_>>>()?;
                 let when_then_expr: Vec<(Arc<dyn PhysicalExpr>, Arc<dyn PhysicalExpr>)> =
-                    when_expr
-                        .iter()
-                        .zip(then_expr.iter())
-                        .map(|(w, t)| (w.clone(), t.clone()))
-                        .collect();
-                let else_expr: Option<Arc<dyn PhysicalExpr>> = if let Some(e) = else_expr
-                {
-                    Some(self.create_physical_expr(
-                        e.as_ref(),
-                        input_dfschema,
-                        input_schema,
-                        ctx_state,
-                    )?)
-                } else {
-                    None
-                };
+                    when_expr.into_iter().zip(then_expr.into_iter()).collect();
+                let else_expr =
+                    else_expr.as_ref().map(|e| create_branch(e)).transpose()?;
                 Ok(Arc::new(CaseExpr::try_new(
                     expr,
                     &when_then_expr,
@@ -1089,6 +1238,20 @@ impl DefaultPhysicalPlanner {
                     ctx_state,
                 )?;
 
+                if ctx_state.config.strict_type_coercion {
+                    let value_type = value_expr.data_type(input_schema)?;
+                    check_strict_coercion(
+                        &value_type,
+                        &Operator::GtEq,
+                        &low_expr.data_type(input_schema)?,
+                    )?;
+                    check_strict_coercion(
+                        &value_type,
+                        &Operator::LtEq,
+                        &high_expr.data_type(input_schema)?,
+                    )?;
+                }
+
                 // rewrite the between into the two binary operators
                 let binary_expr = binary(
                     binary(value_expr.clone(), Operator::GtEq, low_expr, input_schema)?,
@@ -1118,45 +1281,64 @@ impl DefaultPhysicalPlanner {
                         input_schema,
                         ctx_state,
                     )?;
-                    let value_expr_data_type = value_expr.data_type(input_schema)?;
-
                     let list_exprs = list
                         .iter()
-                        .map(|expr| match expr {
-                            Expr::Literal(ScalarValue::Utf8(None)) => self
-                                .create_physical_expr(
-                                    expr,
-                                    input_dfschema,
-                                    input_schema,
-                                    ctx_state,
-                                ),
-                            _ => {
-                                let list_expr = self.create_physical_expr(
-                                    expr,
-                                    input_dfschema,
+                        .map(|expr| {
+                            self.create_physical_expr(
+                                expr,
+                                input_dfschema,
+                                input_schema,
+                                ctx_state,
+                            )
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+
+                    // the value and the list are widened to their common type, and list
+                    // values of other types are casted to the type of the value. NULL
+                    // literals are left as they are since the IN list handles them
+                    let is_null = |expr: &Expr| {
+                        matches!(expr, Expr::Literal(ScalarValue::Utf8(None)))
+                    };
+                    let mut compare_type = value_expr.data_type(input_schema)?;
+                    for (list_expr, expr) in list_exprs.iter().zip(list) {
+                        if is_null(expr) {
+                            continue;
+                        }
+                        let list_type = list_expr.data_type(input_schema)?;
+                        match comparison_coercion(&compare_type, &list_type, true) {
+                            Some(common_type) => compare_type = common_type,
+                            None if ctx_state.config.strict_type_coercion => {
+                                return Err(DataFusionError::Plan(format!(
+                                    "IN list value of type {:?} can't be compared to {:?} without an implicit cast, which strict type coercion does not allow",
+                                    list_type, compare_type
+                                )));
+                            }
+                            None => {}
+                        }
+                    }
+                    let value_expr = expressions::cast(
+                        value_expr,
+                        input_schema,
+                        compare_type.clone(),
+                    )?;
+                    let list_exprs = list_exprs
+                        .into_iter()
+                        .zip(list)
+                        .map(|(list_expr, expr)| {
+                            let list_type = list_expr.data_type(input_schema)?;
+                            if is_null(expr) || list_type == compare_type {
+                                Ok(list_expr)
+                            } else if can_cast_types(&list_type, &compare_type) {
+                                expressions::cast(
+                                    list_expr,
                                     input_schema,
-                                    ctx_state,
-                                )?;
-                                let list_expr_data_type =
-                                    list_expr.data_type(input_schema)?;
-
-                                if list_expr_data_type == value_expr_data_type {
-                                    Ok(list_expr)
-                                } else if can_cast_types(
-                                    &list_expr_data_type,
-                                    &value_expr_data_type,
-                                ) {
-                                    expressions::cast(
-                                        list_expr,
-                                        input_schema,
-                                        value_expr.data_type(input_schema)?,
-                                    )
-                                } else {
-                                    Err(DataFusionError::Plan(format!(
-                                        "Unsupported CAST from {:?} to {:?}",
-                                        list_expr_data_type, value_expr_data_type
-                                    )))
-                                }
+                                    compare_type.clone(),
+                                )
+                            } else {
+                                Err(DataFusionError::Plan(format!(
+                                    "Unsupported CAST from {:?} to {:?}",
+                                    list_type, compare_type
+                                )))
                             }
                         })
                         .collect::<Result<Vec<_>>>()?;
@@ -1233,12 +1415,6 @@ impl DefaultPhysicalPlanner {
                         )),
                     })
                     .collect::<Result<Vec<_>>>()?;
-                if window_frame.is_some() {
-                    return Err(DataFusionError::NotImplemented(
-                            "window expression with window frame definition is not yet supported"
-                                .to_owned(),
-                        ));
-                }
                 windows::create_window_expr(
                     fun,
                     name,
@@ -1292,7 +1468,8 @@ impl DefaultPhysicalPlanner {
                 fun,
                 distinct,
                 args,
-                ..
+                filter,
+                order_by,
             } => {
                 let args = args
                     .iter()
@@ -1305,13 +1482,57 @@ impl DefaultPhysicalPlanner {
                         )
                     })
                     .collect::<Result<Vec<_>>>()?;
-                aggregates::create_aggregate_expr(
+                let order_by = order_by
+                    .iter()
+                    .map(|e| match e {
+                        Expr::Sort {
+                            expr,
+                            asc,
+                            nulls_first,
+                        } => self.create_physical_sort_expr(
+                            expr,
+                            logical_input_schema,
+                            physical_input_schema,
+                            SortOptions {
+                                descending: !*asc,
+                                nulls_first: *nulls_first,
+                            },
+                            ctx_state,
+                        ),
+                        _ => Err(DataFusionError::Plan(
+                            "Sort only accepts sort expressions".to_string(),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let aggregate = aggregates::create_ordered_aggregate_expr(
                     fun,
                     *distinct,
                     &args,
+                    &order_by,
                     physical_input_schema,
                     name,
-                )
+                    ctx_state.config.ansi_mode,
+                )?;
+                match filter {
+                    Some(filter) => {
+                        let predicate = self.create_physical_expr(
+                            filter,
+                            logical_input_schema,
+                            physical_input_schema,
+                            ctx_state,
+                        )?;
+                        let data_type = predicate.data_type(physical_input_schema)?;
+                        if data_type != DataType::Boolean {
+                            return Err(DataFusionError::Plan(format!(
+                                "The FILTER clause of {} must be a boolean predicate, not {:?}",
+                                aggregate.name(),
+                                data_type
+                            )));
+                        }
+                        Ok(Arc::new(FilteredAggregate::new(aggregate, predicate)))
+                    }
+                    None => Ok(aggregate),
+                }
             }
             Expr::AggregateUDF { fun, args, .. } => {
                 let args = args
@@ -1392,21 +1613,33 @@ impl DefaultPhysicalPlanner {
             use PlanType::*;
             let mut stringified_plans = e.stringified_plans.clone();
 
-            stringified_plans.push(e.plan.to_stringified(FinalLogicalPlan));
+            let format = e.format;
+            stringified_plans
+                .push(e.plan.to_stringified_with_format(FinalLogicalPlan, format));
 
             let input = self.create_initial_plan(e.plan.as_ref(), ctx_state).await?;
 
-            stringified_plans
-                .push(displayable(input.as_ref()).to_stringified(InitialPhysicalPlan));
+            stringified_plans.push(
+                displayable(input.as_ref())
+                    .show_estimates(e.costs)
+                    .to_stringified_with_format(InitialPhysicalPlan, format),
+            );
 
             let input = self.optimize_internal(input, ctx_state, |plan, optimizer| {
                 let optimizer_name = optimizer.name().to_string();
                 let plan_type = OptimizedPhysicalPlan { optimizer_name };
-                stringified_plans.push(displayable(plan).to_stringified(plan_type));
+                stringified_plans.push(
+                    displayable(plan)
+                        .show_estimates(e.costs)
+                        .to_stringified_with_format(plan_type, format),
+                );
             })?;
 
-            stringified_plans
-                .push(displayable(input.as_ref()).to_stringified(FinalPhysicalPlan));
+            stringified_plans.push(
+                displayable(input.as_ref())
+                    .show_estimates(e.costs)
+                    .to_stringified_with_format(FinalPhysicalPlan, format),
+            );
 
             Ok(Some(Arc::new(ExplainExec::new(
                 SchemaRef::new(e.schema.as_ref().to_owned().into()),
@@ -1706,6 +1939,23 @@ mod tests {
         let expected = "InListExpr { expr: Column { name: \"c1\", index: 0 }, list: [Literal { value: Utf8(\"a\") }, CastExpr { expr: Literal { value: Int64(1) }, cast_type: Utf8, cast_options: CastOptions { safe: false } }], negated: false }";
         assert!(format!("{:?}", execution_plan).contains(expected));
 
+        // strict type coercion does not cast numbers to strings
+        let mut ctx_state = make_ctx_state();
+        ctx_state.config.strict_type_coercion = true;
+        let execution_plan = DefaultPhysicalPlanner::default()
+            .create_physical_plan(&logical_plan, &ctx_state)
+            .await;
+        let expected_error = "IN list value of type Int64 can't be compared to Utf8 without an implicit cast";
+        match execution_plan {
+            Ok(_) => panic!("Expected planning failure"),
+            Err(e) => assert!(
+                e.to_string().contains(expected_error),
+                "Error '{}' did not contain expected error '{}'",
+                e.to_string(),
+                expected_error
+            ),
+        }
+
         // expression: "a in (true, 'a')"
         let list = vec![
             Expr::Literal(ScalarValue::Boolean(Some(true))),
@@ -115,7 +115,7 @@ impl ExecutionPlan for ProjectionExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        self.input.output_partitioning()
+        self.input.output_partitioning().project(&self.expr)
     }
 
     fn with_new_children(
@@ -285,9 +285,11 @@ mod tests {
                 file_groups: files,
                 statistics: Statistics::default(),
                 projection: None,
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -325,6 +327,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn project_hash_partitioning() -> Result<()> {
+        let schema = test_util::aggr_test_schema();
+        let (_, files) = test::create_partitioned_csv("aggregate_test_100.csv", 4)?;
+        let csv = CsvExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_schema: Arc::clone(&schema),
+                file_groups: files,
+                statistics: Statistics::default(),
+                projection: None,
+                nested_projection: vec![],
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+                bucket_columns: Some(vec!["c2".to_owned()]),
+            },
+            true,
+            b',',
+        );
+        let csv: Arc<dyn ExecutionPlan> = Arc::new(csv);
+
+        // the partitioning column is renamed and moved
+        let projection = ProjectionExec::try_new(
+            vec![
+                (col("c1", &schema)?, "c1".to_string()),
+                (col("c2", &schema)?, "x".to_string()),
+            ],
+            csv.clone(),
+        )?;
+        match projection.output_partitioning() {
+            Partitioning::Hash(exprs, 4) => assert_eq!("x@1", exprs[0].to_string()),
+            other => panic!("unexpected partitioning {:?}", other),
+        }
+
+        // the partitioning column is not projected
+        let projection =
+            ProjectionExec::try_new(vec![(col("c1", &schema)?, "c2".to_string())], csv)?;
+        assert!(matches!(
+            projection.output_partitioning(),
+            Partitioning::UnknownPartitioning(4)
+        ));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_stats_projection_columns_only() {
         let source = Statistics {
@@ -18,6 +18,7 @@
 //! The repartition operator maps N input partitions to M output partitions based on a
 //! partitioning scheme.
 
+use std::cmp::Ordering;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -26,14 +27,17 @@ use std::{any::Any, vec};
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::hash_utils::create_hashes;
 use crate::physical_plan::{DisplayFormatType, ExecutionPlan, Partitioning, Statistics};
+use crate::scalar::ScalarValue;
+use arrow::array::{build_compare, Array, ArrayRef, Int64Array};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
-use arrow::{array::Array, error::Result as ArrowResult};
-use arrow::{compute::take, datatypes::SchemaRef};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
-use super::common::{AbortOnDropMany, AbortOnDropSingle};
+use super::common::{take_array, AbortOnDropMany, AbortOnDropSingle};
 use super::metrics::{self, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet};
-use super::{RecordBatchStream, SendableRecordBatchStream};
+use super::{cancellation, RecordBatchStream, SendableRecordBatchStream};
 use async_trait::async_trait;
 
 use futures::stream::Stream;
@@ -47,20 +51,186 @@ use tokio::task::JoinHandle;
 
 type MaybeBatch = Option<ArrowResult<RecordBatch>>;
 
+/// Assigns the rows of record batches to output partitions for the partitioning schemes
+/// that partition individual rows, i.e. hash, range and custom partitioning
+pub struct BatchPartitioner {
+    partitioning: Partitioning,
+    random_state: ahash::RandomState,
+    hashes_buf: Vec<u64>,
+    /// Boundaries of a range partitioning, one array per sort expression
+    range_boundaries: Vec<ArrayRef>,
+}
+
+impl BatchPartitioner {
+    /// Create a new partitioner for `partitioning`, hashing rows with `random_state`
+    pub fn try_new(
+        partitioning: Partitioning,
+        random_state: ahash::RandomState,
+    ) -> Result<Self> {
+        let range_boundaries = match &partitioning {
+            Partitioning::Hash(_, _) | Partitioning::Custom(_, _) => vec![],
+            Partitioning::RangePartitioning(sort_exprs, boundaries) => {
+                if let Some(boundary) =
+                    boundaries.iter().find(|b| b.len() != sort_exprs.len())
+                {
+                    return Err(DataFusionError::Plan(format!(
+                        "Range partitioning boundary {:?} does not have one value for each of the {} sort expressions",
+                        boundary,
+                        sort_exprs.len()
+                    )));
+                }
+                if boundaries.is_empty() {
+                    vec![]
+                } else {
+                    (0..sort_exprs.len())
+                        .map(|i| {
+                            ScalarValue::iter_to_array(
+                                boundaries.iter().map(|b| b[i].clone()),
+                            )
+                        })
+                        .collect::<Result<Vec<_>>>()?
+                }
+            }
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported repartitioning scheme {:?}",
+                    other
+                )))
+            }
+        };
+        Ok(Self {
+            partitioning,
+            random_state,
+            hashes_buf: vec![],
+            range_boundaries,
+        })
+    }
+
+    /// Returns the indices of the rows of `batch` for each output partition
+    pub fn partition_indices(&mut self, batch: &RecordBatch) -> Result<Vec<Vec<u64>>> {
+        let num_output_partitions = self.partitioning.partition_count();
+        let mut indices = vec![vec![]; num_output_partitions];
+        match &self.partitioning {
+            Partitioning::Hash(exprs, _) => {
+                let arrays = exprs
+                    .iter()
+                    .map(|expr| Ok(expr.evaluate(batch)?.into_array(batch.num_rows())))
+                    .collect::<Result<Vec<_>>>()?;
+                self.hashes_buf.clear();
+                self.hashes_buf.resize(arrays[0].len(), 0);
+                // Hash arrays and compute buckets based on number of partitions
+                let hashes =
+                    create_hashes(&arrays, &self.random_state, &mut self.hashes_buf)?;
+                for (index, hash) in hashes.iter().enumerate() {
+                    indices[(*hash % num_output_partitions as u64) as usize]
+                        .push(index as u64)
+                }
+            }
+            Partitioning::RangePartitioning(sort_exprs, _) => {
+                let keys = sort_exprs
+                    .iter()
+                    .map(|e| Ok(e.expr.evaluate(batch)?.into_array(batch.num_rows())))
+                    .collect::<Result<Vec<_>>>()?;
+                let boundaries = self
+                    .range_boundaries
+                    .iter()
+                    .zip(keys.iter())
+                    .map(|(boundary, key)| {
+                        if boundary.data_type() == key.data_type() {
+                            Ok(boundary.clone())
+                        } else {
+                            Ok(cast(boundary, key.data_type())?)
+                        }
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let comparators = keys
+                    .iter()
+                    .zip(boundaries.iter())
+                    .map(|(key, boundary)| {
+                        Ok(build_compare(key.as_ref(), boundary.as_ref())?)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                // the ordering of the sort key of `row` relative to `boundary`
+                let compare = |row: usize, boundary: usize| {
+                    for (i, e) in sort_exprs.iter().enumerate() {
+                        let ordering = match (
+                            keys[i].is_valid(row),
+                            boundaries[i].is_valid(boundary),
+                        ) {
+                            (false, false) => Ordering::Equal,
+                            (false, true) if e.options.nulls_first => Ordering::Less,
+                            (false, true) => Ordering::Greater,
+                            (true, false) if e.options.nulls_first => Ordering::Greater,
+                            (true, false) => Ordering::Less,
+                            (true, true) if e.options.descending => {
+                                comparators[i](row, boundary).reverse()
+                            }
+                            (true, true) => comparators[i](row, boundary),
+                        };
+                        if ordering != Ordering::Equal {
+                            return ordering;
+                        }
+                    }
+                    Ordering::Equal
+                };
+
+                let num_boundaries = num_output_partitions - 1;
+                for row in 0..batch.num_rows() {
+                    // the partition is the number of boundaries that are smaller than the row
+                    let (mut low, mut high) = (0, num_boundaries);
+                    while low < high {
+                        let mid = (low + high) / 2;
+                        if compare(row, mid) == Ordering::Greater {
+                            low = mid + 1;
+                        } else {
+                            high = mid;
+                        }
+                    }
+                    indices[low].push(row as u64);
+                }
+            }
+            Partitioning::Custom(expr, _) => {
+                let values = expr.evaluate(batch)?.into_array(batch.num_rows());
+                let values = cast(&values, &DataType::Int64)?;
+                let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+                for (row, value) in values.iter().enumerate() {
+                    let partition = value.unwrap_or(0);
+                    if partition < 0 || partition as usize >= num_output_partitions {
+                        return Err(DataFusionError::Execution(format!(
+                            "Partitioning expression returned partition {} but there are {} partitions",
+                            partition, num_output_partitions
+                        )));
+                    }
+                    indices[partition as usize].push(row as u64);
+                }
+            }
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported repartitioning scheme {:?}",
+                    other
+                )))
+            }
+        }
+        Ok(indices)
+    }
+}
+
 /// Inner state of [`RepartitionExec`].
 #[derive(Debug)]
 struct RepartitionExecState {
     /// Channels for sending batches from input partitions to output partitions.
-    /// Key is the partition number.
+    /// Key is the partition number, with a channel shared by the input partitions,
+    /// or one channel per input partition when preserving their order.
     channels:
-        HashMap<usize, (UnboundedSender<MaybeBatch>, UnboundedReceiver<MaybeBatch>)>,
+        HashMap<usize, Vec<(UnboundedSender<MaybeBatch>, UnboundedReceiver<MaybeBatch>)>>,
 
     /// Helper that ensures that that background job is killed once it is no longer needed.
     abort_helper: Arc<AbortOnDropMany<()>>,
 }
 
 /// The repartition operator maps N input partitions to M output partitions based on a
-/// partitioning scheme. No guarantees are made about the order of the resulting partitions.
+/// partitioning scheme. No guarantees are made about the order of the resulting partitions,
+/// unless it preserves the order of the input partitions.
 #[derive(Debug)]
 pub struct RepartitionExec {
     /// Input execution plan
@@ -69,6 +239,10 @@ pub struct RepartitionExec {
     /// Partitioning scheme to use
     partitioning: Partitioning,
 
+    /// Whether each output partition receives the batches of an input partition after
+    /// those of the previous input partitions, rather than as they are produced
+    preserve_input_order: bool,
+
     /// Inner state that is initialized when the first output stream is created.
     state: Arc<Mutex<RepartitionExecState>>,
 
@@ -127,6 +301,12 @@ impl RepartitionExec {
     pub fn partitioning(&self) -> &Partitioning {
         &self.partitioning
     }
+
+    /// Whether the output partitions receive the batches in the order of the input
+    /// partitions
+    pub fn preserves_input_order(&self) -> bool {
+        self.preserve_input_order
+    }
 }
 
 #[async_trait]
@@ -150,6 +330,12 @@ impl ExecutionPlan for RepartitionExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
+            1 if self.preserve_input_order => {
+                Ok(Arc::new(RepartitionExec::try_new_ordered(
+                    children[0].clone(),
+                    self.partitioning.clone(),
+                )?))
+            }
             1 => Ok(Arc::new(RepartitionExec::try_new(
                 children[0].clone(),
                 self.partitioning.clone(),
@@ -173,7 +359,13 @@ impl ExecutionPlan for RepartitionExec {
 
         // if this is the first partition to be invoked then we need to set up initial state
         if state.channels.is_empty() {
-            // create one channel per *output* partition
+            // create one channel per *output* partition, or one per output and input
+            // partition when preserving the order of the input partitions
+            let channels_per_output = if self.preserve_input_order {
+                num_input_partitions.max(1)
+            } else {
+                1
+            };
             for partition in 0..num_output_partitions {
                 // Note that this operator uses unbounded channels to avoid deadlocks because
                 // the output partitions can be read in any order and this could cause input
@@ -181,9 +373,12 @@ impl ExecutionPlan for RepartitionExec {
                 // being read yet. This may cause high memory usage if the next operator is
                 // reading output partitions in order rather than concurrently. One workaround
                 // for this would be to add spill-to-disk capabilities.
-                let (sender, receiver) =
-                    mpsc::unbounded_channel::<Option<ArrowResult<RecordBatch>>>();
-                state.channels.insert(partition, (sender, receiver));
+                let channels = (0..channels_per_output)
+                    .map(|_| {
+                        mpsc::unbounded_channel::<Option<ArrowResult<RecordBatch>>>()
+                    })
+                    .collect();
+                state.channels.insert(partition, channels);
             }
             // Use fixed random state
             let random = ahash::RandomState::with_seeds(0, 0, 0, 0);
@@ -191,16 +386,19 @@ impl ExecutionPlan for RepartitionExec {
             // launch one async task per *input* partition
             let mut join_handles = Vec::with_capacity(num_input_partitions);
             for i in 0..num_input_partitions {
+                let channel = if self.preserve_input_order { i } else { 0 };
                 let txs: HashMap<_, _> = state
                     .channels
                     .iter()
-                    .map(|(partition, (tx, _rx))| (*partition, tx.clone()))
+                    .map(|(partition, channels)| {
+                        (*partition, channels[channel].0.clone())
+                    })
                     .collect();
 
                 let r_metrics = RepartitionMetrics::new(i, partition, &self.metrics);
 
                 let input_task: JoinHandle<Result<()>> =
-                    tokio::spawn(Self::pull_from_input(
+                    cancellation::spawn(Self::pull_from_input(
                         random.clone(),
                         self.input.clone(),
                         i,
@@ -211,7 +409,7 @@ impl ExecutionPlan for RepartitionExec {
 
                 // In a separate task, wait for each input to be done
                 // (and pass along any errors, including panic!s)
-                let join_handle = tokio::spawn(Self::wait_for_task(
+                let join_handle = cancellation::spawn(Self::wait_for_task(
                     AbortOnDropSingle::new(input_task),
                     txs,
                 ));
@@ -227,9 +425,14 @@ impl ExecutionPlan for RepartitionExec {
             num_input_partitions,
             num_input_partitions_processed: 0,
             schema: self.input.schema(),
-            input: UnboundedReceiverStream::new(
-                state.channels.remove(&partition).unwrap().1,
-            ),
+            inputs: state
+                .channels
+                .remove(&partition)
+                .unwrap()
+                .into_iter()
+                .map(|(_tx, rx)| UnboundedReceiverStream::new(rx))
+                .collect(),
+            current: 0,
             drop_helper: Arc::clone(&state.abort_helper),
         }))
     }
@@ -245,7 +448,11 @@ impl ExecutionPlan for RepartitionExec {
     ) -> std::fmt::Result {
         match t {
             DisplayFormatType::Default => {
-                write!(f, "RepartitionExec: partitioning={:?}", self.partitioning)
+                write!(f, "RepartitionExec: partitioning={:?}", self.partitioning)?;
+                if self.preserve_input_order {
+                    write!(f, ", preserve_input_order=true")?;
+                }
+                Ok(())
             }
         }
     }
@@ -264,6 +471,7 @@ impl RepartitionExec {
         Ok(RepartitionExec {
             input,
             partitioning,
+            preserve_input_order: false,
             state: Arc::new(Mutex::new(RepartitionExecState {
                 channels: HashMap::new(),
                 abort_helper: Arc::new(AbortOnDropMany::<()>(vec![])),
@@ -272,6 +480,19 @@ impl RepartitionExec {
         })
     }
 
+    /// Create a new RepartitionExec whose output partitions receive the batches of an
+    /// input partition after those of the previous input partitions, so that their
+    /// content is deterministic. The input partitions are still executed in parallel.
+    pub fn try_new_ordered(
+        input: Arc<dyn ExecutionPlan>,
+        partitioning: Partitioning,
+    ) -> Result<Self> {
+        Ok(RepartitionExec {
+            preserve_input_order: true,
+            ..Self::try_new(input, partitioning)?
+        })
+    }
+
     /// Pulls data from the specified input plan, feeding it to the
     /// output partitions based on the desired partitioning
     ///
@@ -294,7 +515,15 @@ impl RepartitionExec {
         timer.done();
 
         let mut counter = 0;
-        let hashes_buf = &mut vec![];
+        let mut partitioner = match &partitioning {
+            Partitioning::Hash(_, _)
+            | Partitioning::RangePartitioning(_, _)
+            | Partitioning::Custom(_, _) => Some(BatchPartitioner::try_new(
+                partitioning.clone(),
+                random_state,
+            )?),
+            _ => None,
+        };
 
         // While there are still outputs to send to, keep
         // pulling inputs
@@ -323,26 +552,22 @@ impl RepartitionExec {
                     }
                     timer.done();
                 }
-                Partitioning::Hash(exprs, _) => {
+                _ => {
                     let timer = r_metrics.repart_time.timer();
                     let input_batch = result?;
-                    let arrays = exprs
-                        .iter()
-                        .map(|expr| {
-                            Ok(expr
-                                .evaluate(&input_batch)?
-                                .into_array(input_batch.num_rows()))
-                        })
-                        .collect::<Result<Vec<_>>>()?;
-                    hashes_buf.clear();
-                    hashes_buf.resize(arrays[0].len(), 0);
-                    // Hash arrays and compute buckets based on number of partitions
-                    let hashes = create_hashes(&arrays, &random_state, hashes_buf)?;
-                    let mut indices = vec![vec![]; num_output_partitions];
-                    for (index, hash) in hashes.iter().enumerate() {
-                        indices[(*hash % num_output_partitions as u64) as usize]
-                            .push(index as u64)
-                    }
+                    let indices = match &mut partitioner {
+                        Some(partitioner) => {
+                            partitioner.partition_indices(&input_batch)?
+                        }
+                        None => {
+                            // this should be unreachable as long as the validation logic
+                            // in the constructor is kept up-to-date
+                            return Err(DataFusionError::NotImplemented(format!(
+                                "Unsupported repartitioning scheme {:?}",
+                                partitioning
+                            )));
+                        }
+                    };
                     timer.done();
 
                     for (num_output_partition, partition_indices) in
@@ -355,7 +580,7 @@ impl RepartitionExec {
                             .columns()
                             .iter()
                             .map(|c| {
-                                take(c.as_ref(), &indices, None).map_err(|e| {
+                                take_array(c.as_ref(), &indices).map_err(|e| {
                                     DataFusionError::Execution(e.to_string())
                                 })
                             })
@@ -375,14 +600,6 @@ impl RepartitionExec {
                         timer.done();
                     }
                 }
-                other => {
-                    // this should be unreachable as long as the validation logic
-                    // in the constructor is kept up-to-date
-                    return Err(DataFusionError::NotImplemented(format!(
-                        "Unsupported repartitioning scheme {:?}",
-                        other
-                    )));
-                }
             }
             counter += 1;
         }
@@ -440,8 +657,12 @@ struct RepartitionStream {
     /// Schema
     schema: SchemaRef,
 
-    /// channel containing the repartitioned batches
-    input: UnboundedReceiverStream<Option<ArrowResult<RecordBatch>>>,
+    /// channels containing the repartitioned batches, received from one after the
+    /// other when there is one channel per input partition
+    inputs: Vec<UnboundedReceiverStream<Option<ArrowResult<RecordBatch>>>>,
+
+    /// Index of the channel currently received from
+    current: usize,
 
     /// Handle to ensure background tasks are killed when no longer needed.
     #[allow(dead_code)]
@@ -455,10 +676,15 @@ impl Stream for RepartitionStream {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        match self.input.poll_next_unpin(cx) {
+        let current = self.current;
+        match self.inputs[current].poll_next_unpin(cx) {
             Poll::Ready(Some(Some(v))) => Poll::Ready(Some(v)),
             Poll::Ready(Some(None)) => {
                 self.num_input_partitions_processed += 1;
+                if self.current + 1 < self.inputs.len() {
+                    // the input partition of this channel is done
+                    self.current += 1;
+                }
                 if self.num_input_partitions == self.num_input_partitions_processed {
                     // all input partitions have finished sending batches
                     Poll::Ready(None)
@@ -487,7 +713,11 @@ mod tests {
     use super::*;
     use crate::{
         assert_batches_sorted_eq,
-        physical_plan::{collect, expressions::col, memory::MemoryExec},
+        physical_plan::{
+            collect,
+            expressions::{col, PhysicalSortExpr},
+            memory::MemoryExec,
+        },
         test::{
             assert_is_pending,
             exec::{
@@ -496,6 +726,7 @@ mod tests {
             },
         },
     };
+    use arrow::compute::SortOptions;
     use arrow::datatypes::{DataType, Field, Schema};
     use arrow::record_batch::RecordBatch;
     use arrow::{
@@ -541,6 +772,45 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn many_to_one_preserving_input_order() -> Result<()> {
+        let schema = test_schema();
+        let partitions: Vec<_> = (0..3u32)
+            .map(|i| {
+                let batch = RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(UInt32Array::from(vec![i; 4]))],
+                )?;
+                Ok(vec![batch; 10])
+            })
+            .collect::<Result<_>>()?;
+        let exec = MemoryExec::try_new(&partitions, schema, None)?;
+        let exec = RepartitionExec::try_new_ordered(
+            Arc::new(exec),
+            Partitioning::RoundRobinBatch(1),
+        )?;
+        assert!(exec.preserves_input_order());
+
+        let batches =
+            crate::physical_plan::common::collect(exec.execute(0).await?).await?;
+        let values: Vec<_> = batches
+            .iter()
+            .map(|batch| {
+                batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<UInt32Array>()
+                    .unwrap()
+                    .value(0)
+            })
+            .collect();
+        // the batches of each input partition follow the ones of the previous one
+        let expected: Vec<_> = (0..3u32).flat_map(|i| vec![i; 10]).collect();
+        assert_eq!(values, expected);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn many_to_many_round_robin() -> Result<()> {
         // define input partitions
@@ -584,6 +854,133 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn many_to_many_range_partition() -> Result<()> {
+        // define input partitions
+        let schema = test_schema();
+        let partition = create_vec_batches(&schema, 50);
+        let partitions = vec![partition.clone(), partition.clone(), partition.clone()];
+
+        let sort_expr = PhysicalSortExpr {
+            expr: col("c0", &schema)?,
+            options: SortOptions::default(),
+        };
+        let output_partitions = repartition(
+            &schema,
+            partitions.clone(),
+            Partitioning::RangePartitioning(
+                vec![sort_expr],
+                vec![
+                    vec![ScalarValue::UInt32(Some(3))],
+                    vec![ScalarValue::UInt32(Some(5))],
+                ],
+            ),
+        )
+        .await?;
+        assert_eq!(3, output_partitions.len());
+        assert_eq!(
+            vec![vec![1, 2, 3], vec![4, 5], vec![6, 7, 8]],
+            distinct_values(&output_partitions)
+        );
+        assert_eq!(vec![450, 300, 450], row_counts(&output_partitions));
+
+        // boundaries of a different type are cast to the type of the sort expression
+        let sort_expr = PhysicalSortExpr {
+            expr: col("c0", &schema)?,
+            options: SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        };
+        let output_partitions = repartition(
+            &schema,
+            partitions,
+            Partitioning::RangePartitioning(
+                vec![sort_expr],
+                vec![
+                    vec![ScalarValue::Int64(Some(6))],
+                    vec![ScalarValue::Int64(Some(3))],
+                ],
+            ),
+        )
+        .await?;
+        assert_eq!(
+            vec![vec![6, 7, 8], vec![3, 4, 5], vec![1, 2]],
+            distinct_values(&output_partitions)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn many_to_many_custom_partition() -> Result<()> {
+        // define input partitions
+        let schema = test_schema();
+        let partition = create_vec_batches(&schema, 50);
+        let partitions = vec![partition.clone(), partition.clone(), partition.clone()];
+
+        let output_partitions = repartition(
+            &schema,
+            partitions.clone(),
+            Partitioning::Custom(col("c0", &schema)?, 9),
+        )
+        .await?;
+        assert_eq!(9, output_partitions.len());
+        let values = distinct_values(&output_partitions);
+        assert!(values[0].is_empty());
+        for (partition, values) in values.iter().enumerate().skip(1) {
+            assert_eq!(&vec![partition as u32], values);
+        }
+
+        // partitions that are out of range are an error
+        let result = repartition(
+            &schema,
+            partitions,
+            Partitioning::Custom(col("c0", &schema)?, 4),
+        )
+        .await;
+        let error = result.unwrap_err().to_string();
+        assert!(
+            error.contains(
+                "Partitioning expression returned partition 4 but there are 4 partitions"
+            ),
+            "actual: {}",
+            error
+        );
+
+        Ok(())
+    }
+
+    /// Returns the sorted distinct values of column `c0` of each partition
+    fn distinct_values(partitions: &[Vec<RecordBatch>]) -> Vec<Vec<u32>> {
+        partitions
+            .iter()
+            .map(|batches| {
+                let mut values = batches
+                    .iter()
+                    .flat_map(|batch| {
+                        let array = batch
+                            .column(0)
+                            .as_any()
+                            .downcast_ref::<UInt32Array>()
+                            .unwrap();
+                        array.values().to_vec()
+                    })
+                    .collect::<Vec<_>>();
+                values.sort_unstable();
+                values.dedup();
+                values
+            })
+            .collect()
+    }
+
+    fn row_counts(partitions: &[Vec<RecordBatch>]) -> Vec<usize> {
+        partitions
+            .iter()
+            .map(|batches| batches.iter().map(|b| b.num_rows()).sum())
+            .collect()
+    }
+
     fn test_schema() -> Arc<Schema> {
         Arc::new(Schema::new(vec![Field::new("c0", DataType::UInt32, false)]))
     }
@@ -631,7 +1028,7 @@ mod tests {
     #[tokio::test]
     async fn many_to_many_round_robin_within_tokio_task() -> Result<()> {
         let join_handle: JoinHandle<Result<Vec<Vec<RecordBatch>>>> =
-            tokio::spawn(async move {
+            cancellation::spawn(async move {
                 // define input partitions
                 let schema = test_schema();
                 let partition = create_vec_batches(&schema, 50);
@@ -0,0 +1,398 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A compact row format for the keys of hash aggregations and hash joins,
+//! which encodes the values of the key columns of each row as bytes, so that
+//! the keys of two rows are compared with a single byte slice comparison
+//! instead of comparing the values of each column, or converting them to
+//! [`ScalarValue`](crate::scalar::ScalarValue)s.
+//!
+//! Each value is encoded as a validity byte, followed for valid values by
+//! the little endian bytes of fixed width values, or by the length and the
+//! bytes of variable width values. The encoding is only meant to compare keys
+//! for equality, not to order them.
+//!
+//! The values of dictionary columns are encoded once for each value of their
+//! dictionary, and copied for each row, so that they are encoded as the values
+//! of a column of the value type would be.
+
+use crate::error::Result;
+use crate::physical_plan::dictionary::dictionary_keys_and_values;
+use arrow::array::{
+    Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Date64Array,
+    FixedSizeBinaryArray, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array,
+    Int8Array, LargeBinaryArray, LargeStringArray, StringArray,
+    TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+    TimestampSecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use arrow::datatypes::{DataType, TimeUnit};
+
+/// The keys of the rows of a batch, encoded in the row format
+#[derive(Debug)]
+pub struct RowKeys {
+    data: Vec<u8>,
+    /// The start of each row in `data`, followed by the end of the last row
+    offsets: Vec<usize>,
+    /// Whether each row has a null value in one of its columns
+    null_rows: Vec<bool>,
+}
+
+impl RowKeys {
+    /// Encodes the rows of `columns`, which must all have the same length.
+    /// Returns `None` if one of the columns has a type that is not supported
+    /// by the row format, in which case the values of the columns have to be
+    /// compared one by one.
+    pub fn try_new(columns: &[ArrayRef]) -> Result<Option<Self>> {
+        if columns.is_empty() || !columns.iter().all(|c| is_supported(c.data_type())) {
+            return Ok(None);
+        }
+        let num_rows = columns.first().map(|c| c.len()).unwrap_or(0);
+        let columns = columns
+            .iter()
+            .map(|column| match column.data_type() {
+                DataType::Dictionary(_, _) => {
+                    let (keys, values) = dictionary_keys_and_values(column.as_ref())?;
+                    let values = RowKeys::try_new(&[values])?.unwrap();
+                    Ok(KeyColumn::Dictionary(keys, values))
+                }
+                _ => Ok(KeyColumn::Values(column)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // compute the length of each row, to encode the values of each column
+        // in a single pass over the column
+        let mut lengths = vec![0; num_rows];
+        let mut null_rows = vec![false; num_rows];
+        for column in &columns {
+            match column {
+                KeyColumn::Values(column) => {
+                    let width = fixed_width(column.data_type());
+                    for (row, length) in lengths.iter_mut().enumerate() {
+                        *length += 1;
+                        if column.is_null(row) {
+                            null_rows[row] = true;
+                        } else {
+                            *length += match width {
+                                Some(width) => width,
+                                None => 4 + variable_width_value(column, row).len(),
+                            };
+                        }
+                    }
+                }
+                KeyColumn::Dictionary(keys, values) => {
+                    for (row, length) in lengths.iter_mut().enumerate() {
+                        if keys.is_null(row) {
+                            *length += 1;
+                            null_rows[row] = true;
+                        } else {
+                            let key = keys.value(row) as usize;
+                            *length += values.row(key).len();
+                            null_rows[row] |= values.has_null(key);
+                        }
+                    }
+                }
+            }
+        }
+        let mut offsets = Vec::with_capacity(num_rows + 1);
+        offsets.push(0);
+        for length in lengths {
+            offsets.push(offsets.last().unwrap() + length);
+        }
+
+        let mut data = vec![0; *offsets.last().unwrap()];
+        let mut cursors = offsets[..num_rows].to_vec();
+        for column in &columns {
+            match column {
+                KeyColumn::Values(column) => {
+                    encode_column(column, &mut data, &mut cursors)
+                }
+                KeyColumn::Dictionary(keys, values) => {
+                    encode_dictionary_column(keys, values, &mut data, &mut cursors)
+                }
+            }
+        }
+
+        Ok(Some(Self {
+            data,
+            offsets,
+            null_rows,
+        }))
+    }
+
+    /// The number of rows
+    pub fn num_rows(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// The encoded key of `row`
+    pub fn row(&self, row: usize) -> &[u8] {
+        &self.data[self.offsets[row]..self.offsets[row + 1]]
+    }
+
+    /// Whether `row` has a null value in one of its columns
+    pub fn has_null(&self, row: usize) -> bool {
+        self.null_rows[row]
+    }
+
+    /// Whether the key of `row` equals the key of `other_row` of `other`, with
+    /// nulls equal to each other if `null_equals_null`, as in hash joins
+    pub fn row_equals(
+        &self,
+        row: usize,
+        other: &RowKeys,
+        other_row: usize,
+        null_equals_null: bool,
+    ) -> bool {
+        (null_equals_null || !self.has_null(row)) && self.row(row) == other.row(other_row)
+    }
+}
+
+/// A key column to encode
+enum KeyColumn<'a> {
+    /// A column whose values are encoded for each row
+    Values(&'a ArrayRef),
+    /// The keys of a dictionary column, as indices of its encoded values
+    Dictionary(UInt64Array, RowKeys),
+}
+
+fn is_supported(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Dictionary(_, value_type) => {
+            !matches!(value_type.as_ref(), DataType::Dictionary(_, _))
+                && is_supported(value_type)
+        }
+        _ => {
+            fixed_width(data_type).is_some()
+                || matches!(
+                    data_type,
+                    DataType::Utf8
+                        | DataType::LargeUtf8
+                        | DataType::Binary
+                        | DataType::LargeBinary
+                )
+        }
+    }
+}
+
+/// The width of the encoded values of fixed width types
+pub(crate) fn fixed_width(data_type: &DataType) -> Option<usize> {
+    match data_type {
+        DataType::Boolean | DataType::Int8 | DataType::UInt8 => Some(1),
+        DataType::Int16 | DataType::UInt16 => Some(2),
+        DataType::Int32 | DataType::UInt32 | DataType::Float32 | DataType::Date32 => {
+            Some(4)
+        }
+        DataType::Int64
+        | DataType::UInt64
+        | DataType::Float64
+        | DataType::Date64
+        | DataType::Timestamp(_, _) => Some(8),
+        DataType::FixedSizeBinary(width) => Some(*width as usize),
+        _ => None,
+    }
+}
+
+fn variable_width_value(column: &ArrayRef, row: usize) -> &[u8] {
+    macro_rules! value {
+        ($ARRAY_TYPE:ident) => {
+            column
+                .as_any()
+                .downcast_ref::<$ARRAY_TYPE>()
+                .unwrap()
+                .value(row)
+                .as_ref()
+        };
+    }
+    match column.data_type() {
+        DataType::Utf8 => value!(StringArray),
+        DataType::LargeUtf8 => value!(LargeStringArray),
+        DataType::Binary => value!(BinaryArray),
+        DataType::LargeBinary => value!(LargeBinaryArray),
+        other => unreachable!("{:?} does not have variable width values", other),
+    }
+}
+
+/// Writes the encoded values of `column` at the cursors of the rows, and
+/// advances the cursors past them
+fn encode_column(column: &ArrayRef, data: &mut [u8], cursors: &mut [usize]) {
+    macro_rules! encode {
+        ($ARRAY_TYPE:ident, |$VALUE:ident| $BYTES:expr) => {{
+            let array = column.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+            for (row, cursor) in cursors.iter_mut().enumerate() {
+                // the validity byte of nulls is already 0
+                if array.is_valid(row) {
+                    let $VALUE = array.value(row);
+                    let bytes = $BYTES;
+                    let bytes: &[u8] = bytes.as_ref();
+                    data[*cursor] = 1;
+                    data[*cursor + 1..*cursor + 1 + bytes.len()].copy_from_slice(bytes);
+                    *cursor += bytes.len();
+                }
+                *cursor += 1;
+            }
+        }};
+    }
+    macro_rules! encode_variable {
+        ($ARRAY_TYPE:ident) => {{
+            let array = column.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+            for (row, cursor) in cursors.iter_mut().enumerate() {
+                if array.is_valid(row) {
+                    let bytes: &[u8] = array.value(row).as_ref();
+                    data[*cursor] = 1;
+                    *cursor += 1;
+                    data[*cursor..*cursor + 4]
+                        .copy_from_slice(&(bytes.len() as u32).to_le_bytes());
+                    *cursor += 4;
+                    data[*cursor..*cursor + bytes.len()].copy_from_slice(bytes);
+                    *cursor += bytes.len();
+                } else {
+                    *cursor += 1;
+                }
+            }
+        }};
+    }
+
+    match column.data_type() {
+        DataType::Boolean => encode!(BooleanArray, |v| [v as u8]),
+        DataType::Int8 => encode!(Int8Array, |v| v.to_le_bytes()),
+        DataType::Int16 => encode!(Int16Array, |v| v.to_le_bytes()),
+        DataType::Int32 => encode!(Int32Array, |v| v.to_le_bytes()),
+        DataType::Int64 => encode!(Int64Array, |v| v.to_le_bytes()),
+        DataType::UInt8 => encode!(UInt8Array, |v| v.to_le_bytes()),
+        DataType::UInt16 => encode!(UInt16Array, |v| v.to_le_bytes()),
+        DataType::UInt32 => encode!(UInt32Array, |v| v.to_le_bytes()),
+        DataType::UInt64 => encode!(UInt64Array, |v| v.to_le_bytes()),
+        DataType::Float32 => encode!(Float32Array, |v| v.to_le_bytes()),
+        DataType::Float64 => encode!(Float64Array, |v| v.to_le_bytes()),
+        DataType::Date32 => encode!(Date32Array, |v| v.to_le_bytes()),
+        DataType::Date64 => encode!(Date64Array, |v| v.to_le_bytes()),
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            encode!(TimestampSecondArray, |v| v.to_le_bytes())
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            encode!(TimestampMillisecondArray, |v| v.to_le_bytes())
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            encode!(TimestampMicrosecondArray, |v| v.to_le_bytes())
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            encode!(TimestampNanosecondArray, |v| v.to_le_bytes())
+        }
+        DataType::Utf8 => encode_variable!(StringArray),
+        DataType::LargeUtf8 => encode_variable!(LargeStringArray),
+        DataType::Binary => encode_variable!(BinaryArray),
+        DataType::LargeBinary => encode_variable!(LargeBinaryArray),
+        DataType::FixedSizeBinary(_) => encode!(FixedSizeBinaryArray, |v| v),
+        other => unreachable!("{:?} is not supported by the row format", other),
+    }
+}
+
+/// Writes the encoded values of the rows of a dictionary column, with `keys`
+/// and encoded `values`, at the cursors of the rows, and advances the cursors
+/// past them
+fn encode_dictionary_column(
+    keys: &UInt64Array,
+    values: &RowKeys,
+    data: &mut [u8],
+    cursors: &mut [usize],
+) {
+    for (row, cursor) in cursors.iter_mut().enumerate() {
+        if keys.is_null(row) {
+            // the validity byte of nulls is already 0
+            *cursor += 1;
+        } else {
+            let bytes = values.row(keys.value(row) as usize);
+            data[*cursor..*cursor + bytes.len()].copy_from_slice(bytes);
+            *cursor += bytes.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{DictionaryArray, ListArray};
+    use arrow::datatypes::{Int32Type, Int8Type};
+    use std::sync::Arc;
+
+    #[test]
+    fn row_keys() -> Result<()> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from(vec![
+                Some(1),
+                Some(1),
+                None,
+                Some(1),
+                None,
+            ])),
+            Arc::new(StringArray::from(vec![
+                Some("a"),
+                Some("ab"),
+                Some("a"),
+                Some("a"),
+                Some("a"),
+            ])),
+            Arc::new(BooleanArray::from(vec![true, true, false, true, false])),
+        ];
+        let keys = RowKeys::try_new(&columns)?.unwrap();
+        assert_eq!(5, keys.num_rows());
+        assert_eq!(keys.row(0), keys.row(3));
+        assert_ne!(keys.row(0), keys.row(1));
+        assert_ne!(keys.row(0), keys.row(2));
+        // 1 validity byte + 4 bytes, 1 + 4 + 1 bytes, and 1 + 1 bytes
+        assert_eq!(13, keys.row(0).len());
+
+        assert!(!keys.has_null(0));
+        assert!(keys.has_null(2));
+        assert!(keys.row_equals(0, &keys, 3, false));
+        assert!(!keys.row_equals(2, &keys, 4, false));
+        assert!(keys.row_equals(2, &keys, 4, true));
+        Ok(())
+    }
+
+    #[test]
+    fn dictionary_row_keys() -> Result<()> {
+        let dict: DictionaryArray<Int8Type> =
+            vec![Some("ab"), None, Some("a"), Some("ab")]
+                .into_iter()
+                .collect();
+        let strings = StringArray::from(vec![Some("ab"), None, Some("a"), Some("b")]);
+        let dict_keys = RowKeys::try_new(&[Arc::new(dict) as ArrayRef])?.unwrap();
+        let string_keys = RowKeys::try_new(&[Arc::new(strings) as ArrayRef])?.unwrap();
+
+        // the dictionary values are encoded as the strings
+        assert!(dict_keys.row_equals(0, &string_keys, 0, false));
+        assert!(dict_keys.row_equals(2, &string_keys, 2, false));
+        assert!(!dict_keys.row_equals(3, &string_keys, 3, false));
+        assert!(dict_keys.has_null(1));
+        assert!(dict_keys.row_equals(1, &string_keys, 1, true));
+        assert_eq!(dict_keys.row(0), dict_keys.row(3));
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_type() -> Result<()> {
+        let list = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1)]),
+            Some(vec![Some(2)]),
+        ]);
+        let columns: Vec<ArrayRef> =
+            vec![Arc::new(Int32Array::from(vec![1, 2])), Arc::new(list)];
+        assert!(RowKeys::try_new(&columns)?.is_none());
+        Ok(())
+    }
+}
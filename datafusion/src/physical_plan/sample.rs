@@ -0,0 +1,375 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! SampleExec returns a random sample of the rows or record batches of its input
+
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use super::file_format::{AvroExec, CsvExec, NdJsonExec, ParquetExec};
+use super::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use super::{
+    DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
+};
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::SampleMethod;
+use arrow::array::BooleanArray;
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// SampleExec returns each row ([`SampleMethod::Bernoulli`]) or each record
+/// batch ([`SampleMethod::System`]) of its input with probability `fraction`.
+///
+/// The choice only depends on the seed, the partition and the position of the
+/// row in the partition, so that executing it again returns the same sample.
+#[derive(Debug)]
+pub struct SampleExec {
+    /// The input plan
+    input: Arc<dyn ExecutionPlan>,
+    /// How the rows are chosen
+    method: SampleMethod,
+    /// Probability with which each row or batch is returned
+    fraction: f64,
+    /// Seed of the random choice
+    seed: u64,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl SampleExec {
+    /// Create a new SampleExec
+    pub fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        method: SampleMethod,
+        fraction: f64,
+        seed: u64,
+    ) -> Result<Self> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(DataFusionError::Plan(format!(
+                "Sampling fraction must be between 0 and 1, got {}",
+                fraction
+            )));
+        }
+        Ok(Self {
+            input,
+            method,
+            fraction,
+            seed,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    /// The input plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// How the rows are chosen
+    pub fn method(&self) -> SampleMethod {
+        self.method
+    }
+
+    /// Probability with which each row or batch is returned
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    /// Seed of the random choice
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for SampleExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(SampleExec::try_new(
+                children[0].clone(),
+                self.method,
+                self.fraction,
+                self.seed,
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "SampleExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let mut hasher = DefaultHasher::new();
+        (self.seed, partition).hash(&mut hasher);
+
+        Ok(Box::pin(SampleStream {
+            schema: self.input.schema(),
+            input: self.input.execute(partition).await?,
+            method: self.method,
+            fraction: self.fraction,
+            rng: StdRng::seed_from_u64(hasher.finish()),
+            baseline_metrics,
+        }))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(
+                    f,
+                    "SampleExec: method={}, fraction={}, seed={}",
+                    self.method, self.fraction, self.seed
+                )
+            }
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+struct SampleStream {
+    /// Output schema, which is the same as the input schema for this operator
+    schema: SchemaRef,
+    /// The input partition to sample
+    input: SendableRecordBatchStream,
+    /// How the rows are chosen
+    method: SampleMethod,
+    /// Probability with which each row or batch is returned
+    fraction: f64,
+    /// Random number generator of the partition
+    rng: StdRng,
+    /// runtime metrics recording
+    baseline_metrics: BaselineMetrics,
+}
+
+/// Samples `batch`, returning `None` if the whole batch is skipped
+fn sample_batch(
+    batch: RecordBatch,
+    method: SampleMethod,
+    fraction: f64,
+    rng: &mut StdRng,
+) -> Option<ArrowResult<RecordBatch>> {
+    match method {
+        SampleMethod::System => {
+            if rng.gen_bool(fraction) {
+                Some(Ok(batch))
+            } else {
+                None
+            }
+        }
+        SampleMethod::Bernoulli => {
+            let mask = (0..batch.num_rows())
+                .map(|_| Some(rng.gen_bool(fraction)))
+                .collect::<BooleanArray>();
+            Some(filter_record_batch(&batch, &mask))
+        }
+    }
+}
+
+impl Stream for SampleStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            let poll = match this.input.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(batch))) => {
+                    let timer = this.baseline_metrics.elapsed_compute().timer();
+                    let sampled =
+                        sample_batch(batch, this.method, this.fraction, &mut this.rng);
+                    timer.done();
+                    match sampled {
+                        Some(sampled) => Poll::Ready(Some(sampled)),
+                        // skipped batches are not returned
+                        None => continue,
+                    }
+                }
+                other => other,
+            };
+            return this.baseline_metrics.record_poll(poll);
+        }
+    }
+}
+
+impl RecordBatchStream for SampleStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Returns whether the block of rows identified by `key` is part of the sample
+/// with the given `seed` that returns each block with probability `fraction`.
+///
+/// The choice is deterministic, so that all partitions that read parts of the
+/// same file make the same choice.
+pub(crate) fn is_block_sampled(key: impl Hash, fraction: f64, seed: u64) -> bool {
+    let mut hasher = DefaultHasher::new();
+    (seed, key).hash(&mut hasher);
+    fraction >= 1.0 || (hasher.finish() as f64 / u64::MAX as f64) < fraction
+}
+
+/// Creates the plan that samples the output of `input`.
+///
+/// [`SampleMethod::System`] samples of file scans are pushed into the scan, so
+/// that the files, or the row groups of Parquet files, that are not sampled are
+/// not read at all.
+pub fn create_sample_plan(
+    input: Arc<dyn ExecutionPlan>,
+    method: SampleMethod,
+    fraction: f64,
+    seed: u64,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    if method == SampleMethod::System {
+        let input = input.as_any();
+        if let Some(parquet) = input.downcast_ref::<ParquetExec>() {
+            return Ok(Arc::new(
+                parquet.clone().with_row_group_sample(fraction, seed),
+            ));
+        }
+        if let Some(csv) = input.downcast_ref::<CsvExec>() {
+            return Ok(Arc::new(CsvExec::new(
+                csv.base_config().sample_files(fraction, seed),
+                csv.has_header(),
+                csv.delimiter(),
+            )));
+        }
+        if let Some(json) = input.downcast_ref::<NdJsonExec>() {
+            return Ok(Arc::new(NdJsonExec::new(
+                json.base_config().sample_files(fraction, seed),
+            )));
+        }
+        if let Some(avro) = input.downcast_ref::<AvroExec>() {
+            return Ok(Arc::new(AvroExec::new(
+                avro.base_config().sample_files(fraction, seed),
+            )));
+        }
+    }
+    Ok(Arc::new(SampleExec::try_new(
+        input, method, fraction, seed,
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::memory::MemoryExec;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::util::pretty::pretty_format_batches;
+
+    fn input() -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = (0..10)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from(
+                        (i * 100..(i + 1) * 100).collect::<Vec<_>>(),
+                    ))],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        Ok(Arc::new(MemoryExec::try_new(&[batches], schema, None)?))
+    }
+
+    fn num_rows(batches: &[RecordBatch]) -> usize {
+        batches.iter().map(|b| b.num_rows()).sum()
+    }
+
+    #[tokio::test]
+    async fn bernoulli_sample() -> Result<()> {
+        let sample = SampleExec::try_new(input()?, SampleMethod::Bernoulli, 0.2, 42)?;
+        let batches = collect(Arc::new(sample)).await?;
+        let sampled = num_rows(&batches);
+        assert!(sampled > 100 && sampled < 300, "sampled {} rows", sampled);
+
+        // the same seed returns the same sample
+        let sample = SampleExec::try_new(input()?, SampleMethod::Bernoulli, 0.2, 42)?;
+        assert_eq!(
+            pretty_format_batches(&batches)?,
+            pretty_format_batches(&collect(Arc::new(sample)).await?)?
+        );
+
+        let sample = SampleExec::try_new(input()?, SampleMethod::Bernoulli, 0.0, 42)?;
+        assert_eq!(0, num_rows(&collect(Arc::new(sample)).await?));
+        let sample = SampleExec::try_new(input()?, SampleMethod::Bernoulli, 1.0, 42)?;
+        assert_eq!(1000, num_rows(&collect(Arc::new(sample)).await?));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn system_sample() -> Result<()> {
+        let sample = SampleExec::try_new(input()?, SampleMethod::System, 0.5, 7)?;
+        let batches = collect(Arc::new(sample)).await?;
+        // whole batches are returned
+        assert!(batches.iter().all(|b| b.num_rows() == 100));
+        assert!(batches.len() < 10);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_fraction() -> Result<()> {
+        let err = SampleExec::try_new(input()?, SampleMethod::System, 1.5, 7)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("between 0 and 1"), "{}", err);
+        Ok(())
+    }
+}
@@ -17,12 +17,14 @@
 
 //! Defines the SORT plan
 
+use super::cancellation::{self, CancellationToken};
 use super::common::AbortOnDropSingle;
 use super::metrics::{
     BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet, RecordOutput,
 };
 use super::{RecordBatchStream, SendableRecordBatchStream, Statistics};
 use crate::error::{DataFusionError, Result};
+use crate::execution::memory_manager::MemoryReservation;
 use crate::physical_plan::expressions::PhysicalSortExpr;
 use crate::physical_plan::{
     common, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
@@ -34,7 +36,7 @@ use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 use arrow::{array::ArrayRef, error::ArrowError};
 use async_trait::async_trait;
-use futures::stream::Stream;
+use futures::stream::{Stream, StreamExt};
 use futures::Future;
 use pin_project_lite::pin_project;
 use std::any::Any;
@@ -88,6 +90,11 @@ impl SortExec {
     pub fn expr(&self) -> &[PhysicalSortExpr] {
         &self.expr
     }
+
+    /// Whether each partition of the input plan is sorted separately
+    pub fn preserve_partitioning(&self) -> bool {
+        self.preserve_partitioning
+    }
 }
 
 #[async_trait]
@@ -127,10 +134,11 @@ impl ExecutionPlan for SortExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(SortExec::try_new(
+            1 => Ok(Arc::new(SortExec::new_with_partitioning(
                 self.expr.clone(),
                 children[0].clone(),
-            )?)),
+                self.preserve_partitioning,
+            ))),
             _ => Err(DataFusionError::Internal(
                 "SortExec wrong number of children".to_string(),
             )),
@@ -241,15 +249,32 @@ impl SortStream {
     ) -> Self {
         let (tx, rx) = futures::channel::oneshot::channel();
         let schema = input.schema();
-        let join_handle = tokio::spawn(async move {
+        let join_handle = cancellation::spawn(async move {
             let schema = input.schema();
-            let sorted_batch = common::collect(input)
+            // the input is buffered until sorted, reserving the memory of each batch
+            // before it is kept
+            let mut reservation = MemoryReservation::new("SortExec");
+            let sorted_batch = collect_reserved(input, &mut reservation)
                 .await
                 .map_err(DataFusionError::into_arrow_external_error)
                 .and_then(move |batches| {
+                    // the sort can't be interrupted once started, don't start it if
+                    // the query was cancelled while collecting its input
+                    let cancellation = CancellationToken::current();
+                    cancellation
+                        .check()
+                        .map_err(DataFusionError::into_arrow_external_error)?;
+                    // the buffered input is combined into a copy
+                    let buffered = reservation.size();
+                    reservation
+                        .try_grow(buffered)
+                        .map_err(DataFusionError::into_arrow_external_error)?;
                     let timer = baseline_metrics.elapsed_compute().timer();
                     // combine all record batches into one for each column
                     let combined = common::combine_batches(&batches, schema.clone())?;
+                    cancellation
+                        .check()
+                        .map_err(DataFusionError::into_arrow_external_error)?;
                     // sort combined record batch
                     let result = combined
                         .map(|batch| sort_batch(batch, schema, &expr))
@@ -272,6 +297,21 @@ impl SortStream {
     }
 }
 
+/// Collects the batches of `input`, reserving the memory of each batch with
+/// `reservation` before it is kept
+async fn collect_reserved(
+    mut input: SendableRecordBatchStream,
+    reservation: &mut MemoryReservation,
+) -> Result<Vec<RecordBatch>> {
+    let mut batches = vec![];
+    while let Some(batch) = input.next().await {
+        let batch = batch?;
+        reservation.try_grow_batch(&batch)?;
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
 impl Stream for SortStream {
     type Item = ArrowResult<RecordBatch>;
 
@@ -340,9 +380,11 @@ mod tests {
                 file_groups: files,
                 statistics: Statistics::default(),
                 projection: None,
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -389,6 +431,46 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_sort_preserve_partitioning() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = |values: Vec<Option<i32>>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))])
+        };
+        let input = Arc::new(MemoryExec::try_new(
+            &[
+                vec![batch(vec![Some(2), None, Some(1)])?],
+                vec![batch(vec![None, Some(3)])?],
+            ],
+            schema.clone(),
+            None,
+        )?);
+        let sort_exec = SortExec::new_with_partitioning(
+            vec![PhysicalSortExpr {
+                expr: col("a", &schema)?,
+                options: SortOptions {
+                    descending: false,
+                    nulls_first: true,
+                },
+            }],
+            input.clone(),
+            true,
+        );
+
+        // the partitioning must survive rewrites of the plan
+        let sort_exec = sort_exec.with_new_children(vec![input])?;
+        assert_eq!(sort_exec.output_partitioning().partition_count(), 2);
+
+        let result = common::collect(sort_exec.execute(0).await?).await?;
+        let a = as_primitive_array::<Int32Type>(result[0].column(0));
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![None, Some(1), Some(2)]);
+
+        let result = common::collect(sort_exec.execute(1).await?).await?;
+        let a = as_primitive_array::<Int32Type>(result[0].column(0));
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![None, Some(3)]);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_lex_sort_by_float() -> Result<()> {
         let schema = Arc::new(Schema::new(vec![
@@ -42,9 +42,9 @@ use hashbrown::HashMap;
 
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{
-    common::spawn_execution, expressions::PhysicalSortExpr, DisplayFormatType,
-    Distribution, ExecutionPlan, Partitioning, PhysicalExpr, RecordBatchStream,
-    SendableRecordBatchStream, Statistics,
+    cancellation, common::spawn_execution, expressions::PhysicalSortExpr,
+    DisplayFormatType, Distribution, ExecutionPlan, Partitioning, PhysicalExpr,
+    RecordBatchStream, SendableRecordBatchStream, Statistics,
 };
 
 /// Sort preserving merge execution plan
@@ -89,6 +89,11 @@ impl SortPreservingMergeExec {
     pub fn expr(&self) -> &[PhysicalSortExpr] {
         &self.expr
     }
+
+    /// Target size of the merged output batches
+    pub fn target_batch_size(&self) -> usize {
+        self.target_batch_size
+    }
 }
 
 #[async_trait]
@@ -942,9 +947,11 @@ mod tests {
                 file_groups: files,
                 statistics: Statistics::default(),
                 projection: None,
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -1025,9 +1032,11 @@ mod tests {
                 file_groups: files,
                 statistics: Statistics::default(),
                 projection: None,
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -1210,7 +1219,7 @@ mod tests {
         for partition in 0..partition_count {
             let (mut sender, receiver) = mpsc::channel(1);
             let mut stream = batches.execute(partition).await.unwrap();
-            let join_handle = tokio::spawn(async move {
+            let join_handle = cancellation::spawn(async move {
                 while let Some(batch) = stream.next().await {
                     sender.send(batch).await.unwrap();
                     // This causes the MergeStream to wait for more input
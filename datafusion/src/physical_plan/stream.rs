@@ -25,6 +25,7 @@ use tokio::task::JoinHandle;
 use tokio_stream::wrappers::ReceiverStream;
 
 use super::common::AbortOnDropSingle;
+use super::metrics::BaselineMetrics;
 use super::{RecordBatchStream, SendableRecordBatchStream};
 
 /// Adapter for a tokio [`ReceiverStream`] that implements the
@@ -73,3 +74,64 @@ impl RecordBatchStream for RecordBatchReceiverStream {
         self.schema.clone()
     }
 }
+
+/// Stream wrapper that records `BaselineMetrics` for a particular
+/// partition
+pub(crate) struct ObservedStream {
+    inner: SendableRecordBatchStream,
+    baseline_metrics: BaselineMetrics,
+    /// whether polling `inner` is the work of the operator, as for the
+    /// leaves of a plan, rather than the work of its input
+    record_compute: bool,
+}
+
+impl ObservedStream {
+    /// Records the output of `inner`
+    pub(crate) fn new(
+        inner: SendableRecordBatchStream,
+        baseline_metrics: BaselineMetrics,
+    ) -> Self {
+        Self {
+            inner,
+            baseline_metrics,
+            record_compute: false,
+        }
+    }
+
+    /// Records the output of `inner`, and the time spent polling it as the
+    /// `elapsed_compute` of the operator
+    pub(crate) fn new_with_compute(
+        inner: SendableRecordBatchStream,
+        baseline_metrics: BaselineMetrics,
+    ) -> Self {
+        Self {
+            inner,
+            baseline_metrics,
+            record_compute: true,
+        }
+    }
+}
+
+impl RecordBatchStream for ObservedStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl Stream for ObservedStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let poll = if self.record_compute {
+            let elapsed_compute = self.baseline_metrics.elapsed_compute().clone();
+            let _timer = elapsed_compute.timer();
+            self.inner.poll_next_unpin(cx)
+        } else {
+            self.inner.poll_next_unpin(cx)
+        };
+        self.baseline_metrics.record_poll(poll)
+    }
+}
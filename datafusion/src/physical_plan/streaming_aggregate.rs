@@ -0,0 +1,505 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the execution plan for aggregations over unbounded inputs, which emit the
+//! aggregates of every processing time window instead of waiting for the end of the
+//! input.
+
+use std::any::Any;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
+use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use crate::physical_plan::empty::EmptyExec;
+use crate::physical_plan::expressions::col;
+use crate::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
+use crate::physical_plan::memory::MemoryExec;
+use crate::physical_plan::metrics::{
+    BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet, RecordOutput,
+};
+use crate::physical_plan::repartition::RepartitionExec;
+use crate::physical_plan::stream::RecordBatchReceiverStream;
+use crate::physical_plan::{
+    cancellation, collect, AggregateExpr, DisplayFormatType, Distribution, ExecutionPlan,
+    Partitioning, PhysicalExpr, SendableRecordBatchStream, Statistics,
+};
+use arrow::datatypes::SchemaRef;
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::record_batch::RecordBatch;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::sync::mpsc::Sender;
+
+/// Aggregates an unbounded input in tumbling processing time windows. Every input batch
+/// is reduced to partial aggregate states as it arrives, and at the end of every window
+/// the states are merged into the aggregates of the rows received during the window.
+/// Windows without input rows produce no output.
+#[derive(Debug, Clone)]
+pub struct StreamingAggregateExec {
+    /// Grouping expressions
+    group_expr: Vec<(Arc<dyn PhysicalExpr>, String)>,
+    /// Aggregate expressions
+    aggr_expr: Vec<Arc<dyn AggregateExpr>>,
+    /// Input plan, which has a single partition
+    input: Arc<dyn ExecutionPlan>,
+    /// Length of the processing time windows
+    window: Duration,
+    /// Schema of the partial aggregate states
+    partial_schema: SchemaRef,
+    /// Schema after the aggregation is applied
+    schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl StreamingAggregateExec {
+    /// Create a new streaming aggregate execution plan
+    pub fn try_new(
+        group_expr: Vec<(Arc<dyn PhysicalExpr>, String)>,
+        aggr_expr: Vec<Arc<dyn AggregateExpr>>,
+        input: Arc<dyn ExecutionPlan>,
+        window: Duration,
+    ) -> Result<Self> {
+        // derive the schemas from the plans that are evaluated for every window
+        let empty = Arc::new(EmptyExec::new(false, input.schema()));
+        let partial = Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            group_expr.clone(),
+            aggr_expr.clone(),
+            empty,
+            input.schema(),
+        )?);
+        let partial_schema = partial.schema();
+        let schema =
+            final_aggregate(&group_expr, &aggr_expr, partial, input.schema())?.schema();
+        Ok(Self {
+            group_expr,
+            aggr_expr,
+            input,
+            window,
+            partial_schema,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    /// Grouping expressions
+    pub fn group_expr(&self) -> &[(Arc<dyn PhysicalExpr>, String)] {
+        &self.group_expr
+    }
+
+    /// Aggregate expressions
+    pub fn aggr_expr(&self) -> &[Arc<dyn AggregateExpr>] {
+        &self.aggr_expr
+    }
+
+    /// Input plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// Length of the processing time windows
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Reduces a batch of input rows to partial aggregate states
+    async fn aggregate_partial(&self, batch: RecordBatch) -> Result<Vec<RecordBatch>> {
+        let input = MemoryExec::try_new(&[vec![batch]], self.input.schema(), None)?;
+        let partial = HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            self.group_expr.clone(),
+            self.aggr_expr.clone(),
+            Arc::new(input),
+            self.input.schema(),
+        )?;
+        collect(Arc::new(partial)).await
+    }
+
+    /// Merges the partial aggregate states of a window and sends the aggregates to the
+    /// output. Returns false if the output has been dropped.
+    async fn emit_window(
+        &self,
+        partial_states: &mut Vec<RecordBatch>,
+        tx: &Sender<ArrowResult<RecordBatch>>,
+        baseline_metrics: &BaselineMetrics,
+    ) -> Result<bool> {
+        if partial_states.is_empty() {
+            return Ok(!tx.is_closed());
+        }
+        let input = MemoryExec::try_new(
+            &[std::mem::take(partial_states)],
+            self.partial_schema.clone(),
+            None,
+        )?;
+        let aggregate = final_aggregate(
+            &self.group_expr,
+            &self.aggr_expr,
+            Arc::new(input),
+            self.input.schema(),
+        )?;
+        let start = Instant::now();
+        let batches = collect(Arc::new(aggregate)).await?;
+        baseline_metrics.elapsed_compute().add_elapsed(start);
+        for batch in batches {
+            if tx
+                .send(Ok(batch.record_output(baseline_metrics)))
+                .await
+                .is_err()
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn aggregate_windows(
+        &self,
+        mut input: SendableRecordBatchStream,
+        tx: &Sender<ArrowResult<RecordBatch>>,
+        baseline_metrics: &BaselineMetrics,
+    ) -> Result<()> {
+        let mut interval = tokio::time::interval(self.window);
+        // the first tick completes immediately and starts the first window
+        interval.tick().await;
+        let mut partial_states = vec![];
+        loop {
+            tokio::select! {
+                batch = input.next() => match batch {
+                    Some(batch) => {
+                        let batch = batch?;
+                        if batch.num_rows() > 0 {
+                            let start = Instant::now();
+                            partial_states.extend(self.aggregate_partial(batch).await?);
+                            baseline_metrics.elapsed_compute().add_elapsed(start);
+                        }
+                    }
+                    None => {
+                        // the end of a bounded input closes the last window
+                        self.emit_window(&mut partial_states, tx, baseline_metrics)
+                            .await?;
+                        return Ok(());
+                    }
+                },
+                _ = interval.tick() => {
+                    if !self
+                        .emit_window(&mut partial_states, tx, baseline_metrics)
+                        .await?
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Creates the aggregation that merges partial aggregate states
+fn final_aggregate(
+    group_expr: &[(Arc<dyn PhysicalExpr>, String)],
+    aggr_expr: &[Arc<dyn AggregateExpr>],
+    partial: Arc<dyn ExecutionPlan>,
+    input_schema: SchemaRef,
+) -> Result<HashAggregateExec> {
+    let partial_schema = partial.schema();
+    let final_group = group_expr
+        .iter()
+        .map(|(_, name)| Ok((col(name, &partial_schema)?, name.clone())))
+        .collect::<Result<Vec<_>>>()?;
+    HashAggregateExec::try_new(
+        AggregateMode::Final,
+        final_group,
+        aggr_expr.to_vec(),
+        partial,
+        input_schema,
+    )
+}
+
+#[async_trait]
+impl ExecutionPlan for StreamingAggregateExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::SinglePartition
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(StreamingAggregateExec::try_new(
+                self.group_expr.clone(),
+                self.aggr_expr.clone(),
+                children[0].clone(),
+                self.window,
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "StreamingAggregateExec wrong number of children".to_owned(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "StreamingAggregateExec invalid partition {} (expected 0)",
+                partition
+            )));
+        }
+
+        let input = self.input.execute(0).await?;
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        let aggregate = self.clone();
+        let join_handle = cancellation::spawn(async move {
+            let result = aggregate
+                .aggregate_windows(input, &tx, &baseline_metrics)
+                .await;
+            baseline_metrics.done();
+            if let Err(e) = result {
+                // the output may already have been dropped
+                tx.send(Err(ArrowError::ExternalError(Box::new(e))))
+                    .await
+                    .ok();
+            }
+        });
+        Ok(RecordBatchReceiverStream::create(
+            &self.schema,
+            rx,
+            join_handle,
+        ))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                let g: Vec<String> = self
+                    .group_expr
+                    .iter()
+                    .map(|(_, name)| name.clone())
+                    .collect();
+                let a: Vec<String> = self
+                    .aggr_expr
+                    .iter()
+                    .map(|agg| agg.name().to_string())
+                    .collect();
+                write!(
+                    f,
+                    "StreamingAggregateExec: window={:?}, gby=[{}], aggr=[{}]",
+                    self.window,
+                    g.join(", "),
+                    a.join(", ")
+                )
+            }
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// Replaces the outermost aggregation of a physical plan, which consists of a partial
+/// and a final `HashAggregateExec`, by a [`StreamingAggregateExec`] that emits the
+/// aggregates of every processing time window of the given length.
+pub fn plan_streaming_aggregate(
+    plan: Arc<dyn ExecutionPlan>,
+    window: Duration,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    streaming_aggregate(plan, window)?.ok_or_else(|| {
+        DataFusionError::Plan("Streaming aggregates require an aggregation".to_owned())
+    })
+}
+
+fn streaming_aggregate(
+    plan: Arc<dyn ExecutionPlan>,
+    window: Duration,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    if let Some(aggregate) = plan.as_any().downcast_ref::<HashAggregateExec>() {
+        let partial = match aggregate.mode() {
+            AggregateMode::Final | AggregateMode::FinalPartitioned => {
+                partial_aggregate(aggregate.input())
+            }
+            AggregateMode::Partial => None,
+        }
+        .ok_or_else(|| {
+            DataFusionError::Internal(
+                "Expected a final aggregation over a partial aggregation".to_owned(),
+            )
+        })?;
+        if contains_aggregate(partial.input()) {
+            return Err(DataFusionError::NotImplemented(
+                "Streaming aggregates over nested aggregations".to_owned(),
+            ));
+        }
+        let input = match partial.input().output_partitioning().partition_count() {
+            1 => partial.input().clone(),
+            _ => Arc::new(CoalescePartitionsExec::new(partial.input().clone())),
+        };
+        return Ok(Some(Arc::new(StreamingAggregateExec::try_new(
+            partial.group_expr().to_vec(),
+            partial.aggr_expr().to_vec(),
+            input,
+            window,
+        )?)));
+    }
+
+    let mut found = false;
+    let children = plan
+        .children()
+        .into_iter()
+        .map(|child| match streaming_aggregate(child.clone(), window)? {
+            Some(new_child) => {
+                found = true;
+                Ok(new_child)
+            }
+            None => Ok(child),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if found {
+        Ok(Some(plan.with_new_children(children)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the partial aggregation below the repartitions and coalesces that the
+/// planner inserts between a partial and a final aggregation
+fn partial_aggregate(plan: &Arc<dyn ExecutionPlan>) -> Option<&HashAggregateExec> {
+    let any = plan.as_any();
+    if let Some(aggregate) = any.downcast_ref::<HashAggregateExec>() {
+        match aggregate.mode() {
+            AggregateMode::Partial => Some(aggregate),
+            _ => None,
+        }
+    } else if let Some(repartition) = any.downcast_ref::<RepartitionExec>() {
+        partial_aggregate(repartition.input())
+    } else if let Some(coalesce) = any.downcast_ref::<CoalescePartitionsExec>() {
+        partial_aggregate(coalesce.input())
+    } else if let Some(coalesce) = any.downcast_ref::<CoalesceBatchesExec>() {
+        partial_aggregate(coalesce.input())
+    } else {
+        None
+    }
+}
+
+fn contains_aggregate(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    plan.as_any().is::<HashAggregateExec>()
+        || plan.children().iter().any(contains_aggregate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_batches_sorted_eq;
+    use crate::datasource::streaming::StreamingTable;
+    use crate::execution::context::ExecutionContext;
+    use crate::logical_plan::{col, sum};
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    #[tokio::test]
+    async fn aggregate_windows_of_unbounded_input() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8, false),
+            Field::new("b", DataType::Int64, false),
+        ]));
+        let batch = |a: Vec<&str>, b: Vec<i64>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(a)),
+                    Arc::new(Int64Array::from(b)),
+                ],
+            )
+        };
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        let stream =
+            RecordBatchReceiverStream::create(&schema, rx, tokio::spawn(async {}));
+
+        let mut ctx = ExecutionContext::new();
+        let df = ctx.read_table(Arc::new(StreamingTable::new(stream)))?;
+        let mut results = df
+            .stream_aggregates(
+                vec![col("a")],
+                vec![sum(col("b"))],
+                Duration::from_millis(50),
+            )
+            .await?;
+
+        // the aggregates of a window are emitted before the end of the input
+        tx.send(batch(vec!["x", "y", "x"], vec![1, 2, 3]))
+            .await
+            .unwrap();
+        let window = results.next().await.unwrap()?;
+        assert_batches_sorted_eq!(
+            vec![
+                "+---+----------------+",
+                "| a | SUM(?table?.b) |",
+                "+---+----------------+",
+                "| x | 4              |",
+                "| y | 2              |",
+                "+---+----------------+",
+            ],
+            &[window]
+        );
+
+        // every window only aggregates the rows received during the window
+        tx.send(batch(vec!["x"], vec![10])).await.unwrap();
+        let window = results.next().await.unwrap()?;
+        assert_batches_sorted_eq!(
+            vec![
+                "+---+----------------+",
+                "| a | SUM(?table?.b) |",
+                "+---+----------------+",
+                "| x | 10             |",
+                "+---+----------------+",
+            ],
+            &[window]
+        );
+
+        drop(tx);
+        assert!(results.next().await.is_none());
+        Ok(())
+    }
+}
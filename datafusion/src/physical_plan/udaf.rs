@@ -51,7 +51,12 @@ pub struct AggregateUDF {
     pub return_type: ReturnTypeFunction,
     /// actual implementation
     pub accumulator: AccumulatorFunctionImplementation,
-    /// the accumulator's state's description as a function of the return type
+    /// the accumulator's state's description as a function of the return type.
+    ///
+    /// The states are exchanged between the partial and final aggregations as
+    /// Arrow values of these types, e.g. across the stages of a distributed query,
+    /// so an accumulator whose state has no Arrow representation should encode
+    /// it into a single `DataType::Binary` state.
     pub state_type: StateTypeFunction,
 }
 
@@ -143,6 +148,18 @@ pub struct AggregateFunctionExpr {
     name: String,
 }
 
+impl AggregateFunctionExpr {
+    /// The UDAF this expression calls
+    pub fn fun(&self) -> &AggregateUDF {
+        &self.fun
+    }
+
+    /// The return type of the UDAF
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+}
+
 impl AggregateExpr for AggregateFunctionExpr {
     /// Return a reference to Any that can be used for downcasting
     fn as_any(&self) -> &dyn Any {
@@ -0,0 +1,74 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! This module contains functions and structs supporting user-defined table functions.
+
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+use crate::datasource::TableProvider;
+use crate::error::Result;
+use crate::scalar::ScalarValue;
+
+/// Implementation of a table function, creating the table the function returns
+/// from the values of its arguments
+pub type TableFunctionImplementation =
+    Arc<dyn Fn(&[ScalarValue]) -> Result<Arc<dyn TableProvider>> + Send + Sync>;
+
+/// Logical representation of a user-defined table function, such as
+/// `my_range` in `SELECT * FROM my_range(1, 1000)`.
+///
+/// The arguments of a table function must be constants. The table it returns is
+/// scanned like any other table, so the function is planned as a table scan of
+/// the provider it creates.
+#[derive(Clone)]
+pub struct TableUDF {
+    /// name
+    pub name: String,
+    /// actual implementation
+    pub fun: TableFunctionImplementation,
+}
+
+impl Debug for TableUDF {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TableUDF")
+            .field("name", &self.name)
+            .field("fun", &"<FUNC>")
+            .finish()
+    }
+}
+
+impl PartialEq for TableUDF {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl TableUDF {
+    /// Create a new TableUDF
+    pub fn new(name: &str, fun: &TableFunctionImplementation) -> Self {
+        Self {
+            name: name.to_owned(),
+            fun: fun.clone(),
+        }
+    }
+
+    /// Creates the table this function returns for the arguments `args`
+    pub fn call(&self, args: &[ScalarValue]) -> Result<Arc<dyn TableProvider>> {
+        (self.fun)(args)
+    }
+}
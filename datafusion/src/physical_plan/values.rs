@@ -20,8 +20,11 @@
 use super::{common, SendableRecordBatchStream, Statistics};
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{
-    memory::MemoryStream, ColumnarValue, DisplayFormatType, Distribution, ExecutionPlan,
-    Partitioning, PhysicalExpr,
+    memory::MemoryStream,
+    metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
+    stream::ObservedStream,
+    ColumnarValue, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+    PhysicalExpr,
 };
 use crate::scalar::ScalarValue;
 use arrow::array::new_null_array;
@@ -38,6 +41,8 @@ pub struct ValuesExec {
     schema: SchemaRef,
     /// The data
     data: Vec<RecordBatch>,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl ValuesExec {
@@ -85,7 +90,11 @@ impl ValuesExec {
             .collect::<Result<Vec<_>>>()?;
         let batch = RecordBatch::try_new(schema.clone(), arr)?;
         let data: Vec<RecordBatch> = vec![batch];
-        Ok(Self { schema, data })
+        Ok(Self {
+            schema,
+            data,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
     }
 
     /// provides the data
@@ -126,6 +135,7 @@ impl ExecutionPlan for ValuesExec {
             0 => Ok(Arc::new(ValuesExec {
                 schema: self.schema.clone(),
                 data: self.data.clone(),
+                metrics: ExecutionPlanMetricsSet::new(),
             })),
             _ => Err(DataFusionError::Internal(
                 "ValuesExec wrong number of children".to_string(),
@@ -142,11 +152,19 @@ impl ExecutionPlan for ValuesExec {
             )));
         }
 
-        Ok(Box::pin(MemoryStream::try_new(
+        let stream = Box::pin(MemoryStream::try_new(
             self.data(),
             self.schema.clone(),
             None,
-        )?))
+        )?);
+        Ok(Box::pin(ObservedStream::new_with_compute(
+            stream,
+            BaselineMetrics::new(&self.metrics, partition),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
     }
 
     fn fmt_as(
@@ -26,6 +26,7 @@ use crate::physical_plan::{
     aggregates, aggregates::AggregateFunction, functions::Signature,
     type_coercion::data_types, windows::find_ranges_in_range, PhysicalExpr,
 };
+use crate::scalar::ScalarValue;
 use arrow::array::ArrayRef;
 use arrow::datatypes::DataType;
 use arrow::datatypes::Field;
@@ -215,13 +216,23 @@ pub(super) fn signature_for_built_in(fun: &BuiltInWindowFunction) -> Signature {
             ],
             Volatility::Immutable,
         ),
+        // the optional last argument of first_value, last_value and nth_value is a
+        // boolean literal that specifies whether null values are ignored
         BuiltInWindowFunction::FirstValue | BuiltInWindowFunction::LastValue => {
-            Signature::any(1, Volatility::Immutable)
+            Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(2)],
+                Volatility::Immutable,
+            )
         }
-        BuiltInWindowFunction::Ntile => {
-            Signature::exact(vec![DataType::UInt64], Volatility::Immutable)
-        }
-        BuiltInWindowFunction::NthValue => Signature::any(2, Volatility::Immutable),
+        BuiltInWindowFunction::Ntile => Signature::uniform(
+            1,
+            vec![DataType::UInt64, DataType::Int64],
+            Volatility::Immutable,
+        ),
+        BuiltInWindowFunction::NthValue => Signature::one_of(
+            vec![TypeSignature::Any(2), TypeSignature::Any(3)],
+            Volatility::Immutable,
+        ),
     }
 }
 
@@ -256,6 +267,20 @@ pub(crate) trait PartitionEvaluator {
             .collect()
     }
 
+    /// Whether the evaluator computes the value of each row from the rows of its window
+    /// frame, see [`evaluate_frame`](Self::evaluate_frame)
+    fn uses_window_frame(&self) -> bool {
+        false
+    }
+
+    /// evaluate the partition evaluator against the rows of the window frame of a row,
+    /// given as ascending and disjoint ranges of row indices
+    fn evaluate_frame(&self, _frame: &[Range<usize>]) -> Result<ScalarValue> {
+        Err(DataFusionError::NotImplemented(
+            "evaluate_frame is not implemented by default".into(),
+        ))
+    }
+
     /// evaluate the partition evaluator against the partition
     fn evaluate_partition(&self, _partition: Range<usize>) -> Result<ArrayRef>;
 
@@ -442,6 +467,32 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_nth_value_ignore_nulls_return_type() -> Result<()> {
+        let fun = WindowFunction::from_str("nth_value")?;
+        let observed =
+            return_type(&fun, &[DataType::Utf8, DataType::UInt64, DataType::Boolean])?;
+        assert_eq!(DataType::Utf8, observed);
+
+        let fun = WindowFunction::from_str("first_value")?;
+        let observed = return_type(&fun, &[DataType::Float64, DataType::Boolean])?;
+        assert_eq!(DataType::Float64, observed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ntile_return_type() -> Result<()> {
+        let fun = WindowFunction::from_str("ntile")?;
+        let observed = return_type(&fun, &[DataType::Int64])?;
+        assert_eq!(DataType::UInt32, observed);
+
+        let observed = return_type(&fun, &[DataType::UInt64])?;
+        assert_eq!(DataType::UInt32, observed);
+
+        Ok(())
+    }
+
     #[test]
     fn test_percent_rank_return_type() -> Result<()> {
         let fun = WindowFunction::from_str("percent_rank")?;
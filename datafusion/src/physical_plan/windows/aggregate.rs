@@ -18,14 +18,21 @@
 //! Physical exec for aggregate window function expressions.
 
 use crate::error::{DataFusionError, Result};
-use crate::logical_plan::window_frames::{WindowFrame, WindowFrameUnits};
+use crate::logical_plan::window_frames::{
+    WindowFrame, WindowFrameBound, WindowFrameExclusion,
+};
 use crate::physical_plan::windows::find_ranges_in_range;
+use crate::physical_plan::windows::frame::{
+    exclude, needs_range_keys, range_keys, RowFrames,
+};
 use crate::physical_plan::{
     expressions::PhysicalSortExpr, Accumulator, AggregateExpr, PhysicalExpr, WindowExpr,
 };
+use crate::scalar::ScalarValue;
+use arrow::array::{new_empty_array, ArrayRef};
 use arrow::compute::concat;
+use arrow::datatypes::Field;
 use arrow::record_batch::RecordBatch;
-use arrow::{array::ArrayRef, datatypes::Field};
 use std::any::Any;
 use std::iter::IntoIterator;
 use std::ops::Range;
@@ -56,10 +63,14 @@ impl AggregateWindowExpr {
         }
     }
 
-    /// the aggregate window function operates based on window frame, and by default the mode is
-    /// "range".
-    fn evaluation_mode(&self) -> WindowFrameUnits {
-        self.window_frame.unwrap_or_default().units
+    /// The aggregate expression that is evaluated over the window frames
+    pub fn aggregate(&self) -> &Arc<dyn AggregateExpr> {
+        &self.aggregate
+    }
+
+    /// The window frame of the window function, if any
+    pub fn window_frame(&self) -> Option<WindowFrame> {
+        self.window_frame
     }
 
     /// create a new accumulator based on the underlying aggregation function
@@ -97,18 +108,63 @@ impl AggregateWindowExpr {
         concat(&results).map_err(DataFusionError::ArrowError)
     }
 
-    fn group_based_evaluate(&self, _batch: &RecordBatch) -> Result<ArrayRef> {
-        Err(DataFusionError::NotImplemented(format!(
-            "Group based evaluation for {} is not yet implemented",
-            self.name()
-        )))
-    }
+    /// frame based evaluation: for every row the frame of the row is computed from the
+    /// window frame definition and the aggregate is evaluated over the rows of the frame.
+    fn frame_based_evaluate(
+        &self,
+        batch: &RecordBatch,
+        window_frame: &WindowFrame,
+    ) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows();
+        if num_rows == 0 {
+            return Ok(new_empty_array(self.field()?.data_type()));
+        }
+        let partition_points =
+            self.evaluate_partition_points(num_rows, &self.partition_columns(batch)?)?;
+        let sort_partition_points =
+            self.evaluate_partition_points(num_rows, &self.sort_columns(batch)?)?;
+        let values = self.evaluate_args(batch)?;
+        let range_keys = if needs_range_keys(window_frame) {
+            Some(range_keys(self.order_by(), batch)?)
+        } else {
+            None
+        };
+        // frames can be computed incrementally if they all start at the partition start
+        let cumulative = window_frame.start_bound == WindowFrameBound::Preceding(None)
+            && window_frame.exclusion == WindowFrameExclusion::NoOthers;
 
-    fn row_based_evaluate(&self, _batch: &RecordBatch) -> Result<ArrayRef> {
-        Err(DataFusionError::NotImplemented(format!(
-            "Row based evaluation for {} is not yet implemented",
-            self.name()
-        )))
+        let mut results = Vec::with_capacity(num_rows);
+        for partition_range in &partition_points {
+            let peers = find_ranges_in_range(partition_range, &sort_partition_points);
+            let frames = RowFrames {
+                window_frame,
+                partition: partition_range.clone(),
+                peers,
+                range_keys: range_keys.as_ref(),
+            };
+            let mut accumulator = self.create_accumulator()?;
+            let mut accumulated_end = partition_range.start;
+            for (group, peer_range) in peers.iter().enumerate() {
+                for row in peer_range.clone() {
+                    let frame = frames.frame(row, group)?;
+                    if cumulative {
+                        if frame.end > accumulated_end {
+                            accumulator.update(&values, &(accumulated_end..frame.end))?;
+                            accumulated_end = frame.end;
+                        }
+                    } else {
+                        accumulator = self.create_accumulator()?;
+                        for range in
+                            exclude(frame, row, peer_range, window_frame.exclusion)
+                        {
+                            accumulator.update(&values, &range)?;
+                        }
+                    }
+                    results.push(accumulator.evaluate()?);
+                }
+            }
+        }
+        ScalarValue::iter_to_array(results)
     }
 }
 
@@ -140,10 +196,13 @@ impl WindowExpr for AggregateWindowExpr {
 
     /// evaluate the window function values against the batch
     fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
-        match self.evaluation_mode() {
-            WindowFrameUnits::Range => self.peer_based_evaluate(batch),
-            WindowFrameUnits::Rows => self.row_based_evaluate(batch),
-            WindowFrameUnits::Groups => self.group_based_evaluate(batch),
+        match self.window_frame {
+            // the default frame is all rows up to the last peer of the current row
+            None => self.peer_based_evaluate(batch),
+            Some(window_frame) if window_frame == WindowFrame::default() => {
+                self.peer_based_evaluate(batch)
+            }
+            Some(window_frame) => self.frame_based_evaluate(batch, &window_frame),
         }
     }
 }
@@ -177,4 +236,21 @@ impl AggregateWindowAccumulator {
         let value = self.accumulator.evaluate()?;
         Ok(value.to_array_of_size(len))
     }
+
+    /// add the values in `value_range` to the accumulator
+    fn update(&mut self, values: &[ArrayRef], value_range: &Range<usize>) -> Result<()> {
+        if value_range.is_empty() {
+            return Ok(());
+        }
+        let len = value_range.end - value_range.start;
+        let values = values
+            .iter()
+            .map(|v| v.slice(value_range.start, len))
+            .collect::<Vec<_>>();
+        self.accumulator.update_batch(&values)
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        self.accumulator.evaluate()
+    }
 }
@@ -18,10 +18,20 @@
 //! Physical exec for built-in window function expressions.
 
 use crate::error::{DataFusionError, Result};
+use crate::logical_plan::window_frames::WindowFrame;
+use crate::physical_plan::windows::find_ranges_in_range;
+use crate::physical_plan::windows::frame::{
+    exclude, needs_range_keys, range_keys, RowFrames,
+};
 use crate::physical_plan::{
-    expressions::PhysicalSortExpr, window_functions::BuiltInWindowFunctionExpr,
+    expressions::PhysicalSortExpr,
+    window_functions::{
+        BuiltInWindowFunction, BuiltInWindowFunctionExpr, PartitionEvaluator,
+    },
     PhysicalExpr, WindowExpr,
 };
+use crate::scalar::ScalarValue;
+use arrow::array::new_empty_array;
 use arrow::compute::concat;
 use arrow::record_batch::RecordBatch;
 use arrow::{array::ArrayRef, datatypes::Field};
@@ -31,23 +41,90 @@ use std::sync::Arc;
 /// A window expr that takes the form of a built in window function
 #[derive(Debug)]
 pub struct BuiltInWindowExpr {
+    fun: BuiltInWindowFunction,
     expr: Arc<dyn BuiltInWindowFunctionExpr>,
+    args: Vec<Arc<dyn PhysicalExpr>>,
     partition_by: Vec<Arc<dyn PhysicalExpr>>,
     order_by: Vec<PhysicalSortExpr>,
+    window_frame: Option<WindowFrame>,
 }
 
 impl BuiltInWindowExpr {
     /// create a new built-in window function expression
     pub(super) fn new(
+        fun: BuiltInWindowFunction,
         expr: Arc<dyn BuiltInWindowFunctionExpr>,
+        args: &[Arc<dyn PhysicalExpr>],
         partition_by: &[Arc<dyn PhysicalExpr>],
         order_by: &[PhysicalSortExpr],
+        window_frame: Option<WindowFrame>,
     ) -> Self {
         Self {
+            fun,
             expr,
+            args: args.to_vec(),
             partition_by: partition_by.to_vec(),
             order_by: order_by.to_vec(),
+            window_frame,
+        }
+    }
+
+    /// The built-in window function
+    pub fn fun(&self) -> &BuiltInWindowFunction {
+        &self.fun
+    }
+
+    /// All arguments of the window function, including the literal ones such as the
+    /// offset of `lag`, which are not part of [`WindowExpr::expressions`]
+    pub fn args(&self) -> &[Arc<dyn PhysicalExpr>] {
+        &self.args
+    }
+
+    /// The window frame of the window function, if any
+    pub fn window_frame(&self) -> Option<WindowFrame> {
+        self.window_frame
+    }
+
+    /// frame based evaluation for the functions that are computed from the window frame
+    /// of each row, such as first_value, last_value, and nth_value
+    fn frame_based_evaluate(
+        &self,
+        batch: &RecordBatch,
+        evaluator: &dyn PartitionEvaluator,
+    ) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows();
+        if num_rows == 0 {
+            return Ok(new_empty_array(self.field()?.data_type()));
         }
+        let window_frame = self.window_frame.unwrap_or_default();
+        let partition_points =
+            self.evaluate_partition_points(num_rows, &self.partition_columns(batch)?)?;
+        let sort_partition_points =
+            self.evaluate_partition_points(num_rows, &self.sort_columns(batch)?)?;
+        let range_keys = if needs_range_keys(&window_frame) {
+            Some(range_keys(self.order_by(), batch)?)
+        } else {
+            None
+        };
+
+        let mut results = Vec::with_capacity(num_rows);
+        for partition_range in &partition_points {
+            let peers = find_ranges_in_range(partition_range, &sort_partition_points);
+            let frames = RowFrames {
+                window_frame: &window_frame,
+                partition: partition_range.clone(),
+                peers,
+                range_keys: range_keys.as_ref(),
+            };
+            for (group, peer_range) in peers.iter().enumerate() {
+                for row in peer_range.clone() {
+                    let frame = frames.frame(row, group)?;
+                    let frame = exclude(frame, row, peer_range, window_frame.exclusion);
+                    results.push(evaluator.evaluate_frame(&frame)?);
+                }
+            }
+        }
+        ScalarValue::iter_to_array(results)
     }
 }
 
@@ -79,6 +156,9 @@ impl WindowExpr for BuiltInWindowExpr {
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
         let evaluator = self.expr.create_evaluator(batch)?;
+        if evaluator.uses_window_frame() {
+            return self.frame_based_evaluate(batch, evaluator.as_ref());
+        }
         let num_rows = batch.num_rows();
         let partition_points =
             self.evaluate_partition_points(num_rows, &self.partition_columns(batch)?)?;
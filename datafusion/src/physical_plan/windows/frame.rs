@@ -0,0 +1,276 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Computation of the window frame of each row for window functions that are
+//! evaluated over explicit window frames.
+
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::window_frames::{
+    check_range_offset_type, WindowFrame, WindowFrameBound, WindowFrameExclusion,
+    WindowFrameUnits,
+};
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use arrow::array::{Array, Float64Array, Int64Array, PrimitiveArray, UInt64Array};
+use arrow::compute::cast;
+use arrow::datatypes::{ArrowPrimitiveType, DataType};
+use arrow::record_batch::RecordBatch;
+use std::ops::{Add, Neg, Range};
+
+/// whether the boundaries of the frames of `window_frame` depend on the values of the
+/// ORDER BY expression, see [`range_keys`]
+pub(crate) fn needs_range_keys(window_frame: &WindowFrame) -> bool {
+    window_frame.units == WindowFrameUnits::Range && window_frame.has_offset()
+}
+
+/// the values of the single ORDER BY expression used to find the boundaries of RANGE
+/// frames with offsets, in ascending order
+pub(crate) fn range_keys(
+    order_by: &[PhysicalSortExpr],
+    batch: &RecordBatch,
+) -> Result<RangeKeys> {
+    let order_by = match order_by {
+        [order_by] => order_by,
+        order_by => {
+            return Err(DataFusionError::Plan(format!(
+                "With window frame of type RANGE, the order by expression must be of length 1, got {}",
+                order_by.len()
+            )))
+        }
+    };
+    let values = order_by.expr.evaluate(batch)?.into_array(batch.num_rows());
+    check_range_offset_type(values.data_type())?;
+    let descending = order_by.options.descending;
+    // the keys are compared in their own type, as the integers above 2^53 have no
+    // exact f64 representation
+    Ok(match values.data_type() {
+        DataType::UInt64 => {
+            let values = values.as_any().downcast_ref::<UInt64Array>().unwrap();
+            RangeKeys::Integers(ascending_keys(values, descending, |v| v as i128))
+        }
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            let values = cast(&values, &DataType::Float64)?;
+            let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+            RangeKeys::Floats(ascending_keys(values, descending, |v| v))
+        }
+        _ => {
+            let values = cast(&values, &DataType::Int64)?;
+            let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+            RangeKeys::Integers(ascending_keys(values, descending, |v| v as i128))
+        }
+    })
+}
+
+/// the keys of `values`, negated if `descending`
+fn ascending_keys<T, K>(
+    values: &PrimitiveArray<T>,
+    descending: bool,
+    key: impl Fn(T::Native) -> K,
+) -> Vec<Option<K>>
+where
+    T: ArrowPrimitiveType,
+    K: Neg<Output = K>,
+{
+    values
+        .iter()
+        .map(|value| value.map(|value| if descending { -key(value) } else { key(value) }))
+        .collect()
+}
+
+/// Sort keys of a RANGE frame with offsets, negated for descending order so that they
+/// are always ascending within a peer-free stretch of non-null values
+pub(crate) enum RangeKeys {
+    /// the keys of integer expressions, which with the offsets always fit in an i128
+    Integers(Vec<Option<i128>>),
+    /// the keys of floating point expressions
+    Floats(Vec<Option<f64>>),
+}
+
+/// Computes the frame of each row of a partition
+pub(crate) struct RowFrames<'a> {
+    pub window_frame: &'a WindowFrame,
+    /// the rows of the partition
+    pub partition: Range<usize>,
+    /// the peer groups of the partition
+    pub peers: &'a [Range<usize>],
+    /// the sort keys, for RANGE frames with offsets
+    pub range_keys: Option<&'a RangeKeys>,
+}
+
+impl<'a> RowFrames<'a> {
+    /// returns the frame of `row`, which belongs to the peer group with index `group`
+    pub fn frame(&self, row: usize, group: usize) -> Result<Range<usize>> {
+        let (start, end) = match self.window_frame.units {
+            WindowFrameUnits::Rows => (
+                self.rows_bound(row, &self.window_frame.start_bound, false),
+                self.rows_bound(row, &self.window_frame.end_bound, true),
+            ),
+            WindowFrameUnits::Groups => (
+                self.groups_bound(group, &self.window_frame.start_bound, false),
+                self.groups_bound(group, &self.window_frame.end_bound, true),
+            ),
+            WindowFrameUnits::Range => (
+                self.range_bound(row, group, &self.window_frame.start_bound, false)?,
+                self.range_bound(row, group, &self.window_frame.end_bound, true)?,
+            ),
+        };
+        Ok(start..end.max(start))
+    }
+
+    /// the first row (or the row after the last row if `is_end`) of the frame of `row`
+    /// for ROWS frames
+    fn rows_bound(&self, row: usize, bound: &WindowFrameBound, is_end: bool) -> usize {
+        let Range { start, end } = self.partition;
+        let row = match bound {
+            WindowFrameBound::Preceding(None) => start,
+            WindowFrameBound::Following(None) => end,
+            WindowFrameBound::CurrentRow => row + is_end as usize,
+            WindowFrameBound::Preceding(Some(n)) => {
+                (row + is_end as usize).saturating_sub(*n as usize)
+            }
+            WindowFrameBound::Following(Some(n)) => {
+                row.saturating_add(*n as usize) + is_end as usize
+            }
+        };
+        row.max(start).min(end)
+    }
+
+    /// the first row (or the row after the last row if `is_end`) of the frame of a row
+    /// in peer group `group` for GROUPS frames
+    fn groups_bound(
+        &self,
+        group: usize,
+        bound: &WindowFrameBound,
+        is_end: bool,
+    ) -> usize {
+        let Range { start, end } = self.partition;
+        let group = match bound {
+            WindowFrameBound::Preceding(None) => return start,
+            WindowFrameBound::Following(None) => return end,
+            WindowFrameBound::CurrentRow => Some(group),
+            WindowFrameBound::Preceding(Some(n)) => group.checked_sub(*n as usize),
+            WindowFrameBound::Following(Some(n)) => group.checked_add(*n as usize),
+        };
+        match group {
+            Some(group) if group < self.peers.len() => {
+                if is_end {
+                    self.peers[group].end
+                } else {
+                    self.peers[group].start
+                }
+            }
+            // the bound is after the last group
+            Some(_) => end,
+            // the bound is before the first group
+            None => start,
+        }
+    }
+
+    /// the first row (or the row after the last row if `is_end`) of the frame of `row`
+    /// in peer group `group` for RANGE frames
+    fn range_bound(
+        &self,
+        row: usize,
+        group: usize,
+        bound: &WindowFrameBound,
+        is_end: bool,
+    ) -> Result<usize> {
+        let Range { start, end } = self.partition;
+        let offset = match bound {
+            WindowFrameBound::Preceding(None) => return Ok(start),
+            WindowFrameBound::Following(None) => return Ok(end),
+            WindowFrameBound::CurrentRow => 0,
+            WindowFrameBound::Preceding(Some(n)) => -(*n as i128),
+            WindowFrameBound::Following(Some(n)) => *n as i128,
+        };
+        let position = match self.range_keys {
+            Some(RangeKeys::Integers(keys)) => {
+                range_position(keys, &self.partition, row, offset, is_end)
+            }
+            Some(RangeKeys::Floats(keys)) => {
+                range_position(keys, &self.partition, row, offset as f64, is_end)
+            }
+            None => None,
+        };
+        Ok(position.unwrap_or_else(|| {
+            // the frame of rows with a null key are their peers, i.e. all null rows
+            let peers = &self.peers[group];
+            if is_end {
+                peers.end
+            } else {
+                peers.start
+            }
+        }))
+    }
+}
+
+/// the first row (or the row after the last row if `is_end`) of the partition whose
+/// key is at `offset` from the key of `row`, or `None` if the key of `row` is null
+fn range_position<K>(
+    keys: &[Option<K>],
+    partition: &Range<usize>,
+    row: usize,
+    offset: K,
+    is_end: bool,
+) -> Option<usize>
+where
+    K: Copy + PartialOrd + Add<Output = K>,
+{
+    let key = keys[row]? + offset;
+    let Range { start, end } = *partition;
+    // nulls are either all before or all after the non null keys of the partition
+    let non_null_start = start + keys[start..end].partition_point(|k| k.is_none());
+    let non_null_end = start + keys[start..end].partition_point(|k| k.is_some());
+    let (non_null_start, non_null_end) = if non_null_start > start {
+        (non_null_start, end)
+    } else {
+        (start, non_null_end)
+    };
+    let non_null = &keys[non_null_start..non_null_end];
+    let position = if is_end {
+        non_null.partition_point(|k| k.unwrap() <= key)
+    } else {
+        non_null.partition_point(|k| k.unwrap() < key)
+    };
+    Some(non_null_start + position)
+}
+
+/// removes the rows of `exclusion` from the frame of `row` in peer group `peers`
+pub(crate) fn exclude(
+    frame: Range<usize>,
+    row: usize,
+    peers: &Range<usize>,
+    exclusion: WindowFrameExclusion,
+) -> Vec<Range<usize>> {
+    let excluded = match exclusion {
+        WindowFrameExclusion::NoOthers => return vec![frame],
+        WindowFrameExclusion::CurrentRow => vec![row..row + 1],
+        WindowFrameExclusion::Group => vec![peers.clone()],
+        WindowFrameExclusion::Ties => vec![peers.start..row, row + 1..peers.end],
+    };
+    let mut ranges = vec![];
+    let mut start = frame.start;
+    for range in excluded {
+        if range.start > start {
+            ranges.push(start..range.start.min(frame.end));
+        }
+        start = start.max(range.end);
+    }
+    if start < frame.end {
+        ranges.push(start..frame.end);
+    }
+    ranges.into_iter().filter(|r| !r.is_empty()).collect()
+}
@@ -22,7 +22,7 @@ use crate::logical_plan::window_frames::WindowFrame;
 use crate::physical_plan::{
     aggregates,
     expressions::{
-        cume_dist, dense_rank, lag, lead, percent_rank, rank, Literal, NthValue,
+        cume_dist, dense_rank, lag, lead, percent_rank, rank, Literal, NthValue, Ntile,
         PhysicalSortExpr, RowNumber,
     },
     type_coercion::coerce,
@@ -34,12 +34,13 @@ use crate::physical_plan::{
 };
 use crate::scalar::ScalarValue;
 use arrow::datatypes::Schema;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::ops::Range;
 use std::sync::Arc;
 
 mod aggregate;
 mod built_in;
+mod frame;
 mod window_agg_exec;
 
 pub use aggregate::AggregateWindowExpr;
@@ -64,9 +65,12 @@ pub fn create_window_expr(
             window_frame,
         )),
         WindowFunction::BuiltInWindowFunction(fun) => Arc::new(BuiltInWindowExpr::new(
+            fun.clone(),
             create_built_in_window_expr(fun, args, input_schema, name)?,
+            args,
             partition_by,
             order_by,
+            window_frame,
         )),
     })
 }
@@ -74,14 +78,53 @@ pub fn create_window_expr(
 fn get_scalar_value_from_args(
     args: &[Arc<dyn PhysicalExpr>],
     index: usize,
-) -> Option<ScalarValue> {
-    args.get(index).map(|v| {
-        v.as_any()
-            .downcast_ref::<Literal>()
-            .unwrap()
-            .value()
-            .clone()
-    })
+) -> Result<Option<ScalarValue>> {
+    args.get(index)
+        .map(|v| {
+            v.as_any()
+                .downcast_ref::<Literal>()
+                .map(|literal| literal.value().clone())
+                .ok_or_else(|| {
+                    DataFusionError::Plan(format!(
+                        "Argument {} of the window function must be a literal, got {:?}",
+                        index + 1,
+                        v
+                    ))
+                })
+        })
+        .transpose()
+}
+
+/// the value of the literal argument at `index`, which must be a positive integer
+fn get_positive_integer_from_args(
+    args: &[Arc<dyn PhysicalExpr>],
+    index: usize,
+    name: &str,
+) -> Result<u64> {
+    match get_scalar_value_from_args(args, index)? {
+        Some(ScalarValue::Int64(Some(n))) if n > 0 => Ok(n as u64),
+        Some(ScalarValue::UInt64(Some(n))) if n > 0 => Ok(n),
+        value => Err(DataFusionError::Execution(format!(
+            "{} expects a positive integer argument, got {:?}",
+            name, value
+        ))),
+    }
+}
+
+/// whether the optional `IGNORE NULLS` argument at `index` of first_value, last_value
+/// or nth_value is true
+fn get_ignore_nulls_from_args(
+    args: &[Arc<dyn PhysicalExpr>],
+    index: usize,
+) -> Result<bool> {
+    match get_scalar_value_from_args(args, index)? {
+        None => Ok(false),
+        Some(ScalarValue::Boolean(ignore_nulls)) => Ok(ignore_nulls.unwrap_or(false)),
+        Some(value) => Err(DataFusionError::Plan(format!(
+            "The IGNORE NULLS argument of the window function must be a boolean, got {:?}",
+            value
+        ))),
+    }
 }
 
 fn create_built_in_window_expr(
@@ -96,59 +139,58 @@ fn create_built_in_window_expr(
         BuiltInWindowFunction::DenseRank => Arc::new(dense_rank(name)),
         BuiltInWindowFunction::PercentRank => Arc::new(percent_rank(name)),
         BuiltInWindowFunction::CumeDist => Arc::new(cume_dist(name)),
+        BuiltInWindowFunction::Ntile => {
+            let coerced_args = coerce(args, input_schema, &signature_for_built_in(fun))?;
+            let n = get_positive_integer_from_args(&coerced_args, 0, "ntile")?;
+            Arc::new(Ntile::new(name, n))
+        }
         BuiltInWindowFunction::Lag => {
             let coerced_args = coerce(args, input_schema, &signature_for_built_in(fun))?;
             let arg = coerced_args[0].clone();
             let data_type = args[0].data_type(input_schema)?;
-            let shift_offset = get_scalar_value_from_args(&coerced_args, 1)
+            let shift_offset = get_scalar_value_from_args(&coerced_args, 1)?
                 .map(|v| v.try_into())
                 .and_then(|v| v.ok());
-            let default_value = get_scalar_value_from_args(&coerced_args, 2);
+            let default_value = get_scalar_value_from_args(&coerced_args, 2)?;
             Arc::new(lag(name, data_type, arg, shift_offset, default_value))
         }
         BuiltInWindowFunction::Lead => {
             let coerced_args = coerce(args, input_schema, &signature_for_built_in(fun))?;
             let arg = coerced_args[0].clone();
             let data_type = args[0].data_type(input_schema)?;
-            let shift_offset = get_scalar_value_from_args(&coerced_args, 1)
+            let shift_offset = get_scalar_value_from_args(&coerced_args, 1)?
                 .map(|v| v.try_into())
                 .and_then(|v| v.ok());
-            let default_value = get_scalar_value_from_args(&coerced_args, 2);
+            let default_value = get_scalar_value_from_args(&coerced_args, 2)?;
             Arc::new(lead(name, data_type, arg, shift_offset, default_value))
         }
         BuiltInWindowFunction::NthValue => {
             let coerced_args = coerce(args, input_schema, &signature_for_built_in(fun))?;
             let arg = coerced_args[0].clone();
-            let n = coerced_args[1]
-                .as_any()
-                .downcast_ref::<Literal>()
-                .unwrap()
-                .value();
-            let n: i64 = n
-                .clone()
-                .try_into()
-                .map_err(|e| DataFusionError::Execution(format!("{:?}", e)))?;
-            let n: u32 = n as u32;
+            let n = get_positive_integer_from_args(&coerced_args, 1, "nth_value")?;
+            let n = u32::try_from(n)
+                .map_err(|e| DataFusionError::Execution(format!("nth_value: {:?}", e)))?;
+            let ignore_nulls = get_ignore_nulls_from_args(&coerced_args, 2)?;
             let data_type = args[0].data_type(input_schema)?;
-            Arc::new(NthValue::nth(name, arg, data_type, n)?)
+            Arc::new(
+                NthValue::nth(name, arg, data_type, n)?.with_ignore_nulls(ignore_nulls),
+            )
         }
         BuiltInWindowFunction::FirstValue => {
-            let arg =
-                coerce(args, input_schema, &signature_for_built_in(fun))?[0].clone();
+            let coerced_args = coerce(args, input_schema, &signature_for_built_in(fun))?;
+            let arg = coerced_args[0].clone();
+            let ignore_nulls = get_ignore_nulls_from_args(&coerced_args, 1)?;
             let data_type = args[0].data_type(input_schema)?;
-            Arc::new(NthValue::first(name, arg, data_type))
+            Arc::new(
+                NthValue::first(name, arg, data_type).with_ignore_nulls(ignore_nulls),
+            )
         }
         BuiltInWindowFunction::LastValue => {
-            let arg =
-                coerce(args, input_schema, &signature_for_built_in(fun))?[0].clone();
+            let coerced_args = coerce(args, input_schema, &signature_for_built_in(fun))?;
+            let arg = coerced_args[0].clone();
+            let ignore_nulls = get_ignore_nulls_from_args(&coerced_args, 1)?;
             let data_type = args[0].data_type(input_schema)?;
-            Arc::new(NthValue::last(name, arg, data_type))
-        }
-        _ => {
-            return Err(DataFusionError::NotImplemented(format!(
-                "Window function with {:?} not yet implemented",
-                fun
-            )))
+            Arc::new(NthValue::last(name, arg, data_type).with_ignore_nulls(ignore_nulls))
         }
     })
 }
@@ -177,12 +219,13 @@ mod tests {
     use crate::physical_plan::aggregates::AggregateFunction;
     use crate::physical_plan::expressions::col;
     use crate::physical_plan::file_format::{CsvExec, PhysicalPlanConfig};
+    use crate::physical_plan::memory::MemoryExec;
     use crate::physical_plan::{collect, Statistics};
     use crate::test::exec::{assert_strong_count_converges_to_zero, BlockingExec};
     use crate::test::{self, assert_is_pending};
     use crate::test_util::{self, aggr_test_schema};
     use arrow::array::*;
-    use arrow::datatypes::{DataType, Field, SchemaRef};
+    use arrow::datatypes::{DataType, Field, SchemaRef, UInt64Type};
     use arrow::record_batch::RecordBatch;
     use futures::FutureExt;
 
@@ -197,9 +240,11 @@ mod tests {
                 file_groups: files,
                 statistics: Statistics::default(),
                 projection: None,
+                nested_projection: vec![],
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![],
+                bucket_columns: None,
             },
             true,
             b',',
@@ -269,6 +314,65 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn window_function_sorted_input() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("k", DataType::Int32, false),
+            Field::new("v", DataType::Int32, true),
+        ]));
+        let batch = |keys: Vec<i32>| {
+            let values = Int32Array::from(keys.clone());
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from(keys)), Arc::new(values)],
+            )
+        };
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![
+                batch(vec![1, 1, 2])?,
+                batch(vec![2, 2, 3])?,
+                batch(vec![3])?,
+                batch(vec![4, 4])?,
+            ]],
+            schema.clone(),
+            None,
+        )?);
+
+        let window_exec = Arc::new(
+            WindowAggExec::try_new(
+                vec![create_window_expr(
+                    &WindowFunction::AggregateFunction(AggregateFunction::Count),
+                    "count".to_owned(),
+                    &[col("v", &schema)?],
+                    &[col("k", &schema)?],
+                    &[],
+                    None,
+                    schema.as_ref(),
+                )?],
+                input,
+                schema.clone(),
+            )?
+            .with_sorted_input(true),
+        );
+
+        // every group of complete partitions is emitted as soon as it is known
+        let result: Vec<RecordBatch> = collect(window_exec).await?;
+        let counts = result
+            .iter()
+            .map(|batch| {
+                as_primitive_array::<UInt64Type>(batch.column(0))
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![vec![2, 2], vec![3, 3, 3], vec![2, 2], vec![2, 2]]
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_drop_cancel() -> Result<()> {
         let schema =
@@ -23,18 +23,22 @@ use crate::physical_plan::metrics::{
     BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet,
 };
 use crate::physical_plan::{
-    common, ColumnStatistics, DisplayFormatType, Distribution, ExecutionPlan,
-    Partitioning, RecordBatchStream, SendableRecordBatchStream, Statistics, WindowExpr,
+    cancellation, common, ColumnStatistics, DisplayFormatType, Distribution,
+    ExecutionPlan, Partitioning, PhysicalExpr, RecordBatchStream,
+    SendableRecordBatchStream, Statistics, WindowExpr,
 };
+use crate::scalar::ScalarValue;
 use arrow::{
     array::ArrayRef,
+    compute::kernels::partition::lexicographical_partition_ranges,
+    compute::SortColumn,
     datatypes::{Schema, SchemaRef},
     error::{ArrowError, Result as ArrowResult},
     record_batch::RecordBatch,
 };
 use async_trait::async_trait;
 use futures::stream::Stream;
-use futures::FutureExt;
+use futures::{ready, FutureExt, StreamExt};
 use pin_project_lite::pin_project;
 use std::any::Any;
 use std::pin::Pin;
@@ -52,6 +56,8 @@ pub struct WindowAggExec {
     schema: SchemaRef,
     /// Schema before the window
     input_schema: SchemaRef,
+    /// Whether the input is sorted on the partition by keys of the window expressions
+    sorted_input: bool,
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
 }
@@ -70,10 +76,55 @@ impl WindowAggExec {
             window_expr,
             schema,
             input_schema,
+            sorted_input: false,
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
 
+    /// Declare whether the input is sorted on the partition by keys of the window
+    /// expressions, in which case the partitions are evaluated one group at a time
+    /// as they arrive instead of buffering the whole input
+    pub fn with_sorted_input(mut self, sorted_input: bool) -> Self {
+        self.sorted_input = sorted_input;
+        self
+    }
+
+    /// Whether the input is sorted on the partition by keys of the window expressions
+    pub fn sorted_input(&self) -> bool {
+        self.sorted_input
+    }
+
+    /// The partition by keys that are shared by all window expressions, and on which
+    /// the input can be split into independent groups of partitions. `None` if the
+    /// input is not sorted or some window expression has no partition by clause.
+    fn streaming_partition_keys(&self) -> Option<Vec<Arc<dyn PhysicalExpr>>> {
+        if !self.sorted_input {
+            return None;
+        }
+        // the input is sorted on the partition by keys of every window expression, so
+        // the smallest set of keys is a prefix of the sort order
+        let keys = self
+            .window_expr
+            .iter()
+            .map(|expr| expr.partition_by())
+            .min_by_key(|partition_by| partition_by.len())?;
+        if keys.is_empty() {
+            return None;
+        }
+        let shared = self.window_expr.iter().all(|expr| {
+            keys.iter().all(|key| {
+                expr.partition_by()
+                    .iter()
+                    .any(|e| e.to_string() == key.to_string())
+            })
+        });
+        if shared {
+            Some(keys.to_vec())
+        } else {
+            None
+        }
+    }
+
     /// Window expressions
     pub fn window_expr(&self) -> &[Arc<dyn WindowExpr>] {
         &self.window_expr
@@ -130,11 +181,14 @@ impl ExecutionPlan for WindowAggExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(WindowAggExec::try_new(
-                self.window_expr.clone(),
-                children[0].clone(),
-                self.input_schema.clone(),
-            )?)),
+            1 => Ok(Arc::new(
+                WindowAggExec::try_new(
+                    self.window_expr.clone(),
+                    children[0].clone(),
+                    self.input_schema.clone(),
+                )?
+                .with_sorted_input(self.sorted_input),
+            )),
             _ => Err(DataFusionError::Internal(
                 "WindowAggExec wrong number of children".to_owned(),
             )),
@@ -143,6 +197,15 @@ impl ExecutionPlan for WindowAggExec {
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
         let input = self.input.execute(partition).await?;
+        if let Some(partition_keys) = self.streaming_partition_keys() {
+            return Ok(Box::pin(SortedWindowAggStream::new(
+                self.schema.clone(),
+                self.window_expr.clone(),
+                partition_keys,
+                input,
+                BaselineMetrics::new(&self.metrics, partition),
+            )));
+        }
         let stream = Box::pin(WindowAggStream::new(
             self.schema.clone(),
             self.window_expr.clone(),
@@ -210,7 +273,7 @@ fn create_schema(
 
 /// Compute the window aggregate columns
 fn compute_window_aggregates(
-    window_expr: Vec<Arc<dyn WindowExpr>>,
+    window_expr: &[Arc<dyn WindowExpr>],
     batch: &RecordBatch,
 ) -> Result<Vec<ArrayRef>> {
     window_expr
@@ -242,7 +305,7 @@ impl WindowAggStream {
         let (tx, rx) = futures::channel::oneshot::channel();
         let schema_clone = schema.clone();
         let elapsed_compute = baseline_metrics.elapsed_compute().clone();
-        let join_handle = tokio::spawn(async move {
+        let join_handle = cancellation::spawn(async move {
             let schema = schema_clone.clone();
             let result =
                 WindowAggStream::process(input, window_expr, schema, elapsed_compute)
@@ -277,14 +340,7 @@ impl WindowAggStream {
 
         let batch = common::combine_batches(&batches, input_schema.clone())?;
         if let Some(batch) = batch {
-            // calculate window cols
-            let mut columns = compute_window_aggregates(window_expr, &batch)
-                .map_err(DataFusionError::into_arrow_external_error)?;
-            // combine with the original cols
-            // note the setup of window aggregates is that they newly calculated window
-            // expressions are always prepended to the columns
-            columns.extend_from_slice(batch.columns());
-            RecordBatch::try_new(schema, columns)
+            window_aggregate_batch(&schema, &window_expr, &batch)
         } else {
             Ok(RecordBatch::new_empty(schema))
         }
@@ -337,3 +393,171 @@ impl RecordBatchStream for WindowAggStream {
         self.schema.clone()
     }
 }
+
+/// Computes the window expressions of `batch` and prepends them to its columns
+fn window_aggregate_batch(
+    schema: &SchemaRef,
+    window_expr: &[Arc<dyn WindowExpr>],
+    batch: &RecordBatch,
+) -> ArrowResult<RecordBatch> {
+    // calculate window cols
+    let mut columns = compute_window_aggregates(window_expr, batch)
+        .map_err(DataFusionError::into_arrow_external_error)?;
+    // combine with the original cols
+    // note the setup of window aggregates is that they newly calculated window
+    // expressions are always prepended to the columns
+    columns.extend_from_slice(batch.columns());
+    RecordBatch::try_new(schema.clone(), columns)
+}
+
+/// Stream for window aggregation over an input that is sorted on the partition by
+/// keys: the rows are buffered until the partition keys change, and all complete
+/// partitions received so far are evaluated and emitted. Memory use is thus bounded
+/// by the size of the largest partition rather than by the size of the input.
+pub struct SortedWindowAggStream {
+    schema: SchemaRef,
+    window_expr: Vec<Arc<dyn WindowExpr>>,
+    /// the partition by keys that are shared by all window expressions
+    partition_keys: Vec<Arc<dyn PhysicalExpr>>,
+    input: SendableRecordBatchStream,
+    /// the rows of the last, possibly incomplete, partition
+    buffer: Vec<RecordBatch>,
+    /// the partition key values of the rows in `buffer`
+    buffer_key: Vec<ScalarValue>,
+    finished: bool,
+    baseline_metrics: BaselineMetrics,
+}
+
+impl SortedWindowAggStream {
+    /// Create a new SortedWindowAggStream
+    pub fn new(
+        schema: SchemaRef,
+        window_expr: Vec<Arc<dyn WindowExpr>>,
+        partition_keys: Vec<Arc<dyn PhysicalExpr>>,
+        input: SendableRecordBatchStream,
+        baseline_metrics: BaselineMetrics,
+    ) -> Self {
+        Self {
+            schema,
+            window_expr,
+            partition_keys,
+            input,
+            buffer: vec![],
+            buffer_key: vec![],
+            finished: false,
+            baseline_metrics,
+        }
+    }
+
+    /// adds `batch` to the buffered rows, and returns the window aggregates of the
+    /// partitions that are known to be complete
+    fn push(&mut self, batch: RecordBatch) -> Result<Option<RecordBatch>> {
+        if batch.num_rows() == 0 {
+            return Ok(None);
+        }
+        let keys = self
+            .partition_keys
+            .iter()
+            .map(|e| Ok(e.evaluate(&batch)?.into_array(batch.num_rows())))
+            .collect::<Result<Vec<_>>>()?;
+        let sort_columns = keys
+            .iter()
+            .map(|values| SortColumn {
+                values: values.clone(),
+                options: None,
+            })
+            .collect::<Vec<_>>();
+        let ranges = lexicographical_partition_ranges(&sort_columns)?.collect::<Vec<_>>();
+        let last_start = ranges.last().map(|range| range.start).unwrap_or(0);
+        let row_key = |row: usize| {
+            keys.iter()
+                .map(|values| ScalarValue::try_from_array(values, row))
+                .collect::<Result<Vec<_>>>()
+        };
+
+        let continues_buffer = self.buffer.is_empty() || self.buffer_key == row_key(0)?;
+        if ranges.len() == 1 && continues_buffer {
+            // the whole batch belongs to the buffered partition
+            if self.buffer.is_empty() {
+                self.buffer_key = row_key(0)?;
+            }
+            self.buffer.push(batch);
+            return Ok(None);
+        }
+
+        let mut complete = std::mem::take(&mut self.buffer);
+        complete.push(batch.slice(0, last_start));
+        self.buffer = vec![batch.slice(last_start, batch.num_rows() - last_start)];
+        self.buffer_key = row_key(last_start)?;
+        self.evaluate(complete)
+    }
+
+    /// evaluates the window aggregates of the rows of `batches`, which must only hold
+    /// complete partitions
+    fn evaluate(&self, batches: Vec<RecordBatch>) -> Result<Option<RecordBatch>> {
+        let batch = common::combine_batches(&batches, self.input.schema())?;
+        match batch {
+            Some(batch) if batch.num_rows() > 0 => Ok(Some(window_aggregate_batch(
+                &self.schema,
+                &self.window_expr,
+                &batch,
+            )?)),
+            _ => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn poll_next_inner(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<ArrowResult<RecordBatch>>> {
+        let elapsed_compute = self.baseline_metrics.elapsed_compute().clone();
+        loop {
+            if self.finished {
+                return Poll::Ready(None);
+            }
+            let result = match ready!(self.input.poll_next_unpin(cx)) {
+                Some(Ok(batch)) => {
+                    let _timer = elapsed_compute.timer();
+                    self.push(batch)
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => {
+                    let _timer = elapsed_compute.timer();
+                    self.finished = true;
+                    let buffer = std::mem::take(&mut self.buffer);
+                    self.evaluate(buffer)
+                }
+            };
+            match result {
+                Ok(Some(batch)) => return Poll::Ready(Some(Ok(batch))),
+                Ok(None) => continue,
+                Err(e) => {
+                    self.finished = true;
+                    return Poll::Ready(Some(Err(
+                        DataFusionError::into_arrow_external_error(e),
+                    )));
+                }
+            }
+        }
+    }
+}
+
+impl Stream for SortedWindowAggStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let poll = self.poll_next_inner(cx);
+        self.baseline_metrics.record_poll(poll)
+    }
+}
+
+impl RecordBatchStream for SortedWindowAggStream {
+    /// Get the schema
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
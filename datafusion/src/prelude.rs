@@ -28,12 +28,14 @@
 pub use crate::dataframe::DataFrame;
 pub use crate::execution::context::{ExecutionConfig, ExecutionContext};
 pub use crate::execution::options::AvroReadOptions;
-pub use crate::execution::options::{CsvReadOptions, NdJsonReadOptions};
+pub use crate::execution::options::{
+    CsvReadOptions, NdJsonReadOptions, ParquetWriteOptions,
+};
 pub use crate::logical_plan::{
     array, ascii, avg, bit_length, btrim, character_length, chr, col, concat, concat_ws,
-    count, create_udf, date_part, date_trunc, digest, in_list, initcap, left, length,
-    lit, lower, lpad, ltrim, max, md5, min, now, octet_length, random, regexp_replace,
-    repeat, replace, reverse, right, rpad, rtrim, sha224, sha256, sha384, sha512,
-    split_part, starts_with, strpos, substr, sum, to_hex, translate, trim, upper, Column,
-    JoinType, Partitioning,
+    count, create_udf, date_bin, date_part, date_trunc, digest, in_list, initcap, left,
+    length, lit, lower, lpad, ltrim, map_keys, map_values, max, md5, min, now,
+    octet_length, random, regexp_replace, repeat, replace, reverse, right, rpad, rtrim,
+    sha224, sha256, sha384, sha512, split_part, starts_with, strpos, substr, sum, to_hex,
+    translate, trim, upper, Column, JoinType, Partitioning,
 };
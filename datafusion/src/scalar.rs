@@ -69,6 +69,8 @@ pub enum ScalarValue {
     Binary(Option<Vec<u8>>),
     /// large binary
     LargeBinary(Option<Vec<u8>>),
+    /// fixed size binary, of values of the given width in bytes
+    FixedSizeBinary(i32, Option<Vec<u8>>),
     /// list of nested ScalarValue (boxed to reduce size_of(ScalarValue))
     #[allow(clippy::box_collection)]
     List(Option<Box<Vec<ScalarValue>>>, Box<DataType>),
@@ -76,18 +78,20 @@ pub enum ScalarValue {
     Date32(Option<i32>),
     /// Date stored as a signed 64bit int
     Date64(Option<i64>),
-    /// Timestamp Second
-    TimestampSecond(Option<i64>),
-    /// Timestamp Milliseconds
-    TimestampMillisecond(Option<i64>),
-    /// Timestamp Microseconds
-    TimestampMicrosecond(Option<i64>),
-    /// Timestamp Nanoseconds
-    TimestampNanosecond(Option<i64>),
+    /// Timestamp Second, since the UNIX epoch in UTC, with an optional timezone
+    TimestampSecond(Option<i64>, Option<String>),
+    /// Timestamp Milliseconds, since the UNIX epoch in UTC, with an optional timezone
+    TimestampMillisecond(Option<i64>, Option<String>),
+    /// Timestamp Microseconds, since the UNIX epoch in UTC, with an optional timezone
+    TimestampMicrosecond(Option<i64>, Option<String>),
+    /// Timestamp Nanoseconds, since the UNIX epoch in UTC, with an optional timezone
+    TimestampNanosecond(Option<i64>, Option<String>),
     /// Interval with YearMonth unit
     IntervalYearMonth(Option<i32>),
     /// Interval with DayTime unit
     IntervalDayTime(Option<i64>),
+    /// Interval with MonthDayNano unit
+    IntervalMonthDayNano(Option<i128>),
     /// struct of nested ScalarValue (boxed to reduce size_of(ScalarValue))
     #[allow(clippy::box_collection)]
     Struct(Option<Box<Vec<ScalarValue>>>, Box<Vec<Field>>),
@@ -144,24 +148,28 @@ impl PartialEq for ScalarValue {
             (Binary(_), _) => false,
             (LargeBinary(v1), LargeBinary(v2)) => v1.eq(v2),
             (LargeBinary(_), _) => false,
+            (FixedSizeBinary(w1, v1), FixedSizeBinary(w2, v2)) => w1.eq(w2) && v1.eq(v2),
+            (FixedSizeBinary(_, _), _) => false,
             (List(v1, t1), List(v2, t2)) => v1.eq(v2) && t1.eq(t2),
             (List(_, _), _) => false,
             (Date32(v1), Date32(v2)) => v1.eq(v2),
             (Date32(_), _) => false,
             (Date64(v1), Date64(v2)) => v1.eq(v2),
             (Date64(_), _) => false,
-            (TimestampSecond(v1), TimestampSecond(v2)) => v1.eq(v2),
-            (TimestampSecond(_), _) => false,
-            (TimestampMillisecond(v1), TimestampMillisecond(v2)) => v1.eq(v2),
-            (TimestampMillisecond(_), _) => false,
-            (TimestampMicrosecond(v1), TimestampMicrosecond(v2)) => v1.eq(v2),
-            (TimestampMicrosecond(_), _) => false,
-            (TimestampNanosecond(v1), TimestampNanosecond(v2)) => v1.eq(v2),
-            (TimestampNanosecond(_), _) => false,
+            (TimestampSecond(v1, _), TimestampSecond(v2, _)) => v1.eq(v2),
+            (TimestampSecond(_, _), _) => false,
+            (TimestampMillisecond(v1, _), TimestampMillisecond(v2, _)) => v1.eq(v2),
+            (TimestampMillisecond(_, _), _) => false,
+            (TimestampMicrosecond(v1, _), TimestampMicrosecond(v2, _)) => v1.eq(v2),
+            (TimestampMicrosecond(_, _), _) => false,
+            (TimestampNanosecond(v1, _), TimestampNanosecond(v2, _)) => v1.eq(v2),
+            (TimestampNanosecond(_, _), _) => false,
             (IntervalYearMonth(v1), IntervalYearMonth(v2)) => v1.eq(v2),
             (IntervalYearMonth(_), _) => false,
             (IntervalDayTime(v1), IntervalDayTime(v2)) => v1.eq(v2),
             (IntervalDayTime(_), _) => false,
+            (IntervalMonthDayNano(v1), IntervalMonthDayNano(v2)) => v1.eq(v2),
+            (IntervalMonthDayNano(_), _) => false,
             (Struct(v1, t1), Struct(v2, t2)) => v1.eq(v2) && t1.eq(t2),
             (Struct(_, _), _) => false,
         }
@@ -224,6 +232,14 @@ impl PartialOrd for ScalarValue {
             (Binary(_), _) => None,
             (LargeBinary(v1), LargeBinary(v2)) => v1.partial_cmp(v2),
             (LargeBinary(_), _) => None,
+            (FixedSizeBinary(w1, v1), FixedSizeBinary(w2, v2)) => {
+                if w1.eq(w2) {
+                    v1.partial_cmp(v2)
+                } else {
+                    None
+                }
+            }
+            (FixedSizeBinary(_, _), _) => None,
             (List(v1, t1), List(v2, t2)) => {
                 if t1.eq(t2) {
                     v1.partial_cmp(v2)
@@ -236,18 +252,28 @@ impl PartialOrd for ScalarValue {
             (Date32(_), _) => None,
             (Date64(v1), Date64(v2)) => v1.partial_cmp(v2),
             (Date64(_), _) => None,
-            (TimestampSecond(v1), TimestampSecond(v2)) => v1.partial_cmp(v2),
-            (TimestampSecond(_), _) => None,
-            (TimestampMillisecond(v1), TimestampMillisecond(v2)) => v1.partial_cmp(v2),
-            (TimestampMillisecond(_), _) => None,
-            (TimestampMicrosecond(v1), TimestampMicrosecond(v2)) => v1.partial_cmp(v2),
-            (TimestampMicrosecond(_), _) => None,
-            (TimestampNanosecond(v1), TimestampNanosecond(v2)) => v1.partial_cmp(v2),
-            (TimestampNanosecond(_), _) => None,
+            (TimestampSecond(v1, _), TimestampSecond(v2, _)) => v1.partial_cmp(v2),
+            (TimestampSecond(_, _), _) => None,
+            (TimestampMillisecond(v1, _), TimestampMillisecond(v2, _)) => {
+                v1.partial_cmp(v2)
+            }
+            (TimestampMillisecond(_, _), _) => None,
+            (TimestampMicrosecond(v1, _), TimestampMicrosecond(v2, _)) => {
+                v1.partial_cmp(v2)
+            }
+            (TimestampMicrosecond(_, _), _) => None,
+            (TimestampNanosecond(v1, _), TimestampNanosecond(v2, _)) => {
+                v1.partial_cmp(v2)
+            }
+            (TimestampNanosecond(_, _), _) => None,
             (IntervalYearMonth(v1), IntervalYearMonth(v2)) => v1.partial_cmp(v2),
             (IntervalYearMonth(_), _) => None,
             (IntervalDayTime(v1), IntervalDayTime(v2)) => v1.partial_cmp(v2),
             (IntervalDayTime(_), _) => None,
+            (IntervalMonthDayNano(v1), IntervalMonthDayNano(v2)) => v1
+                .map(interval_mdn_parts)
+                .partial_cmp(&v2.map(interval_mdn_parts)),
+            (IntervalMonthDayNano(_), _) => None,
             (Struct(v1, t1), Struct(v2, t2)) => {
                 if t1.eq(t2) {
                     v1.partial_cmp(v2)
@@ -294,18 +320,23 @@ impl std::hash::Hash for ScalarValue {
             LargeUtf8(v) => v.hash(state),
             Binary(v) => v.hash(state),
             LargeBinary(v) => v.hash(state),
+            FixedSizeBinary(w, v) => {
+                w.hash(state);
+                v.hash(state)
+            }
             List(v, t) => {
                 v.hash(state);
                 t.hash(state);
             }
             Date32(v) => v.hash(state),
             Date64(v) => v.hash(state),
-            TimestampSecond(v) => v.hash(state),
-            TimestampMillisecond(v) => v.hash(state),
-            TimestampMicrosecond(v) => v.hash(state),
-            TimestampNanosecond(v) => v.hash(state),
+            TimestampSecond(v, _) => v.hash(state),
+            TimestampMillisecond(v, _) => v.hash(state),
+            TimestampMicrosecond(v, _) => v.hash(state),
+            TimestampNanosecond(v, _) => v.hash(state),
             IntervalYearMonth(v) => v.hash(state),
             IntervalDayTime(v) => v.hash(state),
+            IntervalMonthDayNano(v) => v.hash(state),
             Struct(v, t) => {
                 v.hash(state);
                 t.hash(state);
@@ -349,6 +380,19 @@ macro_rules! typed_cast {
     }};
 }
 
+macro_rules! typed_cast_tz {
+    ($array:expr, $index:expr, $ARRAYTYPE:ident, $SCALAR:ident, $TZ:expr) => {{
+        let array = $array.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
+        ScalarValue::$SCALAR(
+            match array.is_null($index) {
+                true => None,
+                false => Some(array.value($index).into()),
+            },
+            $TZ.clone(),
+        )
+    }};
+}
+
 macro_rules! build_list {
     ($VALUE_BUILDER_TY:ident, $SCALAR_TY:ident, $VALUES:expr, $SIZE:expr) => {{
         match $VALUES {
@@ -387,25 +431,25 @@ macro_rules! build_timestamp_list {
             Some(values) => {
                 let values = values.as_ref();
                 match $TIME_UNIT {
-                    TimeUnit::Second => build_values_list!(
+                    TimeUnit::Second => build_values_list_tz!(
                         TimestampSecondBuilder,
                         TimestampSecond,
                         values,
                         $SIZE
                     ),
-                    TimeUnit::Microsecond => build_values_list!(
+                    TimeUnit::Millisecond => build_values_list_tz!(
                         TimestampMillisecondBuilder,
                         TimestampMillisecond,
                         values,
                         $SIZE
                     ),
-                    TimeUnit::Millisecond => build_values_list!(
+                    TimeUnit::Microsecond => build_values_list_tz!(
                         TimestampMicrosecondBuilder,
                         TimestampMicrosecond,
                         values,
                         $SIZE
                     ),
-                    TimeUnit::Nanosecond => build_values_list!(
+                    TimeUnit::Nanosecond => build_values_list_tz!(
                         TimestampNanosecondBuilder,
                         TimestampNanosecond,
                         values,
@@ -440,6 +484,29 @@ macro_rules! build_values_list {
     }};
 }
 
+macro_rules! build_values_list_tz {
+    ($VALUE_BUILDER_TY:ident, $SCALAR_TY:ident, $VALUES:expr, $SIZE:expr) => {{
+        let mut builder = ListBuilder::new($VALUE_BUILDER_TY::new($VALUES.len()));
+
+        for _ in 0..$SIZE {
+            for scalar_value in $VALUES {
+                match scalar_value {
+                    ScalarValue::$SCALAR_TY(Some(v), _) => {
+                        builder.values().append_value(v.clone()).unwrap()
+                    }
+                    ScalarValue::$SCALAR_TY(None, _) => {
+                        builder.values().append_null().unwrap();
+                    }
+                    _ => panic!("Incompatible ScalarValue for list"),
+                };
+            }
+            builder.append(true).unwrap();
+        }
+
+        builder.finish()
+    }};
+}
+
 macro_rules! build_array_from_option {
     ($DATA_TYPE:ident, $ARRAY_TYPE:ident, $EXPR:expr, $SIZE:expr) => {{
         match $EXPR {
@@ -472,7 +539,43 @@ macro_rules! eq_array_primitive {
     }};
 }
 
+/// Packs the days and milliseconds of an interval into the native value of an
+/// `IntervalDayTime` array
+pub fn interval_dt_value(days: i32, millis: i32) -> i64 {
+    ((days as i64) << 32) | (millis as u32 as i64)
+}
+
+/// Returns the days and milliseconds of the native value of an
+/// `IntervalDayTime` array
+pub fn interval_dt_parts(value: i64) -> (i32, i32) {
+    ((value >> 32) as i32, value as i32)
+}
+
+/// Packs the months, days and nanoseconds of an interval into the native value
+/// of an `IntervalMonthDayNano` array
+pub fn interval_mdn_value(months: i32, days: i32, nanos: i64) -> i128 {
+    let months = months as u32 as u128;
+    let days = (days as u32 as u128) << 32;
+    let nanos = (nanos as u64 as u128) << 64;
+    (months | days | nanos) as i128
+}
+
+/// Returns the months, days and nanoseconds of the native value of an
+/// `IntervalMonthDayNano` array
+pub fn interval_mdn_parts(value: i128) -> (i32, i32, i64) {
+    let value = value as u128;
+    let months = value as u32 as i32;
+    let days = (value >> 32) as u32 as i32;
+    let nanos = (value >> 64) as u64 as i64;
+    (months, days, nanos)
+}
+
 impl ScalarValue {
+    /// Create an interval Scalar with `MonthDayNano` unit from its parts.
+    pub fn new_interval_mdn(months: i32, days: i32, nanos: i64) -> Self {
+        ScalarValue::IntervalMonthDayNano(Some(interval_mdn_value(months, days, nanos)))
+    }
+
     /// Create a decimal Scalar from value/precision and scale.
     pub fn try_new_decimal128(
         value: i128,
@@ -504,17 +607,17 @@ impl ScalarValue {
             ScalarValue::Decimal128(_, precision, scale) => {
                 DataType::Decimal(*precision, *scale)
             }
-            ScalarValue::TimestampSecond(_) => {
-                DataType::Timestamp(TimeUnit::Second, None)
+            ScalarValue::TimestampSecond(_, tz) => {
+                DataType::Timestamp(TimeUnit::Second, tz.clone())
             }
-            ScalarValue::TimestampMillisecond(_) => {
-                DataType::Timestamp(TimeUnit::Millisecond, None)
+            ScalarValue::TimestampMillisecond(_, tz) => {
+                DataType::Timestamp(TimeUnit::Millisecond, tz.clone())
             }
-            ScalarValue::TimestampMicrosecond(_) => {
-                DataType::Timestamp(TimeUnit::Microsecond, None)
+            ScalarValue::TimestampMicrosecond(_, tz) => {
+                DataType::Timestamp(TimeUnit::Microsecond, tz.clone())
             }
-            ScalarValue::TimestampNanosecond(_) => {
-                DataType::Timestamp(TimeUnit::Nanosecond, None)
+            ScalarValue::TimestampNanosecond(_, tz) => {
+                DataType::Timestamp(TimeUnit::Nanosecond, tz.clone())
             }
             ScalarValue::Float32(_) => DataType::Float32,
             ScalarValue::Float64(_) => DataType::Float64,
@@ -522,6 +625,7 @@ impl ScalarValue {
             ScalarValue::LargeUtf8(_) => DataType::LargeUtf8,
             ScalarValue::Binary(_) => DataType::Binary,
             ScalarValue::LargeBinary(_) => DataType::LargeBinary,
+            ScalarValue::FixedSizeBinary(width, _) => DataType::FixedSizeBinary(*width),
             ScalarValue::List(_, data_type) => DataType::List(Box::new(Field::new(
                 "item",
                 data_type.as_ref().clone(),
@@ -533,6 +637,9 @@ impl ScalarValue {
                 DataType::Interval(IntervalUnit::YearMonth)
             }
             ScalarValue::IntervalDayTime(_) => DataType::Interval(IntervalUnit::DayTime),
+            ScalarValue::IntervalMonthDayNano(_) => {
+                DataType::Interval(IntervalUnit::MonthDayNano)
+            }
             ScalarValue::Struct(_, fields) => DataType::Struct(fields.as_ref().clone()),
         }
     }
@@ -545,7 +652,10 @@ impl ScalarValue {
             | ScalarValue::Int16(None)
             | ScalarValue::Int32(None)
             | ScalarValue::Int64(None)
-            | ScalarValue::Float32(None) => self.clone(),
+            | ScalarValue::Float32(None)
+            | ScalarValue::IntervalYearMonth(None)
+            | ScalarValue::IntervalDayTime(None)
+            | ScalarValue::IntervalMonthDayNano(None) => self.clone(),
             ScalarValue::Float64(Some(v)) => ScalarValue::Float64(Some(-v)),
             ScalarValue::Float32(Some(v)) => ScalarValue::Float32(Some(-v)),
             ScalarValue::Int8(Some(v)) => ScalarValue::Int8(Some(-v)),
@@ -555,6 +665,17 @@ impl ScalarValue {
             ScalarValue::Decimal128(Some(v), precision, scale) => {
                 ScalarValue::Decimal128(Some(-v), *precision, *scale)
             }
+            ScalarValue::IntervalYearMonth(Some(v)) => {
+                ScalarValue::IntervalYearMonth(Some(-v))
+            }
+            ScalarValue::IntervalDayTime(Some(v)) => {
+                let (days, millis) = interval_dt_parts(*v);
+                ScalarValue::IntervalDayTime(Some(interval_dt_value(-days, -millis)))
+            }
+            ScalarValue::IntervalMonthDayNano(Some(v)) => {
+                let (months, days, nanos) = interval_mdn_parts(*v);
+                ScalarValue::new_interval_mdn(-months, -days, -nanos)
+            }
             _ => panic!("Cannot run arithmetic negate on scalar value: {:?}", self),
         }
     }
@@ -578,10 +699,17 @@ impl ScalarValue {
                 | ScalarValue::Date64(None)
                 | ScalarValue::Utf8(None)
                 | ScalarValue::LargeUtf8(None)
+                | ScalarValue::Binary(None)
+                | ScalarValue::LargeBinary(None)
+                | ScalarValue::FixedSizeBinary(_, None)
                 | ScalarValue::List(None, _)
-                | ScalarValue::TimestampMillisecond(None)
-                | ScalarValue::TimestampMicrosecond(None)
-                | ScalarValue::TimestampNanosecond(None)
+                | ScalarValue::TimestampSecond(None, _)
+                | ScalarValue::TimestampMillisecond(None, _)
+                | ScalarValue::TimestampMicrosecond(None, _)
+                | ScalarValue::TimestampNanosecond(None, _)
+                | ScalarValue::IntervalYearMonth(None)
+                | ScalarValue::IntervalDayTime(None)
+                | ScalarValue::IntervalMonthDayNano(None)
                 | ScalarValue::Struct(None, _)
                 | ScalarValue::Decimal128(None, _, _) // For decimal type, the value is null means ScalarValue::Decimal128 is null.
         )
@@ -662,6 +790,30 @@ impl ScalarValue {
             }};
         }
 
+        /// Creates an array of $ARRAY_TY by unpacking values of
+        /// SCALAR_TY for timestamp types, in the timezone $TZ
+        macro_rules! build_array_timestamp {
+            ($ARRAY_TY:ident, $SCALAR_TY:ident, $TZ:expr) => {{
+                {
+                    let values = scalars
+                        .map(|sv| {
+                            if let ScalarValue::$SCALAR_TY(v, _) = sv {
+                                Ok(v)
+                            } else {
+                                Err(DataFusionError::Internal(format!(
+                                    "Inconsistent types in ScalarValue::iter_to_array. \
+                                     Expected {:?}, got {:?}",
+                                    data_type, sv
+                                )))
+                            }
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+
+                    Arc::new($ARRAY_TY::from_opt_vec(values, $TZ.clone()))
+                }
+            }};
+        }
+
         /// Creates an array of $ARRAY_TY by unpacking values of
         /// SCALAR_TY for "string-like" types.
         macro_rules! build_array_string {
@@ -769,19 +921,47 @@ impl ScalarValue {
             DataType::LargeUtf8 => build_array_string!(LargeStringArray, LargeUtf8),
             DataType::Binary => build_array_string!(BinaryArray, Binary),
             DataType::LargeBinary => build_array_string!(LargeBinaryArray, LargeBinary),
+            DataType::FixedSizeBinary(width) => {
+                let mut builder =
+                    FixedSizeBinaryBuilder::new(scalars.size_hint().0, *width);
+                for scalar in scalars {
+                    match scalar {
+                        ScalarValue::FixedSizeBinary(_, Some(value)) => {
+                            builder.append_value(&value)?
+                        }
+                        ScalarValue::FixedSizeBinary(_, None) => builder.append_null()?,
+                        sv => {
+                            return Err(DataFusionError::Internal(format!(
+                                "Inconsistent types in ScalarValue::iter_to_array. \
+                                 Expected {:?}, got {:?}",
+                                data_type, sv
+                            )))
+                        }
+                    }
+                }
+                Arc::new(builder.finish())
+            }
             DataType::Date32 => build_array_primitive!(Date32Array, Date32),
             DataType::Date64 => build_array_primitive!(Date64Array, Date64),
-            DataType::Timestamp(TimeUnit::Second, None) => {
-                build_array_primitive!(TimestampSecondArray, TimestampSecond)
+            DataType::Timestamp(TimeUnit::Second, tz) => {
+                build_array_timestamp!(TimestampSecondArray, TimestampSecond, tz)
             }
-            DataType::Timestamp(TimeUnit::Millisecond, None) => {
-                build_array_primitive!(TimestampMillisecondArray, TimestampMillisecond)
+            DataType::Timestamp(TimeUnit::Millisecond, tz) => {
+                build_array_timestamp!(
+                    TimestampMillisecondArray,
+                    TimestampMillisecond,
+                    tz
+                )
             }
-            DataType::Timestamp(TimeUnit::Microsecond, None) => {
-                build_array_primitive!(TimestampMicrosecondArray, TimestampMicrosecond)
+            DataType::Timestamp(TimeUnit::Microsecond, tz) => {
+                build_array_timestamp!(
+                    TimestampMicrosecondArray,
+                    TimestampMicrosecond,
+                    tz
+                )
             }
-            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
-                build_array_primitive!(TimestampNanosecondArray, TimestampNanosecond)
+            DataType::Timestamp(TimeUnit::Nanosecond, tz) => {
+                build_array_timestamp!(TimestampNanosecondArray, TimestampNanosecond, tz)
             }
             DataType::Interval(IntervalUnit::DayTime) => {
                 build_array_primitive!(IntervalDayTimeArray, IntervalDayTime)
@@ -789,6 +969,9 @@ impl ScalarValue {
             DataType::Interval(IntervalUnit::YearMonth) => {
                 build_array_primitive!(IntervalYearMonthArray, IntervalYearMonth)
             }
+            DataType::Interval(IntervalUnit::MonthDayNano) => {
+                build_array_primitive!(IntervalMonthDayNanoArray, IntervalMonthDayNano)
+            }
             DataType::List(fields) if fields.data_type() == &DataType::Int8 => {
                 build_array_list_primitive!(Int8Type, Int8, i8)
             }
@@ -1032,38 +1215,18 @@ impl ScalarValue {
             ScalarValue::UInt64(e) => {
                 build_array_from_option!(UInt64, UInt64Array, e, size)
             }
-            ScalarValue::TimestampSecond(e) => build_array_from_option!(
-                Timestamp,
-                TimeUnit::Second,
-                None,
-                TimestampSecondArray,
-                e,
-                size
+            ScalarValue::TimestampSecond(e, tz) => Arc::new(
+                TimestampSecondArray::from_opt_vec(vec![*e; size], tz.clone()),
             ),
-            ScalarValue::TimestampMillisecond(e) => build_array_from_option!(
-                Timestamp,
-                TimeUnit::Millisecond,
-                None,
-                TimestampMillisecondArray,
-                e,
-                size
+            ScalarValue::TimestampMillisecond(e, tz) => Arc::new(
+                TimestampMillisecondArray::from_opt_vec(vec![*e; size], tz.clone()),
             ),
 
-            ScalarValue::TimestampMicrosecond(e) => build_array_from_option!(
-                Timestamp,
-                TimeUnit::Microsecond,
-                None,
-                TimestampMicrosecondArray,
-                e,
-                size
+            ScalarValue::TimestampMicrosecond(e, tz) => Arc::new(
+                TimestampMicrosecondArray::from_opt_vec(vec![*e; size], tz.clone()),
             ),
-            ScalarValue::TimestampNanosecond(e) => build_array_from_option!(
-                Timestamp,
-                TimeUnit::Nanosecond,
-                None,
-                TimestampNanosecondArray,
-                e,
-                size
+            ScalarValue::TimestampNanosecond(e, tz) => Arc::new(
+                TimestampNanosecondArray::from_opt_vec(vec![*e; size], tz.clone()),
             ),
             ScalarValue::Utf8(e) => match e {
                 Some(value) => {
@@ -1099,6 +1262,17 @@ impl ScalarValue {
                         .collect::<LargeBinaryArray>(),
                 ),
             },
+            ScalarValue::FixedSizeBinary(width, e) => {
+                let mut builder = FixedSizeBinaryBuilder::new(size, *width);
+                for _ in 0..size {
+                    match e {
+                        Some(value) => builder.append_value(value),
+                        None => builder.append_null(),
+                    }
+                    .expect("Failed to append a fixed size binary value");
+                }
+                Arc::new(builder.finish())
+            }
             ScalarValue::List(values, data_type) => Arc::new(match data_type.as_ref() {
                 DataType::Boolean => build_list!(BooleanBuilder, Boolean, values, size),
                 DataType::Int8 => build_list!(Int8Builder, Int8, values, size),
@@ -1112,8 +1286,9 @@ impl ScalarValue {
                 DataType::Utf8 => build_list!(StringBuilder, Utf8, values, size),
                 DataType::Float32 => build_list!(Float32Builder, Float32, values, size),
                 DataType::Float64 => build_list!(Float64Builder, Float64, values, size),
-                DataType::Timestamp(unit, tz) => {
-                    build_timestamp_list!(unit.clone(), tz.clone(), values, size)
+                // the builders of timestamp lists have no timezone
+                DataType::Timestamp(unit, None) => {
+                    build_timestamp_list!(unit.clone(), None, values, size)
                 }
                 &DataType::LargeUtf8 => {
                     build_list!(LargeStringBuilder, LargeUtf8, values, size)
@@ -1149,6 +1324,13 @@ impl ScalarValue {
                 e,
                 size
             ),
+            ScalarValue::IntervalMonthDayNano(e) => build_array_from_option!(
+                Interval,
+                IntervalUnit::MonthDayNano,
+                IntervalMonthDayNanoArray,
+                e,
+                size
+            ),
             ScalarValue::Struct(values, fields) => match values {
                 Some(values) => {
                     let field_values: Vec<_> = fields
@@ -1218,6 +1400,19 @@ impl ScalarValue {
             DataType::LargeBinary => {
                 typed_cast!(array, index, LargeBinaryArray, LargeBinary)
             }
+            DataType::FixedSizeBinary(width) => {
+                let array = array
+                    .as_any()
+                    .downcast_ref::<FixedSizeBinaryArray>()
+                    .unwrap();
+                ScalarValue::FixedSizeBinary(
+                    *width,
+                    match array.is_null(index) {
+                        true => None,
+                        false => Some(array.value(index).into()),
+                    },
+                )
+            }
             DataType::Utf8 => typed_cast!(array, index, StringArray, Utf8),
             DataType::LargeUtf8 => typed_cast!(array, index, LargeStringArray, LargeUtf8),
             DataType::List(nested_type) => {
@@ -1247,27 +1442,49 @@ impl ScalarValue {
             DataType::Date64 => {
                 typed_cast!(array, index, Date64Array, Date64)
             }
-            DataType::Timestamp(TimeUnit::Second, _) => {
-                typed_cast!(array, index, TimestampSecondArray, TimestampSecond)
+            DataType::Timestamp(TimeUnit::Second, tz) => {
+                typed_cast_tz!(array, index, TimestampSecondArray, TimestampSecond, tz)
             }
-            DataType::Timestamp(TimeUnit::Millisecond, _) => {
-                typed_cast!(
+            DataType::Timestamp(TimeUnit::Millisecond, tz) => {
+                typed_cast_tz!(
                     array,
                     index,
                     TimestampMillisecondArray,
-                    TimestampMillisecond
+                    TimestampMillisecond,
+                    tz
                 )
             }
-            DataType::Timestamp(TimeUnit::Microsecond, _) => {
-                typed_cast!(
+            DataType::Timestamp(TimeUnit::Microsecond, tz) => {
+                typed_cast_tz!(
                     array,
                     index,
                     TimestampMicrosecondArray,
-                    TimestampMicrosecond
+                    TimestampMicrosecond,
+                    tz
                 )
             }
-            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
-                typed_cast!(array, index, TimestampNanosecondArray, TimestampNanosecond)
+            DataType::Timestamp(TimeUnit::Nanosecond, tz) => {
+                typed_cast_tz!(
+                    array,
+                    index,
+                    TimestampNanosecondArray,
+                    TimestampNanosecond,
+                    tz
+                )
+            }
+            DataType::Interval(IntervalUnit::YearMonth) => {
+                typed_cast!(array, index, IntervalYearMonthArray, IntervalYearMonth)
+            }
+            DataType::Interval(IntervalUnit::DayTime) => {
+                typed_cast!(array, index, IntervalDayTimeArray, IntervalDayTime)
+            }
+            DataType::Interval(IntervalUnit::MonthDayNano) => {
+                typed_cast!(
+                    array,
+                    index,
+                    IntervalMonthDayNanoArray,
+                    IntervalMonthDayNano
+                )
             }
             DataType::Dictionary(index_type, _) => {
                 let (values, values_index) = match **index_type {
@@ -1396,6 +1613,9 @@ impl ScalarValue {
             ScalarValue::LargeBinary(val) => {
                 eq_array_primitive!(array, index, LargeBinaryArray, val)
             }
+            ScalarValue::FixedSizeBinary(_, val) => {
+                eq_array_primitive!(array, index, FixedSizeBinaryArray, val)
+            }
             ScalarValue::List(_, _) => unimplemented!(),
             ScalarValue::Date32(val) => {
                 eq_array_primitive!(array, index, Date32Array, val)
@@ -1403,16 +1623,16 @@ impl ScalarValue {
             ScalarValue::Date64(val) => {
                 eq_array_primitive!(array, index, Date64Array, val)
             }
-            ScalarValue::TimestampSecond(val) => {
+            ScalarValue::TimestampSecond(val, _) => {
                 eq_array_primitive!(array, index, TimestampSecondArray, val)
             }
-            ScalarValue::TimestampMillisecond(val) => {
+            ScalarValue::TimestampMillisecond(val, _) => {
                 eq_array_primitive!(array, index, TimestampMillisecondArray, val)
             }
-            ScalarValue::TimestampMicrosecond(val) => {
+            ScalarValue::TimestampMicrosecond(val, _) => {
                 eq_array_primitive!(array, index, TimestampMicrosecondArray, val)
             }
-            ScalarValue::TimestampNanosecond(val) => {
+            ScalarValue::TimestampNanosecond(val, _) => {
                 eq_array_primitive!(array, index, TimestampNanosecondArray, val)
             }
             ScalarValue::IntervalYearMonth(val) => {
@@ -1421,6 +1641,9 @@ impl ScalarValue {
             ScalarValue::IntervalDayTime(val) => {
                 eq_array_primitive!(array, index, IntervalDayTimeArray, val)
             }
+            ScalarValue::IntervalMonthDayNano(val) => {
+                eq_array_primitive!(array, index, IntervalMonthDayNanoArray, val)
+            }
             ScalarValue::Struct(_, _) => unimplemented!(),
         }
     }
@@ -1561,10 +1784,10 @@ impl TryFrom<ScalarValue> for i64 {
         match value {
             ScalarValue::Int64(Some(inner_value))
             | ScalarValue::Date64(Some(inner_value))
-            | ScalarValue::TimestampNanosecond(Some(inner_value))
-            | ScalarValue::TimestampMicrosecond(Some(inner_value))
-            | ScalarValue::TimestampMillisecond(Some(inner_value))
-            | ScalarValue::TimestampSecond(Some(inner_value)) => Ok(inner_value),
+            | ScalarValue::TimestampNanosecond(Some(inner_value), _)
+            | ScalarValue::TimestampMicrosecond(Some(inner_value), _)
+            | ScalarValue::TimestampMillisecond(Some(inner_value), _)
+            | ScalarValue::TimestampSecond(Some(inner_value), _) => Ok(inner_value),
             _ => Err(DataFusionError::Internal(format!(
                 "Cannot convert {:?} to {}",
                 value,
@@ -1604,19 +1827,33 @@ impl TryFrom<&DataType> for ScalarValue {
             }
             DataType::Utf8 => ScalarValue::Utf8(None),
             DataType::LargeUtf8 => ScalarValue::LargeUtf8(None),
+            DataType::Binary => ScalarValue::Binary(None),
+            DataType::LargeBinary => ScalarValue::LargeBinary(None),
+            DataType::FixedSizeBinary(width) => {
+                ScalarValue::FixedSizeBinary(*width, None)
+            }
             DataType::Date32 => ScalarValue::Date32(None),
             DataType::Date64 => ScalarValue::Date64(None),
-            DataType::Timestamp(TimeUnit::Second, _) => {
-                ScalarValue::TimestampSecond(None)
+            DataType::Timestamp(TimeUnit::Second, tz) => {
+                ScalarValue::TimestampSecond(None, tz.clone())
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, tz) => {
+                ScalarValue::TimestampMillisecond(None, tz.clone())
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, tz) => {
+                ScalarValue::TimestampMicrosecond(None, tz.clone())
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, tz) => {
+                ScalarValue::TimestampNanosecond(None, tz.clone())
             }
-            DataType::Timestamp(TimeUnit::Millisecond, _) => {
-                ScalarValue::TimestampMillisecond(None)
+            DataType::Interval(IntervalUnit::YearMonth) => {
+                ScalarValue::IntervalYearMonth(None)
             }
-            DataType::Timestamp(TimeUnit::Microsecond, _) => {
-                ScalarValue::TimestampMicrosecond(None)
+            DataType::Interval(IntervalUnit::DayTime) => {
+                ScalarValue::IntervalDayTime(None)
             }
-            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
-                ScalarValue::TimestampNanosecond(None)
+            DataType::Interval(IntervalUnit::MonthDayNano) => {
+                ScalarValue::IntervalMonthDayNano(None)
             }
             DataType::Dictionary(_index_type, value_type) => {
                 value_type.as_ref().try_into()?
@@ -1663,10 +1900,10 @@ impl fmt::Display for ScalarValue {
             ScalarValue::UInt16(e) => format_option!(f, e)?,
             ScalarValue::UInt32(e) => format_option!(f, e)?,
             ScalarValue::UInt64(e) => format_option!(f, e)?,
-            ScalarValue::TimestampSecond(e) => format_option!(f, e)?,
-            ScalarValue::TimestampMillisecond(e) => format_option!(f, e)?,
-            ScalarValue::TimestampMicrosecond(e) => format_option!(f, e)?,
-            ScalarValue::TimestampNanosecond(e) => format_option!(f, e)?,
+            ScalarValue::TimestampSecond(e, _) => format_option!(f, e)?,
+            ScalarValue::TimestampMillisecond(e, _) => format_option!(f, e)?,
+            ScalarValue::TimestampMicrosecond(e, _) => format_option!(f, e)?,
+            ScalarValue::TimestampNanosecond(e, _) => format_option!(f, e)?,
             ScalarValue::Utf8(e) => format_option!(f, e)?,
             ScalarValue::LargeUtf8(e) => format_option!(f, e)?,
             ScalarValue::Binary(e) => match e {
@@ -1680,7 +1917,7 @@ impl fmt::Display for ScalarValue {
                 )?,
                 None => write!(f, "NULL")?,
             },
-            ScalarValue::LargeBinary(e) => match e {
+            ScalarValue::LargeBinary(e) | ScalarValue::FixedSizeBinary(_, e) => match e {
                 Some(l) => write!(
                     f,
                     "{}",
@@ -1706,6 +1943,7 @@ impl fmt::Display for ScalarValue {
             ScalarValue::Date64(e) => format_option!(f, e)?,
             ScalarValue::IntervalDayTime(e) => format_option!(f, e)?,
             ScalarValue::IntervalYearMonth(e) => format_option!(f, e)?,
+            ScalarValue::IntervalMonthDayNano(e) => format_option!(f, e)?,
             ScalarValue::Struct(e, fields) => match e {
                 Some(l) => write!(
                     f,
@@ -1738,16 +1976,30 @@ impl fmt::Debug for ScalarValue {
             ScalarValue::UInt16(_) => write!(f, "UInt16({})", self),
             ScalarValue::UInt32(_) => write!(f, "UInt32({})", self),
             ScalarValue::UInt64(_) => write!(f, "UInt64({})", self),
-            ScalarValue::TimestampSecond(_) => write!(f, "TimestampSecond({})", self),
-            ScalarValue::TimestampMillisecond(_) => {
+            ScalarValue::TimestampSecond(_, None) => {
+                write!(f, "TimestampSecond({})", self)
+            }
+            ScalarValue::TimestampSecond(_, Some(tz)) => {
+                write!(f, "TimestampSecond({}, {})", self, tz)
+            }
+            ScalarValue::TimestampMillisecond(_, None) => {
                 write!(f, "TimestampMillisecond({})", self)
             }
-            ScalarValue::TimestampMicrosecond(_) => {
+            ScalarValue::TimestampMillisecond(_, Some(tz)) => {
+                write!(f, "TimestampMillisecond({}, {})", self, tz)
+            }
+            ScalarValue::TimestampMicrosecond(_, None) => {
                 write!(f, "TimestampMicrosecond({})", self)
             }
-            ScalarValue::TimestampNanosecond(_) => {
+            ScalarValue::TimestampMicrosecond(_, Some(tz)) => {
+                write!(f, "TimestampMicrosecond({}, {})", self, tz)
+            }
+            ScalarValue::TimestampNanosecond(_, None) => {
                 write!(f, "TimestampNanosecond({})", self)
             }
+            ScalarValue::TimestampNanosecond(_, Some(tz)) => {
+                write!(f, "TimestampNanosecond({}, {})", self, tz)
+            }
             ScalarValue::Utf8(None) => write!(f, "Utf8({})", self),
             ScalarValue::Utf8(Some(_)) => write!(f, "Utf8(\"{}\")", self),
             ScalarValue::LargeUtf8(None) => write!(f, "LargeUtf8({})", self),
@@ -1756,6 +2008,12 @@ impl fmt::Debug for ScalarValue {
             ScalarValue::Binary(Some(_)) => write!(f, "Binary(\"{}\")", self),
             ScalarValue::LargeBinary(None) => write!(f, "LargeBinary({})", self),
             ScalarValue::LargeBinary(Some(_)) => write!(f, "LargeBinary(\"{}\")", self),
+            ScalarValue::FixedSizeBinary(width, None) => {
+                write!(f, "FixedSizeBinary({}, {})", width, self)
+            }
+            ScalarValue::FixedSizeBinary(width, Some(_)) => {
+                write!(f, "FixedSizeBinary({}, \"{}\")", width, self)
+            }
             ScalarValue::List(_, _) => write!(f, "List([{}])", self),
             ScalarValue::Date32(_) => write!(f, "Date32(\"{}\")", self),
             ScalarValue::Date64(_) => write!(f, "Date64(\"{}\")", self),
@@ -1765,6 +2023,9 @@ impl fmt::Debug for ScalarValue {
             ScalarValue::IntervalYearMonth(_) => {
                 write!(f, "IntervalYearMonth(\"{}\")", self)
             }
+            ScalarValue::IntervalMonthDayNano(_) => {
+                write!(f, "IntervalMonthDayNano(\"{}\")", self)
+            }
             ScalarValue::Struct(e, fields) => {
                 // Use Debug representation of field values
                 match e {
@@ -1798,25 +2059,25 @@ impl ScalarType<f32> for Float32Type {
 
 impl ScalarType<i64> for TimestampSecondType {
     fn scalar(r: Option<i64>) -> ScalarValue {
-        ScalarValue::TimestampSecond(r)
+        ScalarValue::TimestampSecond(r, None)
     }
 }
 
 impl ScalarType<i64> for TimestampMillisecondType {
     fn scalar(r: Option<i64>) -> ScalarValue {
-        ScalarValue::TimestampMillisecond(r)
+        ScalarValue::TimestampMillisecond(r, None)
     }
 }
 
 impl ScalarType<i64> for TimestampMicrosecondType {
     fn scalar(r: Option<i64>) -> ScalarValue {
-        ScalarValue::TimestampMicrosecond(r)
+        ScalarValue::TimestampMicrosecond(r, None)
     }
 }
 
 impl ScalarType<i64> for TimestampNanosecondType {
     fn scalar(r: Option<i64>) -> ScalarValue {
-        ScalarValue::TimestampNanosecond(r)
+        ScalarValue::TimestampNanosecond(r, None)
     }
 }
 
@@ -2003,6 +2264,23 @@ mod tests {
         }};
     }
 
+    /// Creates array directly and via ScalarValue and ensures they
+    /// are the same, for timestamp arrays without a timezone
+    macro_rules! check_scalar_iter_tz {
+        ($SCALAR_T:ident, $ARRAYTYPE:ident, $INPUT:expr) => {{
+            let scalars: Vec<_> = $INPUT
+                .iter()
+                .map(|v| ScalarValue::$SCALAR_T(*v, None))
+                .collect();
+
+            let array = ScalarValue::iter_to_array(scalars.into_iter()).unwrap();
+
+            let expected: ArrayRef = Arc::new($ARRAYTYPE::from($INPUT));
+
+            assert_eq!(&array, &expected);
+        }};
+    }
+
     /// Creates array directly and via ScalarValue and ensures they
     /// are the same, for string  arrays
     macro_rules! check_scalar_iter_string {
@@ -2056,22 +2334,22 @@ mod tests {
         check_scalar_iter!(UInt32, UInt32Array, vec![Some(1), None, Some(3)]);
         check_scalar_iter!(UInt64, UInt64Array, vec![Some(1), None, Some(3)]);
 
-        check_scalar_iter!(
+        check_scalar_iter_tz!(
             TimestampSecond,
             TimestampSecondArray,
             vec![Some(1), None, Some(3)]
         );
-        check_scalar_iter!(
+        check_scalar_iter_tz!(
             TimestampMillisecond,
             TimestampMillisecondArray,
             vec![Some(1), None, Some(3)]
         );
-        check_scalar_iter!(
+        check_scalar_iter_tz!(
             TimestampMicrosecond,
             TimestampMicrosecondArray,
             vec![Some(1), None, Some(3)]
         );
-        check_scalar_iter!(
+        check_scalar_iter_tz!(
             TimestampNanosecond,
             TimestampNanosecondArray,
             vec![Some(1), None, Some(3)]
@@ -2099,6 +2377,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn scalar_fixed_size_binary_roundtrip() -> Result<()> {
+        let scalars = vec![
+            ScalarValue::FixedSizeBinary(2, Some(vec![1, 2])),
+            ScalarValue::FixedSizeBinary(2, None),
+            ScalarValue::FixedSizeBinary(2, Some(vec![3, 4])),
+        ];
+        let array = ScalarValue::iter_to_array(scalars.clone())?;
+        assert_eq!(array.data_type(), &DataType::FixedSizeBinary(2));
+        for (i, scalar) in scalars.iter().enumerate() {
+            assert_eq!(&ScalarValue::try_from_array(&array, i)?, scalar);
+            assert!(scalar.eq_array(&array, i));
+        }
+        assert!(scalars[1].is_null());
+        assert_eq!(
+            ScalarValue::try_from(&DataType::FixedSizeBinary(2))?,
+            scalars[1]
+        );
+
+        let array = scalars[0].to_array_of_size(3);
+        assert_eq!(array.len(), 3);
+        assert_eq!(ScalarValue::try_from_array(&array, 2)?, scalars[0]);
+        Ok(())
+    }
+
     #[test]
     fn scalar_iter_to_array_empty() {
         let scalars = vec![] as Vec<ScalarValue>;
@@ -2155,6 +2458,39 @@ mod tests {
         assert_eq!(std::mem::size_of::<ScalarValue>(), 48);
     }
 
+    #[test]
+    fn interval_parts() {
+        for (days, millis) in [(0, 0), (1, -1), (-3, 500), (i32::MAX, i32::MIN)] {
+            assert_eq!(
+                interval_dt_parts(interval_dt_value(days, millis)),
+                (days, millis)
+            );
+        }
+        for (months, days, nanos) in [(0, 0, 0), (-1, 2, -3), (i32::MIN, -1, i64::MAX)] {
+            let value = interval_mdn_value(months, days, nanos);
+            assert_eq!(interval_mdn_parts(value), (months, days, nanos));
+        }
+
+        // intervals compare by months, then days and then nanoseconds
+        assert!(
+            ScalarValue::new_interval_mdn(0, -1, 0)
+                < ScalarValue::new_interval_mdn(0, 0, 1)
+        );
+        assert!(
+            ScalarValue::new_interval_mdn(1, 0, 0)
+                > ScalarValue::new_interval_mdn(0, 31, 0)
+        );
+        assert_eq!(
+            ScalarValue::new_interval_mdn(1, -2, 3).arithmetic_negate(),
+            ScalarValue::new_interval_mdn(-1, 2, -3)
+        );
+        assert_eq!(
+            ScalarValue::IntervalDayTime(Some(interval_dt_value(1, -2)))
+                .arithmetic_negate(),
+            ScalarValue::IntervalDayTime(Some(interval_dt_value(-1, 2)))
+        );
+    }
+
     #[test]
     fn scalar_eq_array() {
         // Validate that eq_array has the same semantics as ScalarValue::eq
@@ -2176,6 +2512,8 @@ mod tests {
         let i32_vals = make_typed_vec!(i8_vals, i32);
         let i64_vals = make_typed_vec!(i8_vals, i64);
 
+        let i128_vals = make_typed_vec!(i8_vals, i128);
+
         let u8_vals = vec![Some(0), None, Some(1)];
         let u16_vals = make_typed_vec!(u8_vals, u16);
         let u32_vals = make_typed_vec!(u8_vals, u32);
@@ -2199,6 +2537,15 @@ mod tests {
                     scalars: $INPUT.iter().map(|v| ScalarValue::$SCALAR_TY(*v)).collect(),
                 }
             }};
+            ($INPUT:expr, $ARRAY_TY:ident, $SCALAR_TY:ident, $TZ:expr) => {{
+                TestCase {
+                    array: Arc::new($INPUT.iter().collect::<$ARRAY_TY>()),
+                    scalars: $INPUT
+                        .iter()
+                        .map(|v| ScalarValue::$SCALAR_TY(*v, $TZ))
+                        .collect(),
+                }
+            }};
         }
 
         macro_rules! make_str_test_case {
@@ -2263,12 +2610,28 @@ mod tests {
             make_binary_test_case!(str_vals, LargeBinaryArray, LargeBinary),
             make_test_case!(i32_vals, Date32Array, Date32),
             make_test_case!(i64_vals, Date64Array, Date64),
-            make_test_case!(i64_vals, TimestampSecondArray, TimestampSecond),
-            make_test_case!(i64_vals, TimestampMillisecondArray, TimestampMillisecond),
-            make_test_case!(i64_vals, TimestampMicrosecondArray, TimestampMicrosecond),
-            make_test_case!(i64_vals, TimestampNanosecondArray, TimestampNanosecond),
+            make_test_case!(i64_vals, TimestampSecondArray, TimestampSecond, None),
+            make_test_case!(
+                i64_vals,
+                TimestampMillisecondArray,
+                TimestampMillisecond,
+                None
+            ),
+            make_test_case!(
+                i64_vals,
+                TimestampMicrosecondArray,
+                TimestampMicrosecond,
+                None
+            ),
+            make_test_case!(
+                i64_vals,
+                TimestampNanosecondArray,
+                TimestampNanosecond,
+                None
+            ),
             make_test_case!(i32_vals, IntervalYearMonthArray, IntervalYearMonth),
             make_test_case!(i64_vals, IntervalDayTimeArray, IntervalDayTime),
+            make_test_case!(i128_vals, IntervalMonthDayNanoArray, IntervalMonthDayNano),
             make_str_dict_test_case!(str_vals, Int8Type, Utf8),
             make_str_dict_test_case!(str_vals, Int16Type, Utf8),
             make_str_dict_test_case!(str_vals, Int32Type, Utf8),
@@ -2308,6 +2671,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn scalar_timestamp_timezone_roundtrip() {
+        let tz = Some("+08:00".to_owned());
+        let scalars = vec![
+            ScalarValue::TimestampMillisecond(Some(1), tz.clone()),
+            ScalarValue::TimestampMillisecond(None, tz.clone()),
+        ];
+        let data_type = DataType::Timestamp(TimeUnit::Millisecond, tz.clone());
+        assert_eq!(scalars[0].get_datatype(), data_type);
+        assert!(scalars[1].is_null());
+
+        let array = ScalarValue::iter_to_array(scalars.clone()).unwrap();
+        assert_eq!(array.data_type(), &data_type);
+        for (index, scalar) in scalars.iter().enumerate() {
+            assert_eq!(&ScalarValue::try_from_array(&array, index).unwrap(), scalar);
+        }
+        assert_eq!(scalars[0].to_array_of_size(2).data_type(), &data_type);
+        assert_eq!(
+            ScalarValue::try_from(&data_type).unwrap(),
+            ScalarValue::TimestampMillisecond(None, tz.clone())
+        );
+
+        let list = ScalarValue::List(Some(Box::new(scalars)), Box::new(data_type));
+        assert_eq!(
+            ScalarValue::try_from_array(&list.to_array(), 0).unwrap(),
+            list
+        );
+    }
+
     #[test]
     fn scalar_partial_ordering() {
         use ScalarValue::*;
@@ -20,4 +20,5 @@
 
 pub mod parser;
 pub mod planner;
+pub(crate) mod subquery;
 pub(crate) mod utils;
@@ -25,7 +25,13 @@ use sqlparser::{
     parser::{Parser, ParserError},
     tokenizer::{Token, Tokenizer},
 };
+use std::any::Any;
+use std::fmt::Debug;
 use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::datasource::listing::Bucketing;
+use crate::logical_plan::ExplainFormat;
 
 // Use `Parser::expected` instead, if possible
 macro_rules! parser_err {
@@ -34,6 +40,51 @@ macro_rules! parser_err {
     };
 }
 
+/// Name of the function that the select items `[<qualifier>.]* EXCLUDE (<columns>)`
+/// are rewritten to, as `__wildcard_exclude(<qualifier>, <columns>)`, where the
+/// qualifier is the empty string for unqualified wildcards
+pub const WILDCARD_EXCLUDE: &str = "__wildcard_exclude";
+
+/// Name of the function that the table sample clauses
+/// `TABLESAMPLE <method> (<percentage> [PERCENT]) [REPEATABLE (<seed>)]` are
+/// rewritten to, as the table hint `WITH (__table_sample('<method>', <percentage>[, <seed>]))`
+pub const TABLE_SAMPLE: &str = "__table_sample";
+
+/// Name of the function that the aggregate function calls with a filter clause
+/// `<aggregate>(<args>) FILTER (WHERE <predicate>)` are rewritten to, as
+/// `__aggregate_filter(<aggregate>(<args>), <predicate>)`
+pub const AGGREGATE_FILTER: &str = "__aggregate_filter";
+
+/// Name of the function that the aggregate function calls with an ordering
+/// `<aggregate>(<args> ORDER BY <sort exprs>)` and the ordered-set aggregate
+/// function calls `<aggregate>(<args>) WITHIN GROUP (ORDER BY <sort exprs>)` are
+/// rewritten to, as `__aggregate_order_by(<aggregate>(<args>)[, <expr>, '<options>']*)`
+/// where the options are the `ASC`, `DESC` and `NULLS FIRST|LAST` of each sort
+/// expression
+pub const AGGREGATE_ORDER_BY: &str = "__aggregate_order_by";
+
+/// Name of the function that the window function calls with an exclusion clause
+/// `<function>(<args>) OVER (<window> EXCLUDE <exclusion>)` are rewritten to, as
+/// `__window_exclude(<function>(<args>) OVER (<window>), '<exclusion>')` where the
+/// exclusion is one of `CURRENT ROW`, `GROUP`, `TIES` and `NO OTHERS`
+pub const WINDOW_EXCLUDE: &str = "__window_exclude";
+
+/// Name of the function that the rows of the row-valued IN lists
+/// `(<exprs>) [NOT] IN ((<values>), ...)` are rewritten to, as
+/// `__row(<exprs>) [NOT] IN (__row(<values>), ...)`
+pub const ROW_VALUE: &str = "__row";
+
+/// Names of the functions the clauses are rewritten to, which the queries can't call
+/// themselves
+const REWRITTEN_FUNCTIONS: [&str; 6] = [
+    WILDCARD_EXCLUDE,
+    TABLE_SAMPLE,
+    AGGREGATE_FILTER,
+    AGGREGATE_ORDER_BY,
+    WINDOW_EXCLUDE,
+    ROW_VALUE,
+];
+
 /// Types of files to parse as DataFrames
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FileType {
@@ -77,6 +128,31 @@ pub struct CreateExternalTable {
     pub has_header: bool,
     /// Path to file
     pub location: String,
+    /// Bucketing of the files declared by `CLUSTERED BY (<columns>) INTO <n> BUCKETS`
+    pub bucketing: Option<Bucketing>,
+}
+
+/// DataFusion extension `EXPLAIN [ANALYZE] [VERBOSE] (FORMAT <format>, COSTS) <statement>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainStatement {
+    /// Run the plan and show its metrics?
+    pub analyze: bool,
+    /// Show the intermediate plans or the full metrics?
+    pub verbose: bool,
+    /// How the plans are formatted
+    pub format: ExplainFormat,
+    /// Show the estimated rows and bytes of the nodes of the physical plans?
+    pub costs: bool,
+    /// The explained statement
+    pub statement: Box<SQLStatement>,
+}
+
+/// The options of an EXPLAIN statement being parsed
+struct ExplainOptions {
+    analyze: bool,
+    verbose: bool,
+    format: Option<ExplainFormat>,
+    costs: bool,
 }
 
 /// DataFusion Statement representations.
@@ -88,11 +164,43 @@ pub enum Statement {
     Statement(Box<SQLStatement>),
     /// Extension: `CREATE EXTERNAL TABLE`
     CreateExternalTable(CreateExternalTable),
+    /// Extension: `EXPLAIN` with a `FORMAT` or `COSTS` option
+    Explain(ExplainStatement),
+    /// Extension: a custom statement parsed by a [`StatementParser`]
+    Custom(Arc<dyn CustomStatement>),
+}
+
+/// A custom statement, e.g. `VACUUM t` or `SHOW JOBS`, parsed by a
+/// [`StatementParser`]
+pub trait CustomStatement: Debug + Send + Sync {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Custom statements are equal when their debug representations are
+impl PartialEq for dyn CustomStatement {
+    fn eq(&self, other: &Self) -> bool {
+        format!("{:?}", self) == format!("{:?}", other)
+    }
+}
+
+/// Parses custom statements, extending the SQL supported by [`DFParser`]
+/// without forking it
+pub trait StatementParser {
+    /// Parses the statement starting at the next token of `parser` if it is one
+    /// of the custom statements of this parser, or returns `None` without
+    /// consuming any token otherwise, to parse it as a regular statement
+    fn parse_statement(
+        &self,
+        parser: &mut Parser<'_>,
+    ) -> Result<Option<Arc<dyn CustomStatement>>, ParserError>;
 }
 
 /// SQL Parser
 pub struct DFParser<'a> {
     parser: Parser<'a>,
+    /// Parser of the custom statements
+    statement_parser: Option<&'a dyn StatementParser>,
 }
 
 impl<'a> DFParser<'a> {
@@ -109,9 +217,16 @@ impl<'a> DFParser<'a> {
     ) -> Result<Self, ParserError> {
         let mut tokenizer = Tokenizer::new(dialect, sql);
         let tokens = tokenizer.tokenize()?;
+        check_rewritten_function_calls(&tokens)?;
+        let tokens = rewrite_row_in_list(rewrite_window_exclude(
+            rewrite_aggregate_filter(rewrite_aggregate_order_by(rewrite_table_sample(
+                rewrite_wildcard_exclude(tokens),
+            ))),
+        ));
 
         Ok(DFParser {
             parser: Parser::new(tokens, dialect),
+            statement_parser: None,
         })
     }
 
@@ -125,24 +240,39 @@ impl<'a> DFParser<'a> {
     pub fn parse_sql_with_dialect(
         sql: &str,
         dialect: &dyn Dialect,
+    ) -> Result<Vec<Statement>, ParserError> {
+        DFParser::new_with_dialect(sql, dialect)?.parse_statements()
+    }
+
+    /// Parse a SQL statement and produce a set of statements, whose custom
+    /// statements are parsed by `statement_parser`
+    pub fn parse_sql_with_statement_parser(
+        sql: &str,
+        dialect: &dyn Dialect,
+        statement_parser: &dyn StatementParser,
     ) -> Result<Vec<Statement>, ParserError> {
         let mut parser = DFParser::new_with_dialect(sql, dialect)?;
+        parser.statement_parser = Some(statement_parser);
+        parser.parse_statements()
+    }
+
+    fn parse_statements(mut self) -> Result<Vec<Statement>, ParserError> {
         let mut stmts = Vec::new();
         let mut expecting_statement_delimiter = false;
         loop {
             // ignore empty statements (between successive statement delimiters)
-            while parser.parser.consume_token(&Token::SemiColon) {
+            while self.parser.consume_token(&Token::SemiColon) {
                 expecting_statement_delimiter = false;
             }
 
-            if parser.parser.peek_token() == Token::EOF {
+            if self.parser.peek_token() == Token::EOF {
                 break;
             }
             if expecting_statement_delimiter {
-                return parser.expected("end of statement", parser.parser.peek_token());
+                return self.expected("end of statement", self.parser.peek_token());
             }
 
-            let statement = parser.parse_statement()?;
+            let statement = self.parse_statement()?;
             stmts.push(statement);
             expecting_statement_delimiter = true;
         }
@@ -156,6 +286,11 @@ impl<'a> DFParser<'a> {
 
     /// Parse a new expression
     pub fn parse_statement(&mut self) -> Result<Statement, ParserError> {
+        if let Some(statement_parser) = self.statement_parser {
+            if let Some(statement) = statement_parser.parse_statement(&mut self.parser)? {
+                return Ok(Statement::Custom(statement));
+            }
+        }
         match self.parser.peek_token() {
             Token::Word(w) => {
                 match w.keyword {
@@ -165,6 +300,12 @@ impl<'a> DFParser<'a> {
                         // use custom parsing
                         self.parse_create()
                     }
+                    Keyword::EXPLAIN => {
+                        // move one token forward
+                        self.parser.next_token();
+                        // use custom parsing
+                        self.parse_explain()
+                    }
                     _ => {
                         // use the native parser
                         Ok(Statement::Statement(Box::from(
@@ -191,6 +332,104 @@ impl<'a> DFParser<'a> {
         }
     }
 
+    /// Parse a SQL EXPLAIN statement, which besides the ANSI syntax accepts a
+    /// parenthesized list of options, as in
+    /// `EXPLAIN (ANALYZE, VERBOSE, FORMAT JSON, COSTS) <statement>`
+    pub fn parse_explain(&mut self) -> Result<Statement, ParserError> {
+        let mut options = ExplainOptions {
+            analyze: self.parser.parse_keyword(Keyword::ANALYZE),
+            verbose: self.parser.parse_keyword(Keyword::VERBOSE),
+            format: None,
+            costs: false,
+        };
+
+        if self.parser.consume_token(&Token::LParen) {
+            if self.parse_explain_option(&mut options)? {
+                while self.parser.consume_token(&Token::Comma) {
+                    if !self.parse_explain_option(&mut options)? {
+                        return self.expected(
+                            "ANALYZE, VERBOSE, FORMAT or COSTS",
+                            self.parser.peek_token(),
+                        );
+                    }
+                }
+                self.parser.expect_token(&Token::RParen)?;
+            } else {
+                // the explained statement is a parenthesized query
+                self.parser.prev_token();
+            }
+        }
+
+        let ExplainOptions {
+            analyze,
+            verbose,
+            format,
+            costs,
+        } = options;
+        let statement = Box::new(self.parser.parse_statement()?);
+        Ok(if format.is_some() || costs {
+            Statement::Explain(ExplainStatement {
+                analyze,
+                verbose,
+                format: format.unwrap_or_default(),
+                costs,
+                statement,
+            })
+        } else {
+            Statement::Statement(Box::new(SQLStatement::Explain {
+                describe_alias: false,
+                analyze,
+                verbose,
+                statement,
+            }))
+        })
+    }
+
+    /// Parse an option of EXPLAIN, returning false if the next token does not
+    /// start an option
+    fn parse_explain_option(
+        &mut self,
+        options: &mut ExplainOptions,
+    ) -> Result<bool, ParserError> {
+        match self.parser.peek_token() {
+            Token::Word(w) if w.keyword == Keyword::ANALYZE => {
+                self.parser.next_token();
+                options.analyze = self.parse_explain_bool();
+            }
+            Token::Word(w) if w.keyword == Keyword::VERBOSE => {
+                self.parser.next_token();
+                options.verbose = self.parse_explain_bool();
+            }
+            Token::Word(w) if w.value.eq_ignore_ascii_case("COSTS") => {
+                self.parser.next_token();
+                options.costs = self.parse_explain_bool();
+            }
+            Token::Word(w) if w.value.eq_ignore_ascii_case("FORMAT") => {
+                self.parser.next_token();
+                let name = self.parser.parse_identifier()?.value;
+                options.format = Some(ExplainFormat::from_str(&name).map_err(|_| {
+                    ParserError::ParserError(format!(
+                        "expect one of TEXT, JSON or DOT, found: {}",
+                        name
+                    ))
+                })?);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Parse the optional boolean value of an EXPLAIN option, which is true
+    /// when omitted
+    fn parse_explain_bool(&mut self) -> bool {
+        if self.parser.parse_keyword(Keyword::FALSE) {
+            false
+        } else {
+            self.parser.parse_keyword(Keyword::TRUE);
+            true
+        }
+    }
+
     // This is a copy of the equivalent implementation in sqlparser.
     fn parse_columns(
         &mut self,
@@ -268,6 +507,7 @@ impl<'a> DFParser<'a> {
         self.parser.expect_keyword(Keyword::TABLE)?;
         let table_name = self.parser.parse_object_name()?;
         let (columns, _) = self.parse_columns()?;
+        let bucketing = self.parse_bucketing()?;
         self.parser
             .expect_keywords(&[Keyword::STORED, Keyword::AS])?;
 
@@ -285,10 +525,36 @@ impl<'a> DFParser<'a> {
             file_type,
             has_header,
             location,
+            bucketing,
         };
         Ok(Statement::CreateExternalTable(create))
     }
 
+    /// Parses an optional `CLUSTERED BY (<columns>) INTO <n> BUCKETS` clause
+    fn parse_bucketing(&mut self) -> Result<Option<Bucketing>, ParserError> {
+        if !self.consume_token(&Token::make_keyword("CLUSTERED")) {
+            return Ok(None);
+        }
+        self.parser.expect_keyword(Keyword::BY)?;
+        self.parser.expect_token(&Token::LParen)?;
+        let columns = self
+            .parser
+            .parse_comma_separated(Parser::parse_identifier)?;
+        self.parser.expect_token(&Token::RParen)?;
+        self.parser.expect_keyword(Keyword::INTO)?;
+        let num_buckets = self.parser.parse_literal_uint()?;
+        if !self.consume_token(&Token::make_keyword("BUCKETS")) {
+            return self.expected("BUCKETS", self.parser.peek_token());
+        }
+        if num_buckets == 0 {
+            return parser_err!("The number of buckets must be greater than zero");
+        }
+        Ok(Some(Bucketing::new(
+            columns.into_iter().map(|c| c.value).collect(),
+            num_buckets as usize,
+        )))
+    }
+
     /// Parses the set of valid formats
     fn parse_file_format(&mut self) -> Result<FileType, ParserError> {
         match self.parser.next_token() {
@@ -315,6 +581,631 @@ impl<'a> DFParser<'a> {
     }
 }
 
+/// Rewrites the select items `[<qualifier>.]* EXCLUDE (<columns>)` and
+/// `[<qualifier>.]* EXCEPT (<columns>)`, which sqlparser cannot parse, into calls
+/// of the [`WILDCARD_EXCLUDE`] function that the SQL planner expands.
+/// Rejects the calls of the functions the clauses are rewritten to that are written
+/// in the query itself, which would otherwise be planned as the clauses they stand
+/// for, e.g. with arguments the clauses can't have. The names are reserved whether
+/// quoted or not and in any case.
+fn check_rewritten_function_calls(tokens: &[Token]) -> Result<(), ParserError> {
+    for (i, token) in tokens.iter().enumerate() {
+        let word = match token {
+            Token::Word(word) => word,
+            _ => continue,
+        };
+        let is_call = following(tokens, i)
+            .next()
+            .map_or(false, |next| tokens[next] == Token::LParen);
+        if is_call
+            && REWRITTEN_FUNCTIONS
+                .iter()
+                .any(|name| word.value.eq_ignore_ascii_case(name))
+        {
+            return parser_err!(format!(
+                "Function name {} is reserved for internal use",
+                word.value
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn rewrite_wildcard_exclude(tokens: Vec<Token>) -> Vec<Token> {
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let (columns_start, end) = match wildcard_exclude_columns(&tokens, i) {
+            Some(columns) => columns,
+            None => {
+                rewritten.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        // the qualifier is kept as an identifier, so that its quotes are preserved
+        let mut qualifier = vec![];
+        while let Some(period) = preceding(&rewritten, rewritten.len())
+            .filter(|period| rewritten[*period] == Token::Period)
+        {
+            let name = match preceding(&rewritten, period) {
+                Some(name) if matches!(rewritten[name], Token::Word(_)) => name,
+                _ => break,
+            };
+            qualifier.insert(0, rewritten[name].clone());
+            qualifier.insert(1, Token::Period);
+            rewritten.truncate(name);
+        }
+        qualifier.pop();
+        if qualifier.is_empty() {
+            qualifier.push(Token::SingleQuotedString(String::new()));
+        }
+        rewritten.push(Token::make_word(WILDCARD_EXCLUDE, None));
+        rewritten.push(Token::LParen);
+        rewritten.extend(qualifier);
+        rewritten.push(Token::Comma);
+        // the excluded columns, without the parentheses around them
+        rewritten.extend(tokens[columns_start + 1..end].iter().cloned());
+        rewritten.push(Token::RParen);
+        i = end + 1;
+    }
+    rewritten
+}
+
+/// Returns the positions of the parentheses around the column list if the
+/// wildcard at `start` is followed by an `EXCLUDE (...)` or `EXCEPT (...)` one.
+fn wildcard_exclude_columns(tokens: &[Token], start: usize) -> Option<(usize, usize)> {
+    if tokens[start] != Token::Mul {
+        return None;
+    }
+    let follows_select_item = match &tokens[preceding(tokens, start)?] {
+        Token::Comma | Token::Period => true,
+        Token::Word(w) => matches!(
+            w.keyword,
+            Keyword::SELECT | Keyword::DISTINCT | Keyword::ALL
+        ),
+        _ => false,
+    };
+    let next = following(tokens, start).take(3).collect::<Vec<_>>();
+    let token = |n: usize| next.get(n).map(|position| &tokens[*position]);
+    let is_exclude = match token(0) {
+        Some(Token::Word(w)) => {
+            w.keyword == Keyword::EXCEPT || w.value.eq_ignore_ascii_case("EXCLUDE")
+        }
+        _ => false,
+    };
+    // `SELECT * EXCEPT (SELECT ...)` is a set operation
+    let is_column_list = token(1) == Some(&Token::LParen)
+        && !matches!(token(2), Some(Token::Word(w)) if w.keyword == Keyword::SELECT);
+    if !follows_select_item || !is_exclude || !is_column_list {
+        return None;
+    }
+    closing_paren(tokens, next[1]).map(|end| (next[1], end))
+}
+
+/// Rewrites the table sample clauses
+/// `TABLESAMPLE <method> (<percentage> [PERCENT]) [REPEATABLE (<seed>)]`, which
+/// sqlparser cannot parse, into table hints calling the [`TABLE_SAMPLE`] function
+/// that the SQL planner turns into samples of the table.
+fn rewrite_table_sample(tokens: Vec<Token>) -> Vec<Token> {
+    let is_word = |token: Option<&Token>, word: &str| matches!(token, Some(Token::Word(w)) if w.value.eq_ignore_ascii_case(word));
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let next = following(&tokens, i).take(9).collect::<Vec<_>>();
+        let token = |n: usize| next.get(n).map(|position| &tokens[*position]);
+        let (method, percentage) = match (&tokens[i], token(0), token(1), token(2)) {
+            (
+                Token::Word(sample),
+                Some(Token::Word(method)),
+                Some(Token::LParen),
+                Some(percentage @ Token::Number(_, _)),
+            ) if sample.value.eq_ignore_ascii_case("TABLESAMPLE") => {
+                (method.value.to_uppercase(), percentage.clone())
+            }
+            _ => {
+                rewritten.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+        let mut n = 3;
+        if is_word(token(n), "PERCENT") {
+            n += 1;
+        }
+        if token(n) != Some(&Token::RParen) {
+            rewritten.push(tokens[i].clone());
+            i += 1;
+            continue;
+        }
+        let mut end = next[n] + 1;
+
+        rewritten.push(Token::make_keyword("WITH"));
+        rewritten.push(Token::LParen);
+        rewritten.push(Token::make_word(TABLE_SAMPLE, None));
+        rewritten.push(Token::LParen);
+        rewritten.push(Token::SingleQuotedString(method));
+        rewritten.push(Token::Comma);
+        rewritten.push(percentage);
+        if is_word(token(n + 1), "REPEATABLE")
+            && token(n + 2) == Some(&Token::LParen)
+            && token(n + 4) == Some(&Token::RParen)
+        {
+            if let Some(seed @ Token::Number(_, _)) = token(n + 3) {
+                rewritten.push(Token::Comma);
+                rewritten.push(seed.clone());
+                end = next[n + 4] + 1;
+            }
+        }
+        rewritten.push(Token::RParen);
+        rewritten.push(Token::RParen);
+        i = end;
+    }
+    rewritten
+}
+
+/// Rewrites the filter clauses of aggregate function calls
+/// `<aggregate>(<args>) FILTER (WHERE <predicate>)`, which sqlparser cannot parse,
+/// into calls of the [`AGGREGATE_FILTER`] function that the SQL planner turns into
+/// filtered aggregates.
+fn rewrite_aggregate_filter(tokens: Vec<Token>) -> Vec<Token> {
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let next = following(&tokens, i).take(2).collect::<Vec<_>>();
+        let token = |n: usize| next.get(n).map(|position| &tokens[*position]);
+        let is_filter = matches!(&tokens[i], Token::Word(w) if w.value.eq_ignore_ascii_case("FILTER"))
+            && token(0) == Some(&Token::LParen)
+            && matches!(token(1), Some(Token::Word(w)) if w.keyword == Keyword::WHERE);
+        let call_and_end = match is_filter {
+            true => function_call_start(&rewritten).zip(closing_paren(&tokens, next[0])),
+            false => None,
+        };
+        let (start, end) = match call_and_end {
+            Some(call_and_end) => call_and_end,
+            None => {
+                rewritten.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        let call = rewritten.split_off(start);
+        rewritten.push(Token::make_word(AGGREGATE_FILTER, None));
+        rewritten.push(Token::LParen);
+        rewritten.extend(call);
+        rewritten.push(Token::Comma);
+        // the predicate, without the parentheses and WHERE around it
+        rewritten.extend(rewrite_aggregate_filter(tokens[next[1] + 1..end].to_vec()));
+        rewritten.push(Token::RParen);
+        i = end + 1;
+    }
+    rewritten
+}
+
+/// Rewrites the orderings of aggregate function calls
+/// `<aggregate>(<args> ORDER BY <sort exprs>)` and
+/// `<aggregate>(<args>) WITHIN GROUP (ORDER BY <sort exprs>)`, which sqlparser
+/// cannot parse, into calls of the [`AGGREGATE_ORDER_BY`] function that the SQL
+/// planner turns into ordered aggregates.
+fn rewrite_aggregate_order_by(tokens: Vec<Token>) -> Vec<Token> {
+    let is_word = |token: Option<&Token>, word: &str| matches!(token, Some(Token::Word(w)) if w.value.eq_ignore_ascii_case(word));
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let next = following(&tokens, i).take(4).collect::<Vec<_>>();
+        let token = |n: usize| next.get(n).map(|position| &tokens[*position]);
+        let is_within_group = is_word(Some(&tokens[i]), "WITHIN")
+            && is_word(token(0), "GROUP")
+            && token(1) == Some(&Token::LParen)
+            && is_word(token(2), "ORDER")
+            && is_word(token(3), "BY");
+        let is_order_by = is_word(Some(&tokens[i]), "ORDER") && is_word(token(0), "BY");
+        let call = if is_within_group {
+            function_call_start(&rewritten).zip(
+                closing_paren(&tokens, next[1]).map(|end| (next[3] + 1..end, end + 1)),
+            )
+        } else if is_order_by {
+            aggregate_arguments_start(&rewritten).zip(
+                enclosing_paren_end(&tokens, i).map(|end| (next[0] + 1..end, end + 1)),
+            )
+        } else {
+            None
+        };
+        let sort_exprs = call.as_ref().and_then(|(_, (sort_exprs, _))| {
+            split_sort_exprs(&tokens[sort_exprs.clone()])
+        });
+        let (start, next, sort_exprs) = match (call, sort_exprs) {
+            (Some((start, (_, next))), Some(sort_exprs)) => (start, next, sort_exprs),
+            _ => {
+                rewritten.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        let call = rewritten.split_off(start);
+        rewritten.push(Token::make_word(AGGREGATE_ORDER_BY, None));
+        rewritten.push(Token::LParen);
+        rewritten.extend(call);
+        if is_order_by {
+            // close the arguments, the ORDER BY closed them in the query
+            rewritten.push(Token::RParen);
+        }
+        for (expr, options) in sort_exprs {
+            rewritten.push(Token::Comma);
+            rewritten.extend(rewrite_aggregate_order_by(expr.to_vec()));
+            rewritten.push(Token::Comma);
+            rewritten.push(Token::SingleQuotedString(options));
+        }
+        rewritten.push(Token::RParen);
+        i = next;
+    }
+    rewritten
+}
+
+/// Returns the position of the name of the function call whose arguments the
+/// tokens end within, if they do.
+fn aggregate_arguments_start(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().rev() {
+        match token {
+            Token::RParen => depth += 1,
+            Token::LParen if depth > 0 => depth -= 1,
+            Token::LParen => {
+                // window specifications and subqueries have their own ORDER BY
+                let is_subquery =
+                    match following(tokens, position).next().map(|next| &tokens[next]) {
+                        Some(Token::Word(w)) => matches!(
+                            w.keyword,
+                            Keyword::SELECT | Keyword::WITH | Keyword::VALUES
+                        ),
+                        Some(Token::LParen) => true,
+                        _ => false,
+                    };
+                return match is_subquery {
+                    true => None,
+                    false => function_name_start(tokens, position),
+                };
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the position of the parenthesis closing the innermost one that the
+/// token at `start` is within
+fn enclosing_paren_end(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 0 => return Some(position),
+            Token::RParen => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits the tokens of an ORDER BY clause into its sort expressions and their
+/// options, or returns `None` if one of them is empty
+fn split_sort_exprs(tokens: &[Token]) -> Option<Vec<(&[Token], String)>> {
+    split_top_level_commas(tokens)
+        .into_iter()
+        .map(|expr| {
+            let mut expr = trim_whitespace(expr);
+            let mut options = vec![];
+            if let [rest @ .., Token::Word(first_or_last)] = expr {
+                if let [rest @ .., Token::Word(nulls)] = trim_whitespace(rest) {
+                    if nulls.keyword == Keyword::NULLS
+                        && matches!(first_or_last.keyword, Keyword::FIRST | Keyword::LAST)
+                    {
+                        options.push(format!(
+                            "NULLS {}",
+                            first_or_last.value.to_uppercase()
+                        ));
+                        expr = trim_whitespace(rest);
+                    }
+                }
+            }
+            if let [rest @ .., Token::Word(direction)] = expr {
+                if matches!(direction.keyword, Keyword::ASC | Keyword::DESC) {
+                    options.insert(0, direction.value.to_uppercase());
+                    expr = trim_whitespace(rest);
+                }
+            }
+            match expr.is_empty() {
+                true => None,
+                false => Some((expr, options.join(" "))),
+            }
+        })
+        .collect()
+}
+
+/// Returns the position of the name of the function call that the last token
+/// other than whitespace closes, if it closes one.
+fn function_call_start(tokens: &[Token]) -> Option<usize> {
+    let end = preceding(tokens, tokens.len())?;
+    if tokens[end] != Token::RParen {
+        return None;
+    }
+    let mut depth = 0;
+    for (position, token) in tokens[..=end].iter().enumerate().rev() {
+        match token {
+            Token::RParen => depth += 1,
+            Token::LParen => {
+                depth -= 1;
+                if depth == 0 {
+                    return function_name_start(tokens, position);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the position of the start of the, possibly qualified, function name
+/// before the parenthesis at `paren`, if the parenthesis opens the arguments of
+/// a function call.
+fn function_name_start(tokens: &[Token], paren: usize) -> Option<usize> {
+    // keywords followed by a parenthesized expression, subquery or window
+    let is_name = |token: &Token| {
+        matches!(token, Token::Word(w) if !matches!(
+            w.keyword,
+            Keyword::OVER
+                | Keyword::AS
+                | Keyword::FROM
+                | Keyword::JOIN
+                | Keyword::IN
+                | Keyword::EXISTS
+                | Keyword::WHERE
+                | Keyword::AND
+                | Keyword::OR
+                | Keyword::NOT
+                | Keyword::ON
+                | Keyword::WHEN
+                | Keyword::THEN
+                | Keyword::ELSE
+                | Keyword::SELECT
+                | Keyword::HAVING
+                | Keyword::USING
+                | Keyword::VALUES
+        ))
+    };
+    let mut start = preceding(tokens, paren).filter(|name| is_name(&tokens[*name]))?;
+    while let Some(period) =
+        preceding(tokens, start).filter(|period| tokens[*period] == Token::Period)
+    {
+        match preceding(tokens, period) {
+            Some(qualifier) if matches!(tokens[qualifier], Token::Word(_)) => {
+                start = qualifier
+            }
+            _ => break,
+        }
+    }
+    Some(start)
+}
+
+/// Rewrites the exclusion clauses of the window specifications of window function
+/// calls `<function>(<args>) OVER (<window> EXCLUDE <exclusion>)`, which sqlparser
+/// cannot parse, into calls of the [`WINDOW_EXCLUDE`] function that the SQL planner
+/// turns into window functions excluding rows from their frames.
+fn rewrite_window_exclude(tokens: Vec<Token>) -> Vec<Token> {
+    let is_word = |token: Option<&Token>, word: &str| matches!(token, Some(Token::Word(w)) if w.value.eq_ignore_ascii_case(word));
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let next = following(&tokens, i).take(3).collect::<Vec<_>>();
+        let token = |n: usize| next.get(n).map(|position| &tokens[*position]);
+        // the exclusion and the number of its words
+        let exclusion = if !is_word(Some(&tokens[i]), "EXCLUDE") {
+            None
+        } else if is_word(token(0), "CURRENT") && is_word(token(1), "ROW") {
+            Some(("CURRENT ROW", 2))
+        } else if is_word(token(0), "NO") && is_word(token(1), "OTHERS") {
+            Some(("NO OTHERS", 2))
+        } else if is_word(token(0), "GROUP") {
+            Some(("GROUP", 1))
+        } else if is_word(token(0), "TIES") {
+            Some(("TIES", 1))
+        } else {
+            None
+        };
+        // the exclusion ends the window specification
+        let call = exclusion
+            .filter(|(_, n)| token(*n) == Some(&Token::RParen))
+            .zip(window_call_start(&rewritten));
+        let ((exclusion, n), start) = match call {
+            Some(call) => call,
+            None => {
+                rewritten.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        let call = rewritten.split_off(start);
+        rewritten.push(Token::make_word(WINDOW_EXCLUDE, None));
+        rewritten.push(Token::LParen);
+        rewritten.extend(call);
+        // close the window specification, the exclusion closed it in the query
+        rewritten.push(Token::RParen);
+        rewritten.push(Token::Comma);
+        rewritten.push(Token::SingleQuotedString(exclusion.to_owned()));
+        rewritten.push(Token::RParen);
+        i = next[n] + 1;
+    }
+    rewritten
+}
+
+/// Returns the position of the name of the window function call whose window
+/// specification the tokens end within, if they do.
+fn window_call_start(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().rev() {
+        match token {
+            Token::RParen => depth += 1,
+            Token::LParen if depth > 0 => depth -= 1,
+            Token::LParen => {
+                let over = preceding(tokens, position).filter(|over| {
+                    matches!(&tokens[*over], Token::Word(w) if w.keyword == Keyword::OVER)
+                })?;
+                return function_call_start(&tokens[..over]);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Rewrites the row-valued IN lists `(<exprs>) [NOT] IN ((<values>), ...)`, which
+/// sqlparser cannot parse, into IN lists of calls of the [`ROW_VALUE`] function
+/// that the SQL planner turns into comparisons of the rows.
+fn rewrite_row_in_list(tokens: Vec<Token>) -> Vec<Token> {
+    let is_keyword = |token: Option<&Token>, keyword: Keyword| matches!(token, Some(Token::Word(w)) if w.keyword == keyword);
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        // the parentheses of function calls are not rows
+        let is_call = matches!(
+            preceding(&rewritten, rewritten.len()).map(|last| &rewritten[last]),
+            Some(Token::Word(w)) if !matches!(
+                w.keyword,
+                Keyword::WHERE
+                    | Keyword::AND
+                    | Keyword::OR
+                    | Keyword::NOT
+                    | Keyword::ON
+                    | Keyword::WHEN
+                    | Keyword::THEN
+                    | Keyword::ELSE
+                    | Keyword::SELECT
+                    | Keyword::HAVING
+            )
+        );
+        let row_end = match tokens[i] {
+            Token::LParen if !is_call => closing_paren(&tokens, i)
+                .filter(|end| split_top_level_commas(&tokens[i + 1..*end]).len() > 1),
+            _ => None,
+        };
+        let next =
+            row_end.map_or(vec![], |end| following(&tokens, end).take(3).collect());
+        let token = |n: usize| next.get(n).map(|position| &tokens[*position]);
+        let list_start = if is_keyword(token(0), Keyword::IN) {
+            next.get(1).copied()
+        } else if is_keyword(token(0), Keyword::NOT) && is_keyword(token(1), Keyword::IN)
+        {
+            next.get(2).copied()
+        } else {
+            None
+        };
+        let is_list = list_start.map_or(false, |start| {
+            let first = following(&tokens, start).next().map(|first| &tokens[first]);
+            tokens[start] == Token::LParen
+                && !is_keyword(first, Keyword::SELECT)
+                && !is_keyword(first, Keyword::WITH)
+        });
+        let (list_start, list_end) = match (
+            list_start,
+            list_start.and_then(|s| closing_paren(&tokens, s)),
+        ) {
+            (Some(list_start), Some(list_end)) if is_list => (list_start, list_end),
+            _ => {
+                rewritten.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        rewritten.push(Token::make_word(ROW_VALUE, None));
+        rewritten.extend(tokens[i..=list_start].iter().cloned());
+        for (n, value) in split_top_level_commas(&tokens[list_start + 1..list_end])
+            .into_iter()
+            .enumerate()
+        {
+            if n > 0 {
+                rewritten.push(Token::Comma);
+            }
+            let value = trim_whitespace(value);
+            if value.first() == Some(&Token::LParen) {
+                rewritten.push(Token::make_word(ROW_VALUE, None));
+            }
+            rewritten.extend(value.iter().cloned());
+        }
+        rewritten.push(Token::RParen);
+        i = list_end + 1;
+    }
+    rewritten
+}
+
+/// Splits the tokens at the commas that are not within parentheses
+fn split_top_level_commas(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (position, token) in tokens.iter().enumerate() {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            Token::Comma if depth == 0 => {
+                parts.push(&tokens[start..position]);
+                start = position + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&tokens[start..]);
+    parts
+}
+
+/// Returns the position of the parenthesis closing the one at `start`
+fn closing_paren(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the positions of the tokens after the one at `position` that are not
+/// whitespace or comments
+fn following(tokens: &[Token], position: usize) -> impl Iterator<Item = usize> + '_ {
+    (position + 1..tokens.len())
+        .filter(move |next| !matches!(tokens[*next], Token::Whitespace(_)))
+}
+
+/// Returns the position of the last token before `position` that is not
+/// whitespace or a comment
+fn preceding(tokens: &[Token], position: usize) -> Option<usize> {
+    tokens[..position]
+        .iter()
+        .rposition(|token| !matches!(token, Token::Whitespace(_)))
+}
+
+/// Returns the tokens without the whitespace and comments around them
+fn trim_whitespace(tokens: &[Token]) -> &[Token] {
+    let is_whitespace = |token: &Token| matches!(token, Token::Whitespace(_));
+    let start = tokens
+        .iter()
+        .position(|token| !is_whitespace(token))
+        .unwrap_or(tokens.len());
+    let end = preceding(tokens, tokens.len()).map_or(start, |last| last + 1);
+    &tokens[start..end]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -375,6 +1266,7 @@ mod tests {
             file_type: FileType::CSV,
             has_header: false,
             location: "foo.csv".into(),
+            bucketing: None,
         });
         expect_parse_ok(sql, expected)?;
 
@@ -390,6 +1282,7 @@ mod tests {
                 file_type: FileType::CSV,
                 has_header: true,
                 location: "foo.csv".into(),
+                bucketing: None,
             });
             expect_parse_ok(sql, expected)?;
         }
@@ -402,6 +1295,7 @@ mod tests {
             file_type: FileType::Parquet,
             has_header: false,
             location: "foo.parquet".into(),
+            bucketing: None,
         });
         expect_parse_ok(sql, expected)?;
 
@@ -413,6 +1307,7 @@ mod tests {
             file_type: FileType::Parquet,
             has_header: false,
             location: "foo.parquet".into(),
+            bucketing: None,
         });
         expect_parse_ok(sql, expected)?;
 
@@ -424,6 +1319,19 @@ mod tests {
             file_type: FileType::Avro,
             has_header: false,
             location: "foo.avro".into(),
+            bucketing: None,
+        });
+        expect_parse_ok(sql, expected)?;
+
+        // positive case: bucketed files
+        let sql = "CREATE EXTERNAL TABLE t CLUSTERED BY (c1, \"C2\") INTO 8 BUCKETS STORED AS PARQUET LOCATION 'foo.parquet'";
+        let expected = Statement::CreateExternalTable(CreateExternalTable {
+            name: "t".into(),
+            columns: vec![],
+            file_type: FileType::Parquet,
+            has_header: false,
+            location: "foo.parquet".into(),
+            bucketing: Some(Bucketing::new(vec!["c1".into(), "C2".into()], 8)),
         });
         expect_parse_ok(sql, expected)?;
 
@@ -432,6 +1340,311 @@ mod tests {
             "CREATE EXTERNAL TABLE t(c1 int) STORED AS UNKNOWN_TYPE LOCATION 'foo.csv'";
         expect_parse_error(sql, "expect one of PARQUET, AVRO, NDJSON, or CSV");
 
+        // Error cases: missing or empty buckets
+        let sql = "CREATE EXTERNAL TABLE t CLUSTERED BY (c1) INTO 8 STORED AS PARQUET LOCATION 'foo.parquet'";
+        expect_parse_error(sql, "Expected BUCKETS");
+        let sql = "CREATE EXTERNAL TABLE t CLUSTERED BY (c1) INTO 0 BUCKETS STORED AS PARQUET LOCATION 'foo.parquet'";
+        expect_parse_error(sql, "The number of buckets must be greater than zero");
+
+        Ok(())
+    }
+
+    #[test]
+    fn wildcard_exclude() -> Result<(), ParserError> {
+        let cases = vec![
+            (
+                "SELECT t.* EXCLUDE (a, t.b), c FROM t",
+                "SELECT __wildcard_exclude(t, a, t.b), c FROM t",
+            ),
+            (
+                "SELECT DISTINCT * EXCEPT (a) FROM t",
+                "SELECT DISTINCT __wildcard_exclude('', a) FROM t",
+            ),
+            (
+                "SELECT \"T\".* EXCLUDE (a) FROM \"T\"",
+                "SELECT __wildcard_exclude(\"T\", a) FROM \"T\"",
+            ),
+            (
+                "SELECT * FROM t EXCEPT (SELECT * FROM u)",
+                "SELECT * FROM t EXCEPT (SELECT * FROM u)",
+            ),
+        ];
+        for (sql, expected) in cases {
+            let statements = DFParser::parse_sql(sql)?;
+            match &statements[0] {
+                Statement::Statement(statement) => {
+                    assert_eq!(statement.to_string(), expected)
+                }
+                other => panic!("Expected a query, found: {:?}", other),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn table_sample() -> Result<(), ParserError> {
+        let cases = vec![
+            (
+                "SELECT * FROM t TABLESAMPLE BERNOULLI (10 PERCENT)",
+                "SELECT * FROM t WITH (__table_sample('BERNOULLI', 10))",
+            ),
+            (
+                "SELECT * FROM t AS u tablesample system (2.5) REPEATABLE (42) WHERE a > 1",
+                "SELECT * FROM t AS u WITH (__table_sample('SYSTEM', 2.5, 42)) WHERE a > 1",
+            ),
+        ];
+        for (sql, expected) in cases {
+            let statements = DFParser::parse_sql(sql)?;
+            match &statements[0] {
+                Statement::Statement(statement) => {
+                    assert_eq!(statement.to_string(), expected)
+                }
+                other => panic!("Expected a query, found: {:?}", other),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_filter() -> Result<(), ParserError> {
+        let cases = vec![
+            (
+                "SELECT COUNT(*) FILTER (WHERE a > 1), SUM(b) FROM t",
+                "SELECT __aggregate_filter(COUNT(*), a > 1), SUM(b) FROM t",
+            ),
+            (
+                "SELECT sum(DISTINCT abs(b)) filter (where (a) in (1, 2)) FROM t",
+                "SELECT __aggregate_filter(sum(DISTINCT abs(b)), (a) IN (1, 2)) FROM t",
+            ),
+        ];
+        for (sql, expected) in cases {
+            let statements = DFParser::parse_sql(sql)?;
+            match &statements[0] {
+                Statement::Statement(statement) => {
+                    assert_eq!(statement.to_string(), expected)
+                }
+                other => panic!("Expected a query, found: {:?}", other),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_order_by() -> Result<(), ParserError> {
+        let cases = vec![
+            (
+                "SELECT array_agg(a ORDER BY b DESC, c NULLS FIRST) FROM t",
+                "SELECT __aggregate_order_by(array_agg(a), b, 'DESC', c, 'NULLS FIRST') FROM t",
+            ),
+            (
+                "SELECT percentile_cont(0.5) WITHIN GROUP (ORDER BY a) FILTER (WHERE b > 1) FROM t",
+                "SELECT __aggregate_filter(__aggregate_order_by(percentile_cont(0.5), a, ''), b > 1) FROM t",
+            ),
+            (
+                "SELECT row_number() OVER (ORDER BY a) FROM t",
+                "SELECT row_number() OVER (ORDER BY a) FROM t",
+            ),
+            (
+                "SELECT a FROM t WHERE a IN (SELECT b FROM u ORDER BY b)",
+                "SELECT a FROM t WHERE a IN (SELECT b FROM u ORDER BY b)",
+            ),
+        ];
+        for (sql, expected) in cases {
+            let statements = DFParser::parse_sql(sql)?;
+            match &statements[0] {
+                Statement::Statement(statement) => {
+                    assert_eq!(statement.to_string(), expected)
+                }
+                other => panic!("Expected a query, found: {:?}", other),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn row_in_list() -> Result<(), ParserError> {
+        let cases = vec![
+            (
+                "SELECT * FROM t WHERE (a, b) IN ((1, 2), (3, 4))",
+                "SELECT * FROM t WHERE __row(a, b) IN (__row(1, 2), __row(3, 4))",
+            ),
+            (
+                "SELECT * FROM t WHERE c AND (a, f(b, 1)) NOT IN ((1, 'x'))",
+                "SELECT * FROM t WHERE c AND __row(a, f(b, 1)) NOT IN (__row(1, 'x'))",
+            ),
+            (
+                "SELECT * FROM t WHERE coalesce(a, b) IN (1, 2)",
+                "SELECT * FROM t WHERE coalesce(a, b) IN (1, 2)",
+            ),
+        ];
+        for (sql, expected) in cases {
+            let statements = DFParser::parse_sql(sql)?;
+            match &statements[0] {
+                Statement::Statement(statement) => {
+                    assert_eq!(statement.to_string(), expected)
+                }
+                other => panic!("Expected a query, found: {:?}", other),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn window_exclude() -> Result<(), ParserError> {
+        let cases = vec![
+            (
+                "SELECT sum(a) OVER (ORDER BY b ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING EXCLUDE CURRENT ROW) FROM t",
+                "SELECT __window_exclude(sum(a) OVER (ORDER BY b ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING), 'CURRENT ROW') FROM t",
+            ),
+            (
+                "SELECT count(*) OVER (PARTITION BY c ORDER BY b GROUPS 1 PRECEDING exclude ties), b FROM t",
+                "SELECT __window_exclude(count(*) OVER (PARTITION BY c ORDER BY b GROUPS 1 PRECEDING), 'TIES'), b FROM t",
+            ),
+            (
+                "SELECT first_value(a) OVER (\n  ORDER BY b RANGE 1 PRECEDING\n  EXCLUDE GROUP\n) FROM t",
+                "SELECT __window_exclude(first_value(a) OVER (ORDER BY b RANGE 1 PRECEDING), 'GROUP') FROM t",
+            ),
+        ];
+        for (sql, expected) in cases {
+            let statements = DFParser::parse_sql(sql)?;
+            match &statements[0] {
+                Statement::Statement(statement) => {
+                    assert_eq!(statement.to_string(), expected)
+                }
+                other => panic!("Expected a query, found: {:?}", other),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn mixed_rewrites() -> Result<(), ParserError> {
+        let cases = vec![
+            (
+                "SELECT t.* EXCLUDE (a), array_agg(b ORDER BY c DESC) FILTER (WHERE d > 1) \
+                 FROM t TABLESAMPLE SYSTEM (10) WHERE (a, b) IN ((1, 2))",
+                "SELECT __wildcard_exclude(t, a), \
+                 __aggregate_filter(__aggregate_order_by(array_agg(b), c, 'DESC'), d > 1) \
+                 FROM t WITH (__table_sample('SYSTEM', 10)) WHERE __row(a, b) IN (__row(1, 2))",
+            ),
+            (
+                "SELECT\n  * EXCLUDE ( a ) -- the rest\nFROM t /* sampled */ TABLESAMPLE\n  BERNOULLI ( 5 PERCENT ) REPEATABLE ( 1 )",
+                "SELECT __wildcard_exclude('', a) FROM t WITH (__table_sample('BERNOULLI', 5, 1))",
+            ),
+            (
+                "SELECT count(*)\n  FILTER (WHERE a > 1), s.f(a)  FILTER ( WHERE b ) FROM t",
+                "SELECT __aggregate_filter(count(*), a > 1), __aggregate_filter(s.f(a), b) FROM t",
+            ),
+            (
+                "SELECT percentile_cont(0.5) WITHIN GROUP ( ORDER BY a DESC NULLS\nLAST ) FROM t",
+                "SELECT __aggregate_order_by(percentile_cont(0.5), a, 'DESC NULLS LAST') FROM t",
+            ),
+            (
+                "SELECT * FROM t WHERE ( a , b ) NOT IN ( ( 1 , 2 ) )",
+                "SELECT * FROM t WHERE __row(a, b) NOT IN (__row(1, 2))",
+            ),
+            (
+                "SELECT sum(a) OVER ( ORDER BY b ), '* EXCLUDE (a)' FROM t",
+                "SELECT sum(a) OVER (ORDER BY b), '* EXCLUDE (a)' FROM t",
+            ),
+        ];
+        for (sql, expected) in cases {
+            let statements = DFParser::parse_sql(sql)?;
+            match &statements[0] {
+                Statement::Statement(statement) => {
+                    assert_eq!(statement.to_string(), expected)
+                }
+                other => panic!("Expected a query, found: {:?}", other),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rewritten_function_calls() {
+        expect_parse_error(
+            "SELECT __aggregate_filter(count(*), a > 1) FROM t",
+            "Function name __aggregate_filter is reserved",
+        );
+        expect_parse_error(
+            "SELECT * FROM t WHERE \"__row\" (a, b) IN ((1, 2))",
+            "Function name __row is reserved",
+        );
+        expect_parse_error(
+            "SELECT * FROM t WITH (__TABLE_SAMPLE('SYSTEM', 10))",
+            "Function name __TABLE_SAMPLE is reserved",
+        );
+        // the names are only reserved for functions
+        assert!(DFParser::parse_sql("SELECT __row FROM t").is_ok());
+    }
+
+    #[test]
+    fn explain_format() -> Result<(), ParserError> {
+        let cases = vec![
+            (
+                "EXPLAIN (FORMAT JSON) SELECT 1",
+                false,
+                false,
+                ExplainFormat::Json,
+                false,
+            ),
+            (
+                "EXPLAIN ANALYZE (format dot) SELECT 1",
+                true,
+                false,
+                ExplainFormat::Graphviz,
+                false,
+            ),
+            (
+                "EXPLAIN (ANALYZE, VERBOSE TRUE, FORMAT TEXT) SELECT 1",
+                true,
+                true,
+                ExplainFormat::Indent,
+                false,
+            ),
+            (
+                "EXPLAIN (COSTS) SELECT 1",
+                false,
+                false,
+                ExplainFormat::Indent,
+                true,
+            ),
+            (
+                "EXPLAIN VERBOSE (FORMAT JSON, costs true) SELECT 1",
+                false,
+                true,
+                ExplainFormat::Json,
+                true,
+            ),
+        ];
+        for (sql, analyze, verbose, format, costs) in cases {
+            let statements = DFParser::parse_sql(sql)?;
+            match &statements[0] {
+                Statement::Explain(explain) => {
+                    assert_eq!(explain.analyze, analyze);
+                    assert_eq!(explain.verbose, verbose);
+                    assert_eq!(explain.format, format);
+                    assert_eq!(explain.costs, costs);
+                    assert_eq!(explain.statement.to_string(), "SELECT 1");
+                }
+                other => panic!("Expected an explain, found: {:?}", other),
+            }
+        }
+
+        // without a format, the statement is parsed as usual
+        let statements = DFParser::parse_sql("EXPLAIN VERBOSE (SELECT 1)")?;
+        match &statements[0] {
+            Statement::Statement(statement) => assert!(matches!(
+                **statement,
+                SQLStatement::Explain { verbose: true, .. }
+            )),
+            other => panic!("Expected an explain, found: {:?}", other),
+        }
+
+        expect_parse_error(
+            "EXPLAIN (FORMAT XML) SELECT 1",
+            "expect one of TEXT, JSON or DOT, found: XML",
+        );
         Ok(())
     }
 }
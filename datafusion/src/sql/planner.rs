@@ -25,17 +25,24 @@ use std::{convert::TryInto, vec};
 
 use crate::catalog::TableReference;
 use crate::datasource::TableProvider;
-use crate::logical_plan::window_frames::{WindowFrame, WindowFrameUnits};
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::window_frames::{
+    check_range_offset_type, WindowFrame, WindowFrameExclusion, WindowFrameUnits,
+};
 use crate::logical_plan::Expr::Alias;
 use crate::logical_plan::{
-    and, builder::expand_wildcard, col, lit, normalize_col, union_with_alias, Column,
+    and,
+    builder::{expand_qualified_wildcard, expand_wildcard, using_column_expr},
+    col, inline_single_use_ctes, lit, normalize_col, union_with_alias, Column,
     CreateExternalTable as PlanCreateExternalTable, CreateMemoryTable, DFSchema,
-    DFSchemaRef, DropTable, Expr, LogicalPlan, LogicalPlanBuilder, Operator, PlanType,
-    ToDFSchema, ToStringifiedPlan,
+    DFSchemaRef, DropTable, ExplainFormat, Expr, InsertInto, LogicalPlan,
+    LogicalPlanBuilder, MaterializedCte, Operator, PlanType, Sample, SampleMethod,
+    ToDFSchema, ToStringifiedPlan, WriteMode,
 };
+use crate::optimizer::simplify_expressions::ConstEvaluator;
 use crate::optimizer::utils::exprlist_to_columns;
 use crate::prelude::JoinType;
-use crate::scalar::ScalarValue;
+use crate::scalar::{interval_dt_value, ScalarValue};
 use crate::sql::utils::make_decimal_type;
 use crate::{
     error::{DataFusionError, Result},
@@ -43,8 +50,12 @@ use crate::{
 };
 use crate::{
     physical_plan::udf::ScalarUDF,
+    physical_plan::udtf::TableUDF,
     physical_plan::{aggregates, functions, window_functions},
-    sql::parser::{CreateExternalTable, FileType, Statement as DFStatement},
+    sql::parser::{
+        CreateExternalTable, FileType, Statement as DFStatement, AGGREGATE_FILTER,
+        AGGREGATE_ORDER_BY, ROW_VALUE, TABLE_SAMPLE, WILDCARD_EXCLUDE, WINDOW_EXCLUDE,
+    },
 };
 use arrow::datatypes::*;
 use hashbrown::HashMap;
@@ -74,15 +85,46 @@ use crate::logical_plan::plan::{Analyze, Explain};
 pub trait ContextProvider {
     /// Getter for a datasource
     fn get_table_provider(&self, name: TableReference) -> Option<Arc<dyn TableProvider>>;
+    /// Checks that the query may SELECT from the table, returning a
+    /// [`DataFusionError::PermissionDenied`] if it may not
+    fn authorize_select(&self, _name: TableReference) -> Result<()> {
+        Ok(())
+    }
+    /// Applies the row level security and column masking policies of the table
+    /// to `scan`, the scan of the table
+    fn apply_table_policies(
+        &self,
+        _name: TableReference,
+        scan: LogicalPlan,
+    ) -> Result<LogicalPlan> {
+        Ok(scan)
+    }
     /// Getter for a UDF description
     fn get_function_meta(&self, name: &str) -> Option<Arc<ScalarUDF>>;
     /// Getter for a UDAF description
     fn get_aggregate_meta(&self, name: &str) -> Option<Arc<AggregateUDF>>;
+    /// Getter for a table function description
+    fn get_table_function_meta(&self, _name: &str) -> Option<Arc<TableUDF>> {
+        None
+    }
+}
+
+/// How the SQL planner treats the case of identifiers, such as the names of
+/// tables, columns and aliases
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdentifierNormalization {
+    /// Identifiers are used as written, quoted or not
+    CaseSensitive,
+    /// Unquoted identifiers are folded to lowercase, as in Postgres, and quoted
+    /// identifiers are used as written
+    Lowercase,
 }
 
 /// SQL query planner
 pub struct SqlToRel<'a, S: ContextProvider> {
     schema_provider: &'a S,
+    materialize_ctes: bool,
+    identifier_normalization: IdentifierNormalization,
 }
 
 fn plan_key(key: Value) -> ScalarValue {
@@ -114,7 +156,46 @@ fn plan_indexed(expr: Expr, mut keys: Vec<Value>) -> Expr {
 impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     /// Create a new query planner
     pub fn new(schema_provider: &'a S) -> Self {
-        SqlToRel { schema_provider }
+        SqlToRel {
+            schema_provider,
+            materialize_ctes: false,
+            identifier_normalization: IdentifierNormalization::CaseSensitive,
+        }
+    }
+
+    /// Plan common table expressions that are referenced more than once as
+    /// [`MaterializedCte`] nodes, so that they are only executed once
+    pub fn with_materialize_ctes(mut self, materialize_ctes: bool) -> Self {
+        self.materialize_ctes = materialize_ctes;
+        self
+    }
+
+    /// Customize the case handling of identifiers
+    pub fn with_identifier_normalization(
+        mut self,
+        identifier_normalization: IdentifierNormalization,
+    ) -> Self {
+        self.identifier_normalization = identifier_normalization;
+        self
+    }
+
+    /// Returns the name `id` refers to under the identifier normalization of the
+    /// planner
+    fn normalize_ident(&self, id: &Ident) -> String {
+        match (self.identifier_normalization, id.quote_style) {
+            (IdentifierNormalization::Lowercase, None) => id.value.to_lowercase(),
+            _ => id.value.clone(),
+        }
+    }
+
+    /// Returns `name` with all of its parts normalized and unquoted
+    fn normalize_object_name(&self, name: &ObjectName) -> ObjectName {
+        ObjectName(
+            name.0
+                .iter()
+                .map(|id| Ident::new(self.normalize_ident(id)))
+                .collect(),
+        )
     }
 
     /// Generate a logical plan from an DataFusion SQL statement
@@ -122,6 +203,17 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         match statement {
             DFStatement::CreateExternalTable(s) => self.external_table_to_plan(s),
             DFStatement::Statement(s) => self.sql_statement_to_plan(s),
+            DFStatement::Explain(s) => self.explain_statement_to_plan(
+                s.verbose,
+                s.analyze,
+                s.format,
+                s.costs,
+                &s.statement,
+            ),
+            DFStatement::Custom(s) => Err(DataFusionError::NotImplemented(format!(
+                "Custom statement {:?} must be planned by its statement extension",
+                s
+            ))),
         }
     }
 
@@ -133,7 +225,13 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 statement,
                 analyze,
                 describe_alias: _,
-            } => self.explain_statement_to_plan(*verbose, *analyze, statement),
+            } => self.explain_statement_to_plan(
+                *verbose,
+                *analyze,
+                ExplainFormat::Indent,
+                false,
+                statement,
+            ),
             Statement::Query(query) => self.query_to_plan(query),
             Statement::ShowVariable { variable } => self.show_variable_to_plan(variable),
             Statement::CreateTable {
@@ -161,7 +259,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 let plan = self.query_to_plan(query)?;
 
                 Ok(LogicalPlan::CreateMemoryTable(CreateMemoryTable {
-                    name: name.to_string(),
+                    name: self.normalize_object_name(name).to_string(),
                     input: Arc::new(plan),
                 }))
             }
@@ -170,6 +268,22 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     .to_string(),
             )),
 
+            Statement::Insert {
+                table_name,
+                columns,
+                overwrite: true,
+                source,
+                partitioned,
+                after_columns,
+                ..
+            } if columns.is_empty() && after_columns.is_empty() => {
+                self.insert_to_plan(table_name, partitioned.as_deref(), source)
+            }
+            Statement::Insert { .. } => Err(DataFusionError::NotImplemented(
+                "Only `INSERT OVERWRITE [TABLE] table_name [PARTITION (column, ...)] SELECT ...` statement is supported"
+                    .to_string(),
+            )),
+
             Statement::Drop {
                 object_type: ObjectType::Table,
                 if_exists,
@@ -180,7 +294,9 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             // We don't support cascade and purge for now.
             {
                 Ok(LogicalPlan::DropTable(DropTable {
-                    name: names.get(0).unwrap().to_string(),
+                    name: self
+                        .normalize_object_name(names.get(0).unwrap())
+                        .to_string(),
                     if_exist: *if_exists,
                     schema: DFSchemaRef::new(DFSchema::empty()),
                 }))
@@ -199,9 +315,84 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         }
     }
 
+    /// Generate a logical plan writing the rows of `source` to a table and replacing
+    /// its data, or the data of the written partitions for a `PARTITION` clause. The
+    /// columns of the rows are matched to the columns of the table by position.
+    fn insert_to_plan(
+        &self,
+        table_name: &ObjectName,
+        partitioned: Option<&[SQLExpr]>,
+        source: &Query,
+    ) -> Result<LogicalPlan> {
+        let table_name = self.normalize_object_name(table_name);
+        let table_schema = self
+            .schema_provider
+            .get_table_provider((&table_name).try_into()?)
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!("No table named '{}'", table_name))
+            })?
+            .schema();
+        let mode = match partitioned {
+            None => WriteMode::Overwrite,
+            Some(columns) => WriteMode::OverwritePartitions(
+                columns
+                    .iter()
+                    .map(|column| match column {
+                        SQLExpr::Identifier(ident) => Ok(self.normalize_ident(ident)),
+                        _ => Err(DataFusionError::NotImplemented(format!(
+                            "Unsupported partition {}, only the dynamic partitions of \
+                             `PARTITION (column, ...)` can be overwritten",
+                            column
+                        ))),
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+        };
+
+        let plan = self.query_to_plan(source)?;
+        if plan.schema().fields().len() != table_schema.fields().len() {
+            return Err(DataFusionError::Plan(format!(
+                "Table '{}' has {} columns but the query returns {}",
+                table_name,
+                table_schema.fields().len(),
+                plan.schema().fields().len()
+            )));
+        }
+        let columns = plan
+            .schema()
+            .fields()
+            .iter()
+            .zip(table_schema.fields())
+            .map(|(field, table_field)| {
+                let expr = Expr::Column(field.qualified_column());
+                let expr = if field.data_type() == table_field.data_type() {
+                    expr
+                } else {
+                    Expr::Cast {
+                        expr: Box::new(expr),
+                        data_type: table_field.data_type().clone(),
+                    }
+                };
+                expr.alias(table_field.name())
+            })
+            .collect::<Vec<_>>();
+        let input = LogicalPlanBuilder::from(plan).project(columns)?.build()?;
+
+        Ok(LogicalPlan::InsertInto(InsertInto {
+            table_name: table_name.to_string(),
+            mode,
+            input: Arc::new(input),
+        }))
+    }
+
     /// Generate a logic plan from an SQL query
     pub fn query_to_plan(&self, query: &Query) -> Result<LogicalPlan> {
-        self.query_to_plan_with_alias(query, None, &mut HashMap::new())
+        let plan = self.query_to_plan_with_alias(query, None, &mut HashMap::new())?;
+        if self.materialize_ctes {
+            inline_single_use_ctes(plan)
+        } else {
+            Ok(plan)
+        }
     }
 
     /// Generate a logic plan from an SQL query with optional alias
@@ -216,13 +407,19 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             // Process CTEs from top to bottom
             // do not allow self-references
             for cte in &with.cte_tables {
+                let cte_name = self.normalize_ident(&cte.alias.name);
                 // create logical plan & pass backreferencing CTEs
                 let logical_plan = self.query_to_plan_with_alias(
                     &cte.query,
-                    Some(cte.alias.name.value.clone()),
+                    Some(cte_name.clone()),
                     &mut ctes.clone(),
                 )?;
-                ctes.insert(cte.alias.name.value.clone(), logical_plan);
+                let logical_plan = if self.materialize_ctes {
+                    MaterializedCte::new_plan(&cte_name, logical_plan)
+                } else {
+                    logical_plan
+                };
+                ctes.insert(cte_name, logical_plan);
             }
         }
         let plan = self.set_expr_to_plan(set_expr, alias, ctes)?;
@@ -257,18 +454,14 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                         let union_plan = union_with_alias(left_plan, right_plan, alias)?;
                         LogicalPlanBuilder::from(union_plan).distinct()?.build()
                     }
-                    (SetOperator::Intersect, true) => {
-                        LogicalPlanBuilder::intersect(left_plan, right_plan, true)
-                    }
-                    (SetOperator::Intersect, false) => {
-                        LogicalPlanBuilder::intersect(left_plan, right_plan, false)
-                    }
-                    (SetOperator::Except, true) => {
-                        LogicalPlanBuilder::except(left_plan, right_plan, true)
-                    }
-                    (SetOperator::Except, false) => {
-                        LogicalPlanBuilder::except(left_plan, right_plan, false)
-                    }
+                    (SetOperator::Intersect, all) => with_alias(
+                        LogicalPlanBuilder::intersect(left_plan, right_plan, *all)?,
+                        alias,
+                    ),
+                    (SetOperator::Except, all) => with_alias(
+                        LogicalPlanBuilder::except(left_plan, right_plan, *all)?,
+                        alias,
+                    ),
                 }
             }
             _ => Err(DataFusionError::NotImplemented(format!(
@@ -289,6 +482,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             file_type,
             has_header,
             location,
+            bucketing,
         } = statement;
 
         // semantic checks
@@ -308,21 +502,37 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
         let schema = self.build_schema(columns)?;
 
+        if let Some(bucketing) = bucketing {
+            if !columns.is_empty() {
+                for column in &bucketing.columns {
+                    schema.field_with_name(column).map_err(|_| {
+                        DataFusionError::Plan(format!(
+                            "Bucket column {} is not a column of the table",
+                            column
+                        ))
+                    })?;
+                }
+            }
+        }
+
         Ok(LogicalPlan::CreateExternalTable(PlanCreateExternalTable {
             schema: schema.to_dfschema_ref()?,
             name: name.clone(),
             location: location.clone(),
             file_type: *file_type,
             has_header: *has_header,
+            bucketing: bucketing.clone(),
         }))
     }
 
-    /// Generate a plan for EXPLAIN ... that will print out a plan
-    ///
+    /// Generate a plan for EXPLAIN ... that will print out a plan, where the
+    /// physical plans show the estimated rows and bytes of each node if `costs`
     pub fn explain_statement_to_plan(
         &self,
         verbose: bool,
         analyze: bool,
+        format: ExplainFormat,
+        costs: bool,
         statement: &Statement,
     ) -> Result<LogicalPlan> {
         let plan = self.sql_statement_to_plan(statement)?;
@@ -334,15 +544,19 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             Ok(LogicalPlan::Analyze(Analyze {
                 verbose,
                 input: plan,
+                format,
                 schema,
             }))
         } else {
-            let stringified_plans =
-                vec![plan.to_stringified(PlanType::InitialLogicalPlan)];
+            let stringified_plans = vec![
+                plan.to_stringified_with_format(PlanType::InitialLogicalPlan, format)
+            ];
             Ok(LogicalPlan::Explain(Explain {
                 verbose,
                 plan,
                 stringified_plans,
+                format,
+                costs,
                 schema,
             }))
         }
@@ -357,7 +571,11 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 .options
                 .iter()
                 .any(|x| x.option == ColumnOption::Null);
-            fields.push(Field::new(&column.name.value, data_type, allow_null));
+            fields.push(Field::new(
+                &self.normalize_ident(&column.name),
+                data_type,
+                allow_null,
+            ));
         }
 
         Ok(Schema::new(fields))
@@ -572,7 +790,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             JoinConstraint::Using(idents) => {
                 let keys: Vec<Column> = idents
                     .iter()
-                    .map(|x| Column::from_name(x.value.clone()))
+                    .map(|x| Column::from_name(self.normalize_ident(x)))
                     .collect();
                 LogicalPlanBuilder::from(left)
                     .join_using(&right, join_type, keys)?
@@ -596,32 +814,60 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         ctes: &mut HashMap<String, LogicalPlan>,
     ) -> Result<LogicalPlan> {
         let (plan, alias) = match relation {
-            TableFactor::Table { name, alias, .. } => {
+            TableFactor::Table {
+                name,
+                alias,
+                args,
+                with_hints,
+            } => {
+                let name = self.normalize_object_name(name);
                 let table_name = name.to_string();
                 let cte = ctes.get(&table_name);
-                (
-                    match (
-                        cte,
-                        self.schema_provider.get_table_provider(name.try_into()?),
-                    ) {
-                        (Some(cte_plan), _) => Ok(cte_plan.clone()),
-                        (_, Some(provider)) => LogicalPlanBuilder::scan(
+                if cte.is_none() && args.is_empty() {
+                    self.schema_provider.authorize_select((&name).try_into()?)?;
+                }
+                let plan = match (
+                    cte,
+                    self.schema_provider.get_table_provider((&name).try_into()?),
+                ) {
+                    (Some(cte_plan), _) if args.is_empty() => Ok(cte_plan.clone()),
+                    (_, Some(provider)) if args.is_empty() => {
+                        let scan = LogicalPlanBuilder::scan(
                             // take alias into account to support `JOIN table1 as table2`
                             alias
                                 .as_ref()
-                                .map(|a| a.name.value.as_str())
-                                .unwrap_or(&table_name),
+                                .map(|a| self.normalize_ident(&a.name))
+                                .unwrap_or(table_name),
                             provider,
                             None,
                         )?
-                        .build(),
-                        (None, None) => Err(DataFusionError::Plan(format!(
-                            "Table or CTE with name '{}' not found",
-                            name
-                        ))),
-                    }?,
-                    alias,
-                )
+                        .build()?;
+                        self.schema_provider
+                            .apply_table_policies((&name).try_into()?, scan)
+                    }
+                    // a table function, which may be called without arguments
+                    // when no table has its name
+                    _ => {
+                        match self.schema_provider.get_table_function_meta(&table_name) {
+                            Some(function) => self.table_function_to_plan(
+                                &function,
+                                args,
+                                alias
+                                    .as_ref()
+                                    .map(|a| self.normalize_ident(&a.name))
+                                    .unwrap_or(table_name),
+                            ),
+                            None if args.is_empty() => Err(DataFusionError::Plan(
+                                format!("Table or CTE with name '{}' not found", name),
+                            )),
+                            None => Err(DataFusionError::Plan(format!(
+                                "Table function with name '{}' not found",
+                                name
+                            ))),
+                        }
+                    }
+                }?;
+                (self.table_sample_to_plan(with_hints, plan)?, alias)
             }
             TableFactor::Derived {
                 subquery, alias, ..
@@ -634,7 +880,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 }
                 let logical_plan = self.query_to_plan_with_alias(
                     subquery,
-                    alias.as_ref().map(|a| a.name.value.to_string()),
+                    alias.as_ref().map(|a| self.normalize_ident(&a.name)),
                     ctes,
                 )?;
                 (
@@ -645,7 +891,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                             .fields()
                             .iter()
                             .map(|field| col(field.name())),
-                        alias.as_ref().map(|a| a.name.value.to_string()),
+                        alias.as_ref().map(|a| self.normalize_ident(&a.name)),
                     )?,
                     alias,
                 )
@@ -675,12 +921,12 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             } else {
                 Ok(LogicalPlanBuilder::from(plan.clone())
                     .project_with_alias(
-                        plan.schema()
-                            .fields()
-                            .iter()
-                            .zip(columns_alias.iter())
-                            .map(|(field, ident)| col(field.name()).alias(&ident.value)),
-                        Some(alias.clone().name.value),
+                        plan.schema().fields().iter().zip(columns_alias.iter()).map(
+                            |(field, ident)| {
+                                col(field.name()).alias(&self.normalize_ident(ident))
+                            },
+                        ),
+                        Some(self.normalize_ident(&alias.name)),
                     )?
                     .build()?)
             }
@@ -689,6 +935,150 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         }
     }
 
+    /// Plans the call of the table function `function` with the arguments `args`,
+    /// which must be constants, as a scan of the table it returns
+    fn table_function_to_plan(
+        &self,
+        function: &TableUDF,
+        args: &[FunctionArg],
+        table_name: String,
+    ) -> Result<LogicalPlan> {
+        let schema = DFSchema::empty();
+        let execution_props = ExecutionProps::new();
+        let args = args
+            .iter()
+            .map(|arg| {
+                let expr = match arg {
+                    FunctionArg::Unnamed(expr) => {
+                        self.sql_expr_to_logical_expr(expr, &schema)?
+                    }
+                    FunctionArg::Named { .. } => {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "Named arguments of table function '{}' are not supported",
+                            function.name
+                        )))
+                    }
+                };
+                match expr.rewrite(&mut ConstEvaluator::new(&execution_props))? {
+                    Expr::Literal(value) => Ok(value),
+                    _ => Err(DataFusionError::Plan(format!(
+                        "Arguments of table function '{}' must be constants",
+                        function.name
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        LogicalPlanBuilder::scan(table_name, function.call(&args)?, None)?.build()
+    }
+
+    /// Wraps `plan` into a [`Sample`] if the table hints `with_hints` contain the
+    /// [`TABLE_SAMPLE`] call that `TABLESAMPLE` clauses are rewritten to
+    fn table_sample_to_plan(
+        &self,
+        with_hints: &[SQLExpr],
+        plan: LogicalPlan,
+    ) -> Result<LogicalPlan> {
+        let args = with_hints.iter().find_map(|hint| match hint {
+            SQLExpr::Function(function) if function.name.to_string() == TABLE_SAMPLE => {
+                Some(&function.args)
+            }
+            _ => None,
+        });
+        let args = match args {
+            Some(args) => args,
+            None => return Ok(plan),
+        };
+        let invalid = || DataFusionError::Plan("Invalid TABLESAMPLE clause".to_string());
+        let method = match args.get(0) {
+            Some(FunctionArg::Unnamed(SQLExpr::Value(Value::SingleQuotedString(
+                method,
+            )))) => SampleMethod::from_str(method)?,
+            _ => return Err(invalid()),
+        };
+        let percentage = match args.get(1) {
+            Some(FunctionArg::Unnamed(SQLExpr::Value(Value::Number(n, _)))) => {
+                f64::from_str(n).map_err(|_| invalid())?
+            }
+            _ => return Err(invalid()),
+        };
+        let seed = match args.get(2) {
+            None => None,
+            Some(FunctionArg::Unnamed(SQLExpr::Value(Value::Number(n, _)))) => {
+                Some(u64::from_str(n).map_err(|_| {
+                    DataFusionError::Plan(format!(
+                        "The REPEATABLE seed of TABLESAMPLE must be a non-negative integer, got {}",
+                        n
+                    ))
+                })?)
+            }
+            _ => return Err(invalid()),
+        };
+        if !(0.0..=100.0).contains(&percentage) {
+            return Err(DataFusionError::Plan(format!(
+                "The TABLESAMPLE percentage must be between 0 and 100, got {}",
+                percentage
+            )));
+        }
+        Sample::try_new_plan(plan, method, percentage / 100.0, seed)
+    }
+
+    /// Joins `plan` with the uncorrelated `IN` subqueries of its `WHERE` clause, with
+    /// semi joins for `IN` and null aware anti joins for `NOT IN`
+    fn plan_in_subqueries(
+        &self,
+        plan: LogicalPlan,
+        in_subqueries: Vec<(&SQLExpr, &Query, bool)>,
+        ctes: &mut HashMap<String, LogicalPlan>,
+    ) -> Result<LogicalPlan> {
+        let mut plan = plan;
+        for (i, (expr, subquery, negated)) in in_subqueries.into_iter().enumerate() {
+            let left_key = match self.sql_to_rex(expr, plan.schema())? {
+                Expr::Column(column) => column,
+                other => {
+                    return Err(DataFusionError::NotImplemented(format!(
+                        "IN subqueries are only supported on columns, not {:?}",
+                        other
+                    )))
+                }
+            };
+            let subquery = self.query_to_plan_with_alias(
+                subquery,
+                Some(format!("__subquery{}", i)),
+                &mut ctes.clone(),
+            )?;
+            let subquery_schema = subquery.schema().clone();
+            if subquery_schema.fields().len() != 1 {
+                return Err(DataFusionError::Plan(
+                    "IN subqueries must return a single column".to_string(),
+                ));
+            }
+            let field = subquery_schema.field(0);
+            let left_type = plan.schema().field_from_column(&left_key)?.data_type();
+            let subquery = if field.data_type() == left_type {
+                subquery
+            } else {
+                let key = Expr::Column(field.qualified_column())
+                    .cast_to(left_type, &subquery_schema)?
+                    .alias(field.name());
+                project_with_alias(
+                    subquery,
+                    vec![key],
+                    field.qualifier().map(|q| q.to_string()),
+                )?
+            };
+            let right_key = subquery.schema().field(0).qualified_column();
+            let join_type = if negated {
+                JoinType::NullAwareAnti
+            } else {
+                JoinType::Semi
+            };
+            plan = LogicalPlanBuilder::from(plan)
+                .join(&subquery, join_type, (vec![left_key], vec![right_key]))?
+                .build()?;
+        }
+        Ok(plan)
+    }
+
     /// Generate a logic plan from an SQL select
     fn select_to_plan(
         &self,
@@ -698,7 +1088,13 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     ) -> Result<LogicalPlan> {
         let plans = self.plan_from_tables(&select.from, ctes)?;
 
-        let plan = match &select.selection {
+        // the IN subqueries that were not folded into lists are planned as joins
+        let (selection, in_subqueries) = match &select.selection {
+            Some(selection) => split_in_subqueries(selection),
+            None => (None, vec![]),
+        };
+
+        let plan = match &selection {
             Some(predicate_expr) => {
                 // build join schema
                 let mut fields = vec![];
@@ -768,7 +1164,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 }
             }
         };
-        let plan = plan?;
+        let plan = self.plan_in_subqueries(plan?, in_subqueries, ctes)?;
 
         // The SELECT expressions, with wildcards expanded.
         let select_exprs = self.prepare_select_exprs(&plan, select)?;
@@ -905,31 +1301,119 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         select: &Select,
     ) -> Result<Vec<Expr>> {
         let input_schema = plan.schema();
-        let projection = &select.projection;
-        projection
+        let mut exprs = vec![];
+        for item in &select.projection {
+            match item {
+                SelectItem::Wildcard => {
+                    check_wildcard_from(select)?;
+                    exprs.extend(expand_wildcard(input_schema, plan)?)
+                }
+                SelectItem::QualifiedWildcard(qualifier) => {
+                    exprs.extend(expand_qualified_wildcard(
+                        &self.normalize_object_name(qualifier).to_string(),
+                        input_schema,
+                    )?)
+                }
+                SelectItem::UnnamedExpr(SQLExpr::Function(function))
+                    if function.name.to_string() == WILDCARD_EXCLUDE =>
+                {
+                    exprs.extend(self.wildcard_exclude_to_exprs(
+                        &function.args,
+                        plan,
+                        select,
+                    )?)
+                }
+                // an unqualified column of a USING join may refer to both join columns
+                SelectItem::UnnamedExpr(SQLExpr::Identifier(_)) => {
+                    let expr = self.sql_select_to_rex(item, input_schema)?;
+                    exprs.push(using_column_expr(normalize_col(expr, plan)?, plan)?)
+                }
+                _ => {
+                    let expr = self.sql_select_to_rex(item, input_schema)?;
+                    exprs.push(normalize_col(expr, plan)?)
+                }
+            }
+        }
+        Ok(exprs)
+    }
+
+    /// Expands `[<qualifier>.]* EXCLUDE (<columns>)`, which the parser rewrites
+    /// into a call of [`WILDCARD_EXCLUDE`], into the columns of the wildcard that
+    /// are not excluded.
+    fn wildcard_exclude_to_exprs(
+        &self,
+        args: &[FunctionArg],
+        plan: &LogicalPlan,
+        select: &Select,
+    ) -> Result<Vec<Expr>> {
+        let input_schema = plan.schema();
+        let args = args
             .iter()
-            .map(|expr| self.sql_select_to_rex(expr, input_schema))
-            .collect::<Result<Vec<Expr>>>()?
-            .into_iter()
-            .map(|expr| {
-                Ok(match expr {
-                    Expr::Wildcard => {
-                        if select.from.is_empty() {
-                            return Err(DataFusionError::Plan(
-                                "SELECT * with no tables specified is not valid"
-                                    .to_string(),
-                            ));
-                        }
-                        expand_wildcard(input_schema, plan)?
-                    }
-                    _ => vec![normalize_col(expr, plan)?],
-                })
-            })
-            .flat_map(|res| match res {
-                Ok(v) => v.into_iter().map(Ok).collect(),
-                Err(e) => vec![Err(e)],
+            .map(|arg| match arg {
+                FunctionArg::Unnamed(arg) => Ok(arg),
+                _ => Err(DataFusionError::Internal(format!(
+                    "Invalid argument {} of {}",
+                    arg, WILDCARD_EXCLUDE
+                ))),
             })
-            .collect::<Result<Vec<Expr>>>()
+            .collect::<Result<Vec<_>>>()?;
+        let exprs = match args.first() {
+            Some(SQLExpr::Value(Value::SingleQuotedString(qualifier)))
+                if qualifier.is_empty() =>
+            {
+                check_wildcard_from(select)?;
+                expand_wildcard(input_schema, plan)?
+            }
+            Some(SQLExpr::Identifier(id)) => {
+                expand_qualified_wildcard(&self.normalize_ident(id), input_schema)?
+            }
+            Some(SQLExpr::CompoundIdentifier(ids)) => expand_qualified_wildcard(
+                &self
+                    .normalize_object_name(&ObjectName(ids.clone()))
+                    .to_string(),
+                input_schema,
+            )?,
+            _ => {
+                return Err(DataFusionError::Internal(format!(
+                    "Missing wildcard qualifier of {}",
+                    WILDCARD_EXCLUDE
+                )))
+            }
+        };
+
+        let excluded = args[1..]
+            .iter()
+            .map(
+                |arg| match self.sql_expr_to_logical_expr(arg, input_schema)? {
+                    Expr::Column(col) => Ok(col),
+                    _ => Err(DataFusionError::Plan(format!(
+                        "Expected a column in EXCLUDE list, found {}",
+                        arg
+                    ))),
+                },
+            )
+            .collect::<Result<Vec<_>>>()?;
+        let is_excluded = |expr: &Expr, col: &Column| match expr {
+            Expr::Column(c) => {
+                c.name == col.name
+                    && (col.relation.is_none() || c.relation == col.relation)
+            }
+            Expr::Alias(_, name) => col.relation.is_none() && *name == col.name,
+            _ => false,
+        };
+        if let Some(col) = excluded
+            .iter()
+            .find(|col| !exprs.iter().any(|expr| is_excluded(expr, col)))
+        {
+            return Err(DataFusionError::Plan(format!(
+                "Column {} in EXCLUDE list not found in wildcard",
+                col
+            )));
+        }
+        Ok(exprs
+            .into_iter()
+            .filter(|expr| !excluded.iter().any(|col| is_excluded(expr, col)))
+            .collect())
     }
 
     /// Wrap a plan in a projection
@@ -1120,7 +1604,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             SelectItem::UnnamedExpr(expr) => self.sql_to_rex(expr, schema),
             SelectItem::ExprWithAlias { expr, alias } => Ok(Alias(
                 Box::new(self.sql_to_rex(expr, schema)?),
-                alias.value.clone(),
+                self.normalize_ident(alias),
             )),
             SelectItem::Wildcard => Ok(Expr::Wildcard),
             SelectItem::QualifiedWildcard(_) => Err(DataFusionError::NotImplemented(
@@ -1267,6 +1751,9 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                         SQLExpr::Value(Value::SingleQuotedString(ref s)) => {
                             Ok(lit(s.clone()))
                         }
+                        SQLExpr::Value(Value::HexStringLiteral(ref s)) => {
+                            parse_sql_hex_string(s)
+                        }
                         SQLExpr::Value(Value::Null) => {
                             Ok(Expr::Literal(ScalarValue::Utf8(None)))
                         }
@@ -1294,6 +1781,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         match sql {
             SQLExpr::Value(Value::Number(n, _)) => parse_sql_number(n),
             SQLExpr::Value(Value::SingleQuotedString(ref s)) => Ok(lit(s.clone())),
+            SQLExpr::Value(Value::HexStringLiteral(ref s)) => parse_sql_hex_string(s),
             SQLExpr::Value(Value::Boolean(n)) => Ok(lit(*n)),
             SQLExpr::Value(Value::Null) => Ok(Expr::Literal(ScalarValue::Utf8(None))),
             SQLExpr::Extract { field, expr } => Ok(Expr::ScalarFunction {
@@ -1325,13 +1813,13 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 } else {
                     // create a column expression based on raw user input, this column will be
                     // normalized with qualifer later by the SQL planner.
-                    Ok(col(&id.value))
+                    Ok(col(&self.normalize_ident(id)))
                 }
             }
 
             SQLExpr::MapAccess { ref column, keys } => {
                 if let SQLExpr::Identifier(ref id) = column.as_ref() {
-                    Ok(plan_indexed(col(&id.value), keys.clone()))
+                    Ok(plan_indexed(col(&self.normalize_ident(id)), keys.clone()))
                 } else {
                     Err(DataFusionError::NotImplemented(format!(
                         "map access requires an identifier, found column {} instead",
@@ -1346,12 +1834,39 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     var_names.push(id.value.clone());
                 }
                 if &var_names[0][0..1] == "@" {
-                    Ok(Expr::ScalarVariable(var_names))
-                } else if var_names.len() == 2 {
+                    return Ok(Expr::ScalarVariable(var_names));
+                }
+                let names: Vec<_> =
+                    ids.iter().map(|id| self.normalize_ident(id)).collect();
+                let is_qualified_column = schema
+                    .field_with_qualified_name(&names[0], &names[1])
+                    .is_ok();
+                let is_struct_column = schema
+                    .fields_with_unqualified_name(&names[0])
+                    .iter()
+                    .any(|field| matches!(field.data_type(), DataType::Struct(_)));
+                if names.len() == 2 && (is_qualified_column || !is_struct_column) {
                     // table.column identifier
-                    let name = var_names.pop().unwrap();
-                    let relation = Some(var_names.pop().unwrap());
-                    Ok(Expr::Column(Column { relation, name }))
+                    Ok(Expr::Column(Column {
+                        relation: Some(names[0].clone()),
+                        name: names[1].clone(),
+                    }))
+                } else if is_qualified_column || is_struct_column {
+                    // fields of a struct column, e.g. `t.s.a` or `s.a.b`
+                    let (column, fields) = if is_qualified_column {
+                        let column = Column {
+                            relation: Some(names[0].clone()),
+                            name: names[1].clone(),
+                        };
+                        (column, &names[2..])
+                    } else {
+                        (Column::from_name(&names[0]), &names[1..])
+                    };
+                    let keys = fields
+                        .iter()
+                        .map(|field| Value::SingleQuotedString(field.clone()))
+                        .collect();
+                    Ok(plan_indexed(Expr::Column(column), keys))
                 } else {
                     Err(DataFusionError::NotImplemented(format!(
                         "Unsupported compound identifier '{:?}'",
@@ -1463,6 +1978,12 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 ref list,
                 ref negated,
             } => {
+                if let SQLExpr::Function(function) = expr.as_ref() {
+                    if function.name.to_string() == ROW_VALUE {
+                        return self
+                            .row_in_list_to_expr(function, list, *negated, schema);
+                    }
+                }
                 let list_expr = list
                     .iter()
                     .map(|e| self.sql_expr_to_logical_expr(e, schema))
@@ -1505,6 +2026,24 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 Ok(Expr::ScalarFunction { fun, args })
             }
 
+            SQLExpr::Function(function)
+                if function.name.to_string() == AGGREGATE_FILTER =>
+            {
+                self.aggregate_filter_to_expr(function, schema)
+            }
+
+            SQLExpr::Function(function)
+                if function.name.to_string() == AGGREGATE_ORDER_BY =>
+            {
+                self.aggregate_order_by_to_expr(function, schema)
+            }
+
+            SQLExpr::Function(function)
+                if function.name.to_string() == WINDOW_EXCLUDE =>
+            {
+                self.window_exclude_to_expr(function, schema)
+            }
+
             SQLExpr::Function(function) => {
                 let name = if function.name.0.len() > 1 {
                     // DF doesn't handle compound identifiers
@@ -1522,7 +2061,18 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
                 // first, scalar built-in
                 if let Ok(fun) = functions::BuiltinScalarFunction::from_str(&name) {
-                    let args = self.function_args_to_expr(function, schema)?;
+                    let mut args = self.function_args_to_expr(function, schema)?;
+
+                    // `window(ts, '5 minutes')` is sugar for `window(ts, INTERVAL '5 minutes')`
+                    if fun == functions::BuiltinScalarFunction::Window {
+                        if let Some(Expr::Literal(ScalarValue::Utf8(Some(duration)))) =
+                            args.get(1).cloned()
+                        {
+                            args[1] = self.sql_interval_to_literal(
+                                &duration, &None, &None, &None, &None,
+                            )?;
+                        }
+                    }
 
                     return Ok(Expr::ScalarFunction { fun, args });
                 };
@@ -1544,14 +2094,16 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                         .as_ref()
                         .map(|window_frame| {
                             let window_frame: WindowFrame = window_frame.clone().try_into()?;
-                            if WindowFrameUnits::Range == window_frame.units
-                                && order_by.len() != 1
-                            {
-                                Err(DataFusionError::Plan(format!(
-                                    "With window frame of type RANGE, the order by expression must be of length 1, got {}", order_by.len())))
-                            } else {
-                                Ok(window_frame)
+                            if WindowFrameUnits::Range == window_frame.units {
+                                if order_by.len() != 1 {
+                                    return Err(DataFusionError::Plan(format!(
+                                        "With window frame of type RANGE, the order by expression must be of length 1, got {}", order_by.len())));
+                                }
+                                if window_frame.has_offset() {
+                                    check_range_offset_type(&order_by[0].get_type(schema)?)?;
+                                }
                             }
+                            Ok(window_frame)
                         })
                         .transpose()?;
                     let fun = window_functions::WindowFunction::from_str(&name)?;
@@ -1596,6 +2148,8 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                         fun,
                         distinct: function.distinct,
                         args,
+                        filter: None,
+                        order_by: vec![],
                     });
                 };
 
@@ -1628,6 +2182,245 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         }
     }
 
+    /// Plans the row-valued IN list `(<exprs>) [NOT] IN ((<values>), ...)` that the
+    /// parser rewrote into calls of [`ROW_VALUE`], as the disjunction of the
+    /// comparisons with each row. When not negated, it is also conjoined with the IN
+    /// lists of the values of each column, which can be evaluated with hash sets and
+    /// used to prune scans.
+    fn row_in_list_to_expr(
+        &self,
+        row: &sqlparser::ast::Function,
+        list: &[SQLExpr],
+        negated: bool,
+        schema: &DFSchema,
+    ) -> Result<Expr> {
+        let row_values = |function: &sqlparser::ast::Function| {
+            function
+                .args
+                .iter()
+                .map(|arg| match arg {
+                    FunctionArg::Unnamed(value) => {
+                        self.sql_expr_to_logical_expr(value, schema)
+                    }
+                    FunctionArg::Named { .. } => Err(DataFusionError::Plan(format!(
+                        "Unexpected named value in the row {}",
+                        function
+                    ))),
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let exprs = row_values(row)?;
+        let rows = list
+            .iter()
+            .map(|value| match value {
+                SQLExpr::Function(function) if function.name.to_string() == ROW_VALUE => {
+                    row_values(function)
+                }
+                _ => Ok(vec![]),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if rows.is_empty() || rows.iter().any(|row| row.len() != exprs.len()) {
+            return Err(DataFusionError::Plan(format!(
+                "The IN list of a row of {} values must only have rows of {} values",
+                exprs.len(),
+                exprs.len()
+            )));
+        }
+
+        let equal_rows = rows
+            .iter()
+            .map(|row| {
+                exprs
+                    .iter()
+                    .zip(row)
+                    .map(|(expr, value)| expr.clone().eq(value.clone()))
+                    .reduce(and)
+                    .unwrap()
+            })
+            .reduce(|left, right| left.or(right))
+            .unwrap();
+        if negated {
+            return Ok(Expr::Not(Box::new(equal_rows)));
+        }
+        if rows.len() == 1 {
+            return Ok(equal_rows);
+        }
+        // the values of each column of a row in the list are in the values of the
+        // column, which do not change the result of the comparisons of the rows
+        Ok(exprs
+            .iter()
+            .enumerate()
+            .map(|(i, expr)| {
+                expr.clone()
+                    .in_list(rows.iter().map(|row| row[i].clone()).collect(), false)
+            })
+            .chain(iter::once(equal_rows))
+            .reduce(and)
+            .unwrap())
+    }
+
+    /// Plans the aggregate function call with a filter clause that the parser
+    /// rewrote into a call of [`AGGREGATE_FILTER`]
+    fn aggregate_filter_to_expr(
+        &self,
+        function: &sqlparser::ast::Function,
+        schema: &DFSchema,
+    ) -> Result<Expr> {
+        let (aggregate, predicate) = match function.args.as_slice() {
+            [FunctionArg::Unnamed(aggregate @ SQLExpr::Function(_)), FunctionArg::Unnamed(predicate)]
+                if function.over.is_none() =>
+            {
+                (aggregate, predicate)
+            }
+            _ => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported FILTER clause in {}",
+                    function
+                )))
+            }
+        };
+        match self.sql_expr_to_logical_expr(aggregate, schema)? {
+            Expr::AggregateFunction {
+                fun,
+                args,
+                distinct,
+                filter: None,
+                order_by,
+            } => Ok(Expr::AggregateFunction {
+                fun,
+                args,
+                distinct,
+                filter: Some(Box::new(
+                    self.sql_expr_to_logical_expr(predicate, schema)?,
+                )),
+                order_by,
+            }),
+            _ => Err(DataFusionError::NotImplemented(format!(
+                "FILTER clauses are only supported by built-in aggregate functions, not {}",
+                aggregate
+            ))),
+        }
+    }
+
+    /// Plans the window function call with an exclusion clause that the parser
+    /// rewrote into a call of [`WINDOW_EXCLUDE`]
+    fn window_exclude_to_expr(
+        &self,
+        function: &sqlparser::ast::Function,
+        schema: &DFSchema,
+    ) -> Result<Expr> {
+        let (window_function, exclusion) = match function.args.as_slice() {
+            [FunctionArg::Unnamed(window_function @ SQLExpr::Function(_)), FunctionArg::Unnamed(SQLExpr::Value(Value::SingleQuotedString(
+                exclusion,
+            )))] if function.over.is_none() => (window_function, exclusion),
+            _ => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported EXCLUDE clause in {}",
+                    function
+                )))
+            }
+        };
+        let exclusion = match exclusion.as_str() {
+            "NO OTHERS" => WindowFrameExclusion::NoOthers,
+            "CURRENT ROW" => WindowFrameExclusion::CurrentRow,
+            "GROUP" => WindowFrameExclusion::Group,
+            "TIES" => WindowFrameExclusion::Ties,
+            _ => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported EXCLUDE clause in {}",
+                    function
+                )))
+            }
+        };
+        match self.sql_expr_to_logical_expr(window_function, schema)? {
+            Expr::WindowFunction {
+                fun,
+                args,
+                partition_by,
+                order_by,
+                window_frame: Some(window_frame),
+            } => Ok(Expr::WindowFunction {
+                fun,
+                args,
+                partition_by,
+                order_by,
+                window_frame: Some(window_frame.with_exclusion(exclusion)),
+            }),
+            _ => Err(DataFusionError::Plan(format!(
+                "EXCLUDE clauses require a window frame clause, not found in {}",
+                window_function
+            ))),
+        }
+    }
+
+    /// Plans the aggregate function call with an ordering that the parser
+    /// rewrote into a call of [`AGGREGATE_ORDER_BY`]
+    fn aggregate_order_by_to_expr(
+        &self,
+        function: &sqlparser::ast::Function,
+        schema: &DFSchema,
+    ) -> Result<Expr> {
+        let (aggregate, sort_exprs) = match function.args.split_first() {
+            Some((
+                FunctionArg::Unnamed(aggregate @ SQLExpr::Function(_)),
+                sort_exprs,
+            )) if function.over.is_none() && sort_exprs.len() % 2 == 0 => {
+                (aggregate, sort_exprs)
+            }
+            _ => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported ORDER BY clause in {}",
+                    function
+                )))
+            }
+        };
+        let order_by = sort_exprs
+            .chunks(2)
+            .map(|sort_expr| match sort_expr {
+                [FunctionArg::Unnamed(expr), FunctionArg::Unnamed(SQLExpr::Value(
+                    Value::SingleQuotedString(options),
+                ))] => {
+                    let options = options.split(' ').collect::<Vec<_>>();
+                    let asc = !options.contains(&"DESC");
+                    let nulls_first = match options.last() {
+                        Some(&"FIRST") => true,
+                        Some(&"LAST") => false,
+                        // consistently with the ORDER BY clause of queries
+                        _ => !asc,
+                    };
+                    Ok(Expr::Sort {
+                        expr: Box::new(self.sql_expr_to_logical_expr(expr, schema)?),
+                        asc,
+                        nulls_first,
+                    })
+                }
+                _ => Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported ORDER BY clause in {}",
+                    function
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        match self.sql_expr_to_logical_expr(aggregate, schema)? {
+            Expr::AggregateFunction {
+                fun,
+                args,
+                distinct,
+                filter,
+                order_by: existing,
+            } if existing.is_empty() => Ok(Expr::AggregateFunction {
+                fun,
+                args,
+                distinct,
+                filter,
+                order_by,
+            }),
+            _ => Err(DataFusionError::NotImplemented(format!(
+                "ORDER BY clauses are only supported by built-in aggregate functions, not {}",
+                aggregate
+            ))),
+        }
+    }
+
     fn function_args_to_expr(
         &self,
         function: &sqlparser::ast::Function,
@@ -1678,13 +2471,6 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             )));
         }
 
-        if last_field.is_some() {
-            return Err(DataFusionError::NotImplemented(format!(
-                "Unsupported Interval Expression with last_field {:?}",
-                last_field
-            )));
-        }
-
         if fractional_seconds_precision.is_some() {
             return Err(DataFusionError::NotImplemented(format!(
                 "Unsupported Interval Expression with fractional_seconds_precision {:?}",
@@ -1692,6 +2478,12 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             )));
         }
 
+        if let (Some(leading_field), Some(last_field)) = (leading_field, last_field) {
+            let (months, days, nanos) =
+                parse_interval_fields(value, leading_field, last_field)?;
+            return interval_literal(months, days, nanos, value);
+        }
+
         const SECONDS_PER_HOUR: f32 = 3_600_f32;
         const MILLIS_PER_SECOND: f32 = 1_000_f32;
 
@@ -1716,7 +2508,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
         let calculate_from_part = |interval_period_str: &str,
                                    interval_type: &str|
-         -> Result<(i32, i32, f32)> {
+         -> Result<(i32, i32, f32, i64)> {
             // @todo It's better to use Decimal in order to protect rounding errors
             // Wait https://github.com/apache/arrow/pull/9232
             let interval_period = match f32::from_str(interval_period_str) {
@@ -1736,28 +2528,45 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 )));
             }
 
-            match interval_type.to_lowercase().as_str() {
-                "year" => Ok(align_interval_parts(interval_period * 12_f32, 0.0, 0.0)),
-                "month" => Ok(align_interval_parts(interval_period, 0.0, 0.0)),
-                "day" | "days" => Ok(align_interval_parts(0.0, interval_period, 0.0)),
+            // parts smaller than milliseconds are stored in nanoseconds
+            let sub_millis = |nanos_per_unit: f64| {
+                let nanos = f64::from_str(interval_period_str).unwrap() * nanos_per_unit;
+                (0, 0, 0.0, nanos.round() as i64)
+            };
+
+            let (months, days, millis) = match interval_type.to_lowercase().as_str() {
+                "year" | "years" => {
+                    align_interval_parts(interval_period * 12_f32, 0.0, 0.0)
+                }
+                "month" | "months" => align_interval_parts(interval_period, 0.0, 0.0),
+                "week" | "weeks" => {
+                    align_interval_parts(0.0, interval_period * 7_f32, 0.0)
+                }
+                "day" | "days" => align_interval_parts(0.0, interval_period, 0.0),
                 "hour" | "hours" => {
-                    Ok((0, 0, interval_period * SECONDS_PER_HOUR * MILLIS_PER_SECOND))
+                    (0, 0, interval_period * SECONDS_PER_HOUR * MILLIS_PER_SECOND)
                 }
                 "minutes" | "minute" => {
-                    Ok((0, 0, interval_period * 60_f32 * MILLIS_PER_SECOND))
+                    (0, 0, interval_period * 60_f32 * MILLIS_PER_SECOND)
                 }
-                "seconds" | "second" => Ok((0, 0, interval_period * MILLIS_PER_SECOND)),
-                "milliseconds" | "millisecond" => Ok((0, 0, interval_period)),
-                _ => Err(DataFusionError::NotImplemented(format!(
-                    "Invalid input syntax for type interval: {:?}",
-                    value
-                ))),
-            }
+                "seconds" | "second" => (0, 0, interval_period * MILLIS_PER_SECOND),
+                "milliseconds" | "millisecond" => (0, 0, interval_period),
+                "microseconds" | "microsecond" => return Ok(sub_millis(1_000_f64)),
+                "nanoseconds" | "nanosecond" => return Ok(sub_millis(1_f64)),
+                _ => {
+                    return Err(DataFusionError::NotImplemented(format!(
+                        "Invalid input syntax for type interval: {:?}",
+                        value
+                    )))
+                }
+            };
+            Ok((months, days, millis, 0))
         };
 
         let mut result_month: i64 = 0;
         let mut result_days: i64 = 0;
         let mut result_millis: i64 = 0;
+        let mut result_nanos: i64 = 0;
 
         let mut parts = value.split_whitespace();
 
@@ -1777,7 +2586,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 .map(|part| part.to_string())
                 .unwrap_or(leading_field);
 
-            let (diff_month, diff_days, diff_millis) =
+            let (diff_month, diff_days, diff_millis, diff_nanos) =
                 calculate_from_part(interval_period_str.unwrap(), &unit)?;
 
             result_month += diff_month as i64;
@@ -1806,29 +2615,21 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     value
                 )));
             }
-        }
 
-        // Interval is tricky thing
-        // 1 day is not 24 hours because timezones, 1 year != 365/364! 30 days != 1 month
-        // The true way to store and calculate intervals is to store it as it defined
-        // Due the fact that Arrow supports only two types YearMonth (month) and DayTime (day, time)
-        // It's not possible to store complex intervals
-        // It's possible to do select (NOW() + INTERVAL '1 year') + INTERVAL '1 day'; as workaround
-        if result_month != 0 && (result_days != 0 || result_millis != 0) {
-            return Err(DataFusionError::NotImplemented(format!(
-                "DF does not support intervals that have both a Year/Month part as well as Days/Hours/Mins/Seconds: {:?}. Hint: try breaking the interval into two parts, one with Year/Month and the other with Days/Hours/Mins/Seconds - e.g. (NOW() + INTERVAL '1 year') + INTERVAL '1 day'",
-                value
-            )));
-        }
-
-        if result_month != 0 {
-            return Ok(Expr::Literal(ScalarValue::IntervalYearMonth(Some(
-                result_month as i32,
-            ))));
+            result_nanos = result_nanos.checked_add(diff_nanos).ok_or_else(|| {
+                DataFusionError::NotImplemented(format!(
+                    "Interval field value out of range: {:?}",
+                    value
+                ))
+            })?;
         }
 
-        let result: i64 = (result_days << 32) | result_millis;
-        Ok(Expr::Literal(ScalarValue::IntervalDayTime(Some(result))))
+        interval_literal(
+            result_month,
+            result_days,
+            result_millis * 1_000_000 + result_nanos,
+            value,
+        )
     }
 
     fn show_variable_to_plan(&self, variable: &[Ident]) -> Result<LogicalPlan> {
@@ -1920,6 +2721,32 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     }
 }
 
+/// Replaces the qualifier of the columns of `plan` by `alias`, if any
+fn with_alias(plan: LogicalPlan, alias: Option<String>) -> Result<LogicalPlan> {
+    match alias {
+        Some(_) => {
+            let columns = plan
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| Expr::Column(f.qualified_column()))
+                .collect::<Vec<_>>();
+            project_with_alias(plan, columns, alias)
+        }
+        None => Ok(plan),
+    }
+}
+
+/// Returns an error if `select` uses a wildcard without a FROM clause
+fn check_wildcard_from(select: &Select) -> Result<()> {
+    if select.from.is_empty() {
+        return Err(DataFusionError::Plan(
+            "SELECT * with no tables specified is not valid".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 /// Remove join expressions from a filter expression
 fn remove_join_expressions(
     expr: &Expr,
@@ -2001,6 +2828,39 @@ fn extract_join_keys(
 }
 
 /// Extract join keys from a WHERE clause
+/// Splits the top level conjunctions of `selection` into its `IN` subqueries, as
+/// `(expr, subquery, negated)`, and the rest of the predicate, if any
+fn split_in_subqueries(
+    selection: &SQLExpr,
+) -> (Option<SQLExpr>, Vec<(&SQLExpr, &Query, bool)>) {
+    match selection {
+        SQLExpr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            let (left, mut in_subqueries) = split_in_subqueries(left);
+            let (right, right_subqueries) = split_in_subqueries(right);
+            in_subqueries.extend(right_subqueries);
+            let predicate = match (left, right) {
+                (Some(left), Some(right)) => Some(SQLExpr::BinaryOp {
+                    left: Box::new(left),
+                    op: BinaryOperator::And,
+                    right: Box::new(right),
+                }),
+                (left, right) => left.or(right),
+            };
+            (predicate, in_subqueries)
+        }
+        SQLExpr::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => (None, vec![(expr.as_ref(), subquery.as_ref(), *negated)]),
+        other => (Some(other.clone()), vec![]),
+    }
+}
+
 fn extract_possible_join_keys(
     expr: &Expr,
     accum: &mut Vec<(Column, Column)>,
@@ -2045,6 +2905,113 @@ pub fn convert_data_type(sql_type: &SQLDataType) -> Result<DataType> {
     }
 }
 
+/// Parses the value of an interval with both a leading and a last field, such as
+/// `INTERVAL '1-2' YEAR TO MONTH` or `INTERVAL '3 04:05:06.5' DAY TO SECOND`, into
+/// its months, days and nanoseconds
+fn parse_interval_fields(
+    value: &str,
+    leading_field: &DateTimeField,
+    last_field: &DateTimeField,
+) -> Result<(i64, i64, i64)> {
+    let invalid = || {
+        DataFusionError::SQL(ParserError(format!(
+            "Invalid value {:?} for interval {} TO {}",
+            value, leading_field, last_field
+        )))
+    };
+    let (negative, unsigned) = match value.trim().strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, value.trim()),
+    };
+    let sign = if negative { -1 } else { 1 };
+
+    if let (DateTimeField::Year, DateTimeField::Month) = (leading_field, last_field) {
+        let (years, months) = unsigned.split_once('-').ok_or_else(invalid)?;
+        let years = i64::from_str(years.trim()).map_err(|_| invalid())?;
+        let months = i64::from_str(months.trim()).map_err(|_| invalid())?;
+        if !(0..12).contains(&months) {
+            return Err(invalid());
+        }
+        return Ok((sign * (years * 12 + months), 0, 0));
+    }
+
+    // the fields of day-time intervals, in the order of their position in values
+    let position = |field: &DateTimeField| match field {
+        DateTimeField::Day => Some(0),
+        DateTimeField::Hour => Some(1),
+        DateTimeField::Minute => Some(2),
+        DateTimeField::Second => Some(3),
+        _ => None,
+    };
+    let (first, last) = match (position(leading_field), position(last_field)) {
+        (Some(first), Some(last)) if first < last => (first, last),
+        _ => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Unsupported Interval Expression with leading field {} and last field {}",
+                leading_field, last_field
+            )))
+        }
+    };
+
+    let mut components = vec![];
+    let time = if first == 0 {
+        let (days, time) = unsigned.split_once(' ').ok_or_else(invalid)?;
+        components.push(days);
+        time.trim()
+    } else {
+        unsigned
+    };
+    components.extend(time.split(':'));
+    if components.len() != last - first + 1 {
+        return Err(invalid());
+    }
+
+    const NANOS_PER_SECOND: i64 = 1_000_000_000;
+    let (mut days, mut nanos) = (0, 0_i64);
+    for (field, component) in (first..=last).zip(components) {
+        let component = component.trim();
+        if field == 3 {
+            let seconds = f64::from_str(component).map_err(|_| invalid())?;
+            nanos += (seconds * NANOS_PER_SECOND as f64).round() as i64;
+            continue;
+        }
+        let component = i64::from_str(component).map_err(|_| invalid())?;
+        match field {
+            0 => days = component,
+            1 => nanos += component * 3_600 * NANOS_PER_SECOND,
+            _ => nanos += component * 60 * NANOS_PER_SECOND,
+        }
+    }
+    Ok((0, sign * days, sign * nanos))
+}
+
+/// Creates the literal of an interval of `months`, `days` and `nanos`. Intervals
+/// of only months are `YearMonth` intervals, those of days and milliseconds are
+/// `DayTime` intervals, and all others `MonthDayNano` intervals.
+fn interval_literal(months: i64, days: i64, nanos: i64, value: &str) -> Result<Expr> {
+    const NANOS_PER_MILLI: i64 = 1_000_000;
+    let out_of_range = || {
+        DataFusionError::NotImplemented(format!(
+            "Interval field value out of range: {:?}",
+            value
+        ))
+    };
+    let months = i32::try_from(months).map_err(|_| out_of_range())?;
+    let days = i32::try_from(days).map_err(|_| out_of_range())?;
+    let millis = i32::try_from(nanos / NANOS_PER_MILLI).ok();
+
+    let scalar = match millis {
+        _ if days == 0 && nanos == 0 && months != 0 => {
+            ScalarValue::IntervalYearMonth(Some(months))
+        }
+        Some(millis) if months == 0 && nanos % NANOS_PER_MILLI == 0 => {
+            ScalarValue::IntervalDayTime(Some(interval_dt_value(days, millis)))
+        }
+        _ => ScalarValue::new_interval_mdn(months, days, nanos),
+    };
+    Ok(Expr::Literal(scalar))
+}
+
 #[cfg(test)]
 mod tests {
     use functions::ScalarFunctionImplementation;
@@ -2307,6 +3274,25 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_with_table_sample() {
+        let sql =
+            "SELECT id FROM person AS p TABLESAMPLE BERNOULLI (10 PERCENT) REPEATABLE (1)
+                   WHERE age > 100";
+        let expected = "Projection: #p.id\
+                        \n  Filter: #p.age > Int64(100)\
+                        \n    Sample: method=BERNOULLI, fraction=0.1, seed=1\
+                        \n      TableScan: p projection=None";
+        quick_test(sql, expected);
+
+        let sql = "SELECT id FROM person TABLESAMPLE SYSTEM (200)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"The TABLESAMPLE percentage must be between 0 and 100, got 200\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn select_with_having_referencing_column_not_in_select() {
         let sql = "SELECT id, age
@@ -2758,13 +3744,37 @@ mod tests {
     }
 
     #[test]
-    fn select_unsupported_complex_interval() {
-        let sql = "SELECT INTERVAL '1 year 1 day'";
-        let err = logical_plan(sql).expect_err("query should have failed");
-        assert!(matches!(
-            err,
-            DataFusionError::NotImplemented(msg) if msg == "DF does not support intervals that have both a Year/Month part as well as Days/Hours/Mins/Seconds: \"1 year 1 day\". Hint: try breaking the interval into two parts, one with Year/Month and the other with Days/Hours/Mins/Seconds - e.g. (NOW() + INTERVAL '1 year') + INTERVAL '1 day'",
-        ));
+    fn select_complex_interval() {
+        quick_test(
+            "SELECT INTERVAL '1 year 1 day'",
+            "Projection: IntervalMonthDayNano(\"4294967308\")\
+             \n  EmptyRelation",
+        );
+        quick_test(
+            "SELECT INTERVAL '1 microsecond'",
+            "Projection: IntervalMonthDayNano(\"18446744073709551616000\")\
+             \n  EmptyRelation",
+        );
+    }
+
+    #[test]
+    fn select_interval_with_last_field() {
+        quick_test(
+            "SELECT INTERVAL '1-2' YEAR TO MONTH",
+            "Projection: IntervalYearMonth(\"14\")\
+             \n  EmptyRelation",
+        );
+        quick_test(
+            "SELECT INTERVAL '3 04:05:06.5' DAY TO SECOND",
+            "Projection: IntervalDayTime(\"12899608388\")\
+             \n  EmptyRelation",
+        );
+        let err = logical_plan("SELECT INTERVAL '1 04' DAY TO YEAR")
+            .expect_err("query should have failed");
+        assert_eq!(
+            "This feature is not implemented: Unsupported Interval Expression with leading field DAY and last field YEAR",
+            format!("{}", err)
+        );
     }
 
     #[test]
@@ -3173,6 +4183,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn create_external_table_csv_unknown_bucket_column() {
+        let sql = "CREATE EXTERNAL TABLE t(c1 int) CLUSTERED BY (c2) INTO 4 BUCKETS STORED AS CSV LOCATION 'foo.csv'";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Bucket column c2 is not a column of the table\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn create_external_table_parquet_no_schema() {
         let sql = "CREATE EXTERNAL TABLE t STORED AS PARQUET LOCATION 'foo.parquet'";
@@ -3180,6 +4200,37 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn insert_overwrite() {
+        let sql =
+            "INSERT OVERWRITE TABLE lineitem SELECT qty, o_item_id, price FROM orders";
+        let expected = "InsertInto: \"lineitem\" mode=Overwrite\
+        \n  Projection: CAST(#orders.qty AS UInt32) AS l_item_id, #orders.o_item_id AS l_description, #orders.price AS price\
+        \n    Projection: #orders.qty, #orders.o_item_id, #orders.price\
+        \n      TableScan: orders projection=None";
+        quick_test(sql, expected);
+
+        let sql = "INSERT OVERWRITE TABLE lineitem PARTITION (l_description) \
+            SELECT order_id, o_item_id, price FROM orders";
+        let expected =
+            "InsertInto: \"lineitem\" mode=OverwritePartitions([\"l_description\"])\
+        \n  Projection: #orders.order_id AS l_item_id, #orders.o_item_id AS l_description, #orders.price AS price\
+        \n    Projection: #orders.order_id, #orders.o_item_id, #orders.price\
+        \n      TableScan: orders projection=None";
+        quick_test(sql, expected);
+
+        let sql = "INSERT OVERWRITE TABLE lineitem SELECT order_id FROM orders";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Table 'lineitem' has 3 columns but the query returns 1\")",
+            format!("{:?}", err)
+        );
+
+        let sql = "INSERT INTO lineitem SELECT order_id, o_item_id, price FROM orders";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert!(matches!(err, DataFusionError::NotImplemented(_)));
+    }
+
     #[test]
     fn equijoin_explicit_syntax() {
         let sql = "SELECT id, order_id \
@@ -3248,6 +4299,37 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn lowercase_identifier_normalization() {
+        let sql = "SELECT ID, P.First_Name AS Name, \"age\" \
+            FROM Person AS P \
+            WHERE P.\"age\" > 21";
+        let expected = "Projection: #p.id, #p.first_name AS name, #p.age\
+        \n  Filter: #p.age > Int64(21)\
+        \n    TableScan: p projection=None";
+        let planner = SqlToRel::new(&MockContextProvider {})
+            .with_identifier_normalization(IdentifierNormalization::Lowercase);
+        let ast = DFParser::parse_sql(sql).unwrap();
+        let plan = planner.statement_to_plan(&ast[0]).unwrap();
+        assert_eq!(format!("{:?}", plan), expected);
+
+        // quoted identifiers are not normalized
+        let sql = "SELECT \"ID\" FROM person";
+        let ast = DFParser::parse_sql(sql).unwrap();
+        assert!(planner.statement_to_plan(&ast[0]).is_err());
+    }
+
+    #[test]
+    fn case_sensitive_identifiers() {
+        let sql = "SELECT \"first_name\" AS \"Name\" FROM \"person\"";
+        let expected = "Projection: #person.first_name AS Name\
+        \n  TableScan: person projection=None";
+        quick_test(sql, expected);
+
+        let sql = "SELECT First_Name FROM person";
+        assert!(logical_plan(sql).is_err());
+    }
+
     #[test]
     fn join_with_using() {
         let sql = "SELECT person.first_name, id \
@@ -3274,6 +4356,74 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn project_wildcard_on_right_and_full_join_with_using() {
+        let sql = "SELECT * \
+            FROM lineitem \
+            RIGHT JOIN lineitem as lineitem2 \
+            USING (l_item_id)";
+        let expected = "Projection: #lineitem2.l_item_id, #lineitem.l_description, #lineitem.price, #lineitem2.l_description, #lineitem2.price\
+        \n  Join: Using #lineitem.l_item_id = #lineitem2.l_item_id\
+        \n    TableScan: lineitem projection=None\
+        \n    TableScan: lineitem2 projection=None";
+        quick_test(sql, expected);
+
+        let sql = "SELECT * \
+            FROM lineitem \
+            FULL JOIN lineitem as lineitem2 \
+            USING (l_item_id)";
+        let expected = "Projection: CASE WHEN #lineitem.l_item_id IS NOT NULL THEN #lineitem.l_item_id ELSE #lineitem2.l_item_id END AS l_item_id, #lineitem.l_description, #lineitem.price, #lineitem2.l_description, #lineitem2.price\
+        \n  Join: Using #lineitem.l_item_id = #lineitem2.l_item_id\
+        \n    TableScan: lineitem projection=None\
+        \n    TableScan: lineitem2 projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn project_qualified_wildcard() {
+        let sql = "SELECT lineitem2.*, lineitem.price \
+            FROM lineitem \
+            JOIN lineitem as lineitem2 \
+            USING (l_item_id)";
+        let expected = "Projection: #lineitem2.l_item_id, #lineitem2.l_description, #lineitem2.price, #lineitem.price\
+        \n  Join: Using #lineitem.l_item_id = #lineitem2.l_item_id\
+        \n    TableScan: lineitem projection=None\
+        \n    TableScan: lineitem2 projection=None";
+        quick_test(sql, expected);
+
+        let sql = "SELECT orders.* FROM lineitem";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Invalid qualifier orders in wildcard\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn project_wildcard_exclude() {
+        let sql = "SELECT * EXCLUDE (l_description) FROM lineitem";
+        let expected = "Projection: #lineitem.l_item_id, #lineitem.price\
+        \n  TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+
+        let sql = "SELECT lineitem2.* EXCEPT (lineitem2.price, l_item_id) \
+            FROM lineitem \
+            JOIN lineitem as lineitem2 \
+            USING (l_item_id)";
+        let expected = "Projection: #lineitem2.l_description\
+        \n  Join: Using #lineitem.l_item_id = #lineitem2.l_item_id\
+        \n    TableScan: lineitem projection=None\
+        \n    TableScan: lineitem2 projection=None";
+        quick_test(sql, expected);
+
+        let sql = "SELECT * EXCLUDE (qty) FROM lineitem";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Column #qty in EXCLUDE list not found in wildcard\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn equijoin_explicit_syntax_3_tables() {
         let sql = "SELECT id, order_id, l_description \
@@ -3467,13 +4617,13 @@ mod tests {
     }
 
     #[test]
-    fn over_order_by_with_window_frame_range_value_check() {
+    fn over_order_by_with_window_frame_range_offset() {
         let sql = "SELECT order_id, MAX(qty) OVER (ORDER BY order_id RANGE 3 PRECEDING) from orders";
-        let err = logical_plan(sql).expect_err("query should have failed");
-        assert_eq!(
-            "NotImplemented(\"With WindowFrameUnits=RANGE, the bound cannot be 3 PRECEDING or FOLLOWING at the moment\")",
-            format!("{:?}", err)
-        );
+        let expected = "\
+        Projection: #orders.order_id, #MAX(orders.qty) ORDER BY [#orders.order_id ASC NULLS LAST] RANGE BETWEEN 3 PRECEDING AND CURRENT ROW\
+        \n  WindowAggr: windowExpr=[[MAX(#orders.qty) ORDER BY [#orders.order_id ASC NULLS LAST] RANGE BETWEEN 3 PRECEDING AND CURRENT ROW]]\
+        \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
     }
 
     #[test]
@@ -3498,6 +4648,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn over_order_by_with_window_frame_range_offset_type_check() {
+        let sql = "SELECT id, MAX(age) OVER (ORDER BY birth_date RANGE 3 PRECEDING) from person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"RANGE window frames with offsets require a numeric ORDER BY expression, got Timestamp(Nanosecond, None)\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn over_order_by_with_window_frame_exclude() {
+        let sql = "SELECT order_id, MAX(qty) OVER (ORDER BY order_id ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING EXCLUDE CURRENT ROW) from orders";
+        let expected = "\
+        Projection: #orders.order_id, #MAX(orders.qty) ORDER BY [#orders.order_id ASC NULLS LAST] ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING EXCLUDE CURRENT ROW\
+        \n  WindowAggr: windowExpr=[[MAX(#orders.qty) ORDER BY [#orders.order_id ASC NULLS LAST] ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING EXCLUDE CURRENT ROW]]\
+        \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn over_order_by_with_window_frame_exclude_without_frame() {
+        let sql =
+            "SELECT order_id, MAX(qty) OVER (ORDER BY order_id EXCLUDE TIES) from orders";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"EXCLUDE clauses require a window frame clause, not found in MAX(qty) OVER (ORDER BY order_id)\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn over_order_by_with_window_frame_expression_bound() {
+        let sql = "SELECT order_id, MAX(qty) OVER (ORDER BY order_id ROWS 1 + 1 PRECEDING) from orders";
+        logical_plan(sql).expect_err("expression bounds should not be supported");
+        let sql = "SELECT order_id, MAX(qty) OVER (ORDER BY order_id RANGE INTERVAL '1' DAY PRECEDING) from orders";
+        logical_plan(sql).expect_err("INTERVAL bounds should not be supported");
+    }
+
     #[test]
     fn over_order_by_with_window_frame_single_end_groups() {
         let sql = "SELECT order_id, MAX(qty) OVER (ORDER BY order_id GROUPS 3 PRECEDING), MIN(qty) OVER (ORDER BY order_id DESC) from orders";
@@ -3819,3 +5008,22 @@ fn parse_sql_number(n: &str) -> Result<Expr> {
         Err(_) => Ok(lit(n.parse::<f64>().unwrap())),
     }
 }
+
+/// Parses the digits of a hexadecimal string literal, such as `X'ABCD'`, into a
+/// binary literal
+fn parse_sql_hex_string(s: &str) -> Result<Expr> {
+    let invalid =
+        || DataFusionError::Plan(format!("Invalid hexadecimal string literal X'{}'", s));
+    if s.len() % 2 != 0 {
+        return Err(invalid());
+    }
+    let bytes = (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Expr::Literal(ScalarValue::Binary(Some(bytes))))
+}
@@ -0,0 +1,318 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Folding of uncorrelated scalar and `IN` subqueries into literals
+
+use sqlparser::ast::{
+    Expr as SQLExpr, FunctionArg, Query, Select, SelectItem, SetExpr,
+    Statement as SQLStatement, Value,
+};
+
+use crate::error::{DataFusionError, Result};
+use crate::scalar::ScalarValue;
+use crate::sql::parser::Statement as DFStatement;
+
+/// Returns the scalar and `IN` subqueries of `statement`, in the order that
+/// [`replace_subqueries`] expects their values. The subqueries nested in other
+/// subqueries are not returned.
+pub(crate) fn collect_subqueries(statement: &mut DFStatement) -> Vec<Query> {
+    let mut subqueries = vec![];
+    visit_statement(statement, &mut |expr| {
+        if let SQLExpr::Subquery(subquery) | SQLExpr::InSubquery { subquery, .. } = expr {
+            subqueries.push(subquery.as_ref().clone());
+        }
+        Ok(())
+    })
+    .expect("collecting subqueries can not fail");
+    subqueries
+}
+
+/// Replaces the subqueries returned by [`collect_subqueries`] with the rows
+/// they return, for those with `Some` rows
+pub(crate) fn replace_subqueries(
+    statement: &mut DFStatement,
+    rows: Vec<Option<Vec<SQLExpr>>>,
+) -> Result<()> {
+    let mut rows = rows.into_iter();
+    visit_statement(statement, &mut |expr| {
+        let mut values = match rows.next().flatten() {
+            Some(values) => values,
+            None => return Ok(()),
+        };
+        *expr = match &*expr {
+            SQLExpr::InSubquery {
+                expr: in_expr,
+                negated,
+                ..
+            } => {
+                if values.is_empty() {
+                    // no value is in an empty set, even NULL
+                    SQLExpr::Value(Value::Boolean(*negated))
+                } else {
+                    SQLExpr::InList {
+                        expr: in_expr.clone(),
+                        list: values,
+                        negated: *negated,
+                    }
+                }
+            }
+            _ => {
+                if values.len() > 1 {
+                    return Err(DataFusionError::Execution(
+                        "Scalar subquery returned more than one row".to_string(),
+                    ));
+                }
+                values.pop().unwrap_or(SQLExpr::Value(Value::Null))
+            }
+        };
+        Ok(())
+    })
+}
+
+/// Converts `value` to a SQL literal, if it can be written as one without
+/// changing its value
+pub(crate) fn scalar_to_sql(value: &ScalarValue) -> Option<SQLExpr> {
+    fn number(n: impl ToString) -> Value {
+        Value::Number(n.to_string(), false)
+    }
+
+    let value = match value {
+        value if value.is_null() => Value::Null,
+        ScalarValue::Boolean(Some(v)) => Value::Boolean(*v),
+        ScalarValue::Int8(Some(v)) => number(v),
+        ScalarValue::Int16(Some(v)) => number(v),
+        ScalarValue::Int32(Some(v)) => number(v),
+        ScalarValue::Int64(Some(v)) => number(v),
+        ScalarValue::UInt8(Some(v)) => number(v),
+        ScalarValue::UInt16(Some(v)) => number(v),
+        ScalarValue::UInt32(Some(v)) => number(v),
+        // larger values would be parsed as floats
+        ScalarValue::UInt64(Some(v)) if *v <= i64::MAX as u64 => number(v),
+        // printed as f64 so that the literal has exactly the same value
+        ScalarValue::Float32(Some(v)) if v.is_finite() => number(*v as f64),
+        ScalarValue::Float64(Some(v)) if v.is_finite() => number(v),
+        ScalarValue::Utf8(Some(v)) | ScalarValue::LargeUtf8(Some(v)) => {
+            Value::SingleQuotedString(v.clone())
+        }
+        _ => return None,
+    };
+    Some(SQLExpr::Value(value))
+}
+
+type Visitor<'a> = dyn FnMut(&mut SQLExpr) -> Result<()> + 'a;
+
+fn visit_statement(statement: &mut DFStatement, f: &mut Visitor) -> Result<()> {
+    match statement {
+        DFStatement::Statement(statement) => visit_sql_statement(statement, f),
+        DFStatement::Explain(explain) => visit_sql_statement(&mut explain.statement, f),
+        DFStatement::CreateExternalTable(_) | DFStatement::Custom(_) => Ok(()),
+    }
+}
+
+fn visit_sql_statement(statement: &mut SQLStatement, f: &mut Visitor) -> Result<()> {
+    match statement {
+        SQLStatement::Query(query) => visit_query(query, f),
+        SQLStatement::Explain { statement, .. } => visit_sql_statement(statement, f),
+        _ => Ok(()),
+    }
+}
+
+fn visit_query(query: &mut Query, f: &mut Visitor) -> Result<()> {
+    if let Some(with) = &mut query.with {
+        for cte in &mut with.cte_tables {
+            visit_query(&mut cte.query, f)?;
+        }
+    }
+    visit_set_expr(&mut query.body, f)?;
+    for order_by in &mut query.order_by {
+        visit_expr(&mut order_by.expr, f)?;
+    }
+    Ok(())
+}
+
+fn visit_set_expr(set_expr: &mut SetExpr, f: &mut Visitor) -> Result<()> {
+    match set_expr {
+        SetExpr::Select(select) => visit_select(select, f),
+        SetExpr::Query(query) => visit_query(query, f),
+        SetExpr::SetOperation { left, right, .. } => {
+            visit_set_expr(left, f)?;
+            visit_set_expr(right, f)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn visit_select(select: &mut Select, f: &mut Visitor) -> Result<()> {
+    for item in &mut select.projection {
+        match item {
+            SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                visit_expr(expr, f)?
+            }
+            SelectItem::QualifiedWildcard(_) | SelectItem::Wildcard => {}
+        }
+    }
+    if let Some(selection) = &mut select.selection {
+        visit_expr(selection, f)?;
+    }
+    for expr in &mut select.group_by {
+        visit_expr(expr, f)?;
+    }
+    if let Some(having) = &mut select.having {
+        visit_expr(having, f)?;
+    }
+    Ok(())
+}
+
+/// Calls `f` on the subqueries of `expr`, without visiting the expressions
+/// nested in the subqueries
+fn visit_expr(expr: &mut SQLExpr, f: &mut Visitor) -> Result<()> {
+    match expr {
+        SQLExpr::Subquery(_) => f(expr),
+        SQLExpr::InSubquery { expr: in_expr, .. } => {
+            visit_expr(in_expr, f)?;
+            f(expr)
+        }
+        SQLExpr::BinaryOp { left, right, .. } => {
+            visit_expr(left, f)?;
+            visit_expr(right, f)
+        }
+        SQLExpr::UnaryOp { expr, .. }
+        | SQLExpr::Nested(expr)
+        | SQLExpr::IsNull(expr)
+        | SQLExpr::IsNotNull(expr)
+        | SQLExpr::Cast { expr, .. }
+        | SQLExpr::TryCast { expr, .. } => visit_expr(expr, f),
+        SQLExpr::Between {
+            expr, low, high, ..
+        } => {
+            visit_expr(expr, f)?;
+            visit_expr(low, f)?;
+            visit_expr(high, f)
+        }
+        SQLExpr::InList { expr, list, .. } => {
+            visit_expr(expr, f)?;
+            list.iter_mut().try_for_each(|e| visit_expr(e, f))
+        }
+        SQLExpr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            if let Some(operand) = operand {
+                visit_expr(operand, f)?;
+            }
+            for e in conditions.iter_mut().chain(results.iter_mut()) {
+                visit_expr(e, f)?;
+            }
+            if let Some(else_result) = else_result {
+                visit_expr(else_result, f)?;
+            }
+            Ok(())
+        }
+        SQLExpr::Function(function) => {
+            for arg in &mut function.args {
+                match arg {
+                    FunctionArg::Named { arg, .. } | FunctionArg::Unnamed(arg) => {
+                        visit_expr(arg, f)?
+                    }
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::parser::DFParser;
+
+    fn parse(sql: &str) -> DFStatement {
+        DFParser::parse_sql(sql).unwrap().remove(0)
+    }
+
+    #[test]
+    fn fold_subqueries() -> Result<()> {
+        let mut statement = parse(
+            "SELECT (SELECT max(a) FROM t), b FROM u \
+             WHERE c IN (SELECT c FROM v WHERE d IN (SELECT d FROM w)) \
+             AND e NOT IN (SELECT e FROM x) AND f IN (SELECT f FROM y)",
+        );
+        let subqueries = collect_subqueries(&mut statement);
+        let subqueries = subqueries.iter().map(|q| q.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            subqueries,
+            vec![
+                "SELECT max(a) FROM t",
+                "SELECT c FROM v WHERE d IN (SELECT d FROM w)",
+                "SELECT e FROM x",
+                "SELECT f FROM y",
+            ]
+        );
+
+        let number = |n: i32| scalar_to_sql(&ScalarValue::Int32(Some(n)));
+        replace_subqueries(
+            &mut statement,
+            vec![
+                Some(vec![number(1).unwrap()]),
+                Some(vec![number(2).unwrap(), number(3).unwrap()]),
+                Some(vec![]),
+                None,
+            ],
+        )?;
+        let expected = parse(
+            "SELECT 1, b FROM u WHERE c IN (2, 3) AND true \
+             AND f IN (SELECT f FROM y)",
+        );
+        assert_eq!(expected, statement);
+        Ok(())
+    }
+
+    #[test]
+    fn scalar_subquery_with_several_rows() {
+        let mut statement = parse("SELECT (SELECT a FROM t)");
+        let values = vec!["a", "b"]
+            .into_iter()
+            .map(|v| scalar_to_sql(&ScalarValue::Utf8(Some(v.to_owned()))).unwrap())
+            .collect();
+        let err = replace_subqueries(&mut statement, vec![Some(values)]).unwrap_err();
+        assert_eq!(
+            "Execution error: Scalar subquery returned more than one row",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn scalar_to_sql_literals() {
+        let sql = |v: ScalarValue| scalar_to_sql(&v).map(|e| e.to_string());
+        assert_eq!(Some("NULL".to_owned()), sql(ScalarValue::Int32(None)));
+        assert_eq!(Some("-5".to_owned()), sql(ScalarValue::Int64(Some(-5))));
+        assert_eq!(
+            Some("0.10000000149011612".to_owned()),
+            sql(ScalarValue::Float32(Some(0.1)))
+        );
+        assert_eq!(None, sql(ScalarValue::Float64(Some(f64::NAN))));
+        assert_eq!(None, sql(ScalarValue::UInt64(Some(u64::MAX))));
+        assert_eq!(
+            Some("'it''s'".to_owned()),
+            sql(ScalarValue::Utf8(Some("it's".to_owned())))
+        );
+        assert_eq!(None, sql(ScalarValue::Date32(Some(1))));
+    }
+}
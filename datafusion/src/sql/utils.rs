@@ -218,6 +218,8 @@ where
                 fun,
                 args,
                 distinct,
+                filter,
+                order_by,
             } => Ok(Expr::AggregateFunction {
                 fun: fun.clone(),
                 args: args
@@ -225,6 +227,16 @@ where
                     .map(|e| clone_with_replacement(e, replacement_fn))
                     .collect::<Result<Vec<Expr>>>()?,
                 distinct: *distinct,
+                filter: match filter {
+                    Some(filter) => {
+                        Some(Box::new(clone_with_replacement(filter, replacement_fn)?))
+                    }
+                    None => None,
+                },
+                order_by: order_by
+                    .iter()
+                    .map(|e| clone_with_replacement(e, replacement_fn))
+                    .collect::<Result<Vec<Expr>>>()?,
             }),
             Expr::WindowFunction {
                 fun,
@@ -22,10 +22,12 @@ use crate::datasource::{MemTable, PartitionedFile, TableProvider};
 use crate::error::Result;
 use crate::logical_plan::{LogicalPlan, LogicalPlanBuilder};
 use array::{
-    Array, ArrayRef, StringArray, TimestampMicrosecondArray, TimestampMillisecondArray,
+    Array, ArrayData, ArrayRef, BooleanArray, Int64Array, MapArray, StringArray,
+    StructArray, TimestampMicrosecondArray, TimestampMillisecondArray,
     TimestampNanosecondArray, TimestampSecondArray,
 };
 use arrow::array::{self, DecimalBuilder, Int32Array};
+use arrow::buffer::Buffer;
 use arrow::datatypes::{DataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
 use futures::{Future, FutureExt};
@@ -292,6 +294,43 @@ pub fn make_timestamps() -> RecordBatch {
     .unwrap()
 }
 
+/// Return a map array with Utf8 keys and Int64 values, with one map per item of
+/// `maps`, or a null map for the `None` items
+pub fn make_map_array(maps: Vec<Option<Vec<(&str, Option<i64>)>>>) -> MapArray {
+    let mut offsets = vec![0i32];
+    let mut keys = vec![];
+    let mut values = vec![];
+    for map in &maps {
+        for (key, value) in map.iter().flatten() {
+            keys.push(*key);
+            values.push(*value);
+        }
+        offsets.push(keys.len() as i32);
+    }
+    let entries = StructArray::from(vec![
+        (
+            Field::new("key", DataType::Utf8, false),
+            Arc::new(StringArray::from(keys)) as ArrayRef,
+        ),
+        (
+            Field::new("value", DataType::Int64, true),
+            Arc::new(Int64Array::from(values)) as ArrayRef,
+        ),
+    ]);
+    let validity = maps.iter().map(|map| map.is_some()).collect::<Vec<_>>();
+    let data = ArrayData::builder(DataType::Map(
+        Box::new(Field::new("entries", entries.data_type().clone(), false)),
+        false,
+    ))
+    .len(maps.len())
+    .add_buffer(Buffer::from_slice_ref(&offsets))
+    .add_child_data(entries.data().clone())
+    .null_bit_buffer(BooleanArray::from(validity).values().clone())
+    .build()
+    .unwrap();
+    MapArray::from(data)
+}
+
 /// Asserts that given future is pending.
 pub fn assert_is_pending<'a, T>(fut: &mut Pin<Box<dyn Future<Output = T> + Send + 'a>>) {
     let waker = futures::task::noop_waker();
@@ -22,6 +22,7 @@ use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 
 use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::memory::MemoryExec;
 use datafusion::scalar::ScalarValue;
 use datafusion::{datasource::TableProvider, physical_plan::collect};
 use datafusion::{
@@ -31,7 +32,7 @@ use datafusion::{
 
 use datafusion::execution::context::ExecutionContext;
 use datafusion::logical_plan::{
-    col, Expr, LogicalPlan, LogicalPlanBuilder, TableScan, UNNAMED_TABLE,
+    col, count, lit, Expr, LogicalPlan, LogicalPlanBuilder, TableScan, UNNAMED_TABLE,
 };
 use datafusion::physical_plan::{
     ColumnStatistics, ExecutionPlan, Partitioning, RecordBatchStream,
@@ -304,3 +305,97 @@ fn contains_empty_exec(plan: Arc<dyn ExecutionPlan>) -> bool {
         contains_empty_exec(Arc::clone(&plan.children()[0]))
     }
 }
+
+/// A table of the numbers from 1 to 10, that counts its rows and applies limits
+/// itself
+struct PushDownTableProvider;
+
+#[async_trait]
+impl TableProvider for PushDownTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::new(Schema::new(vec![Field::new("c1", DataType::Int32, false)]))
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        _batch_size: usize,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let values: Vec<i32> = (1..=10).take(limit.unwrap_or(10)).collect();
+        let batch = RecordBatch::try_new(
+            self.schema(),
+            vec![Arc::new(Int32Array::from(values))],
+        )?;
+        Ok(Arc::new(MemoryExec::try_new(
+            &[vec![batch]],
+            self.schema(),
+            projection.clone(),
+        )?))
+    }
+
+    async fn scan_aggregate(
+        &self,
+        _group_expr: &[Expr],
+        _aggr_expr: &[Expr],
+        _filters: &[Expr],
+        schema: SchemaRef,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(UInt64Array::from(vec![10]))],
+        )?;
+        Ok(Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None)?))
+    }
+
+    fn supports_aggregate_pushdown(
+        &self,
+        group_expr: &[Expr],
+        aggr_expr: &[Expr],
+    ) -> Result<bool> {
+        Ok(group_expr.is_empty() && aggr_expr.to_vec() == vec![count(lit(1u8))])
+    }
+
+    fn supports_limit_pushdown(&self) -> bool {
+        true
+    }
+}
+
+#[tokio::test]
+async fn aggregate_and_limit_pushdown() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(PushDownTableProvider))?;
+
+    // the count is computed by the provider
+    let df = ctx.sql("SELECT COUNT(*) FROM test").await?;
+    let plan = ctx.optimize(&df.to_logical_plan())?;
+    assert!(!format!("{:?}", plan).contains("Aggregate"));
+    let expected = vec![
+        "+-----------------+",
+        "| COUNT(UInt8(1)) |",
+        "+-----------------+",
+        "| 10              |",
+        "+-----------------+",
+    ];
+    datafusion::assert_batches_eq!(expected, &df.collect().await?);
+
+    // other aggregates are computed from the scanned rows
+    let df = ctx.sql("SELECT MAX(c1) FROM test").await?;
+    let plan = ctx.optimize(&df.to_logical_plan())?;
+    assert!(format!("{:?}", plan).contains("Aggregate"));
+
+    // the limit is applied by the provider
+    let df = ctx.sql("SELECT c1 FROM test LIMIT 3").await?;
+    let plan = ctx.optimize(&df.to_logical_plan())?;
+    assert!(!format!("{:?}", plan).contains("Limit"));
+    let expected = vec![
+        "+----+", "| c1 |", "+----+", "| 1  |", "| 2  |", "| 3  |", "+----+",
+    ];
+    datafusion::assert_batches_eq!(expected, &df.collect().await?);
+    Ok(())
+}
@@ -232,9 +232,29 @@ async fn prune_int32_eq() {
     // This should prune out groups without error
     assert_eq!(output.predicate_evaluation_errors(), Some(0));
     assert_eq!(output.row_groups_pruned(), Some(3));
+    assert_eq!(output.metric_value("row_groups_scanned"), Some(1));
+    assert!(output.metric_value("bytes_scanned").unwrap() > 0);
     assert_eq!(output.result_rows, 1, "{}", output.description());
 }
 
+#[tokio::test]
+async fn prune_int32_all_row_groups() {
+    // result of sql "SELECT * FROM t where i > 100"
+    let output = ContextWithParquet::new(Scenario::Int32)
+        .await
+        .query("SELECT * FROM t where i > 100")
+        .await;
+
+    println!("{}", output.description());
+    // the file is skipped once all its row groups are pruned
+    assert_eq!(output.predicate_evaluation_errors(), Some(0));
+    assert_eq!(output.row_groups_pruned(), Some(4));
+    assert_eq!(output.metric_value("files_listed"), Some(1));
+    assert_eq!(output.metric_value("files_pruned"), Some(1));
+    assert_eq!(output.metric_value("row_groups_scanned"), Some(0));
+    assert_eq!(output.result_rows, 0, "{}", output.description());
+}
+
 #[tokio::test]
 async fn prune_int32_scalar_fun_and_eq() {
     // resulrt of sql "SELECT * FROM t where abs(i) = 1 and i = 1"
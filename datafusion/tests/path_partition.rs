@@ -21,6 +21,7 @@ use std::{fs, io, sync::Arc};
 
 use async_trait::async_trait;
 use datafusion::{
+    arrow::array::UInt64Array,
     assert_batches_sorted_eq,
     datasource::{
         file_format::{csv::CsvFormat, parquet::ParquetFormat},
@@ -31,7 +32,7 @@ use datafusion::{
         },
     },
     error::{DataFusionError, Result},
-    physical_plan::ColumnStatistics,
+    physical_plan::{collect, displayable, ColumnStatistics},
     prelude::ExecutionContext,
     test_util::{self, arrow_test_data, parquet_test_data},
 };
@@ -217,10 +218,14 @@ async fn parquet_statistics() -> Result<()> {
     assert_eq!(stat_cols.len(), 4);
     // stats for the first col are read from the parquet file
     assert_eq!(stat_cols[0].null_count, Some(3));
-    // TODO assert partition column (1,2,3) stats once implemented (#1186)
-    assert_eq!(stat_cols[1], ColumnStatistics::default());
-    assert_eq!(stat_cols[2], ColumnStatistics::default());
-    assert_eq!(stat_cols[3], ColumnStatistics::default());
+    // partition columns are never null
+    let partition_col_stats = ColumnStatistics {
+        null_count: Some(0),
+        ..ColumnStatistics::default()
+    };
+    assert_eq!(stat_cols[1], partition_col_stats);
+    assert_eq!(stat_cols[2], partition_col_stats);
+    assert_eq!(stat_cols[3], partition_col_stats);
 
     //// WITH PROJECTION ////
     let logical_plan = ctx
@@ -238,8 +243,58 @@ async fn parquet_statistics() -> Result<()> {
     assert_eq!(stat_cols.len(), 2);
     // stats for the first col are read from the parquet file
     assert_eq!(stat_cols[0].null_count, Some(1));
-    // TODO assert partition column stats once implemented (#1186)
-    assert_eq!(stat_cols[1], ColumnStatistics::default());
+    assert_eq!(stat_cols[1], partition_col_stats);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn parquet_count_from_statistics() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+
+    register_partitioned_alltypes_parquet(
+        &mut ctx,
+        &[
+            "year=2021/month=09/day=09/file.parquet",
+            "year=2021/month=10/day=09/file.parquet",
+            "year=2021/month=10/day=28/file.parquet",
+        ],
+        &["year", "month", "day"],
+        "",
+        "single_nan.parquet",
+    )
+    .await;
+
+    // the files of the pruned partitions are counted from their footers
+    let logical_plan = ctx
+        .sql("SELECT COUNT(*), COUNT(day) FROM t WHERE month='10'")
+        .await?
+        .to_logical_plan();
+    let physical_plan = ctx.create_physical_plan(&logical_plan).await?;
+    let plan = displayable(physical_plan.as_ref()).indent().to_string();
+    assert!(!plan.contains("ParquetExec"), "{}", plan);
+
+    let result = collect(physical_plan).await?;
+    let counts = (0..2)
+        .map(|i| {
+            result[0]
+                .column(i)
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .unwrap()
+                .value(0)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![2, 2]);
+
+    // other filters need the data to be scanned
+    let logical_plan = ctx
+        .sql("SELECT COUNT(*) FROM t WHERE month='10' AND mycol > 0")
+        .await?
+        .to_logical_plan();
+    let physical_plan = ctx.create_physical_plan(&logical_plan).await?;
+    let plan = displayable(physical_plan.as_ref()).indent().to_string();
+    assert!(plan.contains("ParquetExec"), "{}", plan);
 
     Ok(())
 }
@@ -41,9 +41,12 @@ use datafusion::logical_plan::LogicalPlan;
 use datafusion::logical_plan::TableScan;
 use datafusion::physical_plan::functions::Volatility;
 use datafusion::physical_plan::metrics::MetricValue;
+use datafusion::physical_plan::udtf::{TableFunctionImplementation, TableUDF};
 use datafusion::physical_plan::ExecutionPlan;
 use datafusion::physical_plan::ExecutionPlanVisitor;
 use datafusion::prelude::*;
+use datafusion::scalar::ScalarValue;
+use datafusion::sql::planner::IdentifierNormalization;
 use datafusion::test_util;
 use datafusion::{datasource::MemTable, physical_plan::collect};
 use datafusion::{
@@ -155,6 +158,167 @@ async fn parquet_single_nan_schema() {
     }
 }
 
+#[tokio::test]
+async fn parquet_nested_projection() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path().join("nested.parquet");
+    let a = StructArray::from(vec![
+        (
+            Field::new("b", DataType::Int32, true),
+            Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+        ),
+        (
+            Field::new("c", DataType::Utf8, true),
+            Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+        ),
+    ]);
+    let s = StructArray::from(vec![
+        (
+            Field::new("a", a.data_type().clone(), true),
+            Arc::new(a) as ArrayRef,
+        ),
+        (
+            Field::new("d", DataType::Int32, true),
+            Arc::new(Int32Array::from(vec![10, 20])) as ArrayRef,
+        ),
+    ]);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("s", s.data_type().clone(), true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Int32Array::from(vec![1, 2])), Arc::new(s)],
+    )?;
+    let file = std::fs::File::create(&path)?;
+    let mut writer = parquet::arrow::ArrowWriter::try_new(file, schema.clone(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_parquet("t", path.to_str().unwrap()).await?;
+
+    // only the leaf columns of the referenced fields are read
+    let sql = "SELECT id, s.a.b AS b FROM t WHERE s['d'] > 10";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    assert_contains!(format!("{:?}", plan), "nested_projection=[s.a.b, s.d]");
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+---+",
+        "| id | b |",
+        "+----+---+",
+        "| 2  | 2 |",
+        "+----+---+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    // the whole struct is read when it is referenced
+    let sql = "SELECT t.s.a.c AS c, s FROM t WHERE id = 1";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    assert_not_contains!(format!("{:?}", plan), "nested_projection");
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_eq!(1, actual[0].num_rows());
+    assert_eq!("x", array_value_to_string(actual[0].column(0), 0)?);
+    assert_eq!(schema.field(1).data_type(), actual[0].column(1).data_type());
+    Ok(())
+}
+
+#[tokio::test]
+async fn parquet_map_columns() -> Result<()> {
+    use parquet::column::writer::ColumnWriter;
+    use parquet::data_type::ByteArray;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::{FileWriter, RowGroupWriter, SerializedFileWriter};
+    use parquet::schema::parser::parse_message_type;
+
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path().join("map.parquet");
+    let schema = parse_message_type(
+        "message schema {
+            REQUIRED INT32 id;
+            OPTIONAL group m (MAP) {
+                REPEATED group key_value {
+                    REQUIRED BYTE_ARRAY key (UTF8);
+                    OPTIONAL INT64 value;
+                }
+            }
+        }",
+    )?;
+    // the rows {a: 1, b: 2}, null and {c: 3}
+    let rep_levels = [0, 1, 0, 0];
+    let file = std::fs::File::create(&path)?;
+    let mut writer = SerializedFileWriter::new(
+        file,
+        Arc::new(schema),
+        Arc::new(WriterProperties::builder().build()),
+    )?;
+    let mut row_group = writer.next_row_group()?;
+    while let Some(mut column) = row_group.next_column()? {
+        match &mut column {
+            ColumnWriter::Int32ColumnWriter(writer) => {
+                writer.write_batch(&[1, 2, 3], None, None)?;
+            }
+            ColumnWriter::ByteArrayColumnWriter(writer) => {
+                let keys: Vec<ByteArray> = vec!["a".into(), "b".into(), "c".into()];
+                writer.write_batch(&keys, Some(&[2, 2, 0, 2]), Some(&rep_levels))?;
+            }
+            ColumnWriter::Int64ColumnWriter(writer) => {
+                writer.write_batch(&[1, 2, 3], Some(&[3, 3, 0, 3]), Some(&rep_levels))?;
+            }
+            _ => unreachable!(),
+        }
+        row_group.close_column(column)?;
+    }
+    writer.close_row_group(row_group)?;
+    writer.close()?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_parquet("t", path.to_str().unwrap()).await?;
+
+    let sql = "SELECT id, m['a'] AS a, m['c'] AS c FROM t ORDER BY id";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+---+---+",
+        "| id | a | c |",
+        "+----+---+---+",
+        "| 1  | 1 |   |",
+        "| 2  |   |   |",
+        "| 3  |   | 3 |",
+        "+----+---+---+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = "SELECT map_keys(m) AS k, map_values(m) AS v FROM t";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let keys = actual[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    assert_eq!(
+        &StringArray::from(vec!["a", "b"]),
+        keys.value(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+    );
+    assert!(keys.is_null(1));
+    let values = actual[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    assert_eq!(
+        &Int64Array::from(vec![3]),
+        values
+            .value(2)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+    );
+    Ok(())
+}
+
 #[tokio::test]
 #[ignore = "Test ignored, will be enabled as part of the nested Parquet reader"]
 async fn parquet_list_columns() {
@@ -1260,6 +1424,101 @@ fn custom_sqrt(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     }
 }
 
+/// Registers `my_range(start, end)`, returning the integers from `start` to `end`
+fn register_range_function(ctx: &mut ExecutionContext) {
+    let fun: TableFunctionImplementation = Arc::new(|args| {
+        let bound = |i: usize| match args.get(i) {
+            Some(ScalarValue::Int64(Some(bound))) => Ok(*bound),
+            _ => Err(DataFusionError::Plan(
+                "my_range expects two integer arguments".to_string(),
+            )),
+        };
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "value",
+            DataType::Int64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(
+                bound(0)?..=bound(1)?,
+            ))],
+        )?;
+        Ok(Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))
+    });
+    ctx.register_udtf(TableUDF::new("my_range", &fun));
+}
+
+#[tokio::test]
+async fn query_table_function() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_range_function(&mut ctx);
+
+    let sql = "SELECT r.value * 2 AS v FROM my_range(1, 1 + 3) AS r WHERE value > 2";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec!["+---+", "| v |", "+---+", "| 6 |", "| 8 |", "+---+"];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    let sql = "SELECT COUNT(*) FROM my_range(1, 10) a JOIN my_range(5, 20) b \
+               ON a.value = b.value";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["6"]]);
+
+    let err = ctx
+        .create_logical_plan("SELECT * FROM my_range(1, 'a')")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error during planning: my_range expects two integer arguments"
+    );
+    let err = ctx
+        .create_logical_plan("SELECT * FROM unknown_range(1, 10)")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error during planning: Table function with name 'unknown_range' not found"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_series_table_functions() -> Result<()> {
+    let config = ExecutionConfig::new().with_target_partitions(4);
+    let mut ctx = ExecutionContext::with_config(config);
+
+    let sql = "SELECT COUNT(*), SUM(n), MIN(n), MAX(n) FROM range(0, 1000000, 3) AS t(n)";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["333334", "166666833333", "0", "999999"]]);
+
+    let sql = "SELECT * FROM generate_series(5, 1, -2)";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-----------------+",
+        "| generate_series |",
+        "+-----------------+",
+        "| 5               |",
+        "| 3               |",
+        "| 1               |",
+        "+-----------------+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    // a date spine
+    let sql = "SELECT d FROM generate_series(DATE '2021-02-27', DATE '2021-03-02', \
+               INTERVAL '1 day') AS days(d) ORDER BY d";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(
+        actual,
+        vec![
+            vec!["2021-02-27"],
+            vec!["2021-02-28"],
+            vec!["2021-03-01"],
+            vec!["2021-03-02"]
+        ]
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_avg() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -1587,6 +1846,111 @@ async fn csv_query_window_with_partition_by_order_by() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_window_ntile_lag_lead_ignore_nulls() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let sql = "SELECT a, b, \
+               ntile(2) OVER (ORDER BY a) AS n, \
+               lag(b, 1, -1) OVER (ORDER BY a) AS lg, \
+               lead(b, 2, 0) OVER (ORDER BY a) AS ld, \
+               first_value(b, true) OVER (ORDER BY a ROWS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING) AS fv, \
+               last_value(b, true) OVER (ORDER BY a ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW) AS lv, \
+               nth_value(b, 2) OVER (ORDER BY a ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING) AS nv \
+               FROM (VALUES (1, 10), (2, NULL), (3, 30), (4, NULL), (5, 50)) AS t(a, b) \
+               ORDER BY a";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+----+---+----+----+----+----+----+",
+        "| a | b  | n | lg | ld | fv | lv | nv |",
+        "+---+----+---+----+----+----+----+----+",
+        "| 1 | 10 | 1 | -1 | 30 | 10 | 10 |    |",
+        "| 2 |    | 1 | 10 |    | 30 | 10 |    |",
+        "| 3 | 30 | 1 |    | 50 | 30 | 30 | 30 |",
+        "| 4 |    | 2 | 30 | 0  | 50 | 30 |    |",
+        "| 5 | 50 | 2 |    | 0  | 50 | 50 | 50 |",
+        "+---+----+---+----+----+----+----+----+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_window_frame_offsets() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let sql = "SELECT a, b, \
+               SUM(b) OVER (ORDER BY a, b ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING) AS r, \
+               SUM(b) OVER (ORDER BY a RANGE BETWEEN 1 PRECEDING AND 2 FOLLOWING) AS rg, \
+               SUM(b) OVER (ORDER BY a GROUPS BETWEEN 1 PRECEDING AND CURRENT ROW) AS g, \
+               SUM(b) OVER (ORDER BY a DESC RANGE BETWEEN CURRENT ROW AND 2 FOLLOWING) AS rd \
+               FROM (VALUES (1, 10), (2, 20), (2, 30), (4, 40), (7, 70)) AS t(a, b) \
+               ORDER BY a, b";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+----+-----+-----+-----+----+",
+        "| a | b  | r   | rg  | g   | rd |",
+        "+---+----+-----+-----+-----+----+",
+        "| 1 | 10 | 30  | 60  | 10  | 10 |",
+        "| 2 | 20 | 60  | 100 | 60  | 60 |",
+        "| 2 | 30 | 90  | 100 | 60  | 60 |",
+        "| 4 | 40 | 140 | 40  | 90  | 90 |",
+        "| 7 | 70 | 110 | 70  | 110 | 70 |",
+        "+---+----+-----+-----+-----+----+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_window_frame_offsets_large_keys() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    // 2^53 and 2^53 + 1 are the same f64
+    let sql = "SELECT a, b, \
+               SUM(b) OVER (ORDER BY a RANGE BETWEEN 0 PRECEDING AND 0 FOLLOWING) AS s, \
+               SUM(b) OVER (ORDER BY a DESC RANGE BETWEEN 1 PRECEDING AND CURRENT ROW) AS d \
+               FROM (VALUES (9007199254740992, 1), (9007199254740993, 2), (9007199254740995, 4)) AS t(a, b) \
+               ORDER BY a";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+------------------+---+---+---+",
+        "| a                | b | s | d |",
+        "+------------------+---+---+---+",
+        "| 9007199254740992 | 1 | 1 | 3 |",
+        "| 9007199254740993 | 2 | 2 | 2 |",
+        "| 9007199254740995 | 4 | 4 | 4 |",
+        "+------------------+---+---+---+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_window_frame_exclusions() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let sql = "SELECT a, b, \
+               SUM(b) OVER (ORDER BY a RANGE BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING EXCLUDE NO OTHERS) AS n, \
+               SUM(b) OVER (ORDER BY a RANGE BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING EXCLUDE CURRENT ROW) AS cr, \
+               SUM(b) OVER (ORDER BY a RANGE BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING EXCLUDE GROUP) AS gr, \
+               SUM(b) OVER (ORDER BY a RANGE BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING EXCLUDE TIES) AS ti, \
+               SUM(b) OVER (ORDER BY a GROUPS BETWEEN 1 PRECEDING AND 1 FOLLOWING EXCLUDE GROUP) AS gx, \
+               first_value(b) OVER (ORDER BY a ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW EXCLUDE CURRENT ROW) AS fv \
+               FROM (VALUES (1, 10), (2, 20), (2, 30), (4, 40), (7, 70)) AS t(a, b) \
+               ORDER BY a, b";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+----+-----+-----+-----+-----+-----+----+",
+        "| a | b  | n   | cr  | gr  | ti  | gx  | fv |",
+        "+---+----+-----+-----+-----+-----+-----+----+",
+        "| 1 | 10 | 170 | 160 | 160 | 170 | 50  |    |",
+        "| 2 | 20 | 170 | 150 | 120 | 140 | 50  | 10 |",
+        "| 2 | 30 | 170 | 140 | 120 | 150 | 50  | 10 |",
+        "| 4 | 40 | 170 | 130 | 130 | 170 | 120 | 10 |",
+        "| 7 | 70 | 170 | 100 | 100 | 170 | 40  | 10 |",
+        "+---+----+-----+-----+-----+-----+-----+----+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_group_by_int_count() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -1971,6 +2335,47 @@ async fn csv_query_limit() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn table_sample() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx).await?;
+    register_alltypes_parquet(&mut ctx).await;
+
+    let cases = vec![
+        ("aggregate_test_100 TABLESAMPLE BERNOULLI (100)", "100"),
+        ("aggregate_test_100 TABLESAMPLE BERNOULLI (0 PERCENT)", "0"),
+        (
+            "aggregate_test_100 AS t TABLESAMPLE SYSTEM (100) REPEATABLE (3)",
+            "100",
+        ),
+        ("aggregate_test_100 TABLESAMPLE SYSTEM (0)", "0"),
+        ("alltypes_plain TABLESAMPLE SYSTEM (100)", "8"),
+        ("alltypes_plain TABLESAMPLE SYSTEM (0)", "0"),
+    ];
+    for (relation, expected) in cases {
+        let sql = format!("SELECT COUNT(*) FROM {}", relation);
+        let actual = execute(&mut ctx, &sql).await;
+        assert_eq!(vec![vec![expected.to_string()]], actual, "{}", sql);
+    }
+
+    let sql = "SELECT COUNT(*) FROM aggregate_test_100 TABLESAMPLE BERNOULLI (50) REPEATABLE (7)";
+    let first = execute(&mut ctx, sql).await;
+    assert_eq!(first, execute(&mut ctx, sql).await);
+
+    // SYSTEM samples of files are pushed into the scan
+    let sql =
+        "EXPLAIN SELECT * FROM alltypes_plain TABLESAMPLE SYSTEM (10) REPEATABLE (1)";
+    let actual = execute(&mut ctx, sql).await;
+    let physical_plan = &actual[1][1];
+    assert!(
+        physical_plan.contains("row_group_sample=0.1 (seed=1)"),
+        "{}",
+        physical_plan
+    );
+    assert!(!physical_plan.contains("SampleExec"), "{}", physical_plan);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_limit_bigger_than_nbr_of_rows() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -2496,6 +2901,59 @@ async fn left_join_using() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn right_and_full_join_using() -> Result<()> {
+    let mut ctx = create_join_context("id", "id")?;
+    let sql = "SELECT id, t1_name, t2_name FROM t1 RIGHT JOIN t2 USING (id) ORDER BY id";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+---------+---------+",
+        "| id | t1_name | t2_name |",
+        "+----+---------+---------+",
+        "| 11 | a       | z       |",
+        "| 22 | b       | y       |",
+        "| 44 | d       | x       |",
+        "| 55 |         | w       |",
+        "+----+---------+---------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = "SELECT * FROM t1 FULL JOIN t2 USING (id) ORDER BY id";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+---------+---------+",
+        "| id | t1_name | t2_name |",
+        "+----+---------+---------+",
+        "| 11 | a       | z       |",
+        "| 22 | b       | y       |",
+        "| 33 | c       |         |",
+        "| 44 | d       | x       |",
+        "| 55 |         | w       |",
+        "+----+---------+---------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn qualified_wildcard_with_exclude() -> Result<()> {
+    let mut ctx = create_join_context("id", "id")?;
+    let sql = "SELECT t2.* EXCLUDE (t2_name), t1.* EXCEPT (id) \
+        FROM t1 JOIN t2 USING (id) ORDER BY id";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+---------+",
+        "| id | t1_name |",
+        "+----+---------+",
+        "| 11 | a       |",
+        "| 22 | b       |",
+        "| 44 | d       |",
+        "+----+---------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn equijoin_implicit_syntax() -> Result<()> {
     let mut ctx = create_join_context("t1_id", "t2_id")?;
@@ -2985,26 +3443,186 @@ async fn csv_explain_analyze_verbose() {
     assert_contains!(formatted, verbose_needle);
 }
 
-/// A macro to assert that some particular line contains two substrings
-///
-/// Usage: `assert_metrics!(actual, operator_name, metrics)`
-///
-macro_rules! assert_metrics {
-    ($ACTUAL: expr, $OPERATOR_NAME: expr, $METRICS: expr) => {
-        let found = $ACTUAL
-            .lines()
-            .any(|line| line.contains($OPERATOR_NAME) && line.contains($METRICS));
-        assert!(
-            found,
-            "Can not find a line with both '{}' and '{}' in\n\n{}",
-            $OPERATOR_NAME, $METRICS, $ACTUAL
-        );
+#[tokio::test]
+async fn fold_uncorrelated_subqueries() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id, t1_name FROM t1 \
+               WHERE t1_id IN (SELECT t2_id FROM t2 WHERE t2_name > 'x') \
+               OR t1_id = (SELECT max(t2_id) FROM t2 WHERE t2_id < 30)";
+    let df = ctx.sql(sql).await?;
+    let plan = format!("{:?}", df.to_logical_plan());
+    assert!(
+        plan.contains("#t1.t1_id IN ([Int64(11), Int64(22)]) OR #t1.t1_id = Int64(22)"),
+        "{}",
+        plan
+    );
+
+    let expected = vec![
+        "+-------+---------+",
+        "| t1_id | t1_name |",
+        "+-------+---------+",
+        "| 11    | a       |",
+        "| 22    | b       |",
+        "+-------+---------+",
+    ];
+    assert_batches_sorted_eq!(expected, &df.collect().await?);
+
+    // empty IN subqueries fold to a constant
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id NOT IN (SELECT t2_id FROM t2 WHERE t2_id > 100)";
+    let batches = ctx.sql(sql).await?.collect().await?;
+    assert_eq!(4, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+
+    let err = ctx.sql("SELECT (SELECT t2_id FROM t2)").await.unwrap_err();
+    assert_eq!(
+        "Execution error: Scalar subquery returned more than one row",
+        err.to_string()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn in_subquery_joins() -> Result<()> {
+    // subqueries returning any row are not folded, but joined
+    let mut ctx = ExecutionContext::with_config(
+        ExecutionConfig::new().with_max_folded_subquery_rows(0),
+    );
+    let table = |name: &str, values: Vec<Option<i32>>| -> Result<Arc<MemTable>> {
+        let schema = Arc::new(Schema::new(vec![Field::new(name, DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(values))],
+        )?;
+        Ok(Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))
     };
+    ctx.register_table("t1", table("a", vec![Some(1), Some(2), None])?)?;
+    ctx.register_table("t2", table("b", vec![Some(1), Some(3)])?)?;
+    ctx.register_table("t3", table("c", vec![Some(1), None])?)?;
+
+    let sql = "SELECT a FROM t1 WHERE a IN (SELECT b FROM t2)";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec!["+---+", "| a |", "+---+", "| 1 |", "+---+"];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    // a null on the left side is not in a non empty set
+    let sql = "SELECT a FROM t1 WHERE a NOT IN (SELECT b FROM t2) AND a > 0";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec!["+---+", "| a |", "+---+", "| 2 |", "+---+"];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    // nothing is known not to be in a set with a null
+    let sql = "SELECT a FROM t1 WHERE a NOT IN (SELECT c FROM t3)";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_eq!(0, actual.iter().map(|b| b.num_rows()).sum::<usize>());
+
+    // but everything is not in an empty set
+    let sql = "SELECT a FROM t1 WHERE a NOT IN (SELECT b FROM t2 WHERE b > 10)";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_eq!(3, actual.iter().map(|b| b.num_rows()).sum::<usize>());
+    Ok(())
 }
 
 #[tokio::test]
-async fn explain_analyze_baseline_metrics() {
-    // This test uses the execute function to run an actual plan under EXPLAIN ANALYZE
+async fn csv_explain_formats() {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv_by_sql(&mut ctx).await;
+
+    let sql = "EXPLAIN (FORMAT JSON) SELECT c1 FROM aggregate_test_100 where c2 > 10";
+    let actual = normalize_vec_for_explain(execute(&mut ctx, sql).await);
+    let expected = vec![
+        vec![
+            "logical_plan",
+            "{\"node\": \"Projection: #aggregate_test_100.c1\", \"schema\": \"[c1:Utf8]\", \"inputs\": [\
+             {\"node\": \"Filter: #aggregate_test_100.c2 > Int64(10)\", \"schema\": \"[c1:Utf8, c2:Int32]\", \"inputs\": [\
+             {\"node\": \"TableScan: aggregate_test_100 projection=Some([0, 1]), filters=[#aggregate_test_100.c2 > Int64(10)]\", \"schema\": \"[c1:Utf8, c2:Int32]\", \"inputs\": []}]}]}",
+        ],
+        vec![
+            "physical_plan",
+            "{\"node\": \"ProjectionExec: expr=[c1@0 as c1]\", \"inputs\": [\
+             {\"node\": \"CoalesceBatchesExec: target_batch_size=4096\", \"inputs\": [\
+             {\"node\": \"FilterExec: CAST(c2@1 AS Int64) > 10\", \"inputs\": [\
+             {\"node\": \"RepartitionExec: partitioning=RoundRobinBatch(NUM_CORES)\", \"inputs\": [\
+             {\"node\": \"CsvExec: files=[ARROW_TEST_DATA/csv/aggregate_test_100.csv], has_header=true, batch_size=8192, limit=None\", \"inputs\": []}]}]}]}]}",
+        ],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "EXPLAIN (FORMAT DOT) SELECT c1 FROM aggregate_test_100 where c2 > 10";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual.len(), 2);
+    for plan in &actual {
+        assert_contains!(
+            &plan[1],
+            "// Begin DataFusion GraphViz Plan (see https://graphviz.org)"
+        );
+    }
+    assert_contains!(
+        &actual[1][1],
+        "[shape=box label=\"FilterExec: CAST(c2@1 AS Int64) > 10\"]"
+    );
+
+    let sql = "EXPLAIN (ANALYZE, FORMAT JSON) SELECT count(*), c1 FROM aggregate_test_100 group by c1";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual[0][0], "Plan with Metrics");
+    assert_contains!(
+        &actual[0][1],
+        "{\"node\": \"CoalescePartitionsExec\", \"metrics\": [{\"name\": \"output_rows\", \"value\": 5}"
+    );
+}
+
+#[tokio::test]
+async fn explain_costs() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Int32Array::from((0..300).collect::<Vec<_>>()))],
+    )?;
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+
+    let sql = "EXPLAIN (COSTS) SELECT a FROM t WHERE a > 10";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual[1][0], "physical_plan");
+    let plan = &actual[1][1];
+    let line = |operator: &str| {
+        plan.lines()
+            .find(|line| line.contains(operator))
+            .unwrap_or_else(|| panic!("Can not find {} in\n\n{}", operator, plan))
+    };
+    // a range predicate is estimated to keep a third of the rows
+    assert_contains!(
+        line("FilterExec"),
+        "estimated_rows=100, estimated_bytes=400"
+    );
+    assert_contains!(line("MemoryExec"), "estimated_rows=300");
+    // the logical plan has no estimates
+    assert_not_contains!(&actual[0][1], "estimated_rows");
+
+    let sql = "EXPLAIN (COSTS FALSE) SELECT a FROM t WHERE a > 10";
+    let actual = execute(&mut ctx, sql).await;
+    assert_not_contains!(&actual[1][1], "estimated_rows");
+    Ok(())
+}
+
+/// A macro to assert that some particular line contains two substrings
+///
+/// Usage: `assert_metrics!(actual, operator_name, metrics)`
+///
+macro_rules! assert_metrics {
+    ($ACTUAL: expr, $OPERATOR_NAME: expr, $METRICS: expr) => {
+        let found = $ACTUAL
+            .lines()
+            .any(|line| line.contains($OPERATOR_NAME) && line.contains($METRICS));
+        assert!(
+            found,
+            "Can not find a line with both '{}' and '{}' in\n\n{}",
+            $OPERATOR_NAME, $METRICS, $ACTUAL
+        );
+    };
+}
+
+#[tokio::test]
+async fn explain_analyze_baseline_metrics() {
+    // This test uses the execute function to run an actual plan under EXPLAIN ANALYZE
     // and then validate the presence of baseline metrics for supported operators
     let config = ExecutionConfig::new().with_target_partitions(3);
     let mut ctx = ExecutionContext::with_config(config);
@@ -4319,6 +4937,154 @@ async fn query_count_distinct() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_multiple_distinct_aggregates() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("k", DataType::Utf8, false),
+        Field::new("a", DataType::Int32, true),
+        Field::new("b", DataType::Int32, true),
+    ]));
+
+    let partition1 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["x", "x", "y", "x"])),
+            Arc::new(Int32Array::from(vec![1, 2, 1, 1])),
+            Arc::new(Int32Array::from(vec![Some(10), Some(10), Some(5), None])),
+        ],
+    )?;
+    let partition2 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["x", "y", "y"])),
+            Arc::new(Int32Array::from(vec![Some(2), Some(3), None])),
+            Arc::new(Int32Array::from(vec![20, 5, 7])),
+        ],
+    )?;
+
+    // the distinct values of each partition are merged by the final aggregation
+    let table = MemTable::try_new(schema, vec![vec![partition1], vec![partition2]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+    let sql = "SELECT k, COUNT(DISTINCT a), SUM(DISTINCT b), COUNT(b) FROM test GROUP BY k ORDER BY k";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+------------------------+----------------------+---------------+",
+        "| k | COUNT(DISTINCT test.a) | SUM(DISTINCT test.b) | COUNT(test.b) |",
+        "+---+------------------------+----------------------+---------------+",
+        "| x | 2                      | 30                   | 3             |",
+        "| y | 2                      | 12                   | 3             |",
+        "+---+------------------------+----------------------+---------------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_aggregate_filter() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("k", DataType::Utf8, false),
+        Field::new("a", DataType::Int32, true),
+        Field::new("b", DataType::Int32, true),
+    ]));
+
+    let partition1 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["x", "x", "y", "x"])),
+            Arc::new(Int32Array::from(vec![1, 2, 1, 1])),
+            Arc::new(Int32Array::from(vec![Some(10), Some(10), Some(5), None])),
+        ],
+    )?;
+    let partition2 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["x", "y", "y"])),
+            Arc::new(Int32Array::from(vec![Some(2), Some(3), None])),
+            Arc::new(Int32Array::from(vec![20, 5, 7])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![partition1], vec![partition2]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+    let sql = "SELECT k, \
+        COUNT(*) FILTER (WHERE a > 1) AS c, \
+        SUM(b) FILTER (WHERE a = 1) AS s, \
+        COUNT(DISTINCT b) FILTER (WHERE b > 5) AS d \
+        FROM test GROUP BY k ORDER BY k";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+---+----+---+",
+        "| k | c | s  | d |",
+        "+---+---+----+---+",
+        "| x | 2 | 10 | 2 |",
+        "| y | 1 | 5  | 1 |",
+        "+---+---+----+---+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    // the filter is part of the name of the aggregate
+    let sql = "SELECT SUM(b) FILTER (WHERE a = 1) FROM test";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----------------------------------------------+",
+        "| SUM(test.b) FILTER (WHERE test.a = Int64(1)) |",
+        "+----------------------------------------------+",
+        "| 15                                           |",
+        "+----------------------------------------------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_ordered_aggregates() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("k", DataType::Utf8, false),
+        Field::new("a", DataType::Int32, true),
+        Field::new("s", DataType::Utf8, true),
+    ]));
+
+    let partition1 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["x", "x", "y", "x"])),
+            Arc::new(Int32Array::from(vec![3, 1, 2, 2])),
+            Arc::new(StringArray::from(vec!["c", "a", "q", "b"])),
+        ],
+    )?;
+    let partition2 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["x", "y", "y"])),
+            Arc::new(Int32Array::from(vec![4, 1, 3])),
+            Arc::new(StringArray::from(vec!["d", "p", "r"])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![partition1], vec![partition2]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+    let sql = "SELECT k, \
+        string_agg(s, '-' ORDER BY a DESC) AS sa, \
+        percentile_cont(0.5) WITHIN GROUP (ORDER BY a) AS pc, \
+        percentile_disc(0.5) WITHIN GROUP (ORDER BY a) AS pd \
+        FROM test GROUP BY k ORDER BY k";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+---------+-----+----+",
+        "| k | sa      | pc  | pd |",
+        "+---+---------+-----+----+",
+        "| x | d-c-b-a | 2.5 | 2  |",
+        "| y | r-q-p   | 2   | 2  |",
+        "+---+---------+-----+----+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn query_group_on_null() -> Result<()> {
     let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Int32, true)]));
@@ -4476,6 +5242,24 @@ async fn query_on_string_dictionary() -> Result<()> {
     ];
     assert_batches_eq!(expected, &actual);
 
+    // comparison with constant, on the values of the dictionary
+    let sql = "SELECT * FROM test WHERE d1 > 'one'";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_batches_eq!(expected, &actual);
+
+    // joining on the keys
+    let sql = "SELECT a.d1 FROM test a JOIN test b ON a.d1 = b.d1";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-------+",
+        "| d1    |",
+        "+-------+",
+        "| one   |",
+        "| three |",
+        "+-------+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
     // Expression evaluation
     let sql = "SELECT concat(d1, '-foo') FROM test";
     let actual = execute_to_batches(&mut ctx, sql).await;
@@ -4628,6 +5412,81 @@ async fn query_cte() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_mixed_case_identifiers() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("Id", DataType::UInt32, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(UInt32Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec!["a", "b", "c"])),
+        ],
+    )?;
+    let table = Arc::new(MemTable::try_new(schema, vec![vec![data]])?);
+    let expected = vec![
+        "+----+------+",
+        "| Id | name |",
+        "+----+------+",
+        "| 2  | b    |",
+        "| 3  | c    |",
+        "+----+------+",
+    ];
+
+    // identifiers are case sensitive by default
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("Mixed", table.clone())?;
+    let sql = "SELECT Id, name FROM Mixed WHERE Id > 1";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_batches_eq!(expected, &actual);
+    assert!(ctx.create_logical_plan("SELECT id FROM Mixed").is_err());
+
+    // unquoted identifiers are folded to lowercase
+    let mut ctx = ExecutionContext::with_config(
+        ExecutionConfig::new()
+            .with_identifier_normalization(IdentifierNormalization::Lowercase),
+    );
+    ctx.register_table("Mixed", table)?;
+    let sql = "SELECT \"Id\", NAME FROM \"Mixed\" WHERE \"Id\" > 1";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_batches_eq!(expected, &actual);
+    assert!(ctx.create_logical_plan("SELECT Id FROM \"Mixed\"").is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_cte_materialized() -> Result<()> {
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_materialize_ctes(true));
+
+    // a CTE referenced twice is materialized once
+    let sql = "WITH t AS (SELECT 1 AS a) SELECT * FROM t UNION ALL SELECT * FROM t";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    assert_eq!(
+        format!("{:?}", plan).matches("MaterializedCte: t").count(),
+        2,
+        "{:?}",
+        plan
+    );
+    let physical_plan = ctx.create_physical_plan(&plan).await?;
+    let formatted = displayable(physical_plan.as_ref()).indent().to_string();
+    assert_contains!(&formatted, "MaterializeExec: cte=t");
+
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec!["+---+", "| a |", "+---+", "| 1 |", "| 1 |", "+---+"];
+    assert_batches_eq!(expected, &actual);
+
+    // a CTE referenced once is inlined
+    let sql = "WITH t AS (SELECT 1 AS a) SELECT * FROM t";
+    let plan = ctx.create_logical_plan(sql)?;
+    assert!(!format!("{:?}", plan).contains("MaterializedCte"));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn query_cte_incorrect() -> Result<()> {
     let ctx = ExecutionContext::new();
@@ -5064,6 +5923,69 @@ async fn test_interval_expressions() -> Result<()> {
         "interval '2' year",
         "2 years 0 mons 0 days 0 hours 0 mins 0.00 secs"
     );
+    test_expression!(
+        "interval '2 weeks'",
+        "0 years 0 mons 14 days 0 hours 0 mins 0.00 secs"
+    );
+    test_expression!(
+        "interval '1-2' year to month",
+        "1 years 2 mons 0 days 0 hours 0 mins 0.00 secs"
+    );
+    test_expression!(
+        "interval '3 04:05:06.5' day to second",
+        "0 years 0 mons 3 days 4 hours 5 mins 6.500 secs"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_interval_arithmetic_and_extract() -> Result<()> {
+    test_expression!(
+        "CAST('2020-01-31' AS DATE) + INTERVAL '1 month'",
+        "2020-02-29"
+    );
+    test_expression!(
+        "CAST('2020-03-01' AS DATE) - INTERVAL '1 day'",
+        "2020-02-29"
+    );
+    test_expression!(
+        "INTERVAL '1 day' + CAST('2020-02-28' AS DATE)",
+        "2020-02-29"
+    );
+    test_expression!(
+        "CAST('2020-01-31' AS DATE) + INTERVAL '1 year 1 month 1 day'",
+        "2021-03-01"
+    );
+    test_expression!(
+        "to_timestamp('2020-01-31T10:00:00') + INTERVAL '1 month 1 hour' \
+         = to_timestamp('2020-02-29T11:00:00')",
+        "true"
+    );
+    test_expression!(
+        "to_timestamp('2020-01-01T00:00:00') - INTERVAL '1 microsecond' \
+         < to_timestamp('2020-01-01T00:00:00')",
+        "true"
+    );
+    test_expression!(
+        "INTERVAL '1 day' + INTERVAL '2 hours'",
+        "0 years 0 mons 1 days 2 hours 0 mins 0.00 secs"
+    );
+    test_expression!(
+        "-INTERVAL '1 month'",
+        "0 years -1 mons 0 days 0 hours 0 mins 0.00 secs"
+    );
+    test_expression!("INTERVAL '1 month' > INTERVAL '29 days'", "true");
+    test_expression!("INTERVAL '24 hours' = INTERVAL '1 day'", "true");
+    test_expression!("INTERVAL '1 year' < INTERVAL '1 year 1 day'", "true");
+    test_expression!(
+        "INTERVAL '-04:05' HOUR TO MINUTE = -INTERVAL '4 hours 5 minutes'",
+        "true"
+    );
+    test_expression!("EXTRACT(HOUR FROM INTERVAL '25 hours')", "25");
+    test_expression!("EXTRACT(DAY FROM INTERVAL '3 04:05:06' DAY TO SECOND)", "3");
+    test_expression!("EXTRACT(YEAR FROM INTERVAL '1-2' YEAR TO MONTH)", "1");
+    test_expression!("EXTRACT(MONTH FROM INTERVAL '1-2' YEAR TO MONTH)", "2");
+    test_expression!("date_part('minute', INTERVAL '1 day 90 minutes')", "30");
     Ok(())
 }
 
@@ -5279,6 +6201,75 @@ async fn test_extract_date_part() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_date_bin() -> Result<()> {
+    test_expression!(
+        "date_bin(INTERVAL '15 minutes', to_timestamp('2020-09-08T13:42:29Z')) \
+         = to_timestamp('2020-09-08T13:30:00Z')",
+        "true"
+    );
+    test_expression!(
+        "date_bin(INTERVAL '15 minutes', to_timestamp('2020-09-08T13:42:29Z'), \
+         to_timestamp('2020-09-08T13:05:00Z')) = to_timestamp('2020-09-08T13:35:00Z')",
+        "true"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn time_bucketed_aggregates() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "time",
+        DataType::Timestamp(TimeUnit::Nanosecond, None),
+        true,
+    )]));
+    // 2021-01-01 00:00:00 plus 60, 299, 300 and 750 seconds
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(TimestampNanosecondArray::from(
+            [60, 299, 300, 750]
+                .iter()
+                .map(|s| (1_609_459_200 + s) * 1_000_000_000)
+                .collect::<Vec<i64>>(),
+        ))],
+    )?;
+    ctx.register_table(
+        "events",
+        Arc::new(MemTable::try_new(schema, vec![vec![data]])?),
+    )?;
+
+    let sql = "SELECT date_bin(INTERVAL '5 minutes', time) AS bucket, COUNT(*) AS n \
+               FROM events \
+               GROUP BY date_bin(INTERVAL '5 minutes', time)";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---------------------+---+",
+        "| bucket              | n |",
+        "+---------------------+---+",
+        "| 2021-01-01 00:00:00 | 2 |",
+        "| 2021-01-01 00:05:00 | 1 |",
+        "| 2021-01-01 00:10:00 | 1 |",
+        "+---------------------+---+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    let sql = "SELECT w['start'] AS window_start, w['end'] AS window_end, COUNT(*) AS n \
+               FROM (SELECT window(time, '10 minutes') AS w FROM events) AS t \
+               GROUP BY w['start'], w['end']";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---------------------+---------------------+---+",
+        "| window_start        | window_end          | n |",
+        "+---------------------+---------------------+---+",
+        "| 2021-01-01 00:00:00 | 2021-01-01 00:10:00 | 3 |",
+        "| 2021-01-01 00:10:00 | 2021-01-01 00:20:00 | 1 |",
+        "+---------------------+---------------------+---+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_in_list_scalar() -> Result<()> {
     test_expression!("'a' IN ('a','b')", "true");
@@ -5324,6 +6315,340 @@ async fn test_in_list_scalar() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_in_list_hash_set() -> Result<()> {
+    // large lists are evaluated with a hash set
+    let list = (0..100)
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    test_expression!(format!("5 IN ({})", list), "true");
+    test_expression!(format!("100 IN ({})", list), "false");
+    test_expression!(format!("100 NOT IN ({})", list), "true");
+    test_expression!(format!("100 IN ({},NULL)", list), "NULL");
+    test_expression!(format!("5 NOT IN ({},NULL)", list), "false");
+    test_expression!(format!("NULL IN ({})", list), "NULL");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_row_in_list() -> Result<()> {
+    test_expression!("(1, 'a') IN ((1, 'a'), (2, 'b'))", "true");
+    test_expression!("(1, 'b') IN ((1, 'a'), (2, 'b'))", "false");
+    test_expression!("(1, 'b') NOT IN ((1, 'a'), (2, 'b'))", "true");
+    test_expression!("(2, 'b') NOT IN ((1, 'a'), (2, 'b'))", "false");
+    test_expression!("(1, NULL) IN ((1, 'a'), (2, 'b'))", "NULL");
+    test_expression!("(3, 'a') IN ((1, 'a'), (2, NULL))", "false");
+    test_expression!("(2, 'a') IN ((1, 'a'), (2, NULL))", "NULL");
+
+    let mut ctx = ExecutionContext::new();
+    let err = ctx
+        .create_logical_plan("SELECT (1, 2) IN ((1, 2), (3))")
+        .unwrap_err();
+    assert_eq!(
+        "Error during planning: The IN list of a row of 2 values must only have rows of 2 values",
+        err.to_string()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_binary_columns() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+
+    let t_schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("b", DataType::Binary, true),
+        Field::new("f", DataType::FixedSizeBinary(2), false),
+    ]));
+    let t_data = RecordBatch::try_new(
+        t_schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+            Arc::new(BinaryArray::from(vec![
+                Some(&[1u8, 2][..]),
+                Some(&[1u8, 2][..]),
+                Some(&[255u8][..]),
+                None,
+            ])),
+            Arc::new(FixedSizeBinaryArray::try_from_iter(
+                vec![[1u8, 2], [3, 4], [1, 2], [5, 6]].into_iter(),
+            )?),
+        ],
+    )?;
+    let t_table = MemTable::try_new(t_schema, vec![vec![t_data]])?;
+    ctx.register_table("t", Arc::new(t_table))?;
+
+    let u_schema = Arc::new(Schema::new(vec![
+        Field::new("k", DataType::FixedSizeBinary(2), false),
+        Field::new("v", DataType::Int32, false),
+    ]));
+    let u_data = RecordBatch::try_new(
+        u_schema.clone(),
+        vec![
+            Arc::new(FixedSizeBinaryArray::try_from_iter(
+                vec![[1u8, 2], [5, 6]].into_iter(),
+            )?),
+            Arc::new(Int32Array::from(vec![10, 20])),
+        ],
+    )?;
+    let u_table = MemTable::try_new(u_schema, vec![vec![u_data]])?;
+    ctx.register_table("u", Arc::new(u_table))?;
+
+    let sql = "SELECT id FROM t WHERE b = X'0102' OR b > X'0102' OR f = X'0506'";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+", "| id |", "+----+", "| 1  |", "| 2  |", "| 3  |", "| 4  |", "+----+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    let sql = "SELECT id FROM t WHERE b <> X'0102' AND f >= X'0102'";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec!["+----+", "| id |", "+----+", "| 3  |", "+----+"];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    let sql = "SELECT COUNT(*) AS c, MIN(id) AS id FROM t GROUP BY b";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+----+",
+        "| c | id |",
+        "+---+----+",
+        "| 1 | 3  |",
+        "| 1 | 4  |",
+        "| 2 | 1  |",
+        "+---+----+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    let sql = "SELECT t.id, u.v FROM t JOIN u ON t.f = u.k";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+----+",
+        "| id | v  |",
+        "+----+----+",
+        "| 1  | 10 |",
+        "| 3  | 10 |",
+        "| 4  | 20 |",
+        "+----+----+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    let err = ctx
+        .create_logical_plan("SELECT X'012' = X'01'")
+        .unwrap_err();
+    assert_eq!(
+        "Error during planning: Invalid hexadecimal string literal X'012'",
+        err.to_string()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_large_utf8_columns() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+
+    let t_schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("s", DataType::LargeUtf8, true),
+    ]));
+    let t_data = RecordBatch::try_new(
+        t_schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+            Arc::new(LargeStringArray::from(vec![
+                Some("apple"),
+                Some("banana"),
+                Some("apple"),
+                None,
+            ])),
+        ],
+    )?;
+    let t_table = MemTable::try_new(t_schema, vec![vec![t_data]])?;
+    ctx.register_table("t", Arc::new(t_table))?;
+
+    let u_schema = Arc::new(Schema::new(vec![
+        Field::new("k", DataType::LargeUtf8, false),
+        Field::new("v", DataType::Int32, false),
+    ]));
+    let u_data = RecordBatch::try_new(
+        u_schema.clone(),
+        vec![
+            Arc::new(LargeStringArray::from(vec!["apple", "cherry"])),
+            Arc::new(Int32Array::from(vec![10, 20])),
+        ],
+    )?;
+    let u_table = MemTable::try_new(u_schema, vec![vec![u_data]])?;
+    ctx.register_table("u", Arc::new(u_table))?;
+
+    let sql = "SELECT id, CASE s WHEN 'apple' THEN s ELSE 'other' END AS c \
+               FROM t WHERE s = 'apple' OR s > 'b' OR s LIKE 'ban%'";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+--------+",
+        "| id | c      |",
+        "+----+--------+",
+        "| 1  | apple  |",
+        "| 2  | other  |",
+        "| 3  | apple  |",
+        "+----+--------+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    let sql = "SELECT s, COUNT(*) AS c, STRING_AGG(s, ',') AS a FROM t GROUP BY s";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+--------+---+-------------+",
+        "| s      | c | a           |",
+        "+--------+---+-------------+",
+        "|        | 1 |             |",
+        "| apple  | 2 | apple,apple |",
+        "| banana | 1 | banana      |",
+        "+--------+---+-------------+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    let sql = "SELECT t.id, u.v FROM t JOIN u ON t.s = u.k";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+----+",
+        "| id | v  |",
+        "+----+----+",
+        "| 1  | 10 |",
+        "| 3  | 10 |",
+        "+----+----+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_timestamps_with_timezone() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+
+    let tz = Some("+08:00".to_owned());
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new(
+            "a",
+            DataType::Timestamp(TimeUnit::Millisecond, tz.clone()),
+            true,
+        ),
+        Field::new("b", DataType::Timestamp(TimeUnit::Second, None), false),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+            Arc::new(TimestampMillisecondArray::from_opt_vec(
+                vec![Some(1_000), Some(2_000), Some(3_000), Some(2_000)],
+                tz.clone(),
+            )),
+            Arc::new(TimestampSecondArray::from_opt_vec(
+                vec![Some(2), Some(2), Some(2), Some(2)],
+                None,
+            )),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    ctx.register_table("t", Arc::new(table))?;
+
+    // the timestamps are compared in milliseconds, whatever their timezones
+    let sql = "SELECT id FROM t WHERE a < b OR a = b";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+", "| id |", "+----+", "| 1  |", "| 2  |", "| 4  |", "+----+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    // aggregates and group keys keep the timezone of their input
+    let sql = "SELECT a, COUNT(*) AS c, MAX(a) AS m FROM t GROUP BY a";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let timestamp = DataType::Timestamp(TimeUnit::Millisecond, tz);
+    assert_eq!(actual[0].schema().field(0).data_type(), &timestamp);
+    assert_eq!(actual[0].schema().field(2).data_type(), &timestamp);
+    let expected = vec![
+        "+---------------------+---+---------------------+",
+        "| a                   | c | m                   |",
+        "+---------------------+---+---------------------+",
+        "| 1970-01-01 00:00:01 | 1 | 1970-01-01 00:00:01 |",
+        "| 1970-01-01 00:00:02 | 2 | 1970-01-01 00:00:02 |",
+        "| 1970-01-01 00:00:03 | 1 | 1970-01-01 00:00:03 |",
+        "+---------------------+---+---------------------+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn implicit_type_coercion() -> Result<()> {
+    // strings are compared to numbers as numbers
+    test_expression!("'10' > 9", "true");
+    test_expression!("9 = '9'", "true");
+    // integers are widened instead of truncating the floats they are compared to
+    test_expression!("1 IN (1.5, 2)", "false");
+    test_expression!("2 IN (1.5, 2)", "true");
+    test_expression!("CASE 1 WHEN 1.5 THEN 'a' ELSE 'b' END", "b");
+    // CASE branches are casted to their common type
+    test_expression!("CASE WHEN false THEN 1 ELSE 2.5 END", "2.5");
+
+    let mut ctx = ExecutionContext::with_config(
+        ExecutionConfig::new().with_strict_type_coercion(true),
+    );
+    let plan = ctx.create_logical_plan("SELECT '10' > 9")?;
+    let err = ctx.create_physical_plan(&plan).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error during planning: 'Utf8 > Int64' can't be evaluated without an implicit cast, which strict type coercion does not allow"
+    );
+
+    // widening numbers is allowed in strict mode
+    let sql = "SELECT 1 IN (1.5, 2), CASE WHEN false THEN 1 ELSE 2.5 END";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["false", "2.5"]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn integer_overflow() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Int64Array::from(vec![i64::MAX, 1]))],
+    )?;
+    let table = Arc::new(MemTable::try_new(schema, vec![vec![batch]])?);
+
+    // integers wrap around by default
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", table.clone())?;
+    let actual = execute(&mut ctx, "SELECT SUM(a), MAX(a + 1) FROM t").await;
+    assert_eq!(actual, vec![vec!["-9223372036854775808", "2"]]);
+    let actual = execute(&mut ctx, "SELECT 9223372036854775807 * 2").await;
+    assert_eq!(actual, vec![vec!["-2"]]);
+
+    // and error in the ANSI mode, including the constants folded by the optimizer
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_ansi_mode(true));
+    ctx.register_table("t", table)?;
+    for sql in [
+        "SELECT SUM(a) FROM t",
+        "SELECT a + 1 FROM t",
+        "SELECT 9223372036854775807 * 2",
+    ] {
+        // constants are folded when the query is planned, the rest when it runs
+        let err = match ctx.sql(sql).await {
+            Ok(df) => df.collect().await.unwrap_err(),
+            Err(err) => err,
+        };
+        assert_contains!(err.to_string(), "Arithmetic overflow");
+    }
+    let actual = execute(&mut ctx, "SELECT SUM(a - 1), MAX(a) FROM t").await;
+    assert_eq!(
+        actual,
+        vec![vec!["9223372036854775806", "9223372036854775807"]]
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn in_list_array() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -6378,6 +7703,36 @@ async fn test_expect_distinct() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn intersect_except_all_with_duplicates() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let left = "SELECT * FROM (VALUES (1), (1), (1), (2), (NULL), (NULL)) AS t1(a)";
+    let right = "SELECT * FROM (VALUES (1), (1), (2), (2), (NULL)) AS t2(a)";
+
+    let sql = format!("{} INTERSECT ALL {} ORDER BY a", left, right);
+    let actual = execute_to_batches(&mut ctx, &sql).await;
+    let expected = vec![
+        "+---+", "| a |", "+---+", "| 1 |", "| 1 |", "| 2 |", "|   |", "+---+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = format!("{} EXCEPT ALL {} ORDER BY a", left, right);
+    let actual = execute_to_batches(&mut ctx, &sql).await;
+    let expected = vec!["+---+", "| a |", "+---+", "| 1 |", "|   |", "+---+"];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = format!(
+        "SELECT x.a FROM ({} INTERSECT {}) AS x ORDER BY x.a",
+        left, right
+    );
+    let actual = execute_to_batches(&mut ctx, &sql).await;
+    let expected = vec![
+        "+---+", "| a |", "+---+", "| 1 |", "| 2 |", "|   |", "+---+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_sort_unprojected_col() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -6512,3 +7867,36 @@ async fn csv_query_with_decimal_by_sql() -> Result<()> {
     assert_batches_eq!(expected, &actual);
     Ok(())
 }
+
+#[tokio::test]
+async fn query_exceeding_memory_limit() -> Result<()> {
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_memory_limit(128));
+    register_aggregate_csv(&mut ctx).await?;
+    let df = ctx
+        .sql("SELECT c1, c13 FROM aggregate_test_100 ORDER BY c13")
+        .await?;
+    let err = df.collect().await.unwrap_err();
+    assert_contains!(err.to_string(), "SortExec could not reserve");
+
+    // the groups of aggregations and the build side of joins are reserved too
+    let df = ctx
+        .sql("SELECT c13, COUNT(*) FROM aggregate_test_100 GROUP BY c13")
+        .await?;
+    let err = df.collect().await.unwrap_err();
+    assert_contains!(err.to_string(), "HashAggregateExec could not reserve");
+    let df = ctx
+        .sql(
+            "SELECT a.c1 FROM aggregate_test_100 a \
+             JOIN aggregate_test_100 b ON a.c13 = b.c13",
+        )
+        .await?;
+    let err = df.collect().await.unwrap_err();
+    assert_contains!(err.to_string(), "HashJoinExec build side");
+
+    // the limit only fails the queries buffering more than it
+    let df = ctx.sql("SELECT c1 FROM aggregate_test_100 LIMIT 2").await?;
+    let num_rows: usize = df.collect().await?.iter().map(|b| b.num_rows()).sum();
+    assert_eq!(num_rows, 2);
+    Ok(())
+}
@@ -113,6 +113,7 @@ impl PyDataFrame {
             "full" => JoinType::Full,
             "semi" => JoinType::Semi,
             "anti" => JoinType::Anti,
+            "null_aware_anti" => JoinType::NullAwareAnti,
             how => {
                 return Err(DataFusionError::Common(format!(
                     "The join type {} does not exist or is not implemented",